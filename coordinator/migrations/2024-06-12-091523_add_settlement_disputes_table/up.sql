@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS settlement_disputes
+(
+    id                  SERIAL PRIMARY KEY       NOT NULL,
+    channel_id          TEXT                     NOT NULL,
+    trader_pubkey       TEXT                     NOT NULL REFERENCES users (pubkey),
+    event_id            TEXT                     NOT NULL,
+    attested_price      REAL                     NOT NULL,
+    mark_price          REAL,
+    second_oracle_price REAL,
+    created_at          timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at         timestamp WITH TIME ZONE,
+    resolution_note     TEXT,
+    UNIQUE (channel_id, event_id)
+);
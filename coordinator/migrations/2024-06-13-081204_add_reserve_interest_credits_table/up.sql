@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS reserve_interest_credits
+(
+    id            SERIAL PRIMARY KEY       NOT NULL,
+    trader_pubkey TEXT                     NOT NULL REFERENCES users (pubkey),
+    channel_id    TEXT                     NOT NULL,
+    reserve_sats  BIGINT                   NOT NULL,
+    apr           REAL                     NOT NULL,
+    period_start  timestamp WITH TIME ZONE NOT NULL,
+    period_end    timestamp WITH TIME ZONE NOT NULL,
+    amount_sats   BIGINT                   NOT NULL,
+    protocol_id   UUID,
+    paid_date     timestamp WITH TIME ZONE,
+    created_at    timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (channel_id, period_end)
+);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS expiry_settlement_attempts
+(
+    id             SERIAL PRIMARY KEY       NOT NULL,
+    position_id    INTEGER                  NOT NULL REFERENCES positions (id),
+    trader_pubkey  TEXT                     NOT NULL REFERENCES users (pubkey),
+    event_id       TEXT                     NOT NULL,
+    attempt        INTEGER                  NOT NULL,
+    attested_price REAL,
+    protocol_id    UUID REFERENCES dlc_protocols (protocol_id),
+    error          TEXT,
+    escalated      BOOLEAN                  NOT NULL DEFAULT false,
+    created_at     timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (position_id, attempt)
+);
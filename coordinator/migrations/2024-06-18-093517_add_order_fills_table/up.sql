@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS order_fills
+(
+    id              SERIAL PRIMARY KEY       NOT NULL,
+    order_id        UUID UNIQUE              NOT NULL,
+    trader_pubkey   TEXT                     NOT NULL,
+    order_type      "OrderType_Type"         NOT NULL,
+    quantity        REAL                     NOT NULL,
+    execution_price REAL                     NOT NULL,
+    index_price     REAL,
+    slippage_bps    REAL,
+    queue_position  INTEGER,
+    time_to_fill_ms BIGINT                   NOT NULL,
+    placed_at       timestamp WITH TIME ZONE NOT NULL,
+    matched_at      timestamp WITH TIME ZONE NOT NULL
+);
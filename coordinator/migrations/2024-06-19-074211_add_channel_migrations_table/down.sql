@@ -0,0 +1,3 @@
+-- type changes to "OrderReason_Type" can not be reverted
+DROP TABLE IF EXISTS channel_migrations;
+DROP TYPE IF EXISTS "ChannelMigrationState_Type";
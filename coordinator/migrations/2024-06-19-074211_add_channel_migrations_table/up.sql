@@ -0,0 +1,31 @@
+ALTER TYPE "OrderReason_Type"
+    ADD
+    VALUE IF NOT EXISTS 'ChannelMigration';
+
+CREATE TYPE "ChannelMigrationState_Type" AS ENUM (
+    'ClosingPosition',
+    'ClosingChannel',
+    'Reopening',
+    'Completed',
+    'Failed'
+);
+
+CREATE TABLE IF NOT EXISTS channel_migrations
+(
+    id                       SERIAL PRIMARY KEY           NOT NULL,
+    trader_pubkey            TEXT                         NOT NULL REFERENCES users (pubkey),
+    old_channel_id           TEXT                         NOT NULL,
+    new_channel_id           TEXT,
+    migration_state          "ChannelMigrationState_Type" NOT NULL,
+    trader_direction         "Direction_Type",
+    quantity                 REAL,
+    trader_leverage          REAL,
+    stable                   BOOLEAN,
+    trader_reserve_sats      BIGINT                       NOT NULL,
+    coordinator_reserve_sats BIGINT                       NOT NULL,
+    close_order_id           UUID,
+    reopen_order_id          UUID,
+    error                    TEXT,
+    created_at               timestamp WITH TIME ZONE     NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at               timestamp WITH TIME ZONE     NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
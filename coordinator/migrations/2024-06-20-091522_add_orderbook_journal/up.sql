@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS orderbook_journal
+(
+    sequence   BIGINT PRIMARY KEY       NOT NULL,
+    event_type TEXT                     NOT NULL,
+    order_id   UUID                     NOT NULL,
+    payload    TEXT                     NOT NULL,
+    created_at timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS orderbook_journal_order_id ON orderbook_journal (order_id);
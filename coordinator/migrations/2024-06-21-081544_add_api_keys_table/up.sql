@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS api_keys
+(
+    -- The id is public, it identifies the key in signed requests.
+    id            UUID PRIMARY KEY         NOT NULL,
+    trader_pubkey TEXT                     NOT NULL REFERENCES users (pubkey),
+    -- The secret has to be known to verify the HMAC of signed requests.
+    secret        TEXT                     NOT NULL,
+    label         TEXT,
+    can_read      BOOLEAN                  NOT NULL,
+    can_trade     BOOLEAN                  NOT NULL,
+    created_at    timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at  timestamp WITH TIME ZONE,
+    revoked_at    timestamp WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_trader_pubkey ON api_keys (trader_pubkey);
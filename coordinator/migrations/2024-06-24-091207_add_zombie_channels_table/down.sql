@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS zombie_channels;
+DROP TYPE IF EXISTS "ZombieChannelState_Type";
@@ -0,0 +1,27 @@
+CREATE TYPE "ZombieChannelState_Type" AS ENUM (
+    'Flagged',
+    'Parked',
+    'ForceClosed',
+    'Exempt',
+    'Cleared'
+);
+
+CREATE TABLE IF NOT EXISTS zombie_channels
+(
+    id                 SERIAL PRIMARY KEY        NOT NULL,
+    channel_id         TEXT                      NOT NULL,
+    trader_pubkey      TEXT                      NOT NULL REFERENCES users (pubkey),
+    state              "ZombieChannelState_Type" NOT NULL,
+    last_seen          timestamp WITH TIME ZONE  NOT NULL,
+    notifications_sent INTEGER                   NOT NULL DEFAULT 0,
+    last_notified_at   timestamp WITH TIME ZONE,
+    protocol_id        UUID,
+    note               TEXT,
+    created_at         timestamp WITH TIME ZONE  NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at         timestamp WITH TIME ZONE  NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- A DLC channel can only be a zombie once at a time.
+CREATE UNIQUE INDEX IF NOT EXISTS zombie_channels_active_channel_id
+    ON zombie_channels (channel_id)
+    WHERE state IN ('Flagged', 'Parked', 'Exempt');
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS message_archive
+(
+    id                SERIAL PRIMARY KEY       NOT NULL,
+    protocol_id       UUID                     NOT NULL,
+    peer_id           TEXT                     NOT NULL,
+    inbound           BOOLEAN                  NOT NULL,
+    message_type      TEXT                     NOT NULL,
+    message_hash      TEXT                     NOT NULL,
+    encrypted_message BYTEA                    NOT NULL,
+    created_at        timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (message_hash, inbound)
+);
+
+CREATE INDEX IF NOT EXISTS message_archive_protocol_id ON message_archive (protocol_id);
@@ -0,0 +1,3 @@
+ALTER TABLE matches
+    DROP COLUMN IF EXISTS index_price,
+    DROP COLUMN IF EXISTS spread_bps;
@@ -0,0 +1,3 @@
+ALTER TABLE matches
+    ADD COLUMN index_price REAL,
+    ADD COLUMN spread_bps  INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,18 @@
+CREATE TYPE "JobOutcome_Type" AS ENUM (
+    'Running',
+    'Succeeded',
+    'Failed'
+);
+
+CREATE TABLE IF NOT EXISTS job_runs
+(
+    id                 SERIAL PRIMARY KEY       NOT NULL,
+    job_name           TEXT                     NOT NULL,
+    triggered_manually BOOLEAN                  NOT NULL,
+    outcome            "JobOutcome_Type"        NOT NULL DEFAULT 'Running',
+    error              TEXT,
+    started_at         timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at        timestamp WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS job_runs_job_name_started_at ON job_runs (job_name, started_at DESC);
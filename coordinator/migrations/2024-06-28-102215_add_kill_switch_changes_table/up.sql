@@ -0,0 +1,11 @@
+-- Every change of the kill switch, the latest one being in effect.
+CREATE TABLE IF NOT EXISTS kill_switch_changes
+(
+    id              SERIAL PRIMARY KEY       NOT NULL,
+    read_only       BOOLEAN                  NOT NULL,
+    no_new_channels BOOLEAN                  NOT NULL,
+    no_withdrawals  BOOLEAN                  NOT NULL,
+    full_halt       BOOLEAN                  NOT NULL,
+    reason          TEXT,
+    created_at      timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,2 @@
+ALTER TABLE "dlc_channels"
+    DROP COLUMN force_close_notified_at;
@@ -0,0 +1,2 @@
+ALTER TABLE "dlc_channels"
+    ADD COLUMN force_close_notified_at TIMESTAMP WITH TIME ZONE;
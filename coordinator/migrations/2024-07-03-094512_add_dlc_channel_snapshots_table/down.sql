@@ -0,0 +1 @@
+DROP TABLE IF EXISTS dlc_channel_snapshots;
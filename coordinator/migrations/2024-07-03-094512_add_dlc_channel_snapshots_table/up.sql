@@ -0,0 +1,16 @@
+-- A snapshot of a DLC channel after every DLC channel event, so that support can tell how a DLC
+-- channel looked like in the past.
+CREATE TABLE IF NOT EXISTS dlc_channel_snapshots
+(
+    id                          SERIAL PRIMARY KEY       NOT NULL,
+    channel_id                  TEXT                     NOT NULL,
+    state                       TEXT                     NOT NULL,
+    reference_id                TEXT,
+    coordinator_collateral_sats BIGINT,
+    trader_collateral_sats      BIGINT,
+    coordinator_reserve_sats    BIGINT,
+    trader_reserve_sats         BIGINT,
+    created_at                  timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS dlc_channel_snapshots_channel_id_idx ON dlc_channel_snapshots (channel_id);
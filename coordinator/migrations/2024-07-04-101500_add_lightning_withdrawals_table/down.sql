@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS lightning_withdrawals;
+DROP TYPE IF EXISTS "LightningPaymentStatus_Type";
@@ -0,0 +1,27 @@
+CREATE TYPE "LightningPaymentStatus_Type" AS ENUM (
+    'Pending',
+    'InFlight',
+    'Succeeded',
+    'Failed'
+);
+
+CREATE TABLE IF NOT EXISTS lightning_withdrawals
+(
+    id                 SERIAL PRIMARY KEY                 NOT NULL,
+    trader_pubkey      TEXT                               NOT NULL,
+    channel_id         TEXT                               NOT NULL,
+    amount_sats        BIGINT                             NOT NULL,
+    invoice            TEXT                               NOT NULL,
+    payment_hash       TEXT UNIQUE                        NOT NULL,
+    payment_status     "LightningPaymentStatus_Type"      NOT NULL DEFAULT 'Pending',
+    -- The DLC protocol debiting the amount from the trader's collateral reserve.
+    protocol_id        UUID,
+    debited_at         timestamp WITH TIME ZONE,
+    -- The DLC protocol crediting the amount back, if the payment failed after the debit.
+    refund_protocol_id UUID,
+    refunded_at        timestamp WITH TIME ZONE,
+    created_at         timestamp WITH TIME ZONE           NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at         timestamp WITH TIME ZONE           NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS lightning_withdrawals_trader_pubkey ON lightning_withdrawals (trader_pubkey);
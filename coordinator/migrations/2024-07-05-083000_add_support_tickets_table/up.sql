@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS support_tickets
+(
+    id            SERIAL PRIMARY KEY       NOT NULL,
+    reference     TEXT UNIQUE              NOT NULL,
+    trader_pubkey TEXT                     NOT NULL REFERENCES users (pubkey),
+    error_code    TEXT                     NOT NULL,
+    error         TEXT                     NOT NULL,
+    order_id      UUID,
+    protocol_id   UUID,
+    app_version   TEXT                     NOT NULL,
+    created_at    timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (trader_pubkey, order_id)
+);
+
+CREATE INDEX IF NOT EXISTS support_tickets_created_at ON support_tickets (created_at);
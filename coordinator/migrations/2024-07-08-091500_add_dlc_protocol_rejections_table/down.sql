@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS dlc_protocol_rejections;
+DROP TYPE IF EXISTS "RejectReason_Type";
@@ -0,0 +1,21 @@
+CREATE TYPE "RejectReason_Type" AS ENUM (
+    'Unknown',
+    'InvalidOffer',
+    'AcceptFailed',
+    'Stale',
+    'Cancelled'
+);
+
+CREATE TABLE IF NOT EXISTS dlc_protocol_rejections
+(
+    id            SERIAL PRIMARY KEY       NOT NULL,
+    protocol_id   UUID                     NOT NULL REFERENCES dlc_protocols (protocol_id),
+    trader_pubkey TEXT                     NOT NULL,
+    -- The order the rejected offer was meant to fill, if the rejecting party told us.
+    order_id      UUID,
+    reason        "RejectReason_Type"      NOT NULL,
+    created_at    timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS dlc_protocol_rejections_trader_pubkey ON dlc_protocol_rejections (trader_pubkey);
+CREATE INDEX IF NOT EXISTS dlc_protocol_rejections_order_id ON dlc_protocol_rejections (order_id);
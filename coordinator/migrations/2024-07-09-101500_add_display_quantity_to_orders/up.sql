@@ -0,0 +1,2 @@
+-- Only set for iceberg orders, which show just this much of their quantity in the orderbook.
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS display_quantity REAL;
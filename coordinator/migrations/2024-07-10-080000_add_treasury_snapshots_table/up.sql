@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS treasury_snapshots
+(
+    id                       SERIAL PRIMARY KEY       NOT NULL,
+    on_chain_external_sats   BIGINT                   NOT NULL,
+    on_chain_internal_sats   BIGINT                   NOT NULL,
+    dlc_channels             INTEGER                  NOT NULL,
+    dlc_margin_sats          BIGINT                   NOT NULL,
+    dlc_reserve_sats         BIGINT                   NOT NULL,
+    force_closing_channels   INTEGER                  NOT NULL,
+    pending_force_close_sats BIGINT                   NOT NULL,
+    -- The order matching fees earned in the day before the snapshot.
+    fees_earned_sats         BIGINT                   NOT NULL,
+    created_at               timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+ALTER TABLE orders
+    DROP COLUMN IF EXISTS client_kind,
+    DROP COLUMN IF EXISTS client_version;
+DROP TYPE IF EXISTS "ClientKind_Type";
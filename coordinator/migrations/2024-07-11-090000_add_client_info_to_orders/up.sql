@@ -0,0 +1,5 @@
+CREATE TYPE "ClientKind_Type" AS ENUM ('App', 'Webapp', 'Sdk');
+-- Not set for orders of older clients and orders created by the coordinator itself.
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS client_kind "ClientKind_Type",
+    ADD COLUMN IF NOT EXISTS client_version TEXT;
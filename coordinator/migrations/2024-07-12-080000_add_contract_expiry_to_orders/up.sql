@@ -0,0 +1,3 @@
+-- Not set for orders for the next expiry.
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS contract_expiry TIMESTAMP WITH TIME ZONE;
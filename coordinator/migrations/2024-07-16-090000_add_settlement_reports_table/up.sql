@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS settlement_reports
+(
+    id           SERIAL PRIMARY KEY       NOT NULL,
+    maker_pubkey TEXT                     NOT NULL,
+    date         DATE                     NOT NULL,
+    -- The report as JSON, as it is delivered to the maker.
+    report       TEXT                     NOT NULL,
+    created_at   timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (maker_pubkey, date)
+);
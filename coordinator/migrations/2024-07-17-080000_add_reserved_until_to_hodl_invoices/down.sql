@@ -0,0 +1,2 @@
+ALTER TABLE hodl_invoices
+    DROP COLUMN IF EXISTS reserved_until;
@@ -0,0 +1,4 @@
+-- Set once the invoice has been accepted. The coordinator must settle the invoice before, or the
+-- offer backed by the invoice is cancelled.
+ALTER TABLE hodl_invoices
+    ADD COLUMN IF NOT EXISTS reserved_until TIMESTAMP WITH TIME ZONE;
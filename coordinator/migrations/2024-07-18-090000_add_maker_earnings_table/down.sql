@@ -0,0 +1 @@
+DROP TABLE IF EXISTS maker_earnings;
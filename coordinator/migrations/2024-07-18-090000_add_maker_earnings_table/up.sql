@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS maker_earnings
+(
+    id                  SERIAL PRIMARY KEY       NOT NULL,
+    maker_pubkey        TEXT                     NOT NULL,
+    date                DATE                     NOT NULL,
+    contract_symbol     "ContractSymbol_Type"    NOT NULL,
+    fills               INTEGER                  NOT NULL DEFAULT 0,
+    volume              REAL                     NOT NULL DEFAULT 0,
+    matching_fees_sats  BIGINT                   NOT NULL DEFAULT 0,
+    spread_capture_sats BIGINT                   NOT NULL DEFAULT 0,
+    -- Reported by the maker, as the coordinator does not know how the maker hedges.
+    hedging_costs_sats  BIGINT                   NOT NULL DEFAULT 0,
+    updated_at          timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (maker_pubkey, date, contract_symbol)
+);
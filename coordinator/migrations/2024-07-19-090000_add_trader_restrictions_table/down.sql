@@ -0,0 +1 @@
+DROP TABLE IF EXISTS trader_restrictions;
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS trader_restrictions
+(
+    id             SERIAL PRIMARY KEY       NOT NULL,
+    trader_pubkey  TEXT                     NOT NULL,
+    reason         TEXT                     NOT NULL,
+    -- A suspension ends at this point in time, a ban never ends.
+    until          timestamp WITH TIME ZONE,
+    -- Whether the restriction was imposed by the abuse heuristics instead of an admin.
+    automatic      BOOLEAN                  NOT NULL DEFAULT false,
+    created_at     timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    lifted_at      timestamp WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS trader_restrictions_trader_pubkey ON trader_restrictions (trader_pubkey);
@@ -0,0 +1,5 @@
+ALTER TABLE expiry_settlement_attempts
+    DROP COLUMN IF EXISTS cet_interval_start,
+    DROP COLUMN IF EXISTS cet_interval_end,
+    DROP COLUMN IF EXISTS cet_trader_payout_sats,
+    DROP COLUMN IF EXISTS exact_trader_payout_sats;
@@ -0,0 +1,7 @@
+-- The contract execution transaction which settles the position, and the payout of the trader at
+-- exactly the attested price to compare it with.
+ALTER TABLE expiry_settlement_attempts
+    ADD COLUMN IF NOT EXISTS cet_interval_start BIGINT,
+    ADD COLUMN IF NOT EXISTS cet_interval_end BIGINT,
+    ADD COLUMN IF NOT EXISTS cet_trader_payout_sats BIGINT,
+    ADD COLUMN IF NOT EXISTS exact_trader_payout_sats BIGINT;
@@ -0,0 +1,8 @@
+-- The DLC channels and contracts, if the coordinator keeps them in the database instead of sled.
+CREATE TABLE IF NOT EXISTS dlc_store (
+    kind SMALLINT NOT NULL,
+    key BYTEA NOT NULL,
+    value BYTEA NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kind, key)
+);
@@ -0,0 +1,15 @@
+-- The oracle attestations used to settle positions on-chain, together with the announcements they
+-- attest. Both are kept in their TLV encoding, so that the settlement can be verified even if the
+-- oracle prunes them.
+CREATE TABLE IF NOT EXISTS settlement_attestations
+(
+    id            SERIAL PRIMARY KEY       NOT NULL,
+    position_id   INTEGER                  NOT NULL REFERENCES positions (id),
+    contract_id   TEXT                     NOT NULL,
+    oracle_pubkey TEXT                     NOT NULL,
+    event_id      TEXT                     NOT NULL,
+    announcement  BYTEA                    NOT NULL,
+    attestation   BYTEA                    NOT NULL,
+    created_at    timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (position_id, oracle_pubkey, event_id)
+);
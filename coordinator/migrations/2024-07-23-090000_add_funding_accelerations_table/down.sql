@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS funding_accelerations;
+DROP TYPE IF EXISTS "AccelerationTrigger_Type";
+DROP TYPE IF EXISTS "AccelerationMethod_Type";
@@ -0,0 +1,27 @@
+CREATE TYPE "AccelerationMethod_Type" AS ENUM (
+    'Provider',
+    'Cpfp'
+);
+
+CREATE TYPE "AccelerationTrigger_Type" AS ENUM (
+    'Manual',
+    'Automatic'
+);
+
+-- The accelerations of stuck DLC channel funding transactions, together with what they cost the
+-- coordinator.
+CREATE TABLE IF NOT EXISTS funding_accelerations
+(
+    id           SERIAL PRIMARY KEY         NOT NULL,
+    channel_id   TEXT                       NOT NULL,
+    funding_txid TEXT                       NOT NULL,
+    method       "AccelerationMethod_Type"  NOT NULL,
+    trigger      "AccelerationTrigger_Type" NOT NULL,
+    -- The CPFP transaction, if the funding transaction was accelerated by spending its change.
+    child_txid   TEXT,
+    cost_sats    BIGINT                     NOT NULL,
+    created_at   timestamp WITH TIME ZONE   NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS funding_accelerations_funding_txid
+    ON funding_accelerations (funding_txid);
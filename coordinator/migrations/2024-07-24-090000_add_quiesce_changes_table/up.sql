@@ -0,0 +1,9 @@
+-- Every change of the pre-upgrade quiesce, the latest one being in effect.
+CREATE TABLE IF NOT EXISTS quiesce_changes
+(
+    id         SERIAL PRIMARY KEY       NOT NULL,
+    engaged    BOOLEAN                  NOT NULL,
+    until      timestamp WITH TIME ZONE,
+    reason     TEXT,
+    created_at timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+-- ... but in this case it does not fully.
+-- Postgres does not allow removing enum type values, so the `EthUsd` variant of `ContractSymbol_Type` is kept.
+
+select 1;
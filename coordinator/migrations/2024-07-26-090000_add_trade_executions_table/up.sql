@@ -0,0 +1,8 @@
+-- The DLC protocols started to execute a match. Claiming the protocol id here before proposing
+-- anything to the trader ensures that a match is only executed once.
+CREATE TABLE IF NOT EXISTS trade_executions
+(
+    protocol_id UUID PRIMARY KEY         NOT NULL,
+    order_id    UUID                     NOT NULL,
+    created_at  timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
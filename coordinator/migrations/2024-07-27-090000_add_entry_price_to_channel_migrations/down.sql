@@ -0,0 +1,3 @@
+ALTER TABLE channel_migrations
+    DROP COLUMN IF EXISTS contract_symbol,
+    DROP COLUMN IF EXISTS average_entry_price;
@@ -0,0 +1,5 @@
+-- The migrated position is closed and reopened at its average entry price, so that moving it does
+-- not realize a profit or loss.
+ALTER TABLE channel_migrations
+    ADD COLUMN IF NOT EXISTS contract_symbol     "ContractSymbol_Type",
+    ADD COLUMN IF NOT EXISTS average_entry_price REAL;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS reserve_top_ups;
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS reserve_top_ups
+(
+    id            SERIAL PRIMARY KEY       NOT NULL,
+    trader_pubkey TEXT                     NOT NULL,
+    channel_id    TEXT                     NOT NULL,
+    -- The address of the coordinator's on-chain wallet the trader deposits to.
+    address       TEXT UNIQUE              NOT NULL,
+    amount_sats   BIGINT                   NOT NULL,
+    txid          TEXT,
+    received_sats BIGINT,
+    confirmed_at  timestamp WITH TIME ZONE,
+    -- The DLC protocol crediting the deposit to the trader's collateral reserve.
+    protocol_id   UUID,
+    credited_at   timestamp WITH TIME ZONE,
+    created_at    timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at    timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS reserve_top_ups_trader_pubkey ON reserve_top_ups (trader_pubkey);
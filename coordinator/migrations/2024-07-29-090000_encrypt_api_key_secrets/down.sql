@@ -0,0 +1,10 @@
+DROP TABLE IF EXISTS api_key_nonces;
+
+-- Encrypted secrets cannot be restored, hence these keys are lost.
+DELETE
+FROM api_keys
+WHERE secret IS NULL;
+ALTER TABLE api_keys
+    ALTER COLUMN secret SET NOT NULL;
+ALTER TABLE api_keys
+    DROP COLUMN IF EXISTS encrypted_secret;
@@ -0,0 +1,17 @@
+-- The secrets are encrypted with a key derived from the seed of the coordinator. Hence, the
+-- coordinator encrypts the existing secrets on startup and clears the plaintext column.
+ALTER TABLE api_keys
+    ADD COLUMN IF NOT EXISTS encrypted_secret BYTEA;
+ALTER TABLE api_keys
+    ALTER COLUMN secret DROP NOT NULL;
+
+CREATE TABLE IF NOT EXISTS api_key_nonces
+(
+    -- The id of the API key, or the pubkey of the trader for requests signed with the node key.
+    signer     TEXT                     NOT NULL,
+    nonce      TEXT                     NOT NULL,
+    created_at timestamp WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (signer, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS api_key_nonces_created_at ON api_key_nonces (created_at);
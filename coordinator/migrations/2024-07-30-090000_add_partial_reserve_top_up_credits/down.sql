@@ -0,0 +1,8 @@
+ALTER TABLE reserve_top_ups
+    DROP COLUMN IF EXISTS settlement_note;
+ALTER TABLE reserve_top_ups
+    DROP COLUMN IF EXISTS settled_at;
+ALTER TABLE reserve_top_ups
+    DROP COLUMN IF EXISTS crediting_sats;
+ALTER TABLE reserve_top_ups
+    DROP COLUMN IF EXISTS credited_sats;
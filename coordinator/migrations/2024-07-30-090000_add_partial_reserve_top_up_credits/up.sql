@@ -0,0 +1,21 @@
+-- The part of the received amount already moved to the trader's collateral reserve.
+ALTER TABLE reserve_top_ups
+    ADD COLUMN credited_sats BIGINT NOT NULL DEFAULT 0;
+-- The part of the received amount the DLC protocol in `protocol_id` moves to the trader's
+-- collateral reserve.
+ALTER TABLE reserve_top_ups
+    ADD COLUMN crediting_sats BIGINT NOT NULL DEFAULT 0;
+-- Set if the top-up was settled outside of the DLC channel, e.g. by refunding the deposit.
+ALTER TABLE reserve_top_ups
+    ADD COLUMN settled_at timestamp WITH TIME ZONE;
+ALTER TABLE reserve_top_ups
+    ADD COLUMN settlement_note TEXT;
+
+-- Top-ups used to be credited in full.
+UPDATE reserve_top_ups
+SET credited_sats = received_sats
+WHERE credited_at IS NOT NULL;
+-- `received_sats` only counts confirmed deposits now.
+UPDATE reserve_top_ups
+SET received_sats = NULL
+WHERE confirmed_at IS NULL;
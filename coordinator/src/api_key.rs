@@ -0,0 +1,224 @@
+use crate::db;
+use crate::db::api_keys::ApiKey;
+use crate::message_archive;
+use crate::session_token::hmac;
+use aes_gcm_siv::Aes256GcmSiv;
+use aes_gcm_siv::KeyInit;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use diesel::PgConnection;
+use rand::RngCore;
+use time::Duration;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::verify_api_signature;
+
+/// Signatures created longer ago than this are rejected, so that a signed request cannot be
+/// replayed later on.
+const MAX_SIGNATURE_AGE: Duration = Duration::minutes(1);
+
+/// Requests to create or revoke an API key which are older than this are rejected.
+pub const MAX_KEY_REQUEST_AGE: Duration = Duration::minutes(5);
+
+/// Nonces are kept for longer than any signed request is accepted, so that a request cannot be
+/// replayed once its nonce has been pruned.
+const NONCE_RETENTION: Duration = Duration::minutes(10);
+
+const MIN_NONCE_LENGTH: usize = 16;
+const MAX_NONCE_LENGTH: usize = 64;
+
+/// Encrypts the secrets of API keys at rest.
+///
+/// The secrets cannot be hashed, as they are needed to verify the HMAC of signed requests.
+/// Instead, they are encrypted with a key derived from the coordinator's seed, so that a leaked
+/// database does not leak the API keys.
+#[derive(Clone)]
+pub struct ApiKeySecrets {
+    cipher: Aes256GcmSiv,
+}
+
+impl ApiKeySecrets {
+    pub fn new(seed_key: [u8; 32]) -> Self {
+        let key = hmac(&seed_key, b"api-key-secret");
+        let cipher = Aes256GcmSiv::new_from_slice(&key).expect("key to have correct key size");
+
+        Self { cipher }
+    }
+
+    pub fn encrypt(&self, secret: &str) -> Result<Vec<u8>> {
+        message_archive::encrypt(&self.cipher, secret.as_bytes())
+    }
+
+    pub fn decrypt(&self, encrypted_secret: &[u8]) -> Result<String> {
+        let secret = message_archive::decrypt(&self.cipher, encrypted_secret)?;
+
+        String::from_utf8(secret).context("Secret is not valid UTF-8")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Permission {
+    Read,
+    Trade,
+}
+
+impl ApiKey {
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Read => self.permissions.read,
+            Permission::Trade => self.permissions.trade,
+        }
+    }
+}
+
+/// A new random secret for an API key, hex encoded.
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+
+    hex::encode(secret)
+}
+
+/// Encrypt the secrets of API keys which were created before secrets were encrypted at rest,
+/// returning the number of encrypted secrets.
+pub fn encrypt_plaintext_secrets(
+    conn: &mut PgConnection,
+    secrets: &ApiKeySecrets,
+) -> Result<usize> {
+    let keys = db::api_keys::get_plaintext_secrets(conn)?;
+
+    for (key_id, secret) in keys.iter() {
+        let encrypted_secret = secrets.encrypt(secret)?;
+        db::api_keys::set_encrypted_secret(conn, *key_id, encrypted_secret)?;
+    }
+
+    Ok(keys.len())
+}
+
+/// Authenticate a message signed with the API key `key_id` at `timestamp` (unix seconds).
+///
+/// Fails if the key does not exist or was revoked, if the signature is invalid, if it was created
+/// too long ago or if the nonce was already used.
+pub fn authenticate(
+    conn: &mut PgConnection,
+    secrets: &ApiKeySecrets,
+    key_id: Uuid,
+    timestamp: i64,
+    nonce: &str,
+    message: &[u8],
+    signature: &str,
+) -> Result<ApiKey> {
+    let signed_at =
+        OffsetDateTime::from_unix_timestamp(timestamp).context("Invalid signature timestamp")?;
+    ensure_recent(signed_at, MAX_SIGNATURE_AGE, OffsetDateTime::now_utc())?;
+    ensure_valid_nonce(nonce)?;
+
+    let api_key = match db::api_keys::get_active(conn, key_id)? {
+        Some(api_key) => api_key,
+        None => bail!("Unknown or revoked API key {key_id}"),
+    };
+
+    let secret = secrets
+        .decrypt(&api_key.encrypted_secret)
+        .with_context(|| format!("Failed to decrypt secret of API key {key_id}"))?;
+    verify_api_signature(&secret, message, signature)
+        .with_context(|| format!("Invalid signature for API key {key_id}"))?;
+
+    // Only record the nonces of authentic requests, so that unauthenticated requests cannot fill
+    // up the store.
+    ensure_unused_nonce(conn, &key_id.to_string(), nonce)?;
+
+    if let Err(e) = db::api_keys::set_last_used(conn, key_id) {
+        tracing::warn!(%key_id, "Failed to update last usage of API key: {e:#}");
+    }
+
+    Ok(api_key)
+}
+
+/// Ensure that the `signer` has not used the nonce before, recording it as used.
+///
+/// The signer is the API key a request was signed with, or the trader for requests signed with
+/// the node key.
+pub fn ensure_unused_nonce(conn: &mut PgConnection, signer: &str, nonce: &str) -> Result<()> {
+    ensure!(
+        db::api_key_nonces::insert(conn, signer, nonce)?,
+        "Nonce {nonce} was already used"
+    );
+
+    Ok(())
+}
+
+/// Delete the nonces which are too old to be needed to reject a replayed request.
+pub fn prune_nonces(conn: &mut PgConnection) -> Result<usize> {
+    let cut_off = OffsetDateTime::now_utc() - NONCE_RETENTION;
+    let deleted = db::api_key_nonces::delete_before(conn, cut_off)?;
+
+    Ok(deleted)
+}
+
+fn ensure_valid_nonce(nonce: &str) -> Result<()> {
+    ensure!(
+        (MIN_NONCE_LENGTH..=MAX_NONCE_LENGTH).contains(&nonce.len())
+            && nonce.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'),
+        "Nonce must be {MIN_NONCE_LENGTH} to {MAX_NONCE_LENGTH} alphanumeric characters"
+    );
+
+    Ok(())
+}
+
+/// Ensure that a request created at `created_at` is at most `max_age` old. Small clock drifts into
+/// the future are tolerated in the same way.
+pub fn ensure_recent(
+    created_at: OffsetDateTime,
+    max_age: Duration,
+    now: OffsetDateTime,
+) -> Result<()> {
+    ensure!(
+        (now - created_at).abs() <= max_age,
+        "Request was created at {created_at}, which is too far from now ({now})"
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_requests_are_rejected() {
+        let now = OffsetDateTime::now_utc();
+
+        assert!(ensure_recent(now - Duration::seconds(30), MAX_SIGNATURE_AGE, now).is_ok());
+        assert!(ensure_recent(now + Duration::seconds(30), MAX_SIGNATURE_AGE, now).is_ok());
+        assert!(ensure_recent(now - Duration::minutes(2), MAX_SIGNATURE_AGE, now).is_err());
+        assert!(ensure_recent(now + Duration::minutes(2), MAX_SIGNATURE_AGE, now).is_err());
+    }
+
+    #[test]
+    fn nonces_must_be_long_enough() {
+        assert!(ensure_valid_nonce(&xxi_node::commons::generate_api_nonce()).is_ok());
+        assert!(ensure_valid_nonce("1f0b9c2e-4d2a-4c1e-9a57-0c3b8d6e7f10").is_ok());
+
+        assert!(ensure_valid_nonce("").is_err());
+        assert!(ensure_valid_nonce("1234").is_err());
+        assert!(ensure_valid_nonce(&"a".repeat(65)).is_err());
+        assert!(ensure_valid_nonce("1f0b9c2e4d2a4c1e\nPOST").is_err());
+    }
+
+    #[test]
+    fn encrypted_secret_can_be_decrypted() {
+        let secrets = ApiKeySecrets::new([1u8; 32]);
+        let secret = generate_secret();
+
+        let encrypted_secret = secrets.encrypt(&secret).unwrap();
+
+        assert_ne!(encrypted_secret, secret.as_bytes());
+        assert_eq!(secrets.decrypt(&encrypted_secret).unwrap(), secret);
+        assert!(ApiKeySecrets::new([2u8; 32])
+            .decrypt(&encrypted_secret)
+            .is_err());
+    }
+}
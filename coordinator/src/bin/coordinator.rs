@@ -1,30 +1,54 @@
+use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
 use bitcoin::key::XOnlyPublicKey;
+use coordinator::api_key;
+use coordinator::api_key::ApiKeySecrets;
 use coordinator::backup::SledBackup;
+use coordinator::cli::DlcStorageBackend;
 use coordinator::cli::Opts;
-use coordinator::db;
 use coordinator::dlc_handler;
 use coordinator::dlc_handler::DlcHandler;
-use coordinator::funding_fee::generate_funding_fee_events_periodically;
+use coordinator::kill_switch::KillSwitch;
 use coordinator::logger;
 use coordinator::message::spawn_delivering_messages_to_authenticated_users;
 use coordinator::message::NewUserMessage;
+use coordinator::message_archive::MessageArchive;
+use coordinator::metrics;
+use coordinator::moderation::Moderation;
+use coordinator::node::channel_migration;
 use coordinator::node::expired_positions;
+use coordinator::node::expiry_settlement;
+use coordinator::node::funding_accelerator;
+use coordinator::node::invoice;
 use coordinator::node::liquidated_positions;
+use coordinator::node::oracle_announcements;
+use coordinator::node::quiesce::Quiesce;
 use coordinator::node::rollover;
+use coordinator::node::settlement_dispute;
 use coordinator::node::storage::NodeStorage;
 use coordinator::node::unrealized_pnl;
+use coordinator::node::zombie_channels;
 use coordinator::node::Node;
 use coordinator::notifications::NotificationService;
 use coordinator::orderbook::async_match;
 use coordinator::orderbook::collaborative_revert;
+use coordinator::orderbook::journal_export::JournalExporter;
 use coordinator::orderbook::trading;
+use coordinator::replication;
+use coordinator::reserve_top_up;
+use coordinator::retention::DataRetention;
+use coordinator::retention::ObjectStorage;
 use coordinator::routes::router;
 use coordinator::run_migration;
-use coordinator::scheduler::NotificationScheduler;
+use coordinator::scheduler::add_jobs;
+use coordinator::scheduler::Scheduler;
+use coordinator::self_check::SelfCheck;
+use coordinator::session_token::SessionTokens;
 use coordinator::settings::Settings;
+use coordinator::settlement_report::ReportUrls;
 use coordinator::storage::CoordinatorTenTenOneStorage;
+use coordinator::storage::DlcStorage;
 use coordinator::trade::websocket::InternalPositionUpdateMessage;
 use diesel::r2d2;
 use diesel::r2d2::ConnectionManager;
@@ -37,21 +61,28 @@ use std::net::IpAddr;
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 use std::str::FromStr;
-use std::sync::mpsc;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tokio::task::spawn_blocking;
-use tokio_cron_scheduler::JobScheduler;
 use tracing::metadata::LevelFilter;
 use xxi_node::node::event::NodeEventHandler;
 use xxi_node::seed::Bip39Seed;
-use xxi_node::storage::DlcChannelEvent;
+use xxi_node::storage::DlcChannelEventBus;
 
 const PROCESS_INCOMING_DLC_MESSAGES_INTERVAL: Duration = Duration::from_millis(200);
 const LIQUIDATED_POSITION_SYNC_INTERVAL: Duration = Duration::from_secs(30);
 const EXPIRED_POSITION_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const EXPIRY_SETTLEMENT_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+const HODL_INVOICE_RECONCILIATION_INTERVAL: Duration = Duration::from_secs(60);
 const UNREALIZED_PNL_SYNC_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const CHANNEL_MIGRATION_SYNC_INTERVAL: Duration = Duration::from_secs(30);
+const ZOMBIE_CHANNEL_SYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const FUNDING_ACCELERATION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const MESSAGE_ARCHIVE_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const API_KEY_NONCE_PRUNE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const ANNOUNCEMENT_PREFETCH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const RESERVE_TOP_UP_SYNC_INTERVAL: Duration = Duration::from_secs(60);
 
 const NODE_ALIAS: &str = "10101.finance";
 
@@ -81,6 +112,7 @@ async fn main() -> Result<()> {
     let address = opts.p2p_address;
     let http_address = opts.http_address;
     let network = opts.network();
+    let replication_target = opts.replication_target();
     let oracle_infos = opts
         .get_oracle_infos()
         .into_iter()
@@ -91,6 +123,46 @@ async fn main() -> Result<()> {
 
     logger::init_tracing(LevelFilter::DEBUG, opts.json, opts.tokio_console)?;
 
+    // set up database connection pool
+    let manager = ConnectionManager::<PgConnection>::new(opts.database.clone());
+    let pool = r2d2::Pool::builder()
+        .build(manager)
+        .expect("Failed to create pool.");
+
+    let self_check = SelfCheck {
+        network,
+        electrs: opts.electrs.clone(),
+        oracles: opts.get_oracle_infos(),
+        oracle_pubkey: XOnlyPublicKey::from_str(&opts.oracle_pubkey).expect("valid public key"),
+        lnd_bridge: lnd_bridge.clone(),
+        pool: pool.clone(),
+    };
+
+    if opts.self_check {
+        let report = self_check.run().await;
+
+        #[allow(clippy::print_stdout)]
+        {
+            println!("{report}");
+        }
+
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
+    let mut conn = pool.get()?;
+    run_migration(&mut conn);
+
+    // We refuse to serve with a misconfiguration, as it would only surface as obscure failures
+    // later on. A service which is only temporarily unavailable must not keep us from starting.
+    let report = self_check.run().await;
+    if report.found_misconfiguration() {
+        bail!("Self-check failed\n{report}");
+    } else if report.passed() {
+        tracing::info!("Self-check passed\n{report}");
+    } else {
+        tracing::warn!("Self-check could not verify every service\n{report}");
+    }
+
     let mut ephemeral_randomness = [0; 32];
     thread_rng().fill_bytes(&mut ephemeral_randomness);
 
@@ -108,27 +180,63 @@ async fn main() -> Result<()> {
 
     let settings = Settings::new(&data_dir).await?;
 
-    // set up database connection pool
-    let manager = ConnectionManager::<PgConnection>::new(opts.database.clone());
-    let pool = r2d2::Pool::builder()
-        .build(manager)
-        .expect("Failed to create pool.");
+    let message_archive = MessageArchive::new(
+        pool.clone(),
+        seed.encryption_key(),
+        settings.message_archive,
+    );
 
-    let mut conn = pool.get()?;
-    run_migration(&mut conn);
+    let session_tokens = SessionTokens::new(seed.encryption_key());
+    let report_urls = ReportUrls::new(seed.encryption_key());
+    let api_key_secrets = ApiKeySecrets::new(seed.encryption_key());
 
-    let storage = CoordinatorTenTenOneStorage::new(data_dir.to_string_lossy().to_string());
+    let encrypted_secrets = api_key::encrypt_plaintext_secrets(&mut conn, &api_key_secrets)
+        .context("Failed to encrypt API key secrets")?;
+    if encrypted_secrets > 0 {
+        tracing::info!(encrypted_secrets, "Encrypted plaintext API key secrets");
+    }
 
-    let node_storage = Arc::new(NodeStorage::new(pool.clone()));
+    let object_storage = opts
+        .archive_url
+        .clone()
+        .map(|url| ObjectStorage::new(url, opts.archive_token.clone()));
+    let data_retention =
+        DataRetention::new(pool.clone(), object_storage, settings.retention.clone());
+    let journal_exporter = JournalExporter::new(
+        pool.clone(),
+        data_dir.join("orderbook-journal-exports"),
+        settings.journal_export.clone(),
+    );
 
     let node_event_handler = Arc::new(NodeEventHandler::new());
 
+    let mut storage = match opts.dlc_storage {
+        DlcStorageBackend::Sled => {
+            CoordinatorTenTenOneStorage::new(data_dir.to_string_lossy().to_string())
+        }
+        DlcStorageBackend::Postgres => CoordinatorTenTenOneStorage::postgres(
+            data_dir.to_string_lossy().to_string(),
+            pool.clone(),
+        )?,
+    };
+    if let Some(target) = replication_target {
+        // In the database, the DLC storage is covered by the database backups.
+        let DlcStorage::Sled(dlc_storage) = &storage.dlc_storage else {
+            bail!("The DLC storage can only be replicated if it is kept in sled");
+        };
+
+        let replication =
+            replication::spawn(dlc_storage.clone(), target, node_event_handler.subscribe());
+        storage = storage.with_replication(replication);
+    }
+
+    let node_storage = Arc::new(NodeStorage::new(pool.clone()));
+
     let wallet_storage = bdk_file_store::Store::open_or_create_new(
         WALLET_DB_PREFIX.as_bytes(),
         data_dir.join("wallet"),
     )?;
 
-    let (dlc_event_sender, dlc_event_receiver) = mpsc::channel::<DlcChannelEvent>();
     let node = Arc::new(xxi_node::node::Node::new(
         NODE_ALIAS,
         network,
@@ -145,16 +253,27 @@ async fn main() -> Result<()> {
         oracle_infos,
         XOnlyPublicKey::from_str(&opts.oracle_pubkey).expect("valid public key"),
         node_event_handler.clone(),
-        dlc_event_sender,
+        DlcChannelEventBus::new(),
     )?);
 
-    let dlc_handler = DlcHandler::new(pool.clone(), node.clone());
+    let kill_switch = KillSwitch::new(pool.clone())?;
+    let quiesce = Quiesce::new(pool.clone())?;
+    let moderation = Moderation::new(pool.clone(), settings.moderation)?;
+
+    let dlc_handler = DlcHandler::new(
+        pool.clone(),
+        node.clone(),
+        message_archive.clone(),
+        kill_switch.clone(),
+    );
     let _handle = dlc_handler::spawn_handling_outbound_dlc_messages(
         dlc_handler,
         node_event_handler.subscribe(),
     );
 
-    let running = node.start(dlc_event_receiver)?;
+    metrics::spawn_counting_dlc_channel_events(node.dlc_storage.subscribe());
+
+    let running = node.start()?;
 
     let (tx_user_feed, _rx) = broadcast::channel::<NewUserMessage>(100);
 
@@ -176,6 +295,10 @@ async fn main() -> Result<()> {
         tx_position_feed.clone(),
         auth_users_notifier.clone(),
         lnd_bridge.clone(),
+        message_archive.clone(),
+        kill_switch,
+        quiesce,
+        moderation,
     );
 
     // TODO: Pass the tokio metrics into Prometheus
@@ -202,6 +325,12 @@ async fn main() -> Result<()> {
                     tracing::info!("On-chain sync failed: {e:#}");
                 }
 
+                // The DLC manager periodic check broadcasts the settlement transactions, hence the
+                // attestations of disputed settlements have to be withheld before.
+                if let Err(e) = settlement_dispute::check_settlements(node.clone()).await {
+                    tracing::error!("Failed to check settlements: {e:#}");
+                }
+
                 spawn_blocking({
                     let node = node.clone();
                     move || {
@@ -252,21 +381,18 @@ async fn main() -> Result<()> {
         tx_orderbook_feed.clone(),
         auth_users_notifier.clone(),
         notification_service.get_sender(),
-        network,
         node.inner.oracle_pubkey,
-    );
+    )?;
     let _handle = async_match::monitor(
         node.clone(),
         node_event_handler.subscribe(),
         auth_users_notifier.clone(),
-        network,
         node.inner.oracle_pubkey,
     );
     let _handle = rollover::monitor(
         pool.clone(),
         node_event_handler.subscribe(),
         notification_service.get_sender(),
-        network,
         node.clone(),
     );
     let _handle = collaborative_revert::monitor(
@@ -292,6 +418,45 @@ async fn main() -> Result<()> {
         }
     });
 
+    tokio::spawn({
+        let node = node.clone();
+        let trading_sender = trading_sender.clone();
+        async move {
+            loop {
+                tokio::time::sleep(CHANNEL_MIGRATION_SYNC_INTERVAL).await;
+                if let Err(e) =
+                    channel_migration::advance(node.clone(), trading_sender.clone()).await
+                {
+                    tracing::error!("Failed to advance channel migrations! Error: {e:#}");
+                }
+            }
+        }
+    });
+
+    tokio::spawn({
+        let node = node.clone();
+        async move {
+            loop {
+                tokio::time::sleep(EXPIRY_SETTLEMENT_SYNC_INTERVAL).await;
+                if let Err(e) = expiry_settlement::settle(node.clone()).await {
+                    tracing::error!("Failed to settle expired positions! Error: {e:#}");
+                }
+            }
+        }
+    });
+
+    tokio::spawn({
+        let node = node.clone();
+        async move {
+            loop {
+                tokio::time::sleep(HODL_INVOICE_RECONCILIATION_INTERVAL).await;
+                if let Err(e) = invoice::reconcile_expired_reservations(node.clone()).await {
+                    tracing::error!("Failed to reconcile hodl invoices! Error: {e:#}");
+                }
+            }
+        }
+    });
+
     tokio::spawn({
         let node = node.clone();
         let trading_sender = trading_sender.clone();
@@ -303,8 +468,104 @@ async fn main() -> Result<()> {
         }
     });
 
+    tokio::spawn({
+        let node = node.clone();
+        async move {
+            let mut unconfirmed_txs = funding_accelerator::UnconfirmedFundingTxs::default();
+            loop {
+                tokio::time::sleep(FUNDING_ACCELERATION_INTERVAL).await;
+                if let Err(e) = funding_accelerator::accelerate_stuck_funding_txs(
+                    node.clone(),
+                    &mut unconfirmed_txs,
+                )
+                .await
+                {
+                    tracing::error!(
+                        "Failed to accelerate stuck funding transactions! Error: {e:#}"
+                    );
+                }
+            }
+        }
+    });
+
+    tokio::spawn({
+        let node = node.clone();
+        let notifier = notification_service.get_sender();
+        async move {
+            loop {
+                tokio::time::sleep(ZOMBIE_CHANNEL_SYNC_INTERVAL).await;
+                if let Err(e) = zombie_channels::detect(node.clone(), notifier.clone()).await {
+                    tracing::error!("Failed to detect zombie channels! Error: {e:#}");
+                }
+            }
+        }
+    });
+
+    tokio::spawn({
+        let node = node.clone();
+        async move {
+            loop {
+                tokio::time::sleep(RESERVE_TOP_UP_SYNC_INTERVAL).await;
+                if let Err(e) = reserve_top_up::track_deposits(node.clone()).await {
+                    tracing::error!("Failed to track reserve top-up deposits! Error: {e:#}");
+                }
+            }
+        }
+    });
+
+    tokio::spawn({
+        let node = node.clone();
+        async move {
+            loop {
+                if let Err(e) = oracle_announcements::prefetch(node.clone()).await {
+                    tracing::error!("Failed to prefetch oracle announcements! Error: {e:#}");
+                }
+                tokio::time::sleep(ANNOUNCEMENT_PREFETCH_INTERVAL).await;
+            }
+        }
+    });
+
+    tokio::spawn({
+        let pool = pool.clone();
+        async move {
+            loop {
+                tokio::time::sleep(API_KEY_NONCE_PRUNE_INTERVAL).await;
+                let pool = pool.clone();
+                match spawn_blocking(move || {
+                    let mut conn = pool.get()?;
+                    api_key::prune_nonces(&mut conn)
+                })
+                .await
+                .expect("task to complete")
+                {
+                    Ok(deleted) => tracing::debug!(deleted, "Pruned API key nonces"),
+                    Err(e) => tracing::error!("Failed to prune API key nonces! Error: {e:#}"),
+                }
+            }
+        }
+    });
+
+    tokio::spawn({
+        let message_archive = message_archive.clone();
+        async move {
+            loop {
+                tokio::time::sleep(MESSAGE_ARCHIVE_PRUNE_INTERVAL).await;
+                let message_archive = message_archive.clone();
+                match spawn_blocking(move || message_archive.prune())
+                    .await
+                    .expect("task to complete")
+                {
+                    Ok(deleted) => tracing::debug!(deleted, "Pruned message archive"),
+                    Err(e) => tracing::error!("Failed to prune message archive! Error: {e:#}"),
+                }
+            }
+        }
+    });
+
     let user_backup = SledBackup::new(data_dir.to_string_lossy().to_string());
 
+    let scheduler = Scheduler::new(pool.clone()).await?;
+
     let app = router(
         node.clone(),
         pool.clone(),
@@ -318,37 +579,42 @@ async fn main() -> Result<()> {
         notification_service.get_sender(),
         user_backup,
         lnd_bridge,
+        session_tokens,
+        api_key_secrets,
+        report_urls,
+        data_retention.clone(),
+        journal_exporter.clone(),
+        scheduler.clone(),
+        opts.admin_token.clone(),
     );
 
-    let sender = notification_service.get_sender();
-    let scheduler = NotificationScheduler::new(sender, settings.clone(), network, node).await;
     tokio::spawn({
-        let pool = pool.clone();
+        let node = node.clone();
+        let auth_users_notifier = auth_users_notifier.clone();
         async move {
-            scheduler
-                .add_rollover_window_reminder_job(pool.clone())
-                .await
-                .expect("To add the rollover window reminder job");
-
-            scheduler
-                .add_rollover_window_close_reminder_job(pool.clone())
-                .await
-                .expect("To add the rollover window close reminder job");
-
-            scheduler
-                .add_reminder_to_close_expired_position_job(pool.clone())
-                .await
-                .expect("To add the close expired position reminder job");
-
-            scheduler
-                .add_reminder_to_close_liquidated_position_job(pool.clone())
-                .await
-                .expect("To add the close liquidated position reminder job");
+            if let Err(e) = invoice::reconcile_pending_invoices(node, auth_users_notifier).await {
+                tracing::error!("Failed to reconcile pending hodl invoices. Error: {e:#}");
+            }
+        }
+    });
 
-            scheduler
-                .add_collect_metrics_job(pool.clone())
-                .await
-                .expect("To add the collect metrics job");
+    tokio::spawn({
+        let scheduler = scheduler.clone();
+        let pool = pool.clone();
+        let notifier = notification_service.get_sender();
+        async move {
+            add_jobs(
+                &scheduler,
+                &settings,
+                pool,
+                node,
+                notifier,
+                auth_users_notifier,
+                data_retention,
+                journal_exporter,
+            )
+            .await
+            .expect("to add jobs");
 
             scheduler
                 .start()
@@ -357,30 +623,6 @@ async fn main() -> Result<()> {
         }
     });
 
-    if let Err(e) = spawn_blocking({
-        let pool = pool.clone();
-        move || {
-            let mut conn = pool.get()?;
-            db::hodl_invoice::cancel_pending_hodl_invoices(&mut conn)?;
-            anyhow::Ok(())
-        }
-    })
-    .await
-    .expect("task to finish")
-    {
-        tracing::error!("Failed to set expired hodl invoices to canceled. Error: {e:#}");
-    }
-
-    generate_funding_fee_events_periodically(
-        &JobScheduler::new().await?,
-        pool.clone(),
-        auth_users_notifier,
-        settings.generate_funding_fee_events_scheduler,
-        settings.index_price_source,
-    )
-    .await
-    .expect("to start task");
-
     tracing::debug!("Listening on http://{}", http_address);
 
     match axum::Server::bind(&http_address)
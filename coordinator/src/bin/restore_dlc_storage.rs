@@ -0,0 +1,42 @@
+use anyhow::ensure;
+use anyhow::Result;
+use clap::Parser;
+use coordinator::logger;
+use coordinator::replication;
+use std::path::PathBuf;
+use tracing::metadata::LevelFilter;
+use xxi_node::storage::sled::SledStorageProvider;
+
+/// Rebuild the DLC storage of the coordinator from its continuous replication.
+#[derive(Parser)]
+struct Opts {
+    /// The directory the DLC storage was replicated to, or to which the replicated objects were
+    /// downloaded from the object storage.
+    #[clap(long)]
+    from: PathBuf,
+
+    /// The data directory of the restored coordinator, e.g. `data/coordinator/regtest`. Must
+    /// not contain a DLC storage yet.
+    #[clap(long)]
+    data_dir: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+
+    logger::init_tracing(LevelFilter::INFO, false, false)?;
+
+    // Sled keeps its database in the `db` file of the data directory.
+    ensure!(
+        !opts.data_dir.join("db").exists(),
+        "{} already contains a DLC storage",
+        opts.data_dir.display()
+    );
+    std::fs::create_dir_all(&opts.data_dir)?;
+
+    let dlc_storage = SledStorageProvider::new(&opts.data_dir.to_string_lossy());
+    replication::restore(&opts.from, &dlc_storage)?;
+    dlc_storage.flush()?;
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::replication::ReplicationTarget;
+use crate::retention::ObjectStorage;
 use anyhow::Result;
 use bitcoin::secp256k1::XOnlyPublicKey;
 use clap::Parser;
@@ -5,6 +7,7 @@ use std::env::current_dir;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
+use url::Url;
 use xxi_node::node::OracleInfo;
 
 #[derive(Parser)]
@@ -83,6 +86,51 @@ pub struct Opts {
     /// If enabled the coordinator will try to connect to lnd via https, wss.
     #[clap(short, long)]
     pub secure_lnd: bool,
+
+    /// The object storage bucket where pruned rows are archived, e.g.
+    /// `https://storage.googleapis.com/10101-archive/coordinator/`. If not specified, tables
+    /// which should be archived before they are pruned are not pruned.
+    #[clap(long)]
+    pub archive_url: Option<Url>,
+
+    /// The bearer token to authenticate with the object storage.
+    #[clap(long, default_value = "")]
+    pub archive_token: String,
+
+    /// Where to keep the DLC channels and contracts. When switching to `postgres`, the existing
+    /// sled storage in the data directory is imported on the first start.
+    #[clap(long, value_enum, default_value = "sled")]
+    pub dlc_storage: DlcStorageBackend,
+
+    /// The directory to which the DLC storage is continuously replicated, e.g. a mounted volume
+    /// of the warm standby.
+    #[clap(long, conflicts_with = "replication_url")]
+    pub replication_dir: Option<PathBuf>,
+
+    /// The object storage bucket to which the DLC storage is continuously replicated. If neither
+    /// this nor `replication_dir` is specified, the DLC storage is not replicated.
+    #[clap(long)]
+    pub replication_url: Option<Url>,
+
+    /// The bearer token to authenticate with the replication object storage.
+    #[clap(long, default_value = "")]
+    pub replication_token: String,
+
+    /// The bearer token the operator has to provide to switch the kill switch. If not specified,
+    /// the kill switch cannot be switched through the admin API.
+    #[clap(long)]
+    pub admin_token: Option<String>,
+
+    /// Only check the configuration against the database, electrs, the oracles and LND, print a
+    /// report and exit. The exit code is non-zero if a check failed.
+    #[clap(long)]
+    pub self_check: bool,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum DlcStorageBackend {
+    Sled,
+    Postgres,
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
@@ -130,6 +178,17 @@ impl Opts {
             .collect()
     }
 
+    pub fn replication_target(&self) -> Option<ReplicationTarget> {
+        match (&self.replication_dir, &self.replication_url) {
+            (Some(dir), _) => Some(ReplicationTarget::Directory(dir.clone())),
+            (None, Some(url)) => Some(ReplicationTarget::ObjectStorage(ObjectStorage::new(
+                url.clone(),
+                self.replication_token.clone(),
+            ))),
+            (None, None) => None,
+        }
+    }
+
     pub fn data_dir(&self) -> Result<PathBuf> {
         let data_dir = match self.data_dir.clone() {
             None => current_dir()?.join("data"),
@@ -0,0 +1,31 @@
+use crate::schema::api_key_nonces;
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = api_key_nonces)]
+struct NewApiKeyNonce {
+    signer: String,
+    nonce: String,
+}
+
+/// Record the nonce of a signed request.
+///
+/// Returns `false` if the signer has already used the nonce.
+pub fn insert(conn: &mut PgConnection, signer: &str, nonce: &str) -> QueryResult<bool> {
+    let affected_rows = diesel::insert_into(api_key_nonces::table)
+        .values(NewApiKeyNonce {
+            signer: signer.to_string(),
+            nonce: nonce.to_string(),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(affected_rows > 0)
+}
+
+pub fn delete_before(conn: &mut PgConnection, cut_off: OffsetDateTime) -> QueryResult<usize> {
+    diesel::delete(api_key_nonces::table)
+        .filter(api_key_nonces::created_at.lt(cut_off))
+        .execute(conn)
+}
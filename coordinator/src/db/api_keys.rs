@@ -0,0 +1,157 @@
+use crate::schema::api_keys;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use diesel::prelude::*;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::ApiKeyPermissions;
+
+#[derive(Queryable, Debug, Clone)]
+struct ApiKeyRecord {
+    id: Uuid,
+    trader_pubkey: String,
+    /// Only set for keys which were created before secrets were encrypted, until the coordinator
+    /// has encrypted them.
+    secret: Option<String>,
+    label: Option<String>,
+    can_read: bool,
+    can_trade: bool,
+    created_at: OffsetDateTime,
+    last_used_at: Option<OffsetDateTime>,
+    revoked_at: Option<OffsetDateTime>,
+    encrypted_secret: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub trader_pubkey: PublicKey,
+    /// The secret, encrypted with [`crate::api_key::ApiKeySecrets`].
+    pub encrypted_secret: Vec<u8>,
+    pub label: Option<String>,
+    pub permissions: ApiKeyPermissions,
+    pub created_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = api_keys)]
+struct NewApiKey {
+    id: Uuid,
+    trader_pubkey: String,
+    encrypted_secret: Vec<u8>,
+    label: Option<String>,
+    can_read: bool,
+    can_trade: bool,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    id: Uuid,
+    trader_pubkey: PublicKey,
+    encrypted_secret: Vec<u8>,
+    label: Option<String>,
+    permissions: ApiKeyPermissions,
+) -> QueryResult<()> {
+    diesel::insert_into(api_keys::table)
+        .values(NewApiKey {
+            id,
+            trader_pubkey: trader_pubkey.to_string(),
+            encrypted_secret,
+            label,
+            can_read: permissions.read,
+            can_trade: permissions.trade,
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Returns the API key with the given id, unless it has been revoked.
+pub fn get_active(conn: &mut PgConnection, id: Uuid) -> Result<Option<ApiKey>> {
+    let record = api_keys::table
+        .filter(api_keys::id.eq(id))
+        .filter(api_keys::revoked_at.is_null())
+        .first::<ApiKeyRecord>(conn)
+        .optional()?;
+
+    record.map(ApiKey::try_from).transpose()
+}
+
+/// Revoke the API key of the given trader.
+///
+/// Returns `false` if the trader has no active API key with the given id.
+pub fn revoke(conn: &mut PgConnection, id: Uuid, trader_pubkey: PublicKey) -> QueryResult<bool> {
+    let affected_rows = diesel::update(api_keys::table)
+        .filter(api_keys::id.eq(id))
+        .filter(api_keys::trader_pubkey.eq(trader_pubkey.to_string()))
+        .filter(api_keys::revoked_at.is_null())
+        .set(api_keys::revoked_at.eq(OffsetDateTime::now_utc()))
+        .execute(conn)?;
+
+    Ok(affected_rows > 0)
+}
+
+pub fn set_last_used(conn: &mut PgConnection, id: Uuid) -> QueryResult<()> {
+    diesel::update(api_keys::table)
+        .filter(api_keys::id.eq(id))
+        .set(api_keys::last_used_at.eq(OffsetDateTime::now_utc()))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Returns the ids and secrets of all API keys whose secret has not been encrypted yet.
+pub fn get_plaintext_secrets(conn: &mut PgConnection) -> QueryResult<Vec<(Uuid, String)>> {
+    let keys = api_keys::table
+        .filter(api_keys::secret.is_not_null())
+        .select((api_keys::id, api_keys::secret))
+        .load::<(Uuid, Option<String>)>(conn)?;
+
+    Ok(keys
+        .into_iter()
+        .filter_map(|(id, secret)| secret.map(|secret| (id, secret)))
+        .collect())
+}
+
+/// Store the encrypted secret of the API key, deleting the plaintext secret.
+pub fn set_encrypted_secret(
+    conn: &mut PgConnection,
+    id: Uuid,
+    encrypted_secret: Vec<u8>,
+) -> QueryResult<()> {
+    diesel::update(api_keys::table)
+        .filter(api_keys::id.eq(id))
+        .set((
+            api_keys::encrypted_secret.eq(encrypted_secret),
+            api_keys::secret.eq(None::<String>),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+impl TryFrom<ApiKeyRecord> for ApiKey {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ApiKeyRecord) -> Result<Self> {
+        Ok(ApiKey {
+            id: value.id,
+            trader_pubkey: PublicKey::from_str(&value.trader_pubkey)?,
+            encrypted_secret: value
+                .encrypted_secret
+                .with_context(|| format!("Secret of API key {} is not encrypted", value.id))?,
+            label: value.label,
+            permissions: ApiKeyPermissions {
+                read: value.can_read,
+                trade: value.can_trade,
+            },
+            created_at: value.created_at,
+            last_used_at: value.last_used_at,
+            revoked_at: value.revoked_at,
+        })
+    }
+}
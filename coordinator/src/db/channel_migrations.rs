@@ -0,0 +1,278 @@
+use crate::db::positions::ContractSymbol;
+use crate::orderbook::db::custom_types::Direction;
+use crate::schema::channel_migrations;
+use crate::schema::sql_types::ChannelMigrationStateType;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::prelude::*;
+use diesel::query_builder::QueryId;
+use diesel::AsExpression;
+use diesel::FromSqlRow;
+use dlc_manager::DlcChannelId;
+use serde::Serialize;
+use std::any::TypeId;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons;
+
+/// The steps of migrating a trader's DLC channel to new parameters.
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Serialize)]
+#[diesel(sql_type = ChannelMigrationStateType)]
+pub enum ChannelMigrationState {
+    /// Waiting for the trader's position to be closed.
+    ClosingPosition,
+    /// Waiting for the DLC channel to be closed collaboratively.
+    ClosingChannel,
+    /// Waiting for the new DLC channel to be opened with the trader's previous position.
+    Reopening,
+    Completed,
+    Failed,
+}
+
+impl QueryId for ChannelMigrationStateType {
+    type QueryId = ChannelMigrationStateType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct ChannelMigrationRecord {
+    id: i32,
+    trader_pubkey: String,
+    old_channel_id: String,
+    new_channel_id: Option<String>,
+    migration_state: ChannelMigrationState,
+    trader_direction: Option<Direction>,
+    quantity: Option<f32>,
+    trader_leverage: Option<f32>,
+    stable: Option<bool>,
+    trader_reserve_sats: i64,
+    coordinator_reserve_sats: i64,
+    close_order_id: Option<Uuid>,
+    reopen_order_id: Option<Uuid>,
+    error: Option<String>,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    contract_symbol: Option<ContractSymbol>,
+    average_entry_price: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelMigration {
+    pub id: i32,
+    pub trader_pubkey: String,
+    pub old_channel_id: String,
+    pub new_channel_id: Option<String>,
+    pub migration_state: ChannelMigrationState,
+    pub trader_direction: Option<commons::Direction>,
+    pub quantity: Option<f32>,
+    pub trader_leverage: Option<f32>,
+    pub stable: Option<bool>,
+    pub trader_reserve_sats: i64,
+    pub coordinator_reserve_sats: i64,
+    pub close_order_id: Option<Uuid>,
+    pub reopen_order_id: Option<Uuid>,
+    pub error: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+    pub contract_symbol: Option<commons::ContractSymbol>,
+    pub average_entry_price: Option<f32>,
+}
+
+/// The position of the trader at the start of a channel migration, which is reopened in the new
+/// DLC channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MigratedPosition {
+    pub contract_symbol: commons::ContractSymbol,
+    pub trader_direction: commons::Direction,
+    pub quantity: f32,
+    pub trader_leverage: f32,
+    pub stable: bool,
+    /// The price at which the position is closed and reopened.
+    pub average_entry_price: f32,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = channel_migrations)]
+struct NewChannelMigration {
+    trader_pubkey: String,
+    old_channel_id: String,
+    migration_state: ChannelMigrationState,
+    trader_direction: Option<Direction>,
+    quantity: Option<f32>,
+    trader_leverage: Option<f32>,
+    stable: Option<bool>,
+    trader_reserve_sats: i64,
+    coordinator_reserve_sats: i64,
+    close_order_id: Option<Uuid>,
+    contract_symbol: Option<ContractSymbol>,
+    average_entry_price: Option<f32>,
+}
+
+impl ChannelMigration {
+    pub fn position(&self) -> Option<MigratedPosition> {
+        Some(MigratedPosition {
+            contract_symbol: self.contract_symbol?,
+            trader_direction: self.trader_direction?,
+            quantity: self.quantity?,
+            trader_leverage: self.trader_leverage?,
+            stable: self.stable.unwrap_or_default(),
+            average_entry_price: self.average_entry_price?,
+        })
+    }
+
+    pub fn trader_reserve(&self) -> Amount {
+        Amount::from_sat(self.trader_reserve_sats as u64)
+    }
+
+    pub fn coordinator_reserve(&self) -> Amount {
+        Amount::from_sat(self.coordinator_reserve_sats as u64)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn insert(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    old_channel_id: &DlcChannelId,
+    migration_state: ChannelMigrationState,
+    position: Option<MigratedPosition>,
+    trader_reserve: Amount,
+    coordinator_reserve: Amount,
+    close_order_id: Option<Uuid>,
+) -> QueryResult<ChannelMigration> {
+    diesel::insert_into(channel_migrations::table)
+        .values(NewChannelMigration {
+            trader_pubkey: trader_pubkey.to_string(),
+            old_channel_id: hex::encode(old_channel_id),
+            migration_state,
+            trader_direction: position.map(|p| p.trader_direction.into()),
+            quantity: position.map(|p| p.quantity),
+            trader_leverage: position.map(|p| p.trader_leverage),
+            stable: position.map(|p| p.stable),
+            trader_reserve_sats: trader_reserve.to_sat() as i64,
+            coordinator_reserve_sats: coordinator_reserve.to_sat() as i64,
+            close_order_id,
+            contract_symbol: position.map(|p| p.contract_symbol.into()),
+            average_entry_price: position.map(|p| p.average_entry_price),
+        })
+        .get_result::<ChannelMigrationRecord>(conn)
+        .map(ChannelMigration::from)
+}
+
+/// Get all channel migrations which have neither completed nor failed yet.
+pub fn get_active(conn: &mut PgConnection) -> QueryResult<Vec<ChannelMigration>> {
+    channel_migrations::table
+        .filter(channel_migrations::migration_state.ne_all([
+            ChannelMigrationState::Completed,
+            ChannelMigrationState::Failed,
+        ]))
+        .order_by(channel_migrations::created_at.asc())
+        .load::<ChannelMigrationRecord>(conn)
+        .map(|migrations| migrations.into_iter().map(ChannelMigration::from).collect())
+}
+
+/// Get all channel migrations, most recent first.
+pub fn get_all(conn: &mut PgConnection) -> QueryResult<Vec<ChannelMigration>> {
+    channel_migrations::table
+        .order_by(channel_migrations::created_at.desc())
+        .load::<ChannelMigrationRecord>(conn)
+        .map(|migrations| migrations.into_iter().map(ChannelMigration::from).collect())
+}
+
+/// Get all channel migrations of the given trader, most recent first.
+pub fn get_by_trader(
+    conn: &mut PgConnection,
+    trader_pubkey: &PublicKey,
+) -> QueryResult<Vec<ChannelMigration>> {
+    channel_migrations::table
+        .filter(channel_migrations::trader_pubkey.eq(trader_pubkey.to_string()))
+        .order_by(channel_migrations::created_at.desc())
+        .load::<ChannelMigrationRecord>(conn)
+        .map(|migrations| migrations.into_iter().map(ChannelMigration::from).collect())
+}
+
+pub fn set_closing_channel(conn: &mut PgConnection, id: i32) -> QueryResult<()> {
+    diesel::update(channel_migrations::table)
+        .filter(channel_migrations::id.eq(id))
+        .set((
+            channel_migrations::migration_state.eq(ChannelMigrationState::ClosingChannel),
+            channel_migrations::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn set_reopening(conn: &mut PgConnection, id: i32, reopen_order_id: Uuid) -> QueryResult<()> {
+    diesel::update(channel_migrations::table)
+        .filter(channel_migrations::id.eq(id))
+        .set((
+            channel_migrations::migration_state.eq(ChannelMigrationState::Reopening),
+            channel_migrations::reopen_order_id.eq(reopen_order_id),
+            channel_migrations::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn set_completed(
+    conn: &mut PgConnection,
+    id: i32,
+    new_channel_id: Option<&DlcChannelId>,
+) -> QueryResult<()> {
+    diesel::update(channel_migrations::table)
+        .filter(channel_migrations::id.eq(id))
+        .set((
+            channel_migrations::migration_state.eq(ChannelMigrationState::Completed),
+            channel_migrations::new_channel_id.eq(new_channel_id.map(hex::encode)),
+            channel_migrations::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn set_failed(conn: &mut PgConnection, id: i32, error: &str) -> QueryResult<()> {
+    diesel::update(channel_migrations::table)
+        .filter(channel_migrations::id.eq(id))
+        .set((
+            channel_migrations::migration_state.eq(ChannelMigrationState::Failed),
+            channel_migrations::error.eq(error),
+            channel_migrations::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+impl From<ChannelMigrationRecord> for ChannelMigration {
+    fn from(value: ChannelMigrationRecord) -> Self {
+        ChannelMigration {
+            id: value.id,
+            trader_pubkey: value.trader_pubkey,
+            old_channel_id: value.old_channel_id,
+            new_channel_id: value.new_channel_id,
+            migration_state: value.migration_state,
+            trader_direction: value.trader_direction.map(commons::Direction::from),
+            quantity: value.quantity,
+            trader_leverage: value.trader_leverage,
+            stable: value.stable,
+            trader_reserve_sats: value.trader_reserve_sats,
+            coordinator_reserve_sats: value.coordinator_reserve_sats,
+            close_order_id: value.close_order_id,
+            reopen_order_id: value.reopen_order_id,
+            error: value.error,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            contract_symbol: value.contract_symbol.map(commons::ContractSymbol::from),
+            average_entry_price: value.average_entry_price,
+        }
+    }
+}
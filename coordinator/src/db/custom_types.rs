@@ -1,22 +1,36 @@
 use crate::db::bonus_status::BonusType;
+use crate::db::channel_migrations::ChannelMigrationState;
 use crate::db::dlc_channels::DlcChannelState;
 use crate::db::dlc_messages::MessageType;
+use crate::db::dlc_protocol_rejections::RejectReason;
 use crate::db::dlc_protocols::DlcProtocolState;
 use crate::db::dlc_protocols::DlcProtocolType;
+use crate::db::funding_accelerations::AccelerationMethod;
+use crate::db::funding_accelerations::AccelerationTrigger;
 use crate::db::hodl_invoice::InvoiceState;
+use crate::db::job_runs::JobOutcome;
 use crate::db::polls::PollType;
 use crate::db::positions::ContractSymbol;
 use crate::db::positions::PositionState;
+use crate::db::zombie_channels::ZombieChannelState;
+use crate::lightning_withdrawal::PaymentStatus;
+use crate::schema::sql_types::AccelerationMethodType;
+use crate::schema::sql_types::AccelerationTriggerType;
 use crate::schema::sql_types::BonusStatusType;
+use crate::schema::sql_types::ChannelMigrationStateType;
 use crate::schema::sql_types::ContractSymbolType;
 use crate::schema::sql_types::DirectionType;
 use crate::schema::sql_types::DlcChannelStateType;
 use crate::schema::sql_types::InvoiceStateType;
+use crate::schema::sql_types::JobOutcomeType;
+use crate::schema::sql_types::LightningPaymentStatusType;
 use crate::schema::sql_types::MessageTypeType;
 use crate::schema::sql_types::PollTypeType;
 use crate::schema::sql_types::PositionStateType;
 use crate::schema::sql_types::ProtocolStateType;
 use crate::schema::sql_types::ProtocolTypeType;
+use crate::schema::sql_types::RejectReasonType;
+use crate::schema::sql_types::ZombieChannelStateType;
 use diesel::deserialize;
 use diesel::deserialize::FromSql;
 use diesel::pg::Pg;
@@ -32,6 +46,7 @@ impl ToSql<ContractSymbolType, Pg> for ContractSymbol {
     fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
         match *self {
             ContractSymbol::BtcUsd => out.write_all(b"BtcUsd")?,
+            ContractSymbol::EthUsd => out.write_all(b"EthUsd")?,
         }
         Ok(IsNull::No)
     }
@@ -41,6 +56,7 @@ impl FromSql<ContractSymbolType, Pg> for ContractSymbol {
     fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
         match bytes.as_bytes() {
             b"BtcUsd" => Ok(ContractSymbol::BtcUsd),
+            b"EthUsd" => Ok(ContractSymbol::EthUsd),
             _ => Err("Unrecognized enum variant".into()),
         }
     }
@@ -293,3 +309,167 @@ impl FromSql<InvoiceStateType, Pg> for InvoiceState {
         }
     }
 }
+
+impl ToSql<ChannelMigrationStateType, Pg> for ChannelMigrationState {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            ChannelMigrationState::ClosingPosition => out.write_all(b"ClosingPosition")?,
+            ChannelMigrationState::ClosingChannel => out.write_all(b"ClosingChannel")?,
+            ChannelMigrationState::Reopening => out.write_all(b"Reopening")?,
+            ChannelMigrationState::Completed => out.write_all(b"Completed")?,
+            ChannelMigrationState::Failed => out.write_all(b"Failed")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<ChannelMigrationStateType, Pg> for ChannelMigrationState {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"ClosingPosition" => Ok(ChannelMigrationState::ClosingPosition),
+            b"ClosingChannel" => Ok(ChannelMigrationState::ClosingChannel),
+            b"Reopening" => Ok(ChannelMigrationState::Reopening),
+            b"Completed" => Ok(ChannelMigrationState::Completed),
+            b"Failed" => Ok(ChannelMigrationState::Failed),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+impl ToSql<ZombieChannelStateType, Pg> for ZombieChannelState {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            ZombieChannelState::Flagged => out.write_all(b"Flagged")?,
+            ZombieChannelState::Parked => out.write_all(b"Parked")?,
+            ZombieChannelState::ForceClosed => out.write_all(b"ForceClosed")?,
+            ZombieChannelState::Exempt => out.write_all(b"Exempt")?,
+            ZombieChannelState::Cleared => out.write_all(b"Cleared")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<ZombieChannelStateType, Pg> for ZombieChannelState {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"Flagged" => Ok(ZombieChannelState::Flagged),
+            b"Parked" => Ok(ZombieChannelState::Parked),
+            b"ForceClosed" => Ok(ZombieChannelState::ForceClosed),
+            b"Exempt" => Ok(ZombieChannelState::Exempt),
+            b"Cleared" => Ok(ZombieChannelState::Cleared),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+impl ToSql<AccelerationMethodType, Pg> for AccelerationMethod {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            AccelerationMethod::Provider => out.write_all(b"Provider")?,
+            AccelerationMethod::Cpfp => out.write_all(b"Cpfp")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<AccelerationMethodType, Pg> for AccelerationMethod {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"Provider" => Ok(AccelerationMethod::Provider),
+            b"Cpfp" => Ok(AccelerationMethod::Cpfp),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+impl ToSql<AccelerationTriggerType, Pg> for AccelerationTrigger {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            AccelerationTrigger::Manual => out.write_all(b"Manual")?,
+            AccelerationTrigger::Automatic => out.write_all(b"Automatic")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<AccelerationTriggerType, Pg> for AccelerationTrigger {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"Manual" => Ok(AccelerationTrigger::Manual),
+            b"Automatic" => Ok(AccelerationTrigger::Automatic),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+impl ToSql<JobOutcomeType, Pg> for JobOutcome {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            JobOutcome::Running => out.write_all(b"Running")?,
+            JobOutcome::Succeeded => out.write_all(b"Succeeded")?,
+            JobOutcome::Failed => out.write_all(b"Failed")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<JobOutcomeType, Pg> for JobOutcome {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"Running" => Ok(JobOutcome::Running),
+            b"Succeeded" => Ok(JobOutcome::Succeeded),
+            b"Failed" => Ok(JobOutcome::Failed),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+impl ToSql<LightningPaymentStatusType, Pg> for PaymentStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            PaymentStatus::Pending => out.write_all(b"Pending")?,
+            PaymentStatus::InFlight => out.write_all(b"InFlight")?,
+            PaymentStatus::Succeeded => out.write_all(b"Succeeded")?,
+            PaymentStatus::Failed => out.write_all(b"Failed")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<LightningPaymentStatusType, Pg> for PaymentStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"Pending" => Ok(PaymentStatus::Pending),
+            b"InFlight" => Ok(PaymentStatus::InFlight),
+            b"Succeeded" => Ok(PaymentStatus::Succeeded),
+            b"Failed" => Ok(PaymentStatus::Failed),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+impl ToSql<RejectReasonType, Pg> for RejectReason {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            RejectReason::Unknown => out.write_all(b"Unknown")?,
+            RejectReason::InvalidOffer => out.write_all(b"InvalidOffer")?,
+            RejectReason::AcceptFailed => out.write_all(b"AcceptFailed")?,
+            RejectReason::Stale => out.write_all(b"Stale")?,
+            RejectReason::Cancelled => out.write_all(b"Cancelled")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<RejectReasonType, Pg> for RejectReason {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"Unknown" => Ok(RejectReason::Unknown),
+            b"InvalidOffer" => Ok(RejectReason::InvalidOffer),
+            b"AcceptFailed" => Ok(RejectReason::AcceptFailed),
+            b"Stale" => Ok(RejectReason::Stale),
+            b"Cancelled" => Ok(RejectReason::Cancelled),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
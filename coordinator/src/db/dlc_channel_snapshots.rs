@@ -0,0 +1,221 @@
+use crate::schema::dlc_channel_snapshots;
+use bitcoin::Amount;
+use bitcoin_old::hashes::hex::ToHex;
+use diesel::prelude::*;
+use dlc_manager::DlcChannelId;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+#[derive(Queryable, Debug, Clone)]
+struct DlcChannelSnapshotRecord {
+    #[allow(dead_code)]
+    id: i32,
+    #[allow(dead_code)]
+    channel_id: String,
+    state: String,
+    reference_id: Option<String>,
+    coordinator_collateral_sats: Option<i64>,
+    trader_collateral_sats: Option<i64>,
+    coordinator_reserve_sats: Option<i64>,
+    trader_reserve_sats: Option<i64>,
+    created_at: OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = dlc_channel_snapshots)]
+struct NewDlcChannelSnapshotRecord {
+    channel_id: String,
+    state: String,
+    reference_id: Option<String>,
+    coordinator_collateral_sats: Option<i64>,
+    trader_collateral_sats: Option<i64>,
+    coordinator_reserve_sats: Option<i64>,
+    trader_reserve_sats: Option<i64>,
+}
+
+/// How a DLC channel looked like after a DLC channel event.
+///
+/// The collaterals and reserves are only known while the DLC channel is signed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DlcChannelSnapshot {
+    pub state: String,
+    pub reference_id: Option<String>,
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub coordinator_collateral: Option<Amount>,
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub trader_collateral: Option<Amount>,
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub coordinator_reserve: Option<Amount>,
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub trader_reserve: Option<Amount>,
+    pub created_at: OffsetDateTime,
+}
+
+/// A snapshot of a DLC channel together with what changed compared to the previous snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DlcChannelHistoryEntry {
+    #[serde(flatten)]
+    pub snapshot: DlcChannelSnapshot,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+    snapshot: &DlcChannelSnapshot,
+) -> QueryResult<()> {
+    let sats = |amount: Option<Amount>| amount.map(|amount| amount.to_sat() as i64);
+
+    diesel::insert_into(dlc_channel_snapshots::table)
+        .values(NewDlcChannelSnapshotRecord {
+            channel_id: channel_id.to_hex(),
+            state: snapshot.state.clone(),
+            reference_id: snapshot.reference_id.clone(),
+            coordinator_collateral_sats: sats(snapshot.coordinator_collateral),
+            trader_collateral_sats: sats(snapshot.trader_collateral),
+            coordinator_reserve_sats: sats(snapshot.coordinator_reserve),
+            trader_reserve_sats: sats(snapshot.trader_reserve),
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// All snapshots of the DLC channel, oldest first.
+pub fn get_by_channel_id(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+) -> QueryResult<Vec<DlcChannelSnapshot>> {
+    let records: Vec<DlcChannelSnapshotRecord> = dlc_channel_snapshots::table
+        .filter(dlc_channel_snapshots::channel_id.eq(channel_id.to_hex()))
+        .order_by(dlc_channel_snapshots::id.asc())
+        .load(conn)?;
+
+    Ok(records.into_iter().map(DlcChannelSnapshot::from).collect())
+}
+
+/// Pair every snapshot with the changes compared to its predecessor.
+///
+/// The first snapshot is compared to an empty one, i.e. all its known fields are reported as
+/// changed.
+pub fn history(snapshots: Vec<DlcChannelSnapshot>) -> Vec<DlcChannelHistoryEntry> {
+    let mut previous: Option<DlcChannelSnapshot> = None;
+
+    snapshots
+        .into_iter()
+        .map(|snapshot| {
+            let changes = diff(previous.as_ref(), &snapshot);
+            previous = Some(snapshot.clone());
+
+            DlcChannelHistoryEntry { snapshot, changes }
+        })
+        .collect()
+}
+
+fn diff(previous: Option<&DlcChannelSnapshot>, current: &DlcChannelSnapshot) -> Vec<FieldChange> {
+    let fields = |snapshot: Option<&DlcChannelSnapshot>| {
+        let sats = |amount: Option<Amount>| amount.map(|amount| amount.to_sat().to_string());
+
+        [
+            ("state", snapshot.map(|s| s.state.clone())),
+            (
+                "reference_id",
+                snapshot.and_then(|s| s.reference_id.clone()),
+            ),
+            (
+                "coordinator_collateral",
+                snapshot.and_then(|s| sats(s.coordinator_collateral)),
+            ),
+            (
+                "trader_collateral",
+                snapshot.and_then(|s| sats(s.trader_collateral)),
+            ),
+            (
+                "coordinator_reserve",
+                snapshot.and_then(|s| sats(s.coordinator_reserve)),
+            ),
+            (
+                "trader_reserve",
+                snapshot.and_then(|s| sats(s.trader_reserve)),
+            ),
+        ]
+    };
+
+    fields(previous)
+        .into_iter()
+        .zip(fields(Some(current)))
+        .filter(|((_, from), (_, to))| from != to)
+        .map(|((field, from), (_, to))| FieldChange { field, from, to })
+        .collect()
+}
+
+impl From<DlcChannelSnapshotRecord> for DlcChannelSnapshot {
+    fn from(value: DlcChannelSnapshotRecord) -> Self {
+        let amount = |sats: Option<i64>| sats.map(|sats| Amount::from_sat(sats as u64));
+
+        Self {
+            state: value.state,
+            reference_id: value.reference_id,
+            coordinator_collateral: amount(value.coordinator_collateral_sats),
+            trader_collateral: amount(value.trader_collateral_sats),
+            coordinator_reserve: amount(value.coordinator_reserve_sats),
+            trader_reserve: amount(value.trader_reserve_sats),
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_reports_changes_between_consecutive_snapshots() {
+        let established = DlcChannelSnapshot {
+            state: "Established".to_string(),
+            reference_id: Some("open".to_string()),
+            coordinator_collateral: Some(Amount::from_sat(100_000)),
+            trader_collateral: Some(Amount::from_sat(50_000)),
+            coordinator_reserve: Some(Amount::from_sat(10_000)),
+            trader_reserve: Some(Amount::from_sat(5_000)),
+            created_at: OffsetDateTime::UNIX_EPOCH,
+        };
+        let settled = DlcChannelSnapshot {
+            state: "Settled".to_string(),
+            reference_id: Some("settle".to_string()),
+            trader_reserve: Some(Amount::from_sat(60_000)),
+            ..established.clone()
+        };
+
+        let history = history(vec![established, settled]);
+
+        assert_eq!(history[0].changes.len(), 6);
+        assert_eq!(
+            history[1].changes,
+            vec![
+                FieldChange {
+                    field: "state",
+                    from: Some("Established".to_string()),
+                    to: Some("Settled".to_string()),
+                },
+                FieldChange {
+                    field: "reference_id",
+                    from: Some("open".to_string()),
+                    to: Some("settle".to_string()),
+                },
+                FieldChange {
+                    field: "trader_reserve",
+                    from: Some("5000".to_string()),
+                    to: Some("60000".to_string()),
+                },
+            ]
+        );
+    }
+}
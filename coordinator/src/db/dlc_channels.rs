@@ -56,6 +56,7 @@ pub(crate) struct DlcChannel {
     updated_at: OffsetDateTime,
     coordinator_funding_sats: i64,
     trader_funding_sats: i64,
+    force_close_notified_at: Option<OffsetDateTime>,
 }
 
 impl QueryId for DlcChannelStateType {
@@ -244,6 +245,66 @@ pub(crate) fn get_dlc_channel(
     Ok(dlc_channel.map(channel::DlcChannel::from))
 }
 
+pub(crate) fn get_open_dlc_channels(
+    conn: &mut PgConnection,
+) -> QueryResult<Vec<channel::DlcChannel>> {
+    let dlc_channels: Vec<DlcChannel> = dlc_channels::table
+        .filter(dlc_channels::channel_state.eq(DlcChannelState::Open))
+        .load(conn)?;
+
+    Ok(dlc_channels
+        .into_iter()
+        .map(channel::DlcChannel::from)
+        .collect())
+}
+
+/// Get the DLC channels which are being force-closed, but whose traders have not been notified
+/// about it yet.
+///
+/// A DLC channel without a close transaction is not closed collaboratively.
+pub(crate) fn get_force_closing_dlc_channels_to_notify(
+    conn: &mut PgConnection,
+) -> QueryResult<Vec<channel::DlcChannel>> {
+    let dlc_channels: Vec<DlcChannel> = dlc_channels::table
+        .filter(dlc_channels::channel_state.eq(DlcChannelState::Closing))
+        .filter(dlc_channels::close_txid.is_null())
+        .filter(dlc_channels::force_close_notified_at.is_null())
+        .load(conn)?;
+
+    Ok(dlc_channels
+        .into_iter()
+        .map(channel::DlcChannel::from)
+        .collect())
+}
+
+/// Get the DLC channels which are being force-closed, but whose funds have not been claimed yet.
+///
+/// A DLC channel without a close transaction is not closed collaboratively.
+pub(crate) fn get_unclaimed_force_closing_dlc_channels(
+    conn: &mut PgConnection,
+) -> QueryResult<Vec<channel::DlcChannel>> {
+    let dlc_channels: Vec<DlcChannel> = dlc_channels::table
+        .filter(dlc_channels::channel_state.eq(DlcChannelState::Closing))
+        .filter(dlc_channels::close_txid.is_null())
+        .filter(dlc_channels::claim_txid.is_null())
+        .load(conn)?;
+
+    Ok(dlc_channels
+        .into_iter()
+        .map(channel::DlcChannel::from)
+        .collect())
+}
+
+pub(crate) fn set_force_close_notified(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+) -> QueryResult<usize> {
+    diesel::update(dlc_channels::table)
+        .set(dlc_channels::force_close_notified_at.eq(OffsetDateTime::now_utc()))
+        .filter(dlc_channels::channel_id.eq(channel_id.to_hex()))
+        .execute(conn)
+}
+
 impl From<DlcChannel> for channel::DlcChannel {
     fn from(value: DlcChannel) -> Self {
         Self {
@@ -0,0 +1,97 @@
+use crate::schema::dlc_protocol_rejections;
+use crate::schema::sql_types::RejectReasonType;
+use bitcoin::secp256k1::PublicKey;
+use diesel::prelude::*;
+use diesel::query_builder::QueryId;
+use diesel::AsExpression;
+use diesel::FromSqlRow;
+use serde::Serialize;
+use std::any::TypeId;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::message_handler;
+use xxi_node::node::ProtocolId;
+
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Serialize)]
+#[diesel(sql_type = RejectReasonType)]
+pub enum RejectReason {
+    Unknown,
+    InvalidOffer,
+    AcceptFailed,
+    Stale,
+    Cancelled,
+}
+
+impl QueryId for RejectReasonType {
+    type QueryId = RejectReasonType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct DlcProtocolRejection {
+    pub id: i32,
+    pub protocol_id: Uuid,
+    pub trader_pubkey: String,
+    pub order_id: Option<Uuid>,
+    pub reason: RejectReason,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    trader_pubkey: PublicKey,
+    order_id: Option<Uuid>,
+    reason: message_handler::RejectReason,
+) -> QueryResult<()> {
+    diesel::insert_into(dlc_protocol_rejections::table)
+        .values((
+            dlc_protocol_rejections::protocol_id.eq(protocol_id.to_uuid()),
+            dlc_protocol_rejections::trader_pubkey.eq(trader_pubkey.to_string()),
+            dlc_protocol_rejections::order_id.eq(order_id),
+            dlc_protocol_rejections::reason.eq(RejectReason::from(reason)),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The most recent rejections, optionally only those of the given trader or order.
+pub fn get(
+    conn: &mut PgConnection,
+    trader_pubkey: Option<PublicKey>,
+    order_id: Option<Uuid>,
+    limit: i64,
+) -> QueryResult<Vec<DlcProtocolRejection>> {
+    let mut query = dlc_protocol_rejections::table.into_boxed();
+
+    if let Some(trader_pubkey) = trader_pubkey {
+        query = query.filter(dlc_protocol_rejections::trader_pubkey.eq(trader_pubkey.to_string()));
+    }
+
+    if let Some(order_id) = order_id {
+        query = query.filter(dlc_protocol_rejections::order_id.eq(order_id));
+    }
+
+    query
+        .order_by(dlc_protocol_rejections::created_at.desc())
+        .limit(limit)
+        .load(conn)
+}
+
+impl From<message_handler::RejectReason> for RejectReason {
+    fn from(value: message_handler::RejectReason) -> Self {
+        match value {
+            message_handler::RejectReason::Unknown => RejectReason::Unknown,
+            message_handler::RejectReason::InvalidOffer => RejectReason::InvalidOffer,
+            message_handler::RejectReason::AcceptFailed => RejectReason::AcceptFailed,
+            message_handler::RejectReason::Stale => RejectReason::Stale,
+            message_handler::RejectReason::Cancelled => RejectReason::Cancelled,
+        }
+    }
+}
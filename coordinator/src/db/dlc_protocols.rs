@@ -8,6 +8,7 @@ use diesel::query_builder::QueryId;
 use diesel::AsExpression;
 use diesel::ExpressionMethods;
 use diesel::FromSqlRow;
+use diesel::OptionalExtension;
 use diesel::PgConnection;
 use diesel::QueryDsl;
 use diesel::QueryResult;
@@ -130,6 +131,19 @@ pub(crate) fn get_dlc_protocol(
     Ok(protocol)
 }
 
+pub(crate) fn get_dlc_protocol_state(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+) -> QueryResult<Option<dlc_protocol::DlcProtocolState>> {
+    let state: Option<DlcProtocolState> = dlc_protocols::table
+        .select(dlc_protocols::protocol_state)
+        .filter(dlc_protocols::protocol_id.eq(protocol_id.to_uuid()))
+        .first(conn)
+        .optional()?;
+
+    Ok(state.map(|state| state.into()))
+}
+
 pub(crate) fn set_dlc_protocol_state_to_failed(
     conn: &mut PgConnection,
     protocol_id: ProtocolId,
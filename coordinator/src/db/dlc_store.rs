@@ -0,0 +1,65 @@
+use crate::schema::dlc_store;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::select;
+use time::OffsetDateTime;
+
+/// The key value pairs of the given kind, or only the one with the given key.
+pub fn get(
+    conn: &mut PgConnection,
+    kind: u8,
+    key: Option<&[u8]>,
+) -> QueryResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut query = dlc_store::table
+        .filter(dlc_store::kind.eq(i16::from(kind)))
+        .select((dlc_store::key, dlc_store::value))
+        .into_boxed();
+
+    if let Some(key) = key {
+        query = query.filter(dlc_store::key.eq(key));
+    }
+
+    query.load(conn)
+}
+
+pub fn upsert(conn: &mut PgConnection, kind: u8, key: &[u8], value: &[u8]) -> QueryResult<()> {
+    diesel::insert_into(dlc_store::table)
+        .values((
+            dlc_store::kind.eq(i16::from(kind)),
+            dlc_store::key.eq(key),
+            dlc_store::value.eq(value),
+        ))
+        .on_conflict((dlc_store::kind, dlc_store::key))
+        .do_update()
+        .set((
+            dlc_store::value.eq(value),
+            dlc_store::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Delete the key value pair with the given key, or all of the given kind without one.
+pub fn delete(conn: &mut PgConnection, kind: u8, key: Option<&[u8]>) -> QueryResult<usize> {
+    let query = dlc_store::table.filter(dlc_store::kind.eq(i16::from(kind)));
+
+    match key {
+        Some(key) => diesel::delete(query.filter(dlc_store::key.eq(key))).execute(conn),
+        None => diesel::delete(query).execute(conn),
+    }
+}
+
+/// All key value pairs, by kind.
+pub fn export(conn: &mut PgConnection) -> QueryResult<Vec<(i16, Vec<u8>, Vec<u8>)>> {
+    dlc_store::table
+        .select((dlc_store::kind, dlc_store::key, dlc_store::value))
+        .order_by((dlc_store::kind, dlc_store::key))
+        .load(conn)
+}
+
+pub fn is_empty(conn: &mut PgConnection) -> QueryResult<bool> {
+    let is_not_empty = select(exists(dlc_store::table)).get_result::<bool>(conn)?;
+
+    Ok(!is_not_empty)
+}
@@ -0,0 +1,107 @@
+use crate::schema::expiry_settlement_attempts;
+use bitcoin::secp256k1::PublicKey;
+use diesel::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::ExpirySettlement;
+use xxi_node::node::ProtocolId;
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct ExpirySettlementAttempt {
+    pub id: i32,
+    pub position_id: i32,
+    pub trader_pubkey: String,
+    pub event_id: String,
+    pub attempt: i32,
+    pub attested_price: Option<f32>,
+    pub protocol_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub escalated: bool,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub cet_interval_start: Option<i64>,
+    pub cet_interval_end: Option<i64>,
+    pub cet_trader_payout_sats: Option<i64>,
+    pub exact_trader_payout_sats: Option<i64>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = expiry_settlement_attempts)]
+struct NewExpirySettlementAttempt {
+    position_id: i32,
+    trader_pubkey: String,
+    event_id: String,
+    attempt: i32,
+    attested_price: Option<f32>,
+    protocol_id: Option<Uuid>,
+    error: Option<String>,
+    escalated: bool,
+    cet_interval_start: Option<i64>,
+    cet_interval_end: Option<i64>,
+    cet_trader_payout_sats: Option<i64>,
+    exact_trader_payout_sats: Option<i64>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn insert(
+    conn: &mut PgConnection,
+    position_id: i32,
+    trader_pubkey: PublicKey,
+    event_id: &str,
+    attempt: i32,
+    attested_price: Option<Decimal>,
+    protocol_id: Option<ProtocolId>,
+    error: Option<String>,
+    escalated: bool,
+    settlement: Option<&ExpirySettlement>,
+) -> QueryResult<ExpirySettlementAttempt> {
+    diesel::insert_into(expiry_settlement_attempts::table)
+        .values(NewExpirySettlementAttempt {
+            position_id,
+            trader_pubkey: trader_pubkey.to_string(),
+            event_id: event_id.to_string(),
+            attempt,
+            attested_price: attested_price.map(|price| price.to_f32().expect("to fit into f32")),
+            protocol_id: protocol_id.map(|protocol_id| protocol_id.to_uuid()),
+            error,
+            escalated,
+            cet_interval_start: settlement.map(|s| s.interval_start as i64),
+            cet_interval_end: settlement.map(|s| s.interval_end as i64),
+            cet_trader_payout_sats: settlement.map(|s| s.trader_payout.to_sat() as i64),
+            exact_trader_payout_sats: settlement.map(|s| s.exact_trader_payout.to_sat() as i64),
+        })
+        .get_result(conn)
+}
+
+/// Get the latest settlement attempt for the given position, if any.
+pub fn get_latest(
+    conn: &mut PgConnection,
+    position_id: i32,
+) -> QueryResult<Option<ExpirySettlementAttempt>> {
+    expiry_settlement_attempts::table
+        .filter(expiry_settlement_attempts::position_id.eq(position_id))
+        .order_by(expiry_settlement_attempts::attempt.desc())
+        .first(conn)
+        .optional()
+}
+
+/// Whether the settlement of the given position has already been escalated to the admins.
+pub fn is_escalated(conn: &mut PgConnection, position_id: i32) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        expiry_settlement_attempts::table
+            .filter(expiry_settlement_attempts::position_id.eq(position_id))
+            .filter(expiry_settlement_attempts::escalated.eq(true)),
+    ))
+    .get_result(conn)
+}
+
+/// Get all escalated settlement attempts, most recent first.
+pub fn get_escalated(conn: &mut PgConnection) -> QueryResult<Vec<ExpirySettlementAttempt>> {
+    expiry_settlement_attempts::table
+        .filter(expiry_settlement_attempts::escalated.eq(true))
+        .order_by(expiry_settlement_attempts::created_at.desc())
+        .load(conn)
+}
@@ -0,0 +1,114 @@
+use crate::schema::funding_accelerations;
+use crate::schema::sql_types::AccelerationMethodType;
+use crate::schema::sql_types::AccelerationTriggerType;
+use bitcoin::Amount;
+use bitcoin::Txid;
+use diesel::prelude::*;
+use diesel::query_builder::QueryId;
+use diesel::AsExpression;
+use diesel::FromSqlRow;
+use dlc_manager::DlcChannelId;
+use serde::Deserialize;
+use serde::Serialize;
+use std::any::TypeId;
+use time::OffsetDateTime;
+
+/// How a stuck funding transaction was accelerated.
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Serialize, Deserialize)]
+#[diesel(sql_type = AccelerationMethodType)]
+pub enum AccelerationMethod {
+    /// The transaction was submitted to the configured accelerator provider.
+    Provider,
+    /// The coordinator spent its change output of the transaction in a child transaction paying
+    /// for both.
+    Cpfp,
+}
+
+/// Who asked for the acceleration.
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Serialize, Deserialize)]
+#[diesel(sql_type = AccelerationTriggerType)]
+pub enum AccelerationTrigger {
+    /// An admin.
+    Manual,
+    /// The acceleration policy, see [`crate::node::funding_accelerator`].
+    Automatic,
+}
+
+impl QueryId for AccelerationMethodType {
+    type QueryId = AccelerationMethodType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+impl QueryId for AccelerationTriggerType {
+    type QueryId = AccelerationTriggerType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct FundingAcceleration {
+    pub id: i32,
+    pub channel_id: String,
+    pub funding_txid: String,
+    pub method: AccelerationMethod,
+    pub trigger: AccelerationTrigger,
+    pub child_txid: Option<String>,
+    /// What the acceleration cost the coordinator.
+    pub cost_sats: i64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = funding_accelerations)]
+struct NewFundingAcceleration {
+    channel_id: String,
+    funding_txid: String,
+    method: AccelerationMethod,
+    trigger: AccelerationTrigger,
+    child_txid: Option<String>,
+    cost_sats: i64,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+    funding_txid: Txid,
+    method: AccelerationMethod,
+    trigger: AccelerationTrigger,
+    child_txid: Option<Txid>,
+    cost: Amount,
+) -> QueryResult<FundingAcceleration> {
+    diesel::insert_into(funding_accelerations::table)
+        .values(NewFundingAcceleration {
+            channel_id: hex::encode(channel_id),
+            funding_txid: funding_txid.to_string(),
+            method,
+            trigger,
+            child_txid: child_txid.map(|txid| txid.to_string()),
+            cost_sats: cost.to_sat() as i64,
+        })
+        .get_result(conn)
+}
+
+/// Get all accelerations, most recent first.
+pub fn get_all(conn: &mut PgConnection) -> QueryResult<Vec<FundingAcceleration>> {
+    funding_accelerations::table
+        .order_by(funding_accelerations::created_at.desc())
+        .load(conn)
+}
+
+/// Whether the funding transaction has been accelerated before.
+pub fn is_accelerated(conn: &mut PgConnection, funding_txid: Txid) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(funding_accelerations::table.filter(
+        funding_accelerations::funding_txid.eq(funding_txid.to_string()),
+    )))
+    .get_result(conn)
+}
@@ -6,6 +6,7 @@ use bitcoin::secp256k1::PublicKey;
 use bitcoin::Amount;
 use diesel::query_builder::QueryId;
 use diesel::AsExpression;
+use diesel::BoolExpressionMethods;
 use diesel::ExpressionMethods;
 use diesel::FromSqlRow;
 use diesel::PgConnection;
@@ -13,6 +14,7 @@ use diesel::QueryDsl;
 use diesel::QueryResult;
 use diesel::RunQueryDsl;
 use std::any::TypeId;
+use std::str::FromStr;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
@@ -35,11 +37,47 @@ impl QueryId for InvoiceStateType {
     }
 }
 
-pub fn cancel_pending_hodl_invoices(conn: &mut PgConnection) -> QueryResult<usize> {
-    diesel::update(hodl_invoices::table)
-        .filter(hodl_invoices::invoice_state.eq_any([InvoiceState::Open, InvoiceState::Accepted]))
-        .set(hodl_invoices::invoice_state.eq(InvoiceState::Canceled))
-        .execute(conn)
+/// A hodl invoice which has neither been settled nor cancelled, and which is not backing an order.
+#[derive(Debug, Clone)]
+pub struct PendingHodlInvoice {
+    pub r_hash: String,
+    pub trader_pubkey: PublicKey,
+    pub amount_sats: u64,
+    pub invoice_state: InvoiceState,
+}
+
+/// Returns the hodl invoices which are still pending.
+///
+/// Accepted invoices with a reservation are left to
+/// [`crate::node::invoice::reconcile_expired_reservations`], as the offers backed by them have to
+/// be cancelled too.
+pub fn get_pending_hodl_invoices(conn: &mut PgConnection) -> Result<Vec<PendingHodlInvoice>> {
+    let rows: Vec<(String, String, i64, InvoiceState)> = hodl_invoices::table
+        .filter(
+            hodl_invoices::invoice_state
+                .eq(InvoiceState::Open)
+                .or(hodl_invoices::invoice_state
+                    .eq(InvoiceState::Accepted)
+                    .and(hodl_invoices::reserved_until.is_null())),
+        )
+        .select((
+            hodl_invoices::r_hash,
+            hodl_invoices::trader_pubkey,
+            hodl_invoices::amount_sats,
+            hodl_invoices::invoice_state,
+        ))
+        .load(conn)?;
+
+    rows.into_iter()
+        .map(|(r_hash, trader_pubkey, amount_sats, invoice_state)| {
+            Ok(PendingHodlInvoice {
+                r_hash,
+                trader_pubkey: PublicKey::from_str(&trader_pubkey)?,
+                amount_sats: amount_sats as u64,
+                invoice_state,
+            })
+        })
+        .collect()
 }
 
 pub fn create_hodl_invoice(
@@ -82,11 +120,57 @@ pub fn get_pre_image_by_order_id(
         .get_result(conn)
 }
 
+/// Returns until when the funds of the hodl invoice associated with the order id are reserved for
+/// the order.
+pub fn get_reserved_until_by_order_id(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+) -> QueryResult<Option<OffsetDateTime>> {
+    hodl_invoices::table
+        .filter(hodl_invoices::order_id.eq(order_id))
+        .select(hodl_invoices::reserved_until)
+        .get_result(conn)
+}
+
+/// An accepted hodl invoice which has not been settled before its reservation expired.
+#[derive(Debug, Clone)]
+pub struct ExpiredReservation {
+    pub r_hash: String,
+    pub trader_pubkey: PublicKey,
+    pub order_id: Option<Uuid>,
+}
+
+pub fn get_expired_reservations(
+    conn: &mut PgConnection,
+    now: OffsetDateTime,
+) -> Result<Vec<ExpiredReservation>> {
+    let rows: Vec<(String, String, Option<Uuid>)> = hodl_invoices::table
+        .filter(hodl_invoices::invoice_state.eq(InvoiceState::Accepted))
+        .filter(hodl_invoices::reserved_until.lt(now))
+        .select((
+            hodl_invoices::r_hash,
+            hodl_invoices::trader_pubkey,
+            hodl_invoices::order_id,
+        ))
+        .load(conn)?;
+
+    rows.into_iter()
+        .map(|(r_hash, trader_pubkey, order_id)| {
+            Ok(ExpiredReservation {
+                r_hash,
+                trader_pubkey: PublicKey::from_str(&trader_pubkey)?,
+                order_id,
+            })
+        })
+        .collect()
+}
+
 pub fn update_hodl_invoice_to_accepted(
     conn: &mut PgConnection,
     hash: &str,
     pre_image: &str,
     order_id: Uuid,
+    reserved_until: OffsetDateTime,
 ) -> Result<Amount> {
     let amount: i64 = diesel::update(hodl_invoices::table)
         .filter(hodl_invoices::r_hash.eq(hash))
@@ -95,6 +179,7 @@ pub fn update_hodl_invoice_to_accepted(
             hodl_invoices::updated_at.eq(OffsetDateTime::now_utc()),
             hodl_invoices::invoice_state.eq(InvoiceState::Accepted),
             hodl_invoices::order_id.eq(order_id),
+            hodl_invoices::reserved_until.eq(reserved_until),
         ))
         .returning(hodl_invoices::amount_sats)
         .get_result(conn)?;
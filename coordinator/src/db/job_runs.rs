@@ -0,0 +1,103 @@
+use crate::schema::job_runs;
+use crate::schema::sql_types::JobOutcomeType;
+use diesel::prelude::*;
+use diesel::query_builder::QueryId;
+use diesel::AsExpression;
+use diesel::FromSqlRow;
+use serde::Serialize;
+use std::any::TypeId;
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Serialize)]
+#[diesel(sql_type = JobOutcomeType)]
+pub enum JobOutcome {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl QueryId for JobOutcomeType {
+    type QueryId = JobOutcomeType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct JobRun {
+    pub id: i32,
+    pub job_name: String,
+    pub triggered_manually: bool,
+    pub outcome: JobOutcome,
+    pub error: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub finished_at: Option<OffsetDateTime>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = job_runs)]
+struct NewJobRun {
+    job_name: String,
+    triggered_manually: bool,
+}
+
+/// Record the start of a job run, returning the ID of the run.
+pub fn start(
+    conn: &mut PgConnection,
+    job_name: &str,
+    triggered_manually: bool,
+) -> QueryResult<i32> {
+    diesel::insert_into(job_runs::table)
+        .values(NewJobRun {
+            job_name: job_name.to_string(),
+            triggered_manually,
+        })
+        .returning(job_runs::id)
+        .get_result(conn)
+}
+
+pub fn finish(conn: &mut PgConnection, id: i32, error: Option<String>) -> QueryResult<()> {
+    let outcome = match error {
+        Some(_) => JobOutcome::Failed,
+        None => JobOutcome::Succeeded,
+    };
+
+    diesel::update(job_runs::table)
+        .filter(job_runs::id.eq(id))
+        .set((
+            job_runs::outcome.eq(outcome),
+            job_runs::error.eq(error),
+            job_runs::finished_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Mark all runs which are still running as failed.
+///
+/// Only to be called before any job is started, as these runs have been interrupted by a restart
+/// of the coordinator.
+pub fn fail_interrupted(conn: &mut PgConnection) -> QueryResult<usize> {
+    diesel::update(job_runs::table)
+        .filter(job_runs::outcome.eq(JobOutcome::Running))
+        .set((
+            job_runs::outcome.eq(JobOutcome::Failed),
+            job_runs::error.eq("Interrupted by a restart of the coordinator"),
+            job_runs::finished_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)
+}
+
+/// The latest runs of the job, the most recent first.
+pub fn get_latest(conn: &mut PgConnection, job_name: &str, limit: i64) -> QueryResult<Vec<JobRun>> {
+    job_runs::table
+        .filter(job_runs::job_name.eq(job_name))
+        .order_by(job_runs::started_at.desc())
+        .limit(limit)
+        .load(conn)
+}
@@ -0,0 +1,76 @@
+use crate::schema::kill_switch_changes;
+use diesel::prelude::*;
+use std::collections::BTreeSet;
+use xxi_node::commons::KillSwitchMode;
+use xxi_node::commons::KillSwitchStatus;
+
+#[derive(Queryable, Debug, Clone)]
+#[allow(dead_code)]
+struct KillSwitchChange {
+    id: i32,
+    read_only: bool,
+    no_new_channels: bool,
+    no_withdrawals: bool,
+    full_halt: bool,
+    reason: Option<String>,
+    created_at: time::OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = kill_switch_changes)]
+struct NewKillSwitchChange {
+    read_only: bool,
+    no_new_channels: bool,
+    no_withdrawals: bool,
+    full_halt: bool,
+    reason: Option<String>,
+}
+
+/// The status of the kill switch as of the latest change. Disengaged if it was never changed.
+pub fn get(conn: &mut PgConnection) -> QueryResult<KillSwitchStatus> {
+    let change: Option<KillSwitchChange> = kill_switch_changes::table
+        .order_by(kill_switch_changes::id.desc())
+        .first(conn)
+        .optional()?;
+
+    Ok(change.map(KillSwitchStatus::from).unwrap_or_default())
+}
+
+pub fn insert(conn: &mut PgConnection, status: &KillSwitchStatus) -> QueryResult<()> {
+    diesel::insert_into(kill_switch_changes::table)
+        .values(NewKillSwitchChange::from(status))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+impl From<KillSwitchChange> for KillSwitchStatus {
+    fn from(value: KillSwitchChange) -> Self {
+        let modes = [
+            (value.read_only, KillSwitchMode::ReadOnly),
+            (value.no_new_channels, KillSwitchMode::NoNewChannels),
+            (value.no_withdrawals, KillSwitchMode::NoWithdrawals),
+            (value.full_halt, KillSwitchMode::FullHalt),
+        ]
+        .into_iter()
+        .filter_map(|(engaged, mode)| engaged.then_some(mode))
+        .collect::<BTreeSet<_>>();
+
+        KillSwitchStatus {
+            modes,
+            reason: value.reason,
+        }
+    }
+}
+
+impl From<&KillSwitchStatus> for NewKillSwitchChange {
+    fn from(value: &KillSwitchStatus) -> Self {
+        NewKillSwitchChange {
+            read_only: value.modes.contains(&KillSwitchMode::ReadOnly),
+            no_new_channels: value.modes.contains(&KillSwitchMode::NoNewChannels),
+            no_withdrawals: value.modes.contains(&KillSwitchMode::NoWithdrawals),
+            full_halt: value.modes.contains(&KillSwitchMode::FullHalt),
+            reason: value.reason.clone(),
+        }
+    }
+}
@@ -16,6 +16,7 @@ use diesel::QueryResult;
 use diesel::Queryable;
 use diesel::QueryableByName;
 use diesel::RunQueryDsl;
+use std::str::FromStr;
 use time::OffsetDateTime;
 use xxi_node::dlc_message::SerializedDlcMessage;
 
@@ -60,6 +61,33 @@ pub(crate) fn get(
     Ok(serialized_dlc_message)
 }
 
+pub(crate) fn get_all(conn: &mut PgConnection) -> Result<Vec<(PublicKey, SerializedDlcMessage)>> {
+    let last_outbound_dlc_messages = last_outbound_dlc_messages::table
+        .inner_join(
+            dlc_messages::table
+                .on(dlc_messages::message_hash.eq(last_outbound_dlc_messages::message_hash)),
+        )
+        .select((
+            last_outbound_dlc_messages::peer_id,
+            dlc_messages::message_type,
+            last_outbound_dlc_messages::message,
+        ))
+        .load::<(String, MessageType, String)>(conn)?;
+
+    last_outbound_dlc_messages
+        .into_iter()
+        .map(|(peer_id, message_type, message)| {
+            let peer_id = PublicKey::from_str(&peer_id)?;
+            let serialized_dlc_message = SerializedDlcMessage {
+                message,
+                message_type: xxi_node::dlc_message::DlcMessageType::from(message_type),
+            };
+
+            Ok((peer_id, serialized_dlc_message))
+        })
+        .collect()
+}
+
 pub(crate) fn upsert(
     conn: &mut PgConnection,
     peer_id: &PublicKey,
@@ -0,0 +1,72 @@
+use crate::schema::message_archive;
+use bitcoin::secp256k1::PublicKey;
+use diesel::prelude::*;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::node::ProtocolId;
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ArchivedMessage {
+    pub id: i32,
+    pub protocol_id: Uuid,
+    pub peer_id: String,
+    pub inbound: bool,
+    pub message_type: String,
+    pub message_hash: String,
+    pub encrypted_message: Vec<u8>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = message_archive)]
+struct NewArchivedMessage {
+    protocol_id: Uuid,
+    peer_id: String,
+    inbound: bool,
+    message_type: String,
+    message_hash: String,
+    encrypted_message: Vec<u8>,
+}
+
+/// Archive an encrypted message. A message which has already been archived is ignored.
+pub fn insert(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    peer_id: PublicKey,
+    inbound: bool,
+    message_type: &str,
+    message_hash: &str,
+    encrypted_message: Vec<u8>,
+) -> QueryResult<()> {
+    diesel::insert_into(message_archive::table)
+        .values(NewArchivedMessage {
+            protocol_id: protocol_id.to_uuid(),
+            peer_id: peer_id.to_string(),
+            inbound,
+            message_type: message_type.to_string(),
+            message_hash: message_hash.to_string(),
+            encrypted_message,
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// All messages archived for the DLC protocol, in the order they were archived.
+pub fn get_by_protocol_id(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+) -> QueryResult<Vec<ArchivedMessage>> {
+    message_archive::table
+        .filter(message_archive::protocol_id.eq(protocol_id.to_uuid()))
+        .order_by(message_archive::id.asc())
+        .load(conn)
+}
+
+/// Delete all messages archived before the cut-off, returning the number of deleted messages.
+pub fn delete_before(conn: &mut PgConnection, cut_off: OffsetDateTime) -> QueryResult<usize> {
+    diesel::delete(message_archive::table)
+        .filter(message_archive::created_at.lt(cut_off))
+        .execute(conn)
+}
@@ -1,10 +1,26 @@
 use crate::schema::metrics;
 use anyhow::ensure;
 use anyhow::Result;
+use diesel::sql_types::BigInt;
+use diesel::sql_types::Text;
 use diesel::ExpressionMethods;
 use diesel::PgConnection;
+use diesel::QueryResult;
+use diesel::QueryableByName;
 use diesel::RunQueryDsl;
 
+#[derive(QueryableByName, Debug, Clone)]
+pub struct TableSize {
+    #[diesel(sql_type = Text)]
+    pub table_name: String,
+    /// The estimated number of rows, as of the last `ANALYZE` of the table.
+    #[diesel(sql_type = BigInt)]
+    pub rows: i64,
+    /// The size of the table on disk, including indices and TOAST data.
+    #[diesel(sql_type = BigInt)]
+    pub bytes: i64,
+}
+
 pub fn create_metrics_entry(conn: &mut PgConnection, on_chain_balance: u64) -> Result<()> {
     let affected_rows = diesel::insert_into(metrics::table)
         .values(metrics::on_chain_balance_sats.eq(on_chain_balance as i64))
@@ -14,3 +30,14 @@ pub fn create_metrics_entry(conn: &mut PgConnection, on_chain_balance: u64) -> R
 
     Ok(())
 }
+
+/// The sizes of all tables in the public schema.
+pub fn get_table_sizes(conn: &mut PgConnection) -> QueryResult<Vec<TableSize>> {
+    diesel::sql_query(
+        "SELECT relname::text AS table_name, \
+         GREATEST(reltuples, 0)::bigint AS rows, \
+         pg_total_relation_size(oid) AS bytes \
+         FROM pg_class WHERE relkind = 'r' AND relnamespace = 'public'::regnamespace",
+    )
+    .load(conn)
+}
@@ -1,21 +1,39 @@
+pub mod api_key_nonces;
+pub mod api_keys;
 pub mod bonus_status;
 pub mod bonus_tiers;
+pub mod channel_migrations;
 pub mod channel_opening_params;
 pub mod collaborative_reverts;
 pub mod custom_types;
+pub mod dlc_channel_snapshots;
 pub mod dlc_channels;
 pub mod dlc_messages;
+pub mod dlc_protocol_rejections;
 pub mod dlc_protocols;
+pub mod dlc_store;
+pub mod expiry_settlement_attempts;
+pub mod funding_accelerations;
 pub mod hodl_invoice;
+pub mod job_runs;
+pub mod kill_switch;
 pub mod last_outbound_dlc_message;
 pub mod liquidity_options;
+pub mod message_archive;
 pub mod metrics;
 pub mod polls;
 pub mod positions;
+pub mod quiesce;
 pub mod reported_errors;
+pub mod retention;
 pub mod rollover_params;
+pub mod settlement_attestations;
+pub mod settlement_disputes;
 pub mod spendable_outputs;
+pub mod support_tickets;
+pub mod trade_executions;
 pub mod trade_params;
 pub mod trades;
 pub mod transactions;
 pub mod user;
+pub mod zombie_channels;
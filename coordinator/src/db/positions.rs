@@ -20,6 +20,11 @@ use rust_decimal::Decimal;
 use std::any::TypeId;
 use time::OffsetDateTime;
 use xxi_node::commons;
+use xxi_node::commons::Cursor;
+use xxi_node::commons::Page;
+use xxi_node::commons::PageParams;
+use xxi_node::commons::PositionFilter;
+use xxi_node::commons::SortOrder;
 
 #[derive(Queryable, Debug, Clone)]
 pub struct Position {
@@ -145,6 +150,65 @@ impl Position {
         Ok(positions)
     }
 
+    /// A page of the closed positions of the given trader, by the time they were closed.
+    pub fn get_closed_positions_by_trader(
+        conn: &mut PgConnection,
+        trader_pubkey: PublicKey,
+        filter: PositionFilter,
+        page: &PageParams<i32>,
+    ) -> QueryResult<Page<crate::position::models::Position, i32>> {
+        let mut query = positions::table
+            .filter(positions::trader_pubkey.eq(trader_pubkey.to_string()))
+            .filter(positions::position_state.eq(PositionState::Closed))
+            .into_boxed();
+
+        if let Some(contract_symbol) = filter.contract_symbol {
+            query =
+                query.filter(positions::contract_symbol.eq(ContractSymbol::from(contract_symbol)));
+        }
+
+        if let Some(after) = page.after {
+            query = match page.sort {
+                SortOrder::Asc => query.filter(
+                    positions::update_timestamp
+                        .gt(after.timestamp)
+                        .or(positions::update_timestamp
+                            .eq(after.timestamp)
+                            .and(positions::id.gt(after.id))),
+                ),
+                SortOrder::Desc => query.filter(
+                    positions::update_timestamp
+                        .lt(after.timestamp)
+                        .or(positions::update_timestamp
+                            .eq(after.timestamp)
+                            .and(positions::id.lt(after.id))),
+                ),
+            };
+        }
+
+        query = match page.sort {
+            SortOrder::Asc => {
+                query.order_by((positions::update_timestamp.asc(), positions::id.asc()))
+            }
+            SortOrder::Desc => {
+                query.order_by((positions::update_timestamp.desc(), positions::id.desc()))
+            }
+        };
+
+        let limit = page.limit();
+        let positions = query.limit(limit + 1).load::<Position>(conn)?;
+
+        let positions = positions
+            .into_iter()
+            .map(crate::position::models::Position::from)
+            .collect();
+
+        Ok(Page::new(positions, limit, |position| Cursor {
+            timestamp: position.update_timestamp,
+            id: position.id,
+        }))
+    }
+
     pub fn get_all_open_or_closing_positions(
         conn: &mut PgConnection,
     ) -> QueryResult<Vec<crate::position::models::Position>> {
@@ -536,6 +600,7 @@ impl From<(PositionState, Option<i64>, Option<f32>)> for crate::position::models
 #[diesel(sql_type = ContractSymbolType)]
 pub enum ContractSymbol {
     BtcUsd,
+    EthUsd,
 }
 
 impl QueryId for ContractSymbolType {
@@ -551,6 +616,7 @@ impl From<ContractSymbol> for commons::ContractSymbol {
     fn from(value: ContractSymbol) -> Self {
         match value {
             ContractSymbol::BtcUsd => commons::ContractSymbol::BtcUsd,
+            ContractSymbol::EthUsd => commons::ContractSymbol::EthUsd,
         }
     }
 }
@@ -559,6 +625,7 @@ impl From<commons::ContractSymbol> for ContractSymbol {
     fn from(value: commons::ContractSymbol) -> Self {
         match value {
             commons::ContractSymbol::BtcUsd => ContractSymbol::BtcUsd,
+            commons::ContractSymbol::EthUsd => ContractSymbol::EthUsd,
         }
     }
 }
@@ -0,0 +1,60 @@
+use crate::node::quiesce::QuiesceStatus;
+use crate::schema::quiesce_changes;
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+#[derive(Queryable, Debug, Clone)]
+#[allow(dead_code)]
+struct QuiesceChange {
+    id: i32,
+    engaged: bool,
+    until: Option<OffsetDateTime>,
+    reason: Option<String>,
+    created_at: OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = quiesce_changes)]
+struct NewQuiesceChange {
+    engaged: bool,
+    until: Option<OffsetDateTime>,
+    reason: Option<String>,
+}
+
+/// The status of the quiesce as of the latest change. Released if it was never changed.
+pub fn get(conn: &mut PgConnection) -> QueryResult<QuiesceStatus> {
+    let change: Option<QuiesceChange> = quiesce_changes::table
+        .order_by(quiesce_changes::id.desc())
+        .first(conn)
+        .optional()?;
+
+    Ok(change.map(QuiesceStatus::from).unwrap_or_default())
+}
+
+pub fn insert(conn: &mut PgConnection, status: &QuiesceStatus) -> QueryResult<()> {
+    diesel::insert_into(quiesce_changes::table)
+        .values(NewQuiesceChange::from(status))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+impl From<QuiesceChange> for QuiesceStatus {
+    fn from(value: QuiesceChange) -> Self {
+        QuiesceStatus {
+            engaged: value.engaged,
+            until: value.until,
+            reason: value.reason,
+        }
+    }
+}
+
+impl From<&QuiesceStatus> for NewQuiesceChange {
+    fn from(value: &QuiesceStatus) -> Self {
+        NewQuiesceChange {
+            engaged: value.engaged,
+            until: value.until,
+            reason: value.reason.clone(),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::retention::RetainedTable;
+use diesel::prelude::*;
+use diesel::sql_types::Array;
+use diesel::sql_types::BigInt;
+use diesel::sql_types::Text;
+use diesel::sql_types::Timestamptz;
+use time::OffsetDateTime;
+
+/// A row which is about to be pruned.
+#[derive(QueryableByName, Debug, Clone)]
+pub struct PrunableRow {
+    /// The primary key of the row, as text.
+    #[diesel(sql_type = Text)]
+    pub key: String,
+    /// The whole row as a JSON object.
+    #[diesel(sql_type = Text)]
+    pub row: String,
+}
+
+struct TableQuery {
+    /// The primary key column.
+    key: &'static str,
+    /// The SQL type of the primary key column.
+    key_type: &'static str,
+    /// Selects the rows of the table `t` which may be pruned, given the cut-off as `$1`.
+    filter: &'static str,
+}
+
+/// Get up to `limit` rows of the table which are older than the cut-off and may be pruned.
+pub fn get_batch(
+    conn: &mut PgConnection,
+    table: RetainedTable,
+    cut_off: OffsetDateTime,
+    limit: i64,
+) -> QueryResult<Vec<PrunableRow>> {
+    let TableQuery { key, filter, .. } = table_query(table);
+
+    diesel::sql_query(format!(
+        "SELECT t.{key}::text AS key, row_to_json(t)::text AS row \
+         FROM {table} t WHERE {filter} ORDER BY t.{key} LIMIT $2"
+    ))
+    .bind::<Timestamptz, _>(cut_off)
+    .bind::<BigInt, _>(limit)
+    .load(conn)
+}
+
+/// Delete the rows with the given primary keys, returning the number of deleted rows.
+pub fn delete(
+    conn: &mut PgConnection,
+    table: RetainedTable,
+    keys: Vec<String>,
+) -> QueryResult<usize> {
+    let TableQuery { key, key_type, .. } = table_query(table);
+
+    diesel::sql_query(format!(
+        "DELETE FROM {table} WHERE {key} = ANY($1::text[]::{key_type}[])"
+    ))
+    .bind::<Array<Text>, _>(keys)
+    .execute(conn)
+}
+
+fn table_query(table: RetainedTable) -> TableQuery {
+    match table {
+        // Orders are referenced by matches, hence they are only pruned once their matches are.
+        RetainedTable::Orders => TableQuery {
+            key: "trader_order_id",
+            key_type: "uuid",
+            filter: "t.timestamp < $1 \
+                     AND t.order_state IN ('Taken', 'Failed', 'Expired', 'Deleted') \
+                     AND NOT EXISTS (SELECT 1 FROM matches m \
+                                     WHERE m.order_id = t.trader_order_id \
+                                     OR m.match_order_id = t.trader_order_id)",
+        },
+        RetainedTable::Matches => TableQuery {
+            key: "id",
+            key_type: "uuid",
+            filter: "t.updated_at < $1 AND t.match_state IN ('Filled', 'Failed')",
+        },
+        RetainedTable::OrderFills => TableQuery {
+            key: "id",
+            key_type: "int4",
+            filter: "t.matched_at < $1",
+        },
+        // The last entry is kept, so that the journal's sequence numbers keep increasing.
+        RetainedTable::OrderbookJournal => TableQuery {
+            key: "sequence",
+            key_type: "int8",
+            filter: "t.created_at < $1 \
+                     AND t.sequence < (SELECT max(sequence) FROM orderbook_journal)",
+        },
+    }
+}
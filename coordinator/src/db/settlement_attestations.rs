@@ -0,0 +1,92 @@
+use crate::schema::settlement_attestations;
+use anyhow::Result;
+use bitcoin::secp256k1::XOnlyPublicKey;
+use diesel::prelude::*;
+use dlc_manager::ContractId;
+use dlc_messages::oracle_msgs::OracleAnnouncement;
+use dlc_messages::oracle_msgs::OracleAttestation;
+use lightning::util::ser::Writeable;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use xxi_node::bitcoin_conversion::to_xonly_pk_30;
+use xxi_node::commons;
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = settlement_attestations)]
+struct NewSettlementAttestation {
+    position_id: i32,
+    contract_id: String,
+    oracle_pubkey: String,
+    event_id: String,
+    announcement: Vec<u8>,
+    attestation: Vec<u8>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct SettlementAttestation {
+    #[diesel(column_name = "id")]
+    _id: i32,
+    position_id: i32,
+    #[diesel(column_name = "contract_id")]
+    _contract_id: String,
+    oracle_pubkey: String,
+    event_id: String,
+    announcement: Vec<u8>,
+    attestation: Vec<u8>,
+    created_at: OffsetDateTime,
+}
+
+/// Keep the attestation the position was settled with, unless it has been kept already.
+pub fn insert(
+    conn: &mut PgConnection,
+    position_id: i32,
+    contract_id: &ContractId,
+    announcement: &OracleAnnouncement,
+    attestation: &OracleAttestation,
+) -> QueryResult<()> {
+    diesel::insert_into(settlement_attestations::table)
+        .values(NewSettlementAttestation {
+            position_id,
+            contract_id: hex::encode(contract_id),
+            oracle_pubkey: to_xonly_pk_30(announcement.oracle_public_key).to_string(),
+            event_id: announcement.oracle_event.event_id.clone(),
+            announcement: announcement.encode(),
+            attestation: attestation.encode(),
+        })
+        .on_conflict((
+            settlement_attestations::position_id,
+            settlement_attestations::oracle_pubkey,
+            settlement_attestations::event_id,
+        ))
+        .do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The attestations the given positions were settled with, by position.
+pub fn get_by_positions(
+    conn: &mut PgConnection,
+    position_ids: &[i32],
+) -> Result<Vec<(i32, commons::SettlementAttestation)>> {
+    let attestations: Vec<SettlementAttestation> = settlement_attestations::table
+        .filter(settlement_attestations::position_id.eq_any(position_ids))
+        .order_by(settlement_attestations::id)
+        .load(conn)?;
+
+    attestations
+        .into_iter()
+        .map(|attestation| {
+            Ok((
+                attestation.position_id,
+                commons::SettlementAttestation {
+                    oracle_pubkey: XOnlyPublicKey::from_str(&attestation.oracle_pubkey)?,
+                    event_id: attestation.event_id,
+                    announcement: hex::encode(attestation.announcement),
+                    attestation: hex::encode(attestation.attestation),
+                    created_at: attestation.created_at,
+                },
+            ))
+        })
+        .collect()
+}
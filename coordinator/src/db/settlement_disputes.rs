@@ -0,0 +1,105 @@
+use crate::schema::settlement_disputes;
+use bitcoin::secp256k1::PublicKey;
+use diesel::prelude::*;
+use dlc_manager::DlcChannelId;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct SettlementDispute {
+    pub id: i32,
+    pub channel_id: String,
+    pub trader_pubkey: String,
+    pub event_id: String,
+    pub attested_price: f32,
+    pub mark_price: Option<f32>,
+    pub second_oracle_price: Option<f32>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub resolved_at: Option<OffsetDateTime>,
+    pub resolution_note: Option<String>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = settlement_disputes)]
+struct NewSettlementDispute {
+    channel_id: String,
+    trader_pubkey: String,
+    event_id: String,
+    attested_price: f32,
+    mark_price: Option<f32>,
+    second_oracle_price: Option<f32>,
+}
+
+/// Insert a new dispute for the settlement of the given DLC channel.
+///
+/// Returns `false` if a dispute for the same channel and oracle event already exists.
+pub fn insert(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+    trader_pubkey: PublicKey,
+    event_id: &str,
+    attested_price: Decimal,
+    mark_price: Option<Decimal>,
+    second_oracle_price: Option<Decimal>,
+) -> QueryResult<bool> {
+    let affected_rows = diesel::insert_into(settlement_disputes::table)
+        .values(NewSettlementDispute {
+            channel_id: hex::encode(channel_id),
+            trader_pubkey: trader_pubkey.to_string(),
+            event_id: event_id.to_string(),
+            attested_price: attested_price.to_f32().expect("to fit into f32"),
+            mark_price: mark_price.map(|price| price.to_f32().expect("to fit into f32")),
+            second_oracle_price: second_oracle_price
+                .map(|price| price.to_f32().expect("to fit into f32")),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(affected_rows > 0)
+}
+
+pub fn get_unresolved(conn: &mut PgConnection) -> QueryResult<Vec<SettlementDispute>> {
+    settlement_disputes::table
+        .filter(settlement_disputes::resolved_at.is_null())
+        .order_by(settlement_disputes::created_at.asc())
+        .load(conn)
+}
+
+/// Whether the settlement of the given DLC channel on the given oracle event is disputed and the
+/// dispute has not been resolved yet.
+pub fn is_unresolved(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+    event_id: &str,
+) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        settlement_disputes::table
+            .filter(settlement_disputes::channel_id.eq(hex::encode(channel_id)))
+            .filter(settlement_disputes::event_id.eq(event_id))
+            .filter(settlement_disputes::resolved_at.is_null()),
+    ))
+    .get_result(conn)
+}
+
+/// Mark an unresolved dispute as resolved.
+///
+/// Returns `None` if no unresolved dispute with the given id exists.
+pub fn resolve(
+    conn: &mut PgConnection,
+    id: i32,
+    resolution_note: &str,
+) -> QueryResult<Option<SettlementDispute>> {
+    diesel::update(settlement_disputes::table)
+        .filter(settlement_disputes::id.eq(id))
+        .filter(settlement_disputes::resolved_at.is_null())
+        .set((
+            settlement_disputes::resolved_at.eq(OffsetDateTime::now_utc()),
+            settlement_disputes::resolution_note.eq(resolution_note),
+        ))
+        .get_result(conn)
+        .optional()
+}
@@ -0,0 +1,125 @@
+use crate::schema::dlc_protocols;
+use crate::schema::orders;
+use crate::schema::support_tickets;
+use bitcoin::secp256k1::PublicKey;
+use diesel::prelude::*;
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::NewSupportTicket;
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct SupportTicket {
+    pub id: i32,
+    pub reference: String,
+    pub trader_pubkey: String,
+    pub error_code: String,
+    pub error: String,
+    pub order_id: Option<Uuid>,
+    pub protocol_id: Option<Uuid>,
+    pub app_version: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = support_tickets)]
+struct NewSupportTicketRow {
+    reference: String,
+    trader_pubkey: String,
+    error_code: String,
+    error: String,
+    order_id: Option<Uuid>,
+    protocol_id: Option<Uuid>,
+    app_version: String,
+}
+
+/// Insert a support ticket, unless the trader already reported the same order.
+///
+/// Returns the reference of the ticket for the order.
+pub fn insert(conn: &mut PgConnection, ticket: NewSupportTicket) -> QueryResult<String> {
+    let trader_pubkey = ticket.trader_pubkey.to_string();
+    let order_id = ticket.order_id;
+
+    let protocol_id = match (ticket.protocol_id, order_id) {
+        (Some(protocol_id), _) => Some(protocol_id),
+        (None, Some(order_id)) => get_protocol_id_for_order(conn, ticket.trader_pubkey, order_id)?,
+        (None, None) => None,
+    };
+
+    let inserted = diesel::insert_into(support_tickets::table)
+        .values(NewSupportTicketRow {
+            reference: new_reference(),
+            trader_pubkey: trader_pubkey.clone(),
+            error_code: ticket.error_code.to_string(),
+            error: ticket.error,
+            order_id,
+            protocol_id,
+            app_version: ticket.app_version,
+        })
+        .on_conflict((support_tickets::trader_pubkey, support_tickets::order_id))
+        .do_nothing()
+        .returning(support_tickets::reference)
+        .get_result(conn)
+        .optional()?;
+
+    match inserted {
+        Some(reference) => Ok(reference),
+        None => support_tickets::table
+            .filter(support_tickets::trader_pubkey.eq(trader_pubkey))
+            .filter(support_tickets::order_id.eq(order_id))
+            .select(support_tickets::reference)
+            .first(conn),
+    }
+}
+
+/// The most recent support tickets, optionally only those of the given trader.
+pub fn get(
+    conn: &mut PgConnection,
+    trader_pubkey: Option<PublicKey>,
+    limit: i64,
+) -> QueryResult<Vec<SupportTicket>> {
+    let mut query = support_tickets::table.into_boxed();
+
+    if let Some(trader_pubkey) = trader_pubkey {
+        query = query.filter(support_tickets::trader_pubkey.eq(trader_pubkey.to_string()));
+    }
+
+    query
+        .order_by(support_tickets::created_at.desc())
+        .limit(limit)
+        .load(conn)
+}
+
+/// A short reference the user can share with the support team.
+fn new_reference() -> String {
+    let id = Uuid::new_v4().simple().to_string();
+
+    format!("T-{}", id[..8].to_uppercase())
+}
+
+/// The first DLC protocol started for the trader after they submitted the given order.
+fn get_protocol_id_for_order(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    order_id: Uuid,
+) -> QueryResult<Option<Uuid>> {
+    let submitted_at: Option<OffsetDateTime> = orders::table
+        .filter(orders::trader_order_id.eq(order_id))
+        .select(orders::timestamp)
+        .first(conn)
+        .optional()?;
+
+    let submitted_at = match submitted_at {
+        Some(submitted_at) => submitted_at,
+        None => return Ok(None),
+    };
+
+    dlc_protocols::table
+        .filter(dlc_protocols::trader_pubkey.eq(trader_pubkey.to_string()))
+        .filter(dlc_protocols::timestamp.ge(submitted_at))
+        .order_by(dlc_protocols::timestamp.asc())
+        .select(dlc_protocols::protocol_id)
+        .first(conn)
+        .optional()
+}
@@ -0,0 +1,24 @@
+use crate::schema::trade_executions;
+use diesel::prelude::*;
+use uuid::Uuid;
+use xxi_node::node::ProtocolId;
+
+/// Claim the [`ProtocolId`] of a trade execution.
+///
+/// Returns `false` if it had already been claimed, i.e. the match is already being (or has been)
+/// executed.
+pub fn claim(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    order_id: Uuid,
+) -> QueryResult<bool> {
+    let affected_rows = diesel::insert_into(trade_executions::table)
+        .values((
+            trade_executions::protocol_id.eq(protocol_id.to_uuid()),
+            trade_executions::order_id.eq(order_id),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(affected_rows > 0)
+}
@@ -5,8 +5,11 @@ use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::Amount;
 use diesel::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
 use std::str::FromStr;
 use time::OffsetDateTime;
+use xxi_node::commons;
 
 #[derive(Queryable, Debug, Clone)]
 #[diesel(table_name = trades)]
@@ -78,6 +81,51 @@ pub fn get_trades(
     Ok(trades)
 }
 
+/// The order matching fees of all trades since `since`, with the time of the trade.
+pub fn get_order_matching_fees_since(
+    conn: &mut PgConnection,
+    since: OffsetDateTime,
+) -> QueryResult<Vec<(OffsetDateTime, Amount)>> {
+    let fees: Vec<(OffsetDateTime, i64)> = trades::table
+        .filter(trades::timestamp.ge(since))
+        .select((trades::timestamp, trades::order_matching_fee_sat))
+        .order_by(trades::timestamp.asc())
+        .load(conn)?;
+
+    Ok(fees
+        .into_iter()
+        .map(|(timestamp, fee)| (timestamp, Amount::from_sat(fee as u64)))
+        .collect())
+}
+
+/// The mean price of the trades in `contract_symbol` executed between `from` and `to`.
+///
+/// Returns `None` if there were no such trades.
+pub fn get_mean_price_between(
+    conn: &mut PgConnection,
+    contract_symbol: commons::ContractSymbol,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+) -> QueryResult<Option<Decimal>> {
+    let prices: Vec<f32> = trades::table
+        .filter(trades::contract_symbol.eq(ContractSymbol::from(contract_symbol)))
+        .filter(trades::timestamp.ge(from))
+        .filter(trades::timestamp.le(to))
+        .select(trades::average_price)
+        .load(conn)?;
+
+    if prices.is_empty() {
+        return Ok(None);
+    }
+
+    let sum = prices
+        .iter()
+        .filter_map(|price| Decimal::from_f32(*price))
+        .sum::<Decimal>();
+
+    Ok(Some(sum / Decimal::from(prices.len())))
+}
+
 impl From<crate::trade::models::NewTrade> for NewTrade {
     fn from(value: crate::trade::models::NewTrade) -> Self {
         NewTrade {
@@ -9,8 +9,10 @@ use bitcoin::secp256k1::PublicKey;
 use diesel::prelude::*;
 use serde::Deserialize;
 use serde::Serialize;
+use std::str::FromStr;
 use time::OffsetDateTime;
 use xxi_node::commons::referral_from_pubkey;
+use xxi_node::commons::Locale;
 use xxi_node::commons::RegisterParams;
 
 #[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +34,15 @@ pub struct User {
     /// The referral code referred by
     pub used_referral_code: Option<String>,
     pub os: Option<String>,
+    /// The language code of the user's locale, see [`Locale`].
+    pub locale: String,
+}
+
+impl User {
+    /// The user's locale, falling back to the default locale if the stored one is not supported.
+    pub fn locale(&self) -> Locale {
+        Locale::from_str(&self.locale).unwrap_or_default()
+    }
 }
 
 #[derive(Insertable, Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +83,7 @@ impl From<RegisterParams> for User {
             os: value.os,
             referral_code,
             used_referral_code: value.referral_code,
+            locale: value.locale.unwrap_or_default().to_string(),
         }
     }
 }
@@ -182,6 +194,19 @@ pub fn update_nickname(
     Ok(())
 }
 
+pub fn set_locale(
+    conn: &mut PgConnection,
+    trader_id: PublicKey,
+    locale: Locale,
+) -> QueryResult<()> {
+    diesel::update(users::table)
+        .filter(users::pubkey.eq(trader_id.to_string()))
+        .set(users::locale.eq(locale.to_string()))
+        .execute(conn)?;
+
+    Ok(())
+}
+
 pub fn login_user(
     conn: &mut PgConnection,
     trader_id: PublicKey,
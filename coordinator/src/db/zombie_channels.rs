@@ -0,0 +1,170 @@
+use crate::schema::sql_types::ZombieChannelStateType;
+use crate::schema::zombie_channels;
+use bitcoin::secp256k1::PublicKey;
+use diesel::prelude::*;
+use diesel::query_builder::QueryId;
+use diesel::AsExpression;
+use diesel::FromSqlRow;
+use dlc_manager::DlcChannelId;
+use serde::Serialize;
+use std::any::TypeId;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::node::ProtocolId;
+
+/// What happened to a DLC channel whose counterparty has been offline for too long.
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Serialize)]
+#[diesel(sql_type = ZombieChannelStateType)]
+pub enum ZombieChannelState {
+    /// The trader is being notified to come back online.
+    Flagged,
+    /// The DLC channel is only monitored occasionally.
+    Parked,
+    /// The DLC channel has been force-closed.
+    ForceClosed,
+    /// An admin decided that the DLC channel must not be treated as a zombie.
+    Exempt,
+    /// The trader came back online or an admin lifted the exemption.
+    Cleared,
+}
+
+impl QueryId for ZombieChannelStateType {
+    type QueryId = ZombieChannelStateType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+/// The states in which a DLC channel is still handled as a zombie.
+const ACTIVE_STATES: [ZombieChannelState; 3] = [
+    ZombieChannelState::Flagged,
+    ZombieChannelState::Parked,
+    ZombieChannelState::Exempt,
+];
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct ZombieChannel {
+    pub id: i32,
+    pub channel_id: String,
+    pub trader_pubkey: String,
+    pub state: ZombieChannelState,
+    /// When the trader was last seen online, when the channel was flagged.
+    #[serde(with = "time::serde::rfc3339")]
+    pub last_seen: OffsetDateTime,
+    pub notifications_sent: i32,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_notified_at: Option<OffsetDateTime>,
+    /// The force-close DLC protocol, once started.
+    pub protocol_id: Option<Uuid>,
+    pub note: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = zombie_channels)]
+struct NewZombieChannel {
+    channel_id: String,
+    trader_pubkey: String,
+    state: ZombieChannelState,
+    last_seen: OffsetDateTime,
+    note: Option<String>,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+    trader_pubkey: PublicKey,
+    state: ZombieChannelState,
+    last_seen: OffsetDateTime,
+    note: Option<String>,
+) -> QueryResult<ZombieChannel> {
+    diesel::insert_into(zombie_channels::table)
+        .values(NewZombieChannel {
+            channel_id: hex::encode(channel_id),
+            trader_pubkey: trader_pubkey.to_string(),
+            state,
+            last_seen,
+            note,
+        })
+        .get_result(conn)
+}
+
+/// Get all DLC channels which are currently handled as zombies, oldest first.
+pub fn get_active(conn: &mut PgConnection) -> QueryResult<Vec<ZombieChannel>> {
+    zombie_channels::table
+        .filter(zombie_channels::state.eq_any(ACTIVE_STATES))
+        .order_by(zombie_channels::created_at.asc())
+        .load(conn)
+}
+
+pub fn get_active_by_channel_id(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+) -> QueryResult<Option<ZombieChannel>> {
+    zombie_channels::table
+        .filter(zombie_channels::channel_id.eq(hex::encode(channel_id)))
+        .filter(zombie_channels::state.eq_any(ACTIVE_STATES))
+        .first(conn)
+        .optional()
+}
+
+pub fn record_notification(conn: &mut PgConnection, id: i32) -> QueryResult<()> {
+    let now = OffsetDateTime::now_utc();
+    diesel::update(zombie_channels::table)
+        .filter(zombie_channels::id.eq(id))
+        .set((
+            zombie_channels::notifications_sent.eq(zombie_channels::notifications_sent + 1),
+            zombie_channels::last_notified_at.eq(now),
+            zombie_channels::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Move the zombie channel to the given state. The note is only replaced if one is provided.
+pub fn set_state(
+    conn: &mut PgConnection,
+    id: i32,
+    state: ZombieChannelState,
+    note: Option<String>,
+) -> QueryResult<ZombieChannel> {
+    let query = diesel::update(zombie_channels::table).filter(zombie_channels::id.eq(id));
+    let now = OffsetDateTime::now_utc();
+
+    match note {
+        Some(note) => query
+            .set((
+                zombie_channels::state.eq(state),
+                zombie_channels::note.eq(note),
+                zombie_channels::updated_at.eq(now),
+            ))
+            .get_result(conn),
+        None => query
+            .set((
+                zombie_channels::state.eq(state),
+                zombie_channels::updated_at.eq(now),
+            ))
+            .get_result(conn),
+    }
+}
+
+pub fn set_force_closed(
+    conn: &mut PgConnection,
+    id: i32,
+    protocol_id: ProtocolId,
+) -> QueryResult<ZombieChannel> {
+    diesel::update(zombie_channels::table)
+        .filter(zombie_channels::id.eq(id))
+        .set((
+            zombie_channels::state.eq(ZombieChannelState::ForceClosed),
+            zombie_channels::protocol_id.eq(protocol_id.to_uuid()),
+            zombie_channels::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .get_result(conn)
+}
@@ -1,5 +1,9 @@
 use crate::db;
+use crate::kill_switch::KillSwitch;
+use crate::lightning_withdrawal;
+use crate::message_archive::MessageArchive;
 use crate::node::storage::NodeStorage;
+use crate::reserve_top_up;
 use crate::storage::CoordinatorTenTenOneStorage;
 use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
@@ -38,6 +42,8 @@ pub struct DlcHandler {
         >,
     >,
     pool: Pool<ConnectionManager<PgConnection>>,
+    message_archive: MessageArchive,
+    kill_switch: KillSwitch,
 }
 
 impl DlcHandler {
@@ -50,8 +56,15 @@ impl DlcHandler {
                 NodeStorage,
             >,
         >,
+        message_archive: MessageArchive,
+        kill_switch: KillSwitch,
     ) -> Self {
-        DlcHandler { node, pool }
+        DlcHandler {
+            node,
+            pool,
+            message_archive,
+            kill_switch,
+        }
     }
 }
 
@@ -86,6 +99,18 @@ pub fn spawn_handling_outbound_dlc_messages(
                     }
                 }
                 Ok(NodeEvent::DlcChannelEvent { .. }) => {} // ignored
+                Ok(NodeEvent::FundingTransactionConfirmations { .. }) => {} // ignored
+                Ok(NodeEvent::ChainDiscrepancy { .. }) => {} // ignored
+                Ok(NodeEvent::ProtocolVersionMismatch { .. }) => {} // ignored
+                Ok(NodeEvent::CollaborativeCloseFee { peer, msg }) => {
+                    if let Err(e) = dlc_handler
+                        .node
+                        .handle_collaborative_close_fee(peer, msg)
+                        .await
+                    {
+                        tracing::error!(peer=%peer, "Failed to handle collaborative close fee message. {e:#}")
+                    }
+                }
                 Err(RecvError::Lagged(skipped)) => {
                     tracing::warn!("Skipped {skipped} messages");
                 }
@@ -120,30 +145,16 @@ impl DlcHandler {
         let mut conn = self.pool.get()?;
 
         let serialized_outbound_message = SerializedDlcMessage::try_from(&msg)?;
-        let outbound_msg = DlcMessage::new(peer, serialized_outbound_message.clone(), false)?;
+        let outbound_msg = DlcMessage::new(peer, serialized_outbound_message, false)?;
 
         db::dlc_messages::insert(&mut conn, outbound_msg)?;
-        db::last_outbound_dlc_message::upsert(&mut conn, &peer, serialized_outbound_message)
+        self.message_archive.archive(peer, &msg, false);
+
+        self.node.store_last_outbound_dlc_message(peer, &msg)
     }
 
     pub fn send_last_dlc_message(&self, peer: PublicKey) -> Result<()> {
-        let mut conn = self.pool.get()?;
-
-        let last_serialized_message = db::last_outbound_dlc_message::get(&mut conn, &peer)?;
-
-        if let Some(last_serialized_message) = last_serialized_message {
-            let message = TenTenOneMessage::try_from(&last_serialized_message)?;
-            send_dlc_message(
-                &self.node.dlc_message_handler,
-                &self.node.peer_manager,
-                peer,
-                message,
-            );
-        } else {
-            tracing::debug!(%peer, "No last dlc message found. Nothing todo.");
-        }
-
-        Ok(())
+        self.node.resend_last_outbound_dlc_message(peer)
     }
 
     pub fn on_connect(&self, peer: PublicKey) -> Result<()> {
@@ -160,15 +171,31 @@ impl DlcHandler {
             .iter()
             .find(|c| c.counter_party == to_secp_pk_29(peer))
         {
-            tracing::info!("Accepting pending dlc channel close offer.");
             // Pending dlc channel close offer with the intend to close the dlc channel
             // on-chain
-
-            // TODO(bonomat): we should verify that the proposed amount is acceptable
-            self.node
-                .accept_dlc_channel_collaborative_close(channel_id)?;
-
-            return Ok(());
+            let result = self
+                .kill_switch
+                .ensure_withdrawals_allowed()
+                .and_then(|()| {
+                    let mut conn = self.pool.get()?;
+                    lightning_withdrawal::ensure_no_outstanding_withdrawals(&mut conn, peer)?;
+                    reserve_top_up::ensure_no_outstanding_top_ups(&mut conn, peer)
+                });
+
+            match result {
+                Ok(()) => {
+                    tracing::info!("Accepting pending dlc channel close offer.");
+
+                    // TODO(bonomat): we should verify that the proposed amount is acceptable
+                    self.node
+                        .accept_dlc_channel_collaborative_close(channel_id)?;
+
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::warn!("Not accepting pending dlc channel close offer: {e:#}");
+                }
+            }
         }
 
         self.send_last_dlc_message(peer)?;
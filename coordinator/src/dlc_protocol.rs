@@ -1,7 +1,10 @@
 use crate::db;
 use crate::funding_fee::insert_protocol_funding_fee_event;
 use crate::funding_fee::mark_funding_fee_event_as_paid;
+use crate::lightning_withdrawal::mark_lightning_withdrawals_as_applied;
 use crate::position::models::PositionState;
+use crate::reserve_interest::mark_reserve_interest_credits_as_paid;
+use crate::reserve_top_up::mark_reserve_top_ups_as_credited;
 use crate::trade::models::NewTrade;
 use crate::trade::websocket::InternalPositionUpdateMessage;
 use anyhow::Context;
@@ -21,6 +24,7 @@ use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use time::OffsetDateTime;
 use tokio::sync::broadcast::Sender;
+use uuid::Uuid;
 use xxi_node::cfd::calculate_pnl;
 use xxi_node::commons;
 use xxi_node::commons::Direction;
@@ -85,6 +89,7 @@ pub struct RolloverParams {
     pub expiry_timestamp: OffsetDateTime,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DlcProtocolState {
     Pending,
     Success,
@@ -117,6 +122,18 @@ pub enum DlcProtocolType {
     },
 }
 
+/// A DLC protocol with the same [`ProtocolId`] has already been claimed, e.g. because the same
+/// match was executed twice.
+///
+/// `state` is the state of the existing DLC protocol, or `None` if the first execution has not
+/// started it (yet).
+#[derive(Debug, thiserror::Error)]
+#[error("DLC protocol {protocol_id} has already been claimed: {state:?}")]
+pub struct DuplicateDlcProtocol {
+    pub protocol_id: ProtocolId,
+    pub state: Option<DlcProtocolState>,
+}
+
 pub struct DlcProtocolExecutor {
     pool: Pool<ConnectionManager<PgConnection>>,
 }
@@ -126,6 +143,21 @@ impl DlcProtocolExecutor {
         DlcProtocolExecutor { pool }
     }
 
+    /// Claim the [`ProtocolId`] of a trade execution, before proposing anything to the trader.
+    ///
+    /// Fails with [`DuplicateDlcProtocol`] if it has already been claimed. The claim is atomic, so
+    /// concurrent executions of the same match can't both succeed.
+    pub fn claim_trade_protocol(&self, protocol_id: ProtocolId, order_id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        if !db::trade_executions::claim(&mut conn, protocol_id, order_id)? {
+            let state = db::dlc_protocols::get_dlc_protocol_state(&mut conn, protocol_id)?;
+            return Err(DuplicateDlcProtocol { protocol_id, state }.into());
+        }
+
+        Ok(())
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn start_open_channel_protocol(
         &self,
@@ -314,6 +346,27 @@ impl DlcProtocolExecutor {
         Ok(())
     }
 
+    pub fn start_force_close_channel_protocol(
+        &self,
+        protocol_id: ProtocolId,
+        previous_protocol_id: Option<ProtocolId>,
+        channel_id: &DlcChannelId,
+        trader_id: &PublicKey,
+    ) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::dlc_protocols::create(
+            &mut conn,
+            protocol_id,
+            previous_protocol_id,
+            None,
+            channel_id,
+            db::dlc_protocols::DlcProtocolType::ForceClose,
+            trader_id,
+        )?;
+
+        Ok(())
+    }
+
     pub fn fail_dlc_protocol(&self, protocol_id: ProtocolId) -> Result<()> {
         let mut conn = self.pool.get()?;
         db::dlc_protocols::set_dlc_protocol_state_to_failed(&mut conn, protocol_id)?;
@@ -434,6 +487,8 @@ impl DlcProtocolExecutor {
     /// - Create and insert new trade.
     ///
     /// - Mark relevant funding fee events as paid.
+    ///
+    /// - Mark the Lightning withdrawals refunded by the settlement as applied.
     fn finish_settle_dlc_protocol(
         &self,
         conn: &mut PgConnection,
@@ -524,6 +579,7 @@ impl DlcProtocolExecutor {
         db::trades::insert(conn, new_trade)?;
 
         mark_funding_fee_event_as_paid(conn, protocol_id)?;
+        mark_lightning_withdrawals_as_applied(conn, protocol_id)?;
 
         Ok(())
     }
@@ -577,6 +633,11 @@ impl DlcProtocolExecutor {
 
         db::trades::insert(conn, new_trade)?;
 
+        mark_reserve_interest_credits_as_paid(conn, protocol_id)?;
+
+        mark_reserve_top_ups_as_credited(conn, protocol_id)?;
+        mark_lightning_withdrawals_as_applied(conn, protocol_id)?;
+
         Ok(())
     }
 
@@ -624,6 +685,9 @@ impl DlcProtocolExecutor {
         db::trades::insert(conn, new_trade)?;
 
         mark_funding_fee_event_as_paid(conn, protocol_id)?;
+        mark_reserve_interest_credits_as_paid(conn, protocol_id)?;
+        mark_reserve_top_ups_as_credited(conn, protocol_id)?;
+        mark_lightning_withdrawals_as_applied(conn, protocol_id)?;
 
         Ok(())
     }
@@ -660,6 +724,9 @@ impl DlcProtocolExecutor {
         )?;
 
         mark_funding_fee_event_as_paid(conn, protocol_id)?;
+        mark_reserve_interest_credits_as_paid(conn, protocol_id)?;
+        mark_reserve_top_ups_as_credited(conn, protocol_id)?;
+        mark_lightning_withdrawals_as_applied(conn, protocol_id)?;
 
         Ok(())
     }
@@ -1,5 +1,7 @@
 use crate::decimal_from_f32;
 use crate::message::OrderbookMessage;
+use crate::orderbook::websocket::FeedMessage;
+use crate::scheduler::Scheduler;
 use crate::FundingFee;
 use anyhow::bail;
 use anyhow::Context;
@@ -19,7 +21,7 @@ use time::format_description;
 use time::OffsetDateTime;
 use tokio::sync::broadcast;
 use tokio::task::block_in_place;
-use tokio_cron_scheduler::JobScheduler;
+use tokio::task::spawn_blocking;
 use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::Direction;
 use xxi_node::commons::FundingRate;
@@ -69,44 +71,50 @@ pub enum IndexPriceSource {
 }
 
 pub async fn generate_funding_fee_events_periodically(
-    scheduler: &JobScheduler,
+    scheduler: &Scheduler,
     pool: Pool<ConnectionManager<PgConnection>>,
     auth_users_notifier: tokio::sync::mpsc::Sender<OrderbookMessage>,
     schedule: String,
     index_price_source: IndexPriceSource,
 ) -> Result<()> {
     scheduler
-        .add(tokio_cron_scheduler::Job::new(
-            schedule.as_str(),
-            move |_, _| {
-                let mut attempts_left = 10;
-
-                // We want to retry
-                while let (Err(e), true) = (
-                    generate_funding_fee_events(
-                        &pool,
-                        index_price_source,
-                        auth_users_notifier.clone(),
-                    ),
-                    attempts_left > 0,
-                ) {
-                    attempts_left -= 1;
-
-                    tracing::error!(
-                        retry_interval = ?RETRY_INTERVAL,
-                        attempts_left,
-                        "Failed to generate funding fee events: {e:#}. \
-                         Trying again"
-                    );
-
-                    std::thread::sleep(RETRY_INTERVAL);
-                }
-            },
-        )?)
+        .add_job("generate_funding_fee_events", &schedule, move || {
+            let pool = pool.clone();
+            let auth_users_notifier = auth_users_notifier.clone();
+            async move {
+                spawn_blocking(move || {
+                    let mut attempts_left = 10;
+
+                    // We want to retry
+                    loop {
+                        let e = match generate_funding_fee_events(
+                            &pool,
+                            index_price_source,
+                            auth_users_notifier.clone(),
+                        ) {
+                            Ok(()) => return Ok(()),
+                            Err(e) if attempts_left == 0 => return Err(e),
+                            Err(e) => e,
+                        };
+
+                        attempts_left -= 1;
+
+                        tracing::error!(
+                            retry_interval = ?RETRY_INTERVAL,
+                            attempts_left,
+                            "Failed to generate funding fee events: {e:#}. \
+                             Trying again"
+                        );
+
+                        std::thread::sleep(RETRY_INTERVAL);
+                    }
+                })
+                .await
+                .expect("task to complete")
+            }
+        })
         .await?;
 
-    scheduler.start().await?;
-
     Ok(())
 }
 
@@ -143,21 +151,13 @@ fn generate_funding_fee_events(
     // TODO: Funding rates should be specific to contract symbols.
     let contract_symbol = ContractSymbol::BtcUsd;
 
-    let index_price = match index_price_source {
-        IndexPriceSource::Bitmex => block_in_place(move || {
-            let current_index_price =
-                get_bitmex_index_price(&contract_symbol, funding_rate.end_date())?;
-
-            anyhow::Ok(current_index_price)
-        })?,
-        IndexPriceSource::Test => {
-            #[cfg(not(debug_assertions))]
-            panic!("Cannot use a test index price in release mode");
-
-            #[cfg(debug_assertions)]
-            rust_decimal_macros::dec!(50_000)
-        }
-    };
+    let index_price = block_in_place(move || {
+        get_index_price(
+            index_price_source,
+            &contract_symbol,
+            funding_rate.end_date(),
+        )
+    })?;
 
     if index_price.is_zero() {
         bail!("Cannot generate funding fee events with zero index price");
@@ -221,7 +221,7 @@ fn generate_funding_fee_events(
 /// Calculate the funding fee.
 ///
 /// We assume that the `index_price` is not zero. Otherwise, the function panics.
-fn calculate_funding_fee(
+pub(crate) fn calculate_funding_fee(
     quantity: f32,
     // Positive means longs pay shorts; negative means shorts pay longs.
     funding_rate: Decimal,
@@ -249,6 +249,26 @@ fn calculate_funding_fee(
     SignedAmount::from_btc(funding_fee_btc).expect("to fit")
 }
 
+/// Get the index price of the `contract_symbol` at `timestamp` from the `index_price_source`.
+///
+/// This function blocks while waiting for the index price.
+pub(crate) fn get_index_price(
+    index_price_source: IndexPriceSource,
+    contract_symbol: &ContractSymbol,
+    timestamp: OffsetDateTime,
+) -> Result<Decimal> {
+    match index_price_source {
+        IndexPriceSource::Bitmex => get_bitmex_index_price(contract_symbol, timestamp),
+        IndexPriceSource::Test => {
+            #[cfg(not(debug_assertions))]
+            panic!("Cannot use a test index price in release mode");
+
+            #[cfg(debug_assertions)]
+            Ok(rust_decimal_macros::dec!(50_000))
+        }
+    }
+}
+
 fn get_bitmex_index_price(
     contract_symbol: &ContractSymbol,
     timestamp: OffsetDateTime,
@@ -287,6 +307,7 @@ fn get_bitmex_index_price(
 fn bitmex_symbol(contract_symbol: &ContractSymbol) -> &str {
     match contract_symbol {
         ContractSymbol::BtcUsd => "BXBT",
+        ContractSymbol::EthUsd => ".BETH",
     }
 }
 
@@ -315,7 +336,7 @@ pub fn funding_fee_from_funding_fee_events(events: &[FundingFeeEvent]) -> Fundin
 
 pub fn insert_funding_rates(
     conn: &mut PgConnection,
-    tx_orderbook_feed: broadcast::Sender<Message>,
+    tx_orderbook_feed: broadcast::Sender<FeedMessage>,
     funding_rates: &[FundingRate],
 ) -> Result<()> {
     db::insert_funding_rates(conn, funding_rates)?;
@@ -325,7 +346,9 @@ pub fn insert_funding_rates(
     let next_funding_rate = get_next_funding_rate(conn)?;
 
     if let Some(next_funding_rate) = next_funding_rate {
-        if let Err(e) = tx_orderbook_feed.send(Message::NextFundingRate(next_funding_rate)) {
+        if let Err(e) = tx_orderbook_feed.send(FeedMessage::for_all(Message::NextFundingRate(
+            next_funding_rate,
+        ))) {
             tracing::error!("Failed to notify traders about next funding rate: {e}");
         }
     }
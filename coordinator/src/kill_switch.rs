@@ -0,0 +1,94 @@
+use crate::db;
+use anyhow::bail;
+use anyhow::Result;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use xxi_node::commons::KillSwitchStatus;
+
+/// The operator's emergency switch to disable parts of the coordinator.
+///
+/// The status is kept in memory, as it is consulted on every order, trade and withdrawal, and
+/// persisted so that it survives a restart.
+#[derive(Clone)]
+pub struct KillSwitch {
+    pool: Pool<ConnectionManager<PgConnection>>,
+    status: Arc<RwLock<KillSwitchStatus>>,
+}
+
+impl KillSwitch {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Result<Self> {
+        let mut conn = pool.get()?;
+        let status = db::kill_switch::get(&mut conn)?;
+
+        if status.is_engaged() {
+            tracing::warn!(%status, "Kill switch is engaged");
+        }
+
+        Ok(Self {
+            pool,
+            status: Arc::new(RwLock::new(status)),
+        })
+    }
+
+    pub fn status(&self) -> KillSwitchStatus {
+        self.status.read().clone()
+    }
+
+    /// Replace the engaged modes of the kill switch.
+    pub fn set(&self, status: KillSwitchStatus) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::kill_switch::insert(&mut conn, &status)?;
+
+        tracing::warn!(%status, "Changed kill switch");
+
+        *self.status.write() = status;
+
+        Ok(())
+    }
+
+    pub fn ensure_new_orders_allowed(&self) -> Result<()> {
+        let status = self.status.read();
+        if !status.allows_new_orders() {
+            bail!("New orders are disabled{}", reason(&status));
+        }
+
+        Ok(())
+    }
+
+    pub fn ensure_new_channels_allowed(&self) -> Result<()> {
+        let status = self.status.read();
+        if !status.allows_new_channels() {
+            bail!("Opening DLC channels is disabled{}", reason(&status));
+        }
+
+        Ok(())
+    }
+
+    pub fn ensure_withdrawals_allowed(&self) -> Result<()> {
+        let status = self.status.read();
+        if !status.allows_withdrawals() {
+            bail!("Withdrawals are disabled{}", reason(&status));
+        }
+
+        Ok(())
+    }
+
+    pub fn ensure_trading_allowed(&self) -> Result<()> {
+        let status = self.status.read();
+        if !status.allows_trading() {
+            bail!("Trading is halted{}", reason(&status));
+        }
+
+        Ok(())
+    }
+}
+
+fn reason(status: &KillSwitchStatus) -> String {
+    match &status.reason {
+        Some(reason) => format!(": {reason}"),
+        None => String::new(),
+    }
+}
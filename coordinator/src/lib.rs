@@ -23,6 +23,7 @@ mod emergency_kit;
 mod leaderboard;
 mod payout_curve;
 
+pub mod api_key;
 pub mod backup;
 pub mod campaign;
 pub mod check_version;
@@ -31,21 +32,34 @@ pub mod db;
 pub mod dlc_handler;
 pub mod dlc_protocol;
 pub mod funding_fee;
+pub mod kill_switch;
+pub mod lightning_withdrawal;
 pub mod logger;
+pub mod maker_earnings;
 pub mod message;
-mod metrics;
+pub mod message_archive;
+pub mod metrics;
+pub mod moderation;
 pub mod node;
 pub mod notifications;
 pub mod orderbook;
 pub mod position;
 pub mod referrals;
+pub mod replication;
+pub mod reserve_interest;
+pub mod reserve_top_up;
+pub mod retention;
 pub mod routes;
 pub mod routing_fee;
 pub mod scheduler;
 pub mod schema;
+pub mod self_check;
+pub mod session_token;
 pub mod settings;
+pub mod settlement_report;
 pub mod storage;
 pub mod trade;
+pub mod treasury;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 
@@ -61,6 +75,8 @@ pub enum AppError {
     BadRequest(String),
     ServiceUnavailable(String),
     Unauthorized,
+    Forbidden(String),
+    TooManyRequests(String),
 }
 
 impl IntoResponse for AppError {
@@ -70,6 +86,8 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "".to_string()),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
         };
 
         let body = Json(json!({
@@ -0,0 +1,666 @@
+use crate::db::positions;
+use crate::funding_fee::funding_fee_from_funding_fee_events;
+use crate::funding_fee::get_outstanding_funding_fee_events;
+use crate::node::Node;
+use crate::position::models::PositionState;
+use crate::schema::sql_types::LightningPaymentStatusType;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::query_builder::QueryId;
+use diesel::AsExpression;
+use diesel::FromSqlRow;
+use diesel::PgConnection;
+use dlc_manager::DlcChannelId;
+use futures::StreamExt;
+use lnd_bridge::SendPaymentParams;
+use serde::Serialize;
+use std::any::TypeId;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::oneshot;
+use tokio::task::spawn_blocking;
+use xxi_node::node::ProtocolId;
+
+mod db;
+
+pub use db::get_by_trader as get_lightning_withdrawals;
+pub use db::mark_lightning_withdrawals_as_applied;
+
+/// How long we try to pay the trader's invoice before giving up.
+const PAYMENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long we wait for the renew debiting the trader's collateral reserve before giving up.
+const DEBIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often we check whether the trader's collateral reserve has been debited.
+const DEBIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long we wait for the payment to lock in an HTLC before answering the trader.
+///
+/// The payment continues in the background if it takes longer than this.
+const HTLC_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The maximum routing fee paid by the coordinator, relative to the withdrawn amount.
+const MAX_ROUTING_FEE_PPM: u64 = 5_000;
+
+/// The minimum routing fee budget, so that small withdrawals can still be routed.
+const MIN_ROUTING_FEE: Amount = Amount::from_sat(10);
+
+/// A withdrawal of part of the trader's collateral reserve, paid out to a Lightning invoice of
+/// the trader.
+///
+/// The coordinator first moves the amount from the trader's to the coordinator's collateral
+/// reserve with a renew of the DLC channel, and only pays the invoice once that renew has
+/// finished. That way the coordinator never pays out coins which are still on the trader's side of
+/// the DLC channel, however the channel is closed afterwards. Should the payment fail, the amount
+/// is moved back to the trader's collateral reserve with the next DLC channel update.
+#[derive(Clone, Debug)]
+pub struct LightningWithdrawal {
+    pub id: i32,
+    pub trader_pubkey: PublicKey,
+    pub channel_id: DlcChannelId,
+    pub amount: Amount,
+    pub invoice: String,
+    pub payment_hash: String,
+    pub payment_status: PaymentStatus,
+    /// When the amount was moved from the trader's to the coordinator's collateral reserve.
+    pub debited_at: Option<OffsetDateTime>,
+    /// When the amount was moved back to the trader's collateral reserve, after the payment
+    /// failed.
+    pub refunded_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Serialize)]
+#[diesel(sql_type = LightningPaymentStatusType)]
+pub enum PaymentStatus {
+    /// The payment has not been started, or no HTLC has been locked in yet.
+    Pending,
+    /// An HTLC has been locked in, the payment can no longer be taken back.
+    InFlight,
+    Succeeded,
+    Failed,
+}
+
+impl QueryId for LightningPaymentStatusType {
+    type QueryId = LightningPaymentStatusType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+impl LightningWithdrawal {
+    /// Whether the amount still has to be moved from the trader's to the coordinator's collateral
+    /// reserve, before the invoice is paid.
+    fn is_outstanding_debit(&self) -> bool {
+        self.debited_at.is_none() && self.payment_status == PaymentStatus::Pending
+    }
+
+    /// Whether the amount has been debited for a payment which failed afterwards, and thus has to
+    /// be moved back to the trader's collateral reserve.
+    fn is_outstanding_refund(&self) -> bool {
+        self.debited_at.is_some()
+            && self.refunded_at.is_none()
+            && self.payment_status == PaymentStatus::Failed
+    }
+}
+
+/// The Lightning withdrawals applied to the collateral reserves of a DLC channel update.
+#[derive(Debug, Default)]
+pub struct LightningWithdrawals {
+    debit_ids: Vec<i32>,
+    refund_ids: Vec<i32>,
+}
+
+/// Move the outstanding Lightning withdrawals of a trader from the trader's to the coordinator's
+/// collateral reserve, and the refunds of failed withdrawals back.
+///
+/// Returns the updated collateral reserves of the coordinator and the trader and the applied
+/// withdrawals. If either collateral reserve is insufficient, no withdrawals are applied.
+pub fn apply_lightning_withdrawals(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    collateral_reserve_coordinator: Amount,
+    collateral_reserve_trader: Amount,
+) -> Result<(Amount, Amount, LightningWithdrawals)> {
+    let withdrawals = db::get_by_trader(conn, trader_pubkey)?;
+
+    let debits = withdrawals
+        .iter()
+        .filter(|withdrawal| withdrawal.is_outstanding_debit())
+        .collect::<Vec<_>>();
+    let refunds = withdrawals
+        .iter()
+        .filter(|withdrawal| withdrawal.is_outstanding_refund())
+        .collect::<Vec<_>>();
+
+    if debits.is_empty() && refunds.is_empty() {
+        return Ok((
+            collateral_reserve_coordinator,
+            collateral_reserve_trader,
+            LightningWithdrawals::default(),
+        ));
+    }
+
+    let debit = debits.iter().map(|withdrawal| withdrawal.amount).sum();
+    let refund = refunds.iter().map(|withdrawal| withdrawal.amount).sum();
+
+    let (collateral_reserve_coordinator, collateral_reserve_trader) = match adjust_reserves(
+        collateral_reserve_coordinator,
+        collateral_reserve_trader,
+        debit,
+        refund,
+    ) {
+        Some(reserves) => reserves,
+        None => {
+            tracing::warn!(
+                %trader_pubkey,
+                %debit,
+                %refund,
+                %collateral_reserve_coordinator,
+                %collateral_reserve_trader,
+                "Insufficient collateral reserve to apply Lightning withdrawals"
+            );
+
+            return Ok((
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+                LightningWithdrawals::default(),
+            ));
+        }
+    };
+
+    let withdrawals = LightningWithdrawals {
+        debit_ids: debits.iter().map(|withdrawal| withdrawal.id).collect(),
+        refund_ids: refunds.iter().map(|withdrawal| withdrawal.id).collect(),
+    };
+
+    Ok((
+        collateral_reserve_coordinator,
+        collateral_reserve_trader,
+        withdrawals,
+    ))
+}
+
+/// Fail if the trader has Lightning withdrawals which are not yet settled in their DLC channel.
+///
+/// Closing the DLC channel in that state would leave the trader without the refund of a failed
+/// withdrawal.
+pub fn ensure_no_outstanding_withdrawals(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+) -> Result<()> {
+    let withdrawals = db::get_by_trader(conn, trader_pubkey)?;
+
+    let outstanding = withdrawals
+        .iter()
+        .filter(|withdrawal| {
+            withdrawal.is_outstanding_debit() || withdrawal.is_outstanding_refund()
+        })
+        .map(|withdrawal| withdrawal.id)
+        .collect::<Vec<_>>();
+
+    ensure!(
+        outstanding.is_empty(),
+        "Lightning withdrawals {outstanding:?} are not yet settled in the DLC channel"
+    );
+
+    Ok(())
+}
+
+/// Associate the applied Lightning withdrawals with the DLC protocol settling them.
+pub fn link_to_protocol(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    withdrawals: &LightningWithdrawals,
+) -> Result<()> {
+    db::set_protocol_id(
+        conn,
+        protocol_id,
+        &withdrawals.debit_ids,
+        &withdrawals.refund_ids,
+    )?;
+
+    Ok(())
+}
+
+/// Pay the trader's `invoice` from their collateral reserve.
+///
+/// The trader's collateral reserve is debited with a renew of their DLC channel before we pay the
+/// invoice. Returns once the payment has locked in an HTLC. If the payment takes longer than
+/// [`HTLC_LOCK_TIMEOUT`], it continues in the background and we return without waiting for it.
+pub async fn withdraw(node: Node, trader_pubkey: PublicKey, invoice: String) -> Result<()> {
+    node.kill_switch.ensure_withdrawals_allowed()?;
+    node.quiesce.ensure_protocols_allowed()?;
+
+    let payment_request = node
+        .lnd_bridge
+        .decode_payment_request(&invoice)
+        .await
+        .context("Invalid invoice")?;
+
+    let amount = Amount::from_sat(payment_request.num_satoshis);
+    ensure!(amount > Amount::ZERO, "Invoice without amount");
+
+    let expires_at = (payment_request.timestamp + payment_request.expiry) as i64;
+    let payment_deadline =
+        OffsetDateTime::now_utc().unix_timestamp() + PAYMENT_TIMEOUT.as_secs() as i64;
+    ensure!(expires_at > payment_deadline, "Invoice expires too soon");
+
+    let channel_id = node
+        .inner
+        .get_signed_channel_by_trader_id(trader_pubkey)
+        .context("No DLC channel to withdraw from")?
+        .channel_id;
+
+    let id = spawn_blocking({
+        let node = node.clone();
+        let invoice = invoice.clone();
+        move || {
+            let mut conn = node.pool.get()?;
+
+            let position = positions::Position::get_position_by_trader(
+                &mut conn,
+                trader_pubkey,
+                vec![PositionState::Open],
+            )?
+            .context("Withdrawing via Lightning requires an open position")?;
+
+            let withdrawals = db::get_by_trader(&mut conn, trader_pubkey)?;
+            if withdrawals
+                .iter()
+                .any(|withdrawal| withdrawal.payment_status == PaymentStatus::Pending)
+            {
+                bail!("Another Lightning withdrawal is still pending");
+            }
+
+            let funding_fee_events =
+                get_outstanding_funding_fee_events(&mut conn, trader_pubkey, position.id)?;
+            let funding_fee = funding_fee_from_funding_fee_events(&funding_fee_events);
+
+            let (collateral_reserve_coordinator, collateral_reserve_trader) =
+                node.apply_funding_fee_to_channel(channel_id, funding_fee)?;
+            let (_, collateral_reserve_trader, _) = apply_lightning_withdrawals(
+                &mut conn,
+                trader_pubkey,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+
+            ensure!(
+                amount <= collateral_reserve_trader,
+                "Cannot withdraw {amount} with a collateral reserve of {collateral_reserve_trader}"
+            );
+
+            let id = db::insert(
+                &mut conn,
+                trader_pubkey,
+                &channel_id,
+                amount,
+                &invoice,
+                &payment_request.payment_hash,
+            )?;
+
+            anyhow::Ok(id)
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    tracing::info!(%trader_pubkey, id, %amount, "Debiting Lightning withdrawal");
+
+    if let Err(e) = debit(&node, trader_pubkey, id).await {
+        // Should the renew still finish, the amount is refunded with the next DLC channel update.
+        if let Err(e) = spawn_blocking({
+            let pool = node.pool.clone();
+            move || {
+                let mut conn = pool.get()?;
+                db::set_payment_status(&mut conn, id, PaymentStatus::Failed)?;
+                anyhow::Ok(())
+            }
+        })
+        .await
+        .expect("task to complete")
+        {
+            tracing::error!(%trader_pubkey, id, "Failed to fail Lightning withdrawal: {e:#}");
+        }
+
+        bail!("Lightning withdrawal failed: {e:#}");
+    }
+
+    tracing::info!(%trader_pubkey, id, %amount, "Paying Lightning withdrawal");
+
+    let (htlc_locked_sender, htlc_locked_receiver) = oneshot::channel();
+
+    let fee_limit = Amount::from_sat(amount.to_sat() * MAX_ROUTING_FEE_PPM / 1_000_000);
+    let params = SendPaymentParams {
+        payment_request: invoice,
+        timeout_seconds: PAYMENT_TIMEOUT.as_secs() as i32,
+        fee_limit_sat: fee_limit.max(MIN_ROUTING_FEE).to_sat(),
+    };
+
+    tokio::spawn(track_payment(
+        node,
+        trader_pubkey,
+        id,
+        params,
+        htlc_locked_sender,
+    ));
+
+    match tokio::time::timeout(HTLC_LOCK_TIMEOUT, htlc_locked_receiver).await {
+        Ok(Ok(Ok(()))) => Ok(()),
+        Ok(Ok(Err(reason))) => bail!("Lightning withdrawal failed: {reason}"),
+        Ok(Err(_)) => bail!("Lightning withdrawal failed"),
+        Err(_) => {
+            tracing::warn!(%trader_pubkey, id, "Lightning withdrawal is taking long");
+            Ok(())
+        }
+    }
+}
+
+/// Debit the Lightning withdrawal from the trader's collateral reserve with a renew of their DLC
+/// channel, and wait for the renew to finish.
+async fn debit(node: &Node, trader_pubkey: PublicKey, id: i32) -> Result<()> {
+    let mut conn = node.pool.get()?;
+
+    let position = positions::Position::get_position_by_trader(
+        &mut conn,
+        trader_pubkey,
+        vec![PositionState::Open],
+    )?
+    .context("No open position")?;
+
+    let channel_id = node
+        .inner
+        .get_signed_channel_by_trader_id(trader_pubkey)?
+        .channel_id;
+
+    node.propose_reserve_update(&mut conn, &channel_id, position)
+        .await
+        .context("Failed to propose renew")?;
+
+    drop(conn);
+
+    tokio::time::timeout(DEBIT_TIMEOUT, async {
+        loop {
+            let withdrawal = spawn_blocking({
+                let pool = node.pool.clone();
+                move || {
+                    let mut conn = pool.get()?;
+                    let withdrawal = db::get(&mut conn, id)?;
+                    anyhow::Ok(withdrawal)
+                }
+            })
+            .await
+            .expect("task to complete")?;
+
+            if withdrawal.debited_at.is_some() {
+                return anyhow::Ok(());
+            }
+
+            tokio::time::sleep(DEBIT_POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .context("Timed out waiting for renew")?
+}
+
+/// Pay the already debited Lightning withdrawal and keep its payment status up to date.
+///
+/// Should the payment fail, we propose a renew of the DLC channel to refund the amount.
+async fn track_payment(
+    node: Node,
+    trader_pubkey: PublicKey,
+    id: i32,
+    params: SendPaymentParams,
+    htlc_locked: oneshot::Sender<Result<(), String>>,
+) {
+    let mut htlc_locked = Some(htlc_locked);
+    let mut payment_status = PaymentStatus::Pending;
+
+    let lnd_bridge = node.lnd_bridge.clone();
+    let mut stream = lnd_bridge.send_payment(params);
+
+    while let Some(payment) = stream.next().await {
+        let payment = match payment {
+            Ok(payment) => payment,
+            Err(e) => {
+                tracing::error!(%trader_pubkey, id, "Failed to track Lightning withdrawal: {e:#}");
+                break;
+            }
+        };
+
+        let new_payment_status = match payment.status {
+            lnd_bridge::PaymentStatus::Succeeded => PaymentStatus::Succeeded,
+            lnd_bridge::PaymentStatus::Failed => PaymentStatus::Failed,
+            _ if payment.has_locked_htlc() => PaymentStatus::InFlight,
+            _ => PaymentStatus::Pending,
+        };
+
+        if new_payment_status == payment_status {
+            continue;
+        }
+
+        tracing::info!(
+            %trader_pubkey,
+            id,
+            ?new_payment_status,
+            failure_reason = %payment.failure_reason,
+            "Lightning withdrawal payment status changed"
+        );
+
+        payment_status = new_payment_status;
+
+        if let Err(e) = spawn_blocking({
+            let pool = node.pool.clone();
+            move || {
+                let mut conn = pool.get()?;
+                db::set_payment_status(&mut conn, id, payment_status)?;
+                anyhow::Ok(())
+            }
+        })
+        .await
+        .expect("task to complete")
+        {
+            tracing::error!(
+                %trader_pubkey,
+                id,
+                "Failed to update Lightning withdrawal payment status: {e:#}"
+            );
+        }
+
+        match payment_status {
+            PaymentStatus::Pending => {}
+            PaymentStatus::InFlight | PaymentStatus::Succeeded => {
+                if let Some(htlc_locked) = htlc_locked.take() {
+                    let _ = htlc_locked.send(Ok(()));
+                }
+            }
+            PaymentStatus::Failed => {
+                if let Some(htlc_locked) = htlc_locked.take() {
+                    let _ = htlc_locked.send(Err(payment.failure_reason.clone()));
+                }
+
+                // The trader's collateral reserve has already been debited, so we have to refund
+                // it.
+                propose_reserve_update(&node, trader_pubkey).await;
+            }
+        }
+    }
+}
+
+/// Refund the failed Lightning withdrawals of the trader with a renew of their DLC channel.
+///
+/// If this is not possible right now, e.g. because the position is being resized, the
+/// withdrawals are settled with the next renew or rollover.
+async fn propose_reserve_update(node: &Node, trader_pubkey: PublicKey) {
+    let result = async {
+        let mut conn = node.pool.get()?;
+
+        let position = positions::Position::get_position_by_trader(
+            &mut conn,
+            trader_pubkey,
+            vec![PositionState::Open],
+        )?
+        .context("No open position")?;
+
+        let channel_id = node
+            .inner
+            .get_signed_channel_by_trader_id(trader_pubkey)?
+            .channel_id;
+
+        node.propose_reserve_update(&mut conn, &channel_id, position)
+            .await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!(
+            %trader_pubkey,
+            "Could not refund Lightning withdrawals now, deferring to next renew: {e:#}"
+        );
+    }
+}
+
+/// Apply `debit` from the trader's to the coordinator's collateral reserve and `refund` back.
+///
+/// Returns `None` if either collateral reserve is insufficient.
+fn adjust_reserves(
+    collateral_reserve_coordinator: Amount,
+    collateral_reserve_trader: Amount,
+    debit: Amount,
+    refund: Amount,
+) -> Option<(Amount, Amount)> {
+    let collateral_reserve_coordinator =
+        (collateral_reserve_coordinator + debit).checked_sub(refund)?;
+    let collateral_reserve_trader = (collateral_reserve_trader + refund).checked_sub(debit)?;
+
+    Some((collateral_reserve_coordinator, collateral_reserve_trader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn debit_moves_amount_to_coordinator() {
+        let reserves = adjust_reserves(
+            Amount::from_sat(1_000),
+            Amount::from_sat(5_000),
+            Amount::from_sat(2_000),
+            Amount::ZERO,
+        );
+
+        assert_eq!(
+            reserves,
+            Some((Amount::from_sat(3_000), Amount::from_sat(3_000)))
+        );
+    }
+
+    #[test]
+    fn refund_moves_amount_back_to_trader() {
+        let reserves = adjust_reserves(
+            Amount::from_sat(3_000),
+            Amount::from_sat(3_000),
+            Amount::ZERO,
+            Amount::from_sat(2_000),
+        );
+
+        assert_eq!(
+            reserves,
+            Some((Amount::from_sat(1_000), Amount::from_sat(5_000)))
+        );
+    }
+
+    #[test]
+    fn debit_and_refund_are_netted() {
+        // The coordinator's reserve can cover the refund only thanks to the debit.
+        let reserves = adjust_reserves(
+            Amount::ZERO,
+            Amount::from_sat(1_000),
+            Amount::from_sat(1_000),
+            Amount::from_sat(500),
+        );
+
+        assert_eq!(
+            reserves,
+            Some((Amount::from_sat(500), Amount::from_sat(500)))
+        );
+    }
+
+    #[test]
+    fn insufficient_trader_reserve() {
+        let reserves = adjust_reserves(
+            Amount::from_sat(1_000),
+            Amount::from_sat(1_000),
+            Amount::from_sat(1_001),
+            Amount::ZERO,
+        );
+
+        assert_eq!(reserves, None);
+    }
+
+    #[test]
+    fn insufficient_coordinator_reserve() {
+        let reserves = adjust_reserves(
+            Amount::from_sat(1_000),
+            Amount::from_sat(1_000),
+            Amount::ZERO,
+            Amount::from_sat(1_001),
+        );
+
+        assert_eq!(reserves, None);
+    }
+
+    #[test]
+    fn pending_withdrawal_is_debited_before_payment() {
+        let withdrawal = dummy_withdrawal(PaymentStatus::Pending, None);
+
+        assert!(withdrawal.is_outstanding_debit());
+        assert!(!withdrawal.is_outstanding_refund());
+    }
+
+    #[test]
+    fn withdrawal_failed_before_debit_is_neither_debited_nor_refunded() {
+        let withdrawal = dummy_withdrawal(PaymentStatus::Failed, None);
+
+        assert!(!withdrawal.is_outstanding_debit());
+        assert!(!withdrawal.is_outstanding_refund());
+    }
+
+    #[test]
+    fn withdrawal_failed_after_debit_is_refunded() {
+        let withdrawal = dummy_withdrawal(PaymentStatus::Failed, Some(OffsetDateTime::now_utc()));
+
+        assert!(!withdrawal.is_outstanding_debit());
+        assert!(withdrawal.is_outstanding_refund());
+    }
+
+    fn dummy_withdrawal(
+        payment_status: PaymentStatus,
+        debited_at: Option<OffsetDateTime>,
+    ) -> LightningWithdrawal {
+        LightningWithdrawal {
+            id: 1,
+            trader_pubkey: PublicKey::from_str(
+                "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+            )
+            .unwrap(),
+            channel_id: [0; 32],
+            amount: Amount::from_sat(1_000),
+            invoice: String::new(),
+            payment_hash: String::new(),
+            payment_status,
+            debited_at,
+            refunded_at: None,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::lightning_withdrawal;
+use crate::lightning_withdrawal::PaymentStatus;
+use crate::schema::lightning_withdrawals;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::prelude::*;
+use dlc_manager::DlcChannelId;
+use hex::FromHex;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use xxi_node::node::ProtocolId;
+
+#[derive(Queryable, Debug)]
+struct LightningWithdrawal {
+    id: i32,
+    trader_pubkey: String,
+    channel_id: String,
+    amount_sats: i64,
+    invoice: String,
+    payment_hash: String,
+    payment_status: PaymentStatus,
+    _protocol_id: Option<uuid::Uuid>,
+    debited_at: Option<OffsetDateTime>,
+    _refund_protocol_id: Option<uuid::Uuid>,
+    refunded_at: Option<OffsetDateTime>,
+    created_at: OffsetDateTime,
+    _updated_at: OffsetDateTime,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    channel_id: &DlcChannelId,
+    amount: Amount,
+    invoice: &str,
+    payment_hash: &str,
+) -> QueryResult<i32> {
+    diesel::insert_into(lightning_withdrawals::table)
+        .values(&(
+            lightning_withdrawals::trader_pubkey.eq(trader_pubkey.to_string()),
+            lightning_withdrawals::channel_id.eq(hex::encode(channel_id)),
+            lightning_withdrawals::amount_sats.eq(amount.to_sat() as i64),
+            lightning_withdrawals::invoice.eq(invoice),
+            lightning_withdrawals::payment_hash.eq(payment_hash),
+        ))
+        .returning(lightning_withdrawals::id)
+        .get_result(conn)
+}
+
+pub fn get(
+    conn: &mut PgConnection,
+    id: i32,
+) -> QueryResult<lightning_withdrawal::LightningWithdrawal> {
+    let withdrawal: LightningWithdrawal = lightning_withdrawals::table
+        .filter(lightning_withdrawals::id.eq(id))
+        .first(conn)?;
+
+    Ok(withdrawal.into())
+}
+
+pub fn get_by_trader(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+) -> QueryResult<Vec<lightning_withdrawal::LightningWithdrawal>> {
+    let withdrawals: Vec<LightningWithdrawal> = lightning_withdrawals::table
+        .filter(lightning_withdrawals::trader_pubkey.eq(trader_pubkey.to_string()))
+        .order_by(lightning_withdrawals::id.asc())
+        .load(conn)?;
+
+    Ok(withdrawals.into_iter().map(Into::into).collect())
+}
+
+pub fn set_payment_status(
+    conn: &mut PgConnection,
+    id: i32,
+    payment_status: PaymentStatus,
+) -> QueryResult<()> {
+    diesel::update(lightning_withdrawals::table)
+        .filter(lightning_withdrawals::id.eq(id))
+        .set((
+            lightning_withdrawals::payment_status.eq(payment_status),
+            lightning_withdrawals::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Associate the withdrawals with the DLC protocol which will debit, or refund, them.
+///
+/// A withdrawal which was associated with a failed DLC protocol is simply associated with the
+/// next one.
+pub fn set_protocol_id(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    debit_ids: &[i32],
+    refund_ids: &[i32],
+) -> QueryResult<()> {
+    if !debit_ids.is_empty() {
+        diesel::update(lightning_withdrawals::table)
+            .filter(lightning_withdrawals::id.eq_any(debit_ids))
+            .filter(lightning_withdrawals::debited_at.is_null())
+            .set(lightning_withdrawals::protocol_id.eq(protocol_id.to_uuid()))
+            .execute(conn)?;
+    }
+
+    if !refund_ids.is_empty() {
+        diesel::update(lightning_withdrawals::table)
+            .filter(lightning_withdrawals::id.eq_any(refund_ids))
+            .filter(lightning_withdrawals::refunded_at.is_null())
+            .set(lightning_withdrawals::refund_protocol_id.eq(protocol_id.to_uuid()))
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Mark the withdrawals debited or refunded by the given DLC protocol accordingly.
+pub fn mark_lightning_withdrawals_as_applied(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+) -> QueryResult<()> {
+    let now = OffsetDateTime::now_utc();
+
+    diesel::update(lightning_withdrawals::table)
+        .filter(lightning_withdrawals::protocol_id.eq(protocol_id.to_uuid()))
+        .filter(lightning_withdrawals::debited_at.is_null())
+        .set((
+            lightning_withdrawals::debited_at.eq(now),
+            lightning_withdrawals::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    diesel::update(lightning_withdrawals::table)
+        .filter(lightning_withdrawals::refund_protocol_id.eq(protocol_id.to_uuid()))
+        .filter(lightning_withdrawals::refunded_at.is_null())
+        .set((
+            lightning_withdrawals::refunded_at.eq(now),
+            lightning_withdrawals::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+impl From<LightningWithdrawal> for lightning_withdrawal::LightningWithdrawal {
+    fn from(value: LightningWithdrawal) -> Self {
+        Self {
+            id: value.id,
+            trader_pubkey: PublicKey::from_str(&value.trader_pubkey).expect("valid pubkey"),
+            channel_id: DlcChannelId::from_hex(value.channel_id).expect("valid dlc channel id"),
+            amount: Amount::from_sat(value.amount_sats as u64),
+            invoice: value.invoice,
+            payment_hash: value.payment_hash,
+            payment_status: value.payment_status,
+            debited_at: value.debited_at,
+            refunded_at: value.refunded_at,
+            created_at: value.created_at,
+        }
+    }
+}
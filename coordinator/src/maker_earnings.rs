@@ -0,0 +1,222 @@
+//! Earnings of the makers per day (UTC) and contract, so that liquidity providers can evaluate
+//! their profitability.
+//!
+//! The earnings are derived from the daily [`SettlementReport`]s when they are generated:
+//!
+//! - the order matching fees of the fills,
+//! - the spread captured by the fills, i.e. what the maker would have gained had they closed each
+//!   fill at the index price at the time of the match.
+//!
+//! The coordinator does not know how a maker hedges, hence makers report their hedging costs per
+//! day and contract themselves. The coordinator does not grant rebates to makers, hence there are
+//! none to account for.
+
+use crate::settlement_report::Fill;
+use crate::settlement_report::SettlementReport;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use time::Date;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+mod db;
+
+pub use db::get_earnings_of_maker;
+pub use db::set_hedging_costs;
+pub use db::upsert;
+
+time::serde::format_description!(day, Date, "[year]-[month]-[day]");
+
+/// The earnings of a maker in one contract on one day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyEarnings {
+    #[serde(with = "day")]
+    pub date: Date,
+    pub contract_symbol: ContractSymbol,
+    pub fills: usize,
+    /// The number of contracts filled, in either direction.
+    pub volume: Decimal,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub matching_fees: Amount,
+    /// Fills without a known index price do not contribute.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub spread_capture: SignedAmount,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub hedging_costs: Amount,
+}
+
+/// The earnings of a maker over a range of days.
+#[derive(Debug, Clone, Serialize)]
+pub struct EarningsSummary {
+    /// Oldest first.
+    pub daily: Vec<DailyEarnings>,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub net: SignedAmount,
+}
+
+/// The hedging costs of a maker in one contract on one day, as reported by the maker.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HedgingCosts {
+    #[serde(with = "day")]
+    pub date: Date,
+    pub contract_symbol: ContractSymbol,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub hedging_costs: Amount,
+}
+
+/// The query parameters to select the earnings of a range of days, both inclusive.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EarningsRange {
+    #[serde(with = "day")]
+    pub from: Date,
+    #[serde(with = "day")]
+    pub to: Date,
+}
+
+impl DailyEarnings {
+    /// The earnings of the maker per contract in the given report. The hedging costs are not part
+    /// of the report and remain zero.
+    pub fn from_report(report: &SettlementReport) -> Vec<Self> {
+        let mut earnings = BTreeMap::<String, DailyEarnings>::new();
+        for fill in report.fills.iter() {
+            let symbol =
+                earnings
+                    .entry(fill.contract_symbol.to_string())
+                    .or_insert(DailyEarnings {
+                        date: report.date,
+                        contract_symbol: fill.contract_symbol,
+                        fills: 0,
+                        volume: Decimal::ZERO,
+                        matching_fees: Amount::ZERO,
+                        spread_capture: SignedAmount::ZERO,
+                        hedging_costs: Amount::ZERO,
+                    });
+
+            symbol.fills += 1;
+            symbol.volume += fill.quantity;
+            symbol.matching_fees += fill.matching_fee;
+            symbol.spread_capture += spread_capture(fill).unwrap_or(SignedAmount::ZERO);
+        }
+
+        earnings.into_values().collect()
+    }
+
+    pub fn net(&self) -> SignedAmount {
+        self.spread_capture
+            - self.matching_fees.to_signed().expect("to fit")
+            - self.hedging_costs.to_signed().expect("to fit")
+    }
+}
+
+impl EarningsSummary {
+    pub fn new(daily: Vec<DailyEarnings>) -> Self {
+        let net = daily
+            .iter()
+            .fold(SignedAmount::ZERO, |net, earnings| net + earnings.net());
+
+        Self { daily, net }
+    }
+}
+
+/// What the maker would have gained had they closed the fill at the index price at the time of the
+/// match.
+///
+/// The contracts are inverse, i.e. one contract is worth one USD and the gains are in bitcoin.
+fn spread_capture(fill: &Fill) -> Option<SignedAmount> {
+    let index_price = fill.index_price.filter(|price| !price.is_zero())?;
+    if fill.price.is_zero() {
+        return None;
+    }
+
+    // A long fill gains if it was bought below the index price.
+    let btc = fill.quantity / fill.price - fill.quantity / index_price;
+    let btc = match fill.direction {
+        Direction::Long => btc,
+        Direction::Short => -btc,
+    };
+
+    let sats = (btc * Decimal::from(100_000_000)).round_dp(0).to_i64()?;
+
+    Some(SignedAmount::from_sat(sats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::PublicKey;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::macros::date;
+    use time::macros::datetime;
+    use uuid::Uuid;
+
+    fn fill(direction: Direction, price: Decimal, index_price: Option<Decimal>) -> Fill {
+        Fill {
+            match_id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            direction,
+            quantity: dec!(1_000),
+            price,
+            matching_fee: Amount::from_sat(300),
+            index_price,
+            filled_at: datetime!(2024-07-15 09:00 UTC),
+        }
+    }
+
+    #[test]
+    fn maker_captures_spread_on_both_sides() {
+        // 1_000 / 49_500 - 1_000 / 50_000 BTC
+        let long = fill(Direction::Long, dec!(49_500), Some(dec!(50_000)));
+        assert_eq!(spread_capture(&long), Some(SignedAmount::from_sat(20_202)));
+
+        // 1_000 / 50_000 - 1_000 / 50_500 BTC
+        let short = fill(Direction::Short, dec!(50_500), Some(dec!(50_000)));
+        assert_eq!(spread_capture(&short), Some(SignedAmount::from_sat(19_802)));
+    }
+
+    #[test]
+    fn fill_through_the_index_price_loses() {
+        let long = fill(Direction::Long, dec!(50_500), Some(dec!(50_000)));
+
+        assert_eq!(spread_capture(&long), Some(SignedAmount::from_sat(-19_802)));
+    }
+
+    #[test]
+    fn earnings_of_a_report() {
+        let maker = PublicKey::from_str(
+            "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+        )
+        .unwrap();
+        let report = SettlementReport::generate(
+            maker,
+            date!(2024 - 07 - 15),
+            vec![
+                fill(Direction::Long, dec!(49_500), Some(dec!(50_000))),
+                fill(Direction::Short, dec!(50_500), Some(dec!(50_000))),
+                fill(Direction::Short, dec!(50_500), None),
+            ],
+        );
+
+        let earnings = DailyEarnings::from_report(&report);
+
+        assert_eq!(
+            earnings,
+            vec![DailyEarnings {
+                date: date!(2024 - 07 - 15),
+                contract_symbol: ContractSymbol::BtcUsd,
+                fills: 3,
+                volume: dec!(3_000),
+                matching_fees: Amount::from_sat(900),
+                spread_capture: SignedAmount::from_sat(40_004),
+                hedging_costs: Amount::ZERO,
+            }]
+        );
+        assert_eq!(earnings[0].net(), SignedAmount::from_sat(39_104));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::db::positions::ContractSymbol;
+use crate::maker_earnings::DailyEarnings;
+use crate::schema::maker_earnings;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use diesel::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use time::Date;
+use time::OffsetDateTime;
+
+/// Store the earnings of a maker derived from their settlement report.
+///
+/// The hedging costs reported by the maker are kept.
+pub fn upsert(conn: &mut PgConnection, maker: PublicKey, earnings: &DailyEarnings) -> Result<()> {
+    let fills = earnings.fills as i32;
+    let volume = earnings.volume.to_f32().expect("to fit into f32");
+    let matching_fees_sats = earnings.matching_fees.to_sat() as i64;
+    let spread_capture_sats = earnings.spread_capture.to_sat();
+
+    diesel::insert_into(maker_earnings::table)
+        .values((
+            maker_earnings::maker_pubkey.eq(maker.to_string()),
+            maker_earnings::date.eq(earnings.date),
+            maker_earnings::contract_symbol.eq(ContractSymbol::from(earnings.contract_symbol)),
+            maker_earnings::fills.eq(fills),
+            maker_earnings::volume.eq(volume),
+            maker_earnings::matching_fees_sats.eq(matching_fees_sats),
+            maker_earnings::spread_capture_sats.eq(spread_capture_sats),
+        ))
+        .on_conflict((
+            maker_earnings::maker_pubkey,
+            maker_earnings::date,
+            maker_earnings::contract_symbol,
+        ))
+        .do_update()
+        .set((
+            maker_earnings::fills.eq(fills),
+            maker_earnings::volume.eq(volume),
+            maker_earnings::matching_fees_sats.eq(matching_fees_sats),
+            maker_earnings::spread_capture_sats.eq(spread_capture_sats),
+            maker_earnings::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Store the hedging costs reported by a maker, even if the earnings of the day have not been
+/// derived yet.
+pub fn set_hedging_costs(
+    conn: &mut PgConnection,
+    maker: PublicKey,
+    date: Date,
+    contract_symbol: xxi_node::commons::ContractSymbol,
+    hedging_costs: Amount,
+) -> Result<()> {
+    let hedging_costs_sats = hedging_costs.to_sat() as i64;
+
+    diesel::insert_into(maker_earnings::table)
+        .values((
+            maker_earnings::maker_pubkey.eq(maker.to_string()),
+            maker_earnings::date.eq(date),
+            maker_earnings::contract_symbol.eq(ContractSymbol::from(contract_symbol)),
+            maker_earnings::hedging_costs_sats.eq(hedging_costs_sats),
+        ))
+        .on_conflict((
+            maker_earnings::maker_pubkey,
+            maker_earnings::date,
+            maker_earnings::contract_symbol,
+        ))
+        .do_update()
+        .set((
+            maker_earnings::hedging_costs_sats.eq(hedging_costs_sats),
+            maker_earnings::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The earnings of the maker from `from` to `to`, both inclusive, oldest first.
+pub fn get_earnings_of_maker(
+    conn: &mut PgConnection,
+    maker: PublicKey,
+    from: Date,
+    to: Date,
+) -> QueryResult<Vec<DailyEarnings>> {
+    let earnings = maker_earnings::table
+        .filter(
+            maker_earnings::maker_pubkey
+                .eq(maker.to_string())
+                .and(maker_earnings::date.ge(from))
+                .and(maker_earnings::date.le(to)),
+        )
+        .order_by((maker_earnings::date.asc(), maker_earnings::id.asc()))
+        .select((
+            maker_earnings::date,
+            maker_earnings::contract_symbol,
+            maker_earnings::fills,
+            maker_earnings::volume,
+            maker_earnings::matching_fees_sats,
+            maker_earnings::spread_capture_sats,
+            maker_earnings::hedging_costs_sats,
+        ))
+        .load::<(Date, ContractSymbol, i32, f32, i64, i64, i64)>(conn)?;
+
+    let earnings = earnings
+        .into_iter()
+        .map(
+            |(
+                date,
+                contract_symbol,
+                fills,
+                volume,
+                matching_fees_sats,
+                spread_capture_sats,
+                hedging_costs_sats,
+            )| DailyEarnings {
+                date,
+                contract_symbol: contract_symbol.into(),
+                fills: fills as usize,
+                volume: Decimal::from_f32(volume).expect("to fit into Decimal"),
+                matching_fees: Amount::from_sat(matching_fees_sats as u64),
+                spread_capture: SignedAmount::from_sat(spread_capture_sats),
+                hedging_costs: Amount::from_sat(hedging_costs_sats as u64),
+            },
+        )
+        .collect();
+
+    Ok(earnings)
+}
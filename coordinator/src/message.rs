@@ -1,5 +1,6 @@
 use crate::notifications::Notification;
 use crate::notifications::NotificationKind;
+use crate::orderbook::outbound_queue::OutboundSender;
 use anyhow::Context;
 use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
@@ -12,6 +13,9 @@ use tokio::sync::broadcast;
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::Sender;
+use xxi_node::commons::ErrorCode;
+use xxi_node::commons::Locale;
+use xxi_node::commons::LocalizedError;
 use xxi_node::commons::Message;
 
 /// This value is arbitrarily set to 100 and defines the message accepted in the message
@@ -31,7 +35,8 @@ pub enum OrderbookMessage {
 #[derive(Clone)]
 pub struct NewUserMessage {
     pub new_user: PublicKey,
-    pub sender: Sender<Message>,
+    pub sender: OutboundSender,
+    pub locale: Locale,
 }
 
 pub fn spawn_delivering_messages_to_authenticated_users(
@@ -49,9 +54,10 @@ pub fn spawn_delivering_messages_to_authenticated_users(
             loop {
                 match user_feed.recv().await {
                     Ok(new_user_msg) => {
-                        traders
-                            .write()
-                            .insert(new_user_msg.new_user, new_user_msg.sender);
+                        traders.write().insert(
+                            new_user_msg.new_user,
+                            (new_user_msg.sender, new_user_msg.locale),
+                        );
                     }
                     Err(RecvError::Closed) => {
                         tracing::error!("New user message sender died! Channel closed");
@@ -90,7 +96,7 @@ pub fn spawn_delivering_messages_to_authenticated_users(
 }
 
 async fn process_orderbook_message(
-    authenticated_users: &RwLock<HashMap<PublicKey, Sender<Message>>>,
+    authenticated_users: &RwLock<HashMap<PublicKey, (OutboundSender, Locale)>>,
     notification_sender: &Sender<Notification>,
     notification: OrderbookMessage,
 ) -> Result<()> {
@@ -105,8 +111,9 @@ async fn process_orderbook_message(
             let trader = authenticated_users.read().get(&trader_id).cloned();
 
             match trader {
-                Some(sender) => {
-                    if let Err(e) = sender.send(message).await {
+                Some((sender, locale)) => {
+                    // If the trader can't keep up, they are disconnected and notified below instead.
+                    if let Err(e) = sender.send(localize(message, locale)) {
                         tracing::warn!(%trader_id, "Connection lost to trader: {e:#}");
                     } else {
                         tracing::trace!(
@@ -135,3 +142,24 @@ async fn process_orderbook_message(
 
     Ok(())
 }
+
+/// Attach the error message in the user's locale to error messages.
+fn localize(message: Message, locale: Locale) -> Message {
+    match message {
+        Message::TradeError {
+            order_id, error, ..
+        } => {
+            let localized = LocalizedError::new(error.trade_error_code(), locale);
+            Message::TradeError {
+                order_id,
+                error,
+                localized: Some(localized),
+            }
+        }
+        Message::RolloverError { error, .. } => Message::RolloverError {
+            error,
+            localized: Some(LocalizedError::new(ErrorCode::RolloverFailed, locale)),
+        },
+        message => message,
+    }
+}
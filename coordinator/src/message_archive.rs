@@ -0,0 +1,222 @@
+use crate::db;
+use aes_gcm_siv::AeadInPlace;
+use aes_gcm_siv::Aes256GcmSiv;
+use aes_gcm_siv::KeyInit;
+use aes_gcm_siv::Nonce;
+use anyhow::anyhow;
+use anyhow::ensure;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use parking_lot::RwLock;
+use rand::Rng;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use xxi_node::dlc_message::SerializedDlcMessage;
+use xxi_node::message_handler::TenTenOneMessage;
+use xxi_node::node::tentenone_message_name;
+use xxi_node::node::ProtocolId;
+
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MessageArchiveSettings {
+    /// Whether every DLC message exchanged as part of a DLC protocol should be archived.
+    pub enabled: bool,
+    /// How long archived messages are kept before they are deleted.
+    pub retention_days: u32,
+}
+
+/// Audit trail of the raw DLC messages exchanged with our peers.
+///
+/// Messages are encrypted at rest, as they contain everything needed to reconstruct a trader's
+/// DLC channel.
+#[derive(Clone)]
+pub struct MessageArchive {
+    pool: Pool<ConnectionManager<PgConnection>>,
+    cipher: Aes256GcmSiv,
+    settings: Arc<RwLock<MessageArchiveSettings>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchivedMessage {
+    pub peer_id: String,
+    pub inbound: bool,
+    pub message_type: String,
+    pub message: serde_json::Value,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl MessageArchive {
+    pub fn new(
+        pool: Pool<ConnectionManager<PgConnection>>,
+        key: [u8; 32],
+        settings: MessageArchiveSettings,
+    ) -> Self {
+        let cipher = Aes256GcmSiv::new_from_slice(&key).expect("key to have correct key size");
+
+        Self {
+            pool,
+            cipher,
+            settings: Arc::new(RwLock::new(settings)),
+        }
+    }
+
+    pub fn update_settings(&self, settings: MessageArchiveSettings) {
+        *self.settings.write() = settings;
+    }
+
+    /// Archive a DLC message, if archiving is enabled and the message belongs to a DLC protocol.
+    ///
+    /// Failing to archive a message must not interrupt the DLC protocol, hence errors are only
+    /// logged.
+    pub fn archive(&self, peer: PublicKey, msg: &TenTenOneMessage, inbound: bool) {
+        if !self.settings.read().enabled {
+            return;
+        }
+
+        let protocol_id = match msg.get_reference_id().map(ProtocolId::try_from) {
+            Some(Ok(protocol_id)) => protocol_id,
+            Some(Err(e)) => {
+                tracing::warn!(%peer, "Not archiving message with invalid protocol ID: {e:#}");
+                return;
+            }
+            None => return,
+        };
+
+        if let Err(e) = self.insert(peer, msg, protocol_id, inbound) {
+            tracing::error!(
+                %peer,
+                %protocol_id,
+                kind = %tentenone_message_name(msg),
+                inbound,
+                "Failed to archive message: {e:#}"
+            );
+        }
+    }
+
+    /// Get all archived messages of a DLC protocol, in the order they were archived.
+    pub fn get(&self, protocol_id: ProtocolId) -> Result<Vec<ArchivedMessage>> {
+        let mut conn = self.pool.get()?;
+        let messages = db::message_archive::get_by_protocol_id(&mut conn, protocol_id)?;
+
+        messages
+            .into_iter()
+            .map(|message| {
+                let plaintext = self.decrypt(&message.encrypted_message)?;
+
+                Ok(ArchivedMessage {
+                    peer_id: message.peer_id,
+                    inbound: message.inbound,
+                    message_type: message.message_type,
+                    message: serde_json::from_slice(&plaintext)?,
+                    created_at: message.created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Delete all archived messages which are older than the retention period.
+    pub fn prune(&self) -> Result<usize> {
+        let retention_days = self.settings.read().retention_days;
+        let cut_off = OffsetDateTime::now_utc() - time::Duration::days(retention_days as i64);
+
+        let mut conn = self.pool.get()?;
+        let deleted = db::message_archive::delete_before(&mut conn, cut_off)?;
+
+        Ok(deleted)
+    }
+
+    fn insert(
+        &self,
+        peer: PublicKey,
+        msg: &TenTenOneMessage,
+        protocol_id: ProtocolId,
+        inbound: bool,
+    ) -> Result<()> {
+        let serialized_message = SerializedDlcMessage::try_from(msg)?;
+        let message_hash = serialized_message.generate_hash();
+        let encrypted_message = self.encrypt(serialized_message.message.as_bytes())?;
+
+        let mut conn = self.pool.get()?;
+        db::message_archive::insert(
+            &mut conn,
+            protocol_id,
+            peer,
+            inbound,
+            &tentenone_message_name(msg),
+            &message_hash,
+            encrypted_message,
+        )?;
+
+        Ok(())
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        encrypt(&self.cipher, plaintext)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        decrypt(&self.cipher, ciphertext)
+    }
+}
+
+/// Encrypt the plaintext with a random nonce, which is prepended to the ciphertext.
+pub(crate) fn encrypt(cipher: &Aes256GcmSiv, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let nonce = rand::thread_rng().gen::<[u8; NONCE_LENGTH]>();
+    let nonce = Nonce::from_slice(&nonce);
+
+    let mut buffer = plaintext.to_vec();
+    cipher
+        .encrypt_in_place(nonce, b"", &mut buffer)
+        .map_err(|e| anyhow!("{e:#}"))?;
+
+    let mut ciphertext = nonce.to_vec();
+    ciphertext.extend_from_slice(&buffer);
+    Ok(ciphertext)
+}
+
+pub(crate) fn decrypt(cipher: &Aes256GcmSiv, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    ensure!(ciphertext.len() > NONCE_LENGTH, "Ciphertext too short");
+
+    let (nonce, ciphertext) = ciphertext.split_at(NONCE_LENGTH);
+    let nonce = Nonce::from_slice(nonce);
+
+    let mut buffer = ciphertext.to_vec();
+    cipher
+        .decrypt_in_place(nonce, b"", &mut buffer)
+        .map_err(|e| anyhow!("{e:#}"))?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_message_can_be_decrypted() {
+        let cipher = Aes256GcmSiv::new_from_slice(&[1u8; 32]).unwrap();
+        let plaintext = br#"{"reference_id":"deadbeef"}"#;
+
+        let ciphertext = encrypt(&cipher, plaintext).unwrap();
+
+        assert_ne!(&ciphertext[NONCE_LENGTH..], plaintext.as_slice());
+        assert_eq!(decrypt(&cipher, &ciphertext).unwrap(), plaintext.to_vec());
+    }
+
+    #[test]
+    fn message_cannot_be_decrypted_with_other_key() {
+        let cipher = Aes256GcmSiv::new_from_slice(&[1u8; 32]).unwrap();
+        let other_cipher = Aes256GcmSiv::new_from_slice(&[2u8; 32]).unwrap();
+
+        let ciphertext = encrypt(&cipher, b"message").unwrap();
+
+        assert!(decrypt(&other_cipher, &ciphertext).is_err());
+    }
+}
@@ -4,6 +4,58 @@ use anyhow::Result;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::PooledConnection;
 use diesel::PgConnection;
+use lazy_static::lazy_static;
+use prometheus::register_int_counter_vec;
+use prometheus::register_int_gauge_vec;
+use prometheus::IntCounterVec;
+use prometheus::IntGaugeVec;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use xxi_node::storage::DlcChannelEvent;
+
+lazy_static! {
+    static ref TABLE_ROWS: IntGaugeVec = register_int_gauge_vec!(
+        "coordinator_table_rows",
+        "Estimated number of rows per database table.",
+        &["table"]
+    )
+    .expect("valid metric");
+    static ref TABLE_SIZE: IntGaugeVec = register_int_gauge_vec!(
+        "coordinator_table_size_bytes",
+        "Size of each database table on disk, including indices.",
+        &["table"]
+    )
+    .expect("valid metric");
+    static ref DLC_CHANNEL_EVENTS: IntCounterVec = register_int_counter_vec!(
+        "coordinator_dlc_channel_events_total",
+        "Number of DLC channel state transitions per event.",
+        &["event"]
+    )
+    .expect("valid metric");
+}
+
+/// Count the state transitions of the DLC channels, independently of their processing by the
+/// node.
+pub fn spawn_counting_dlc_channel_events(mut receiver: broadcast::Receiver<DlcChannelEvent>) {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(dlc_channel_event) => {
+                    DLC_CHANNEL_EVENTS
+                        .with_label_values(&[dlc_channel_event.name()])
+                        .inc();
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Skipped counting {skipped} DLC channel events");
+                }
+                Err(RecvError::Closed) => {
+                    tracing::error!("The DLC channel event bus has been closed");
+                    break;
+                }
+            }
+        }
+    });
+}
 
 pub fn collect_metrics(
     mut conn: PooledConnection<ConnectionManager<PgConnection>>,
@@ -16,5 +68,14 @@ pub fn collect_metrics(
     )?;
     // TODO: also collect LN balance
 
+    for table in db::metrics::get_table_sizes(&mut conn)? {
+        TABLE_ROWS
+            .with_label_values(&[&table.table_name])
+            .set(table.rows);
+        TABLE_SIZE
+            .with_label_values(&[&table.table_name])
+            .set(table.bytes);
+    }
+
     Ok(())
 }
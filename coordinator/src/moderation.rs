@@ -0,0 +1,298 @@
+//! Suspensions and bans of abusive traders, e.g. traders spamming orders or repeatedly aborting
+//! DLC protocols.
+//!
+//! Restricted traders can neither authenticate nor post orders, and their matches are not executed.
+//! Admins restrict traders manually; with [`ModerationSettings::auto_suspend`], traders exceeding
+//! one of the configured limits are suspended automatically.
+
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use time::Duration;
+use time::OffsetDateTime;
+
+mod db;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModerationSettings {
+    /// Whether traders exceeding one of the limits below are suspended automatically.
+    pub auto_suspend: bool,
+    /// The max number of market orders a trader may submit within an hour. Makers quote with
+    /// limit orders, which are not limited.
+    pub max_market_orders_per_hour: u32,
+    /// The max number of DLC protocols a trader may reject within a day.
+    pub max_protocol_rejections_per_day: u32,
+    /// How long an automatic suspension lasts.
+    pub suspension_hours: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Restriction {
+    pub trader_pubkey: PublicKey,
+    pub reason: String,
+    /// When the suspension ends. A ban never ends.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+    /// Whether the restriction was imposed by the abuse heuristics instead of an admin.
+    pub automatic: bool,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// The trader is suspended or banned.
+#[derive(Debug, thiserror::Error)]
+#[error("Trader {} is {restriction}", restriction.trader_pubkey)]
+pub struct TraderRestricted {
+    pub restriction: Restriction,
+}
+
+/// The restrictions of traders, kept in memory as they are consulted on every authentication,
+/// order and trade.
+#[derive(Clone)]
+pub struct Moderation {
+    pool: Pool<ConnectionManager<PgConnection>>,
+    restrictions: Arc<RwLock<HashMap<PublicKey, Restriction>>>,
+    settings: Arc<RwLock<ModerationSettings>>,
+}
+
+impl Moderation {
+    pub fn new(
+        pool: Pool<ConnectionManager<PgConnection>>,
+        settings: ModerationSettings,
+    ) -> Result<Self> {
+        let mut conn = pool.get()?;
+        let restrictions = db::get_active(&mut conn, OffsetDateTime::now_utc())?
+            .into_iter()
+            .map(|restriction| (restriction.trader_pubkey, restriction))
+            .collect();
+
+        Ok(Self {
+            pool,
+            restrictions: Arc::new(RwLock::new(restrictions)),
+            settings: Arc::new(RwLock::new(settings)),
+        })
+    }
+
+    pub fn update_settings(&self, settings: ModerationSettings) {
+        *self.settings.write() = settings;
+    }
+
+    pub fn ensure_allowed(&self, trader_id: PublicKey) -> Result<(), TraderRestricted> {
+        match self.restrictions.read().get(&trader_id) {
+            Some(restriction) if restriction.is_active(OffsetDateTime::now_utc()) => {
+                Err(TraderRestricted {
+                    restriction: restriction.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// All restrictions which are still in effect.
+    pub fn restrictions(&self) -> Vec<Restriction> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut restrictions = self
+            .restrictions
+            .read()
+            .values()
+            .filter(|restriction| restriction.is_active(now))
+            .cloned()
+            .collect::<Vec<_>>();
+        restrictions.sort_by_key(|restriction| restriction.created_at);
+
+        restrictions
+    }
+
+    /// Suspend the trader for the given duration, or ban them without one. Replaces any previous
+    /// restriction of the trader.
+    pub fn restrict(
+        &self,
+        trader_id: PublicKey,
+        reason: String,
+        duration: Option<Duration>,
+        automatic: bool,
+    ) -> Result<Restriction> {
+        let until = duration.map(|duration| OffsetDateTime::now_utc() + duration);
+
+        let mut conn = self.pool.get()?;
+        let restriction = db::replace(&mut conn, trader_id, &reason, until, automatic)?;
+
+        tracing::warn!(%trader_id, %restriction, "Restricted trader");
+
+        self.restrictions
+            .write()
+            .insert(trader_id, restriction.clone());
+
+        Ok(restriction)
+    }
+
+    pub fn lift(&self, trader_id: PublicKey) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::lift(&mut conn, trader_id)?;
+
+        tracing::info!(%trader_id, "Lifted restriction of trader");
+
+        self.restrictions.write().remove(&trader_id);
+
+        Ok(())
+    }
+
+    /// Suspend the traders who exceeded one of the limits of the [`ModerationSettings`], unless
+    /// they are already restricted.
+    pub fn suspend_abusive_traders(&self) -> Result<()> {
+        let settings = *self.settings.read();
+        if !settings.auto_suspend {
+            return Ok(());
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let mut conn = self.pool.get()?;
+        let market_orders =
+            db::count_market_orders_per_trader(&mut conn, now - Duration::hours(1))?;
+        let rejections =
+            db::count_protocol_rejections_per_trader(&mut conn, now - Duration::days(1))?;
+
+        for (trader_id, reason) in abusive_traders(&settings, &market_orders, &rejections) {
+            if self.ensure_allowed(trader_id).is_err() {
+                continue;
+            }
+
+            self.restrict(
+                trader_id,
+                reason,
+                Some(Duration::hours(i64::from(settings.suspension_hours))),
+                true,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Restriction {
+    pub fn is_active(&self, now: OffsetDateTime) -> bool {
+        self.until.map_or(true, |until| until > now)
+    }
+}
+
+impl fmt::Display for Restriction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.until {
+            Some(until) => write!(f, "suspended until {until}: {}", self.reason),
+            None => write!(f, "banned: {}", self.reason),
+        }
+    }
+}
+
+/// The traders exceeding one of the limits, with the reason for their suspension.
+fn abusive_traders(
+    settings: &ModerationSettings,
+    market_orders: &[(PublicKey, i64)],
+    protocol_rejections: &[(PublicKey, i64)],
+) -> Vec<(PublicKey, String)> {
+    let spammers = market_orders
+        .iter()
+        .filter(|(_, count)| *count > i64::from(settings.max_market_orders_per_hour))
+        .map(|(trader_id, count)| {
+            (
+                *trader_id,
+                format!("Submitted {count} market orders within an hour"),
+            )
+        });
+
+    let aborters = protocol_rejections
+        .iter()
+        .filter(|(_, count)| *count > i64::from(settings.max_protocol_rejections_per_day))
+        .map(|(trader_id, count)| {
+            (
+                *trader_id,
+                format!("Rejected {count} DLC protocols within a day"),
+            )
+        });
+
+    let mut abusive = HashMap::new();
+    for (trader_id, reason) in spammers.chain(aborters) {
+        abusive
+            .entry(trader_id)
+            .and_modify(|reasons: &mut String| {
+                reasons.push_str(". ");
+                reasons.push_str(&reason);
+            })
+            .or_insert(reason);
+    }
+
+    abusive.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn trader(pubkey: &str) -> PublicKey {
+        PublicKey::from_str(pubkey).unwrap()
+    }
+
+    #[test]
+    fn traders_exceeding_a_limit_are_abusive() {
+        let spammer = trader("02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655");
+        let aborter = trader("027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007");
+        let regular = trader("0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166");
+
+        let settings = ModerationSettings {
+            auto_suspend: true,
+            max_market_orders_per_hour: 10,
+            max_protocol_rejections_per_day: 3,
+            suspension_hours: 24,
+        };
+
+        let mut abusive = abusive_traders(
+            &settings,
+            &[(spammer, 11), (regular, 10)],
+            &[(aborter, 4), (regular, 3)],
+        );
+        abusive.sort();
+
+        let mut expected = vec![
+            (
+                spammer,
+                "Submitted 11 market orders within an hour".to_string(),
+            ),
+            (aborter, "Rejected 4 DLC protocols within a day".to_string()),
+        ];
+        expected.sort();
+
+        assert_eq!(abusive, expected);
+    }
+
+    #[test]
+    fn suspensions_end_but_bans_do_not() {
+        let now = OffsetDateTime::now_utc();
+        let suspension = Restriction {
+            trader_pubkey: trader(
+                "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+            ),
+            reason: "Spam".to_string(),
+            until: Some(now + Duration::hours(1)),
+            automatic: false,
+            created_at: now,
+        };
+        let ban = Restriction {
+            until: None,
+            ..suspension.clone()
+        };
+
+        assert!(suspension.is_active(now));
+        assert!(!suspension.is_active(now + Duration::hours(2)));
+        assert!(ban.is_active(now + Duration::days(3650)));
+    }
+}
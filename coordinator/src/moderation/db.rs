@@ -0,0 +1,121 @@
+use crate::moderation::Restriction;
+use crate::orderbook::db::custom_types::OrderType;
+use crate::schema::dlc_protocol_rejections;
+use crate::schema::orders;
+use crate::schema::trader_restrictions;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use std::str::FromStr;
+use time::OffsetDateTime;
+
+#[derive(Queryable, Debug)]
+struct TraderRestriction {
+    #[allow(dead_code)]
+    id: i32,
+    trader_pubkey: String,
+    reason: String,
+    until: Option<OffsetDateTime>,
+    automatic: bool,
+    created_at: OffsetDateTime,
+    #[allow(dead_code)]
+    lifted_at: Option<OffsetDateTime>,
+}
+
+/// The restrictions which have neither been lifted nor run out by `now`.
+pub fn get_active(conn: &mut PgConnection, now: OffsetDateTime) -> Result<Vec<Restriction>> {
+    trader_restrictions::table
+        .filter(trader_restrictions::lifted_at.is_null())
+        .filter(
+            trader_restrictions::until
+                .is_null()
+                .or(trader_restrictions::until.gt(now)),
+        )
+        .order_by(trader_restrictions::created_at.asc())
+        .load::<TraderRestriction>(conn)?
+        .into_iter()
+        .map(Restriction::try_from)
+        .collect()
+}
+
+/// Lift the previous restrictions of the trader and store the new one.
+pub fn replace(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    reason: &str,
+    until: Option<OffsetDateTime>,
+    automatic: bool,
+) -> Result<Restriction> {
+    let restriction = conn.transaction(|conn| {
+        lift(conn, trader_pubkey)?;
+
+        diesel::insert_into(trader_restrictions::table)
+            .values((
+                trader_restrictions::trader_pubkey.eq(trader_pubkey.to_string()),
+                trader_restrictions::reason.eq(reason),
+                trader_restrictions::until.eq(until),
+                trader_restrictions::automatic.eq(automatic),
+            ))
+            .get_result::<TraderRestriction>(conn)
+    })?;
+
+    restriction.try_into()
+}
+
+pub fn lift(conn: &mut PgConnection, trader_pubkey: PublicKey) -> QueryResult<usize> {
+    diesel::update(trader_restrictions::table)
+        .filter(trader_restrictions::trader_pubkey.eq(trader_pubkey.to_string()))
+        .filter(trader_restrictions::lifted_at.is_null())
+        .set(trader_restrictions::lifted_at.eq(OffsetDateTime::now_utc()))
+        .execute(conn)
+}
+
+/// The number of market orders submitted since `since`, by trader.
+pub fn count_market_orders_per_trader(
+    conn: &mut PgConnection,
+    since: OffsetDateTime,
+) -> Result<Vec<(PublicKey, i64)>> {
+    let rows = orders::table
+        .filter(orders::timestamp.ge(since))
+        .filter(orders::order_type.eq(OrderType::Market))
+        .group_by(orders::trader_id)
+        .select((orders::trader_id, count_star()))
+        .load::<(String, i64)>(conn)?;
+
+    parse_counts(rows)
+}
+
+/// The number of DLC protocols rejected since `since`, by trader.
+pub fn count_protocol_rejections_per_trader(
+    conn: &mut PgConnection,
+    since: OffsetDateTime,
+) -> Result<Vec<(PublicKey, i64)>> {
+    let rows = dlc_protocol_rejections::table
+        .filter(dlc_protocol_rejections::created_at.ge(since))
+        .group_by(dlc_protocol_rejections::trader_pubkey)
+        .select((dlc_protocol_rejections::trader_pubkey, count_star()))
+        .load::<(String, i64)>(conn)?;
+
+    parse_counts(rows)
+}
+
+fn parse_counts(rows: Vec<(String, i64)>) -> Result<Vec<(PublicKey, i64)>> {
+    rows.into_iter()
+        .map(|(trader, count)| Ok((PublicKey::from_str(&trader)?, count)))
+        .collect()
+}
+
+impl TryFrom<TraderRestriction> for Restriction {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TraderRestriction) -> Result<Self> {
+        Ok(Restriction {
+            trader_pubkey: PublicKey::from_str(&value.trader_pubkey)?,
+            reason: value.reason,
+            until: value.until,
+            automatic: value.automatic,
+            created_at: value.created_at,
+        })
+    }
+}
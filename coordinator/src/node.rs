@@ -1,7 +1,17 @@
 use crate::db;
 use crate::dlc_protocol;
+use crate::funding_fee::IndexPriceSource;
+use crate::kill_switch::KillSwitch;
 use crate::message::OrderbookMessage;
+use crate::message_archive::MessageArchive;
+use crate::moderation::Moderation;
+use crate::node::funding_accelerator::FundingAcceleratorSettings;
+use crate::node::oracle_announcements::AnnouncementPrefetchSettings;
+use crate::node::quiesce::Quiesce;
 use crate::node::storage::NodeStorage;
+use crate::node::zombie_channels::ZombieChannelSettings;
+use crate::orderbook::matching_preference::MatchingPreferenceSettings;
+use crate::orderbook::spread::SpreadSettings;
 use crate::position::models::PositionState;
 use crate::storage::CoordinatorTenTenOneStorage;
 use crate::trade::websocket::InternalPositionUpdateMessage;
@@ -27,6 +37,7 @@ use tokio::sync::mpsc;
 use tokio::sync::RwLock;
 use xxi_node::bitcoin_conversion::to_secp_pk_29;
 use xxi_node::bitcoin_conversion::to_secp_pk_30;
+use xxi_node::commons::ExpirySchedule;
 use xxi_node::commons::Message::RolloverError;
 use xxi_node::commons::Message::TradeError;
 use xxi_node::commons::TradingError;
@@ -48,12 +59,19 @@ use xxi_node::node::ProtocolId;
 use xxi_node::node::RunningNode;
 
 pub mod channel;
+pub mod channel_migration;
 pub mod expired_positions;
+pub mod expiry_settlement;
+pub mod funding_accelerator;
 pub mod invoice;
 pub mod liquidated_positions;
+pub mod oracle_announcements;
+pub mod quiesce;
 pub mod rollover;
+pub mod settlement_dispute;
 pub mod storage;
 pub mod unrealized_pnl;
+pub mod zombie_channels;
 
 #[derive(Debug, Clone)]
 pub struct NodeSettings {
@@ -62,6 +80,17 @@ pub struct NodeSettings {
     pub allow_opening_positions: bool,
     pub maintenance_margin_rate: f32,
     pub order_matching_fee_rate: f32,
+    pub spread: SpreadSettings,
+    pub matching_preference: MatchingPreferenceSettings,
+    pub max_settlement_price_divergence: Option<f32>,
+    pub expiry_smoothing: bool,
+    pub expiry_settlement_grace_period_days: u32,
+    pub reserve_interest_apr: f32,
+    pub index_price_source: IndexPriceSource,
+    pub zombie_channels: ZombieChannelSettings,
+    pub funding_accelerator: FundingAcceleratorSettings,
+    pub announcement_prefetch: AnnouncementPrefetchSettings,
+    pub expiry_schedule: Option<ExpirySchedule>,
 }
 
 #[derive(Clone)]
@@ -79,9 +108,14 @@ pub struct Node {
     pub tx_position_feed: Sender<InternalPositionUpdateMessage>,
     trade_notifier: mpsc::Sender<OrderbookMessage>,
     pub lnd_bridge: LndBridge,
+    pub message_archive: MessageArchive,
+    pub kill_switch: KillSwitch,
+    pub quiesce: Quiesce,
+    pub moderation: Moderation,
 }
 
 impl Node {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         inner: Arc<
             node::Node<
@@ -96,6 +130,10 @@ impl Node {
         tx_position_feed: Sender<InternalPositionUpdateMessage>,
         trade_notifier: mpsc::Sender<OrderbookMessage>,
         lnd_bridge: LndBridge,
+        message_archive: MessageArchive,
+        kill_switch: KillSwitch,
+        quiesce: Quiesce,
+        moderation: Moderation,
     ) -> Self {
         Self {
             inner,
@@ -105,9 +143,22 @@ impl Node {
             tx_position_feed,
             trade_notifier,
             lnd_bridge,
+            message_archive,
+            kill_switch,
+            quiesce,
+            moderation,
         }
     }
 
+    /// The configured expiry schedule, or the default schedule of the network.
+    pub async fn expiry_schedule(&self) -> ExpirySchedule {
+        self.settings
+            .read()
+            .await
+            .expiry_schedule
+            .unwrap_or_else(|| ExpirySchedule::for_network(self.inner.network))
+    }
+
     /// Returns true or false, whether the given peer_id is connected with us.
     pub fn is_connected(&self, peer_id: PublicKey) -> bool {
         self.inner
@@ -152,7 +203,11 @@ impl Node {
                                 if let Some(order_id) = msg.get_order_id() {
                                     OrderbookMessage::TraderMessage {
                                         trader_id: to_secp_pk_30(node_id),
-                                        message: TradeError { order_id, error },
+                                        message: TradeError {
+                                            order_id,
+                                            error,
+                                            localized: None,
+                                        },
                                         notification: None,
                                     }
                                 } else {
@@ -162,7 +217,10 @@ impl Node {
                             }
                             TenTenOneMessageType::Rollover => OrderbookMessage::TraderMessage {
                                 trader_id: to_secp_pk_30(node_id),
-                                message: RolloverError { error },
+                                message: RolloverError {
+                                    error,
+                                    localized: None,
+                                },
                                 notification: None,
                             },
                             TenTenOneMessageType::Other => {
@@ -242,6 +300,8 @@ impl Node {
             }
         };
 
+        self.message_archive.archive(node_id, msg, true);
+
         self.verify_collab_close_offer(&node_id, msg)?;
 
         let resp = self
@@ -366,14 +426,27 @@ impl Node {
             TenTenOneMessage::CollaborativeCloseOffer(TenTenOneCollaborativeCloseOffer {
                 collaborative_close_offer: close_offer,
             }) => {
-                tracing::info!(
-                    channel_id = hex::encode(close_offer.channel_id),
-                    node_id = node_id.to_string(),
-                    "Accepting offer to collaboratively close a channel"
-                );
+                // The offer stays pending and is accepted once the trader reconnects, if
+                // withdrawals are allowed again by then.
+                match self.kill_switch.ensure_withdrawals_allowed() {
+                    Ok(()) => {
+                        tracing::info!(
+                            channel_id = hex::encode(close_offer.channel_id),
+                            node_id = node_id.to_string(),
+                            "Accepting offer to collaboratively close a channel"
+                        );
 
-                self.inner
-                    .accept_dlc_channel_collaborative_close(&close_offer.channel_id)?;
+                        self.inner
+                            .accept_dlc_channel_collaborative_close(&close_offer.channel_id)?;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            channel_id = hex::encode(close_offer.channel_id),
+                            node_id = node_id.to_string(),
+                            "Not accepting offer to collaboratively close a channel: {e:#}"
+                        );
+                    }
+                }
             }
             TenTenOneMessage::Accept(TenTenOneAcceptChannel {
                 accept_channel:
@@ -432,6 +505,8 @@ impl Node {
                         reference_id,
                         ..
                     },
+                order_id,
+                reason,
             }) => {
                 let channel_id_hex_string = hex::encode(channel_id);
 
@@ -450,11 +525,26 @@ impl Node {
                 };
                 let protocol_id = ProtocolId::try_from(reference_id)?;
 
+                tracing::info!(
+                    %protocol_id,
+                    ?order_id,
+                    ?reason,
+                    "DLC protocol offer was rejected"
+                );
+
+                let mut connection = self.pool.get()?;
+                db::dlc_protocol_rejections::insert(
+                    &mut connection,
+                    protocol_id,
+                    node_id,
+                    *order_id,
+                    *reason,
+                )?;
+
                 let protocol_executor = dlc_protocol::DlcProtocolExecutor::new(self.pool.clone());
                 protocol_executor.fail_dlc_protocol(protocol_id)?;
 
                 let channel = self.inner.get_dlc_channel_by_id(channel_id)?;
-                let mut connection = self.pool.get()?;
 
                 match channel {
                     Channel::Cancelled(_) => {
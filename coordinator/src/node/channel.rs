@@ -1,8 +1,11 @@
 use crate::db;
+use crate::db::dlc_channel_snapshots::DlcChannelSnapshot;
 use crate::dlc_protocol;
 use crate::dlc_protocol::DlcProtocolType;
+use crate::lightning_withdrawal;
 use crate::node::Node;
 use crate::position::models::PositionState;
+use crate::reserve_top_up;
 use crate::FundingFee;
 use anyhow::bail;
 use anyhow::Context;
@@ -23,17 +26,39 @@ use dlc_manager::channel::SettledClosingChannel;
 use dlc_manager::contract::ClosedContract;
 use dlc_manager::contract::Contract;
 use dlc_manager::contract::PreClosedContract;
+use dlc_manager::ContractId;
 use dlc_manager::DlcChannelId;
+use dlc_manager::Oracle;
 use dlc_manager::ReferenceId;
+use dlc_messages::oracle_msgs::OracleAnnouncement;
+use dlc_messages::oracle_msgs::OracleAttestation;
+use lazy_static::lazy_static;
+use prometheus::register_int_counter_vec;
+use prometheus::IntCounterVec;
 use rust_decimal::Decimal;
 use time::OffsetDateTime;
 use tokio::sync::broadcast::error::RecvError;
+use tokio::task::spawn_blocking;
 use xxi_node::bitcoin_conversion::to_secp_pk_30;
 use xxi_node::bitcoin_conversion::to_txid_30;
+use xxi_node::bitcoin_conversion::to_xonly_pk_30;
+use xxi_node::commons::OracleEventId;
+use xxi_node::node::chain_audit::ChainDiscrepancy;
 use xxi_node::node::event::NodeEvent;
+use xxi_node::node::signed_channel_state_name;
 use xxi_node::node::ProtocolId;
+use xxi_node::node::Storage as _;
 use xxi_node::storage::DlcChannelEvent;
 
+lazy_static! {
+    static ref CHAIN_DISCREPANCIES: IntCounterVec = register_int_counter_vec!(
+        "coordinator_chain_discrepancies_total",
+        "Number of discrepancies found between the DLC channels and the blockchain.",
+        &["kind"]
+    )
+    .expect("valid metric");
+}
+
 pub enum DlcChannelState {
     Pending,
     Open,
@@ -62,13 +87,52 @@ pub struct DlcChannel {
 }
 
 impl Node {
-    pub async fn force_close_dlc_channel(&self, channel_id: DlcChannelId) -> Result<()> {
-        self.inner.close_dlc_channel(channel_id, true).await?;
-        Ok(())
+    pub async fn force_close_dlc_channel(&self, channel_id: DlcChannelId) -> Result<ProtocolId> {
+        let channel = self.inner.get_dlc_channel_by_id(&channel_id)?;
+        let previous_id = channel
+            .get_reference_id()
+            .map(ProtocolId::try_from)
+            .transpose()?;
+
+        // A force close can't wait for the Lightning withdrawals and reserve top-ups to be
+        // settled, so they have to be reconciled manually.
+        {
+            let trader_pubkey = to_secp_pk_30(channel.get_counter_party_id());
+            let mut conn = self.pool.get()?;
+            if let Err(e) =
+                lightning_withdrawal::ensure_no_outstanding_withdrawals(&mut conn, trader_pubkey)
+            {
+                tracing::error!(%trader_pubkey, "Force closing DLC channel: {e:#}");
+            }
+            if let Err(e) = reserve_top_up::ensure_no_outstanding_top_ups(&mut conn, trader_pubkey)
+            {
+                tracing::error!(%trader_pubkey, "Force closing DLC channel: {e:#}");
+            }
+        }
+
+        let protocol_id = self.inner.close_dlc_channel(channel_id, true).await?;
+
+        let protocol_executor = dlc_protocol::DlcProtocolExecutor::new(self.pool.clone());
+
+        protocol_executor.start_force_close_channel_protocol(
+            protocol_id,
+            previous_id,
+            &channel.get_id(),
+            &to_secp_pk_30(channel.get_counter_party_id()),
+        )?;
+
+        Ok(protocol_id)
     }
 
     pub async fn close_dlc_channel(&self, channel_id: DlcChannelId) -> Result<()> {
         let channel = self.inner.get_dlc_channel_by_id(&channel_id)?;
+
+        {
+            let trader_pubkey = to_secp_pk_30(channel.get_counter_party_id());
+            let mut conn = self.pool.get()?;
+            lightning_withdrawal::ensure_no_outstanding_withdrawals(&mut conn, trader_pubkey)?;
+            reserve_top_up::ensure_no_outstanding_top_ups(&mut conn, trader_pubkey)?;
+        }
         let previous_id = channel
             .get_reference_id()
             .map(ProtocolId::try_from)
@@ -104,10 +168,32 @@ impl Node {
                                 );
                             }
                         }
+                        Ok(NodeEvent::ChainDiscrepancy {
+                            channel_id,
+                            discrepancy,
+                        }) => {
+                            let result = spawn_blocking({
+                                let node = node.clone();
+                                move || node.process_chain_discrepancy(&channel_id, discrepancy)
+                            })
+                            .await
+                            .expect("task to complete");
+
+                            if let Err(e) = result {
+                                tracing::error!(
+                                    channel_id = hex::encode(channel_id),
+                                    ?discrepancy,
+                                    "Failed to process chain discrepancy. Error: {e:#}"
+                                );
+                            }
+                        }
                         Ok(NodeEvent::Connected { .. })
                         | Ok(NodeEvent::SendDlcMessage { .. })
                         | Ok(NodeEvent::StoreDlcMessage { .. })
-                        | Ok(NodeEvent::SendLastDlcMessage { .. }) => {} // ignored
+                        | Ok(NodeEvent::SendLastDlcMessage { .. })
+                        | Ok(NodeEvent::FundingTransactionConfirmations { .. })
+                        | Ok(NodeEvent::CollaborativeCloseFee { .. })
+                        | Ok(NodeEvent::ProtocolVersionMismatch { .. }) => {} // ignored
                         Err(RecvError::Lagged(skipped)) => {
                             tracing::warn!("Skipped {skipped} messages");
                         }
@@ -121,6 +207,57 @@ impl Node {
         });
     }
 
+    /// Act on a discrepancy between a DLC channel and the blockchain.
+    ///
+    /// A DLC channel which has been force-closed behind our back is moved to closing, so that it
+    /// gets tracked like any other force-closed DLC channel. A closing transaction which does not
+    /// get confirmed is broadcast again, in case it was dropped from the mempool.
+    pub fn process_chain_discrepancy(
+        &self,
+        channel_id: &DlcChannelId,
+        discrepancy: ChainDiscrepancy,
+    ) -> Result<()> {
+        CHAIN_DISCREPANCIES
+            .with_label_values(&[discrepancy.kind()])
+            .inc();
+
+        if let ChainDiscrepancy::ClosingTransactionUnconfirmed { closing_txid } = discrepancy {
+            return self.rebroadcast_transaction(closing_txid);
+        }
+
+        let force_close_txid = match discrepancy.force_close_txid() {
+            Some(txid) => txid,
+            None => return Ok(()),
+        };
+
+        let mut conn = self.pool.get()?;
+        db::dlc_channels::set_channel_force_closing(&mut conn, channel_id, force_close_txid)?;
+
+        tracing::warn!(
+            channel_id = hex::encode(channel_id),
+            %force_close_txid,
+            "Set DLC channel to closing after its funding output got spent"
+        );
+
+        Ok(())
+    }
+
+    /// Broadcast a transaction we broadcast before again.
+    fn rebroadcast_transaction(&self, txid: Txid) -> Result<()> {
+        let tx = self
+            .inner
+            .node_storage
+            .get_transaction(&txid.to_string())?
+            .with_context(|| format!("Transaction {txid} not found"))?;
+        let tx = bitcoin::consensus::deserialize::<bitcoin::Transaction>(&hex::decode(tx.raw())?)?;
+
+        self.inner.blockchain.broadcast_transaction_blocking(&tx)?;
+
+        tracing::warn!(%txid, "Rebroadcast unconfirmed closing transaction of DLC channel");
+
+        Ok(())
+    }
+
     pub fn process_dlc_channel_event(&self, dlc_channel_event: DlcChannelEvent) -> Result<()> {
         let mut conn = self.pool.get()?;
 
@@ -141,6 +278,16 @@ impl Node {
 
         let channel = &self.inner.get_dlc_channel_by_reference_id(protocol_id)?;
 
+        // The snapshot is only meant for support, hence failing to record it must not stop us
+        // from processing the event.
+        if let Err(e) = self.record_dlc_channel_snapshot(&mut conn, channel, protocol_id) {
+            tracing::error!(
+                channel_id = hex::encode(channel.get_id()),
+                ?dlc_channel_event,
+                "Failed to record DLC channel snapshot: {e:#}"
+            );
+        }
+
         match dlc_channel_event {
             DlcChannelEvent::Offered(_) => {
                 let open_protocol_id = ProtocolId::try_from(protocol_id)?;
@@ -288,6 +435,46 @@ impl Node {
         Ok(())
     }
 
+    /// Record how the DLC channel looks like after a DLC channel event, so that support can look
+    /// into its history.
+    fn record_dlc_channel_snapshot(
+        &self,
+        conn: &mut PgConnection,
+        channel: &Channel,
+        reference_id: ReferenceId,
+    ) -> Result<()> {
+        let (coordinator_collateral, trader_collateral, coordinator_reserve, trader_reserve) =
+            match channel {
+                Channel::Signed(signed_channel) => (
+                    Some(Amount::from_sat(signed_channel.own_params.collateral)),
+                    Some(Amount::from_sat(signed_channel.counter_params.collateral)),
+                    self.inner
+                        .get_dlc_channel_usable_balance(&signed_channel.channel_id)
+                        .ok(),
+                    self.inner
+                        .get_dlc_channel_usable_balance_counterparty(&signed_channel.channel_id)
+                        .ok(),
+                ),
+                _ => (None, None, None, None),
+            };
+
+        let snapshot = DlcChannelSnapshot {
+            state: channel_state_name(channel),
+            reference_id: ProtocolId::try_from(reference_id)
+                .ok()
+                .map(|protocol_id| protocol_id.to_string()),
+            coordinator_collateral,
+            trader_collateral,
+            coordinator_reserve,
+            trader_reserve,
+            created_at: OffsetDateTime::now_utc(),
+        };
+
+        db::dlc_channel_snapshots::insert(conn, &channel.get_id(), &snapshot)?;
+
+        Ok(())
+    }
+
     pub fn apply_funding_fee_to_channel(
         &self,
         dlc_channel_id: DlcChannelId,
@@ -408,6 +595,31 @@ impl Node {
             format!("Couldn't find closing position for trader. trader_id = {trader_id}")
         })?;
 
+        // The announcements are only part of the contract until it is closed. Afterwards, we have
+        // to fetch them from the oracles again.
+        let announcements: Vec<OracleAnnouncement> = match &contract {
+            Contract::PreClosed(PreClosedContract {
+                signed_contract, ..
+            }) => signed_contract
+                .accepted_contract
+                .offered_contract
+                .contract_info
+                .iter()
+                .flat_map(|contract_info| contract_info.oracle_announcements.clone())
+                .collect(),
+            Contract::Closed(_) => {
+                let event_id =
+                    OracleEventId::new(position.contract_symbol, position.expiry_timestamp)
+                        .to_string();
+                self.inner
+                    .oracles
+                    .iter()
+                    .filter_map(|oracle| oracle.get_announcement(&event_id).ok())
+                    .collect()
+            }
+            _ => vec![],
+        };
+
         let (closing_price, trader_realized_pnl_sat) = match contract {
             Contract::PreClosed(PreClosedContract {
                 // We assume a closed contract does always have an attestation
@@ -421,6 +633,14 @@ impl Node {
                 signed_cet: Some(signed_cet),
                 ..
             }) => {
+                keep_settlement_attestations(
+                    conn,
+                    position.id,
+                    contract_id,
+                    &attestations,
+                    &announcements,
+                );
+
                 let trader_realized_pnl_sat = self.calculate_trader_realized_pnl_from_cet(
                     conn,
                     &dlc_protocol.channel_id,
@@ -531,3 +751,69 @@ impl Node {
         Ok(trader_realized_pnl_sat)
     }
 }
+
+/// The name of the state the DLC channel is in, e.g. `Established` for a signed DLC channel with
+/// an open position.
+fn channel_state_name(channel: &Channel) -> String {
+    let name = match channel {
+        Channel::Signed(signed_channel) => return signed_channel_state_name(signed_channel),
+        Channel::Offered(_) => "Offered",
+        Channel::Accepted(_) => "Accepted",
+        Channel::FailedAccept(_) => "FailedAccept",
+        Channel::FailedSign(_) => "FailedSign",
+        Channel::Cancelled(_) => "Cancelled",
+        Channel::Closing(_) => "Closing",
+        Channel::SettledClosing(_) => "SettledClosing",
+        Channel::Closed(_) => "Closed",
+        Channel::CounterClosed(_) => "CounterClosed",
+        Channel::ClosedPunished(_) => "ClosedPunished",
+        Channel::CollaborativelyClosed(_) => "CollaborativelyClosed",
+    };
+
+    name.to_string()
+}
+
+/// Keep the attestations the position was settled with, together with the announcements they
+/// attest, so that the settlement can still be verified once the oracle has pruned them.
+///
+/// Failing to do so must not prevent the position from being closed, hence errors are only logged.
+fn keep_settlement_attestations(
+    conn: &mut PgConnection,
+    position_id: i32,
+    contract_id: &ContractId,
+    attestations: &[OracleAttestation],
+    announcements: &[OracleAnnouncement],
+) {
+    for attestation in attestations {
+        let oracle_pubkey = to_xonly_pk_30(attestation.oracle_public_key);
+
+        let announcement = match announcements
+            .iter()
+            .find(|announcement| announcement.oracle_public_key == attestation.oracle_public_key)
+        {
+            Some(announcement) => announcement,
+            None => {
+                tracing::warn!(
+                    position_id,
+                    %oracle_pubkey,
+                    "Cannot keep settlement attestation without announcement"
+                );
+                continue;
+            }
+        };
+
+        if let Err(e) = db::settlement_attestations::insert(
+            conn,
+            position_id,
+            contract_id,
+            announcement,
+            attestation,
+        ) {
+            tracing::error!(
+                position_id,
+                %oracle_pubkey,
+                "Failed to keep settlement attestation: {e:#}"
+            );
+        }
+    }
+}
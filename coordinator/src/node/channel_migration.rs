@@ -0,0 +1,410 @@
+use crate::db;
+use crate::db::channel_migrations::ChannelMigration;
+use crate::db::channel_migrations::ChannelMigrationState;
+use crate::db::channel_migrations::MigratedPosition;
+use crate::node::channel::DlcChannelState;
+use crate::node::Node;
+use crate::orderbook::db::orders;
+use crate::orderbook::trading::NewOrderMessage;
+use crate::orderbook::trading::OrderbookCommand;
+use crate::parse_dlc_channel_id;
+use crate::position::models::PositionState;
+use crate::ChannelOpeningParams;
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use diesel::PgConnection;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::ops::Add;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use xxi_node::bitcoin_conversion::to_secp_pk_29;
+use xxi_node::commons::NewMarketOrder;
+use xxi_node::commons::OrderReason;
+use xxi_node::commons::OrderState;
+
+/// The timeout before we give up on a channel migration, e.g. because the trader did not come
+/// online to execute one of its steps.
+pub const CHANNEL_MIGRATION_TIMEOUT: Duration = Duration::days(1);
+
+/// Start migrating the DLC channel of the given trader to the current protocol parameters.
+///
+/// The channel is collaboratively closed and immediately reopened, preserving the reserves of both
+/// parties. If the trader has an open position, it is closed before closing the channel and
+/// reopened with the same direction, quantity and leverage in the new channel, i.e. the position is
+/// moved through a back-to-back resize. Both trades are executed at the average entry price of the
+/// position and without a matching fee, so that moving the position does not realize a profit or
+/// loss.
+///
+/// Every step is persisted before it is executed, so that a failure leaves a record of how far the
+/// migration got.
+pub async fn start(
+    node: &Node,
+    trading_sender: &mpsc::Sender<OrderbookCommand>,
+    trader_pubkey: PublicKey,
+) -> Result<ChannelMigration> {
+    // Older apps can't deserialize the orders and matches of a channel migration.
+    if !node
+        .inner
+        .dlc_message_handler
+        .supports_channel_migration(&to_secp_pk_29(trader_pubkey))
+    {
+        bail!("Trader's app does not support channel migrations");
+    }
+
+    let mut conn = node.pool.get()?;
+
+    if db::channel_migrations::get_active(&mut conn)?
+        .iter()
+        .any(|migration| migration.trader_pubkey == trader_pubkey.to_string())
+    {
+        bail!("Channel migration already in progress");
+    }
+
+    if orders::get_by_trader_id_and_state(&mut conn, trader_pubkey, OrderState::Matched)?.is_some()
+    {
+        bail!("Trader has a pending match. Try again once it has been executed");
+    }
+
+    let signed_channel = node
+        .inner
+        .get_signed_channel_by_trader_id(trader_pubkey)
+        .context("No DLC channel to migrate")?;
+    let channel_id = signed_channel.channel_id;
+
+    let coordinator_reserve = node.inner.get_dlc_channel_usable_balance(&channel_id)?;
+    let trader_reserve = node
+        .inner
+        .get_dlc_channel_usable_balance_counterparty(&channel_id)?;
+
+    let position = db::positions::Position::get_position_by_trader(
+        &mut conn,
+        trader_pubkey,
+        vec![
+            PositionState::Open,
+            PositionState::Proposed,
+            PositionState::Closing { closing_price: 0.0 },
+            PositionState::Rollover,
+            PositionState::Resizing,
+        ],
+    )?;
+
+    let migration = match position {
+        Some(position) => {
+            if position.position_state != PositionState::Open {
+                bail!(
+                    "Can't migrate channel while position is in state {:?}",
+                    position.position_state
+                );
+            }
+
+            let migrated_position = MigratedPosition {
+                contract_symbol: position.contract_symbol,
+                trader_direction: position.trader_direction,
+                quantity: position.quantity,
+                trader_leverage: position.trader_leverage,
+                stable: position.stable,
+                average_entry_price: position.average_entry_price,
+            };
+
+            let order = NewMarketOrder {
+                id: Uuid::new_v4(),
+                contract_symbol: position.contract_symbol,
+                quantity: Decimal::try_from(position.quantity).expect("to fit into decimal"),
+                trader_id: trader_pubkey,
+                direction: position.trader_direction.opposite(),
+                leverage: Decimal::from_f32(position.trader_leverage).expect("to fit into decimal"),
+                expiry: OffsetDateTime::now_utc().add(CHANNEL_MIGRATION_TIMEOUT),
+                stable: position.stable,
+                contract_expiry: None,
+            };
+
+            let migration = db::channel_migrations::insert(
+                &mut conn,
+                trader_pubkey,
+                &channel_id,
+                ChannelMigrationState::ClosingPosition,
+                Some(migrated_position),
+                trader_reserve,
+                coordinator_reserve,
+                Some(order.id),
+            )?;
+
+            if let Err(e) = submit_order(
+                &mut conn,
+                trading_sender,
+                order,
+                None,
+                migrated_position.average_entry_price,
+            )
+            .await
+            {
+                db::channel_migrations::set_failed(&mut conn, migration.id, &format!("{e:#}"))?;
+                return Err(e);
+            }
+
+            migration
+        }
+        None => {
+            let migration = db::channel_migrations::insert(
+                &mut conn,
+                trader_pubkey,
+                &channel_id,
+                ChannelMigrationState::ClosingChannel,
+                None,
+                trader_reserve,
+                coordinator_reserve,
+                None,
+            )?;
+
+            if let Err(e) = node
+                .close_dlc_channel(channel_id)
+                .await
+                .context("Failed to close DLC channel")
+            {
+                db::channel_migrations::set_failed(&mut conn, migration.id, &format!("{e:#}"))?;
+                return Err(e);
+            }
+
+            migration
+        }
+    };
+
+    tracing::info!(
+        trader_pubkey = %trader_pubkey,
+        channel_id = %hex::encode(channel_id),
+        state = ?migration.migration_state,
+        "Started channel migration"
+    );
+
+    Ok(migration)
+}
+
+/// Move all active channel migrations forward to their next step, if possible.
+pub async fn advance(node: Node, trading_sender: mpsc::Sender<OrderbookCommand>) -> Result<()> {
+    let mut conn = node.pool.get()?;
+
+    let migrations = db::channel_migrations::get_active(&mut conn)
+        .context("Failed to load active channel migrations")?;
+
+    for migration in migrations {
+        let id = migration.id;
+        let trader_pubkey = migration.trader_pubkey.clone();
+
+        if migration.created_at.add(CHANNEL_MIGRATION_TIMEOUT) < OffsetDateTime::now_utc() {
+            tracing::warn!(id, trader_pubkey, "Channel migration timed out");
+            db::channel_migrations::set_failed(&mut conn, id, "Timed out")?;
+            continue;
+        }
+
+        if let Err(e) = advance_migration(&node, &trading_sender, &mut conn, &migration).await {
+            tracing::error!(
+                id,
+                trader_pubkey,
+                "Failed to advance channel migration: {e:#}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn advance_migration(
+    node: &Node,
+    trading_sender: &mpsc::Sender<OrderbookCommand>,
+    conn: &mut PgConnection,
+    migration: &ChannelMigration,
+) -> Result<()> {
+    let trader_pubkey = migration.trader_pubkey.parse::<PublicKey>()?;
+    let old_channel_id = parse_dlc_channel_id(&migration.old_channel_id)?;
+
+    match migration.migration_state {
+        ChannelMigrationState::ClosingPosition => {
+            let order_id = migration
+                .close_order_id
+                .context("Missing order to close position")?;
+            if let Some(reason) = order_failure(conn, order_id)? {
+                db::channel_migrations::set_failed(conn, migration.id, &reason)?;
+                return Ok(());
+            }
+
+            if has_position(conn, trader_pubkey)? {
+                tracing::debug!(%trader_pubkey, "Waiting for position to be closed");
+                return Ok(());
+            }
+
+            db::channel_migrations::set_closing_channel(conn, migration.id)?;
+
+            if let Err(e) = node
+                .close_dlc_channel(old_channel_id)
+                .await
+                .context("Failed to close DLC channel")
+            {
+                db::channel_migrations::set_failed(conn, migration.id, &format!("{e:#}"))?;
+                return Err(e);
+            }
+        }
+        ChannelMigrationState::ClosingChannel => {
+            let channel = db::dlc_channels::get_dlc_channel(conn, &old_channel_id)?
+                .context("Missing DLC channel")?;
+
+            match channel.channel_state {
+                DlcChannelState::Closed => {}
+                DlcChannelState::Failed | DlcChannelState::Cancelled => {
+                    db::channel_migrations::set_failed(
+                        conn,
+                        migration.id,
+                        "Failed to close DLC channel",
+                    )?;
+                    return Ok(());
+                }
+                _ => {
+                    tracing::debug!(%trader_pubkey, "Waiting for DLC channel to be closed");
+                    return Ok(());
+                }
+            }
+
+            match migration.position() {
+                Some(position) => {
+                    let order = NewMarketOrder {
+                        id: Uuid::new_v4(),
+                        contract_symbol: position.contract_symbol,
+                        quantity: Decimal::try_from(position.quantity)
+                            .expect("to fit into decimal"),
+                        trader_id: trader_pubkey,
+                        direction: position.trader_direction,
+                        leverage: Decimal::from_f32(position.trader_leverage)
+                            .expect("to fit into decimal"),
+                        expiry: OffsetDateTime::now_utc().add(CHANNEL_MIGRATION_TIMEOUT),
+                        stable: position.stable,
+                        contract_expiry: None,
+                    };
+                    let order_id = order.id;
+
+                    let channel_opening_params = ChannelOpeningParams {
+                        trader_reserve: migration.trader_reserve(),
+                        coordinator_reserve: migration.coordinator_reserve(),
+                        external_funding: None,
+                    };
+
+                    db::channel_migrations::set_reopening(conn, migration.id, order_id)?;
+
+                    if let Err(e) = submit_order(
+                        conn,
+                        trading_sender,
+                        order,
+                        Some(channel_opening_params),
+                        position.average_entry_price,
+                    )
+                    .await
+                    {
+                        db::channel_migrations::set_failed(conn, migration.id, &format!("{e:#}"))?;
+                        return Err(e);
+                    }
+                }
+                None => {
+                    // Without a position there is nothing to reopen. The next trade of the trader
+                    // opens a DLC channel with the new parameters.
+                    db::channel_migrations::set_completed(conn, migration.id, None)?;
+                }
+            }
+        }
+        ChannelMigrationState::Reopening => {
+            let order_id = migration
+                .reopen_order_id
+                .context("Missing order to reopen position")?;
+            if let Some(reason) = order_failure(conn, order_id)? {
+                db::channel_migrations::set_failed(conn, migration.id, &reason)?;
+                return Ok(());
+            }
+
+            let position = db::positions::Position::get_position_by_trader(
+                conn,
+                trader_pubkey,
+                vec![PositionState::Open],
+            )?;
+            let new_channel_id = match node.inner.get_signed_channel_by_trader_id(trader_pubkey) {
+                Ok(channel) if channel.channel_id != old_channel_id => channel.channel_id,
+                _ => {
+                    tracing::debug!(%trader_pubkey, "Waiting for DLC channel to be reopened");
+                    return Ok(());
+                }
+            };
+
+            if position.is_none() {
+                tracing::debug!(%trader_pubkey, "Waiting for position to be reopened");
+                return Ok(());
+            }
+
+            db::channel_migrations::set_completed(conn, migration.id, Some(&new_channel_id))?;
+
+            tracing::info!(
+                %trader_pubkey,
+                new_channel_id = %hex::encode(new_channel_id),
+                "Completed channel migration"
+            );
+        }
+        ChannelMigrationState::Completed | ChannelMigrationState::Failed => {}
+    }
+
+    Ok(())
+}
+
+/// Submit the order of a channel migration step, to be filled at `price` without a matching fee.
+async fn submit_order(
+    conn: &mut PgConnection,
+    trading_sender: &mpsc::Sender<OrderbookCommand>,
+    order: NewMarketOrder,
+    channel_opening_params: Option<ChannelOpeningParams>,
+    price: f32,
+) -> Result<()> {
+    let order = orders::insert_market_order(conn, order, OrderReason::ChannelMigration, None)
+        .map_err(|e| anyhow!(e))
+        .context("Failed to insert channel migration order into DB")?;
+
+    trading_sender
+        .send(OrderbookCommand::NewOrder(NewOrderMessage {
+            order,
+            channel_opening_params,
+            order_reason: OrderReason::ChannelMigration,
+            fixed_price: Some(Decimal::from_f32(price).expect("to fit into decimal")),
+        }))
+        .await
+        .context("Failed to submit channel migration order")?;
+
+    Ok(())
+}
+
+/// Why the order of a channel migration step did not go through, if it did not.
+fn order_failure(conn: &mut PgConnection, order_id: Uuid) -> Result<Option<String>> {
+    let order = orders::get_with_id(conn, order_id)?.context("Missing channel migration order")?;
+
+    let reason = match order.order_state {
+        OrderState::Failed => Some(format!("Order {order_id} failed")),
+        OrderState::Expired => Some(format!("Order {order_id} expired")),
+        OrderState::Deleted => Some(format!("Order {order_id} was deleted")),
+        OrderState::Open | OrderState::Matched | OrderState::Taken => None,
+    };
+
+    Ok(reason)
+}
+
+fn has_position(conn: &mut PgConnection, trader_pubkey: PublicKey) -> Result<bool> {
+    let position = db::positions::Position::get_position_by_trader(
+        conn,
+        trader_pubkey,
+        vec![
+            PositionState::Open,
+            PositionState::Closing { closing_price: 0.0 },
+            PositionState::Resizing,
+            PositionState::Rollover,
+        ],
+    )?;
+
+    Ok(position.is_some())
+}
@@ -3,6 +3,7 @@ use crate::node::Node;
 use crate::orderbook;
 use crate::orderbook::db::orders;
 use crate::orderbook::trading::NewOrderMessage;
+use crate::orderbook::trading::OrderbookCommand;
 use crate::position::models::Position;
 use crate::position::models::PositionState;
 use anyhow::anyhow;
@@ -25,7 +26,7 @@ use xxi_node::commons::OrderState;
 /// not be larger than our refund transaction time lock.
 pub const EXPIRED_POSITION_TIMEOUT: Duration = Duration::days(7);
 
-pub async fn close(node: Node, trading_sender: mpsc::Sender<NewOrderMessage>) -> Result<()> {
+pub async fn close(node: Node, trading_sender: mpsc::Sender<OrderbookCommand>) -> Result<()> {
     let mut conn = node.pool.get()?;
 
     let positions = db::positions::Position::get_all_open_positions(&mut conn)
@@ -87,19 +88,25 @@ pub async fn close(node: Node, trading_sender: mpsc::Sender<NewOrderMessage>) ->
             // close.
             expiry: OffsetDateTime::now_utc().add(EXPIRED_POSITION_TIMEOUT),
             stable: position.stable,
+            contract_expiry: None,
         };
 
-        let order = orders::insert_market_order(&mut conn, new_order.clone(), OrderReason::Expired)
-            .map_err(|e| anyhow!(e))
-            .context("Failed to insert expired order into DB")?;
+        let order =
+            orders::insert_market_order(&mut conn, new_order.clone(), OrderReason::Expired, None)
+                .map_err(|e| anyhow!(e))
+                .context("Failed to insert expired order into DB")?;
 
         let message = NewOrderMessage {
             order,
             channel_opening_params: None,
             order_reason: OrderReason::Expired,
+            fixed_price: None,
         };
 
-        if let Err(e) = trading_sender.send(message).await {
+        if let Err(e) = trading_sender
+            .send(OrderbookCommand::NewOrder(message))
+            .await
+        {
             tracing::error!(order_id=%new_order.id, trader_id=%new_order.trader_id, "Failed to submit new order for closing expired position. Error: {e:#}");
             continue;
         }
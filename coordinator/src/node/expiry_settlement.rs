@@ -0,0 +1,404 @@
+use crate::db;
+use crate::message::OrderbookMessage;
+use crate::node::settlement_dispute;
+use crate::node::Node;
+use crate::orderbook;
+use crate::payout_curve;
+use crate::position::models::Position;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use diesel::PgConnection;
+use dlc::RangePayout;
+use dlc_manager::contract::Contract;
+use dlc_manager::contract::ContractDescriptor;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use xxi_node::commons::ExpirySettlement;
+use xxi_node::commons::MatchState;
+use xxi_node::commons::Message;
+use xxi_node::commons::OracleEventId;
+use xxi_node::commons::OrderState;
+use xxi_node::node::ProtocolId;
+
+/// The time after the grace period by which we expect an expired position to be settled.
+/// Afterwards, the settlement is escalated to the admins.
+const EXPIRY_SETTLEMENT_DEADLINE: Duration = Duration::days(2);
+
+const INITIAL_RETRY_INTERVAL: Duration = Duration::minutes(5);
+const MAX_RETRY_INTERVAL: Duration = Duration::hours(2);
+
+/// Settle open positions which expired more than
+/// [`crate::node::NodeSettings::expiry_settlement_grace_period_days`] ago.
+///
+/// For every such position, we poll the oracle for the attestation of the expiry event, backing
+/// off exponentially between attempts. Once the attestation is available, the DLC channel is force
+/// closed, so that it gets settled on-chain using the attestation, unless the attested price is
+/// disputed (see [`settlement_dispute::may_settle`]). Every attempt is recorded in the
+/// `expiry_settlement_attempts` table, linked to the force-close DLC protocol once it has been
+/// started.
+pub async fn settle(node: Node) -> Result<()> {
+    let mut conn = node.pool.get()?;
+
+    let grace_period = Duration::days(i64::from(
+        node.settings
+            .read()
+            .await
+            .expiry_settlement_grace_period_days,
+    ));
+
+    let now = OffsetDateTime::now_utc();
+    let positions = db::positions::Position::get_all_open_positions_with_expiry_before(
+        &mut conn,
+        now - grace_period,
+    )
+    .context("Failed to fetch expired positions")?;
+
+    for position in positions {
+        if let Err(e) = settle_position(&node, &mut conn, &position, grace_period, now).await {
+            tracing::error!(
+                trader_pubkey = %position.trader,
+                position_id = position.id,
+                "Failed to settle expired position: {e:#}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn settle_position(
+    node: &Node,
+    conn: &mut PgConnection,
+    position: &Position,
+    grace_period: Duration,
+    now: OffsetDateTime,
+) -> Result<()> {
+    let attempt = match db::expiry_settlement_attempts::get_latest(conn, position.id)? {
+        Some(latest) if now < latest.created_at + retry_interval(latest.attempt) => return Ok(()),
+        Some(latest) => latest.attempt + 1,
+        None => 1,
+    };
+
+    let oracle_event_id = OracleEventId::new(position.contract_symbol, position.expiry_timestamp);
+    let event_id = oracle_event_id.to_string();
+
+    let attested_prices = spawn_blocking({
+        let node = node.clone();
+        let event_id = event_id.clone();
+        move || node.inner.get_attested_prices(&event_id)
+    })
+    .await
+    .expect("task to complete");
+
+    let attested_price = attested_prices
+        .iter()
+        .find(|(public_key, _)| *public_key == node.inner.oracle_pubkey)
+        .map(|(_, price)| *price);
+
+    let is_disputed = match attested_price {
+        Some(_) => {
+            let channel = node
+                .inner
+                .get_signed_channel_by_trader_id(position.trader)?;
+
+            !settlement_dispute::may_settle(
+                node,
+                conn,
+                &channel.channel_id,
+                position.trader,
+                &oracle_event_id,
+                &attested_prices,
+            )
+            .await?
+        }
+        None => false,
+    };
+
+    let (protocol_id, error, settlement) = match attested_price {
+        Some(_) if is_disputed => (None, Some("Attested price is disputed".to_string()), None),
+        Some(attested_price) => {
+            let expiry_smoothing = node.settings.read().await.expiry_smoothing;
+
+            // The settlement must be broken down before force closing, while the contract is
+            // still confirmed.
+            let settlement = match expiry_smoothing {
+                true => break_down_settlement(node, position, attested_price)
+                    .inspect_err(|e| {
+                        tracing::warn!(
+                            trader_pubkey = %position.trader,
+                            position_id = position.id,
+                            "Failed to break down expiry settlement: {e:#}"
+                        )
+                    })
+                    .ok(),
+                false => None,
+            };
+
+            match force_close(node, conn, position, attested_price).await {
+                Ok(protocol_id) => (Some(protocol_id), None, settlement),
+                Err(e) => (None, Some(format!("{e:#}")), None),
+            }
+        }
+        None => (None, None, None),
+    };
+
+    let escalated = protocol_id.is_none()
+        && now >= position.expiry_timestamp + grace_period + EXPIRY_SETTLEMENT_DEADLINE
+        && !db::expiry_settlement_attempts::is_escalated(conn, position.id)?;
+
+    db::expiry_settlement_attempts::insert(
+        conn,
+        position.id,
+        position.trader,
+        &event_id,
+        attempt,
+        attested_price,
+        protocol_id,
+        error.clone(),
+        escalated,
+        settlement.as_ref(),
+    )
+    .context("Failed to record expiry settlement attempt")?;
+
+    if escalated {
+        tracing::error!(
+            trader_pubkey = %position.trader,
+            position_id = position.id,
+            event_id,
+            attempt,
+            ?attested_price,
+            ?error,
+            "Expired position has not been settled before the deadline. Manual intervention required"
+        );
+    } else if let Some(protocol_id) = protocol_id {
+        tracing::info!(
+            trader_pubkey = %position.trader,
+            position_id = position.id,
+            event_id,
+            attempt,
+            ?attested_price,
+            %protocol_id,
+            ?settlement,
+            "Settling expired position at attestation"
+        );
+
+        if let Some(settlement) = settlement {
+            report_settlement(node, position, settlement).await;
+        }
+    } else {
+        tracing::debug!(
+            trader_pubkey = %position.trader,
+            position_id = position.id,
+            event_id,
+            attempt,
+            ?error,
+            retry_interval = %retry_interval(attempt),
+            "Could not settle expired position yet"
+        );
+    }
+
+    Ok(())
+}
+
+/// Force close the DLC channel of an expired position, so that it gets settled on-chain using the
+/// oracle attestation.
+async fn force_close(
+    node: &Node,
+    conn: &mut PgConnection,
+    position: &Position,
+    attested_price: Decimal,
+) -> Result<ProtocolId> {
+    let channel = node
+        .inner
+        .get_signed_channel_by_trader_id(position.trader)?;
+
+    // The order closing the expired position must not be executed once the trader comes back
+    // online.
+    if let Some(order) = orderbook::db::orders::get_by_trader_id_and_state(
+        conn,
+        position.trader,
+        OrderState::Matched,
+    )? {
+        orderbook::db::orders::set_order_state(conn, order.id, OrderState::Expired)?;
+        orderbook::db::matches::set_match_state_by_order_id(conn, order.id, MatchState::Failed)?;
+    }
+
+    let protocol_id = node.force_close_dlc_channel(channel.channel_id).await?;
+
+    db::positions::Position::set_open_position_to_closing(
+        conn,
+        &position.trader,
+        Some(attested_price),
+    )?;
+
+    Ok(protocol_id)
+}
+
+/// Break down the settlement of the position by the CET of the attested price, comparing its
+/// payout to the payout at exactly the attested price.
+fn break_down_settlement(
+    node: &Node,
+    position: &Position,
+    attested_price: Decimal,
+) -> Result<ExpirySettlement> {
+    let channel = node
+        .inner
+        .get_signed_channel_by_trader_id(position.trader)?;
+
+    let contract = match node
+        .inner
+        .get_contract_by_dlc_channel_id(&channel.channel_id)?
+    {
+        Contract::Confirmed(contract) => contract.accepted_contract,
+        _ => bail!("Cannot break down the settlement of a contract that is not confirmed"),
+    };
+
+    let total_collateral = contract.offered_contract.total_collateral;
+    let contract_info = contract
+        .offered_contract
+        .contract_info
+        .first()
+        .context("contract info to exist on a confirmed contract")?;
+    let range_payouts = match &contract_info.contract_descriptor {
+        ContractDescriptor::Numerical(descriptor) => {
+            payout_curve::get_range_payouts(descriptor, total_collateral)
+                .context("Could not compute the CETs")?
+        }
+        ContractDescriptor::Enum(_) => {
+            bail!("Cannot break down the settlement of an enum contract")
+        }
+    };
+
+    let outcome = attested_price
+        .to_u64()
+        .context("Attested price is not a valid outcome")?;
+    let cet = cet_for_outcome(&range_payouts, outcome)
+        .with_context(|| format!("No CET for attested price {attested_price}"))?;
+
+    // The trader's payout at exactly the attested price is their collateral plus their PnL, i.e.
+    // what the coordinator loses of their margin.
+    let coordinator_settlement =
+        position.calculate_coordinator_settlement_amount(attested_price, Amount::ZERO)?;
+    let exact_trader_payout = (contract.accept_params.collateral
+        + position.coordinator_margin.to_sat())
+    .saturating_sub(coordinator_settlement);
+
+    Ok(ExpirySettlement {
+        contract_symbol: position.contract_symbol,
+        attested_price,
+        interval_start: cet.start as u64,
+        interval_end: (cet.start + cet.count - 1) as u64,
+        trader_payout: Amount::from_sat(cet.payout.accept),
+        exact_trader_payout: Amount::from_sat(exact_trader_payout),
+    })
+}
+
+/// The CET paying out for the given outcome.
+fn cet_for_outcome(range_payouts: &[RangePayout], outcome: u64) -> Option<&RangePayout> {
+    let outcome = outcome as usize;
+
+    range_payouts
+        .iter()
+        .find(|range| range.start <= outcome && outcome < range.start + range.count)
+}
+
+/// Tell the trader which CET settles their position, and how its payout compares to the payout
+/// at exactly the attested price.
+async fn report_settlement(node: &Node, position: &Position, settlement: ExpirySettlement) {
+    if settlement.deviation() != SignedAmount::ZERO {
+        tracing::info!(
+            trader_pubkey = %position.trader,
+            position_id = position.id,
+            attested_price = %settlement.attested_price,
+            interval_start = settlement.interval_start,
+            interval_end = settlement.interval_end,
+            deviation = %settlement.deviation(),
+            on_interval_boundary = settlement.is_on_interval_boundary(),
+            "Payout of expiry settlement deviates from the payout at the attested price"
+        );
+    }
+
+    let message = OrderbookMessage::TraderMessage {
+        trader_id: position.trader,
+        message: Message::ExpirySettlement(settlement),
+        notification: None,
+    };
+
+    if let Err(e) = node.trade_notifier.send(message).await {
+        tracing::error!(
+            trader_pubkey = %position.trader,
+            "Failed to report expiry settlement to trader: {e:#}"
+        );
+    }
+}
+
+/// The time to wait after the given attempt, doubling with every attempt up to
+/// [`MAX_RETRY_INTERVAL`].
+fn retry_interval(attempt: i32) -> Duration {
+    let exponent = (attempt.max(1) - 1).min(16) as u32;
+
+    (INITIAL_RETRY_INTERVAL * 2_i32.pow(exponent)).min(MAX_RETRY_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dlc::Payout;
+
+    fn range(start: usize, count: usize, accept: u64) -> RangePayout {
+        RangePayout {
+            start,
+            count,
+            payout: Payout {
+                offer: 100_000 - accept,
+                accept,
+            },
+        }
+    }
+
+    #[test]
+    fn cet_for_outcome_includes_interval_boundaries() {
+        let range_payouts = vec![
+            range(0, 50_000, 0),
+            range(50_000, 10, 40_000),
+            range(50_010, 10, 41_000),
+        ];
+
+        assert_eq!(
+            cet_for_outcome(&range_payouts, 49_999),
+            Some(&range_payouts[0])
+        );
+        assert_eq!(
+            cet_for_outcome(&range_payouts, 50_000),
+            Some(&range_payouts[1])
+        );
+        assert_eq!(
+            cet_for_outcome(&range_payouts, 50_009),
+            Some(&range_payouts[1])
+        );
+        assert_eq!(
+            cet_for_outcome(&range_payouts, 50_010),
+            Some(&range_payouts[2])
+        );
+        assert_eq!(cet_for_outcome(&range_payouts, 50_020), None);
+    }
+
+    #[test]
+    fn retry_interval_doubles_with_every_attempt() {
+        assert_eq!(retry_interval(1), Duration::minutes(5));
+        assert_eq!(retry_interval(2), Duration::minutes(10));
+        assert_eq!(retry_interval(3), Duration::minutes(20));
+    }
+
+    #[test]
+    fn retry_interval_is_capped() {
+        assert_eq!(retry_interval(6), Duration::hours(2));
+        assert_eq!(retry_interval(i32::MAX), Duration::hours(2));
+    }
+}
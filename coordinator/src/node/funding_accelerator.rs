@@ -0,0 +1,308 @@
+//! Acceleration of stuck DLC channel funding transactions.
+//!
+//! A funding transaction is either submitted to the configured accelerator provider, or the
+//! coordinator spends its change output of the funding transaction in a child transaction paying
+//! for both (CPFP). Every acceleration is recorded together with what it cost the coordinator.
+
+use crate::db;
+use crate::db::funding_accelerations::AccelerationMethod;
+use crate::db::funding_accelerations::AccelerationTrigger;
+use crate::db::funding_accelerations::FundingAcceleration;
+use crate::node::channel::DlcChannelState;
+use crate::node::Node;
+use crate::position::models::PositionState;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::Amount;
+use bitcoin::Txid;
+use dlc_manager::DlcChannelId;
+use lightning::chain::chaininterface::ConfirmationTarget;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio::task::spawn_blocking;
+use xxi_node::ConfirmationStatus;
+use xxi_node::FeeConfig;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundingAcceleratorSettings {
+    /// Whether the funding transactions of DLC channels with an open position are accelerated
+    /// automatically.
+    pub auto_accelerate: bool,
+    /// The number of blocks after which an unconfirmed funding transaction is accelerated
+    /// automatically.
+    pub unconfirmed_blocks: u32,
+    /// The most the coordinator pays for a single acceleration.
+    pub max_cost_sats: u64,
+    /// The endpoint of the accelerator provider. Without one, funding transactions are only
+    /// accelerated with CPFP.
+    pub provider_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProviderRequest {
+    txid: Txid,
+    max_cost_sats: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderResponse {
+    /// What the provider charged for the acceleration.
+    cost_sats: u64,
+}
+
+/// Accelerate the unconfirmed funding transaction of the DLC channel.
+///
+/// The accelerator provider is tried first, if one is configured. CPFP is used as a fallback.
+pub async fn accelerate(
+    node: &Node,
+    channel_id: DlcChannelId,
+    trigger: AccelerationTrigger,
+) -> Result<FundingAcceleration> {
+    let settings = node.settings.read().await.funding_accelerator.clone();
+
+    let channel = spawn_blocking({
+        let pool = node.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            let channel = db::dlc_channels::get_dlc_channel(&mut conn, &channel_id)?;
+            anyhow::Ok(channel)
+        }
+    })
+    .await
+    .expect("task to complete")?
+    .context("Unknown DLC channel")?;
+
+    ensure!(
+        matches!(channel.channel_state, DlcChannelState::Open),
+        "DLC channel is not open"
+    );
+    let funding_txid = channel
+        .funding_txid
+        .context("DLC channel has no funding transaction")?;
+
+    ensure!(
+        matches!(
+            node.inner.get_confirmation_status(&funding_txid),
+            ConfirmationStatus::Mempool { .. }
+        ),
+        "Funding transaction {funding_txid} is not waiting for confirmation"
+    );
+
+    let max_cost = Amount::from_sat(settings.max_cost_sats);
+
+    let provider_acceleration = match &settings.provider_url {
+        Some(url) => match accelerate_with_provider(url, funding_txid, max_cost).await {
+            Ok(cost) => Some(cost),
+            Err(e) => {
+                tracing::warn!(
+                    %funding_txid,
+                    "Failed to accelerate funding transaction with provider. Falling back to \
+                     CPFP: {e:#}"
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    let (method, child_txid, cost) = match provider_acceleration {
+        Some(cost) => (AccelerationMethod::Provider, None, cost),
+        None => {
+            let (child_txid, fee) = node
+                .inner
+                .accelerate_with_cpfp(
+                    funding_txid,
+                    FeeConfig::Priority(ConfirmationTarget::HighPriority),
+                    max_cost,
+                )
+                .await?;
+
+            (AccelerationMethod::Cpfp, Some(child_txid), fee)
+        }
+    };
+
+    tracing::info!(
+        channel_id = hex::encode(channel_id),
+        %funding_txid,
+        ?method,
+        ?trigger,
+        ?child_txid,
+        %cost,
+        "Accelerated funding transaction"
+    );
+
+    let acceleration = spawn_blocking({
+        let pool = node.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            let acceleration = db::funding_accelerations::insert(
+                &mut conn,
+                &channel_id,
+                funding_txid,
+                method,
+                trigger,
+                child_txid,
+                cost,
+            )?;
+            anyhow::Ok(acceleration)
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    Ok(acceleration)
+}
+
+async fn accelerate_with_provider(url: &str, txid: Txid, max_cost: Amount) -> Result<Amount> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&ProviderRequest {
+            txid,
+            max_cost_sats: max_cost.to_sat(),
+        })
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<ProviderResponse>()
+        .await?;
+
+    let cost = Amount::from_sat(response.cost_sats);
+    if cost > max_cost {
+        bail!("Provider charged {cost}, more than the maximum of {max_cost}");
+    }
+
+    Ok(cost)
+}
+
+/// The funding transactions which are waiting for confirmation, by the block height at which they
+/// were first seen unconfirmed.
+///
+/// This is only kept in memory, so the count starts over when the coordinator is restarted.
+#[derive(Debug, Default)]
+pub struct UnconfirmedFundingTxs {
+    first_seen: HashMap<Txid, u64>,
+}
+
+impl UnconfirmedFundingTxs {
+    /// Track the currently `unconfirmed` funding transactions at the given `height`.
+    ///
+    /// Returns those which have been unconfirmed for at least `blocks` blocks.
+    fn update(&mut self, unconfirmed: &[Txid], height: u64, blocks: u32) -> Vec<Txid> {
+        self.first_seen.retain(|txid, _| unconfirmed.contains(txid));
+
+        unconfirmed
+            .iter()
+            .filter(|txid| {
+                let first_seen = *self.first_seen.entry(**txid).or_insert(height);
+                height.saturating_sub(first_seen) >= blocks as u64
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// Accelerate the funding transactions which have been unconfirmed for longer than
+/// [`FundingAcceleratorSettings::unconfirmed_blocks`], if the trader has an open position.
+///
+/// Every funding transaction is only accelerated automatically once.
+pub async fn accelerate_stuck_funding_txs(
+    node: Node,
+    unconfirmed_txs: &mut UnconfirmedFundingTxs,
+) -> Result<()> {
+    let settings = node.settings.read().await.funding_accelerator.clone();
+    if !settings.auto_accelerate {
+        return Ok(());
+    }
+
+    let height = node.inner.get_blockchain_height()?;
+
+    let channels = spawn_blocking({
+        let pool = node.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            let channels = db::dlc_channels::get_open_dlc_channels(&mut conn)?;
+            anyhow::Ok(channels)
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    let unconfirmed = channels
+        .iter()
+        .filter_map(|channel| {
+            let funding_txid = channel.funding_txid?;
+            matches!(
+                node.inner.get_confirmation_status(&funding_txid),
+                ConfirmationStatus::Mempool { .. }
+            )
+            .then_some(funding_txid)
+        })
+        .collect::<Vec<_>>();
+
+    let stuck = unconfirmed_txs.update(&unconfirmed, height, settings.unconfirmed_blocks);
+
+    for channel in channels
+        .into_iter()
+        .filter(|channel| matches!(channel.funding_txid, Some(txid) if stuck.contains(&txid)))
+    {
+        let channel_id = channel.channel_id;
+        let trader = channel.trader;
+        let funding_txid = channel.funding_txid.expect("stuck funding transaction");
+
+        let is_due = spawn_blocking({
+            let pool = node.pool.clone();
+            move || {
+                let mut conn = pool.get()?;
+
+                let position = db::positions::Position::get_position_by_trader(
+                    &mut conn,
+                    trader,
+                    vec![PositionState::Open],
+                )?;
+                let is_accelerated =
+                    db::funding_accelerations::is_accelerated(&mut conn, funding_txid)?;
+
+                anyhow::Ok(position.is_some() && !is_accelerated)
+            }
+        })
+        .await
+        .expect("task to complete")?;
+
+        if !is_due {
+            continue;
+        }
+
+        if let Err(e) = accelerate(&node, channel_id, AccelerationTrigger::Automatic).await {
+            tracing::error!(
+                channel_id = hex::encode(channel_id),
+                %funding_txid,
+                "Failed to accelerate stuck funding transaction: {e:#}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+
+    #[test]
+    fn funding_tx_is_stuck_after_unconfirmed_blocks() {
+        let txid = Txid::all_zeros();
+        let mut unconfirmed_txs = UnconfirmedFundingTxs::default();
+
+        assert!(unconfirmed_txs.update(&[txid], 100, 3).is_empty());
+        assert!(unconfirmed_txs.update(&[txid], 102, 3).is_empty());
+        assert_eq!(unconfirmed_txs.update(&[txid], 103, 3), vec![txid]);
+
+        // Once confirmed, the transaction is forgotten.
+        assert!(unconfirmed_txs.update(&[], 104, 3).is_empty());
+        assert!(unconfirmed_txs.update(&[txid], 105, 3).is_empty());
+    }
+}
@@ -1,17 +1,43 @@
 use crate::db;
+use crate::db::hodl_invoice::ExpiredReservation;
+use crate::db::hodl_invoice::PendingHodlInvoice;
 use crate::message::OrderbookMessage;
+use crate::node::Node;
 use crate::notifications::NotificationKind;
+use crate::orderbook::db::matches;
+use crate::orderbook::db::orders;
+use anyhow::ensure;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
 use bitcoin::Amount;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 use diesel::PgConnection;
+use dlc_manager::channel::Channel;
+use dlc_messages::channel::Reject;
 use futures_util::TryStreamExt;
 use lnd_bridge::InvoiceState;
 use lnd_bridge::LndBridge;
+use time::Duration;
+use time::OffsetDateTime;
 use tokio::sync::mpsc;
 use tokio::task::spawn_blocking;
+use uuid::Uuid;
+use xxi_node::bitcoin_conversion::to_secp_pk_29;
 use xxi_node::commons;
+use xxi_node::commons::MatchState;
 use xxi_node::commons::Message;
+use xxi_node::commons::OrderState;
+use xxi_node::message_handler::RejectReason;
+use xxi_node::message_handler::TenTenOneMessage;
+use xxi_node::message_handler::TenTenOneReject;
+
+/// How long the funds of an accepted hodl invoice are reserved for the order they fund.
+///
+/// The coordinator must settle the invoice within this time. Otherwise the DLC offer backed by the
+/// invoice is cancelled, so that the coordinator does not lock up funds for a payment which it can
+/// not claim anymore.
+pub const EXTERNAL_FUNDING_RESERVATION_TTL: Duration = Duration::minutes(10);
 
 /// Watches a hodl invoice with the given r_hash
 pub fn spawn_invoice_watch(
@@ -106,3 +132,281 @@ pub fn spawn_invoice_watch(
         tracing::info!(%trader_pubkey, r_hash, "Stopping hodl invoice watch.");
     });
 }
+
+/// Ensure that lnd holds an accepted payment of at least `amount` for the hodl invoice with the
+/// given `r_hash`.
+///
+/// The trader could have sent us the pre-image of an invoice which was never paid, or which has
+/// been cancelled in the meantime. We must not propose a DLC offer funded by such an invoice.
+pub async fn ensure_invoice_accepted(
+    lnd_bridge: &LndBridge,
+    r_hash: &str,
+    amount: Amount,
+) -> Result<()> {
+    let invoice = lnd_bridge.lookup_invoice(r_hash).await?;
+
+    ensure!(
+        invoice.state == InvoiceState::Accepted,
+        "Hodl invoice is not accepted: {:?}",
+        invoice.state
+    );
+
+    let amount_paid = Amount::from_sat(invoice.amt_paid_sat);
+    ensure!(
+        amount_paid >= amount,
+        "Hodl invoice is underpaid: {amount_paid} < {amount}"
+    );
+
+    Ok(())
+}
+
+/// Reconcile the pending hodl invoices with their state in lnd.
+///
+/// Invoices may have been paid, settled or cancelled while the coordinator was down, in which case
+/// nobody is watching them anymore. Invoices which are still open are watched again, so that the
+/// trader can continue with opening the channel once the payment arrives. Accepted invoices which
+/// are not backing an order cannot be resumed anymore and are cancelled, so that the payment is
+/// returned to the trader.
+pub async fn reconcile_pending_invoices(
+    node: Node,
+    trader_sender: mpsc::Sender<OrderbookMessage>,
+) -> Result<()> {
+    let invoices = spawn_blocking({
+        let pool = node.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            db::hodl_invoice::get_pending_hodl_invoices(&mut conn)
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    tracing::info!(
+        pending_invoices = invoices.len(),
+        "Reconciling pending hodl invoices"
+    );
+
+    for invoice in invoices {
+        if let Err(e) = reconcile_pending_invoice(&node, trader_sender.clone(), &invoice).await {
+            tracing::error!(
+                trader_pubkey = %invoice.trader_pubkey,
+                r_hash = invoice.r_hash,
+                "Failed to reconcile pending hodl invoice: {e:#}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn reconcile_pending_invoice(
+    node: &Node,
+    trader_sender: mpsc::Sender<OrderbookMessage>,
+    pending: &PendingHodlInvoice,
+) -> Result<()> {
+    let trader_pubkey = pending.trader_pubkey;
+    let r_hash = pending.r_hash.clone();
+
+    let invoice = node.lnd_bridge.lookup_invoice(&r_hash).await?;
+
+    match (pending.invoice_state, invoice.state) {
+        (_, InvoiceState::Settled) => {
+            tracing::info!(%trader_pubkey, r_hash, "Hodl invoice has been settled in the meantime");
+
+            spawn_blocking({
+                let pool = node.pool.clone();
+                move || {
+                    let mut conn = pool.get()?;
+                    db::hodl_invoice::update_hodl_invoice_to_settled(&mut conn, r_hash)?;
+                    anyhow::Ok(())
+                }
+            })
+            .await
+            .expect("task to complete")?;
+        }
+        (_, InvoiceState::Canceled) => {
+            tracing::info!(%trader_pubkey, r_hash, "Hodl invoice has been canceled in the meantime");
+
+            spawn_blocking({
+                let pool = node.pool.clone();
+                move || {
+                    let mut conn = pool.get()?;
+                    db::hodl_invoice::update_hodl_invoice_to_canceled(&mut conn, r_hash)?;
+                    anyhow::Ok(())
+                }
+            })
+            .await
+            .expect("task to complete")?;
+        }
+        (db::hodl_invoice::InvoiceState::Open, InvoiceState::Open | InvoiceState::Accepted) => {
+            tracing::info!(
+                %trader_pubkey,
+                r_hash,
+                state = ?invoice.state,
+                "Resuming watch of pending hodl invoice"
+            );
+
+            spawn_invoice_watch(
+                node.pool.clone(),
+                trader_sender,
+                node.lnd_bridge.clone(),
+                commons::HodlInvoiceParams {
+                    trader_pubkey,
+                    amt_sats: pending.amount_sats,
+                    r_hash,
+                },
+            );
+        }
+        (_, InvoiceState::Open | InvoiceState::Accepted) => {
+            tracing::warn!(
+                %trader_pubkey,
+                r_hash,
+                state = ?invoice.state,
+                "Accepted hodl invoice is not backing an order. Cancelling invoice"
+            );
+
+            node.lnd_bridge.cancel_invoice(r_hash.clone()).await?;
+
+            spawn_blocking({
+                let pool = node.pool.clone();
+                move || {
+                    let mut conn = pool.get()?;
+                    db::hodl_invoice::update_hodl_invoice_to_canceled(&mut conn, r_hash)?;
+                    anyhow::Ok(())
+                }
+            })
+            .await
+            .expect("task to complete")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Cancel the offers backed by accepted hodl invoices which have not been settled before their
+/// reservation expired.
+///
+/// This covers the cases in which the trade execution could not clean up after itself, e.g.
+/// because the coordinator was restarted in between, or because the invoice watch missed the
+/// settlement of the invoice.
+pub async fn reconcile_expired_reservations(node: Node) -> Result<()> {
+    let reservations = spawn_blocking({
+        let pool = node.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            db::hodl_invoice::get_expired_reservations(&mut conn, OffsetDateTime::now_utc())
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    for reservation in reservations {
+        if let Err(e) = reconcile_expired_reservation(&node, &reservation).await {
+            tracing::error!(
+                trader_pubkey = %reservation.trader_pubkey,
+                r_hash = reservation.r_hash,
+                "Failed to reconcile expired hodl invoice reservation: {e:#}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn reconcile_expired_reservation(
+    node: &Node,
+    reservation: &ExpiredReservation,
+) -> Result<()> {
+    let trader_pubkey = reservation.trader_pubkey;
+    let r_hash = reservation.r_hash.clone();
+
+    let invoice = node.lnd_bridge.lookup_invoice(&r_hash).await?;
+
+    if invoice.state == InvoiceState::Settled {
+        // The invoice watch must have missed the settlement.
+        tracing::info!(%trader_pubkey, r_hash, "Hodl invoice has been settled already");
+
+        spawn_blocking({
+            let pool = node.pool.clone();
+            move || {
+                let mut conn = pool.get()?;
+                db::hodl_invoice::update_hodl_invoice_to_settled(&mut conn, r_hash)?;
+                anyhow::Ok(())
+            }
+        })
+        .await
+        .expect("task to complete")?;
+
+        return Ok(());
+    }
+
+    tracing::warn!(
+        %trader_pubkey,
+        r_hash,
+        order_id = ?reservation.order_id,
+        state = ?invoice.state,
+        "Hodl invoice reservation expired before settlement. Cancelling offer"
+    );
+
+    if let Some(order_id) = reservation.order_id {
+        cancel_pending_offer(node, trader_pubkey, order_id).await?;
+    }
+
+    if invoice.state != InvoiceState::Canceled {
+        node.lnd_bridge.cancel_invoice(r_hash.clone()).await?;
+    }
+
+    spawn_blocking({
+        let pool = node.pool.clone();
+        let order_id = reservation.order_id;
+        move || {
+            let mut conn = pool.get()?;
+            db::hodl_invoice::update_hodl_invoice_to_canceled(&mut conn, r_hash)?;
+
+            if let Some(order_id) = order_id {
+                let order = orders::get_with_id(&mut conn, order_id)?;
+                if matches!(order, Some(order) if order.order_state == OrderState::Matched) {
+                    orders::set_order_state(&mut conn, order_id, OrderState::Failed)?;
+                    matches::set_match_state_by_order_id(&mut conn, order_id, MatchState::Failed)?;
+                }
+            }
+
+            anyhow::Ok(())
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    Ok(())
+}
+
+/// Reject the DLC channel offer to the trader, if it has not been accepted yet.
+async fn cancel_pending_offer(node: &Node, trader: PublicKey, order_id: Uuid) -> Result<()> {
+    let channel = node.inner.get_dlc_channel(|channel| {
+        channel.get_counter_party_id() == to_secp_pk_29(trader)
+            && matches!(channel, Channel::Offered(_))
+    })?;
+
+    if let Some(channel) = channel {
+        node.process_dlc_message(
+            trader,
+            &TenTenOneMessage::Reject(TenTenOneReject {
+                reject: Reject {
+                    channel_id: channel.get_id(),
+                    timestamp: OffsetDateTime::now_utc().unix_timestamp() as u64,
+                    reference_id: None,
+                },
+                order_id: Some(order_id),
+                reason: RejectReason::Cancelled,
+            }),
+        )?;
+
+        spawn_blocking({
+            let node = node.inner.clone();
+            move || node.delete_last_outbound_dlc_message(&trader)
+        })
+        .await??;
+    }
+
+    Ok(())
+}
@@ -5,6 +5,7 @@ use crate::node::Node;
 use crate::orderbook;
 use crate::orderbook::db::orders;
 use crate::orderbook::trading::NewOrderMessage;
+use crate::orderbook::trading::OrderbookCommand;
 use anyhow::Result;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
@@ -21,12 +22,13 @@ use xxi_node::commons::MatchState;
 use xxi_node::commons::NewMarketOrder;
 use xxi_node::commons::OrderReason;
 use xxi_node::commons::OrderState;
+use xxi_node::node::confirmation::ChannelOperation;
 
 /// The timeout before we give up on closing a liquidated position collaboratively. This value
 /// should not be larger than our refund transaction time lock.
 pub const LIQUIDATION_POSITION_TIMEOUT: Duration = Duration::days(7);
 
-pub async fn monitor(node: Node, trading_sender: mpsc::Sender<NewOrderMessage>) {
+pub async fn monitor(node: Node, trading_sender: mpsc::Sender<OrderbookCommand>) {
     if let Err(e) =
         check_if_positions_need_to_get_liquidated(trading_sender.clone(), node.clone()).await
     {
@@ -37,7 +39,7 @@ pub async fn monitor(node: Node, trading_sender: mpsc::Sender<NewOrderMessage>)
 /// For all open positions, check if the maintenance margin has been reached. Send a liquidation
 /// async match to the traders whose positions have been liquidated.
 async fn check_if_positions_need_to_get_liquidated(
-    trading_sender: mpsc::Sender<NewOrderMessage>,
+    trading_sender: mpsc::Sender<OrderbookCommand>,
     node: Node,
 ) -> Result<()> {
     let mut conn = node.pool.get()?;
@@ -121,7 +123,10 @@ async fn check_if_positions_need_to_get_liquidated(
             // liquidation.
             match node
                 .inner
-                .check_if_signed_channel_is_confirmed(position.trader)
+                .check_if_signed_channel_is_confirmed_for(
+                    position.trader,
+                    ChannelOperation::ClosePosition,
+                )
                 .await
             {
                 Ok(true) => {
@@ -149,6 +154,7 @@ async fn check_if_positions_need_to_get_liquidated(
                 // abandoned and we should force close.
                 expiry: OffsetDateTime::now_utc().add(LIQUIDATION_POSITION_TIMEOUT),
                 stable: position.stable,
+                contract_expiry: None,
             };
 
             let order_reason = match trader_liquidation {
@@ -160,6 +166,7 @@ async fn check_if_positions_need_to_get_liquidated(
                 &mut conn,
                 new_order.clone(),
                 order_reason.clone(),
+                None,
             ) {
                 Ok(order) => order,
                 Err(e) => {
@@ -172,9 +179,13 @@ async fn check_if_positions_need_to_get_liquidated(
                 order,
                 channel_opening_params: None,
                 order_reason,
+                fixed_price: None,
             };
 
-            if let Err(e) = trading_sender.send(message).await {
+            if let Err(e) = trading_sender
+                .send(OrderbookCommand::NewOrder(message))
+                .await
+            {
                 tracing::error!(order_id=%new_order.id, trader_id=%new_order.trader_id, "Failed to submit new order for closing liquidated position. Error: {e:#}");
                 continue;
             }
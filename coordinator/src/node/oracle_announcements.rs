@@ -0,0 +1,76 @@
+use crate::node::Node;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use prometheus::register_int_gauge;
+use prometheus::IntGauge;
+use serde::Deserialize;
+use serde::Serialize;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use xxi_node::commons::ContractSymbol;
+
+lazy_static! {
+    static ref MISSING_ANNOUNCEMENTS: IntGauge = register_int_gauge!(
+        "coordinator_missing_oracle_announcements",
+        "Number of oracle announcements missing for the expiry of upcoming rollovers."
+    )
+    .expect("valid metric");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnnouncementPrefetchSettings {
+    /// The number of upcoming expiries whose oracle announcements are fetched ahead of time.
+    pub expiries: usize,
+    /// How many hours ahead of the rollovers to an expiry a missing announcement for it is
+    /// reported.
+    pub alert_hours: u32,
+}
+
+/// Fetch the oracle announcements of the next [`AnnouncementPrefetchSettings::expiries`]
+/// expiries ahead of time, so that rollovers can be proposed from the cache of the oracle
+/// clients.
+///
+/// An announcement which is still missing shortly before rollovers to its expiry are proposed is
+/// logged as an error and counted in the `coordinator_missing_oracle_announcements` metric.
+pub async fn prefetch(node: Node) -> Result<()> {
+    let settings = node.settings.read().await.announcement_prefetch;
+    let schedule = node.expiry_schedule().await;
+    let now = OffsetDateTime::now_utc();
+
+    spawn_blocking(move || {
+        node.inner.prune_announcements(now);
+
+        // Rollovers proposed within the alert window extend positions up to this expiry.
+        let needed_until =
+            schedule.next_expiry(now + Duration::hours(i64::from(settings.alert_hours)));
+
+        let mut missing = 0;
+        for event_id in schedule.next_event_ids(ContractSymbol::BtcUsd, now, settings.expiries) {
+            let expiry = event_id.maturity();
+            let event_id = event_id.to_string();
+
+            let oracles = node.inner.prefetch_announcements(&event_id);
+            if oracles.is_empty() {
+                continue;
+            }
+
+            if expiry <= needed_until {
+                tracing::error!(
+                    event_id,
+                    ?oracles,
+                    "Oracle announcement for upcoming rollovers is missing"
+                );
+                missing += oracles.len();
+            } else {
+                tracing::debug!(event_id, ?oracles, "Oracle announcement not published yet");
+            }
+        }
+
+        MISSING_ANNOUNCEMENTS.set(missing as i64);
+    })
+    .await
+    .expect("task to complete");
+
+    Ok(())
+}
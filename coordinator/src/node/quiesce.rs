@@ -0,0 +1,417 @@
+//! Quiescing the coordinator ahead of breaking upgrades.
+//!
+//! An upgrade which changes the DLC protocol or how DLC channels are stored should only be
+//! rolled out once every DLC channel rests in `Established` or `Settled`. While the coordinator is
+//! quiesced, traders cannot start new DLC protocols, i.e. trades, scheduled rollovers and
+//! Lightning withdrawals are rejected. Instead, the coordinator drives its DLC channels towards a
+//! resting state:
+//!
+//! - Protocols waiting for the trader are completed by resending our last DLC message.
+//! - Offers of the coordinator which the trader has not answered within [`STALE_OFFER_TIMEOUT`]
+//!   are cancelled, by rolling the DLC channel back to its previous state.
+//! - Positions which expire before the end of the maintenance window are rolled over early, so
+//!   that they do not need a rollover during the upgrade.
+//!
+//! The readiness of every DLC channel is reported, so that the operator knows when it is safe to
+//! upgrade. The coordinator stays quiesced across restarts until it is released.
+
+use crate::db;
+use crate::dlc_protocol;
+use crate::node::Node;
+use crate::position::models::Position;
+use crate::position::models::PositionState;
+use anyhow::bail;
+use anyhow::Result;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::r2d2::PooledConnection;
+use diesel::PgConnection;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::channel::signed_channel::SignedChannelStateType;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::Arc;
+use time::Duration;
+use time::OffsetDateTime;
+use xxi_node::bitcoin_conversion::to_secp_pk_30;
+use xxi_node::node::event::NodeEvent;
+use xxi_node::node::ProtocolId;
+
+/// How long the trader has to answer an offer of the coordinator before it is cancelled.
+const STALE_OFFER_TIMEOUT: Duration = Duration::minutes(10);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuiesceStatus {
+    pub engaged: bool,
+    /// The end of the maintenance window. Positions expiring before are rolled over early.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+    pub reason: Option<String>,
+}
+
+/// The readiness of a DLC channel for an upgrade.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Readiness {
+    /// The DLC channel rests in `Established` or `Settled`, and its position does not expire
+    /// during the maintenance window.
+    Ready,
+    /// The DLC channel is in the middle of a DLC protocol.
+    InProtocol,
+    /// The position expires during the maintenance window and has to be rolled over first.
+    NeedsRollover,
+    /// The DLC channel is being closed on-chain, which does not involve the trader anymore.
+    Closing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelReadiness {
+    pub channel_id: String,
+    pub trader_pubkey: String,
+    pub state: String,
+    pub readiness: Readiness,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub position_expiry: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuiesceReport {
+    pub status: QuiesceStatus,
+    /// Whether every DLC channel is either [`Readiness::Ready`] or [`Readiness::Closing`].
+    pub ready: bool,
+    pub channels: Vec<ChannelReadiness>,
+}
+
+/// Blocks new DLC protocols while the coordinator is quiesced, see the [module docs](self).
+///
+/// Like the [`crate::kill_switch::KillSwitch`], the status is kept in memory and persisted so
+/// that it survives a restart.
+#[derive(Clone)]
+pub struct Quiesce {
+    pool: Pool<ConnectionManager<PgConnection>>,
+    status: Arc<RwLock<QuiesceStatus>>,
+}
+
+impl Quiesce {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Result<Self> {
+        let mut conn = pool.get()?;
+        let status = db::quiesce::get(&mut conn)?;
+
+        if status.engaged {
+            tracing::warn!(?status, "Coordinator is quiesced ahead of an upgrade");
+        }
+
+        Ok(Self {
+            pool,
+            status: Arc::new(RwLock::new(status)),
+        })
+    }
+
+    pub fn status(&self) -> QuiesceStatus {
+        self.status.read().clone()
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.status.read().engaged
+    }
+
+    pub fn set(&self, status: QuiesceStatus) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::quiesce::insert(&mut conn, &status)?;
+
+        tracing::warn!(?status, "Changed quiesce");
+
+        *self.status.write() = status;
+
+        Ok(())
+    }
+
+    pub fn ensure_protocols_allowed(&self) -> Result<()> {
+        let status = self.status.read();
+        if status.engaged {
+            match &status.reason {
+                Some(reason) => bail!("The coordinator is preparing for an upgrade: {reason}"),
+                None => bail!("The coordinator is preparing for an upgrade"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Drive every DLC channel towards a resting state, see the [module docs](self).
+///
+/// Failing to drive one DLC channel does not stop us from driving the others.
+pub async fn drive(node: Node) -> Result<()> {
+    let status = node.quiesce.status();
+    if !status.engaged {
+        return Ok(());
+    }
+
+    let mut conn = node.pool.get()?;
+
+    for channel in node.inner.list_signed_dlc_channels()? {
+        if let Err(e) = drive_channel(&node, &mut conn, &channel).await {
+            tracing::error!(
+                channel_id = hex::encode(channel.channel_id),
+                "Failed to drive DLC channel to a resting state: {e:#}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The readiness of every DLC channel for the upgrade.
+pub fn report(node: &Node) -> Result<QuiesceReport> {
+    let status = node.quiesce.status();
+
+    let mut conn = node.pool.get()?;
+
+    let mut channels = vec![];
+    for channel in node.inner.list_signed_dlc_channels()? {
+        let trader = to_secp_pk_30(channel.counter_party);
+        let position = db::positions::Position::get_position_by_trader(
+            &mut conn,
+            trader,
+            vec![PositionState::Open, PositionState::Rollover],
+        )?;
+        let position_expiry = position.map(|position| position.expiry_timestamp);
+
+        let state = channel.state.get_type();
+
+        channels.push(ChannelReadiness {
+            channel_id: hex::encode(channel.channel_id),
+            trader_pubkey: trader.to_string(),
+            state: format!("{state:?}"),
+            readiness: readiness(state, position_expiry, status.until),
+            position_expiry,
+        });
+    }
+
+    let ready = channels
+        .iter()
+        .all(|channel| matches!(channel.readiness, Readiness::Ready | Readiness::Closing));
+
+    Ok(QuiesceReport {
+        status,
+        ready,
+        channels,
+    })
+}
+
+async fn drive_channel(
+    node: &Node,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    channel: &SignedChannel,
+) -> Result<()> {
+    let trader = to_secp_pk_30(channel.counter_party);
+
+    match channel.state {
+        SignedChannelState::Established { .. } | SignedChannelState::Settled { .. } => {
+            let position = db::positions::Position::get_position_by_trader(
+                conn,
+                trader,
+                vec![PositionState::Open],
+            )?;
+
+            if let Some(position) = position {
+                node.roll_over_before_upgrade(conn, position).await?;
+            }
+        }
+        SignedChannelState::Closing { .. } | SignedChannelState::SettledClosing { .. } => {}
+        SignedChannelState::SettledOffered { .. }
+        | SignedChannelState::RenewOffered { is_offer: true, .. }
+            if is_stale_offer(conn, channel)? =>
+        {
+            cancel_offer(node, conn, channel)?;
+        }
+        _ => {
+            if node.is_connected(trader) {
+                tracing::info!(
+                    %trader,
+                    channel_id = hex::encode(channel.channel_id),
+                    "Resending last DLC message to complete protocol before upgrade"
+                );
+
+                node.inner
+                    .event_handler
+                    .publish(NodeEvent::SendLastDlcMessage { peer: trader });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Node {
+    /// Roll over the `position` if it expires during the maintenance window.
+    ///
+    /// Unlike scheduled rollovers, this is not blocked while the coordinator is quiesced.
+    pub(crate) async fn roll_over_before_upgrade(
+        &self,
+        conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+        position: Position,
+    ) -> Result<()> {
+        let trader = position.trader;
+
+        let Some(until) = self.quiesce.status().until else {
+            return Ok(());
+        };
+
+        if position.position_state != PositionState::Open || position.expiry_timestamp >= until {
+            return Ok(());
+        }
+
+        let next_expiry = self
+            .expiry_schedule()
+            .await
+            .next_expiry(OffsetDateTime::now_utc());
+        if next_expiry <= position.expiry_timestamp {
+            tracing::warn!(
+                %trader,
+                %until,
+                "Position expires during the maintenance window, but cannot be rolled over yet"
+            );
+            return Ok(());
+        }
+
+        if !self.is_connected(trader) {
+            tracing::debug!(%trader, "Skipping early rollover, user is not connected");
+            return Ok(());
+        }
+
+        let signed_channel = self.inner.get_signed_channel_by_trader_id(trader)?;
+
+        tracing::info!(%trader, %until, "Rolling over position early ahead of upgrade");
+
+        self.propose_rollover(conn, &signed_channel.channel_id, position)
+            .await
+    }
+}
+
+/// Whether the trader has not answered our offer within [`STALE_OFFER_TIMEOUT`].
+fn is_stale_offer(conn: &mut PgConnection, channel: &SignedChannel) -> Result<bool> {
+    let Some(reference_id) = channel.reference_id else {
+        return Ok(false);
+    };
+    let protocol_id = ProtocolId::try_from(reference_id)?;
+
+    let protocol = db::dlc_protocols::get_dlc_protocol(conn, protocol_id)?;
+
+    Ok(
+        protocol.protocol_state == dlc_protocol::DlcProtocolState::Pending
+            && protocol.timestamp + STALE_OFFER_TIMEOUT < OffsetDateTime::now_utc(),
+    )
+}
+
+/// Cancel our unanswered offer by rolling the DLC channel back to its previous state.
+///
+/// This is safe, as the trader has not signed anything yet which we would have to revoke.
+fn cancel_offer(node: &Node, conn: &mut PgConnection, channel: &SignedChannel) -> Result<()> {
+    let trader = to_secp_pk_30(channel.counter_party);
+    let channel_id = hex::encode(channel.channel_id);
+
+    tracing::warn!(%trader, %channel_id, "Cancelling unanswered offer before upgrade");
+
+    node.inner.roll_back_channel(channel)?;
+
+    if let Some(reference_id) = channel.reference_id {
+        dlc_protocol::DlcProtocolExecutor::new(node.pool.clone())
+            .fail_dlc_protocol(ProtocolId::try_from(reference_id)?)?;
+    }
+
+    // Like a rejected offer, see `Node::process_dlc_message`.
+    let (original, updated) = match node.inner.get_signed_channel_by_trader_id(trader)?.state {
+        SignedChannelState::Established { .. } => (
+            vec![
+                // the closing price doesn't matter here.
+                PositionState::Closing { closing_price: 0.0 },
+                PositionState::Rollover,
+                PositionState::Resizing,
+            ],
+            PositionState::Open,
+        ),
+        SignedChannelState::Settled { .. } => {
+            (vec![PositionState::Proposed], PositionState::Failed)
+        }
+        _ => return Ok(()),
+    };
+
+    if let Err(e) =
+        db::positions::Position::update_position_state(conn, trader.to_string(), original, updated)
+    {
+        tracing::debug!(%trader, "No position to revert after cancelling offer: {e:#}");
+    }
+
+    Ok(())
+}
+
+fn readiness(
+    state: SignedChannelStateType,
+    position_expiry: Option<OffsetDateTime>,
+    until: Option<OffsetDateTime>,
+) -> Readiness {
+    match state {
+        SignedChannelStateType::Established | SignedChannelStateType::Settled => {
+            match (position_expiry, until) {
+                (Some(expiry), Some(until)) if expiry < until => Readiness::NeedsRollover,
+                _ => Readiness::Ready,
+            }
+        }
+        SignedChannelStateType::Closing | SignedChannelStateType::SettledClosing => {
+            Readiness::Closing
+        }
+        _ => Readiness::InProtocol,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn resting_channels_are_ready_unless_position_expires_during_window() {
+        let until = Some(datetime!(2024-07-26 12:00 UTC));
+
+        assert_eq!(
+            readiness(SignedChannelStateType::Settled, None, until),
+            Readiness::Ready
+        );
+        assert_eq!(
+            readiness(
+                SignedChannelStateType::Established,
+                Some(datetime!(2024-07-28 15:00 UTC)),
+                until
+            ),
+            Readiness::Ready
+        );
+        assert_eq!(
+            readiness(
+                SignedChannelStateType::Established,
+                Some(datetime!(2024-07-26 11:00 UTC)),
+                until
+            ),
+            Readiness::NeedsRollover
+        );
+    }
+
+    #[test]
+    fn channels_in_protocol_are_not_ready() {
+        for state in [
+            SignedChannelStateType::RenewOffered,
+            SignedChannelStateType::RenewFinalized,
+            SignedChannelStateType::SettledReceived,
+            SignedChannelStateType::CollaborativeCloseOffered,
+        ] {
+            assert_eq!(readiness(state, None, None), Readiness::InProtocol);
+        }
+
+        assert_eq!(
+            readiness(SignedChannelStateType::SettledClosing, None, None),
+            Readiness::Closing
+        );
+    }
+}
@@ -6,17 +6,19 @@ use crate::dlc_protocol;
 use crate::dlc_protocol::RolloverParams;
 use crate::funding_fee::funding_fee_from_funding_fee_events;
 use crate::funding_fee::get_outstanding_funding_fee_events;
+use crate::lightning_withdrawal;
 use crate::node::Node;
 use crate::notifications::Notification;
 use crate::notifications::NotificationKind;
-use crate::payout_curve::build_contract_descriptor;
+use crate::payout_curve::build_contract_descriptor_with_funding_fee;
 use crate::position::models::Position;
 use crate::position::models::PositionState;
+use crate::reserve_interest;
+use crate::reserve_top_up;
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
-use bitcoin::Network;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 use diesel::r2d2::PooledConnection;
@@ -34,6 +36,7 @@ use tokio::sync::broadcast;
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
 use tokio::task::spawn_blocking;
+use xxi_node::bitcoin_conversion::to_xonly_pk_30;
 use xxi_node::commons;
 use xxi_node::node::event::NodeEvent;
 use xxi_node::node::ProtocolId;
@@ -42,7 +45,6 @@ pub fn monitor(
     pool: Pool<ConnectionManager<PgConnection>>,
     mut receiver: broadcast::Receiver<NodeEvent>,
     notifier: mpsc::Sender<Notification>,
-    network: Network,
     node: Node,
 ) -> RemoteHandle<()> {
     let (fut, remote_handle) = async move {
@@ -55,7 +57,7 @@ pub fn monitor(
                         let pool = pool.clone();
                         async move {
                             if let Err(e) = node
-                                .check_if_eligible_for_rollover(pool, notifier, peer, network)
+                                .check_if_eligible_for_rollover(pool, notifier, peer)
                                 .await
                             {
                                 tracing::error!(
@@ -90,7 +92,6 @@ impl Node {
         pool: Pool<ConnectionManager<PgConnection>>,
         notifier: mpsc::Sender<Notification>,
         trader_id: PublicKey,
-        network: Network,
     ) -> Result<()> {
         let mut conn = spawn_blocking(move || pool.get())
             .await
@@ -116,7 +117,11 @@ impl Node {
             None => return Ok(()),
         };
 
-        self.check_rollover(&mut conn, position, network, &notifier, None)
+        if self.quiesce.is_engaged() {
+            return self.roll_over_before_upgrade(&mut conn, position).await;
+        }
+
+        self.check_rollover(&mut conn, position, &notifier, None)
             .await
     }
 
@@ -124,20 +129,28 @@ impl Node {
         &self,
         connection: &mut PooledConnection<ConnectionManager<PgConnection>>,
         position: Position,
-        network: Network,
         notifier: &mpsc::Sender<Notification>,
         notification: Option<NotificationKind>,
     ) -> Result<()> {
         let trader_id = position.trader;
         let expiry_timestamp = position.expiry_timestamp;
 
+        // Positions expiring during the maintenance window are rolled over early instead, see
+        // [`crate::node::quiesce`].
+        if self.quiesce.is_engaged() {
+            tracing::debug!(%trader_id, "Skipping rollover while quiesced");
+            return Ok(());
+        }
+
         let signed_channel = self.inner.get_signed_channel_by_trader_id(trader_id)?;
 
-        if commons::is_eligible_for_rollover(OffsetDateTime::now_utc(), network)
+        let schedule = self.expiry_schedule().await;
+        let now = OffsetDateTime::now_utc();
+        if schedule.is_eligible_for_rollover(now)
             // not expired
-            && OffsetDateTime::now_utc() < expiry_timestamp
+            && now < expiry_timestamp
         {
-            let next_expiry = commons::calculate_next_expiry(OffsetDateTime::now_utc(), network);
+            let next_expiry = schedule.next_expiry(now);
             if expiry_timestamp >= next_expiry {
                 tracing::trace!(%trader_id, "Position has already been rolled over");
                 return Ok(());
@@ -156,13 +169,8 @@ impl Node {
 
             if self.is_connected(trader_id) {
                 tracing::info!(%trader_id, "Proposing to rollover DLC channel");
-                self.propose_rollover(
-                    connection,
-                    &signed_channel.channel_id,
-                    position,
-                    self.inner.network,
-                )
-                .await?;
+                self.propose_rollover(connection, &signed_channel.channel_id, position)
+                    .await?;
             } else {
                 tracing::warn!(%trader_id, "Skipping rollover, user is not connected.");
             }
@@ -177,11 +185,42 @@ impl Node {
         conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
         dlc_channel_id: &DlcChannelId,
         position: Position,
-        network: Network,
+    ) -> Result<()> {
+        let next_expiry = self
+            .expiry_schedule()
+            .await
+            .next_expiry(OffsetDateTime::now_utc());
+
+        self.propose_renew(conn, dlc_channel_id, position, next_expiry)
+            .await
+    }
+
+    /// Initiates the rollover protocol with the app, without extending the expiry of the
+    /// position.
+    ///
+    /// This is used to settle changes to the collateral reserves, e.g. Lightning withdrawals.
+    pub async fn propose_reserve_update(
+        &self,
+        conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+        dlc_channel_id: &DlcChannelId,
+        position: Position,
+    ) -> Result<()> {
+        let expiry = position.expiry_timestamp;
+
+        self.propose_renew(conn, dlc_channel_id, position, expiry)
+            .await
+    }
+
+    async fn propose_renew(
+        &self,
+        conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+        dlc_channel_id: &DlcChannelId,
+        position: Position,
+        next_expiry: OffsetDateTime,
     ) -> Result<()> {
         let trader_pubkey = position.trader;
 
-        let next_expiry = commons::calculate_next_expiry(OffsetDateTime::now_utc(), network);
+        self.kill_switch.ensure_trading_allowed()?;
 
         let (oracle_pk, contract_tx_fee_rate) = {
             let old_contract = self.inner.get_contract_by_dlc_channel_id(dlc_channel_id)?;
@@ -206,6 +245,20 @@ impl Node {
             )
         };
 
+        let next_event_id =
+            commons::OracleEventId::new(position.contract_symbol, next_expiry).to_string();
+
+        // Fail before touching the DLC channel if the oracle has not announced the event we are
+        // rolling over to. The announcement is usually cached already, see
+        // [`crate::node::oracle_announcements::prefetch`].
+        spawn_blocking({
+            let node = self.inner.clone();
+            let event_id = next_event_id.clone();
+            move || node.get_announcement(to_xonly_pk_30(oracle_pk), &event_id)
+        })
+        .await
+        .expect("task to complete")?;
+
         let maintenance_margin_rate = { self.settings.read().await.maintenance_margin_rate };
         let maintenance_margin_rate =
             Decimal::try_from(maintenance_margin_rate).expect("to fit into decimal");
@@ -215,43 +268,61 @@ impl Node {
 
         let funding_fee = funding_fee_from_funding_fee_events(&funding_fee_events);
 
-        let position = position.apply_funding_fee(funding_fee, maintenance_margin_rate);
-        let (collateral_reserve_coordinator, collateral_reserve_trader) =
-            self.apply_funding_fee_to_channel(*dlc_channel_id, funding_fee)?;
+        // The funding fee is settled in the payout function, hence the collateral of both parties
+        // stays the same. The paying party pays it with its margin, which moves its liquidation
+        // price, and the other party gets it on top of its collateral reserve.
+        let collateral_reserve_coordinator =
+            self.inner.get_dlc_channel_usable_balance(dlc_channel_id)?;
+        let collateral_reserve_trader = self
+            .inner
+            .get_dlc_channel_usable_balance_counterparty(dlc_channel_id)?;
 
-        let Position {
-            coordinator_margin: margin_coordinator,
-            trader_margin: margin_trader,
-            coordinator_leverage: leverage_coordinator,
-            trader_leverage: leverage_trader,
-            coordinator_liquidation_price: liquidation_price_coordinator,
-            trader_liquidation_price: liquidation_price_trader,
-            ..
-        } = position;
+        let (
+            collateral_reserve_coordinator,
+            collateral_reserve_trader,
+            reserve_interest_credit_ids,
+        ) = reserve_interest::apply_reserve_interest_credits(
+            conn,
+            trader_pubkey,
+            collateral_reserve_coordinator,
+            collateral_reserve_trader,
+        )?;
 
-        let contract_descriptor = build_contract_descriptor(
+        let (collateral_reserve_coordinator, collateral_reserve_trader, reserve_top_up_credits) =
+            reserve_top_up::apply_reserve_top_ups(
+                conn,
+                trader_pubkey,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+
+        let (collateral_reserve_coordinator, collateral_reserve_trader, lightning_withdrawals) =
+            lightning_withdrawal::apply_lightning_withdrawals(
+                conn,
+                trader_pubkey,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+
+        let contract_descriptor = build_contract_descriptor_with_funding_fee(
             Decimal::try_from(position.average_entry_price).expect("to fit"),
-            margin_coordinator,
-            margin_trader,
-            leverage_coordinator,
-            leverage_trader,
+            position.coordinator_margin,
+            position.trader_margin,
+            position.coordinator_leverage,
+            position.trader_leverage,
             position.trader_direction,
             collateral_reserve_coordinator,
             collateral_reserve_trader,
             position.quantity,
             position.contract_symbol,
+            funding_fee,
         )
         .context("Could not build contract descriptor")?;
 
-        let next_event_id = format!(
-            "{}{}",
-            position.contract_symbol,
-            next_expiry.unix_timestamp()
-        );
-
         let new_contract_input = ContractInput {
-            offer_collateral: (margin_coordinator + collateral_reserve_coordinator).to_sat(),
-            accept_collateral: (margin_trader + collateral_reserve_trader).to_sat(),
+            offer_collateral: (position.coordinator_margin + collateral_reserve_coordinator)
+                .to_sat(),
+            accept_collateral: (position.trader_margin + collateral_reserve_trader).to_sat(),
             fee_rate: contract_tx_fee_rate,
             contract_infos: vec![ContractInputInfo {
                 contract_descriptor,
@@ -263,6 +334,18 @@ impl Node {
             }],
         };
 
+        // The margins, leverages and liquidation prices of the position once the funding fee is
+        // paid.
+        let Position {
+            coordinator_margin: margin_coordinator,
+            trader_margin: margin_trader,
+            coordinator_leverage: leverage_coordinator,
+            trader_leverage: leverage_trader,
+            coordinator_liquidation_price: liquidation_price_coordinator,
+            trader_liquidation_price: liquidation_price_trader,
+            ..
+        } = position.apply_funding_fee(funding_fee, maintenance_margin_rate);
+
         let protocol_id = ProtocolId::new();
 
         tracing::debug!(
@@ -320,6 +403,19 @@ impl Node {
             )
             .context("Failed to insert start of rollover protocol in dlc_protocols table")?;
 
+        reserve_interest::set_reserve_interest_credits_protocol_id(
+            conn,
+            protocol_id,
+            &reserve_interest_credit_ids,
+        )
+        .context("Failed to link reserve interest credits to rollover protocol")?;
+
+        reserve_top_up::link_to_protocol(conn, protocol_id, &reserve_top_up_credits)
+            .context("Failed to link reserve top-ups to rollover protocol")?;
+
+        lightning_withdrawal::link_to_protocol(conn, protocol_id, &lightning_withdrawals)
+            .context("Failed to link Lightning withdrawals to rollover protocol")?;
+
         db::positions::Position::rollover_position(conn, trader_pubkey, &next_expiry)
             .context("Failed to set position state to rollover")?;
 
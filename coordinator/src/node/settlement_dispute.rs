@@ -0,0 +1,290 @@
+use crate::db;
+use crate::node::Node;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::secp256k1::XOnlyPublicKey;
+use diesel::PgConnection;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::channel::Channel;
+use dlc_manager::DlcChannelId;
+use lazy_static::lazy_static;
+use prometheus::register_int_counter_vec;
+use prometheus::register_int_gauge;
+use prometheus::IntCounterVec;
+use prometheus::IntGauge;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+use std::str::FromStr;
+use time::Duration;
+use tokio::task::spawn_blocking;
+use xxi_node::bitcoin_conversion::to_secp_pk_30;
+use xxi_node::commons::OracleEventId;
+use xxi_node::ContractDetails;
+
+lazy_static! {
+    static ref SETTLEMENT_DISPUTES: IntCounterVec = register_int_counter_vec!(
+        "coordinator_settlement_disputes_total",
+        "Number of DLC channel settlements disputed because the attested price diverges from the reference prices.",
+        &["contract_symbol"]
+    )
+    .expect("valid metric");
+    static ref UNRESOLVED_SETTLEMENT_DISPUTES: IntGauge = register_int_gauge!(
+        "coordinator_unresolved_settlement_disputes",
+        "Number of settlement disputes awaiting manual resolution."
+    )
+    .expect("valid metric");
+}
+
+/// The trades executed in this period before the maturity of an oracle event make up the internal
+/// mark price the attested price is compared against.
+const MARK_PRICE_WINDOW: Duration = Duration::hours(1);
+
+/// Check the oracle attestations of all DLC channels which are being settled on-chain, and
+/// withhold the attestations of disputed events from the DLC manager.
+///
+/// A DLC channel whose attested price diverges by more than the configured threshold from the
+/// reference prices is recorded as disputed. Until the dispute has been resolved manually, the DLC
+/// manager does not get the attestation of the oracle event, hence it does not settle DLC channels
+/// on that event. DLC channels on other events are settled as usual.
+///
+/// Note, this cannot keep the trader from settling the DLC channel with the attestation.
+pub async fn check_settlements(node: Node) -> Result<()> {
+    let max_divergence = max_divergence(&node).await?;
+
+    spawn_blocking(move || {
+        let mut conn = node.pool.get()?;
+
+        if let Some(max_divergence) = max_divergence {
+            for channel in node.inner.list_dlc_channels()? {
+                if !is_settling(&channel) {
+                    continue;
+                }
+
+                if let Err(e) = check_channel(&node, &mut conn, &channel, max_divergence) {
+                    tracing::error!(
+                        channel_id = hex::encode(channel.get_id()),
+                        "Failed to check settlement of DLC channel: {e:#}"
+                    );
+                }
+            }
+        }
+
+        // Disputes are resolved through the admin API, hence we refresh the withheld attestations
+        // even if the check is disabled.
+        let disputes = db::settlement_disputes::get_unresolved(&mut conn)?;
+        UNRESOLVED_SETTLEMENT_DISPUTES.set(disputes.len() as i64);
+
+        let event_ids = disputes
+            .into_iter()
+            .map(|dispute| dispute.event_id)
+            .collect::<HashSet<_>>();
+        if !event_ids.is_empty() {
+            tracing::warn!(
+                ?event_ids,
+                "Withholding attestations of oracle events with unresolved settlement disputes"
+            );
+        }
+        node.inner.withhold_attestations(event_ids);
+
+        anyhow::Ok(())
+    })
+    .await
+    .expect("task to complete")
+}
+
+/// Check the price attested for the settlement of a DLC channel, recording a settlement dispute
+/// if it diverges from the reference prices.
+///
+/// Returns `true` if the DLC channel may be settled with the attestation, i.e. if there is no
+/// unresolved dispute for it.
+pub async fn may_settle(
+    node: &Node,
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+    trader: PublicKey,
+    event_id: &OracleEventId,
+    attested_prices: &[(XOnlyPublicKey, Decimal)],
+) -> Result<bool> {
+    let max_divergence = match max_divergence(node).await? {
+        Some(max_divergence) => max_divergence,
+        None => return Ok(true),
+    };
+
+    check_settlement(
+        node,
+        conn,
+        channel_id,
+        trader,
+        event_id,
+        attested_prices,
+        max_divergence,
+    )?;
+
+    let is_disputed =
+        db::settlement_disputes::is_unresolved(conn, channel_id, &event_id.to_string())?;
+
+    Ok(!is_disputed)
+}
+
+async fn max_divergence(node: &Node) -> Result<Option<Decimal>> {
+    node.settings
+        .read()
+        .await
+        .max_settlement_price_divergence
+        .map(|max_divergence| {
+            Decimal::from_f32(max_divergence).context("Invalid max settlement price divergence")
+        })
+        .transpose()
+}
+
+fn check_channel(
+    node: &Node,
+    conn: &mut PgConnection,
+    channel: &Channel,
+    max_divergence: Decimal,
+) -> Result<()> {
+    let contract_id = channel
+        .get_contract_id()
+        .context("Missing contract id for settling DLC channel")?;
+    let contract = node
+        .inner
+        .get_contract_by_id(&contract_id)?
+        .context("Missing contract for settling DLC channel")?;
+    let event_id = ContractDetails::from(contract)
+        .event_id
+        .context("Missing oracle event for settling DLC channel")?;
+    let event_id = OracleEventId::from_str(&event_id)?;
+
+    let attested_prices = node.inner.get_attested_prices(&event_id.to_string());
+
+    check_settlement(
+        node,
+        conn,
+        &channel.get_id(),
+        to_secp_pk_30(channel.get_counter_party_id()),
+        &event_id,
+        &attested_prices,
+        max_divergence,
+    )
+}
+
+/// Compare the price attested by our oracle against the internal mark price at the maturity of
+/// the event and the price attested by the other oracles, recording a settlement dispute for the
+/// DLC channel if it diverges from either.
+fn check_settlement(
+    node: &Node,
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+    trader: PublicKey,
+    event_id: &OracleEventId,
+    attested_prices: &[(XOnlyPublicKey, Decimal)],
+    max_divergence: Decimal,
+) -> Result<()> {
+    let attested_price = match attested_prices
+        .iter()
+        .find(|(public_key, _)| *public_key == node.inner.oracle_pubkey)
+    {
+        Some((_, price)) => *price,
+        // Nothing to check before the oracle has attested to the event.
+        None => return Ok(()),
+    };
+
+    let second_oracle_price = attested_prices
+        .iter()
+        .find(|(public_key, _)| *public_key != node.inner.oracle_pubkey)
+        .map(|(_, price)| *price);
+
+    let maturity = event_id.maturity();
+    let mark_price = db::trades::get_mean_price_between(
+        conn,
+        event_id.contract_symbol(),
+        maturity - MARK_PRICE_WINDOW,
+        maturity,
+    )?;
+
+    if mark_price.is_none() && second_oracle_price.is_none() {
+        tracing::warn!(
+            %event_id,
+            %attested_price,
+            "Cannot cross-check attested price without a mark price or a second oracle"
+        );
+        return Ok(());
+    }
+
+    let is_disputed = [mark_price, second_oracle_price]
+        .into_iter()
+        .flatten()
+        .any(|reference| diverges(attested_price, reference, max_divergence));
+
+    if !is_disputed {
+        return Ok(());
+    }
+
+    if db::settlement_disputes::insert(
+        conn,
+        channel_id,
+        trader,
+        &event_id.to_string(),
+        attested_price,
+        mark_price,
+        second_oracle_price,
+    )? {
+        SETTLEMENT_DISPUTES
+            .with_label_values(&[&event_id.contract_symbol().to_string()])
+            .inc();
+
+        tracing::error!(
+            channel_id = hex::encode(channel_id),
+            %trader,
+            %event_id,
+            %attested_price,
+            ?mark_price,
+            ?second_oracle_price,
+            "Attested price diverges from reference prices. Pausing settlement of DLC channel until the dispute is resolved"
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether the DLC channel is going to be settled on-chain using the oracle attestation.
+fn is_settling(channel: &Channel) -> bool {
+    matches!(
+        channel,
+        Channel::Signed(SignedChannel {
+            state: SignedChannelState::Closing { .. },
+            ..
+        }) | Channel::Closing(_)
+    )
+}
+
+/// Whether the relative difference between the attested price and the reference price exceeds
+/// `max_divergence`.
+fn diverges(attested_price: Decimal, reference_price: Decimal, max_divergence: Decimal) -> bool {
+    if reference_price.is_zero() {
+        return true;
+    }
+
+    ((attested_price - reference_price) / reference_price).abs() > max_divergence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn attested_price_within_threshold_does_not_diverge() {
+        assert!(!diverges(dec!(50_000), dec!(51_000), dec!(0.05)));
+        assert!(!diverges(dec!(52_500), dec!(50_000), dec!(0.05)));
+    }
+
+    #[test]
+    fn attested_price_beyond_threshold_diverges() {
+        assert!(diverges(dec!(40_000), dec!(50_000), dec!(0.05)));
+        assert!(diverges(dec!(52_501), dec!(50_000), dec!(0.05)));
+    }
+}
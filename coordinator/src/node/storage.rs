@@ -1,11 +1,13 @@
 use crate::db;
 use anyhow::anyhow;
 use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 use diesel::PgConnection;
 use lightning::chain::transaction::OutPoint;
 use lightning::sign::SpendableOutputDescriptor;
+use xxi_node::dlc_message::SerializedDlcMessage;
 use xxi_node::node;
 use xxi_node::transaction::Transaction;
 
@@ -71,4 +73,35 @@ impl node::Storage for NodeStorage {
             .collect::<Vec<_>>();
         Ok(transactions)
     }
+
+    // Last outbound DLC messages
+
+    fn upsert_last_outbound_dlc_message(
+        &self,
+        peer: PublicKey,
+        message: SerializedDlcMessage,
+    ) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::last_outbound_dlc_message::upsert(&mut conn, &peer, message)
+    }
+
+    fn get_last_outbound_dlc_message(
+        &self,
+        peer: &PublicKey,
+    ) -> Result<Option<SerializedDlcMessage>> {
+        let mut conn = self.pool.get()?;
+        let message = db::last_outbound_dlc_message::get(&mut conn, peer)?;
+        Ok(message)
+    }
+
+    fn delete_last_outbound_dlc_message(&self, peer: &PublicKey) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::last_outbound_dlc_message::delete(&mut conn, peer)?;
+        Ok(())
+    }
+
+    fn all_last_outbound_dlc_messages(&self) -> Result<Vec<(PublicKey, SerializedDlcMessage)>> {
+        let mut conn = self.pool.get()?;
+        db::last_outbound_dlc_message::get_all(&mut conn)
+    }
 }
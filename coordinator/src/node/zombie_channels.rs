@@ -0,0 +1,455 @@
+use crate::db;
+use crate::db::zombie_channels::ZombieChannel;
+use crate::db::zombie_channels::ZombieChannelState;
+use crate::node::Node;
+use crate::notifications::Notification;
+use crate::notifications::NotificationKind;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use diesel::PgConnection;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::channel::Channel;
+use dlc_manager::DlcChannelId;
+use serde::Deserialize;
+use serde::Serialize;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use xxi_node::bitcoin_conversion::to_secp_pk_30;
+
+/// The time between two notifications to the trader of a zombie channel. We also wait this long
+/// after the last notification before parking or force-closing the DLC channel.
+const NOTIFICATION_INTERVAL: Duration = Duration::days(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ZombieChannelSettings {
+    /// The number of days without a connection to the trader after which a DLC channel is
+    /// considered a zombie.
+    pub max_offline_days: u32,
+    /// The number of push notifications sent to the trader before acting on a zombie channel.
+    pub notification_attempts: u32,
+    pub action: ZombieChannelAction,
+}
+
+/// What to do with a zombie channel once the trader has been notified.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ZombieChannelAction {
+    /// Keep the DLC channel, but only audit it against the blockchain occasionally.
+    Park,
+    /// Force-close the DLC channel.
+    ForceClose,
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    Wait,
+    Notify,
+    Act,
+}
+
+/// Detect DLC channels whose trader has not connected to us for longer than
+/// [`ZombieChannelSettings::max_offline_days`].
+///
+/// The trader of a zombie channel is notified a few times, before the channel is either parked or
+/// force-closed. Only DLC channels without a position are considered, as positions are settled
+/// at expiry anyway, see [`crate::node::expiry_settlement`]. A channel stops being a zombie as
+/// soon as the trader comes back online.
+pub async fn detect(node: Node, notifier: mpsc::Sender<Notification>) -> Result<()> {
+    let settings = node.settings.read().await.zombie_channels;
+    let now = OffsetDateTime::now_utc();
+
+    let mut conn = node.pool.get()?;
+
+    for channel in node.inner.list_signed_dlc_channels()? {
+        if let Err(e) = check_channel(&node, &mut conn, &notifier, &channel, settings, now).await {
+            tracing::error!(
+                channel_id = hex::encode(channel.channel_id),
+                "Failed to check for zombie channel: {e:#}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_channel(
+    node: &Node,
+    conn: &mut PgConnection,
+    notifier: &mpsc::Sender<Notification>,
+    channel: &SignedChannel,
+    settings: ZombieChannelSettings,
+    now: OffsetDateTime,
+) -> Result<()> {
+    let channel_id = channel.channel_id;
+    let trader = to_secp_pk_30(channel.counter_party);
+
+    let zombie = db::zombie_channels::get_active_by_channel_id(conn, &channel_id)?;
+    if let Some(ZombieChannel {
+        state: ZombieChannelState::Exempt,
+        ..
+    }) = zombie
+    {
+        return Ok(());
+    }
+
+    let last_seen = last_seen(node, conn, trader, now)?;
+    if now - last_seen < Duration::days(settings.max_offline_days as i64) {
+        if let Some(zombie) = zombie {
+            db::zombie_channels::set_state(conn, zombie.id, ZombieChannelState::Cleared, None)?;
+            node.inner.chain_auditor.unpark_channel(&channel_id);
+
+            tracing::info!(
+                channel_id = hex::encode(channel_id),
+                %trader,
+                "Trader of zombie channel is back online"
+            );
+        }
+
+        return Ok(());
+    }
+
+    if !matches!(channel.state, SignedChannelState::Settled { .. }) {
+        return Ok(());
+    }
+
+    let zombie = match zombie {
+        Some(zombie) => zombie,
+        None => {
+            tracing::warn!(
+                channel_id = hex::encode(channel_id),
+                %trader,
+                %last_seen,
+                "Flagging zombie channel"
+            );
+
+            db::zombie_channels::insert(
+                conn,
+                &channel_id,
+                trader,
+                ZombieChannelState::Flagged,
+                last_seen,
+                None,
+            )?
+        }
+    };
+
+    match zombie.state {
+        ZombieChannelState::Flagged => {}
+        ZombieChannelState::Parked => {
+            // The parked channels are only kept in memory, hence we have to park them again after
+            // a restart.
+            node.inner.chain_auditor.park_channel(channel_id);
+            return Ok(());
+        }
+        ZombieChannelState::ForceClosed
+        | ZombieChannelState::Exempt
+        | ZombieChannelState::Cleared => return Ok(()),
+    }
+
+    match next_step(&zombie, settings, now) {
+        Step::Wait => {}
+        Step::Notify => {
+            notifier
+                .send(Notification::new(trader, notification(settings.action)))
+                .await
+                .context("Failed to send zombie channel notification")?;
+
+            db::zombie_channels::record_notification(conn, zombie.id)?;
+        }
+        Step::Act => {
+            match settings.action {
+                ZombieChannelAction::Park => {
+                    park_zombie(node, conn, &zombie, &channel_id, None)?;
+                }
+                ZombieChannelAction::ForceClose => {
+                    force_close_zombie(node, conn, &zombie, &channel_id).await?;
+                }
+            }
+
+            tracing::warn!(
+                channel_id = hex::encode(channel_id),
+                %trader,
+                notifications_sent = zombie.notifications_sent,
+                action = ?settings.action,
+                "Trader of zombie channel did not come back online"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Park the DLC channel on behalf of an admin, regardless of when the trader was last seen.
+pub async fn park(node: &Node, channel_id: DlcChannelId, note: String) -> Result<ZombieChannel> {
+    let mut conn = node.pool.get()?;
+
+    let zombie = get_or_flag(node, &mut conn, &channel_id)?;
+    ensure!(
+        zombie.state != ZombieChannelState::Parked,
+        "DLC channel is already parked"
+    );
+
+    park_zombie(node, &mut conn, &zombie, &channel_id, Some(note))
+}
+
+/// Force-close the DLC channel on behalf of an admin, regardless of when the trader was last seen.
+pub async fn force_close(
+    node: &Node,
+    channel_id: DlcChannelId,
+    note: String,
+) -> Result<ZombieChannel> {
+    let mut conn = node.pool.get()?;
+
+    let zombie = get_or_flag(node, &mut conn, &channel_id)?;
+    let zombie = db::zombie_channels::set_state(&mut conn, zombie.id, zombie.state, Some(note))?;
+
+    force_close_zombie(node, &mut conn, &zombie, &channel_id).await
+}
+
+/// Never treat the DLC channel as a zombie, until the exemption is lifted with [`clear`].
+pub async fn exempt(node: &Node, channel_id: DlcChannelId, note: String) -> Result<ZombieChannel> {
+    let mut conn = node.pool.get()?;
+
+    let zombie = get_or_flag(node, &mut conn, &channel_id)?;
+    let zombie = db::zombie_channels::set_state(
+        &mut conn,
+        zombie.id,
+        ZombieChannelState::Exempt,
+        Some(note),
+    )?;
+
+    node.inner.chain_auditor.unpark_channel(&channel_id);
+
+    Ok(zombie)
+}
+
+/// Stop treating the DLC channel as a zombie. If the trader is still offline, the channel will be
+/// flagged again.
+pub async fn clear(node: &Node, channel_id: DlcChannelId) -> Result<ZombieChannel> {
+    let mut conn = node.pool.get()?;
+
+    let zombie = db::zombie_channels::get_active_by_channel_id(&mut conn, &channel_id)?
+        .context("DLC channel is not a zombie")?;
+    let zombie =
+        db::zombie_channels::set_state(&mut conn, zombie.id, ZombieChannelState::Cleared, None)?;
+
+    node.inner.chain_auditor.unpark_channel(&channel_id);
+
+    Ok(zombie)
+}
+
+/// Get the zombie channel, flagging the DLC channel first if it isn't a zombie yet.
+fn get_or_flag(
+    node: &Node,
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+) -> Result<ZombieChannel> {
+    if let Some(zombie) = db::zombie_channels::get_active_by_channel_id(conn, channel_id)? {
+        return Ok(zombie);
+    }
+
+    let trader = to_secp_pk_30(signed_channel(node, channel_id)?.counter_party);
+
+    let last_seen = last_seen(node, conn, trader, OffsetDateTime::now_utc())?;
+
+    let zombie = db::zombie_channels::insert(
+        conn,
+        channel_id,
+        trader,
+        ZombieChannelState::Flagged,
+        last_seen,
+        None,
+    )?;
+
+    Ok(zombie)
+}
+
+fn park_zombie(
+    node: &Node,
+    conn: &mut PgConnection,
+    zombie: &ZombieChannel,
+    channel_id: &DlcChannelId,
+    note: Option<String>,
+) -> Result<ZombieChannel> {
+    let zombie = db::zombie_channels::set_state(conn, zombie.id, ZombieChannelState::Parked, note)?;
+    node.inner.chain_auditor.park_channel(*channel_id);
+
+    Ok(zombie)
+}
+
+async fn force_close_zombie(
+    node: &Node,
+    conn: &mut PgConnection,
+    zombie: &ZombieChannel,
+    channel_id: &DlcChannelId,
+) -> Result<ZombieChannel> {
+    let channel = signed_channel(node, channel_id)?;
+
+    // Positions are settled by the expiry settlement, which knows the attested price.
+    ensure!(
+        matches!(channel.state, SignedChannelState::Settled { .. }),
+        "Cannot force-close zombie channel with an open position"
+    );
+
+    let protocol_id = node.force_close_dlc_channel(*channel_id).await?;
+
+    let zombie = db::zombie_channels::set_force_closed(conn, zombie.id, protocol_id)?;
+    node.inner.chain_auditor.unpark_channel(channel_id);
+
+    tracing::info!(
+        channel_id = hex::encode(channel_id),
+        trader = zombie.trader_pubkey,
+        %protocol_id,
+        "Force-closing zombie channel"
+    );
+
+    Ok(zombie)
+}
+
+fn signed_channel(node: &Node, channel_id: &DlcChannelId) -> Result<SignedChannel> {
+    match node.inner.get_dlc_channel_by_id(channel_id)? {
+        Channel::Signed(channel) => Ok(channel),
+        _ => bail!("DLC channel is not open"),
+    }
+}
+
+/// When we were last connected to the trader.
+fn last_seen(
+    node: &Node,
+    conn: &mut PgConnection,
+    trader: PublicKey,
+    now: OffsetDateTime,
+) -> Result<OffsetDateTime> {
+    if node.inner.is_connected(trader) {
+        return Ok(now);
+    }
+
+    let user = db::user::by_id(conn, trader.to_string())?
+        .with_context(|| format!("Unknown trader {trader}"))?;
+
+    Ok(user.last_login)
+}
+
+fn next_step(zombie: &ZombieChannel, settings: ZombieChannelSettings, now: OffsetDateTime) -> Step {
+    if let Some(last_notified_at) = zombie.last_notified_at {
+        if now < last_notified_at + NOTIFICATION_INTERVAL {
+            return Step::Wait;
+        }
+    }
+
+    if zombie.notifications_sent < settings.notification_attempts as i32 {
+        Step::Notify
+    } else {
+        Step::Act
+    }
+}
+
+fn notification(action: ZombieChannelAction) -> NotificationKind {
+    let message = match action {
+        ZombieChannelAction::Park => "Open your app to keep your channel up to date".to_string(),
+        ZombieChannelAction::ForceClose => {
+            "Open your app, otherwise your channel will be closed on-chain".to_string()
+        }
+    };
+
+    NotificationKind::Custom {
+        title: "We haven't seen you in a while 👋".to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: ZombieChannelSettings = ZombieChannelSettings {
+        max_offline_days: 30,
+        notification_attempts: 2,
+        action: ZombieChannelAction::Park,
+    };
+
+    #[test]
+    fn trader_is_notified_before_acting() {
+        let now = OffsetDateTime::now_utc();
+
+        assert_eq!(
+            next_step(&dummy_zombie(0, None), SETTINGS, now),
+            Step::Notify
+        );
+        assert_eq!(
+            next_step(
+                &dummy_zombie(1, Some(now - Duration::days(1))),
+                SETTINGS,
+                now
+            ),
+            Step::Notify
+        );
+        assert_eq!(
+            next_step(
+                &dummy_zombie(2, Some(now - Duration::days(1))),
+                SETTINGS,
+                now
+            ),
+            Step::Act
+        );
+    }
+
+    #[test]
+    fn trader_is_given_time_after_notification() {
+        let now = OffsetDateTime::now_utc();
+
+        assert_eq!(
+            next_step(
+                &dummy_zombie(1, Some(now - Duration::hours(1))),
+                SETTINGS,
+                now
+            ),
+            Step::Wait
+        );
+        assert_eq!(
+            next_step(
+                &dummy_zombie(2, Some(now - Duration::hours(23))),
+                SETTINGS,
+                now
+            ),
+            Step::Wait
+        );
+    }
+
+    #[test]
+    fn without_notifications_zombie_is_acted_on_immediately() {
+        let settings = ZombieChannelSettings {
+            notification_attempts: 0,
+            ..SETTINGS
+        };
+
+        assert_eq!(
+            next_step(&dummy_zombie(0, None), settings, OffsetDateTime::now_utc()),
+            Step::Act
+        );
+    }
+
+    fn dummy_zombie(
+        notifications_sent: i32,
+        last_notified_at: Option<OffsetDateTime>,
+    ) -> ZombieChannel {
+        ZombieChannel {
+            id: 1,
+            channel_id: hex::encode([1u8; 32]),
+            trader_pubkey: "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007"
+                .to_string(),
+            state: ZombieChannelState::Flagged,
+            last_seen: OffsetDateTime::UNIX_EPOCH,
+            notifications_sent,
+            last_notified_at,
+            protocol_id: None,
+            note: None,
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            updated_at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+}
@@ -6,8 +6,10 @@ use bitcoin::secp256k1::PublicKey;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 use diesel::PgConnection;
+use std::collections::HashMap;
 use std::fmt::Display;
 use tokio::sync::mpsc;
+use xxi_node::commons::Locale;
 
 /// Types of notification that can be sent to 10101 app users
 
@@ -17,9 +19,21 @@ pub enum NotificationKind {
     PositionSoonToExpire,
     PositionExpired,
     CollaborativeRevert,
-    Custom { title: String, message: String },
+    /// The trader's DLC channel is being force-closed on-chain.
+    ///
+    /// Sent as a high-priority data message, so that the app can react to it even if it is not
+    /// running.
+    ChannelForceClosed,
+    Custom {
+        title: String,
+        message: String,
+    },
 }
 
+/// The `type` of the data message sent for [`NotificationKind::ChannelForceClosed`], as expected
+/// by the app.
+const CHANNEL_FORCE_CLOSED_DATA_TYPE: &str = "channel_force_closed";
+
 impl Display for NotificationKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -27,6 +41,7 @@ impl Display for NotificationKind {
             NotificationKind::PositionExpired => write!(f, "PositionExpired"),
             NotificationKind::RolloverWindowOpen => write!(f, "RolloverWindowOpen"),
             NotificationKind::CollaborativeRevert => write!(f, "CollaborativeRevertPending"),
+            NotificationKind::ChannelForceClosed => write!(f, "ChannelForceClosed"),
             NotificationKind::Custom { .. } => write!(f, "Custom"),
         }
     }
@@ -100,24 +115,27 @@ impl NotificationService {
                         }
                     };
 
-                    let fcm_tokens = users
-                        .iter()
-                        .map(|user| user.fcm_token.clone())
-                        .filter(|token| !token.is_empty() && token != "unavailable")
-                        .map(FcmToken::new)
-                        .filter_map(Result::ok)
-                        .collect::<Vec<_>>();
+                    for user in users {
+                        if user.fcm_token == "unavailable" {
+                            continue;
+                        }
+
+                        let user_fcm_token = match FcmToken::new(user.fcm_token.clone()) {
+                            Ok(token) => token,
+                            Err(_) => continue,
+                        };
 
-                    for user_fcm_token in fcm_tokens {
-                        tracing::info!(%notification_kind, %user_fcm_token, "Sending notification");
+                        let locale = user.locale();
+
+                        tracing::info!(%notification_kind, %user_fcm_token, %locale, "Sending notification");
 
                         if !fcm_api_key.is_empty() {
-                            let notification = build_notification(&notification_kind);
                             if let Err(e) = send_notification(
                                 &client,
                                 &fcm_api_key,
                                 &user_fcm_token,
-                                notification,
+                                &notification_kind,
+                                locale,
                             )
                             .await
                             {
@@ -140,36 +158,97 @@ impl NotificationService {
     }
 }
 
-/// Prepares the notification text
-fn build_notification(kind: &NotificationKind) -> fcm::Notification<'_> {
+/// Prepares the notification text in the given locale
+fn build_notification(kind: &NotificationKind, locale: Locale) -> fcm::Notification<'_> {
     let mut notification_builder = fcm::NotificationBuilder::new();
     match kind {
-        NotificationKind::PositionSoonToExpire => {
-            notification_builder.title("Your position is about to expire ⏳");
-            notification_builder
-                .body("Open your app to roll over your position for the next cycle.");
-        }
-        NotificationKind::PositionExpired => {
-            notification_builder.title("Your position has expired 🥴");
-            notification_builder.body("Open your app to execute the expiration.");
-        }
-        NotificationKind::RolloverWindowOpen => {
-            notification_builder.title("Rollover window is open 🪟");
-            notification_builder
-                .body("Open your app to roll over your position for the next cycle.");
-        }
-        NotificationKind::CollaborativeRevert => {
-            notification_builder.title("Error detected");
-            notification_builder.body("Please open your app to recover your funds.");
-        }
         NotificationKind::Custom { title, message } => {
             notification_builder.title(title);
             notification_builder.body(message);
         }
+        kind => {
+            if let Some((title, body)) = notification_text(kind, locale) {
+                notification_builder.title(title);
+                notification_builder.body(body);
+            }
+        }
     }
     notification_builder.finalize()
 }
 
+/// Prepares the payload of a data message in the given locale.
+///
+/// The app shows the title and body itself, if it was not running when receiving the message.
+fn build_data(kind: &NotificationKind, locale: Locale) -> HashMap<&'static str, &'static str> {
+    let mut data = HashMap::new();
+
+    if kind == &NotificationKind::ChannelForceClosed {
+        data.insert("type", CHANNEL_FORCE_CLOSED_DATA_TYPE);
+    }
+
+    if let Some((title, body)) = notification_text(kind, locale) {
+        data.insert("title", title);
+        data.insert("body", body);
+    }
+
+    data
+}
+
+/// The catalog of notification texts, returning the title and body of a notification in the given
+/// locale.
+///
+/// Custom notifications are not part of the catalog, as they come with their own text.
+fn notification_text(
+    kind: &NotificationKind,
+    locale: Locale,
+) -> Option<(&'static str, &'static str)> {
+    let text = match (kind, locale) {
+        (NotificationKind::PositionSoonToExpire, Locale::English) => (
+            "Your position is about to expire ⏳",
+            "Open your app to roll over your position for the next cycle.",
+        ),
+        (NotificationKind::PositionSoonToExpire, Locale::German) => (
+            "Deine Position läuft bald ab ⏳",
+            "Öffne die App, um deine Position für den nächsten Zyklus zu verlängern.",
+        ),
+        (NotificationKind::PositionExpired, Locale::English) => (
+            "Your position has expired 🥴",
+            "Open your app to execute the expiration.",
+        ),
+        (NotificationKind::PositionExpired, Locale::German) => (
+            "Deine Position ist abgelaufen 🥴",
+            "Öffne die App, um den Ablauf auszuführen.",
+        ),
+        (NotificationKind::RolloverWindowOpen, Locale::English) => (
+            "Rollover window is open 🪟",
+            "Open your app to roll over your position for the next cycle.",
+        ),
+        (NotificationKind::RolloverWindowOpen, Locale::German) => (
+            "Das Rollover-Fenster ist offen 🪟",
+            "Öffne die App, um deine Position für den nächsten Zyklus zu verlängern.",
+        ),
+        (NotificationKind::CollaborativeRevert, Locale::English) => (
+            "Error detected",
+            "Please open your app to recover your funds.",
+        ),
+        (NotificationKind::CollaborativeRevert, Locale::German) => (
+            "Fehler erkannt",
+            "Bitte öffne die App, um deine Guthaben wiederherzustellen.",
+        ),
+        (NotificationKind::ChannelForceClosed, Locale::English) => (
+            "Your channel is being closed on-chain ⛓️",
+            "Open your app to follow the recovery of your funds.",
+        ),
+        (NotificationKind::ChannelForceClosed, Locale::German) => (
+            "Dein Kanal wird on-chain geschlossen ⛓️",
+            "Öffne die App, um die Wiederherstellung deiner Guthaben zu verfolgen.",
+        ),
+        (NotificationKind::Custom { .. }, _) => return None,
+    };
+
+    Some(text)
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FcmToken(String);
 
@@ -190,16 +269,29 @@ impl Display for FcmToken {
     }
 }
 
-async fn send_notification<'a>(
+async fn send_notification(
     client: &fcm::Client,
     api_key: &str,
     fcm_token: &FcmToken,
-    notification: fcm::Notification<'a>,
+    kind: &NotificationKind,
+    locale: Locale,
 ) -> Result<()> {
     ensure!(!api_key.is_empty(), "FCM API key is empty");
 
     let mut message_builder = fcm::MessageBuilder::new(api_key, fcm_token.get());
-    message_builder.notification(notification);
+    match kind {
+        NotificationKind::ChannelForceClosed => {
+            let data = build_data(kind, locale);
+            message_builder
+                .data(&data)
+                .context("Could not serialize FCM data")?;
+            message_builder.priority(fcm::Priority::High);
+            message_builder.content_available(true);
+        }
+        kind => {
+            message_builder.notification(build_notification(kind, locale));
+        }
+    }
     let message = message_builder.finalize();
     let response = client
         .send(message)
@@ -208,3 +300,40 @@ async fn send_notification<'a>(
     tracing::debug!("Sent notification. Response: {:?}", response);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_text_is_localized() {
+        let (english_title, _) =
+            notification_text(&NotificationKind::PositionExpired, Locale::English).unwrap();
+        let (german_title, _) =
+            notification_text(&NotificationKind::PositionExpired, Locale::German).unwrap();
+
+        assert_eq!(english_title, "Your position has expired 🥴");
+        assert_eq!(german_title, "Deine Position ist abgelaufen 🥴");
+    }
+
+    #[test]
+    fn custom_notification_is_not_part_of_catalog() {
+        let kind = NotificationKind::Custom {
+            title: "title".to_string(),
+            message: "message".to_string(),
+        };
+
+        assert!(notification_text(&kind, Locale::German).is_none());
+    }
+
+    #[test]
+    fn channel_force_closed_data_is_typed_and_localized() {
+        let data = build_data(&NotificationKind::ChannelForceClosed, Locale::German);
+
+        assert_eq!(data.get("type"), Some(&"channel_force_closed"));
+        assert_eq!(
+            data.get("title"),
+            Some(&"Dein Kanal wird on-chain geschlossen ⛓️")
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use crate::funding_fee::get_index_price;
+use crate::funding_fee::IndexPriceSource;
+use crate::orderbook::db::order_fills;
+use crate::orderbook::trading::sort_orders;
+use crate::orderbook::trading::MatchParams;
+use bitcoin::secp256k1::PublicKey;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use lazy_static::lazy_static;
+use prometheus::exponential_buckets;
+use prometheus::linear_buckets;
+use prometheus::register_histogram;
+use prometheus::register_histogram_vec;
+use prometheus::Histogram;
+use prometheus::HistogramVec;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use uuid::Uuid;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+use xxi_node::commons::Order;
+use xxi_node::commons::OrderType;
+
+lazy_static! {
+    static ref ORDER_TIME_TO_FILL: HistogramVec = register_histogram_vec!(
+        "coordinator_order_time_to_fill_seconds",
+        "Time from placing an order until its first match.",
+        &["order_type"],
+        exponential_buckets(0.01, 4.0, 12).expect("valid buckets")
+    )
+    .expect("valid metric");
+    static ref ORDER_SLIPPAGE: HistogramVec = register_histogram_vec!(
+        "coordinator_order_slippage_bps",
+        "Execution price of a filled order relative to the index price when it was placed, in \
+         basis points. Positive values are to the disadvantage of the trader.",
+        &["order_type"],
+        linear_buckets(-100.0, 10.0, 21).expect("valid buckets")
+    )
+    .expect("valid metric");
+    static ref ORDER_QUEUE_POSITION: Histogram = register_histogram!(
+        "coordinator_order_queue_position_at_fill",
+        "Position of a limit order in the orderbook when it was filled.",
+        linear_buckets(0.0, 1.0, 10).expect("valid buckets")
+    )
+    .expect("valid metric");
+}
+
+/// What we learn about an order when it is first matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderFill {
+    pub order_id: Uuid,
+    pub trader_pubkey: PublicKey,
+    pub order_type: OrderType,
+    pub direction: Direction,
+    pub quantity: Decimal,
+    pub execution_price: Decimal,
+    /// The index price when the order was placed.
+    pub index_price: Option<Decimal>,
+    /// The position of a limit order in the orderbook at the time it was filled, where `0` is
+    /// the top of the book.
+    ///
+    /// Only set for limit orders.
+    pub queue_position: Option<usize>,
+    pub placed_at: OffsetDateTime,
+    pub matched_at: OffsetDateTime,
+}
+
+impl OrderFill {
+    fn new(
+        order: &Order,
+        execution_price: Decimal,
+        queue_position: Option<usize>,
+        matched_at: OffsetDateTime,
+    ) -> Self {
+        Self {
+            order_id: order.id,
+            trader_pubkey: order.trader_id,
+            order_type: order.order_type,
+            direction: order.direction,
+            quantity: order.quantity,
+            execution_price,
+            index_price: None,
+            queue_position,
+            placed_at: order.timestamp,
+            matched_at,
+        }
+    }
+
+    pub fn time_to_fill(&self) -> Duration {
+        (self.matched_at - self.placed_at).max(Duration::ZERO)
+    }
+
+    /// The difference between the execution price and the index price at placement, in basis
+    /// points.
+    ///
+    /// A positive value means that the trader got a worse price than the index price, i.e. a long
+    /// order was executed above or a short order was executed below the index price.
+    pub fn slippage_bps(&self) -> Option<Decimal> {
+        let index_price = self.index_price.filter(|price| !price.is_zero())?;
+
+        let slippage = match self.direction {
+            Direction::Long => self.execution_price - index_price,
+            Direction::Short => index_price - self.execution_price,
+        };
+
+        Some(slippage / index_price * Decimal::from(10_000))
+    }
+}
+
+/// Collect the [`OrderFill`]s of the `market_order` and the limit orders it was matched with.
+///
+/// The `limit_orders` are the orders in the orderbook which were considered for the match.
+pub fn order_fills(
+    market_order: &Order,
+    limit_orders: &[Order],
+    match_params: &MatchParams,
+    matched_at: OffsetDateTime,
+) -> Vec<OrderFill> {
+    let mut fills = vec![];
+
+    if let Some(taker_match) = match_params.taker_match.filled_with.matches.first() {
+        fills.push(OrderFill::new(
+            market_order,
+            taker_match.execution_price,
+            None,
+            matched_at,
+        ));
+    }
+
+    let book = sort_orders(limit_orders.to_vec(), market_order.direction);
+    for maker_match in match_params.makers_matches.iter() {
+        let order_id = maker_match.filled_with.order_id;
+
+        let queue_position = book.iter().position(|order| order.id == order_id);
+        let (Some(queue_position), Some(execution)) =
+            (queue_position, maker_match.filled_with.matches.first())
+        else {
+            tracing::warn!(%order_id, "Could not find matched limit order in orderbook");
+            continue;
+        };
+
+        fills.push(OrderFill::new(
+            &book[queue_position],
+            execution.execution_price,
+            Some(queue_position),
+            matched_at,
+        ));
+    }
+
+    fills
+}
+
+/// Record the analytics of the given [`OrderFill`]s in the database and in the metrics.
+///
+/// The index price at the time each order was placed is fetched in the background, so that the
+/// matching is not delayed.
+pub fn record_order_fills(
+    pool: Pool<ConnectionManager<PgConnection>>,
+    index_price_source: IndexPriceSource,
+    fills: Vec<OrderFill>,
+) {
+    spawn_blocking(move || {
+        for mut fill in fills {
+            fill.index_price = match get_index_price(
+                index_price_source,
+                &ContractSymbol::BtcUsd,
+                fill.placed_at,
+            ) {
+                Ok(index_price) => Some(index_price),
+                Err(e) => {
+                    tracing::warn!(
+                        order_id = %fill.order_id,
+                        "Failed to get index price at order placement: {e:#}"
+                    );
+                    None
+                }
+            };
+
+            observe(&fill);
+
+            let mut conn = match pool.get() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::error!("Failed to get connection to record order fill: {e:#}");
+                    continue;
+                }
+            };
+
+            if let Err(e) = order_fills::insert(&mut conn, &fill) {
+                tracing::error!(order_id = %fill.order_id, "Failed to record order fill: {e:#}");
+            }
+        }
+    });
+}
+
+fn observe(fill: &OrderFill) {
+    let order_type = match fill.order_type {
+        OrderType::Market => "market",
+        OrderType::Limit => "limit",
+    };
+
+    ORDER_TIME_TO_FILL
+        .with_label_values(&[order_type])
+        .observe(fill.time_to_fill().as_seconds_f64());
+
+    if let Some(slippage) = fill.slippage_bps().and_then(|slippage| slippage.to_f64()) {
+        ORDER_SLIPPAGE
+            .with_label_values(&[order_type])
+            .observe(slippage);
+    }
+
+    if let Some(queue_position) = fill.queue_position {
+        ORDER_QUEUE_POSITION.observe(queue_position as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::trading::TraderMatchParams;
+    use bitcoin::secp256k1::XOnlyPublicKey;
+    use bitcoin::Amount;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use xxi_node::commons::FilledWith;
+    use xxi_node::commons::Match;
+    use xxi_node::commons::OrderReason;
+    use xxi_node::commons::OrderState;
+
+    #[test]
+    fn slippage_is_positive_if_trader_gets_worse_price() {
+        let long = dummy_fill(Direction::Long, dec!(50_050), Some(dec!(50_000)));
+        let short = dummy_fill(Direction::Short, dec!(50_050), Some(dec!(50_000)));
+
+        assert_eq!(long.slippage_bps(), Some(dec!(10)));
+        assert_eq!(short.slippage_bps(), Some(dec!(-10)));
+    }
+
+    #[test]
+    fn no_slippage_without_index_price() {
+        let fill = dummy_fill(Direction::Long, dec!(50_000), None);
+
+        assert_eq!(fill.slippage_bps(), None);
+    }
+
+    #[test]
+    fn time_to_fill_is_never_negative() {
+        let mut fill = dummy_fill(Direction::Long, dec!(50_000), None);
+        fill.matched_at = fill.placed_at - Duration::seconds(1);
+
+        assert_eq!(fill.time_to_fill(), Duration::ZERO);
+    }
+
+    #[test]
+    fn order_fills_include_queue_position_of_maker() {
+        let now = OffsetDateTime::now_utc();
+        let best = dummy_order(OrderType::Limit, Direction::Short, dec!(50_000), now);
+        let later = dummy_order(
+            OrderType::Limit,
+            Direction::Short,
+            dec!(50_000),
+            now + Duration::seconds(1),
+        );
+        let worse = dummy_order(
+            OrderType::Limit,
+            Direction::Short,
+            dec!(51_000),
+            now - Duration::seconds(1),
+        );
+        let market_order = dummy_order(OrderType::Market, Direction::Long, dec!(0), now);
+
+        let match_params = dummy_match_params(&market_order, &later);
+
+        let fills = order_fills(
+            &market_order,
+            &[worse, later.clone(), best],
+            &match_params,
+            now + Duration::seconds(5),
+        );
+
+        assert_eq!(fills.len(), 2);
+
+        assert_eq!(fills[0].order_id, market_order.id);
+        assert_eq!(fills[0].queue_position, None);
+        assert_eq!(fills[0].time_to_fill(), Duration::seconds(5));
+
+        assert_eq!(fills[1].order_id, later.id);
+        assert_eq!(fills[1].queue_position, Some(1));
+        assert_eq!(fills[1].execution_price, dec!(50_000));
+        assert_eq!(fills[1].time_to_fill(), Duration::seconds(4));
+    }
+
+    fn dummy_fill(
+        direction: Direction,
+        execution_price: Decimal,
+        index_price: Option<Decimal>,
+    ) -> OrderFill {
+        let now = OffsetDateTime::now_utc();
+        let order = dummy_order(OrderType::Market, direction, dec!(0), now);
+
+        OrderFill {
+            index_price,
+            ..OrderFill::new(&order, execution_price, None, now)
+        }
+    }
+
+    fn dummy_order(
+        order_type: OrderType,
+        direction: Direction,
+        price: Decimal,
+        timestamp: OffsetDateTime,
+    ) -> Order {
+        Order {
+            id: Uuid::new_v4(),
+            price,
+            trader_id: PublicKey::from_str(
+                "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007",
+            )
+            .unwrap(),
+            direction,
+            leverage: 1.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity: dec!(100),
+            order_type,
+            timestamp,
+            expiry: timestamp + Duration::minutes(1),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        }
+    }
+
+    fn dummy_match_params(market_order: &Order, maker_order: &Order) -> MatchParams {
+        let oracle_pk = XOnlyPublicKey::from_str(
+            "16f88cf7d21e6c0f46bcbc983a4e3b19726c6c98858cc31c83551a88fde171c0",
+        )
+        .unwrap();
+
+        let filled_with = |order_id, matched_order: &Order| FilledWith {
+            order_id,
+            expiry_timestamp: OffsetDateTime::now_utc(),
+            oracle_pk,
+            matches: vec![Match {
+                id: Uuid::new_v4(),
+                order_id: matched_order.id,
+                quantity: market_order.quantity,
+                pubkey: matched_order.trader_id,
+                execution_price: maker_order.price,
+                matching_fee: Amount::ZERO,
+                index_price: None,
+                spread_bps: 0,
+            }],
+        };
+
+        MatchParams {
+            taker_match: TraderMatchParams {
+                trader_id: market_order.trader_id,
+                filled_with: filled_with(market_order.id, maker_order),
+            },
+            makers_matches: vec![TraderMatchParams {
+                trader_id: maker_order.trader_id,
+                filled_with: filled_with(maker_order.id, market_order),
+            }],
+        }
+    }
+}
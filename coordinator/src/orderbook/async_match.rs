@@ -9,7 +9,6 @@ use anyhow::ensure;
 use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::secp256k1::XOnlyPublicKey;
-use bitcoin::Network;
 use futures::future::RemoteHandle;
 use futures::FutureExt;
 use rust_decimal::prelude::ToPrimitive;
@@ -18,7 +17,6 @@ use tokio::sync::broadcast;
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
 use tokio::task::spawn_blocking;
-use xxi_node::commons;
 use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::FilledWith;
 use xxi_node::commons::Match;
@@ -32,7 +30,6 @@ pub fn monitor(
     node: Node,
     mut receiver: broadcast::Receiver<NodeEvent>,
     notifier: mpsc::Sender<OrderbookMessage>,
-    network: Network,
     oracle_pk: XOnlyPublicKey,
 ) -> RemoteHandle<()> {
     let (fut, remote_handle) = async move {
@@ -48,8 +45,7 @@ pub fn monitor(
                                 "Checking if the user needs to be notified about pending matches"
                             );
                             if let Err(e) =
-                                process_pending_match(node, notifier, trader_id, network, oracle_pk)
-                                    .await
+                                process_pending_match(node, notifier, trader_id, oracle_pk).await
                             {
                                 tracing::error!("Failed to process pending match. Error: {e:#}");
                             }
@@ -79,7 +75,6 @@ async fn process_pending_match(
     node: Node,
     notifier: mpsc::Sender<OrderbookMessage>,
     trader_id: PublicKey,
-    network: Network,
     oracle_pk: XOnlyPublicKey,
 ) -> Result<()> {
     let mut conn = spawn_blocking({
@@ -100,7 +95,9 @@ async fn process_pending_match(
         tracing::debug!(%trader_id, order_id=%order.id, "Executing pending match");
 
         let matches = matches::get_matches_by_order_id(&mut conn, order.id)?;
-        let filled_with = get_filled_with_from_matches(matches, network, oracle_pk)?;
+        let expiry_timestamp =
+            order.contract_expiry_at(OffsetDateTime::now_utc(), node.expiry_schedule().await);
+        let filled_with = get_filled_with_from_matches(matches, expiry_timestamp, oracle_pk)?;
 
         let channel_opening_params =
             db::channel_opening_params::get_by_order_id(&mut conn, order.id)?;
@@ -129,7 +126,7 @@ async fn process_pending_match(
 
 fn get_filled_with_from_matches(
     matches: Vec<Matches>,
-    network: Network,
+    expiry_timestamp: OffsetDateTime,
     oracle_pk: XOnlyPublicKey,
 ) -> Result<FilledWith> {
     ensure!(
@@ -142,8 +139,6 @@ fn get_filled_with_from_matches(
         .expect("to have at least one match")
         .order_id;
 
-    let expiry_timestamp = commons::calculate_next_expiry(OffsetDateTime::now_utc(), network);
-
     Ok(FilledWith {
         order_id,
         expiry_timestamp,
@@ -157,6 +152,8 @@ fn get_filled_with_from_matches(
                 pubkey: m.match_trader_id,
                 execution_price: m.execution_price,
                 matching_fee: m.matching_fee,
+                index_price: m.index_price,
+                spread_bps: m.spread_bps,
             })
             .collect(),
     })
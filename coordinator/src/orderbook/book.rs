@@ -0,0 +1,586 @@
+use bitcoin::secp256k1::PublicKey;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+use xxi_node::commons::ExpirySchedule;
+use xxi_node::commons::Order;
+use xxi_node::commons::OrderReason;
+use xxi_node::commons::OrderState;
+use xxi_node::commons::OrderType;
+
+/// The number of hex characters of the trader id shown in the [`L3Book`].
+const TRUNCATED_TRADER_ID_LEN: usize = 10;
+
+/// The independent books of all markets, one per [`ContractSymbol`].
+///
+/// Within a market, orders are only matched with orders for the same contract expiry.
+///
+/// The books are owned by the trading task and only ever modified from there, hence they do not
+/// need any synchronisation.
+#[derive(Debug)]
+pub struct OrderBooks {
+    books: HashMap<ContractSymbol, OrderBook>,
+}
+
+impl OrderBooks {
+    pub fn new(orders: Vec<Order>) -> Self {
+        let mut books = Self {
+            books: HashMap::new(),
+        };
+        for order in orders {
+            books.insert(order);
+        }
+
+        books
+    }
+
+    /// Adds an open limit order to the book of its market. Other orders are ignored.
+    ///
+    /// Returns whether the order was added.
+    pub fn insert(&mut self, order: Order) -> bool {
+        self.books
+            .entry(order.contract_symbol)
+            .or_default()
+            .insert(order)
+    }
+
+    /// Removes the order from the book of whichever market it is in, returning it if it was found.
+    pub fn remove(&mut self, order_id: &Uuid) -> Option<Order> {
+        self.books
+            .values_mut()
+            .find_map(|book| book.remove(order_id))
+    }
+
+    /// Removes all orders which expired at `now` from all books, returning them.
+    pub fn remove_expired(&mut self, now: OffsetDateTime) -> Vec<Order> {
+        self.books
+            .values_mut()
+            .flat_map(|book| book.remove_expired(now))
+            .collect()
+    }
+
+    pub fn get(&self, order_id: &Uuid) -> Option<&Order> {
+        self.books.values().find_map(|book| book.get(order_id))
+    }
+
+    /// The ids of all orders of the given trader, in all markets.
+    pub fn order_ids_of_trader(&self, trader_id: PublicKey) -> Vec<Uuid> {
+        self.books
+            .values()
+            .flat_map(|book| book.orders.values())
+            .filter(|order| order.trader_id == trader_id)
+            .map(|order| order.id)
+            .collect()
+    }
+
+    /// All orders of the given market for the given contract expiry in the given direction, see
+    /// [`OrderBook::orders`].
+    ///
+    /// Orders without contract expiry are for the next expiry of the `schedule` at `now`.
+    pub fn orders(
+        &self,
+        contract_symbol: ContractSymbol,
+        contract_expiry: OffsetDateTime,
+        direction: Direction,
+        now: OffsetDateTime,
+        schedule: ExpirySchedule,
+    ) -> Vec<Order> {
+        self.books
+            .get(&contract_symbol)
+            .map(|book| book.orders(direction))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|order| order.contract_expiry_at(now, schedule) == contract_expiry)
+            .collect()
+    }
+
+    /// A level 3 view of the book of the given market, see [`OrderBook::l3`].
+    pub fn l3(
+        &self,
+        contract_symbol: ContractSymbol,
+        sequence: i64,
+        now: OffsetDateTime,
+    ) -> L3Book {
+        match self.books.get(&contract_symbol) {
+            Some(book) => book.l3(contract_symbol, sequence, now),
+            None => OrderBook::default().l3(contract_symbol, sequence, now),
+        }
+    }
+
+    /// The number of orders in all books.
+    pub fn len(&self) -> usize {
+        self.books.values().map(OrderBook::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.books.values().all(OrderBook::is_empty)
+    }
+}
+
+/// The open limit orders of a single market.
+#[derive(Debug, Default)]
+pub struct OrderBook {
+    orders: HashMap<Uuid, Order>,
+}
+
+impl OrderBook {
+    pub fn new(orders: Vec<Order>) -> Self {
+        let mut book = Self::default();
+        for order in orders {
+            book.insert(order);
+        }
+
+        book
+    }
+
+    /// Adds an open limit order to the book. Other orders are ignored.
+    ///
+    /// Returns whether the order was added.
+    pub fn insert(&mut self, order: Order) -> bool {
+        if order.order_type != OrderType::Limit || order.order_state != OrderState::Open {
+            return false;
+        }
+
+        self.orders.insert(order.id, order);
+        true
+    }
+
+    /// Removes the order from the book, returning it if it was in the book.
+    pub fn remove(&mut self, order_id: &Uuid) -> Option<Order> {
+        self.orders.remove(order_id)
+    }
+
+    /// Removes all orders which expired at `now`, returning them.
+    pub fn remove_expired(&mut self, now: OffsetDateTime) -> Vec<Order> {
+        let expired = self
+            .orders
+            .values()
+            .filter(|order| order.expiry < now)
+            .map(|order| order.id)
+            .collect::<Vec<_>>();
+
+        expired
+            .iter()
+            .filter_map(|order_id| self.orders.remove(order_id))
+            .collect()
+    }
+
+    pub fn get(&self, order_id: &Uuid) -> Option<&Order> {
+        self.orders.get(order_id)
+    }
+
+    /// All orders in the given direction.
+    ///
+    /// The orders are returned in the order they were placed in, so that matching against them is
+    /// deterministic.
+    pub fn orders(&self, direction: Direction) -> Vec<Order> {
+        let mut orders = self
+            .orders
+            .values()
+            .filter(|order| order.direction == direction)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        orders.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.id.cmp(&b.id)));
+
+        orders
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// A level 3 view of the book, i.e. every single order, as of `sequence`.
+    ///
+    /// Both sides are sorted by price-time priority, best price first.
+    pub fn l3(
+        &self,
+        contract_symbol: ContractSymbol,
+        sequence: i64,
+        now: OffsetDateTime,
+    ) -> L3Book {
+        let mut bids = self.orders(Direction::Long);
+        bids.sort_by(|a, b| b.price.cmp(&a.price));
+
+        let mut asks = self.orders(Direction::Short);
+        asks.sort_by(|a, b| a.price.cmp(&b.price));
+
+        let to_l3 = |orders: Vec<Order>| {
+            orders
+                .into_iter()
+                .map(|order| L3Order::new(order, now))
+                .collect()
+        };
+
+        L3Book {
+            contract_symbol,
+            sequence,
+            timestamp: now,
+            bids: to_l3(bids),
+            asks: to_l3(asks),
+        }
+    }
+}
+
+/// The iceberg order left in the book after `filled` of its quantity was matched, if any.
+///
+/// A fill within the displayed slice keeps the time priority of the order. Once the displayed
+/// slice is exhausted, it is replenished from the hidden quantity and the order moves to the back
+/// of its price level at `now`, as if it was placed anew. Otherwise a maker could keep the front
+/// of the queue with arbitrary size, while only ever showing a small part of it.
+///
+/// Regular limit orders are always taken entirely, hence nothing is left of them.
+pub fn remaining_iceberg(order: &Order, filled: Decimal, now: OffsetDateTime) -> Option<Order> {
+    order.display_quantity?;
+
+    let quantity = order.quantity - filled;
+    if quantity <= Decimal::ZERO {
+        return None;
+    }
+
+    let timestamp = if filled < order.visible_quantity() {
+        order.timestamp
+    } else {
+        now
+    };
+
+    Some(Order {
+        quantity,
+        timestamp,
+        ..order.clone()
+    })
+}
+
+/// The orderbook of a single market as seen by the matching engine, for operational monitoring.
+#[derive(Debug, Clone, Serialize)]
+pub struct L3Book {
+    pub contract_symbol: ContractSymbol,
+    /// The sequence number of the last orderbook journal entry applied to any of the books, as the
+    /// journal is shared between all markets.
+    pub sequence: i64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    /// Long limit orders.
+    pub bids: Vec<L3Order>,
+    /// Short limit orders.
+    pub asks: Vec<L3Order>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct L3Order {
+    pub id: Uuid,
+    /// Only the beginning of the trader id, which is enough to tell makers apart.
+    pub trader_id: String,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub price: Decimal,
+    /// Including the hidden quantity of an iceberg order.
+    #[serde(with = "rust_decimal::serde::float")]
+    pub quantity: Decimal,
+    #[serde(with = "rust_decimal::serde::float_option")]
+    pub display_quantity: Option<Decimal>,
+    /// None for the next expiry.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub contract_expiry: Option<OffsetDateTime>,
+    pub leverage: f32,
+    /// How long the order has been in the book, in seconds.
+    pub age: i64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expiry: OffsetDateTime,
+    /// Why the order was placed.
+    pub source: OrderReason,
+}
+
+impl L3Order {
+    fn new(order: Order, now: OffsetDateTime) -> Self {
+        Self {
+            id: order.id,
+            trader_id: truncate_trader_id(&order.trader_id),
+            price: order.price,
+            quantity: order.quantity,
+            display_quantity: order.display_quantity,
+            contract_expiry: order.contract_expiry,
+            leverage: order.leverage,
+            age: (now - order.timestamp).whole_seconds().max(0),
+            expiry: order.expiry,
+            source: order.order_reason,
+        }
+    }
+}
+
+fn truncate_trader_id(trader_id: &PublicKey) -> String {
+    let mut trader_id = trader_id.to_string();
+    trader_id.truncate(TRUNCATED_TRADER_ID_LEN);
+
+    format!("{trader_id}...")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::PublicKey;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::Duration;
+    use xxi_node::commons::ContractSymbol;
+    use xxi_node::commons::OrderReason;
+
+    #[test]
+    fn only_open_limit_orders_are_added() {
+        let mut book = OrderBook::default();
+
+        assert!(book.insert(dummy_order(Direction::Long, OrderType::Limit, 0)));
+        assert!(!book.insert(dummy_order(Direction::Long, OrderType::Market, 0)));
+        assert!(!book.insert(Order {
+            order_state: OrderState::Taken,
+            ..dummy_order(Direction::Long, OrderType::Limit, 0)
+        }));
+
+        assert_eq!(book.len(), 1);
+    }
+
+    #[test]
+    fn orders_are_returned_in_placement_order() {
+        let first = dummy_order(Direction::Short, OrderType::Limit, 0);
+        let second = dummy_order(Direction::Short, OrderType::Limit, 1);
+        let long = dummy_order(Direction::Long, OrderType::Limit, 2);
+
+        let book = OrderBook::new(vec![second.clone(), long, first.clone()]);
+
+        assert_eq!(book.orders(Direction::Short), vec![first, second]);
+    }
+
+    #[test]
+    fn expired_orders_are_removed() {
+        let now = OffsetDateTime::now_utc();
+        let expired = Order {
+            expiry: now - Duration::seconds(1),
+            ..dummy_order(Direction::Long, OrderType::Limit, 0)
+        };
+        let open = dummy_order(Direction::Long, OrderType::Limit, 1);
+
+        let mut book = OrderBook::new(vec![expired.clone(), open.clone()]);
+
+        assert_eq!(book.remove_expired(now), vec![expired]);
+        assert_eq!(book.orders(Direction::Long), vec![open]);
+    }
+
+    #[test]
+    fn order_ids_of_trader_only_include_their_orders() {
+        let own = dummy_order(Direction::Long, OrderType::Limit, 0);
+        let other = Order {
+            trader_id: PublicKey::from_str(
+                "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+            )
+            .unwrap(),
+            ..dummy_order(Direction::Short, OrderType::Limit, 1)
+        };
+
+        let books = OrderBooks::new(vec![own.clone(), other]);
+
+        assert_eq!(books.order_ids_of_trader(own.trader_id), vec![own.id]);
+    }
+
+    #[test]
+    fn l3_book_is_sorted_by_price_time_priority() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::seconds(10);
+
+        let best_bid = Order {
+            price: dec!(50_100),
+            ..dummy_order(Direction::Long, OrderType::Limit, 2)
+        };
+        let first_bid = dummy_order(Direction::Long, OrderType::Limit, 0);
+        let second_bid = dummy_order(Direction::Long, OrderType::Limit, 1);
+        let best_ask = Order {
+            price: dec!(49_900),
+            ..dummy_order(Direction::Short, OrderType::Limit, 3)
+        };
+        let ask = dummy_order(Direction::Short, OrderType::Limit, 0);
+
+        let book = OrderBook::new(vec![
+            second_bid.clone(),
+            ask.clone(),
+            first_bid.clone(),
+            best_ask.clone(),
+            best_bid.clone(),
+        ]);
+
+        let l3 = book.l3(ContractSymbol::BtcUsd, 42, now);
+
+        assert_eq!(l3.contract_symbol, ContractSymbol::BtcUsd);
+        assert_eq!(l3.sequence, 42);
+        assert_eq!(
+            l3.bids.iter().map(|order| order.id).collect::<Vec<_>>(),
+            vec![best_bid.id, first_bid.id, second_bid.id]
+        );
+        assert_eq!(
+            l3.asks.iter().map(|order| order.id).collect::<Vec<_>>(),
+            vec![best_ask.id, ask.id]
+        );
+        assert_eq!(l3.bids[0].age, 8);
+        assert_eq!(l3.bids[0].trader_id, "027f31ebc5...");
+    }
+
+    #[test]
+    fn orders_are_routed_to_the_book_of_their_market() {
+        let now = OffsetDateTime::now_utc();
+        let next_expiry = ExpirySchedule::Weekly.next_expiry(now);
+
+        let long = dummy_order(Direction::Long, OrderType::Limit, 0);
+        let short = dummy_order(Direction::Short, OrderType::Limit, 1);
+
+        let mut books = OrderBooks::new(vec![long.clone(), short.clone()]);
+
+        assert_eq!(books.len(), 2);
+        assert_eq!(
+            books.orders(
+                ContractSymbol::BtcUsd,
+                next_expiry,
+                Direction::Long,
+                now,
+                ExpirySchedule::Weekly
+            ),
+            vec![long.clone()]
+        );
+        assert_eq!(books.get(&short.id), Some(&short));
+
+        assert_eq!(books.remove(&long.id), Some(long));
+        assert_eq!(books.remove(&Uuid::new_v4()), None);
+        assert!(books
+            .orders(
+                ContractSymbol::BtcUsd,
+                next_expiry,
+                Direction::Long,
+                now,
+                ExpirySchedule::Weekly
+            )
+            .is_empty());
+        assert_eq!(books.len(), 1);
+    }
+
+    #[test]
+    fn orders_are_only_returned_for_their_contract_expiry() {
+        let now = OffsetDateTime::now_utc();
+        let expiries = ExpirySchedule::Weekly.next_expiries(now, 2);
+
+        let next = dummy_order(Direction::Long, OrderType::Limit, 0);
+        let explicitly_next = Order {
+            contract_expiry: Some(expiries[0]),
+            ..dummy_order(Direction::Long, OrderType::Limit, 1)
+        };
+        let later = Order {
+            contract_expiry: Some(expiries[1]),
+            ..dummy_order(Direction::Long, OrderType::Limit, 2)
+        };
+
+        let books = OrderBooks::new(vec![next.clone(), explicitly_next.clone(), later.clone()]);
+
+        assert_eq!(
+            books.orders(
+                ContractSymbol::BtcUsd,
+                expiries[0],
+                Direction::Long,
+                now,
+                ExpirySchedule::Weekly
+            ),
+            vec![next, explicitly_next]
+        );
+        assert_eq!(
+            books.orders(
+                ContractSymbol::BtcUsd,
+                expiries[1],
+                Direction::Long,
+                now,
+                ExpirySchedule::Weekly
+            ),
+            vec![later]
+        );
+    }
+
+    #[test]
+    fn fill_within_displayed_slice_keeps_priority() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::seconds(10);
+        let iceberg = Order {
+            quantity: dec!(1000),
+            display_quantity: Some(dec!(300)),
+            ..dummy_order(Direction::Short, OrderType::Limit, 0)
+        };
+
+        let remaining = remaining_iceberg(&iceberg, dec!(60), now).unwrap();
+
+        assert_eq!(remaining.quantity, dec!(940));
+        assert_eq!(remaining.visible_quantity(), dec!(40));
+        assert_eq!(remaining.timestamp, iceberg.timestamp);
+    }
+
+    #[test]
+    fn replenished_iceberg_loses_priority() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::seconds(10);
+        let iceberg = Order {
+            quantity: dec!(1000),
+            display_quantity: Some(dec!(300)),
+            ..dummy_order(Direction::Short, OrderType::Limit, 0)
+        };
+        let later = dummy_order(Direction::Short, OrderType::Limit, 1);
+
+        // Exhausting the displayed slice exactly replenishes it as well.
+        let remaining = remaining_iceberg(&iceberg, dec!(100), now).unwrap();
+        assert_eq!(remaining.quantity, dec!(900));
+        assert_eq!(remaining.visible_quantity(), dec!(300));
+        assert_eq!(remaining.timestamp, now);
+
+        // Fills may reach into the hidden quantity.
+        let remaining = remaining_iceberg(&iceberg, dec!(450), now).unwrap();
+        assert_eq!(remaining.quantity, dec!(550));
+        assert_eq!(remaining.visible_quantity(), dec!(250));
+        assert_eq!(remaining.timestamp, now);
+
+        let book = OrderBook::new(vec![remaining.clone(), later.clone()]);
+        assert_eq!(book.orders(Direction::Short), vec![later, remaining]);
+    }
+
+    #[test]
+    fn filled_orders_leave_nothing_in_the_book() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::seconds(10);
+        let iceberg = Order {
+            quantity: dec!(1000),
+            display_quantity: Some(dec!(300)),
+            ..dummy_order(Direction::Short, OrderType::Limit, 0)
+        };
+        let order = dummy_order(Direction::Short, OrderType::Limit, 0);
+
+        assert_eq!(remaining_iceberg(&iceberg, dec!(1000), now), None);
+        assert_eq!(remaining_iceberg(&iceberg, dec!(1200), now), None);
+        assert_eq!(remaining_iceberg(&order, dec!(10), now), None);
+    }
+
+    fn dummy_order(direction: Direction, order_type: OrderType, placed_after_secs: i64) -> Order {
+        Order {
+            id: Uuid::new_v4(),
+            price: dec!(50_000),
+            leverage: 1.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            trader_id: PublicKey::from_str(
+                "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007",
+            )
+            .unwrap(),
+            direction,
+            quantity: dec!(100),
+            order_type,
+            timestamp: OffsetDateTime::UNIX_EPOCH + Duration::seconds(placed_after_secs),
+            expiry: OffsetDateTime::now_utc() + Duration::minutes(1),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Deletes the orders of makers whose websocket connection dropped, so that their stale quotes
+//! cannot be picked off.
+//!
+//! Makers opt in when authenticating the connection. If the last such connection of a maker drops,
+//! all of the maker's orders are deleted after the grace period, unless the maker reconnects in
+//! the meantime.
+
+use crate::orderbook::trading::OrderbookCommand;
+use bitcoin::secp256k1::PublicKey;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tokio::task::AbortHandle;
+
+#[derive(Clone)]
+pub struct CancelOnDisconnect {
+    makers: Arc<Mutex<HashMap<PublicKey, MakerConnections>>>,
+    trading_sender: mpsc::Sender<OrderbookCommand>,
+}
+
+#[derive(Default)]
+struct MakerConnections {
+    /// The number of open connections which asked to cancel on disconnect.
+    connections: usize,
+    /// The deletion of the maker's orders, waiting for the grace period to pass.
+    pending: Option<AbortHandle>,
+}
+
+/// Held by the websocket connection of a maker which asked to cancel on disconnect.
+///
+/// Dropping it, i.e. closing the connection, schedules the deletion of the maker's orders.
+pub struct ConnectionGuard {
+    registry: CancelOnDisconnect,
+    trader_id: PublicKey,
+    grace_period: Duration,
+}
+
+impl CancelOnDisconnect {
+    pub fn new(trading_sender: mpsc::Sender<OrderbookCommand>) -> Self {
+        Self {
+            makers: Default::default(),
+            trading_sender,
+        }
+    }
+
+    /// Register a connection of the maker, aborting a pending deletion of the maker's orders.
+    pub fn connected(&self, trader_id: PublicKey, grace_period: Duration) -> ConnectionGuard {
+        let mut makers = self.makers.lock();
+        let maker = makers.entry(trader_id).or_default();
+
+        maker.connections += 1;
+        if let Some(pending) = maker.pending.take() {
+            tracing::info!(%trader_id, "Maker reconnected within the grace period");
+            pending.abort();
+        }
+
+        ConnectionGuard {
+            registry: self.clone(),
+            trader_id,
+            grace_period,
+        }
+    }
+
+    fn disconnected(&self, trader_id: PublicKey, grace_period: Duration) {
+        let mut makers = self.makers.lock();
+        let Some(maker) = makers.get_mut(&trader_id) else {
+            return;
+        };
+
+        maker.connections = maker.connections.saturating_sub(1);
+        if maker.connections > 0 {
+            return;
+        }
+
+        tracing::info!(
+            %trader_id,
+            ?grace_period,
+            "Maker disconnected, deleting their orders after the grace period"
+        );
+
+        let task = tokio::spawn({
+            let registry = self.clone();
+            async move {
+                tokio::time::sleep(grace_period).await;
+
+                {
+                    let mut makers = registry.makers.lock();
+                    // The maker may have reconnected just as the grace period passed.
+                    if makers
+                        .get(&trader_id)
+                        .is_some_and(|maker| maker.connections > 0)
+                    {
+                        return;
+                    }
+
+                    makers.remove(&trader_id);
+                }
+
+                if let Err(e) = registry.delete_orders(trader_id).await {
+                    tracing::error!(%trader_id, "Failed to delete orders of maker: {e:#}");
+                }
+            }
+        });
+
+        maker.pending = Some(task.abort_handle());
+    }
+
+    async fn delete_orders(&self, trader_id: PublicKey) -> anyhow::Result<()> {
+        let (response, deleted) = oneshot::channel();
+        self.trading_sender
+            .send(OrderbookCommand::DeleteOrdersOfTrader {
+                trader_id,
+                response,
+            })
+            .await?;
+
+        let deleted = deleted.await??;
+        tracing::info!(%trader_id, ?deleted, "Deleted orders of disconnected maker");
+
+        Ok(())
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.registry
+            .disconnected(self.trader_id, self.grace_period);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+    fn maker() -> PublicKey {
+        PublicKey::from_str("02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn orders_are_deleted_after_the_grace_period() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let registry = CancelOnDisconnect::new(sender);
+
+        drop(registry.connected(maker(), GRACE_PERIOD));
+
+        tokio::time::sleep(GRACE_PERIOD / 2).await;
+        assert!(receiver.try_recv().is_err());
+
+        tokio::time::sleep(GRACE_PERIOD).await;
+        match receiver.try_recv() {
+            Ok(OrderbookCommand::DeleteOrdersOfTrader { trader_id, .. }) => {
+                assert_eq!(trader_id, maker())
+            }
+            _ => panic!("Expected the orders of the maker to be deleted"),
+        }
+    }
+
+    #[tokio::test]
+    async fn orders_are_kept_if_the_maker_reconnects() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let registry = CancelOnDisconnect::new(sender);
+
+        drop(registry.connected(maker(), GRACE_PERIOD));
+        let _reconnected = registry.connected(maker(), GRACE_PERIOD);
+
+        tokio::time::sleep(GRACE_PERIOD * 2).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn orders_are_kept_while_another_connection_is_open() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let registry = CancelOnDisconnect::new(sender);
+
+        let _first = registry.connected(maker(), GRACE_PERIOD);
+        drop(registry.connected(maker(), GRACE_PERIOD));
+
+        tokio::time::sleep(GRACE_PERIOD * 2).await;
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! The contract expiries traders can choose from, besides the next expiry.
+
+use crate::node::Node;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::ExpirySchedule;
+use xxi_node::commons::OracleEventId;
+
+/// Ensure that an order is for one of the next `listed_expiries` expiries and that the oracle has
+/// announced the event at that expiry, so that the contract can be set up once the order is
+/// matched.
+///
+/// Orders without contract expiry are for the next expiry, which is always listed.
+pub async fn validate_contract_expiry(
+    node: &Node,
+    contract_symbol: ContractSymbol,
+    contract_expiry: Option<OffsetDateTime>,
+    listed_expiries: usize,
+) -> Result<()> {
+    let contract_expiry = match contract_expiry {
+        Some(contract_expiry) => contract_expiry,
+        None => return Ok(()),
+    };
+
+    ensure_listed(
+        contract_expiry,
+        OffsetDateTime::now_utc(),
+        node.expiry_schedule().await,
+        listed_expiries,
+    )?;
+
+    spawn_blocking({
+        let node = node.clone();
+        move || {
+            let event_id = OracleEventId::new(contract_symbol, contract_expiry).to_string();
+            node.inner
+                .get_announcement(node.inner.oracle_pubkey, &event_id)
+                .with_context(|| format!("Contract expiry {contract_expiry} is not announced yet"))
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    Ok(())
+}
+
+fn ensure_listed(
+    contract_expiry: OffsetDateTime,
+    now: OffsetDateTime,
+    schedule: ExpirySchedule,
+    listed_expiries: usize,
+) -> Result<()> {
+    let expiries = schedule.next_expiries(now, listed_expiries.max(1));
+
+    ensure!(
+        expiries.contains(&contract_expiry),
+        "Contract expiry {contract_expiry} is not listed, expected one of {expiries:?}"
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    fn now() -> OffsetDateTime {
+        // Wed Jul 10 2024 12:00:00 GMT+0000
+        OffsetDateTime::from_unix_timestamp(1720612800).unwrap()
+    }
+
+    #[test]
+    fn listed_expiries_are_accepted() {
+        let expiries = ExpirySchedule::Weekly.next_expiries(now(), 2);
+
+        assert!(ensure_listed(expiries[0], now(), ExpirySchedule::Weekly, 2).is_ok());
+        assert!(ensure_listed(expiries[1], now(), ExpirySchedule::Weekly, 2).is_ok());
+    }
+
+    #[test]
+    fn hourly_expiries_are_listed() {
+        let expiries = ExpirySchedule::Hourly.next_expiries(now(), 2);
+
+        assert!(ensure_listed(expiries[1], now(), ExpirySchedule::Hourly, 2).is_ok());
+        assert!(ensure_listed(expiries[1], now(), ExpirySchedule::Daily, 2).is_err());
+    }
+
+    #[test]
+    fn expiries_which_are_not_listed_are_rejected() {
+        let expiries = ExpirySchedule::Weekly.next_expiries(now(), 3);
+
+        // Only the next expiry is listed.
+        assert!(ensure_listed(expiries[1], now(), ExpirySchedule::Weekly, 1).is_err());
+        // Beyond the listed expiries.
+        assert!(ensure_listed(expiries[2], now(), ExpirySchedule::Weekly, 2).is_err());
+        // Not an expiry at all.
+        assert!(ensure_listed(
+            expiries[0] - Duration::hours(1),
+            now(),
+            ExpirySchedule::Weekly,
+            2
+        )
+        .is_err());
+    }
+}
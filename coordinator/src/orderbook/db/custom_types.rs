@@ -1,3 +1,4 @@
+use crate::schema::sql_types::ClientKindType;
 use crate::schema::sql_types::DirectionType;
 use crate::schema::sql_types::MatchStateType;
 use crate::schema::sql_types::OrderReasonType;
@@ -153,6 +154,8 @@ pub(crate) enum OrderReason {
     /// The order has been created automatically as the position got liquidated.
     TraderLiquidated,
     CoordinatorLiquidated,
+    /// The order has been created automatically to migrate the trader's DLC channel.
+    ChannelMigration,
 }
 
 impl QueryId for OrderReasonType {
@@ -171,6 +174,7 @@ impl ToSql<OrderReasonType, Pg> for OrderReason {
             OrderReason::Expired => out.write_all(b"Expired")?,
             OrderReason::TraderLiquidated => out.write_all(b"TraderLiquidated")?,
             OrderReason::CoordinatorLiquidated => out.write_all(b"CoordinatorLiquidated")?,
+            OrderReason::ChannelMigration => out.write_all(b"ChannelMigration")?,
         }
         Ok(IsNull::No)
     }
@@ -183,6 +187,7 @@ impl FromSql<OrderReasonType, Pg> for OrderReason {
             b"Expired" => Ok(OrderReason::Expired),
             b"TraderLiquidated" => Ok(OrderReason::TraderLiquidated),
             b"CoordinatorLiquidated" => Ok(OrderReason::CoordinatorLiquidated),
+            b"ChannelMigration" => Ok(OrderReason::ChannelMigration),
             _ => Err("Unrecognized enum variant".into()),
         }
     }
@@ -226,3 +231,42 @@ impl FromSql<MatchStateType, Pg> for MatchState {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression, Eq, Hash)]
+#[diesel(sql_type = ClientKindType)]
+pub(crate) enum ClientKind {
+    App,
+    Webapp,
+    Sdk,
+}
+
+impl QueryId for ClientKindType {
+    type QueryId = ClientKindType;
+    const HAS_STATIC_QUERY_ID: bool = false;
+
+    fn query_id() -> Option<TypeId> {
+        None
+    }
+}
+
+impl ToSql<ClientKindType, Pg> for ClientKind {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            ClientKind::App => out.write_all(b"App")?,
+            ClientKind::Webapp => out.write_all(b"Webapp")?,
+            ClientKind::Sdk => out.write_all(b"Sdk")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<ClientKindType, Pg> for ClientKind {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"App" => Ok(ClientKind::App),
+            b"Webapp" => Ok(ClientKind::Webapp),
+            b"Sdk" => Ok(ClientKind::Sdk),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
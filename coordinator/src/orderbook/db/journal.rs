@@ -0,0 +1,117 @@
+use crate::orderbook::trading::OrderbookEvent;
+use crate::schema::orderbook_journal;
+use anyhow::Context;
+use anyhow::Result;
+use diesel::dsl::max;
+use diesel::prelude::*;
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = orderbook_journal)]
+struct NewJournalEntry {
+    sequence: i64,
+    event_type: String,
+    order_id: Uuid,
+    payload: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct JournalEntryRecord {
+    sequence: i64,
+    payload: String,
+    created_at: OffsetDateTime,
+}
+
+/// An [`OrderbookEvent`] as recorded in the orderbook journal.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub sequence: i64,
+    pub event: OrderbookEvent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// Append the event to the journal under the given sequence number.
+///
+/// Fails if there already is an event with the same sequence number, i.e. if another writer
+/// modified the orderbook.
+pub fn insert(conn: &mut PgConnection, sequence: i64, event: &OrderbookEvent) -> Result<()> {
+    let payload = serde_json::to_string(event).context("Failed to serialize orderbook event")?;
+
+    diesel::insert_into(orderbook_journal::table)
+        .values(NewJournalEntry {
+            sequence,
+            event_type: event.event_type().to_string(),
+            order_id: event.order_id(),
+            payload,
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The sequence number of the last event in the journal, or 0 if the journal is empty.
+pub fn last_sequence(conn: &mut PgConnection) -> QueryResult<i64> {
+    let sequence = orderbook_journal::table
+        .select(max(orderbook_journal::sequence))
+        .first::<Option<i64>>(conn)?;
+
+    Ok(sequence.unwrap_or_default())
+}
+
+/// All events recorded for the given order, in the order they were applied.
+pub fn get_by_order_id(conn: &mut PgConnection, order_id: Uuid) -> Result<Vec<JournalEntry>> {
+    let records = orderbook_journal::table
+        .filter(orderbook_journal::order_id.eq(order_id))
+        .select((
+            orderbook_journal::sequence,
+            orderbook_journal::payload,
+            orderbook_journal::created_at,
+        ))
+        .order_by(orderbook_journal::sequence.asc())
+        .load::<JournalEntryRecord>(conn)?;
+
+    records.into_iter().map(JournalEntry::try_from).collect()
+}
+
+/// Up to `limit` events recorded within `[from, to)` after the given sequence number, in the order
+/// they were applied.
+pub fn get_between(
+    conn: &mut PgConnection,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+    after_sequence: i64,
+    limit: i64,
+) -> Result<Vec<JournalEntry>> {
+    let records = orderbook_journal::table
+        .filter(orderbook_journal::created_at.ge(from))
+        .filter(orderbook_journal::created_at.lt(to))
+        .filter(orderbook_journal::sequence.gt(after_sequence))
+        .select((
+            orderbook_journal::sequence,
+            orderbook_journal::payload,
+            orderbook_journal::created_at,
+        ))
+        .order_by(orderbook_journal::sequence.asc())
+        .limit(limit)
+        .load::<JournalEntryRecord>(conn)?;
+
+    records.into_iter().map(JournalEntry::try_from).collect()
+}
+
+impl TryFrom<JournalEntryRecord> for JournalEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JournalEntryRecord) -> Result<Self> {
+        let event = serde_json::from_str(&value.payload)
+            .with_context(|| format!("Failed to deserialize orderbook event {}", value.sequence))?;
+
+        Ok(JournalEntry {
+            sequence: value.sequence,
+            event,
+            created_at: value.created_at,
+        })
+    }
+}
@@ -35,6 +35,8 @@ struct Matches {
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub matching_fee_sats: i64,
+    pub index_price: Option<f32>,
+    pub spread_bps: i32,
 }
 
 pub fn insert(conn: &mut PgConnection, match_params: &TraderMatchParams) -> Result<()> {
@@ -111,6 +113,10 @@ impl Matches {
                 created_at: updated_at,
                 updated_at,
                 matching_fee_sats: m.matching_fee.to_sat() as i64,
+                index_price: m
+                    .index_price
+                    .map(|price| price.to_f32().expect("to fit into f32")),
+                spread_bps: m.spread_bps as i32,
             })
             .collect()
     }
@@ -130,6 +136,10 @@ impl From<commons::Matches> for Matches {
             created_at: OffsetDateTime::now_utc(),
             updated_at: OffsetDateTime::now_utc(),
             matching_fee_sats: value.matching_fee.to_sat() as i64,
+            index_price: value
+                .index_price
+                .map(|price| price.to_f32().expect("to fit into f32")),
+            spread_bps: value.spread_bps as i32,
         }
     }
 }
@@ -159,6 +169,10 @@ impl From<Matches> for commons::Matches {
             created_at: OffsetDateTime::now_utc(),
             updated_at: OffsetDateTime::now_utc(),
             matching_fee: Amount::from_sat(value.matching_fee_sats as u64),
+            index_price: value
+                .index_price
+                .map(|price| Decimal::from_f32(price).expect("to fit into decimal")),
+            spread_bps: value.spread_bps as u32,
         }
     }
 }
@@ -1,3 +1,5 @@
 pub mod custom_types;
+pub mod journal;
 pub mod matches;
+pub mod order_fills;
 pub mod orders;
@@ -0,0 +1,125 @@
+use crate::orderbook::analytics::OrderFill;
+use crate::orderbook::db::custom_types::OrderType;
+use crate::schema::order_fills;
+use diesel::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons;
+
+#[derive(Queryable, Debug, Clone)]
+struct OrderFillRecord {
+    id: i32,
+    order_id: Uuid,
+    trader_pubkey: String,
+    order_type: OrderType,
+    quantity: f32,
+    execution_price: f32,
+    index_price: Option<f32>,
+    slippage_bps: Option<f32>,
+    queue_position: Option<i32>,
+    time_to_fill_ms: i64,
+    placed_at: OffsetDateTime,
+    matched_at: OffsetDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = order_fills)]
+struct NewOrderFill {
+    order_id: Uuid,
+    trader_pubkey: String,
+    order_type: OrderType,
+    quantity: f32,
+    execution_price: f32,
+    index_price: Option<f32>,
+    slippage_bps: Option<f32>,
+    queue_position: Option<i32>,
+    time_to_fill_ms: i64,
+    placed_at: OffsetDateTime,
+    matched_at: OffsetDateTime,
+}
+
+/// The analytics recorded when an order was first matched.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderFillAnalytics {
+    pub id: i32,
+    pub order_id: Uuid,
+    pub trader_pubkey: String,
+    pub order_type: commons::OrderType,
+    pub quantity: f32,
+    pub execution_price: f32,
+    pub index_price: Option<f32>,
+    pub slippage_bps: Option<f32>,
+    pub queue_position: Option<i32>,
+    pub time_to_fill_ms: i64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub placed_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub matched_at: OffsetDateTime,
+}
+
+/// Insert the analytics of an order fill.
+///
+/// Only the first match of an order is recorded, i.e. the insert is ignored if there already are
+/// analytics for the same order.
+pub fn insert(conn: &mut PgConnection, fill: &OrderFill) -> QueryResult<()> {
+    diesel::insert_into(order_fills::table)
+        .values(NewOrderFill::from(fill))
+        .on_conflict(order_fills::order_id)
+        .do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Get the analytics of the `limit` most recent order fills, most recent first.
+pub fn get_latest(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<OrderFillAnalytics>> {
+    let fills: Vec<OrderFillRecord> = order_fills::table
+        .order_by(order_fills::matched_at.desc())
+        .limit(limit)
+        .load(conn)?;
+
+    Ok(fills.into_iter().map(OrderFillAnalytics::from).collect())
+}
+
+impl From<&OrderFill> for NewOrderFill {
+    fn from(fill: &OrderFill) -> Self {
+        NewOrderFill {
+            order_id: fill.order_id,
+            trader_pubkey: fill.trader_pubkey.to_string(),
+            order_type: fill.order_type.into(),
+            quantity: fill.quantity.to_f32().expect("to fit into f32"),
+            execution_price: fill.execution_price.to_f32().expect("to fit into f32"),
+            index_price: fill
+                .index_price
+                .map(|price| price.to_f32().expect("to fit into f32")),
+            slippage_bps: fill
+                .slippage_bps()
+                .map(|slippage| slippage.to_f32().expect("to fit into f32")),
+            queue_position: fill.queue_position.map(|position| position as i32),
+            time_to_fill_ms: fill.time_to_fill().whole_milliseconds() as i64,
+            placed_at: fill.placed_at,
+            matched_at: fill.matched_at,
+        }
+    }
+}
+
+impl From<OrderFillRecord> for OrderFillAnalytics {
+    fn from(value: OrderFillRecord) -> Self {
+        OrderFillAnalytics {
+            id: value.id,
+            order_id: value.order_id,
+            trader_pubkey: value.trader_pubkey,
+            order_type: value.order_type.into(),
+            quantity: value.quantity,
+            execution_price: value.execution_price,
+            index_price: value.index_price,
+            slippage_bps: value.slippage_bps,
+            queue_position: value.queue_position,
+            time_to_fill_ms: value.time_to_fill_ms,
+            placed_at: value.placed_at,
+            matched_at: value.matched_at,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::db::positions::ContractSymbol;
+use crate::orderbook::db::custom_types::ClientKind;
 use crate::orderbook::db::custom_types::Direction;
 use crate::orderbook::db::custom_types::MatchState;
 use crate::orderbook::db::custom_types::OrderReason;
@@ -7,8 +8,11 @@ use crate::orderbook::db::custom_types::OrderType;
 use crate::schema::matches;
 use crate::schema::orders;
 use bitcoin::secp256k1::PublicKey;
+use diesel::dsl::count_star;
 use diesel::dsl::max;
 use diesel::dsl::min;
+use diesel::dsl::sum;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::QueryResult;
 use diesel::PgConnection;
@@ -19,13 +23,18 @@ use time::OffsetDateTime;
 use uuid::Uuid;
 use xxi_node::commons;
 use xxi_node::commons::BestPrice;
+use xxi_node::commons::ClientInfo;
 use xxi_node::commons::Direction as OrderbookDirection;
 use xxi_node::commons::NewLimitOrder;
 use xxi_node::commons::NewMarketOrder;
 use xxi_node::commons::Order as OrderbookOrder;
+use xxi_node::commons::OrderFilter;
 use xxi_node::commons::OrderReason as OrderBookOrderReason;
 use xxi_node::commons::OrderState as OrderBookOrderState;
 use xxi_node::commons::OrderType as OrderBookOrderType;
+use xxi_node::commons::Page;
+use xxi_node::commons::PageParams;
+use xxi_node::commons::SortOrder;
 
 impl From<commons::Direction> for Direction {
     fn from(value: commons::Direction) -> Self {
@@ -89,6 +98,26 @@ impl From<OrderBookOrderState> for OrderState {
     }
 }
 
+impl From<commons::ClientKind> for ClientKind {
+    fn from(value: commons::ClientKind) -> Self {
+        match value {
+            commons::ClientKind::App => ClientKind::App,
+            commons::ClientKind::Webapp => ClientKind::Webapp,
+            commons::ClientKind::Sdk => ClientKind::Sdk,
+        }
+    }
+}
+
+impl From<ClientKind> for commons::ClientKind {
+    fn from(value: ClientKind) -> Self {
+        match value {
+            ClientKind::App => commons::ClientKind::App,
+            ClientKind::Webapp => commons::ClientKind::Webapp,
+            ClientKind::Sdk => commons::ClientKind::Sdk,
+        }
+    }
+}
+
 #[derive(Queryable, Debug, Clone)]
 struct Order {
     // this id is only internally but needs to be here or diesel complains
@@ -107,6 +136,12 @@ struct Order {
     pub leverage: f32,
     pub order_reason: OrderReason,
     pub stable: bool,
+    pub display_quantity: Option<f32>,
+    #[allow(dead_code)]
+    pub client_kind: Option<ClientKind>,
+    #[allow(dead_code)]
+    pub client_version: Option<String>,
+    pub contract_expiry: Option<OffsetDateTime>,
 }
 
 impl From<Order> for OrderbookOrder {
@@ -126,6 +161,10 @@ impl From<Order> for OrderbookOrder {
             order_state: value.order_state.into(),
             order_reason: value.order_reason.into(),
             stable: value.stable,
+            display_quantity: value.display_quantity.map(|display_quantity| {
+                Decimal::from_f32(display_quantity).expect("To be able to convert f32 to decimal")
+            }),
+            contract_expiry: value.contract_expiry,
         }
     }
 }
@@ -137,6 +176,7 @@ impl From<OrderReason> for OrderBookOrderReason {
             OrderReason::Expired => OrderBookOrderReason::Expired,
             OrderReason::TraderLiquidated => OrderBookOrderReason::TraderLiquidated,
             OrderReason::CoordinatorLiquidated => OrderBookOrderReason::CoordinatorLiquidated,
+            OrderReason::ChannelMigration => OrderBookOrderReason::ChannelMigration,
         }
     }
 }
@@ -148,6 +188,7 @@ impl From<OrderBookOrderReason> for OrderReason {
             OrderBookOrderReason::Expired => OrderReason::Expired,
             OrderBookOrderReason::TraderLiquidated => OrderReason::TraderLiquidated,
             OrderBookOrderReason::CoordinatorLiquidated => OrderReason::CoordinatorLiquidated,
+            OrderBookOrderReason::ChannelMigration => OrderReason::ChannelMigration,
         }
     }
 }
@@ -166,6 +207,25 @@ struct NewOrder {
     pub contract_symbol: ContractSymbol,
     pub leverage: f32,
     pub stable: bool,
+    pub display_quantity: Option<f32>,
+    pub client_kind: Option<ClientKind>,
+    pub client_version: Option<String>,
+    pub contract_expiry: Option<OffsetDateTime>,
+}
+
+impl NewOrder {
+    fn with_client_info(self, client_info: Option<ClientInfo>) -> Self {
+        let (client_kind, client_version) = match client_info {
+            Some(ClientInfo { kind, version }) => (Some(kind.into()), Some(version)),
+            None => (None, None),
+        };
+
+        NewOrder {
+            client_kind,
+            client_version,
+            ..self
+        }
+    }
 }
 
 impl From<NewLimitOrder> for NewOrder {
@@ -193,6 +253,15 @@ impl From<NewLimitOrder> for NewOrder {
                 .to_f32()
                 .expect("To be able to convert decimal to f32"),
             stable: value.stable,
+            display_quantity: value.display_quantity.map(|display_quantity| {
+                display_quantity
+                    .round_dp(2)
+                    .to_f32()
+                    .expect("To be able to convert decimal to f32")
+            }),
+            client_kind: None,
+            client_version: None,
+            contract_expiry: value.contract_expiry,
         }
     }
 }
@@ -219,6 +288,10 @@ impl From<NewMarketOrder> for NewOrder {
                 .to_f32()
                 .expect("To be able to convert decimal to f32"),
             stable: value.stable,
+            display_quantity: None,
+            client_kind: None,
+            client_version: None,
+            contract_expiry: value.contract_expiry,
         }
     }
 }
@@ -302,26 +375,105 @@ pub fn get_best_ask_price(
     Ok(price.map(|ask| Decimal::try_from(ask).expect("to fit into decimal")))
 }
 
-pub fn get_all_orders(
+/// All orders of the given trader which are still in progress, i.e. open, matched or taken, newest
+/// first.
+pub fn get_open_orders_by_trader_id(
     conn: &mut PgConnection,
-    order_type: OrderBookOrderType,
-    order_state: OrderBookOrderState,
-    filter_expired: bool,
+    trader_id: PublicKey,
 ) -> QueryResult<Vec<OrderbookOrder>> {
-    let filters = orders::table
-        .filter(orders::order_state.eq(OrderState::from(order_state)))
-        .filter(orders::order_type.eq(OrderType::from(order_type)));
-    let orders: Vec<Order> = if filter_expired {
-        filters
-            .filter(orders::expiry.gt(OffsetDateTime::now_utc()))
-            .load::<Order>(conn)?
-    } else {
-        filters.load::<Order>(conn)?
-    };
+    let orders: Vec<Order> = orders::table
+        .filter(orders::trader_id.eq(trader_id.to_string()))
+        .filter(orders::order_state.eq_any([
+            OrderState::Open,
+            OrderState::Matched,
+            OrderState::Taken,
+        ]))
+        .order_by(orders::timestamp.desc())
+        .load::<Order>(conn)?;
 
     Ok(orders.into_iter().map(OrderbookOrder::from).collect())
 }
 
+/// A page of the open limit orders which have not expired yet.
+pub fn get_open_limit_orders_page(
+    conn: &mut PgConnection,
+    filter: OrderFilter,
+    page: &PageParams<Uuid>,
+) -> QueryResult<Page<OrderbookOrder, Uuid>> {
+    let query = orders::table
+        .filter(orders::order_state.eq(OrderState::Open))
+        .filter(orders::order_type.eq(OrderType::Limit))
+        .filter(orders::expiry.gt(OffsetDateTime::now_utc()))
+        .into_boxed();
+
+    load_page(conn, query, filter, page)
+}
+
+/// A page of the orders of the given trader which are still in progress, i.e. open, matched or
+/// taken.
+pub fn get_open_orders_page_by_trader_id(
+    conn: &mut PgConnection,
+    trader_id: PublicKey,
+    filter: OrderFilter,
+    page: &PageParams<Uuid>,
+) -> QueryResult<Page<OrderbookOrder, Uuid>> {
+    let query = orders::table
+        .filter(orders::trader_id.eq(trader_id.to_string()))
+        .filter(orders::order_state.eq_any([
+            OrderState::Open,
+            OrderState::Matched,
+            OrderState::Taken,
+        ]))
+        .into_boxed();
+
+    load_page(conn, query, filter, page)
+}
+
+/// Load the page of the filtered orders, ordered by their timestamp and id.
+fn load_page(
+    conn: &mut PgConnection,
+    mut query: orders::BoxedQuery<'_, Pg>,
+    filter: OrderFilter,
+    page: &PageParams<Uuid>,
+) -> QueryResult<Page<OrderbookOrder, Uuid>> {
+    if let Some(contract_symbol) = filter.contract_symbol {
+        query = query.filter(orders::contract_symbol.eq(ContractSymbol::from(contract_symbol)));
+    }
+
+    if let Some(direction) = filter.direction {
+        query = query.filter(orders::direction.eq(Direction::from(direction)));
+    }
+
+    if let Some(after) = page.after {
+        query = match page.sort {
+            SortOrder::Asc => query.filter(
+                orders::timestamp.gt(after.timestamp).or(orders::timestamp
+                    .eq(after.timestamp)
+                    .and(orders::trader_order_id.gt(after.id))),
+            ),
+            SortOrder::Desc => query.filter(
+                orders::timestamp.lt(after.timestamp).or(orders::timestamp
+                    .eq(after.timestamp)
+                    .and(orders::trader_order_id.lt(after.id))),
+            ),
+        };
+    }
+
+    query = match page.sort {
+        SortOrder::Asc => query.order_by((orders::timestamp.asc(), orders::trader_order_id.asc())),
+        SortOrder::Desc => {
+            query.order_by((orders::timestamp.desc(), orders::trader_order_id.desc()))
+        }
+    };
+
+    let limit = page.limit();
+    let orders: Vec<Order> = query.limit(limit + 1).load(conn)?;
+
+    let orders = orders.into_iter().map(OrderbookOrder::from).collect();
+
+    Ok(Page::new(orders, limit, OrderbookOrder::cursor))
+}
+
 pub fn get_all_matched_market_orders_by_order_reason(
     conn: &mut PgConnection,
     order_reasons: Vec<commons::OrderReason>,
@@ -348,11 +500,13 @@ pub fn insert_limit_order(
     order: NewLimitOrder,
     // TODO: All limit orders are "manual".
     order_reason: OrderBookOrderReason,
+    client_info: Option<ClientInfo>,
 ) -> QueryResult<OrderbookOrder> {
     let new_order = NewOrder {
         order_reason: OrderReason::from(order_reason),
         ..NewOrder::from(order)
-    };
+    }
+    .with_client_info(client_info);
     let order: Order = diesel::insert_into(orders::table)
         .values(new_order)
         .get_result(conn)?;
@@ -365,11 +519,13 @@ pub fn insert_market_order(
     conn: &mut PgConnection,
     order: NewMarketOrder,
     order_reason: OrderBookOrderReason,
+    client_info: Option<ClientInfo>,
 ) -> QueryResult<OrderbookOrder> {
     let new_order = NewOrder {
         order_reason: OrderReason::from(order_reason),
         ..NewOrder::from(order)
-    };
+    }
+    .with_client_info(client_info);
     let order: Order = diesel::insert_into(orders::table)
         .values(new_order)
         .get_result(conn)?;
@@ -390,35 +546,44 @@ pub fn set_is_taken(
     }
 }
 
-/// Mark an order as [`OrderState::Deleted`], if it belongs to the given `trader_id`.
-pub fn delete_trader_order(
+/// Mark an order as [`OrderState::Deleted`].
+pub fn delete(conn: &mut PgConnection, id: Uuid) -> QueryResult<OrderbookOrder> {
+    set_order_state(conn, id, commons::OrderState::Deleted)
+}
+
+/// Returns the number of affected rows: 1.
+pub fn set_order_state(
     conn: &mut PgConnection,
     id: Uuid,
-    trader_id: PublicKey,
+    order_state: commons::OrderState,
 ) -> QueryResult<OrderbookOrder> {
     let order: Order = diesel::update(orders::table)
         .filter(orders::trader_order_id.eq(id))
-        .filter(orders::trader_id.eq(trader_id.to_string()))
-        .set(orders::order_state.eq(OrderState::Deleted))
+        .set((orders::order_state.eq(OrderState::from(order_state)),))
         .get_result(conn)?;
 
     Ok(OrderbookOrder::from(order))
 }
 
-/// Mark an order as [`OrderState::Deleted`].
-pub fn delete(conn: &mut PgConnection, id: Uuid) -> QueryResult<OrderbookOrder> {
-    set_order_state(conn, id, commons::OrderState::Deleted)
-}
-
-/// Returns the number of affected rows: 1.
-pub fn set_order_state(
+/// Updates the quantity left of a partially filled iceberg order, which stays open.
+///
+/// The `timestamp` determines the time priority of the order, which is lost once its displayed
+/// quantity is replenished.
+pub fn set_remaining_quantity(
     conn: &mut PgConnection,
     id: Uuid,
-    order_state: commons::OrderState,
+    quantity: Decimal,
+    timestamp: OffsetDateTime,
 ) -> QueryResult<OrderbookOrder> {
     let order: Order = diesel::update(orders::table)
         .filter(orders::trader_order_id.eq(id))
-        .set((orders::order_state.eq(OrderState::from(order_state)),))
+        .set((
+            orders::quantity.eq(quantity
+                .round_dp(2)
+                .to_f32()
+                .expect("To be able to convert decimal to f32")),
+            orders::timestamp.eq(timestamp),
+        ))
         .get_result(conn)?;
 
     Ok(OrderbookOrder::from(order))
@@ -511,3 +676,91 @@ pub fn get_all_limit_order_filled_matches(
 
     Ok(filled_matches)
 }
+
+/// The orders of a client in a given state. The client is unknown for orders submitted by older
+/// clients and for orders created by the coordinator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientOrderCount {
+    pub client_kind: Option<commons::ClientKind>,
+    pub client_version: Option<String>,
+    pub order_state: OrderBookOrderState,
+    pub count: i64,
+}
+
+/// The filled matches of the orders of a client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientMatchCount {
+    pub client_kind: Option<commons::ClientKind>,
+    pub client_version: Option<String>,
+    pub count: i64,
+    pub quantity: Decimal,
+}
+
+/// The number of orders placed since `since`, by the client which submitted them and their
+/// current state.
+pub fn count_orders_by_client(
+    conn: &mut PgConnection,
+    since: OffsetDateTime,
+) -> QueryResult<Vec<ClientOrderCount>> {
+    let rows = orders::table
+        .filter(orders::timestamp.ge(since))
+        .group_by((
+            orders::client_kind,
+            orders::client_version,
+            orders::order_state,
+        ))
+        .select((
+            orders::client_kind,
+            orders::client_version,
+            orders::order_state,
+            count_star(),
+        ))
+        .load::<(Option<ClientKind>, Option<String>, OrderState, i64)>(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(client_kind, client_version, order_state, count)| ClientOrderCount {
+                client_kind: client_kind.map(commons::ClientKind::from),
+                client_version,
+                order_state: order_state.into(),
+                count,
+            },
+        )
+        .collect())
+}
+
+/// The number of filled matches created since `since` and their total quantity, by the client
+/// which submitted the matched order.
+pub fn count_filled_matches_by_client(
+    conn: &mut PgConnection,
+    since: OffsetDateTime,
+) -> QueryResult<Vec<ClientMatchCount>> {
+    let rows = matches::table
+        .inner_join(orders::table.on(orders::trader_order_id.eq(matches::order_id)))
+        .filter(matches::created_at.ge(since))
+        .filter(matches::match_state.eq(MatchState::Filled))
+        .group_by((orders::client_kind, orders::client_version))
+        .select((
+            orders::client_kind,
+            orders::client_version,
+            count_star(),
+            sum(matches::quantity),
+        ))
+        .load::<(Option<ClientKind>, Option<String>, i64, Option<f32>)>(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(client_kind, client_version, count, quantity)| ClientMatchCount {
+                client_kind: client_kind.map(commons::ClientKind::from),
+                client_version,
+                count,
+                quantity: quantity
+                    .and_then(Decimal::from_f32)
+                    .unwrap_or_default()
+                    .round_dp(2),
+            },
+        )
+        .collect())
+}
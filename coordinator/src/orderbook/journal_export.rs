@@ -0,0 +1,407 @@
+//! Daily exports of the orderbook journal, for research on the full history of the orderbook.
+//!
+//! Every event applied by the matching engine, i.e. every added, deleted, expired, matched and
+//! rejected order, is written to one zstd-compressed Parquet file per day (UTC), in the order the
+//! events were applied. Each row is one [`JournalEntry`], with the event type and the order id in
+//! their own columns and the full event as JSON, see [`schema`].
+//!
+//! Exports are stored in the data directory of the coordinator, where admins can download them.
+//! They are kept independently of the journal, so that a day can still be downloaded after its
+//! events have been pruned from the journal.
+
+use crate::orderbook::db::journal;
+use crate::orderbook::db::journal::JournalEntry;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use arrow_array::ArrayRef;
+use arrow_array::Int64Array;
+use arrow_array::RecordBatch;
+use arrow_array::StringArray;
+use arrow_array::TimestampMicrosecondArray;
+use arrow_schema::DataType;
+use arrow_schema::Field;
+use arrow_schema::Schema;
+use arrow_schema::SchemaRef;
+use arrow_schema::TimeUnit;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use parking_lot::RwLock;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::basic::ZstdLevel;
+use parquet::file::properties::WriterProperties;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fs;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+use time::macros::format_description;
+use time::Date;
+use time::Duration;
+use time::OffsetDateTime;
+use time::Time;
+use tokio::task::spawn_blocking;
+
+/// How many journal entries are loaded from the DB at once.
+const BATCH_SIZE: i64 = 10_000;
+
+const EXTENSION: &str = "parquet";
+
+time::serde::format_description!(day, Date, "[year]-[month]-[day]");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalExportSettings {
+    /// Whether the previous day is exported by the scheduled job. Admins can export any day
+    /// regardless.
+    pub enabled: bool,
+    /// How long exports are kept before they are deleted.
+    pub retention_days: u32,
+}
+
+/// An exported day of the orderbook journal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JournalExport {
+    #[serde(with = "day")]
+    pub date: Date,
+    pub size_bytes: u64,
+}
+
+/// Writes the orderbook journal to daily export files, see the [module docs](self).
+#[derive(Clone)]
+pub struct JournalExporter {
+    pool: Pool<ConnectionManager<PgConnection>>,
+    dir: PathBuf,
+    settings: Arc<RwLock<JournalExportSettings>>,
+}
+
+impl JournalExporter {
+    pub fn new(
+        pool: Pool<ConnectionManager<PgConnection>>,
+        dir: PathBuf,
+        settings: JournalExportSettings,
+    ) -> Self {
+        Self {
+            pool,
+            dir,
+            settings: Arc::new(RwLock::new(settings)),
+        }
+    }
+
+    pub fn update_settings(&self, settings: JournalExportSettings) {
+        *self.settings.write() = settings;
+    }
+
+    /// Export the previous day and delete the exports which are older than the retention period.
+    pub async fn export_previous_day(&self) -> Result<()> {
+        let settings = self.settings.read().clone();
+        if !settings.enabled {
+            return Ok(());
+        }
+
+        let today = OffsetDateTime::now_utc().date();
+
+        let export = self
+            .export(today.previous_day().expect("a previous day"))
+            .await?;
+        tracing::info!(date = %export.date, size_bytes = export.size_bytes, "Exported orderbook journal");
+
+        let deleted = self.delete_before(today - Duration::days(settings.retention_days as i64))?;
+        if deleted > 0 {
+            tracing::info!(deleted, "Deleted old orderbook journal exports");
+        }
+
+        Ok(())
+    }
+
+    /// Export the events of the given day, replacing an earlier export of the same day.
+    ///
+    /// Days which have not ended yet cannot be exported, as the export would be incomplete.
+    pub async fn export(&self, date: Date) -> Result<JournalExport> {
+        let from = date.with_time(Time::MIDNIGHT).assume_utc();
+        let to = from + Duration::days(1);
+
+        if to > OffsetDateTime::now_utc() {
+            bail!("Cannot export {date} before the day has ended");
+        }
+
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create export directory {}", self.dir.display()))?;
+
+        let path = self.path(date);
+        let tmp_path = path.with_extension("tmp");
+
+        spawn_blocking({
+            let pool = self.pool.clone();
+            let tmp_path = tmp_path.clone();
+            move || {
+                let mut conn = pool.get()?;
+
+                let file = File::create(&tmp_path)?;
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::ZSTD(ZstdLevel::default()))
+                    .build();
+                let mut writer = ArrowWriter::try_new(file, schema(), Some(properties))?;
+
+                let mut sequence = 0;
+                loop {
+                    let entries = journal::get_between(&mut conn, from, to, sequence, BATCH_SIZE)?;
+
+                    if !entries.is_empty() {
+                        writer.write(&to_record_batch(&entries)?)?;
+                    }
+
+                    match entries.last() {
+                        Some(last) if entries.len() as i64 == BATCH_SIZE => {
+                            sequence = last.sequence;
+                        }
+                        _ => break,
+                    }
+                }
+
+                writer.close()?;
+
+                anyhow::Ok(())
+            }
+        })
+        .await
+        .expect("task to complete")
+        .with_context(|| format!("Failed to export orderbook journal of {date}"))?;
+
+        // Only complete exports are ever listed or downloaded.
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(JournalExport {
+            date,
+            size_bytes: fs::metadata(&path)?.len(),
+        })
+    }
+
+    /// All exports, newest first.
+    pub fn list(&self) -> Result<Vec<JournalExport>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut exports = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+
+            let file_name = entry.file_name();
+            let Some(date) = file_name.to_str().and_then(parse_file_name) else {
+                continue;
+            };
+
+            exports.push(JournalExport {
+                date,
+                size_bytes: entry.metadata()?.len(),
+            });
+        }
+
+        exports.sort_by(|a, b| b.date.cmp(&a.date));
+
+        Ok(exports)
+    }
+
+    /// The Parquet export of the given day, if there is one.
+    pub fn read(&self, date: Date) -> Result<Option<Vec<u8>>> {
+        let path = self.path(date);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let export =
+            fs::read(&path).with_context(|| format!("Failed to read export {}", path.display()))?;
+
+        Ok(Some(export))
+    }
+
+    /// Delete the exports of the days before the given date. Returns the number of deleted
+    /// exports.
+    fn delete_before(&self, date: Date) -> Result<usize> {
+        let expired = self
+            .list()?
+            .into_iter()
+            .filter(|export| export.date < date)
+            .collect::<Vec<_>>();
+
+        for export in expired.iter() {
+            fs::remove_file(self.path(export.date))?;
+        }
+
+        Ok(expired.len())
+    }
+
+    fn path(&self, date: Date) -> PathBuf {
+        self.dir.join(file_name(date))
+    }
+}
+
+/// The name of the export file of the given day, e.g. `orderbook_journal-2024-07-24.parquet`.
+pub fn file_name(date: Date) -> String {
+    let date = date
+        .format(format_description!("[year]-[month]-[day]"))
+        .expect("to format date");
+
+    format!("orderbook_journal-{date}.{EXTENSION}")
+}
+
+fn parse_file_name(file_name: &str) -> Option<Date> {
+    let date = file_name
+        .strip_prefix("orderbook_journal-")?
+        .strip_suffix(&format!(".{EXTENSION}"))?;
+
+    Date::parse(date, format_description!("[year]-[month]-[day]")).ok()
+}
+
+/// The columns of the export files.
+///
+/// - `sequence`: The sequence number of the event in the journal.
+/// - `created_at`: When the event was applied, in microseconds since the epoch (UTC).
+/// - `event_type`: The kind of event, e.g. `OrderAdded` or `OrderMatched`.
+/// - `order_id`: The order the event applies to. For a match, this is the market order.
+/// - `event`: The full [`OrderbookEvent`] as JSON, e.g. including the added order or the ids of
+///   the matched limit orders.
+///
+/// [`OrderbookEvent`]: crate::orderbook::trading::OrderbookEvent
+pub fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("sequence", DataType::Int64, false),
+        Field::new(
+            "created_at",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("event_type", DataType::Utf8, false),
+        Field::new("order_id", DataType::Utf8, false),
+        Field::new("event", DataType::Utf8, false),
+    ]))
+}
+
+/// Convert the entries into the rows of an export file, see [`schema`].
+fn to_record_batch(entries: &[JournalEntry]) -> Result<RecordBatch> {
+    let sequences = entries
+        .iter()
+        .map(|entry| entry.sequence)
+        .collect::<Vec<_>>();
+    let created_at = entries
+        .iter()
+        .map(|entry| (entry.created_at.unix_timestamp_nanos() / 1_000) as i64)
+        .collect::<Vec<_>>();
+    let event_types = entries
+        .iter()
+        .map(|entry| entry.event.event_type())
+        .collect::<Vec<_>>();
+    let order_ids = entries
+        .iter()
+        .map(|entry| entry.event.order_id().to_string())
+        .collect::<Vec<_>>();
+    let events = entries
+        .iter()
+        .map(|entry| serde_json::to_string(&entry.event))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to serialize orderbook event")?;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from(sequences)),
+        Arc::new(TimestampMicrosecondArray::from(created_at).with_timezone("UTC")),
+        Arc::new(StringArray::from(event_types)),
+        Arc::new(StringArray::from(order_ids)),
+        Arc::new(StringArray::from(events)),
+    ];
+
+    let batch = RecordBatch::try_new(schema(), columns)?;
+
+    Ok(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::trading::OrderbookEvent;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use time::macros::date;
+    use time::macros::datetime;
+    use uuid::Uuid;
+
+    #[test]
+    fn file_name_roundtrip() {
+        let file_name = file_name(date!(2024 - 07 - 24));
+
+        assert_eq!(file_name, "orderbook_journal-2024-07-24.parquet");
+        assert_eq!(parse_file_name(&file_name), Some(date!(2024 - 07 - 24)));
+        assert_eq!(parse_file_name("orderbook_journal-2024-07-24.tmp"), None);
+    }
+
+    #[test]
+    fn entries_roundtrip_through_parquet() {
+        let order_id = Uuid::nil();
+        let maker_order_id = Uuid::from_u128(1);
+        let entries = [
+            JournalEntry {
+                sequence: 1,
+                event: OrderbookEvent::OrderDeleted { order_id },
+                created_at: datetime!(2024-07-24 12:00 UTC),
+            },
+            JournalEntry {
+                sequence: 2,
+                event: OrderbookEvent::OrderMatched {
+                    order_id,
+                    maker_order_ids: vec![maker_order_id],
+                },
+                created_at: datetime!(2024-07-24 12:00:00.000001 UTC),
+            },
+        ];
+
+        let path = std::env::temp_dir().join(format!("{}.parquet", Uuid::new_v4()));
+
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), schema(), None).unwrap();
+        writer.write(&to_record_batch(&entries).unwrap()).unwrap();
+        writer.close().unwrap();
+
+        let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(batch.num_rows(), 2);
+
+        let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+
+        let sequences = column("sequence");
+        let sequences = sequences.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(sequences.values(), &[1, 2]);
+
+        let created_at = column("created_at");
+        let created_at = created_at
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        assert_eq!(created_at.value(1) - created_at.value(0), 1);
+        assert_eq!(created_at.value(0), 1_721_822_400_000_000);
+
+        let event_types = column("event_type");
+        let event_types = event_types.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(event_types.value(0), "OrderDeleted");
+        assert_eq!(event_types.value(1), "OrderMatched");
+
+        let order_ids = column("order_id");
+        let order_ids = order_ids.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(order_ids.value(1), order_id.to_string());
+
+        let events = column("event");
+        let events = events.as_any().downcast_ref::<StringArray>().unwrap();
+        let event = serde_json::from_str::<serde_json::Value>(events.value(1)).unwrap();
+        assert_eq!(event, serde_json::to_value(&entries[1].event).unwrap());
+    }
+}
@@ -0,0 +1,252 @@
+use bitcoin::secp256k1::PublicKey;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashSet;
+use xxi_node::commons::Direction;
+use xxi_node::commons::Order;
+
+/// How the matching engine chooses between resting limit orders which could all fill a market
+/// order.
+///
+/// By default, the best price wins. With the inventory-aware preference enabled, fills which
+/// reduce the coordinator's net exposure are preferred, as long as their price is not much worse
+/// than the best price.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct MatchingPreferenceSettings {
+    /// Whether to prefer fills which reduce the coordinator's net exposure.
+    pub reduce_exposure: bool,
+    /// How much worse than the best price a preferred fill may be for the taker, in basis points.
+    pub max_price_deviation_bps: u32,
+}
+
+/// What the matching engine knows about the coordinator's inventory when matching a market order.
+#[derive(Debug, Clone)]
+pub struct Inventory {
+    /// The net exposure of the coordinator in the market, in contracts. Positive if the
+    /// coordinator is long.
+    pub net_exposure: Decimal,
+    /// The makers the coordinator is the counterparty of, e.g. traders quoting from the app.
+    ///
+    /// Filling a taker with the order of such a maker crosses two of our traders, leaving the
+    /// coordinator's net exposure untouched.
+    pub crossing_makers: HashSet<PublicKey>,
+}
+
+impl Inventory {
+    /// The net exposure of the coordinator after filling the `taker` with the order of `maker`.
+    fn exposure_after(&self, taker: &Order, maker: &Order) -> Decimal {
+        if self.crossing_makers.contains(&maker.trader_id) {
+            return self.net_exposure;
+        }
+
+        // The coordinator takes the other side of the taker.
+        match taker.direction {
+            Direction::Long => self.net_exposure - taker.quantity,
+            Direction::Short => self.net_exposure + taker.quantity,
+        }
+    }
+}
+
+/// Move the limit orders which reduce the coordinator's net exposure the most to the front.
+///
+/// The `limit_orders` are expected to be sorted by price-time priority already, see
+/// [`crate::orderbook::trading::sort_orders`]. Only orders which can fill the taker on their own
+/// and whose price is within [`MatchingPreferenceSettings::max_price_deviation_bps`] of the best
+/// price are considered. Among equally good candidates, price-time priority is preserved.
+pub fn prefer_exposure_reducing(
+    limit_orders: Vec<Order>,
+    taker: &Order,
+    inventory: &Inventory,
+    settings: MatchingPreferenceSettings,
+) -> Vec<Order> {
+    if !settings.reduce_exposure {
+        return limit_orders;
+    }
+
+    let best_price = match limit_orders.first() {
+        Some(order) => order.price,
+        None => return limit_orders,
+    };
+    let max_deviation =
+        best_price * Decimal::from(settings.max_price_deviation_bps) / Decimal::from(10_000);
+
+    let (mut candidates, others): (Vec<_>, Vec<_>) = limit_orders.into_iter().partition(|order| {
+        let deviation = match taker.direction {
+            Direction::Long => order.price - best_price,
+            Direction::Short => best_price - order.price,
+        };
+
+        order.quantity >= taker.quantity && deviation <= max_deviation
+    });
+
+    // The sort is stable, hence price-time priority is kept among equally good candidates.
+    candidates.sort_by_key(|order| inventory.exposure_after(taker, order).abs());
+
+    candidates.into_iter().chain(others).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::Duration;
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+    use xxi_node::commons::ContractSymbol;
+    use xxi_node::commons::OrderReason;
+    use xxi_node::commons::OrderState;
+    use xxi_node::commons::OrderType;
+
+    const ENABLED: MatchingPreferenceSettings = MatchingPreferenceSettings {
+        reduce_exposure: true,
+        max_price_deviation_bps: 10,
+    };
+
+    #[test]
+    fn prefers_crossing_maker_if_taker_increases_exposure() {
+        let external = order(Direction::Short, dec!(50_000), dec!(100), external_maker());
+        let crossing = order(Direction::Short, dec!(50_020), dec!(100), crossing_maker());
+        let taker = order(Direction::Long, dec!(0), dec!(100), taker_id());
+
+        // The coordinator is already short, so taking the other side of another long is riskier.
+        let inventory = Inventory {
+            net_exposure: dec!(-1_000),
+            crossing_makers: HashSet::from([crossing_maker()]),
+        };
+
+        let orders = prefer_exposure_reducing(
+            vec![external.clone(), crossing.clone()],
+            &taker,
+            &inventory,
+            ENABLED,
+        );
+
+        assert_eq!(orders, vec![crossing, external]);
+    }
+
+    #[test]
+    fn prefers_external_maker_if_taker_reduces_exposure() {
+        let crossing = order(Direction::Short, dec!(50_000), dec!(100), crossing_maker());
+        let external = order(Direction::Short, dec!(50_020), dec!(100), external_maker());
+        let taker = order(Direction::Long, dec!(0), dec!(100), taker_id());
+
+        let inventory = Inventory {
+            net_exposure: dec!(1_000),
+            crossing_makers: HashSet::from([crossing_maker()]),
+        };
+
+        let orders = prefer_exposure_reducing(
+            vec![crossing.clone(), external.clone()],
+            &taker,
+            &inventory,
+            ENABLED,
+        );
+
+        assert_eq!(orders, vec![external, crossing]);
+    }
+
+    #[test]
+    fn does_not_prefer_orders_with_much_worse_price() {
+        let external = order(Direction::Short, dec!(50_000), dec!(100), external_maker());
+        let crossing = order(Direction::Short, dec!(50_100), dec!(100), crossing_maker());
+        let taker = order(Direction::Long, dec!(0), dec!(100), taker_id());
+
+        let inventory = Inventory {
+            net_exposure: dec!(-1_000),
+            crossing_makers: HashSet::from([crossing_maker()]),
+        };
+
+        let orders = prefer_exposure_reducing(
+            vec![external.clone(), crossing.clone()],
+            &taker,
+            &inventory,
+            ENABLED,
+        );
+
+        assert_eq!(orders, vec![external, crossing]);
+    }
+
+    #[test]
+    fn does_not_prefer_orders_too_small_to_fill_taker() {
+        let external = order(Direction::Long, dec!(50_000), dec!(100), external_maker());
+        let crossing = order(Direction::Long, dec!(50_000), dec!(50), crossing_maker());
+        let taker = order(Direction::Short, dec!(0), dec!(100), taker_id());
+
+        let inventory = Inventory {
+            net_exposure: dec!(1_000),
+            crossing_makers: HashSet::from([crossing_maker()]),
+        };
+
+        let orders = prefer_exposure_reducing(
+            vec![external.clone(), crossing.clone()],
+            &taker,
+            &inventory,
+            ENABLED,
+        );
+
+        assert_eq!(orders, vec![external, crossing]);
+    }
+
+    #[test]
+    fn keeps_price_time_priority_if_disabled() {
+        let external = order(Direction::Short, dec!(50_000), dec!(100), external_maker());
+        let crossing = order(Direction::Short, dec!(50_000), dec!(100), crossing_maker());
+        let taker = order(Direction::Long, dec!(0), dec!(100), taker_id());
+
+        let inventory = Inventory {
+            net_exposure: dec!(-1_000),
+            crossing_makers: HashSet::from([crossing_maker()]),
+        };
+
+        let orders = prefer_exposure_reducing(
+            vec![external.clone(), crossing.clone()],
+            &taker,
+            &inventory,
+            MatchingPreferenceSettings::default(),
+        );
+
+        assert_eq!(orders, vec![external, crossing]);
+    }
+
+    fn order(
+        direction: Direction,
+        price: Decimal,
+        quantity: Decimal,
+        trader_id: PublicKey,
+    ) -> Order {
+        Order {
+            id: Uuid::new_v4(),
+            price,
+            trader_id,
+            direction,
+            leverage: 1.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity,
+            order_type: OrderType::Limit,
+            timestamp: OffsetDateTime::now_utc(),
+            expiry: OffsetDateTime::now_utc() + Duration::minutes(1),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        }
+    }
+
+    fn external_maker() -> PublicKey {
+        PublicKey::from_str("027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007")
+            .unwrap()
+    }
+
+    fn crossing_maker() -> PublicKey {
+        PublicKey::from_str("02d5aa8fce495f6301b466594af056a46104dcdc6d735ec4793aa43108854cbd4a")
+            .unwrap()
+    }
+
+    fn taker_id() -> PublicKey {
+        PublicKey::from_str("0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166")
+            .unwrap()
+    }
+}
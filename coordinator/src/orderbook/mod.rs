@@ -1,6 +1,17 @@
+pub mod analytics;
 pub mod async_match;
+pub mod book;
+pub mod cancel_on_disconnect;
 pub mod collaborative_revert;
+pub mod contract_expiry;
 pub mod db;
+pub mod journal_export;
+pub mod matching_preference;
+pub mod open_order_policy;
+pub mod order_flow;
+pub mod order_simulation;
+pub mod outbound_queue;
+pub mod spread;
 pub mod trading;
 pub mod websocket;
 
@@ -0,0 +1,219 @@
+//! Limits on the orders a trader may have open at the same time, e.g. to prevent traders from
+//! accidentally stacking market orders while the app is lagging.
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use xxi_node::commons::Direction;
+use xxi_node::commons::NewOrder;
+use xxi_node::commons::Order;
+use xxi_node::commons::OrderType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OpenOrderPolicySettings {
+    /// Whether new orders are validated against the open orders of the trader.
+    pub enabled: bool,
+    /// The max number of market orders a trader may have in progress per side, including the new
+    /// order.
+    pub max_open_market_orders_per_side: usize,
+    /// The max total quantity of the orders a trader may have in progress per side, including the
+    /// new order. Unlimited if not set.
+    pub max_open_quantity_per_side: Option<u64>,
+    /// Whether the whitelisted makers are exempt from the policy, as they quote on both sides with
+    /// many limit orders.
+    pub exempt_makers: bool,
+}
+
+/// The new order would exceed one of the limits of the [`OpenOrderPolicySettings`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum OpenOrderPolicyViolation {
+    #[error(
+        "Too many open {direction} market orders: {open} are in progress, at most {max} are allowed"
+    )]
+    TooManyMarketOrders {
+        direction: Direction,
+        open: usize,
+        max: usize,
+    },
+    #[error(
+        "Too much open {direction} quantity: {quantity} contracts including the new order, at most \
+         {max} are allowed"
+    )]
+    QuantityExceeded {
+        direction: Direction,
+        quantity: Decimal,
+        max: u64,
+    },
+}
+
+impl OpenOrderPolicySettings {
+    /// Validate the `new_order` against the orders of the trader which are still in progress.
+    ///
+    /// Only the open orders on the same side as the new order are considered.
+    pub fn validate(
+        &self,
+        new_order: &NewOrder,
+        open_orders: &[Order],
+    ) -> Result<(), OpenOrderPolicyViolation> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let direction = new_order.direction();
+        let same_side = open_orders
+            .iter()
+            .filter(|order| order.direction == direction)
+            .filter(|order| order.id != new_order.id());
+
+        if let NewOrder::Market(_) = new_order {
+            let open = same_side
+                .clone()
+                .filter(|order| order.order_type == OrderType::Market)
+                .count();
+
+            if open + 1 > self.max_open_market_orders_per_side {
+                return Err(OpenOrderPolicyViolation::TooManyMarketOrders {
+                    direction,
+                    open,
+                    max: self.max_open_market_orders_per_side,
+                });
+            }
+        }
+
+        if let Some(max) = self.max_open_quantity_per_side {
+            let quantity =
+                same_side.map(|order| order.quantity).sum::<Decimal>() + new_order.quantity();
+
+            if quantity > Decimal::from(max) {
+                return Err(OpenOrderPolicyViolation::QuantityExceeded {
+                    direction,
+                    quantity,
+                    max,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::PublicKey;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::Duration;
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+    use xxi_node::commons::ContractSymbol;
+    use xxi_node::commons::NewMarketOrder;
+    use xxi_node::commons::OrderReason;
+    use xxi_node::commons::OrderState;
+
+    const POLICY: OpenOrderPolicySettings = OpenOrderPolicySettings {
+        enabled: true,
+        max_open_market_orders_per_side: 1,
+        max_open_quantity_per_side: Some(1_000),
+        exempt_makers: true,
+    };
+
+    #[test]
+    fn rejects_second_market_order_on_the_same_side() {
+        let open_orders = [order(Direction::Long, OrderType::Market, dec!(100))];
+
+        let result = POLICY.validate(&new_market_order(Direction::Long, dec!(100)), &open_orders);
+
+        assert_eq!(
+            result,
+            Err(OpenOrderPolicyViolation::TooManyMarketOrders {
+                direction: Direction::Long,
+                open: 1,
+                max: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn accepts_market_order_on_the_other_side() {
+        let open_orders = [order(Direction::Long, OrderType::Market, dec!(100))];
+
+        let result = POLICY.validate(&new_market_order(Direction::Short, dec!(100)), &open_orders);
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn rejects_order_exceeding_open_quantity_per_side() {
+        let open_orders = [
+            order(Direction::Short, OrderType::Limit, dec!(600)),
+            order(Direction::Long, OrderType::Limit, dec!(900)),
+        ];
+
+        let result = POLICY.validate(&new_market_order(Direction::Short, dec!(500)), &open_orders);
+
+        assert_eq!(
+            result,
+            Err(OpenOrderPolicyViolation::QuantityExceeded {
+                direction: Direction::Short,
+                quantity: dec!(1_100),
+                max: 1_000,
+            })
+        );
+    }
+
+    #[test]
+    fn accepts_any_order_if_disabled() {
+        let open_orders = [order(Direction::Long, OrderType::Market, dec!(1_000))];
+        let policy = OpenOrderPolicySettings {
+            enabled: false,
+            ..POLICY
+        };
+
+        let result = policy.validate(
+            &new_market_order(Direction::Long, dec!(1_000)),
+            &open_orders,
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    fn new_market_order(direction: Direction, quantity: Decimal) -> NewOrder {
+        NewOrder::Market(NewMarketOrder {
+            id: Uuid::new_v4(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity,
+            trader_id: trader_id(),
+            direction,
+            leverage: dec!(2),
+            expiry: OffsetDateTime::now_utc() + Duration::minutes(1),
+            stable: false,
+            contract_expiry: None,
+        })
+    }
+
+    fn order(direction: Direction, order_type: OrderType, quantity: Decimal) -> Order {
+        Order {
+            id: Uuid::new_v4(),
+            price: dec!(50_000),
+            trader_id: trader_id(),
+            direction,
+            leverage: 2.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity,
+            order_type,
+            timestamp: OffsetDateTime::now_utc(),
+            expiry: OffsetDateTime::now_utc() + Duration::minutes(1),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        }
+    }
+
+    fn trader_id() -> PublicKey {
+        PublicKey::from_str("0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166")
+            .unwrap()
+    }
+}
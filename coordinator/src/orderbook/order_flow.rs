@@ -0,0 +1,205 @@
+//! The order flow broken down by the client which submitted the orders, i.e. the app, the webapp
+//! or the SDK, and its version.
+
+use crate::orderbook::db::orders;
+use crate::orderbook::db::orders::ClientMatchCount;
+use crate::orderbook::db::orders::ClientOrderCount;
+use anyhow::Result;
+use diesel::PgConnection;
+use lazy_static::lazy_static;
+use prometheus::register_int_counter_vec;
+use prometheus::IntCounterVec;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use time::OffsetDateTime;
+use xxi_node::commons::ClientInfo;
+use xxi_node::commons::ClientKind;
+use xxi_node::commons::OrderState;
+
+lazy_static! {
+    static ref ORDERS: IntCounterVec = register_int_counter_vec!(
+        "coordinator_orders_total",
+        "Number of orders placed by traders, by the client which submitted them.",
+        &["client_kind", "client_version"]
+    )
+    .expect("valid metric");
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderFlowReport {
+    #[serde(with = "time::serde::rfc3339")]
+    pub since: OffsetDateTime,
+    /// Ordered by the number of orders, descending.
+    pub clients: Vec<ClientOrderFlow>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ClientOrderFlow {
+    /// None for older clients and for orders created by the coordinator, e.g. on expiry.
+    pub client_kind: Option<ClientKind>,
+    pub client_version: Option<String>,
+    pub orders: i64,
+    pub matched_orders: i64,
+    pub failed_orders: i64,
+    pub filled_matches: i64,
+    /// The total quantity of the filled matches, in contracts.
+    pub matched_quantity: Decimal,
+}
+
+/// Count an order placed by a trader in the `coordinator_orders_total` metric.
+pub fn record_order(client_info: Option<&ClientInfo>) {
+    let (kind, version) = match client_info {
+        Some(client_info) => (client_info.kind.to_string(), client_info.version.as_str()),
+        None => ("unknown".to_string(), "unknown"),
+    };
+
+    ORDERS.with_label_values(&[&kind, version]).inc();
+}
+
+/// The orders placed and the matches created since `since`, per client.
+pub fn get_order_flow_report(
+    conn: &mut PgConnection,
+    since: OffsetDateTime,
+) -> Result<OrderFlowReport> {
+    let orders = orders::count_orders_by_client(conn, since)?;
+    let matches = orders::count_filled_matches_by_client(conn, since)?;
+
+    Ok(OrderFlowReport {
+        since,
+        clients: order_flow_by_client(orders, matches),
+    })
+}
+
+fn order_flow_by_client(
+    orders: Vec<ClientOrderCount>,
+    matches: Vec<ClientMatchCount>,
+) -> Vec<ClientOrderFlow> {
+    let mut clients: HashMap<(Option<ClientKind>, Option<String>), ClientOrderFlow> =
+        HashMap::new();
+
+    for order in orders {
+        let client = clients
+            .entry((order.client_kind, order.client_version.clone()))
+            .or_insert_with(|| ClientOrderFlow {
+                client_kind: order.client_kind,
+                client_version: order.client_version,
+                ..ClientOrderFlow::default()
+            });
+
+        client.orders += order.count;
+        match order.order_state {
+            OrderState::Matched => client.matched_orders += order.count,
+            OrderState::Failed => client.failed_orders += order.count,
+            _ => {}
+        }
+    }
+
+    for filled in matches {
+        let client = clients
+            .entry((filled.client_kind, filled.client_version.clone()))
+            .or_insert_with(|| ClientOrderFlow {
+                client_kind: filled.client_kind,
+                client_version: filled.client_version,
+                ..ClientOrderFlow::default()
+            });
+
+        client.filled_matches += filled.count;
+        client.matched_quantity += filled.quantity;
+    }
+
+    let mut clients = clients.into_values().collect::<Vec<_>>();
+    clients.sort_by(|a, b| {
+        b.orders
+            .cmp(&a.orders)
+            .then_with(|| a.client_version.cmp(&b.client_version))
+    });
+
+    clients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn app(version: &str) -> (Option<ClientKind>, Option<String>) {
+        (Some(ClientKind::App), Some(version.to_string()))
+    }
+
+    fn order_count(
+        (client_kind, client_version): (Option<ClientKind>, Option<String>),
+        order_state: OrderState,
+        count: i64,
+    ) -> ClientOrderCount {
+        ClientOrderCount {
+            client_kind,
+            client_version,
+            order_state,
+            count,
+        }
+    }
+
+    #[test]
+    fn order_flow_is_broken_down_by_client_and_version() {
+        let orders = vec![
+            order_count(app("1.9.0"), OrderState::Matched, 3),
+            order_count(app("1.9.0"), OrderState::Failed, 1),
+            order_count(app("1.8.0"), OrderState::Matched, 1),
+            order_count((None, None), OrderState::Matched, 2),
+        ];
+        let matches = vec![ClientMatchCount {
+            client_kind: Some(ClientKind::App),
+            client_version: Some("1.9.0".to_string()),
+            count: 3,
+            quantity: dec!(300),
+        }];
+
+        let clients = order_flow_by_client(orders, matches);
+
+        assert_eq!(
+            clients,
+            vec![
+                ClientOrderFlow {
+                    client_kind: Some(ClientKind::App),
+                    client_version: Some("1.9.0".to_string()),
+                    orders: 4,
+                    matched_orders: 3,
+                    failed_orders: 1,
+                    filled_matches: 3,
+                    matched_quantity: dec!(300),
+                },
+                ClientOrderFlow {
+                    client_kind: None,
+                    client_version: None,
+                    orders: 2,
+                    matched_orders: 2,
+                    ..ClientOrderFlow::default()
+                },
+                ClientOrderFlow {
+                    client_kind: Some(ClientKind::App),
+                    client_version: Some("1.8.0".to_string()),
+                    orders: 1,
+                    matched_orders: 1,
+                    ..ClientOrderFlow::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_of_orders_placed_before_the_report_are_included() {
+        let matches = vec![ClientMatchCount {
+            client_kind: Some(ClientKind::Sdk),
+            client_version: Some("0.1.0".to_string()),
+            count: 1,
+            quantity: dec!(50),
+        }];
+
+        let clients = order_flow_by_client(vec![], matches);
+
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].orders, 0);
+        assert_eq!(clients[0].filled_matches, 1);
+    }
+}
@@ -0,0 +1,174 @@
+//! Lets makers preview how a hypothetical market order would be matched with the current book.
+//!
+//! The order is matched by the matching engine like any other market order, but nothing is
+//! committed. As a simulation is as expensive as matching an actual order, every trader may only
+//! run a limited number of them per minute.
+
+use crate::orderbook::trading::MatchParams;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use time::Duration;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::Order;
+use xxi_node::commons::OrderReason;
+use xxi_node::commons::OrderSimulation;
+use xxi_node::commons::OrderState;
+use xxi_node::commons::OrderType;
+use xxi_node::commons::SimulateOrderParams;
+use xxi_node::commons::SimulatedFill;
+
+/// The number of simulations a trader may run within [`RATE_LIMIT_WINDOW`].
+const MAX_SIMULATIONS_PER_WINDOW: usize = 30;
+
+const RATE_LIMIT_WINDOW: Duration = Duration::minutes(1);
+
+/// Limits the simulations per trader within a sliding window.
+#[derive(Clone, Default)]
+pub struct SimulationRateLimiter {
+    simulations: Arc<Mutex<HashMap<PublicKey, VecDeque<OffsetDateTime>>>>,
+}
+
+impl SimulationRateLimiter {
+    /// Record a simulation of the trader, unless the trader reached the limit already.
+    ///
+    /// Returns whether the simulation may be run.
+    pub fn try_acquire(&self, trader_id: PublicKey, now: OffsetDateTime) -> bool {
+        let mut simulations = self.simulations.lock();
+
+        // Forget about traders who did not simulate anything recently.
+        simulations.retain(|_, timestamps| {
+            timestamps
+                .back()
+                .is_some_and(|last| *last > now - RATE_LIMIT_WINDOW)
+        });
+
+        let timestamps = simulations.entry(trader_id).or_default();
+        while timestamps
+            .front()
+            .is_some_and(|first| *first <= now - RATE_LIMIT_WINDOW)
+        {
+            timestamps.pop_front();
+        }
+
+        if timestamps.len() >= MAX_SIMULATIONS_PER_WINDOW {
+            return false;
+        }
+
+        timestamps.push_back(now);
+
+        true
+    }
+}
+
+/// The market order to hand to the matching engine for the simulation.
+pub fn hypothetical_order(
+    trader_id: PublicKey,
+    params: &SimulateOrderParams,
+    now: OffsetDateTime,
+) -> Order {
+    Order {
+        id: Uuid::new_v4(),
+        price: Decimal::ZERO,
+        leverage: 1.0,
+        contract_symbol: params.contract_symbol,
+        trader_id,
+        direction: params.direction,
+        quantity: params.quantity,
+        order_type: OrderType::Market,
+        timestamp: now,
+        expiry: now + Duration::minutes(1),
+        order_state: OrderState::Open,
+        order_reason: OrderReason::Manual,
+        stable: false,
+        display_quantity: None,
+        contract_expiry: params.contract_expiry,
+    }
+}
+
+/// Summarize the fills of the simulated order from the point of view of the taker.
+pub fn summarize(order: &Order, matched_orders: Option<&MatchParams>) -> OrderSimulation {
+    let fills = matched_orders
+        .map(|matched_orders| {
+            matched_orders
+                .taker_match
+                .filled_with
+                .matches
+                .iter()
+                .map(|m| SimulatedFill {
+                    quantity: m.quantity,
+                    execution_price: m.execution_price,
+                    matching_fee: m.matching_fee,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let filled_quantity = fills.iter().map(|fill| fill.quantity).sum::<Decimal>();
+
+    let average_execution_price = (filled_quantity > Decimal::ZERO).then(|| {
+        fills
+            .iter()
+            .map(|fill| fill.execution_price * fill.quantity)
+            .sum::<Decimal>()
+            / filled_quantity
+    });
+
+    OrderSimulation {
+        unfilled_quantity: (order.quantity - filled_quantity).max(Decimal::ZERO),
+        filled_quantity,
+        average_execution_price,
+        matching_fee: fills.iter().map(|fill| fill.matching_fee).sum::<Amount>(),
+        fills,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::SecretKey;
+    use bitcoin::secp256k1::SECP256K1;
+
+    fn trader(byte: u8) -> PublicKey {
+        SecretKey::from_slice(&[byte; 32])
+            .unwrap()
+            .public_key(SECP256K1)
+    }
+
+    #[test]
+    fn rate_limit_applies_per_trader_within_window() {
+        let limiter = SimulationRateLimiter::default();
+        let now = OffsetDateTime::now_utc();
+
+        for _ in 0..MAX_SIMULATIONS_PER_WINDOW {
+            assert!(limiter.try_acquire(trader(1), now));
+        }
+        assert!(!limiter.try_acquire(trader(1), now));
+        assert!(limiter.try_acquire(trader(2), now));
+
+        assert!(limiter.try_acquire(trader(1), now + RATE_LIMIT_WINDOW));
+    }
+
+    #[test]
+    fn summary_without_match_fills_nothing() {
+        let params = SimulateOrderParams {
+            contract_symbol: xxi_node::commons::ContractSymbol::BtcUsd,
+            direction: xxi_node::commons::Direction::Long,
+            quantity: Decimal::from(100),
+            contract_expiry: None,
+        };
+        let order = hypothetical_order(trader(1), &params, OffsetDateTime::now_utc());
+
+        let simulation = summarize(&order, None);
+
+        assert!(simulation.fills.is_empty());
+        assert_eq!(simulation.unfilled_quantity, Decimal::from(100));
+        assert_eq!(simulation.average_execution_price, None);
+        assert_eq!(simulation.matching_fee, Amount::ZERO);
+    }
+}
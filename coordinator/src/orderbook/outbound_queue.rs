@@ -0,0 +1,358 @@
+//! The outbound queue of a single websocket connection.
+//!
+//! Every message for a client goes through the queue of its connection, so that a client on a
+//! slow connection only delays its own messages. Sending never waits for the client. Once the
+//! queue is full:
+//!
+//! - Orderbook updates are dropped. Instead, the client is sent a fresh snapshot of the orderbook
+//!   once it has caught up.
+//! - Messages which only matter in their latest version, e.g. the next funding rate, replace the
+//!   queued message of the same kind. They do so even if the queue is not full.
+//! - Trade-critical messages are never dropped. A client which can't keep up with those is
+//!   disconnected.
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use prometheus::register_int_counter;
+use prometheus::register_int_counter_vec;
+use prometheus::IntCounter;
+use prometheus::IntCounterVec;
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Notify;
+use xxi_node::commons::Message;
+
+/// The number of messages which may be waiting for a client.
+pub const OUTBOUND_QUEUE_CAPACITY: usize = 100;
+
+lazy_static! {
+    static ref OUTBOUND_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "coordinator_websocket_outbound_messages_total",
+        "Messages for websocket clients, by whether they were queued, coalesced with a queued \
+         message or dropped.",
+        &["outcome"]
+    )
+    .expect("valid metric");
+    static ref SLOW_CLIENT_DISCONNECTS: IntCounter = register_int_counter!(
+        "coordinator_websocket_slow_client_disconnects_total",
+        "Number of websocket clients which were disconnected because they could not keep up."
+    )
+    .expect("valid metric");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Websocket client is disconnected")]
+pub struct Disconnected;
+
+/// What to send to the client next.
+#[derive(Debug)]
+pub enum Outbound {
+    Message(Message),
+    /// Orderbook updates were dropped, so the client needs a snapshot of the orderbook.
+    Resync,
+    /// The client can't keep up and has to be disconnected.
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// A change to the orderbook, which is covered by a later snapshot of the orderbook.
+    OrderbookUpdate,
+    /// A message of which only the latest one is of interest.
+    LatestWins,
+    /// A message which must be delivered, e.g. about a trade.
+    Critical,
+}
+
+impl Kind {
+    fn of(message: &Message) -> Self {
+        match message {
+            Message::AllOrders(_)
+            | Message::NewOrder(_)
+            | Message::DeleteOrder(_)
+            | Message::Update(_) => Kind::OrderbookUpdate,
+            Message::NextFundingRate(_) | Message::KillSwitch(_) => Kind::LatestWins,
+            Message::InvalidAuthentication(_)
+            | Message::Authenticated(_)
+            | Message::DlcChannelCollaborativeRevert { .. }
+            | Message::TradeError { .. }
+            | Message::LnPaymentReceived { .. }
+            | Message::RolloverError { .. }
+            | Message::FundingFeeEvent(_)
+            | Message::AllFundingFeeEvents(_)
+            | Message::AsyncMatch { .. }
+            | Message::ExpirySettlement(_) => Kind::Critical,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    messages: VecDeque<Message>,
+    /// Whether orderbook updates were dropped. Until the snapshot is sent, later orderbook updates
+    /// are dropped too.
+    resync: bool,
+    disconnected: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    notify: Notify,
+    capacity: usize,
+}
+
+#[derive(Clone)]
+pub struct OutboundSender(Arc<Shared>);
+
+pub struct OutboundReceiver(Arc<Shared>);
+
+pub fn outbound_queue(capacity: usize) -> (OutboundSender, OutboundReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State::default()),
+        notify: Notify::new(),
+        capacity,
+    });
+
+    (OutboundSender(shared.clone()), OutboundReceiver(shared))
+}
+
+impl OutboundSender {
+    /// Queue a message for the client, without waiting for the client.
+    ///
+    /// Fails if the client is disconnected, including if it was disconnected because queuing
+    /// this message would have exceeded the capacity of the queue.
+    pub fn send(&self, message: Message) -> Result<(), Disconnected> {
+        let mut state = self.0.state.lock();
+        if state.disconnected {
+            return Err(Disconnected);
+        }
+
+        let kind = Kind::of(&message);
+
+        if kind == Kind::LatestWins {
+            let queued = state
+                .messages
+                .iter_mut()
+                .find(|queued| mem::discriminant(*queued) == mem::discriminant(&message));
+            if let Some(queued) = queued {
+                *queued = message;
+                OUTBOUND_MESSAGES.with_label_values(&["coalesced"]).inc();
+                return Ok(());
+            }
+        }
+
+        if state.messages.len() >= self.0.capacity {
+            let queued = state.messages.len();
+            state
+                .messages
+                .retain(|message| Kind::of(message) != Kind::OrderbookUpdate);
+
+            let dropped = queued - state.messages.len();
+            if dropped > 0 {
+                state.resync = true;
+                OUTBOUND_MESSAGES
+                    .with_label_values(&["dropped"])
+                    .inc_by(dropped as u64);
+            }
+        }
+
+        if kind == Kind::OrderbookUpdate
+            && (state.resync || state.messages.len() >= self.0.capacity)
+        {
+            state.resync = true;
+            OUTBOUND_MESSAGES.with_label_values(&["dropped"]).inc();
+            self.0.notify.notify_one();
+            return Ok(());
+        }
+
+        if state.messages.len() >= self.0.capacity {
+            tracing::warn!(
+                queued = state.messages.len(),
+                "Websocket client can't keep up, disconnecting"
+            );
+
+            state.messages.clear();
+            state.disconnected = true;
+            SLOW_CLIENT_DISCONNECTS.inc();
+            self.0.notify.notify_one();
+
+            return Err(Disconnected);
+        }
+
+        state.messages.push_back(message);
+        OUTBOUND_MESSAGES.with_label_values(&["queued"]).inc();
+        self.0.notify.notify_one();
+
+        Ok(())
+    }
+
+    /// Send the client a snapshot of the orderbook, e.g. because orderbook updates were
+    /// missed before reaching the queue.
+    pub fn resync(&self) {
+        self.0.state.lock().resync = true;
+        self.0.notify.notify_one();
+    }
+}
+
+impl OutboundReceiver {
+    /// Wait for what to send to the client next.
+    ///
+    /// The snapshot of the orderbook is only requested once all queued messages were sent.
+    pub async fn recv(&self) -> Outbound {
+        loop {
+            {
+                let mut state = self.0.state.lock();
+                if state.disconnected {
+                    return Outbound::Disconnect;
+                }
+
+                if let Some(message) = state.messages.pop_front() {
+                    return Outbound::Message(message);
+                }
+
+                if state.resync {
+                    state.resync = false;
+                    return Outbound::Resync;
+                }
+            }
+
+            self.0.notify.notified().await;
+        }
+    }
+}
+
+impl Drop for OutboundReceiver {
+    fn drop(&mut self) {
+        self.0.state.lock().disconnected = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+    use xxi_node::commons::FundingRate;
+    use xxi_node::commons::TradingError;
+
+    fn orderbook_update() -> Message {
+        Message::DeleteOrder(Uuid::new_v4())
+    }
+
+    fn trade_error() -> Message {
+        Message::TradeError {
+            order_id: Uuid::new_v4(),
+            error: TradingError::Other("test".to_string()),
+            localized: None,
+        }
+    }
+
+    fn funding_rate(rate: rust_decimal::Decimal) -> Message {
+        let now = OffsetDateTime::now_utc();
+        Message::NextFundingRate(FundingRate::new(rate, now, now))
+    }
+
+    async fn recv_all(receiver: &OutboundReceiver) -> Vec<Outbound> {
+        let mut outbound = vec![];
+        while let Ok(next) =
+            tokio::time::timeout(std::time::Duration::from_millis(10), receiver.recv()).await
+        {
+            outbound.push(next);
+        }
+
+        outbound
+    }
+
+    #[tokio::test]
+    async fn messages_are_delivered_in_order() {
+        let (sender, receiver) = outbound_queue(10);
+
+        sender.send(orderbook_update()).unwrap();
+        sender.send(trade_error()).unwrap();
+
+        let outbound = recv_all(&receiver).await;
+
+        assert!(matches!(
+            outbound[..],
+            [
+                Outbound::Message(Message::DeleteOrder(_)),
+                Outbound::Message(Message::TradeError { .. })
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn orderbook_updates_are_replaced_by_a_snapshot_when_full() {
+        let (sender, receiver) = outbound_queue(3);
+
+        sender.send(orderbook_update()).unwrap();
+        sender.send(trade_error()).unwrap();
+        sender.send(orderbook_update()).unwrap();
+        // The queue is full.
+        sender.send(orderbook_update()).unwrap();
+        // Updates are dropped until the snapshot is sent.
+        sender.send(orderbook_update()).unwrap();
+        sender.send(trade_error()).unwrap();
+
+        let outbound = recv_all(&receiver).await;
+
+        assert!(matches!(
+            outbound[..],
+            [
+                Outbound::Message(Message::TradeError { .. }),
+                Outbound::Message(Message::TradeError { .. }),
+                Outbound::Resync
+            ]
+        ));
+
+        // Once the snapshot is sent, updates are queued again.
+        sender.send(orderbook_update()).unwrap();
+        assert!(matches!(
+            recv_all(&receiver).await[..],
+            [Outbound::Message(Message::DeleteOrder(_))]
+        ));
+    }
+
+    #[tokio::test]
+    async fn only_the_latest_funding_rate_is_delivered() {
+        let (sender, receiver) = outbound_queue(10);
+
+        sender.send(funding_rate(dec!(0.001))).unwrap();
+        sender.send(trade_error()).unwrap();
+        sender.send(funding_rate(dec!(0.002))).unwrap();
+
+        let outbound = recv_all(&receiver).await;
+
+        assert_eq!(outbound.len(), 2);
+        match &outbound[0] {
+            Outbound::Message(Message::NextFundingRate(funding_rate)) => {
+                assert_eq!(funding_rate.rate(), dec!(0.002))
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_which_cannot_keep_up_with_critical_messages_is_disconnected() {
+        let (sender, receiver) = outbound_queue(2);
+
+        sender.send(trade_error()).unwrap();
+        sender.send(trade_error()).unwrap();
+
+        assert_eq!(sender.send(trade_error()), Err(Disconnected));
+        assert!(matches!(receiver.recv().await, Outbound::Disconnect));
+        assert_eq!(sender.send(orderbook_update()), Err(Disconnected));
+    }
+
+    #[tokio::test]
+    async fn sending_fails_once_the_connection_is_gone() {
+        let (sender, receiver) = outbound_queue(2);
+
+        drop(receiver);
+
+        assert_eq!(sender.send(trade_error()), Err(Disconnected));
+    }
+}
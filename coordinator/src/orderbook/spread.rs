@@ -0,0 +1,99 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use xxi_node::commons::Direction;
+
+/// The highest spread we ever apply to an execution price, regardless of the configured spread.
+pub const MAX_SPREAD_BPS: u32 = 100;
+
+/// The spread added to the execution price of market orders, e.g. during volatile periods.
+///
+/// The spread is taken from the trader: a long market order is executed above and a short market
+/// order below the price of the limit order it was matched with. The maker's execution price is
+/// not affected.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct SpreadSettings {
+    /// The spread applied to long market orders, in basis points.
+    pub long_bps: u32,
+    /// The spread applied to short market orders, in basis points.
+    pub short_bps: u32,
+}
+
+impl SpreadSettings {
+    /// The spread to apply to a market order in the given [`Direction`], bounded by
+    /// [`MAX_SPREAD_BPS`].
+    pub fn spread_bps(&self, direction: Direction) -> u32 {
+        let spread_bps = match direction {
+            Direction::Long => self.long_bps,
+            Direction::Short => self.short_bps,
+        };
+
+        if spread_bps > MAX_SPREAD_BPS {
+            tracing::warn!(
+                %spread_bps,
+                max_spread_bps = MAX_SPREAD_BPS,
+                ?direction,
+                "Configured spread exceeds the maximum spread"
+            );
+        }
+
+        spread_bps.min(MAX_SPREAD_BPS)
+    }
+}
+
+/// Apply a spread of `spread_bps` to the matched `price`, to the disadvantage of a trader going
+/// in `direction`.
+///
+/// The result is rounded to the cent, so that clients can verify the execution price from the
+/// matched price and the spread.
+pub fn apply_spread(price: Decimal, direction: Direction, spread_bps: u32) -> Decimal {
+    let spread = price * Decimal::from(spread_bps) / Decimal::from(10_000);
+
+    let price = match direction {
+        Direction::Long => price + spread,
+        Direction::Short => price - spread,
+    };
+
+    price.round_dp(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn spread_is_applied_to_the_disadvantage_of_the_trader() {
+        assert_eq!(
+            apply_spread(dec!(50_000), Direction::Long, 10),
+            dec!(50_050)
+        );
+        assert_eq!(
+            apply_spread(dec!(50_000), Direction::Short, 10),
+            dec!(49_950)
+        );
+    }
+
+    #[test]
+    fn zero_spread_keeps_the_matched_price() {
+        assert_eq!(
+            apply_spread(dec!(50_000.5), Direction::Long, 0),
+            dec!(50_000.5)
+        );
+        assert_eq!(
+            apply_spread(dec!(50_000.5), Direction::Short, 0),
+            dec!(50_000.5)
+        );
+    }
+
+    #[test]
+    fn spread_is_bounded() {
+        let settings = SpreadSettings {
+            long_bps: 5,
+            short_bps: 10_000,
+        };
+
+        assert_eq!(settings.spread_bps(Direction::Long), 5);
+        assert_eq!(settings.spread_bps(Direction::Short), MAX_SPREAD_BPS);
+    }
+}
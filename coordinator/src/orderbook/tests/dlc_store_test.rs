@@ -0,0 +1,108 @@
+use crate::logger::init_tracing_for_test;
+use crate::orderbook::tests::setup_pool;
+use crate::orderbook::tests::start_postgres;
+use crate::storage::CoordinatorTenTenOneStorage;
+use crate::storage::PostgresStorageProvider;
+use std::fs;
+use testcontainers::clients::Cli;
+use xxi_node::storage::sled::SledStorageProvider;
+use xxi_node::storage::DlcStoreProvider;
+use xxi_node::storage::Op;
+
+#[tokio::test]
+async fn postgres_dlc_store_behaves_like_a_kv_store() {
+    init_tracing_for_test();
+
+    let docker = Cli::default();
+    let (_container, conn_spec) = start_postgres(&docker).unwrap();
+
+    let storage = PostgresStorageProvider::new(setup_pool(conn_spec));
+    assert!(storage.is_empty().unwrap());
+
+    storage
+        .write(1, b"contract".to_vec(), b"offered".to_vec())
+        .unwrap();
+    storage
+        .write(1, b"contract".to_vec(), b"signed".to_vec())
+        .unwrap();
+    storage
+        .write(2, b"channel-1".to_vec(), b"signed".to_vec())
+        .unwrap();
+    storage
+        .write(2, b"channel-2".to_vec(), b"closed".to_vec())
+        .unwrap();
+
+    let contracts = storage.read(1, None).unwrap();
+    assert_eq!(contracts.len(), 1);
+    assert_eq!(contracts[0].key, b"contract".to_vec());
+    assert_eq!(contracts[0].value, b"signed".to_vec());
+
+    let channel = storage.read(2, Some(b"channel-2".to_vec())).unwrap();
+    assert_eq!(channel.len(), 1);
+    assert_eq!(channel[0].value, b"closed".to_vec());
+    assert!(storage
+        .read(2, Some(b"unknown".to_vec()))
+        .unwrap()
+        .is_empty());
+
+    storage.delete(2, Some(b"channel-1".to_vec())).unwrap();
+    assert_eq!(storage.read(2, None).unwrap().len(), 1);
+
+    storage.delete(2, None).unwrap();
+    assert!(storage.read(2, None).unwrap().is_empty());
+    assert_eq!(storage.read(1, None).unwrap().len(), 1);
+
+    storage
+        .write_batch(vec![
+            Op::Delete {
+                kind: 1,
+                key: Some(b"contract".to_vec()),
+            },
+            Op::Write {
+                kind: 1,
+                key: b"final-contract".to_vec(),
+                value: b"signed".to_vec(),
+            },
+            Op::Write {
+                kind: 2,
+                key: b"channel".to_vec(),
+                value: b"signed".to_vec(),
+            },
+        ])
+        .unwrap();
+
+    let contracts = storage.read(1, None).unwrap();
+    assert_eq!(contracts.len(), 1);
+    assert_eq!(contracts[0].key, b"final-contract".to_vec());
+    assert_eq!(storage.read(2, None).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn existing_sled_storage_is_imported_into_postgres() {
+    init_tracing_for_test();
+
+    let docker = Cli::default();
+    let (_container, conn_spec) = start_postgres(&docker).unwrap();
+    let pool = setup_pool(conn_spec);
+
+    let dir = std::env::temp_dir().join(format!("dlc-store-{}", uuid::Uuid::new_v4()));
+    let data_dir = dir.to_string_lossy().to_string();
+
+    let sled = SledStorageProvider::new(&data_dir);
+    sled.write(1, b"contract".to_vec(), b"signed".to_vec())
+        .unwrap();
+    sled.write(2, b"channel".to_vec(), b"signed".to_vec())
+        .unwrap();
+    drop(sled);
+
+    let storage = CoordinatorTenTenOneStorage::postgres(data_dir.clone(), pool.clone()).unwrap();
+    assert_eq!(storage.read(1, None).unwrap().len(), 1);
+    assert_eq!(storage.read(2, None).unwrap().len(), 1);
+
+    // The sled storage is only imported as long as the database does not contain one yet.
+    storage.delete(2, None).unwrap();
+    let storage = CoordinatorTenTenOneStorage::postgres(data_dir, pool).unwrap();
+    assert!(storage.read(2, None).unwrap().is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
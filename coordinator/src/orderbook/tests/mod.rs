@@ -1,3 +1,4 @@
+mod dlc_store_test;
 mod registration_test;
 mod sample_test;
 
@@ -5,6 +6,7 @@ use crate::run_migration;
 use anyhow::Result;
 use diesel::r2d2;
 use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
 use diesel::r2d2::PooledConnection;
 use diesel::PgConnection;
 use testcontainers::clients::Cli;
@@ -39,7 +41,7 @@ pub fn start_postgres(docker: &Cli) -> Result<(Container<GenericImage>, String)>
     Ok((node, connection_string.clone()))
 }
 
-pub fn setup_db(db_url: String) -> PooledConnection<ConnectionManager<PgConnection>> {
+pub fn setup_pool(db_url: String) -> Pool<ConnectionManager<PgConnection>> {
     let manager = ConnectionManager::<PgConnection>::new(db_url);
     let pool = r2d2::Pool::builder()
         .build(manager)
@@ -47,5 +49,10 @@ pub fn setup_db(db_url: String) -> PooledConnection<ConnectionManager<PgConnecti
 
     let mut conn = pool.get().unwrap();
     run_migration(&mut conn);
-    conn
+
+    pool
+}
+
+pub fn setup_db(db_url: String) -> PooledConnection<ConnectionManager<PgConnection>> {
+    setup_pool(db_url).get().unwrap()
 }
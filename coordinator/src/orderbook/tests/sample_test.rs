@@ -29,6 +29,7 @@ async fn crud_test() {
         &mut conn,
         dummy_limit_order(OffsetDateTime::now_utc() + Duration::minutes(1)),
         OrderReason::Manual,
+        None,
     )
     .unwrap();
 
@@ -49,14 +50,14 @@ async fn test_all_limit_orders() {
     assert!(orders.is_empty());
 
     let order_1 = dummy_limit_order(OffsetDateTime::now_utc() + Duration::minutes(1));
-    orders::insert_limit_order(&mut conn, order_1, OrderReason::Manual).unwrap();
+    orders::insert_limit_order(&mut conn, order_1, OrderReason::Manual, None).unwrap();
 
     let order_2 = dummy_market_order(OffsetDateTime::now_utc() + Duration::minutes(1));
-    orders::insert_market_order(&mut conn, order_2, OrderReason::Manual).unwrap();
+    orders::insert_market_order(&mut conn, order_2, OrderReason::Manual, None).unwrap();
 
     let order_3 = dummy_limit_order(OffsetDateTime::now_utc() + Duration::minutes(1));
     let second_limit_order =
-        orders::insert_limit_order(&mut conn, order_3, OrderReason::Manual).unwrap();
+        orders::insert_limit_order(&mut conn, order_3, OrderReason::Manual, None).unwrap();
     orders::set_order_state(&mut conn, second_limit_order.id, OrderState::Failed).unwrap();
 
     let orders = orders::all_limit_orders(&mut conn).unwrap();
@@ -76,6 +77,7 @@ fn dummy_market_order(expiry: OffsetDateTime) -> NewMarketOrder {
         contract_symbol: commons::ContractSymbol::BtcUsd,
         leverage: dec!(1.0),
         stable: false,
+        contract_expiry: None,
     }
 }
 
@@ -93,5 +95,7 @@ fn dummy_limit_order(expiry: OffsetDateTime) -> NewLimitOrder {
         contract_symbol: commons::ContractSymbol::BtcUsd,
         leverage: dec!(1.0),
         stable: false,
+        display_quantity: None,
+        contract_expiry: None,
     }
 }
@@ -3,28 +3,45 @@ use crate::message::OrderbookMessage;
 use crate::node::Node;
 use crate::notifications::Notification;
 use crate::notifications::NotificationKind;
+use crate::orderbook::analytics;
+use crate::orderbook::book::remaining_iceberg;
+use crate::orderbook::book::L3Book;
+use crate::orderbook::book::OrderBooks;
+use crate::orderbook::db::journal;
 use crate::orderbook::db::matches;
 use crate::orderbook::db::orders;
+use crate::orderbook::matching_preference::prefer_exposure_reducing;
+use crate::orderbook::matching_preference::Inventory;
+use crate::orderbook::matching_preference::MatchingPreferenceSettings;
+use crate::orderbook::spread::apply_spread;
+use crate::orderbook::websocket::FeedMessage;
 use crate::referrals;
 use crate::trade::TradeExecutor;
 use crate::ChannelOpeningParams;
 use anyhow::anyhow;
 use anyhow::bail;
+use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::secp256k1::XOnlyPublicKey;
 use bitcoin::Amount;
-use bitcoin::Network;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::PooledConnection;
+use diesel::Connection;
+use diesel::PgConnection;
 use futures::future::RemoteHandle;
 use futures::FutureExt;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal::RoundingStrategy;
+use serde::Deserialize;
+use serde::Serialize;
 use std::cmp::Ordering;
 use time::OffsetDateTime;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 use tokio::task::spawn_blocking;
 use uuid::Uuid;
 use xxi_node::commons::ContractSymbol;
@@ -41,14 +58,70 @@ use xxi_node::commons::TradeAndChannelParams;
 use xxi_node::commons::TradeParams;
 use xxi_node::commons::TradingError;
 
-/// This value is arbitrarily set to 100 and defines the number of new order messages buffered in
+/// This value is arbitrarily set to 100 and defines the number of orderbook commands buffered in
 /// the channel.
-const NEW_ORDERS_BUFFER_SIZE: usize = 100;
+const ORDERBOOK_COMMANDS_BUFFER_SIZE: usize = 100;
 
 pub struct NewOrderMessage {
     pub order: Order,
     pub order_reason: OrderReason,
     pub channel_opening_params: Option<ChannelOpeningParams>,
+    /// If set, the market order is filled by the coordinator at this price without a matching fee,
+    /// instead of being matched with the book. Used to move a position without realizing a profit
+    /// or loss, e.g. when migrating a DLC channel.
+    pub fixed_price: Option<Decimal>,
+}
+
+/// The commands processed by the orderbook.
+///
+/// All commands are processed one after another by a single task, which is the only writer of the
+/// orderbook. Hence, two commands can never race for the same limit order.
+pub enum OrderbookCommand {
+    NewOrder(NewOrderMessage),
+    DeleteOrder {
+        order_id: Uuid,
+        /// If set, the order is only deleted if it belongs to this trader.
+        trader_id: Option<PublicKey>,
+        response: oneshot::Sender<Result<Order>>,
+    },
+    /// Delete all orders of the trader in all markets, responding with the deleted orders.
+    DeleteOrdersOfTrader {
+        trader_id: PublicKey,
+        response: oneshot::Sender<Result<Vec<Order>>>,
+    },
+    /// Read the book of a market as it is held in memory, without going through the DB.
+    GetL3Book {
+        contract_symbol: ContractSymbol,
+        response: oneshot::Sender<L3Book>,
+    },
+    /// Match a hypothetical market order with the book, without changing it.
+    SimulateOrder {
+        order: Order,
+        response: oneshot::Sender<Result<Option<MatchParams>>>,
+    },
+}
+
+/// The changes applied to the orderbook, in the order they were applied.
+///
+/// Every event is recorded in the orderbook journal, in the same DB transaction as the change
+/// itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderbookEvent {
+    /// A limit order was added to the book.
+    OrderAdded { order: Order },
+    /// A limit order was deleted by its maker.
+    OrderDeleted { order_id: Uuid },
+    /// A limit order was removed from the book, because it expired.
+    OrderExpired { order_id: Uuid },
+    /// A market order was matched with the given limit orders, which were removed from the book.
+    ///
+    /// Iceberg orders with quantity left stay in the book.
+    OrderMatched {
+        order_id: Uuid,
+        maker_order_ids: Vec<Uuid>,
+    },
+    /// A market order could not be matched.
+    OrderRejected { order_id: Uuid, reason: String },
 }
 
 #[derive(Clone)]
@@ -63,80 +136,46 @@ pub struct TraderMatchParams {
     pub filled_with: FilledWith,
 }
 
-/// Spawn a task that processes [`NewOrderMessage`]s.
+/// Spawn the task that processes [`OrderbookCommand`]s.
 ///
-/// To feed messages to this task, the caller can use the corresponding
-/// [`mpsc::Sender<NewOrderMessage>`] returned.
+/// The open limit orders of all markets are loaded into memory once and only modified by this task
+/// afterwards. Every market has its own book, so orders are only ever matched with orders of the
+/// same [`ContractSymbol`].
+/// To feed commands to this task, the caller can use the corresponding
+/// [`mpsc::Sender<OrderbookCommand>`] returned.
 pub fn start(
     node: Node,
-    tx_orderbook_feed: broadcast::Sender<Message>,
+    tx_orderbook_feed: broadcast::Sender<FeedMessage>,
     trade_notifier: mpsc::Sender<OrderbookMessage>,
     notifier: mpsc::Sender<Notification>,
-    network: Network,
     oracle_pk: XOnlyPublicKey,
-) -> (RemoteHandle<()>, mpsc::Sender<NewOrderMessage>) {
-    let (sender, mut receiver) = mpsc::channel::<NewOrderMessage>(NEW_ORDERS_BUFFER_SIZE);
+) -> Result<(RemoteHandle<()>, mpsc::Sender<OrderbookCommand>)> {
+    let (books, sequence) = {
+        let mut conn = node.pool.get()?;
+        let orders = orders::all_limit_orders(&mut conn).context("Failed to load limit orders")?;
+        let sequence = journal::last_sequence(&mut conn)
+            .context("Failed to load last orderbook journal sequence")?;
+
+        (OrderBooks::new(orders), sequence)
+    };
 
-    let (fut, remote_handle) = async move {
-        while let Some(new_order_msg) = receiver.recv().await {
-            tokio::spawn({
-                let tx_orderbook_feed = tx_orderbook_feed.clone();
-                let notifier = notifier.clone();
-                let trade_notifier = trade_notifier.clone();
-                let node = node.clone();
-                async move {
-                    let new_order = new_order_msg.order;
-                    let trader_id = new_order.trader_id;
-                    let order_id = new_order.id;
-                    let channel_opening_params = new_order_msg.channel_opening_params;
-
-                    tracing::trace!(
-                        %trader_id,
-                        %order_id,
-                        order_type = ?new_order.order_type,
-                        "Processing new order",
-                    );
+    tracing::info!(orders = books.len(), sequence, "Loaded orderbook");
 
-                    if let Err(error) = match &new_order.order_type {
-                        OrderType::Market => {
-                            process_new_market_order(
-                                node,
-                                notifier.clone(),
-                                trade_notifier.clone(),
-                                &new_order,
-                                network,
-                                oracle_pk,
-                                channel_opening_params
-                            )
-                            .await
-                        }
-                        OrderType::Limit => {
-                            process_new_limit_order(
-                                node,
-                                tx_orderbook_feed,
-                                new_order.clone(),
-                            )
-                            .await
-                        }
-                    } {
-
-                        if new_order.order_reason == OrderReason::Manual {
-                            // TODO(holzeis): the maker is currently not subscribed to the websocket
-                            // api, hence it wouldn't receive the error message.
-                            if let Err(e) = trade_notifier
-                                .send(OrderbookMessage::TraderMessage {
-                                    trader_id,
-                                    message: TradeError { order_id, error },
-                                    notification: None,
-                                })
-                                .await
-                            {
-                                tracing::error!(%trader_id, %order_id, "Failed to send trade error. Error: {e:#}");
-                            }
-                        }
-                    }
-                }
-            });
+    let mut engine = MatchingEngine {
+        node,
+        books,
+        sequence,
+        tx_orderbook_feed,
+        trade_notifier,
+        notifier,
+        oracle_pk,
+    };
+
+    let (sender, mut receiver) = mpsc::channel::<OrderbookCommand>(ORDERBOOK_COMMANDS_BUFFER_SIZE);
+
+    let (fut, remote_handle) = async move {
+        while let Some(command) = receiver.recv().await {
+            engine.process(command).await;
         }
 
         tracing::error!("Channel closed");
@@ -145,181 +184,677 @@ pub fn start(
 
     tokio::spawn(fut);
 
-    (remote_handle, sender)
+    Ok((remote_handle, sender))
 }
 
-pub async fn process_new_limit_order(
-    node: Node,
-    tx_orderbook_feed: broadcast::Sender<Message>,
+/// Get the L3 view of the book of a market from the matching engine.
+pub async fn get_l3_book(
+    trading_sender: &mpsc::Sender<OrderbookCommand>,
+    contract_symbol: ContractSymbol,
+) -> Result<L3Book> {
+    let (response, book) = oneshot::channel();
+    trading_sender
+        .send(OrderbookCommand::GetL3Book {
+            contract_symbol,
+            response,
+        })
+        .await
+        .map_err(|e| anyhow!("Failed to send get L3 book command: {e:#}"))?;
+
+    book.await
+        .context("Matching engine dropped L3 book request")
+}
+
+/// Match a hypothetical market order with the live book, exactly as the matching engine would,
+/// but without committing anything.
+pub async fn simulate_order(
+    trading_sender: &mpsc::Sender<OrderbookCommand>,
     order: Order,
-) -> Result<(), TradingError> {
-    let mut conn = spawn_blocking(move || node.pool.get())
+) -> Result<Option<MatchParams>> {
+    let (response, matched_orders) = oneshot::channel();
+    trading_sender
+        .send(OrderbookCommand::SimulateOrder { order, response })
         .await
-        .expect("task to complete")
-        .map_err(|e| anyhow!("{e:#}"))?;
-
-    // Before processing any match we set all expired limit orders to failed, to ensure they do not
-    // get matched.
-    //
-    // TODO(holzeis): Orders should probably not have an expiry, but should either be replaced or
-    // deleted if not wanted anymore.
-    // TODO: I don't think this is necessary anymore. We are manually deleting orders now.
-    let expired_limit_orders =
-        orders::set_expired_limit_orders_to_expired(&mut conn).map_err(|e| anyhow!("{e:#}"))?;
-    for expired_limit_order in expired_limit_orders {
-        tx_orderbook_feed
-            .send(Message::DeleteOrder(expired_limit_order.id))
-            .context("Could not update price feed")?;
-    }
-
-    tx_orderbook_feed
-        .send(Message::NewOrder(order))
-        .map_err(|e| anyhow!(e))
-        .context("Could not update price feed")?;
+        .map_err(|e| anyhow!("Failed to send simulate order command: {e:#}"))?;
 
-    Ok(())
+    matched_orders
+        .await
+        .context("Matching engine dropped simulate order request")?
 }
 
-// TODO(holzeis): This functions runs multiple inserts in separate db transactions. This should only
-// happen in a single transaction to ensure either all data or nothing is stored to the database.
-pub async fn process_new_market_order(
+/// The single writer of the orderbook.
+struct MatchingEngine {
     node: Node,
-    notifier: mpsc::Sender<Notification>,
+    books: OrderBooks,
+    /// The sequence number of the last event recorded in the orderbook journal.
+    sequence: i64,
+    tx_orderbook_feed: broadcast::Sender<FeedMessage>,
     trade_notifier: mpsc::Sender<OrderbookMessage>,
-    order: &Order,
-    network: Network,
+    notifier: mpsc::Sender<Notification>,
     oracle_pk: XOnlyPublicKey,
-    channel_opening_params: Option<ChannelOpeningParams>,
-) -> Result<(), TradingError> {
-    let mut conn = spawn_blocking({
-        let node = node.clone();
-        move || node.pool.get()
-    })
-    .await
-    .expect("task to complete")
-    .map_err(|e| anyhow!("{e:#}"))?;
-
-    // Reject new order if there is already a matched order waiting for execution.
-    if let Some(order) =
-        orders::get_by_trader_id_and_state(&mut conn, order.trader_id, OrderState::Matched)
-            .map_err(|e| anyhow!("{e:#}"))?
-    {
-        return Err(TradingError::InvalidOrder(format!(
-            "trader_id={}, order_id={}. Order is currently in execution. \
+}
+
+impl MatchingEngine {
+    async fn process(&mut self, command: OrderbookCommand) {
+        // Before processing any command we remove all expired limit orders, to ensure they do not
+        // get matched.
+        if let Err(e) = self.remove_expired_orders().await {
+            tracing::error!("Failed to remove expired orders: {e:#}");
+        }
+
+        match command {
+            OrderbookCommand::NewOrder(message) => self.process_new_order(message).await,
+            OrderbookCommand::DeleteOrder {
+                order_id,
+                trader_id,
+                response,
+            } => {
+                let result = self.delete_order(order_id, trader_id).await;
+                if response.send(result).is_err() {
+                    tracing::debug!(%order_id, "Caller is no longer waiting for deleted order");
+                }
+            }
+            OrderbookCommand::DeleteOrdersOfTrader {
+                trader_id,
+                response,
+            } => {
+                let result = self.delete_orders_of_trader(trader_id).await;
+                if response.send(result).is_err() {
+                    tracing::debug!(%trader_id, "Caller is no longer waiting for deleted orders");
+                }
+            }
+            OrderbookCommand::GetL3Book {
+                contract_symbol,
+                response,
+            } => {
+                let book = self
+                    .books
+                    .l3(contract_symbol, self.sequence, OffsetDateTime::now_utc());
+                if response.send(book).is_err() {
+                    tracing::debug!("Caller is no longer waiting for L3 book");
+                }
+            }
+            OrderbookCommand::SimulateOrder { order, response } => {
+                let result = self.simulate_market_order(&order).await;
+                if response.send(result).is_err() {
+                    tracing::debug!("Caller is no longer waiting for simulated order");
+                }
+            }
+        }
+    }
+
+    async fn process_new_order(&mut self, message: NewOrderMessage) {
+        let new_order = message.order;
+        let trader_id = new_order.trader_id;
+        let order_id = new_order.id;
+
+        tracing::trace!(
+            %trader_id,
+            %order_id,
+            order_type = ?new_order.order_type,
+            contract_symbol = %new_order.contract_symbol,
+            sequence = self.sequence,
+            "Processing new order",
+        );
+
+        let result = match new_order.order_type {
+            OrderType::Market => {
+                self.process_new_market_order(
+                    &new_order,
+                    message.channel_opening_params,
+                    message.fixed_price,
+                )
+                .await
+            }
+            OrderType::Limit => self.process_new_limit_order(new_order.clone()).await,
+        };
+
+        if let Err(error) = result {
+            if new_order.order_reason == OrderReason::Manual {
+                // Sending the error must not hold up the processing of the next command.
+                let trade_notifier = self.trade_notifier.clone();
+                tokio::spawn(async move {
+                    // TODO(holzeis): the maker is currently not subscribed to the websocket
+                    // api, hence it wouldn't receive the error message.
+                    if let Err(e) = trade_notifier
+                        .send(OrderbookMessage::TraderMessage {
+                            trader_id,
+                            message: TradeError {
+                                order_id,
+                                error,
+                                localized: None,
+                            },
+                            notification: None,
+                        })
+                        .await
+                    {
+                        tracing::error!(%trader_id, %order_id, "Failed to send trade error. Error: {e:#}");
+                    }
+                });
+            }
+        }
+    }
+
+    async fn process_new_limit_order(&mut self, order: Order) -> Result<(), TradingError> {
+        if order.order_state != OrderState::Open {
+            return Err(TradingError::InvalidOrder(format!(
+                "order_id={}. Order is {:?}",
+                order.id, order.order_state
+            )));
+        }
+
+        let mut conn = self.connection().await?;
+        self.commit(
+            &mut conn,
+            &OrderbookEvent::OrderAdded {
+                order: order.clone(),
+            },
+            |_| Ok(()),
+        )?;
+
+        self.books.insert(order.clone());
+        self.publish(order.contract_symbol, Message::NewOrder(order.displayed()));
+
+        Ok(())
+    }
+
+    async fn process_new_market_order(
+        &mut self,
+        order: &Order,
+        channel_opening_params: Option<ChannelOpeningParams>,
+        fixed_price: Option<Decimal>,
+    ) -> Result<(), TradingError> {
+        let mut conn = self.connection().await?;
+
+        // Reject new order if there is already a matched order waiting for execution.
+        if let Some(order) =
+            orders::get_by_trader_id_and_state(&mut conn, order.trader_id, OrderState::Matched)
+                .map_err(|e| anyhow!("{e:#}"))?
+        {
+            return Err(TradingError::InvalidOrder(format!(
+                "trader_id={}, order_id={}. Order is currently in execution. \
                  Can't accept new orders until the order execution is finished",
-            order.trader_id, order.id
-        )));
-    }
-
-    let opposite_direction_limit_orders = orders::all_by_direction_and_type(
-        &mut conn,
-        order.direction.opposite(),
-        OrderType::Limit,
-        true,
-    )
-    .map_err(|e| anyhow!("{e:#}"))?;
-
-    let fee_percent = { node.settings.read().await.order_matching_fee_rate };
-    let fee_percent = Decimal::try_from(fee_percent).expect("to fit into decimal");
-
-    let trader_pubkey_string = order.trader_id.to_string();
-    let status = referrals::get_referral_status(order.trader_id, &mut conn)?;
-    let fee_discount = status.referral_fee_bonus;
-    let fee_percent = fee_percent - (fee_percent * fee_discount);
-
-    tracing::debug!(
-        trader_pubkey = trader_pubkey_string,
-        %fee_discount, total_fee_percent = %fee_percent, "Fee discount calculated");
-
-    let matched_orders = match match_order(
-        order,
-        opposite_direction_limit_orders,
-        network,
-        oracle_pk,
-        fee_percent,
-    ) {
-        Ok(Some(matched_orders)) => matched_orders,
-        Ok(None) => {
-            // TODO(holzeis): Currently we still respond to the user immediately if there
-            // has been a match or not, that's the reason why we also have to set the order
-            // to failed here. But actually we could keep the order until either expired or
-            // a match has been found and then update the state accordingly.
-
-            orders::set_order_state(&mut conn, order.id, OrderState::Failed)
-                .map_err(|e| anyhow!("{e:#}"))?;
-            return Err(TradingError::NoMatchFound(format!(
-                "Could not match order {}",
-                order.id
+                order.trader_id, order.id
             )));
         }
-        Err(e) => {
-            orders::set_order_state(&mut conn, order.id, OrderState::Failed)
-                .map_err(|e| anyhow!("{e:#}"))?;
+
+        let (opposite_direction_limit_orders, matched_orders) = match fixed_price {
+            Some(price) => (
+                vec![],
+                Ok(Some(self.match_at_fixed_price(order, price).await)),
+            ),
+            None => self.match_with_book(&mut conn, order).await?,
+        };
+
+        let matched_orders = match matched_orders {
+            Ok(Some(matched_orders)) => matched_orders,
+            Ok(None) => {
+                // TODO(holzeis): Currently we still respond to the user immediately if there
+                // has been a match or not, that's the reason why we also have to set the order
+                // to failed here. But actually we could keep the order until either expired or
+                // a match has been found and then update the state accordingly.
+                self.reject(&mut conn, order, "No match found")?;
+                return Err(TradingError::NoMatchFound(format!(
+                    "Could not match order {}",
+                    order.id
+                )));
+            }
+            Err(e) => {
+                self.reject(&mut conn, order, &format!("{e:#}"))?;
+                return Err(TradingError::Other(format!("Failed to match order: {e:#}")));
+            }
+        };
+
+        tracing::info!(
+            trader_id=%order.trader_id,
+            order_id=%order.id,
+            "Found a match with {} makers for new order",
+            matched_orders.taker_match.filled_with.matches.len()
+        );
+
+        let order_fills = analytics::order_fills(
+            order,
+            &opposite_direction_limit_orders,
+            &matched_orders,
+            OffsetDateTime::now_utc(),
+        );
+
+        let maker_order_ids = matched_orders
+            .makers_matches
+            .iter()
+            .map(|maker_match| maker_match.filled_with.order_id)
+            .collect::<Vec<_>>();
+
+        let now = OffsetDateTime::now_utc();
+        let remaining_icebergs = matched_orders
+            .makers_matches
+            .iter()
+            .filter_map(|maker_match| {
+                let maker_order = self.books.get(&maker_match.filled_with.order_id)?;
+                let filled = maker_match
+                    .filled_with
+                    .matches
+                    .iter()
+                    .map(|m| m.quantity)
+                    .sum();
+
+                remaining_iceberg(maker_order, filled, now)
+            })
+            .collect::<Vec<_>>();
+
+        let event = OrderbookEvent::OrderMatched {
+            order_id: order.id,
+            maker_order_ids: maker_order_ids.clone(),
+        };
+        let result = self.commit(&mut conn, &event, |conn| {
+            for match_param in matched_orders.matches() {
+                matches::insert(conn, match_param)?;
+
+                let trader_id = match_param.trader_id;
+                let order_id = match_param.filled_with.order_id.to_string();
+
+                let order_state = if order.order_type == OrderType::Limit {
+                    // FIXME: The maker is currently not connected to the WebSocket so we can't
+                    // notify him about a trade. However, trades are always accepted by the
+                    // maker at the moment so in order to not have all limit orders in order
+                    // state `Match` we are setting the order to `Taken` even if we couldn't
+                    // notify the maker.
+                    OrderState::Taken
+                } else {
+                    OrderState::Matched
+                };
+
+                if let Some(iceberg) = remaining_icebergs
+                    .iter()
+                    .find(|iceberg| iceberg.id == match_param.filled_with.order_id)
+                {
+                    tracing::debug!(
+                        %trader_id,
+                        order_id,
+                        remaining_quantity = %iceberg.quantity,
+                        "Keeping partially filled iceberg order open"
+                    );
+
+                    orders::set_remaining_quantity(
+                        conn,
+                        iceberg.id,
+                        iceberg.quantity,
+                        iceberg.timestamp,
+                    )?;
+                    continue;
+                }
+
+                tracing::debug!(%trader_id, order_id, "Updating the order state to {order_state:?}");
+
+                orders::set_order_state(conn, match_param.filled_with.order_id, order_state)?;
+            }
+
+            if let Some(channel_opening_params) = channel_opening_params {
+                db::channel_opening_params::insert(conn, order.id, channel_opening_params)?;
+            }
+
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            self.reject(&mut conn, order, "Failed to persist match")?;
             return Err(TradingError::Other(format!("Failed to match order: {e:#}")));
         }
-    };
 
-    tracing::info!(
-        trader_id=%order.trader_id,
-        order_id=%order.id,
-        "Found a match with {} makers for new order",
-        matched_orders.taker_match.filled_with.matches.len()
-    );
-
-    for match_param in matched_orders.matches() {
-        matches::insert(&mut conn, match_param)?;
-
-        let trader_id = match_param.trader_id;
-        let order_id = match_param.filled_with.order_id.to_string();
-
-        tracing::info!(%trader_id, order_id, "Notifying trader about match");
-
-        let notification = match &order.order_reason {
-            OrderReason::Expired => Some(NotificationKind::PositionExpired),
-            OrderReason::TraderLiquidated => Some(NotificationKind::Custom {
-                title: "Woops, you got liquidated 💸".to_string(),
-                message: "Open your app to execute the liquidation".to_string(),
-            }),
-            OrderReason::CoordinatorLiquidated => Some(NotificationKind::Custom {
-                title: "Your counterparty got liquidated 💸".to_string(),
-                message: "Open your app to execute the liquidation".to_string(),
-            }),
-            OrderReason::Manual => None,
-        };
+        for maker_order_id in maker_order_ids {
+            self.books.remove(&maker_order_id);
+            self.publish(order.contract_symbol, Message::DeleteOrder(maker_order_id));
+        }
 
-        if let Some(notification) = notification {
-            // send user a push notification
-            notifier
-                .send(Notification::new(order.trader_id, notification))
-                .await
-                .with_context(|| {
-                    format!(
-                        "Failed to send push notification. trader_id = {}",
-                        order.trader_id
-                    )
-                })?;
+        // The public book only learns about the new displayed slice, not about the fill itself.
+        for iceberg in remaining_icebergs {
+            self.books.insert(iceberg.clone());
+            self.publish(
+                order.contract_symbol,
+                Message::NewOrder(iceberg.displayed()),
+            );
         }
 
-        let order_state = if order.order_type == OrderType::Limit {
-            // FIXME: The maker is currently not connected to the WebSocket so we can't
-            // notify him about a trade. However, trades are always accepted by the
-            // maker at the moment so in order to not have all limit orders in order
-            // state `Match` we are setting the order to `Taken` even if we couldn't
-            // notify the maker.
-            OrderState::Taken
+        let index_price_source = self.node.settings.read().await.index_price_source;
+        analytics::record_order_fills(self.node.pool.clone(), index_price_source, order_fills);
+
+        // The match is final at this point. Notifying the trader and executing the trade happens
+        // outside of the matching engine, so that the next command does not have to wait for it.
+        tokio::spawn({
+            let node = self.node.clone();
+            let notifier = self.notifier.clone();
+            let trade_notifier = self.trade_notifier.clone();
+            let order = order.clone();
+            async move {
+                if let Err(e) = execute_match(
+                    node,
+                    notifier,
+                    trade_notifier,
+                    &order,
+                    matched_orders,
+                    channel_opening_params,
+                )
+                .await
+                {
+                    tracing::error!(
+                        trader_id = %order.trader_id,
+                        order_id = %order.id,
+                        "Failed to execute match: {e:#}"
+                    );
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Fill the market order with the coordinator at `price`, without a matching fee.
+    async fn match_at_fixed_price(&self, order: &Order, price: Decimal) -> MatchParams {
+        let schedule = self.node.expiry_schedule().await;
+        let expiry_timestamp = order.contract_expiry_at(OffsetDateTime::now_utc(), schedule);
+
+        match_at_fixed_price(
+            order,
+            price,
+            expiry_timestamp,
+            self.oracle_pk,
+            self.node.inner.info.pubkey,
+        )
+    }
+
+    /// Match the market order with the book of its market, without changing anything.
+    ///
+    /// Returns the limit orders the market order was matched with, next to the outcome of the
+    /// matching.
+    async fn match_with_book(
+        &self,
+        conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+        order: &Order,
+    ) -> Result<(Vec<Order>, Result<Option<MatchParams>>)> {
+        // Orders without contract expiry are for the next expiry, as they always used to be.
+        let now = OffsetDateTime::now_utc();
+        let schedule = self.node.expiry_schedule().await;
+        let expiry_timestamp = order.contract_expiry_at(now, schedule);
+
+        let opposite_direction_limit_orders = self.books.orders(
+            order.contract_symbol,
+            expiry_timestamp,
+            order.direction.opposite(),
+            now,
+            schedule,
+        );
+
+        let (fee_percent, spread, matching_preference) = {
+            let settings = self.node.settings.read().await;
+            (
+                settings.order_matching_fee_rate,
+                settings.spread,
+                settings.matching_preference,
+            )
+        };
+        let fee_percent = Decimal::try_from(fee_percent).expect("to fit into decimal");
+        let spread_bps = spread.spread_bps(order.direction);
+
+        let trader_pubkey_string = order.trader_id.to_string();
+        let status = referrals::get_referral_status(order.trader_id, conn)?;
+        let fee_discount = status.referral_fee_bonus;
+        let fee_percent = fee_percent - (fee_percent * fee_discount);
+
+        tracing::debug!(
+            trader_pubkey = trader_pubkey_string,
+            %fee_discount, total_fee_percent = %fee_percent, "Fee discount calculated");
+
+        let inventory = if matching_preference.reduce_exposure {
+            match self.inventory(conn, order.contract_symbol) {
+                Ok(inventory) => Some(inventory),
+                Err(e) => {
+                    // Not knowing our inventory must not prevent the trader from trading.
+                    tracing::warn!(
+                        order_id = %order.id,
+                        "Failed to get inventory, matching by best price: {e:#}"
+                    );
+                    None
+                }
+            }
         } else {
-            OrderState::Matched
+            None
         };
 
-        tracing::debug!(%trader_id, order_id, "Updating the order state to {order_state:?}");
+        let matched_orders = match_order(
+            order,
+            opposite_direction_limit_orders.clone(),
+            expiry_timestamp,
+            self.oracle_pk,
+            fee_percent,
+            spread_bps,
+            inventory
+                .as_ref()
+                .map(|inventory| (matching_preference, inventory)),
+        );
+
+        Ok((opposite_direction_limit_orders, matched_orders))
+    }
+
+    async fn simulate_market_order(&self, order: &Order) -> Result<Option<MatchParams>> {
+        let mut conn = self.connection().await?;
+        let (_, matched_orders) = self.match_with_book(&mut conn, order).await?;
+
+        matched_orders
+    }
+
+    async fn delete_order(
+        &mut self,
+        order_id: Uuid,
+        trader_id: Option<PublicKey>,
+    ) -> Result<Order> {
+        let order = self
+            .books
+            .get(&order_id)
+            .with_context(|| format!("Order {order_id} is not in the orderbook"))?;
+
+        if let Some(trader_id) = trader_id {
+            ensure!(
+                order.trader_id == trader_id,
+                "Trader {trader_id} tried to delete order {order_id} of someone else"
+            );
+        }
+
+        let contract_symbol = order.contract_symbol;
+
+        let mut conn = self.connection().await?;
+        let order = self.commit(
+            &mut conn,
+            &OrderbookEvent::OrderDeleted { order_id },
+            |conn| Ok(orders::delete(conn, order_id)?),
+        )?;
+
+        self.books.remove(&order_id);
+        self.publish(contract_symbol, Message::DeleteOrder(order_id));
+
+        Ok(order)
+    }
+
+    async fn delete_orders_of_trader(&mut self, trader_id: PublicKey) -> Result<Vec<Order>> {
+        let mut deleted = vec![];
+        for order_id in self.books.order_ids_of_trader(trader_id) {
+            deleted.push(self.delete_order(order_id, Some(trader_id)).await?);
+        }
+
+        Ok(deleted)
+    }
+
+    async fn remove_expired_orders(&mut self) -> Result<()> {
+        let expired_orders = self.books.remove_expired(OffsetDateTime::now_utc());
+        if expired_orders.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.connection().await?;
+        for order in expired_orders {
+            tracing::debug!(order_id = %order.id, "Removing expired limit order");
+
+            self.commit(
+                &mut conn,
+                &OrderbookEvent::OrderExpired { order_id: order.id },
+                |conn| {
+                    Ok(orders::set_order_state(
+                        conn,
+                        order.id,
+                        OrderState::Expired,
+                    )?)
+                },
+            )?;
+
+            self.publish(order.contract_symbol, Message::DeleteOrder(order.id));
+        }
+
+        Ok(())
+    }
+
+    /// Set the market order to failed, because it could not be matched.
+    fn reject(&mut self, conn: &mut PgConnection, order: &Order, reason: &str) -> Result<()> {
+        self.commit(
+            conn,
+            &OrderbookEvent::OrderRejected {
+                order_id: order.id,
+                reason: reason.to_string(),
+            },
+            |conn| Ok(orders::set_order_state(conn, order.id, OrderState::Failed)?),
+        )
+    }
+
+    /// Apply the changes to the DB and record the event in the journal, in a single transaction.
+    ///
+    /// The sequence number is only advanced if the transaction was committed, so that the journal
+    /// does not have gaps.
+    fn commit<T>(
+        &mut self,
+        conn: &mut PgConnection,
+        event: &OrderbookEvent,
+        changes: impl FnOnce(&mut PgConnection) -> Result<T>,
+    ) -> Result<T> {
+        let sequence = self.sequence + 1;
+
+        let result = conn.transaction(|conn| {
+            let result = changes(conn)?;
+            journal::insert(conn, sequence, event)?;
+
+            anyhow::Ok(result)
+        })?;
+
+        self.sequence = sequence;
 
-        orders::set_order_state(&mut conn, match_param.filled_with.order_id, order_state)
-            .map_err(|e| anyhow!("{e:#}"))?;
+        Ok(result)
     }
 
-    if let Some(channel_opening_params) = channel_opening_params {
-        db::channel_opening_params::insert(&mut conn, order.id, channel_opening_params)
-            .map_err(|e| anyhow!("{e:#}"))?;
+    /// The net exposure of the coordinator in the given market and the traders it is the
+    /// counterparty of.
+    fn inventory(
+        &self,
+        conn: &mut PgConnection,
+        contract_symbol: ContractSymbol,
+    ) -> Result<Inventory> {
+        let net_exposure = db::positions::Position::get_all_open_positions(conn)?
+            .into_iter()
+            .filter(|position| position.contract_symbol == contract_symbol)
+            .map(|position| {
+                let quantity = Decimal::try_from(position.quantity).expect("to fit");
+
+                // The coordinator is the counterparty of the trader.
+                match position.trader_direction {
+                    Direction::Long => -quantity,
+                    Direction::Short => quantity,
+                }
+            })
+            .sum();
+
+        let crossing_makers = self
+            .node
+            .inner
+            .list_signed_dlc_channels()?
+            .into_iter()
+            .map(|channel| channel.counter_party)
+            .collect();
+
+        Ok(Inventory {
+            net_exposure,
+            crossing_makers,
+        })
+    }
+
+    fn publish(&self, contract_symbol: ContractSymbol, message: Message) {
+        if let Err(e) = self
+            .tx_orderbook_feed
+            .send(FeedMessage::for_market(contract_symbol, message))
+        {
+            tracing::trace!("Could not update price feed: {e:#}");
+        }
+    }
+
+    async fn connection(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>> {
+        let pool = self.node.pool.clone();
+        spawn_blocking(move || pool.get())
+            .await
+            .expect("task to complete")
+            .map_err(|e| anyhow!("{e:#}"))
+    }
+}
+
+/// Notify the trader about the match and execute the trade, if the trader is connected.
+async fn execute_match(
+    node: Node,
+    notifier: mpsc::Sender<Notification>,
+    trade_notifier: mpsc::Sender<OrderbookMessage>,
+    order: &Order,
+    matched_orders: MatchParams,
+    channel_opening_params: Option<ChannelOpeningParams>,
+) -> Result<()> {
+    let notification = match &order.order_reason {
+        OrderReason::Expired => Some(NotificationKind::PositionExpired),
+        OrderReason::TraderLiquidated => Some(NotificationKind::Custom {
+            title: "Woops, you got liquidated 💸".to_string(),
+            message: "Open your app to execute the liquidation".to_string(),
+        }),
+        OrderReason::CoordinatorLiquidated => Some(NotificationKind::Custom {
+            title: "Your counterparty got liquidated 💸".to_string(),
+            message: "Open your app to execute the liquidation".to_string(),
+        }),
+        OrderReason::ChannelMigration => Some(NotificationKind::Custom {
+            title: "Your channel is being migrated 🔧".to_string(),
+            message: "Open your app to continue the migration".to_string(),
+        }),
+        OrderReason::Manual => None,
+    };
+
+    if let Some(notification) = notification {
+        tracing::info!(trader_id = %order.trader_id, order_id = %order.id, "Notifying trader about match");
+
+        // send user a push notification
+        notifier
+            .send(Notification::new(order.trader_id, notification))
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to send push notification. trader_id = {}",
+                    order.trader_id
+                )
+            })?;
+    }
+
+    if order.order_reason == OrderReason::ChannelMigration {
+        // The trader does not know about orders placed on their behalf, hence we have to tell them
+        // about the match before executing the trade.
+        trade_notifier
+            .send(OrderbookMessage::TraderMessage {
+                trader_id: order.trader_id,
+                message: Message::AsyncMatch {
+                    order: order.clone(),
+                    filled_with: matched_orders.taker_match.filled_with.clone(),
+                },
+                notification: None,
+            })
+            .await
+            .context("Failed to send async match")?;
     }
 
     if node.inner.is_connected(order.trader_id) {
@@ -330,7 +865,7 @@ pub async fn process_new_market_order(
             .execute(&TradeAndChannelParams {
                 trade_params: TradeParams {
                     pubkey: order.trader_id,
-                    contract_symbol: ContractSymbol::BtcUsd,
+                    contract_symbol: order.contract_symbol,
                     leverage: order.leverage,
                     quantity: order.quantity.to_f32().expect("to fit into f32"),
                     direction: order.direction,
@@ -348,7 +883,8 @@ pub async fn process_new_market_order(
             }
             OrderReason::Expired
             | OrderReason::TraderLiquidated
-            | OrderReason::CoordinatorLiquidated => {
+            | OrderReason::CoordinatorLiquidated
+            | OrderReason::ChannelMigration => {
                 tracing::info!(trader_id = %order.trader_id, order_id = %order.id, order_reason = ?order.order_reason, "Skipping trade execution as trader is not connected")
             }
         }
@@ -357,18 +893,51 @@ pub async fn process_new_market_order(
     Ok(())
 }
 
+impl OrderbookEvent {
+    /// The order the event is recorded for. For a match, this is the market order.
+    pub fn order_id(&self) -> Uuid {
+        match self {
+            OrderbookEvent::OrderAdded { order } => order.id,
+            OrderbookEvent::OrderDeleted { order_id }
+            | OrderbookEvent::OrderExpired { order_id }
+            | OrderbookEvent::OrderMatched { order_id, .. }
+            | OrderbookEvent::OrderRejected { order_id, .. } => *order_id,
+        }
+    }
+
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            OrderbookEvent::OrderAdded { .. } => "OrderAdded",
+            OrderbookEvent::OrderDeleted { .. } => "OrderDeleted",
+            OrderbookEvent::OrderExpired { .. } => "OrderExpired",
+            OrderbookEvent::OrderMatched { .. } => "OrderMatched",
+            OrderbookEvent::OrderRejected { .. } => "OrderRejected",
+        }
+    }
+}
+
 /// Matches an [`Order`] of [`OrderType::Market`] with a list of [`Order`]s of [`OrderType::Limit`].
 ///
-/// The caller is expected to provide a list of `opposite_direction_orders` of [`OrderType::Limit`]
-/// and opposite [`Direction`] to the `market_order`. We nevertheless ensure that this is the case
-/// to be on the safe side.
-
+/// The caller is expected to provide a list of `opposite_direction_orders` of [`OrderType::Limit`],
+/// opposite [`Direction`] and the same [`ContractSymbol`] as the `market_order`. We nevertheless
+/// ensure that this is the case to be on the safe side.
+///
+/// The limit orders also have to be for the same contract expiry as the `market_order`, which is
+/// the `expiry_timestamp` of the resulting matches.
+///
+/// The `market_order` is executed at the price of the matched limit order, adjusted by
+/// `spread_bps` to the disadvantage of the taker. The limit order is executed at its own price.
+///
+/// If a `matching_preference` is given, limit orders reducing the coordinator's net exposure may
+/// take precedence over the best price, see [`prefer_exposure_reducing`].
 fn match_order(
     market_order: &Order,
     opposite_direction_orders: Vec<Order>,
-    network: Network,
+    expiry_timestamp: OffsetDateTime,
     oracle_pk: XOnlyPublicKey,
     fee_percent: Decimal,
+    spread_bps: u32,
+    matching_preference: Option<(MatchingPreferenceSettings, &Inventory)>,
 ) -> Result<Option<MatchParams>> {
     if market_order.order_type == OrderType::Limit {
         // We don't match limit orders with other limit orders at the moment.
@@ -378,10 +947,15 @@ fn match_order(
     let opposite_direction_orders = opposite_direction_orders
         .into_iter()
         .filter(|o| !o.direction.eq(&market_order.direction))
+        .filter(|o| o.contract_symbol == market_order.contract_symbol)
         .collect();
 
     let mut orders = sort_orders(opposite_direction_orders, market_order.direction);
 
+    if let Some((settings, inventory)) = matching_preference {
+        orders = prefer_exposure_reducing(orders, market_order, inventory, settings);
+    }
+
     let mut remaining_quantity = market_order.quantity;
     let mut matched_orders = vec![];
     while !orders.is_empty() {
@@ -403,9 +977,6 @@ fn match_order(
         return Ok(None);
     }
 
-    let expiry_timestamp =
-        xxi_node::commons::calculate_next_expiry(OffsetDateTime::now_utc(), network);
-
     let matches = matched_orders
         .iter()
         .map(|maker_order| {
@@ -435,6 +1006,8 @@ fn match_order(
                             pubkey: market_order.trader_id,
                             execution_price: maker_order.price,
                             matching_fee,
+                            index_price: Some(maker_order.price),
+                            spread_bps: 0,
                         }],
                     },
                 },
@@ -443,8 +1016,14 @@ fn match_order(
                     order_id: maker_order.id,
                     quantity: market_order.quantity,
                     pubkey: maker_order.trader_id,
-                    execution_price: maker_order.price,
+                    execution_price: apply_spread(
+                        maker_order.price,
+                        market_order.direction,
+                        spread_bps,
+                    ),
                     matching_fee,
+                    index_price: Some(maker_order.price),
+                    spread_bps,
                 },
             )
         })
@@ -472,6 +1051,38 @@ fn match_order(
     }))
 }
 
+/// Fill the `market_order` with the coordinator identified by `coordinator_pubkey` at `price`,
+/// without a matching fee and without a maker.
+fn match_at_fixed_price(
+    market_order: &Order,
+    price: Decimal,
+    expiry_timestamp: OffsetDateTime,
+    oracle_pk: XOnlyPublicKey,
+    coordinator_pubkey: PublicKey,
+) -> MatchParams {
+    MatchParams {
+        taker_match: TraderMatchParams {
+            trader_id: market_order.trader_id,
+            filled_with: FilledWith {
+                order_id: market_order.id,
+                expiry_timestamp,
+                oracle_pk,
+                matches: vec![Match {
+                    id: Uuid::new_v4(),
+                    order_id: market_order.id,
+                    quantity: market_order.quantity,
+                    pubkey: coordinator_pubkey,
+                    execution_price: price,
+                    matching_fee: Amount::ZERO,
+                    index_price: Some(price),
+                    spread_bps: 0,
+                }],
+            },
+        },
+        makers_matches: vec![],
+    }
+}
+
 /// Sort the provided list of limit [`Order`]s based on the [`Direction`] of the market order to be
 /// matched.
 ///
@@ -490,7 +1101,10 @@ fn match_order(
 ///
 /// Additionally, if two orders have the same price, the one with the earlier `timestamp` takes
 /// precedence.
-fn sort_orders(mut limit_orders: Vec<Order>, market_order_direction: Direction) -> Vec<Order> {
+pub(crate) fn sort_orders(
+    mut limit_orders: Vec<Order>,
+    market_order_direction: Direction,
+) -> Vec<Order> {
     limit_orders.sort_by(|a, b| {
         if a.price.cmp(&b.price) == Ordering::Equal {
             return a.timestamp.cmp(&b.timestamp);
@@ -670,14 +1284,18 @@ mod tests {
             order_state: OrderState::Open,
             order_reason: OrderReason::Manual,
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         };
 
         let matched_orders = match_order(
             &order,
             all_orders,
-            Network::Bitcoin,
+            contract_expiry(),
             get_oracle_public_key(),
             Decimal::ZERO,
+            0,
+            None,
         )
         .unwrap()
         .unwrap();
@@ -694,6 +1312,10 @@ mod tests {
         assert_eq!(maker_matches.first().unwrap().quantity, dec!(100));
 
         assert_eq!(matched_orders.taker_match.filled_with.order_id, order.id);
+        assert_eq!(
+            matched_orders.taker_match.filled_with.expiry_timestamp,
+            contract_expiry()
+        );
         assert_eq!(matched_orders.taker_match.filled_with.matches.len(), 1);
         assert_eq!(
             matched_orders
@@ -753,14 +1375,18 @@ mod tests {
             order_state: OrderState::Open,
             order_reason: OrderReason::Manual,
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         };
 
         assert!(match_order(
             &order,
             all_orders,
-            Network::Bitcoin,
+            contract_expiry(),
             get_oracle_public_key(),
             Decimal::ZERO,
+            0,
+            None,
         )
         .is_err());
     }
@@ -811,20 +1437,180 @@ mod tests {
             order_state: OrderState::Open,
             order_reason: OrderReason::Manual,
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         };
 
         let matched_orders = match_order(
             &order,
             all_orders,
-            Network::Bitcoin,
+            contract_expiry(),
             get_oracle_public_key(),
             Decimal::ZERO,
+            0,
+            None,
         )
         .unwrap();
 
         assert!(matched_orders.is_none());
     }
 
+    #[test]
+    fn given_spread_then_taker_execution_price_is_adjusted() {
+        let maker_order = dummy_long_order(
+            dec!(20_000),
+            Uuid::new_v4(),
+            dec!(100),
+            Duration::seconds(0),
+        );
+
+        let order = Order {
+            id: Uuid::new_v4(),
+            price: Default::default(),
+            trader_id: PublicKey::from_str(
+                "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007",
+            )
+            .unwrap(),
+            direction: Direction::Short,
+            leverage: 1.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity: dec!(100),
+            order_type: OrderType::Market,
+            timestamp: OffsetDateTime::now_utc(),
+            expiry: OffsetDateTime::now_utc() + Duration::minutes(1),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        };
+
+        let matched_orders = match_order(
+            &order,
+            vec![maker_order],
+            contract_expiry(),
+            get_oracle_public_key(),
+            Decimal::ZERO,
+            10,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+
+        let taker_match = matched_orders.taker_match.filled_with.matches[0];
+        assert_eq!(taker_match.execution_price, dec!(19_980));
+        assert_eq!(taker_match.index_price, Some(dec!(20_000)));
+        assert_eq!(taker_match.spread_bps, 10);
+
+        let maker_match = matched_orders.makers_matches[0].filled_with.matches[0];
+        assert_eq!(maker_match.execution_price, dec!(20_000));
+        assert_eq!(maker_match.index_price, Some(dec!(20_000)));
+        assert_eq!(maker_match.spread_bps, 0);
+    }
+
+    #[test]
+    fn given_iceberg_then_hidden_quantity_is_matched_and_priority_lost_on_replenish() {
+        let iceberg = Order {
+            display_quantity: Some(dec!(300)),
+            ..dummy_long_order(
+                dec!(20_000),
+                Uuid::new_v4(),
+                dec!(1000),
+                Duration::seconds(0),
+            )
+        };
+        let later = dummy_long_order(
+            dec!(20_000),
+            Uuid::new_v4(),
+            dec!(100),
+            Duration::seconds(10),
+        );
+
+        let order = Order {
+            id: Uuid::new_v4(),
+            price: Default::default(),
+            trader_id: PublicKey::from_str(
+                "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007",
+            )
+            .unwrap(),
+            direction: Direction::Short,
+            leverage: 1.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity: dec!(500),
+            order_type: OrderType::Market,
+            timestamp: OffsetDateTime::now_utc(),
+            expiry: OffsetDateTime::now_utc() + Duration::minutes(1),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        };
+
+        // Only 100 of the iceberg are shown, but the whole order is matched.
+        let matched_orders = match_order(
+            &order,
+            vec![later.clone(), iceberg.clone()],
+            contract_expiry(),
+            get_oracle_public_key(),
+            Decimal::ZERO,
+            0,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+
+        let maker_match = &matched_orders.makers_matches[0].filled_with;
+        assert_eq!(maker_match.order_id, iceberg.id);
+        assert_eq!(maker_match.matches[0].quantity, dec!(500));
+
+        let now = OffsetDateTime::now_utc() + Duration::minutes(1);
+        let remaining = remaining_iceberg(&iceberg, dec!(500), now).unwrap();
+        assert_eq!(remaining.quantity, dec!(500));
+        assert_eq!(remaining.displayed().quantity, dec!(200));
+
+        // The replenished iceberg queues up behind the order placed after it.
+        let orders = sort_orders(vec![remaining.clone(), later.clone()], Direction::Short);
+        assert_eq!(orders, vec![later, remaining]);
+    }
+
+    #[test]
+    fn channel_migration_order_is_filled_at_fixed_price_without_fee() {
+        let coordinator_pubkey = PublicKey::from_str(
+            "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+        )
+        .unwrap();
+
+        let order = Order {
+            order_type: OrderType::Market,
+            order_reason: OrderReason::ChannelMigration,
+            direction: Direction::Short,
+            ..dummy_long_order(
+                Decimal::ZERO,
+                Uuid::new_v4(),
+                dec!(100),
+                Duration::seconds(0),
+            )
+        };
+
+        let matched_orders = match_at_fixed_price(
+            &order,
+            dec!(60_123.5),
+            contract_expiry(),
+            get_oracle_public_key(),
+            coordinator_pubkey,
+        );
+
+        assert!(matched_orders.makers_matches.is_empty());
+
+        let filled_with = matched_orders.taker_match.filled_with;
+        assert_eq!(filled_with.order_id, order.id);
+        assert_eq!(filled_with.matches[0].execution_price, dec!(60_123.5));
+        assert_eq!(filled_with.order_matching_fee(), Amount::ZERO);
+        assert_eq!(filled_with.matches[0].quantity, dec!(100));
+        assert_eq!(filled_with.matches[0].pubkey, coordinator_pubkey);
+    }
+
     fn dummy_long_order(
         price: Decimal,
         id: Uuid,
@@ -848,9 +1634,16 @@ mod tests {
             order_state: OrderState::Open,
             order_reason: OrderReason::Manual,
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         }
     }
 
+    fn contract_expiry() -> OffsetDateTime {
+        // Tue Jul 16 2024 15:00:00 GMT+0000
+        OffsetDateTime::from_unix_timestamp(1721142000).unwrap()
+    }
+
     fn get_oracle_public_key() -> XOnlyPublicKey {
         XOnlyPublicKey::from_str("16f88cf7d21e6c0f46bcbc983a4e3b19726c6c98858cc31c83551a88fde171c0")
             .unwrap()
@@ -1,28 +1,51 @@
+use crate::api_key;
+use crate::api_key::Permission;
 use crate::db;
 use crate::db::user;
 use crate::funding_fee::get_funding_fee_events_for_active_trader_positions;
 use crate::funding_fee::get_next_funding_rate;
 use crate::message::NewUserMessage;
+use crate::orderbook::cancel_on_disconnect::ConnectionGuard;
+use crate::orderbook::contract_expiry;
 use crate::orderbook::db::orders;
+use crate::orderbook::order_flow;
+use crate::orderbook::outbound_queue::outbound_queue;
+use crate::orderbook::outbound_queue::Outbound;
+use crate::orderbook::outbound_queue::OUTBOUND_QUEUE_CAPACITY;
 use crate::orderbook::trading::NewOrderMessage;
+use crate::orderbook::trading::OrderbookCommand;
 use crate::referrals;
 use crate::routes::AppState;
+use crate::trade;
+use crate::trade::minimums::TradeMinimums;
 use anyhow::bail;
 use anyhow::Result;
+use axum::extract::ws::close_code;
+use axum::extract::ws::CloseFrame;
 use axum::extract::ws::Message as WebsocketMessage;
 use axum::extract::ws::WebSocket;
 use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::PgConnection;
 use futures::SinkExt;
 use futures::StreamExt;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
+use time::OffsetDateTime;
 use tokio::sync::broadcast::error::RecvError;
-use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tokio::sync::watch;
 use tokio::task::spawn_blocking;
 use uuid::Uuid;
+use xxi_node::commons::api_key_auth_message;
 use xxi_node::commons::create_sign_message;
+use xxi_node::commons::ClientInfo;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Locale;
 use xxi_node::commons::Message;
 use xxi_node::commons::NewLimitOrder;
+use xxi_node::commons::Order;
 use xxi_node::commons::OrderReason;
 use xxi_node::commons::OrderbookRequest;
 use xxi_node::commons::ReferralStatus;
@@ -31,21 +54,104 @@ use xxi_node::commons::AUTH_SIGN_MESSAGE;
 
 const WEBSOCKET_SEND_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// A message for the subscribers of the orderbook feed.
+#[derive(Debug, Clone)]
+pub struct FeedMessage {
+    /// The market the message is about. Messages which do not concern a single market, e.g. the
+    /// next funding rate, are sent to every subscriber.
+    pub contract_symbol: Option<ContractSymbol>,
+    pub message: Message,
+}
+
+impl FeedMessage {
+    pub fn for_market(contract_symbol: ContractSymbol, message: Message) -> Self {
+        Self {
+            contract_symbol: Some(contract_symbol),
+            message,
+        }
+    }
+
+    pub fn for_all(message: Message) -> Self {
+        Self {
+            contract_symbol: None,
+            message,
+        }
+    }
+}
+
+/// The markets a websocket client receives orderbook updates for.
+#[derive(Debug, Clone, Default)]
+enum Subscription {
+    /// Clients which never sent [`OrderbookRequest::Subscribe`] only receive updates of the BTCUSD
+    /// market. Apps which predate the other markets fail to deserialize their orders.
+    #[default]
+    Legacy,
+    Markets(HashSet<ContractSymbol>),
+}
+
+impl Subscription {
+    fn includes(&self, contract_symbol: ContractSymbol) -> bool {
+        match self {
+            Subscription::Legacy => contract_symbol == ContractSymbol::BtcUsd,
+            Subscription::Markets(contract_symbols) => contract_symbols.contains(&contract_symbol),
+        }
+    }
+
+    fn wants(&self, message: &FeedMessage) -> bool {
+        match message.contract_symbol {
+            Some(contract_symbol) => self.includes(contract_symbol),
+            None => true,
+        }
+    }
+}
+
+/// The open limit orders of the markets the client is subscribed to, as shown in the public
+/// orderbook.
+fn subscribed_limit_orders(conn: &mut PgConnection, subscription: &Subscription) -> Vec<Order> {
+    orders::all_limit_orders(conn)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|order| subscription.includes(order.contract_symbol))
+        .map(|order| order.displayed())
+        .collect()
+}
+
 async fn handle_insert_order(
     state: Arc<AppState>,
     trader_id: PublicKey,
     order: NewLimitOrder,
+    client_info: Option<ClientInfo>,
 ) -> Result<()> {
     if order.trader_id != trader_id {
         bail!("Maker {trader_id} tried to trade on behalf of someone else: {order:?}");
     }
 
+    state.node.kill_switch.ensure_new_orders_allowed()?;
+    state.node.moderation.ensure_allowed(trader_id)?;
+    order.ensure_valid_display_quantity()?;
+
+    let listed_expiries = state.settings.read().await.listed_expiries;
+    contract_expiry::validate_contract_expiry(
+        &state.node,
+        order.contract_symbol,
+        order.contract_expiry,
+        listed_expiries,
+    )
+    .await?;
+
     tracing::trace!(?order, "Inserting order");
 
     let order = spawn_blocking({
         let mut conn = state.pool.clone().get()?;
         move || {
-            let order = orders::insert_limit_order(&mut conn, order, OrderReason::Manual)?;
+            let order = orders::insert_limit_order(
+                &mut conn,
+                order,
+                OrderReason::Manual,
+                client_info.clone(),
+            )?;
+
+            order_flow::record_order(client_info.as_ref());
 
             anyhow::Ok(order)
         }
@@ -54,11 +160,12 @@ async fn handle_insert_order(
 
     let _ = state
         .trading_sender
-        .send(NewOrderMessage {
+        .send(OrderbookCommand::NewOrder(NewOrderMessage {
             order,
             channel_opening_params: None,
             order_reason: OrderReason::Manual,
-        })
+            fixed_price: None,
+        }))
         .await;
 
     Ok(())
@@ -71,21 +178,99 @@ async fn handle_delete_order(
 ) -> Result<()> {
     tracing::trace!(%order_id, "Deleting order");
 
-    spawn_blocking({
-        let mut conn = state.pool.clone().get()?;
-        move || {
-            orders::delete_trader_order(&mut conn, order_id, trader_id)?;
-
-            anyhow::Ok(())
-        }
-    })
-    .await??;
+    let (response, order) = oneshot::channel();
+    state
+        .trading_sender
+        .send(OrderbookCommand::DeleteOrder {
+            order_id,
+            trader_id: Some(trader_id),
+            response,
+        })
+        .await?;
 
-    let _ = state.tx_orderbook_feed.send(Message::DeleteOrder(order_id));
+    order.await??;
 
     Ok(())
 }
 
+/// Register the connection of an authenticated maker which asked to have their orders deleted if
+/// the connection drops.
+async fn cancel_on_disconnect(
+    state: &AppState,
+    maker: Option<PublicKey>,
+    cancel_on_disconnect: bool,
+) -> Option<ConnectionGuard> {
+    let maker = maker.filter(|_| cancel_on_disconnect)?;
+
+    let grace_period = Duration::from_secs(
+        state
+            .settings
+            .read()
+            .await
+            .cancel_on_disconnect_grace_period_secs,
+    );
+
+    Some(state.cancel_on_disconnect.connected(maker, grace_period))
+}
+
+/// The configuration sent to a trader once authenticated.
+pub(crate) async fn tentenone_config(
+    state: &AppState,
+    conn: &mut PgConnection,
+    trader_id: PublicKey,
+) -> TenTenOneConfig {
+    let liquidity_options = db::liquidity_options::get_all(conn).unwrap_or_default();
+
+    let (
+        min_quantity,
+        maintenance_margin_rate,
+        order_matching_fee_rate,
+        max_leverage,
+        force_close_cost_multiplier,
+        listed_expiries,
+    ) = {
+        let settings = state.settings.read().await;
+        (
+            settings.min_quantity,
+            settings.maintenance_margin_rate,
+            settings.order_matching_fee_rate,
+            settings.max_leverage,
+            settings.force_close_cost_multiplier,
+            settings.listed_expiries,
+        )
+    };
+
+    let minimums = trade::minimums::get_trade_minimums(
+        &state.node,
+        conn,
+        min_quantity,
+        force_close_cost_multiplier,
+    )
+    .unwrap_or_else(|e| {
+        tracing::warn!("Failed to get trade minimums: {e:#}");
+        TradeMinimums {
+            min_quantity,
+            min_channel_collateral: Amount::ZERO,
+        }
+    });
+
+    let referral_status = referrals::update_referral_status_for_user(conn, trader_id.to_string())
+        .unwrap_or(ReferralStatus::new(trader_id));
+
+    TenTenOneConfig {
+        liquidity_options,
+        min_quantity: minimums.min_quantity,
+        min_channel_collateral_sats: minimums.min_channel_collateral.to_sat(),
+        maintenance_margin_rate,
+        order_matching_fee_rate,
+        referral_status,
+        max_leverage,
+        session_token: None,
+        kill_switch: state.node.kill_switch.status(),
+        listed_expiries,
+    }
+}
+
 // This function deals with a single websocket connection, i.e., a single
 // connected client / user, for which we will spawn two independent tasks (for
 // receiving / sending messages).
@@ -96,29 +281,64 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
     // We subscribe *before* sending the "joined" message, so that we will also
     // display it to our client.
     let mut price_feed = state.tx_orderbook_feed.subscribe();
+    let (subscription_sender, subscription) = watch::channel(Subscription::default());
 
-    let (local_sender, mut local_receiver) = mpsc::channel::<Message>(100);
+    // Messages for the client are queued per connection, so that a client on a slow connection
+    // does not hold up anyone else.
+    let (local_sender, local_receiver) = outbound_queue(OUTBOUND_QUEUE_CAPACITY);
 
-    let mut local_recv_task = tokio::spawn(async move {
-        while let Some(local_msg) = local_receiver.recv().await {
-            match serde_json::to_string(&local_msg) {
-                Ok(msg) => {
-                    if let Err(err) = tokio::time::timeout(
-                        WEBSOCKET_SEND_TIMEOUT,
-                        sender.send(WebsocketMessage::Text(msg.clone())),
-                    )
-                    .await
-                    {
-                        tracing::error!("Could not forward message {msg} : {err:#}");
+    let mut local_recv_task = {
+        let state = state.clone();
+        let subscription = subscription.clone();
+        tokio::spawn(async move {
+            loop {
+                let local_msg = match local_receiver.recv().await {
+                    Outbound::Message(message) => message,
+                    Outbound::Resync => {
+                        let subscription = subscription.borrow().clone();
+                        match state.pool.clone().get() {
+                            Ok(mut conn) => Message::AllOrders(subscribed_limit_orders(
+                                &mut conn,
+                                &subscription,
+                            )),
+                            Err(err) => {
+                                tracing::error!("Could not get connection to db pool {err:#}");
+                                return;
+                            }
+                        }
+                    }
+                    Outbound::Disconnect => {
+                        tracing::warn!("Disconnecting websocket client which can't keep up");
+
+                        let close = WebsocketMessage::Close(Some(CloseFrame {
+                            code: close_code::AGAIN,
+                            reason: "Too many pending messages".into(),
+                        }));
+                        let _ =
+                            tokio::time::timeout(WEBSOCKET_SEND_TIMEOUT, sender.send(close)).await;
                         return;
                     }
-                }
-                Err(error) => {
-                    tracing::warn!("Could not deserialize message {error:#}");
+                };
+
+                match serde_json::to_string(&local_msg) {
+                    Ok(msg) => {
+                        if let Err(err) = tokio::time::timeout(
+                            WEBSOCKET_SEND_TIMEOUT,
+                            sender.send(WebsocketMessage::Text(msg.clone())),
+                        )
+                        .await
+                        {
+                            tracing::error!("Could not forward message {msg} : {err:#}");
+                            return;
+                        }
+                    }
+                    Err(error) => {
+                        tracing::warn!("Could not deserialize message {error:#}");
+                    }
                 }
             }
-        }
-    });
+        })
+    };
 
     // Spawn the first task that will receive broadcast messages and send
     // messages over the websocket to our client.
@@ -127,8 +347,12 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
         tokio::spawn(async move {
             loop {
                 match price_feed.recv().await {
-                    Ok(st) => {
-                        if let Err(error) = local_sender.send(st).await {
+                    Ok(message) => {
+                        if !subscription.borrow().wants(&message) {
+                            continue;
+                        }
+
+                        if let Err(error) = local_sender.send(message.message) {
                             tracing::error!("Could not send message {error:#}");
                             return;
                         }
@@ -137,9 +361,11 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
                         tracing::error!("price feed sender died! Channel closed.");
                         break;
                     }
-                    Err(RecvError::Lagged(skip)) => tracing::warn!(%skip,
-                        "Lagging behind on price feed."
-                    ),
+                    Err(RecvError::Lagged(skip)) => {
+                        tracing::warn!(%skip, "Lagging behind on price feed.");
+                        // The skipped messages may have included orderbook updates.
+                        local_sender.resync();
+                    }
                 }
             }
         })
@@ -149,6 +375,10 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
     let local_sender = local_sender.clone();
     let mut recv_task = tokio::spawn(async move {
         let mut whitelisted_maker = Option::<PublicKey>::None;
+        // The client which authenticated the connection, attributed to the orders it inserts.
+        let mut client_info_of_session = Option::<ClientInfo>::None;
+        // Deletes the orders of the maker once the connection drops, if they asked for it.
+        let mut _connection_guard = Option::<ConnectionGuard>::None;
 
         while let Some(Ok(WebsocketMessage::Text(text))) = receiver.next().await {
             match serde_json::from_str(text.as_str()) {
@@ -157,9 +387,13 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
 
                     match whitelisted_maker {
                         Some(authenticated_trader_id) => {
-                            if let Err(e) =
-                                handle_insert_order(state.clone(), authenticated_trader_id, order)
-                                    .await
+                            if let Err(e) = handle_insert_order(
+                                state.clone(),
+                                authenticated_trader_id,
+                                order,
+                                client_info_of_session.clone(),
+                            )
+                            .await
                             {
                                 tracing::error!(%order_id, "Failed to insert order: {e:#}");
                                 // TODO: Send error to peer.
@@ -200,6 +434,8 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
                     version,
                     os,
                     signature,
+                    client_info,
+                    cancel_on_disconnect: cancel_orders_on_disconnect,
                 }) => {
                     let msg = create_sign_message(AUTH_SIGN_MESSAGE.to_vec());
                     let trader_id = signature.pubkey;
@@ -215,46 +451,33 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
 
                     match state.secp.verify_ecdsa(&msg, &signature, &trader_id) {
                         Ok(_) => {
-                            let liquidity_options =
-                                db::liquidity_options::get_all(&mut conn).unwrap_or_default();
-
-                            let (
-                                min_quantity,
-                                maintenance_margin_rate,
-                                order_matching_fee_rate,
-                                max_leverage,
-                            ) = {
-                                let settings = state.settings.read().await;
-                                (
-                                    settings.min_quantity,
-                                    settings.maintenance_margin_rate,
-                                    settings.order_matching_fee_rate,
-                                    settings.max_leverage,
-                                )
-                            };
+                            if let Err(e) = state.node.moderation.ensure_allowed(trader_id) {
+                                tracing::warn!(%trader_id, "Rejecting restricted trader: {e:#}");
+                                if let Err(er) = local_sender
+                                    .send(Message::InvalidAuthentication(format!("{e:#}")))
+                                {
+                                    tracing::error!(
+                                        %trader_id, "Failed to notify user about restriction: {er:#}"
+                                    );
+                                    return;
+                                }
+                                continue;
+                            }
 
-                            let referral_status = referrals::update_referral_status_for_user(
-                                &mut conn,
-                                trader_id.to_string(),
-                            )
-                            .unwrap_or(ReferralStatus::new(trader_id));
-                            if let Err(e) = local_sender
-                                .send(Message::Authenticated(TenTenOneConfig {
-                                    liquidity_options,
-                                    min_quantity,
-                                    maintenance_margin_rate,
-                                    order_matching_fee_rate,
-                                    referral_status,
-                                    max_leverage,
-                                }))
-                                .await
-                            {
+                            let mut config = tentenone_config(&state, &mut conn, trader_id).await;
+                            config.session_token = Some(
+                                state
+                                    .session_tokens
+                                    .issue(trader_id, OffsetDateTime::now_utc()),
+                            );
+                            if let Err(e) = local_sender.send(Message::Authenticated(config)) {
                                 tracing::error!(%trader_id, "Could not respond to user {e:#}");
                                 return;
                             }
 
-                            let orders = orders::all_limit_orders(&mut conn).unwrap_or_default();
-                            if let Err(e) = local_sender.send(Message::AllOrders(orders)).await {
+                            let orders =
+                                subscribed_limit_orders(&mut conn, &subscription_sender.borrow());
+                            if let Err(e) = local_sender.send(Message::AllOrders(orders)) {
                                 tracing::error!(%trader_id, "Failed to send all orders to user {e:#}");
                             }
 
@@ -266,7 +489,6 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
                                 Ok(funding_fee_events) => {
                                     if let Err(e) = local_sender
                                         .send(Message::AllFundingFeeEvents(funding_fee_events))
-                                        .await
                                     {
                                         tracing::error!(
                                             %trader_id,
@@ -286,9 +508,8 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
 
                             match get_next_funding_rate(&mut conn) {
                                 Ok(Some(funding_rate)) => {
-                                    if let Err(e) = local_sender
-                                        .send(Message::NextFundingRate(funding_rate))
-                                        .await
+                                    if let Err(e) =
+                                        local_sender.send(Message::NextFundingRate(funding_rate))
                                     {
                                         tracing::error!(
                                             %trader_id,
@@ -317,12 +538,23 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
                                 tracing::error!(%trader_id, "Failed to update logged in user. Error: {e:#}")
                             }
 
+                            let locale = match user::get_user(&mut conn, &trader_id) {
+                                Ok(Some(user)) => user.locale(),
+                                Ok(None) => Locale::default(),
+                                Err(e) => {
+                                    tracing::error!(%trader_id, "Failed to load user locale. Error: {e:#}");
+                                    Locale::default()
+                                }
+                            };
+
                             let message = NewUserMessage {
                                 new_user: trader_id,
                                 sender: local_sender.clone(),
+                                locale,
                             };
 
-                            tracing::debug!(%trader_id, "New login");
+                            tracing::debug!(%trader_id, ?client_info, "New login");
+                            client_info_of_session = client_info;
 
                             // Check if the trader is a whitelisted maker.
                             {
@@ -335,17 +567,21 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
                                 }
                             }
 
+                            _connection_guard = cancel_on_disconnect(
+                                &state,
+                                whitelisted_maker,
+                                cancel_orders_on_disconnect,
+                            )
+                            .await;
+
                             if let Err(e) = state.tx_user_feed.send(message) {
                                 tracing::error!(%trader_id, "Could not send new user message. Error: {e:#}");
                             }
                         }
                         Err(err) => {
-                            if let Err(er) = local_sender
-                                .send(Message::InvalidAuthentication(format!(
-                                    "Could not authenticate {err:#}"
-                                )))
-                                .await
-                            {
+                            if let Err(er) = local_sender.send(Message::InvalidAuthentication(
+                                format!("Could not authenticate {err:#}"),
+                            )) {
                                 tracing::error!(
                                     %trader_id, "Failed to notify user about invalid authentication: {er:#}"
                                 );
@@ -354,6 +590,119 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
                         }
                     }
                 }
+                Ok(OrderbookRequest::AuthenticateWithApiKey {
+                    key_id,
+                    timestamp,
+                    nonce,
+                    signature,
+                    client_info,
+                    cancel_on_disconnect: cancel_orders_on_disconnect,
+                }) => {
+                    let message = api_key_auth_message(timestamp, &nonce);
+                    let pool = state.pool.clone();
+                    let secrets = state.api_key_secrets.clone();
+                    let api_key = spawn_blocking(move || {
+                        let mut conn = pool.get()?;
+                        api_key::authenticate(
+                            &mut conn, &secrets, key_id, timestamp, &nonce, &message, &signature,
+                        )
+                    })
+                    .await
+                    .expect("task to complete");
+
+                    let api_key = match api_key {
+                        Ok(api_key) => api_key,
+                        Err(err) => {
+                            if let Err(er) = local_sender.send(Message::InvalidAuthentication(
+                                format!("Could not authenticate {err:#}"),
+                            )) {
+                                tracing::error!(
+                                    %key_id, "Failed to notify bot about invalid authentication: {er:#}"
+                                );
+                                return;
+                            }
+                            continue;
+                        }
+                    };
+
+                    let trader_id = api_key.trader_pubkey;
+
+                    if let Err(e) = state.node.moderation.ensure_allowed(trader_id) {
+                        tracing::warn!(%trader_id, "Rejecting restricted trader: {e:#}");
+                        if let Err(er) =
+                            local_sender.send(Message::InvalidAuthentication(format!("{e:#}")))
+                        {
+                            tracing::error!(
+                                %trader_id, "Failed to notify bot about restriction: {er:#}"
+                            );
+                            return;
+                        }
+                        continue;
+                    }
+
+                    let mut conn = match state.pool.clone().get() {
+                        Ok(conn) => conn,
+                        Err(err) => {
+                            tracing::error!("Could not get connection to db pool {err:#}");
+                            return;
+                        }
+                    };
+
+                    let config = tentenone_config(&state, &mut conn, trader_id).await;
+                    if let Err(e) = local_sender.send(Message::Authenticated(config)) {
+                        tracing::error!(%trader_id, "Could not respond to bot {e:#}");
+                        return;
+                    }
+
+                    let orders = subscribed_limit_orders(&mut conn, &subscription_sender.borrow());
+                    if let Err(e) = local_sender.send(Message::AllOrders(orders)) {
+                        tracing::error!(%trader_id, "Failed to send all orders to bot {e:#}");
+                    }
+
+                    tracing::debug!(%trader_id, %key_id, ?client_info, "New login with API key");
+                    client_info_of_session = client_info;
+
+                    // Unlike the app, a bot is not registered as the trader's connection, so
+                    // that messages about the trader's DLC channel keep going to the app.
+                    if api_key.allows(Permission::Trade) {
+                        let settings = state.settings.read().await;
+
+                        if !settings.whitelist_enabled
+                            || settings.whitelisted_makers.contains(&trader_id)
+                        {
+                            whitelisted_maker = Some(trader_id);
+                        }
+                    }
+
+                    _connection_guard = cancel_on_disconnect(
+                        &state,
+                        whitelisted_maker,
+                        cancel_orders_on_disconnect,
+                    )
+                    .await;
+                }
+                Ok(OrderbookRequest::Subscribe(contract_symbols)) => {
+                    tracing::debug!(?contract_symbols, "Subscribing to markets");
+
+                    // Update the subscription before taking the snapshot, so that no update
+                    // in between is lost.
+                    let new_subscription =
+                        Subscription::Markets(contract_symbols.into_iter().collect());
+                    subscription_sender.send_replace(new_subscription.clone());
+
+                    let orders = match state.pool.clone().get() {
+                        Ok(mut conn) => subscribed_limit_orders(&mut conn, &new_subscription),
+                        Err(err) => {
+                            tracing::error!("Could not get connection to db pool {err:#}");
+                            return;
+                        }
+                    };
+
+                    if let Err(e) = local_sender.send(Message::AllOrders(orders)) {
+                        tracing::error!("Failed to send orders of subscribed markets {e:#}");
+                        return;
+                    }
+                }
                 Err(err) => {
                     tracing::trace!("Could not deserialize msg: {text} {err:#}");
                 }
@@ -377,3 +726,34 @@ pub async fn websocket_connection(stream: WebSocket, state: Arc<AppState>) {
         },
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_subscription_only_includes_btcusd() {
+        let subscription = Subscription::default();
+
+        assert!(subscription.includes(ContractSymbol::BtcUsd));
+        assert!(!subscription.includes(ContractSymbol::EthUsd));
+    }
+
+    #[test]
+    fn legacy_subscription_does_not_want_ethusd_updates() {
+        let subscription = Subscription::default();
+
+        let message =
+            FeedMessage::for_market(ContractSymbol::EthUsd, Message::DeleteOrder(Uuid::nil()));
+
+        assert!(!subscription.wants(&message));
+    }
+
+    #[test]
+    fn subscription_includes_subscribed_markets() {
+        let subscription = Subscription::Markets(HashSet::from([ContractSymbol::EthUsd]));
+
+        assert!(subscription.includes(ContractSymbol::EthUsd));
+        assert!(!subscription.includes(ContractSymbol::BtcUsd));
+    }
+}
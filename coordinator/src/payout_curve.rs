@@ -1,4 +1,4 @@
-use anyhow::ensure;
+use crate::FundingFee;
 use anyhow::Context;
 use anyhow::Result;
 use bitcoin::Amount;
@@ -10,18 +10,29 @@ use dlc_manager::payout_curve::PayoutPoint;
 use dlc_manager::payout_curve::PolynomialPayoutCurvePiece;
 use dlc_manager::payout_curve::RoundingInterval;
 use dlc_manager::payout_curve::RoundingIntervals;
+use payout_curve::AccruedFundingFee;
+use payout_curve::Discretization;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 use tracing::instrument;
-use xxi_node::cfd::calculate_long_bankruptcy_price;
-use xxi_node::cfd::calculate_short_bankruptcy_price;
+use xxi_node::cfd::oracle_digits;
 use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::Direction;
 
+mod cache;
+
+pub use cache::get_range_payouts;
+
+/// The number of intervals between the liquidation prices of a payout function, spent mostly
+/// around the initial price.
+///
+/// This bounds the number of CETs, which dominate the time it takes to negotiate a DLC and the
+/// size of the messages.
+const PAYOUT_CURVE_INTERVALS: u64 = 100;
+
 /// Builds the contract descriptor from the point of view of the coordinator.
 ///
 /// It's the direction of the coordinator because the coordinator is always proposing.
-#[instrument]
 #[allow(clippy::too_many_arguments)]
 pub fn build_contract_descriptor(
     initial_price: Decimal,
@@ -35,15 +46,49 @@ pub fn build_contract_descriptor(
     quantity: f32,
     symbol: ContractSymbol,
 ) -> Result<ContractDescriptor> {
-    ensure!(
-        symbol == ContractSymbol::BtcUsd,
-        "We only support BTCUSD at the moment. \
-         For other symbols we will need a different payout curve"
-    );
+    build_contract_descriptor_with_funding_fee(
+        initial_price,
+        coordinator_margin,
+        trader_margin,
+        leverage_coordinator,
+        leverage_trader,
+        coordinator_direction,
+        coordinator_collateral_reserve,
+        trader_collateral_reserve,
+        quantity,
+        symbol,
+        FundingFee::Zero,
+    )
+}
+
+/// Builds the contract descriptor like [`build_contract_descriptor`], with the accrued
+/// `funding_fee` settled in the payout function.
+///
+/// The margins, leverages and collateral reserves are the ones before the funding fee is paid. The
+/// paying party pays it with its margin, which moves its liquidation price, and the other party
+/// gets it on top of its collateral reserve. See
+/// [`payout_curve::build_payout_points_with_funding_fee`].
+#[instrument]
+#[allow(clippy::too_many_arguments)]
+pub fn build_contract_descriptor_with_funding_fee(
+    initial_price: Decimal,
+    coordinator_margin: Amount,
+    trader_margin: Amount,
+    leverage_coordinator: f32,
+    leverage_trader: f32,
+    coordinator_direction: Direction,
+    coordinator_collateral_reserve: Amount,
+    trader_collateral_reserve: Amount,
+    quantity: f32,
+    symbol: ContractSymbol,
+    funding_fee: FundingFee,
+) -> Result<ContractDescriptor> {
+    let contract_type = symbol.contract_type();
 
-    tracing::info!("Building contract descriptor");
+    tracing::info!(?contract_type, "Building contract descriptor");
 
-    let (payout_function, rounding_intervals) = build_inverse_payout_function(
+    let (payout_function, rounding_intervals) = build_payout_function(
+        symbol,
         coordinator_margin,
         trader_margin,
         initial_price,
@@ -53,6 +98,7 @@ pub fn build_contract_descriptor(
         trader_collateral_reserve,
         coordinator_direction,
         quantity,
+        funding_fee,
     )?;
 
     Ok(ContractDescriptor::Numerical(NumericalDescriptor {
@@ -61,17 +107,19 @@ pub fn build_contract_descriptor(
         difference_params: None,
         oracle_numeric_infos: dlc_trie::OracleNumericInfo {
             base: 2,
-            nb_digits: vec![20],
+            nb_digits: vec![oracle_digits(symbol)],
         },
     }))
 }
 
-/// Build a [`PayoutFunction`] for an inverse perpetual future e.g. BTCUSD. Perspective is always
-/// from the person who offers, i.e. in our case from the coordinator.
+/// Build a [`PayoutFunction`] for a perpetual future on the given [`ContractSymbol`], e.g. an
+/// inverse one for BTCUSD. Perspective is always from the person who offers, i.e. in our case from
+/// the coordinator.
 ///
 /// Additionally returns the [`RoundingIntervals`] to indicate how it should be discretized.
 #[allow(clippy::too_many_arguments)]
-fn build_inverse_payout_function(
+fn build_payout_function(
+    symbol: ContractSymbol,
     // TODO: The `coordinator_margin` and `trader_margin` are _not_ orthogonal to the other
     // arguments passed in.
     coordinator_margin: Amount,
@@ -83,40 +131,37 @@ fn build_inverse_payout_function(
     trader_collateral_reserve: Amount,
     coordinator_direction: Direction,
     quantity: f32,
+    funding_fee: FundingFee,
 ) -> Result<(PayoutFunction, RoundingIntervals)> {
     let leverage_coordinator =
         Decimal::from_f32(leverage_coordinator).expect("to fit into decimal");
     let leverage_trader = Decimal::from_f32(leverage_trader).expect("to fit into decimal");
 
-    let (coordinator_liquidation_price, trader_liquidation_price) = get_liquidation_prices(
-        initial_price,
-        coordinator_direction,
-        leverage_coordinator,
-        leverage_trader,
-    );
-
-    let (long_liquidation_price, short_liquidation_price) = match coordinator_direction {
-        Direction::Long => (coordinator_liquidation_price, trader_liquidation_price),
-        Direction::Short => (trader_liquidation_price, coordinator_liquidation_price),
-    };
-
-    let price_params = payout_curve::PriceParams::new_btc_usd(
-        initial_price,
-        long_liquidation_price,
-        short_liquidation_price,
-    )?;
-
     let party_params_coordinator =
         payout_curve::PartyParams::new(coordinator_margin, coordinator_collateral_reserve);
     let party_params_trader =
         payout_curve::PartyParams::new(trader_margin, trader_collateral_reserve);
 
-    let payout_points = payout_curve::build_inverse_payout_function(
+    // The coordinator is always the offer party.
+    let funding_fee = match funding_fee {
+        FundingFee::Zero => AccruedFundingFee::Zero,
+        FundingFee::CoordinatorPays(fee) => AccruedFundingFee::OfferPays(fee),
+        FundingFee::TraderPays(fee) => AccruedFundingFee::AcceptPays(fee),
+    };
+
+    let payout_points = payout_curve::build_payout_points_with_funding_fee(
+        symbol,
+        initial_price,
         quantity,
         party_params_coordinator,
         party_params_trader,
-        price_params,
+        leverage_coordinator,
+        leverage_trader,
         coordinator_direction,
+        Discretization::Adaptive {
+            intervals: PAYOUT_CURVE_INTERVALS,
+        },
+        funding_fee,
     )?;
 
     let mut pieces = vec![];
@@ -150,33 +195,14 @@ fn build_inverse_payout_function(
     Ok((payout_function, rounding_intervals))
 }
 
-/// Returns the liquidation price for `(coordinator, maker)` with a maintenance margin of 0%. also
-/// known as the bankruptcy price.
-fn get_liquidation_prices(
-    initial_price: Decimal,
-    coordinator_direction: Direction,
-    leverage_coordinator: Decimal,
-    leverage_trader: Decimal,
-) -> (Decimal, Decimal) {
-    let (coordinator_liquidation_price, trader_liquidation_price) = match coordinator_direction {
-        Direction::Long => (
-            calculate_long_bankruptcy_price(leverage_coordinator, initial_price),
-            calculate_short_bankruptcy_price(leverage_trader, initial_price),
-        ),
-        Direction::Short => (
-            calculate_short_bankruptcy_price(leverage_coordinator, initial_price),
-            calculate_long_bankruptcy_price(leverage_trader, initial_price),
-        ),
-    };
-    (coordinator_liquidation_price, trader_liquidation_price)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use proptest::prelude::*;
     use rust_decimal_macros::dec;
     use xxi_node::cfd::calculate_margin;
+    use xxi_node::commons::fold_dust_reserve;
+    use xxi_node::commons::is_dust;
 
     #[test]
     fn payout_price_range_is_below_max_price() {
@@ -375,6 +401,9 @@ mod tests {
                 .payout
                 .offer;
 
+            // A collateral reserve below the dust limit is folded into the margin.
+            let (_, collateral_reserve_coordinator) =
+                fold_dust_reserve(margin_coordinator, collateral_reserve_coordinator);
             assert_eq!(liquidation_payout_offer, collateral_reserve_coordinator.to_sat());
 
             let liquidation_payout_accept = range_payouts
@@ -384,44 +413,60 @@ mod tests {
                 .payout
                 .accept;
 
+            let (_, collateral_reserve_trader) =
+                fold_dust_reserve(margin_trader, collateral_reserve_trader);
             assert_eq!(liquidation_payout_accept, collateral_reserve_trader.to_sat());
         }
     }
 
     #[test]
-    fn calculate_liquidation_price_coordinator_long() {
-        let initial_price = dec!(30_000);
-        let coordinator_direction = Direction::Long;
-        let leverage_coordinator = dec!(2.0);
-        let leverage_trader = dec!(3.0);
+    fn rollover_with_dust_collateral_reserves() {
+        let initial_price = dec!(60_000);
+        let quantity = 1_000.0;
+        let leverage_coordinator = 2.0;
+        let coordinator_margin = calculate_margin(initial_price, quantity, leverage_coordinator);
 
-        let (coordinator, maker) = get_liquidation_prices(
-            initial_price,
-            coordinator_direction,
-            leverage_coordinator,
-            leverage_trader,
-        );
+        let leverage_trader = 2.0;
+        let trader_margin = calculate_margin(initial_price, quantity, leverage_trader);
 
-        assert_eq!(coordinator, dec!(20_000));
-        assert_eq!(maker, dec!(45_000));
-    }
+        // E.g. what is left of the trader's collateral reserve after a Lightning withdrawal.
+        let coordinator_collateral_reserve = Amount::ZERO;
+        let trader_collateral_reserve = Amount::from_sat(700);
 
-    #[test]
-    fn calculate_liquidation_price_coordinator_short() {
-        let initial_price = dec!(30_000);
-        let coordinator_direction = Direction::Short;
-        let leverage_coordinator = dec!(2.0);
-        let leverage_trader = dec!(3.0);
+        let total_collateral = coordinator_margin
+            + trader_margin
+            + coordinator_collateral_reserve
+            + trader_collateral_reserve;
 
-        let (coordinator, maker) = get_liquidation_prices(
+        // The funding fee leaves the coordinator with a collateral reserve below the dust limit
+        // too.
+        let descriptor = build_contract_descriptor_with_funding_fee(
             initial_price,
-            coordinator_direction,
+            coordinator_margin,
+            trader_margin,
             leverage_coordinator,
             leverage_trader,
-        );
+            Direction::Short,
+            coordinator_collateral_reserve,
+            trader_collateral_reserve,
+            quantity,
+            ContractSymbol::BtcUsd,
+            FundingFee::TraderPays(Amount::from_sat(300)),
+        )
+        .unwrap();
+
+        let range_payouts = match descriptor {
+            ContractDescriptor::Enum(_) => unreachable!(),
+            ContractDescriptor::Numerical(numerical) => numerical
+                .get_range_payouts(total_collateral.to_sat())
+                .unwrap(),
+        };
 
-        assert_eq!(coordinator, dec!(60_000));
-        assert_eq!(maker, dec!(22_500));
+        // No CET has a dust output.
+        for range_payout in &range_payouts {
+            assert!(!is_dust(Amount::from_sat(range_payout.payout.offer)));
+            assert!(!is_dust(Amount::from_sat(range_payout.payout.accept)));
+        }
     }
 
     #[test]
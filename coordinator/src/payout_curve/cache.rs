@@ -0,0 +1,207 @@
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use dlc::RangePayout;
+use dlc_manager::contract::numerical_descriptor::NumericalDescriptor;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use prometheus::register_int_counter_vec;
+use prometheus::IntCounterVec;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+/// How many outcome structures are kept. Roughly one per open position is needed to hit the cache
+/// for every contract of the week.
+const CAPACITY: usize = 2_000;
+
+lazy_static! {
+    static ref RANGE_PAYOUTS: Mutex<RangePayoutCache> = Mutex::new(RangePayoutCache::new(CAPACITY));
+    static ref CACHE_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "coordinator_range_payout_cache_lookups_total",
+        "Lookups of precomputed range payouts, by whether they were cached.",
+        &["result"]
+    )
+    .expect("valid metric");
+}
+
+/// Identifies the outcome structure of a contract, i.e. the payout for every range of outcomes the
+/// CETs are built from.
+///
+/// E.g. a rollover without funding fees or reserve interest yields the same key as the previous
+/// contract of the channel, since only the maturity changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct CacheKey {
+    /// The hash of the payout function, its rounding intervals and the oracle digits.
+    payout_function: sha256::Hash,
+    total_collateral: u64,
+}
+
+impl CacheKey {
+    fn new(descriptor: &NumericalDescriptor, total_collateral: u64) -> Result<Self> {
+        let descriptor =
+            serde_json::to_vec(descriptor).context("Could not serialize payout function")?;
+
+        Ok(Self {
+            payout_function: sha256::Hash::hash(&descriptor),
+            total_collateral,
+        })
+    }
+}
+
+/// Compute the range payouts of the payout function in `descriptor`, reusing the ones of an
+/// identical payout function with the same total collateral if they are still cached.
+pub fn get_range_payouts(
+    descriptor: &NumericalDescriptor,
+    total_collateral: u64,
+) -> Result<Vec<RangePayout>> {
+    let key = CacheKey::new(descriptor, total_collateral)?;
+
+    let cached = RANGE_PAYOUTS.lock().get(&key);
+
+    let result = if cached.is_some() { "hit" } else { "miss" };
+    CACHE_LOOKUPS.with_label_values(&[result]).inc();
+
+    if let Some(range_payouts) = cached {
+        return Ok(range_payouts);
+    }
+
+    let range_payouts = descriptor
+        .get_range_payouts(total_collateral)
+        .context("Could not compute range payouts")?;
+
+    RANGE_PAYOUTS.lock().insert(key, range_payouts.clone());
+
+    Ok(range_payouts)
+}
+
+/// A map of bounded size, which evicts the oldest entry once it is full.
+struct RangePayoutCache {
+    capacity: usize,
+    range_payouts: HashMap<CacheKey, Vec<RangePayout>>,
+    insertion_order: VecDeque<CacheKey>,
+}
+
+impl RangePayoutCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            range_payouts: HashMap::new(),
+            insertion_order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<Vec<RangePayout>> {
+        self.range_payouts.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: CacheKey, range_payouts: Vec<RangePayout>) {
+        if self.range_payouts.insert(key, range_payouts).is_some() {
+            return;
+        }
+
+        self.insertion_order.push_back(key);
+        if self.insertion_order.len() > self.capacity {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.range_payouts.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payout_curve::build_contract_descriptor;
+    use bitcoin::Amount;
+    use dlc_manager::contract::ContractDescriptor;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use xxi_node::commons::ContractSymbol;
+    use xxi_node::commons::Direction;
+
+    #[test]
+    fn oldest_range_payouts_are_evicted() {
+        let mut cache = RangePayoutCache::new(2);
+
+        cache.insert(key(1), vec![]);
+        cache.insert(key(2), vec![]);
+        cache.insert(key(3), vec![]);
+
+        assert!(cache.get(&key(1)).is_none());
+        assert!(cache.get(&key(2)).is_some());
+        assert!(cache.get(&key(3)).is_some());
+    }
+
+    #[test]
+    fn reinserting_range_payouts_does_not_evict_others() {
+        let mut cache = RangePayoutCache::new(2);
+
+        cache.insert(key(1), vec![]);
+        cache.insert(key(2), vec![]);
+        cache.insert(key(2), vec![]);
+
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(2)).is_some());
+    }
+
+    #[test]
+    fn identical_payout_functions_share_a_key() {
+        let total_collateral = 1_450_000;
+
+        let a = CacheKey::new(&descriptor(dec!(30_000)), total_collateral).unwrap();
+        let b = CacheKey::new(&descriptor(dec!(30_000)), total_collateral).unwrap();
+        let other_price = CacheKey::new(&descriptor(dec!(40_000)), total_collateral).unwrap();
+        let other_collateral = CacheKey::new(&descriptor(dec!(30_000)), 2_000_000).unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, other_price);
+        assert_ne!(a, other_collateral);
+    }
+
+    #[test]
+    fn cached_range_payouts_match_computed_ones() {
+        let descriptor = descriptor(dec!(30_000));
+        let total_collateral = 1_450_000;
+
+        let computed = descriptor.get_range_payouts(total_collateral).unwrap();
+
+        // The first lookup computes the range payouts, the second one hits the cache.
+        assert_eq!(
+            get_range_payouts(&descriptor, total_collateral).unwrap(),
+            computed
+        );
+        assert_eq!(
+            get_range_payouts(&descriptor, total_collateral).unwrap(),
+            computed
+        );
+    }
+
+    fn key(n: u8) -> CacheKey {
+        CacheKey {
+            payout_function: sha256::Hash::hash(&[n]),
+            total_collateral: 1_000_000,
+        }
+    }
+
+    fn descriptor(initial_price: Decimal) -> NumericalDescriptor {
+        let contract_descriptor = build_contract_descriptor(
+            initial_price,
+            Amount::from_sat(1_000_000),
+            Amount::from_sat(400_000),
+            1.0,
+            2.5,
+            Direction::Short,
+            Amount::ZERO,
+            Amount::from_sat(50_000),
+            300.0,
+            ContractSymbol::BtcUsd,
+        )
+        .unwrap();
+
+        match contract_descriptor {
+            ContractDescriptor::Numerical(descriptor) => descriptor,
+            ContractDescriptor::Enum(_) => unreachable!("numerical descriptor"),
+        }
+    }
+}
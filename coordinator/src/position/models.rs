@@ -18,10 +18,10 @@ use rust_decimal::Decimal;
 use time::OffsetDateTime;
 use xxi_node::bitmex_client::Quote;
 use xxi_node::cfd::calculate_leverage;
-use xxi_node::cfd::calculate_long_liquidation_price;
+use xxi_node::cfd::calculate_liquidation_price;
 use xxi_node::cfd::calculate_margin;
 use xxi_node::cfd::calculate_pnl;
-use xxi_node::cfd::calculate_short_liquidation_price;
+use xxi_node::cfd::calculate_pnl_for_symbol;
 use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::Direction;
 use xxi_node::commons::TradeParams;
@@ -141,7 +141,8 @@ impl Position {
         let long_margin = calculate_margin(average_entry_price, self.quantity, long_leverage);
         let short_margin = calculate_margin(average_entry_price, self.quantity, short_leverage);
 
-        let pnl = calculate_pnl(
+        let pnl = calculate_pnl_for_symbol(
+            self.contract_symbol,
             average_entry_price,
             closing_price,
             self.quantity,
@@ -226,18 +227,13 @@ impl Position {
                 let new_coordinator_leverage =
                     calculate_leverage(quantity, new_coordinator_margin, average_entry_price);
 
-                let new_coordinator_liquidation_price = match self.trader_direction.opposite() {
-                    Direction::Long => calculate_long_liquidation_price(
-                        new_coordinator_leverage,
-                        average_entry_price,
-                        maintenance_margin_rate,
-                    ),
-                    Direction::Short => calculate_short_liquidation_price(
-                        new_coordinator_leverage,
-                        average_entry_price,
-                        maintenance_margin_rate,
-                    ),
-                };
+                let new_coordinator_liquidation_price = calculate_liquidation_price(
+                    self.contract_symbol,
+                    self.trader_direction.opposite(),
+                    new_coordinator_leverage,
+                    average_entry_price,
+                    maintenance_margin_rate,
+                );
 
                 Self {
                     coordinator_margin: new_coordinator_margin,
@@ -258,18 +254,13 @@ impl Position {
                 let new_trader_leverage =
                     calculate_leverage(quantity, new_trader_margin, average_entry_price);
 
-                let new_trader_liquidation_price = match self.trader_direction {
-                    Direction::Long => calculate_long_liquidation_price(
-                        new_trader_leverage,
-                        average_entry_price,
-                        maintenance_margin_rate,
-                    ),
-                    Direction::Short => calculate_short_liquidation_price(
-                        new_trader_leverage,
-                        average_entry_price,
-                        maintenance_margin_rate,
-                    ),
-                };
+                let new_trader_liquidation_price = calculate_liquidation_price(
+                    self.contract_symbol,
+                    self.trader_direction,
+                    new_trader_leverage,
+                    average_entry_price,
+                    maintenance_margin_rate,
+                );
 
                 Self {
                     trader_margin: new_trader_margin,
@@ -1290,6 +1281,7 @@ mod tests {
         let trader_margin = calculate_margin(initial_price, quantity, trader_leverage as f32);
 
         let coordinator_liquidation_price = liquidation_price(
+            ContractSymbol::BtcUsd,
             initial_price,
             Decimal::from(coordinator_leverage),
             trader_direction.opposite(),
@@ -1297,6 +1289,7 @@ mod tests {
         );
 
         let trader_liquidation_price = liquidation_price(
+            ContractSymbol::BtcUsd,
             initial_price,
             Decimal::from(trader_leverage),
             trader_direction,
@@ -0,0 +1,238 @@
+//! The risk of an active position once funding fees have accrued.
+//!
+//! The liquidation prices stored with a position are only updated when the outstanding funding
+//! fees are settled in the DLC channel, e.g. on rollover. Until then, the effective liquidation
+//! prices are computed from the outstanding funding fee events, so they change as soon as a new
+//! funding fee event is created.
+
+use crate::db;
+use crate::decimal_from_f32;
+use crate::funding_fee::funding_fee_from_funding_fee_events;
+use crate::funding_fee::get_outstanding_funding_fee_events;
+use crate::funding_fee::FundingFeeEvent;
+use crate::position::models::Position;
+use crate::position::models::PositionState;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use diesel::PgConnection;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use xxi_node::cfd::calculate_margin_ratio;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PositionRisk {
+    pub position_id: i32,
+    /// The price at which the trader gets liquidated, given the margin left after funding fees.
+    pub trader_liquidation_price: Decimal,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub trader_margin: Amount,
+    pub trader_margin_ratio: Decimal,
+    /// The price at which the coordinator gets liquidated, given the margin left after funding
+    /// fees.
+    pub coordinator_liquidation_price: Decimal,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub coordinator_margin: Amount,
+    pub coordinator_margin_ratio: Decimal,
+    /// The funding fees which have not been settled in the DLC channel yet. A positive amount
+    /// indicates that the trader pays the coordinator.
+    ///
+    /// The party paying the funding fee pays it from its margin. The party earning it gets it
+    /// added to its collateral reserve, which leaves its liquidation price unchanged.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub outstanding_funding_fee: SignedAmount,
+}
+
+/// The risk of the active position of the trader, if any, accounting for all the funding fee
+/// events which are still outstanding.
+pub fn get_position_risk(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    maintenance_margin_rate: Decimal,
+) -> Result<Option<PositionRisk>> {
+    let position = db::positions::Position::get_position_by_trader(
+        conn,
+        trader_pubkey,
+        vec![
+            PositionState::Open,
+            PositionState::Rollover,
+            PositionState::Resizing,
+        ],
+    )?;
+
+    let position = match position {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let funding_fee_events = get_outstanding_funding_fee_events(conn, trader_pubkey, position.id)?;
+
+    Ok(Some(calculate_position_risk(
+        position,
+        &funding_fee_events,
+        maintenance_margin_rate,
+    )))
+}
+
+fn calculate_position_risk(
+    position: Position,
+    funding_fee_events: &[FundingFeeEvent],
+    maintenance_margin_rate: Decimal,
+) -> PositionRisk {
+    let outstanding_funding_fee = funding_fee_events.iter().map(|event| event.amount).sum();
+
+    let funding_fee = funding_fee_from_funding_fee_events(funding_fee_events);
+    let position = position.apply_funding_fee(funding_fee, maintenance_margin_rate);
+
+    PositionRisk {
+        position_id: position.id,
+        trader_liquidation_price: decimal_from_f32(position.trader_liquidation_price),
+        trader_margin: position.trader_margin,
+        trader_margin_ratio: calculate_margin_ratio(
+            decimal_from_f32(position.trader_leverage),
+            maintenance_margin_rate,
+        ),
+        coordinator_liquidation_price: decimal_from_f32(position.coordinator_liquidation_price),
+        coordinator_margin: position.coordinator_margin,
+        coordinator_margin_ratio: calculate_margin_ratio(
+            decimal_from_f32(position.coordinator_leverage),
+            maintenance_margin_rate,
+        ),
+        outstanding_funding_fee,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::f32_from_decimal;
+    use crate::trade::liquidation_price;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::OffsetDateTime;
+    use xxi_node::commons::ContractSymbol;
+    use xxi_node::commons::Direction;
+
+    const MAINTENANCE_MARGIN_RATE: Decimal = dec!(0.1);
+
+    #[test]
+    fn risk_without_funding_fees_is_the_one_at_open() {
+        let position = position();
+
+        let risk = calculate_position_risk(position, &[], MAINTENANCE_MARGIN_RATE);
+
+        assert_eq!(
+            risk.trader_liquidation_price,
+            decimal_from_f32(position.trader_liquidation_price)
+        );
+        assert_eq!(risk.trader_margin, position.trader_margin);
+        assert_eq!(risk.trader_margin_ratio, dec!(0.2));
+        assert_eq!(risk.coordinator_margin_ratio, dec!(0.2));
+        assert_eq!(risk.outstanding_funding_fee, SignedAmount::ZERO);
+    }
+
+    #[test]
+    fn funding_fees_paid_by_the_trader_move_their_liquidation_price() {
+        let position = position();
+        let events = [
+            funding_fee_event(SignedAmount::from_sat(15_000)),
+            funding_fee_event(SignedAmount::from_sat(10_000)),
+        ];
+
+        let risk = calculate_position_risk(position, &events, MAINTENANCE_MARGIN_RATE);
+
+        assert_eq!(risk.trader_margin, Amount::from_sat(100_000));
+        assert_eq!(risk.trader_margin_ratio, dec!(0.25));
+        assert!(
+            risk.trader_liquidation_price > decimal_from_f32(position.trader_liquidation_price)
+        );
+        assert_eq!(
+            risk.coordinator_liquidation_price,
+            decimal_from_f32(position.coordinator_liquidation_price)
+        );
+        assert_eq!(risk.outstanding_funding_fee, SignedAmount::from_sat(25_000));
+    }
+
+    #[test]
+    fn funding_fees_earned_by_the_trader_leave_their_liquidation_price_unchanged() {
+        let position = position();
+        let events = [funding_fee_event(SignedAmount::from_sat(-25_000))];
+
+        let risk = calculate_position_risk(position, &events, MAINTENANCE_MARGIN_RATE);
+
+        assert_eq!(
+            risk.trader_liquidation_price,
+            decimal_from_f32(position.trader_liquidation_price)
+        );
+        assert_eq!(risk.trader_margin, position.trader_margin);
+        assert_eq!(risk.coordinator_margin, Amount::from_sat(100_000));
+        assert!(
+            risk.coordinator_liquidation_price
+                < decimal_from_f32(position.coordinator_liquidation_price)
+        );
+        assert_eq!(
+            risk.outstanding_funding_fee,
+            SignedAmount::from_sat(-25_000)
+        );
+    }
+
+    fn position() -> Position {
+        let initial_price = dec!(40_000);
+        let leverage = Decimal::TWO;
+
+        Position {
+            id: 1,
+            trader: trader(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity: 100.0,
+            trader_direction: Direction::Long,
+            average_entry_price: 40_000.0,
+            closing_price: None,
+            trader_realized_pnl_sat: None,
+            trader_liquidation_price: f32_from_decimal(liquidation_price(
+                ContractSymbol::BtcUsd,
+                initial_price,
+                leverage,
+                Direction::Long,
+                MAINTENANCE_MARGIN_RATE,
+            )),
+            coordinator_liquidation_price: f32_from_decimal(liquidation_price(
+                ContractSymbol::BtcUsd,
+                initial_price,
+                leverage,
+                Direction::Short,
+                MAINTENANCE_MARGIN_RATE,
+            )),
+            trader_margin: Amount::from_sat(125_000),
+            coordinator_margin: Amount::from_sat(125_000),
+            trader_leverage: 2.0,
+            coordinator_leverage: 2.0,
+            position_state: PositionState::Open,
+            order_matching_fees: Amount::ZERO,
+            creation_timestamp: OffsetDateTime::now_utc(),
+            expiry_timestamp: OffsetDateTime::now_utc(),
+            update_timestamp: OffsetDateTime::now_utc(),
+            temporary_contract_id: None,
+            stable: false,
+        }
+    }
+
+    fn funding_fee_event(amount: SignedAmount) -> FundingFeeEvent {
+        FundingFeeEvent {
+            id: 1,
+            amount,
+            trader_pubkey: trader(),
+            position_id: 1,
+            due_date: OffsetDateTime::now_utc(),
+            price: dec!(40_000),
+            funding_rate: dec!(0.001),
+            paid_date: None,
+        }
+    }
+
+    fn trader() -> PublicKey {
+        PublicKey::from_str("02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655")
+            .unwrap()
+    }
+}
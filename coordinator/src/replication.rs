@@ -0,0 +1,512 @@
+//! Continuous replication of the DLC storage to a warm standby location.
+//!
+//! The DLC channel state lives in sled and not in the database, so the database backups alone
+//! cannot restore it. Every write to and delete from the DLC storage is recorded and shipped to
+//! the standby location within a few seconds, together with the DLC channel events for auditing.
+//!
+//! Every start of the coordinator begins a new generation, which starts with a full snapshot of
+//! the storage followed by numbered segments of the changes since. Replaying the snapshot and the
+//! segments of a generation in order rebuilds the storage, see [`restore`].
+
+use crate::retention::ObjectStorage;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fs;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::task::spawn_blocking;
+use xxi_node::node::event::NodeEvent;
+use xxi_node::storage::sled::SledStorageProvider;
+use xxi_node::storage::DlcStoreProvider;
+use xxi_node::storage::Op;
+
+/// How often the recorded changes are shipped, i.e. the maximum amount of channel state we can
+/// lose under normal operation.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The prefix of all replicated objects.
+const REPLICATION_PREFIX: &str = "dlc-storage";
+
+const SNAPSHOT_NAME: &str = "snapshot.jsonl.gz";
+
+/// Where the replicated DLC storage is shipped to.
+#[derive(Clone)]
+pub enum ReplicationTarget {
+    Directory(PathBuf),
+    ObjectStorage(ObjectStorage),
+}
+
+/// Records the changes to the DLC storage, to be shipped by the task started with [`spawn`].
+#[derive(Clone)]
+pub struct StorageReplication {
+    sender: mpsc::UnboundedSender<ReplicationOp>,
+}
+
+/// A change to the DLC storage. Keys and values are hex encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ReplicationOp {
+    Write {
+        kind: u8,
+        key: String,
+        value: String,
+    },
+    /// Deletes the whole `kind` if no key is given.
+    Delete { kind: u8, key: Option<String> },
+    /// Not applied on restore, but helps to reconstruct what happened to a channel.
+    ChannelEvent { event: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ReplicationRecord {
+    seq: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    timestamp: OffsetDateTime,
+    #[serde(flatten)]
+    op: ReplicationOp,
+}
+
+impl StorageReplication {
+    pub fn record_write(&self, kind: u8, key: &[u8], value: &[u8]) {
+        self.record(ReplicationOp::Write {
+            kind,
+            key: hex::encode(key),
+            value: hex::encode(value),
+        });
+    }
+
+    pub fn record_delete(&self, kind: u8, key: Option<&[u8]>) {
+        self.record(ReplicationOp::Delete {
+            kind,
+            key: key.map(hex::encode),
+        });
+    }
+
+    pub fn record_batch(&self, ops: &[Op]) {
+        for op in ops {
+            match op {
+                Op::Write { kind, key, value } => self.record_write(*kind, key, value),
+                Op::Delete { kind, key } => self.record_delete(*kind, key.as_deref()),
+            }
+        }
+    }
+
+    fn record(&self, op: ReplicationOp) {
+        if self.sender.send(op).is_err() {
+            tracing::error!("Failed to record DLC storage change, replication has stopped");
+        }
+    }
+}
+
+/// Start replicating the DLC storage to the `target`.
+///
+/// The returned [`StorageReplication`] has to be installed into the storage before the node
+/// starts writing to it, otherwise changes are missed until the next generation.
+pub fn spawn(
+    dlc_storage: Arc<SledStorageProvider>,
+    target: ReplicationTarget,
+    mut node_events: broadcast::Receiver<NodeEvent>,
+) -> StorageReplication {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let replication = StorageReplication { sender };
+
+    tokio::spawn({
+        let replication = replication.clone();
+        async move {
+            loop {
+                match node_events.recv().await {
+                    Ok(NodeEvent::DlcChannelEvent { dlc_channel_event }) => {
+                        replication.record(ReplicationOp::ChannelEvent {
+                            event: format!("{dlc_channel_event:?}"),
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Lagging behind on node events");
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let generation = generation_name(OffsetDateTime::now_utc());
+        tracing::info!(%generation, "Starting DLC storage replication");
+
+        // Changes recorded while the snapshot is taken are replayed on top of it, which is
+        // harmless as they are applied in the order they happened.
+        let snapshot = loop {
+            match upload_snapshot(&dlc_storage, &target, &generation).await {
+                Ok(entries) => break entries,
+                Err(e) => {
+                    tracing::error!("Failed to replicate DLC storage snapshot: {e:#}");
+                    tokio::time::sleep(FLUSH_INTERVAL).await;
+                }
+            }
+        };
+        tracing::info!(%generation, entries = snapshot, "Replicated DLC storage snapshot");
+
+        let mut next_seq = 0;
+        let mut pending = vec![];
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let mut closed = false;
+            loop {
+                match receiver.try_recv() {
+                    Ok(op) => {
+                        pending.push(ReplicationRecord {
+                            seq: next_seq,
+                            timestamp: OffsetDateTime::now_utc(),
+                            op,
+                        });
+                        next_seq += 1;
+                    }
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) => {
+                        closed = true;
+                        break;
+                    }
+                }
+            }
+
+            if let Some(first) = pending.first() {
+                // A failed segment is retried with the next flush, including the changes
+                // recorded in the meantime, so that the segments never have gaps.
+                let key = segment_key(&generation, first.seq);
+                let uploaded = async {
+                    let body = encode_jsonl(&pending)?;
+                    target.put(&key, body).await
+                }
+                .await;
+
+                match uploaded {
+                    Ok(()) => {
+                        tracing::debug!(key, changes = pending.len(), "Replicated changes");
+                        pending.clear();
+                    }
+                    Err(e) => {
+                        tracing::error!(key, "Failed to replicate DLC storage changes: {e:#}")
+                    }
+                }
+            }
+
+            if closed && pending.is_empty() {
+                tracing::info!(%generation, "Stopped DLC storage replication");
+                return;
+            }
+        }
+    });
+
+    replication
+}
+
+async fn upload_snapshot(
+    dlc_storage: &Arc<SledStorageProvider>,
+    target: &ReplicationTarget,
+    generation: &str,
+) -> Result<usize> {
+    let ops = spawn_blocking({
+        let dlc_storage = dlc_storage.clone();
+        move || {
+            dlc_storage
+                .export()
+                .into_iter()
+                .map(|entry| ReplicationOp::Write {
+                    kind: entry.kind,
+                    key: hex::encode(entry.key),
+                    value: hex::encode(entry.value),
+                })
+                .collect::<Vec<_>>()
+        }
+    })
+    .await
+    .expect("task to complete");
+
+    let body = encode_jsonl(&ops)?;
+    target
+        .put(
+            &format!("{REPLICATION_PREFIX}/{generation}/{SNAPSHOT_NAME}"),
+            body,
+        )
+        .await?;
+
+    Ok(ops.len())
+}
+
+impl ReplicationTarget {
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        match self {
+            ReplicationTarget::Directory(dir) => {
+                let path = dir.join(key);
+                let parent = path.parent().context("Missing parent directory")?;
+                tokio::fs::create_dir_all(parent).await?;
+
+                // Writing to a temporary file first, so that a restore never reads a partially
+                // written segment.
+                let tmp = path.with_extension("tmp");
+                tokio::fs::write(&tmp, body).await?;
+                tokio::fs::rename(&tmp, &path).await?;
+
+                Ok(())
+            }
+            ReplicationTarget::ObjectStorage(object_storage) => object_storage.put(key, body).await,
+        }
+    }
+}
+
+/// Rebuild the DLC storage from the latest generation replicated to `dir` into `dlc_storage`.
+///
+/// Objects replicated to an object storage have to be downloaded to a directory first, keeping
+/// their keys as paths. Returns the number of applied changes.
+pub fn restore(dir: &Path, dlc_storage: &SledStorageProvider) -> Result<usize> {
+    let generations = dir.join(REPLICATION_PREFIX);
+    let generation = fs::read_dir(&generations)
+        .with_context(|| format!("Could not read {}", generations.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(SNAPSHOT_NAME).exists())
+        .map(|entry| entry.path())
+        .max()
+        .context("No replicated generation found")?;
+
+    tracing::info!(generation = %generation.display(), "Restoring DLC storage");
+
+    let snapshot: Vec<ReplicationOp> = decode_jsonl(&generation.join(SNAPSHOT_NAME))?;
+    let mut applied = 0;
+    for op in snapshot {
+        apply(dlc_storage, op)?;
+        applied += 1;
+    }
+
+    let mut segments = fs::read_dir(&generation)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.ends_with(".jsonl.gz") && name != SNAPSHOT_NAME)
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+    segments.sort();
+
+    let mut next_seq = 0;
+    for segment in segments {
+        let records: Vec<ReplicationRecord> = decode_jsonl(&segment)?;
+        for record in records {
+            // A retried segment may contain the changes of an earlier attempt again.
+            if record.seq < next_seq {
+                continue;
+            }
+            ensure!(
+                record.seq == next_seq,
+                "Missing changes {next_seq}..{} before {}",
+                record.seq,
+                segment.display()
+            );
+
+            apply(dlc_storage, record.op)?;
+            applied += 1;
+            next_seq += 1;
+        }
+    }
+
+    tracing::info!(applied, "Restored DLC storage");
+
+    Ok(applied)
+}
+
+fn apply(dlc_storage: &SledStorageProvider, op: ReplicationOp) -> Result<()> {
+    match op {
+        ReplicationOp::Write { kind, key, value } => {
+            dlc_storage.write(kind, hex::decode(key)?, hex::decode(value)?)
+        }
+        ReplicationOp::Delete { kind, key } => {
+            let key = key.map(hex::decode).transpose()?;
+            dlc_storage.delete(kind, key)
+        }
+        ReplicationOp::ChannelEvent { .. } => Ok(()),
+    }
+}
+
+/// The name of a generation, e.g. `20240708T120000Z`. Sorts chronologically.
+fn generation_name(started_at: OffsetDateTime) -> String {
+    started_at
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .expect("to format timestamp")
+}
+
+/// The object key of a segment, e.g. `dlc-storage/20240708T120000Z/00000000000000000042.jsonl.gz`.
+fn segment_key(generation: &str, first_seq: u64) -> String {
+    format!("{REPLICATION_PREFIX}/{generation}/{first_seq:020}.jsonl.gz")
+}
+
+fn encode_jsonl<T: Serialize>(rows: &[T]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    for row in rows {
+        serde_json::to_writer(&mut encoder, row)?;
+        encoder.write_all(b"\n")?;
+    }
+
+    Ok(encoder.finish()?)
+}
+
+fn decode_jsonl<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Vec<T>> {
+    let file =
+        fs::File::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+
+    let mut rows = vec![];
+    for line in BufReader::new(GzDecoder::new(file)).lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(&line) {
+            Ok(row) => rows.push(row),
+            Err(e) => bail!("Malformed line in {}: {e}", path.display()),
+        }
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn segment_keys_sort_by_sequence() {
+        let generation = generation_name(datetime!(2024-07-08 12:00:00 UTC));
+
+        let first = segment_key(&generation, 9);
+        let second = segment_key(&generation, 10);
+
+        assert_eq!(
+            first,
+            "dlc-storage/20240708T120000Z/00000000000000000009.jsonl.gz"
+        );
+        assert!(first < second);
+    }
+
+    #[test]
+    fn restore_replays_snapshot_and_segments() {
+        let dir = std::env::temp_dir().join(format!("replication-{}", uuid::Uuid::new_v4()));
+        let generation_dir = dir.join(REPLICATION_PREFIX).join("20240708T120000Z");
+        fs::create_dir_all(&generation_dir).unwrap();
+
+        let snapshot = [
+            ReplicationOp::Write {
+                kind: 2,
+                key: hex::encode("channel"),
+                value: hex::encode("offered"),
+            },
+            ReplicationOp::Write {
+                kind: 1,
+                key: hex::encode("contract"),
+                value: hex::encode("offered"),
+            },
+        ];
+        fs::write(
+            generation_dir.join(SNAPSHOT_NAME),
+            encode_jsonl(&snapshot).unwrap(),
+        )
+        .unwrap();
+
+        let records = [
+            ReplicationOp::Write {
+                kind: 2,
+                key: hex::encode("channel"),
+                value: hex::encode("signed"),
+            },
+            ReplicationOp::ChannelEvent {
+                event: "Established(None)".to_string(),
+            },
+            ReplicationOp::Delete {
+                kind: 1,
+                key: Some(hex::encode("contract")),
+            },
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(seq, op)| ReplicationRecord {
+            seq: seq as u64,
+            timestamp: datetime!(2024-07-08 12:00:01 UTC),
+            op,
+        })
+        .collect::<Vec<_>>();
+        fs::write(
+            generation_dir.join("00000000000000000000.jsonl.gz"),
+            encode_jsonl(&records[..2]).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            generation_dir.join("00000000000000000002.jsonl.gz"),
+            encode_jsonl(&records[2..]).unwrap(),
+        )
+        .unwrap();
+
+        let storage = SledStorageProvider::new(dir.join("restored").to_str().unwrap());
+        let applied = restore(&dir, &storage).unwrap();
+
+        let channels = storage.read(2, None).unwrap();
+        assert_eq!(applied, 5);
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].value, b"signed".to_vec());
+        assert!(storage.read(1, None).unwrap().is_empty());
+
+        drop(storage);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn restore_fails_on_missing_changes() {
+        let dir = std::env::temp_dir().join(format!("replication-{}", uuid::Uuid::new_v4()));
+        let generation_dir = dir.join(REPLICATION_PREFIX).join("20240708T120000Z");
+        fs::create_dir_all(&generation_dir).unwrap();
+
+        fs::write(
+            generation_dir.join(SNAPSHOT_NAME),
+            encode_jsonl::<ReplicationOp>(&[]).unwrap(),
+        )
+        .unwrap();
+        let record = ReplicationRecord {
+            seq: 3,
+            timestamp: datetime!(2024-07-08 12:00:01 UTC),
+            op: ReplicationOp::Delete { kind: 1, key: None },
+        };
+        fs::write(
+            generation_dir.join("00000000000000000003.jsonl.gz"),
+            encode_jsonl(&[record]).unwrap(),
+        )
+        .unwrap();
+
+        let storage = SledStorageProvider::new(dir.join("restored").to_str().unwrap());
+        assert!(restore(&dir, &storage).is_err());
+
+        drop(storage);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,465 @@
+use crate::node::Node;
+use crate::scheduler::Scheduler;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use dlc_manager::DlcChannelId;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use xxi_node::commons::ReserveInterest;
+
+mod db;
+
+pub use db::get_outstanding_reserve_interest_credits;
+pub use db::mark_reserve_interest_credits_as_paid;
+pub use db::set_protocol_id as set_reserve_interest_credits_protocol_id;
+
+const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// Interest accrued on the collateral reserve of a trader's DLC channel over a period of time.
+///
+/// The coordinator pays the credit by moving the amount from its own collateral reserve to the
+/// trader's collateral reserve during the next renew or rollover.
+#[derive(Clone, Copy, Debug)]
+pub struct ReserveInterestCredit {
+    pub id: i32,
+    pub trader_pubkey: PublicKey,
+    pub channel_id: DlcChannelId,
+    /// The trader's collateral reserve the interest was accrued on.
+    pub reserve: Amount,
+    pub apr: f32,
+    pub period_start: OffsetDateTime,
+    pub period_end: OffsetDateTime,
+    pub amount: Amount,
+    pub paid_date: Option<OffsetDateTime>,
+}
+
+pub async fn accrue_reserve_interest_periodically(
+    scheduler: &Scheduler,
+    node: Node,
+    schedule: String,
+) -> Result<()> {
+    scheduler
+        .add_job("accrue_reserve_interest", &schedule, move || {
+            let node = node.clone();
+            async move {
+                // Read the APR on every run, so that settings updates take effect without a
+                // restart.
+                let apr = node.settings.read().await.reserve_interest_apr;
+
+                spawn_blocking(move || {
+                    accrue_reserve_interest(&node.pool, apr, OffsetDateTime::now_utc(), false)
+                })
+                .await
+                .expect("task to complete")
+            }
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Close the current accrual periods of all open DLC channels at `apr`, right before the APR is
+/// changed to another value.
+///
+/// Otherwise, the new APR would also apply to the time before the change.
+pub async fn close_accrual_periods(
+    pool: Pool<ConnectionManager<PgConnection>>,
+    apr: f32,
+) -> Result<()> {
+    spawn_blocking(move || accrue_reserve_interest(&pool, apr, OffsetDateTime::now_utc(), true))
+        .await
+        .expect("task to complete")
+}
+
+/// What to record for the current accrual period of a DLC channel.
+#[derive(Debug, PartialEq)]
+enum Accrual {
+    /// Keep accruing, the interest is not worth a sat yet.
+    Pending,
+    Credit {
+        period_start: OffsetDateTime,
+        amount: Amount,
+    },
+    /// Record the period without interest, so that a later APR does not apply to it.
+    WithoutInterest { period_start: OffsetDateTime },
+    /// Extend the last period without interest, which was recorded at the same APR.
+    ExtendWithoutInterest { id: i32 },
+}
+
+/// Accrue interest on the trader's collateral reserve of every open DLC channel.
+///
+/// Interest accrues from the end of the last accrual period of a DLC channel, or from the
+/// creation of the DLC channel, using the trader's collateral reserve at the time of accrual. A
+/// credit is only recorded once it is worth at least one sat, so that frequent accruals do not
+/// lose interest to rounding.
+///
+/// While the APR is not positive, the time is recorded as a period without interest. That way,
+/// interest only ever accrues from the moment it was enabled at the current APR. With `close`, the
+/// current periods are recorded even if they are not worth a sat, because the APR is about to
+/// change.
+fn accrue_reserve_interest(
+    pool: &Pool<ConnectionManager<PgConnection>>,
+    apr: f32,
+    now: OffsetDateTime,
+    close: bool,
+) -> Result<()> {
+    let mut conn = pool.get()?;
+
+    let apr_decimal = Decimal::from_f32(apr).context("Invalid reserve interest APR")?;
+
+    for channel in crate::db::dlc_channels::get_open_dlc_channels(&mut conn)? {
+        let last_period = db::get_last_period(&mut conn, &channel.channel_id)?;
+
+        let accrual = next_accrual(
+            last_period.as_ref(),
+            channel.created_at,
+            channel.trader_reserve_sats,
+            apr,
+            apr_decimal,
+            now,
+            close,
+        );
+
+        match accrual {
+            Accrual::Pending => {}
+            Accrual::Credit {
+                period_start,
+                amount,
+            } => {
+                db::insert(
+                    &mut conn,
+                    channel.trader,
+                    &channel.channel_id,
+                    channel.trader_reserve_sats,
+                    apr,
+                    period_start,
+                    now,
+                    amount,
+                )
+                .context("Failed to insert reserve interest credit")?;
+
+                tracing::debug!(
+                    trader_pubkey = %channel.trader,
+                    channel_id = hex::encode(channel.channel_id),
+                    reserve = %channel.trader_reserve_sats,
+                    %amount,
+                    "Accrued reserve interest"
+                );
+            }
+            Accrual::WithoutInterest { period_start } => {
+                db::insert_without_interest(
+                    &mut conn,
+                    channel.trader,
+                    &channel.channel_id,
+                    channel.trader_reserve_sats,
+                    apr,
+                    period_start,
+                    now,
+                )
+                .context("Failed to insert reserve interest period")?;
+            }
+            Accrual::ExtendWithoutInterest { id } => {
+                db::extend_period(&mut conn, id, now)
+                    .context("Failed to extend reserve interest period")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn next_accrual(
+    last_period: Option<&ReserveInterestCredit>,
+    channel_created_at: OffsetDateTime,
+    reserve: Amount,
+    apr: f32,
+    apr_decimal: Decimal,
+    now: OffsetDateTime,
+    close: bool,
+) -> Accrual {
+    if apr <= 0.0 {
+        return match last_period {
+            Some(last_period) if last_period.amount == Amount::ZERO && last_period.apr == apr => {
+                Accrual::ExtendWithoutInterest { id: last_period.id }
+            }
+            Some(last_period) => Accrual::WithoutInterest {
+                period_start: last_period.period_end,
+            },
+            None => Accrual::WithoutInterest {
+                period_start: channel_created_at,
+            },
+        };
+    }
+
+    let period_start = last_period
+        .map(|last_period| last_period.period_end)
+        .unwrap_or(channel_created_at);
+
+    let amount = calculate_reserve_interest(reserve, apr_decimal, now - period_start);
+
+    if amount > Amount::ZERO {
+        Accrual::Credit {
+            period_start,
+            amount,
+        }
+    } else if close {
+        Accrual::WithoutInterest { period_start }
+    } else {
+        Accrual::Pending
+    }
+}
+
+/// Calculate the interest earned on `reserve` over `period` at the given annual percentage rate.
+///
+/// The interest is rounded down to the next sat.
+fn calculate_reserve_interest(reserve: Amount, apr: Decimal, period: time::Duration) -> Amount {
+    if period.is_negative() {
+        return Amount::ZERO;
+    }
+
+    let reserve = Decimal::from(reserve.to_sat());
+    let period = Decimal::from(period.whole_seconds()) / Decimal::from(SECONDS_PER_YEAR);
+
+    let interest = (reserve * apr * period)
+        .round_dp_with_strategy(0, RoundingStrategy::ToZero)
+        .to_u64()
+        .expect("to fit");
+
+    Amount::from_sat(interest)
+}
+
+/// Move the outstanding reserve interest credits of a trader from the coordinator's to the
+/// trader's collateral reserve.
+///
+/// Returns the updated collateral reserves of the coordinator and the trader and the IDs of the
+/// applied credits. If the coordinator's collateral reserve is insufficient, no credits are
+/// applied.
+pub fn apply_reserve_interest_credits(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    collateral_reserve_coordinator: Amount,
+    collateral_reserve_trader: Amount,
+) -> Result<(Amount, Amount, Vec<i32>)> {
+    let credits = get_outstanding_reserve_interest_credits(conn, trader_pubkey)?;
+    let credit = credits.iter().map(|credit| credit.amount).sum::<Amount>();
+
+    let collateral_reserve_coordinator = match collateral_reserve_coordinator.checked_sub(credit) {
+        Some(collateral_reserve_coordinator) => collateral_reserve_coordinator,
+        None => {
+            tracing::warn!(
+                %trader_pubkey,
+                %credit,
+                %collateral_reserve_coordinator,
+                "Insufficient coordinator collateral reserve to pay reserve interest"
+            );
+
+            return Ok((
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+                vec![],
+            ));
+        }
+    };
+
+    let credit_ids = credits.iter().map(|credit| credit.id).collect();
+
+    Ok((
+        collateral_reserve_coordinator,
+        collateral_reserve_trader + credit,
+        credit_ids,
+    ))
+}
+
+pub fn get_reserve_interest(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    apr: f32,
+) -> Result<ReserveInterest> {
+    let pending = get_outstanding_reserve_interest_credits(conn, trader_pubkey)?
+        .iter()
+        .map(|credit| credit.amount)
+        .sum::<Amount>();
+    let paid = db::get_paid_reserve_interest(conn, trader_pubkey)?;
+
+    Ok(ReserveInterest {
+        apr: Decimal::from_f32(apr).context("Invalid reserve interest APR")?,
+        pending,
+        paid,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::macros::datetime;
+
+    #[test]
+    fn reserve_interest_for_a_year() {
+        let interest = calculate_reserve_interest(
+            Amount::from_sat(1_000_000),
+            dec!(0.05),
+            time::Duration::days(365),
+        );
+
+        assert_eq!(interest, Amount::from_sat(50_000));
+    }
+
+    #[test]
+    fn reserve_interest_is_rounded_down() {
+        let interest = calculate_reserve_interest(
+            Amount::from_sat(100_000),
+            dec!(0.05),
+            time::Duration::hours(1),
+        );
+
+        // 100_000 * 0.05 / 8760 = 0.57 sats
+        assert_eq!(interest, Amount::ZERO);
+    }
+
+    #[test]
+    fn no_interest_accrues_before_it_is_enabled() {
+        let created_at = datetime!(2024-01-01 00:00 UTC);
+        let enabled_at = datetime!(2024-06-01 00:00 UTC);
+        let reserve = Amount::from_sat(1_000_000);
+
+        // While the APR is zero, the time is recorded without interest.
+        let accrual = next_accrual(None, created_at, reserve, 0.0, dec!(0), enabled_at, false);
+        assert_eq!(
+            accrual,
+            Accrual::WithoutInterest {
+                period_start: created_at
+            }
+        );
+
+        let last_period = period(1, 0.0, created_at, enabled_at, Amount::ZERO);
+
+        let accrual = next_accrual(
+            Some(&last_period),
+            created_at,
+            reserve,
+            0.05,
+            dec!(0.05),
+            enabled_at + time::Duration::days(73),
+            false,
+        );
+
+        // 1_000_000 * 0.05 * 73 / 365 = 10_000 sats
+        assert_eq!(
+            accrual,
+            Accrual::Credit {
+                period_start: enabled_at,
+                amount: Amount::from_sat(10_000),
+            }
+        );
+    }
+
+    #[test]
+    fn disabled_interest_extends_the_last_period_without_interest() {
+        let created_at = datetime!(2024-01-01 00:00 UTC);
+        let last_period = period(
+            1,
+            0.0,
+            created_at,
+            datetime!(2024-02-01 00:00 UTC),
+            Amount::ZERO,
+        );
+
+        let accrual = next_accrual(
+            Some(&last_period),
+            created_at,
+            Amount::from_sat(1_000_000),
+            0.0,
+            dec!(0),
+            datetime!(2024-03-01 00:00 UTC),
+            false,
+        );
+
+        assert_eq!(accrual, Accrual::ExtendWithoutInterest { id: 1 });
+    }
+
+    #[test]
+    fn disabling_interest_starts_a_new_period() {
+        let created_at = datetime!(2024-01-01 00:00 UTC);
+        let period_end = datetime!(2024-02-01 00:00 UTC);
+        let last_period = period(1, 0.05, created_at, period_end, Amount::from_sat(4_000));
+
+        let accrual = next_accrual(
+            Some(&last_period),
+            created_at,
+            Amount::from_sat(1_000_000),
+            0.0,
+            dec!(0),
+            datetime!(2024-03-01 00:00 UTC),
+            false,
+        );
+
+        assert_eq!(
+            accrual,
+            Accrual::WithoutInterest {
+                period_start: period_end
+            }
+        );
+    }
+
+    #[test]
+    fn closing_records_periods_worth_less_than_a_sat() {
+        let created_at = datetime!(2024-01-01 00:00 UTC);
+        let period_end = datetime!(2024-02-01 00:00 UTC);
+        let last_period = period(1, 0.05, created_at, period_end, Amount::from_sat(4_000));
+        let now = period_end + time::Duration::hours(1);
+
+        let accrual = |close| {
+            next_accrual(
+                Some(&last_period),
+                created_at,
+                Amount::from_sat(100_000),
+                0.05,
+                dec!(0.05),
+                now,
+                close,
+            )
+        };
+
+        assert_eq!(accrual(false), Accrual::Pending);
+        assert_eq!(
+            accrual(true),
+            Accrual::WithoutInterest {
+                period_start: period_end
+            }
+        );
+    }
+
+    fn period(
+        id: i32,
+        apr: f32,
+        period_start: OffsetDateTime,
+        period_end: OffsetDateTime,
+        amount: Amount,
+    ) -> ReserveInterestCredit {
+        ReserveInterestCredit {
+            id,
+            trader_pubkey: PublicKey::from_str(
+                "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+            )
+            .unwrap(),
+            channel_id: [0; 32],
+            reserve: Amount::from_sat(1_000_000),
+            apr,
+            period_start,
+            period_end,
+            amount,
+            paid_date: None,
+        }
+    }
+}
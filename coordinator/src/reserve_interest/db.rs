@@ -0,0 +1,189 @@
+use crate::reserve_interest;
+use crate::schema::reserve_interest_credits;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::prelude::*;
+use dlc_manager::DlcChannelId;
+use hex::FromHex;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use xxi_node::node::ProtocolId;
+
+#[derive(Queryable, Debug)]
+struct ReserveInterestCredit {
+    id: i32,
+    trader_pubkey: String,
+    channel_id: String,
+    reserve_sats: i64,
+    apr: f32,
+    period_start: OffsetDateTime,
+    period_end: OffsetDateTime,
+    amount_sats: i64,
+    _protocol_id: Option<uuid::Uuid>,
+    paid_date: Option<OffsetDateTime>,
+    _created_at: OffsetDateTime,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn insert(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    channel_id: &DlcChannelId,
+    reserve: Amount,
+    apr: f32,
+    period_start: OffsetDateTime,
+    period_end: OffsetDateTime,
+    amount: Amount,
+) -> QueryResult<()> {
+    diesel::insert_into(reserve_interest_credits::table)
+        .values(&(
+            reserve_interest_credits::trader_pubkey.eq(trader_pubkey.to_string()),
+            reserve_interest_credits::channel_id.eq(hex::encode(channel_id)),
+            reserve_interest_credits::reserve_sats.eq(reserve.to_sat() as i64),
+            reserve_interest_credits::apr.eq(apr),
+            reserve_interest_credits::period_start.eq(period_start),
+            reserve_interest_credits::period_end.eq(period_end),
+            reserve_interest_credits::amount_sats.eq(amount.to_sat() as i64),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Record a period in which no interest accrued. There is nothing to pay for it.
+pub fn insert_without_interest(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    channel_id: &DlcChannelId,
+    reserve: Amount,
+    apr: f32,
+    period_start: OffsetDateTime,
+    period_end: OffsetDateTime,
+) -> QueryResult<()> {
+    diesel::insert_into(reserve_interest_credits::table)
+        .values(&(
+            reserve_interest_credits::trader_pubkey.eq(trader_pubkey.to_string()),
+            reserve_interest_credits::channel_id.eq(hex::encode(channel_id)),
+            reserve_interest_credits::reserve_sats.eq(reserve.to_sat() as i64),
+            reserve_interest_credits::apr.eq(apr),
+            reserve_interest_credits::period_start.eq(period_start),
+            reserve_interest_credits::period_end.eq(period_end),
+            reserve_interest_credits::amount_sats.eq(0),
+            reserve_interest_credits::paid_date.eq(period_end),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Move the end of a period without interest to `period_end`.
+pub fn extend_period(
+    conn: &mut PgConnection,
+    id: i32,
+    period_end: OffsetDateTime,
+) -> QueryResult<()> {
+    diesel::update(reserve_interest_credits::table)
+        .filter(reserve_interest_credits::id.eq(id))
+        .filter(reserve_interest_credits::amount_sats.eq(0))
+        .set(reserve_interest_credits::period_end.eq(period_end))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The last accrual period of the given DLC channel, if any.
+pub fn get_last_period(
+    conn: &mut PgConnection,
+    channel_id: &DlcChannelId,
+) -> QueryResult<Option<reserve_interest::ReserveInterestCredit>> {
+    let period: Option<ReserveInterestCredit> = reserve_interest_credits::table
+        .filter(reserve_interest_credits::channel_id.eq(hex::encode(channel_id)))
+        .order_by(reserve_interest_credits::period_end.desc())
+        .first(conn)
+        .optional()?;
+
+    Ok(period.map(reserve_interest::ReserveInterestCredit::from))
+}
+
+/// Get the reserve interest credits of a trader which have not been paid yet.
+pub fn get_outstanding_reserve_interest_credits(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+) -> QueryResult<Vec<reserve_interest::ReserveInterestCredit>> {
+    let credits: Vec<ReserveInterestCredit> = reserve_interest_credits::table
+        .filter(reserve_interest_credits::trader_pubkey.eq(trader_pubkey.to_string()))
+        .filter(reserve_interest_credits::paid_date.is_null())
+        .order_by(reserve_interest_credits::period_end.asc())
+        .load(conn)?;
+
+    Ok(credits
+        .into_iter()
+        .map(reserve_interest::ReserveInterestCredit::from)
+        .collect())
+}
+
+/// The sum of all reserve interest credits which have been paid to a trader.
+pub fn get_paid_reserve_interest(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+) -> QueryResult<Amount> {
+    let paid: Vec<i64> = reserve_interest_credits::table
+        .filter(reserve_interest_credits::trader_pubkey.eq(trader_pubkey.to_string()))
+        .filter(reserve_interest_credits::paid_date.is_not_null())
+        .select(reserve_interest_credits::amount_sats)
+        .load(conn)?;
+
+    Ok(Amount::from_sat(paid.into_iter().sum::<i64>() as u64))
+}
+
+/// Associate reserve interest credits with the DLC protocol which will pay them.
+///
+/// A credit which was associated with a failed DLC protocol is simply associated with the next
+/// one.
+pub fn set_protocol_id(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    credit_ids: &[i32],
+) -> QueryResult<()> {
+    if credit_ids.is_empty() {
+        return Ok(());
+    }
+
+    diesel::update(reserve_interest_credits::table)
+        .filter(reserve_interest_credits::id.eq_any(credit_ids))
+        .filter(reserve_interest_credits::paid_date.is_null())
+        .set(reserve_interest_credits::protocol_id.eq(protocol_id.to_uuid()))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Mark the reserve interest credits paid by the given DLC protocol as paid.
+pub fn mark_reserve_interest_credits_as_paid(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+) -> QueryResult<()> {
+    diesel::update(reserve_interest_credits::table)
+        .filter(reserve_interest_credits::protocol_id.eq(protocol_id.to_uuid()))
+        .filter(reserve_interest_credits::paid_date.is_null())
+        .set(reserve_interest_credits::paid_date.eq(OffsetDateTime::now_utc()))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+impl From<ReserveInterestCredit> for reserve_interest::ReserveInterestCredit {
+    fn from(value: ReserveInterestCredit) -> Self {
+        Self {
+            id: value.id,
+            trader_pubkey: PublicKey::from_str(&value.trader_pubkey).expect("valid pubkey"),
+            channel_id: DlcChannelId::from_hex(value.channel_id).expect("valid dlc channel id"),
+            reserve: Amount::from_sat(value.reserve_sats as u64),
+            apr: value.apr,
+            period_start: value.period_start,
+            period_end: value.period_end,
+            amount: Amount::from_sat(value.amount_sats as u64),
+            paid_date: value.paid_date,
+        }
+    }
+}
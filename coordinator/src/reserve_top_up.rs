@@ -0,0 +1,689 @@
+use crate::db::positions;
+use crate::funding_fee::funding_fee_from_funding_fee_events;
+use crate::funding_fee::get_outstanding_funding_fee_events;
+use crate::lightning_withdrawal;
+use crate::node::Node;
+use crate::position::models::PositionState;
+use crate::reserve_interest;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Address;
+use bitcoin::Amount;
+use bitcoin::Txid;
+use diesel::PgConnection;
+use dlc_manager::DlcChannelId;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use xxi_node::node::ProtocolId;
+use xxi_node::TransactionDetails;
+
+mod db;
+
+pub use db::mark_reserve_top_ups_as_credited;
+
+/// The smallest amount we accept to top up a collateral reserve with.
+const MIN_TOP_UP: Amount = Amount::from_sat(10_000);
+
+/// The number of confirmations of the deposit before we credit it to the trader.
+const REQUIRED_CONFIRMATIONS: u32 = 1;
+
+/// How long we keep following the deposits to a fully credited top-up, in case the trader deposits
+/// to the same address again.
+const DEPOSIT_TRACKING_AFTER_CREDIT: Duration = Duration::days(7);
+
+/// A deposit from the trader's on-chain wallet to top up their collateral reserve.
+///
+/// Coins can't be added to a DLC channel without a new funding transaction. Instead, the trader
+/// deposits to the coordinator's on-chain wallet, and once the deposit is confirmed the coordinator
+/// moves the received amount from its own to the trader's collateral reserve with a renew of the
+/// DLC channel. Hence, a top-up is limited by the coordinator's collateral reserve.
+///
+/// At most the requested amount is credited, in as many renews as the coordinator's collateral
+/// reserve requires. Anything beyond that, or a top-up which can't be credited at all, has to be
+/// refunded and settled manually.
+#[derive(Clone, Debug)]
+pub struct ReserveTopUp {
+    pub id: i32,
+    pub trader_pubkey: PublicKey,
+    pub channel_id: DlcChannelId,
+    /// The address of the coordinator's on-chain wallet to deposit to.
+    pub address: Address,
+    /// The requested amount.
+    pub amount: Amount,
+    /// The last deposit, once we have seen one.
+    pub txid: Option<Txid>,
+    /// The amount actually received in confirmed deposits.
+    pub received: Option<Amount>,
+    pub confirmed_at: Option<OffsetDateTime>,
+    /// The part of the received amount already moved to the trader's collateral reserve.
+    pub credited: Amount,
+    pub credited_at: Option<OffsetDateTime>,
+    /// Set if the top-up was settled outside of the DLC channel.
+    pub settled_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+/// The part of a top-up moved to the trader's collateral reserve by a DLC protocol.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReserveTopUpCredit {
+    pub id: i32,
+    pub amount: Amount,
+}
+
+impl ReserveTopUp {
+    /// The part of the received amount which can be credited, i.e. at most the requested amount.
+    fn creditable(&self) -> Amount {
+        self.received.unwrap_or(Amount::ZERO).min(self.amount)
+    }
+
+    /// What still has to be moved to the trader's collateral reserve.
+    fn outstanding_credit(&self) -> Amount {
+        if self.confirmed_at.is_none() || self.credited_at.is_some() || self.settled_at.is_some() {
+            return Amount::ZERO;
+        }
+
+        self.creditable()
+            .checked_sub(self.credited)
+            .unwrap_or(Amount::ZERO)
+    }
+
+    fn is_fully_credited(&self) -> bool {
+        self.credited >= self.creditable()
+    }
+}
+
+/// All deposits to the address of a top-up.
+#[derive(Debug, PartialEq)]
+struct TopUpDeposits {
+    /// The last deposit.
+    txid: Txid,
+    /// The sum of the deposits with enough confirmations.
+    confirmed: Amount,
+    /// The sum of the deposits which still need confirmations.
+    unconfirmed: Amount,
+}
+
+/// Request to top up the trader's collateral reserve by `amount`.
+///
+/// Returns the top-up with the address the trader has to deposit to. If the trader has not paid
+/// for a previous request yet, e.g. because the app was closed in between, that one is returned
+/// instead, so that the trader does not deposit twice.
+pub async fn request(node: Node, trader_pubkey: PublicKey, amount: Amount) -> Result<ReserveTopUp> {
+    node.quiesce.ensure_protocols_allowed()?;
+
+    ensure!(amount >= MIN_TOP_UP, "Cannot top up less than {MIN_TOP_UP}");
+
+    let channel_id = node
+        .inner
+        .get_signed_channel_by_trader_id(trader_pubkey)
+        .context("No DLC channel to top up")?
+        .channel_id;
+
+    spawn_blocking(move || {
+        let mut conn = node.pool.get()?;
+
+        let position = positions::Position::get_position_by_trader(
+            &mut conn,
+            trader_pubkey,
+            vec![PositionState::Open],
+        )?
+        .context("Topping up the collateral reserve requires an open position")?;
+
+        let top_ups = db::get_by_trader(&mut conn, trader_pubkey)?;
+        if let Some(top_up) = top_ups
+            .iter()
+            .find(|top_up| top_up.credited_at.is_none() && top_up.settled_at.is_none())
+        {
+            ensure!(
+                top_up.txid.is_none(),
+                "Reserve top-up {} is not yet credited",
+                top_up.id
+            );
+
+            return Ok(top_up.clone());
+        }
+
+        let funding_fee_events =
+            get_outstanding_funding_fee_events(&mut conn, trader_pubkey, position.id)?;
+        let funding_fee = funding_fee_from_funding_fee_events(&funding_fee_events);
+
+        let (collateral_reserve_coordinator, collateral_reserve_trader) =
+            node.apply_funding_fee_to_channel(channel_id, funding_fee)?;
+        let (collateral_reserve_coordinator, collateral_reserve_trader, _) =
+            reserve_interest::apply_reserve_interest_credits(
+                &mut conn,
+                trader_pubkey,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+        let (collateral_reserve_coordinator, _, _) =
+            lightning_withdrawal::apply_lightning_withdrawals(
+                &mut conn,
+                trader_pubkey,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+
+        ensure!(
+            amount <= collateral_reserve_coordinator,
+            "Cannot top up {amount} with a coordinator collateral reserve of \
+             {collateral_reserve_coordinator}"
+        );
+
+        let address = node.inner.get_new_address()?;
+        let top_up = db::insert(&mut conn, trader_pubkey, &channel_id, &address, amount)?;
+
+        tracing::info!(
+            %trader_pubkey,
+            id = top_up.id,
+            %amount,
+            %address,
+            "Requested reserve top-up"
+        );
+
+        Ok(top_up)
+    })
+    .await
+    .expect("task to complete")
+}
+
+/// Follow the deposits of the requested top-ups, and propose a renew of the DLC channel to credit
+/// them once they are confirmed.
+pub async fn track_deposits(node: Node) -> Result<()> {
+    let top_ups = spawn_blocking({
+        let pool = node.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            let credited_before = OffsetDateTime::now_utc() - DEPOSIT_TRACKING_AFTER_CREDIT;
+            let top_ups = db::get_tracked(&mut conn, credited_before)?;
+            anyhow::Ok(top_ups)
+        }
+    })
+    .await
+    .expect("task to complete")?;
+
+    if top_ups.is_empty() {
+        return Ok(());
+    }
+
+    let history = node.inner.get_on_chain_history();
+
+    for mut top_up in top_ups {
+        let deposits = match find_deposits(&history, &top_up.address) {
+            Some(deposits) => deposits,
+            None => continue,
+        };
+
+        let received = top_up.received.unwrap_or(Amount::ZERO);
+
+        // The trader may deposit more than once, or replace a deposit, e.g. to bump its fee.
+        if top_up.txid == Some(deposits.txid) && received == deposits.confirmed {
+            continue;
+        }
+
+        let trader_pubkey = top_up.trader_pubkey;
+        let mut conn = node.pool.get()?;
+
+        top_up.received = Some(deposits.confirmed);
+        if !top_up.is_fully_credited() {
+            top_up.credited_at = None;
+        }
+
+        db::set_deposit(
+            &mut conn,
+            top_up.id,
+            deposits.txid,
+            deposits.confirmed,
+            top_up.credited_at,
+        )?;
+
+        tracing::info!(
+            %trader_pubkey,
+            id = top_up.id,
+            txid = %deposits.txid,
+            confirmed = %deposits.confirmed,
+            unconfirmed = %deposits.unconfirmed,
+            "Received reserve top-up deposit"
+        );
+
+        if deposits.confirmed > top_up.amount {
+            tracing::warn!(
+                %trader_pubkey,
+                id = top_up.id,
+                requested = %top_up.amount,
+                received = %deposits.confirmed,
+                "Received more than requested for reserve top-up, the excess has to be refunded \
+                 manually"
+            );
+        }
+
+        if deposits.confirmed > received {
+            db::mark_as_confirmed(&mut conn, top_up.id)?;
+            top_up.confirmed_at = top_up.confirmed_at.or(Some(OffsetDateTime::now_utc()));
+
+            drop(conn);
+
+            if top_up.outstanding_credit() > Amount::ZERO {
+                tracing::info!(
+                    %trader_pubkey,
+                    id = top_up.id,
+                    txid = %deposits.txid,
+                    "Reserve top-up deposit confirmed, crediting collateral reserve"
+                );
+
+                propose_reserve_update(&node, trader_pubkey).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Move the confirmed top-ups of a trader from the coordinator's to the trader's collateral
+/// reserve.
+///
+/// Returns the updated collateral reserves of the coordinator and the trader and the applied
+/// credits. Top-ups are credited oldest first, as far as the coordinator's collateral reserve
+/// allows. The rest stays outstanding for the next renew or rollover.
+pub fn apply_reserve_top_ups(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    collateral_reserve_coordinator: Amount,
+    collateral_reserve_trader: Amount,
+) -> Result<(Amount, Amount, Vec<ReserveTopUpCredit>)> {
+    let top_ups = db::get_by_trader(conn, trader_pubkey)?;
+
+    let credits = credit_reserve_top_ups(&top_ups, collateral_reserve_coordinator);
+
+    let credit = credits.iter().map(|credit| credit.amount).sum::<Amount>();
+    let outstanding = top_ups
+        .iter()
+        .map(ReserveTopUp::outstanding_credit)
+        .sum::<Amount>();
+
+    if credit < outstanding {
+        tracing::warn!(
+            %trader_pubkey,
+            %credit,
+            %outstanding,
+            %collateral_reserve_coordinator,
+            "Insufficient coordinator collateral reserve to fully credit reserve top-ups"
+        );
+    }
+
+    Ok((
+        collateral_reserve_coordinator - credit,
+        collateral_reserve_trader + credit,
+        credits,
+    ))
+}
+
+/// Split the coordinator's collateral reserve among the outstanding credits of the top-ups, oldest
+/// first.
+fn credit_reserve_top_ups(
+    top_ups: &[ReserveTopUp],
+    collateral_reserve_coordinator: Amount,
+) -> Vec<ReserveTopUpCredit> {
+    let mut available = collateral_reserve_coordinator;
+
+    top_ups
+        .iter()
+        .filter_map(|top_up| {
+            let amount = top_up.outstanding_credit().min(available);
+            if amount == Amount::ZERO {
+                return None;
+            }
+
+            available -= amount;
+
+            Some(ReserveTopUpCredit {
+                id: top_up.id,
+                amount,
+            })
+        })
+        .collect()
+}
+
+/// Fail if the trader has deposited for top-ups which are not yet credited in their DLC channel.
+///
+/// Closing the DLC channel in that state would leave the trader without their deposit. If a top-up
+/// can't be credited, it has to be refunded and then settled via
+/// `/api/admin/reserve-top-ups/:id/settle`.
+pub fn ensure_no_outstanding_top_ups(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+) -> Result<()> {
+    let outstanding = db::get_by_trader(conn, trader_pubkey)?
+        .iter()
+        .filter(|top_up| {
+            top_up.txid.is_some() && top_up.credited_at.is_none() && top_up.settled_at.is_none()
+        })
+        .map(|top_up| top_up.id)
+        .collect::<Vec<_>>();
+
+    ensure!(
+        outstanding.is_empty(),
+        "Reserve top-ups {outstanding:?} are not yet credited in the DLC channel. Refund and \
+         settle them manually if they can't be credited"
+    );
+
+    Ok(())
+}
+
+/// Associate the applied top-up credits with the DLC protocol crediting them.
+pub fn link_to_protocol(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    credits: &[ReserveTopUpCredit],
+) -> Result<()> {
+    db::set_protocol_id(conn, protocol_id, credits)?;
+
+    Ok(())
+}
+
+/// Settle a top-up which is not fully credited outside of the DLC channel, e.g. after refunding
+/// the deposit to the trader.
+///
+/// The top-up no longer prevents the DLC channel from being closed, and what is left of it is
+/// never credited.
+pub fn settle(conn: &mut PgConnection, id: i32, note: &str) -> Result<ReserveTopUp> {
+    let top_up = db::settle(conn, id, note)?
+        .with_context(|| format!("No unsettled reserve top-up with id {id}"))?;
+
+    tracing::info!(
+        trader_pubkey = %top_up.trader_pubkey,
+        id,
+        credited = %top_up.credited,
+        note,
+        "Settled reserve top-up manually"
+    );
+
+    Ok(top_up)
+}
+
+/// Credit the confirmed top-ups of the trader with a renew of their DLC channel.
+///
+/// If this is not possible right now, e.g. because the position is being resized, the top-ups are
+/// credited with the next renew or rollover.
+async fn propose_reserve_update(node: &Node, trader_pubkey: PublicKey) {
+    let result = async {
+        let mut conn = node.pool.get()?;
+
+        let position = positions::Position::get_position_by_trader(
+            &mut conn,
+            trader_pubkey,
+            vec![PositionState::Open],
+        )?
+        .context("No open position")?;
+
+        let channel_id = node
+            .inner
+            .get_signed_channel_by_trader_id(trader_pubkey)?
+            .channel_id;
+
+        node.propose_reserve_update(&mut conn, &channel_id, position)
+            .await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!(
+            %trader_pubkey,
+            "Could not credit reserve top-ups now, deferring to next renew: {e:#}"
+        );
+    }
+}
+
+/// Sum up the transactions in `history` paying to `address`.
+fn find_deposits(history: &[TransactionDetails], address: &Address) -> Option<TopUpDeposits> {
+    let script_pubkey = address.script_pubkey();
+
+    history
+        .iter()
+        .filter_map(|details| {
+            let amount = details
+                .transaction
+                .output
+                .iter()
+                .filter(|output| output.script_pubkey == script_pubkey)
+                .map(|output| output.value)
+                .sum::<u64>();
+
+            (amount > 0).then(|| {
+                (
+                    details.transaction.txid(),
+                    Amount::from_sat(amount),
+                    details.confirmation_status.n_confirmations(),
+                )
+            })
+        })
+        .fold(None, |deposits, (txid, amount, n_confirmations)| {
+            let TopUpDeposits {
+                confirmed,
+                unconfirmed,
+                ..
+            } = deposits.unwrap_or(TopUpDeposits {
+                txid,
+                confirmed: Amount::ZERO,
+                unconfirmed: Amount::ZERO,
+            });
+
+            let (confirmed, unconfirmed) = if n_confirmations >= REQUIRED_CONFIRMATIONS {
+                (confirmed + amount, unconfirmed)
+            } else {
+                (confirmed, unconfirmed + amount)
+            };
+
+            Some(TopUpDeposits {
+                txid,
+                confirmed,
+                unconfirmed,
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::Network;
+    use bitcoin::Transaction;
+    use bitcoin::TxOut;
+    use std::num::NonZeroU32;
+    use std::str::FromStr;
+    use xxi_node::ConfirmationStatus;
+
+    #[test]
+    fn deposit_sums_outputs_to_address() {
+        let address = dummy_address();
+        let history = vec![dummy_details(
+            vec![(address.clone(), 30_000), (address.clone(), 20_000)],
+            ConfirmationStatus::Unknown,
+        )];
+
+        let deposits = find_deposits(&history, &address).unwrap();
+
+        assert_eq!(deposits.confirmed, Amount::ZERO);
+        assert_eq!(deposits.unconfirmed, Amount::from_sat(50_000));
+    }
+
+    #[test]
+    fn deposit_reports_confirmations() {
+        let address = dummy_address();
+        let history = vec![dummy_details(vec![(address.clone(), 50_000)], confirmed())];
+
+        let deposits = find_deposits(&history, &address).unwrap();
+
+        assert_eq!(deposits.confirmed, Amount::from_sat(50_000));
+        assert_eq!(deposits.unconfirmed, Amount::ZERO);
+    }
+
+    #[test]
+    fn all_deposits_to_address_are_summed() {
+        let address = dummy_address();
+        let first = dummy_details(vec![(address.clone(), 30_000)], confirmed());
+        let second = dummy_details(vec![(address.clone(), 25_000)], confirmed());
+        let third = dummy_details(vec![(address.clone(), 5_000)], ConfirmationStatus::Unknown);
+        let last_txid = third.transaction.txid();
+
+        let deposits = find_deposits(&[first, second, third], &address).unwrap();
+
+        assert_eq!(
+            deposits,
+            TopUpDeposits {
+                txid: last_txid,
+                confirmed: Amount::from_sat(55_000),
+                unconfirmed: Amount::from_sat(5_000),
+            }
+        );
+    }
+
+    #[test]
+    fn no_deposit_to_other_address() {
+        let address = dummy_address();
+        let other = Address::from_str("bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el")
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let history = vec![dummy_details(
+            vec![(other, 50_000)],
+            ConfirmationStatus::Unknown,
+        )];
+
+        assert_eq!(find_deposits(&history, &address), None);
+    }
+
+    #[test]
+    fn credit_is_capped_at_requested_amount() {
+        let top_up = dummy_top_up(1, 50_000, 80_000, 0);
+
+        let credits = credit_reserve_top_ups(&[top_up], Amount::from_sat(1_000_000));
+
+        assert_eq!(
+            credits,
+            vec![ReserveTopUpCredit {
+                id: 1,
+                amount: Amount::from_sat(50_000),
+            }]
+        );
+    }
+
+    #[test]
+    fn insufficient_coordinator_reserve_credits_partially_oldest_first() {
+        let top_ups = vec![
+            dummy_top_up(1, 50_000, 50_000, 0),
+            dummy_top_up(2, 40_000, 40_000, 0),
+        ];
+
+        let credits = credit_reserve_top_ups(&top_ups, Amount::from_sat(60_000));
+
+        assert_eq!(
+            credits,
+            vec![
+                ReserveTopUpCredit {
+                    id: 1,
+                    amount: Amount::from_sat(50_000),
+                },
+                ReserveTopUpCredit {
+                    id: 2,
+                    amount: Amount::from_sat(10_000),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn partially_credited_top_up_keeps_the_rest_outstanding() {
+        let top_up = dummy_top_up(1, 50_000, 50_000, 20_000);
+
+        assert_eq!(top_up.outstanding_credit(), Amount::from_sat(30_000));
+        assert!(!top_up.is_fully_credited());
+
+        let credits = credit_reserve_top_ups(&[top_up], Amount::from_sat(1_000_000));
+
+        assert_eq!(
+            credits,
+            vec![ReserveTopUpCredit {
+                id: 1,
+                amount: Amount::from_sat(30_000),
+            }]
+        );
+    }
+
+    #[test]
+    fn settled_top_up_is_not_credited() {
+        let top_up = ReserveTopUp {
+            settled_at: Some(OffsetDateTime::now_utc()),
+            ..dummy_top_up(1, 50_000, 50_000, 20_000)
+        };
+
+        assert_eq!(top_up.outstanding_credit(), Amount::ZERO);
+        assert_eq!(
+            credit_reserve_top_ups(&[top_up], Amount::from_sat(1_000_000)),
+            vec![]
+        );
+    }
+
+    fn dummy_top_up(id: i32, amount: u64, received: u64, credited: u64) -> ReserveTopUp {
+        let now = OffsetDateTime::now_utc();
+
+        ReserveTopUp {
+            id,
+            trader_pubkey: PublicKey::from_str(
+                "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+            )
+            .unwrap(),
+            channel_id: [0; 32],
+            address: dummy_address(),
+            amount: Amount::from_sat(amount),
+            txid: None,
+            received: Some(Amount::from_sat(received)),
+            confirmed_at: Some(now),
+            credited: Amount::from_sat(credited),
+            credited_at: None,
+            settled_at: None,
+            created_at: now,
+        }
+    }
+
+    fn confirmed() -> ConfirmationStatus {
+        ConfirmationStatus::Confirmed {
+            n_confirmations: NonZeroU32::new(3).unwrap(),
+            timestamp: OffsetDateTime::now_utc(),
+        }
+    }
+
+    fn dummy_address() -> Address {
+        Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap()
+    }
+
+    fn dummy_details(
+        outputs: Vec<(Address, u64)>,
+        confirmation_status: ConfirmationStatus,
+    ) -> TransactionDetails {
+        TransactionDetails {
+            transaction: Transaction {
+                version: 2,
+                lock_time: LockTime::ZERO,
+                input: vec![],
+                output: outputs
+                    .into_iter()
+                    .map(|(address, value)| TxOut {
+                        value,
+                        script_pubkey: address.script_pubkey(),
+                    })
+                    .collect(),
+            },
+            sent: Amount::ZERO,
+            received: Amount::ZERO,
+            fee: Ok(Amount::ZERO),
+            confirmation_status,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::reserve_top_up;
+use crate::schema::reserve_top_ups;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Address;
+use bitcoin::Amount;
+use bitcoin::Txid;
+use diesel::prelude::*;
+use dlc_manager::DlcChannelId;
+use hex::FromHex;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use xxi_node::node::ProtocolId;
+
+#[derive(Queryable, Debug)]
+struct ReserveTopUp {
+    id: i32,
+    trader_pubkey: String,
+    channel_id: String,
+    address: String,
+    amount_sats: i64,
+    txid: Option<String>,
+    received_sats: Option<i64>,
+    confirmed_at: Option<OffsetDateTime>,
+    _protocol_id: Option<uuid::Uuid>,
+    credited_at: Option<OffsetDateTime>,
+    created_at: OffsetDateTime,
+    _updated_at: OffsetDateTime,
+    credited_sats: i64,
+    crediting_sats: i64,
+    settled_at: Option<OffsetDateTime>,
+    _settlement_note: Option<String>,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+    channel_id: &DlcChannelId,
+    address: &Address,
+    amount: Amount,
+) -> QueryResult<reserve_top_up::ReserveTopUp> {
+    let top_up: ReserveTopUp = diesel::insert_into(reserve_top_ups::table)
+        .values(&(
+            reserve_top_ups::trader_pubkey.eq(trader_pubkey.to_string()),
+            reserve_top_ups::channel_id.eq(hex::encode(channel_id)),
+            reserve_top_ups::address.eq(address.to_string()),
+            reserve_top_ups::amount_sats.eq(amount.to_sat() as i64),
+        ))
+        .get_result(conn)?;
+
+    Ok(top_up.into())
+}
+
+pub fn get_by_trader(
+    conn: &mut PgConnection,
+    trader_pubkey: PublicKey,
+) -> QueryResult<Vec<reserve_top_up::ReserveTopUp>> {
+    let top_ups: Vec<ReserveTopUp> = reserve_top_ups::table
+        .filter(reserve_top_ups::trader_pubkey.eq(trader_pubkey.to_string()))
+        .order_by(reserve_top_ups::id.asc())
+        .load(conn)?;
+
+    Ok(top_ups.into_iter().map(Into::into).collect())
+}
+
+/// Get the top-ups whose deposits we still have to follow: those which were neither settled
+/// manually nor fully credited before `credited_before`.
+pub fn get_tracked(
+    conn: &mut PgConnection,
+    credited_before: OffsetDateTime,
+) -> QueryResult<Vec<reserve_top_up::ReserveTopUp>> {
+    let top_ups: Vec<ReserveTopUp> = reserve_top_ups::table
+        .filter(reserve_top_ups::settled_at.is_null())
+        .filter(
+            reserve_top_ups::credited_at
+                .is_null()
+                .or(reserve_top_ups::credited_at.gt(credited_before)),
+        )
+        .order_by(reserve_top_ups::id.asc())
+        .load(conn)?;
+
+    Ok(top_ups.into_iter().map(Into::into).collect())
+}
+
+/// Update the deposits of a top-up. If another deposit arrived after the top-up was fully
+/// credited, `credited_at` is reset.
+pub fn set_deposit(
+    conn: &mut PgConnection,
+    id: i32,
+    txid: Txid,
+    received: Amount,
+    credited_at: Option<OffsetDateTime>,
+) -> QueryResult<()> {
+    diesel::update(reserve_top_ups::table)
+        .filter(reserve_top_ups::id.eq(id))
+        .set((
+            reserve_top_ups::txid.eq(txid.to_string()),
+            reserve_top_ups::received_sats.eq(received.to_sat() as i64),
+            reserve_top_ups::credited_at.eq(credited_at),
+            reserve_top_ups::updated_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn mark_as_confirmed(conn: &mut PgConnection, id: i32) -> QueryResult<()> {
+    let now = OffsetDateTime::now_utc();
+
+    diesel::update(reserve_top_ups::table)
+        .filter(reserve_top_ups::id.eq(id))
+        .filter(reserve_top_ups::confirmed_at.is_null())
+        .set((
+            reserve_top_ups::confirmed_at.eq(now),
+            reserve_top_ups::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Associate the top-ups with the DLC protocol which will credit them, together with the amount
+/// credited by it.
+///
+/// A top-up which was associated with a failed DLC protocol is simply associated with the next
+/// one.
+pub fn set_protocol_id(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+    credits: &[reserve_top_up::ReserveTopUpCredit],
+) -> QueryResult<()> {
+    for credit in credits {
+        diesel::update(reserve_top_ups::table)
+            .filter(reserve_top_ups::id.eq(credit.id))
+            .filter(reserve_top_ups::credited_at.is_null())
+            .set((
+                reserve_top_ups::protocol_id.eq(protocol_id.to_uuid()),
+                reserve_top_ups::crediting_sats.eq(credit.amount.to_sat() as i64),
+            ))
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Add the amounts credited by the given DLC protocol to the top-ups, marking those which are now
+/// fully credited.
+pub fn mark_reserve_top_ups_as_credited(
+    conn: &mut PgConnection,
+    protocol_id: ProtocolId,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let top_ups: Vec<ReserveTopUp> = reserve_top_ups::table
+            .filter(reserve_top_ups::protocol_id.eq(protocol_id.to_uuid()))
+            .filter(reserve_top_ups::credited_at.is_null())
+            .load(conn)?;
+
+        let now = OffsetDateTime::now_utc();
+
+        for top_up in top_ups {
+            let crediting = top_up.crediting_sats;
+
+            let mut top_up = reserve_top_up::ReserveTopUp::from(top_up);
+            top_up.credited += Amount::from_sat(crediting as u64);
+
+            let credited_at = top_up.is_fully_credited().then_some(now);
+
+            diesel::update(reserve_top_ups::table)
+                .filter(reserve_top_ups::id.eq(top_up.id))
+                .set((
+                    reserve_top_ups::credited_sats.eq(top_up.credited.to_sat() as i64),
+                    reserve_top_ups::crediting_sats.eq(0),
+                    reserve_top_ups::credited_at.eq(credited_at),
+                    reserve_top_ups::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Mark a top-up which is not fully credited as settled outside of the DLC channel.
+///
+/// Returns `None` if there is no such top-up.
+pub fn settle(
+    conn: &mut PgConnection,
+    id: i32,
+    note: &str,
+) -> QueryResult<Option<reserve_top_up::ReserveTopUp>> {
+    let now = OffsetDateTime::now_utc();
+
+    let top_up: Option<ReserveTopUp> = diesel::update(reserve_top_ups::table)
+        .filter(reserve_top_ups::id.eq(id))
+        .filter(reserve_top_ups::credited_at.is_null())
+        .filter(reserve_top_ups::settled_at.is_null())
+        .set((
+            reserve_top_ups::settled_at.eq(now),
+            reserve_top_ups::settlement_note.eq(note),
+            reserve_top_ups::updated_at.eq(now),
+        ))
+        .get_result(conn)
+        .optional()?;
+
+    Ok(top_up.map(Into::into))
+}
+
+impl From<ReserveTopUp> for reserve_top_up::ReserveTopUp {
+    fn from(value: ReserveTopUp) -> Self {
+        Self {
+            id: value.id,
+            trader_pubkey: PublicKey::from_str(&value.trader_pubkey).expect("valid pubkey"),
+            channel_id: DlcChannelId::from_hex(value.channel_id).expect("valid dlc channel id"),
+            address: Address::from_str(&value.address)
+                .expect("valid address")
+                .assume_checked(),
+            amount: Amount::from_sat(value.amount_sats as u64),
+            txid: value
+                .txid
+                .map(|txid| Txid::from_str(&txid).expect("valid txid")),
+            received: value
+                .received_sats
+                .map(|received| Amount::from_sat(received as u64)),
+            confirmed_at: value.confirmed_at,
+            credited: Amount::from_sat(value.credited_sats as u64),
+            credited_at: value.credited_at,
+            settled_at: value.settled_at,
+            created_at: value.created_at,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::db;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use parking_lot::RwLock;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionSettings {
+    /// Whether old rows are pruned at all.
+    pub enabled: bool,
+    /// How many rows are archived and deleted at once. Keeps transactions and archive objects
+    /// small.
+    pub batch_size: u32,
+    /// How long the rows of each table are kept. Tables without a policy are never pruned.
+    pub policies: Vec<RetentionPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub table: RetainedTable,
+    /// How long rows are kept before they are deleted.
+    pub retention_days: u32,
+    /// Whether rows are uploaded to the object storage before they are deleted.
+    ///
+    /// If no object storage is configured, tables which should be archived are not pruned.
+    pub archive: bool,
+}
+
+/// The tables which can be pruned.
+///
+/// Only rows which have reached a final state are pruned, e.g. orders are never deleted while
+/// they are still open or referenced by a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetainedTable {
+    Orders,
+    Matches,
+    OrderFills,
+    OrderbookJournal,
+}
+
+/// Prunes old rows from tables which would otherwise grow unbounded, according to the
+/// [`RetentionSettings`].
+#[derive(Clone)]
+pub struct DataRetention {
+    pool: Pool<ConnectionManager<PgConnection>>,
+    object_storage: Option<ObjectStorage>,
+    settings: Arc<RwLock<RetentionSettings>>,
+}
+
+/// An object storage bucket accepting `PUT` requests, e.g. the XML API of Google Cloud Storage
+/// or an S3-compatible gateway.
+#[derive(Clone)]
+pub struct ObjectStorage {
+    client: reqwest::Client,
+    url: Url,
+    token: Option<String>,
+}
+
+impl DataRetention {
+    pub fn new(
+        pool: Pool<ConnectionManager<PgConnection>>,
+        object_storage: Option<ObjectStorage>,
+        settings: RetentionSettings,
+    ) -> Self {
+        Self {
+            pool,
+            object_storage,
+            settings: Arc::new(RwLock::new(settings)),
+        }
+    }
+
+    pub fn update_settings(&self, settings: RetentionSettings) {
+        *self.settings.write() = settings;
+    }
+
+    /// Prune all tables with a [`RetentionPolicy`].
+    ///
+    /// A failure to prune one table does not prevent the other tables from being pruned.
+    pub async fn prune(&self) {
+        let settings = self.settings.read().clone();
+        if !settings.enabled {
+            return;
+        }
+
+        let now = OffsetDateTime::now_utc();
+        for policy in settings.policies {
+            match self.prune_table(policy, settings.batch_size, now).await {
+                Ok(deleted) => {
+                    tracing::info!(table = %policy.table, deleted, "Pruned table");
+                }
+                Err(e) => {
+                    tracing::error!(table = %policy.table, "Failed to prune table: {e:#}");
+                }
+            }
+        }
+    }
+
+    /// Delete the rows of the table which are older than the retention period, batch by batch,
+    /// returning the number of deleted rows.
+    async fn prune_table(
+        &self,
+        policy: RetentionPolicy,
+        batch_size: u32,
+        now: OffsetDateTime,
+    ) -> Result<usize> {
+        let object_storage = match (policy.archive, &self.object_storage) {
+            (true, Some(object_storage)) => Some(object_storage),
+            (true, None) => bail!("Archiving is enabled, but no object storage is configured"),
+            (false, _) => None,
+        };
+
+        let cut_off = now - time::Duration::days(policy.retention_days as i64);
+        let batch_size = batch_size.max(1) as i64;
+
+        let mut deleted = 0;
+        for batch in 0.. {
+            let rows = spawn_blocking({
+                let pool = self.pool.clone();
+                move || {
+                    let mut conn = pool.get()?;
+                    let rows =
+                        db::retention::get_batch(&mut conn, policy.table, cut_off, batch_size)?;
+                    anyhow::Ok(rows)
+                }
+            })
+            .await
+            .expect("task to complete")?;
+
+            if rows.is_empty() {
+                break;
+            }
+
+            if let Some(object_storage) = object_storage {
+                let key = archive_key(policy.table, now, batch);
+                let body = compress_jsonl(rows.iter().map(|row| row.row.as_str()))?;
+
+                object_storage
+                    .put(&key, body)
+                    .await
+                    .with_context(|| format!("Failed to archive batch {key}"))?;
+            }
+
+            let keys = rows.into_iter().map(|row| row.key).collect::<Vec<_>>();
+            let is_last_batch = (keys.len() as i64) < batch_size;
+
+            deleted += spawn_blocking({
+                let pool = self.pool.clone();
+                move || {
+                    let mut conn = pool.get()?;
+                    let deleted = db::retention::delete(&mut conn, policy.table, keys)?;
+                    anyhow::Ok(deleted)
+                }
+            })
+            .await
+            .expect("task to complete")?;
+
+            if is_last_batch {
+                break;
+            }
+        }
+
+        Ok(deleted)
+    }
+}
+
+impl ObjectStorage {
+    /// Objects are stored under the given URL, authenticated with the bearer token if it is not
+    /// empty.
+    pub fn new(mut url: Url, token: String) -> Self {
+        // Otherwise, the last path segment would be replaced when joining the object key.
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            token: (!token.is_empty()).then_some(token),
+        }
+    }
+
+    pub(crate) async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        let url = self.url.join(key)?;
+
+        let mut request = self
+            .client
+            .put(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/gzip")
+            .body(body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        request.send().await?.error_for_status()?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for RetainedTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table = match self {
+            RetainedTable::Orders => "orders",
+            RetainedTable::Matches => "matches",
+            RetainedTable::OrderFills => "order_fills",
+            RetainedTable::OrderbookJournal => "orderbook_journal",
+        };
+
+        f.write_str(table)
+    }
+}
+
+/// The object key of an archived batch, e.g. `orders/20240626T120000Z-00000.jsonl.gz`.
+fn archive_key(table: RetainedTable, started_at: OffsetDateTime, batch: usize) -> String {
+    let started_at = started_at
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .expect("to format timestamp");
+
+    format!("{table}/{started_at}-{batch:05}.jsonl.gz")
+}
+
+/// Gzip the rows as JSON lines.
+fn compress_jsonl<'a>(rows: impl Iterator<Item = &'a str>) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    for row in rows {
+        encoder.write_all(row.as_bytes())?;
+        encoder.write_all(b"\n")?;
+    }
+
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use time::macros::datetime;
+
+    #[test]
+    fn archive_key_contains_table_and_batch() {
+        let key = archive_key(
+            RetainedTable::OrderbookJournal,
+            datetime!(2024-06-26 12:00:00 UTC),
+            3,
+        );
+
+        assert_eq!(key, "orderbook_journal/20240626T120000Z-00003.jsonl.gz");
+    }
+
+    #[test]
+    fn compressed_rows_are_json_lines() {
+        let rows = [r#"{"id":1}"#, r#"{"id":2}"#];
+
+        let compressed = compress_jsonl(rows.into_iter()).unwrap();
+
+        let mut decompressed = String::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, "{\"id\":1}\n{\"id\":2}\n");
+    }
+}
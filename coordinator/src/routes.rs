@@ -1,56 +1,129 @@
+use crate::api_key::ApiKeySecrets;
 use crate::backup::SledBackup;
 use crate::campaign::post_push_campaign;
 use crate::collaborative_revert::confirm_collaborative_revert;
 use crate::db;
 use crate::db::user;
 use crate::db::user::User;
+use crate::decimal_from_f32;
+use crate::funding_fee;
 use crate::leaderboard::generate_leader_board;
 use crate::leaderboard::LeaderBoard;
 use crate::leaderboard::LeaderBoardCategory;
 use crate::leaderboard::LeaderBoardQueryParams;
+use crate::lightning_withdrawal;
 use crate::message::NewUserMessage;
 use crate::message::OrderbookMessage;
 use crate::node::invoice;
 use crate::node::Node;
 use crate::notifications::Notification;
-use crate::orderbook::trading::NewOrderMessage;
+use crate::orderbook::cancel_on_disconnect::CancelOnDisconnect;
+use crate::orderbook::journal_export::JournalExporter;
+use crate::orderbook::order_simulation::SimulationRateLimiter;
+use crate::orderbook::trading::OrderbookCommand;
+use crate::orderbook::websocket::FeedMessage;
 use crate::parse_dlc_channel_id;
+use crate::reserve_interest;
+use crate::reserve_top_up;
+use crate::retention::DataRetention;
 use crate::routes::admin::post_funding_rates;
+use crate::scheduler::Scheduler;
+use crate::session_token::SessionTokens;
 use crate::settings::Settings;
+use crate::settlement_report::ReportUrls;
+use crate::trade::simulation::simulate_trade;
+use crate::trade::simulation::SimulationSettings;
 use crate::trade::websocket::InternalPositionUpdateMessage;
 use crate::AppError;
+use admin::accelerate_funding_transaction;
+use admin::clear_zombie_channel;
 use admin::close_channel;
 use admin::collaborative_revert;
 use admin::delete_dlc_channel;
+use admin::download_orderbook_journal_export;
+use admin::exempt_zombie_channel;
+use admin::export_orderbook_journal;
+use admin::force_close_zombie_channel;
+use admin::get_archived_messages;
 use admin::get_balance;
+use admin::get_channel_migrations;
+use admin::get_dlc_channel_history;
+use admin::get_escalated_expiry_settlements;
 use admin::get_fee_rate_estimation;
+use admin::get_funding_accelerations;
+use admin::get_job_runs;
+use admin::get_jobs;
+use admin::get_kill_switch;
+use admin::get_last_outbound_dlc_messages;
+use admin::get_latency;
+use admin::get_order_fills;
+use admin::get_order_flow;
+use admin::get_orderbook;
+use admin::get_orderbook_journal;
+use admin::get_orderbook_journal_exports;
+use admin::get_position_risk;
+use admin::get_quiesce;
+use admin::get_rejections;
+use admin::get_restrictions;
 use admin::get_settings;
+use admin::get_settlement_disputes;
+use admin::get_support_tickets;
+use admin::get_trader_channel_migrations;
+use admin::get_treasury;
+use admin::get_treasury_history;
 use admin::get_user_referral_status;
 use admin::get_utxos;
+use admin::get_zombie_channels;
 use admin::is_connected;
+use admin::lift_restriction;
 use admin::list_dlc_channels;
 use admin::list_on_chain_transactions;
 use admin::list_peers;
 use admin::migrate_dlc_channels;
+use admin::orderbook_websocket;
+use admin::park_zombie_channel;
+use admin::pause_job;
 use admin::post_sync;
+use admin::put_kill_switch;
+use admin::release_quiesce;
+use admin::require_admin_token;
 use admin::resend_renew_revoke_message;
+use admin::resolve_settlement_dispute;
+use admin::restrict_trader;
+use admin::resume_job;
 use admin::roll_back_dlc_channel;
 use admin::rollover;
+use admin::settle_reserve_top_up;
+use admin::start_channel_migration;
+use admin::start_quiesce;
+use admin::trigger_job;
 use admin::update_settings;
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
+use api_keys::create_api_key;
+use api_keys::delete_trader_order;
+use api_keys::get_maker_earnings;
+use api_keys::get_settlement_report;
+use api_keys::get_settlement_reports;
+use api_keys::get_trader_orders;
+use api_keys::post_simulate_order;
+use api_keys::post_trader_order;
+use api_keys::put_hedging_costs;
+use api_keys::revoke_api_key;
 use axum::extract::ConnectInfo;
 use axum::extract::DefaultBodyLimit;
 use axum::extract::Path;
 use axum::extract::Query;
 use axum::extract::State;
 use axum::extract::WebSocketUpgrade;
+use axum::middleware;
 use axum::response::IntoResponse;
 use axum::routing::delete;
 use axum::routing::get;
 use axum::routing::post;
 use axum::routing::put;
+use axum::Extension;
 use axum::Json;
 use axum::Router;
 use bitcoin::consensus::encode::serialize_hex;
@@ -58,17 +131,30 @@ use bitcoin::secp256k1::ecdsa::Signature;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::secp256k1::Secp256k1;
 use bitcoin::secp256k1::VerifyOnly;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use bootstrap::get_bootstrap;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 use diesel::PgConnection;
+use latency::track_latency;
+use latency::LatencyTracker;
 use lnd_bridge::InvoiceParams;
 use lnd_bridge::LndBridge;
 use orderbook::delete_order;
 use orderbook::get_order;
 use orderbook::get_orders;
 use orderbook::post_order;
+use orderbook::post_order_v2;
 use orderbook::websocket_handler;
+use pagination::Paginated;
+use prometheus::Encoder;
+use prometheus::TextEncoder;
 use serde::Serialize;
+use session::create_session_token;
+use session::require_session_token;
+use session::AuthenticatedTrader;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -80,31 +166,45 @@ use tokio::sync::mpsc;
 use tokio::sync::RwLock;
 use tokio::task::spawn_blocking;
 use tracing::instrument;
+use versioning::track_api_version;
+use versioning::ApiVersion;
 use xxi_node::commons;
 use xxi_node::commons::Backup;
 use xxi_node::commons::CollaborativeRevertTraderResponse;
 use xxi_node::commons::DeleteBackup;
-use xxi_node::commons::Message;
+use xxi_node::commons::PageParams;
 use xxi_node::commons::Poll;
 use xxi_node::commons::PollAnswers;
+use xxi_node::commons::PositionFilter;
 use xxi_node::commons::RegisterParams;
 use xxi_node::commons::ReportedError;
 use xxi_node::commons::Restore;
 use xxi_node::commons::SignedValue;
+use xxi_node::commons::SimulateTradeParams;
+use xxi_node::commons::TradeSimulation;
 use xxi_node::commons::UpdateUsernameParams;
 use xxi_node::node::NodeInfo;
 
 mod admin;
+mod api_keys;
+mod bootstrap;
+mod latency;
 mod orderbook;
+mod pagination;
+mod session;
+mod versioning;
+
+pub use latency::LatencySloSettings;
+pub use latency::LatencyTarget;
 
 pub struct AppState {
     pub node: Node,
     // Channel used to send messages to all connected clients.
-    pub tx_orderbook_feed: broadcast::Sender<Message>,
+    pub tx_orderbook_feed: broadcast::Sender<FeedMessage>,
     /// A channel used to send messages about position updates
     pub tx_position_feed: broadcast::Sender<InternalPositionUpdateMessage>,
     pub tx_user_feed: broadcast::Sender<NewUserMessage>,
-    pub trading_sender: mpsc::Sender<NewOrderMessage>,
+    pub trading_sender: mpsc::Sender<OrderbookCommand>,
     pub pool: Pool<ConnectionManager<PgConnection>>,
     pub settings: RwLock<Settings>,
     pub node_alias: String,
@@ -113,6 +213,16 @@ pub struct AppState {
     pub user_backup: SledBackup,
     pub secp: Secp256k1<VerifyOnly>,
     pub lnd_bridge: LndBridge,
+    pub session_tokens: SessionTokens,
+    pub api_key_secrets: ApiKeySecrets,
+    pub report_urls: ReportUrls,
+    pub data_retention: DataRetention,
+    pub journal_exporter: JournalExporter,
+    pub scheduler: Scheduler,
+    pub latency: LatencyTracker,
+    pub cancel_on_disconnect: CancelOnDisconnect,
+    pub simulation_rate_limiter: SimulationRateLimiter,
+    pub admin_token: Option<String>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -121,16 +231,25 @@ pub fn router(
     pool: Pool<ConnectionManager<PgConnection>>,
     settings: Settings,
     node_alias: &str,
-    trading_sender: mpsc::Sender<NewOrderMessage>,
-    tx_orderbook_feed: broadcast::Sender<Message>,
+    trading_sender: mpsc::Sender<OrderbookCommand>,
+    tx_orderbook_feed: broadcast::Sender<FeedMessage>,
     tx_position_feed: broadcast::Sender<InternalPositionUpdateMessage>,
     tx_user_feed: broadcast::Sender<NewUserMessage>,
     auth_users_notifier: mpsc::Sender<OrderbookMessage>,
     notification_sender: mpsc::Sender<Notification>,
     user_backup: SledBackup,
     lnd_bridge: LndBridge,
+    session_tokens: SessionTokens,
+    api_key_secrets: ApiKeySecrets,
+    report_urls: ReportUrls,
+    data_retention: DataRetention,
+    journal_exporter: JournalExporter,
+    scheduler: Scheduler,
+    admin_token: Option<String>,
 ) -> Router {
     let secp = Secp256k1::verification_only();
+    let latency = LatencyTracker::new(settings.latency_slo.clone());
+    let cancel_on_disconnect = CancelOnDisconnect::new(trading_sender.clone());
 
     let app_state = Arc::new(AppState {
         node,
@@ -146,29 +265,26 @@ pub fn router(
         user_backup,
         secp,
         lnd_bridge,
+        session_tokens,
+        api_key_secrets,
+        report_urls,
+        data_retention,
+        journal_exporter,
+        scheduler,
+        latency: latency.clone(),
+        cancel_on_disconnect,
+        simulation_rate_limiter: SimulationRateLimiter::default(),
+        admin_token,
     });
 
+    let admin_token = middleware::from_fn_with_state(app_state.clone(), require_admin_token);
+
     Router::new()
         .route("/", get(lightning_peer_ws_handler))
-        .route("/api/version", get(version))
-        .route("/api/polls", post(post_poll_answer))
-        .route("/api/polls/:node_id", get(get_polls))
-        .route(
-            "/api/fee_rate_estimate/:target",
-            get(get_fee_rate_estimation),
-        )
-        .route("/api/backup/:node_id", post(back_up).delete(delete_backup))
-        .route("/api/restore/:node_id", get(restore))
-        .route("/api/newaddress", get(get_unused_address))
-        .route("/api/node", get(get_node_info))
-        .route("/api/orderbook/orders", get(get_orders).post(post_order))
-        .route("/api/orderbook/orders/:order_id", get(get_order))
-        .route("/api/orderbook/websocket", get(websocket_handler))
-        .route("/api/invoice", post(create_invoice))
-        .route("/api/users", post(post_register))
-        .route("/api/users/:trader_pubkey", get(get_user))
-        .route("/api/users/nickname", put(update_nickname))
-        .route("/api/report-error", post(post_error))
+        // Unversioned routes are kept for app versions which predate API versioning.
+        .nest("/api", api_v1())
+        .nest("/api/v1", api_v1())
+        .nest("/api/v2", api_v2(app_state.clone()))
         // TODO: we should move this back into public once we add signing to this function
         .route(
             "/api/admin/orderbook/orders/:order_id",
@@ -178,6 +294,10 @@ pub fn router(
         .route("/api/admin/wallet/balance", get(get_balance))
         .route("/api/admin/wallet/utxos", get(get_utxos))
         .route("/api/admin/channels/:channel_id", delete(close_channel))
+        .route(
+            "/api/admin/channels/:channel_id/history",
+            get(get_dlc_channel_history),
+        )
         .route("/api/admin/peers", get(list_peers))
         .route("/api/admin/dlc_channels", get(list_dlc_channels))
         .route(
@@ -190,10 +310,6 @@ pub fn router(
         )
         .route("/api/admin/transactions", get(list_on_chain_transactions))
         .route("/api/admin/channels/revert", post(collaborative_revert))
-        .route(
-            "/api/channels/confirm-collab-revert",
-            post(collaborative_revert_confirm),
-        )
         .route("/api/admin/is_connected/:target_pubkey", get(is_connected))
         .route(
             "/api/admin/settings",
@@ -213,18 +329,207 @@ pub fn router(
             "/api/admin/users/:trader_pubkey/referrals",
             get(get_user_referral_status),
         )
+        .route(
+            "/api/admin/users/:trader_pubkey/position-risk",
+            get(get_position_risk),
+        )
         .route("/api/admin/funding-rates", post(post_funding_rates))
+        .route(
+            "/api/admin/settlement-disputes",
+            get(get_settlement_disputes),
+        )
+        .route(
+            "/api/admin/settlement-disputes/:dispute_id/resolve",
+            post(resolve_settlement_dispute),
+        )
+        .route(
+            "/api/admin/reserve-top-ups/:top_up_id/settle",
+            post(settle_reserve_top_up),
+        )
+        .route("/api/admin/support-tickets", get(get_support_tickets))
+        .route("/api/admin/rejections", get(get_rejections))
+        .route("/api/admin/treasury", get(get_treasury))
+        .route("/api/admin/treasury/history", get(get_treasury_history))
+        .route("/api/admin/order-flow", get(get_order_flow))
+        .route("/api/admin/zombie-channels", get(get_zombie_channels))
+        .route(
+            "/api/admin/zombie-channels/:channel_id",
+            delete(clear_zombie_channel),
+        )
+        .route(
+            "/api/admin/zombie-channels/:channel_id/park",
+            post(park_zombie_channel),
+        )
+        .route(
+            "/api/admin/zombie-channels/:channel_id/force-close",
+            post(force_close_zombie_channel),
+        )
+        .route(
+            "/api/admin/zombie-channels/:channel_id/exempt",
+            post(exempt_zombie_channel),
+        )
+        .route(
+            "/api/admin/channels/:channel_id/accelerate-funding",
+            post(accelerate_funding_transaction),
+        )
+        .route(
+            "/api/admin/funding-accelerations",
+            get(get_funding_accelerations),
+        )
+        .route(
+            "/api/admin/message-archive/:protocol_id",
+            get(get_archived_messages),
+        )
+        .route(
+            "/api/admin/expiry-settlements/escalated",
+            get(get_escalated_expiry_settlements),
+        )
+        .route(
+            "/api/admin/dlc-messages/last-outbound",
+            get(get_last_outbound_dlc_messages),
+        )
+        .route("/api/admin/order-fills", get(get_order_fills))
+        .route("/api/admin/orderbook", get(get_orderbook))
+        .route("/api/admin/orderbook/websocket", get(orderbook_websocket))
+        .route(
+            "/api/admin/orderbook-journal/:order_id",
+            get(get_orderbook_journal),
+        )
+        .route(
+            "/api/admin/orderbook-journal-exports",
+            get(get_orderbook_journal_exports),
+        )
+        .route(
+            "/api/admin/orderbook-journal-exports/:date",
+            get(download_orderbook_journal_export).post(export_orderbook_journal),
+        )
+        .route("/api/admin/channel-migrations", get(get_channel_migrations))
+        .route(
+            "/api/admin/channel-migrations/:trader_pubkey",
+            get(get_trader_channel_migrations).post(start_channel_migration),
+        )
+        .route(
+            "/api/admin/kill-switch",
+            get(get_kill_switch).merge(put(put_kill_switch).route_layer(admin_token.clone())),
+        )
+        .route(
+            "/api/admin/quiesce",
+            get(get_quiesce).merge(
+                post(start_quiesce)
+                    .delete(release_quiesce)
+                    .route_layer(admin_token.clone()),
+            ),
+        )
+        .route("/api/admin/restrictions", get(get_restrictions))
+        .route(
+            "/api/admin/restrictions/:trader_pubkey",
+            post(restrict_trader)
+                .delete(lift_restriction)
+                .route_layer(admin_token),
+        )
+        .route("/api/admin/latency", get(get_latency))
+        .route("/api/admin/jobs", get(get_jobs))
+        .route("/api/admin/jobs/:job_name/runs", get(get_job_runs))
+        .route("/api/admin/jobs/:job_name/trigger", post(trigger_job))
+        .route("/api/admin/jobs/:job_name/pause", post(pause_job))
+        .route("/api/admin/jobs/:job_name/resume", post(resume_job))
         .route("/health", get(get_health))
-        .route("/api/leaderboard", get(get_leaderboard))
+        .route("/metrics", get(get_metrics))
         .route(
             "/api/admin/trade/websocket",
             get(crate::trade::websocket::websocket_handler),
         )
         .layer(DefaultBodyLimit::disable())
         .layer(DefaultBodyLimit::max(50 * 1024))
+        .layer(middleware::from_fn_with_state(latency, track_latency))
         .with_state(app_state)
 }
 
+/// The public routes, as served by all API versions.
+///
+/// Routes are relative to the API version prefix, e.g. `/api/v2`.
+fn public_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/version", get(version))
+        .route("/polls", post(post_poll_answer))
+        .route("/polls/:node_id", get(get_polls))
+        .route("/fee_rate_estimate/:target", get(get_fee_rate_estimation))
+        .route("/backup/:node_id", post(back_up).delete(delete_backup))
+        .route("/restore/:node_id", get(restore))
+        .route("/newaddress", get(get_unused_address))
+        .route("/node", get(get_node_info))
+        .route("/orderbook/orders/:order_id", get(get_order))
+        .route("/orderbook/websocket", get(websocket_handler))
+        .route("/invoice", post(create_invoice))
+        .route("/withdraw/lightning", post(post_lightning_withdrawal))
+        .route("/reserve/top-up", post(post_reserve_top_up))
+        .route("/users", post(post_register))
+        .route("/users/nickname", put(update_nickname))
+        .route("/report-error", post(post_error))
+        .route("/support-tickets", post(post_support_ticket))
+        .route("/simulate-trade", post(post_simulate_trade))
+        .route("/orderbook/simulate", post(post_simulate_order))
+        .route(
+            "/channels/confirm-collab-revert",
+            post(collaborative_revert_confirm),
+        )
+        .route("/leaderboard", get(get_leaderboard))
+}
+
+fn api_v1() -> Router<Arc<AppState>> {
+    public_routes()
+        .route("/orderbook/orders", get(get_orders).post(post_order))
+        .route("/users/:trader_pubkey", get(get_user))
+        .route(
+            "/reserve-interest/:trader_pubkey",
+            get(get_reserve_interest),
+        )
+        .layer(middleware::from_fn_with_state(
+            ApiVersion::V1,
+            track_api_version,
+        ))
+}
+
+/// In contrast to [`api_v1`], the routes acting on behalf of a trader require a session token.
+fn api_v2(app_state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let session = middleware::from_fn_with_state(app_state, require_session_token);
+
+    public_routes()
+        .route(
+            "/orderbook/orders",
+            get(get_orders).merge(post(post_order_v2).route_layer(session.clone())),
+        )
+        .route(
+            "/users/:trader_pubkey",
+            get(get_user).route_layer(session.clone()),
+        )
+        .route(
+            "/reserve-interest/:trader_pubkey",
+            get(get_reserve_interest).route_layer(session.clone()),
+        )
+        .route(
+            "/users/:trader_pubkey/positions",
+            get(get_position_history).route_layer(session.clone()),
+        )
+        .route("/bootstrap", get(get_bootstrap).route_layer(session))
+        .route("/session-tokens", post(create_session_token))
+        .route("/api-keys", post(create_api_key))
+        .route("/api-keys/:key_id", delete(revoke_api_key))
+        .route(
+            "/trader/orders",
+            get(get_trader_orders).post(post_trader_order),
+        )
+        .route("/trader/orders/:order_id", delete(delete_trader_order))
+        .route("/trader/settlement-reports", get(get_settlement_reports))
+        .route("/settlement-reports/:report_id", get(get_settlement_report))
+        .route("/trader/earnings", get(get_maker_earnings))
+        .route("/trader/earnings/hedging-costs", put(put_hedging_costs))
+        .layer(middleware::from_fn_with_state(
+            ApiVersion::V2,
+            track_api_version,
+        ))
+}
+
 #[derive(serde::Serialize)]
 struct HelloWorld {
     hello: String,
@@ -287,8 +592,13 @@ pub async fn post_register(
             register_params.version.clone(),
             register_params.os,
             register_params.referral_code,
-        )
-        .map_err(|e| anyhow!(e))
+        )?;
+
+        if let Some(locale) = register_params.locale {
+            user::set_locale(&mut conn, register_params.pubkey, locale)?;
+        }
+
+        anyhow::Ok(())
     })
     .await
     .expect("task to finish")
@@ -335,10 +645,15 @@ impl TryFrom<User> for commons::User {
 pub async fn get_user(
     State(state): State<Arc<AppState>>,
     Path(trader_pubkey): Path<String>,
+    trader: Option<Extension<AuthenticatedTrader>>,
 ) -> Result<Json<commons::User>, AppError> {
     let trader_pubkey = PublicKey::from_str(trader_pubkey.as_str())
         .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
 
+    if let Some(Extension(trader)) = trader {
+        trader.ensure(trader_pubkey)?;
+    }
+
     let option = spawn_blocking(move || {
         let mut conn = state.pool.get().context("Could not get connection")?;
         user::get_user(&mut conn, &trader_pubkey)
@@ -353,12 +668,118 @@ pub async fn get_user(
     }
 }
 
+#[instrument(skip_all, err(Debug))]
+pub async fn get_reserve_interest(
+    State(state): State<Arc<AppState>>,
+    Path(trader_pubkey): Path<String>,
+    trader: Option<Extension<AuthenticatedTrader>>,
+) -> Result<Json<commons::ReserveInterest>, AppError> {
+    let trader_pubkey = PublicKey::from_str(trader_pubkey.as_str())
+        .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
+
+    if let Some(Extension(trader)) = trader {
+        trader.ensure(trader_pubkey)?;
+    }
+
+    let apr = state.node.settings.read().await.reserve_interest_apr;
+
+    let reserve_interest = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        reserve_interest::get_reserve_interest(&mut conn, trader_pubkey, apr)
+    })
+    .await
+    .expect("task to finish")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load reserve interest: {e:#}"))
+    })?;
+
+    Ok(Json(reserve_interest))
+}
+
+/// A page of the closed positions of the trader, most recently closed first by default, with the
+/// oracle attestations the positions settled on-chain were settled with.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_position_history(
+    State(state): State<Arc<AppState>>,
+    Path(trader_pubkey): Path<String>,
+    Query(page): Query<PageParams<i32>>,
+    Query(filter): Query<PositionFilter>,
+    Extension(trader): Extension<AuthenticatedTrader>,
+) -> Result<Paginated<commons::ClosedPosition, i32>, AppError> {
+    let trader_pubkey = PublicKey::from_str(trader_pubkey.as_str())
+        .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
+
+    trader.ensure(trader_pubkey)?;
+
+    let closed_positions = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let positions = db::positions::Position::get_closed_positions_by_trader(
+            &mut conn,
+            trader_pubkey,
+            filter,
+            &page,
+        )?;
+
+        let position_ids = positions
+            .items
+            .iter()
+            .map(|position| position.id)
+            .collect::<Vec<_>>();
+        let mut attestations = HashMap::<i32, Vec<_>>::new();
+        for (position_id, attestation) in
+            db::settlement_attestations::get_by_positions(&mut conn, &position_ids)?
+        {
+            attestations
+                .entry(position_id)
+                .or_default()
+                .push(attestation);
+        }
+
+        let closed_positions = positions.map(|position| {
+            let settlement_attestations = attestations.remove(&position.id).unwrap_or_default();
+
+            commons::ClosedPosition {
+                contract_symbol: position.contract_symbol,
+                direction: position.trader_direction,
+                quantity: decimal_from_f32(position.quantity),
+                average_entry_price: decimal_from_f32(position.average_entry_price),
+                closing_price: position.closing_price.map(decimal_from_f32),
+                realized_pnl: position.trader_realized_pnl_sat.map(SignedAmount::from_sat),
+                closed_at: position.update_timestamp,
+                settlement_attestations,
+            }
+        });
+
+        anyhow::Ok(closed_positions)
+    })
+    .await
+    .expect("task to finish")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load position history: {e:#}"))
+    })?;
+
+    Ok(Paginated(closed_positions))
+}
+
 pub async fn get_health() -> Result<Json<String>, AppError> {
     // TODO: Implement any health check logic we'd need
     // So far this just returns if the server is running
     Ok(Json("Server is healthy".to_string()))
 }
 
+/// Expose the coordinator's metrics in the Prometheus text format.
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> Result<String, AppError> {
+    state.latency.update_burn_rates(OffsetDateTime::now_utc());
+
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to encode metrics: {e:#}")))?;
+
+    String::from_utf8(buffer)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to encode metrics: {e:#}")))
+}
+
 #[derive(Serialize)]
 pub struct Version {
     version: String,
@@ -597,6 +1018,38 @@ async fn post_error(
     Ok(())
 }
 
+/// Create a support ticket for a failed trade, so that the user can refer to it when contacting
+/// support.
+#[instrument(skip_all, err(Debug))]
+async fn post_support_ticket(
+    State(state): State<Arc<AppState>>,
+    Json(ticket): Json<SignedValue<commons::NewSupportTicket>>,
+) -> Result<Json<commons::SupportTicketReference>, AppError> {
+    let trader_pubkey = ticket.value.trader_pubkey;
+
+    ticket
+        .verify(&state.secp, &trader_pubkey)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let ticket = ticket.value;
+
+    let reference = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let reference = db::support_tickets::insert(&mut conn, ticket)?;
+
+        anyhow::Ok(reference)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not create support ticket: {e:#}"))
+    })?;
+
+    tracing::info!(%trader_pubkey, reference, "Created support ticket");
+
+    Ok(Json(commons::SupportTicketReference { reference }))
+}
+
 #[instrument(skip_all, err(Debug))]
 async fn create_invoice(
     State(state): State<Arc<AppState>>,
@@ -661,3 +1114,91 @@ async fn create_invoice(
 
     Ok(Json(response.payment_request))
 }
+
+/// Pay a Lightning invoice of the trader from their collateral reserve.
+#[instrument(skip_all, err(Debug))]
+async fn post_lightning_withdrawal(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<SignedValue<commons::LightningWithdrawalParams>>,
+) -> Result<(), AppError> {
+    let trader_pubkey = params.value.trader_pubkey;
+
+    params
+        .verify(&state.secp, &trader_pubkey)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    lightning_withdrawal::withdraw(state.node.clone(), trader_pubkey, params.value.invoice)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Could not withdraw via Lightning: {e:#}")))?;
+
+    Ok(())
+}
+
+/// Request to top up the trader's collateral reserve with an on-chain deposit.
+#[instrument(skip_all, err(Debug))]
+async fn post_reserve_top_up(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<SignedValue<commons::ReserveTopUpParams>>,
+) -> Result<Json<commons::ReserveTopUp>, AppError> {
+    let trader_pubkey = params.value.trader_pubkey;
+
+    params
+        .verify(&state.secp, &trader_pubkey)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let top_up = reserve_top_up::request(
+        state.node.clone(),
+        trader_pubkey,
+        Amount::from_sat(params.value.amount_sats),
+    )
+    .await
+    .map_err(|e| AppError::BadRequest(format!("Could not top up collateral reserve: {e:#}")))?;
+
+    Ok(Json(commons::ReserveTopUp {
+        address: top_up.address.to_string(),
+        amount_sats: top_up.amount.to_sat(),
+    }))
+}
+
+/// Simulate a trade, without executing it.
+///
+/// This allows the app to preview margins, fees, funding and payouts using exactly the same
+/// computations the coordinator uses during trade execution.
+#[instrument(skip_all, err(Debug))]
+async fn post_simulate_trade(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<SimulateTradeParams>,
+) -> Result<Json<TradeSimulation>, AppError> {
+    let expiry_schedule = state.node.expiry_schedule().await;
+    let settings = {
+        let settings = state.settings.read().await;
+        SimulationSettings {
+            maintenance_margin_rate: decimal_from_f32(settings.maintenance_margin_rate),
+            order_matching_fee_rate: decimal_from_f32(settings.order_matching_fee_rate),
+            expiry_schedule,
+        }
+    };
+
+    let next_funding_rate = spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get().context("Could not get db connection")?;
+            let funding_rate = funding_fee::get_next_funding_rate(&mut conn)?;
+
+            anyhow::Ok(funding_rate)
+        }
+    })
+    .await
+    .expect("task to finish")
+    .map_err(|e| AppError::InternalServerError(format!("Could not load funding rate: {e:#}")))?;
+
+    let simulation = simulate_trade(
+        &params,
+        next_funding_rate,
+        settings,
+        OffsetDateTime::now_utc(),
+    )
+    .map_err(|e| AppError::BadRequest(format!("Could not simulate trade: {e:#}")))?;
+
+    Ok(Json(simulation))
+}
@@ -1,18 +1,52 @@
 use crate::collaborative_revert;
 use crate::db;
+use crate::db::job_runs::JobRun;
 use crate::funding_fee::insert_funding_rates;
+use crate::message_archive::ArchivedMessage;
+use crate::moderation::Restriction;
+use crate::node::channel_migration;
+use crate::node::funding_accelerator;
+use crate::node::quiesce;
+use crate::node::quiesce::QuiesceReport;
+use crate::node::quiesce::QuiesceStatus;
+use crate::node::zombie_channels;
+use crate::orderbook::book::L3Book;
+use crate::orderbook::db::journal;
+use crate::orderbook::db::order_fills;
+use crate::orderbook::journal_export;
+use crate::orderbook::journal_export::JournalExport;
+use crate::orderbook::order_flow;
+use crate::orderbook::trading::get_l3_book;
+use crate::orderbook::websocket::FeedMessage;
 use crate::parse_dlc_channel_id;
+use crate::position;
 use crate::position::models::Position;
 use crate::referrals;
+use crate::reserve_interest;
+use crate::reserve_top_up;
+use crate::routes::latency::EndpointSummary;
 use crate::routes::AppState;
+use crate::scheduler::JobStatus;
 use crate::settings::SettingsFile;
+use crate::treasury;
 use crate::AppError;
 use anyhow::Context;
+use axum::extract::ws::Message as WebsocketMessage;
+use axum::extract::ws::WebSocket;
+use axum::extract::ws::WebSocketUpgrade;
 use axum::extract::Path;
 use axum::extract::Query;
 use axum::extract::State;
+use axum::http::header::AUTHORIZATION;
+use axum::http::header::CONTENT_DISPOSITION;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::Request;
+use axum::middleware::Next;
 use axum::response::IntoResponse;
+use axum::response::Response;
 use axum::Json;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::Amount;
 use bitcoin::OutPoint;
@@ -39,13 +73,22 @@ use std::fmt;
 use std::num::NonZeroU32;
 use std::str::FromStr;
 use std::sync::Arc;
+use time::macros::format_description;
+use time::Date;
+use time::Duration;
 use time::OffsetDateTime;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::task::spawn_blocking;
 use tracing::instrument;
+use uuid::Uuid;
 use xxi_node::bitcoin_conversion::to_secp_pk_30;
 use xxi_node::bitcoin_conversion::to_txid_30;
 use xxi_node::commons;
 use xxi_node::commons::CollaborativeRevertCoordinatorRequest;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::KillSwitchStatus;
+use xxi_node::commons::Message;
+use xxi_node::node::tentenone_message_name;
 use xxi_node::node::ProtocolId;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -302,7 +345,11 @@ pub async fn close_channel(
     tracing::info!(channel_id = %channel_id_string, "Attempting to close channel");
 
     match params.force.unwrap_or_default() {
-        true => state.node.force_close_dlc_channel(channel_id).await,
+        true => state
+            .node
+            .force_close_dlc_channel(channel_id)
+            .await
+            .map(|_| ()),
         false => state.node.close_dlc_channel(channel_id).await,
     }
     .map_err(|e| AppError::InternalServerError(format!("{e:#}")))?;
@@ -428,12 +475,7 @@ pub async fn rollover(
 
     state
         .node
-        .propose_rollover(
-            &mut connection,
-            &dlc_channel_id,
-            position,
-            state.node.inner.network,
-        )
+        .propose_rollover(&mut connection, &dlc_channel_id, position)
         .await
         .map_err(|e| {
             AppError::InternalServerError(format!("Failed to rollover DLC channel: {e:#}",))
@@ -633,8 +675,19 @@ pub async fn update_settings(
 ) -> Result<(), AppError> {
     let mut settings = state.settings.write().await;
 
+    let reserve_interest_apr = settings.reserve_interest_apr;
+
     settings.update(updated_settings.clone());
 
+    // The accrued interest has to be recorded at the previous APR, before the new one takes effect.
+    if settings.reserve_interest_apr != reserve_interest_apr {
+        if let Err(e) =
+            reserve_interest::close_accrual_periods(state.pool.clone(), reserve_interest_apr).await
+        {
+            tracing::error!("Could not close reserve interest periods: {e:#}");
+        }
+    }
+
     settings
         .write_to_file()
         .await
@@ -642,6 +695,19 @@ pub async fn update_settings(
 
     // Forward relevant settings down to the xxi node.
     state.node.inner.update_settings(settings.xxi.clone()).await;
+    *state.node.settings.write().await = settings.to_node_settings();
+    state
+        .node
+        .message_archive
+        .update_settings(settings.message_archive);
+    state
+        .data_retention
+        .update_settings(settings.retention.clone());
+    state.latency.update_settings(settings.latency_slo.clone());
+    state.node.moderation.update_settings(settings.moderation);
+    state
+        .journal_exporter
+        .update_settings(settings.journal_export.clone());
 
     Ok(())
 }
@@ -668,6 +734,32 @@ pub async fn get_user_referral_status(
     Ok(Json(referral_status))
 }
 
+/// The effective liquidation prices and margin ratios of the active position of a trader,
+/// accounting for the funding fees accrued since the position was last updated.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_position_risk(
+    State(state): State<Arc<AppState>>,
+    Path(trader_pubkey): Path<String>,
+) -> Result<Json<Option<position::risk::PositionRisk>>, AppError> {
+    let trader_pubkey = PublicKey::from_str(&trader_pubkey)
+        .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
+
+    let maintenance_margin_rate = state.settings.read().await.maintenance_margin_rate;
+    let maintenance_margin_rate = Decimal::try_from(maintenance_margin_rate).expect("to fit");
+
+    let risk = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        position::risk::get_position_risk(&mut conn, trader_pubkey, maintenance_margin_rate)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not compute position risk: {e:#}"))
+    })?;
+
+    Ok(Json(risk))
+}
+
 #[instrument(skip_all, err(Debug))]
 pub async fn post_funding_rates(
     State(state): State<Arc<AppState>>,
@@ -696,6 +788,1026 @@ pub async fn post_funding_rates(
     Ok(())
 }
 
+#[instrument(skip_all, err(Debug))]
+pub async fn get_settlement_disputes(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<db::settlement_disputes::SettlementDispute>>, AppError> {
+    let disputes = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let disputes = db::settlement_disputes::get_unresolved(&mut conn)?;
+
+        anyhow::Ok(disputes)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load settlement disputes: {e:#}"))
+    })?;
+
+    Ok(Json(disputes))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveSettlementDispute {
+    /// Why the settlement may proceed with the attested price.
+    note: String,
+}
+
+/// Resolve a settlement dispute, allowing the settlement of DLC channels to proceed once no other
+/// dispute is unresolved.
+#[instrument(skip_all, err(Debug))]
+pub async fn resolve_settlement_dispute(
+    State(state): State<Arc<AppState>>,
+    Path(dispute_id): Path<i32>,
+    Json(params): Json<ResolveSettlementDispute>,
+) -> Result<Json<db::settlement_disputes::SettlementDispute>, AppError> {
+    let dispute = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let dispute = db::settlement_disputes::resolve(&mut conn, dispute_id, &params.note)?;
+
+        anyhow::Ok(dispute)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not resolve settlement dispute: {e:#}"))
+    })?
+    .ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "No unresolved settlement dispute with id {dispute_id}"
+        ))
+    })?;
+
+    tracing::info!(?dispute, "Resolved settlement dispute");
+
+    Ok(Json(dispute))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SettleReserveTopUp {
+    /// How the top-up was settled, e.g. the txid of the refund.
+    note: String,
+}
+
+/// Settle a reserve top-up which can't be credited in the DLC channel, after refunding the trader
+/// outside of it. The top-up then no longer prevents the DLC channel from being closed.
+#[instrument(skip_all, err(Debug))]
+pub async fn settle_reserve_top_up(
+    State(state): State<Arc<AppState>>,
+    Path(top_up_id): Path<i32>,
+    Json(params): Json<SettleReserveTopUp>,
+) -> Result<(), AppError> {
+    spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        reserve_top_up::settle(&mut conn, top_up_id, &params.note)?;
+
+        anyhow::Ok(())
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::BadRequest(format!("Could not settle reserve top-up: {e:#}")))?;
+
+    Ok(())
+}
+
+/// DLC channels which are currently handled as zombies, because their trader has been offline for
+/// too long.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_zombie_channels(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<db::zombie_channels::ZombieChannel>>, AppError> {
+    let zombies = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let zombies = db::zombie_channels::get_active(&mut conn)?;
+
+        anyhow::Ok(zombies)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not load zombie channels: {e:#}")))?;
+
+    Ok(Json(zombies))
+}
+
+/// Accelerate the unconfirmed funding transaction of the DLC channel, see
+/// [`funding_accelerator::accelerate`].
+#[instrument(skip_all, err(Debug))]
+pub async fn accelerate_funding_transaction(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<String>,
+) -> Result<Json<db::funding_accelerations::FundingAcceleration>, AppError> {
+    let channel_id = parse_dlc_channel_id(&channel_id)
+        .map_err(|_| AppError::BadRequest("Provided channel ID was invalid".to_string()))?;
+
+    let acceleration = funding_accelerator::accelerate(
+        &state.node,
+        channel_id,
+        db::funding_accelerations::AccelerationTrigger::Manual,
+    )
+    .await
+    .map_err(|e| {
+        AppError::BadRequest(format!("Could not accelerate funding transaction: {e:#}"))
+    })?;
+
+    Ok(Json(acceleration))
+}
+
+#[derive(Serialize)]
+pub struct FundingAccelerations {
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub total_cost_sats: Amount,
+    pub accelerations: Vec<db::funding_accelerations::FundingAcceleration>,
+}
+
+/// All accelerations of funding transactions, most recent first, and what they cost in total.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_funding_accelerations(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FundingAccelerations>, AppError> {
+    let accelerations = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let accelerations = db::funding_accelerations::get_all(&mut conn)?;
+
+        anyhow::Ok(accelerations)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load funding accelerations: {e:#}"))
+    })?;
+
+    let total_cost_sats = accelerations
+        .iter()
+        .map(|acceleration| Amount::from_sat(acceleration.cost_sats as u64))
+        .sum();
+
+    Ok(Json(FundingAccelerations {
+        total_cost_sats,
+        accelerations,
+    }))
+}
+
+/// The history of a DLC channel, with the changes between consecutive states.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_dlc_channel_history(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<String>,
+) -> Result<Json<Vec<db::dlc_channel_snapshots::DlcChannelHistoryEntry>>, AppError> {
+    let channel_id = DlcChannelId::from_hex(channel_id)
+        .map_err(|e| AppError::BadRequest(format!("Invalid DLC channel ID: {e}")))?;
+
+    let snapshots = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let snapshots = db::dlc_channel_snapshots::get_by_channel_id(&mut conn, &channel_id)?;
+
+        anyhow::Ok(snapshots)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load DLC channel history: {e:#}"))
+    })?;
+
+    Ok(Json(db::dlc_channel_snapshots::history(snapshots)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZombieChannelOverride {
+    /// Why the admin overrides the zombie channel detection.
+    note: String,
+}
+
+/// Park the DLC channel right away, without waiting for the trader to be notified.
+#[instrument(skip_all, err(Debug))]
+pub async fn park_zombie_channel(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<String>,
+    Json(params): Json<ZombieChannelOverride>,
+) -> Result<Json<db::zombie_channels::ZombieChannel>, AppError> {
+    let channel_id = parse_dlc_channel_id(&channel_id)
+        .map_err(|_| AppError::BadRequest("Provided channel ID was invalid".to_string()))?;
+
+    let zombie = zombie_channels::park(&state.node, channel_id, params.note)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Could not park DLC channel: {e:#}")))?;
+
+    tracing::info!(?zombie, "Parked zombie channel");
+
+    Ok(Json(zombie))
+}
+
+/// Force-close the DLC channel right away, without waiting for the trader to be notified.
+#[instrument(skip_all, err(Debug))]
+pub async fn force_close_zombie_channel(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<String>,
+    Json(params): Json<ZombieChannelOverride>,
+) -> Result<Json<db::zombie_channels::ZombieChannel>, AppError> {
+    let channel_id = parse_dlc_channel_id(&channel_id)
+        .map_err(|_| AppError::BadRequest("Provided channel ID was invalid".to_string()))?;
+
+    let zombie = zombie_channels::force_close(&state.node, channel_id, params.note)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Could not force-close DLC channel: {e:#}")))?;
+
+    Ok(Json(zombie))
+}
+
+/// Never treat the DLC channel as a zombie, e.g. because the trader told us they will be offline
+/// for a while.
+#[instrument(skip_all, err(Debug))]
+pub async fn exempt_zombie_channel(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<String>,
+    Json(params): Json<ZombieChannelOverride>,
+) -> Result<Json<db::zombie_channels::ZombieChannel>, AppError> {
+    let channel_id = parse_dlc_channel_id(&channel_id)
+        .map_err(|_| AppError::BadRequest("Provided channel ID was invalid".to_string()))?;
+
+    let zombie = zombie_channels::exempt(&state.node, channel_id, params.note)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Could not exempt DLC channel: {e:#}")))?;
+
+    tracing::info!(
+        ?zombie,
+        "Exempted DLC channel from zombie channel detection"
+    );
+
+    Ok(Json(zombie))
+}
+
+/// Stop treating the DLC channel as a zombie, lifting an exemption or unparking it.
+#[instrument(skip_all, err(Debug))]
+pub async fn clear_zombie_channel(
+    State(state): State<Arc<AppState>>,
+    Path(channel_id): Path<String>,
+) -> Result<Json<db::zombie_channels::ZombieChannel>, AppError> {
+    let channel_id = parse_dlc_channel_id(&channel_id)
+        .map_err(|_| AppError::BadRequest("Provided channel ID was invalid".to_string()))?;
+
+    let zombie = zombie_channels::clear(&state.node, channel_id)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Could not clear zombie channel: {e:#}")))?;
+
+    tracing::info!(?zombie, "Cleared zombie channel");
+
+    Ok(Json(zombie))
+}
+
+/// The decrypted DLC messages exchanged with the trader as part of a DLC protocol.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_archived_messages(
+    State(state): State<Arc<AppState>>,
+    Path(protocol_id): Path<Uuid>,
+) -> Result<Json<Vec<ArchivedMessage>>, AppError> {
+    let protocol_id = ProtocolId::from(protocol_id);
+
+    let messages = spawn_blocking(move || state.node.message_archive.get(protocol_id))
+        .await
+        .expect("task to complete")
+        .map_err(|e| {
+            AppError::InternalServerError(format!("Could not load archived messages: {e:#}"))
+        })?;
+
+    Ok(Json(messages))
+}
+
+/// Expired positions which could not be settled at attestation before the deadline.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_escalated_expiry_settlements(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<db::expiry_settlement_attempts::ExpirySettlementAttempt>>, AppError> {
+    let attempts = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let attempts = db::expiry_settlement_attempts::get_escalated(&mut conn)?;
+
+        anyhow::Ok(attempts)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load expiry settlements: {e:#}"))
+    })?;
+
+    Ok(Json(attempts))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderFillsParams {
+    limit: Option<i64>,
+}
+
+/// Time-to-fill, slippage and queue position of the most recently filled orders.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_order_fills(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OrderFillsParams>,
+) -> Result<Json<Vec<order_fills::OrderFillAnalytics>>, AppError> {
+    let limit = params.limit.unwrap_or(100);
+
+    let fills = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let fills = order_fills::get_latest(&mut conn, limit)?;
+
+        anyhow::Ok(fills)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not load order fills: {e:#}")))?;
+
+    Ok(Json(fills))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SupportTicketsParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    trader_pubkey: Option<PublicKey>,
+    limit: Option<i64>,
+}
+
+/// The most recent support tickets created by the app for failed trades.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_support_tickets(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SupportTicketsParams>,
+) -> Result<Json<Vec<db::support_tickets::SupportTicket>>, AppError> {
+    let limit = params.limit.unwrap_or(100);
+
+    let tickets = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let tickets = db::support_tickets::get(&mut conn, params.trader_pubkey, limit)?;
+
+        anyhow::Ok(tickets)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not load support tickets: {e:#}")))?;
+
+    Ok(Json(tickets))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RejectionsParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    trader_pubkey: Option<PublicKey>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    order_id: Option<Uuid>,
+    limit: Option<i64>,
+}
+
+/// The most recent DLC protocol offers rejected by the apps, with the reason they gave.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_rejections(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RejectionsParams>,
+) -> Result<Json<Vec<db::dlc_protocol_rejections::DlcProtocolRejection>>, AppError> {
+    let limit = params.limit.unwrap_or(100);
+
+    let rejections = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let rejections = db::dlc_protocol_rejections::get(
+            &mut conn,
+            params.trader_pubkey,
+            params.order_id,
+            limit,
+        )?;
+
+        anyhow::Ok(rejections)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not load rejections: {e:#}")))?;
+
+    Ok(Json(rejections))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TreasuryParams {
+    days: Option<u32>,
+}
+
+/// The current funds of the coordinator and the fees it earned on each of the last days.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_treasury(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TreasuryParams>,
+) -> Result<Json<treasury::TreasuryReport>, AppError> {
+    let days = params.days.unwrap_or(30);
+
+    let report = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        treasury::get_treasury_report(&mut conn, &state.node, days, OffsetDateTime::now_utc())
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not load treasury report: {e:#}")))?;
+
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TreasuryHistoryParams {
+    limit: Option<i64>,
+}
+
+/// The most recent snapshots of the funds of the coordinator.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_treasury_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TreasuryHistoryParams>,
+) -> Result<Json<Vec<treasury::TreasurySnapshot>>, AppError> {
+    let limit = params.limit.unwrap_or(30);
+
+    let snapshots = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let snapshots = treasury::get_treasury_snapshots(&mut conn, limit)?;
+
+        anyhow::Ok(snapshots)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load treasury snapshots: {e:#}"))
+    })?;
+
+    Ok(Json(snapshots))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderFlowParams {
+    days: Option<u32>,
+}
+
+/// The orders and matches of the last days, broken down by the client which submitted the
+/// orders.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_order_flow(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OrderFlowParams>,
+) -> Result<Json<order_flow::OrderFlowReport>, AppError> {
+    let days = params.days.unwrap_or(7);
+    let since = OffsetDateTime::now_utc() - Duration::days(i64::from(days));
+
+    let report = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        order_flow::get_order_flow_report(&mut conn, since)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not load order flow: {e:#}")))?;
+
+    Ok(Json(report))
+}
+
+/// The changes the orderbook recorded for an order, in the order they were applied.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_orderbook_journal(
+    State(state): State<Arc<AppState>>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<Vec<journal::JournalEntry>>, AppError> {
+    let entries = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let entries = journal::get_by_order_id(&mut conn, order_id)?;
+
+        anyhow::Ok(entries)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load orderbook journal: {e:#}"))
+    })?;
+
+    Ok(Json(entries))
+}
+
+/// The daily exports of the orderbook journal, newest first.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_orderbook_journal_exports(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<JournalExport>>, AppError> {
+    let exports = spawn_blocking(move || state.journal_exporter.list())
+        .await
+        .expect("task to complete")
+        .map_err(|e| AppError::InternalServerError(format!("Could not list exports: {e:#}")))?;
+
+    Ok(Json(exports))
+}
+
+/// Export the orderbook journal of the given day, replacing an earlier export of the same day.
+#[instrument(skip_all, err(Debug))]
+pub async fn export_orderbook_journal(
+    State(state): State<Arc<AppState>>,
+    Path(date): Path<String>,
+) -> Result<Json<JournalExport>, AppError> {
+    let date = parse_export_date(&date)?;
+
+    let export =
+        state.journal_exporter.export(date).await.map_err(|e| {
+            AppError::BadRequest(format!("Could not export orderbook journal: {e:#}"))
+        })?;
+
+    Ok(Json(export))
+}
+
+/// Download the Parquet export of the orderbook journal of the given day.
+#[instrument(skip_all, err(Debug))]
+pub async fn download_orderbook_journal_export(
+    State(state): State<Arc<AppState>>,
+    Path(date): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let date = parse_export_date(&date)?;
+
+    let export = spawn_blocking(move || state.journal_exporter.read(date))
+        .await
+        .expect("task to complete")
+        .map_err(|e| AppError::InternalServerError(format!("Could not read export: {e:#}")))?
+        .ok_or_else(|| AppError::BadRequest(format!("No export of {date}")))?;
+
+    let headers = [
+        (CONTENT_TYPE, "application/vnd.apache.parquet".to_string()),
+        (
+            CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}\"",
+                journal_export::file_name(date)
+            ),
+        ),
+    ];
+
+    Ok((headers, export))
+}
+
+fn parse_export_date(date: &str) -> Result<Date, AppError> {
+    Date::parse(date, format_description!("[year]-[month]-[day]"))
+        .map_err(|e| AppError::BadRequest(format!("Invalid date {date}: {e:#}")))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderbookParams {
+    /// The market of the book. Defaults to [`ContractSymbol::BtcUsd`].
+    contract_symbol: Option<ContractSymbol>,
+}
+
+impl OrderbookParams {
+    fn contract_symbol(&self) -> ContractSymbol {
+        self.contract_symbol.unwrap_or(ContractSymbol::BtcUsd)
+    }
+}
+
+/// The full book of a market, straight from the in-memory state of the matching engine.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_orderbook(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OrderbookParams>,
+) -> Result<Json<L3Book>, AppError> {
+    let book = get_l3_book(&state.trading_sender, params.contract_symbol())
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Could not get orderbook: {e:#}")))?;
+
+    Ok(Json(book))
+}
+
+/// Streams the full orderbook, see [`get_orderbook`]. A new snapshot is sent whenever the book
+/// changes.
+pub async fn orderbook_websocket(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OrderbookParams>,
+) -> impl IntoResponse {
+    let contract_symbol = params.contract_symbol();
+    ws.on_upgrade(move |socket| stream_orderbook(socket, state, contract_symbol))
+}
+
+async fn stream_orderbook(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    contract_symbol: ContractSymbol,
+) {
+    // Subscribe before taking the first snapshot, so that we don't miss any change.
+    let mut orderbook_feed = state.tx_orderbook_feed.subscribe();
+    let mut last_sequence = None;
+
+    loop {
+        let book = match get_l3_book(&state.trading_sender, contract_symbol).await {
+            Ok(book) => book,
+            Err(e) => {
+                tracing::error!("Could not get orderbook: {e:#}");
+                break;
+            }
+        };
+
+        // Expired orders are only removed when the next command is processed, hence the book might
+        // not have changed yet.
+        if last_sequence != Some(book.sequence) {
+            last_sequence = Some(book.sequence);
+
+            let book = match serde_json::to_string(&book) {
+                Ok(book) => book,
+                Err(e) => {
+                    tracing::error!("Could not serialize orderbook: {e:#}");
+                    break;
+                }
+            };
+
+            if let Err(e) = socket.send(WebsocketMessage::Text(book)).await {
+                tracing::debug!("Admin orderbook stream closed: {e:#}");
+                break;
+            }
+        }
+
+        // Wait for the next change of the book.
+        loop {
+            match orderbook_feed.recv().await {
+                Ok(FeedMessage {
+                    contract_symbol: Some(symbol),
+                    message: Message::NewOrder(_) | Message::DeleteOrder(_),
+                }) if symbol == contract_symbol => break,
+                Ok(_) => continue,
+                // We take a new snapshot anyway, hence nothing is lost.
+                Err(RecvError::Lagged(_)) => break,
+                Err(RecvError::Closed) => {
+                    tracing::error!("Orderbook feed sender died! Channel closed.");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Start migrating the DLC channel of a trader to the current protocol parameters.
+///
+/// The channel is closed and reopened with the same reserves and, if the trader has an open
+/// position, the same position.
+#[instrument(skip_all, err(Debug))]
+pub async fn start_channel_migration(
+    State(state): State<Arc<AppState>>,
+    Path(trader_pubkey): Path<String>,
+) -> Result<Json<db::channel_migrations::ChannelMigration>, AppError> {
+    let trader_pubkey = trader_pubkey
+        .as_str()
+        .parse()
+        .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
+
+    let migration = channel_migration::start(&state.node, &state.trading_sender, trader_pubkey)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Could not start channel migration: {e:#}")))?;
+
+    Ok(Json(migration))
+}
+
+/// All channel migrations, most recent first.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_channel_migrations(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<db::channel_migrations::ChannelMigration>>, AppError> {
+    let migrations = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let migrations = db::channel_migrations::get_all(&mut conn)?;
+
+        anyhow::Ok(migrations)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load channel migrations: {e:#}"))
+    })?;
+
+    Ok(Json(migrations))
+}
+
+/// The progress of the channel migrations of a trader, most recent first.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_trader_channel_migrations(
+    State(state): State<Arc<AppState>>,
+    Path(trader_pubkey): Path<String>,
+) -> Result<Json<Vec<db::channel_migrations::ChannelMigration>>, AppError> {
+    let trader_pubkey: PublicKey = trader_pubkey
+        .as_str()
+        .parse()
+        .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
+
+    let migrations = spawn_blocking(move || {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        let migrations = db::channel_migrations::get_by_trader(&mut conn, &trader_pubkey)?;
+
+        anyhow::Ok(migrations)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Could not load channel migrations: {e:#}"))
+    })?;
+
+    Ok(Json(migrations))
+}
+
+#[derive(Serialize)]
+pub struct LastOutboundDlcMessage {
+    peer: String,
+    message_type: String,
+    reference_id: Option<String>,
+}
+
+/// The last outbound DLC message per peer, which is resent when the peer reconnects.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_last_outbound_dlc_messages(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<LastOutboundDlcMessage>>, AppError> {
+    let messages = spawn_blocking(move || state.node.inner.list_last_outbound_dlc_messages())
+        .await
+        .expect("task to complete")
+        .map_err(|e| {
+            AppError::InternalServerError(format!(
+                "Could not load last outbound DLC messages: {e:#}"
+            ))
+        })?;
+
+    let messages = messages
+        .into_iter()
+        .map(|(peer, msg)| LastOutboundDlcMessage {
+            peer: peer.to_string(),
+            message_type: tentenone_message_name(&msg),
+            reference_id: msg
+                .get_reference_id()
+                .and_then(|reference_id| ProtocolId::try_from(reference_id).ok())
+                .map(|protocol_id| protocol_id.to_string()),
+        })
+        .collect();
+
+    Ok(Json(messages))
+}
+
+pub async fn get_kill_switch(State(state): State<Arc<AppState>>) -> Json<KillSwitchStatus> {
+    Json(state.node.kill_switch.status())
+}
+
+/// Replace the engaged modes of the kill switch and inform all connected traders.
+///
+/// Disengage the kill switch by providing no modes.
+#[instrument(skip_all, err(Debug))]
+pub async fn put_kill_switch(
+    State(state): State<Arc<AppState>>,
+    Json(status): Json<KillSwitchStatus>,
+) -> Result<Json<KillSwitchStatus>, AppError> {
+    spawn_blocking({
+        let kill_switch = state.node.kill_switch.clone();
+        let status = status.clone();
+        move || kill_switch.set(status)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not switch kill switch: {e:#}")))?;
+
+    if let Err(e) = state
+        .tx_orderbook_feed
+        .send(FeedMessage::for_all(Message::KillSwitch(status.clone())))
+    {
+        tracing::warn!("Failed to notify traders about kill switch: {e}");
+    }
+
+    Ok(Json(status))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartQuiesce {
+    /// The end of the maintenance window. Positions expiring before are rolled over early.
+    #[serde(with = "time::serde::rfc3339")]
+    pub until: OffsetDateTime,
+    pub reason: Option<String>,
+}
+
+/// The readiness of every DLC channel for an upgrade.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_quiesce(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<QuiesceReport>, AppError> {
+    let report = spawn_blocking(move || quiesce::report(&state.node))
+        .await
+        .expect("task to complete")
+        .map_err(|e| AppError::InternalServerError(format!("Could not report readiness: {e:#}")))?;
+
+    Ok(Json(report))
+}
+
+/// Quiesce the coordinator ahead of an upgrade and drive all DLC channels to a resting state, see
+/// [`quiesce`].
+///
+/// Calling this again drives the DLC channels again, e.g. once more traders are online.
+#[instrument(skip_all, err(Debug))]
+pub async fn start_quiesce(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<StartQuiesce>,
+) -> Result<Json<QuiesceStatus>, AppError> {
+    let status = QuiesceStatus {
+        engaged: true,
+        until: Some(params.until),
+        reason: params.reason,
+    };
+
+    set_quiesce(&state, status.clone()).await?;
+
+    tokio::spawn({
+        let node = state.node.clone();
+        async move {
+            if let Err(e) = quiesce::drive(node).await {
+                tracing::error!("Failed to drive DLC channels to a resting state: {e:#}");
+            }
+        }
+    });
+
+    Ok(Json(status))
+}
+
+/// Allow new DLC protocols again, once the upgrade is done.
+#[instrument(skip_all, err(Debug))]
+pub async fn release_quiesce(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<QuiesceStatus>, AppError> {
+    let status = QuiesceStatus::default();
+
+    set_quiesce(&state, status.clone()).await?;
+
+    Ok(Json(status))
+}
+
+async fn set_quiesce(state: &AppState, status: QuiesceStatus) -> Result<(), AppError> {
+    spawn_blocking({
+        let quiesce = state.node.quiesce.clone();
+        move || quiesce.set(status)
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not change quiesce: {e:#}")))
+}
+
+pub async fn get_restrictions(State(state): State<Arc<AppState>>) -> Json<Vec<Restriction>> {
+    Json(state.node.moderation.restrictions())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestrictTrader {
+    reason: String,
+    /// How long the trader is suspended. Without a duration, the trader is banned.
+    duration_hours: Option<u32>,
+}
+
+/// Suspend or ban the trader, replacing any previous restriction.
+#[instrument(skip_all, err(Debug))]
+pub async fn restrict_trader(
+    State(state): State<Arc<AppState>>,
+    Path(trader_pubkey): Path<String>,
+    Json(restrict): Json<RestrictTrader>,
+) -> Result<Json<Restriction>, AppError> {
+    let trader_pubkey: PublicKey = trader_pubkey
+        .as_str()
+        .parse()
+        .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
+
+    let restriction = spawn_blocking(move || {
+        state.node.moderation.restrict(
+            trader_pubkey,
+            restrict.reason,
+            restrict
+                .duration_hours
+                .map(|hours| Duration::hours(i64::from(hours))),
+            false,
+        )
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Could not restrict trader: {e:#}")))?;
+
+    Ok(Json(restriction))
+}
+
+#[instrument(skip_all, err(Debug))]
+pub async fn lift_restriction(
+    State(state): State<Arc<AppState>>,
+    Path(trader_pubkey): Path<String>,
+) -> Result<(), AppError> {
+    let trader_pubkey: PublicKey = trader_pubkey
+        .as_str()
+        .parse()
+        .map_err(|_| AppError::BadRequest("Invalid trader id provided".to_string()))?;
+
+    spawn_blocking(move || state.node.moderation.lift(trader_pubkey))
+        .await
+        .expect("task to complete")
+        .map_err(|e| AppError::InternalServerError(format!("Could not lift restriction: {e:#}")))?;
+
+    Ok(())
+}
+
+/// Middleware rejecting requests without the admin token in the `Authorization` header.
+pub async fn require_admin_token<B>(
+    State(state): State<Arc<AppState>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(admin_token) = &state.admin_token else {
+        tracing::warn!("Rejecting admin request, as no admin token is configured");
+        return AppError::Unauthorized.into_response();
+    };
+
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "));
+
+    // Comparing the hashes does not leak how much of the token was guessed correctly.
+    let is_valid = token.is_some_and(|token| {
+        sha256::Hash::hash(token.as_bytes()) == sha256::Hash::hash(admin_token.as_bytes())
+    });
+    if !is_valid {
+        return AppError::Unauthorized.into_response();
+    }
+
+    next.run(request).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LatencyParams {
+    limit: Option<usize>,
+}
+
+/// The endpoints which spent their latency and error budget the fastest over the last hour, worst
+/// first.
+pub async fn get_latency(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LatencyParams>,
+) -> Json<Vec<EndpointSummary>> {
+    let limit = params.limit.unwrap_or(10);
+
+    Json(
+        state
+            .latency
+            .worst_endpoints(60, limit, OffsetDateTime::now_utc()),
+    )
+}
+
+pub async fn get_jobs(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<JobStatus>>, AppError> {
+    let jobs = spawn_blocking(move || state.scheduler.jobs())
+        .await
+        .expect("task to complete")
+        .map_err(|e| AppError::InternalServerError(format!("Could not load jobs: {e:#}")))?;
+
+    Ok(Json(jobs))
+}
+
+pub async fn get_job_runs(
+    State(state): State<Arc<AppState>>,
+    Path(job_name): Path<String>,
+) -> Result<Json<Vec<JobRun>>, AppError> {
+    let runs = spawn_blocking(move || state.scheduler.runs(&job_name, 100))
+        .await
+        .expect("task to complete")
+        .map_err(|e| AppError::BadRequest(format!("Could not load job runs: {e:#}")))?;
+
+    Ok(Json(runs))
+}
+
+/// Run the job right away, regardless of its schedule.
+#[instrument(skip_all, err(Debug))]
+pub async fn trigger_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_name): Path<String>,
+) -> Result<(), AppError> {
+    state
+        .scheduler
+        .trigger(&job_name)
+        .map_err(|e| AppError::BadRequest(format!("Could not trigger job: {e:#}")))?;
+
+    tracing::info!(job_name, "Triggered job");
+
+    Ok(())
+}
+
+/// Stop running the job on its schedule, until it is resumed or the coordinator restarts.
+#[instrument(skip_all, err(Debug))]
+pub async fn pause_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_name): Path<String>,
+) -> Result<(), AppError> {
+    state
+        .scheduler
+        .set_paused(&job_name, true)
+        .map_err(|e| AppError::BadRequest(format!("Could not pause job: {e:#}")))
+}
+
+#[instrument(skip_all, err(Debug))]
+pub async fn resume_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_name): Path<String>,
+) -> Result<(), AppError> {
+    state
+        .scheduler
+        .set_paused(&job_name, false)
+        .map_err(|e| AppError::BadRequest(format!("Could not resume job: {e:#}")))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FundingRates(Vec<FundingRate>);
 
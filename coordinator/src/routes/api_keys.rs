@@ -0,0 +1,596 @@
+use crate::api_key;
+use crate::api_key::Permission;
+use crate::db;
+use crate::db::api_keys::ApiKey;
+use crate::maker_earnings;
+use crate::maker_earnings::EarningsRange;
+use crate::maker_earnings::EarningsSummary;
+use crate::maker_earnings::HedgingCosts;
+use crate::orderbook;
+use crate::orderbook::order_simulation;
+use crate::orderbook::trading::OrderbookCommand;
+use crate::routes::orderbook::place_order;
+use crate::routes::pagination::Paginated;
+use crate::routes::AppState;
+use crate::settlement_report;
+use crate::settlement_report::SettlementReport;
+use crate::settlement_report::SettlementReportLink;
+use crate::AppError;
+use anyhow::Context;
+use axum::body::Bytes;
+use axum::extract::OriginalUri;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::Method;
+use axum::http::StatusCode;
+use axum::Json;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::sync::oneshot;
+use tokio::task::spawn_blocking;
+use tracing::instrument;
+use uuid::Uuid;
+use xxi_node::commons::api_request_message;
+use xxi_node::commons::CreateApiKeyParams;
+use xxi_node::commons::CreatedApiKey;
+use xxi_node::commons::NewOrder;
+use xxi_node::commons::Order;
+use xxi_node::commons::OrderFilter;
+use xxi_node::commons::OrderSimulation;
+use xxi_node::commons::PageParams;
+use xxi_node::commons::RevokeApiKeyParams;
+use xxi_node::commons::SignedValue;
+use xxi_node::commons::SimulateOrderParams;
+use xxi_node::commons::API_KEY_HEADER;
+use xxi_node::commons::API_NONCE_HEADER;
+use xxi_node::commons::API_SIGNATURE_HEADER;
+use xxi_node::commons::API_TIMESTAMP_HEADER;
+
+#[instrument(skip_all, err(Debug))]
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<SignedValue<CreateApiKeyParams>>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), AppError> {
+    let trader_pubkey = params.value.trader_pubkey;
+
+    params
+        .verify(&state.secp, &trader_pubkey)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    // The signature of a request signed with the node key serves as its nonce.
+    let nonce = hex::encode(params.signature.serialize_compact());
+
+    let params = params.value;
+    api_key::ensure_recent(
+        params.timestamp,
+        api_key::MAX_KEY_REQUEST_AGE,
+        OffsetDateTime::now_utc(),
+    )
+    .map_err(|e| AppError::BadRequest(format!("{e:#}")))?;
+
+    let key_id = Uuid::new_v4();
+    let secret = api_key::generate_secret();
+    let encrypted_secret = state
+        .api_key_secrets
+        .encrypt(&secret)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to encrypt secret: {e:#}")))?;
+
+    spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+
+            db::user::get_user(&mut conn, &trader_pubkey)?
+                .with_context(|| format!("Unknown trader {trader_pubkey}"))?;
+
+            api_key::ensure_unused_nonce(&mut conn, &trader_pubkey.to_string(), &nonce)?;
+
+            db::api_keys::insert(
+                &mut conn,
+                key_id,
+                trader_pubkey,
+                encrypted_secret,
+                params.label,
+                params.permissions,
+            )?;
+
+            anyhow::Ok(())
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::BadRequest(format!("Failed to create API key: {e:#}")))?;
+
+    tracing::info!(%trader_pubkey, %key_id, permissions = ?params.permissions, "Created API key");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKey {
+            key_id,
+            secret,
+            permissions: params.permissions,
+        }),
+    ))
+}
+
+#[instrument(skip_all, err(Debug))]
+pub async fn revoke_api_key(
+    Path(key_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<SignedValue<RevokeApiKeyParams>>,
+) -> Result<(), AppError> {
+    let trader_pubkey = params.value.trader_pubkey;
+
+    params
+        .verify(&state.secp, &trader_pubkey)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    if params.value.key_id != key_id {
+        return Err(AppError::BadRequest(
+            "Signed key id does not match the requested key id".to_string(),
+        ));
+    }
+
+    api_key::ensure_recent(
+        params.value.timestamp,
+        api_key::MAX_KEY_REQUEST_AGE,
+        OffsetDateTime::now_utc(),
+    )
+    .map_err(|e| AppError::BadRequest(format!("{e:#}")))?;
+
+    let nonce = hex::encode(params.signature.serialize_compact());
+
+    let revoked = spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+
+            api_key::ensure_unused_nonce(&mut conn, &trader_pubkey.to_string(), &nonce)?;
+
+            let revoked = db::api_keys::revoke(&mut conn, key_id, trader_pubkey)?;
+
+            anyhow::Ok(revoked)
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::BadRequest(format!("Failed to revoke API key: {e:#}")))?;
+
+    if !revoked {
+        return Err(AppError::BadRequest(format!("Unknown API key {key_id}")));
+    }
+
+    tracing::info!(%trader_pubkey, %key_id, "Revoked API key");
+
+    Ok(())
+}
+
+/// A page of the open orders of the trader owning the API key, newest first by default.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_trader_orders(
+    State(state): State<Arc<AppState>>,
+    Query(page): Query<PageParams<Uuid>>,
+    Query(filter): Query<OrderFilter>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Paginated<Order, Uuid>, AppError> {
+    let api_key = authenticate_request(
+        &state,
+        &method,
+        uri.path(),
+        &headers,
+        &body,
+        Permission::Read,
+    )
+    .await?;
+
+    let orders = spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            let orders = orderbook::db::orders::get_open_orders_page_by_trader_id(
+                &mut conn,
+                api_key.trader_pubkey,
+                filter,
+                &page,
+            )?;
+
+            anyhow::Ok(orders)
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Failed to load orders: {e:#}")))?;
+
+    Ok(Paginated(orders))
+}
+
+/// Place an order on behalf of the trader owning the API key.
+///
+/// The trade is executed in the trader's existing DLC channel, hence orders cannot be placed with
+/// an API key before the trader has opened a channel with the app.
+#[instrument(skip_all, err(Debug))]
+pub async fn post_trader_order(
+    State(state): State<Arc<AppState>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Order>), AppError> {
+    let api_key = authenticate_request(
+        &state,
+        &method,
+        uri.path(),
+        &headers,
+        &body,
+        Permission::Trade,
+    )
+    .await?;
+
+    let new_order: NewOrder = serde_json::from_slice(&body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid order: {e:#}")))?;
+
+    if new_order.trader_id() != api_key.trader_pubkey {
+        tracing::warn!(
+            key_id = %api_key.id,
+            trader_id = %new_order.trader_id(),
+            "API key used to place an order on behalf of another trader"
+        );
+        return Err(AppError::Unauthorized);
+    }
+
+    if let NewOrder::Market(_) = new_order {
+        state
+            .node
+            .inner
+            .get_signed_channel_by_trader_id(api_key.trader_pubkey)
+            .map_err(|_| {
+                AppError::BadRequest(
+                    "Orders placed with an API key require an open DLC channel".to_string(),
+                )
+            })?;
+    }
+
+    // The client is not known for orders placed with an API key.
+    let order = place_order(&state, new_order, None, None).await?;
+
+    Ok((StatusCode::CREATED, Json(order)))
+}
+
+/// Preview how a market order of the trader owning the API key would be matched with the
+/// current book, without placing it.
+#[instrument(skip_all, err(Debug))]
+pub async fn post_simulate_order(
+    State(state): State<Arc<AppState>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<OrderSimulation>, AppError> {
+    let api_key = authenticate_request(
+        &state,
+        &method,
+        uri.path(),
+        &headers,
+        &body,
+        Permission::Read,
+    )
+    .await?;
+
+    let params: SimulateOrderParams = serde_json::from_slice(&body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid order: {e:#}")))?;
+
+    if params.quantity <= Decimal::ZERO {
+        return Err(AppError::BadRequest(
+            "Quantity must be positive".to_string(),
+        ));
+    }
+
+    let now = OffsetDateTime::now_utc();
+    if !state
+        .simulation_rate_limiter
+        .try_acquire(api_key.trader_pubkey, now)
+    {
+        return Err(AppError::TooManyRequests(
+            "Too many order simulations, try again later".to_string(),
+        ));
+    }
+
+    let order = order_simulation::hypothetical_order(api_key.trader_pubkey, &params, now);
+    let matched_orders = orderbook::trading::simulate_order(&state.trading_sender, order.clone())
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Failed to match order: {e:#}")))?;
+
+    Ok(Json(order_simulation::summarize(
+        &order,
+        matched_orders.as_ref(),
+    )))
+}
+
+/// Delete an order of the trader owning the API key.
+#[instrument(skip_all, err(Debug))]
+pub async fn delete_trader_order(
+    Path(order_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Order>, AppError> {
+    let api_key = authenticate_request(
+        &state,
+        &method,
+        uri.path(),
+        &headers,
+        &body,
+        Permission::Trade,
+    )
+    .await?;
+
+    let (response, order) = oneshot::channel();
+    state
+        .trading_sender
+        .send(OrderbookCommand::DeleteOrder {
+            order_id,
+            trader_id: Some(api_key.trader_pubkey),
+            response,
+        })
+        .await
+        .map_err(|e| {
+            AppError::InternalServerError(format!("Failed to send delete order message: {e:#}"))
+        })?;
+
+    let order = order
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to delete order: {e:#}")))?
+        .map_err(|e| AppError::BadRequest(format!("Failed to delete order: {e:#}")))?;
+
+    Ok(Json(order))
+}
+
+/// How many of the most recent settlement reports are listed.
+const MAX_LISTED_SETTLEMENT_REPORTS: i64 = 31;
+
+/// The daily settlement reports of the maker owning the API key, newest first.
+///
+/// Each report is listed with a signed URL, which allows to download the report without the API
+/// key for a limited time, e.g. from an accounting tool.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_settlement_reports(
+    State(state): State<Arc<AppState>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Vec<SettlementReportLink>>, AppError> {
+    let api_key = authenticate_request(
+        &state,
+        &method,
+        uri.path(),
+        &headers,
+        &body,
+        Permission::Read,
+    )
+    .await?;
+
+    let reports = spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            let reports = settlement_report::get_reports_of_maker(
+                &mut conn,
+                api_key.trader_pubkey,
+                MAX_LISTED_SETTLEMENT_REPORTS,
+            )?;
+
+            anyhow::Ok(reports)
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Failed to load settlement reports: {e:#}"))
+    })?;
+
+    let now = OffsetDateTime::now_utc();
+    let links = reports
+        .into_iter()
+        .map(|(id, date)| state.report_urls.link(id, date, now))
+        .collect();
+
+    Ok(Json(links))
+}
+
+#[derive(Deserialize)]
+pub struct SignedUrlParams {
+    expires: i64,
+    signature: String,
+}
+
+/// Download a settlement report through a signed URL, see [`get_settlement_reports`].
+#[instrument(skip_all, err(Debug))]
+pub async fn get_settlement_report(
+    Path(report_id): Path<i32>,
+    Query(params): Query<SignedUrlParams>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SettlementReport>, AppError> {
+    state
+        .report_urls
+        .verify(
+            report_id,
+            params.expires,
+            &params.signature,
+            OffsetDateTime::now_utc(),
+        )
+        .map_err(|e| {
+            tracing::debug!(report_id, "Rejected settlement report URL: {e:#}");
+            AppError::Unauthorized
+        })?;
+
+    let report = spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            settlement_report::get_report(&mut conn, report_id)
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Failed to load settlement report: {e:#}")))?
+    .ok_or_else(|| AppError::BadRequest(format!("Unknown settlement report {report_id}")))?;
+
+    Ok(Json(report))
+}
+
+/// The earnings of the maker owning the API key per day and contract, see
+/// [`crate::maker_earnings`].
+#[instrument(skip_all, err(Debug))]
+pub async fn get_maker_earnings(
+    Query(range): Query<EarningsRange>,
+    State(state): State<Arc<AppState>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<EarningsSummary>, AppError> {
+    let api_key = authenticate_request(
+        &state,
+        &method,
+        uri.path(),
+        &headers,
+        &body,
+        Permission::Read,
+    )
+    .await?;
+
+    if range.from > range.to {
+        return Err(AppError::BadRequest(format!(
+            "Invalid range from {} to {}",
+            range.from, range.to
+        )));
+    }
+
+    let earnings = spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            let earnings = maker_earnings::get_earnings_of_maker(
+                &mut conn,
+                api_key.trader_pubkey,
+                range.from,
+                range.to,
+            )?;
+
+            anyhow::Ok(earnings)
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Failed to load earnings: {e:#}")))?;
+
+    Ok(Json(EarningsSummary::new(earnings)))
+}
+
+/// Report the hedging costs of the maker owning the API key for one day and contract.
+///
+/// Reporting the costs again replaces the previously reported costs.
+#[instrument(skip_all, err(Debug))]
+pub async fn put_hedging_costs(
+    State(state): State<Arc<AppState>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(), AppError> {
+    let api_key = authenticate_request(
+        &state,
+        &method,
+        uri.path(),
+        &headers,
+        &body,
+        Permission::Trade,
+    )
+    .await?;
+
+    let costs: HedgingCosts = serde_json::from_slice(&body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid hedging costs: {e:#}")))?;
+
+    spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            maker_earnings::set_hedging_costs(
+                &mut conn,
+                api_key.trader_pubkey,
+                costs.date,
+                costs.contract_symbol,
+                costs.hedging_costs,
+            )
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::InternalServerError(format!("Failed to store hedging costs: {e:#}")))?;
+
+    Ok(())
+}
+
+/// Authenticate a REST request signed with an API key, see
+/// [`xxi_node::commons::api_request_message`].
+async fn authenticate_request(
+    state: &Arc<AppState>,
+    method: &Method,
+    path: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    permission: Permission,
+) -> Result<ApiKey, AppError> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+            .ok_or(AppError::Unauthorized)
+    };
+
+    let key_id = header(API_KEY_HEADER)?
+        .parse::<Uuid>()
+        .map_err(|_| AppError::Unauthorized)?;
+    let timestamp = header(API_TIMESTAMP_HEADER)?
+        .parse::<i64>()
+        .map_err(|_| AppError::Unauthorized)?;
+    let nonce = header(API_NONCE_HEADER)?;
+    let signature = header(API_SIGNATURE_HEADER)?;
+
+    let message = api_request_message(timestamp, &nonce, method.as_str(), path, body);
+
+    let api_key = spawn_blocking({
+        let pool = state.pool.clone();
+        let secrets = state.api_key_secrets.clone();
+        move || {
+            let mut conn = pool.get()?;
+            api_key::authenticate(
+                &mut conn, &secrets, key_id, timestamp, &nonce, &message, &signature,
+            )
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| {
+        tracing::warn!(%key_id, "Failed to authenticate API request: {e:#}");
+        AppError::Unauthorized
+    })?;
+
+    if !api_key.allows(permission) {
+        tracing::warn!(%key_id, ?permission, "API key lacks permission");
+        return Err(AppError::Unauthorized);
+    }
+
+    Ok(api_key)
+}
@@ -0,0 +1,112 @@
+use crate::db;
+use crate::funding_fee::get_funding_fee_events_for_active_trader_positions;
+use crate::funding_fee::get_next_funding_rate;
+use crate::orderbook::db::orders;
+use crate::orderbook::websocket::tentenone_config;
+use crate::reserve_interest;
+use crate::routes::session::AuthenticatedTrader;
+use crate::routes::AppState;
+use crate::AppError;
+use anyhow::Context;
+use anyhow::Result;
+use axum::extract::State;
+use axum::Extension;
+use axum::Json;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use std::sync::Arc;
+use tokio::task::spawn_blocking;
+use tracing::instrument;
+use xxi_node::commons;
+use xxi_node::commons::Bootstrap;
+use xxi_node::commons::Order;
+
+/// Everything the app needs on startup, so that it does not have to make a request for each.
+///
+/// The parts are loaded concurrently, each with its own database connection.
+#[instrument(skip_all, err(Debug))]
+pub async fn get_bootstrap(
+    State(state): State<Arc<AppState>>,
+    Extension(AuthenticatedTrader(trader_pubkey)): Extension<AuthenticatedTrader>,
+) -> Result<Json<Bootstrap>, AppError> {
+    let apr = state.node.settings.read().await.reserve_interest_apr;
+
+    let config = async {
+        let mut conn = state.pool.get().context("Could not get connection")?;
+        anyhow::Ok(tentenone_config(&state, &mut conn, trader_pubkey).await)
+    };
+
+    let orders = load(&state.pool, |conn| {
+        let orders = orders::all_limit_orders(conn)?;
+        anyhow::Ok(orders.iter().map(Order::displayed).collect::<Vec<_>>())
+    });
+
+    let funding_fee_events = load(&state.pool, move |conn| {
+        let events = get_funding_fee_events_for_active_trader_positions(conn, trader_pubkey)?;
+        anyhow::Ok(events)
+    });
+
+    let next_funding_rate = load(&state.pool, |conn| {
+        let funding_rate = get_next_funding_rate(conn)?;
+        anyhow::Ok(funding_rate)
+    });
+
+    let user = load(&state.pool, move |conn| {
+        let user = db::user::get_user(conn, &trader_pubkey)?;
+        anyhow::Ok(user)
+    });
+
+    let reserve_interest = load(&state.pool, move |conn| {
+        reserve_interest::get_reserve_interest(conn, trader_pubkey, apr)
+    });
+
+    let polls = load(&state.pool, move |conn| {
+        let polls = db::polls::active(conn, &trader_pubkey)?;
+        anyhow::Ok(polls)
+    });
+
+    let (config, orders, funding_fee_events, next_funding_rate, user, reserve_interest, polls) = tokio::join!(
+        config,
+        orders,
+        funding_fee_events,
+        next_funding_rate,
+        user,
+        reserve_interest,
+        polls
+    );
+
+    let internal = |e: anyhow::Error| {
+        AppError::InternalServerError(format!("Could not load bootstrap: {e:#}"))
+    };
+
+    let user = user
+        .map_err(internal)?
+        .map(commons::User::try_from)
+        .transpose()?;
+
+    Ok(Json(Bootstrap {
+        config: config.map_err(internal)?,
+        orders: orders.map_err(internal)?,
+        funding_fee_events: funding_fee_events.map_err(internal)?,
+        next_funding_rate: next_funding_rate.map_err(internal)?,
+        user,
+        reserve_interest: reserve_interest.map_err(internal)?,
+        polls: polls.map_err(internal)?,
+    }))
+}
+
+async fn load<T, F>(pool: &Pool<ConnectionManager<PgConnection>>, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut PgConnection) -> Result<T> + Send + 'static,
+{
+    let pool = pool.clone();
+
+    spawn_blocking(move || {
+        let mut conn = pool.get().context("Could not get connection")?;
+        f(&mut conn)
+    })
+    .await
+    .expect("task to complete")
+}
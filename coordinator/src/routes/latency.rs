@@ -0,0 +1,432 @@
+//! Latency and error rate of every endpoint, measured against service level objectives (SLOs).
+//!
+//! A request is bad if it fails with a server error or takes longer than the latency target of its
+//! endpoint. The error budget of an endpoint is the fraction of bad requests the
+//! [`LatencySloSettings::objective`] allows for. The burn rate is the fraction of bad requests
+//! relative to that budget: a burn rate of 1 spends the budget exactly, a burn rate of 14.4 over an
+//! hour spends the budget of a month within two days.
+//!
+//! The burn rates over the last 5 minutes and the last hour are exported as the
+//! `coordinator_http_slo_burn_rate` metric, so that alerts can be defined on them.
+
+use axum::extract::MatchedPath;
+use axum::extract::State;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use parking_lot::RwLock;
+use prometheus::exponential_buckets;
+use prometheus::register_gauge_vec;
+use prometheus::register_histogram_vec;
+use prometheus::register_int_counter_vec;
+use prometheus::GaugeVec;
+use prometheus::HistogramVec;
+use prometheus::IntCounterVec;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use time::OffsetDateTime;
+
+lazy_static! {
+    static ref REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "coordinator_http_request_duration_seconds",
+        "Duration of the requests to the coordinator, by endpoint.",
+        &["endpoint"],
+        exponential_buckets(0.005, 2.0, 14).expect("valid buckets")
+    )
+    .expect("valid metric");
+    static ref REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "coordinator_http_requests_total",
+        "Number of requests to the coordinator, by endpoint and outcome (ok, slow or error).",
+        &["endpoint", "outcome"]
+    )
+    .expect("valid metric");
+    static ref BURN_RATE: GaugeVec = register_gauge_vec!(
+        "coordinator_http_slo_burn_rate",
+        "Rate at which an endpoint spends its error budget over the window. 1 spends the budget \
+         exactly.",
+        &["endpoint", "window"]
+    )
+    .expect("valid metric");
+}
+
+/// The upper bounds of the latency buckets kept per minute, to estimate percentiles.
+const LATENCY_BUCKETS_MS: [u64; 12] = [
+    5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+/// How many minutes of statistics are kept per endpoint.
+const WINDOW_MINUTES: i64 = 60;
+
+/// The windows over which the burn rate is exported.
+const BURN_RATE_WINDOWS: [(&str, i64); 2] = [("5m", 5), ("1h", 60)];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencySloSettings {
+    /// The fraction of requests which have to succeed within the latency target of their
+    /// endpoint, e.g. 0.99.
+    pub objective: f64,
+    /// The latency target of endpoints without a specific one.
+    pub default_target_ms: u64,
+    /// The latency targets of specific endpoints.
+    pub targets: Vec<LatencyTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyTarget {
+    /// The method and route of the endpoint, e.g. `POST /api/v2/orderbook/orders`.
+    pub endpoint: String,
+    pub target_ms: u64,
+}
+
+/// How an endpoint performed over a window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EndpointSummary {
+    pub endpoint: String,
+    pub requests: u64,
+    /// Requests which failed with a server error.
+    pub errors: u64,
+    /// Requests which succeeded, but took longer than the latency target.
+    pub slow: u64,
+    pub target_ms: u64,
+    /// Upper bound of the median latency.
+    pub p50_ms: u64,
+    /// Upper bound of the 99th percentile latency.
+    pub p99_ms: u64,
+    pub burn_rate: f64,
+}
+
+/// Keeps the statistics of the last hour per endpoint.
+#[derive(Clone)]
+pub struct LatencyTracker {
+    settings: Arc<RwLock<LatencySloSettings>>,
+    endpoints: Arc<Mutex<HashMap<String, VecDeque<MinuteStats>>>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MinuteStats {
+    /// Minutes since the unix epoch.
+    minute: i64,
+    requests: u64,
+    errors: u64,
+    slow: u64,
+    /// The number of requests per latency bucket, the last one catching everything above
+    /// [`LATENCY_BUCKETS_MS`].
+    latencies: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+/// Middleware recording the latency and outcome of every request to a known route.
+pub async fn track_latency<B>(
+    State(tracker): State<LatencyTracker>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    // Requests to unknown routes are not tracked, so that they cannot inflate the number of
+    // endpoints.
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| format!("{} {}", request.method(), path.as_str()));
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    if let Some(endpoint) = endpoint {
+        tracker.record(
+            &endpoint,
+            start.elapsed(),
+            response.status().is_server_error(),
+            OffsetDateTime::now_utc(),
+        );
+    }
+
+    response
+}
+
+impl LatencySloSettings {
+    pub fn target(&self, endpoint: &str) -> Duration {
+        let target_ms = self
+            .targets
+            .iter()
+            .find(|target| target.endpoint == endpoint)
+            .map(|target| target.target_ms)
+            .unwrap_or(self.default_target_ms);
+
+        Duration::from_millis(target_ms)
+    }
+
+    /// The burn rate of a window with `bad` out of `requests` requests.
+    fn burn_rate(&self, requests: u64, bad: u64) -> f64 {
+        if requests == 0 {
+            return 0.0;
+        }
+
+        let error_budget = (1.0 - self.objective).max(f64::EPSILON);
+
+        (bad as f64 / requests as f64) / error_budget
+    }
+}
+
+impl LatencyTracker {
+    pub fn new(settings: LatencySloSettings) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            endpoints: Default::default(),
+        }
+    }
+
+    pub fn update_settings(&self, settings: LatencySloSettings) {
+        *self.settings.write() = settings;
+    }
+
+    pub fn record(&self, endpoint: &str, latency: Duration, error: bool, now: OffsetDateTime) {
+        let target = self.settings.read().target(endpoint);
+        let slow = !error && latency > target;
+
+        let outcome = if error {
+            "error"
+        } else if slow {
+            "slow"
+        } else {
+            "ok"
+        };
+        REQUEST_DURATION
+            .with_label_values(&[endpoint])
+            .observe(latency.as_secs_f64());
+        REQUESTS.with_label_values(&[endpoint, outcome]).inc();
+
+        let minute = now.unix_timestamp().div_euclid(60);
+
+        let mut endpoints = self.endpoints.lock();
+        let minutes = endpoints.entry(endpoint.to_string()).or_default();
+
+        if minutes.back().map(|stats| stats.minute) != Some(minute) {
+            minutes.push_back(MinuteStats {
+                minute,
+                ..MinuteStats::default()
+            });
+        }
+        while minutes
+            .front()
+            .is_some_and(|stats| stats.minute <= minute - WINDOW_MINUTES)
+        {
+            minutes.pop_front();
+        }
+
+        let stats = minutes.back_mut().expect("current minute");
+        stats.requests += 1;
+        stats.errors += u64::from(error);
+        stats.slow += u64::from(slow);
+
+        let latency_ms = latency.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|upper_bound| latency_ms <= *upper_bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        stats.latencies[bucket] += 1;
+    }
+
+    /// The endpoints which spent their error budget the fastest over the last `window_minutes`,
+    /// worst first.
+    pub fn worst_endpoints(
+        &self,
+        window_minutes: i64,
+        limit: usize,
+        now: OffsetDateTime,
+    ) -> Vec<EndpointSummary> {
+        let settings = self.settings.read().clone();
+        let since = now.unix_timestamp().div_euclid(60) - window_minutes.min(WINDOW_MINUTES);
+
+        let endpoints = self.endpoints.lock();
+        let mut summaries = endpoints
+            .iter()
+            .map(|(endpoint, minutes)| {
+                let mut total = MinuteStats::default();
+                for stats in minutes.iter().filter(|stats| stats.minute > since) {
+                    total.requests += stats.requests;
+                    total.errors += stats.errors;
+                    total.slow += stats.slow;
+                    for (bucket, count) in stats.latencies.iter().enumerate() {
+                        total.latencies[bucket] += count;
+                    }
+                }
+
+                EndpointSummary {
+                    endpoint: endpoint.clone(),
+                    requests: total.requests,
+                    errors: total.errors,
+                    slow: total.slow,
+                    target_ms: settings.target(endpoint).as_millis() as u64,
+                    p50_ms: total.percentile(0.5),
+                    p99_ms: total.percentile(0.99),
+                    burn_rate: settings.burn_rate(total.requests, total.errors + total.slow),
+                }
+            })
+            .filter(|summary| summary.requests > 0)
+            .collect::<Vec<_>>();
+
+        summaries.sort_by(|a, b| {
+            b.burn_rate
+                .total_cmp(&a.burn_rate)
+                .then_with(|| b.p99_ms.cmp(&a.p99_ms))
+        });
+        summaries.truncate(limit);
+
+        summaries
+    }
+
+    /// Update the `coordinator_http_slo_burn_rate` metric of all endpoints.
+    ///
+    /// Called before the metrics are scraped, so that the burn rate of endpoints which are not
+    /// requested anymore decays.
+    pub fn update_burn_rates(&self, now: OffsetDateTime) {
+        let endpoints = self.endpoints.lock().keys().cloned().collect::<Vec<_>>();
+
+        for (window, minutes) in BURN_RATE_WINDOWS {
+            let burn_rates = self
+                .worst_endpoints(minutes, usize::MAX, now)
+                .into_iter()
+                .map(|summary| (summary.endpoint, summary.burn_rate))
+                .collect::<HashMap<_, _>>();
+
+            // Endpoints without requests in the window do not burn any budget.
+            for endpoint in endpoints.iter() {
+                let burn_rate = burn_rates.get(endpoint).copied().unwrap_or_default();
+                BURN_RATE
+                    .with_label_values(&[endpoint, window])
+                    .set(burn_rate);
+            }
+        }
+    }
+}
+
+impl MinuteStats {
+    /// The upper bound of the latency bucket containing the given percentile.
+    fn percentile(&self, percentile: f64) -> u64 {
+        let rank = (self.requests as f64 * percentile).ceil() as u64;
+
+        let mut seen = 0;
+        for (bucket, count) in self.latencies.iter().enumerate() {
+            seen += count;
+            if seen >= rank.max(1) {
+                return LATENCY_BUCKETS_MS.get(bucket).copied().unwrap_or(u64::MAX);
+            }
+        }
+
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn tracker() -> LatencyTracker {
+        LatencyTracker::new(LatencySloSettings {
+            objective: 0.99,
+            default_target_ms: 1_000,
+            targets: vec![LatencyTarget {
+                endpoint: "POST /api/v2/orderbook/orders".to_string(),
+                target_ms: 100,
+            }],
+        })
+    }
+
+    #[test]
+    fn slow_and_failed_requests_burn_the_error_budget() {
+        let tracker = tracker();
+        let now = datetime!(2024-07-20 12:00 UTC);
+
+        for _ in 0..97 {
+            tracker.record(
+                "POST /api/v2/orderbook/orders",
+                Duration::from_millis(20),
+                false,
+                now,
+            );
+        }
+        tracker.record(
+            "POST /api/v2/orderbook/orders",
+            Duration::from_millis(200),
+            false,
+            now,
+        );
+        tracker.record(
+            "POST /api/v2/orderbook/orders",
+            Duration::from_millis(20),
+            true,
+            now,
+        );
+        // Slow and failed at once only counts as failed.
+        tracker.record(
+            "POST /api/v2/orderbook/orders",
+            Duration::from_millis(200),
+            true,
+            now,
+        );
+
+        let summaries = tracker.worst_endpoints(60, 10, now);
+
+        assert_eq!(summaries.len(), 1);
+        let summary = &summaries[0];
+        assert_eq!(summary.requests, 100);
+        assert_eq!(summary.errors, 2);
+        assert_eq!(summary.slow, 1);
+        assert_eq!(summary.target_ms, 100);
+        assert_eq!(summary.p50_ms, 25);
+        assert_eq!(summary.p99_ms, 250);
+        assert!((summary.burn_rate - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn worst_endpoints_come_first() {
+        let tracker = tracker();
+        let now = datetime!(2024-07-20 12:00 UTC);
+
+        tracker.record("GET /api/v2/version", Duration::from_millis(5), false, now);
+        tracker.record(
+            "GET /api/v2/orderbook/orders",
+            Duration::from_millis(5),
+            true,
+            now,
+        );
+
+        let endpoints = tracker
+            .worst_endpoints(60, 1, now)
+            .into_iter()
+            .map(|summary| summary.endpoint)
+            .collect::<Vec<_>>();
+
+        assert_eq!(endpoints, vec!["GET /api/v2/orderbook/orders"]);
+    }
+
+    #[test]
+    fn requests_older_than_the_window_are_forgotten() {
+        let tracker = tracker();
+
+        tracker.record(
+            "GET /api/v2/version",
+            Duration::from_millis(5),
+            true,
+            datetime!(2024-07-20 11:00 UTC),
+        );
+        tracker.record(
+            "GET /api/v2/version",
+            Duration::from_millis(5),
+            false,
+            datetime!(2024-07-20 12:00 UTC),
+        );
+
+        let summaries = tracker.worst_endpoints(60, 10, datetime!(2024-07-20 12:00 UTC));
+
+        assert_eq!(summaries[0].requests, 1);
+        assert_eq!(summaries[0].burn_rate, 0.0);
+    }
+}
@@ -1,36 +1,47 @@
 use crate::check_version::check_version;
 use crate::db;
+use crate::node::invoice::EXTERNAL_FUNDING_RESERVATION_TTL;
 use crate::orderbook;
+use crate::orderbook::contract_expiry;
 use crate::orderbook::db::orders;
+use crate::orderbook::order_flow;
 use crate::orderbook::trading::NewOrderMessage;
+use crate::orderbook::trading::OrderbookCommand;
 use crate::orderbook::websocket::websocket_connection;
+use crate::routes::pagination::Paginated;
+use crate::routes::session::AuthenticatedTrader;
 use crate::routes::AppState;
+use crate::trade;
 use crate::AppError;
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
 use axum::extract::ws::WebSocketUpgrade;
 use axum::extract::Path;
+use axum::extract::Query;
 use axum::extract::State;
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use axum::Extension;
 use axum::Json;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::PooledConnection;
 use diesel::PgConnection;
 use rust_decimal::Decimal;
 use std::sync::Arc;
-use tokio::sync::broadcast::Sender;
+use time::OffsetDateTime;
+use tokio::sync::oneshot;
 use tokio::task::spawn_blocking;
 use tracing::instrument;
 use uuid::Uuid;
 use xxi_node::commons;
-use xxi_node::commons::Message;
+use xxi_node::commons::ClientInfo;
 use xxi_node::commons::NewOrder;
 use xxi_node::commons::NewOrderRequest;
 use xxi_node::commons::Order;
+use xxi_node::commons::OrderFilter;
 use xxi_node::commons::OrderReason;
-use xxi_node::commons::OrderState;
-use xxi_node::commons::OrderType;
+use xxi_node::commons::PageParams;
 
 #[instrument(skip_all, err(Debug))]
 fn get_db_connection(
@@ -57,28 +68,101 @@ pub async fn get_order(
     Ok(Json(order))
 }
 
+/// A page of the open limit orders, as shown in the public orderbook.
 #[instrument(skip_all, err(Debug))]
-pub async fn get_orders(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Order>>, AppError> {
+pub async fn get_orders(
+    State(state): State<Arc<AppState>>,
+    Query(page): Query<PageParams<Uuid>>,
+    Query(filter): Query<OrderFilter>,
+) -> Result<Paginated<Order, Uuid>, AppError> {
     let mut conn = get_db_connection(&state)?;
-    let orders =
-        orderbook::db::orders::get_all_orders(&mut conn, OrderType::Limit, OrderState::Open, true)
-            .map_err(|e| AppError::InternalServerError(format!("Failed to load order: {e:#}")))?;
+    let orders = orderbook::db::orders::get_open_limit_orders_page(&mut conn, filter, &page)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to load order: {e:#}")))?;
 
-    Ok(Json(orders))
+    Ok(Paginated(orders.map(|order| order.displayed())))
 }
 
+/// API v1: the order is accepted without being returned. Superseded by [`post_order_v2`].
 #[instrument(skip_all, err(Debug))]
 pub async fn post_order(
     State(state): State<Arc<AppState>>,
     Json(new_order_request): Json<NewOrderRequest>,
 ) -> Result<(), AppError> {
+    submit_order(&state, new_order_request).await?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, err(Debug))]
+pub async fn post_order_v2(
+    State(state): State<Arc<AppState>>,
+    Extension(trader): Extension<AuthenticatedTrader>,
+    Json(new_order_request): Json<NewOrderRequest>,
+) -> Result<(StatusCode, Json<Order>), AppError> {
+    trader.ensure(new_order_request.value.trader_id())?;
+
+    let order = submit_order(&state, new_order_request).await?;
+
+    Ok((StatusCode::CREATED, Json(order)))
+}
+
+async fn submit_order(
+    state: &Arc<AppState>,
+    new_order_request: NewOrderRequest,
+) -> Result<Order, AppError> {
     new_order_request
         .verify(&state.secp)
         .map_err(|_| AppError::Unauthorized)?;
 
-    let new_order = new_order_request.value;
+    place_order(
+        state,
+        new_order_request.value,
+        new_order_request.channel_opening_params,
+        new_order_request.client_info,
+    )
+    .await
+}
+
+/// Validates the new order, stores it and hands it over to the matching engine.
+///
+/// The caller is responsible for authenticating the trader of the order.
+pub(super) async fn place_order(
+    state: &Arc<AppState>,
+    new_order: NewOrder,
+    channel_opening_params: Option<commons::ChannelOpeningParams>,
+    client_info: Option<ClientInfo>,
+) -> Result<Order, AppError> {
     let order_id = new_order.id();
 
+    state
+        .node
+        .kill_switch
+        .ensure_new_orders_allowed()
+        .map_err(|e| AppError::ServiceUnavailable(format!("{e:#}")))?;
+
+    state
+        .node
+        .moderation
+        .ensure_allowed(new_order.trader_id())
+        .map_err(|e| AppError::Forbidden(format!("{e:#}")))?;
+
+    if channel_opening_params.is_some() {
+        state
+            .node
+            .kill_switch
+            .ensure_new_channels_allowed()
+            .map_err(|e| AppError::ServiceUnavailable(format!("{e:#}")))?;
+    }
+
+    // Market orders are executed right away, which starts a DLC protocol.
+    if matches!(new_order, NewOrder::Market(_)) {
+        state
+            .node
+            .quiesce
+            .ensure_protocols_allowed()
+            .map_err(|e| AppError::ServiceUnavailable(format!("{e:#}")))?;
+    }
+
     // TODO(holzeis): We should add a similar check eventually for limit orders (makers).
     if let NewOrder::Market(new_order) = &new_order {
         let mut conn = state
@@ -91,6 +175,15 @@ pub async fn post_order(
 
     let settings = state.settings.read().await;
 
+    contract_expiry::validate_contract_expiry(
+        &state.node,
+        new_order.contract_symbol(),
+        new_order.contract_expiry(),
+        settings.listed_expiries,
+    )
+    .await
+    .map_err(|e| AppError::BadRequest(format!("{e:#}")))?;
+
     if let NewOrder::Limit(new_order) = &new_order {
         if settings.whitelist_enabled && !settings.whitelisted_makers.contains(&new_order.trader_id)
         {
@@ -106,14 +199,62 @@ pub async fn post_order(
                 "Limit orders with zero price are not allowed".to_string(),
             ));
         }
+
+        new_order
+            .ensure_valid_display_quantity()
+            .map_err(|e| AppError::BadRequest(format!("{e:#}")))?;
+    }
+
+    let open_order_policy = settings.open_order_policy;
+    let is_exempt = open_order_policy.exempt_makers
+        && settings.whitelisted_makers.contains(&new_order.trader_id());
+    if open_order_policy.enabled && !is_exempt {
+        let mut conn = state
+            .pool
+            .get()
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        let open_orders = orders::get_open_orders_by_trader_id(&mut conn, new_order.trader_id())
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Failed to load open orders: {e:#}"))
+            })?;
+
+        if let Err(e) = open_order_policy.validate(&new_order, &open_orders) {
+            tracing::warn!(
+                trader_id = %new_order.trader_id(),
+                order_id = %order_id,
+                "Rejected order violating the open order policy: {e}"
+            );
+
+            return Err(AppError::BadRequest(format!("{e:#}")));
+        }
+    }
+
+    if let NewOrder::Market(new_order) = &new_order {
+        let mut conn = state
+            .pool
+            .get()
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+
+        // If the channel is funded externally, the trader reserve is derived from the external
+        // funding later on.
+        let trader_reserve = channel_opening_params
+            .as_ref()
+            .filter(|params| params.pre_image.is_none())
+            .map(|params| params.trader_reserve);
+
+        trade::minimums::validate_market_order(
+            &state.node,
+            &mut conn,
+            new_order,
+            trader_reserve,
+            settings.min_quantity,
+            settings.force_close_cost_multiplier,
+        )
+        .map_err(|e| AppError::BadRequest(format!("{e:#}")))?;
     }
 
     let pool = state.pool.clone();
-    let external_funding = match new_order_request
-        .channel_opening_params
-        .clone()
-        .and_then(|c| c.pre_image)
-    {
+    let external_funding = match channel_opening_params.clone().and_then(|c| c.pre_image) {
         Some(pre_image_str) => {
             let pre_image =
                 commons::PreImage::from_url_safe_encoded_pre_image(pre_image_str.as_str())
@@ -134,6 +275,7 @@ pub async fn post_order(
                     inner_hash.as_str(),
                     pre_image_str.as_str(),
                     order_id,
+                    OffsetDateTime::now_utc() + EXTERNAL_FUNDING_RESERVATION_TTL,
                 )?;
 
                 anyhow::Ok(amount)
@@ -155,14 +297,21 @@ pub async fn post_order(
         let mut conn = pool.get()?;
 
         let order = match new_order {
-            NewOrder::Market(o) => {
-                orders::insert_market_order(&mut conn, o.clone(), OrderReason::Manual)
+            NewOrder::Market(o) => orders::insert_market_order(
+                &mut conn,
+                o.clone(),
+                OrderReason::Manual,
+                client_info.clone(),
+            ),
+            NewOrder::Limit(o) => {
+                orders::insert_limit_order(&mut conn, o, OrderReason::Manual, client_info.clone())
             }
-            NewOrder::Limit(o) => orders::insert_limit_order(&mut conn, o, OrderReason::Manual),
         }
         .map_err(|e| anyhow!(e))
         .context("Failed to insert new order into DB")?;
 
+        order_flow::record_order(client_info.as_ref());
+
         anyhow::Ok(order)
     })
     .await
@@ -172,33 +321,25 @@ pub async fn post_order(
     // FIXME(holzeis): We shouldn't blindly trust the user about the coordinator reserve. Note, we
     // already ignore the trader reserve parameter when the channel is externally funded.
     let message = NewOrderMessage {
-        order,
-        channel_opening_params: new_order_request.channel_opening_params.map(|params| {
-            crate::ChannelOpeningParams {
-                trader_reserve: params.trader_reserve,
-                coordinator_reserve: params.coordinator_reserve,
-                external_funding,
-            }
+        order: order.clone(),
+        channel_opening_params: channel_opening_params.map(|params| crate::ChannelOpeningParams {
+            trader_reserve: params.trader_reserve,
+            coordinator_reserve: params.coordinator_reserve,
+            external_funding,
         }),
         order_reason: OrderReason::Manual,
+        fixed_price: None,
     };
 
-    state.trading_sender.send(message).await.map_err(|e| {
-        AppError::InternalServerError(format!("Failed to send new order message: {e:#}"))
-    })?;
-
-    Ok(())
-}
+    state
+        .trading_sender
+        .send(OrderbookCommand::NewOrder(message))
+        .await
+        .map_err(|e| {
+            AppError::InternalServerError(format!("Failed to send new order message: {e:#}"))
+        })?;
 
-fn update_pricefeed(pricefeed_msg: Message, sender: Sender<Message>) {
-    match sender.send(pricefeed_msg) {
-        Ok(_) => {
-            tracing::trace!("Pricefeed updated")
-        }
-        Err(error) => {
-            tracing::warn!("Could not update pricefeed due to '{error}'")
-        }
-    }
+    Ok(order)
 }
 
 #[instrument(skip_all, err(Debug))]
@@ -206,11 +347,23 @@ pub async fn delete_order(
     Path(order_id): Path<Uuid>,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Order>, AppError> {
-    let mut conn = get_db_connection(&state)?;
-    let order = orderbook::db::orders::delete(&mut conn, order_id)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to delete order: {e:#}")))?;
-    let sender = state.tx_orderbook_feed.clone();
-    update_pricefeed(Message::DeleteOrder(order_id), sender);
+    let (response, order) = oneshot::channel();
+    state
+        .trading_sender
+        .send(OrderbookCommand::DeleteOrder {
+            order_id,
+            trader_id: None,
+            response,
+        })
+        .await
+        .map_err(|e| {
+            AppError::InternalServerError(format!("Failed to send delete order message: {e:#}"))
+        })?;
+
+    let order = order
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to delete order: {e:#}")))?
+        .map_err(|e| AppError::BadRequest(format!("Failed to delete order: {e:#}")))?;
 
     Ok(Json(order))
 }
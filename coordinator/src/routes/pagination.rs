@@ -0,0 +1,31 @@
+use axum::http::HeaderName;
+use axum::http::HeaderValue;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::Json;
+use serde::Serialize;
+use std::fmt;
+use xxi_node::commons::Page;
+use xxi_node::commons::NEXT_CURSOR_HEADER;
+
+/// Responds with the items of the page as a JSON array, and with the cursor to the next page in
+/// the [`NEXT_CURSOR_HEADER`].
+pub struct Paginated<T, I>(pub Page<T, I>);
+
+impl<T: Serialize, I: fmt::Display> IntoResponse for Paginated<T, I> {
+    fn into_response(self) -> Response {
+        let Page { items, next } = self.0;
+
+        let mut response = Json(items).into_response();
+
+        if let Some(next) = next {
+            let value =
+                HeaderValue::from_str(&next.to_string()).expect("cursor to be a valid header");
+            response
+                .headers_mut()
+                .insert(HeaderName::from_static(NEXT_CURSOR_HEADER), value);
+        }
+
+        response
+    }
+}
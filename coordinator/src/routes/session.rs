@@ -0,0 +1,112 @@
+use crate::db;
+use crate::routes::AppState;
+use crate::session_token;
+use crate::AppError;
+use anyhow::Context;
+use axum::extract::State;
+use axum::http::header::AUTHORIZATION;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::Json;
+use bitcoin::secp256k1::PublicKey;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use tracing::instrument;
+use xxi_node::commons::CreateSessionTokenParams;
+use xxi_node::commons::SessionToken;
+use xxi_node::commons::SignedValue;
+
+/// The trader authenticated by the session token of the request.
+///
+/// Inserted into the request extensions by [`require_session_token`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuthenticatedTrader(pub PublicKey);
+
+impl AuthenticatedTrader {
+    /// Ensure that the request acts on behalf of the authenticated trader.
+    pub fn ensure(&self, trader_pubkey: PublicKey) -> Result<(), AppError> {
+        if self.0 != trader_pubkey {
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(())
+    }
+}
+
+/// Issue a session token to a trader, who proves their identity by signing the request with their
+/// node key.
+#[instrument(skip_all, err(Debug))]
+pub async fn create_session_token(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<SignedValue<CreateSessionTokenParams>>,
+) -> Result<Json<SessionToken>, AppError> {
+    let trader_pubkey = params.value.trader_pubkey;
+
+    params
+        .verify(&state.secp, &trader_pubkey)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let now = OffsetDateTime::now_utc();
+    crate::api_key::ensure_recent(
+        params.value.timestamp,
+        session_token::MAX_CREATE_REQUEST_AGE,
+        now,
+    )
+    .map_err(|e| AppError::BadRequest(format!("{e:#}")))?;
+
+    spawn_blocking({
+        let pool = state.pool.clone();
+        move || {
+            let mut conn = pool.get()?;
+            db::user::get_user(&mut conn, &trader_pubkey)?
+                .with_context(|| format!("Unknown trader {trader_pubkey}"))?;
+
+            anyhow::Ok(())
+        }
+    })
+    .await
+    .expect("task to complete")
+    .map_err(|e| AppError::BadRequest(format!("Failed to create session token: {e:#}")))?;
+
+    Ok(Json(state.session_tokens.issue(trader_pubkey, now)))
+}
+
+/// Middleware rejecting requests without a valid session token in the `Authorization` header.
+///
+/// The trader the token was issued to is made available to the handler as an
+/// [`AuthenticatedTrader`] extension.
+pub async fn require_session_token<B>(
+    State(state): State<Arc<AppState>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "));
+
+    let Some(token) = token else {
+        return AppError::Unauthorized.into_response();
+    };
+
+    let trader_pubkey = match state
+        .session_tokens
+        .verify(token, OffsetDateTime::now_utc())
+    {
+        Ok(trader_pubkey) => trader_pubkey,
+        Err(e) => {
+            tracing::debug!("Rejecting request with invalid session token: {e:#}");
+            return AppError::Unauthorized.into_response();
+        }
+    };
+
+    request
+        .extensions_mut()
+        .insert(AuthenticatedTrader(trader_pubkey));
+
+    next.run(request).await
+}
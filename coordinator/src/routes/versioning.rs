@@ -0,0 +1,165 @@
+use axum::extract::MatchedPath;
+use axum::extract::State;
+use axum::http::header::LINK;
+use axum::http::HeaderName;
+use axum::http::HeaderValue;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use lazy_static::lazy_static;
+use prometheus::register_int_counter_vec;
+use prometheus::IntCounterVec;
+use time::macros::datetime;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+lazy_static! {
+    static ref API_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "coordinator_api_requests_total",
+        "Number of requests to the public API, by API version and route.",
+        &["version", "route"]
+    )
+    .expect("valid metric");
+}
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// The versions of the public API.
+///
+/// Routes without a version prefix (`/api/...`) are served as [`ApiVersion::V1`], because that is
+/// what old app versions call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+/// When an API version was deprecated and when it is going to be removed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deprecation {
+    pub deprecated_at: OffsetDateTime,
+    pub sunset_at: OffsetDateTime,
+    pub successor: ApiVersion,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    pub fn deprecation(&self) -> Option<Deprecation> {
+        match self {
+            // The app calls `/api/v2` from the release after 4.0.1 on. The deprecation takes effect
+            // once that release has rolled out, and the sunset leaves users half a year to update.
+            ApiVersion::V1 => Some(Deprecation {
+                deprecated_at: datetime!(2026-12-01 00:00 UTC),
+                sunset_at: datetime!(2027-06-30 23:59:59 UTC),
+                successor: ApiVersion::V2,
+            }),
+            ApiVersion::V2 => None,
+        }
+    }
+}
+
+/// Middleware counting the requests per API version and route, and announcing the deprecation of
+/// the requested API version through the `Deprecation`, `Sunset` and `Link` response headers.
+pub async fn track_api_version<B>(
+    State(version): State<ApiVersion>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+
+    API_REQUESTS
+        .with_label_values(&[version.as_str(), &route])
+        .inc();
+
+    let mut response = next.run(request).await;
+
+    if let Some(deprecation) = version.deprecation() {
+        let headers = response.headers_mut();
+        for (name, value) in deprecation.headers() {
+            headers.insert(name, value);
+        }
+    }
+
+    response
+}
+
+impl Deprecation {
+    fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        let http_date = format_description!(
+            "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+        );
+
+        let mut headers = vec![(
+            DEPRECATION,
+            HeaderValue::from_str(&format!("@{}", self.deprecated_at.unix_timestamp()))
+                .expect("valid header value"),
+        )];
+
+        match self.sunset_at.format(&http_date) {
+            Ok(sunset) => headers.push((
+                SUNSET,
+                HeaderValue::from_str(&sunset).expect("valid header value"),
+            )),
+            Err(e) => tracing::error!("Failed to format sunset date: {e:#}"),
+        }
+
+        headers.push((
+            LINK,
+            HeaderValue::from_str(&format!(
+                "</api/{}>; rel=\"successor-version\"",
+                self.successor.as_str()
+            ))
+            .expect("valid header value"),
+        ));
+
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_announces_its_sunset() {
+        let headers = ApiVersion::V1.deprecation().unwrap().headers();
+
+        assert_eq!(
+            headers,
+            vec![
+                (DEPRECATION, HeaderValue::from_static("@1796083200")),
+                (
+                    SUNSET,
+                    HeaderValue::from_static("Wed, 30 Jun 2027 23:59:59 GMT")
+                ),
+                (
+                    LINK,
+                    HeaderValue::from_static("</api/v2>; rel=\"successor-version\"")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn v1_is_sunset_after_its_deprecation() {
+        let deprecation = ApiVersion::V1.deprecation().unwrap();
+
+        assert!(deprecation.sunset_at > deprecation.deprecated_at);
+    }
+
+    #[test]
+    fn v2_is_not_deprecated() {
+        assert_eq!(ApiVersion::V2.deprecation(), None);
+    }
+}
@@ -1,427 +1,274 @@
 use crate::db;
-use crate::metrics::collect_metrics;
-use crate::node::Node;
-use crate::notifications::Notification;
-use crate::notifications::NotificationKind;
-use crate::orderbook;
-use crate::referrals;
-use crate::settings::Settings;
+use crate::db::job_runs::JobRun;
+use anyhow::Context;
 use anyhow::Result;
-use bitcoin::Network;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 use diesel::PgConnection;
-use time::OffsetDateTime;
-use tokio::sync::mpsc;
+use futures::future::BoxFuture;
+use futures::Future;
+use futures::FutureExt;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::task::spawn_blocking;
 use tokio_cron_scheduler::Job;
 use tokio_cron_scheduler::JobScheduler;
-use tokio_cron_scheduler::JobSchedulerError;
-use xxi_node::commons;
-
-pub struct NotificationScheduler {
-    pub scheduler: JobScheduler,
-    sender: mpsc::Sender<Notification>,
-    settings: Settings,
-    network: Network,
-    node: Node,
+
+mod jobs;
+
+pub use jobs::add_jobs;
+
+type Task = Box<dyn Fn() -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Runs jobs on cron schedules.
+///
+/// Every run of a job is recorded in the database. A job never runs more than once at the same
+/// time: if a run is still in progress when the job is due again, the job is skipped.
+#[derive(Clone)]
+pub struct Scheduler {
+    scheduler: JobScheduler,
+    pool: Pool<ConnectionManager<PgConnection>>,
+    jobs: Arc<RwLock<BTreeMap<String, Arc<ScheduledJob>>>>,
 }
 
-impl NotificationScheduler {
-    pub async fn new(
-        sender: mpsc::Sender<Notification>,
-        settings: Settings,
-        network: Network,
-        node: Node,
-    ) -> Self {
-        let scheduler = JobScheduler::new()
-            .await
-            .expect("To be able to start the scheduler");
+struct ScheduledJob {
+    name: String,
+    schedule: String,
+    task: Task,
+    /// A paused job is not run on its schedule, until the coordinator restarts. It can still be
+    /// triggered manually.
+    paused: AtomicBool,
+    running: AtomicBool,
+}
 
-        Self {
+/// A run of a job. No other run of the same job can start while it exists.
+struct RunningJob {
+    job: Arc<ScheduledJob>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub schedule: String,
+    pub paused: bool,
+    pub running: bool,
+    pub last_run: Option<JobRun>,
+}
+
+impl Scheduler {
+    pub async fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Result<Self> {
+        let scheduler = JobScheduler::new().await?;
+
+        Ok(Self {
             scheduler,
-            sender,
-            settings,
-            network,
-            node,
-        }
+            pool,
+            jobs: Arc::new(RwLock::new(BTreeMap::new())),
+        })
     }
 
-    pub async fn update_bonus_status_for_users(
-        &self,
-        pool: Pool<ConnectionManager<PgConnection>>,
-    ) -> Result<()> {
-        let schedule = self.settings.update_user_bonus_status_scheduler.clone();
+    // We don't want the doc block below to be auto-formatted.
+    #[rustfmt::skip]
+    /// Run the task on the given cron schedule.
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// *     *     *      *              *       *             *
+    pub async fn add_job<F, Fut>(&self, name: &str, schedule: &str, task: F) -> Result<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let job = Arc::new(ScheduledJob {
+            name: name.to_string(),
+            schedule: schedule.to_string(),
+            task: Box::new(move || task().boxed()),
+            paused: AtomicBool::new(false),
+            running: AtomicBool::new(false),
+        });
+
+        let cron_job = Job::new_async(schedule, {
+            let job = job.clone();
+            let pool = self.pool.clone();
+            move |_, _| {
+                let job = job.clone();
+                let pool = pool.clone();
+                Box::pin(async move {
+                    if job.paused.load(Ordering::SeqCst) {
+                        tracing::debug!(job = job.name, "Skipping paused job");
+                        return;
+                    }
 
-        let uuid = self
-            .scheduler
-            .add(build_update_bonus_status_job(schedule.as_str(), pool)?)
-            .await?;
+                    match job.try_start() {
+                        Some(running_job) => running_job.run(pool, false).await,
+                        None => {
+                            tracing::warn!(job = job.name, "Skipping job, as it is still running")
+                        }
+                    }
+                })
+            }
+        })
+        .with_context(|| format!("Invalid schedule for job {name}: {schedule}"))?;
 
-        tracing::debug!(
-            job_id = uuid.to_string(),
-            "Started new job to update users bonus status"
-        );
+        let job_id = self.scheduler.add(cron_job).await?;
+        self.jobs.write().insert(name.to_string(), job);
 
-        Ok(())
-    }
+        tracing::debug!(%job_id, job = name, schedule, "Added job");
 
-    pub async fn add_collect_metrics_job(
-        &self,
-        pool: Pool<ConnectionManager<PgConnection>>,
-    ) -> Result<()> {
-        let schedule = self.settings.collect_metrics_scheduler.clone();
-
-        let uuid = self
-            .scheduler
-            .add(build_metrics_collector_job(
-                schedule.as_str(),
-                pool,
-                self.node.clone(),
-            )?)
-            .await?;
-        tracing::debug!(
-            job_id = uuid.to_string(),
-            "Started new job to collect metrics"
-        );
         Ok(())
     }
 
-    pub async fn add_reminder_to_close_expired_position_job(
-        &self,
-        pool: Pool<ConnectionManager<PgConnection>>,
-    ) -> Result<()> {
-        let sender = self.sender.clone();
-        let schedule = self.settings.close_expired_position_scheduler.clone();
-
-        let uuid = self
-            .scheduler
-            .add(build_remind_to_close_expired_position_notification_job(
-                schedule.as_str(),
-                sender,
-                pool,
-            )?)
-            .await?;
-
-        tracing::debug!(
-            job_id = uuid.to_string(),
-            "Started new job to remind to close an expired position"
-        );
+    pub async fn start(&self) -> Result<()> {
+        let interrupted = spawn_blocking({
+            let pool = self.pool.clone();
+            move || {
+                let mut conn = pool.get()?;
+                let interrupted = db::job_runs::fail_interrupted(&mut conn)?;
+                anyhow::Ok(interrupted)
+            }
+        })
+        .await
+        .expect("task to complete")?;
 
-        Ok(())
-    }
+        if interrupted > 0 {
+            tracing::warn!(interrupted, "Marked interrupted job runs as failed");
+        }
 
-    pub async fn add_reminder_to_close_liquidated_position_job(
-        &self,
-        pool: Pool<ConnectionManager<PgConnection>>,
-    ) -> Result<()> {
-        let sender = self.sender.clone();
-        let schedule = self.settings.close_liquidated_position_scheduler.clone();
-
-        let uuid = self
-            .scheduler
-            .add(build_remind_to_close_liquidated_position_notification_job(
-                schedule.as_str(),
-                sender,
-                pool,
-            )?)
-            .await?;
-
-        tracing::debug!(
-            job_id = uuid.to_string(),
-            "Started new job to remind to close a liquidated position"
-        );
+        self.scheduler.start().await?;
 
         Ok(())
     }
 
-    pub async fn add_rollover_window_reminder_job(
-        &self,
-        pool: Pool<ConnectionManager<PgConnection>>,
-    ) -> Result<()> {
-        let schedule = self.settings.rollover_window_open_scheduler.clone();
-        let network = self.network;
-        let node = self.node.clone();
-        let sender = self.sender.clone();
-
-        let uuid = self
-            .scheduler
-            .add(build_rollover_notification_job(
-                schedule.as_str(),
-                pool,
-                network,
-                NotificationKind::RolloverWindowOpen,
-                node,
-                sender,
-            )?)
-            .await?;
-
-        tracing::debug!(
-            job_id = uuid.to_string(),
-            "Started new job to remind rollover window is open"
-        );
+    /// Run the job now, regardless of its schedule and whether it is paused.
+    ///
+    /// Fails if the job is still running.
+    pub fn trigger(&self, name: &str) -> Result<()> {
+        let job = self.job(name)?;
+        let running_job = job
+            .try_start()
+            .with_context(|| format!("Job {name} is still running"))?;
+
+        tokio::spawn({
+            let pool = self.pool.clone();
+            async move { running_job.run(pool, true).await }
+        });
 
         Ok(())
     }
 
-    pub async fn add_rollover_window_close_reminder_job(
-        &self,
-        pool: Pool<ConnectionManager<PgConnection>>,
-    ) -> Result<()> {
-        let schedule = self.settings.rollover_window_close_scheduler.clone();
-        let network = self.network;
-        let node = self.node.clone();
-        let sender = self.sender.clone();
-
-        let uuid = self
-            .scheduler
-            .add(build_rollover_notification_job(
-                schedule.as_str(),
-                pool,
-                network,
-                NotificationKind::PositionSoonToExpire,
-                node,
-                sender,
-            )?)
-            .await?;
-
-        tracing::debug!(
-            job_id = uuid.to_string(),
-            "Started new job to remind rollover window is closing"
-        );
+    pub fn set_paused(&self, name: &str, paused: bool) -> Result<()> {
+        let job = self.job(name)?;
+        job.paused.store(paused, Ordering::SeqCst);
+
+        tracing::info!(job = name, paused, "Updated job");
 
         Ok(())
     }
 
-    pub async fn start(&self) -> Result<()> {
-        self.scheduler.start().await?;
-        Ok(())
+    /// The status of all jobs, ordered by name.
+    pub fn jobs(&self) -> Result<Vec<JobStatus>> {
+        let jobs = self.jobs.read().values().cloned().collect::<Vec<_>>();
+
+        let mut conn = self.pool.get()?;
+        jobs.into_iter()
+            .map(|job| {
+                let last_run = db::job_runs::get_latest(&mut conn, &job.name, 1)?
+                    .into_iter()
+                    .next();
+
+                Ok(JobStatus {
+                    name: job.name.clone(),
+                    schedule: job.schedule.clone(),
+                    paused: job.paused.load(Ordering::SeqCst),
+                    running: job.running.load(Ordering::SeqCst),
+                    last_run,
+                })
+            })
+            .collect()
     }
-}
 
-fn build_rollover_notification_job(
-    schedule: &str,
-    pool: Pool<ConnectionManager<PgConnection>>,
-    network: Network,
-    notification: NotificationKind,
-    node: Node,
-    notifier: mpsc::Sender<Notification>,
-) -> Result<Job, JobSchedulerError> {
-    Job::new_async(schedule, move |_, _| {
-        let notifier = notifier.clone();
-        let mut conn = match pool.get() {
-            Ok(conn) => conn,
-            Err(e) => {
-                return Box::pin(async move {
-                    tracing::error!("Failed to get connection. Error: {e:#}")
-                });
-            }
-        };
+    /// The latest runs of the job, the most recent first.
+    pub fn runs(&self, name: &str, limit: i64) -> Result<Vec<JobRun>> {
+        let job = self.job(name)?;
 
-        if !commons::is_eligible_for_rollover(OffsetDateTime::now_utc(), network) {
-            return Box::pin(async move {
-                tracing::warn!("Rollover window hasn't started yet. Job schedule seems to be miss-aligned with the rollover window. Skipping user notifications.");
-            });
-        }
+        let mut conn = self.pool.get()?;
+        let runs = db::job_runs::get_latest(&mut conn, &job.name, limit)?;
 
-        // calculates the expiry of the next rollover window. positions which have an
-        // expiry before that haven't rolled over yet, and need to be reminded.
-        let expiry = commons::calculate_next_expiry(OffsetDateTime::now_utc(), network);
-        match db::positions::Position::get_all_open_positions_with_expiry_before(&mut conn, expiry)
-        {
-            Ok(positions) => Box::pin({
-                tracing::debug!(
-                    nr_of_positions = positions.len(),
-                    "Found positions to rollover"
-                );
-                let notification = notification.clone();
-                let node = node.clone();
-                async move {
-                    for position in positions {
-                        if let Err(e) = node
-                            .check_rollover(
-                                &mut conn,
-                                position,
-                                node.inner.network,
-                                &notifier,
-                                Some(notification.clone()),
-                            )
-                            .await
-                        {
-                            tracing::error!(trader_id=%position.trader, "Failed to check rollover. {e:#}");
-                        }
-                    }
-                }
-            }),
-            Err(error) => Box::pin(async move {
-                tracing::error!("Could not load positions with fcm token {error:#}")
-            }),
-        }
-    })
-}
+        Ok(runs)
+    }
 
-fn build_update_bonus_status_job(
-    schedule: &str,
-    pool: Pool<ConnectionManager<PgConnection>>,
-) -> Result<Job, JobSchedulerError> {
-    Job::new_async(schedule, move |_, _| {
-        let mut conn = match pool.get() {
-            Ok(conn) => conn,
-            Err(e) => {
-                return Box::pin(async move {
-                    tracing::error!("Failed to get connection. Error: {e:#}")
-                });
-            }
-        };
+    fn job(&self, name: &str) -> Result<Arc<ScheduledJob>> {
+        self.jobs
+            .read()
+            .get(name)
+            .cloned()
+            .with_context(|| format!("Unknown job {name}"))
+    }
+}
 
-        match referrals::update_referral_status(&mut conn) {
-            Ok(number_of_updated_users) => Box::pin({
-                async move {
-                    tracing::debug!(
-                        number_of_updated_users,
-                        "Successfully updated users bonus status."
-                    )
-                }
-            }),
-            Err(error) => {
-                Box::pin(
-                    async move { tracing::error!("Could not load update bonus status {error:#}") },
-                )
-            }
+impl ScheduledJob {
+    fn try_start(self: &Arc<Self>) -> Option<RunningJob> {
+        let was_running = self.running.swap(true, Ordering::SeqCst);
+        if was_running {
+            return None;
         }
-    })
+
+        Some(RunningJob { job: self.clone() })
+    }
 }
 
-fn build_remind_to_close_expired_position_notification_job(
-    schedule: &str,
-    notification_sender: mpsc::Sender<Notification>,
-    pool: Pool<ConnectionManager<PgConnection>>,
-) -> Result<Job, JobSchedulerError> {
-    Job::new_async(schedule, move |_, _| {
-        let notification_sender = notification_sender.clone();
-        let mut conn = match pool.get() {
-            Ok(conn) => conn,
-            Err(e) => {
-                return Box::pin(async move {
-                    tracing::error!("Failed to get connection. Error: {e:#}")
-                });
+impl RunningJob {
+    async fn run(self, pool: Pool<ConnectionManager<PgConnection>>, triggered_manually: bool) {
+        let name = self.job.name.clone();
+
+        // Failing to record the run must not prevent the job from running.
+        let run_id = spawn_blocking({
+            let pool = pool.clone();
+            let name = name.clone();
+            move || {
+                let mut conn = pool.get()?;
+                let run_id = db::job_runs::start(&mut conn, &name, triggered_manually)?;
+                anyhow::Ok(run_id)
             }
-        };
+        })
+        .await
+        .expect("task to complete")
+        .inspect_err(|e| tracing::error!(job = name, "Failed to record start of job: {e:#}"))
+        .ok();
 
-        // Note, positions that are expired longer than
-        // [`crate::node::expired_positions::EXPIRED_POSITION_TIMEOUT`] are set to closing, hence
-        // those positions will not get notified anymore afterwards.
-        match orderbook::db::orders::get_all_matched_market_orders_by_order_reason(
-            &mut conn,
-            vec![commons::OrderReason::Expired],
-        ) {
-            Ok(orders) => Box::pin({
-                async move {
-                    for order in orders {
-                        tracing::debug!(trader_id=%order.trader_id, "Sending reminder to close expired position.");
-                        if let Err(e) = notification_sender
-                            .send(Notification::new(
-                                order.trader_id,
-                                NotificationKind::PositionExpired,
-                            ))
-                            .await
-                        {
-                            tracing::error!(
-                                "Failed to send {:?} notification: {e:?}",
-                                NotificationKind::PositionExpired
-                            );
-                        }
-                    }
-                }
-            }),
-            Err(error) => Box::pin(async move {
-                tracing::error!("Could not load positions with fcm token {error:#}")
-            }),
-        }
-    })
-}
+        tracing::debug!(job = name, triggered_manually, "Running job");
 
-fn build_remind_to_close_liquidated_position_notification_job(
-    schedule: &str,
-    notification_sender: mpsc::Sender<Notification>,
-    pool: Pool<ConnectionManager<PgConnection>>,
-) -> Result<Job, JobSchedulerError> {
-    Job::new_async(schedule, move |_, _| {
-        let notification_sender = notification_sender.clone();
-        let mut conn = match pool.get() {
-            Ok(conn) => conn,
+        let error = match (self.job.task)().await {
+            Ok(()) => {
+                tracing::debug!(job = name, "Job succeeded");
+                None
+            }
             Err(e) => {
-                return Box::pin(async move {
-                    tracing::error!("Failed to get connection. Error: {e:#}")
-                });
+                tracing::error!(job = name, "Job failed: {e:#}");
+                Some(format!("{e:#}"))
             }
         };
 
-        // Note, positions that are liquidated longer than
-        // [`crate::node::liquidated_positions::LIQUIDATED_POSITION_TIMEOUT`] are set to closing,
-        // hence those positions will not get notified anymore afterwards.
-        match orderbook::db::orders::get_all_matched_market_orders_by_order_reason(
-            &mut conn,
-            vec![
-                commons::OrderReason::TraderLiquidated,
-                commons::OrderReason::CoordinatorLiquidated,
-            ],
-        ) {
-            Ok(orders) => Box::pin({
-                async move {
-                    for order in orders {
-                        tracing::debug!(trader_id=%order.trader_id, "Sending reminder to close liquidated position.");
-
-                        let notification_kind = NotificationKind::Custom {
-                            title: "Pending liquidation 💸".to_string(),
-                            message: "Open your app to execute the liquidation ".to_string(),
-                        };
-
-                        if let Err(e) = notification_sender
-                            .send(Notification::new(
-                                order.trader_id,
-                                notification_kind.clone(),
-                            ))
-                            .await
-                        {
-                            tracing::error!(
-                                "Failed to send {:?} notification: {e:?}",
-                                notification_kind
-                            );
-                        }
-                    }
-                }
-            }),
-            Err(error) => Box::pin(async move {
-                tracing::error!("Could not load orders with fcm token {error:#}")
-            }),
+        if let Some(run_id) = run_id {
+            if let Err(e) = spawn_blocking(move || {
+                let mut conn = pool.get()?;
+                db::job_runs::finish(&mut conn, run_id, error)?;
+                anyhow::Ok(())
+            })
+            .await
+            .expect("task to complete")
+            {
+                tracing::error!(job = name, "Failed to record end of job: {e:#}");
+            }
         }
-    })
+    }
 }
 
-fn build_metrics_collector_job(
-    schedule: &str,
-    pool: Pool<ConnectionManager<PgConnection>>,
-    node: Node,
-) -> Result<Job, JobSchedulerError> {
-    Job::new_async(schedule, move |_, _| {
-        let conn = match pool.get() {
-            Ok(conn) => conn,
-            Err(e) => {
-                return Box::pin(async move {
-                    tracing::error!("Failed to get connection. Error: {e:#}")
-                });
-            }
-        };
-        let node = node.clone();
-        Box::pin({
-            async move {
-                match collect_metrics(conn, node) {
-                    Ok(_) => {}
-                    Err(error) => {
-                        tracing::error!("Failed collecting metrics {error:#}");
-                    }
-                }
-            }
-        })
-    })
+impl Drop for RunningJob {
+    fn drop(&mut self) {
+        self.job.running.store(false, Ordering::SeqCst);
+    }
 }
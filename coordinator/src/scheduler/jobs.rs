@@ -0,0 +1,329 @@
+use crate::db;
+use crate::funding_fee::generate_funding_fee_events_periodically;
+use crate::message::OrderbookMessage;
+use crate::metrics::collect_metrics;
+use crate::node::Node;
+use crate::notifications::Notification;
+use crate::notifications::NotificationKind;
+use crate::orderbook;
+use crate::orderbook::journal_export::JournalExporter;
+use crate::reserve_interest::accrue_reserve_interest_periodically;
+use crate::retention::DataRetention;
+use crate::scheduler::Scheduler;
+use crate::settings::Settings;
+use crate::settlement_report::generate_settlement_reports_periodically;
+use crate::treasury::snapshot_treasury_periodically;
+use anyhow::Result;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use tokio::task::spawn_blocking;
+use xxi_node::commons;
+
+/// Add all jobs of the coordinator to the scheduler, with the schedules from the settings.
+#[allow(clippy::too_many_arguments)]
+pub async fn add_jobs(
+    scheduler: &Scheduler,
+    settings: &Settings,
+    pool: Pool<ConnectionManager<PgConnection>>,
+    node: Node,
+    notifier: mpsc::Sender<Notification>,
+    auth_users_notifier: mpsc::Sender<OrderbookMessage>,
+    data_retention: DataRetention,
+    journal_exporter: JournalExporter,
+) -> Result<()> {
+    for (name, schedule, notification) in [
+        (
+            "rollover_window_open_reminder",
+            &settings.rollover_window_open_scheduler,
+            NotificationKind::RolloverWindowOpen,
+        ),
+        (
+            "rollover_window_close_reminder",
+            &settings.rollover_window_close_scheduler,
+            NotificationKind::PositionSoonToExpire,
+        ),
+    ] {
+        scheduler
+            .add_job(name, schedule, {
+                let pool = pool.clone();
+                let node = node.clone();
+                let notifier = notifier.clone();
+                move || {
+                    remind_rollover(
+                        pool.clone(),
+                        notification.clone(),
+                        node.clone(),
+                        notifier.clone(),
+                    )
+                }
+            })
+            .await?;
+    }
+
+    scheduler
+        .add_job(
+            "close_expired_position_reminder",
+            &settings.close_expired_position_scheduler,
+            {
+                let pool = pool.clone();
+                let notifier = notifier.clone();
+                move || remind_to_close_expired_position(pool.clone(), notifier.clone())
+            },
+        )
+        .await?;
+
+    scheduler
+        .add_job(
+            "close_liquidated_position_reminder",
+            &settings.close_liquidated_position_scheduler,
+            {
+                let pool = pool.clone();
+                let notifier = notifier.clone();
+                move || remind_to_close_liquidated_position(pool.clone(), notifier.clone())
+            },
+        )
+        .await?;
+
+    scheduler
+        .add_job(
+            "notify_force_closed_channels",
+            &settings.notify_force_closed_channels_scheduler,
+            {
+                let pool = pool.clone();
+                let notifier = notifier.clone();
+                move || notify_force_closed_channels(pool.clone(), notifier.clone())
+            },
+        )
+        .await?;
+
+    scheduler
+        .add_job("collect_metrics", &settings.collect_metrics_scheduler, {
+            let pool = pool.clone();
+            let node = node.clone();
+            move || {
+                let pool = pool.clone();
+                let node = node.clone();
+                async move {
+                    spawn_blocking(move || collect_metrics(pool.get()?, node))
+                        .await
+                        .expect("task to complete")
+                }
+            }
+        })
+        .await?;
+
+    scheduler
+        .add_job("prune_data", &settings.prune_data_scheduler, move || {
+            let data_retention = data_retention.clone();
+            async move {
+                data_retention.prune().await;
+                Ok(())
+            }
+        })
+        .await?;
+
+    scheduler
+        .add_job("moderate_traders", &settings.moderate_traders_scheduler, {
+            let moderation = node.moderation.clone();
+            move || {
+                let moderation = moderation.clone();
+                async move {
+                    spawn_blocking(move || moderation.suspend_abusive_traders())
+                        .await
+                        .expect("task to complete")
+                }
+            }
+        })
+        .await?;
+
+    scheduler
+        .add_job(
+            "export_orderbook_journal",
+            &settings.export_orderbook_journal_scheduler,
+            move || {
+                let journal_exporter = journal_exporter.clone();
+                async move { journal_exporter.export_previous_day().await }
+            },
+        )
+        .await?;
+
+    generate_funding_fee_events_periodically(
+        scheduler,
+        pool.clone(),
+        auth_users_notifier,
+        settings.generate_funding_fee_events_scheduler.clone(),
+        settings.index_price_source,
+    )
+    .await?;
+
+    generate_settlement_reports_periodically(
+        scheduler,
+        pool,
+        settings.settlement_report_scheduler.clone(),
+    )
+    .await?;
+
+    accrue_reserve_interest_periodically(
+        scheduler,
+        node.clone(),
+        settings.accrue_reserve_interest_scheduler.clone(),
+    )
+    .await?;
+
+    snapshot_treasury_periodically(
+        scheduler,
+        node,
+        settings.treasury_snapshot_scheduler.clone(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn remind_rollover(
+    pool: Pool<ConnectionManager<PgConnection>>,
+    notification: NotificationKind,
+    node: Node,
+    notifier: mpsc::Sender<Notification>,
+) -> Result<()> {
+    let schedule = node.expiry_schedule().await;
+    if !schedule.is_eligible_for_rollover(OffsetDateTime::now_utc()) {
+        tracing::warn!("Rollover window hasn't started yet. Job schedule seems to be miss-aligned with the rollover window. Skipping user notifications.");
+        return Ok(());
+    }
+
+    let mut conn = pool.get()?;
+
+    // calculates the expiry of the next rollover window. positions which have an
+    // expiry before that haven't rolled over yet, and need to be reminded.
+    let expiry = schedule.next_expiry(OffsetDateTime::now_utc());
+    let positions =
+        db::positions::Position::get_all_open_positions_with_expiry_before(&mut conn, expiry)?;
+
+    tracing::debug!(
+        nr_of_positions = positions.len(),
+        "Found positions to rollover"
+    );
+
+    for position in positions {
+        if let Err(e) = node
+            .check_rollover(&mut conn, position, &notifier, Some(notification.clone()))
+            .await
+        {
+            tracing::error!(trader_id=%position.trader, "Failed to check rollover. {e:#}");
+        }
+    }
+
+    Ok(())
+}
+
+async fn remind_to_close_expired_position(
+    pool: Pool<ConnectionManager<PgConnection>>,
+    notification_sender: mpsc::Sender<Notification>,
+) -> Result<()> {
+    let mut conn = pool.get()?;
+
+    // Note, positions that are expired longer than
+    // [`crate::node::expired_positions::EXPIRED_POSITION_TIMEOUT`] are set to closing, hence
+    // those positions will not get notified anymore afterwards.
+    let orders = orderbook::db::orders::get_all_matched_market_orders_by_order_reason(
+        &mut conn,
+        vec![commons::OrderReason::Expired],
+    )?;
+
+    for order in orders {
+        tracing::debug!(trader_id=%order.trader_id, "Sending reminder to close expired position.");
+        if let Err(e) = notification_sender
+            .send(Notification::new(
+                order.trader_id,
+                NotificationKind::PositionExpired,
+            ))
+            .await
+        {
+            tracing::error!(
+                "Failed to send {:?} notification: {e:?}",
+                NotificationKind::PositionExpired
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Let traders know right away if their DLC channel is being force-closed, as they might otherwise
+/// only find out once they open the app again.
+///
+/// Every trader is notified once per DLC channel.
+async fn notify_force_closed_channels(
+    pool: Pool<ConnectionManager<PgConnection>>,
+    notification_sender: mpsc::Sender<Notification>,
+) -> Result<()> {
+    let mut conn = pool.get()?;
+
+    let channels = db::dlc_channels::get_force_closing_dlc_channels_to_notify(&mut conn)?;
+
+    for channel in channels {
+        let channel_id = hex::encode(channel.channel_id);
+        tracing::info!(trader_id=%channel.trader, %channel_id, "Notifying trader about force-closed DLC channel.");
+
+        if let Err(e) = notification_sender
+            .send(Notification::new(
+                channel.trader,
+                NotificationKind::ChannelForceClosed,
+            ))
+            .await
+        {
+            tracing::error!(
+                "Failed to send {:?} notification: {e:?}",
+                NotificationKind::ChannelForceClosed
+            );
+            continue;
+        }
+
+        db::dlc_channels::set_force_close_notified(&mut conn, &channel.channel_id)?;
+    }
+
+    Ok(())
+}
+
+async fn remind_to_close_liquidated_position(
+    pool: Pool<ConnectionManager<PgConnection>>,
+    notification_sender: mpsc::Sender<Notification>,
+) -> Result<()> {
+    let mut conn = pool.get()?;
+
+    // Note, positions that are liquidated longer than
+    // [`crate::node::liquidated_positions::LIQUIDATED_POSITION_TIMEOUT`] are set to closing,
+    // hence those positions will not get notified anymore afterwards.
+    let orders = orderbook::db::orders::get_all_matched_market_orders_by_order_reason(
+        &mut conn,
+        vec![
+            commons::OrderReason::TraderLiquidated,
+            commons::OrderReason::CoordinatorLiquidated,
+        ],
+    )?;
+
+    for order in orders {
+        tracing::debug!(trader_id=%order.trader_id, "Sending reminder to close liquidated position.");
+
+        let notification_kind = NotificationKind::Custom {
+            title: "Pending liquidation 💸".to_string(),
+            message: "Open your app to execute the liquidation ".to_string(),
+        };
+
+        if let Err(e) = notification_sender
+            .send(Notification::new(
+                order.trader_id,
+                notification_kind.clone(),
+            ))
+            .await
+        {
+            tracing::error!("Failed to send {:?} notification: {e:?}", notification_kind);
+        }
+    }
+
+    Ok(())
+}
@@ -1,14 +1,30 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "AccelerationMethod_Type"))]
+    pub struct AccelerationMethodType;
+
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "AccelerationTrigger_Type"))]
+    pub struct AccelerationTriggerType;
+
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "BonusStatus_Type"))]
     pub struct BonusStatusType;
 
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "ChannelMigrationState_Type"))]
+    pub struct ChannelMigrationStateType;
+
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "ChannelState_Type"))]
     pub struct ChannelStateType;
 
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "ClientKind_Type"))]
+    pub struct ClientKindType;
+
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "ContractSymbol_Type"))]
     pub struct ContractSymbolType;
@@ -29,6 +45,14 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "InvoiceState_Type"))]
     pub struct InvoiceStateType;
 
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "JobOutcome_Type"))]
+    pub struct JobOutcomeType;
+
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "LightningPaymentStatus_Type"))]
+    pub struct LightningPaymentStatusType;
+
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "MatchState_Type"))]
     pub struct MatchStateType;
@@ -68,6 +92,14 @@ pub mod sql_types {
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "Protocol_Type_Type"))]
     pub struct ProtocolTypeType;
+
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "RejectReason_Type"))]
+    pub struct RejectReasonType;
+
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "ZombieChannelState_Type"))]
+    pub struct ZombieChannelStateType;
 }
 
 diesel::table! {
@@ -80,6 +112,29 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    api_key_nonces (signer, nonce) {
+        signer -> Text,
+        nonce -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    api_keys (id) {
+        id -> Uuid,
+        trader_pubkey -> Text,
+        secret -> Nullable<Text>,
+        label -> Nullable<Text>,
+        can_read -> Bool,
+        can_trade -> Bool,
+        created_at -> Timestamptz,
+        last_used_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+        encrypted_secret -> Nullable<Bytea>,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::BonusStatusType;
@@ -109,6 +164,34 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ChannelMigrationStateType;
+    use super::sql_types::DirectionType;
+    use super::sql_types::ContractSymbolType;
+
+    channel_migrations (id) {
+        id -> Int4,
+        trader_pubkey -> Text,
+        old_channel_id -> Text,
+        new_channel_id -> Nullable<Text>,
+        migration_state -> ChannelMigrationStateType,
+        trader_direction -> Nullable<DirectionType>,
+        quantity -> Nullable<Float4>,
+        trader_leverage -> Nullable<Float4>,
+        stable -> Nullable<Bool>,
+        trader_reserve_sats -> Int8,
+        coordinator_reserve_sats -> Int8,
+        close_order_id -> Nullable<Uuid>,
+        reopen_order_id -> Nullable<Uuid>,
+        error -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        contract_symbol -> Nullable<ContractSymbolType>,
+        average_entry_price -> Nullable<Float4>,
+    }
+}
+
 diesel::table! {
     channel_opening_params (order_id) {
         order_id -> Text,
@@ -160,6 +243,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    dlc_channel_snapshots (id) {
+        id -> Int4,
+        channel_id -> Text,
+        state -> Text,
+        reference_id -> Nullable<Text>,
+        coordinator_collateral_sats -> Nullable<Int8>,
+        trader_collateral_sats -> Nullable<Int8>,
+        coordinator_reserve_sats -> Nullable<Int8>,
+        trader_reserve_sats -> Nullable<Int8>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::DlcChannelStateType;
@@ -182,6 +279,7 @@ diesel::table! {
         updated_at -> Timestamptz,
         coordinator_funding_sats -> Int8,
         trader_funding_sats -> Int8,
+        force_close_notified_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -198,6 +296,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::RejectReasonType;
+
+    dlc_protocol_rejections (id) {
+        id -> Int4,
+        protocol_id -> Uuid,
+        trader_pubkey -> Text,
+        order_id -> Nullable<Uuid>,
+        reason -> RejectReasonType,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::ProtocolStateType;
@@ -216,6 +328,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    dlc_store (kind, key) {
+        kind -> Int2,
+        key -> Bytea,
+        value -> Bytea,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::InvoiceStateType;
@@ -230,6 +351,43 @@ diesel::table! {
         updated_at -> Nullable<Timestamptz>,
         invoice_state -> InvoiceStateType,
         order_id -> Nullable<Uuid>,
+        reserved_until -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    expiry_settlement_attempts (id) {
+        id -> Int4,
+        position_id -> Int4,
+        trader_pubkey -> Text,
+        event_id -> Text,
+        attempt -> Int4,
+        attested_price -> Nullable<Float4>,
+        protocol_id -> Nullable<Uuid>,
+        error -> Nullable<Text>,
+        escalated -> Bool,
+        created_at -> Timestamptz,
+        cet_interval_start -> Nullable<Int8>,
+        cet_interval_end -> Nullable<Int8>,
+        cet_trader_payout_sats -> Nullable<Int8>,
+        exact_trader_payout_sats -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::AccelerationMethodType;
+    use super::sql_types::AccelerationTriggerType;
+
+    funding_accelerations (id) {
+        id -> Int4,
+        channel_id -> Text,
+        funding_txid -> Text,
+        method -> AccelerationMethodType,
+        trigger -> AccelerationTriggerType,
+        child_txid -> Nullable<Text>,
+        cost_sats -> Int8,
+        created_at -> Timestamptz,
     }
 }
 
@@ -257,6 +415,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::JobOutcomeType;
+
+    job_runs (id) {
+        id -> Int4,
+        job_name -> Text,
+        triggered_manually -> Bool,
+        outcome -> JobOutcomeType,
+        error -> Nullable<Text>,
+        started_at -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    kill_switch_changes (id) {
+        id -> Int4,
+        read_only -> Bool,
+        no_new_channels -> Bool,
+        no_withdrawals -> Bool,
+        full_halt -> Bool,
+        reason -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     last_outbound_dlc_messages (peer_id) {
         peer_id -> Text,
@@ -281,6 +466,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::LightningPaymentStatusType;
+
+    lightning_withdrawals (id) {
+        id -> Int4,
+        trader_pubkey -> Text,
+        channel_id -> Text,
+        amount_sats -> Int8,
+        invoice -> Text,
+        payment_hash -> Text,
+        payment_status -> LightningPaymentStatusType,
+        protocol_id -> Nullable<Uuid>,
+        debited_at -> Nullable<Timestamptz>,
+        refund_protocol_id -> Nullable<Uuid>,
+        refunded_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     liquidity_options (id) {
         id -> Int4,
@@ -309,6 +515,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ContractSymbolType;
+
+    maker_earnings (id) {
+        id -> Int4,
+        maker_pubkey -> Text,
+        date -> Date,
+        contract_symbol -> ContractSymbolType,
+        fills -> Int4,
+        volume -> Float4,
+        matching_fees_sats -> Int8,
+        spread_capture_sats -> Int8,
+        hedging_costs_sats -> Int8,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::MatchStateType;
@@ -325,6 +549,21 @@ diesel::table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         matching_fee_sats -> Int8,
+        index_price -> Nullable<Float4>,
+        spread_bps -> Int4,
+    }
+}
+
+diesel::table! {
+    message_archive (id) {
+        id -> Int4,
+        protocol_id -> Uuid,
+        peer_id -> Text,
+        inbound -> Bool,
+        message_type -> Text,
+        message_hash -> Text,
+        encrypted_message -> Bytea,
+        created_at -> Timestamptz,
     }
 }
 
@@ -336,6 +575,36 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::OrderTypeType;
+
+    order_fills (id) {
+        id -> Int4,
+        order_id -> Uuid,
+        trader_pubkey -> Text,
+        order_type -> OrderTypeType,
+        quantity -> Float4,
+        execution_price -> Float4,
+        index_price -> Nullable<Float4>,
+        slippage_bps -> Nullable<Float4>,
+        queue_position -> Nullable<Int4>,
+        time_to_fill_ms -> Int8,
+        placed_at -> Timestamptz,
+        matched_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    orderbook_journal (sequence) {
+        sequence -> Int8,
+        event_type -> Text,
+        order_id -> Uuid,
+        payload -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::DirectionType;
@@ -343,6 +612,7 @@ diesel::table! {
     use super::sql_types::OrderStateType;
     use super::sql_types::ContractSymbolType;
     use super::sql_types::OrderReasonType;
+    use super::sql_types::ClientKindType;
 
     orders (id) {
         id -> Int4,
@@ -359,6 +629,10 @@ diesel::table! {
         leverage -> Float4,
         order_reason -> OrderReasonType,
         stable -> Bool,
+        display_quantity -> Nullable<Float4>,
+        client_kind -> Nullable<ClientKindType>,
+        client_version -> Nullable<Text>,
+        contract_expiry -> Nullable<Timestamptz>,
     }
 }
 
@@ -447,6 +721,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    quiesce_changes (id) {
+        id -> Int4,
+        engaged -> Bool,
+        until -> Nullable<Timestamptz>,
+        reason -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     reported_errors (id) {
         id -> Int4,
@@ -457,6 +741,43 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    reserve_interest_credits (id) {
+        id -> Int4,
+        trader_pubkey -> Text,
+        channel_id -> Text,
+        reserve_sats -> Int8,
+        apr -> Float4,
+        period_start -> Timestamptz,
+        period_end -> Timestamptz,
+        amount_sats -> Int8,
+        protocol_id -> Nullable<Uuid>,
+        paid_date -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    reserve_top_ups (id) {
+        id -> Int4,
+        trader_pubkey -> Text,
+        channel_id -> Text,
+        address -> Text,
+        amount_sats -> Int8,
+        txid -> Nullable<Text>,
+        received_sats -> Nullable<Int8>,
+        confirmed_at -> Nullable<Timestamptz>,
+        protocol_id -> Nullable<Uuid>,
+        credited_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        credited_sats -> Int8,
+        crediting_sats -> Int8,
+        settled_at -> Nullable<Timestamptz>,
+        settlement_note -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     rollover_params (id) {
         id -> Int4,
@@ -482,6 +803,44 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    settlement_attestations (id) {
+        id -> Int4,
+        position_id -> Int4,
+        contract_id -> Text,
+        oracle_pubkey -> Text,
+        event_id -> Text,
+        announcement -> Bytea,
+        attestation -> Bytea,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    settlement_disputes (id) {
+        id -> Int4,
+        channel_id -> Text,
+        trader_pubkey -> Text,
+        event_id -> Text,
+        attested_price -> Float4,
+        mark_price -> Nullable<Float4>,
+        second_oracle_price -> Nullable<Float4>,
+        created_at -> Timestamptz,
+        resolved_at -> Nullable<Timestamptz>,
+        resolution_note -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    settlement_reports (id) {
+        id -> Int4,
+        maker_pubkey -> Text,
+        date -> Date,
+        report -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     spendable_outputs (id) {
         id -> Int4,
@@ -491,6 +850,28 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    support_tickets (id) {
+        id -> Int4,
+        reference -> Text,
+        trader_pubkey -> Text,
+        error_code -> Text,
+        error -> Text,
+        order_id -> Nullable<Uuid>,
+        protocol_id -> Nullable<Uuid>,
+        app_version -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    trade_executions (protocol_id) {
+        protocol_id -> Uuid,
+        order_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::DirectionType;
@@ -508,6 +889,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    trader_restrictions (id) {
+        id -> Int4,
+        trader_pubkey -> Text,
+        reason -> Text,
+        until -> Nullable<Timestamptz>,
+        automatic -> Bool,
+        created_at -> Timestamptz,
+        lifted_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::ContractSymbolType;
@@ -528,6 +921,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    treasury_snapshots (id) {
+        id -> Int4,
+        on_chain_external_sats -> Int8,
+        on_chain_internal_sats -> Int8,
+        dlc_channels -> Int4,
+        dlc_margin_sats -> Int8,
+        dlc_reserve_sats -> Int8,
+        force_closing_channels -> Int4,
+        pending_force_close_sats -> Int8,
+        fees_earned_sats -> Int8,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     transactions (txid) {
         txid -> Text,
@@ -551,50 +959,98 @@ diesel::table! {
         referral_code -> Text,
         used_referral_code -> Nullable<Text>,
         os -> Nullable<Text>,
+        locale -> Text,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ZombieChannelStateType;
+
+    zombie_channels (id) {
+        id -> Int4,
+        channel_id -> Text,
+        trader_pubkey -> Text,
+        state -> ZombieChannelStateType,
+        last_seen -> Timestamptz,
+        notifications_sent -> Int4,
+        last_notified_at -> Nullable<Timestamptz>,
+        protocol_id -> Nullable<Uuid>,
+        note -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
 diesel::joinable!(answers -> choices (choice_id));
 diesel::joinable!(choices -> polls (poll_id));
+diesel::joinable!(expiry_settlement_attempts -> positions (position_id));
 diesel::joinable!(funding_fee_events -> positions (position_id));
 diesel::joinable!(last_outbound_dlc_messages -> dlc_messages (message_hash));
 diesel::joinable!(liquidity_request_logs -> liquidity_options (liquidity_option));
 diesel::joinable!(polls_whitelist -> polls (poll_id));
 diesel::joinable!(protocol_funding_fee_events -> funding_fee_events (funding_fee_event_id));
+diesel::joinable!(settlement_attestations -> positions (position_id));
 diesel::joinable!(trades -> positions (position_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     answers,
+    api_key_nonces,
+    api_keys,
     bonus_status,
     bonus_tiers,
+    channel_migrations,
     channel_opening_params,
     channels,
     choices,
     collaborative_reverts,
+    dlc_channel_snapshots,
     dlc_channels,
     dlc_messages,
+    dlc_protocol_rejections,
     dlc_protocols,
+    dlc_store,
+    expiry_settlement_attempts,
+    funding_accelerations,
     funding_fee_events,
     funding_rates,
     hodl_invoices,
+    job_runs,
+    kill_switch_changes,
     last_outbound_dlc_messages,
     legacy_collaborative_reverts,
+    lightning_withdrawals,
     liquidity_options,
     liquidity_request_logs,
+    maker_earnings,
     matches,
+    message_archive,
     metrics,
+    order_fills,
+    orderbook_journal,
     orders,
     payments,
     polls,
     polls_whitelist,
     positions,
     protocol_funding_fee_events,
+    quiesce_changes,
     reported_errors,
+    reserve_interest_credits,
+    reserve_top_ups,
     rollover_params,
     routing_fees,
+    settlement_attestations,
+    settlement_disputes,
+    settlement_reports,
     spendable_outputs,
+    support_tickets,
+    trade_executions,
     trade_params,
+    trader_restrictions,
     trades,
     transactions,
+    treasury_snapshots,
     users,
+    zombie_channels,
 );
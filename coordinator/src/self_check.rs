@@ -0,0 +1,345 @@
+//! Checks that the coordinator is configured consistently with the services it depends on.
+//!
+//! A misconfiguration, e.g. an electrs server on the wrong network or a wrong oracle public key,
+//! would otherwise only surface as obscure failures at runtime. The checks are run on every
+//! startup, and the coordinator refuses to start with a misconfiguration. A service which can't be
+//! checked, e.g. because it is temporarily unreachable, only results in a warning. With
+//! `--self-check`, the coordinator only runs the checks once, reports the results and exits.
+
+use crate::MIGRATIONS;
+use anyhow::anyhow;
+use anyhow::Context;
+use bitcoin::blockdata::constants::genesis_block;
+use bitcoin::key::XOnlyPublicKey;
+use bitcoin::BlockHash;
+use bitcoin::Network;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use diesel_migrations::MigrationHarness;
+use lnd_bridge::LndBridge;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::task::spawn_blocking;
+use xxi_node::node::OracleInfo;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct SelfCheck {
+    pub network: Network,
+    pub electrs: String,
+    pub oracles: Vec<OracleInfo>,
+    /// The oracle used to propose DLC channels.
+    pub oracle_pubkey: XOnlyPublicKey,
+    pub lnd_bridge: LndBridge,
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[derive(Debug)]
+pub struct SelfCheckReport {
+    pub checks: Vec<CheckResult>,
+}
+
+#[derive(Debug)]
+pub struct CheckResult {
+    pub name: String,
+    /// What was verified, or why the check failed.
+    pub outcome: Result<String, CheckFailure>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckFailure {
+    /// The coordinator is configured inconsistently with the service.
+    Misconfiguration(String),
+    /// The service could not be checked, e.g. because it is not reachable.
+    Unavailable(String),
+}
+
+impl CheckFailure {
+    fn misconfiguration(message: impl Into<String>) -> Self {
+        Self::Misconfiguration(message.into())
+    }
+
+    fn unavailable(e: anyhow::Error) -> Self {
+        Self::Unavailable(format!("{e:#}"))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OraclePublicKey {
+    public_key: XOnlyPublicKey,
+}
+
+impl SelfCheck {
+    pub async fn run(&self) -> SelfCheckReport {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("valid client");
+
+        let mut checks = vec![
+            CheckResult::new("Database migrations", self.check_migrations().await),
+            CheckResult::new("Electrs network", self.check_electrs_network(&client).await),
+            CheckResult::new("Default oracle", self.check_default_oracle()),
+        ];
+
+        for oracle in self.oracles.iter() {
+            checks.push(CheckResult::new(
+                format!("Oracle {}", oracle.endpoint),
+                check_oracle(&client, oracle).await,
+            ));
+        }
+
+        checks.push(CheckResult::new("LND", self.check_lnd().await));
+
+        SelfCheckReport { checks }
+    }
+
+    async fn check_migrations(&self) -> Result<String, CheckFailure> {
+        let pool = self.pool.clone();
+        let pending = spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            let pending = conn
+                .pending_migrations(MIGRATIONS)
+                .map_err(|e| anyhow!("{e:#}"))?
+                .iter()
+                .map(|migration| migration.name().to_string())
+                .collect::<Vec<_>>();
+
+            anyhow::Ok(pending)
+        })
+        .await
+        .expect("task to complete")
+        .context("Failed to access the database")
+        .map_err(CheckFailure::unavailable)?;
+
+        if !pending.is_empty() {
+            return Err(CheckFailure::misconfiguration(format!(
+                "Pending migrations: {}",
+                pending.join(", ")
+            )));
+        }
+
+        Ok("All migrations applied".to_string())
+    }
+
+    async fn check_electrs_network(
+        &self,
+        client: &reqwest::Client,
+    ) -> Result<String, CheckFailure> {
+        let genesis_block_hash = async {
+            let genesis_block_hash = client
+                .get(format!("{}/block-height/0", self.electrs))
+                .send()
+                .await
+                .context("Electrs is not reachable")?
+                .error_for_status()?
+                .text()
+                .await?;
+
+            BlockHash::from_str(genesis_block_hash.trim()).context("Invalid genesis block hash")
+        }
+        .await
+        .map_err(CheckFailure::unavailable)?;
+
+        let expected = genesis_block(self.network).block_hash();
+        if genesis_block_hash != expected {
+            return Err(CheckFailure::misconfiguration(format!(
+                "Electrs is not on {}: genesis block {genesis_block_hash} != {expected}",
+                self.network
+            )));
+        }
+
+        Ok(format!("On {}", self.network))
+    }
+
+    fn check_default_oracle(&self) -> Result<String, CheckFailure> {
+        if !self
+            .oracles
+            .iter()
+            .any(|oracle| oracle.public_key == self.oracle_pubkey)
+        {
+            return Err(CheckFailure::misconfiguration(format!(
+                "Oracle {} is not among the configured oracles",
+                self.oracle_pubkey
+            )));
+        }
+
+        Ok(format!("Using {}", self.oracle_pubkey))
+    }
+
+    async fn check_lnd(&self) -> Result<String, CheckFailure> {
+        let info = self
+            .lnd_bridge
+            .get_info()
+            .await
+            .context("LND is not reachable")
+            .map_err(CheckFailure::unavailable)?;
+
+        let expected = lnd_network(self.network);
+        let Some(chain) = info.chains.iter().find(|chain| chain.chain == "bitcoin") else {
+            return Err(CheckFailure::misconfiguration(
+                "LND is not running on bitcoin",
+            ));
+        };
+        if chain.network != expected {
+            return Err(CheckFailure::misconfiguration(format!(
+                "LND is on {}, not on {expected}",
+                chain.network
+            )));
+        }
+
+        if !info.synced_to_chain {
+            tracing::warn!("LND is not synced to the chain yet");
+        }
+
+        Ok(format!("{} on {}", info.identity_pubkey, chain.network))
+    }
+}
+
+async fn check_oracle(
+    client: &reqwest::Client,
+    oracle: &OracleInfo,
+) -> Result<String, CheckFailure> {
+    let response = async {
+        let response: OraclePublicKey = client
+            .get(format!("{}/oracle/publickey", oracle.endpoint))
+            .send()
+            .await
+            .context("Oracle is not reachable")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Invalid oracle public key")?;
+
+        anyhow::Ok(response)
+    }
+    .await
+    .map_err(CheckFailure::unavailable)?;
+
+    if response.public_key != oracle.public_key {
+        return Err(CheckFailure::misconfiguration(format!(
+            "Oracle announces {}, not {}",
+            response.public_key, oracle.public_key
+        )));
+    }
+
+    Ok(format!("Announces {}", oracle.public_key))
+}
+
+/// The name of the network as reported by LND.
+fn lnd_network(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "mainnet",
+        Network::Testnet => "testnet",
+        Network::Signet => "signet",
+        Network::Regtest => "regtest",
+        _ => "unknown",
+    }
+}
+
+impl CheckResult {
+    fn new(name: impl Into<String>, outcome: Result<String, CheckFailure>) -> Self {
+        Self {
+            name: name.into(),
+            outcome,
+        }
+    }
+}
+
+impl SelfCheckReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.outcome.is_ok())
+    }
+
+    /// Whether any check found the coordinator to be configured inconsistently with a service.
+    pub fn found_misconfiguration(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| matches!(check.outcome, Err(CheckFailure::Misconfiguration(_))))
+    }
+}
+
+impl fmt::Display for SelfCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in self.checks.iter() {
+            match &check.outcome {
+                Ok(details) => writeln!(f, "[ OK ] {}: {details}", check.name)?,
+                Err(CheckFailure::Misconfiguration(error)) => {
+                    writeln!(f, "[FAIL] {}: {error}", check.name)?
+                }
+                Err(CheckFailure::Unavailable(error)) => {
+                    writeln!(f, "[WARN] {}: {error}", check.name)?
+                }
+            }
+        }
+
+        let failed = self
+            .checks
+            .iter()
+            .filter(|check| check.outcome.is_err())
+            .count();
+        if failed == 0 {
+            write!(f, "All {} checks passed", self.checks.len())
+        } else {
+            write!(f, "{failed} of {} checks failed", self.checks.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_fails_if_any_check_fails() {
+        let report = SelfCheckReport {
+            checks: vec![
+                CheckResult::new(
+                    "Database migrations",
+                    Ok("All migrations applied".to_string()),
+                ),
+                CheckResult::new(
+                    "LND",
+                    Err(CheckFailure::misconfiguration(
+                        "LND is on testnet, not on regtest",
+                    )),
+                ),
+            ],
+        };
+
+        assert!(!report.passed());
+        assert!(report.found_misconfiguration());
+        assert_eq!(
+            report.to_string(),
+            "[ OK ] Database migrations: All migrations applied\n\
+             [FAIL] LND: LND is on testnet, not on regtest\n\
+             1 of 2 checks failed"
+        );
+    }
+
+    #[test]
+    fn unavailable_service_is_no_misconfiguration() {
+        let report = SelfCheckReport {
+            checks: vec![
+                CheckResult::new("Default oracle", Ok("Using oracle".to_string())),
+                CheckResult::new(
+                    "LND",
+                    Err(CheckFailure::unavailable(anyhow!("LND is not reachable"))),
+                ),
+            ],
+        };
+
+        assert!(!report.passed());
+        assert!(!report.found_misconfiguration());
+        assert_eq!(
+            report.to_string(),
+            "[ OK ] Default oracle: Using oracle\n\
+             [WARN] LND: LND is not reachable\n\
+             1 of 2 checks failed"
+        );
+    }
+}
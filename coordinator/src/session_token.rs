@@ -0,0 +1,150 @@
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::hashes::hmac::Hmac;
+use bitcoin::hashes::hmac::HmacEngine;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::HashEngine;
+use bitcoin::secp256k1::PublicKey;
+use serde::Deserialize;
+use serde::Serialize;
+use time::Duration;
+use time::OffsetDateTime;
+use xxi_node::commons::SessionToken;
+
+/// How long a session token is valid. The app requests a new one once it expires.
+pub const SESSION_TOKEN_LIFETIME: Duration = Duration::minutes(15);
+
+/// Requests for a new session token which are older than this are rejected.
+pub const MAX_CREATE_REQUEST_AGE: Duration = Duration::minutes(5);
+
+/// Issues and verifies the session tokens which authenticate traders towards the REST API.
+///
+/// Tokens are not stored: a token is the trader's pubkey and its validity period, signed with a
+/// key derived from the coordinator's seed. Hence, sessions survive restarts of the coordinator.
+#[derive(Clone)]
+pub struct SessionTokens {
+    key: [u8; 32],
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Claims {
+    /// The trader the token was issued to.
+    sub: PublicKey,
+    /// When the token was issued, in seconds since the unix epoch.
+    iat: i64,
+    /// When the token expires, in seconds since the unix epoch.
+    exp: i64,
+}
+
+impl SessionTokens {
+    pub fn new(seed_key: [u8; 32]) -> Self {
+        Self {
+            key: hmac(&seed_key, b"session-token"),
+        }
+    }
+
+    pub fn issue(&self, trader_pubkey: PublicKey, now: OffsetDateTime) -> SessionToken {
+        let expires_at = now + SESSION_TOKEN_LIFETIME;
+
+        let claims = Claims {
+            sub: trader_pubkey,
+            iat: now.unix_timestamp(),
+            exp: expires_at.unix_timestamp(),
+        };
+        let claims = serde_json::to_vec(&claims).expect("to serialize claims");
+        let claims = hex::encode(claims);
+
+        let signature = hex::encode(hmac(&self.key, claims.as_bytes()));
+
+        SessionToken {
+            token: format!("{claims}.{signature}"),
+            expires_at,
+        }
+    }
+
+    /// Verify the session token, returning the trader it was issued to.
+    pub fn verify(&self, token: &str, now: OffsetDateTime) -> Result<PublicKey> {
+        let (claims, signature) = token.split_once('.').context("Malformed session token")?;
+
+        let signature = hex::decode(signature).context("Malformed session token signature")?;
+        let expected = hmac(&self.key, claims.as_bytes());
+
+        ensure!(
+            constant_time_eq(&signature, &expected),
+            "Invalid session token signature"
+        );
+
+        let claims = hex::decode(claims).context("Malformed session token claims")?;
+        let claims: Claims =
+            serde_json::from_slice(&claims).context("Malformed session token claims")?;
+
+        ensure!(
+            now.unix_timestamp() < claims.exp,
+            "Session token expired at {}",
+            claims.exp
+        );
+
+        Ok(claims.sub)
+    }
+}
+
+pub(crate) fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut engine = HmacEngine::<sha256::Hash>::new(key);
+    engine.input(message);
+
+    Hmac::<sha256::Hash>::from_engine(engine).to_byte_array()
+}
+
+/// Compare in constant time, to not leak how much of a signature is correct.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn trader() -> PublicKey {
+        PublicKey::from_str("02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655")
+            .unwrap()
+    }
+
+    #[test]
+    fn issued_token_verifies_until_it_expires() {
+        let tokens = SessionTokens::new([1u8; 32]);
+        let now = OffsetDateTime::now_utc();
+
+        let token = tokens.issue(trader(), now);
+
+        assert_eq!(tokens.verify(&token.token, now).unwrap(), trader());
+        assert!(tokens
+            .verify(&token.token, now + SESSION_TOKEN_LIFETIME)
+            .is_err());
+    }
+
+    #[test]
+    fn tampered_token_does_not_verify() {
+        let tokens = SessionTokens::new([1u8; 32]);
+        let now = OffsetDateTime::now_utc();
+
+        let token = tokens.issue(trader(), now);
+        let (_, signature) = token.token.split_once('.').unwrap();
+
+        let claims = Claims {
+            sub: trader(),
+            iat: now.unix_timestamp(),
+            exp: (now + Duration::days(365)).unix_timestamp(),
+        };
+        let claims = hex::encode(serde_json::to_vec(&claims).unwrap());
+        let tampered = format!("{claims}.{signature}");
+
+        assert!(tokens.verify(&tampered, now).is_err());
+        assert!(SessionTokens::new([2u8; 32])
+            .verify(&token.token, now)
+            .is_err());
+        assert!(tokens.verify("not a token", now).is_err());
+    }
+}
@@ -1,5 +1,16 @@
 use crate::funding_fee::IndexPriceSource;
+use crate::message_archive::MessageArchiveSettings;
+use crate::moderation::ModerationSettings;
+use crate::node::funding_accelerator::FundingAcceleratorSettings;
+use crate::node::oracle_announcements::AnnouncementPrefetchSettings;
+use crate::node::zombie_channels::ZombieChannelSettings;
 use crate::node::NodeSettings;
+use crate::orderbook::journal_export::JournalExportSettings;
+use crate::orderbook::matching_preference::MatchingPreferenceSettings;
+use crate::orderbook::open_order_policy::OpenOrderPolicySettings;
+use crate::orderbook::spread::SpreadSettings;
+use crate::retention::RetentionSettings;
+use crate::routes::LatencySloSettings;
 use anyhow::Context;
 use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
@@ -9,6 +20,7 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
+use xxi_node::commons::ExpirySchedule;
 use xxi_node::node::XXINodeSettings;
 
 const SETTINGS_FILE_NAME: &str = "coordinator-settings.toml";
@@ -79,6 +91,48 @@ pub struct Settings {
     /// sec   min   hour   day of month   month   day of week   year
     /// * *     *      *              *       *             *
     pub generate_funding_fee_events_scheduler: String,
+    /// A cron syntax for accruing interest on the collateral reserves of DLC channels.
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// * *     *      *              *       *             *
+    pub accrue_reserve_interest_scheduler: String,
+    /// A cron syntax for pruning old rows according to the [`RetentionSettings`].
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// * *     *      *              *       *             *
+    pub prune_data_scheduler: String,
+    /// A cron syntax for notifying traders whose DLC channel is being force-closed.
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// * *     *      *              *       *             *
+    pub notify_force_closed_channels_scheduler: String,
+    /// A cron syntax for storing a snapshot of the coordinator funds.
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// * *     *      *              *       *             *
+    pub treasury_snapshot_scheduler: String,
+    /// A cron syntax for generating the daily settlement reports of the makers.
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// * *     *      *              *       *             *
+    pub settlement_report_scheduler: String,
+    /// A cron syntax for suspending traders who exceed one of the [`ModerationSettings`] limits.
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// * *     *      *              *       *             *
+    pub moderate_traders_scheduler: String,
+    /// A cron syntax for exporting the orderbook journal of the previous day.
+    ///
+    /// The format is:
+    /// sec   min   hour   day of month   month   day of week   year
+    /// * *     *      *              *       *             *
+    pub export_orderbook_journal_scheduler: String,
 
     // Location of the settings file in the file system.
     path: PathBuf,
@@ -89,6 +143,10 @@ pub struct Settings {
     /// A list of makers who are allowed to post limit orders. This is to prevent spam.
     pub whitelisted_makers: Vec<PublicKey>,
 
+    /// How long to wait for a maker which asked to cancel on disconnect to reconnect, before all
+    /// of their orders are deleted.
+    pub cancel_on_disconnect_grace_period_secs: u64,
+
     /// The min quantity that we accept to be traded with.
     pub min_quantity: u64,
 
@@ -100,11 +158,74 @@ pub struct Settings {
     /// moment applied for taker and maker orders.
     pub order_matching_fee_rate: f32,
 
+    /// The spread added to the execution price of market orders.
+    pub spread: SpreadSettings,
+
+    /// How to choose between limit orders which could all fill a market order.
+    pub matching_preference: MatchingPreferenceSettings,
+
     /// Where to get the index price from. This value is used to calculate funding fees.
     pub index_price_source: IndexPriceSource,
 
     /// The max leverage a trader can take
     pub max_leverage: u8,
+
+    /// The number of expiries traders can choose from when placing an order, starting with the
+    /// next expiry. E.g. 2 lists the weekly and the bi-weekly expiry.
+    pub listed_expiries: usize,
+
+    /// When contracts expire and can be rolled over. Defaults to the schedule of the network,
+    /// i.e. weekly on mainnet and daily everywhere else.
+    pub expiry_schedule: Option<ExpirySchedule>,
+
+    /// If set, the on-chain settlement of a DLC channel is paused if the price attested by the
+    /// oracle diverges by more than this rate from the internal mark price at the maturity of the
+    /// event or the price attested by the second oracle.
+    pub max_settlement_price_divergence: Option<f32>,
+
+    /// Whether the payout of an expiry settlement is validated against the payout at exactly the
+    /// attested price, and the breakdown of the settlement reported to the trader.
+    pub expiry_smoothing: bool,
+
+    /// The number of days after expiry we give the trader to close an expired position
+    /// collaboratively, before we settle it on-chain using the oracle attestation.
+    pub expiry_settlement_grace_period_days: u32,
+
+    /// The annual percentage rate paid on the trader's collateral reserve in a DLC channel. The
+    /// accrued interest is credited to the trader during the next renew or rollover.
+    pub reserve_interest_apr: f32,
+
+    /// The minimum collateral of the trader in a new DLC channel and the minimum notional value of
+    /// a trade, as a multiple of the estimated cost of force-closing a DLC channel at the current
+    /// fee rate. A value of zero only enforces [`min_quantity`].
+    pub force_close_cost_multiplier: f32,
+
+    /// How to deal with DLC channels whose counterparty has been offline for a long time.
+    pub zombie_channels: ZombieChannelSettings,
+
+    /// When and how stuck funding transactions of DLC channels are accelerated.
+    pub funding_accelerator: FundingAcceleratorSettings,
+
+    /// How far ahead oracle announcements are fetched for upcoming rollovers.
+    pub announcement_prefetch: AnnouncementPrefetchSettings,
+
+    /// Whether and for how long the raw DLC messages exchanged with traders are archived.
+    pub message_archive: MessageArchiveSettings,
+
+    /// How long rows are kept in tables which would otherwise grow unbounded.
+    pub retention: RetentionSettings,
+
+    /// The latency and error rate objectives of the HTTP endpoints.
+    pub latency_slo: LatencySloSettings,
+
+    /// The limits beyond which abusive traders are suspended.
+    pub moderation: ModerationSettings,
+
+    /// The limits on the orders a trader may have open at the same time.
+    pub open_order_policy: OpenOrderPolicySettings,
+
+    /// Whether the orderbook journal is exported daily, and for how long the exports are kept.
+    pub journal_export: JournalExportSettings,
 }
 
 impl Settings {
@@ -140,6 +261,17 @@ impl Settings {
             allow_opening_positions: self.new_positions_enabled,
             maintenance_margin_rate: self.maintenance_margin_rate,
             order_matching_fee_rate: self.order_matching_fee_rate,
+            spread: self.spread,
+            matching_preference: self.matching_preference,
+            max_settlement_price_divergence: self.max_settlement_price_divergence,
+            expiry_smoothing: self.expiry_smoothing,
+            expiry_settlement_grace_period_days: self.expiry_settlement_grace_period_days,
+            reserve_interest_apr: self.reserve_interest_apr,
+            index_price_source: self.index_price_source,
+            zombie_channels: self.zombie_channels,
+            funding_accelerator: self.funding_accelerator.clone(),
+            announcement_prefetch: self.announcement_prefetch,
+            expiry_schedule: self.expiry_schedule,
         }
     }
 
@@ -158,14 +290,40 @@ impl Settings {
             update_user_bonus_status_scheduler: file.update_user_bonus_status_scheduler,
             collect_metrics_scheduler: file.collect_metrics_scheduler,
             generate_funding_fee_events_scheduler: file.generate_funding_fee_events_scheduler,
+            accrue_reserve_interest_scheduler: file.accrue_reserve_interest_scheduler,
+            prune_data_scheduler: file.prune_data_scheduler,
+            notify_force_closed_channels_scheduler: file.notify_force_closed_channels_scheduler,
+            treasury_snapshot_scheduler: file.treasury_snapshot_scheduler,
+            settlement_report_scheduler: file.settlement_report_scheduler,
+            moderate_traders_scheduler: file.moderate_traders_scheduler,
+            export_orderbook_journal_scheduler: file.export_orderbook_journal_scheduler,
             path,
             whitelist_enabled: file.whitelist_enabled,
             whitelisted_makers: file.whitelisted_makers,
+            cancel_on_disconnect_grace_period_secs: file.cancel_on_disconnect_grace_period_secs,
             min_quantity: file.min_quantity,
             maintenance_margin_rate: file.maintenance_margin_rate,
             order_matching_fee_rate: file.order_matching_fee_rate,
+            spread: file.spread,
+            matching_preference: file.matching_preference,
             index_price_source: file.index_price_source,
             max_leverage: file.max_leverage,
+            listed_expiries: file.listed_expiries,
+            expiry_schedule: file.expiry_schedule,
+            max_settlement_price_divergence: file.max_settlement_price_divergence,
+            expiry_smoothing: file.expiry_smoothing,
+            expiry_settlement_grace_period_days: file.expiry_settlement_grace_period_days,
+            reserve_interest_apr: file.reserve_interest_apr,
+            force_close_cost_multiplier: file.force_close_cost_multiplier,
+            zombie_channels: file.zombie_channels,
+            funding_accelerator: file.funding_accelerator,
+            announcement_prefetch: file.announcement_prefetch,
+            message_archive: file.message_archive,
+            retention: file.retention,
+            latency_slo: file.latency_slo,
+            moderation: file.moderation,
+            open_order_policy: file.open_order_policy,
+            journal_export: file.journal_export,
         }
     }
 }
@@ -185,17 +343,61 @@ pub struct SettingsFile {
     collect_metrics_scheduler: String,
 
     generate_funding_fee_events_scheduler: String,
+    accrue_reserve_interest_scheduler: String,
+    prune_data_scheduler: String,
+    notify_force_closed_channels_scheduler: String,
+    treasury_snapshot_scheduler: String,
+    settlement_report_scheduler: String,
+    moderate_traders_scheduler: String,
+    export_orderbook_journal_scheduler: String,
 
     whitelist_enabled: bool,
     whitelisted_makers: Vec<PublicKey>,
 
+    cancel_on_disconnect_grace_period_secs: u64,
+
     min_quantity: u64,
     maintenance_margin_rate: f32,
     order_matching_fee_rate: f32,
 
+    spread: SpreadSettings,
+
+    matching_preference: MatchingPreferenceSettings,
+
     index_price_source: IndexPriceSource,
 
     max_leverage: u8,
+
+    listed_expiries: usize,
+
+    expiry_schedule: Option<ExpirySchedule>,
+
+    max_settlement_price_divergence: Option<f32>,
+
+    expiry_smoothing: bool,
+
+    expiry_settlement_grace_period_days: u32,
+
+    reserve_interest_apr: f32,
+
+    force_close_cost_multiplier: f32,
+
+    zombie_channels: ZombieChannelSettings,
+
+    funding_accelerator: FundingAcceleratorSettings,
+
+    announcement_prefetch: AnnouncementPrefetchSettings,
+
+    message_archive: MessageArchiveSettings,
+
+    retention: RetentionSettings,
+
+    latency_slo: LatencySloSettings,
+
+    moderation: ModerationSettings,
+
+    open_order_policy: OpenOrderPolicySettings,
+    journal_export: JournalExportSettings,
 }
 
 impl From<Settings> for SettingsFile {
@@ -210,13 +412,39 @@ impl From<Settings> for SettingsFile {
             update_user_bonus_status_scheduler: value.update_user_bonus_status_scheduler,
             collect_metrics_scheduler: value.collect_metrics_scheduler,
             generate_funding_fee_events_scheduler: value.generate_funding_fee_events_scheduler,
+            accrue_reserve_interest_scheduler: value.accrue_reserve_interest_scheduler,
+            prune_data_scheduler: value.prune_data_scheduler,
+            notify_force_closed_channels_scheduler: value.notify_force_closed_channels_scheduler,
+            treasury_snapshot_scheduler: value.treasury_snapshot_scheduler,
+            settlement_report_scheduler: value.settlement_report_scheduler,
+            moderate_traders_scheduler: value.moderate_traders_scheduler,
+            export_orderbook_journal_scheduler: value.export_orderbook_journal_scheduler,
             whitelist_enabled: false,
             whitelisted_makers: value.whitelisted_makers,
+            cancel_on_disconnect_grace_period_secs: value.cancel_on_disconnect_grace_period_secs,
             min_quantity: value.min_quantity,
             maintenance_margin_rate: value.maintenance_margin_rate,
             order_matching_fee_rate: value.order_matching_fee_rate,
+            spread: value.spread,
+            matching_preference: value.matching_preference,
             index_price_source: value.index_price_source,
             max_leverage: value.max_leverage,
+            listed_expiries: value.listed_expiries,
+            expiry_schedule: value.expiry_schedule,
+            max_settlement_price_divergence: value.max_settlement_price_divergence,
+            expiry_smoothing: value.expiry_smoothing,
+            expiry_settlement_grace_period_days: value.expiry_settlement_grace_period_days,
+            reserve_interest_apr: value.reserve_interest_apr,
+            force_close_cost_multiplier: value.force_close_cost_multiplier,
+            zombie_channels: value.zombie_channels,
+            funding_accelerator: value.funding_accelerator,
+            announcement_prefetch: value.announcement_prefetch,
+            message_archive: value.message_archive,
+            retention: value.retention,
+            latency_slo: value.latency_slo,
+            moderation: value.moderation,
+            open_order_policy: value.open_order_policy,
+            journal_export: value.journal_export,
         }
     }
 }
@@ -224,7 +452,13 @@ impl From<Settings> for SettingsFile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::zombie_channels::ZombieChannelAction;
+    use crate::retention::RetainedTable;
+    use crate::retention::RetentionPolicy;
+    use crate::routes::LatencyTarget;
     use std::str::FromStr;
+    use xxi_node::node::confirmation::MinConfirmations;
+    use xxi_node::node::dlc_channel::CloseFeeRateBounds;
 
     #[test]
     fn toml_serde_roundtrip() {
@@ -236,6 +470,11 @@ mod tests {
                 fee_rate_sync_interval: std::time::Duration::from_secs(1),
                 sub_channel_manager_periodic_check_interval: std::time::Duration::from_secs(1),
                 shadow_sync_interval: std::time::Duration::from_secs(1),
+                confirmation_tracking_interval: std::time::Duration::from_secs(1),
+                chain_audit_interval: Some(std::time::Duration::from_secs(1)),
+                min_confirmations: MinConfirmations::default(),
+                close_fee_rate_bounds: CloseFeeRateBounds::default(),
+                bdk_client_concurrency: 5,
             },
             rollover_window_open_scheduler: "foo".to_string(),
             rollover_window_close_scheduler: "bar".to_string(),
@@ -244,16 +483,98 @@ mod tests {
             update_user_bonus_status_scheduler: "bazinga".to_string(),
             collect_metrics_scheduler: "42".to_string(),
             generate_funding_fee_events_scheduler: "qux".to_string(),
+            accrue_reserve_interest_scheduler: "quux".to_string(),
+            prune_data_scheduler: "corge".to_string(),
+            notify_force_closed_channels_scheduler: "grault".to_string(),
+            treasury_snapshot_scheduler: "garply".to_string(),
+            settlement_report_scheduler: "waldo".to_string(),
+            moderate_traders_scheduler: "fred".to_string(),
+            export_orderbook_journal_scheduler: "plugh".to_string(),
             whitelist_enabled: false,
             whitelisted_makers: vec![PublicKey::from_str(
                 "0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166",
             )
             .unwrap()],
+            cancel_on_disconnect_grace_period_secs: 10,
             min_quantity: 1,
             maintenance_margin_rate: 0.1,
             order_matching_fee_rate: 0.003,
+            spread: SpreadSettings {
+                long_bps: 5,
+                short_bps: 10,
+            },
+            matching_preference: MatchingPreferenceSettings {
+                reduce_exposure: true,
+                max_price_deviation_bps: 5,
+            },
             index_price_source: IndexPriceSource::Bitmex,
             max_leverage: 5,
+            listed_expiries: 2,
+            expiry_schedule: Some(ExpirySchedule::Hourly),
+            max_settlement_price_divergence: Some(0.05),
+            expiry_smoothing: true,
+            expiry_settlement_grace_period_days: 7,
+            reserve_interest_apr: 0.05,
+            force_close_cost_multiplier: 10.0,
+            zombie_channels: ZombieChannelSettings {
+                max_offline_days: 30,
+                notification_attempts: 3,
+                action: ZombieChannelAction::Park,
+            },
+            funding_accelerator: FundingAcceleratorSettings {
+                auto_accelerate: true,
+                unconfirmed_blocks: 6,
+                max_cost_sats: 50_000,
+                provider_url: Some("https://accelerator.example.com/accelerate".to_string()),
+            },
+            announcement_prefetch: AnnouncementPrefetchSettings {
+                expiries: 4,
+                alert_hours: 24,
+            },
+            message_archive: MessageArchiveSettings {
+                enabled: true,
+                retention_days: 90,
+            },
+            retention: RetentionSettings {
+                enabled: true,
+                batch_size: 1_000,
+                policies: vec![
+                    RetentionPolicy {
+                        table: RetainedTable::Orders,
+                        retention_days: 180,
+                        archive: true,
+                    },
+                    RetentionPolicy {
+                        table: RetainedTable::OrderbookJournal,
+                        retention_days: 30,
+                        archive: false,
+                    },
+                ],
+            },
+            latency_slo: LatencySloSettings {
+                objective: 0.99,
+                default_target_ms: 1_000,
+                targets: vec![LatencyTarget {
+                    endpoint: "POST /api/v2/orderbook/orders".to_string(),
+                    target_ms: 500,
+                }],
+            },
+            moderation: ModerationSettings {
+                auto_suspend: true,
+                max_market_orders_per_hour: 100,
+                max_protocol_rejections_per_day: 10,
+                suspension_hours: 24,
+            },
+            open_order_policy: OpenOrderPolicySettings {
+                enabled: true,
+                max_open_market_orders_per_side: 1,
+                max_open_quantity_per_side: Some(100_000),
+                exempt_makers: true,
+            },
+            journal_export: JournalExportSettings {
+                enabled: true,
+                retention_days: 365,
+            },
         };
 
         let serialized = toml::to_string_pretty(&original).unwrap();
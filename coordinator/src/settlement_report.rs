@@ -0,0 +1,324 @@
+//! Daily settlement reports for the makers, so that they can reconcile their fills.
+//!
+//! A report covers the fills of the limit orders of one maker on one day (UTC). Reports are
+//! generated by a scheduled job after the day has ended and stored, so that they can be fetched
+//! later on, e.g. by an accounting tool. Makers list their reports with their API key and download
+//! them through signed URLs, which do not need the API key.
+//!
+//! The report is generated from the fills alone by [`SettlementReport::generate`], so that other
+//! exports of the fills can share it.
+//!
+//! The coordinator does not grant rebates to makers, hence there are none to report.
+
+use crate::maker_earnings;
+use crate::maker_earnings::DailyEarnings;
+use crate::scheduler::Scheduler;
+use crate::session_token::constant_time_eq;
+use crate::session_token::hmac;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::PgConnection;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use time::Date;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use uuid::Uuid;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+mod db;
+
+pub use db::get_report;
+pub use db::get_reports_of_maker;
+
+/// How long a signed URL to download a report is valid.
+pub const SIGNED_URL_LIFETIME: Duration = Duration::hours(24);
+
+time::serde::format_description!(day, Date, "[year]-[month]-[day]");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettlementReport {
+    pub maker: PublicKey,
+    #[serde(with = "day")]
+    pub date: Date,
+    /// The fills of the day, oldest first.
+    pub fills: Vec<Fill>,
+    /// The order matching fees of all fills.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub fees: Amount,
+    /// How much the fills changed the position of the maker in each contract.
+    pub net_position_changes: Vec<NetPositionChange>,
+}
+
+/// A fill of a limit order of the maker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    pub match_id: Uuid,
+    pub order_id: Uuid,
+    pub contract_symbol: ContractSymbol,
+    /// The direction of the maker's order.
+    pub direction: Direction,
+    pub quantity: Decimal,
+    /// The price of the maker's order.
+    pub price: Decimal,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub matching_fee: Amount,
+    /// The index price at the time of the match, if it was known.
+    #[serde(default)]
+    pub index_price: Option<Decimal>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub filled_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetPositionChange {
+    pub contract_symbol: ContractSymbol,
+    /// Positive if the maker went long, negative if the maker went short.
+    pub quantity: Decimal,
+}
+
+/// A report of a maker, as listed to them.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettlementReportLink {
+    #[serde(with = "day")]
+    pub date: Date,
+    /// Where to download the report from, without authentication.
+    pub url: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub url_expires_at: OffsetDateTime,
+}
+
+impl SettlementReport {
+    pub fn generate(maker: PublicKey, date: Date, mut fills: Vec<Fill>) -> Self {
+        fills.sort_by_key(|fill| fill.filled_at);
+
+        let fees = fills.iter().map(|fill| fill.matching_fee).sum();
+
+        let mut net_position_changes = BTreeMap::<String, NetPositionChange>::new();
+        for fill in fills.iter() {
+            let quantity = match fill.direction {
+                Direction::Long => fill.quantity,
+                Direction::Short => -fill.quantity,
+            };
+
+            net_position_changes
+                .entry(fill.contract_symbol.to_string())
+                .or_insert(NetPositionChange {
+                    contract_symbol: fill.contract_symbol,
+                    quantity: Decimal::ZERO,
+                })
+                .quantity += quantity;
+        }
+
+        Self {
+            maker,
+            date,
+            fills,
+            fees,
+            net_position_changes: net_position_changes.into_values().collect(),
+        }
+    }
+}
+
+/// Signs the URLs to download reports with, so that a report can be downloaded without the API
+/// key of the maker.
+///
+/// Like session tokens, the URLs are not stored and remain valid across restarts of the
+/// coordinator.
+#[derive(Clone)]
+pub struct ReportUrls {
+    key: [u8; 32],
+}
+
+impl ReportUrls {
+    pub fn new(seed_key: [u8; 32]) -> Self {
+        Self {
+            key: hmac(&seed_key, b"settlement-report-url"),
+        }
+    }
+
+    pub fn link(&self, report_id: i32, date: Date, now: OffsetDateTime) -> SettlementReportLink {
+        let expires_at = now + SIGNED_URL_LIFETIME;
+        let expires = expires_at.unix_timestamp();
+
+        let signature = hex::encode(self.sign(report_id, expires));
+
+        SettlementReportLink {
+            date,
+            url: format!(
+                "/api/v2/settlement-reports/{report_id}?expires={expires}&signature={signature}"
+            ),
+            url_expires_at: expires_at,
+        }
+    }
+
+    pub fn verify(
+        &self,
+        report_id: i32,
+        expires: i64,
+        signature: &str,
+        now: OffsetDateTime,
+    ) -> Result<()> {
+        let signature = hex::decode(signature).context("Malformed signature")?;
+        let expected = self.sign(report_id, expires);
+
+        ensure!(constant_time_eq(&signature, &expected), "Invalid signature");
+
+        ensure!(now.unix_timestamp() < expires, "URL expired at {expires}");
+
+        Ok(())
+    }
+
+    fn sign(&self, report_id: i32, expires: i64) -> [u8; 32] {
+        hmac(&self.key, format!("{report_id}.{expires}").as_bytes())
+    }
+}
+
+pub async fn generate_settlement_reports_periodically(
+    scheduler: &Scheduler,
+    pool: Pool<ConnectionManager<PgConnection>>,
+    schedule: String,
+) -> Result<()> {
+    scheduler
+        .add_job("generate_settlement_reports", &schedule, move || {
+            let pool = pool.clone();
+            async move {
+                spawn_blocking(move || {
+                    let mut conn = pool.get()?;
+
+                    // The reports are for the day which has just ended.
+                    let yesterday = OffsetDateTime::now_utc().date() - Duration::days(1);
+                    generate_settlement_reports(&mut conn, yesterday)
+                })
+                .await
+                .expect("task to complete")
+            }
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Generate the reports of all makers with fills on `date`, together with their earnings.
+///
+/// Reports which were generated before are replaced, so that the job can safely be re-run.
+pub fn generate_settlement_reports(conn: &mut PgConnection, date: Date) -> Result<()> {
+    let from = date.midnight().assume_utc();
+    let to = from + Duration::days(1);
+
+    let mut fills_by_maker = BTreeMap::<PublicKey, Vec<Fill>>::new();
+    for (maker, fill) in db::get_maker_fills(conn, from, to)? {
+        fills_by_maker.entry(maker).or_default().push(fill);
+    }
+
+    for (maker, fills) in fills_by_maker {
+        let report = SettlementReport::generate(maker, date, fills);
+
+        db::upsert(conn, &report)
+            .with_context(|| format!("Failed to store settlement report of {maker}"))?;
+
+        for earnings in DailyEarnings::from_report(&report) {
+            maker_earnings::upsert(conn, maker, &earnings)
+                .with_context(|| format!("Failed to store earnings of {maker}"))?;
+        }
+
+        tracing::info!(
+            %maker,
+            %date,
+            fills = report.fills.len(),
+            fees = %report.fees,
+            "Generated settlement report"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::macros::date;
+    use time::macros::datetime;
+
+    fn maker() -> PublicKey {
+        PublicKey::from_str("02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655")
+            .unwrap()
+    }
+
+    fn fill(direction: Direction, quantity: Decimal, filled_at: OffsetDateTime) -> Fill {
+        Fill {
+            match_id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            direction,
+            quantity,
+            price: dec!(60_000),
+            matching_fee: Amount::from_sat(500),
+            index_price: Some(dec!(60_010)),
+            filled_at,
+        }
+    }
+
+    #[test]
+    fn report_sums_up_the_fills_of_the_day() {
+        let second = fill(Direction::Short, dec!(300), datetime!(2024-07-15 18:00 UTC));
+        let first = fill(
+            Direction::Long,
+            dec!(1_000),
+            datetime!(2024-07-15 09:00 UTC),
+        );
+
+        let report = SettlementReport::generate(
+            maker(),
+            date!(2024 - 07 - 15),
+            vec![second.clone(), first.clone()],
+        );
+
+        assert_eq!(report.fills, vec![first, second]);
+        assert_eq!(report.fees, Amount::from_sat(1_000));
+        assert_eq!(
+            report.net_position_changes,
+            vec![NetPositionChange {
+                contract_symbol: ContractSymbol::BtcUsd,
+                quantity: dec!(700),
+            }]
+        );
+    }
+
+    #[test]
+    fn day_without_fills_has_an_empty_report() {
+        let report = SettlementReport::generate(maker(), date!(2024 - 07 - 15), vec![]);
+
+        assert!(report.fills.is_empty());
+        assert_eq!(report.fees, Amount::ZERO);
+        assert!(report.net_position_changes.is_empty());
+    }
+
+    #[test]
+    fn signed_url_verifies_until_it_expires() {
+        let urls = ReportUrls::new([1u8; 32]);
+        let now = OffsetDateTime::now_utc();
+        let expires = (now + SIGNED_URL_LIFETIME).unix_timestamp();
+
+        let link = urls.link(42, date!(2024 - 07 - 15), now);
+        let signature = link.url.split("signature=").nth(1).unwrap();
+
+        assert!(urls.verify(42, expires, signature, now).is_ok());
+        assert!(urls.verify(43, expires, signature, now).is_err());
+        assert!(urls.verify(42, expires + 1, signature, now).is_err());
+        assert!(urls
+            .verify(42, expires, signature, now + SIGNED_URL_LIFETIME)
+            .is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::db::positions::ContractSymbol;
+use crate::orderbook::db::custom_types::Direction;
+use crate::orderbook::db::custom_types::MatchState;
+use crate::orderbook::db::custom_types::OrderType;
+use crate::schema::matches;
+use crate::schema::orders;
+use crate::schema::settlement_reports;
+use crate::settlement_report::Fill;
+use crate::settlement_report::SettlementReport;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use diesel::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use time::Date;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// The fills of the limit orders of all makers in `[from, to)`.
+pub(super) fn get_maker_fills(
+    conn: &mut PgConnection,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+) -> Result<Vec<(PublicKey, Fill)>> {
+    // The maker's own match remains `Pending`, hence we look at the `Filled` matches of the takers
+    // instead, which point to the maker's limit order.
+    let fills = matches::table
+        .inner_join(orders::table.on(orders::trader_order_id.eq(matches::match_order_id)))
+        .filter(
+            orders::order_type
+                .eq(OrderType::Limit)
+                .and(matches::match_state.eq(MatchState::Filled))
+                .and(matches::updated_at.ge(from))
+                .and(matches::updated_at.lt(to)),
+        )
+        .select((
+            matches::id,
+            matches::match_trader_id,
+            matches::match_order_id,
+            orders::contract_symbol,
+            orders::direction,
+            matches::quantity,
+            orders::price,
+            matches::matching_fee_sats,
+            matches::index_price,
+            matches::updated_at,
+        ))
+        .load::<(
+            Uuid,
+            String,
+            Uuid,
+            ContractSymbol,
+            Direction,
+            f32,
+            f32,
+            i64,
+            Option<f32>,
+            OffsetDateTime,
+        )>(conn)?;
+
+    fills
+        .into_iter()
+        .map(
+            |(
+                match_id,
+                maker,
+                order_id,
+                contract_symbol,
+                direction,
+                quantity,
+                price,
+                matching_fee_sats,
+                index_price,
+                filled_at,
+            )| {
+                let maker = PublicKey::from_str(&maker).context("Invalid maker pubkey")?;
+
+                let fill = Fill {
+                    match_id,
+                    order_id,
+                    contract_symbol: contract_symbol.into(),
+                    direction: direction.into(),
+                    quantity: Decimal::from_f32(quantity).expect("to fit into Decimal"),
+                    price: Decimal::from_f32(price).expect("to fit into Decimal"),
+                    matching_fee: Amount::from_sat(matching_fee_sats as u64),
+                    index_price: index_price.and_then(Decimal::from_f32),
+                    filled_at,
+                };
+
+                Ok((maker, fill))
+            },
+        )
+        .collect()
+}
+
+pub(super) fn upsert(conn: &mut PgConnection, report: &SettlementReport) -> Result<()> {
+    let json = serde_json::to_string(report)?;
+
+    diesel::insert_into(settlement_reports::table)
+        .values((
+            settlement_reports::maker_pubkey.eq(report.maker.to_string()),
+            settlement_reports::date.eq(report.date),
+            settlement_reports::report.eq(&json),
+        ))
+        .on_conflict((settlement_reports::maker_pubkey, settlement_reports::date))
+        .do_update()
+        .set((
+            settlement_reports::report.eq(&json),
+            settlement_reports::created_at.eq(OffsetDateTime::now_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The IDs and dates of the most recent reports of the maker, newest first.
+pub fn get_reports_of_maker(
+    conn: &mut PgConnection,
+    maker: PublicKey,
+    limit: i64,
+) -> QueryResult<Vec<(i32, Date)>> {
+    settlement_reports::table
+        .filter(settlement_reports::maker_pubkey.eq(maker.to_string()))
+        .order_by(settlement_reports::date.desc())
+        .limit(limit)
+        .select((settlement_reports::id, settlement_reports::date))
+        .load(conn)
+}
+
+pub fn get_report(conn: &mut PgConnection, id: i32) -> Result<Option<SettlementReport>> {
+    let report: Option<String> = settlement_reports::table
+        .find(id)
+        .select(settlement_reports::report)
+        .first(conn)
+        .optional()?;
+
+    report
+        .map(|report| {
+            serde_json::from_str(&report)
+                .with_context(|| format!("Failed to parse settlement report {id}"))
+        })
+        .transpose()
+}
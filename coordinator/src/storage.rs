@@ -1,30 +1,189 @@
+use crate::db;
+use crate::replication::StorageReplication;
+use anyhow::Result;
+use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::Pool;
+use diesel::Connection;
+use diesel::PgConnection;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 use xxi_node::storage::sled::SledStorageProvider;
 use xxi_node::storage::DlcStoreProvider;
 use xxi_node::storage::KeyValue;
+use xxi_node::storage::Op;
 
 #[derive(Clone)]
 pub struct CoordinatorTenTenOneStorage {
-    pub dlc_storage: Arc<SledStorageProvider>,
+    pub dlc_storage: DlcStorage,
     pub data_dir: String,
+    replication: Option<StorageReplication>,
+}
+
+/// Where the DLC channels and contracts are kept.
+#[derive(Clone)]
+pub enum DlcStorage {
+    Sled(Arc<SledStorageProvider>),
+    Postgres(PostgresStorageProvider),
+}
+
+/// Keeps the DLC channels and contracts in the `dlc_store` table, next to the rest of the data of
+/// the coordinator. This way they are covered by the database backups.
+#[derive(Clone)]
+pub struct PostgresStorageProvider {
+    pool: Pool<ConnectionManager<PgConnection>>,
 }
 
 impl CoordinatorTenTenOneStorage {
     pub fn new(data_dir: String) -> CoordinatorTenTenOneStorage {
-        let data_dir = PathBuf::from(data_dir);
+        let data_dir = create_data_dir(data_dir);
+        let dlc_storage = Arc::new(SledStorageProvider::new(&data_dir));
 
-        if !data_dir.exists() {
-            fs::create_dir_all(data_dir.as_path()).expect("Failed to create data dir");
+        CoordinatorTenTenOneStorage {
+            dlc_storage: DlcStorage::Sled(dlc_storage),
+            data_dir,
+            replication: None,
         }
+    }
 
-        let data_dir = data_dir.to_string_lossy().to_string();
-        let dlc_storage = Arc::new(SledStorageProvider::new(&data_dir));
+    /// Keep the DLC storage in the database.
+    ///
+    /// If the database does not contain a DLC storage yet, the one in sled is imported from the
+    /// data directory, so that an existing coordinator can switch over by just restarting.
+    pub fn postgres(
+        data_dir: String,
+        pool: Pool<ConnectionManager<PgConnection>>,
+    ) -> Result<CoordinatorTenTenOneStorage> {
+        let data_dir = create_data_dir(data_dir);
+        let dlc_storage = PostgresStorageProvider::new(pool);
 
-        CoordinatorTenTenOneStorage {
-            dlc_storage,
+        // Sled keeps its database in the `db` file of the data directory.
+        if PathBuf::from(&data_dir).join("db").exists() && dlc_storage.is_empty()? {
+            let sled = SledStorageProvider::new(&data_dir);
+            let imported = dlc_storage.import(&sled)?;
+
+            tracing::info!(%imported, "Imported DLC storage from sled into the database");
+        }
+
+        Ok(CoordinatorTenTenOneStorage {
+            dlc_storage: DlcStorage::Postgres(dlc_storage),
             data_dir,
+            replication: None,
+        })
+    }
+
+    /// Record all changes to the DLC storage for replication to the warm standby.
+    pub fn with_replication(self, replication: StorageReplication) -> Self {
+        Self {
+            replication: Some(replication),
+            ..self
+        }
+    }
+}
+
+impl PostgresStorageProvider {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+        let is_empty = db::dlc_store::is_empty(&mut conn)?;
+
+        Ok(is_empty)
+    }
+
+    /// Copy all key value pairs of the sled storage in a single transaction.
+    ///
+    /// Returns the number of imported key value pairs.
+    pub fn import(&self, sled: &SledStorageProvider) -> Result<usize> {
+        let export = sled.export();
+
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            for entry in export.iter() {
+                db::dlc_store::upsert(conn, entry.kind, &entry.key, &entry.value)?;
+            }
+
+            diesel::QueryResult::Ok(())
+        })?;
+
+        Ok(export.len())
+    }
+}
+
+impl DlcStoreProvider for PostgresStorageProvider {
+    fn read(&self, kind: u8, key: Option<Vec<u8>>) -> Result<Vec<KeyValue>> {
+        let mut conn = self.pool.get()?;
+        let key_values = db::dlc_store::get(&mut conn, kind, key.as_deref())?
+            .into_iter()
+            .map(|(key, value)| KeyValue { key, value })
+            .collect();
+
+        Ok(key_values)
+    }
+
+    fn write(&self, kind: u8, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::dlc_store::upsert(&mut conn, kind, &key, &value)?;
+
+        Ok(())
+    }
+
+    fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        db::dlc_store::delete(&mut conn, kind, key.as_deref())?;
+
+        Ok(())
+    }
+
+    fn write_batch(&self, ops: Vec<Op>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            for op in ops.iter() {
+                match op {
+                    Op::Write { kind, key, value } => {
+                        db::dlc_store::upsert(conn, *kind, key, value)?;
+                    }
+                    Op::Delete { kind, key } => {
+                        db::dlc_store::delete(conn, *kind, key.as_deref())?;
+                    }
+                }
+            }
+
+            diesel::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+impl DlcStoreProvider for DlcStorage {
+    fn read(&self, kind: u8, key: Option<Vec<u8>>) -> Result<Vec<KeyValue>> {
+        match self {
+            DlcStorage::Sled(sled) => sled.read(kind, key),
+            DlcStorage::Postgres(postgres) => postgres.read(kind, key),
+        }
+    }
+
+    fn write(&self, kind: u8, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        match self {
+            DlcStorage::Sled(sled) => sled.write(kind, key, value),
+            DlcStorage::Postgres(postgres) => postgres.write(kind, key, value),
+        }
+    }
+
+    fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> Result<()> {
+        match self {
+            DlcStorage::Sled(sled) => sled.delete(kind, key),
+            DlcStorage::Postgres(postgres) => postgres.delete(kind, key),
+        }
+    }
+
+    fn write_batch(&self, ops: Vec<Op>) -> Result<()> {
+        match self {
+            DlcStorage::Sled(sled) => sled.write_batch(ops),
+            DlcStorage::Postgres(postgres) => postgres.write_batch(ops),
         }
     }
 }
@@ -35,10 +194,42 @@ impl DlcStoreProvider for CoordinatorTenTenOneStorage {
     }
 
     fn write(&self, kind: u8, key: Vec<u8>, value: Vec<u8>) -> anyhow::Result<()> {
-        self.dlc_storage.write(kind, key, value)
+        self.dlc_storage.write(kind, key.clone(), value.clone())?;
+
+        if let Some(replication) = &self.replication {
+            replication.record_write(kind, &key, &value);
+        }
+
+        Ok(())
     }
 
     fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> anyhow::Result<()> {
-        self.dlc_storage.delete(kind, key)
+        self.dlc_storage.delete(kind, key.clone())?;
+
+        if let Some(replication) = &self.replication {
+            replication.record_delete(kind, key.as_deref());
+        }
+
+        Ok(())
     }
+
+    fn write_batch(&self, ops: Vec<Op>) -> anyhow::Result<()> {
+        self.dlc_storage.write_batch(ops.clone())?;
+
+        if let Some(replication) = &self.replication {
+            replication.record_batch(&ops);
+        }
+
+        Ok(())
+    }
+}
+
+fn create_data_dir(data_dir: String) -> String {
+    let data_dir = PathBuf::from(data_dir);
+
+    if !data_dir.exists() {
+        fs::create_dir_all(data_dir.as_path()).expect("Failed to create data dir");
+    }
+
+    data_dir.to_string_lossy().to_string()
 }
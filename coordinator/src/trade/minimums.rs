@@ -0,0 +1,295 @@
+use crate::db;
+use crate::node::Node;
+use crate::orderbook;
+use crate::position::models::PositionState;
+use anyhow::ensure;
+use anyhow::Result;
+use bitcoin::Amount;
+use diesel::PgConnection;
+use lightning::chain::chaininterface::ConfirmationTarget;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy;
+use xxi_node::cfd::calculate_margin;
+use xxi_node::commons::BestPrice;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+use xxi_node::commons::NewMarketOrder;
+use xxi_node::node::dlc_channel::estimated_dlc_channel_fee_reserve;
+
+/// The smallest trade and DLC channel the coordinator accepts in the current fee environment.
+///
+/// If on-chain fees are high, a small DLC channel is not worth enforcing on-chain, as the cost of
+/// force-closing it would eat up most of its collateral. Hence, the minimums scale with the
+/// estimated cost of force-closing a DLC channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeMinimums {
+    /// The smallest allowed amount of contracts.
+    pub min_quantity: u64,
+    /// The smallest allowed collateral of the trader in a new DLC channel, i.e. margin plus
+    /// collateral reserve.
+    pub min_channel_collateral: Amount,
+}
+
+impl TradeMinimums {
+    /// Derive the minimums from the estimated cost of force-closing a DLC channel at
+    /// `fee_rate_sats_per_vb`.
+    ///
+    /// Both the trader's channel collateral and the notional value of a trade must be at least
+    /// `force_close_cost_multiplier` times the force-close cost. The minimum quantity never drops
+    /// below the configured `min_quantity`, which is also used if there is no `price` to convert
+    /// the notional value into contracts.
+    pub fn new(
+        fee_rate_sats_per_vb: f64,
+        price: Option<Decimal>,
+        min_quantity: u64,
+        force_close_cost_multiplier: f32,
+    ) -> Self {
+        let force_close_cost = estimated_dlc_channel_fee_reserve(fee_rate_sats_per_vb);
+
+        let min_channel_collateral = Decimal::from(force_close_cost.to_sat())
+            * Decimal::try_from(force_close_cost_multiplier.max(0.0))
+                .expect("multiplier to fit into decimal");
+        let min_channel_collateral = min_channel_collateral
+            .round_dp_with_strategy(0, RoundingStrategy::AwayFromZero)
+            .to_u64()
+            .expect("to fit into u64");
+
+        let min_quantity = match price {
+            Some(price) => {
+                let min_notional =
+                    Decimal::from(min_channel_collateral) / Decimal::from(100_000_000);
+                let quantity = (min_notional * price)
+                    .round_dp_with_strategy(0, RoundingStrategy::AwayFromZero)
+                    .to_u64()
+                    .expect("to fit into u64");
+
+                quantity.max(min_quantity)
+            }
+            None => min_quantity,
+        };
+
+        Self {
+            min_quantity,
+            min_channel_collateral: Amount::from_sat(min_channel_collateral),
+        }
+    }
+}
+
+/// Get the trade minimums based on the current fee rate and the best price in the orderbook.
+pub fn get_trade_minimums(
+    node: &Node,
+    conn: &mut PgConnection,
+    min_quantity: u64,
+    force_close_cost_multiplier: f32,
+) -> Result<TradeMinimums> {
+    let (fee_rate, price) = get_fee_rate_and_price(node, conn, ContractSymbol::BtcUsd)?;
+
+    Ok(TradeMinimums::new(
+        fee_rate,
+        price,
+        min_quantity,
+        force_close_cost_multiplier,
+    ))
+}
+
+/// Reject a market order which is smaller than the current trade minimums.
+///
+/// If the order opens a new DLC channel, `trader_reserve` is the collateral reserve the trader
+/// brings in addition to the margin.
+///
+/// Orders reducing or closing the trader's open position are only checked against the configured
+/// `min_quantity`, as otherwise a rise in fees could lock the trader into their position.
+pub fn validate_market_order(
+    node: &Node,
+    conn: &mut PgConnection,
+    order: &NewMarketOrder,
+    trader_reserve: Option<Amount>,
+    min_quantity: u64,
+    force_close_cost_multiplier: f32,
+) -> Result<()> {
+    let position = db::positions::Position::get_position_by_trader(
+        conn,
+        order.trader_id,
+        vec![PositionState::Open],
+    )?;
+
+    if let Some(position) = position {
+        if position.contract_symbol == order.contract_symbol
+            && reduces_position(order, position.trader_direction, position.quantity)
+        {
+            ensure!(
+                order.quantity >= Decimal::from(min_quantity),
+                "Order quantity of {} contracts is below the minimum of {min_quantity} contracts",
+                order.quantity,
+            );
+
+            return Ok(());
+        }
+    }
+
+    let (fee_rate, price) = get_fee_rate_and_price(node, conn, order.contract_symbol)?;
+    let minimums = TradeMinimums::new(fee_rate, price, min_quantity, force_close_cost_multiplier);
+
+    ensure!(
+        order.quantity >= Decimal::from(minimums.min_quantity),
+        "Order quantity of {} contracts is below the minimum of {} contracts",
+        order.quantity,
+        minimums.min_quantity
+    );
+
+    if let (Some(trader_reserve), Some(price)) = (trader_reserve, price) {
+        let margin = calculate_margin(
+            price,
+            order.quantity.to_f32().expect("quantity to fit into f32"),
+            order.leverage.to_f32().expect("leverage to fit into f32"),
+        );
+
+        ensure!(
+            margin + trader_reserve >= minimums.min_channel_collateral,
+            "Channel collateral of {} is below the minimum of {}",
+            margin + trader_reserve,
+            minimums.min_channel_collateral
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether the order reduces or closes a position in `position_direction` with
+/// `position_quantity` contracts, without opening a position in the other direction.
+fn reduces_position(
+    order: &NewMarketOrder,
+    position_direction: Direction,
+    position_quantity: f32,
+) -> bool {
+    let position_quantity =
+        Decimal::try_from(position_quantity).expect("position quantity to fit into decimal");
+
+    order.direction != position_direction && order.quantity <= position_quantity
+}
+
+fn get_fee_rate_and_price(
+    node: &Node,
+    conn: &mut PgConnection,
+    contract_symbol: ContractSymbol,
+) -> Result<(f64, Option<Decimal>)> {
+    let fee_rate = node
+        .inner
+        .fee_rate_estimator
+        .get(ConfirmationTarget::Normal)
+        .as_sat_per_vb() as f64;
+
+    let best_price = orderbook::db::orders::get_best_price(conn, contract_symbol)?;
+
+    Ok((fee_rate, mid_price(&best_price)))
+}
+
+fn mid_price(best_price: &BestPrice) -> Option<Decimal> {
+    match (best_price.bid, best_price.ask) {
+        (Some(bid), Some(ask)) => Some((bid + ask) / Decimal::TWO),
+        (Some(price), None) | (None, Some(price)) => Some(price),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::PublicKey;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+
+    #[test]
+    fn minimums_scale_with_fee_rate() {
+        let low = TradeMinimums::new(1.0, Some(dec!(50_000)), 1, 10.0);
+        let high = TradeMinimums::new(100.0, Some(dec!(50_000)), 1, 10.0);
+
+        assert!(high.min_channel_collateral > low.min_channel_collateral);
+        assert!(high.min_quantity > low.min_quantity);
+        assert_eq!(
+            high.min_channel_collateral,
+            estimated_dlc_channel_fee_reserve(100.0) * 10
+        );
+    }
+
+    #[test]
+    fn min_quantity_covers_min_channel_collateral() {
+        let minimums = TradeMinimums::new(50.0, Some(dec!(60_000)), 1, 10.0);
+
+        let min_notional = Decimal::from(minimums.min_channel_collateral.to_sat())
+            / Decimal::from(100_000_000)
+            * dec!(60_000);
+
+        assert!(Decimal::from(minimums.min_quantity) >= min_notional);
+        assert!(Decimal::from(minimums.min_quantity - 1) < min_notional);
+    }
+
+    #[test]
+    fn configured_min_quantity_is_lower_bound() {
+        let minimums = TradeMinimums::new(1.0, Some(dec!(50_000)), 100, 10.0);
+
+        assert_eq!(minimums.min_quantity, 100);
+    }
+
+    #[test]
+    fn configured_min_quantity_without_price() {
+        let minimums = TradeMinimums::new(100.0, None, 5, 10.0);
+
+        assert_eq!(minimums.min_quantity, 5);
+    }
+
+    #[test]
+    fn zero_multiplier_disables_scaling() {
+        let minimums = TradeMinimums::new(100.0, Some(dec!(50_000)), 1, 0.0);
+
+        assert_eq!(
+            minimums,
+            TradeMinimums {
+                min_quantity: 1,
+                min_channel_collateral: Amount::ZERO,
+            }
+        );
+    }
+
+    #[test]
+    fn order_in_opposite_direction_reduces_position() {
+        let order = dummy_order(Direction::Short, dec!(100));
+
+        assert!(reduces_position(&order, Direction::Long, 100.0));
+        assert!(reduces_position(&order, Direction::Long, 500.0));
+    }
+
+    #[test]
+    fn order_flipping_position_does_not_reduce_it() {
+        let order = dummy_order(Direction::Short, dec!(100));
+
+        assert!(!reduces_position(&order, Direction::Long, 50.0));
+    }
+
+    #[test]
+    fn order_in_same_direction_does_not_reduce_position() {
+        let order = dummy_order(Direction::Long, dec!(100));
+
+        assert!(!reduces_position(&order, Direction::Long, 500.0));
+    }
+
+    fn dummy_order(direction: Direction, quantity: Decimal) -> NewMarketOrder {
+        NewMarketOrder {
+            id: Uuid::new_v4(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity,
+            trader_id: PublicKey::from_str(
+                "0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166",
+            )
+            .unwrap(),
+            direction,
+            leverage: dec!(2),
+            expiry: OffsetDateTime::now_utc(),
+            stable: false,
+            contract_expiry: None,
+        }
+    }
+}
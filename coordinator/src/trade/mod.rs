@@ -2,9 +2,13 @@ use crate::compute_relative_contracts;
 use crate::db;
 use crate::decimal_from_f32;
 use crate::dlc_protocol;
+use crate::dlc_protocol::DlcProtocolState;
+use crate::dlc_protocol::DuplicateDlcProtocol;
 use crate::funding_fee::funding_fee_from_funding_fee_events;
 use crate::funding_fee::get_outstanding_funding_fee_events;
+use crate::lightning_withdrawal;
 use crate::message::OrderbookMessage;
+use crate::node::invoice;
 use crate::node::Node;
 use crate::orderbook::db::matches;
 use crate::orderbook::db::orders;
@@ -12,6 +16,8 @@ use crate::payout_curve;
 use crate::position::models::NewPosition;
 use crate::position::models::Position;
 use crate::position::models::PositionState;
+use crate::reserve_interest;
+use crate::reserve_top_up;
 use anyhow::anyhow;
 use anyhow::bail;
 use anyhow::ensure;
@@ -41,26 +47,33 @@ use tokio::task::spawn_blocking;
 use uuid::Uuid;
 use xxi_node::bitcoin_conversion::to_secp_pk_29;
 use xxi_node::bitcoin_conversion::to_xonly_pk_29;
-use xxi_node::cfd::calculate_long_liquidation_price;
+use xxi_node::cfd::calculate_liquidation_price;
 use xxi_node::cfd::calculate_margin;
-use xxi_node::cfd::calculate_pnl;
-use xxi_node::cfd::calculate_short_liquidation_price;
+use xxi_node::cfd::calculate_pnl_for_symbol;
 use xxi_node::commons;
+use xxi_node::commons::round_collateral_reserve;
+use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::Direction;
 use xxi_node::commons::MatchState;
 use xxi_node::commons::Message;
+use xxi_node::commons::OracleEventId;
 use xxi_node::commons::OrderState;
+use xxi_node::commons::ReserveRounding;
 use xxi_node::commons::TradeAndChannelParams;
 use xxi_node::commons::TradeParams;
+use xxi_node::message_handler::RejectReason;
 use xxi_node::message_handler::TenTenOneMessage;
 use xxi_node::message_handler::TenTenOneReject;
+use xxi_node::node::confirmation::ChannelOperation;
 use xxi_node::node::dlc_channel::estimated_dlc_channel_fee_reserve;
 use xxi_node::node::dlc_channel::estimated_funding_transaction_fee;
 use xxi_node::node::event::NodeEvent;
 use xxi_node::node::signed_channel_state_name;
 use xxi_node::node::ProtocolId;
 
+pub mod minimums;
 pub mod models;
+pub mod simulation;
 pub mod websocket;
 
 enum TradeAction {
@@ -153,7 +166,7 @@ impl TradeExecutor {
                     if let Err(e) = self.settle_invoice(trader_id, order_id).await {
                         tracing::error!(%trader_id, %order_id, "Failed to settle invoice with provided pre_image. Cancelling offer. Error: {e:#}");
 
-                        if let Err(e) = self.cancel_offer(trader_id).await {
+                        if let Err(e) = self.cancel_offer(trader_id, order_id).await {
                             tracing::error!(%trader_id, %order_id, "Failed to cancel offer. Error: {e:#}");
                         }
 
@@ -166,6 +179,7 @@ impl TradeExecutor {
                             message: Message::TradeError {
                                 order_id,
                                 error: e.into(),
+                                localized: None,
                             },
                             notification: None,
                         };
@@ -184,13 +198,47 @@ impl TradeExecutor {
                     .event_handler
                     .publish(NodeEvent::SendLastDlcMessage { peer: trader_id });
             }
+            Err(e) if e.downcast_ref::<DuplicateDlcProtocol>().is_some() => {
+                // The match is already being (or has been) executed. The outcome of the original
+                // execution must not be overwritten, so the order is left as it is.
+                let DuplicateDlcProtocol { protocol_id, state } = e
+                    .downcast_ref::<DuplicateDlcProtocol>()
+                    .expect("checked above");
+
+                match state {
+                    Some(DlcProtocolState::Success) => tracing::info!(
+                        %trader_id,
+                        %order_id,
+                        %protocol_id,
+                        "Ignoring duplicate trade execution, the match has already been executed"
+                    ),
+                    Some(DlcProtocolState::Pending) => tracing::info!(
+                        %trader_id,
+                        %order_id,
+                        %protocol_id,
+                        "Ignoring duplicate trade execution, the match is still being executed"
+                    ),
+                    None => tracing::info!(
+                        %trader_id,
+                        %order_id,
+                        %protocol_id,
+                        "Ignoring duplicate trade execution, the match has already been claimed"
+                    ),
+                    Some(DlcProtocolState::Failed) => tracing::warn!(
+                        %trader_id,
+                        %order_id,
+                        %protocol_id,
+                        "Ignoring duplicate trade execution, the match has already failed"
+                    ),
+                }
+            }
             Err(e) => {
                 tracing::error!(%trader_id, %order_id,"Failed to execute trade. Error: {e:#}");
 
                 if params.external_funding.is_some() {
                     // TODO(holzeis): It might make sense to do this for any failed offer to
                     // unreserve potentially reserved utxos.
-                    if let Err(e) = self.cancel_offer(trader_id).await {
+                    if let Err(e) = self.cancel_offer(trader_id, order_id).await {
                         tracing::error!(%trader_id, %order_id, "Failed to cancel offer. Error: {e:#}");
                     }
 
@@ -210,6 +258,7 @@ impl TradeExecutor {
                     message: Message::TradeError {
                         order_id,
                         error: e.into(),
+                        localized: None,
                     },
                     notification: None,
                 };
@@ -221,11 +270,23 @@ impl TradeExecutor {
     }
 
     /// Settles the accepted invoice for the given trader
+    ///
+    /// Fails if the reservation of the invoice has expired, as the offer backed by the invoice is
+    /// cancelled by [`invoice::reconcile_expired_reservations`] then.
     async fn settle_invoice(&self, trader: PublicKey, order_id: Uuid) -> Result<()> {
         let pre_image = spawn_blocking({
             let pool = self.node.pool.clone();
             move || {
                 let mut conn = pool.get()?;
+
+                let reserved_until =
+                    db::hodl_invoice::get_reserved_until_by_order_id(&mut conn, order_id)?
+                        .context("Missing reservation")?;
+                ensure!(
+                    OffsetDateTime::now_utc() < reserved_until,
+                    "Reservation expired at {reserved_until}"
+                );
+
                 let pre_image = db::hodl_invoice::get_pre_image_by_order_id(&mut conn, order_id)?;
 
                 anyhow::Ok(pre_image)
@@ -242,7 +303,7 @@ impl TradeExecutor {
     }
 
     /// Cancels a potential pending offer if the proposal failed.
-    async fn cancel_offer(&self, trader: PublicKey) -> Result<()> {
+    async fn cancel_offer(&self, trader: PublicKey, order_id: Uuid) -> Result<()> {
         if let Some(channel) = self
             .node
             .inner
@@ -256,17 +317,14 @@ impl TradeExecutor {
                         timestamp: OffsetDateTime::now_utc().unix_timestamp() as u64,
                         reference_id: None,
                     },
+                    order_id: Some(order_id),
+                    reason: RejectReason::Cancelled,
                 }),
             )?;
 
             spawn_blocking({
-                let pool = self.node.pool.clone();
-                move || {
-                    let mut conn = pool.get()?;
-                    db::last_outbound_dlc_message::delete(&mut conn, &trader)?;
-
-                    anyhow::Ok(())
-                }
+                let node = self.node.inner.clone();
+                move || node.delete_last_outbound_dlc_message(&trader)
             })
             .await??;
         }
@@ -307,6 +365,13 @@ impl TradeExecutor {
 
         let order_id = params.trade_params.filled_with.order_id;
         let trader_id = params.trade_params.pubkey;
+
+        // A match must only be executed once. If its DLC protocol has already been claimed, we
+        // must not touch the order, the position or the DLC channel again.
+        let protocol_id = trade_protocol_id(&params.trade_params)?;
+        dlc_protocol::DlcProtocolExecutor::new(self.node.pool.clone())
+            .claim_trade_protocol(protocol_id, order_id)?;
+
         let order =
             orders::get_with_id(&mut connection, order_id)?.context("Could not find order")?;
         let is_stable_order = order.stable;
@@ -321,6 +386,10 @@ impl TradeExecutor {
             order.order_state
         );
 
+        self.node.kill_switch.ensure_trading_allowed()?;
+        self.node.quiesce.ensure_protocols_allowed()?;
+        self.node.moderation.ensure_allowed(trader_id)?;
+
         tracing::info!(%trader_id, %order_id, "Executing match");
 
         let trade_action = self.determine_trade_action(&mut connection, params).await?;
@@ -331,6 +400,13 @@ impl TradeExecutor {
             "Trading is disabled except for closing positions"
         );
 
+        if matches!(
+            trade_action,
+            TradeAction::OpenDlcChannel | TradeAction::OpenSingleFundedChannel { .. }
+        ) {
+            self.node.kill_switch.ensure_new_channels_allowed()?;
+        }
+
         match trade_action {
             TradeAction::OpenDlcChannel => {
                 let collateral_reserve_coordinator = params
@@ -339,9 +415,12 @@ impl TradeExecutor {
                 let collateral_reserve_trader = params
                     .trader_reserve
                     .context("Missing trader collateral reserve")?;
+                let collateral_reserve_trader =
+                    round_collateral_reserve(collateral_reserve_trader, ReserveRounding::Down);
 
                 self.open_dlc_channel(
                     &mut connection,
+                    protocol_id,
                     &params.trade_params,
                     collateral_reserve_coordinator,
                     collateral_reserve_trader,
@@ -352,6 +431,13 @@ impl TradeExecutor {
                 .context("Failed to open DLC channel")?;
             }
             TradeAction::OpenSingleFundedChannel { external_funding } => {
+                // We must not lock up funds in a DLC offer if the payment backing it can not be
+                // claimed.
+                let r_hash = db::hodl_invoice::get_r_hash_by_order_id(&mut connection, order_id)?;
+                invoice::ensure_invoice_accepted(&self.node.lnd_bridge, &r_hash, external_funding)
+                    .await
+                    .context("Failed to verify external funding")?;
+
                 let collateral_reserve_coordinator = params
                     .coordinator_reserve
                     .context("Missing coordinator collateral reserve")?;
@@ -389,9 +475,12 @@ impl TradeExecutor {
                             + channel_fee_reserve,
                     )
                     .context("Not enough external funds to open position")?;
+                let collateral_reserve_trader =
+                    round_collateral_reserve(collateral_reserve_trader, ReserveRounding::Up);
 
                 self.open_dlc_channel(
                     &mut connection,
+                    protocol_id,
                     &params.trade_params,
                     collateral_reserve_coordinator,
                     collateral_reserve_trader,
@@ -408,6 +497,7 @@ impl TradeExecutor {
             } => self
                 .open_position(
                     &mut connection,
+                    protocol_id,
                     channel_id,
                     &params.trade_params,
                     own_payout,
@@ -422,6 +512,7 @@ impl TradeExecutor {
             } => self
                 .start_closing_position(
                     &mut connection,
+                    protocol_id,
                     order,
                     &position,
                     &params.trade_params,
@@ -436,6 +527,7 @@ impl TradeExecutor {
             } => self
                 .resize_position(
                     &mut connection,
+                    protocol_id,
                     channel_id,
                     &position,
                     &params.trade_params,
@@ -448,9 +540,11 @@ impl TradeExecutor {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn open_dlc_channel(
         &self,
         conn: &mut PgConnection,
+        protocol_id: ProtocolId,
         trade_params: &TradeParams,
         collateral_reserve_coordinator: Amount,
         collateral_reserve_trader: Amount,
@@ -467,9 +561,14 @@ impl TradeExecutor {
 
         let order_matching_fee = trade_params.order_matching_fee();
 
-        // The coordinator gets the `order_matching_fee` directly in the collateral reserve.
-        let collateral_reserve_with_fee_coordinator =
-            collateral_reserve_coordinator + order_matching_fee;
+        // The coordinator gets the `order_matching_fee` directly in the collateral reserve. If that
+        // reserve would be dust, the coordinator tops it up.
+        let collateral_reserve_with_fee_coordinator = round_collateral_reserve(
+            collateral_reserve_coordinator + order_matching_fee,
+            ReserveRounding::Up,
+        );
+        let collateral_reserve_coordinator =
+            collateral_reserve_with_fee_coordinator - order_matching_fee;
 
         let initial_price = trade_params.filled_with.average_execution_price();
 
@@ -502,10 +601,6 @@ impl TradeExecutor {
         )
         .context("Could not build contract descriptor")?;
 
-        let contract_symbol = trade_params.contract_symbol.label();
-        let maturity_time = trade_params.filled_with.expiry_timestamp;
-        let maturity_time = maturity_time.unix_timestamp();
-
         let sats_per_vbyte = self
             .node
             .inner
@@ -520,7 +615,11 @@ impl TradeExecutor {
 
         // The contract input to be used for setting up the trade between the trader and the
         // coordinator.
-        let event_id = format!("{contract_symbol}{maturity_time}");
+        let event_id = OracleEventId::new(
+            trade_params.contract_symbol,
+            trade_params.filled_with.expiry_timestamp,
+        )
+        .to_string();
 
         let (offer_collateral, accept_collateral, fee_config) = match trader_required_utxos {
             TraderRequiredLiquidity::ForTradeCostAndTxFees => (
@@ -556,8 +655,6 @@ impl TradeExecutor {
             }],
         };
 
-        let protocol_id = ProtocolId::new();
-
         tracing::debug!(
             %protocol_id,
             event_id,
@@ -610,9 +707,11 @@ impl TradeExecutor {
         .await
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn open_position(
         &self,
         conn: &mut PgConnection,
+        protocol_id: ProtocolId,
         dlc_channel_id: DlcChannelId,
         trade_params: &TradeParams,
         coordinator_dlc_channel_collateral: Amount,
@@ -670,6 +769,77 @@ impl TradeExecutor {
                 )
             })?;
 
+        let (
+            coordinator_collateral_reserve,
+            trader_collateral_reserve_with_interest,
+            reserve_interest_credit_ids,
+        ) = reserve_interest::apply_reserve_interest_credits(
+            conn,
+            peer_id,
+            coordinator_collateral_reserve,
+            trader_collateral_reserve,
+        )?;
+
+        // The reserve interest credits move coins from the coordinator's to the trader's side of
+        // the DLC channel.
+        let reserve_interest_credit =
+            trader_collateral_reserve_with_interest - trader_collateral_reserve;
+        let trader_collateral_reserve = trader_collateral_reserve_with_interest;
+        let coordinator_dlc_channel_collateral =
+            coordinator_dlc_channel_collateral - reserve_interest_credit;
+        let trader_dlc_channel_collateral = trader_dlc_channel_collateral + reserve_interest_credit;
+
+        let (
+            coordinator_collateral_reserve,
+            trader_collateral_reserve_with_top_ups,
+            reserve_top_up_credits,
+        ) = reserve_top_up::apply_reserve_top_ups(
+            conn,
+            peer_id,
+            coordinator_collateral_reserve,
+            trader_collateral_reserve,
+        )?;
+
+        // Like reserve interest credits, reserve top-ups move coins from the coordinator's to the
+        // trader's side of the DLC channel.
+        let reserve_top_up_credit =
+            trader_collateral_reserve_with_top_ups - trader_collateral_reserve;
+        let trader_collateral_reserve = trader_collateral_reserve_with_top_ups;
+        let coordinator_dlc_channel_collateral =
+            coordinator_dlc_channel_collateral - reserve_top_up_credit;
+        let trader_dlc_channel_collateral = trader_dlc_channel_collateral + reserve_top_up_credit;
+
+        let (
+            coordinator_collateral_reserve,
+            trader_collateral_reserve_with_withdrawals,
+            lightning_withdrawals,
+        ) = lightning_withdrawal::apply_lightning_withdrawals(
+            conn,
+            peer_id,
+            coordinator_collateral_reserve,
+            trader_collateral_reserve,
+        )?;
+
+        // Lightning withdrawals move coins from the trader's to the coordinator's side of the DLC
+        // channel, refunds of failed withdrawals move them back.
+        let (coordinator_dlc_channel_collateral, trader_dlc_channel_collateral) =
+            match trader_collateral_reserve_with_withdrawals.checked_sub(trader_collateral_reserve)
+            {
+                Some(refund) => (
+                    coordinator_dlc_channel_collateral - refund,
+                    trader_dlc_channel_collateral + refund,
+                ),
+                None => {
+                    let debit =
+                        trader_collateral_reserve - trader_collateral_reserve_with_withdrawals;
+                    (
+                        coordinator_dlc_channel_collateral + debit,
+                        trader_dlc_channel_collateral - debit,
+                    )
+                }
+            };
+        let trader_collateral_reserve = trader_collateral_reserve_with_withdrawals;
+
         tracing::debug!(
             %peer_id,
             order_id = %trade_params.filled_with.order_id,
@@ -696,10 +866,6 @@ impl TradeExecutor {
         )
         .context("Could not build contract descriptor")?;
 
-        let contract_symbol = trade_params.contract_symbol.label();
-        let maturity_time = trade_params.filled_with.expiry_timestamp;
-        let maturity_time = maturity_time.unix_timestamp();
-
         let sats_per_vbyte = self
             .node
             .inner
@@ -715,7 +881,11 @@ impl TradeExecutor {
 
         // The contract input to be used for setting up the trade between the trader and the
         // coordinator.
-        let event_id = format!("{contract_symbol}{maturity_time}");
+        let event_id = OracleEventId::new(
+            trade_params.contract_symbol,
+            trade_params.filled_with.expiry_timestamp,
+        )
+        .to_string();
 
         tracing::debug!(
             event_id,
@@ -737,7 +907,6 @@ impl TradeExecutor {
             }],
         };
 
-        let protocol_id = ProtocolId::new();
         let channel = self.node.inner.get_dlc_channel_by_id(&dlc_channel_id)?;
         let previous_protocol_id = match channel.get_reference_id() {
             Some(reference_id) => Some(ProtocolId::try_from(reference_id)?),
@@ -765,6 +934,19 @@ impl TradeExecutor {
             trade_params,
         )?;
 
+        reserve_interest::set_reserve_interest_credits_protocol_id(
+            conn,
+            protocol_id,
+            &reserve_interest_credit_ids,
+        )
+        .context("Failed to link reserve interest credits to open position protocol")?;
+
+        reserve_top_up::link_to_protocol(conn, protocol_id, &reserve_top_up_credits)
+            .context("Failed to link reserve top-ups to open position protocol")?;
+
+        lightning_withdrawal::link_to_protocol(conn, protocol_id, &lightning_withdrawals)
+            .context("Failed to link Lightning withdrawals to open position protocol")?;
+
         // TODO(holzeis): The position should only get created after the dlc protocol has finished
         // successfully.
         self.persist_position(
@@ -781,6 +963,7 @@ impl TradeExecutor {
     async fn resize_position(
         &self,
         conn: &mut PgConnection,
+        protocol_id: ProtocolId,
         dlc_channel_id: DlcChannelId,
         position: &Position,
         trade_params: &TradeParams,
@@ -789,7 +972,10 @@ impl TradeExecutor {
         if !self
             .node
             .inner
-            .check_if_signed_channel_is_confirmed(position.trader)
+            .check_if_signed_channel_is_confirmed_for(
+                position.trader,
+                ChannelOperation::ResizePosition,
+            )
             .await?
         {
             bail!("Underlying DLC channel not yet confirmed.");
@@ -814,6 +1000,33 @@ impl TradeExecutor {
             .node
             .apply_funding_fee_to_channel(dlc_channel_id, funding_fee)?;
 
+        let (
+            collateral_reserve_coordinator,
+            collateral_reserve_trader,
+            reserve_interest_credit_ids,
+        ) = reserve_interest::apply_reserve_interest_credits(
+            conn,
+            peer_id,
+            collateral_reserve_coordinator,
+            collateral_reserve_trader,
+        )?;
+
+        let (collateral_reserve_coordinator, collateral_reserve_trader, reserve_top_up_credits) =
+            reserve_top_up::apply_reserve_top_ups(
+                conn,
+                peer_id,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+
+        let (collateral_reserve_coordinator, collateral_reserve_trader, lightning_withdrawals) =
+            lightning_withdrawal::apply_lightning_withdrawals(
+                conn,
+                peer_id,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+
         tracing::info!(
             %peer_id,
             order_id = %trade_params.filled_with.order_id,
@@ -887,9 +1100,7 @@ impl TradeExecutor {
         )
         .context("Could not build contract descriptor")?;
 
-        let contract_symbol = trade_params.contract_symbol.label();
         let expiry_timestamp = trade_params.filled_with.expiry_timestamp;
-        let expiry_unix_timestamp = expiry_timestamp.unix_timestamp();
 
         let sats_per_vbyte = self
             .node
@@ -906,7 +1117,8 @@ impl TradeExecutor {
 
         // The contract input to be used for setting up the trade between the trader and the
         // coordinator.
-        let event_id = format!("{contract_symbol}{expiry_unix_timestamp}");
+        let event_id =
+            OracleEventId::new(trade_params.contract_symbol, expiry_timestamp).to_string();
 
         tracing::debug!(
             event_id,
@@ -928,7 +1140,6 @@ impl TradeExecutor {
             }],
         };
 
-        let protocol_id = ProtocolId::new();
         let channel = self.node.inner.get_dlc_channel_by_id(&dlc_channel_id)?;
         let previous_id = match channel.get_reference_id() {
             Some(reference_id) => Some(ProtocolId::try_from(reference_id)?),
@@ -963,6 +1174,19 @@ impl TradeExecutor {
             funding_fee_event_ids,
         )?;
 
+        reserve_interest::set_reserve_interest_credits_protocol_id(
+            conn,
+            protocol_id,
+            &reserve_interest_credit_ids,
+        )
+        .context("Failed to link reserve interest credits to resize protocol")?;
+
+        reserve_top_up::link_to_protocol(conn, protocol_id, &reserve_top_up_credits)
+            .context("Failed to link reserve top-ups to resize protocol")?;
+
+        lightning_withdrawal::link_to_protocol(conn, protocol_id, &lightning_withdrawals)
+            .context("Failed to link Lightning withdrawals to resize protocol")?;
+
         db::positions::Position::set_position_to_resizing(
             conn,
             peer_id,
@@ -997,6 +1221,7 @@ impl TradeExecutor {
             Decimal::try_from(maintenance_margin_rate).expect("to fit into decimal");
 
         let trader_liquidation_price = liquidation_price(
+            trade_params.contract_symbol,
             price,
             Decimal::try_from(trade_params.leverage).expect("to fit into decimal"),
             trade_params.direction,
@@ -1004,6 +1229,7 @@ impl TradeExecutor {
         );
 
         let coordinator_liquidation_price = liquidation_price(
+            trade_params.contract_symbol,
             price,
             Decimal::try_from(coordinator_leverage).expect("to fit into decimal"),
             trade_params.direction.opposite(),
@@ -1047,6 +1273,7 @@ impl TradeExecutor {
     pub async fn start_closing_position(
         &self,
         conn: &mut PgConnection,
+        protocol_id: ProtocolId,
         order: commons::Order,
         position: &Position,
         trade_params: &TradeParams,
@@ -1055,7 +1282,10 @@ impl TradeExecutor {
         if !self
             .node
             .inner
-            .check_if_signed_channel_is_confirmed(position.trader)
+            .check_if_signed_channel_is_confirmed_for(
+                position.trader,
+                ChannelOperation::ClosePosition,
+            )
             .await?
         {
             bail!("Underlying DLC channel not yet confirmed.");
@@ -1073,10 +1303,20 @@ impl TradeExecutor {
 
         let position = position.apply_funding_fee(funding_fee, maintenance_margin_rate);
 
-        let (collateral_reserve_coordinator, _) = self
+        let (collateral_reserve_coordinator, collateral_reserve_trader) = self
             .node
             .apply_funding_fee_to_channel(channel_id, funding_fee)?;
 
+        // Refunds of failed Lightning withdrawals are paid out to the trader when closing the
+        // position.
+        let (collateral_reserve_coordinator, _, lightning_withdrawals) =
+            lightning_withdrawal::apply_lightning_withdrawals(
+                conn,
+                position.trader,
+                collateral_reserve_coordinator,
+                collateral_reserve_trader,
+            )?;
+
         let closing_price = trade_params.average_execution_price();
         let position_settlement_amount_coordinator = position
             .calculate_coordinator_settlement_amount(
@@ -1087,7 +1327,6 @@ impl TradeExecutor {
         let dlc_channel_settlement_amount_coordinator =
             position_settlement_amount_coordinator + collateral_reserve_coordinator.to_sat();
 
-        let protocol_id = ProtocolId::new();
         tracing::info!(
             %protocol_id,
             ?position,
@@ -1150,6 +1389,9 @@ impl TradeExecutor {
             funding_fee_event_ids,
         )?;
 
+        lightning_withdrawal::link_to_protocol(conn, protocol_id, &lightning_withdrawals)
+            .context("Failed to link Lightning withdrawals to settle protocol")?;
+
         db::positions::Position::set_open_position_to_closing(
             conn,
             &position.trader,
@@ -1426,6 +1668,7 @@ fn apply_resize_to_position(
             let realized_pnl = None;
 
             let coordinator_liquidation_price = liquidation_price(
+                position.contract_symbol,
                 average_execution_price,
                 Decimal::try_from(position.coordinator_leverage).expect("to fit"),
                 position.trader_direction.opposite(),
@@ -1433,6 +1676,7 @@ fn apply_resize_to_position(
             );
 
             let trader_liquidation_price = liquidation_price(
+                position.contract_symbol,
                 average_execution_price,
                 Decimal::try_from(position.trader_leverage).expect("to fit"),
                 position.trader_direction,
@@ -1487,7 +1731,8 @@ fn apply_resize_to_position(
 
             // The PNL is capped by the margin, so the coordinator should never end up eating into
             // the accrued order matching fees to pay the trader.
-            let realized_pnl_trader = calculate_pnl(
+            let realized_pnl_trader = calculate_pnl_for_symbol(
+                position.contract_symbol,
                 position_average_execution_price,
                 order_average_execution_price,
                 order_contracts.to_f32().expect("to fit"),
@@ -1576,6 +1821,7 @@ fn apply_resize_to_position(
             let coordinator_direction = trader_direction.opposite();
 
             let trader_liquidation_price = liquidation_price(
+                position.contract_symbol,
                 order_average_execution_price,
                 Decimal::try_from(position.trader_leverage).expect("to fit"),
                 trader_direction,
@@ -1583,6 +1829,7 @@ fn apply_resize_to_position(
             );
 
             let coordinator_liquidation_price = liquidation_price(
+                position.contract_symbol,
                 order_average_execution_price,
                 Decimal::try_from(position.coordinator_leverage).expect("to fit"),
                 trader_direction.opposite(),
@@ -1610,7 +1857,8 @@ fn apply_resize_to_position(
 
             // The PNL is capped by the margin, so the coordinator should never end up eating into
             // the accrued order matching fees to pay the trader.
-            let realized_pnl_trader = calculate_pnl(
+            let realized_pnl_trader = calculate_pnl_for_symbol(
+                position.contract_symbol,
                 position_average_execution_price,
                 order_average_execution_price,
                 position.quantity,
@@ -1707,16 +1955,37 @@ fn margin_coordinator(trade_params: &TradeParams, coordinator_leverage: f32) ->
     )
 }
 
+/// The [`ProtocolId`] of the DLC protocol executing the trade.
+///
+/// The trader's order is executed against the first match, hence the [`ProtocolId`] is derived
+/// from the order and that match.
+fn trade_protocol_id(trade_params: &TradeParams) -> Result<ProtocolId> {
+    let filled_with = &trade_params.filled_with;
+    let first_match = filled_with
+        .matches
+        .first()
+        .context("Trade params without matches")?;
+
+    Ok(ProtocolId::from_order_and_match(
+        filled_with.order_id,
+        first_match.id,
+    ))
+}
+
 pub fn liquidation_price(
+    contract_symbol: ContractSymbol,
     price: Decimal,
     leverage: Decimal,
     direction: Direction,
     maintenance_margin: Decimal,
 ) -> Decimal {
-    match direction {
-        Direction::Long => calculate_long_liquidation_price(leverage, price, maintenance_margin),
-        Direction::Short => calculate_short_liquidation_price(leverage, price, maintenance_margin),
-    }
+    calculate_liquidation_price(
+        contract_symbol,
+        direction,
+        leverage,
+        price,
+        maintenance_margin,
+    )
 }
 
 pub fn coordinator_leverage_for_trade(_counterparty_peer_id: &PublicKey) -> Result<f32> {
@@ -1745,7 +2014,21 @@ mod tests {
     use insta::assert_debug_snapshot;
     use rust_decimal_macros::dec;
     use std::str::FromStr;
-    use xxi_node::commons::ContractSymbol;
+
+    #[test]
+    fn protocol_id_is_derived_deterministically_from_order_and_match() {
+        let order_id = Uuid::new_v4();
+        let match_id = Uuid::new_v4();
+
+        assert_eq!(
+            ProtocolId::from_order_and_match(order_id, match_id),
+            ProtocolId::from_order_and_match(order_id, match_id)
+        );
+        assert_ne!(
+            ProtocolId::from_order_and_match(order_id, match_id),
+            ProtocolId::from_order_and_match(order_id, Uuid::new_v4())
+        );
+    }
 
     #[test]
     fn apply_resize() {
@@ -1851,12 +2134,14 @@ mod tests {
         maintenance_margin: Decimal,
     ) {
         let coordinator_liquidation_price = liquidation_price(
+            ContractSymbol::BtcUsd,
             average_entry_price,
             Decimal::try_from(coordinator_leverage).unwrap(),
             trader_direction.opposite(),
             maintenance_margin,
         );
         let trader_liquidation_price = liquidation_price(
+            ContractSymbol::BtcUsd,
             average_entry_price,
             Decimal::try_from(trader_leverage).unwrap(),
             trader_direction,
@@ -0,0 +1,341 @@
+use crate::funding_fee::calculate_funding_fee;
+use crate::payout_curve;
+use crate::trade::coordinator_leverage_for_trade;
+use crate::trade::liquidation_price;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use dlc_manager::contract::ContractDescriptor;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use xxi_node::cfd::calculate_margin;
+use xxi_node::cfd::calculate_pnl_for_symbol;
+use xxi_node::commons::order_matching_fee;
+use xxi_node::commons::Direction;
+use xxi_node::commons::ExpirySchedule;
+use xxi_node::commons::FundingRate;
+use xxi_node::commons::SimulateTradeParams;
+use xxi_node::commons::TradeSimulation;
+
+/// The coordinator settings relevant to simulate a trade.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationSettings {
+    pub maintenance_margin_rate: Decimal,
+    pub order_matching_fee_rate: Decimal,
+    pub expiry_schedule: ExpirySchedule,
+}
+
+/// Simulate the outcome of a trade, without touching any channel or position.
+///
+/// The margins, fees, liquidation prices and payouts are computed with the same functions that are
+/// used when executing a trade, so that the preview matches the actual execution. The position is
+/// assumed to be opened at `now` in a fresh DLC channel.
+pub fn simulate_trade(
+    params: &SimulateTradeParams,
+    next_funding_rate: Option<FundingRate>,
+    settings: SimulationSettings,
+    now: OffsetDateTime,
+) -> Result<TradeSimulation> {
+    ensure!(params.quantity > 0.0, "Quantity must be positive");
+    ensure!(params.leverage > 0.0, "Leverage must be positive");
+    ensure!(
+        params.entry_price > Decimal::ZERO,
+        "Entry price must be positive"
+    );
+    ensure!(
+        params.exit_price > Decimal::ZERO,
+        "Exit price must be positive"
+    );
+
+    let leverage_trader = params.leverage;
+    let leverage_coordinator = coordinator_leverage_for_trade(&params.trader_pubkey)?;
+
+    let margin_trader = calculate_margin(params.entry_price, params.quantity, leverage_trader);
+    let margin_coordinator =
+        calculate_margin(params.entry_price, params.quantity, leverage_coordinator);
+
+    let opening_fee = order_matching_fee(
+        params.quantity,
+        params.entry_price,
+        settings.order_matching_fee_rate,
+    );
+    let closing_fee = order_matching_fee(
+        params.quantity,
+        params.exit_price,
+        settings.order_matching_fee_rate,
+    );
+
+    let liquidation_price_trader = liquidation_price(
+        params.contract_symbol,
+        params.entry_price,
+        Decimal::try_from(leverage_trader).context("leverage to fit into decimal")?,
+        params.direction,
+        settings.maintenance_margin_rate,
+    );
+    let liquidation_price_coordinator = liquidation_price(
+        params.contract_symbol,
+        params.entry_price,
+        Decimal::try_from(leverage_coordinator).context("leverage to fit into decimal")?,
+        params.direction.opposite(),
+        settings.maintenance_margin_rate,
+    );
+
+    let expiry_timestamp = settings.expiry_schedule.next_expiry(now);
+
+    let (funding_fee_estimate, funding_periods) = match next_funding_rate {
+        Some(funding_rate) => {
+            let funding_periods = funding_periods_until(funding_rate, now, expiry_timestamp);
+            let funding_fee = calculate_funding_fee(
+                params.quantity,
+                funding_rate.rate(),
+                params.entry_price,
+                params.direction,
+            );
+
+            (funding_fee * funding_periods as i64, funding_periods)
+        }
+        None => (SignedAmount::ZERO, 0),
+    };
+
+    let (margin_long, margin_short) = match params.direction {
+        Direction::Long => (margin_trader, margin_coordinator),
+        Direction::Short => (margin_coordinator, margin_trader),
+    };
+
+    let pnl = calculate_pnl_for_symbol(
+        params.contract_symbol,
+        params.entry_price,
+        params.exit_price,
+        params.quantity,
+        params.direction,
+        margin_long.to_sat(),
+        margin_short.to_sat(),
+    )?;
+
+    // Like in `TradeExecutor::open_dlc_channel`, the coordinator gets the order matching fee
+    // directly in the collateral reserve.
+    let trader_reserve = params.trader_reserve.unwrap_or(Amount::ZERO);
+    let coordinator_reserve = params.coordinator_reserve.unwrap_or(Amount::ZERO) + opening_fee;
+
+    let contract_descriptor = payout_curve::build_contract_descriptor(
+        params.entry_price,
+        margin_coordinator,
+        margin_trader,
+        leverage_coordinator,
+        leverage_trader,
+        params.direction.opposite(),
+        coordinator_reserve,
+        trader_reserve,
+        params.quantity,
+        params.contract_symbol,
+    )
+    .context("Could not build contract descriptor")?;
+
+    let total_collateral =
+        margin_coordinator + margin_trader + coordinator_reserve + trader_reserve;
+
+    let payout_at_exit_price = trader_payout_at_price(
+        &contract_descriptor,
+        total_collateral,
+        params.exit_price,
+        params.contract_symbol.max_price(),
+    )?;
+
+    Ok(TradeSimulation {
+        margin_trader,
+        margin_coordinator,
+        leverage_coordinator,
+        opening_fee,
+        closing_fee,
+        funding_fee_estimate,
+        funding_periods,
+        expiry_timestamp,
+        liquidation_price_trader,
+        liquidation_price_coordinator,
+        pnl: SignedAmount::from_sat(pnl),
+        payout_at_exit_price,
+    })
+}
+
+/// The number of funding periods of the same length as the given [`FundingRate`] which end
+/// between `now` and `expiry`.
+fn funding_periods_until(
+    funding_rate: FundingRate,
+    now: OffsetDateTime,
+    expiry: OffsetDateTime,
+) -> u32 {
+    let period = funding_rate.end_date() - funding_rate.start_date();
+    if !period.is_positive() || expiry <= now {
+        return 0;
+    }
+
+    let periods = (expiry - now).whole_seconds() / period.whole_seconds();
+
+    u32::try_from(periods).unwrap_or(u32::MAX)
+}
+
+/// Look up the payout of the accept party, i.e. the trader, in the CET which would be used if the
+/// oracle attested to `price`, capped at the `max_price` the oracle can attest to.
+fn trader_payout_at_price(
+    contract_descriptor: &ContractDescriptor,
+    total_collateral: Amount,
+    price: Decimal,
+    max_price: u64,
+) -> Result<Amount> {
+    let outcome = price
+        .round()
+        .to_u64()
+        .context("price to fit into u64")?
+        .min(max_price) as usize;
+
+    let range_payouts = match contract_descriptor {
+        ContractDescriptor::Enum(_) => {
+            unreachable!("We are not using DLCs with enumerated outcomes")
+        }
+        ContractDescriptor::Numerical(descriptor) => {
+            payout_curve::get_range_payouts(descriptor, total_collateral.to_sat())?
+        }
+    };
+
+    let range_payout = range_payouts
+        .iter()
+        .find(|range| range.start <= outcome && outcome < range.start + range.count)
+        .context("No payout found for price")?;
+
+    Ok(Amount::from_sat(range_payout.payout.accept))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::PublicKey;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::ext::NumericalDuration;
+    use time::macros::datetime;
+    use xxi_node::commons::ContractSymbol;
+
+    /// 12 hours before the next daily expiry, i.e. outside the rollover window.
+    const NOW: OffsetDateTime = datetime!(2024-07-24 12:00 UTC);
+
+    fn params(quantity: f32, entry_price: Decimal, exit_price: Decimal) -> SimulateTradeParams {
+        SimulateTradeParams {
+            trader_pubkey: PublicKey::from_str(
+                "02bd998ebd176715fe92b7467cf6b1df8023950a4dd911db4c94dfc89cc9f5a655",
+            )
+            .unwrap(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            direction: Direction::Long,
+            quantity,
+            leverage: 2.0,
+            entry_price,
+            exit_price,
+            trader_reserve: Some(Amount::from_sat(10_000)),
+            coordinator_reserve: Some(Amount::from_sat(10_000)),
+        }
+    }
+
+    fn simulate(params: &SimulateTradeParams) -> TradeSimulation {
+        // An 8-hour funding period, which fits once into the 12 hours until the expiry.
+        let funding_rate = FundingRate::new(dec!(0.0001), NOW - 8.hours(), NOW);
+
+        simulate_trade(
+            params,
+            Some(funding_rate),
+            SimulationSettings {
+                maintenance_margin_rate: dec!(0.1),
+                order_matching_fee_rate: dec!(0.003),
+                expiry_schedule: ExpirySchedule::Daily,
+            },
+            NOW,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn simulated_payout_at_entry_price_returns_margin_and_reserve() {
+        let params = params(100.0, dec!(50_000), dec!(50_000));
+
+        let simulation = simulate(&params);
+
+        // 100 [$] / (50_000 [$/BTC] * 2) = 0.001 [BTC]
+        assert_eq!(simulation.margin_trader, Amount::from_sat(100_000));
+        assert_eq!(simulation.margin_coordinator, Amount::from_sat(100_000));
+        // 100 [$] / 50_000 [$/BTC] * 0.3% = 0.000006 [BTC]
+        assert_eq!(simulation.opening_fee, Amount::from_sat(600));
+        assert_eq!(simulation.closing_fee, Amount::from_sat(600));
+        assert_eq!(simulation.pnl, SignedAmount::ZERO);
+        // 100 [$] / 50_000 [$/BTC] * 0.01% = 0.0000002 [BTC] for one period.
+        assert_eq!(simulation.funding_periods, 1);
+        assert_eq!(simulation.funding_fee_estimate, SignedAmount::from_sat(20));
+        assert_eq!(simulation.expiry_timestamp, datetime!(2024-07-25 00:00 UTC));
+
+        // The payout curve is discretized, so we only expect the payout to be close to the margin
+        // plus the collateral reserve.
+        let expected_payout = (simulation.margin_trader + Amount::from_sat(10_000)).to_sat();
+        assert!(
+            simulation
+                .payout_at_exit_price
+                .to_sat()
+                .abs_diff(expected_payout)
+                < 1_000
+        );
+        assert!(simulation.liquidation_price_trader < params.entry_price);
+        assert!(simulation.liquidation_price_coordinator > params.entry_price);
+    }
+
+    #[test]
+    fn simulated_long_trade_with_price_increase() {
+        let params = params(1_000.0, dec!(50_000), dec!(51_000));
+
+        let simulation = simulate(&params);
+
+        // 1_000 [$] / (50_000 [$/BTC] * 2) = 0.01 [BTC]
+        assert_eq!(simulation.margin_trader, Amount::from_sat(1_000_000));
+        // 1_000 [$] / 50_000 [$/BTC] * 0.3% = 0.00006 [BTC]
+        assert_eq!(simulation.opening_fee, Amount::from_sat(6_000));
+        // 1_000 [$] / 51_000 [$/BTC] * 0.3% = 0.0000588235... [BTC]
+        assert_eq!(simulation.closing_fee, Amount::from_sat(5_882));
+        // 1_000 [$] / 50_000 [$/BTC] - 1_000 [$] / 51_000 [$/BTC] = 0.00039215686... [BTC]
+        assert_eq!(simulation.pnl, SignedAmount::from_sat(39_216));
+        // 1_000 [$] / 50_000 [$/BTC] * 0.01% = 0.000002 [BTC] for one period.
+        assert_eq!(simulation.funding_periods, 1);
+        assert_eq!(simulation.funding_fee_estimate, SignedAmount::from_sat(200));
+    }
+
+    #[test]
+    fn simulated_short_trade_with_price_increase() {
+        let params = SimulateTradeParams {
+            direction: Direction::Short,
+            ..params(1_000.0, dec!(50_000), dec!(51_000))
+        };
+
+        let simulation = simulate(&params);
+
+        assert_eq!(simulation.margin_trader, Amount::from_sat(1_000_000));
+        assert_eq!(simulation.opening_fee, Amount::from_sat(6_000));
+        assert_eq!(simulation.closing_fee, Amount::from_sat(5_882));
+        assert_eq!(simulation.pnl, SignedAmount::from_sat(-39_216));
+        // With a positive funding rate, shorts get paid.
+        assert_eq!(simulation.funding_periods, 1);
+        assert_eq!(
+            simulation.funding_fee_estimate,
+            SignedAmount::from_sat(-200)
+        );
+    }
+
+    #[test]
+    fn no_funding_periods_after_expiry() {
+        let now = OffsetDateTime::now_utc();
+        let funding_rate = FundingRate::new(dec!(0.0001), now - 8.hours(), now);
+
+        assert_eq!(funding_periods_until(funding_rate, now, now - 1.hours()), 0);
+        assert_eq!(
+            funding_periods_until(funding_rate, now, now + 17.hours()),
+            2
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! The funds of the coordinator, so that finance can keep track of them.
+//!
+//! The funds are computed from the on-chain wallet, the DLC storage and the positions and trades
+//! recorded in the database. A snapshot of them is stored periodically to keep their history.
+
+use crate::node::Node;
+use crate::scheduler::Scheduler;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::Amount;
+use diesel::PgConnection;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use serde::Serialize;
+use time::Date;
+use time::Duration;
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+
+mod db;
+
+pub use db::get_latest as get_treasury_snapshots;
+
+time::serde::format_description!(day, Date, "[year]-[month]-[day]");
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TreasuryBalances {
+    pub on_chain: OnChainFunds,
+    pub dlc_channels: DlcChannelFunds,
+    pub pending_force_closes: PendingForceCloses,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct OnChainFunds {
+    /// Received on the addresses handed out by the coordinator wallet.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub external: Amount,
+    /// Change of the transactions of the coordinator wallet.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub internal: Amount,
+}
+
+/// The funds of the coordinator locked in open DLC channels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DlcChannelFunds {
+    pub channels: usize,
+    /// The coordinator margin of all open positions.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub margin: Amount,
+    /// The coordinator collateral reserve of all open DLC channels.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub reserve: Amount,
+}
+
+/// The funds of the coordinator in force-closed DLC channels which have not been claimed yet.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PendingForceCloses {
+    pub channels: usize,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DailyFees {
+    #[serde(with = "day")]
+    pub date: Date,
+    pub trades: usize,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub order_matching_fees: Amount,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TreasuryReport {
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    #[serde(flatten)]
+    pub balances: TreasuryBalances,
+    /// The fees earned on each day of the report, oldest first.
+    pub fees_earned: Vec<DailyFees>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TreasurySnapshot {
+    pub id: i32,
+    #[serde(flatten)]
+    pub balances: TreasuryBalances,
+    /// The order matching fees earned in the day before the snapshot.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub fees_earned: Amount,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+pub async fn snapshot_treasury_periodically(
+    scheduler: &Scheduler,
+    node: Node,
+    schedule: String,
+) -> Result<()> {
+    scheduler
+        .add_job("snapshot_treasury", &schedule, move || {
+            let node = node.clone();
+            async move {
+                spawn_blocking(move || snapshot_treasury(&node, OffsetDateTime::now_utc()))
+                    .await
+                    .expect("task to complete")
+            }
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// The current funds of the coordinator and the fees earned on each of the last `days` days,
+/// including today.
+pub fn get_treasury_report(
+    conn: &mut PgConnection,
+    node: &Node,
+    days: u32,
+    now: OffsetDateTime,
+) -> Result<TreasuryReport> {
+    let balances = get_balances(conn, node)?;
+
+    let last_day = now.date();
+    let first_day = last_day - Duration::days(i64::from(days.max(1)) - 1);
+    let fees =
+        crate::db::trades::get_order_matching_fees_since(conn, first_day.midnight().assume_utc())?;
+
+    Ok(TreasuryReport {
+        timestamp: now,
+        balances,
+        fees_earned: daily_fees(fees, first_day, last_day),
+    })
+}
+
+fn snapshot_treasury(node: &Node, now: OffsetDateTime) -> Result<()> {
+    let mut conn = node.pool.get()?;
+
+    let balances = get_balances(&mut conn, node)?;
+    let fees_earned =
+        crate::db::trades::get_order_matching_fees_since(&mut conn, now - Duration::days(1))?
+            .into_iter()
+            .map(|(_, fee)| fee)
+            .sum::<Amount>();
+
+    db::insert(&mut conn, balances, fees_earned)?;
+
+    tracing::info!(?balances, %fees_earned, "Stored treasury snapshot");
+
+    Ok(())
+}
+
+fn get_balances(conn: &mut PgConnection, node: &Node) -> Result<TreasuryBalances> {
+    let on_chain = node.inner.get_on_chain_balance_by_descriptor();
+
+    let margin = crate::db::positions::Position::get_all_open_positions(conn)?
+        .iter()
+        .map(|position| position.coordinator_margin)
+        .sum::<Amount>();
+
+    let mut channels = 0;
+    let mut reserve = Amount::ZERO;
+    for channel in node.inner.list_signed_dlc_channels()? {
+        // The funds of a closing DLC channel are accounted for as pending force-close.
+        if let SignedChannelState::Closing { .. } = channel.state {
+            continue;
+        }
+
+        let channel_reserve = node
+            .inner
+            .get_dlc_channel_usable_balance(&channel.channel_id)
+            .with_context(|| {
+                format!(
+                    "Could not get reserve of DLC channel {}",
+                    hex::encode(channel.channel_id)
+                )
+            })?;
+
+        channels += 1;
+        reserve += channel_reserve;
+    }
+
+    let force_closing = crate::db::dlc_channels::get_unclaimed_force_closing_dlc_channels(conn)?;
+
+    Ok(TreasuryBalances {
+        on_chain: OnChainFunds {
+            external: on_chain.external,
+            internal: on_chain.internal,
+        },
+        dlc_channels: DlcChannelFunds {
+            channels,
+            margin,
+            reserve,
+        },
+        pending_force_closes: PendingForceCloses {
+            channels: force_closing.len(),
+            amount: force_closing
+                .iter()
+                .map(|channel| channel.coordinator_funding_sats)
+                .sum(),
+        },
+    })
+}
+
+/// Sum up the fees of the trades for every day from `first_day` to `last_day`, including days
+/// without trades.
+fn daily_fees(
+    fees: Vec<(OffsetDateTime, Amount)>,
+    first_day: Date,
+    last_day: Date,
+) -> Vec<DailyFees> {
+    let mut days = Vec::new();
+
+    let mut date = first_day;
+    while date <= last_day {
+        days.push(DailyFees {
+            date,
+            trades: 0,
+            order_matching_fees: Amount::ZERO,
+        });
+        date = match date.next_day() {
+            Some(next_day) => next_day,
+            None => break,
+        };
+    }
+
+    for (timestamp, fee) in fees {
+        let date = timestamp.to_offset(time::UtcOffset::UTC).date();
+        if let Some(day) = days.iter_mut().find(|day| day.date == date) {
+            day.trades += 1;
+            day.order_matching_fees += fee;
+        }
+    }
+
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+    use time::macros::datetime;
+
+    #[test]
+    fn fees_are_summed_up_per_day() {
+        let fees = vec![
+            (datetime!(2024-07-01 09:00 UTC), Amount::from_sat(100)),
+            (datetime!(2024-07-01 23:59 UTC), Amount::from_sat(200)),
+            (datetime!(2024-07-03 00:00 UTC), Amount::from_sat(50)),
+        ];
+
+        let days = daily_fees(fees, date!(2024 - 07 - 01), date!(2024 - 07 - 03));
+
+        assert_eq!(
+            days,
+            vec![
+                DailyFees {
+                    date: date!(2024 - 07 - 01),
+                    trades: 2,
+                    order_matching_fees: Amount::from_sat(300),
+                },
+                DailyFees {
+                    date: date!(2024 - 07 - 02),
+                    trades: 0,
+                    order_matching_fees: Amount::ZERO,
+                },
+                DailyFees {
+                    date: date!(2024 - 07 - 03),
+                    trades: 1,
+                    order_matching_fees: Amount::from_sat(50),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fees_are_grouped_by_utc_day() {
+        let fees = vec![(datetime!(2024-07-02 01:00 +02:00), Amount::from_sat(100))];
+
+        let days = daily_fees(fees, date!(2024 - 07 - 01), date!(2024 - 07 - 02));
+
+        assert_eq!(days[0].order_matching_fees, Amount::from_sat(100));
+        assert_eq!(days[1].order_matching_fees, Amount::ZERO);
+    }
+
+    #[test]
+    fn daily_fees_are_serialized_with_the_date() {
+        let fees = DailyFees {
+            date: date!(2024 - 07 - 01),
+            trades: 1,
+            order_matching_fees: Amount::from_sat(100),
+        };
+
+        assert_eq!(
+            serde_json::to_value(fees).unwrap(),
+            serde_json::json!({
+                "date": "2024-07-01",
+                "trades": 1,
+                "order_matching_fees": 100,
+            })
+        );
+    }
+}
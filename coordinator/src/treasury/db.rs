@@ -0,0 +1,89 @@
+use crate::schema::treasury_snapshots;
+use crate::treasury;
+use crate::treasury::DlcChannelFunds;
+use crate::treasury::OnChainFunds;
+use crate::treasury::PendingForceCloses;
+use crate::treasury::TreasuryBalances;
+use bitcoin::Amount;
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+#[derive(Queryable, Debug)]
+struct TreasurySnapshot {
+    id: i32,
+    on_chain_external_sats: i64,
+    on_chain_internal_sats: i64,
+    dlc_channels: i32,
+    dlc_margin_sats: i64,
+    dlc_reserve_sats: i64,
+    force_closing_channels: i32,
+    pending_force_close_sats: i64,
+    fees_earned_sats: i64,
+    created_at: OffsetDateTime,
+}
+
+pub fn insert(
+    conn: &mut PgConnection,
+    balances: TreasuryBalances,
+    fees_earned: Amount,
+) -> QueryResult<()> {
+    diesel::insert_into(treasury_snapshots::table)
+        .values((
+            treasury_snapshots::on_chain_external_sats
+                .eq(balances.on_chain.external.to_sat() as i64),
+            treasury_snapshots::on_chain_internal_sats
+                .eq(balances.on_chain.internal.to_sat() as i64),
+            treasury_snapshots::dlc_channels.eq(balances.dlc_channels.channels as i32),
+            treasury_snapshots::dlc_margin_sats.eq(balances.dlc_channels.margin.to_sat() as i64),
+            treasury_snapshots::dlc_reserve_sats.eq(balances.dlc_channels.reserve.to_sat() as i64),
+            treasury_snapshots::force_closing_channels
+                .eq(balances.pending_force_closes.channels as i32),
+            treasury_snapshots::pending_force_close_sats
+                .eq(balances.pending_force_closes.amount.to_sat() as i64),
+            treasury_snapshots::fees_earned_sats.eq(fees_earned.to_sat() as i64),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The most recent treasury snapshots, newest first.
+pub fn get_latest(
+    conn: &mut PgConnection,
+    limit: i64,
+) -> QueryResult<Vec<treasury::TreasurySnapshot>> {
+    let snapshots: Vec<TreasurySnapshot> = treasury_snapshots::table
+        .order_by(treasury_snapshots::created_at.desc())
+        .limit(limit)
+        .load(conn)?;
+
+    Ok(snapshots
+        .into_iter()
+        .map(treasury::TreasurySnapshot::from)
+        .collect())
+}
+
+impl From<TreasurySnapshot> for treasury::TreasurySnapshot {
+    fn from(value: TreasurySnapshot) -> Self {
+        treasury::TreasurySnapshot {
+            id: value.id,
+            balances: TreasuryBalances {
+                on_chain: OnChainFunds {
+                    external: Amount::from_sat(value.on_chain_external_sats as u64),
+                    internal: Amount::from_sat(value.on_chain_internal_sats as u64),
+                },
+                dlc_channels: DlcChannelFunds {
+                    channels: value.dlc_channels as usize,
+                    margin: Amount::from_sat(value.dlc_margin_sats as u64),
+                    reserve: Amount::from_sat(value.dlc_reserve_sats as u64),
+                },
+                pending_force_closes: PendingForceCloses {
+                    channels: value.force_closing_channels as usize,
+                    amount: Amount::from_sat(value.pending_force_close_sats as u64),
+                },
+            },
+            fees_earned: Amount::from_sat(value.fees_earned_sats as u64),
+            created_at: value.created_at,
+        }
+    }
+}
@@ -182,6 +182,8 @@ impl Request for PostOrderRequest {
 pub enum ContractSymbol {
     #[serde(rename = "XBTUSD")]
     XbtUsd,
+    #[serde(rename = "ETHUSD")]
+    EthUsd,
 }
 
 /// Get your positions.
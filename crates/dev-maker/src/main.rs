@@ -120,6 +120,8 @@ async fn post_order(
                 expiry: OffsetDateTime::now_utc()
                     + time::Duration::seconds(order_expiry_seconds as i64),
                 stable: false,
+                display_quantity: None,
+                contract_expiry: None,
             }),
             None,
             secret_key,
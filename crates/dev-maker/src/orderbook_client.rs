@@ -4,6 +4,8 @@ use reqwest::Url;
 use secp256k1::SecretKey;
 use uuid::Uuid;
 use xxi_node::commons::ChannelOpeningParams;
+use xxi_node::commons::ClientInfo;
+use xxi_node::commons::ClientKind;
 use xxi_node::commons::NewOrder;
 use xxi_node::commons::NewOrderRequest;
 
@@ -42,6 +44,10 @@ impl OrderbookClient {
             value: order,
             signature,
             channel_opening_params,
+            client_info: Some(ClientInfo {
+                kind: ClientKind::Sdk,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }),
         };
 
         let response = self
@@ -16,11 +16,13 @@ use axum::Router;
 use base64::engine::general_purpose;
 use base64::Engine;
 use lnd_bridge::CancelInvoice;
+use lnd_bridge::Chain;
 use lnd_bridge::Invoice;
 use lnd_bridge::InvoiceParams;
 use lnd_bridge::InvoiceResponse;
 use lnd_bridge::InvoiceResult;
 use lnd_bridge::InvoiceState;
+use lnd_bridge::NodeInfo;
 use lnd_bridge::SettleInvoice;
 use serde::Deserialize;
 use std::net::SocketAddr;
@@ -49,13 +51,15 @@ async fn main() -> Result<()> {
         .route("/v2/invoices/hodl", post(create_invoice))
         .route("/v2/invoices/settle", post(settle_invoice))
         .route("/v2/invoices/cancel", post(cancel_invoice))
+        .route("/v2/invoices/lookup", get(lookup_invoice))
+        .route("/v1/getinfo", get(get_info))
         .route("/pay_invoice", post(pay_invoice))
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
                 .layer(Extension(tx.clone())),
         )
-        .with_state(Arc::new(RwLock::new("".to_string())));
+        .with_state(Arc::new(RwLock::new(MockInvoice::default())));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 18080));
     tracing::info!("Listening on http://{}", addr);
@@ -69,7 +73,7 @@ async fn main() -> Result<()> {
 
 async fn create_invoice(
     Extension(tx): Extension<broadcast::Sender<String>>,
-    State(state): State<Arc<RwLock<String>>>,
+    State(state): State<Arc<RwLock<MockInvoice>>>,
     headers: HeaderMap,
     Json(params): Json<InvoiceParams>,
 ) -> impl IntoResponse {
@@ -99,7 +103,11 @@ async fn create_invoice(
             let hash = general_purpose::URL_SAFE
                 .decode(&params.hash)
                 .expect("to decode");
-            *state.write().expect("") = general_purpose::STANDARD.encode(hash);
+            *state.write().expect("") = MockInvoice {
+                r_hash: general_purpose::STANDARD.encode(hash),
+                value: params.value,
+                state: InvoiceState::Open,
+            };
 
             let message = serde_json::to_string(&result).expect("to serialize");
 
@@ -117,12 +125,14 @@ async fn create_invoice(
 
 async fn settle_invoice(
     Extension(tx): Extension<broadcast::Sender<String>>,
-    State(state): State<Arc<RwLock<String>>>,
+    State(state): State<Arc<RwLock<MockInvoice>>>,
     headers: HeaderMap,
     Json(_): Json<SettleInvoice>,
 ) -> impl IntoResponse {
     match headers.get("Grpc-Metadata-macaroon") {
         Some(_) => {
+            state.write().expect("").state = InvoiceState::Settled;
+
             let message = serde_json::to_string(&InvoiceResult {
                 result: Invoice {
                     memo: "".to_string(),
@@ -130,7 +140,7 @@ async fn settle_invoice(
                     amt_paid_sat: 0,
                     state: InvoiceState::Settled,
                     payment_request: "".to_string(),
-                    r_hash: state.read().expect("").to_string(),
+                    r_hash: state.read().expect("").r_hash.clone(),
                     add_index: 0,
                     settle_index: 0,
                 },
@@ -151,12 +161,14 @@ async fn settle_invoice(
 
 async fn cancel_invoice(
     Extension(tx): Extension<broadcast::Sender<String>>,
-    State(state): State<Arc<RwLock<String>>>,
+    State(state): State<Arc<RwLock<MockInvoice>>>,
     headers: HeaderMap,
     Json(_): Json<CancelInvoice>,
 ) -> impl IntoResponse {
     match headers.get("Grpc-Metadata-macaroon") {
         Some(_) => {
+            state.write().expect("").state = InvoiceState::Canceled;
+
             let message = serde_json::to_string(&InvoiceResult {
                 result: Invoice {
                     memo: "".to_string(),
@@ -164,7 +176,7 @@ async fn cancel_invoice(
                     amt_paid_sat: 0,
                     state: InvoiceState::Canceled,
                     payment_request: "".to_string(),
-                    r_hash: state.read().expect("").to_string(),
+                    r_hash: state.read().expect("").r_hash.clone(),
                     add_index: 0,
                     settle_index: 0,
                 },
@@ -183,18 +195,95 @@ async fn cancel_invoice(
     }
 }
 
+async fn get_info(headers: HeaderMap) -> impl IntoResponse {
+    match headers.get("Grpc-Metadata-macaroon") {
+        Some(_) => {
+            (
+                StatusCode::OK,
+                Json(NodeInfo {
+                    identity_pubkey:
+                        "02d0a6a5d1f1e7a7b6e5b2a6f1e3d4c5b6a7980d1e2f3a4b5c6d7e8f9a0b1c2d3e"
+                            .to_string(),
+                    synced_to_chain: true,
+                    chains: vec![Chain {
+                        chain: "bitcoin".to_string(),
+                        network: "regtest".to_string(),
+                    }],
+                }),
+            )
+                .into_response()
+        }
+        None => Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body::<String>("Missing macaroon".into())
+            .expect("body")
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct LookupQuery {
+    payment_hash: String,
+}
+
+async fn lookup_invoice(
+    State(state): State<Arc<RwLock<MockInvoice>>>,
+    Query(params): Query<LookupQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    match headers.get("Grpc-Metadata-macaroon") {
+        Some(_) => {
+            let invoice = state.read().expect("");
+
+            let hash = general_purpose::URL_SAFE
+                .decode(&params.payment_hash)
+                .expect("to decode");
+            if general_purpose::STANDARD.encode(hash) != invoice.r_hash {
+                return (StatusCode::NOT_FOUND, "Unknown invoice").into_response();
+            }
+
+            let amt_paid_sat = match invoice.state {
+                InvoiceState::Accepted | InvoiceState::Settled => invoice.value,
+                InvoiceState::Open | InvoiceState::Canceled => 0,
+            };
+
+            (
+                StatusCode::OK,
+                Json(Invoice {
+                    memo: "".to_string(),
+                    expiry: 0,
+                    amt_paid_sat,
+                    state: invoice.state.clone(),
+                    payment_request: "".to_string(),
+                    r_hash: invoice.r_hash.clone(),
+                    add_index: 0,
+                    settle_index: 0,
+                }),
+            )
+                .into_response()
+        }
+        None => Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body::<String>("Missing macaroon".into())
+            .expect("body")
+            .into_response(),
+    }
+}
+
 async fn pay_invoice(
     Extension(tx): Extension<broadcast::Sender<String>>,
-    State(state): State<Arc<RwLock<String>>>,
+    State(state): State<Arc<RwLock<MockInvoice>>>,
 ) -> impl IntoResponse {
+    state.write().expect("").state = InvoiceState::Accepted;
+
     let message = serde_json::to_string(&InvoiceResult {
         result: Invoice {
             memo: "".to_string(),
             expiry: 0,
-            amt_paid_sat: 0,
+            amt_paid_sat: state.read().expect("").value,
             state: InvoiceState::Accepted,
             payment_request: "".to_string(),
-            r_hash: state.read().expect("").to_string(),
+            r_hash: state.read().expect("").r_hash.clone(),
             add_index: 0,
             settle_index: 0,
         },
@@ -205,6 +294,24 @@ async fn pay_invoice(
     StatusCode::OK
 }
 
+/// The mock only knows the most recently created invoice.
+struct MockInvoice {
+    /// Standard base64 encoded, like lnd reports it.
+    r_hash: String,
+    value: u64,
+    state: InvoiceState,
+}
+
+impl Default for MockInvoice {
+    fn default() -> Self {
+        Self {
+            r_hash: "".to_string(),
+            value: 0,
+            state: InvoiceState::Open,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct SubscribeQuery {
     settle_index: Option<u64>,
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use futures::StreamExt;
+use lnd_bridge::SendPaymentParams;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter("info,lnd_bridge=trace")
+        .init();
+
+    let macaroon = "[enter macroon here]".to_string();
+    let lnd_bridge = lnd_bridge::LndBridge::new("localhost:18080".to_string(), macaroon, false);
+
+    let payment_request = "".to_string();
+    let mut stream = lnd_bridge.send_payment(SendPaymentParams {
+        payment_request,
+        timeout_seconds: 60,
+        fee_limit_sat: 100,
+    });
+
+    while let Some(result) = stream.next().await {
+        match result {
+            Ok(payment) => tracing::info!("{payment:?}"),
+            Err(e) => tracing::error!("{e:#}"),
+        }
+    }
+
+    Ok(())
+}
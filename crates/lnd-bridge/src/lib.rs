@@ -2,6 +2,7 @@ use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
 use async_stream::stream;
+use futures::SinkExt;
 use futures::Stream;
 use futures::StreamExt;
 use reqwest::Method;
@@ -56,6 +57,20 @@ pub struct InvoiceResponse {
     pub payment_request: String,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NodeInfo {
+    pub identity_pubkey: String,
+    pub synced_to_chain: bool,
+    pub chains: Vec<Chain>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Chain {
+    pub chain: String,
+    /// E.g. `mainnet`, `testnet`, `signet` or `regtest`.
+    pub network: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SettleInvoice {
     pub preimage: String,
@@ -78,6 +93,86 @@ pub enum InvoiceState {
     Accepted,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PaymentRequest {
+    pub destination: String,
+    pub payment_hash: String,
+    #[serde(deserialize_with = "string_as_u64", serialize_with = "u64_as_string")]
+    pub num_satoshis: u64,
+    #[serde(deserialize_with = "string_as_u64", serialize_with = "u64_as_string")]
+    pub timestamp: u64,
+    #[serde(deserialize_with = "string_as_u64", serialize_with = "u64_as_string")]
+    pub expiry: u64,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SendPaymentParams {
+    pub payment_request: String,
+    /// Upper limit on the time the payment may take to succeed.
+    pub timeout_seconds: i32,
+    /// The maximum routing fee we are willing to pay.
+    #[serde(serialize_with = "u64_as_string", deserialize_with = "string_as_u64")]
+    pub fee_limit_sat: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PaymentResult {
+    pub result: Payment,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Payment {
+    pub payment_hash: String,
+    pub status: PaymentStatus,
+    #[serde(default)]
+    pub failure_reason: String,
+    #[serde(default)]
+    pub htlcs: Vec<HtlcAttempt>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HtlcAttempt {
+    pub status: HtlcStatus,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum PaymentStatus {
+    #[serde(rename = "UNKNOWN")]
+    Unknown,
+    #[serde(rename = "INITIATED")]
+    Initiated,
+    #[serde(rename = "IN_FLIGHT")]
+    InFlight,
+    #[serde(rename = "SUCCEEDED")]
+    Succeeded,
+    #[serde(rename = "FAILED")]
+    Failed,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum HtlcStatus {
+    #[serde(rename = "IN_FLIGHT")]
+    InFlight,
+    #[serde(rename = "SUCCEEDED")]
+    Succeeded,
+    #[serde(rename = "FAILED")]
+    Failed,
+}
+
+impl Payment {
+    /// Whether an HTLC of the payment has been locked in with our channel peer.
+    ///
+    /// From then on, we can't take back the payment anymore. It either succeeds or it fails with
+    /// the HTLC being removed again.
+    pub fn has_locked_htlc(&self) -> bool {
+        self.htlcs
+            .iter()
+            .any(|htlc| matches!(htlc.status, HtlcStatus::InFlight | HtlcStatus::Succeeded))
+    }
+}
+
 fn string_as_u64<'de, T, D>(de: D) -> Result<T, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -174,6 +269,137 @@ impl LndBridge {
         Ok(())
     }
 
+    /// Gets general information about the lnd node, e.g. the network it is running on.
+    pub async fn get_info(&self) -> Result<NodeInfo> {
+        let builder = self.client.request(
+            Method::GET,
+            format!(
+                "{}://{}/v1/getinfo",
+                if self.secure { "https" } else { "http" },
+                self.endpoint
+            ),
+        );
+
+        let resp = builder
+            .header("Grpc-Metadata-macaroon", self.macaroon.clone())
+            .send()
+            .await?;
+
+        let info: NodeInfo = resp.error_for_status()?.json().await?;
+
+        Ok(info)
+    }
+
+    /// Looks up the current state of the invoice with the given `r_hash`.
+    pub async fn lookup_invoice(&self, r_hash: &str) -> Result<Invoice> {
+        let builder = self.client.request(
+            Method::GET,
+            format!(
+                "{}://{}/v2/invoices/lookup?payment_hash={r_hash}",
+                if self.secure { "https" } else { "http" },
+                self.endpoint
+            ),
+        );
+
+        let resp = builder
+            .header("Grpc-Metadata-macaroon", self.macaroon.clone())
+            .send()
+            .await?;
+
+        let invoice: Invoice = resp.error_for_status()?.json().await?;
+
+        Ok(invoice)
+    }
+
+    /// Decodes a BOLT11 payment request.
+    pub async fn decode_payment_request(&self, payment_request: &str) -> Result<PaymentRequest> {
+        let builder = self.client.request(
+            Method::GET,
+            format!(
+                "{}://{}/v1/payreq/{payment_request}",
+                if self.secure { "https" } else { "http" },
+                self.endpoint
+            ),
+        );
+
+        let resp = builder
+            .header("Grpc-Metadata-macaroon", self.macaroon.clone())
+            .send()
+            .await?;
+
+        let payment_request: PaymentRequest = resp.error_for_status()?.json().await?;
+
+        Ok(payment_request)
+    }
+
+    /// Pays a BOLT11 payment request, reporting the progress of the payment until it either
+    /// succeeded or failed.
+    pub fn send_payment(
+        &self,
+        params: SendPaymentParams,
+    ) -> impl Stream<Item = Result<Payment>> + Unpin + '_ {
+        let stream = stream! {
+            tracing::debug!("Connecting to lnd websocket API");
+
+            let url_str = &*format!("{}://{}/v2/router/send?method=POST", if self.secure { "wss" } else { "ws" }, self.endpoint);
+            let url = url::Url::parse(url_str)?;
+
+            let mut req = url.into_client_request()?;
+            let headers = req.headers_mut();
+            headers.insert("Grpc-Metadata-macaroon", self.macaroon.parse().map_err(|e| anyhow!(format!("{e:#}")))?);
+
+            let (mut connection, _) = tokio_tungstenite::connect_async(req)
+                .await
+                .context("Could not connect to websocket")?;
+
+            // The request of a streaming endpoint is sent as the first message.
+            connection
+                .send(tungstenite::Message::Text(serde_json::to_string(&params)?))
+                .await
+                .context("Could not send payment request")?;
+
+            tracing::info!("Sent payment to lnd websocket API");
+
+            loop {
+                match connection.next().await {
+                    Some(Ok(msg)) => match msg {
+                        tungstenite::Message::Text(text) => {
+                            match serde_json::from_str::<PaymentResult>(&text) {
+                                Ok(payment) => {
+                                    let done = matches!(
+                                        payment.result.status,
+                                        PaymentStatus::Succeeded | PaymentStatus::Failed
+                                    );
+
+                                    yield Ok(payment.result);
+
+                                    if done {
+                                        return;
+                                    }
+                                }
+                                Err(e) => yield Err(anyhow!(format!("{text}. Error: {e:#}")))
+                            }
+                        }
+                        tungstenite::Message::Ping(_) => {
+                            tracing::trace!("Received ping from lnd");
+                        }
+                        other => {
+                            tracing::trace!("Unsupported message: {:?}", other);
+                            continue;
+                        }
+                    },
+                    None => return,
+                    Some(Err(e)) => {
+                        yield Err(anyhow!(e));
+                        return;
+                    }
+                }
+            }
+        };
+
+        stream.boxed()
+    }
+
     /// Subscribes to an invoice update for a given `r_hash` to the lnd api.
     pub fn subscribe_to_invoice(
         &self,
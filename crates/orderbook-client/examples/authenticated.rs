@@ -31,6 +31,8 @@ async fn main() -> Result<Never> {
             None,
             None,
             None,
+            Some(orderbook_client::sdk_client_info()),
+            false,
         )
         .await?;
 
@@ -7,13 +7,26 @@ use futures::SinkExt;
 use futures::Stream;
 use futures::StreamExt;
 use secp256k1::Message;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::SystemTime;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::UNIX_EPOCH;
 use tokio_tungstenite_wasm as tungstenite;
 use tokio_tungstenite_wasm::WebSocketStream;
+use uuid::Uuid;
+use xxi_node::commons::api_key_auth_message;
 use xxi_node::commons::create_sign_message;
+use xxi_node::commons::generate_api_nonce;
+use xxi_node::commons::sign_with_api_secret;
+use xxi_node::commons::ClientInfo;
+use xxi_node::commons::ClientKind;
 use xxi_node::commons::OrderbookRequest;
 use xxi_node::commons::Signature;
 use xxi_node::commons::AUTH_SIGN_MESSAGE;
 
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
 /// Connects to the 10101 orderbook WebSocket API.
 ///
 /// If the connection needs authentication please use `subscribe_with_authentication` instead.
@@ -23,11 +36,14 @@ pub async fn subscribe(
     SplitSink<WebSocketStream, tungstenite::Message>,
     impl Stream<Item = Result<String, anyhow::Error>> + Unpin,
 )> {
-    subscribe_impl(None, url, None, None, None).await
+    subscribe_impl(None, url).await
 }
 
 /// Connects to the orderbook WebSocket API with authentication.
 ///
+/// With `cancel_on_disconnect`, the coordinator deletes all orders of the maker if the connection
+/// drops and is not re-established within its grace period.
+///
 /// It subscribes and yields all messages.
 pub async fn subscribe_with_authentication(
     url: String,
@@ -35,12 +51,78 @@ pub async fn subscribe_with_authentication(
     fcm_token: Option<String>,
     version: Option<String>,
     os: Option<String>,
+    client_info: Option<ClientInfo>,
+    cancel_on_disconnect: bool,
 ) -> Result<(
     SplitSink<WebSocketStream, tungstenite::Message>,
     impl Stream<Item = Result<String, anyhow::Error>> + Unpin,
 )> {
     let signature = create_auth_message_signature(authenticate);
-    subscribe_impl(Some(signature), url, fcm_token, version, os).await
+    let authentication = OrderbookRequest::Authenticate {
+        fcm_token,
+        version,
+        signature,
+        os,
+        client_info,
+        cancel_on_disconnect,
+    };
+
+    subscribe_impl(Some(authentication), url).await
+}
+
+/// Connects to the orderbook WebSocket API, authenticating with an API key instead of the node
+/// key.
+///
+/// See [`subscribe_with_authentication`] for `cancel_on_disconnect`.
+///
+/// It subscribes and yields all messages.
+pub async fn subscribe_with_api_key(
+    url: String,
+    key_id: Uuid,
+    secret: &str,
+    cancel_on_disconnect: bool,
+) -> Result<(
+    SplitSink<WebSocketStream, tungstenite::Message>,
+    impl Stream<Item = Result<String, anyhow::Error>> + Unpin,
+)> {
+    let timestamp = unix_timestamp()?;
+    let nonce = generate_api_nonce();
+    let signature = sign_with_api_secret(secret, &api_key_auth_message(timestamp, &nonce));
+
+    let authentication = OrderbookRequest::AuthenticateWithApiKey {
+        key_id,
+        timestamp,
+        nonce,
+        signature,
+        client_info: Some(sdk_client_info()),
+        cancel_on_disconnect,
+    };
+
+    subscribe_impl(Some(authentication), url).await
+}
+
+/// Identifies this crate towards the coordinator, so that orders submitted through it are
+/// attributed to the SDK.
+pub fn sdk_client_info() -> ClientInfo {
+    ClientInfo {
+        kind: ClientKind::Sdk,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_timestamp() -> Result<i64> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("System time is before the unix epoch")?;
+
+    Ok(timestamp.as_secs() as i64)
+}
+
+/// The system time is not available in the browser, hence we have to ask JavaScript.
+#[cfg(target_arch = "wasm32")]
+fn unix_timestamp() -> Result<i64> {
+    Ok((js_sys::Date::now() / 1000.0) as i64)
 }
 
 pub fn create_auth_message_signature(authenticate: impl Fn(Message) -> Signature) -> Signature {
@@ -49,11 +131,8 @@ pub fn create_auth_message_signature(authenticate: impl Fn(Message) -> Signature
 
 /// Connects to the orderbook WebSocket API and yields all messages.
 async fn subscribe_impl(
-    signature: Option<Signature>,
+    authentication: Option<OrderbookRequest>,
     url: String,
-    fcm_token: Option<String>,
-    version: Option<String>,
-    os: Option<String>,
 ) -> Result<(
     SplitSink<WebSocketStream, tungstenite::Message>,
     impl Stream<Item = Result<String>> + Unpin,
@@ -66,16 +145,9 @@ async fn subscribe_impl(
 
     tracing::info!("Connected to orderbook realtime API");
 
-    if let Some(signature) = signature {
+    if let Some(authentication) = authentication {
         let _ = connection
-            .send(tungstenite::Message::try_from(
-                OrderbookRequest::Authenticate {
-                    fcm_token,
-                    version,
-                    signature,
-                    os,
-                },
-            )?)
+            .send(tungstenite::Message::try_from(authentication)?)
             .await;
     }
 
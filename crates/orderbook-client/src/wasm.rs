@@ -0,0 +1,537 @@
+//! Bindings for building, signing and parsing orderbook messages from JavaScript, so that the web
+//! frontend shares the exact wire format with the coordinator.
+//!
+//! Values are passed as plain JavaScript objects in their JSON representation. Their TypeScript
+//! definitions are emitted into the `.d.ts` file generated alongside the bindings.
+
+use crate::sdk_client_info;
+use secp256k1::SecretKey;
+use secp256k1::SECP256K1;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use xxi_node::commons::api_key_auth_message;
+use xxi_node::commons::api_request_message;
+use xxi_node::commons::create_sign_message;
+use xxi_node::commons::sign_with_api_secret;
+use xxi_node::commons::ChannelOpeningParams;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+use xxi_node::commons::Message;
+use xxi_node::commons::NewLimitOrder;
+use xxi_node::commons::NewMarketOrder;
+use xxi_node::commons::NewOrder;
+use xxi_node::commons::NewOrderRequest;
+use xxi_node::commons::OrderbookRequest;
+use xxi_node::commons::Signature;
+use xxi_node::commons::AUTH_SIGN_MESSAGE;
+
+#[wasm_bindgen(typescript_custom_section)]
+const COMMONS_TYPES: &'static str = r#"
+// TypeScript definitions of the orderbook wire format, i.e. the JSON representation of the types
+// in `xxi_node::commons`. Keep in sync with the Rust types.
+
+/** Hex encoded secp256k1 public key. */
+export type PublicKey = string;
+/** Hex encoded x-only secp256k1 public key. */
+export type XOnlyPublicKey = string;
+/** DER encoded, hex encoded ECDSA signature. */
+export type EcdsaSignature = string;
+export type Uuid = string;
+/** Seconds since the unix epoch. */
+export type UnixTimestamp = number;
+/** RFC 3339 formatted date. */
+export type Rfc3339 = string;
+/**
+ * A date in the default format of the `time` crate: `[year, ordinal, hour, minute, second,
+ * nanosecond, offset_hours, offset_minutes, offset_seconds]`.
+ */
+export type TimeTuple = number[];
+/** A decimal serialized as string, to not lose precision. */
+export type DecimalString = string;
+export type Sats = number;
+
+export type ContractSymbol = "BtcUsd" | "EthUsd";
+export type Direction = "Long" | "Short";
+export type OrderType = "Market" | "Limit";
+export type OrderState = "Open" | "Matched" | "Taken" | "Failed" | "Expired" | "Deleted";
+export type OrderReason =
+  | "Manual"
+  | "Expired"
+  | "CoordinatorLiquidated"
+  | "TraderLiquidated"
+  | "ChannelMigration";
+export type Locale = "en" | "de";
+export type ErrorCode = "InvalidOrder" | "NoMatchFound" | "TradeFailed" | "RolloverFailed";
+export type BonusStatusType = "Referral" | "Referent";
+
+export interface NewMarketOrder {
+  id: Uuid;
+  contract_symbol: ContractSymbol;
+  quantity: number;
+  trader_id: PublicKey;
+  direction: Direction;
+  leverage: number;
+  expiry: UnixTimestamp;
+  stable: boolean;
+  /** One of the listed expiries. If not set, the contract expires at the next expiry. */
+  contract_expiry?: UnixTimestamp | null;
+}
+
+export interface NewLimitOrder {
+  id: Uuid;
+  contract_symbol: ContractSymbol;
+  price: number;
+  quantity: number;
+  trader_id: PublicKey;
+  direction: Direction;
+  leverage: number;
+  expiry: UnixTimestamp;
+  stable: boolean;
+  /** If set, only this much of the quantity is shown in the public orderbook at a time. */
+  display_quantity?: number | null;
+  /** One of the listed expiries. If not set, the contract expires at the next expiry. */
+  contract_expiry?: UnixTimestamp | null;
+}
+
+/** The parameters of a new order, which are completed with a fresh order id. */
+export type NewMarketOrderParams = Omit<NewMarketOrder, "id">;
+export type NewLimitOrderParams = Omit<NewLimitOrder, "id">;
+
+export type NewOrder = { Market: NewMarketOrder } | { Limit: NewLimitOrder };
+
+export interface ChannelOpeningParams {
+  trader_reserve: Sats;
+  coordinator_reserve: Sats;
+  /** If set, the channel is opened with funding only from the coordinator. */
+  pre_image: string | null;
+}
+
+export interface NewOrderRequest {
+  value: NewOrder;
+  signature: EcdsaSignature;
+  channel_opening_params: ChannelOpeningParams | null;
+  client_info?: ClientInfo;
+}
+
+export type ClientKind = "App" | "Webapp" | "Sdk";
+
+export interface ClientInfo {
+  kind: ClientKind;
+  version: string;
+}
+
+export interface Order {
+  id: Uuid;
+  price: number;
+  leverage: number;
+  contract_symbol: ContractSymbol;
+  trader_id: PublicKey;
+  direction: Direction;
+  quantity: number;
+  order_type: OrderType;
+  timestamp: Rfc3339;
+  expiry: Rfc3339;
+  order_state: OrderState;
+  order_reason: OrderReason;
+  stable: boolean;
+  /** Only set on the own iceberg orders of a maker. */
+  display_quantity?: number;
+  /** If not set, the contract expires at the next expiry at the time the order is matched. */
+  contract_expiry?: Rfc3339;
+}
+
+export interface Signature {
+  pubkey: PublicKey;
+  signature: EcdsaSignature;
+}
+
+export type OrderbookRequest =
+  | {
+      Authenticate: {
+        fcm_token: string | null;
+        version: string | null;
+        os: string | null;
+        signature: Signature;
+        client_info?: ClientInfo;
+      };
+    }
+  | {
+      AuthenticateWithApiKey: {
+        key_id: Uuid;
+        timestamp: UnixTimestamp;
+        /** A new random value for every request. */
+        nonce: string;
+        /** Hex encoded HMAC-SHA256. */
+        signature: string;
+        client_info?: ClientInfo;
+      };
+    }
+  | { InsertOrder: NewLimitOrder }
+  | { DeleteOrder: Uuid }
+  | { Subscribe: ContractSymbol[] };
+
+export interface LiquidityOption {
+  id: number;
+  rank: number;
+  title: string;
+  trade_up_to_sats: Sats;
+  min_deposit_sats: Sats;
+  max_deposit_sats: Sats;
+  min_fee_sats: Sats;
+  fee_percentage: number;
+  coordinator_leverage: number;
+  created_at: Rfc3339;
+  updated_at: Rfc3339;
+  active: boolean;
+}
+
+export interface ReferralStatus {
+  referral_code: string;
+  number_of_activated_referrals: number;
+  number_of_total_referrals: number;
+  referral_tier: number;
+  referral_fee_bonus: number;
+  bonus_status_type: BonusStatusType | null;
+}
+
+export interface TenTenOneConfig {
+  liquidity_options: LiquidityOption[];
+  min_quantity: number;
+  maintenance_margin_rate: number;
+  order_matching_fee_rate: number;
+  referral_status: ReferralStatus;
+  max_leverage: number;
+  min_channel_collateral_sats: Sats;
+  session_token: SessionToken | null;
+  kill_switch: KillSwitchStatus;
+  /** The number of expiries orders can be placed for, starting with the next expiry. */
+  listed_expiries: number;
+}
+
+export type KillSwitchMode = "read_only" | "no_new_channels" | "no_withdrawals" | "full_halt";
+
+export interface KillSwitchStatus {
+  modes: KillSwitchMode[];
+  reason: string | null;
+}
+
+export interface SessionToken {
+  token: string;
+  expires_at: UnixTimestamp;
+}
+
+export type TradingError =
+  | { InvalidOrder: string }
+  | { NoMatchFound: string }
+  | { Other: string };
+
+export interface LocalizedError {
+  code: ErrorCode;
+  locale: Locale;
+  message: string;
+}
+
+export interface FundingFeeEvent {
+  contract_symbol: ContractSymbol;
+  contracts: DecimalString;
+  direction: Direction;
+  price: number;
+  /** Positive if the trader pays the coordinator, negative if the coordinator pays the trader. */
+  fee: Sats;
+  due_date: TimeTuple;
+}
+
+export interface FundingRate {
+  /** Positive if longs pay shorts, negative if shorts pay longs. */
+  rate: DecimalString;
+  start_date: TimeTuple;
+  end_date: TimeTuple;
+}
+
+export interface Match {
+  id: Uuid;
+  order_id: Uuid;
+  quantity: number;
+  pubkey: PublicKey;
+  execution_price: number;
+  matching_fee: Sats;
+  /** The matched price before the spread was applied. */
+  index_price: number | null;
+  /** The spread applied to `index_price`, in basis points. */
+  spread_bps: number;
+}
+
+export interface FilledWith {
+  order_id: Uuid;
+  expiry_timestamp: TimeTuple;
+  oracle_pk: XOnlyPublicKey;
+  matches: Match[];
+}
+
+/** Messages sent by the orderbook. */
+export type Message =
+  | { AllOrders: Order[] }
+  | { NewOrder: Order }
+  | { DeleteOrder: Uuid }
+  | { Update: Order }
+  | { InvalidAuthentication: string }
+  | { Authenticated: TenTenOneConfig }
+  | {
+      DlcChannelCollaborativeRevert: {
+        /** The DLC channel id as array of 32 bytes. */
+        channel_id: number[];
+        coordinator_address: string;
+        coordinator_amount: Sats;
+        trader_amount: Sats;
+        execution_price: number;
+      };
+    }
+  | {
+      TradeError: {
+        order_id: Uuid;
+        error: TradingError;
+        localized: LocalizedError | null;
+      };
+    }
+  | { LnPaymentReceived: { r_hash: string; amount: Sats } }
+  | { RolloverError: { error: TradingError; localized: LocalizedError | null } }
+  | { FundingFeeEvent: FundingFeeEvent }
+  | { AllFundingFeeEvents: FundingFeeEvent[] }
+  | { NextFundingRate: FundingRate }
+  | { AsyncMatch: { order: Order; filled_with: FilledWith } }
+  | { KillSwitch: KillSwitchStatus };
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "NewMarketOrderParams")]
+    pub type JsNewMarketOrderParams;
+    #[wasm_bindgen(typescript_type = "NewLimitOrderParams")]
+    pub type JsNewLimitOrderParams;
+    #[wasm_bindgen(typescript_type = "NewOrder")]
+    pub type JsNewOrder;
+    #[wasm_bindgen(typescript_type = "ChannelOpeningParams")]
+    pub type JsChannelOpeningParams;
+    #[wasm_bindgen(typescript_type = "NewOrderRequest")]
+    pub type JsNewOrderRequest;
+    #[wasm_bindgen(typescript_type = "OrderbookRequest")]
+    pub type JsOrderbookRequest;
+    #[wasm_bindgen(typescript_type = "Message")]
+    pub type JsMessage;
+}
+
+#[derive(Deserialize)]
+struct NewMarketOrderParams {
+    contract_symbol: ContractSymbol,
+    #[serde(with = "rust_decimal::serde::float")]
+    quantity: rust_decimal::Decimal,
+    trader_id: secp256k1::PublicKey,
+    direction: Direction,
+    #[serde(with = "rust_decimal::serde::float")]
+    leverage: rust_decimal::Decimal,
+    #[serde(with = "time::serde::timestamp")]
+    expiry: OffsetDateTime,
+    stable: bool,
+    #[serde(default, with = "time::serde::timestamp::option")]
+    contract_expiry: Option<OffsetDateTime>,
+}
+
+#[derive(Deserialize)]
+struct NewLimitOrderParams {
+    contract_symbol: ContractSymbol,
+    #[serde(with = "rust_decimal::serde::float")]
+    price: rust_decimal::Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    quantity: rust_decimal::Decimal,
+    trader_id: secp256k1::PublicKey,
+    direction: Direction,
+    #[serde(with = "rust_decimal::serde::float")]
+    leverage: rust_decimal::Decimal,
+    #[serde(with = "time::serde::timestamp")]
+    expiry: OffsetDateTime,
+    stable: bool,
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    display_quantity: Option<rust_decimal::Decimal>,
+    #[serde(default, with = "time::serde::timestamp::option")]
+    contract_expiry: Option<OffsetDateTime>,
+}
+
+/// Build a new market order with a fresh order id.
+#[wasm_bindgen(js_name = newMarketOrder)]
+pub fn new_market_order(params: JsNewMarketOrderParams) -> Result<JsNewOrder, JsError> {
+    let params: NewMarketOrderParams = from_js(&params)?;
+
+    let order = NewOrder::Market(NewMarketOrder {
+        id: Uuid::new_v4(),
+        contract_symbol: params.contract_symbol,
+        quantity: params.quantity,
+        trader_id: params.trader_id,
+        direction: params.direction,
+        leverage: params.leverage,
+        expiry: params.expiry,
+        stable: params.stable,
+        contract_expiry: params.contract_expiry,
+    });
+
+    Ok(to_js(&order)?.unchecked_into())
+}
+
+/// Build a new limit order with a fresh order id.
+#[wasm_bindgen(js_name = newLimitOrder)]
+pub fn new_limit_order(params: JsNewLimitOrderParams) -> Result<JsNewOrder, JsError> {
+    let params: NewLimitOrderParams = from_js(&params)?;
+
+    let order = NewOrder::Limit(NewLimitOrder {
+        id: Uuid::new_v4(),
+        contract_symbol: params.contract_symbol,
+        price: params.price,
+        quantity: params.quantity,
+        trader_id: params.trader_id,
+        direction: params.direction,
+        leverage: params.leverage,
+        expiry: params.expiry,
+        stable: params.stable,
+        display_quantity: params.display_quantity,
+        contract_expiry: params.contract_expiry,
+    });
+
+    Ok(to_js(&order)?.unchecked_into())
+}
+
+/// Sign the order with the hex encoded secret key of the trader, returning the request to post to
+/// the orderbook.
+#[wasm_bindgen(js_name = signNewOrder)]
+pub fn sign_new_order(
+    order: JsNewOrder,
+    secret_key: &str,
+    channel_opening_params: Option<JsChannelOpeningParams>,
+) -> Result<JsNewOrderRequest, JsError> {
+    let order: NewOrder = from_js(&order)?;
+    let channel_opening_params = channel_opening_params
+        .map(|params| from_js::<ChannelOpeningParams>(&params))
+        .transpose()?;
+
+    let secret_key = parse_secret_key(secret_key)?;
+    if secret_key.public_key(SECP256K1) != order.trader_id() {
+        return Err(JsError::new(
+            "The order does not belong to the given secret key",
+        ));
+    }
+
+    let request = NewOrderRequest {
+        signature: secret_key.sign_ecdsa(order.message()),
+        value: order,
+        channel_opening_params,
+        client_info: Some(sdk_client_info()),
+    };
+
+    Ok(to_js(&request)?.unchecked_into())
+}
+
+/// Create the request authenticating the websocket connection with the hex encoded secret key of
+/// the trader.
+///
+/// With `cancel_on_disconnect`, the coordinator deletes all orders of the maker if the connection
+/// drops and is not re-established within its grace period.
+#[wasm_bindgen(js_name = authenticate)]
+pub fn authenticate(
+    secret_key: &str,
+    version: Option<String>,
+    cancel_on_disconnect: Option<bool>,
+) -> Result<JsOrderbookRequest, JsError> {
+    let secret_key = parse_secret_key(secret_key)?;
+
+    let request = OrderbookRequest::Authenticate {
+        fcm_token: None,
+        version,
+        os: Some("web".to_string()),
+        signature: Signature {
+            pubkey: secret_key.public_key(SECP256K1),
+            signature: secret_key.sign_ecdsa(create_sign_message(AUTH_SIGN_MESSAGE.to_vec())),
+        },
+        client_info: Some(sdk_client_info()),
+        cancel_on_disconnect: cancel_on_disconnect.unwrap_or_default(),
+    };
+
+    Ok(to_js(&request)?.unchecked_into())
+}
+
+/// Create the request authenticating the websocket connection with an API key.
+///
+/// The `nonce` has to be a new random value for every request, e.g. from `crypto.randomUUID()`.
+#[wasm_bindgen(js_name = authenticateWithApiKey)]
+pub fn authenticate_with_api_key(
+    key_id: &str,
+    secret: &str,
+    timestamp: f64,
+    nonce: &str,
+    cancel_on_disconnect: Option<bool>,
+) -> Result<JsOrderbookRequest, JsError> {
+    let key_id =
+        Uuid::from_str(key_id).map_err(|e| JsError::new(&format!("Invalid key id: {e}")))?;
+    let timestamp = timestamp as i64;
+
+    let request = OrderbookRequest::AuthenticateWithApiKey {
+        key_id,
+        timestamp,
+        nonce: nonce.to_string(),
+        signature: sign_with_api_secret(secret, &api_key_auth_message(timestamp, nonce)),
+        client_info: Some(sdk_client_info()),
+        cancel_on_disconnect: cancel_on_disconnect.unwrap_or_default(),
+    };
+
+    Ok(to_js(&request)?.unchecked_into())
+}
+
+/// The hex encoded signature of a REST request authenticated with an API key.
+///
+/// The `nonce` has to be a new random value for every request, sent in the `x-10101-nonce`
+/// header.
+#[wasm_bindgen(js_name = signApiRequest)]
+pub fn sign_api_request(
+    secret: &str,
+    timestamp: f64,
+    nonce: &str,
+    method: &str,
+    path: &str,
+    body: &str,
+) -> String {
+    let message = api_request_message(timestamp as i64, nonce, method, path, body.as_bytes());
+
+    sign_with_api_secret(secret, &message)
+}
+
+/// Parse a message received from the orderbook websocket, failing if it does not match the wire
+/// format.
+#[wasm_bindgen(js_name = parseMessage)]
+pub fn parse_message(text: &str) -> Result<JsMessage, JsError> {
+    let message: Message = serde_json::from_str(text)
+        .map_err(|e| JsError::new(&format!("Invalid orderbook message: {e}")))?;
+
+    Ok(to_js(&message)?.unchecked_into())
+}
+
+fn parse_secret_key(secret_key: &str) -> Result<SecretKey, JsError> {
+    SecretKey::from_str(secret_key).map_err(|e| JsError::new(&format!("Invalid secret key: {e}")))
+}
+
+fn from_js<T: DeserializeOwned>(value: &JsValue) -> Result<T, JsError> {
+    let json: String = js_sys::JSON::stringify(value)
+        .map_err(|_| JsError::new("Value cannot be serialized to JSON"))?
+        .into();
+
+    serde_json::from_str(&json).map_err(|e| JsError::new(&format!("Invalid value: {e}")))
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| JsError::new(&format!("Failed to serialize value: {e}")))?;
+
+    js_sys::JSON::parse(&json).map_err(|_| JsError::new("Failed to parse serialized value"))
+}
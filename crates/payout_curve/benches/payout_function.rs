@@ -0,0 +1,132 @@
+//! Measures building the payout function of a contract, which the coordinator does for every
+//! renewal and rollover, and computing its range payouts, which `rust-dlc` derives the CETs from.
+//!
+//! Compare with the time it takes to sign and verify the CET adaptor signatures of a contract,
+//! which dominates a rollover. Run with
+//!
+//! `cargo bench -p payout_curve --bench payout_function`
+//!
+//! and
+//!
+//! `cargo bench -p xxi-node --bench cet_adaptor_signatures`.
+
+#![allow(clippy::unwrap_used)]
+
+use bitcoin::Amount;
+use dlc_manager::payout_curve::PayoutFunction;
+use dlc_manager::payout_curve::PayoutFunctionPiece;
+use dlc_manager::payout_curve::PolynomialPayoutCurvePiece;
+use dlc_manager::payout_curve::RoundingInterval;
+use dlc_manager::payout_curve::RoundingIntervals;
+use payout_curve::build_payout_points;
+use payout_curve::Discretization;
+use payout_curve::PartyParams;
+use payout_curve::PayoutPoint;
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use std::time::Instant;
+use xxi_node::cfd::calculate_margin;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+/// The coordinator spends 100 intervals between the liquidation prices of a payout function.
+const INTERVALS: [u64; 3] = [50, 100, 200];
+
+const ITERATIONS: u32 = 100;
+
+fn main() {
+    let initial_price = dec!(60_000);
+    let quantity = 10_000.0;
+    let leverage_coordinator = dec!(2);
+    let leverage_trader = dec!(5);
+
+    let coordinator = PartyParams::new(
+        calculate_margin(initial_price, quantity, 2.0),
+        Amount::from_sat(50_000),
+    );
+    let trader = PartyParams::new(
+        calculate_margin(initial_price, quantity, 5.0),
+        Amount::from_sat(50_000),
+    );
+    let total_collateral = coordinator.total_collateral() + trader.total_collateral();
+
+    let rounding_intervals = RoundingIntervals {
+        intervals: vec![RoundingInterval {
+            begin_interval: 0,
+            rounding_mod: 1,
+        }],
+    };
+
+    for intervals in INTERVALS {
+        let build_payout_function = || {
+            let payout_points = build_payout_points(
+                ContractSymbol::BtcUsd,
+                initial_price,
+                quantity,
+                coordinator,
+                trader,
+                leverage_coordinator,
+                leverage_trader,
+                Direction::Short,
+                Discretization::Adaptive { intervals },
+            )
+            .unwrap();
+
+            payout_function(&payout_points)
+        };
+
+        let build = measure(build_payout_function);
+
+        let function = build_payout_function();
+        let range_payouts = measure(|| {
+            function
+                .to_range_payouts(total_collateral, &rounding_intervals)
+                .unwrap()
+        });
+
+        let ranges = function
+            .to_range_payouts(total_collateral, &rounding_intervals)
+            .unwrap()
+            .len();
+
+        println!(
+            "{intervals:>4} intervals, {ranges:>4} ranges | build payout function: {build:>10.2?} | \
+             compute range payouts: {range_payouts:>10.2?}"
+        );
+    }
+}
+
+/// The mean duration of running `f`.
+fn measure<T>(f: impl Fn() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(f());
+    }
+
+    start.elapsed() / ITERATIONS
+}
+
+fn payout_function(payout_points: &[(PayoutPoint, PayoutPoint)]) -> PayoutFunction {
+    let pieces = payout_points
+        .iter()
+        .map(|(lower, upper)| {
+            let piece = PolynomialPayoutCurvePiece::new(vec![
+                dlc_manager::payout_curve::PayoutPoint {
+                    event_outcome: lower.event_outcome,
+                    outcome_payout: lower.outcome_payout,
+                    extra_precision: lower.extra_precision,
+                },
+                dlc_manager::payout_curve::PayoutPoint {
+                    event_outcome: upper.event_outcome,
+                    outcome_payout: upper.outcome_payout,
+                    extra_precision: upper.extra_precision,
+                },
+            ])
+            .unwrap();
+
+            PayoutFunctionPiece::PolynomialPayoutCurvePiece(piece)
+        })
+        .collect();
+
+    PayoutFunction::new(pieces).unwrap()
+}
@@ -91,6 +91,7 @@ fn main() -> Result<()> {
         party_params_accept,
         price_params,
         direction_offer,
+        None,
     )?;
 
     discretized_payouts_as_csv(
@@ -121,6 +122,7 @@ fn main() -> Result<()> {
         party_params_accept,
         price_params,
         direction_offer,
+        None,
     )?;
 
     discretized_payouts_as_csv(
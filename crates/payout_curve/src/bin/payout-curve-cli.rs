@@ -0,0 +1,254 @@
+//! Quote and visualize the payout curve of a position without running the coordinator.
+//!
+//! The payout curve is built with the same function the coordinator uses for its contracts, from
+//! the perspective of the coordinator.
+//!
+//! ```sh
+//! cargo run -p payout_curve --bin payout-curve-cli -- \
+//!     --quantity 100 --price 50000 --trader-leverage 2 --direction long --svg payout.svg
+//! ```
+
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::Amount;
+use clap::Parser;
+use clap::ValueEnum;
+use payout_curve::Discretization;
+use payout_curve::PartyParams;
+use payout_curve::PayoutPoint;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use xxi_node::cfd::calculate_margin;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+const SVG_WIDTH: f64 = 800.0;
+const SVG_HEIGHT: f64 = 400.0;
+const SVG_PADDING: f64 = 40.0;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "btcusd")]
+    symbol: ContractSymbol,
+
+    /// The number of contracts.
+    #[clap(long)]
+    quantity: f32,
+
+    /// The price at which the position is opened.
+    #[clap(long)]
+    price: Decimal,
+
+    #[clap(long)]
+    trader_leverage: f32,
+
+    #[clap(long, default_value = "2.0")]
+    coordinator_leverage: f32,
+
+    /// The collateral reserve of the trader, in sats.
+    #[clap(long, default_value = "0")]
+    trader_reserve: u64,
+
+    /// The collateral reserve of the coordinator, in sats.
+    #[clap(long, default_value = "0")]
+    coordinator_reserve: u64,
+
+    /// The direction of the trader.
+    #[clap(long, value_enum)]
+    direction: TraderDirection,
+
+    #[clap(long, value_enum, default_value = "table")]
+    format: Format,
+
+    /// Render the payout curve as SVG to the given file.
+    #[clap(long)]
+    svg: Option<PathBuf>,
+
+    /// The number of intervals between the liquidation prices.
+    #[clap(long, default_value = "100")]
+    intervals: u64,
+
+    /// Use intervals of the same size, instead of finer ones around the price.
+    #[clap(long)]
+    uniform: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TraderDirection {
+    Long,
+    Short,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Csv,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+
+    let trader_direction = match opts.direction {
+        TraderDirection::Long => Direction::Long,
+        TraderDirection::Short => Direction::Short,
+    };
+
+    let coordinator_margin = calculate_margin(opts.price, opts.quantity, opts.coordinator_leverage);
+    let trader_margin = calculate_margin(opts.price, opts.quantity, opts.trader_leverage);
+
+    let coordinator = PartyParams::new(
+        coordinator_margin,
+        Amount::from_sat(opts.coordinator_reserve),
+    );
+    let trader = PartyParams::new(trader_margin, Amount::from_sat(opts.trader_reserve));
+    let total_collateral = coordinator.total_collateral() + trader.total_collateral();
+
+    let discretization = if opts.uniform {
+        Discretization::Uniform {
+            intervals: opts.intervals,
+        }
+    } else {
+        Discretization::Adaptive {
+            intervals: opts.intervals,
+        }
+    };
+
+    let payout_points = payout_curve::build_payout_points(
+        opts.symbol,
+        opts.price,
+        opts.quantity,
+        coordinator,
+        trader,
+        Decimal::from_f32(opts.coordinator_leverage).context("Invalid coordinator leverage")?,
+        Decimal::from_f32(opts.trader_leverage).context("Invalid trader leverage")?,
+        trader_direction.opposite(),
+        discretization,
+    )?;
+
+    match opts.format {
+        Format::Table => print_table(&payout_points, total_collateral),
+        Format::Csv => write_csv(&payout_points, total_collateral)?,
+    }
+
+    if let Some(path) = opts.svg {
+        let svg = render_svg(&payout_points, total_collateral, opts.price);
+        fs::write(&path, svg).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn print_table(payout_points: &[(PayoutPoint, PayoutPoint)], total_collateral: u64) {
+    println!(
+        "{:>10} {:>10} {:>18} {:>18} {:>18} {:>18}",
+        "from", "to", "coordinator (from)", "coordinator (to)", "trader (from)", "trader (to)"
+    );
+
+    for (lower, upper) in payout_points {
+        println!(
+            "{:>10} {:>10} {:>18} {:>18} {:>18} {:>18}",
+            lower.event_outcome,
+            upper.event_outcome,
+            lower.outcome_payout,
+            upper.outcome_payout,
+            total_collateral - lower.outcome_payout,
+            total_collateral - upper.outcome_payout,
+        );
+    }
+}
+
+fn write_csv(payout_points: &[(PayoutPoint, PayoutPoint)], total_collateral: u64) -> Result<()> {
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    wtr.write_record([
+        "from",
+        "to",
+        "coordinator_from",
+        "coordinator_to",
+        "trader_from",
+        "trader_to",
+    ])?;
+
+    for (lower, upper) in payout_points {
+        wtr.write_record([
+            lower.event_outcome.to_string(),
+            upper.event_outcome.to_string(),
+            lower.outcome_payout.to_string(),
+            upper.outcome_payout.to_string(),
+            (total_collateral - lower.outcome_payout).to_string(),
+            (total_collateral - upper.outcome_payout).to_string(),
+        ])?;
+    }
+    wtr.flush()?;
+
+    Ok(())
+}
+
+/// Render the payouts of both parties over the price.
+///
+/// The last interval reaches up to the maximum price we support, so the price axis is cut off at
+/// twice the initial price to keep the interesting part of the curve readable.
+fn render_svg(
+    payout_points: &[(PayoutPoint, PayoutPoint)],
+    total_collateral: u64,
+    initial_price: Decimal,
+) -> String {
+    let max_price = initial_price.to_f64().expect("price to fit into f64") * 2.0;
+
+    let x = |price: u64| SVG_PADDING + (price as f64).min(max_price) / max_price * plot_width();
+    let y = |payout: u64| {
+        SVG_HEIGHT - SVG_PADDING - payout as f64 / total_collateral.max(1) as f64 * plot_height()
+    };
+
+    let mut coordinator = String::new();
+    let mut trader = String::new();
+    for (lower, upper) in payout_points {
+        for point in [lower, upper] {
+            let _ = write!(
+                coordinator,
+                "{:.1},{:.1} ",
+                x(point.event_outcome),
+                y(point.outcome_payout)
+            );
+            let _ = write!(
+                trader,
+                "{:.1},{:.1} ",
+                x(point.event_outcome),
+                y(total_collateral - point.outcome_payout)
+            );
+        }
+    }
+
+    let bottom = SVG_HEIGHT - SVG_PADDING;
+    let right = SVG_WIDTH - SVG_PADDING;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{SVG_WIDTH}" height="{SVG_HEIGHT}" font-family="sans-serif" font-size="12">
+  <line x1="{SVG_PADDING}" y1="{bottom}" x2="{right}" y2="{bottom}" stroke="black"/>
+  <line x1="{SVG_PADDING}" y1="{SVG_PADDING}" x2="{SVG_PADDING}" y2="{bottom}" stroke="black"/>
+  <text x="{SVG_PADDING}" y="{label_y}">0</text>
+  <text x="{right}" y="{label_y}" text-anchor="end">{max_price}</text>
+  <text x="{SVG_PADDING}" y="{top_label_y}">{total_collateral} sats</text>
+  <polyline points="{coordinator}" fill="none" stroke="#1f77b4"/>
+  <polyline points="{trader}" fill="none" stroke="#ff7f0e"/>
+  <text x="{right}" y="{SVG_PADDING}" text-anchor="end" fill="#1f77b4">coordinator</text>
+  <text x="{right}" y="{legend_y}" text-anchor="end" fill="#ff7f0e">trader</text>
+</svg>
+"##,
+        label_y = bottom + 15.0,
+        top_label_y = SVG_PADDING - 5.0,
+        legend_y = SVG_PADDING + 15.0,
+    )
+}
+
+fn plot_width() -> f64 {
+    SVG_WIDTH - 2.0 * SVG_PADDING
+}
+
+fn plot_height() -> f64 {
+    SVG_HEIGHT - 2.0 * SVG_PADDING
+}
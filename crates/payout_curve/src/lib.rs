@@ -6,8 +6,22 @@ use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::Deserialize;
 use serde::Serialize;
+use xxi_node::cfd::calculate_leverage;
+use xxi_node::cfd::calculate_linear_long_bankruptcy_price;
+use xxi_node::cfd::calculate_linear_long_liquidation_price;
+use xxi_node::cfd::calculate_linear_pnl;
+use xxi_node::cfd::calculate_linear_short_bankruptcy_price;
+use xxi_node::cfd::calculate_linear_short_liquidation_price;
+use xxi_node::cfd::calculate_long_bankruptcy_price;
+use xxi_node::cfd::calculate_long_liquidation_price;
 use xxi_node::cfd::calculate_pnl;
-use xxi_node::cfd::BTCUSD_MAX_PRICE;
+use xxi_node::cfd::calculate_short_bankruptcy_price;
+use xxi_node::cfd::calculate_short_liquidation_price;
+use xxi_node::commons::ensure_not_dust;
+use xxi_node::commons::fold_dust_reserve;
+use xxi_node::commons::round_dust_payout;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::ContractType;
 use xxi_node::commons::Direction;
 
 /// Factor by which we can multiply the total margin being wagered in order to get consistent
@@ -15,14 +29,70 @@ use xxi_node::commons::Direction;
 ///
 /// E.g. with a value of 0.01 and a total margin of 20_000 sats would get payout jumps of 200 sats,
 /// for a total of ~100 intervals.
-///
-/// TODO: We should not use the same rounding for all non-constant parts of the payout function,
-/// because not all intervals are equally as likely. That way we can avoid excessive CET generation.
 pub const ROUNDING_PERCENT: f32 = 0.01;
 
 /// Number of intervals which we want to use to discretize the payout function.
 const PAYOUT_CURVE_DISCRETIZATION_INTERVALS: u64 = 200;
 
+/// How the middle (non-constant) part of the payout function is discretized.
+///
+/// Every interval has its own payout and therefore needs its own CETs, so the number of intervals
+/// bounds the number of CETs the parties have to negotiate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Discretization {
+    /// Intervals of the same size between the two liquidation prices.
+    Uniform { intervals: u64 },
+    /// Intervals which are finest around the initial price and grow towards the liquidation
+    /// prices, where the price is less likely to settle.
+    ///
+    /// Half of the `intervals` are spent on either side of the initial price.
+    Adaptive { intervals: u64 },
+}
+
+impl Default for Discretization {
+    fn default() -> Self {
+        Self::Uniform {
+            intervals: PAYOUT_CURVE_DISCRETIZATION_INTERVALS,
+        }
+    }
+}
+
+impl Discretization {
+    /// The boundaries of the intervals between `lower` and `upper`, in ascending order and
+    /// including both ends.
+    fn interval_bounds(&self, lower: u64, initial: u64, upper: u64) -> Vec<u64> {
+        let mut bounds = match *self {
+            Discretization::Uniform { intervals } => {
+                let step = ((upper - lower) / intervals.max(1)).max(1);
+
+                (lower..upper)
+                    .step_by(step as usize)
+                    .chain([upper])
+                    .collect::<Vec<_>>()
+            }
+            Discretization::Adaptive { intervals } => {
+                let initial = initial.clamp(lower, upper);
+                let n = (intervals / 2).max(1);
+
+                // The distance of the k-th boundary from the initial price grows quadratically with
+                // k, so the size of the intervals grows linearly towards the liquidation prices.
+                let offset = |distance: u64, k: u64| {
+                    (distance as u128 * (k * k) as u128 / (n * n) as u128) as u64
+                };
+
+                let below = (1..=n).rev().map(|k| initial - offset(initial - lower, k));
+                let above = (1..=n).map(|k| initial + offset(upper - initial, k));
+
+                below.chain([initial]).chain(above).collect::<Vec<_>>()
+            }
+        };
+
+        bounds.dedup();
+
+        bounds
+    }
+}
+
 /// A payout point representing a payout for a given outcome.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PayoutPoint {
@@ -64,6 +134,16 @@ impl PartyParams {
     pub fn total_collateral(&self) -> u64 {
         self.margin + self.collateral_reserve
     }
+
+    /// Fold a collateral reserve which would be dust into the margin, see [`fold_dust_reserve`].
+    fn fold_dust_reserve(self) -> Self {
+        let (margin, collateral_reserve) = fold_dust_reserve(
+            Amount::from_sat(self.margin),
+            Amount::from_sat(self.collateral_reserve),
+        );
+
+        Self::new(margin, collateral_reserve)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -77,6 +157,8 @@ pub struct PriceParams {
     ///
     /// This is _higher_ than the initial price.
     short_liquidation_price: Decimal,
+    /// The highest price the oracle can attest to for the contract symbol.
+    max_price: u64,
 }
 
 impl PriceParams {
@@ -85,17 +167,34 @@ impl PriceParams {
         long_liquidation: Decimal,
         short_liquidation: Decimal,
     ) -> Result<Self> {
-        // We cap the short liquidation at the maximum possible value of Bitcoin w.r.t to USD that
+        Self::for_symbol(
+            ContractSymbol::BtcUsd,
+            initial,
+            long_liquidation,
+            short_liquidation,
+        )
+    }
+
+    pub fn for_symbol(
+        symbol: ContractSymbol,
+        initial: Decimal,
+        long_liquidation: Decimal,
+        short_liquidation: Decimal,
+    ) -> Result<Self> {
+        let max_price = symbol.max_price();
+
+        // We cap the short liquidation at the maximum possible price of the contract symbol that
         // we support.
-        let short_liquidation = short_liquidation.min(Decimal::from(BTCUSD_MAX_PRICE));
+        let short_liquidation = short_liquidation.min(Decimal::from(max_price));
 
-        Self::new(initial, short_liquidation, long_liquidation)
+        Self::new(initial, short_liquidation, long_liquidation, max_price)
     }
 
     fn new(
         initial: Decimal,
         short_liquidation: Decimal,
         long_liquidation: Decimal,
+        max_price: u64,
     ) -> Result<Self> {
         ensure!(
             long_liquidation <= initial,
@@ -111,10 +210,271 @@ impl PriceParams {
             initial_price: initial,
             short_liquidation_price: short_liquidation,
             long_liquidation_price: long_liquidation,
+            max_price,
         })
     }
 }
 
+/// A partial liquidation of a position, before it is liquidated entirely at its liquidation price.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LiquidationStep {
+    /// The maintenance margin rate at which the step is triggered.
+    ///
+    /// The price of the step is computed like the liquidation price of a position with this
+    /// maintenance margin rate, e.g. with [`calculate_long_liquidation_price`].
+    pub maintenance_margin_rate: Decimal,
+    /// The share of the quantity of the position which is closed at the step.
+    pub share: Decimal,
+}
+
+/// The partial liquidations of both parties, as their margin runs out.
+///
+/// The shares closed at the steps keep the PnL at the price of their step, whereas the rest of the
+/// position keeps following the price until it is liquidated entirely.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaintenanceMarginLadder {
+    /// Ordered by maintenance margin rate, highest first, i.e. in the order the steps are
+    /// triggered as the price moves towards the liquidation price.
+    steps: Vec<LiquidationStep>,
+}
+
+impl MaintenanceMarginLadder {
+    pub fn new(mut steps: Vec<LiquidationStep>) -> Result<Self> {
+        for step in steps.iter() {
+            ensure!(
+                step.maintenance_margin_rate > Decimal::ZERO
+                    && step.maintenance_margin_rate < Decimal::ONE,
+                "Maintenance margin rate of {} not between 0 and 1",
+                step.maintenance_margin_rate
+            );
+            ensure!(
+                step.share > Decimal::ZERO,
+                "Share of {} not positive",
+                step.share
+            );
+        }
+
+        let total_share = steps.iter().map(|step| step.share).sum::<Decimal>();
+        ensure!(
+            total_share < Decimal::ONE,
+            "Steps close {total_share} of the position, leaving nothing to liquidate"
+        );
+
+        steps.sort_by(|a, b| b.maintenance_margin_rate.cmp(&a.maintenance_margin_rate));
+
+        Ok(Self { steps })
+    }
+
+    pub fn steps(&self) -> &[LiquidationStep] {
+        &self.steps
+    }
+}
+
+/// Build the discretized payout function of a perpetual future on the given [`ContractSymbol`]
+/// from the leverages of both parties, from the perspective of the offer party.
+///
+/// This is how the coordinator builds the payout function of every contract it proposes: the
+/// parties are liquidated at their bankruptcy price, i.e. with a maintenance margin of 0%.
+#[allow(clippy::too_many_arguments)]
+pub fn build_payout_points(
+    symbol: ContractSymbol,
+    initial_price: Decimal,
+    // The number of contracts.
+    quantity: f32,
+    offer_party: PartyParams,
+    accept_party: PartyParams,
+    leverage_offer: Decimal,
+    leverage_accept: Decimal,
+    offer_party_direction: Direction,
+    discretization: Discretization,
+) -> Result<Vec<(PayoutPoint, PayoutPoint)>> {
+    build_payout_points_for_contract_type(
+        symbol.contract_type(),
+        symbol,
+        initial_price,
+        quantity,
+        offer_party,
+        accept_party,
+        leverage_offer,
+        leverage_accept,
+        offer_party_direction,
+        discretization,
+    )
+}
+
+/// Build the payout points like [`build_payout_points`], for a contract of the given
+/// [`ContractType`] instead of the one traded under the `symbol`.
+///
+/// The `symbol` only determines the highest price the oracle can attest to. This allows listing a
+/// [`ContractType::Linear`] product on an underlying whose perpetual future is inverse, or vice
+/// versa.
+#[allow(clippy::too_many_arguments)]
+pub fn build_payout_points_for_contract_type(
+    contract_type: ContractType,
+    symbol: ContractSymbol,
+    initial_price: Decimal,
+    // The number of contracts.
+    quantity: f32,
+    offer_party: PartyParams,
+    accept_party: PartyParams,
+    leverage_offer: Decimal,
+    leverage_accept: Decimal,
+    offer_party_direction: Direction,
+    discretization: Discretization,
+) -> Result<Vec<(PayoutPoint, PayoutPoint)>> {
+    let (offer_liquidation_price, accept_liquidation_price) = get_liquidation_prices(
+        contract_type,
+        initial_price,
+        offer_party_direction,
+        leverage_offer,
+        leverage_accept,
+    );
+
+    let (long_liquidation_price, short_liquidation_price) = match offer_party_direction {
+        Direction::Long => (offer_liquidation_price, accept_liquidation_price),
+        Direction::Short => (accept_liquidation_price, offer_liquidation_price),
+    };
+
+    let price_params = PriceParams::for_symbol(
+        symbol,
+        initial_price,
+        long_liquidation_price,
+        short_liquidation_price,
+    )?;
+
+    build_payout_function(
+        contract_type,
+        quantity,
+        offer_party,
+        accept_party,
+        price_params,
+        offer_party_direction,
+        discretization,
+        None,
+    )
+}
+
+/// A funding fee which accrued between the parties, e.g. since their last rollover.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccruedFundingFee {
+    Zero,
+    OfferPays(Amount),
+    AcceptPays(Amount),
+}
+
+/// Build the payout points like [`build_payout_points`], with the accrued `funding_fee` settled in
+/// the payouts.
+///
+/// The paying party pays the funding fee with its margin, so its leverage grows and its
+/// liquidation price moves towards the initial price. The other party gets the funding fee at
+/// every outcome, as if it had been added to its collateral reserve. The collateral of each party
+/// stays the same, so the funding fee does not have to be moved between the parties before
+/// building the payout points. The paying party can't pay more than its margin.
+///
+/// The leverages are the ones before the funding fee is paid.
+///
+/// The collateral reserves come from the balances of an existing DLC channel, so they can't be
+/// rounded like the ones of a new DLC channel. Instead, a collateral reserve which would be dust,
+/// once the funding fee is settled, is folded into the margin of its party, see
+/// [`fold_dust_reserve`].
+#[allow(clippy::too_many_arguments)]
+pub fn build_payout_points_with_funding_fee(
+    symbol: ContractSymbol,
+    initial_price: Decimal,
+    // The number of contracts.
+    quantity: f32,
+    offer_party: PartyParams,
+    accept_party: PartyParams,
+    leverage_offer: Decimal,
+    leverage_accept: Decimal,
+    offer_party_direction: Direction,
+    discretization: Discretization,
+    funding_fee: AccruedFundingFee,
+) -> Result<Vec<(PayoutPoint, PayoutPoint)>> {
+    let quantity_decimal = Decimal::try_from(quantity).context("Quantity to fit into decimal")?;
+
+    let settle = |payer: PartyParams, receiver: PartyParams, fee: Amount| {
+        let fee = fee.to_sat().min(payer.margin);
+
+        let payer = PartyParams {
+            margin: payer.margin - fee,
+            ..payer
+        };
+        let receiver = PartyParams {
+            collateral_reserve: receiver.collateral_reserve + fee,
+            ..receiver
+        };
+        let leverage_payer = calculate_leverage(
+            quantity_decimal,
+            Amount::from_sat(payer.margin),
+            initial_price,
+        );
+
+        (payer, receiver, leverage_payer)
+    };
+
+    let (offer_party, accept_party, leverage_offer, leverage_accept) = match funding_fee {
+        AccruedFundingFee::Zero => (offer_party, accept_party, leverage_offer, leverage_accept),
+        AccruedFundingFee::OfferPays(fee) => {
+            let (offer_party, accept_party, leverage_offer) =
+                settle(offer_party, accept_party, fee);
+
+            (offer_party, accept_party, leverage_offer, leverage_accept)
+        }
+        AccruedFundingFee::AcceptPays(fee) => {
+            let (accept_party, offer_party, leverage_accept) =
+                settle(accept_party, offer_party, fee);
+
+            (offer_party, accept_party, leverage_offer, leverage_accept)
+        }
+    };
+
+    let offer_party = offer_party.fold_dust_reserve();
+    let accept_party = accept_party.fold_dust_reserve();
+
+    build_payout_points(
+        symbol,
+        initial_price,
+        quantity,
+        offer_party,
+        accept_party,
+        leverage_offer,
+        leverage_accept,
+        offer_party_direction,
+        discretization,
+    )
+}
+
+/// Returns the liquidation price for `(offer, accept)` with a maintenance margin of 0%, also known
+/// as the bankruptcy price.
+pub fn get_liquidation_prices(
+    contract_type: ContractType,
+    initial_price: Decimal,
+    offer_direction: Direction,
+    leverage_offer: Decimal,
+    leverage_accept: Decimal,
+) -> (Decimal, Decimal) {
+    let long_bankruptcy_price = |leverage| match contract_type {
+        ContractType::Inverse => calculate_long_bankruptcy_price(leverage, initial_price),
+        ContractType::Linear => calculate_linear_long_bankruptcy_price(leverage, initial_price),
+    };
+    let short_bankruptcy_price = |leverage| match contract_type {
+        ContractType::Inverse => calculate_short_bankruptcy_price(leverage, initial_price),
+        ContractType::Linear => calculate_linear_short_bankruptcy_price(leverage, initial_price),
+    };
+
+    match offer_direction {
+        Direction::Long => (
+            long_bankruptcy_price(leverage_offer),
+            short_bankruptcy_price(leverage_accept),
+        ),
+        Direction::Short => (
+            short_bankruptcy_price(leverage_offer),
+            long_bankruptcy_price(leverage_accept),
+        ),
+    }
+}
+
 /// Build a discretized payout function for an inverse perpetual future (e.g. BTCUSD) from the
 /// perspective of the offer party.
 ///
@@ -123,6 +483,15 @@ impl PriceParams {
 ///
 /// Each tuple is meant to map to one [`dlc_manager::payout_curve::PolynomialPayoutCurvePiece`] when
 /// building the corresponding [`dlc_manager::payout_curve::PayoutFunction`].
+///
+/// The payouts of the offer party leave the accept party with the rest of the total collateral at
+/// every point. Rounding dust in the middle part of the payout function is settled with the payout
+/// of the party going long, so both parties arrive at the same payouts.
+///
+/// Without a `ladder`, the payout of a party jumps to its collateral reserve at its liquidation
+/// price. With a [`MaintenanceMarginLadder`], shares of its position are liquidated on the way, and
+/// the party keeps what is left of its margin when it is liquidated entirely. This only adds up
+/// if the liquidation prices are the bankruptcy prices of the parties.
 pub fn build_inverse_payout_function(
     // The number of contracts.
     quantity: f32,
@@ -130,7 +499,77 @@ pub fn build_inverse_payout_function(
     accept_party: PartyParams,
     price_params: PriceParams,
     offer_party_direction: Direction,
+    ladder: Option<&MaintenanceMarginLadder>,
+) -> Result<Vec<(PayoutPoint, PayoutPoint)>> {
+    build_payout_function(
+        ContractType::Inverse,
+        quantity,
+        offer_party,
+        accept_party,
+        price_params,
+        offer_party_direction,
+        Discretization::default(),
+        ladder,
+    )
+}
+
+/// Build a discretized payout function for a linear perpetual future from the perspective of the
+/// offer party.
+///
+/// The payout function has the same shape as the one built by [`build_inverse_payout_function`],
+/// but the payout grows proportionally with the price between the two liquidation prices.
+pub fn build_linear_payout_function(
+    // The number of contracts.
+    quantity: f32,
+    offer_party: PartyParams,
+    accept_party: PartyParams,
+    price_params: PriceParams,
+    offer_party_direction: Direction,
 ) -> Result<Vec<(PayoutPoint, PayoutPoint)>> {
+    build_payout_function(
+        ContractType::Linear,
+        quantity,
+        offer_party,
+        accept_party,
+        price_params,
+        offer_party_direction,
+        Discretization::default(),
+        None,
+    )
+}
+
+/// Build a discretized payout function for a perpetual future of the given [`ContractType`] from
+/// the perspective of the offer party.
+///
+/// The PnL of an [`ContractType::Inverse`] contract is settled in the base asset, e.g. BTC for
+/// BTCUSD, whereas the PnL of a [`ContractType::Linear`] contract is settled in the quote asset
+/// and converted to sats at the initial price. See [`build_inverse_payout_function`] for the
+/// shape of the returned payout points.
+///
+/// The `discretization` determines the intervals between the two liquidation prices, see
+/// [`Discretization`]. The `ladder` adds partial liquidations, see
+/// [`build_inverse_payout_function`].
+#[allow(clippy::too_many_arguments)]
+pub fn build_payout_function(
+    contract_type: ContractType,
+    quantity: f32,
+    offer_party: PartyParams,
+    accept_party: PartyParams,
+    price_params: PriceParams,
+    offer_party_direction: Direction,
+    discretization: Discretization,
+    ladder: Option<&MaintenanceMarginLadder>,
+) -> Result<Vec<(PayoutPoint, PayoutPoint)>> {
+    // The payout of a liquidated party is its collateral reserve.
+    ensure_not_dust(
+        "offer collateral reserve",
+        Amount::from_sat(offer_party.collateral_reserve),
+    )?;
+    ensure_not_dust(
+        "accept collateral reserve",
+        Amount::from_sat(accept_party.collateral_reserve),
+    )?;
+
     let mut pieces = vec![];
 
     let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
@@ -146,6 +585,22 @@ pub fn build_inverse_payout_function(
         ),
     };
 
+    let position = Position::new(
+        contract_type,
+        quantity,
+        offer_party,
+        accept_party,
+        offer_party_direction,
+        price_params,
+        ladder,
+    );
+
+    // A party which is liquidated entirely keeps what its partial liquidations left it with.
+    let collateral_reserve_long =
+        collateral_reserve_long + position.remaining_margin(Direction::Long)?;
+    let collateral_reserve_short =
+        collateral_reserve_short + position.remaining_margin(Direction::Short)?;
+
     let (long_liquidation_interval_start, long_liquidation_interval_end) =
         calculate_long_liquidation_interval_payouts(
             offer_party_direction,
@@ -164,16 +619,17 @@ pub fn build_inverse_payout_function(
             total_collateral,
             price_params.short_liquidation_price,
             collateral_reserve_short,
+            price_params.max_price,
         )?;
 
     let mid_range = calculate_mid_range_payouts(
         offer_party,
         accept_party,
-        price_params.initial_price,
+        &position,
         &long_liquidation_interval_end,
         &short_liquidation_interval_start,
         offer_party_direction,
-        quantity,
+        discretization,
     )?;
 
     for (lower, upper) in mid_range.iter() {
@@ -223,6 +679,8 @@ pub fn build_inverse_payout_function(
         .filter(|(start, end)| start.event_outcome != end.event_outcome)
         .collect::<Vec<_>>();
 
+    ensure_payouts_within_total_collateral(&pieces, offer_party, accept_party)?;
+
     Ok(pieces)
 }
 
@@ -285,7 +743,7 @@ fn calculate_long_liquidation_interval_payouts(
 fn calculate_mid_range_payouts(
     offer_party: PartyParams,
     accept_party: PartyParams,
-    initial_price: Decimal,
+    position: &Position,
     // The end of the price interval within which the party going long gets liquidated. This is the
     // highest of the two points in terms of price.
     long_liquidation_interval_end_payout: &PayoutPoint,
@@ -293,62 +751,57 @@ fn calculate_mid_range_payouts(
     // the lowest of the two points in terms of price.
     short_liquidation_interval_start_payout: &PayoutPoint,
     offer_direction: Direction,
-    quantity: f32,
+    discretization: Discretization,
 ) -> Result<Vec<(PayoutPoint, PayoutPoint)>> {
     let long_liquidation_price = long_liquidation_interval_end_payout.event_outcome;
     let short_liquidation_price = short_liquidation_interval_start_payout.event_outcome;
 
-    let min_payout_offer_party = offer_party.collateral_reserve;
-
-    // This excludes the collateral reserve of the accept party.
-    let max_payout_offer_party = offer_party.total_collateral() + accept_party.margin;
-
-    let (long_margin, short_margin) = match offer_direction {
-        Direction::Long => (offer_party.margin, accept_party.margin),
-        Direction::Short => (accept_party.margin, offer_party.margin),
-    };
-
-    let step = {
-        let diff = short_liquidation_price
-            .checked_sub(long_liquidation_price)
-            .context("Long liquidation price smaller than short liquidation price")?;
-        diff / PAYOUT_CURVE_DISCRETIZATION_INTERVALS
-    };
-
-    let pieces = (long_liquidation_price..short_liquidation_price)
-        .step_by(step as usize)
-        .map(|interval_start_price| {
-            let interval_mid_price = interval_start_price + step / 2;
-
-            let pnl = calculate_pnl(
-                initial_price,
-                Decimal::from(interval_mid_price),
-                quantity,
+    ensure!(
+        long_liquidation_price <= short_liquidation_price,
+        "Short liquidation price smaller than long liquidation price"
+    );
+
+    let mut bounds = discretization.interval_bounds(
+        long_liquidation_price,
+        position.initial_price.to_u64().expect("to fit into u64"),
+        short_liquidation_price,
+    );
+
+    // The payout changes its slope at every partial liquidation.
+    bounds.extend(position.step_prices());
+    bounds.sort();
+    bounds.dedup();
+
+    let pieces = bounds
+        .windows(2)
+        .map(|interval| {
+            let (interval_start_price, interval_end_price) = (interval[0], interval[1]);
+            let interval_mid_price =
+                interval_start_price + (interval_end_price - interval_start_price) / 2;
+
+            let offer_pnl = position.pnl(interval_mid_price, offer_direction)?;
+            let accept_pnl = position.pnl(interval_mid_price, offer_direction.opposite())?;
+
+            let offer_payout = offer_party.total_collateral() as i64 + offer_pnl;
+            let accept_payout = accept_party.total_collateral() as i64 + accept_pnl;
+
+            let interval_payout = settle_rounding_dust(
+                offer_payout,
+                accept_payout,
+                offer_party,
+                accept_party,
                 offer_direction,
-                long_margin,
-                short_margin,
-            )?;
-
-            // If this is the start of the middle interval after the long liquidation interval.
-            let interval_payout = offer_party.total_collateral() as i64 + pnl;
-
-            // Payout cannot be below min.
-            let interval_payout = interval_payout.max(min_payout_offer_party as i64);
-
-            // Payout cannot be above max.
-            let interval_payout = interval_payout.min(max_payout_offer_party as i64);
+            );
 
             let interval_start_payout_point = PayoutPoint {
                 event_outcome: interval_start_price,
-                outcome_payout: interval_payout as u64,
+                outcome_payout: interval_payout,
                 extra_precision: 0,
             };
 
-            let interval_end_price = (interval_start_price + step).min(short_liquidation_price);
-
             let interval_end_payout_point = PayoutPoint {
                 event_outcome: interval_end_price,
-                outcome_payout: interval_payout as u64,
+                outcome_payout: interval_payout,
                 extra_precision: 0,
             };
 
@@ -359,22 +812,289 @@ fn calculate_mid_range_payouts(
     Ok(pieces)
 }
 
+/// The position between both parties, see [`build_payout_function`].
+struct Position {
+    contract_type: ContractType,
+    initial_price: Decimal,
+    quantity: f32,
+    long_margin: u64,
+    short_margin: u64,
+    /// The prices at which shares of the position of the party going long are liquidated, and the
+    /// shares. The prices are between the liquidation price and the initial price.
+    long_steps: Vec<(u64, Decimal)>,
+    /// The prices at which shares of the position of the party going short are liquidated, and
+    /// the shares. The prices are between the initial price and the liquidation price.
+    short_steps: Vec<(u64, Decimal)>,
+}
+
+impl Position {
+    fn new(
+        contract_type: ContractType,
+        quantity: f32,
+        offer_party: PartyParams,
+        accept_party: PartyParams,
+        offer_direction: Direction,
+        price_params: PriceParams,
+        ladder: Option<&MaintenanceMarginLadder>,
+    ) -> Self {
+        let initial_price = price_params.initial_price;
+
+        let (long_margin, short_margin) = match offer_direction {
+            Direction::Long => (offer_party.margin, accept_party.margin),
+            Direction::Short => (accept_party.margin, offer_party.margin),
+        };
+
+        let leverage = |margin| {
+            calculate_leverage(
+                Decimal::try_from(quantity).expect("quantity to fit into decimal"),
+                Amount::from_sat(margin),
+                initial_price,
+            )
+        };
+        let (long_leverage, short_leverage) = (leverage(long_margin), leverage(short_margin));
+
+        let to_price = |price: Decimal, lower: Decimal, upper: Decimal| {
+            price
+                .clamp(lower, upper)
+                .to_u64()
+                .expect("price to fit into u64")
+        };
+
+        let steps = ladder.map(|ladder| ladder.steps()).unwrap_or_default();
+
+        let long_steps = steps
+            .iter()
+            .map(|step| {
+                let price = match contract_type {
+                    ContractType::Inverse => calculate_long_liquidation_price(
+                        long_leverage,
+                        initial_price,
+                        step.maintenance_margin_rate,
+                    ),
+                    ContractType::Linear => calculate_linear_long_liquidation_price(
+                        long_leverage,
+                        initial_price,
+                        step.maintenance_margin_rate,
+                    ),
+                };
+                let price = to_price(price, price_params.long_liquidation_price, initial_price);
+
+                (price, step.share)
+            })
+            .collect();
+
+        let short_steps = steps
+            .iter()
+            .map(|step| {
+                let price = match contract_type {
+                    ContractType::Inverse => calculate_short_liquidation_price(
+                        short_leverage,
+                        initial_price,
+                        step.maintenance_margin_rate,
+                    ),
+                    ContractType::Linear => calculate_linear_short_liquidation_price(
+                        short_leverage,
+                        initial_price,
+                        step.maintenance_margin_rate,
+                    ),
+                };
+                let price = to_price(price, initial_price, price_params.short_liquidation_price);
+
+                (price, step.share)
+            })
+            .collect();
+
+        Self {
+            contract_type,
+            initial_price,
+            quantity,
+            long_margin,
+            short_margin,
+            long_steps,
+            short_steps,
+        }
+    }
+
+    fn step_prices(&self) -> impl Iterator<Item = u64> + '_ {
+        self.long_steps
+            .iter()
+            .chain(self.short_steps.iter())
+            .map(|(price, _)| *price)
+    }
+
+    /// The PnL of the party going in `direction` if the price ends up at `price`.
+    ///
+    /// The shares which were liquidated on the way to `price` are closed at the price of their
+    /// step.
+    fn pnl(&self, price: u64, direction: Direction) -> Result<i64> {
+        let liquidated = self
+            .long_steps
+            .iter()
+            .filter(|(step_price, _)| price <= *step_price)
+            .chain(
+                self.short_steps
+                    .iter()
+                    .filter(|(step_price, _)| price >= *step_price),
+            );
+
+        let mut pnl = 0;
+        let mut open_share = Decimal::ONE;
+        for (step_price, share) in liquidated {
+            pnl += self.share_pnl(*step_price, *share, direction)?;
+            open_share -= *share;
+        }
+
+        pnl += self.share_pnl(price, open_share, direction)?;
+
+        Ok(pnl)
+    }
+
+    /// What is left of the margin of the party going in `direction` from its partial
+    /// liquidations, once it is liquidated entirely.
+    fn remaining_margin(&self, direction: Direction) -> Result<u64> {
+        let (steps, margin) = match direction {
+            Direction::Long => (&self.long_steps, self.long_margin),
+            Direction::Short => (&self.short_steps, self.short_margin),
+        };
+
+        let mut remaining_margin = 0;
+        for (step_price, share) in steps {
+            let margin = (Decimal::from(margin) * *share)
+                .to_i64()
+                .expect("margin to fit into i64");
+            let pnl = self.share_pnl(*step_price, *share, direction)?;
+
+            remaining_margin += (margin + pnl).max(0) as u64;
+        }
+
+        Ok(remaining_margin)
+    }
+
+    fn share_pnl(&self, price: u64, share: Decimal, direction: Direction) -> Result<i64> {
+        let calculate_pnl = match self.contract_type {
+            ContractType::Inverse => calculate_pnl,
+            ContractType::Linear => calculate_linear_pnl,
+        };
+
+        let quantity =
+            Decimal::try_from(self.quantity).expect("quantity to fit into decimal") * share;
+
+        calculate_pnl(
+            self.initial_price,
+            Decimal::from(price),
+            quantity.to_f32().expect("quantity to fit into f32"),
+            direction,
+            self.long_margin,
+            self.short_margin,
+        )
+    }
+}
+
+/// Settle the rounding dust between the payouts of the offer and the accept party, computed
+/// independently from their own PnL, and return the payout of the offer party.
+///
+/// Both payouts must add up to exactly the total collateral, otherwise the counterparty rejects the
+/// contract. Rounding can leave their sum a few sats off, so the dust is settled with the following
+/// rule: the party going short gets its payout, bounded by its collateral reserve and by what it
+/// can win, and the party going long gets the rest of the total collateral.
+///
+/// The rule only depends on the direction of the parties, so both parties arrive at the same
+/// payouts. Since the bounds of both parties mirror each other, the payout of the party going long
+/// is within its bounds too.
+///
+/// Finally, a payout which would be a dust output in the CET goes to the counterparty, see
+/// [`round_dust_payout`]. This never undercuts a collateral reserve, since dust reserves are
+/// refused or folded into the margin.
+fn settle_rounding_dust(
+    offer_payout: i64,
+    accept_payout: i64,
+    offer_party: PartyParams,
+    accept_party: PartyParams,
+    offer_direction: Direction,
+) -> u64 {
+    let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
+
+    let (short_payout, short_party, long_party) = match offer_direction {
+        Direction::Long => (accept_payout, accept_party, offer_party),
+        Direction::Short => (offer_payout, offer_party, accept_party),
+    };
+
+    // The short party gets at least its collateral reserve and at most everything but the
+    // collateral reserve of the long party.
+    let short_payout = short_payout.clamp(
+        short_party.collateral_reserve as i64,
+        (short_party.total_collateral() + long_party.margin) as i64,
+    ) as u64;
+
+    let offer_payout = match offer_direction {
+        Direction::Long => total_collateral - short_payout,
+        Direction::Short => short_payout,
+    };
+
+    round_dust_payout(
+        Amount::from_sat(offer_payout),
+        Amount::from_sat(total_collateral),
+    )
+    .to_sat()
+}
+
+/// Ensure that every payout point of the offer party leaves both parties at least their
+/// collateral reserve, i.e. that the payouts of both parties add up to the total collateral.
+fn ensure_payouts_within_total_collateral(
+    pieces: &[(PayoutPoint, PayoutPoint)],
+    offer_party: PartyParams,
+    accept_party: PartyParams,
+) -> Result<()> {
+    let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
+
+    for point in pieces.iter().flat_map(|(start, end)| [start, end]) {
+        let offer_payout = point.outcome_payout;
+
+        ensure!(
+            offer_payout >= offer_party.collateral_reserve,
+            "Payout of offer party at {} below its collateral reserve: {offer_payout} < {}",
+            point.event_outcome,
+            offer_party.collateral_reserve
+        );
+
+        let accept_payout = total_collateral
+            .checked_sub(offer_payout)
+            .with_context(|| {
+                format!(
+                    "Payout of offer party at {} exceeds total collateral: {offer_payout} > \
+                     {total_collateral}",
+                    point.event_outcome
+                )
+            })?;
+
+        ensure!(
+            accept_payout >= accept_party.collateral_reserve,
+            "Payout of accept party at {} below its collateral reserve: {accept_payout} < {}",
+            point.event_outcome,
+            accept_party.collateral_reserve
+        );
+    }
+
+    Ok(())
+}
+
 /// Calculate the payout points for the interval where the party going short gets liquidated, from
 /// the perspective of the offer party.
 ///
-/// The price ranges from the `short_liquidation_price` to `BTCUSD_MAX_PRICE`.
+/// The price ranges from the `short_liquidation_price` to the `max_price` of the contract symbol.
 fn calculate_short_liquidation_interval_payouts(
     offer_direction: Direction,
     total_collateral: u64,
     liquidation_price_short: Decimal,
     collateral_reserve_short: u64,
+    max_price: u64,
 ) -> Result<(PayoutPoint, PayoutPoint)> {
     let liquidation_price_short = {
         let price = liquidation_price_short.to_u64().expect("to fit");
 
         // We cannot end up generating an interval with a constant price, because `rust-dlc` says
         // that `Payout points must have ascending event outcome value`.
-        if price == BTCUSD_MAX_PRICE {
+        if price == max_price {
             price - 1
         } else {
             price
@@ -394,7 +1114,7 @@ fn calculate_short_liquidation_interval_payouts(
             };
 
             let interval_end = PayoutPoint {
-                event_outcome: BTCUSD_MAX_PRICE,
+                event_outcome: max_price,
                 outcome_payout,
                 extra_precision: 0,
             };
@@ -412,7 +1132,7 @@ fn calculate_short_liquidation_interval_payouts(
             };
 
             let interval_end = PayoutPoint {
-                event_outcome: BTCUSD_MAX_PRICE,
+                event_outcome: max_price,
                 outcome_payout,
                 extra_precision: 0,
             };
@@ -434,9 +1154,69 @@ mod tests {
     use rust_decimal_macros::dec;
     use std::fs::File;
     use std::ops::Mul;
+    use xxi_node::cfd::calculate_linear_long_bankruptcy_price;
+    use xxi_node::cfd::calculate_linear_short_bankruptcy_price;
     use xxi_node::cfd::calculate_long_bankruptcy_price;
     use xxi_node::cfd::calculate_margin;
     use xxi_node::cfd::calculate_short_bankruptcy_price;
+    use xxi_node::cfd::BTCUSD_MAX_PRICE;
+
+    #[test]
+    fn calculate_liquidation_price_offer_long() {
+        let initial_price = dec!(30_000);
+        let offer_direction = Direction::Long;
+        let leverage_offer = dec!(2.0);
+        let leverage_accept = dec!(3.0);
+
+        let (offer, accept) = get_liquidation_prices(
+            ContractType::Inverse,
+            initial_price,
+            offer_direction,
+            leverage_offer,
+            leverage_accept,
+        );
+
+        assert_eq!(offer, dec!(20_000));
+        assert_eq!(accept, dec!(45_000));
+    }
+
+    #[test]
+    fn calculate_liquidation_price_offer_short() {
+        let initial_price = dec!(30_000);
+        let offer_direction = Direction::Short;
+        let leverage_offer = dec!(2.0);
+        let leverage_accept = dec!(3.0);
+
+        let (offer, accept) = get_liquidation_prices(
+            ContractType::Inverse,
+            initial_price,
+            offer_direction,
+            leverage_offer,
+            leverage_accept,
+        );
+
+        assert_eq!(offer, dec!(60_000));
+        assert_eq!(accept, dec!(22_500));
+    }
+
+    #[test]
+    fn calculate_linear_liquidation_price_offer_long() {
+        let initial_price = dec!(30_000);
+        let offer_direction = Direction::Long;
+        let leverage_offer = dec!(2.0);
+        let leverage_accept = dec!(3.0);
+
+        let (offer, accept) = get_liquidation_prices(
+            ContractType::Linear,
+            initial_price,
+            offer_direction,
+            leverage_offer,
+            leverage_accept,
+        );
+
+        assert_eq!(offer, dec!(15_000));
+        assert_eq!(accept, dec!(40_000));
+    }
 
     /// set this to true to export test data to csv files
     /// An example gnuplot file has been provided in [`payout_curve.gp`]
@@ -591,6 +1371,7 @@ mod tests {
             initial_price,
             long_liquidation_price,
             short_liquidation_price,
+            max_price: BTCUSD_MAX_PRICE,
         };
 
         let payout_function = build_inverse_payout_function(
@@ -599,12 +1380,206 @@ mod tests {
             accept_party,
             price_params,
             offer_party_direction,
+            None,
         )
         .unwrap();
 
         assert_debug_snapshot!(payout_function);
     }
 
+    #[test]
+    fn linear_payout_function_grows_proportionally_with_price() {
+        let quantity = 60_000.0;
+        let initial_price = dec!(30_000);
+        let leverage = Decimal::TWO;
+
+        let margin = calculate_margin(initial_price, quantity, leverage.to_f32().unwrap());
+
+        let party = PartyParams {
+            margin: margin.to_sat(),
+            collateral_reserve: 0,
+        };
+
+        let price_params = PriceParams {
+            initial_price,
+            long_liquidation_price: calculate_linear_long_bankruptcy_price(leverage, initial_price),
+            short_liquidation_price: calculate_linear_short_bankruptcy_price(
+                leverage,
+                initial_price,
+            ),
+            max_price: BTCUSD_MAX_PRICE,
+        };
+
+        let payout_function =
+            build_linear_payout_function(quantity, party, party, price_params, Direction::Long)
+                .unwrap();
+
+        // The constant step-up intervals in between are skipped.
+        let mid_range = payout_function
+            .iter()
+            .filter(|(start, end)| {
+                start.outcome_payout == end.outcome_payout
+                    && start.event_outcome >= 15_000
+                    && end.event_outcome <= 45_000
+            })
+            .collect::<Vec<_>>();
+
+        let payout_increments = mid_range
+            .windows(2)
+            .map(|pair| pair[1].0.outcome_payout as i64 - pair[0].0.outcome_payout as i64)
+            .collect::<Vec<_>>();
+
+        let min_increment = payout_increments.iter().min().unwrap();
+        let max_increment = payout_increments.iter().max().unwrap();
+
+        // Every price step of $150 adds the same amount of sats, give or take rounding.
+        assert!(*min_increment > 0);
+        assert!(max_increment - min_increment <= 1);
+    }
+
+    #[test]
+    fn payout_points_follow_pnl_of_selected_contract_type() {
+        let quantity = 60_000.0;
+        let initial_price = dec!(30_000);
+        let leverage = Decimal::TWO;
+
+        let margin = calculate_margin(initial_price, quantity, leverage.to_f32().unwrap());
+        let party = PartyParams::new(margin, Amount::from_sat(10_000));
+
+        for contract_type in [ContractType::Inverse, ContractType::Linear] {
+            let payout_points = build_payout_points_for_contract_type(
+                contract_type,
+                ContractSymbol::BtcUsd,
+                initial_price,
+                quantity,
+                party,
+                party,
+                leverage,
+                leverage,
+                Direction::Long,
+                Discretization::Uniform { intervals: 100 },
+            )
+            .unwrap();
+
+            let calculate_pnl = match contract_type {
+                ContractType::Inverse => calculate_pnl,
+                ContractType::Linear => calculate_linear_pnl,
+            };
+
+            let (long_liquidation_price, short_liquidation_price) = get_liquidation_prices(
+                contract_type,
+                initial_price,
+                Direction::Long,
+                leverage,
+                leverage,
+            );
+            let long_liquidation_price = long_liquidation_price.to_u64().unwrap();
+            let short_liquidation_price = short_liquidation_price.to_u64().unwrap();
+
+            // The constant intervals between the liquidation prices, without the step-up
+            // intervals in between. Each of them ended one dollar higher before being connected to
+            // the next one.
+            let mid_range = payout_points
+                .iter()
+                .filter(|(start, end)| {
+                    start.outcome_payout == end.outcome_payout
+                        && start.event_outcome >= long_liquidation_price
+                        && end.event_outcome < short_liquidation_price
+                })
+                .collect::<Vec<_>>();
+            assert!(!mid_range.is_empty());
+
+            for (start, end) in mid_range {
+                let mid_price =
+                    start.event_outcome + (end.event_outcome + 1 - start.event_outcome) / 2;
+
+                let pnl = calculate_pnl(
+                    initial_price,
+                    Decimal::from(mid_price),
+                    quantity,
+                    Direction::Long,
+                    margin.to_sat(),
+                    margin.to_sat(),
+                )
+                .unwrap();
+                let expected_payout = party.total_collateral() as i64 + pnl;
+
+                assert!(
+                    (start.outcome_payout as i64 - expected_payout).abs() <= 1,
+                    "{contract_type:?} payout at {mid_price} is {}, expected {expected_payout}",
+                    start.outcome_payout
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn payout_points_of_symbol_use_its_contract_type() {
+        let quantity = 60_000.0;
+        let initial_price = dec!(3_000);
+        let leverage = Decimal::TWO;
+
+        let margin = calculate_margin(initial_price, quantity, leverage.to_f32().unwrap());
+        let party = PartyParams::new(margin, Amount::from_sat(10_000));
+
+        let build = |contract_type| {
+            build_payout_points_for_contract_type(
+                contract_type,
+                ContractSymbol::EthUsd,
+                initial_price,
+                quantity,
+                party,
+                party,
+                leverage,
+                leverage,
+                Direction::Short,
+                Discretization::default(),
+            )
+            .unwrap()
+        };
+
+        let payout_points = build_payout_points(
+            ContractSymbol::EthUsd,
+            initial_price,
+            quantity,
+            party,
+            party,
+            leverage,
+            leverage,
+            Direction::Short,
+            Discretization::default(),
+        )
+        .unwrap();
+
+        assert_eq!(payout_points, build(ContractType::Linear));
+        assert_ne!(payout_points, build(ContractType::Inverse));
+    }
+
+    #[test]
+    fn adaptive_intervals_grow_towards_liquidation_prices() {
+        let bounds =
+            Discretization::Adaptive { intervals: 100 }.interval_bounds(20_000, 30_000, 60_000);
+
+        assert_eq!(bounds.first(), Some(&20_000));
+        assert_eq!(bounds.last(), Some(&60_000));
+        assert!(bounds.contains(&30_000));
+        assert!(bounds.len() - 1 <= 100);
+
+        let sizes = bounds
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect::<Vec<_>>();
+        let initial = bounds.iter().position(|bound| *bound == 30_000).unwrap();
+
+        // Shrinking towards the initial price from below and growing away from it above.
+        assert!(sizes[..initial].windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(sizes[initial..].windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // Finer than uniform intervals around the initial price, with half the intervals.
+        let uniform = Discretization::default().interval_bounds(20_000, 30_000, 60_000);
+        assert!(sizes[initial] < uniform[1] - uniform[0]);
+    }
+
     #[test]
     fn ensure_all_bounds_smaller_or_equal_max_btc_price() {
         // setup
@@ -633,10 +1608,25 @@ mod tests {
         // act: offer long
         let offer_direction = Direction::Long;
 
+        let position = Position::new(
+            ContractType::Inverse,
+            quantity,
+            party_params_offer,
+            party_params_accept,
+            offer_direction,
+            PriceParams {
+                initial_price,
+                long_liquidation_price,
+                short_liquidation_price,
+                max_price: BTCUSD_MAX_PRICE,
+            },
+            None,
+        );
+
         let mid_range_payouts_offer_long = calculate_mid_range_payouts(
             party_params_offer,
             party_params_accept,
-            initial_price,
+            &position,
             &PayoutPoint {
                 event_outcome: long_liquidation_price.to_u64().unwrap(),
                 outcome_payout: party_params_offer.collateral_reserve,
@@ -649,7 +1639,7 @@ mod tests {
                 extra_precision: 0,
             },
             offer_direction,
-            quantity,
+            Discretization::default(),
         )
         .expect("To be able to compute mid range");
 
@@ -685,6 +1675,7 @@ mod tests {
             total_collateral,
             liquidation_price_short,
             collateral_reserve_offer,
+            BTCUSD_MAX_PRICE,
         )
         .unwrap();
 
@@ -719,6 +1710,7 @@ mod tests {
             total_collateral,
             liquidation_price_short,
             collateral_reserve_accept,
+            BTCUSD_MAX_PRICE,
         )
         .unwrap();
 
@@ -757,6 +1749,7 @@ mod tests {
             total_collateral,
             Decimal::from(BTCUSD_MAX_PRICE),
             collateral_reserve_accept,
+            BTCUSD_MAX_PRICE,
         )
         .unwrap();
 
@@ -830,7 +1823,7 @@ mod tests {
             let offer_direction = Direction::Short;
 
             let (lower, upper) =
-                calculate_short_liquidation_interval_payouts(offer_direction, total_collateral, Decimal::from(bound), collateral_reserve_short).unwrap();
+                calculate_short_liquidation_interval_payouts(offer_direction, total_collateral, Decimal::from(bound), collateral_reserve_short, BTCUSD_MAX_PRICE).unwrap();
 
             // assert
             prop_assert_eq!(lower.event_outcome, bound);
@@ -850,7 +1843,7 @@ mod tests {
             let offer_direction = Direction::Long;
 
             let (lower, upper) =
-                calculate_short_liquidation_interval_payouts(offer_direction, total_collateral, Decimal::from(bound), collateral_reserve_short).unwrap();
+                calculate_short_liquidation_interval_payouts(offer_direction, total_collateral, Decimal::from(bound), collateral_reserve_short, BTCUSD_MAX_PRICE).unwrap();
 
             // assert
             assert_eq!(lower.event_outcome, bound);
@@ -860,6 +1853,357 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rounding_dust_is_settled_with_the_payout_of_the_long_party() {
+        let offer_party = PartyParams {
+            margin: 50_000,
+            collateral_reserve: 1_000,
+        };
+        let accept_party = PartyParams {
+            margin: 100_000,
+            collateral_reserve: 2_000,
+        };
+        let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
+
+        // The payouts are 3 sats short of the total collateral.
+        let (offer_payout, accept_payout) = (70_000, 82_997);
+
+        let offer_long = settle_rounding_dust(
+            offer_payout,
+            accept_payout,
+            offer_party,
+            accept_party,
+            Direction::Long,
+        );
+        assert_eq!(offer_long, total_collateral - 82_997);
+
+        let offer_short = settle_rounding_dust(
+            offer_payout,
+            accept_payout,
+            offer_party,
+            accept_party,
+            Direction::Short,
+        );
+        assert_eq!(offer_short, 70_000);
+    }
+
+    #[test]
+    fn settled_payouts_respect_collateral_reserves() {
+        let offer_party = PartyParams {
+            margin: 50_000,
+            collateral_reserve: 1_000,
+        };
+        let accept_party = PartyParams {
+            margin: 100_000,
+            collateral_reserve: 2_000,
+        };
+        let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
+
+        // The short party would get more than the total collateral.
+        let offer_payout =
+            settle_rounding_dust(0, 160_000, offer_party, accept_party, Direction::Long);
+        assert_eq!(offer_payout, offer_party.collateral_reserve);
+
+        // The short party would get less than its collateral reserve.
+        let offer_payout =
+            settle_rounding_dust(-10, 160_000, offer_party, accept_party, Direction::Short);
+        assert_eq!(offer_payout, offer_party.collateral_reserve);
+        assert_eq!(
+            total_collateral - offer_payout,
+            accept_party.total_collateral() + offer_party.margin
+        );
+    }
+
+    #[test]
+    fn payouts_exceeding_total_collateral_are_rejected() {
+        let party = PartyParams {
+            margin: 50_000,
+            collateral_reserve: 0,
+        };
+        let point = |outcome_payout| PayoutPoint {
+            event_outcome: 30_000,
+            outcome_payout,
+            extra_precision: 0,
+        };
+
+        assert!(ensure_payouts_within_total_collateral(
+            &[(point(0), point(100_000))],
+            party,
+            party
+        )
+        .is_ok());
+        assert!(ensure_payouts_within_total_collateral(
+            &[(point(0), point(100_001))],
+            party,
+            party
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn dust_payouts_are_settled_with_the_counterparty() {
+        let offer_party = PartyParams {
+            margin: 50_000,
+            collateral_reserve: 0,
+        };
+        let accept_party = PartyParams {
+            margin: 50_000,
+            collateral_reserve: 0,
+        };
+        let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
+
+        let offer_payout =
+            settle_rounding_dust(500, 99_500, offer_party, accept_party, Direction::Short);
+        assert_eq!(offer_payout, 0);
+
+        let offer_payout =
+            settle_rounding_dust(99_500, 500, offer_party, accept_party, Direction::Short);
+        assert_eq!(offer_payout, total_collateral);
+    }
+
+    #[test]
+    fn partial_liquidations_leave_the_liquidated_party_with_margin() {
+        let quantity = 60_000.0;
+        let initial_price = dec!(30_000);
+        let margin = calculate_margin(initial_price, quantity, 2.0).to_sat();
+
+        let party = PartyParams {
+            margin,
+            collateral_reserve: 0,
+        };
+        let price_params = PriceParams {
+            initial_price,
+            long_liquidation_price: calculate_long_bankruptcy_price(Decimal::TWO, initial_price),
+            short_liquidation_price: calculate_short_bankruptcy_price(Decimal::TWO, initial_price),
+            max_price: BTCUSD_MAX_PRICE,
+        };
+
+        let ladder = MaintenanceMarginLadder::new(vec![
+            LiquidationStep {
+                maintenance_margin_rate: dec!(0.1),
+                share: dec!(0.25),
+            },
+            LiquidationStep {
+                maintenance_margin_rate: dec!(0.2),
+                share: dec!(0.25),
+            },
+        ])
+        .unwrap();
+
+        let without_ladder = build_inverse_payout_function(
+            quantity,
+            party,
+            party,
+            price_params,
+            Direction::Long,
+            None,
+        )
+        .unwrap();
+        let with_ladder = build_inverse_payout_function(
+            quantity,
+            party,
+            party,
+            price_params,
+            Direction::Long,
+            Some(&ladder),
+        )
+        .unwrap();
+
+        // Without the ladder, the liquidated party is left with nothing.
+        assert_eq!(without_ladder.first().unwrap().0.outcome_payout, 0);
+        assert_eq!(without_ladder.last().unwrap().0.outcome_payout, 2 * margin);
+
+        // With the ladder, the shares liquidated at 20% and 10% maintenance margin leave the
+        // liquidated party with 0.1 BTC and 0.05 BTC respectively, give or take the rounding of the
+        // step prices.
+        let remaining_margin = 15_000_000;
+        let tolerance = 10_000;
+        assert!(
+            with_ladder
+                .first()
+                .unwrap()
+                .0
+                .outcome_payout
+                .abs_diff(remaining_margin)
+                <= tolerance
+        );
+        assert!(
+            with_ladder
+                .last()
+                .unwrap()
+                .0
+                .outcome_payout
+                .abs_diff(2 * margin - remaining_margin)
+                <= tolerance
+        );
+
+        // The payout of the party going long still grows with the price.
+        let payouts = with_ladder
+            .iter()
+            .flat_map(|(start, end)| [start.outcome_payout, end.outcome_payout])
+            .collect::<Vec<_>>();
+        assert!(payouts.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn maintenance_margin_ladder_must_leave_a_share_to_liquidate() {
+        let step = |maintenance_margin_rate, share| LiquidationStep {
+            maintenance_margin_rate,
+            share,
+        };
+
+        assert!(MaintenanceMarginLadder::new(vec![step(dec!(0.1), dec!(0.5))]).is_ok());
+        assert!(MaintenanceMarginLadder::new(vec![
+            step(dec!(0.1), dec!(0.5)),
+            step(dec!(0.2), dec!(0.5))
+        ])
+        .is_err());
+        assert!(MaintenanceMarginLadder::new(vec![step(dec!(1), dec!(0.5))]).is_err());
+        assert!(MaintenanceMarginLadder::new(vec![step(dec!(0.1), dec!(0))]).is_err());
+    }
+
+    #[test]
+    fn funding_fee_is_settled_in_the_payouts() {
+        let quantity = 60_000.0;
+        let initial_price = dec!(30_000);
+        let margin = calculate_margin(initial_price, quantity, 2.0);
+
+        let offer_party = PartyParams::new(margin, Amount::from_sat(100_000));
+        let accept_party = PartyParams::new(margin, Amount::from_sat(200_000));
+        let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
+
+        let build = |funding_fee| {
+            build_payout_points_with_funding_fee(
+                ContractSymbol::BtcUsd,
+                initial_price,
+                quantity,
+                offer_party,
+                accept_party,
+                Decimal::TWO,
+                Decimal::TWO,
+                Direction::Long,
+                Discretization::default(),
+                funding_fee,
+            )
+            .unwrap()
+        };
+
+        let fee = 50_000;
+        let without_fee = build(AccruedFundingFee::Zero);
+        let offer_pays = build(AccruedFundingFee::OfferPays(Amount::from_sat(fee)));
+        let accept_pays = build(AccruedFundingFee::AcceptPays(Amount::from_sat(fee)));
+
+        // The liquidation price of the paying party moves towards the initial price. The offer
+        // party goes long, hence it is liquidated at the end of the first interval.
+        let long_liquidation = |points: &[(PayoutPoint, PayoutPoint)]| points[0].1.event_outcome;
+        let short_liquidation =
+            |points: &[(PayoutPoint, PayoutPoint)]| points.last().unwrap().0.event_outcome;
+        assert!(long_liquidation(&offer_pays) > long_liquidation(&without_fee));
+        assert_eq!(
+            short_liquidation(&offer_pays),
+            short_liquidation(&without_fee)
+        );
+        assert!(short_liquidation(&accept_pays) < short_liquidation(&without_fee));
+        assert_eq!(
+            long_liquidation(&accept_pays),
+            long_liquidation(&without_fee)
+        );
+
+        // The other party gets the fee at every outcome, i.e. its payout never drops below its
+        // collateral reserve plus the fee.
+        assert_eq!(
+            offer_pays.last().unwrap().1.outcome_payout,
+            total_collateral - accept_party.collateral_reserve - fee
+        );
+        assert_eq!(
+            accept_pays.first().unwrap().0.outcome_payout,
+            offer_party.collateral_reserve + fee
+        );
+
+        // A liquidated party keeps its collateral reserve.
+        assert_eq!(
+            offer_pays.first().unwrap().0.outcome_payout,
+            offer_party.collateral_reserve
+        );
+        assert_eq!(
+            accept_pays.last().unwrap().1.outcome_payout,
+            total_collateral - accept_party.collateral_reserve
+        );
+    }
+
+    #[test]
+    fn dust_collateral_reserves_are_refused() {
+        let initial_price = dec!(30_000);
+        let price_params = PriceParams {
+            initial_price,
+            long_liquidation_price: calculate_long_bankruptcy_price(Decimal::TWO, initial_price),
+            short_liquidation_price: calculate_short_bankruptcy_price(Decimal::TWO, initial_price),
+            max_price: BTCUSD_MAX_PRICE,
+        };
+        let party = |collateral_reserve| PartyParams {
+            margin: 100_000,
+            collateral_reserve,
+        };
+
+        let error = build_inverse_payout_function(
+            60.0,
+            party(500),
+            party(0),
+            price_params,
+            Direction::Short,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The offer collateral reserve of 500 sats is below the dust limit of 1000 sats"
+        );
+
+        assert!(build_inverse_payout_function(
+            60.0,
+            party(1_000),
+            party(0),
+            price_params,
+            Direction::Short,
+            None,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn dust_collateral_reserves_of_existing_channels_are_folded_into_the_margin() {
+        let quantity = 60_000.0;
+        let initial_price = dec!(30_000);
+        let margin = calculate_margin(initial_price, quantity, 2.0);
+
+        // E.g. the collateral reserve of the accept party after a withdrawal.
+        let offer_party = PartyParams::new(margin, Amount::ZERO);
+        let accept_party = PartyParams::new(margin, Amount::from_sat(700));
+        let total_collateral = offer_party.total_collateral() + accept_party.total_collateral();
+
+        // The funding fee leaves the offer party with a collateral reserve of 500 sats.
+        let payout_points = build_payout_points_with_funding_fee(
+            ContractSymbol::BtcUsd,
+            initial_price,
+            quantity,
+            offer_party,
+            accept_party,
+            Decimal::TWO,
+            Decimal::TWO,
+            Direction::Long,
+            Discretization::default(),
+            AccruedFundingFee::AcceptPays(Amount::from_sat(500)),
+        )
+        .unwrap();
+
+        // A liquidated party has no collateral reserve left, i.e. no dust output.
+        assert_eq!(payout_points.first().unwrap().0.outcome_payout, 0);
+        assert_eq!(
+            payout_points.last().unwrap().1.outcome_payout,
+            total_collateral
+        );
+    }
+
     proptest! {
         #[test]
         fn midrange_always_positive(initial_price in 20_000i32..50_000, short_leverage in 1i32..5) {
@@ -901,10 +2245,25 @@ mod tests {
             // act: offer long
             let offer_direction = Direction::Long;
 
+            let position = Position::new(
+                ContractType::Inverse,
+                quantity,
+                party_params_offer,
+                party_params_accept,
+                offer_direction,
+                PriceParams {
+                    initial_price,
+                    long_liquidation_price,
+                    short_liquidation_price,
+                    max_price: BTCUSD_MAX_PRICE,
+                },
+                None,
+            );
+
             let mid_range_payouts_offer_long = calculate_mid_range_payouts(
                 party_params_offer,
                 party_params_accept,
-                initial_price,
+                &position,
                 &PayoutPoint {
                     event_outcome: long_liquidation_price.to_u64().unwrap(),
                     outcome_payout: party_params_offer.collateral_reserve,
@@ -916,7 +2275,7 @@ mod tests {
                     extra_precision: 0,
                 },
                 offer_direction,
-                quantity,
+                Discretization::default(),
             )
             .expect("To be able to compute mid range");
 
@@ -937,6 +2296,7 @@ mod bounds_tests {
     use xxi_node::cfd::calculate_long_bankruptcy_price;
     use xxi_node::cfd::calculate_margin;
     use xxi_node::cfd::calculate_short_bankruptcy_price;
+    use xxi_node::cfd::BTCUSD_MAX_PRICE;
 
     #[test]
     fn correct_bounds_between_middle_and_liquidation_intervals() {
@@ -1019,6 +2379,7 @@ mod bounds_tests {
             initial_price,
             long_liquidation_price,
             short_liquidation_price,
+            max_price: BTCUSD_MAX_PRICE,
         };
 
         let payout_function = build_inverse_payout_function(
@@ -1027,6 +2388,7 @@ mod bounds_tests {
             accept_party,
             price_params,
             offer_party_direction,
+            None,
         )
         .unwrap();
 
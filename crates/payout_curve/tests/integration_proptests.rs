@@ -10,6 +10,7 @@ use dlc_manager::payout_curve::RoundingInterval;
 use dlc_manager::payout_curve::RoundingIntervals;
 use payout_curve::build_inverse_payout_function;
 use payout_curve::PartyParams;
+use payout_curve::PayoutPoint;
 use payout_curve::PriceParams;
 use proptest::prelude::*;
 use rust_decimal::prelude::FromPrimitive;
@@ -21,7 +22,9 @@ use std::time::UNIX_EPOCH;
 use xxi_node::cfd::calculate_long_bankruptcy_price;
 use xxi_node::cfd::calculate_margin;
 use xxi_node::cfd::calculate_short_bankruptcy_price;
+use xxi_node::commons::round_collateral_reserve;
 use xxi_node::commons::Direction;
+use xxi_node::commons::ReserveRounding;
 
 /// set this to true to export test data to csv files
 const PRINT_CSV: bool = false;
@@ -224,6 +227,102 @@ proptest! {
     }
 }
 
+proptest! {
+    #[test]
+    fn payouts_always_add_up_to_total_collateral(
+        quantity in 1.0f32..10_000.0,
+        initial_price in 20_000u32..80_000,
+        leverage_coordinator in 1u8..5,
+        leverage_trader in 1u8..5,
+        is_coordinator_long in proptest::bool::ANY,
+        collateral_reserve_coordinator in 0u64..1_000_000,
+        collateral_reserve_trader in 0u64..1_000_000,
+    ) {
+        let initial_price = Decimal::from(initial_price);
+        let leverage_coordinator = leverage_coordinator as f32;
+        let leverage_trader = leverage_trader as f32;
+
+        let coordinator_direction = if is_coordinator_long {
+            Direction::Long
+        } else {
+            Direction::Short
+        };
+
+        let (leverage_long, leverage_short) = match coordinator_direction {
+            Direction::Long => (leverage_coordinator, leverage_trader),
+            Direction::Short => (leverage_trader, leverage_coordinator),
+        };
+
+        let price_params = PriceParams::new_btc_usd(
+            initial_price,
+            calculate_long_bankruptcy_price(
+                Decimal::from_f32(leverage_long).unwrap(),
+                initial_price,
+            ),
+            calculate_short_bankruptcy_price(
+                Decimal::from_f32(leverage_short).unwrap(),
+                initial_price,
+            ),
+        )
+        .unwrap();
+
+        // Dust reserves are refused, so they are rounded like in the channel construction.
+        let collateral_reserve_coordinator = round_collateral_reserve(
+            Amount::from_sat(collateral_reserve_coordinator),
+            ReserveRounding::Up,
+        )
+        .to_sat();
+        let collateral_reserve_trader = round_collateral_reserve(
+            Amount::from_sat(collateral_reserve_trader),
+            ReserveRounding::Down,
+        )
+        .to_sat();
+
+        let party_params_coordinator = PartyParams::new(
+            calculate_margin(initial_price, quantity, leverage_coordinator),
+            Amount::from_sat(collateral_reserve_coordinator),
+        );
+        let party_params_trader = PartyParams::new(
+            calculate_margin(initial_price, quantity, leverage_trader),
+            Amount::from_sat(collateral_reserve_trader),
+        );
+
+        let total_collateral =
+            party_params_coordinator.total_collateral() + party_params_trader.total_collateral();
+
+        let payout_points = build_inverse_payout_function(
+            quantity,
+            party_params_coordinator,
+            party_params_trader,
+            price_params,
+            coordinator_direction,
+            None,
+        )
+        .unwrap();
+
+        let range_payouts = payout_function(&payout_points)
+            .to_range_payouts(
+                total_collateral,
+                &RoundingIntervals {
+                    intervals: vec![RoundingInterval {
+                        begin_interval: 0,
+                        rounding_mod: 1,
+                    }],
+                },
+            )
+            .unwrap();
+
+        for range_payout in range_payouts {
+            prop_assert_eq!(
+                range_payout.payout.offer + range_payout.payout.accept,
+                total_collateral
+            );
+            prop_assert!(range_payout.payout.offer >= collateral_reserve_coordinator);
+            prop_assert!(range_payout.payout.accept >= collateral_reserve_trader);
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn computed_payout_curve(
     quantity: f32,
@@ -253,28 +352,13 @@ fn computed_payout_curve(
         party_params_trader,
         price_params,
         coordinator_direction,
+        None,
     )?;
 
     let start = SystemTime::now();
     let now = start.duration_since(UNIX_EPOCH)?;
 
-    let mut pieces = vec![];
-    for (lower, upper) in &payout_points {
-        let lower_range = PolynomialPayoutCurvePiece::new(vec![
-            dlc_manager::payout_curve::PayoutPoint {
-                event_outcome: lower.event_outcome,
-                outcome_payout: lower.outcome_payout,
-                extra_precision: lower.extra_precision,
-            },
-            dlc_manager::payout_curve::PayoutPoint {
-                event_outcome: upper.event_outcome,
-                outcome_payout: upper.outcome_payout,
-                extra_precision: upper.extra_precision,
-            },
-        ])?;
-
-        pieces.push(PayoutFunctionPiece::PolynomialPayoutCurvePiece(lower_range));
-    }
+    let payout_function = payout_function(&payout_points);
 
     if PRINT_CSV {
         let file = File::create(format!("./testrun-{}.csv", now.as_millis()))?;
@@ -293,9 +377,6 @@ fn computed_payout_curve(
         wtr.flush()?;
     }
 
-    let payout_function =
-        PayoutFunction::new(pieces).context("could not create payout function")?;
-
     let total_collateral =
         party_params_coordinator.total_collateral() + party_params_trader.total_collateral();
     let _ = payout_function.to_range_payouts(
@@ -311,6 +392,31 @@ fn computed_payout_curve(
     Ok(())
 }
 
+fn payout_function(payout_points: &[(PayoutPoint, PayoutPoint)]) -> PayoutFunction {
+    let pieces = payout_points
+        .iter()
+        .map(|(lower, upper)| {
+            let piece = PolynomialPayoutCurvePiece::new(vec![
+                dlc_manager::payout_curve::PayoutPoint {
+                    event_outcome: lower.event_outcome,
+                    outcome_payout: lower.outcome_payout,
+                    extra_precision: lower.extra_precision,
+                },
+                dlc_manager::payout_curve::PayoutPoint {
+                    event_outcome: upper.event_outcome,
+                    outcome_payout: upper.outcome_payout,
+                    extra_precision: upper.extra_precision,
+                },
+            ])
+            .unwrap();
+
+            PayoutFunctionPiece::PolynomialPayoutCurvePiece(piece)
+        })
+        .collect();
+
+    PayoutFunction::new(pieces).unwrap()
+}
+
 /// Initialise tracing for tests
 #[cfg(test)]
 pub(crate) fn init_tracing_for_test() {
@@ -5,6 +5,7 @@ use crate::wait_until;
 use native::api;
 use native::api::DlcChannel;
 use native::trade::order::api::NewOrder;
+use std::path::Path;
 use tempfile::TempDir;
 use tokio::task::block_in_place;
 
@@ -20,12 +21,30 @@ impl AppHandle {
     pub fn stop(&self) {
         self._handle.abort()
     }
+
+    pub fn app_dir(&self) -> &Path {
+        self._app_dir.path()
+    }
+
+    pub fn seed_dir(&self) -> &Path {
+        self._seed_dir.path()
+    }
 }
 
 pub async fn run_app(seed_phrase: Option<Vec<String>>) -> AppHandle {
     let app_dir = TempDir::new().unwrap();
     let seed_dir = TempDir::new().unwrap();
 
+    run_app_in(app_dir, seed_dir, seed_phrase).await
+}
+
+/// Run the app with existing data directories, e.g. restored from a
+/// [`crate::snapshot::Snapshot`].
+pub async fn run_app_in(
+    app_dir: TempDir,
+    seed_dir: TempDir,
+    seed_phrase: Option<Vec<String>>,
+) -> AppHandle {
     tracing::debug!(?app_dir, ?seed_dir, "Starting 10101 backend");
 
     let _app_handle = {
@@ -42,7 +61,7 @@ pub async fn run_app(seed_phrase: Option<Vec<String>>) -> AppHandle {
                 move || {
                     api::restore_from_seed_phrase(
                         seed_phrase.join(" "),
-                        format!("{seed_dir}/regtest/seed"),
+                        format!("{seed_dir}/{}/seed", test_network()),
                     )
                     .unwrap();
                 }
@@ -69,7 +88,7 @@ pub async fn run_app(seed_phrase: Option<Vec<String>>) -> AppHandle {
     wait_until!(app.rx.wallet_info().is_some()); // wait for initial wallet sync
 
     block_in_place(move || {
-        api::register_beta("hello@10101.finance".to_string(), None).expect("to work")
+        api::register_beta("hello@10101.finance".to_string(), None, None).expect("to work")
     });
 
     app
@@ -146,6 +165,14 @@ pub fn submit_channel_opening_order(
     });
 }
 
+/// The network the e2e tests run on, `regtest` unless overridden through `E2E_NETWORK`.
+///
+/// Running against a different network, e.g. `signet`, requires a coordinator, electrs and
+/// oracle of a public test deployment on that network.
+fn test_network() -> String {
+    std::env::var("E2E_NETWORK").unwrap_or_else(|_| "regtest".to_string())
+}
+
 // Values mostly taken from `environment.dart`
 fn test_config() -> native::config::api::Config {
     native::config::api::Config {
@@ -155,11 +182,12 @@ fn test_config() -> native::config::api::Config {
         host: "127.0.0.1".to_string(),
         p2p_port: 9045,
         http_port: 8000,
-        network: "regtest".to_string(),
+        network: test_network(),
         oracle_endpoint: "http://127.0.0.1:8081".to_string(),
         oracle_pubkey: "16f88cf7d21e6c0f46bcbc983a4e3b19726c6c98858cc31c83551a88fde171c0"
             .to_string(),
         health_check_interval_secs: 1, // We want to measure health more often in tests
         meme_endpoint: "https://localhost:8080/memes/".to_string(),
+        fallback_coordinators: vec![],
     }
 }
@@ -7,5 +7,6 @@ pub mod http;
 pub mod lnd_mock;
 pub mod logger;
 pub mod setup;
+pub mod snapshot;
 pub mod test_flow;
 pub mod test_subscriber;
@@ -1,11 +1,15 @@
 use crate::app::refresh_wallet_info;
 use crate::app::run_app;
+use crate::app::run_app_in;
 use crate::app::submit_channel_opening_order;
 use crate::app::AppHandle;
 use crate::bitcoind::Bitcoind;
 use crate::coordinator::Coordinator;
 use crate::http::init_reqwest;
 use crate::logger::init_tracing;
+use crate::snapshot::copy_dir;
+use crate::snapshot::snapshots_enabled;
+use crate::snapshot::Snapshot;
 use crate::wait_until;
 use bitcoin::address::NetworkUnchecked;
 use bitcoin::Address;
@@ -15,6 +19,9 @@ use native::api::ContractSymbol;
 use native::trade::order::api::NewOrder;
 use native::trade::order::api::OrderType;
 use native::trade::position::PositionState;
+use reqwest::Client;
+use tempfile::TempDir;
+use tokio::task::block_in_place;
 use xxi_node::node::rust_dlc_manager::manager::NB_CONFIRMATIONS;
 
 pub struct TestSetup {
@@ -120,10 +127,70 @@ impl TestSetup {
     }
 
     /// Start test with a running app with a funded wallet and an open position.
+    ///
+    /// If `E2E_SNAPSHOTS` is set, the state is restored from a [`Snapshot`] of a previous run,
+    /// which is captured first if needed.
     pub async fn new_with_open_position() -> Self {
         let order = dummy_order();
 
-        Self::new_with_open_position_custom(order, 0, 0).await
+        if !snapshots_enabled() {
+            return Self::new_with_open_position_custom(order, 0, 0).await;
+        }
+
+        let snapshot = Snapshot::new("open-position").unwrap();
+        if snapshot.is_available() {
+            return Self::restore(&snapshot).await;
+        }
+
+        let setup = Self::new_with_open_position_custom(order, 0, 0).await;
+
+        block_in_place(|| snapshot.capture(setup.app.app_dir(), setup.app.seed_dir())).unwrap();
+        wait_for_electrs(&init_reqwest()).await;
+
+        setup
+    }
+
+    /// Start test from the state captured in the given [`Snapshot`].
+    pub async fn restore(snapshot: &Snapshot) -> Self {
+        init_tracing();
+
+        block_in_place(|| snapshot.restore()).unwrap();
+
+        let client = init_reqwest();
+        wait_for_electrs(&client).await;
+
+        let bitcoind = Bitcoind::new_local(client.clone());
+
+        let coordinator = Coordinator::new_local(client.clone());
+        wait_until!(coordinator.is_running().await);
+
+        // The app could modify its data directories, so we run it on copies.
+        let app_dir = TempDir::new().unwrap();
+        let seed_dir = TempDir::new().unwrap();
+        block_in_place(|| {
+            copy_dir(&snapshot.app_dir(), app_dir.path())?;
+            copy_dir(&snapshot.seed_dir(), seed_dir.path())
+        })
+        .unwrap();
+
+        let app = run_app_in(app_dir, seed_dir, None).await;
+
+        wait_until!(app
+            .rx
+            .position()
+            .is_some_and(|position| position.position_state == PositionState::Open));
+
+        refresh_wallet_info();
+
+        let setup = Self {
+            app,
+            coordinator,
+            bitcoind,
+        };
+
+        setup.sync_coordinator().await;
+
+        setup
     }
 
     /// Start test with a running app with a funded wallet and an open position based on a custom
@@ -196,3 +263,12 @@ pub fn dummy_order() -> NewOrder {
         stable: false,
     }
 }
+
+/// Wait for electrs to serve requests, e.g. after its container was restarted.
+async fn wait_for_electrs(client: &Client) {
+    wait_until!(client
+        .get("http://localhost:3000/blocks/tip/height")
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success()));
+}
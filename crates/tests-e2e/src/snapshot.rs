@@ -0,0 +1,286 @@
+//! Snapshots of the regtest environment, to skip the minutes spent on funding and mining in the
+//! standard setup.
+//!
+//! A snapshot captures the bitcoind and electrs data directories, a dump of the coordinator
+//! database, the coordinator data directory and the data directories of the app. Restoring it
+//! stops the coordinator and the blockchain containers, puts the captured state back in place and
+//! starts them again.
+//!
+//! Snapshots are only used if `E2E_SNAPSHOTS` is set, and require the services started with `just
+//! services`.
+//!
+//! A snapshot is keyed on the code which could change the outcome of the setup, so that it is
+//! never restored after a change to the DLC protocol, the coordinator or the app.
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::HashEngine;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// The sources which affect the state produced by the standard setup, relative to the root of
+/// the repository.
+const PROTOCOL_PATHS: &[&str] = &[
+    "Cargo.lock",
+    "coordinator/src",
+    "coordinator/migrations",
+    "coordinator/example-settings",
+    "crates/xxi-node/src",
+    "crates/tests-e2e/src",
+    "mobile/native/src",
+    "mobile/native/migrations",
+];
+
+/// After this long the open position could be close to expiry, so we set up from scratch again.
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+const BITCOIN_CONTAINER: &str = "bitcoin";
+const BITCOIN_DATA_DIR: &str = "/home/bitcoin/.bitcoin/regtest";
+const ELECTRS_CONTAINER: &str = "electrs";
+const ELECTRS_DATA_DIR: &str = "/home/user/db";
+const DB_CONTAINER: &str = "db";
+const COORDINATOR_DATABASE: &str = "orderbook";
+
+/// Marks a complete snapshot. Written last, so that an interrupted capture is never restored.
+const COMPLETE_MARKER: &str = "complete";
+
+pub struct Snapshot {
+    dir: PathBuf,
+}
+
+pub fn snapshots_enabled() -> bool {
+    std::env::var("E2E_SNAPSHOTS").is_ok()
+}
+
+impl Snapshot {
+    /// The snapshot of the setup called `name` for the current code.
+    pub fn new(name: &str) -> Result<Self> {
+        let dir = repository_root()
+            .join("data/e2e-snapshots")
+            .join(name)
+            .join(snapshot_key()?);
+
+        Ok(Self { dir })
+    }
+
+    /// Whether the snapshot was captured and is recent enough to be restored.
+    pub fn is_available(&self) -> bool {
+        let captured_at = match fs::metadata(self.dir.join(COMPLETE_MARKER))
+            .and_then(|metadata| metadata.modified())
+        {
+            Ok(captured_at) => captured_at,
+            Err(_) => return false,
+        };
+
+        let age = SystemTime::now()
+            .duration_since(captured_at)
+            .unwrap_or_default();
+
+        age < MAX_AGE
+    }
+
+    pub fn app_dir(&self) -> PathBuf {
+        self.dir.join("app")
+    }
+
+    pub fn seed_dir(&self) -> PathBuf {
+        self.dir.join("seed")
+    }
+
+    /// Capture the current state of the environment and of the app with the given data
+    /// directories.
+    ///
+    /// The app, the coordinator and the chain must be idle.
+    pub fn capture(&self, app_dir: &Path, seed_dir: &Path) -> Result<()> {
+        tracing::info!(dir = ?self.dir, "Capturing e2e snapshot");
+
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        copy_dir(app_dir, &self.app_dir())?;
+        copy_dir(seed_dir, &self.seed_dir())?;
+        copy_dir(&coordinator_data_dir(), &self.dir.join("coordinator"))?;
+
+        let dump = Command::new("docker")
+            .args(["exec", DB_CONTAINER, "pg_dump", "-U", "postgres", "-Fc"])
+            .arg(COORDINATOR_DATABASE)
+            .output()
+            .context("Could not run pg_dump")?;
+        if !dump.status.success() {
+            bail!(
+                "Could not dump coordinator database: {}",
+                String::from_utf8_lossy(&dump.stderr)
+            );
+        }
+        fs::write(self.dir.join("coordinator.dump"), dump.stdout)?;
+
+        // Copying the data directories of running containers could capture them half-written.
+        run(Command::new("docker").args(["stop", ELECTRS_CONTAINER, BITCOIN_CONTAINER]))?;
+
+        let copied = self
+            .copy_from_container(BITCOIN_CONTAINER, BITCOIN_DATA_DIR, "bitcoin")
+            .and_then(|_| self.copy_from_container(ELECTRS_CONTAINER, ELECTRS_DATA_DIR, "electrs"));
+
+        start_containers()?;
+        copied?;
+
+        fs::write(self.dir.join(COMPLETE_MARKER), b"")?;
+
+        tracing::info!(dir = ?self.dir, "Captured e2e snapshot");
+
+        Ok(())
+    }
+
+    /// Put the captured state of the environment back in place.
+    ///
+    /// The app must be started separately, from copies of [`Snapshot::app_dir`] and
+    /// [`Snapshot::seed_dir`].
+    pub fn restore(&self) -> Result<()> {
+        tracing::info!(dir = ?self.dir, "Restoring e2e snapshot");
+
+        // The coordinator must not write to its database or its data directory while we replace
+        // them.
+        let _ = Command::new("pkill").args(["-9", "coordinator"]).status();
+
+        run(Command::new("docker").args(["stop", ELECTRS_CONTAINER, BITCOIN_CONTAINER]))?;
+        self.copy_to_container(BITCOIN_CONTAINER, BITCOIN_DATA_DIR, "bitcoin")?;
+        self.copy_to_container(ELECTRS_CONTAINER, ELECTRS_DATA_DIR, "electrs")?;
+        start_containers()?;
+
+        let dump = fs::File::open(self.dir.join("coordinator.dump"))?;
+        run(Command::new("docker")
+            .args([
+                "exec",
+                "-i",
+                DB_CONTAINER,
+                "pg_restore",
+                "-U",
+                "postgres",
+                "--clean",
+                "--if-exists",
+                "-d",
+            ])
+            .arg(COORDINATOR_DATABASE)
+            .stdin(dump))?;
+
+        let coordinator_data_dir = coordinator_data_dir();
+        if coordinator_data_dir.exists() {
+            fs::remove_dir_all(&coordinator_data_dir)?;
+        }
+        copy_dir(&self.dir.join("coordinator"), &coordinator_data_dir)?;
+
+        run(Command::new("just")
+            .arg("run-coordinator-detached")
+            .current_dir(repository_root()))?;
+
+        tracing::info!(dir = ?self.dir, "Restored e2e snapshot");
+
+        Ok(())
+    }
+
+    fn copy_from_container(&self, container: &str, path: &str, name: &str) -> Result<()> {
+        run(Command::new("docker")
+            .args(["run", "--rm", "--volumes-from", container, "-v"])
+            .arg(format!("{}:/snapshot", self.dir.display()))
+            .args(["busybox", "cp", "-a", path])
+            .arg(format!("/snapshot/{name}")))
+    }
+
+    fn copy_to_container(&self, container: &str, path: &str, name: &str) -> Result<()> {
+        run(Command::new("docker")
+            .args(["run", "--rm", "--volumes-from", container, "-v"])
+            .arg(format!("{}:/snapshot:ro", self.dir.display()))
+            .args(["busybox", "sh", "-c"])
+            .arg(format!("rm -rf {path} && cp -a /snapshot/{name} {path}")))
+    }
+}
+
+/// A hash over all the [`PROTOCOL_PATHS`].
+fn snapshot_key() -> Result<String> {
+    let root = repository_root();
+
+    let mut files = Vec::new();
+    for path in PROTOCOL_PATHS {
+        collect_files(&root.join(path), &mut files)?;
+    }
+    files.sort();
+
+    let mut engine = sha256::Hash::engine();
+    for file in files {
+        engine.input(file.strip_prefix(&root)?.to_string_lossy().as_bytes());
+        engine.input(&fs::read(&file)?);
+    }
+
+    Ok(sha256::Hash::from_engine(engine).to_string())
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+    } else if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            collect_files(&entry?.path(), files)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy the contents of the directory `from` into `to`, recursively.
+pub fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Electrs takes a moment to be ready again; callers have to wait for it before using the chain.
+fn start_containers() -> Result<()> {
+    run(Command::new("docker").args(["start", BITCOIN_CONTAINER, ELECTRS_CONTAINER]))
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let output = command
+        .output()
+        .with_context(|| format!("Could not run {command:?}"))?;
+
+    if !output.status.success() {
+        bail!(
+            "{command:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+fn coordinator_data_dir() -> PathBuf {
+    repository_root().join("data/coordinator/regtest")
+}
+
+fn repository_root() -> PathBuf {
+    // Docker only accepts absolute paths without `..` for volumes.
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .canonicalize()
+        .expect("repository root to exist")
+}
@@ -0,0 +1,142 @@
+//! Compares signing and verifying CET adaptor signatures one after the other with doing so in
+//! parallel.
+//!
+//! Run with `cargo bench -p xxi-node --bench cet_adaptor_signatures`.
+
+#![allow(clippy::unwrap_used)]
+
+use bitcoin_old::OutPoint;
+use bitcoin_old::PackedLockTime;
+use bitcoin_old::Script;
+use bitcoin_old::Sequence;
+use bitcoin_old::Transaction;
+use bitcoin_old::TxIn;
+use bitcoin_old::TxOut;
+use bitcoin_old::Witness;
+use rand::thread_rng;
+use rand::Rng;
+use secp256k1_zkp::PublicKey;
+use secp256k1_zkp::SecretKey;
+use secp256k1_zkp::SECP256K1;
+use std::time::Duration;
+use std::time::Instant;
+use xxi_node::dlc::parallel;
+use xxi_node::dlc::parallel::AdaptedCet;
+
+/// Numerical contracts with the payout curves used by 10101 have a few hundred CETs.
+const CET_COUNTS: [usize; 3] = [100, 500, 1_000];
+
+const FUND_OUTPUT_VALUE: u64 = 1_000_000;
+
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let own_sk = random_sk();
+    let own_pk = PublicKey::from_secret_key(SECP256K1, &own_sk);
+    let other_pk = PublicKey::from_secret_key(SECP256K1, &random_sk());
+    let funding_script_pubkey = dlc::make_funding_redeemscript(&own_pk, &other_pk);
+
+    println!("Workers: {}", parallel::worker_count());
+
+    for count in CET_COUNTS {
+        let cets = (0..count as u64).map(dummy_cet).collect::<Vec<_>>();
+
+        let sequential_sign = measure(|| {
+            cets.iter()
+                .map(|AdaptedCet { cet, adaptor_point }| {
+                    dlc::create_cet_adaptor_sig_from_point(
+                        SECP256K1,
+                        cet,
+                        adaptor_point,
+                        &own_sk,
+                        &funding_script_pubkey,
+                        FUND_OUTPUT_VALUE,
+                    )
+                    .unwrap()
+                })
+                .collect::<Vec<_>>()
+        });
+        let parallel_sign = measure(|| {
+            parallel::sign_cet_adaptor_sigs(
+                &cets,
+                &own_sk,
+                &funding_script_pubkey,
+                FUND_OUTPUT_VALUE,
+            )
+            .unwrap()
+        });
+
+        let adaptor_sigs = parallel::sign_cet_adaptor_sigs(
+            &cets,
+            &own_sk,
+            &funding_script_pubkey,
+            FUND_OUTPUT_VALUE,
+        )
+        .unwrap();
+
+        let sequential_verify = measure(|| {
+            for (AdaptedCet { cet, adaptor_point }, adaptor_sig) in cets.iter().zip(&adaptor_sigs) {
+                dlc::verify_cet_adaptor_sig_from_point(
+                    SECP256K1,
+                    adaptor_sig,
+                    cet,
+                    adaptor_point,
+                    &own_pk,
+                    &funding_script_pubkey,
+                    FUND_OUTPUT_VALUE,
+                )
+                .unwrap();
+            }
+        });
+        let parallel_verify = measure(|| {
+            parallel::verify_cet_adaptor_sigs(
+                &cets,
+                &adaptor_sigs,
+                &own_pk,
+                &funding_script_pubkey,
+                FUND_OUTPUT_VALUE,
+            )
+            .unwrap()
+        });
+
+        println!(
+            "{count:>5} CETs | sign: {sequential_sign:>10.2?} sequential, {parallel_sign:>10.2?} \
+             parallel | verify: {sequential_verify:>10.2?} sequential, {parallel_verify:>10.2?} \
+             parallel"
+        );
+    }
+}
+
+/// The mean duration of running `f`.
+fn measure<T>(f: impl Fn() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(f());
+    }
+
+    start.elapsed() / ITERATIONS
+}
+
+fn random_sk() -> SecretKey {
+    SecretKey::from_slice(&thread_rng().gen::<[u8; 32]>()).expect("valid secret key")
+}
+
+fn dummy_cet(output_value: u64) -> AdaptedCet {
+    AdaptedCet {
+        cet: Transaction {
+            version: 2,
+            lock_time: PackedLockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::default(),
+                script_sig: Script::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: output_value,
+                script_pubkey: Script::new(),
+            }],
+        },
+        adaptor_point: PublicKey::from_secret_key(SECP256K1, &random_sk()),
+    }
+}
@@ -0,0 +1,1460 @@
+//! Generates the DLC storage fixtures in `crates/xxi-node/test_files`, which the round-trip tests
+//! in `xxi_node::storage` read.
+//!
+//! Two in-memory `rust-dlc` managers open a DLC channel with each other, confirm it and settle it
+//! off-chain. A second DLC channel is force-closed once the mock oracle has attested to the price,
+//! which leaves its contract `PreClosed`. The contract and channel states on the way are written
+//! as fixtures. The LN-DLC sub-channel states are assembled from the keys and the funding output
+//! of the first DLC channel, as 10101 nodes no longer run the sub-channel protocol.
+//!
+//! The wallets, the chain, the clock and the oracle are mocks derived from a fixed seed.
+//! `rust-dlc` draws the temporary ids, the serial ids and the adaptor signature nonces itself
+//! though. Hence, a DLC channel is reopened until its serial ids come out in a fixed order, which
+//! fixes every transaction, and the drawn values are replaced with seeded ones before the fixtures
+//! are written. The fixtures are generated twice and compared, so that a new random field fails
+//! the generator instead of changing the fixtures on every run.
+//!
+//! Note, the replaced adaptor signatures, and the signatures decrypted from them, do not verify
+//! against their transactions. The fixtures only pin down the serialization format.
+//!
+//! Run with
+//!
+//! `cargo run -p xxi-node --bin gen-fixtures [output directory]`.
+
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin_old::psbt::PartiallySignedTransaction;
+use bitcoin_old::util::sighash::SighashCache;
+use bitcoin_old::Address;
+use bitcoin_old::EcdsaSighashType;
+use bitcoin_old::Network;
+use bitcoin_old::OutPoint;
+use bitcoin_old::PackedLockTime;
+use bitcoin_old::Script;
+use bitcoin_old::Sequence;
+use bitcoin_old::Transaction;
+use bitcoin_old::TxIn;
+use bitcoin_old::TxOut;
+use bitcoin_old::Txid;
+use bitcoin_old::Witness;
+use dlc::channel::sub_channel::SplitTx;
+use dlc::FeeConfig;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::channel::Channel;
+use dlc_manager::contract::contract_input::ContractInput;
+use dlc_manager::contract::contract_input::ContractInputInfo;
+use dlc_manager::contract::contract_input::OracleInput;
+use dlc_manager::contract::numerical_descriptor::NumericalDescriptor;
+use dlc_manager::contract::ser::Serializable;
+use dlc_manager::contract::Contract;
+use dlc_manager::contract::ContractDescriptor;
+use dlc_manager::error::Error;
+use dlc_manager::manager::Manager;
+use dlc_manager::payout_curve::PayoutFunction;
+use dlc_manager::payout_curve::PayoutFunctionPiece;
+use dlc_manager::payout_curve::PayoutPoint;
+use dlc_manager::payout_curve::PolynomialPayoutCurvePiece;
+use dlc_manager::payout_curve::RoundingInterval;
+use dlc_manager::payout_curve::RoundingIntervals;
+use dlc_manager::subchannel::AcceptedSubChannel;
+use dlc_manager::subchannel::LnRollBackInfo;
+use dlc_manager::subchannel::OfferedSubChannel;
+use dlc_manager::subchannel::SignedSubChannel;
+use dlc_manager::subchannel::SubChannel;
+use dlc_manager::subchannel::SubChannelState;
+use dlc_manager::ContractId;
+use dlc_manager::DlcChannelId;
+use dlc_manager::Oracle;
+use dlc_manager::ReferenceId;
+use dlc_manager::Storage;
+use dlc_manager::Utxo;
+use dlc_messages::channel::AcceptChannel;
+use dlc_messages::channel::OfferChannel;
+use dlc_messages::oracle_msgs::DigitDecompositionEventDescriptor;
+use dlc_messages::oracle_msgs::EventDescriptor;
+use dlc_messages::oracle_msgs::OracleAnnouncement;
+use dlc_messages::oracle_msgs::OracleAttestation;
+use dlc_messages::oracle_msgs::OracleEvent;
+use dlc_messages::ChannelMessage;
+use dlc_messages::Message;
+use lightning::chain::chaininterface::ConfirmationTarget;
+use lightning::chain::chaininterface::FeeEstimator;
+use lightning::chain::transaction::OutPoint as LnOutPoint;
+use lightning::ln::chan_utils::CounterpartyCommitmentSecrets;
+use lightning::ln::ChannelId;
+use lightning::util::ser::Writeable;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use secp256k1_zkp::EcdsaAdaptorSignature;
+use secp256k1_zkp::KeyPair;
+use secp256k1_zkp::PublicKey;
+use secp256k1_zkp::SecretKey;
+use secp256k1_zkp::XOnlyPublicKey;
+use secp256k1_zkp::SECP256K1;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::OracleEventId;
+use xxi_node::node::ProtocolId;
+use xxi_node::storage::DlcChannelEventBus;
+use xxi_node::storage::DlcStorageProvider;
+use xxi_node::storage::TenTenOneInMemoryStorage;
+
+const SEED: u64 = 10101;
+
+const NETWORK: Network = Network::Regtest;
+const BLOCK_HEIGHT: u64 = 800_000;
+/// Enough for `rust-dlc` to consider the funding transaction confirmed.
+const CONFIRMATIONS: u32 = 6;
+/// The relative timelock of the CETs spending the buffer transaction. Mirrors `CET_NSEQUENCE` of
+/// `rust-dlc`.
+const CET_NSEQUENCE: u32 = 288;
+const FEE_RATE_SATS_PER_KW: u32 = 1_000;
+const FEE_RATE_SATS_PER_VB: u64 = 2;
+
+/// Each party funds the DLC channel from a single coin of this value.
+const COIN_SATS: u64 = 1_000_000;
+const OFFER_COLLATERAL_SATS: u64 = 100_000;
+const ACCEPT_COLLATERAL_SATS: u64 = 50_000;
+/// What the accepting party gets when the channel is settled off-chain.
+const SETTLEMENT_SATS: u64 = 60_000;
+
+/// 2029-12-31 00:00:00 UTC, the time of the mock clock until the contract is closed.
+const NOW: u64 = 1_893_369_600;
+/// 2030-01-01 00:00:00 UTC, so that the contract has not matured yet when the DLC channel is
+/// opened.
+const MATURITY: i64 = 1_893_456_000;
+const NB_DIGITS: u16 = 20;
+/// The price the mock oracle attests to, for which both parties get a payout.
+const ATTESTED_PRICE: u64 = 60_000;
+
+/// How often a DLC channel is reopened at most until its serial ids are in canonical order. Each
+/// attempt succeeds with a probability of 1/24.
+const MAX_ATTEMPTS: usize = 1_000;
+
+/// The update index of an LN-DLC sub-channel before its first split. Mirrors
+/// `INITIAL_SPLIT_NUMBER` of `rust-dlc`.
+const INITIAL_SPLIT_NUMBER: u64 = (1 << 48) - 1;
+/// The capacity of the LN channel which the DLC channel of the sub-channel fixtures is split off.
+const LN_CHANNEL_SATS: u64 = 500_000;
+
+type FixtureManager = Manager<
+    Arc<MockWallet>,
+    Arc<MockChain>,
+    Arc<DlcStorageProvider<TenTenOneInMemoryStorage>>,
+    Arc<MockOracle>,
+    Arc<MockTime>,
+    Arc<FixedFeeRate>,
+>;
+
+/// Serialize the object in the given state as a named fixture, failing if it is in another state.
+macro_rules! fixture {
+    ($name:literal, $state:expr, $variant:path) => {
+        match $state {
+            $variant(object) => ($name, object.serialize()?),
+            _ => bail!("{} is in an unexpected state", $name),
+        }
+    };
+}
+
+fn main() -> Result<()> {
+    let dir = match std::env::args().nth(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("test_files"),
+    };
+
+    let fixtures = generate()?;
+    ensure!(
+        fixtures == generate()?,
+        "The fixtures differ between runs, rust-dlc draws a random value which is not replaced"
+    );
+
+    for (name, serialized) in fixtures {
+        let path = dir.join(name);
+        fs::write(&path, serialized).with_context(|| format!("Failed to write {path:?}"))?;
+
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn generate() -> Result<Vec<(&'static str, Vec<u8>)>> {
+    let mut fixtures = Fixtures::default();
+
+    settled_channel_fixtures(&mut fixtures)?;
+    pre_closed_fixture(&mut fixtures)?;
+
+    Ok(fixtures.canonicalize())
+}
+
+/// The states of a DLC channel and its contract from offering the channel to settling it
+/// off-chain, as well as the sub-channel states.
+fn settled_channel_fixtures(fixtures: &mut Fixtures) -> Result<()> {
+    let OpenChannel {
+        setup,
+        contract_id,
+        channel_id,
+        states,
+    } = open_channel(fixtures, 1)?;
+    let Setup {
+        chain,
+        offer_party,
+        accept_party,
+        ..
+    } = &setup;
+
+    for state in states {
+        fixtures.push(state);
+    }
+
+    // Confirm the funding transaction.
+
+    chain.confirm(CONFIRMATIONS);
+    offer_party.manager.periodic_check()?;
+    accept_party.manager.periodic_check()?;
+
+    fixtures.push(fixture!(
+        "Confirmed",
+        offer_party.contract(&contract_id)?,
+        Contract::Confirmed
+    ));
+    fixtures.push(fixture!(
+        "Confirmed1",
+        accept_party.contract(&contract_id)?,
+        Contract::Confirmed
+    ));
+
+    sub_channel_fixtures(fixtures, &setup, &channel_id)?;
+
+    // Settle the DLC channel off-chain.
+
+    let (settle_offer, _) =
+        offer_party
+            .manager
+            .settle_offer(&channel_id, SETTLEMENT_SATS, Some(reference_id(2)))?;
+
+    accept_party.receive(
+        ChannelMessage::SettleOffer(settle_offer),
+        offer_party.node_id,
+        false,
+    )?;
+
+    let (settle_accept, _) = accept_party.manager.accept_settle_offer(&channel_id)?;
+    fixtures.replace_adaptor_signatures([&settle_accept.settle_adaptor_signature]);
+
+    let settle_confirm = match offer_party.receive(
+        ChannelMessage::SettleAccept(settle_accept),
+        accept_party.node_id,
+        true,
+    )? {
+        Some(ChannelMessage::SettleConfirm(settle_confirm)) => settle_confirm,
+        _ => bail!("Missing settle confirm message"),
+    };
+    fixtures.replace_adaptor_signatures([&settle_confirm.settle_adaptor_signature]);
+
+    let settle_finalize = accept_party
+        .receive(
+            ChannelMessage::SettleConfirm(settle_confirm),
+            offer_party.node_id,
+            true,
+        )?
+        .context("Missing settle finalize message")?;
+    offer_party.receive(settle_finalize, accept_party.node_id, false)?;
+
+    fixtures.push(fixture!(
+        "SignedChannelSettled",
+        accept_party.settled_channel(&channel_id)?,
+        Channel::Signed
+    ));
+
+    Ok(())
+}
+
+/// The contract of a DLC channel which was force-closed after the oracle attested to the price,
+/// once the CET has been published.
+fn pre_closed_fixture(fixtures: &mut Fixtures) -> Result<()> {
+    let OpenChannel {
+        setup,
+        contract_id,
+        channel_id,
+        ..
+    } = open_channel(fixtures, 2)?;
+    let Setup {
+        chain,
+        time,
+        oracle,
+        offer_party,
+        accept_party,
+        ..
+    } = &setup;
+
+    chain.confirm(CONFIRMATIONS);
+    offer_party.manager.periodic_check()?;
+    accept_party.manager.periodic_check()?;
+
+    time.set(MATURITY as u64 + 60);
+    oracle.attest(ATTESTED_PRICE);
+
+    offer_party
+        .manager
+        .force_close_channel(&channel_id, Some(reference_id(3)))?;
+
+    // Once the buffer transaction is buried deep enough, the CET spending it is published. The
+    // CET itself stays unconfirmed, so that the contract is not closed yet.
+    chain.confirm(CET_NSEQUENCE);
+    offer_party.manager.periodic_check()?;
+
+    match offer_party.contract(&contract_id)? {
+        Contract::PreClosed(pre_closed) => {
+            fixtures.replace_witness_signatures(&pre_closed.signed_cet)?;
+            fixtures.add("PreClosed", &pre_closed)?;
+        }
+        _ => bail!("PreClosed is in an unexpected state"),
+    }
+
+    Ok(())
+}
+
+/// LN-DLC sub-channel states of both parties, from offering to signing the split of an LN
+/// channel into the DLC channel `channel_id` and the glue output, which keeps the LN channel
+/// going.
+///
+/// The states are assembled by hand, as 10101 nodes no longer run the sub-channel protocol. All
+/// signatures are deterministic and valid.
+fn sub_channel_fixtures(
+    fixtures: &mut Fixtures,
+    setup: &Setup,
+    channel_id: &DlcChannelId,
+) -> Result<()> {
+    let Setup {
+        offer_party,
+        accept_party,
+        ..
+    } = setup;
+
+    let offer_channel = offer_party.signed_channel(channel_id)?;
+    let accept_channel = accept_party.signed_channel(channel_id)?;
+
+    let offer_fund_pk = offer_channel.own_params.fund_pubkey;
+    let accept_fund_pk = accept_channel.own_params.fund_pubkey;
+    let offer_fund_sk = offer_party.wallet.secret_key(&offer_fund_pk)?;
+    let accept_fund_sk = accept_party.wallet.secret_key(&accept_fund_pk)?;
+
+    let funding_redeemscript = dlc::make_funding_redeemscript(&offer_fund_pk, &accept_fund_pk);
+    let dlc_funding_output = offer_channel.fund_tx.output[offer_channel.fund_output_index].clone();
+    let fund_value = LN_CHANNEL_SATS + dlc_funding_output.value;
+
+    let ln_funding_tx = transaction(
+        Vec::new(),
+        vec![TxOut {
+            value: fund_value,
+            script_pubkey: funding_redeemscript.to_v0_p2wsh(),
+        }],
+    );
+    let ln_funding_outpoint = OutPoint::new(ln_funding_tx.txid(), 0);
+
+    let split_tx = SplitTx {
+        transaction: transaction(
+            vec![ln_funding_outpoint],
+            vec![
+                TxOut {
+                    value: LN_CHANNEL_SATS,
+                    script_pubkey: funding_redeemscript.to_v0_p2wsh(),
+                },
+                dlc_funding_output,
+            ],
+        ),
+        output_script: funding_redeemscript.clone(),
+    };
+    let ln_glue_transaction = transaction(
+        vec![OutPoint::new(split_tx.transaction.txid(), 0)],
+        vec![TxOut {
+            value: LN_CHANNEL_SATS,
+            script_pubkey: funding_redeemscript.to_v0_p2wsh(),
+        }],
+    );
+    let ln_rollback = LnRollBackInfo {
+        channel_value_satoshis: LN_CHANNEL_SATS,
+        value_to_self_msat: LN_CHANNEL_SATS * 1_000 / 2,
+        funding_outpoint: LnOutPoint {
+            txid: ln_funding_outpoint.txid,
+            index: 0,
+        },
+    };
+
+    let offer_per_split_seed = offer_party.wallet.new_secret_key();
+    let offer_per_split_point = PublicKey::from_secret_key(SECP256K1, &offer_per_split_seed);
+    let accept_per_split_seed = accept_party.wallet.new_secret_key();
+    let accept_per_split_point = PublicKey::from_secret_key(SECP256K1, &accept_per_split_seed);
+
+    let split_sighash = segwit_sighash(&split_tx.transaction, &funding_redeemscript, fund_value)?;
+    let offer_split_adaptor_signature = EcdsaAdaptorSignature::encrypt_no_aux_rand(
+        SECP256K1,
+        &split_sighash,
+        &offer_fund_sk,
+        &accept_per_split_point,
+    );
+    let accept_split_adaptor_signature = EcdsaAdaptorSignature::encrypt_no_aux_rand(
+        SECP256K1,
+        &split_sighash,
+        &accept_fund_sk,
+        &offer_per_split_point,
+    );
+
+    let glue_sighash =
+        segwit_sighash(&ln_glue_transaction, &funding_redeemscript, LN_CHANNEL_SATS)?;
+    let accept_glue_signature = SECP256K1.sign_ecdsa(&glue_sighash, &accept_fund_sk);
+
+    let offered = SubChannel {
+        channel_id: ChannelId(seeded_bytes("sub-channel id", 1)),
+        counter_party: accept_party.node_id,
+        update_idx: INITIAL_SPLIT_NUMBER,
+        state: SubChannelState::Offered(OfferedSubChannel {
+            per_split_point: offer_per_split_point,
+        }),
+        per_split_seed: Some(offer_per_split_seed),
+        fee_rate_per_vb: FEE_RATE_SATS_PER_VB,
+        own_base_points: offer_channel.own_points.clone(),
+        counter_base_points: None,
+        fund_value_satoshis: fund_value,
+        original_funding_redeemscript: funding_redeemscript,
+        is_offer: true,
+        own_fund_pk: offer_fund_pk,
+        counter_fund_pk: accept_fund_pk,
+        counter_party_secrets: CounterpartyCommitmentSecrets::new(),
+    };
+    let received = SubChannel {
+        counter_party: offer_party.node_id,
+        per_split_seed: None,
+        own_base_points: accept_channel.own_points.clone(),
+        counter_base_points: Some(offer_channel.own_points.clone()),
+        is_offer: false,
+        own_fund_pk: accept_fund_pk,
+        counter_fund_pk: offer_fund_pk,
+        ..offered.clone()
+    };
+    let accepted = SubChannel {
+        state: SubChannelState::Accepted(AcceptedSubChannel {
+            offer_per_split_point,
+            accept_per_split_point,
+            accept_split_adaptor_signature,
+            split_tx: split_tx.clone(),
+            ln_glue_transaction: ln_glue_transaction.clone(),
+            ln_rollback: ln_rollback.clone(),
+            commitment_transactions: Vec::new(),
+        }),
+        per_split_seed: Some(accept_per_split_seed),
+        ..received.clone()
+    };
+    let signed = SubChannel {
+        state: SubChannelState::Signed(SignedSubChannel {
+            own_per_split_point: offer_per_split_point,
+            counter_per_split_point: accept_per_split_point,
+            own_split_adaptor_signature: offer_split_adaptor_signature,
+            counter_split_adaptor_signature: accept_split_adaptor_signature,
+            split_tx,
+            ln_glue_transaction,
+            counter_glue_signature: accept_glue_signature,
+            ln_rollback,
+        }),
+        counter_base_points: Some(accept_channel.own_points.clone()),
+        ..offered.clone()
+    };
+
+    fixtures.add("OfferedSubChannel", &offered)?;
+    fixtures.add("OfferedSubChannel1", &received)?;
+    fixtures.add("AcceptedSubChannel", &accepted)?;
+    fixtures.add("SignedSubChannel", &signed)?;
+
+    Ok(())
+}
+
+/// A DLC channel whose funding transaction has been signed by both parties.
+struct OpenChannel {
+    setup: Setup,
+    contract_id: ContractId,
+    channel_id: DlcChannelId,
+    /// The contract and channel states on the way, from the offer to the signed channel.
+    states: Vec<(&'static str, Vec<u8>)>,
+}
+
+/// Open DLC channel `index`, reopening it until the serial ids drawn by `rust-dlc` are in
+/// canonical order.
+fn open_channel(fixtures: &mut Fixtures, index: u64) -> Result<OpenChannel> {
+    for _ in 0..MAX_ATTEMPTS {
+        if let Some(open_channel) = try_open_channel(fixtures, Setup::new(index)?, index)? {
+            return Ok(open_channel);
+        }
+    }
+
+    bail!("Serial ids of DLC channel {index} not in canonical order after {MAX_ATTEMPTS} attempts")
+}
+
+/// Open a DLC channel, unless the serial ids drawn by `rust-dlc` are not in canonical order.
+///
+/// The drawn values are registered to be replaced in `fixtures` only if the channel is opened.
+fn try_open_channel(
+    fixtures: &mut Fixtures,
+    setup: Setup,
+    index: u64,
+) -> Result<Option<OpenChannel>> {
+    let Setup {
+        oracle,
+        event_id,
+        offer_party,
+        accept_party,
+        ..
+    } = &setup;
+
+    let offer_channel = offer_party.manager.offer_channel(
+        &contract_input(oracle.get_public_key(), event_id),
+        accept_party.node_id,
+        FeeConfig::EvenSplit,
+        Some(reference_id(1)),
+    )?;
+    let temporary_contract_id = offer_channel.temporary_contract_id;
+    let temporary_channel_id = offer_channel.temporary_channel_id;
+
+    accept_party.receive(
+        ChannelMessage::Offer(offer_channel.clone()),
+        offer_party.node_id,
+        false,
+    )?;
+
+    let mut states = vec![
+        fixture!(
+            "Offered",
+            accept_party.contract(&temporary_contract_id)?,
+            Contract::Offered
+        ),
+        fixture!(
+            "OfferedChannel",
+            accept_party.channel(&temporary_channel_id)?,
+            Channel::Offered
+        ),
+    ];
+
+    let (accept_channel, channel_id, contract_id, _) = accept_party
+        .manager
+        .accept_channel(&temporary_channel_id, FeeConfig::EvenSplit)?;
+
+    let serial_ids = serial_ids(&offer_channel, &accept_channel);
+    let in_canonical_order = serial_ids
+        .iter()
+        .all(|group| group.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    if !in_canonical_order {
+        return Ok(None);
+    }
+
+    for (random, canonical) in serial_ids.iter().flatten() {
+        fixtures.replace(&random.to_be_bytes(), &canonical.to_be_bytes());
+    }
+
+    let canonical_temporary_contract_id = seeded_bytes("temporary contract id", index);
+    let canonical_temporary_channel_id = seeded_bytes("temporary channel id", index);
+    fixtures.replace(&temporary_contract_id, &canonical_temporary_contract_id);
+    fixtures.replace(&temporary_channel_id, &canonical_temporary_channel_id);
+
+    // The final ids are derived from the funding transaction by XORing in the temporary ids.
+    fixtures.replace(
+        &contract_id,
+        &xor(
+            &xor(&contract_id, &temporary_contract_id),
+            &canonical_temporary_contract_id,
+        ),
+    );
+    fixtures.replace(
+        &channel_id,
+        &xor(
+            &xor(&channel_id, &temporary_channel_id),
+            &canonical_temporary_channel_id,
+        ),
+    );
+
+    fixtures.replace_adaptor_signatures(
+        accept_channel
+            .cet_adaptor_signatures
+            .ecdsa_adaptor_signatures
+            .iter()
+            .map(|cet_adaptor_signature| &cet_adaptor_signature.signature)
+            .chain([&accept_channel.buffer_adaptor_signature]),
+    );
+
+    states.push(fixture!(
+        "Accepted",
+        accept_party.contract(&contract_id)?,
+        Contract::Accepted
+    ));
+    states.push(fixture!(
+        "AcceptedChannel",
+        accept_party.channel(&channel_id)?,
+        Channel::Accepted
+    ));
+
+    let sign_channel = match offer_party.receive(
+        ChannelMessage::Accept(accept_channel),
+        accept_party.node_id,
+        true,
+    )? {
+        Some(ChannelMessage::Sign(sign_channel)) => sign_channel,
+        _ => bail!("Missing sign channel message"),
+    };
+
+    fixtures.replace_adaptor_signatures(
+        sign_channel
+            .cet_adaptor_signatures
+            .ecdsa_adaptor_signatures
+            .iter()
+            .map(|cet_adaptor_signature| &cet_adaptor_signature.signature)
+            .chain([&sign_channel.buffer_adaptor_signature]),
+    );
+
+    states.push(fixture!(
+        "Signed1",
+        offer_party.contract(&contract_id)?,
+        Contract::Signed
+    ));
+
+    accept_party.receive(
+        ChannelMessage::Sign(sign_channel),
+        offer_party.node_id,
+        false,
+    )?;
+
+    states.push(fixture!(
+        "Signed",
+        accept_party.contract(&contract_id)?,
+        Contract::Signed
+    ));
+    states.push(fixture!(
+        "SignedChannelEstablished",
+        accept_party.established_channel(&channel_id)?,
+        Channel::Signed
+    ));
+
+    Ok(Some(OpenChannel {
+        setup,
+        contract_id,
+        channel_id,
+        states,
+    }))
+}
+
+/// The serial ids drawn by `rust-dlc` with the canonical values replacing them, grouped by the
+/// inputs or outputs they order.
+///
+/// The transactions of the DLC channel only depend on the order of the serial ids within each
+/// group, not on their values.
+fn serial_ids(offer: &OfferChannel, accept: &AcceptChannel) -> [Vec<(u64, u64)>; 3] {
+    let funding_inputs = offer
+        .funding_inputs
+        .iter()
+        .chain(&accept.funding_inputs)
+        .zip(1..)
+        .map(|(funding_input, canonical)| (funding_input.input_serial_id, canonical))
+        .collect();
+
+    [
+        funding_inputs,
+        // The outputs of the funding transaction.
+        vec![
+            (offer.fund_output_serial_id, 10),
+            (offer.change_serial_id, 11),
+            (accept.change_serial_id, 12),
+        ],
+        // The outputs of the CETs and of the refund transaction.
+        vec![(offer.payout_serial_id, 20), (accept.payout_serial_id, 21)],
+    ]
+}
+
+/// The fixtures, with the values drawn by `rust-dlc` to be replaced by seeded ones.
+#[derive(Default)]
+struct Fixtures {
+    fixtures: Vec<(&'static str, Vec<u8>)>,
+    /// Pairs of a random value and the canonical value of the same length replacing it.
+    replacements: Vec<(Vec<u8>, Vec<u8>)>,
+    /// How many placeholder signatures have been created.
+    placeholders: u64,
+}
+
+impl Fixtures {
+    fn push(&mut self, fixture: (&'static str, Vec<u8>)) {
+        self.fixtures.push(fixture);
+    }
+
+    fn add(&mut self, name: &'static str, object: &impl Serializable) -> Result<()> {
+        self.push((name, object.serialize()?));
+
+        Ok(())
+    }
+
+    fn replace(&mut self, random: &[u8], canonical: &[u8]) {
+        assert_eq!(
+            random.len(),
+            canonical.len(),
+            "Replacement must be of the same length"
+        );
+
+        self.replacements
+            .push((random.to_vec(), canonical.to_vec()));
+    }
+
+    /// Replace adaptor signatures, which are encrypted with a random nonce, by deterministic ones
+    /// for an unrelated message.
+    fn replace_adaptor_signatures<'a>(
+        &mut self,
+        signatures: impl IntoIterator<Item = &'a EcdsaAdaptorSignature>,
+    ) {
+        for signature in signatures {
+            let placeholder = self.next_placeholder();
+
+            let sk = seeded_secret_key("adaptor signature key", placeholder);
+            let encryption_key = PublicKey::from_secret_key(SECP256K1, &sk);
+            let message = secp256k1_zkp::Message::from_slice(&seeded_bytes(
+                "adaptor signature message",
+                placeholder,
+            ))
+            .expect("32 bytes");
+
+            let canonical = EcdsaAdaptorSignature::encrypt_no_aux_rand(
+                SECP256K1,
+                &message,
+                &sk,
+                &encryption_key,
+            );
+
+            self.replace(signature.as_ref(), canonical.as_ref());
+        }
+    }
+
+    /// Replace the signatures in the witnesses of `tx`, one of which has been decrypted from a
+    /// random adaptor signature, by deterministic ones of the same length.
+    fn replace_witness_signatures(&mut self, tx: &Transaction) -> Result<()> {
+        for input in &tx.input {
+            for item in input.witness.iter() {
+                if item.first() != Some(&0x30) || !(9..=73).contains(&item.len()) {
+                    continue;
+                }
+
+                let placeholder = self.next_placeholder();
+                let canonical = placeholder_signature(placeholder, item.len())?;
+
+                self.replace(item, &canonical);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next_placeholder(&mut self) -> u64 {
+        self.placeholders += 1;
+        self.placeholders
+    }
+
+    /// The serialized fixtures, with every random value replaced.
+    fn canonicalize(self) -> Vec<(&'static str, Vec<u8>)> {
+        let Self {
+            fixtures,
+            replacements,
+            ..
+        } = self;
+
+        // Every replaced value is at least 8 bytes long, so that they can be looked up by their
+        // first 8 bytes.
+        let mut by_prefix = HashMap::<&[u8], Vec<&(Vec<u8>, Vec<u8>)>>::new();
+        for replacement in &replacements {
+            by_prefix
+                .entry(&replacement.0[..8])
+                .or_default()
+                .push(replacement);
+        }
+
+        fixtures
+            .into_iter()
+            .map(|(name, mut serialized)| {
+                let mut i = 0;
+                while i + 8 <= serialized.len() {
+                    let replacement = by_prefix.get(&serialized[i..i + 8]).and_then(|candidates| {
+                        candidates
+                            .iter()
+                            .find(|(random, _)| serialized[i..].starts_with(random))
+                    });
+
+                    match replacement {
+                        Some((random, canonical)) => {
+                            serialized[i..i + random.len()].copy_from_slice(canonical);
+                            i += random.len();
+                        }
+                        None => i += 1,
+                    }
+                }
+
+                (name, serialized)
+            })
+            .collect()
+    }
+}
+
+/// The mocks and the two parties of a DLC channel.
+struct Setup {
+    chain: Arc<MockChain>,
+    time: Arc<MockTime>,
+    oracle: Arc<MockOracle>,
+    event_id: String,
+    offer_party: Party,
+    accept_party: Party,
+}
+
+impl Setup {
+    /// The setup of DLC channel `index`. The keys, coins and oracle announcement only depend on
+    /// `index`, so that reopening the channel starts from the same setup.
+    fn new(index: u64) -> Result<Self> {
+        let mut rng = StdRng::from_seed(seeded_bytes("setup", index));
+
+        let maturity = OffsetDateTime::from_unix_timestamp(MATURITY)?;
+        let event_id = OracleEventId::new(ContractSymbol::BtcUsd, maturity).to_string();
+
+        let chain = Arc::new(MockChain::default());
+        let time = Arc::new(MockTime::new(NOW));
+        let oracle = Arc::new(MockOracle::new(&mut rng, &event_id));
+        let offer_party = Party::new(&mut rng, chain.clone(), time.clone(), oracle.clone())?;
+        let accept_party = Party::new(&mut rng, chain.clone(), time.clone(), oracle.clone())?;
+
+        Ok(Self {
+            chain,
+            time,
+            oracle,
+            event_id,
+            offer_party,
+            accept_party,
+        })
+    }
+}
+
+/// One side of the DLC channel.
+struct Party {
+    node_id: PublicKey,
+    wallet: Arc<MockWallet>,
+    manager: FixtureManager,
+}
+
+impl Party {
+    fn new(
+        rng: &mut StdRng,
+        chain: Arc<MockChain>,
+        time: Arc<MockTime>,
+        oracle: Arc<MockOracle>,
+    ) -> Result<Self> {
+        let node_id = PublicKey::from_secret_key(SECP256K1, &secret_key(rng));
+        let wallet = Arc::new(MockWallet::new(
+            StdRng::from_seed(rng.gen()),
+            chain.clone(),
+        )?);
+
+        let storage = Arc::new(DlcStorageProvider::new(
+            TenTenOneInMemoryStorage::new(),
+            DlcChannelEventBus::new(),
+        ));
+
+        let mut oracles = HashMap::new();
+        oracles.insert(oracle.get_public_key(), oracle);
+
+        let manager = Manager::new(
+            wallet.clone(),
+            chain,
+            storage,
+            oracles,
+            time,
+            Arc::new(FixedFeeRate),
+        )?;
+
+        Ok(Self {
+            node_id,
+            wallet,
+            manager,
+        })
+    }
+
+    /// Process a message of the counterparty, returning our response.
+    fn receive(
+        &self,
+        message: ChannelMessage,
+        counterparty: PublicKey,
+        expect_response: bool,
+    ) -> Result<Option<ChannelMessage>> {
+        let response = self
+            .manager
+            .on_dlc_message(&Message::Channel(message), counterparty)?;
+
+        let response = match response {
+            Some(Message::Channel(response)) => Some(response),
+            Some(_) => bail!("Unexpected response to channel message"),
+            None => None,
+        };
+
+        ensure!(
+            response.is_some() == expect_response,
+            "Unexpected response to channel message"
+        );
+
+        Ok(response)
+    }
+
+    fn contract(&self, contract_id: &ContractId) -> Result<Contract> {
+        self.manager
+            .get_store()
+            .get_contract(contract_id)?
+            .with_context(|| format!("Missing contract {}", hex::encode(contract_id)))
+    }
+
+    fn channel(&self, channel_id: &DlcChannelId) -> Result<Channel> {
+        self.manager
+            .get_store()
+            .get_channel(channel_id)?
+            .with_context(|| format!("Missing channel {}", hex::encode(channel_id)))
+    }
+
+    fn signed_channel(&self, channel_id: &DlcChannelId) -> Result<SignedChannel> {
+        match self.channel(channel_id)? {
+            Channel::Signed(signed_channel) => Ok(signed_channel),
+            _ => bail!("Channel is not signed"),
+        }
+    }
+
+    fn established_channel(&self, channel_id: &DlcChannelId) -> Result<Channel> {
+        let channel = self.channel(channel_id)?;
+        ensure!(
+            matches!(
+                &channel,
+                Channel::Signed(signed_channel)
+                    if matches!(signed_channel.state, SignedChannelState::Established { .. })
+            ),
+            "Channel is not established"
+        );
+
+        Ok(channel)
+    }
+
+    fn settled_channel(&self, channel_id: &DlcChannelId) -> Result<Channel> {
+        let channel = self.channel(channel_id)?;
+        ensure!(
+            matches!(
+                &channel,
+                Channel::Signed(signed_channel)
+                    if matches!(signed_channel.state, SignedChannelState::Settled { .. })
+            ),
+            "Channel is not settled"
+        );
+
+        Ok(channel)
+    }
+}
+
+/// A numerical contract on the BTCUSD price, in which the accepting party is long.
+fn contract_input(oracle: XOnlyPublicKey, event_id: &str) -> ContractInput {
+    let total_collateral = OFFER_COLLATERAL_SATS + ACCEPT_COLLATERAL_SATS;
+    let max_outcome = (1 << NB_DIGITS) - 1;
+
+    let piece = |from: (u64, u64), to: (u64, u64)| {
+        PolynomialPayoutCurvePiece::new(vec![
+            PayoutPoint {
+                event_outcome: from.0,
+                outcome_payout: from.1,
+                extra_precision: 0,
+            },
+            PayoutPoint {
+                event_outcome: to.0,
+                outcome_payout: to.1,
+                extra_precision: 0,
+            },
+        ])
+        .map(PayoutFunctionPiece::PolynomialPayoutCurvePiece)
+        .expect("valid payout curve piece")
+    };
+
+    let payout_function = PayoutFunction::new(vec![
+        piece((0, 0), (50_000, 0)),
+        piece((50_000, 0), (70_000, total_collateral)),
+        piece((70_000, total_collateral), (max_outcome, total_collateral)),
+    ])
+    .expect("valid payout function");
+
+    ContractInput {
+        offer_collateral: OFFER_COLLATERAL_SATS,
+        accept_collateral: ACCEPT_COLLATERAL_SATS,
+        fee_rate: FEE_RATE_SATS_PER_VB,
+        contract_infos: vec![ContractInputInfo {
+            contract_descriptor: ContractDescriptor::Numerical(NumericalDescriptor {
+                payout_function,
+                rounding_intervals: RoundingIntervals {
+                    intervals: vec![RoundingInterval {
+                        begin_interval: 0,
+                        rounding_mod: 1_000,
+                    }],
+                },
+                difference_params: None,
+                oracle_numeric_infos: dlc_trie::OracleNumericInfo {
+                    base: 2,
+                    nb_digits: vec![NB_DIGITS as usize],
+                },
+            }),
+            oracles: OracleInput {
+                public_keys: vec![oracle],
+                event_id: event_id.to_string(),
+                threshold: 1,
+            },
+        }],
+    }
+}
+
+/// A reference id like those derived from the [`ProtocolId`]s of 10101 nodes.
+fn reference_id(n: u128) -> ReferenceId {
+    ProtocolId::from(Uuid::from_u128(n)).into()
+}
+
+fn secret_key(rng: &mut StdRng) -> SecretKey {
+    SecretKey::from_slice(&rng.gen::<[u8; 32]>()).expect("valid secret key")
+}
+
+/// 32 bytes derived from [`SEED`], which are unique for each `label` and `index`.
+fn seeded_bytes(label: &str, index: u64) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(SEED.to_be_bytes())
+        .chain_update(label)
+        .chain_update(index.to_be_bytes())
+        .finalize()
+        .into()
+}
+
+fn seeded_secret_key(label: &str, index: u64) -> SecretKey {
+    SecretKey::from_slice(&seeded_bytes(label, index)).expect("valid secret key")
+}
+
+/// A deterministic DER-encoded signature of `len` bytes, including the sighash type.
+fn placeholder_signature(placeholder: u64, len: usize) -> Result<Vec<u8>> {
+    let sk = seeded_secret_key("placeholder signature key", placeholder);
+
+    // The length of a DER-encoded signature depends on its values, hence try messages until one
+    // yields a signature of the wanted length.
+    for attempt in 0..1_000 {
+        let message = secp256k1_zkp::Message::from_slice(&seeded_bytes(
+            "placeholder signature message",
+            placeholder * 1_000 + attempt,
+        ))
+        .expect("32 bytes");
+
+        let mut signature = SECP256K1.sign_ecdsa(&message, &sk).serialize_der().to_vec();
+        signature.push(EcdsaSighashType::All as u8);
+
+        if signature.len() == len {
+            return Ok(signature);
+        }
+    }
+
+    bail!("No placeholder signature of {len} bytes")
+}
+
+fn xor(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut xor = *a;
+    for (x, b) in xor.iter_mut().zip(b) {
+        *x ^= b;
+    }
+
+    xor
+}
+
+fn transaction(inputs: Vec<OutPoint>, output: Vec<TxOut>) -> Transaction {
+    Transaction {
+        version: 2,
+        lock_time: PackedLockTime::ZERO,
+        input: inputs
+            .into_iter()
+            .map(|previous_output| TxIn {
+                previous_output,
+                script_sig: Script::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output,
+    }
+}
+
+/// The sighash of the first input of `tx`, which spends a segwit output of `value` sats.
+fn segwit_sighash(
+    tx: &Transaction,
+    script_code: &Script,
+    value: u64,
+) -> Result<secp256k1_zkp::Message> {
+    let sighash = SighashCache::new(tx).segwit_signature_hash(
+        0,
+        script_code,
+        value,
+        EcdsaSighashType::All,
+    )?;
+
+    Ok(secp256k1_zkp::Message::from_slice(&sighash[..]).expect("32 bytes"))
+}
+
+/// A chain which confirms every transaction it knows of at once, whenever
+/// [`MockChain::confirm`] is called.
+#[derive(Default)]
+struct MockChain {
+    transactions: Mutex<HashMap<Txid, (Transaction, u32)>>,
+}
+
+impl MockChain {
+    fn add(&self, tx: Transaction) {
+        self.transactions.lock().insert(tx.txid(), (tx, 0));
+    }
+
+    fn confirm(&self, confirmations: u32) {
+        for (_, tx_confirmations) in self.transactions.lock().values_mut() {
+            *tx_confirmations = confirmations;
+        }
+    }
+}
+
+impl dlc_manager::Blockchain for MockChain {
+    fn send_transaction(&self, tx: &Transaction) -> Result<(), Error> {
+        self.add(tx.clone());
+
+        Ok(())
+    }
+
+    fn get_network(&self) -> Result<Network, Error> {
+        Ok(NETWORK)
+    }
+
+    fn get_blockchain_height(&self) -> Result<u64, Error> {
+        Ok(BLOCK_HEIGHT)
+    }
+
+    fn get_block_at_height(&self, height: u64) -> Result<bitcoin_old::Block, Error> {
+        Err(Error::BlockchainError(format!(
+            "No block at height {height}"
+        )))
+    }
+
+    fn get_transaction(&self, txid: &Txid) -> Result<Transaction, Error> {
+        self.transactions
+            .lock()
+            .get(txid)
+            .map(|(tx, _)| tx.clone())
+            .ok_or_else(|| Error::BlockchainError(format!("Transaction {txid} not found")))
+    }
+
+    fn get_transaction_confirmations(&self, txid: &Txid) -> Result<u32, Error> {
+        let confirmations = self
+            .transactions
+            .lock()
+            .get(txid)
+            .map(|(_, confirmations)| *confirmations)
+            .unwrap_or_default();
+
+        Ok(confirmations)
+    }
+
+    fn get_txo_confirmations(&self, _: &OutPoint) -> Result<Option<(u32, Txid)>, Error> {
+        Ok(None)
+    }
+}
+
+/// A clock which stands still, until it is set.
+struct MockTime {
+    now: AtomicU64,
+}
+
+impl MockTime {
+    fn new(now: u64) -> Self {
+        Self {
+            now: AtomicU64::new(now),
+        }
+    }
+
+    fn set(&self, now: u64) {
+        self.now.store(now, Ordering::Relaxed);
+    }
+}
+
+impl dlc_manager::Time for MockTime {
+    fn unix_time_now(&self) -> u64 {
+        self.now.load(Ordering::Relaxed)
+    }
+}
+
+/// A wallet with a single P2WPKH coin, whose keys are derived from a seeded RNG.
+struct MockWallet {
+    rng: Mutex<StdRng>,
+    keys: Mutex<HashMap<PublicKey, SecretKey>>,
+    coin: (Utxo, SecretKey),
+}
+
+impl MockWallet {
+    fn new(mut rng: StdRng, chain: Arc<MockChain>) -> Result<Self> {
+        let sk = secret_key(&mut rng);
+        let address = p2wpkh_address(&sk)?;
+
+        let funding_tx = transaction(
+            vec![OutPoint::null()],
+            vec![TxOut {
+                value: COIN_SATS,
+                script_pubkey: address.script_pubkey(),
+            }],
+        );
+
+        let utxo = Utxo {
+            tx_out: funding_tx.output[0].clone(),
+            outpoint: OutPoint::new(funding_tx.txid(), 0),
+            address,
+            redeem_script: Script::new(),
+            reserved: false,
+        };
+
+        chain.add(funding_tx);
+
+        Ok(Self {
+            rng: Mutex::new(rng),
+            keys: Mutex::new(HashMap::new()),
+            coin: (utxo, sk),
+        })
+    }
+
+    fn new_secret_key(&self) -> SecretKey {
+        let sk = secret_key(&mut self.rng.lock());
+        self.keys
+            .lock()
+            .insert(PublicKey::from_secret_key(SECP256K1, &sk), sk);
+
+        sk
+    }
+
+    fn secret_key(&self, pk: &PublicKey) -> Result<SecretKey> {
+        self.keys.lock().get(pk).copied().context("Unknown PK")
+    }
+}
+
+impl dlc_manager::Wallet for MockWallet {
+    fn get_new_address(&self) -> Result<Address, Error> {
+        p2wpkh_address(&self.new_secret_key())
+            .map_err(|e| Error::WalletError(format!("{e:#}").into()))
+    }
+
+    fn get_new_secret_key(&self) -> Result<SecretKey, Error> {
+        Ok(self.new_secret_key())
+    }
+
+    fn get_utxos_for_amount(
+        &self,
+        amount: u64,
+        _: Option<u64>,
+        _: u64,
+        _: bool,
+    ) -> Result<Vec<Utxo>, Error> {
+        if amount == 0 {
+            return Ok(Vec::new());
+        }
+
+        let (utxo, _) = &self.coin;
+        if utxo.tx_out.value < amount {
+            return Err(Error::WalletError(
+                format!("Coin does not cover amount {amount} sats").into(),
+            ));
+        }
+
+        Ok(vec![utxo.clone()])
+    }
+
+    fn import_address(&self, _: &Address) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn unreserve_utxos(&self, _: &[OutPoint]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl dlc_manager::Signer for MockWallet {
+    fn sign_psbt_input(
+        &self,
+        psbt: &mut PartiallySignedTransaction,
+        index: usize,
+    ) -> Result<(), Error> {
+        let (utxo, sk) = &self.coin;
+
+        let outpoint = psbt.unsigned_tx.input[index].previous_output;
+        if outpoint != utxo.outpoint {
+            return Err(Error::WalletError(
+                format!("Unknown input {outpoint}").into(),
+            ));
+        }
+
+        let pk = bitcoin_old::PublicKey::new(PublicKey::from_secret_key(SECP256K1, sk));
+        let script_code = Script::new_p2pkh(&pk.pubkey_hash());
+        let sighash = SighashCache::new(&psbt.unsigned_tx)
+            .segwit_signature_hash(
+                index,
+                &script_code,
+                utxo.tx_out.value,
+                EcdsaSighashType::All,
+            )
+            .map_err(|e| Error::WalletError(format!("{e}").into()))?;
+
+        let message = secp256k1_zkp::Message::from_slice(&sighash[..]).expect("32 bytes");
+        let mut signature = SECP256K1.sign_ecdsa(&message, sk).serialize_der().to_vec();
+        signature.push(EcdsaSighashType::All as u8);
+
+        psbt.inputs[index].final_script_witness =
+            Some(Witness::from_vec(vec![signature, pk.to_bytes()]));
+
+        Ok(())
+    }
+
+    fn get_secret_key_for_pubkey(&self, pk: &PublicKey) -> Result<SecretKey, Error> {
+        self.secret_key(pk)
+            .map_err(|e| Error::StorageError(format!("{e:#}")))
+    }
+}
+
+fn p2wpkh_address(sk: &SecretKey) -> Result<Address> {
+    let pk = bitcoin_old::PublicKey::new(PublicKey::from_secret_key(SECP256K1, sk));
+    let address = Address::p2wpkh(&pk, NETWORK)?;
+
+    Ok(address)
+}
+
+/// An oracle which announced a single numerical event, and attests to it once
+/// [`MockOracle::attest`] is called.
+struct MockOracle {
+    key_pair: KeyPair,
+    /// The secret nonces of the announcement, one per digit.
+    nonces: Vec<SecretKey>,
+    announcement: OracleAnnouncement,
+    attestation: Mutex<Option<OracleAttestation>>,
+}
+
+impl MockOracle {
+    fn new(rng: &mut StdRng, event_id: &str) -> Self {
+        let key_pair = KeyPair::from_secret_key(SECP256K1, &secret_key(rng));
+        let (public_key, _) = XOnlyPublicKey::from_keypair(&key_pair);
+
+        let nonces = (0..NB_DIGITS).map(|_| secret_key(rng)).collect::<Vec<_>>();
+
+        let oracle_nonces = nonces
+            .iter()
+            .map(|nonce| {
+                let nonce = KeyPair::from_secret_key(SECP256K1, nonce);
+                XOnlyPublicKey::from_keypair(&nonce).0
+            })
+            .collect();
+
+        let oracle_event = OracleEvent {
+            oracle_nonces,
+            event_maturity_epoch: MATURITY as u32,
+            event_descriptor: EventDescriptor::DigitDecompositionEvent(
+                DigitDecompositionEventDescriptor {
+                    base: 2,
+                    is_signed: false,
+                    unit: "usd".to_string(),
+                    precision: 0,
+                    nb_digits: NB_DIGITS,
+                },
+            ),
+            event_id: event_id.to_string(),
+        };
+
+        let digest = Sha256::digest(oracle_event.encode());
+        let message = secp256k1_zkp::Message::from_slice(&digest).expect("32 bytes");
+        let announcement_signature = SECP256K1.sign_schnorr_no_aux_rand(&message, &key_pair);
+
+        Self {
+            key_pair,
+            nonces,
+            announcement: OracleAnnouncement {
+                announcement_signature,
+                oracle_public_key: public_key,
+                oracle_event,
+            },
+            attestation: Mutex::new(None),
+        }
+    }
+
+    /// Attest to `price`, signing each of its binary digits with the nonce announced for it.
+    fn attest(&self, price: u64) {
+        let outcomes = (0..NB_DIGITS)
+            .rev()
+            .map(|digit| ((price >> digit) & 1).to_string())
+            .collect::<Vec<_>>();
+
+        let signatures = outcomes
+            .iter()
+            .zip(&self.nonces)
+            .map(|(outcome, nonce)| {
+                let digest = Sha256::digest(outcome.as_bytes());
+                let message = secp256k1_zkp::Message::from_slice(&digest).expect("32 bytes");
+
+                dlc::secp_utils::schnorrsig_sign_with_nonce(
+                    SECP256K1,
+                    &message,
+                    &self.key_pair,
+                    &nonce.secret_bytes(),
+                )
+            })
+            .collect();
+
+        *self.attestation.lock() = Some(OracleAttestation {
+            event_id: self.announcement.oracle_event.event_id.clone(),
+            oracle_public_key: self.get_public_key(),
+            signatures,
+            outcomes,
+        });
+    }
+}
+
+impl Oracle for MockOracle {
+    fn get_public_key(&self) -> XOnlyPublicKey {
+        self.announcement.oracle_public_key
+    }
+
+    fn get_announcement(&self, event_id: &str) -> Result<OracleAnnouncement, Error> {
+        if event_id != self.announcement.oracle_event.event_id {
+            return Err(Error::OracleError(format!("Unknown event {event_id}")));
+        }
+
+        Ok(self.announcement.clone())
+    }
+
+    fn get_attestation(&self, event_id: &str) -> Result<OracleAttestation, Error> {
+        if event_id != self.announcement.oracle_event.event_id {
+            return Err(Error::OracleError(format!("Unknown event {event_id}")));
+        }
+
+        self.attestation
+            .lock()
+            .clone()
+            .ok_or_else(|| Error::OracleError(format!("Event {event_id} has not been attested to")))
+    }
+}
+
+struct FixedFeeRate;
+
+impl FeeEstimator for FixedFeeRate {
+    fn get_est_sat_per_1000_weight(&self, _: ConfirmationTarget) -> u32 {
+        FEE_RATE_SATS_PER_KW
+    }
+}
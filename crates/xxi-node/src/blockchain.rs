@@ -1,4 +1,6 @@
 use crate::bitcoin_conversion::to_tx_30;
+use crate::esplora::with_retry_blocking;
+use crate::esplora::EsploraClient;
 use crate::node::Storage;
 use anyhow::Context;
 use anyhow::Result;
@@ -12,15 +14,23 @@ use bitcoin::OutPoint;
 use bitcoin::Transaction;
 use bitcoin::Txid;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::task::spawn_blocking;
 use tracing::instrument;
 
+/// The timeout of a single request to electrs. Failed requests are retried, see
+/// [`crate::esplora`].
 const SOCKET_TIMEOUT: u64 = 30;
 
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long idle connections to electrs are kept open to be reused by later requests.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
 #[derive(Clone)]
 pub struct Blockchain<N> {
     /// Async client used during on-chain syncing and, sometimes, to broadcast transactions.
-    pub(crate) esplora_client_async: esplora_client::AsyncClient,
+    pub(crate) esplora_client_async: EsploraClient,
     /// Blocking client used when the task to be performed is in a blocking context (usually
     /// blocking trait methods).
     esplora_client_blocking: esplora_client::BlockingClient,
@@ -31,10 +41,24 @@ impl<N> Blockchain<N>
 where
     N: Storage,
 {
-    pub fn new(electrs_url: String, node_storage: Arc<N>) -> Result<Self> {
-        let esplora_client_async = esplora_client::Builder::new(&electrs_url)
-            .timeout(SOCKET_TIMEOUT)
-            .build_async()?;
+    /// Create the clients to talk to electrs.
+    ///
+    /// The async client keeps up to `concurrency` connections to electrs open, so that the
+    /// parallel requests of an on-chain wallet sync reuse connections instead of opening a new
+    /// one per request. If electrs is served over TLS and supports it, HTTP/2 is negotiated via
+    /// ALPN to multiplex them over a single connection.
+    pub fn new(electrs_url: String, node_storage: Arc<N>, concurrency: u8) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(SOCKET_TIMEOUT))
+            .connect_timeout(CONNECT_TIMEOUT)
+            .pool_max_idle_per_host(concurrency.max(1) as usize)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(POOL_IDLE_TIMEOUT)
+            .build()?;
+        let esplora_client_async = EsploraClient::new(esplora_client::AsyncClient::from_client(
+            electrs_url.clone(),
+            http_client,
+        ));
         let esplora_client_blocking = esplora_client::Builder::new(&electrs_url)
             .timeout(SOCKET_TIMEOUT)
             .build_blocking()?;
@@ -64,34 +88,39 @@ where
     }
 
     pub fn get_blockchain_tip(&self) -> Result<u64> {
-        let height = self.esplora_client_blocking.get_height()?;
+        let height =
+            with_retry_blocking("get_height", || self.esplora_client_blocking.get_height())?;
 
         Ok(height as u64)
     }
 
     pub fn get_block_hash(&self, height: u64) -> Result<BlockHash> {
-        let block_hash = self.esplora_client_blocking.get_block_hash(height as u32)?;
+        let block_hash = with_retry_blocking("get_block_hash", || {
+            self.esplora_client_blocking.get_block_hash(height as u32)
+        })?;
 
         Ok(block_hash)
     }
 
     pub fn get_block_by_hash(&self, block_hash: &BlockHash) -> Result<Block> {
-        let block = self
-            .esplora_client_blocking
-            .get_block_by_hash(block_hash)?
-            .context("Could not find block")?;
+        let block = with_retry_blocking("get_block_by_hash", || {
+            self.esplora_client_blocking.get_block_by_hash(block_hash)
+        })?
+        .context("Could not find block")?;
 
         Ok(block)
     }
 
     pub fn get_transaction(&self, txid: &Txid) -> Result<Option<Transaction>> {
-        let tx = self.esplora_client_blocking.get_tx(txid)?;
+        let tx = with_retry_blocking("get_tx", || self.esplora_client_blocking.get_tx(txid))?;
 
         Ok(tx)
     }
 
     pub fn get_transaction_confirmations(&self, txid: &Txid) -> Result<u32> {
-        let status = self.esplora_client_blocking.get_tx_status(txid)?;
+        let status = with_retry_blocking("get_tx_status", || {
+            self.esplora_client_blocking.get_tx_status(txid)
+        })?;
 
         let tx_height = match status.block_height {
             Some(height) => height,
@@ -102,9 +131,10 @@ where
     }
 
     pub fn get_txo_confirmations(&self, txo: &OutPoint) -> Result<Option<(u32, Txid)>> {
-        let status = self
-            .esplora_client_blocking
-            .get_output_status(&txo.txid, txo.vout as u64)?;
+        let status = with_retry_blocking("get_output_status", || {
+            self.esplora_client_blocking
+                .get_output_status(&txo.txid, txo.vout as u64)
+        })?;
 
         let (tx_height, txid) = match status {
             Some(OutputStatus {
@@ -125,7 +155,7 @@ where
     }
 
     fn tx_height_to_confirmations(&self, tx_height: u32) -> Result<u32> {
-        let tip = self.esplora_client_blocking.get_height()?;
+        let tip = with_retry_blocking("get_height", || self.esplora_client_blocking.get_height())?;
 
         let confirmations = match tip.checked_sub(tx_height) {
             Some(diff) => diff + 1,
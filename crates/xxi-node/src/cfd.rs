@@ -1,3 +1,5 @@
+use crate::commons::ContractSymbol;
+use crate::commons::ContractType;
 use crate::commons::Direction;
 use anyhow::Context;
 use anyhow::Result;
@@ -9,9 +11,33 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use std::ops::Neg;
 
+/// The highest price of BTCUSD the oracle can attest to, i.e. with 20 binary digits.
 pub const BTCUSD_MAX_PRICE: u64 = 1_048_575;
 
+/// The highest price of ETHUSD the oracle can attest to, i.e. with 17 binary digits.
+pub const ETHUSD_MAX_PRICE: u64 = 131_071;
+
+/// The number of binary digits with which the oracle attests to the price of the symbol.
+pub fn oracle_digits(symbol: ContractSymbol) -> usize {
+    match symbol {
+        ContractSymbol::BtcUsd => 20,
+        ContractSymbol::EthUsd => 17,
+    }
+}
+
+/// The highest price the oracle can attest to for the symbol. The payout curve of a contract ends
+/// at this price.
+pub fn max_price(symbol: ContractSymbol) -> u64 {
+    match symbol {
+        ContractSymbol::BtcUsd => BTCUSD_MAX_PRICE,
+        ContractSymbol::EthUsd => ETHUSD_MAX_PRICE,
+    }
+}
+
 /// Calculate the collateral in sats.
+///
+/// This holds for inverse and linear contracts alike, as the margin of a linear contract is
+/// converted to sats at the opening price.
 pub fn calculate_margin(open_price: Decimal, quantity: f32, leverage: f32) -> Amount {
     let quantity = Decimal::try_from(quantity).expect("quantity to fit into decimal");
     let leverage = Decimal::try_from(leverage).expect("leverage to fix into decimal");
@@ -42,6 +68,15 @@ pub fn calculate_leverage(quantity: Decimal, margin: Amount, open_price: Decimal
         .unwrap_or(Decimal::TEN * Decimal::ONE_THOUSAND)
 }
 
+/// Calculate the margin ratio of a position, i.e. its maintenance margin as a share of its margin.
+///
+/// The maintenance margin is a share of the value of the position at the opening price, so the
+/// margin ratio grows as funding fees are paid out of the margin. A position with a margin ratio of
+/// 1 or more would be liquidated at its opening price already.
+pub fn calculate_margin_ratio(leverage: Decimal, maintenance_margin_rate: Decimal) -> Decimal {
+    leverage * maintenance_margin_rate
+}
+
 /// Calculate the quantity from price, collateral and leverage Margin in sats, calculation in BTC
 pub fn calculate_quantity(opening_price: f32, margin: u64, leverage: f32) -> f32 {
     let margin_amount = bitcoin::Amount::from_sat(margin);
@@ -85,6 +120,101 @@ pub fn calculate_short_liquidation_price(
     price * leverage / (leverage - Decimal::ONE + (maintenance_margin_rate * leverage))
 }
 
+/// Calculate the liquidation price of the party going long in a linear contract.
+///
+/// Unlike for inverse contracts, the price distance to the liquidation price is the same for
+/// both directions.
+pub fn calculate_linear_long_liquidation_price(
+    leverage: Decimal,
+    price: Decimal,
+    maintenance_margin_rate: Decimal,
+) -> Decimal {
+    price * (leverage - Decimal::ONE + (maintenance_margin_rate * leverage)) / leverage
+}
+
+pub fn calculate_linear_long_bankruptcy_price(leverage: Decimal, price: Decimal) -> Decimal {
+    calculate_linear_long_liquidation_price(leverage, price, Decimal::ZERO)
+}
+
+/// Calculate the liquidation price of the party going short in a linear contract.
+pub fn calculate_linear_short_liquidation_price(
+    leverage: Decimal,
+    price: Decimal,
+    maintenance_margin_rate: Decimal,
+) -> Decimal {
+    price * (leverage + Decimal::ONE - (maintenance_margin_rate * leverage)) / leverage
+}
+
+pub fn calculate_linear_short_bankruptcy_price(leverage: Decimal, price: Decimal) -> Decimal {
+    calculate_linear_short_liquidation_price(leverage, price, Decimal::ZERO)
+}
+
+/// Calculate the liquidation price of a party in a contract of the given symbol, depending on its
+/// [`ContractType`].
+///
+/// The liquidation price of the party going short is capped at the [`max_price`] of the symbol.
+pub fn calculate_liquidation_price(
+    symbol: ContractSymbol,
+    direction: Direction,
+    leverage: Decimal,
+    price: Decimal,
+    maintenance_margin_rate: Decimal,
+) -> Decimal {
+    let liquidation_price = match (symbol.contract_type(), direction) {
+        (ContractType::Inverse, Direction::Long) => {
+            calculate_long_liquidation_price(leverage, price, maintenance_margin_rate)
+        }
+        (ContractType::Inverse, Direction::Short) => {
+            calculate_short_liquidation_price(leverage, price, maintenance_margin_rate)
+        }
+        (ContractType::Linear, Direction::Long) => {
+            calculate_linear_long_liquidation_price(leverage, price, maintenance_margin_rate)
+        }
+        (ContractType::Linear, Direction::Short) => {
+            calculate_linear_short_liquidation_price(leverage, price, maintenance_margin_rate)
+        }
+    };
+
+    liquidation_price.min(Decimal::from(max_price(symbol)))
+}
+
+/// Calculate the bankruptcy price of a party in a contract of the given symbol, see
+/// [`calculate_liquidation_price`].
+pub fn calculate_bankruptcy_price(
+    symbol: ContractSymbol,
+    direction: Direction,
+    leverage: Decimal,
+    price: Decimal,
+) -> Decimal {
+    calculate_liquidation_price(symbol, direction, leverage, price, Decimal::ZERO)
+}
+
+/// Compute the PnL of a contract of the given symbol at a particular `closing_price`, with
+/// [`calculate_pnl`] or [`calculate_linear_pnl`] depending on its [`ContractType`].
+pub fn calculate_pnl_for_symbol(
+    symbol: ContractSymbol,
+    opening_price: Decimal,
+    closing_price: Decimal,
+    quantity: f32,
+    direction: Direction,
+    initial_margin_long: u64,
+    initial_margin_short: u64,
+) -> Result<i64> {
+    let calculate_pnl = match symbol.contract_type() {
+        ContractType::Inverse => calculate_pnl,
+        ContractType::Linear => calculate_linear_pnl,
+    };
+
+    calculate_pnl(
+        opening_price,
+        closing_price,
+        quantity,
+        direction,
+        initial_margin_long,
+        initial_margin_short,
+    )
+}
+
 /// Compute the payout for the given CFD parameters at a particular `closing_price`.
 ///
 /// The `opening_price` of the position is the weighted opening price per quantity.
@@ -115,6 +245,55 @@ pub fn calculate_pnl(
         uncapped_pnl.round_dp_with_strategy(0, rust_decimal::RoundingStrategy::MidpointTowardZero)
     };
 
+    cap_pnl(
+        uncapped_pnl_long,
+        direction,
+        initial_margin_long,
+        initial_margin_short,
+    )
+}
+
+/// Compute the payout of a linear contract at a particular `closing_price`, see [`calculate_pnl`].
+///
+/// The PnL in USD is converted to sats at the `opening_price`, hence it is proportional to the
+/// price change.
+pub fn calculate_linear_pnl(
+    opening_price: Decimal,
+    closing_price: Decimal,
+    quantity: f32,
+    direction: Direction,
+    initial_margin_long: u64,
+    initial_margin_short: u64,
+) -> Result<i64> {
+    let uncapped_pnl_long = {
+        let quantity = Decimal::try_from(quantity).expect("quantity to fit into decimal");
+
+        let uncapped_pnl = match opening_price != Decimal::ZERO {
+            true => quantity * (closing_price - opening_price) / (opening_price * opening_price),
+            false => dec!(0.0),
+        };
+
+        let uncapped_pnl = uncapped_pnl * dec!(100_000_000);
+        // we need to round to zero or else we might lose some sats somewhere
+        uncapped_pnl.round_dp_with_strategy(0, rust_decimal::RoundingStrategy::MidpointTowardZero)
+    };
+
+    cap_pnl(
+        uncapped_pnl_long,
+        direction,
+        initial_margin_long,
+        initial_margin_short,
+    )
+}
+
+/// Cap the PnL of the party going long, in sats, by the margin of both parties and return it from
+/// the perspective of `direction`.
+fn cap_pnl(
+    uncapped_pnl_long: Decimal,
+    direction: Direction,
+    initial_margin_long: u64,
+    initial_margin_short: u64,
+) -> Result<i64> {
     let short_margin = Decimal::from_u64(initial_margin_short).context("be able to parse u64")?;
     let long_margin = Decimal::from_u64(initial_margin_long).context("to be abble to parse u64")?;
 
@@ -145,6 +324,26 @@ pub fn calculate_pnl(
 mod tests {
     use super::*;
 
+    #[test]
+    fn margin_ratio_grows_as_margin_shrinks() {
+        let quantity = dec!(1_000);
+        let open_price = dec!(50_000);
+        let maintenance_margin_rate = dec!(0.1);
+
+        let margin = calculate_margin(open_price, 1_000.0, 2.0);
+        let leverage = calculate_leverage(quantity, margin, open_price);
+        assert_eq!(
+            calculate_margin_ratio(leverage, maintenance_margin_rate),
+            dec!(0.2)
+        );
+
+        let leverage = calculate_leverage(quantity, margin / 2, open_price);
+        assert_eq!(
+            calculate_margin_ratio(leverage, maintenance_margin_rate),
+            dec!(0.4)
+        );
+    }
+
     #[test]
     fn given_position_when_price_same_then_zero_pnl() {
         let opening_price = Decimal::from(20000);
@@ -537,4 +736,125 @@ mod tests {
         assert_eq!(dec!(50000), liquidation_price);
         assert_ne!(liquidation_price, bankruptcy_price);
     }
+
+    #[test]
+    fn given_linear_long_position_when_price_rises_then_pnl_is_proportional() {
+        let opening_price = dec!(20_000);
+        let quantity = 100.0;
+        let long_margin = calculate_margin(opening_price, quantity, 2.0);
+        let short_margin = calculate_margin(opening_price, quantity, 1.0);
+
+        let pnl = |closing_price| {
+            calculate_linear_pnl(
+                opening_price,
+                closing_price,
+                quantity,
+                Direction::Long,
+                long_margin.to_sat(),
+                short_margin.to_sat(),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(pnl(dec!(22_000)), 50_000);
+        assert_eq!(pnl(dec!(24_000)), 100_000);
+        assert_eq!(pnl(dec!(18_000)), -50_000);
+    }
+
+    #[test]
+    fn given_linear_position_when_price_reaches_bankruptcy_price_then_margin_is_lost() {
+        let opening_price = dec!(20_000);
+        let quantity = 100.0;
+        let leverage = 2.0;
+        let margin = calculate_margin(opening_price, quantity, leverage);
+
+        let long_bankruptcy_price =
+            calculate_linear_long_bankruptcy_price(Decimal::TWO, opening_price);
+        let short_bankruptcy_price =
+            calculate_linear_short_bankruptcy_price(Decimal::TWO, opening_price);
+
+        let pnl_long = calculate_linear_pnl(
+            opening_price,
+            long_bankruptcy_price,
+            quantity,
+            Direction::Long,
+            margin.to_sat(),
+            margin.to_sat(),
+        )
+        .unwrap();
+        let pnl_short = calculate_linear_pnl(
+            opening_price,
+            short_bankruptcy_price,
+            quantity,
+            Direction::Short,
+            margin.to_sat(),
+            margin.to_sat(),
+        )
+        .unwrap();
+
+        assert_eq!(dec!(10_000), long_bankruptcy_price);
+        assert_eq!(dec!(30_000), short_bankruptcy_price);
+        assert_eq!(pnl_long, -(margin.to_sat() as i64));
+        assert_eq!(pnl_short, -(margin.to_sat() as i64));
+    }
+
+    #[test]
+    pub fn test_calculate_linear_liquidation_prices_with_maintenance_margin_rate() {
+        let leverage = dec!(2);
+        let price = dec!(30_000);
+        let maintenance_margin_rate = dec!(0.1);
+
+        let long_liquidation_price =
+            calculate_linear_long_liquidation_price(leverage, price, maintenance_margin_rate);
+        let short_liquidation_price =
+            calculate_linear_short_liquidation_price(leverage, price, maintenance_margin_rate);
+
+        assert_eq!(dec!(18_000), long_liquidation_price);
+        assert_eq!(dec!(42_000), short_liquidation_price);
+    }
+
+    #[test]
+    fn liquidation_price_depends_on_the_contract_type_of_the_symbol() {
+        let leverage = dec!(2);
+        let price = dec!(3_000);
+        let maintenance_margin_rate = dec!(0.1);
+
+        let liquidation_price = |symbol, direction| {
+            calculate_liquidation_price(symbol, direction, leverage, price, maintenance_margin_rate)
+        };
+
+        assert_eq!(
+            liquidation_price(ContractSymbol::BtcUsd, Direction::Short),
+            dec!(5_000)
+        );
+        assert_eq!(
+            liquidation_price(ContractSymbol::EthUsd, Direction::Long),
+            dec!(1_800)
+        );
+        assert_eq!(
+            liquidation_price(ContractSymbol::EthUsd, Direction::Short),
+            dec!(4_200)
+        );
+
+        // A short position without leverage would only be liquidated at an infinite price.
+        assert_eq!(
+            calculate_bankruptcy_price(
+                ContractSymbol::EthUsd,
+                Direction::Short,
+                Decimal::ONE,
+                dec!(100_000)
+            ),
+            Decimal::from(ETHUSD_MAX_PRICE)
+        );
+    }
+
+    #[test]
+    fn max_price_is_the_highest_price_the_oracle_can_attest_to() {
+        for symbol in ContractSymbol::ALL {
+            assert_eq!(
+                max_price(symbol),
+                2u64.pow(oracle_digits(symbol) as u32) - 1
+            );
+        }
+    }
 }
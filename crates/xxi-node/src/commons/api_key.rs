@@ -0,0 +1,187 @@
+use crate::commons::AUTH_SIGN_MESSAGE;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::hashes::hmac::Hmac;
+use bitcoin::hashes::hmac::HmacEngine;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::HashEngine;
+use bitcoin::secp256k1::PublicKey;
+use serde::Deserialize;
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// The header carrying the id of the API key a request is signed with.
+pub const API_KEY_HEADER: &str = "x-10101-api-key";
+/// The header carrying the unix timestamp (in seconds) at which a request was signed.
+pub const API_TIMESTAMP_HEADER: &str = "x-10101-timestamp";
+/// The header carrying a random value which is unique per request, so that a signed request can
+/// only be used once, see [`generate_api_nonce`].
+pub const API_NONCE_HEADER: &str = "x-10101-nonce";
+/// The header carrying the hex encoded HMAC-SHA256 of the request, see [`api_request_message`].
+pub const API_SIGNATURE_HEADER: &str = "x-10101-signature";
+
+/// What a client authenticated with an API key is allowed to do.
+///
+/// Withdrawing funds is never permitted with an API key, only the node key of the trader can do
+/// that.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct ApiKeyPermissions {
+    /// Read the trader's orders and positions.
+    pub read: bool,
+    /// Place and delete orders on behalf of the trader.
+    pub trade: bool,
+}
+
+/// Request to create an API key for the trader, signed with the trader's node key.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateApiKeyParams {
+    pub trader_pubkey: PublicKey,
+    /// A name for the key, to tell keys apart.
+    pub label: Option<String>,
+    pub permissions: ApiKeyPermissions,
+    /// When the request was created. Requests older than a few minutes are rejected, so that a
+    /// leaked request cannot be replayed to create further keys.
+    #[serde(with = "time::serde::timestamp")]
+    pub timestamp: OffsetDateTime,
+}
+
+/// Request to revoke one of the trader's API keys, signed with the trader's node key.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RevokeApiKeyParams {
+    pub trader_pubkey: PublicKey,
+    pub key_id: Uuid,
+    /// When the request was created, see [`CreateApiKeyParams::timestamp`].
+    #[serde(with = "time::serde::timestamp")]
+    pub timestamp: OffsetDateTime,
+}
+
+/// A newly created API key.
+///
+/// The secret is only ever returned once, on creation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedApiKey {
+    pub key_id: Uuid,
+    pub secret: String,
+    pub permissions: ApiKeyPermissions,
+}
+
+/// A new random nonce for a request authenticated with an API key.
+///
+/// The coordinator rejects a nonce which was already used with the same API key.
+pub fn generate_api_nonce() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// The message which is signed to authenticate a REST request with an API key.
+///
+/// The path includes the API version prefix, e.g. `/api/v2/trader/orders`, but not the query.
+pub fn api_request_message(
+    timestamp: i64,
+    nonce: &str,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Vec<u8> {
+    let mut message =
+        format!("{timestamp}\n{nonce}\n{}\n{path}\n", method.to_uppercase()).into_bytes();
+    message.extend_from_slice(body);
+
+    message
+}
+
+/// The message which is signed to authenticate an orderbook websocket connection with an API key.
+pub fn api_key_auth_message(timestamp: i64, nonce: &str) -> Vec<u8> {
+    let mut message = format!("{timestamp}\n{nonce}\n").into_bytes();
+    message.extend_from_slice(AUTH_SIGN_MESSAGE);
+
+    message
+}
+
+/// Sign the message with the secret of an API key, returning the hex encoded HMAC-SHA256.
+pub fn sign_with_api_secret(secret: &str, message: &[u8]) -> String {
+    hex::encode(hmac(secret, message))
+}
+
+/// Verify that the hex encoded `signature` is the HMAC-SHA256 of the message under the secret of
+/// an API key.
+pub fn verify_api_signature(secret: &str, message: &[u8], signature: &str) -> Result<()> {
+    let signature = hex::decode(signature).context("Signature is not hex encoded")?;
+    let expected = hmac(secret, message);
+
+    // Compare in constant time, to not leak how much of the signature is correct.
+    ensure!(
+        signature.len() == expected.len()
+            && signature
+                .iter()
+                .zip(expected.iter())
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0,
+        "Invalid signature"
+    );
+
+    Ok(())
+}
+
+fn hmac(secret: &str, message: &[u8]) -> [u8; 32] {
+    let mut engine = HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+    engine.input(message);
+
+    Hmac::<sha256::Hash>::from_engine(engine).to_byte_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_request_verifies() {
+        let secret = "b2f1c6c0d0e3";
+        let message = api_request_message(
+            1718841600,
+            "4f1c2a9e",
+            "post",
+            "/api/v2/trader/orders",
+            b"{}",
+        );
+
+        let signature = sign_with_api_secret(secret, &message);
+
+        assert!(verify_api_signature(secret, &message, &signature).is_ok());
+    }
+
+    #[test]
+    fn tampered_request_does_not_verify() {
+        let secret = "b2f1c6c0d0e3";
+        let message = api_request_message(
+            1718841600,
+            "4f1c2a9e",
+            "POST",
+            "/api/v2/trader/orders",
+            b"{}",
+        );
+        let signature = sign_with_api_secret(secret, &message);
+
+        let tampered = api_request_message(
+            1718841601,
+            "4f1c2a9e",
+            "POST",
+            "/api/v2/trader/orders",
+            b"{}",
+        );
+        let other_nonce = api_request_message(
+            1718841600,
+            "4f1c2a9f",
+            "POST",
+            "/api/v2/trader/orders",
+            b"{}",
+        );
+
+        assert!(verify_api_signature(secret, &tampered, &signature).is_err());
+        assert!(verify_api_signature(secret, &other_nonce, &signature).is_err());
+        assert!(verify_api_signature("other secret", &message, &signature).is_err());
+        assert!(verify_api_signature(secret, &message, "not hex").is_err());
+    }
+}
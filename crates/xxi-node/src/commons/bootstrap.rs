@@ -0,0 +1,28 @@
+use crate::commons::FundingFeeEvent;
+use crate::commons::FundingRate;
+use crate::commons::Order;
+use crate::commons::Poll;
+use crate::commons::ReserveInterest;
+use crate::commons::TenTenOneConfig;
+use crate::commons::User;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Everything the app needs from the coordinator on startup, in a single response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bootstrap {
+    /// The configuration, including the trader's referral status.
+    ///
+    /// Does not include a session token, the trader already has one to request the bootstrap.
+    pub config: TenTenOneConfig,
+    /// The open limit orders of all markets, from which the prices are derived.
+    pub orders: Vec<Order>,
+    /// The funding fee events of the trader's open positions.
+    pub funding_fee_events: Vec<FundingFeeEvent>,
+    pub next_funding_rate: Option<FundingRate>,
+    /// [`None`] if the trader has not registered yet.
+    pub user: Option<User>,
+    pub reserve_interest: ReserveInterest,
+    /// The polls the trader has not answered yet.
+    pub polls: Vec<Poll>,
+}
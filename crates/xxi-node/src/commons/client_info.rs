@@ -0,0 +1,34 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+
+/// The client through which a trader talks to the coordinator, so that the order flow can be
+/// broken down by client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientInfo {
+    pub kind: ClientKind,
+    /// The version of the client, e.g. `1.9.2`.
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClientKind {
+    /// The mobile app.
+    App,
+    /// The self-hosted webapp.
+    Webapp,
+    /// A trading bot using the orderbook client.
+    Sdk,
+}
+
+impl fmt::Display for ClientKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ClientKind::App => "app",
+            ClientKind::Webapp => "webapp",
+            ClientKind::Sdk => "sdk",
+        };
+
+        f.write_str(kind)
+    }
+}
@@ -0,0 +1,191 @@
+//! The dust policy applied when constructing DLC channels and contracts.
+//!
+//! Outputs below the dust limit make a transaction non-standard, so `rust-dlc` drops them from the
+//! transactions of a DLC channel and their value goes to the miners. Instead of relying on that,
+//! the coordinator and the app apply the following rules, so that both sides agree on the outputs:
+//!
+//! - A collateral reserve below the dust limit is rounded, see [`round_collateral_reserve`].
+//! - A collateral reserve of an existing DLC channel which fell below the dust limit, e.g. after a
+//!   funding fee or a withdrawal, is folded into the margin of the same party, see
+//!   [`fold_dust_reserve`].
+//! - A payout below the dust limit is given to the counterparty, see [`round_dust_payout`].
+//! - A contract in which a collateral reserve is still below the dust limit is refused, because
+//!   the payout of a liquidated party would be dust. See [`ensure_not_dust`].
+
+use bitcoin::Amount;
+use thiserror::Error;
+
+/// The smallest output `rust-dlc` keeps in the transactions of a DLC channel, e.g. in the CETs.
+pub const DUST_LIMIT: Amount = Amount::from_sat(1_000);
+
+/// Whether an output of this `amount` would be dust.
+///
+/// Empty outputs are not dust, because they are left out of the transaction.
+pub fn is_dust(amount: Amount) -> bool {
+    amount > Amount::ZERO && amount < DUST_LIMIT
+}
+
+/// How to round a collateral reserve which would be dust.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReserveRounding {
+    /// Round the reserve down to zero.
+    ///
+    /// Used for reserves which the trader funds with their own coins, so that the trader is never
+    /// charged more than they asked for. The coins stay in their wallet.
+    Down,
+    /// Round the reserve up to the dust limit.
+    ///
+    /// Used for reserves which the coordinator funds. This includes the reserve of a trader who
+    /// has paid the coordinator with an external funding, so that the trader does not lose the
+    /// left over coins.
+    Up,
+}
+
+/// Round a collateral reserve which would be dust according to `rounding`.
+///
+/// Reserves of zero or of at least the dust limit are left untouched.
+pub fn round_collateral_reserve(reserve: Amount, rounding: ReserveRounding) -> Amount {
+    if !is_dust(reserve) {
+        return reserve;
+    }
+
+    match rounding {
+        ReserveRounding::Down => Amount::ZERO,
+        ReserveRounding::Up => DUST_LIMIT,
+    }
+}
+
+/// Fold a collateral reserve which would be dust into the `margin` of the same party.
+///
+/// Returns the new `(margin, reserve)`. A reserve computed from the balance of an existing DLC
+/// channel can't be rounded with the coins of a wallet, so the coins are wagered instead. The party
+/// keeps them unless it is liquidated, in which case they go to the counterparty like any other
+/// dust payout.
+pub fn fold_dust_reserve(margin: Amount, reserve: Amount) -> (Amount, Amount) {
+    if !is_dust(reserve) {
+        return (margin, reserve);
+    }
+
+    (margin + reserve, Amount::ZERO)
+}
+
+/// Round the `payout` of a party in a CET, such that neither party gets a dust output.
+///
+/// A payout below the dust limit goes to the counterparty, and so does the rest of the
+/// `total_collateral` if it would be dust. Parties applying this rule to the payouts of the same
+/// contract arrive at the same CETs, regardless of the perspective.
+pub fn round_dust_payout(payout: Amount, total_collateral: Amount) -> Amount {
+    if is_dust(payout) {
+        return Amount::ZERO;
+    }
+
+    let counterparty_payout = total_collateral.checked_sub(payout).unwrap_or(Amount::ZERO);
+    if is_dust(counterparty_payout) {
+        return total_collateral;
+    }
+
+    payout
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("The {output} of {} sats is below the dust limit of {} sats", .amount.to_sat(), DUST_LIMIT.to_sat())]
+pub struct DustOutputError {
+    /// The output which would be dust, e.g. `trader collateral reserve`.
+    pub output: &'static str,
+    pub amount: Amount,
+}
+
+/// Refuse an `output` of `amount` which would be dust.
+pub fn ensure_not_dust(output: &'static str, amount: Amount) -> Result<(), DustOutputError> {
+    if is_dust(amount) {
+        return Err(DustOutputError { output, amount });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_non_empty_outputs_below_the_dust_limit_are_dust() {
+        assert!(!is_dust(Amount::ZERO));
+        assert!(is_dust(Amount::from_sat(1)));
+        assert!(is_dust(Amount::from_sat(999)));
+        assert!(!is_dust(DUST_LIMIT));
+    }
+
+    #[test]
+    fn dust_reserves_are_rounded() {
+        let dust = Amount::from_sat(500);
+
+        assert_eq!(
+            round_collateral_reserve(dust, ReserveRounding::Down),
+            Amount::ZERO
+        );
+        assert_eq!(
+            round_collateral_reserve(dust, ReserveRounding::Up),
+            DUST_LIMIT
+        );
+
+        let reserve = Amount::from_sat(5_000);
+        assert_eq!(
+            round_collateral_reserve(reserve, ReserveRounding::Down),
+            reserve
+        );
+        assert_eq!(
+            round_collateral_reserve(Amount::ZERO, ReserveRounding::Up),
+            Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn dust_reserves_are_folded_into_the_margin() {
+        let margin = Amount::from_sat(100_000);
+
+        assert_eq!(
+            fold_dust_reserve(margin, Amount::from_sat(500)),
+            (Amount::from_sat(100_500), Amount::ZERO)
+        );
+        assert_eq!(fold_dust_reserve(margin, DUST_LIMIT), (margin, DUST_LIMIT));
+        assert_eq!(
+            fold_dust_reserve(margin, Amount::ZERO),
+            (margin, Amount::ZERO)
+        );
+    }
+
+    #[test]
+    fn dust_payouts_go_to_the_counterparty() {
+        let total_collateral = Amount::from_sat(100_000);
+
+        assert_eq!(
+            round_dust_payout(Amount::from_sat(999), total_collateral),
+            Amount::ZERO
+        );
+        assert_eq!(
+            round_dust_payout(Amount::from_sat(99_001), total_collateral),
+            total_collateral
+        );
+        assert_eq!(
+            round_dust_payout(Amount::from_sat(50_000), total_collateral),
+            Amount::from_sat(50_000)
+        );
+        assert_eq!(
+            round_dust_payout(Amount::ZERO, total_collateral),
+            Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn dust_outputs_are_refused() {
+        let error =
+            ensure_not_dust("trader collateral reserve", Amount::from_sat(546)).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The trader collateral reserve of 546 sats is below the dust limit of 1000 sats"
+        );
+        assert!(ensure_not_dust("trader collateral reserve", Amount::ZERO).is_ok());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::commons::ContractSymbol;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// How an expired position is settled on-chain using the price attested by the oracle.
+///
+/// The position is settled by the contract execution transaction (CET) whose price interval
+/// contains the attested price. As the payout curve is discretized into these intervals, the
+/// payout of the CET can deviate from the payout at exactly the attested price, most noticeably if
+/// the attested price lands on the boundary of an interval.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExpirySettlement {
+    pub contract_symbol: ContractSymbol,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub attested_price: Decimal,
+    /// The lowest price of the interval of the CET.
+    pub interval_start: u64,
+    /// The highest price of the interval of the CET.
+    pub interval_end: u64,
+    /// The payout of the trader in the CET, including their collateral reserve.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub trader_payout: Amount,
+    /// The payout of the trader at exactly the attested price, including their collateral
+    /// reserve.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub exact_trader_payout: Amount,
+}
+
+impl ExpirySettlement {
+    /// How much more the trader is paid by the CET than at exactly the attested price.
+    pub fn deviation(&self) -> SignedAmount {
+        self.trader_payout.to_signed().expect("to fit")
+            - self.exact_trader_payout.to_signed().expect("to fit")
+    }
+
+    /// Whether the attested price is the lowest or highest price of the interval of the CET.
+    pub fn is_on_interval_boundary(&self) -> bool {
+        self.attested_price == Decimal::from(self.interval_start)
+            || self.attested_price == Decimal::from(self.interval_end)
+    }
+}
@@ -0,0 +1,132 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A scope of the coordinator's kill switch.
+///
+/// The operator engages one or more modes in an emergency. Each mode disables part of the
+/// coordinator, whereas [`KillSwitchMode::FullHalt`] disables everything the other modes do.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum KillSwitchMode {
+    /// No new orders are accepted.
+    ReadOnly,
+    /// No new DLC channels are opened.
+    NoNewChannels,
+    /// DLC channels are not closed collaboratively, i.e. traders cannot withdraw their funds.
+    NoWithdrawals,
+    /// No orders, no trades, no rollovers, no new DLC channels and no withdrawals.
+    FullHalt,
+}
+
+/// The modes of the kill switch which are currently engaged.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct KillSwitchStatus {
+    pub modes: BTreeSet<KillSwitchMode>,
+    /// Why the kill switch was engaged, to be shown to the traders.
+    pub reason: Option<String>,
+}
+
+impl KillSwitchStatus {
+    pub fn is_engaged(&self) -> bool {
+        !self.modes.is_empty()
+    }
+
+    pub fn allows_new_orders(&self) -> bool {
+        !self.disables(KillSwitchMode::ReadOnly)
+    }
+
+    pub fn allows_new_channels(&self) -> bool {
+        !self.disables(KillSwitchMode::NoNewChannels)
+    }
+
+    pub fn allows_withdrawals(&self) -> bool {
+        !self.disables(KillSwitchMode::NoWithdrawals)
+    }
+
+    /// Whether matches are executed and positions are rolled over.
+    pub fn allows_trading(&self) -> bool {
+        !self.modes.contains(&KillSwitchMode::FullHalt)
+    }
+
+    fn disables(&self, mode: KillSwitchMode) -> bool {
+        self.modes.contains(&mode) || self.modes.contains(&KillSwitchMode::FullHalt)
+    }
+}
+
+impl fmt::Display for KillSwitchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            KillSwitchMode::ReadOnly => "read_only",
+            KillSwitchMode::NoNewChannels => "no_new_channels",
+            KillSwitchMode::NoWithdrawals => "no_withdrawals",
+            KillSwitchMode::FullHalt => "full_halt",
+        };
+
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for KillSwitchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modes = self
+            .modes
+            .iter()
+            .map(|mode| mode.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match &self.reason {
+            Some(reason) => write!(f, "{modes} ({reason})"),
+            None => f.write_str(&modes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_halt_disables_everything() {
+        let status = KillSwitchStatus {
+            modes: BTreeSet::from([KillSwitchMode::FullHalt]),
+            reason: None,
+        };
+
+        assert!(!status.allows_new_orders());
+        assert!(!status.allows_new_channels());
+        assert!(!status.allows_withdrawals());
+        assert!(!status.allows_trading());
+    }
+
+    #[test]
+    fn modes_only_disable_their_scope() {
+        let status = KillSwitchStatus {
+            modes: BTreeSet::from([KillSwitchMode::NoWithdrawals]),
+            reason: None,
+        };
+
+        assert!(status.allows_new_orders());
+        assert!(status.allows_new_channels());
+        assert!(!status.allows_withdrawals());
+        assert!(status.allows_trading());
+        assert!(!KillSwitchStatus::default().is_engaged());
+    }
+
+    #[test]
+    fn modes_are_serialized_in_snake_case() {
+        let status = KillSwitchStatus {
+            modes: BTreeSet::from([KillSwitchMode::ReadOnly, KillSwitchMode::NoNewChannels]),
+            reason: Some("Upgrade".to_string()),
+        };
+
+        let json = serde_json::to_string(&status).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"modes":["read_only","no_new_channels"],"reason":"Upgrade"}"#
+        );
+    }
+}
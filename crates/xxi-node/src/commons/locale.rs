@@ -0,0 +1,173 @@
+use anyhow::bail;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// The languages in which the coordinator can address a user.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "de")]
+    German,
+}
+
+impl Locale {
+    /// The ISO 639-1 code of the language.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::German => "de",
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = anyhow::Error;
+
+    /// Parse a locale from its language code, ignoring any region, e.g. `de_AT` or `en-US`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s
+            .split(['_', '-'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let locale = match language.as_str() {
+            "en" => Locale::English,
+            "de" => Locale::German,
+            _ => bail!("Unsupported locale: {s}"),
+        };
+
+        Ok(locale)
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.code().fmt(f)
+    }
+}
+
+/// Identifies an error reported to the user, independent of the language of its message.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ErrorCode {
+    InvalidOrder,
+    NoMatchFound,
+    TradeFailed,
+    RolloverFailed,
+}
+
+impl ErrorCode {
+    /// The message describing the error in the given locale.
+    pub fn message(&self, locale: Locale) -> &'static str {
+        match (self, locale) {
+            (ErrorCode::InvalidOrder, Locale::English) => "Your order was rejected.",
+            (ErrorCode::InvalidOrder, Locale::German) => "Deine Order wurde abgelehnt.",
+            (ErrorCode::NoMatchFound, Locale::English) => {
+                "There is currently not enough liquidity to fill your order."
+            }
+            (ErrorCode::NoMatchFound, Locale::German) => {
+                "Derzeit gibt es nicht genügend Liquidität, um deine Order auszuführen."
+            }
+            (ErrorCode::TradeFailed, Locale::English) => "Your trade could not be executed.",
+            (ErrorCode::TradeFailed, Locale::German) => {
+                "Dein Trade konnte nicht ausgeführt werden."
+            }
+            (ErrorCode::RolloverFailed, Locale::English) => {
+                "Your position could not be rolled over."
+            }
+            (ErrorCode::RolloverFailed, Locale::German) => {
+                "Deine Position konnte nicht verlängert werden."
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            ErrorCode::InvalidOrder => "InvalidOrder",
+            ErrorCode::NoMatchFound => "NoMatchFound",
+            ErrorCode::TradeFailed => "TradeFailed",
+            ErrorCode::RolloverFailed => "RolloverFailed",
+        };
+
+        code.fmt(f)
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = match s {
+            "InvalidOrder" => ErrorCode::InvalidOrder,
+            "NoMatchFound" => ErrorCode::NoMatchFound,
+            "TradeFailed" => ErrorCode::TradeFailed,
+            "RolloverFailed" => ErrorCode::RolloverFailed,
+            _ => bail!("Unknown error code: {s}"),
+        };
+
+        Ok(code)
+    }
+}
+
+/// An error reported to the user, alongside its message in the user's locale.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocalizedError {
+    pub code: ErrorCode,
+    pub locale: Locale,
+    pub message: String,
+}
+
+impl LocalizedError {
+    pub fn new(code: ErrorCode, locale: Locale) -> Self {
+        Self {
+            code,
+            locale,
+            message: code.message(locale).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_locale_ignoring_region() {
+        assert_eq!(Locale::from_str("de_AT").unwrap(), Locale::German);
+        assert_eq!(Locale::from_str("en-US").unwrap(), Locale::English);
+        assert_eq!(Locale::from_str("DE").unwrap(), Locale::German);
+    }
+
+    #[test]
+    fn unsupported_locale_is_rejected() {
+        assert!(Locale::from_str("fr_FR").is_err());
+        assert!(Locale::from_str("").is_err());
+    }
+
+    #[test]
+    fn locale_serde_roundtrip() {
+        let serialized = serde_json::to_string(&Locale::German).unwrap();
+        assert_eq!(serialized, "\"de\"");
+
+        let deserialized: Locale = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, Locale::German);
+    }
+
+    #[test]
+    fn error_code_string_roundtrip() {
+        for code in [
+            ErrorCode::InvalidOrder,
+            ErrorCode::NoMatchFound,
+            ErrorCode::TradeFailed,
+            ErrorCode::RolloverFailed,
+        ] {
+            assert_eq!(ErrorCode::from_str(&code.to_string()).unwrap(), code);
+        }
+    }
+}
@@ -1,10 +1,18 @@
 use crate::commons::order::Order;
 use crate::commons::signature::Signature;
+use crate::commons::ClientInfo;
+use crate::commons::ContractSymbol;
+use crate::commons::ErrorCode;
+use crate::commons::ExpirySettlement;
+use crate::commons::FilledWith;
+use crate::commons::FundingFeeEvent;
 use crate::commons::FundingRate;
+use crate::commons::KillSwitchStatus;
 use crate::commons::LiquidityOption;
+use crate::commons::LocalizedError;
 use crate::commons::NewLimitOrder;
 use crate::commons::ReferralStatus;
-use crate::FundingFeeEvent;
+use crate::commons::SessionToken;
 use anyhow::Result;
 use bitcoin::address::NetworkUnchecked;
 use bitcoin::Address;
@@ -42,6 +50,9 @@ pub enum Message {
     TradeError {
         order_id: Uuid,
         error: TradingError,
+        /// The error in the user's locale. Only set by the coordinator when delivering the message.
+        #[serde(default)]
+        localized: Option<LocalizedError>,
     },
     LnPaymentReceived {
         r_hash: String,
@@ -50,10 +61,22 @@ pub enum Message {
     },
     RolloverError {
         error: TradingError,
+        /// The error in the user's locale. Only set by the coordinator when delivering the message.
+        #[serde(default)]
+        localized: Option<LocalizedError>,
     },
     FundingFeeEvent(FundingFeeEvent),
     AllFundingFeeEvents(Vec<FundingFeeEvent>),
     NextFundingRate(FundingRate),
+    /// Informs the trader about a match for an order which the coordinator placed on their behalf.
+    AsyncMatch {
+        order: Order,
+        filled_with: FilledWith,
+    },
+    /// The modes of the coordinator's kill switch changed.
+    KillSwitch(KillSwitchStatus),
+    /// An expired position of the trader is being settled on-chain using the oracle attestation.
+    ExpirySettlement(ExpirySettlement),
 }
 
 #[derive(Serialize, Deserialize, Clone, Error, Debug, PartialEq)]
@@ -66,6 +89,17 @@ pub enum TradingError {
     Other(String),
 }
 
+impl TradingError {
+    /// The [`ErrorCode`] of a trading error, which failed while executing a trade.
+    pub fn trade_error_code(&self) -> ErrorCode {
+        match self {
+            TradingError::InvalidOrder(_) => ErrorCode::InvalidOrder,
+            TradingError::NoMatchFound(_) => ErrorCode::NoMatchFound,
+            TradingError::Other(_) => ErrorCode::TradeFailed,
+        }
+    }
+}
+
 impl From<anyhow::Error> for TradingError {
     fn from(value: anyhow::Error) -> Self {
         TradingError::Other(format!("{value:#}"))
@@ -81,6 +115,26 @@ pub struct TenTenOneConfig {
     pub order_matching_fee_rate: f32,
     pub referral_status: ReferralStatus,
     pub max_leverage: u8,
+    /// The smallest collateral the trader has to bring into a new DLC channel, given the current
+    /// on-chain fees.
+    #[serde(default)]
+    pub min_channel_collateral_sats: u64,
+    /// A token to authenticate the trader towards the REST API, issued when authenticating with
+    /// the node key.
+    #[serde(default)]
+    pub session_token: Option<SessionToken>,
+    #[serde(default)]
+    pub kill_switch: KillSwitchStatus,
+    /// The number of expiries orders can be placed for, starting with the next expiry, see
+    /// [`crate::commons::calculate_next_expiries`].
+    #[serde(default = "only_next_expiry")]
+    pub listed_expiries: usize,
+}
+
+/// Coordinators which do not list their expiries only trade contracts expiring at the next
+/// expiry.
+fn only_next_expiry() -> usize {
+    1
 }
 
 #[derive(Serialize, Clone, Deserialize, Debug)]
@@ -90,9 +144,37 @@ pub enum OrderbookRequest {
         version: Option<String>,
         os: Option<String>,
         signature: Signature,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_info: Option<ClientInfo>,
+        /// Delete all orders of the maker if the connection drops and is not re-established
+        /// within the grace period of the coordinator.
+        #[serde(default)]
+        cancel_on_disconnect: bool,
+    },
+    /// Authenticate with an API key instead of the node key, e.g. from a trading bot.
+    ///
+    /// The signature is the HMAC of [`crate::commons::api_key_auth_message`] under the secret of
+    /// the API key.
+    AuthenticateWithApiKey {
+        key_id: Uuid,
+        timestamp: i64,
+        /// See [`crate::commons::generate_api_nonce`].
+        nonce: String,
+        signature: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_info: Option<ClientInfo>,
+        /// See [`OrderbookRequest::Authenticate`].
+        #[serde(default)]
+        cancel_on_disconnect: bool,
     },
     InsertOrder(NewLimitOrder),
     DeleteOrder(Uuid),
+    /// Only receive orderbook updates of the given markets, replacing any previous subscription.
+    ///
+    /// Without a subscription, only updates of the BTCUSD market are received, as apps which
+    /// predate the other markets can't deserialize their orders. The coordinator responds with
+    /// [`Message::AllOrders`] of the subscribed markets.
+    Subscribe(Vec<ContractSymbol>),
 }
 
 impl TryFrom<OrderbookRequest> for tungstenite::Message {
@@ -120,6 +202,9 @@ impl Display for Message {
             Message::FundingFeeEvent(_) => "FundingFeeEvent",
             Message::AllFundingFeeEvents(_) => "FundingFeeEvent",
             Message::NextFundingRate(_) => "NextFundingRate",
+            Message::AsyncMatch { .. } => "AsyncMatch",
+            Message::KillSwitch(_) => "KillSwitch",
+            Message::ExpirySettlement(_) => "ExpirySettlement",
         };
 
         f.write_str(s)
@@ -176,3 +261,86 @@ impl TryFrom<PositionMessageRequest> for tungstenite::Message {
 pub enum PositionMessageRequest {
     Authenticate { signature: Signature },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commons::Direction;
+    use crate::commons::OrderReason;
+    use crate::commons::OrderState;
+    use crate::commons::OrderType;
+    use bitcoin::secp256k1::PublicKey;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+    use std::str::FromStr;
+    use time::OffsetDateTime;
+
+    #[test]
+    fn messages_are_externally_tagged() {
+        let order_id = Uuid::from_str("67e5504410b1426f9247bb680e5fe0c8").unwrap();
+
+        let message = serde_json::to_value(Message::DeleteOrder(order_id)).unwrap();
+
+        assert_eq!(
+            message,
+            json!({ "DeleteOrder": "67e55044-10b1-426f-9247-bb680e5fe0c8" })
+        );
+    }
+
+    #[test]
+    fn orders_without_display_quantity_are_accepted() {
+        let order = Order {
+            id: Uuid::new_v4(),
+            price: dec!(50_000),
+            leverage: 2.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            trader_id: PublicKey::from_str(
+                "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007",
+            )
+            .unwrap(),
+            direction: Direction::Short,
+            quantity: dec!(1000),
+            order_type: OrderType::Limit,
+            timestamp: OffsetDateTime::UNIX_EPOCH,
+            expiry: OffsetDateTime::UNIX_EPOCH,
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: Some(dec!(300)),
+            contract_expiry: None,
+        };
+
+        let message = serde_json::to_value(Message::NewOrder(order.displayed())).unwrap();
+
+        // Older apps and makers neither send nor expect the display quantity.
+        assert!(message["NewOrder"].get("display_quantity").is_none());
+        match serde_json::from_value(message).unwrap() {
+            Message::NewOrder(parsed) => assert_eq!(parsed, order.displayed()),
+            message => panic!("Unexpected message {message:?}"),
+        }
+    }
+
+    #[test]
+    fn api_key_authentication_without_client_info_is_accepted() {
+        let request = json!({
+            "AuthenticateWithApiKey": {
+                "key_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                "timestamp": 1720612800,
+                "nonce": "9b2f3c4d5e6f708192a3b4c5d6e7f801",
+                "signature": "deadbeef",
+            }
+        });
+
+        match serde_json::from_value(request).unwrap() {
+            OrderbookRequest::AuthenticateWithApiKey {
+                client_info,
+                cancel_on_disconnect,
+                ..
+            } => {
+                assert_eq!(client_info, None);
+                assert!(!cancel_on_disconnect);
+            }
+            request => panic!("Unexpected request {request:?}"),
+        }
+    }
+}
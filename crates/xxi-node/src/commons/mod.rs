@@ -1,4 +1,5 @@
-use anyhow::bail;
+use crate::cfd;
+use anyhow::Context;
 use bitcoin::secp256k1::PublicKey;
 use rust_decimal::Decimal;
 use serde::Deserialize;
@@ -8,35 +9,65 @@ use std::str::FromStr;
 use time::OffsetDateTime;
 use time::Time;
 
+mod api_key;
 mod backup;
+mod bootstrap;
+mod client_info;
 mod collab_revert;
+mod dust;
+mod expiry_settlement;
 mod funding_fee_event;
+mod kill_switch;
 mod liquidity_option;
+mod locale;
 mod message;
+mod oracle_event_id;
 mod order;
 mod order_matching_fee;
+mod order_simulation;
+mod pagination;
 mod polls;
+mod position_history;
 mod pre_image;
 mod price;
 mod reported_error;
+mod reserve_interest;
 mod rollover;
+mod session_token;
 mod signature;
+mod support_ticket;
 mod trade;
+mod trade_simulation;
 
 pub use crate::commons::trade::*;
+pub use api_key::*;
 pub use backup::*;
+pub use bootstrap::*;
+pub use client_info::*;
 pub use collab_revert::*;
+pub use dust::*;
+pub use expiry_settlement::*;
 pub use funding_fee_event::*;
+pub use kill_switch::*;
 pub use liquidity_option::*;
+pub use locale::*;
 pub use message::*;
+pub use oracle_event_id::*;
 pub use order::*;
 pub use order_matching_fee::order_matching_fee;
+pub use order_simulation::*;
+pub use pagination::*;
 pub use polls::*;
+pub use position_history::*;
 pub use pre_image::*;
 pub use price::*;
 pub use reported_error::ReportedError;
+pub use reserve_interest::*;
 pub use rollover::*;
+pub use session_token::*;
 pub use signature::*;
+pub use support_ticket::*;
+pub use trade_simulation::*;
 
 pub const AUTH_SIGN_MESSAGE: &[u8; 19] = b"Hello it's me Mario";
 
@@ -50,6 +81,9 @@ pub struct RegisterParams {
     pub os: Option<String>,
     /// Entered referral code, i.e. this user was revered by using this referral code
     pub referral_code: Option<String>,
+    /// The locale in which the user wants to receive notifications and error messages.
+    #[serde(default)]
+    pub locale: Option<Locale>,
 }
 
 /// Registration details for enrolling into the beta program
@@ -90,6 +124,26 @@ pub struct HodlInvoiceParams {
     pub r_hash: String,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LightningWithdrawalParams {
+    pub trader_pubkey: PublicKey,
+    /// The BOLT11 invoice to pay from the trader's collateral reserve.
+    pub invoice: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReserveTopUpParams {
+    pub trader_pubkey: PublicKey,
+    pub amount_sats: u64,
+}
+
+/// Where to deposit to top up the trader's collateral reserve.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReserveTopUp {
+    pub address: String,
+    pub amount_sats: u64,
+}
+
 pub fn referral_from_pubkey(public_key: PublicKey) -> String {
     let referral_code = public_key
         .to_string()
@@ -145,14 +199,40 @@ impl ReferralStatus {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum ContractSymbol {
     BtcUsd,
+    EthUsd,
 }
 
 impl ContractSymbol {
+    /// All the symbols we know of, whether they are listed or not.
+    pub const ALL: [ContractSymbol; 2] = [ContractSymbol::BtcUsd, ContractSymbol::EthUsd];
+
     pub fn label(self) -> String {
         match self {
             ContractSymbol::BtcUsd => "btcusd".to_string(),
+            ContractSymbol::EthUsd => "ethusd".to_string(),
+        }
+    }
+
+    /// The symbol of the corresponding perpetual swap on BitMEX.
+    pub fn bitmex_symbol(self) -> &'static str {
+        match self {
+            ContractSymbol::BtcUsd => "XBTUSD",
+            ContractSymbol::EthUsd => "ETHUSD",
         }
     }
+
+    /// The type of contract traded under this symbol, which determines its payout curve.
+    pub fn contract_type(self) -> ContractType {
+        match self {
+            ContractSymbol::BtcUsd => ContractType::Inverse,
+            ContractSymbol::EthUsd => ContractType::Linear,
+        }
+    }
+
+    /// The highest price the oracle can attest to for this symbol, see [`cfd::max_price`].
+    pub fn max_price(self) -> u64 {
+        cfd::max_price(self)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -185,21 +265,20 @@ impl FromStr for ContractSymbol {
     type Err = anyhow::Error;
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
-        match value.to_lowercase().as_str() {
-            "btcusd" => Ok(ContractSymbol::BtcUsd),
-            // BitMEX representation
-            "xbtusd" => Ok(ContractSymbol::BtcUsd),
-            unknown => bail!("Unknown contract symbol {unknown}"),
-        }
+        ContractSymbol::ALL
+            .into_iter()
+            .find(|symbol| {
+                value.eq_ignore_ascii_case(&symbol.label())
+                    // BitMEX representation
+                    || value.eq_ignore_ascii_case(symbol.bitmex_symbol())
+            })
+            .with_context(|| format!("Unknown contract symbol {}", value.to_lowercase()))
     }
 }
 
 impl fmt::Display for ContractSymbol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let symbol = match self {
-            ContractSymbol::BtcUsd => "btcusd",
-        };
-        symbol.to_string().fmt(f)
+        self.label().fmt(f)
     }
 }
 
@@ -231,6 +310,14 @@ pub mod tests {
             ContractSymbol::from_str("xbtusd").unwrap(),
             ContractSymbol::BtcUsd
         );
+        assert_eq!(
+            ContractSymbol::from_str("ethusd").unwrap(),
+            ContractSymbol::EthUsd
+        );
+        assert_eq!(
+            ContractSymbol::from_str("ETHUSD").unwrap(),
+            ContractSymbol::EthUsd
+        );
         assert!(ContractSymbol::from_str("dogeusd").is_err());
     }
 
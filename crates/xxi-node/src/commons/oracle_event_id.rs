@@ -0,0 +1,188 @@
+//! The ids of the oracle events which attest the price of a contract at its expiry.
+//!
+//! The oracle announces one event per contract symbol and maturity. Both parties have to derive
+//! the same id as the oracle does, otherwise the contract is bound to an event which will never be
+//! attested and can only be refunded.
+
+use crate::commons::ContractSymbol;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use std::fmt;
+use std::str::FromStr;
+use time::OffsetDateTime;
+
+/// The formats in which the oracle builds its event ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OracleEventIdVersion {
+    /// The contract symbol followed by the maturity as a unix timestamp, e.g. `btcusd1707465600`.
+    V0,
+}
+
+impl OracleEventIdVersion {
+    /// The format used for new contracts.
+    pub const CURRENT: Self = Self::V0;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OracleEventId {
+    version: OracleEventIdVersion,
+    contract_symbol: ContractSymbol,
+    /// Only precise to the second, like the event id itself.
+    maturity: OffsetDateTime,
+}
+
+impl OracleEventId {
+    /// The id of the event attesting the price of `contract_symbol` at `maturity`.
+    pub fn new(contract_symbol: ContractSymbol, maturity: OffsetDateTime) -> Self {
+        Self {
+            version: OracleEventIdVersion::CURRENT,
+            contract_symbol,
+            maturity: maturity
+                .replace_nanosecond(0)
+                .expect("zero nanoseconds to be valid"),
+        }
+    }
+
+    pub fn version(&self) -> OracleEventIdVersion {
+        self.version
+    }
+
+    pub fn contract_symbol(&self) -> ContractSymbol {
+        self.contract_symbol
+    }
+
+    pub fn maturity(&self) -> OffsetDateTime {
+        self.maturity
+    }
+
+    /// Ensure that the event attests the price of `contract_symbol` at `maturity`.
+    pub fn ensure_matches(
+        &self,
+        contract_symbol: ContractSymbol,
+        maturity: OffsetDateTime,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            self.contract_symbol == contract_symbol,
+            "Oracle event {self} is for {}, expected {contract_symbol}",
+            self.contract_symbol
+        );
+        ensure!(
+            self.maturity.unix_timestamp() == maturity.unix_timestamp(),
+            "Oracle event {self} matures at {}, expected {maturity}",
+            self.maturity
+        );
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for OracleEventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            OracleEventIdVersion::V0 => write!(
+                f,
+                "{}{}",
+                self.contract_symbol.label(),
+                self.maturity.unix_timestamp()
+            ),
+        }
+    }
+}
+
+impl FromStr for OracleEventId {
+    type Err = anyhow::Error;
+
+    fn from_str(event_id: &str) -> Result<Self, Self::Err> {
+        parse_v0(event_id).with_context(|| format!("Invalid oracle event id {event_id:?}"))
+    }
+}
+
+fn parse_v0(event_id: &str) -> anyhow::Result<OracleEventId> {
+    let split = event_id
+        .find(|c: char| c.is_ascii_digit())
+        .context("Missing maturity")?;
+    let (symbol, timestamp) = event_id.split_at(split);
+
+    let contract_symbol = ContractSymbol::from_str(symbol)?;
+
+    // Other spellings of the symbol would not match the id of the oracle.
+    if contract_symbol.label() != symbol {
+        bail!("Unexpected spelling of contract symbol {symbol}");
+    }
+
+    if !timestamp.chars().all(|c| c.is_ascii_digit()) || timestamp.starts_with('0') {
+        bail!("Malformed maturity {timestamp}");
+    }
+
+    let maturity = OffsetDateTime::from_unix_timestamp(timestamp.parse()?)?;
+
+    Ok(OracleEventId {
+        version: OracleEventIdVersion::V0,
+        contract_symbol,
+        maturity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn event_id_round_trip() {
+        let event_id = OracleEventId::new(ContractSymbol::BtcUsd, datetime!(2024-02-09 08:00 UTC));
+
+        let serialized = event_id.to_string();
+        let parsed = OracleEventId::from_str(&serialized).unwrap();
+
+        assert_eq!(serialized, "btcusd1707465600");
+        assert_eq!(parsed, event_id);
+        assert_eq!(parsed.version(), OracleEventIdVersion::V0);
+    }
+
+    #[test]
+    fn event_id_ignores_sub_second_precision() {
+        let maturity = datetime!(2024-02-09 08:00:00.5 UTC);
+
+        let event_id = OracleEventId::new(ContractSymbol::BtcUsd, maturity);
+
+        assert_eq!(event_id.to_string(), "btcusd1707465600");
+        assert!(event_id
+            .ensure_matches(ContractSymbol::BtcUsd, maturity)
+            .is_ok());
+    }
+
+    #[test]
+    fn reject_malformed_event_ids() {
+        for event_id in [
+            "",
+            "btcusd",
+            "1707465600",
+            "xbtusd1707465600",
+            "BTCUSD1707465600",
+            "ethusd1707465600",
+            "btcusd01707465600",
+            "btcusd-1707465600",
+            "btcusd1707465600x",
+            "btcusd99999999999999999999",
+        ] {
+            assert!(
+                OracleEventId::from_str(event_id).is_err(),
+                "{event_id:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn event_id_must_match_contract() {
+        let event_id = OracleEventId::from_str("btcusd1707465600").unwrap();
+
+        assert!(event_id
+            .ensure_matches(ContractSymbol::BtcUsd, datetime!(2024-02-09 08:00 UTC))
+            .is_ok());
+        assert!(event_id
+            .ensure_matches(ContractSymbol::BtcUsd, datetime!(2024-02-16 08:00 UTC))
+            .is_err());
+    }
+}
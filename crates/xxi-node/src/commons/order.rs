@@ -1,5 +1,9 @@
+use crate::commons::ClientInfo;
 use crate::commons::ContractSymbol;
+use crate::commons::Cursor;
 use crate::commons::Direction;
+use crate::commons::ExpirySchedule;
+use anyhow::ensure;
 use anyhow::Result;
 use bitcoin::hashes::sha256;
 use bitcoin::secp256k1::PublicKey;
@@ -19,6 +23,9 @@ pub struct NewOrderRequest {
     /// A signature of the sha256 of [`value`]
     pub signature: Signature,
     pub channel_opening_params: Option<ChannelOpeningParams>,
+    /// The client submitting the order. Not part of the signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_info: Option<ClientInfo>,
 }
 
 impl NewOrderRequest {
@@ -66,6 +73,27 @@ impl NewOrder {
         }
     }
 
+    pub fn quantity(&self) -> Decimal {
+        match self {
+            NewOrder::Market(o) => o.quantity,
+            NewOrder::Limit(o) => o.quantity,
+        }
+    }
+
+    pub fn contract_symbol(&self) -> ContractSymbol {
+        match self {
+            NewOrder::Market(o) => o.contract_symbol,
+            NewOrder::Limit(o) => o.contract_symbol,
+        }
+    }
+
+    pub fn contract_expiry(&self) -> Option<OffsetDateTime> {
+        match self {
+            NewOrder::Market(o) => o.contract_expiry,
+            NewOrder::Limit(o) => o.contract_expiry,
+        }
+    }
+
     pub fn price(&self) -> String {
         match self {
             NewOrder::Market(_) => "Market".to_string(),
@@ -95,6 +123,14 @@ pub struct NewMarketOrder {
     #[serde(with = "time::serde::timestamp")]
     pub expiry: OffsetDateTime,
     pub stable: bool,
+    /// The expiry of the contract to trade, one of the expiries listed by the coordinator. If not
+    /// set, the contract expires at the next expiry.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::timestamp::option"
+    )]
+    pub contract_expiry: Option<OffsetDateTime>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -112,6 +148,21 @@ pub struct NewLimitOrder {
     #[serde(with = "time::serde::timestamp")]
     pub expiry: OffsetDateTime,
     pub stable: bool,
+    /// If set, the order is an iceberg order and only this much of its quantity is shown in the
+    /// public orderbook at a time. The hidden quantity can still be matched.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "rust_decimal::serde::float_option"
+    )]
+    pub display_quantity: Option<Decimal>,
+    /// The expiry of the contract to trade, see [`NewMarketOrder::contract_expiry`].
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::timestamp::option"
+    )]
+    pub contract_expiry: Option<OffsetDateTime>,
 }
 
 impl NewLimitOrder {
@@ -140,8 +191,34 @@ impl NewLimitOrder {
         vec.append(&mut price.to_vec());
         vec.append(&mut leverage.to_vec());
 
+        // Only part of the message if set, so that the signatures of regular limit orders do not
+        // change.
+        if let Some(display_quantity) = self.display_quantity {
+            let display_quantity = format!("{:.2}", display_quantity);
+            vec.append(&mut display_quantity.as_bytes().to_vec());
+        }
+
+        append_contract_expiry(&mut vec, self.contract_expiry);
+
         Message::from_hashed_data::<sha256::Hash>(vec.as_slice())
     }
+
+    /// Ensure that the display quantity of an iceberg order is a positive part of its quantity.
+    pub fn ensure_valid_display_quantity(&self) -> Result<()> {
+        if let Some(display_quantity) = self.display_quantity {
+            ensure!(
+                display_quantity > Decimal::ZERO,
+                "Display quantity must be positive"
+            );
+            ensure!(
+                display_quantity <= self.quantity,
+                "Display quantity {display_quantity} exceeds order quantity {}",
+                self.quantity
+            );
+        }
+
+        Ok(())
+    }
 }
 
 impl NewMarketOrder {
@@ -167,10 +244,21 @@ impl NewMarketOrder {
         vec.append(&mut quantity.to_vec());
         vec.append(&mut leverage.to_vec());
 
+        append_contract_expiry(&mut vec, self.contract_expiry);
+
         Message::from_hashed_data::<sha256::Hash>(vec.as_slice())
     }
 }
 
+/// Only part of the message if set, so that the signatures of orders for the next expiry do not
+/// change.
+fn append_contract_expiry(vec: &mut Vec<u8>, contract_expiry: Option<OffsetDateTime>) {
+    if let Some(contract_expiry) = contract_expiry {
+        let mut seconds = contract_expiry.unix_timestamp().to_le_bytes().to_vec();
+        vec.append(&mut seconds);
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum OrderType {
     #[allow(dead_code)]
@@ -204,6 +292,9 @@ pub enum OrderReason {
     Expired,
     CoordinatorLiquidated,
     TraderLiquidated,
+    /// The order has been created by the coordinator to migrate the trader's DLC channel to new
+    /// parameters, i.e. to close and reopen the trader's position.
+    ChannelMigration,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -225,6 +316,76 @@ pub struct Order {
     pub order_state: OrderState,
     pub order_reason: OrderReason,
     pub stable: bool,
+    /// The quantity shown in the public orderbook at a time, if this is an iceberg order.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "rust_decimal::serde::float_option"
+    )]
+    pub display_quantity: Option<Decimal>,
+    /// The expiry of the contract traded with this order. If not set, the contract expires at the
+    /// next expiry at the time the order is matched.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    pub contract_expiry: Option<OffsetDateTime>,
+}
+
+impl Order {
+    /// The expiry of the contract traded with this order at `now`.
+    pub fn contract_expiry_at(
+        &self,
+        now: OffsetDateTime,
+        schedule: ExpirySchedule,
+    ) -> OffsetDateTime {
+        self.contract_expiry
+            .unwrap_or_else(|| schedule.next_expiry(now))
+    }
+
+    /// The quantity of the order shown in the public orderbook.
+    ///
+    /// An iceberg order shows its odd remainder first, so that every slice replenished from the
+    /// hidden quantity is a full [`Order::display_quantity`].
+    pub fn visible_quantity(&self) -> Decimal {
+        match self.display_quantity {
+            Some(display_quantity) if display_quantity > Decimal::ZERO => {
+                let slice = self.quantity % display_quantity;
+                if slice.is_zero() {
+                    display_quantity.min(self.quantity)
+                } else {
+                    slice
+                }
+            }
+            _ => self.quantity,
+        }
+    }
+
+    /// The order as shown in the public orderbook, i.e. without the hidden quantity of an iceberg
+    /// order.
+    pub fn displayed(&self) -> Order {
+        Order {
+            quantity: self.visible_quantity(),
+            display_quantity: None,
+            ..self.clone()
+        }
+    }
+
+    /// The position of the order in a paginated list of orders.
+    pub fn cursor(&self) -> Cursor<Uuid> {
+        Cursor {
+            timestamp: self.timestamp,
+            id: self.id,
+        }
+    }
+}
+
+/// Narrows down a paginated list of orders.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct OrderFilter {
+    pub contract_symbol: Option<ContractSymbol>,
+    pub direction: Option<Direction>,
 }
 
 /// Extra information required to open a DLC channel, independent of the [`TradeParams`] associated
@@ -245,15 +406,24 @@ pub struct ChannelOpeningParams {
 pub mod tests {
     use crate::commons::ContractSymbol;
     use crate::commons::Direction;
+    use crate::commons::ExpirySchedule;
     use crate::commons::NewLimitOrder;
+    use crate::commons::NewMarketOrder;
     use crate::commons::NewOrder;
     use crate::commons::NewOrderRequest;
+    use crate::commons::Order;
+    use crate::commons::OrderReason;
+    use crate::commons::OrderState;
+    use crate::commons::OrderType;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
     use secp256k1::rand;
     use secp256k1::Secp256k1;
     use secp256k1::SecretKey;
     use secp256k1::SECP256K1;
     use std::str::FromStr;
     use time::ext::NumericalDuration;
+    use time::macros::datetime;
     use time::OffsetDateTime;
     use uuid::Uuid;
 
@@ -272,6 +442,8 @@ pub mod tests {
             leverage: rust_decimal_macros::dec!(2.0),
             expiry: OffsetDateTime::now_utc(),
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         };
 
         let message = order.message();
@@ -299,6 +471,8 @@ pub mod tests {
             // Note: the last 5 is too much as it does not get serialized
             expiry: OffsetDateTime::UNIX_EPOCH + 1.1010101015.seconds(),
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         };
 
         let message = original_order.clone().message();
@@ -310,6 +484,7 @@ pub mod tests {
             value: NewOrder::Limit(original_order),
             signature,
             channel_opening_params: None,
+            client_info: None,
         };
 
         let original_serialized_request = serde_json::to_string(&original_request).unwrap();
@@ -340,4 +515,161 @@ pub mod tests {
         let secp = Secp256k1::verification_only();
         parsed_request.verify(&secp).unwrap();
     }
+
+    #[test]
+    fn iceberg_order_shows_odd_remainder_first() {
+        let order = dummy_iceberg_order(dec!(1000), dec!(300));
+
+        assert_eq!(order.visible_quantity(), dec!(100));
+        assert_eq!(
+            Order {
+                quantity: dec!(900),
+                ..order.clone()
+            }
+            .visible_quantity(),
+            dec!(300)
+        );
+        assert_eq!(
+            Order {
+                quantity: dec!(250),
+                ..order
+            }
+            .visible_quantity(),
+            dec!(250)
+        );
+    }
+
+    #[test]
+    fn displayed_order_hides_iceberg() {
+        let order = dummy_iceberg_order(dec!(1000), dec!(300));
+
+        let displayed = order.displayed();
+
+        assert_eq!(displayed.quantity, dec!(100));
+        assert_eq!(displayed.display_quantity, None);
+        assert_eq!(
+            Order {
+                display_quantity: None,
+                ..order.clone()
+            }
+            .displayed(),
+            Order {
+                display_quantity: None,
+                ..order
+            }
+        );
+    }
+
+    #[test]
+    fn display_quantity_must_be_part_of_quantity() {
+        let order = NewLimitOrder {
+            id: Default::default(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            price: dec!(53_000),
+            quantity: dec!(2000),
+            trader_id: SecretKey::new(&mut rand::thread_rng()).public_key(SECP256K1),
+            direction: Direction::Long,
+            leverage: dec!(2.0),
+            expiry: OffsetDateTime::now_utc(),
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        };
+
+        assert!(order.ensure_valid_display_quantity().is_ok());
+        for (display_quantity, valid) in [
+            (dec!(500), true),
+            (dec!(2000), true),
+            (dec!(0), false),
+            (dec!(-1), false),
+            (dec!(2001), false),
+        ] {
+            let order = NewLimitOrder {
+                display_quantity: Some(display_quantity),
+                ..order
+            };
+            assert_eq!(
+                order.ensure_valid_display_quantity().is_ok(),
+                valid,
+                "{display_quantity}"
+            );
+        }
+
+        // The display quantity is signed, so that it cannot be changed on the way.
+        assert_ne!(
+            order.message(),
+            NewLimitOrder {
+                display_quantity: Some(dec!(500)),
+                ..order
+            }
+            .message()
+        );
+    }
+
+    #[test]
+    fn contract_expiry_is_signed_if_set() {
+        let order = NewMarketOrder {
+            id: Default::default(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            quantity: dec!(2000),
+            trader_id: SecretKey::new(&mut rand::thread_rng()).public_key(SECP256K1),
+            direction: Direction::Long,
+            leverage: dec!(2.0),
+            expiry: OffsetDateTime::UNIX_EPOCH,
+            stable: false,
+            contract_expiry: None,
+        };
+
+        let in_two_weeks = NewMarketOrder {
+            contract_expiry: Some(datetime!(2024-07-28 15:00 UTC)),
+            ..order.clone()
+        };
+
+        assert_ne!(order.message(), in_two_weeks.message());
+
+        let serialized = serde_json::to_value(&order).unwrap();
+        assert!(serialized.get("contract_expiry").is_none());
+    }
+
+    #[test]
+    fn order_without_contract_expiry_expires_at_next_expiry() {
+        let order = dummy_iceberg_order(dec!(1000), dec!(300));
+        // Wednesday
+        let now = datetime!(2024-07-10 12:00 UTC);
+
+        assert_eq!(
+            order.contract_expiry_at(now, ExpirySchedule::Weekly),
+            datetime!(2024-07-14 15:00 UTC)
+        );
+
+        let order = Order {
+            contract_expiry: Some(datetime!(2024-07-21 15:00 UTC)),
+            ..order
+        };
+
+        assert_eq!(
+            order.contract_expiry_at(now, ExpirySchedule::Weekly),
+            datetime!(2024-07-21 15:00 UTC)
+        );
+    }
+
+    fn dummy_iceberg_order(quantity: Decimal, display_quantity: Decimal) -> Order {
+        Order {
+            id: Uuid::new_v4(),
+            price: dec!(53_000),
+            leverage: 2.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            trader_id: SecretKey::new(&mut rand::thread_rng()).public_key(SECP256K1),
+            direction: Direction::Short,
+            quantity,
+            order_type: OrderType::Limit,
+            timestamp: OffsetDateTime::now_utc(),
+            expiry: OffsetDateTime::now_utc(),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: Some(display_quantity),
+            contract_expiry: None,
+        }
+    }
 }
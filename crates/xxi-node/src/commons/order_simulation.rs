@@ -0,0 +1,53 @@
+use crate::commons::ContractSymbol;
+use crate::commons::Direction;
+use bitcoin::Amount;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+/// A hypothetical market order, to preview how it would be matched with the current book.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimulateOrderParams {
+    pub contract_symbol: ContractSymbol,
+    pub direction: Direction,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub quantity: Decimal,
+    /// The expiry of the contract to trade. If not set, the contract expires at the next expiry.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::timestamp::option"
+    )]
+    pub contract_expiry: Option<OffsetDateTime>,
+}
+
+/// How a [`SimulateOrderParams`] would have been matched at the time of the simulation.
+///
+/// The book may change before an actual order is placed, hence the fills are not guaranteed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderSimulation {
+    pub fills: Vec<SimulatedFill>,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub filled_quantity: Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub unfilled_quantity: Decimal,
+    /// The average price of the fills, weighted by their quantity. Not set if nothing would have
+    /// been filled.
+    #[serde(with = "rust_decimal::serde::float_option")]
+    pub average_execution_price: Option<Decimal>,
+    /// The order matching fee of all fills.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub matching_fee: Amount,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimulatedFill {
+    #[serde(with = "rust_decimal::serde::float")]
+    pub quantity: Decimal,
+    /// The price of the limit order, adjusted by the spread.
+    #[serde(with = "rust_decimal::serde::float")]
+    pub execution_price: Decimal,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub matching_fee: Amount,
+}
@@ -0,0 +1,182 @@
+//! Cursor-based pagination of list endpoints.
+//!
+//! Items are listed in a stable order by their timestamp, with their id breaking ties. A page
+//! points to the next one with the [`Cursor`] of its last item, so that items inserted while
+//! paging through a list neither shift the pages nor show up twice.
+//!
+//! The items of a page are responded with as a plain JSON array, so that clients which predate
+//! pagination keep working. The cursor to the next page is returned in the
+//! [`NEXT_CURSOR_HEADER`], which is missing on the last page.
+
+use anyhow::Context;
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use std::fmt;
+use std::str::FromStr;
+use time::OffsetDateTime;
+
+/// The most items a page may hold, regardless of the requested limit.
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// The response header holding the cursor to the next page.
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Oldest first.
+    Asc,
+    /// Newest first.
+    #[default]
+    Desc,
+}
+
+/// The query parameters selecting a page of a list endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "I: fmt::Display", deserialize = "I: FromStr"))]
+pub struct PageParams<I> {
+    /// The cursor of the previous page. The first page is returned without one.
+    pub after: Option<Cursor<I>>,
+    /// The number of items on the page, at most [`MAX_PAGE_SIZE`].
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub sort: SortOrder,
+}
+
+impl<I> Default for PageParams<I> {
+    fn default() -> Self {
+        Self {
+            after: None,
+            limit: None,
+            sort: SortOrder::default(),
+        }
+    }
+}
+
+impl<I> PageParams<I> {
+    /// The number of items on the page, defaulting to and capped at [`MAX_PAGE_SIZE`].
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(MAX_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE) as i64
+    }
+}
+
+/// The position of an item in a list, by its timestamp and id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor<I> {
+    pub timestamp: OffsetDateTime,
+    pub id: I,
+}
+
+impl<I: fmt::Display> fmt::Display for Cursor<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.timestamp.unix_timestamp_nanos(), self.id)
+    }
+}
+
+impl<I: FromStr> FromStr for Cursor<I> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (timestamp, id) = s.split_once('_').context("Missing cursor id")?;
+
+        let timestamp = timestamp
+            .parse::<i128>()
+            .ok()
+            .and_then(|nanos| OffsetDateTime::from_unix_timestamp_nanos(nanos).ok())
+            .context("Invalid cursor timestamp")?;
+        let id = id.parse().ok().context("Invalid cursor id")?;
+
+        Ok(Self { timestamp, id })
+    }
+}
+
+impl<I: fmt::Display> Serialize for Cursor<I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, I: FromStr> Deserialize<'de> for Cursor<I> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cursor = String::deserialize(deserializer)?;
+        cursor.parse().map_err(de::Error::custom)
+    }
+}
+
+/// A page of items, with the cursor to the next page if there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T, I> {
+    pub items: Vec<T>,
+    pub next: Option<Cursor<I>>,
+}
+
+impl<T, I> Page<T, I> {
+    /// Build the page from up to `limit + 1` items. The extra item is dropped and only indicates
+    /// that there is a next page.
+    pub fn new(mut items: Vec<T>, limit: i64, cursor: impl Fn(&T) -> Cursor<I>) -> Self {
+        let limit = limit.max(0) as usize;
+
+        let next = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(cursor)
+        } else {
+            None
+        };
+
+        Self { items, next }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U, I> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next: self.next,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+    use uuid::Uuid;
+
+    #[test]
+    fn cursor_roundtrip() {
+        let cursor = Cursor {
+            timestamp: datetime!(2024-07-24 10:15:30.123456 UTC),
+            id: Uuid::new_v4(),
+        };
+
+        assert_eq!(cursor.to_string().parse::<Cursor<Uuid>>().unwrap(), cursor);
+        assert!("1721816130".parse::<Cursor<i32>>().is_err());
+        assert!("1721816130_abc".parse::<Cursor<i32>>().is_err());
+    }
+
+    #[test]
+    fn page_points_to_next_page_only_if_there_is_one() {
+        let timestamp = datetime!(2024-07-24 10:00 UTC);
+        let cursor = |id: &i32| Cursor { timestamp, id: *id };
+
+        let page = Page::new(vec![1, 2, 3], 2, cursor);
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next, Some(Cursor { timestamp, id: 2 }));
+
+        let page = Page::new(vec![1, 2], 2, cursor);
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn limit_is_capped() {
+        let params = PageParams::<i32> {
+            limit: Some(10_000),
+            ..PageParams::default()
+        };
+        assert_eq!(params.limit(), MAX_PAGE_SIZE as i64);
+
+        assert_eq!(PageParams::<i32>::default().limit(), MAX_PAGE_SIZE as i64);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::commons::ContractSymbol;
+use crate::commons::Direction;
+use bitcoin::secp256k1::XOnlyPublicKey;
+use bitcoin::SignedAmount;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+/// A closed position of a trader.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClosedPosition {
+    pub contract_symbol: ContractSymbol,
+    pub direction: Direction,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub quantity: Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub average_entry_price: Decimal,
+    #[serde(with = "rust_decimal::serde::float_option")]
+    pub closing_price: Option<Decimal>,
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub realized_pnl: Option<SignedAmount>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub closed_at: OffsetDateTime,
+    /// The oracle attestations the position was settled with on-chain. Empty if the position was
+    /// closed off-chain.
+    pub settlement_attestations: Vec<SettlementAttestation>,
+}
+
+/// Narrows down a paginated list of positions.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PositionFilter {
+    pub contract_symbol: Option<ContractSymbol>,
+}
+
+/// An oracle attestation a position was settled with on-chain, and the announcement it attests.
+///
+/// Both are hex encoded in the TLV format of the DLC specification, so that anyone can verify the
+/// signatures of the oracle and hence the outcome of the settlement, without relying on the oracle
+/// still serving them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SettlementAttestation {
+    pub oracle_pubkey: XOnlyPublicKey,
+    pub event_id: String,
+    pub announcement: String,
+    pub attestation: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
@@ -137,6 +137,8 @@ mod test {
             order_state,
             order_reason: OrderReason::Manual,
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         }
     }
 
@@ -0,0 +1,18 @@
+use bitcoin::Amount;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The interest a trader earns on the collateral reserve of their DLC channel.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ReserveInterest {
+    /// The annual percentage rate paid on the collateral reserve, e.g. 0.05 for 5%.
+    #[serde(with = "rust_decimal::serde::float")]
+    pub apr: Decimal,
+    /// Interest which has accrued, but will only be credited during the next renew or rollover.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub pending: Amount,
+    /// Interest which has already been credited to the collateral reserve.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub paid: Amount,
+}
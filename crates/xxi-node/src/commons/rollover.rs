@@ -1,71 +1,173 @@
+use crate::commons::ContractSymbol;
+use crate::commons::OracleEventId;
 use bitcoin::Network;
+use serde::Deserialize;
+use serde::Serialize;
 use time::macros::time;
 use time::Duration;
 use time::OffsetDateTime;
 use time::Weekday;
 
-/// Calculates the next expiry timestamp based on the given timestamp and the network.
-pub fn calculate_next_expiry(timestamp: OffsetDateTime, network: Network) -> OffsetDateTime {
-    match network {
-        // Calculates the expiry timestamp at the next Sunday at 3 pm UTC from a given offset date
-        // time. If the argument falls in between Friday, 3 pm UTC and Sunday, 3pm UTC, the
-        // expiry will be calculated to next weeks Sunday at 3 pm
-        Network::Bitcoin => {
-            let days = if is_eligible_for_rollover(timestamp, network)
-                || timestamp.weekday() == Weekday::Sunday
-            {
-                // if the provided timestamp is in the rollover weekend or on a sunday, we expire
-                // the sunday the week after.
-                7 - timestamp.weekday().number_from_monday() + 7
-            } else {
-                7 - timestamp.weekday().number_from_monday()
-            };
-            let time = timestamp
-                .date()
-                .with_hms(15, 0, 0)
-                .expect("to fit into time");
-
-            (time + Duration::days(days as i64)).assume_utc()
+/// When contracts expire and when positions can be rolled over to the following expiry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpirySchedule {
+    /// Contracts expire on Sundays at 3 pm UTC and can be rolled over from Friday 3 pm UTC.
+    Weekly,
+    /// Contracts expire at midnight UTC and can be rolled over during the last 8 hours before.
+    Daily,
+    /// Contracts expire at every full hour and can be rolled over during the last 20 minutes
+    /// before. Meant for short test cycles.
+    Hourly,
+}
+
+impl ExpirySchedule {
+    /// The schedule used on `network` unless configured otherwise: weekly on mainnet, daily
+    /// everywhere else.
+    pub fn for_network(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => ExpirySchedule::Weekly,
+            _ => ExpirySchedule::Daily,
         }
-        // Calculates the expiry timestamp on the same day at midnight unless its already in
-        // rollover then the next day midnight.
-        _ => {
-            if is_eligible_for_rollover(timestamp, network) {
-                let after_tomorrow = timestamp.date() + Duration::days(2);
-                after_tomorrow.midnight().assume_utc()
-            } else {
-                let tomorrow = timestamp.date() + Duration::days(1);
-                tomorrow.midnight().assume_utc()
+    }
+
+    /// The expiry of a contract entered at `timestamp`.
+    ///
+    /// Within the rollover window the upcoming expiry is skipped, as the contract would be rolled
+    /// over right away.
+    pub fn next_expiry(&self, timestamp: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            ExpirySchedule::Weekly => {
+                let days = if self.is_eligible_for_rollover(timestamp)
+                    || timestamp.weekday() == Weekday::Sunday
+                {
+                    // if the provided timestamp is in the rollover weekend or on a sunday, we
+                    // expire the sunday the week after.
+                    7 - timestamp.weekday().number_from_monday() + 7
+                } else {
+                    7 - timestamp.weekday().number_from_monday()
+                };
+                let time = timestamp
+                    .date()
+                    .with_hms(15, 0, 0)
+                    .expect("to fit into time");
+
+                (time + Duration::days(days as i64)).assume_utc()
             }
+            ExpirySchedule::Daily | ExpirySchedule::Hourly => {
+                let boundary = self.next_period_boundary(timestamp);
+                if self.is_eligible_for_rollover(timestamp) {
+                    boundary + self.period()
+                } else {
+                    boundary
+                }
+            }
+        }
+    }
+
+    /// The next `count` expiries after the given timestamp, i.e. the expiries contracts can be
+    /// traded for, starting with the next expiry.
+    pub fn next_expiries(&self, timestamp: OffsetDateTime, count: usize) -> Vec<OffsetDateTime> {
+        let mut expiries = Vec::with_capacity(count);
+
+        let mut expiry = timestamp;
+        for _ in 0..count {
+            expiry = self.next_expiry(expiry);
+            expiries.push(expiry);
+        }
+
+        expiries
+    }
+
+    /// The ids of the oracle events attesting the price of `contract_symbol` at the next `count`
+    /// expiries after the given timestamp.
+    pub fn next_event_ids(
+        &self,
+        contract_symbol: ContractSymbol,
+        timestamp: OffsetDateTime,
+        count: usize,
+    ) -> Vec<OracleEventId> {
+        self.next_expiries(timestamp, count)
+            .into_iter()
+            .map(|expiry| OracleEventId::new(contract_symbol, expiry))
+            .collect()
+    }
+
+    /// Whether positions can be rolled over to the following expiry at `timestamp`.
+    pub fn is_eligible_for_rollover(&self, timestamp: OffsetDateTime) -> bool {
+        match self {
+            // Returns true if the given date falls in between Friday 15 pm UTC and Sunday 15 pm
+            // UTC
+            ExpirySchedule::Weekly => match timestamp.weekday() {
+                Weekday::Friday => timestamp.time() >= time!(15:00),
+                Weekday::Saturday => true,
+                Weekday::Sunday => timestamp.time() < time!(15:00),
+                _ => false,
+            },
+            ExpirySchedule::Daily | ExpirySchedule::Hourly => {
+                (self.next_period_boundary(timestamp) - timestamp) < self.rollover_window()
+            }
+        }
+    }
+
+    /// The time between two expiries.
+    pub fn period(&self) -> Duration {
+        match self {
+            ExpirySchedule::Weekly => Duration::weeks(1),
+            ExpirySchedule::Daily => Duration::days(1),
+            ExpirySchedule::Hourly => Duration::hours(1),
         }
     }
+
+    /// How long before an expiry positions can be rolled over.
+    pub fn rollover_window(&self) -> Duration {
+        match self {
+            ExpirySchedule::Weekly => Duration::hours(48),
+            ExpirySchedule::Daily => Duration::hours(8),
+            ExpirySchedule::Hourly => Duration::minutes(20),
+        }
+    }
+
+    /// The first multiple of the period since the unix epoch after `timestamp`.
+    fn next_period_boundary(&self, timestamp: OffsetDateTime) -> OffsetDateTime {
+        let period = self.period().whole_seconds();
+        let timestamp = timestamp.unix_timestamp();
+        let boundary = (timestamp.div_euclid(period) + 1) * period;
+
+        OffsetDateTime::from_unix_timestamp(boundary).expect("valid timestamp")
+    }
+}
+
+/// Calculates the next expiry timestamp based on the given timestamp and the default
+/// [`ExpirySchedule`] of the network.
+pub fn calculate_next_expiry(timestamp: OffsetDateTime, network: Network) -> OffsetDateTime {
+    ExpirySchedule::for_network(network).next_expiry(timestamp)
+}
+
+/// The next `count` expiries after the given timestamp, i.e. the expiries contracts can be traded
+/// for, starting with the next expiry.
+pub fn calculate_next_expiries(
+    timestamp: OffsetDateTime,
+    network: Network,
+    count: usize,
+) -> Vec<OffsetDateTime> {
+    ExpirySchedule::for_network(network).next_expiries(timestamp, count)
 }
 
 /// Checks whether the provided expiry date is eligible for a rollover
 pub fn is_eligible_for_rollover(timestamp: OffsetDateTime, network: Network) -> bool {
-    match network {
-        // Returns true if the given date falls in between Friday 15 pm UTC and Sunday 15 pm UTC
-        Network::Bitcoin => match timestamp.weekday() {
-            Weekday::Friday => timestamp.time() >= time!(15:00),
-            Weekday::Saturday => true,
-            Weekday::Sunday => timestamp.time() < time!(15:00),
-            _ => false,
-        },
-        // Returns true if the timestamp is less than 8 hours from now
-        _ => {
-            let midnight = (OffsetDateTime::now_utc().date() + Duration::days(1))
-                .midnight()
-                .assume_utc();
-            (midnight - timestamp) < Duration::hours(8)
-        }
-    }
+    ExpirySchedule::for_network(network).is_eligible_for_rollover(timestamp)
 }
 
 #[cfg(test)]
 mod test {
+    use crate::commons::rollover::calculate_next_expiries;
     use crate::commons::rollover::calculate_next_expiry;
     use crate::commons::rollover::is_eligible_for_rollover;
+    use crate::commons::rollover::ExpirySchedule;
+    use crate::commons::ContractSymbol;
     use bitcoin::Network;
+    use time::macros::datetime;
     use time::Duration;
     use time::OffsetDateTime;
 
@@ -215,4 +317,103 @@ mod test {
             Network::Regtest
         ))
     }
+
+    #[test]
+    fn test_expiry_timestamp_signet_midnight() {
+        // 12:00 on the current day
+        let timestamp = OffsetDateTime::now_utc().date().midnight() + Duration::hours(12);
+        let expiry = calculate_next_expiry(timestamp.assume_utc(), Network::Signet);
+
+        let midnight = (OffsetDateTime::now_utc().date() + Duration::days(1))
+            .midnight()
+            .assume_utc();
+
+        assert_eq!(midnight, expiry);
+    }
+
+    #[test]
+    fn test_is_eligable_for_rollover_signet() {
+        let timestamp = OffsetDateTime::now_utc().date().midnight() + Duration::hours(17);
+        assert!(is_eligible_for_rollover(
+            timestamp.assume_utc(),
+            Network::Signet
+        ))
+    }
+
+    #[test]
+    fn next_expiries_are_weekly_on_mainnet() {
+        // Wednesday
+        let now = datetime!(2024-07-10 12:00 UTC);
+
+        let expiries = calculate_next_expiries(now, Network::Bitcoin, 3);
+
+        assert_eq!(
+            expiries,
+            vec![
+                datetime!(2024-07-14 15:00 UTC),
+                datetime!(2024-07-21 15:00 UTC),
+                datetime!(2024-07-28 15:00 UTC),
+            ]
+        );
+    }
+
+    #[test]
+    fn next_expiries_skip_the_expiry_in_the_rollover_window() {
+        // Saturday
+        let now = datetime!(2024-07-13 12:00 UTC);
+
+        let expiries = calculate_next_expiries(now, Network::Bitcoin, 2);
+
+        assert_eq!(
+            expiries,
+            vec![
+                datetime!(2024-07-21 15:00 UTC),
+                datetime!(2024-07-28 15:00 UTC),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_next_expiries_if_none_are_listed() {
+        let now = datetime!(2024-07-10 12:00 UTC);
+
+        assert!(calculate_next_expiries(now, Network::Bitcoin, 0).is_empty());
+    }
+
+    #[test]
+    fn hourly_expiry_is_the_next_full_hour() {
+        let expiry = ExpirySchedule::Hourly.next_expiry(datetime!(2024-07-10 12:30 UTC));
+
+        assert_eq!(expiry, datetime!(2024-07-10 13:00 UTC));
+    }
+
+    #[test]
+    fn hourly_expiry_skips_the_full_hour_in_the_rollover_window() {
+        let schedule = ExpirySchedule::Hourly;
+
+        assert!(!schedule.is_eligible_for_rollover(datetime!(2024-07-10 12:40 UTC)));
+        assert!(schedule.is_eligible_for_rollover(datetime!(2024-07-10 12:40:01 UTC)));
+        assert_eq!(
+            schedule.next_expiry(datetime!(2024-07-10 23:50 UTC)),
+            datetime!(2024-07-11 01:00 UTC)
+        );
+    }
+
+    #[test]
+    fn hourly_expiry_at_a_full_hour_is_the_following_hour() {
+        let expiry = ExpirySchedule::Hourly.next_expiry(datetime!(2024-07-10 12:00 UTC));
+
+        assert_eq!(expiry, datetime!(2024-07-10 13:00 UTC));
+    }
+
+    #[test]
+    fn next_event_ids_follow_the_schedule() {
+        let event_ids = ExpirySchedule::Hourly
+            .next_event_ids(ContractSymbol::BtcUsd, datetime!(2024-02-09 06:30 UTC), 2)
+            .into_iter()
+            .map(|event_id| event_id.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(event_ids, vec!["btcusd1707462000", "btcusd1707465600"]);
+    }
 }
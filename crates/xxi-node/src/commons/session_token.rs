@@ -0,0 +1,36 @@
+use bitcoin::secp256k1::PublicKey;
+use serde::Deserialize;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+/// A short-lived token authenticating the trader towards the coordinator's REST API.
+///
+/// The token is sent as `Authorization: Bearer <token>`. It is opaque to the app: only the
+/// coordinator, which issued it, can verify it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionToken {
+    pub token: String,
+    #[serde(with = "time::serde::timestamp")]
+    pub expires_at: OffsetDateTime,
+}
+
+impl SessionToken {
+    /// Whether the token is still valid for at least `margin`, so that it does not expire while a
+    /// request is in flight.
+    pub fn is_valid_for(&self, margin: time::Duration, now: OffsetDateTime) -> bool {
+        self.expires_at - margin > now
+    }
+}
+
+/// Request for a new session token, signed with the trader's node key.
+///
+/// This is how the app resumes a session, e.g. once its token expired, without reconnecting to
+/// the orderbook websocket.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateSessionTokenParams {
+    pub trader_pubkey: PublicKey,
+    /// When the request was created. Requests older than a few minutes are rejected, so that a
+    /// leaked request cannot be replayed.
+    #[serde(with = "time::serde::timestamp")]
+    pub timestamp: OffsetDateTime,
+}
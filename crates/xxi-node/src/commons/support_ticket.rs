@@ -0,0 +1,25 @@
+use crate::commons::ErrorCode;
+use bitcoin::secp256k1::PublicKey;
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// A support ticket the app creates, with the user's consent, when a trade fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSupportTicket {
+    pub trader_pubkey: PublicKey,
+    pub error_code: ErrorCode,
+    /// The error as shown to the user.
+    pub error: String,
+    pub order_id: Option<Uuid>,
+    /// The DLC protocol which failed, if the app knows it. Otherwise, the coordinator looks it up
+    /// by the order.
+    pub protocol_id: Option<Uuid>,
+    pub app_version: String,
+}
+
+/// Identifies a support ticket towards the user and the support team.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupportTicketReference {
+    pub reference: String,
+}
@@ -9,6 +9,19 @@ use serde::Serialize;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+/// How the payout of a contract depends on the price of the underlying.
+///
+/// For both types the quantity is denominated in USD and the contract is settled in sats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContractType {
+    /// The PnL is converted to sats at the closing price, hence the payout in sats is inversely
+    /// proportional to the price, e.g. BTCUSD.
+    Inverse,
+    /// The PnL is converted to sats at the opening price, hence the payout in sats is
+    /// proportional to the price.
+    Linear,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TradeAndChannelParams {
     pub trade_params: TradeParams,
@@ -93,12 +106,26 @@ pub struct Match {
 
     /// The execution price as defined by the orderbook
     ///
-    /// The trade is to be executed at this price.
+    /// The trade is to be executed at this price. It only differs from the `index_price` by the
+    /// `spread_bps` applied by the orderbook.
     #[serde(with = "rust_decimal::serde::float")]
     pub execution_price: Decimal,
 
     #[serde(with = "bitcoin::amount::serde::as_sat")]
     pub matching_fee: Amount,
+
+    /// The price at which the order was matched, before the spread was applied
+    ///
+    /// This is `None` for matches created by an orderbook which did not report it.
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    pub index_price: Option<Decimal>,
+
+    /// The spread in basis points applied to the `index_price` to get the `execution_price`
+    ///
+    /// The spread is always applied to the disadvantage of the trader, i.e. a long order is
+    /// executed above and a short order below the `index_price`.
+    #[serde(default)]
+    pub spread_bps: u32,
 }
 
 impl From<Matches> for Match {
@@ -110,6 +137,8 @@ impl From<Matches> for Match {
             pubkey: value.trader_id,
             execution_price: value.execution_price,
             matching_fee: value.matching_fee,
+            index_price: value.index_price,
+            spread_bps: value.spread_bps,
         }
     }
 }
@@ -205,6 +234,8 @@ pub struct Matches {
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub matching_fee: Amount,
+    pub index_price: Option<Decimal>,
+    pub spread_bps: u32,
 }
 
 #[cfg(test)]
@@ -216,6 +247,9 @@ mod test {
 
     use crate::commons::trade::FilledWith;
     use crate::commons::trade::Match;
+    use crate::commons::trade::TradeParams;
+    use crate::commons::ContractSymbol;
+    use crate::commons::Direction;
     use bitcoin::secp256k1::PublicKey;
     use bitcoin::secp256k1::XOnlyPublicKey;
     use bitcoin::Amount;
@@ -245,6 +279,8 @@ mod test {
                     pubkey: dummy_public_key(),
                     execution_price: match_0_price,
                     matching_fee: Amount::from_sat(1000),
+                    index_price: None,
+                    spread_bps: 0,
                 },
                 Match {
                     id: Uuid::new_v4(),
@@ -253,6 +289,8 @@ mod test {
                     pubkey: dummy_public_key(),
                     execution_price: match_1_price,
                     matching_fee: Amount::from_sat(1000),
+                    index_price: None,
+                    spread_bps: 0,
                 },
             ],
         };
@@ -261,4 +299,49 @@ mod test {
 
         assert_eq!(average_execution_price.round_dp(2), dec!(11250.00));
     }
+
+    #[test]
+    fn trade_params_without_spread_are_accepted() {
+        let trade_params = TradeParams {
+            pubkey: dummy_public_key(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            leverage: 2.0,
+            quantity: 100.0,
+            direction: Direction::Long,
+            filled_with: FilledWith {
+                order_id: Uuid::new_v4(),
+                expiry_timestamp: OffsetDateTime::UNIX_EPOCH,
+                oracle_pk: XOnlyPublicKey::from_str(
+                    "16f88cf7d21e6c0f46bcbc983a4e3b19726c6c98858cc31c83551a88fde171c0",
+                )
+                .expect("To be a valid pubkey"),
+                matches: vec![Match {
+                    id: Uuid::new_v4(),
+                    order_id: Uuid::new_v4(),
+                    quantity: dec!(100),
+                    pubkey: dummy_public_key(),
+                    execution_price: dec!(50_000),
+                    matching_fee: Amount::from_sat(1000),
+                    index_price: Some(dec!(49_950)),
+                    spread_bps: 10,
+                }],
+            },
+        };
+
+        let mut value = serde_json::to_value(&trade_params).unwrap();
+        assert_eq!(
+            serde_json::from_value::<TradeParams>(value.clone()).unwrap(),
+            trade_params
+        );
+
+        // An orderbook which does not apply a spread does not send these fields.
+        let legacy_match = value["filled_with"]["matches"][0].as_object_mut().unwrap();
+        legacy_match.remove("index_price");
+        legacy_match.remove("spread_bps");
+
+        let parsed = serde_json::from_value::<TradeParams>(value).unwrap();
+        assert_eq!(parsed.filled_with.matches[0].index_price, None);
+        assert_eq!(parsed.filled_with.matches[0].spread_bps, 0);
+        assert_eq!(parsed.average_execution_price(), dec!(50_000));
+    }
 }
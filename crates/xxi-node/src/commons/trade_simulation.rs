@@ -0,0 +1,72 @@
+use crate::commons::ContractSymbol;
+use crate::commons::Direction;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+/// The parameters of a hypothetical trade, used to preview its outcome without executing it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimulateTradeParams {
+    pub trader_pubkey: PublicKey,
+    pub contract_symbol: ContractSymbol,
+    /// The direction from the point of view of the trader.
+    pub direction: Direction,
+    pub quantity: f32,
+    pub leverage: f32,
+    /// The price at which the position would be opened.
+    #[serde(with = "rust_decimal::serde::float")]
+    pub entry_price: Decimal,
+    /// The price at which the position would be closed.
+    #[serde(with = "rust_decimal::serde::float")]
+    pub exit_price: Decimal,
+    /// The collateral reserve the trader would keep in the DLC channel, outside of the bet.
+    #[serde(default, with = "bitcoin::amount::serde::as_sat::opt")]
+    pub trader_reserve: Option<Amount>,
+    /// The collateral reserve the coordinator would keep in the DLC channel, outside of the bet.
+    #[serde(default, with = "bitcoin::amount::serde::as_sat::opt")]
+    pub coordinator_reserve: Option<Amount>,
+}
+
+/// The outcome of a simulated trade, computed with the same code paths used during trade
+/// execution.
+///
+/// All values are from the point of view of the trader.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TradeSimulation {
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub margin_trader: Amount,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub margin_coordinator: Amount,
+    pub leverage_coordinator: f32,
+    /// The order matching fee charged when opening the position at the entry price.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub opening_fee: Amount,
+    /// The order matching fee charged when closing the position at the exit price.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub closing_fee: Amount,
+    /// The estimated funding fee until the position expires, based on the next funding rate.
+    ///
+    /// A positive amount indicates that the trader pays the coordinator; a negative amount
+    /// indicates that the coordinator pays the trader.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub funding_fee_estimate: SignedAmount,
+    /// The number of funding periods accounted for in [`funding_fee_estimate`].
+    pub funding_periods: u32,
+    pub expiry_timestamp: OffsetDateTime,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub liquidation_price_trader: Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub liquidation_price_coordinator: Decimal,
+    /// The PnL of the trader at the exit price, excluding fees.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub pnl: SignedAmount,
+    /// The payout the trader would receive from the DLC if the oracle attested the exit price.
+    ///
+    /// This includes the trader's collateral reserve.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub payout_at_exit_price: Amount,
+}
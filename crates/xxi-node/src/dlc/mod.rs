@@ -1,6 +1,7 @@
 mod contract_details;
 mod dlc_channel_details;
 mod logger;
+pub mod parallel;
 
 pub use contract_details::ContractDetails;
 pub use dlc_channel_details::DlcChannelDetails;
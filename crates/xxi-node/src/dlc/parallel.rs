@@ -0,0 +1,204 @@
+//! Signing and verifying the adaptor signatures of many CETs at once.
+//!
+//! A DLC channel with a numerical contract has hundreds of CETs, each with an adaptor signature
+//! which has to be created by one party and verified by the other. Every signature is
+//! independent, so the work is split into one chunk per CPU core.
+//!
+//! The DLC manager signs and verifies the CETs of a channel itself. With the `parallel` feature,
+//! it does so on a thread pool as well.
+
+use bitcoin_old::Script;
+use bitcoin_old::Transaction;
+use secp256k1_zkp::EcdsaAdaptorSignature;
+use secp256k1_zkp::PublicKey;
+use secp256k1_zkp::SecretKey;
+use secp256k1_zkp::SECP256K1;
+use std::num::NonZeroUsize;
+use std::thread;
+
+/// Below this many items per chunk, spawning a thread costs more than it saves.
+const MIN_CHUNK_SIZE: usize = 16;
+
+/// A CET together with the adaptor point of the outcome it pays out.
+#[derive(Debug, Clone)]
+pub struct AdaptedCet {
+    pub cet: Transaction,
+    pub adaptor_point: PublicKey,
+}
+
+/// The number of threads work is split across, i.e. the number of available CPU cores.
+pub fn worker_count() -> usize {
+    thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1)
+}
+
+/// Create the adaptor signatures of the CETs with our funding key, in the order of the CETs.
+pub fn sign_cet_adaptor_sigs(
+    cets: &[AdaptedCet],
+    funding_sk: &SecretKey,
+    funding_script_pubkey: &Script,
+    fund_output_value: u64,
+) -> Result<Vec<EcdsaAdaptorSignature>, dlc::Error> {
+    try_par_map(cets, |AdaptedCet { cet, adaptor_point }| {
+        dlc::create_cet_adaptor_sig_from_point(
+            SECP256K1,
+            cet,
+            adaptor_point,
+            funding_sk,
+            funding_script_pubkey,
+            fund_output_value,
+        )
+    })
+}
+
+/// Verify the counterparty's adaptor signatures of the CETs, which are expected in the order of
+/// the CETs.
+pub fn verify_cet_adaptor_sigs(
+    cets: &[AdaptedCet],
+    adaptor_sigs: &[EcdsaAdaptorSignature],
+    counterparty_funding_pk: &PublicKey,
+    funding_script_pubkey: &Script,
+    fund_output_value: u64,
+) -> Result<(), dlc::Error> {
+    if cets.len() != adaptor_sigs.len() {
+        return Err(dlc::Error::InvalidArgument);
+    }
+
+    let pairs = cets.iter().zip(adaptor_sigs).collect::<Vec<_>>();
+    try_par_map(
+        &pairs,
+        |(AdaptedCet { cet, adaptor_point }, adaptor_sig)| {
+            dlc::verify_cet_adaptor_sig_from_point(
+                SECP256K1,
+                adaptor_sig,
+                cet,
+                adaptor_point,
+                counterparty_funding_pk,
+                funding_script_pubkey,
+                fund_output_value,
+            )
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Apply `f` to every item, with the items split into one chunk per worker.
+///
+/// The results are in the order of the items. Small inputs are processed on the calling thread.
+fn try_par_map<T, R, E, F>(items: &[T], f: F) -> Result<Vec<R>, E>
+where
+    T: Sync,
+    R: Send,
+    E: Send,
+    F: Fn(&T) -> Result<R, E> + Sync,
+{
+    let chunk_size = items.len().div_ceil(worker_count()).max(MIN_CHUNK_SIZE);
+    if items.len() <= chunk_size {
+        return items.iter().map(f).collect();
+    }
+
+    let f = &f;
+    thread::scope(|scope| {
+        let workers = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Result<Vec<_>, _>>()))
+            .collect::<Vec<_>>();
+
+        let mut results = Vec::with_capacity(items.len());
+        for worker in workers {
+            results.extend(worker.join().expect("worker not to panic")?);
+        }
+
+        Ok(results)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin_old::OutPoint;
+    use bitcoin_old::PackedLockTime;
+    use bitcoin_old::Sequence;
+    use bitcoin_old::TxIn;
+    use bitcoin_old::TxOut;
+    use bitcoin_old::Witness;
+    use rand::thread_rng;
+    use rand::Rng;
+
+    #[test]
+    fn results_are_in_order_of_items() {
+        let items = (0..1_000).collect::<Vec<u32>>();
+
+        let results = try_par_map(&items, |i| Ok::<_, ()>(i * 2)).unwrap();
+
+        assert_eq!(results, items.iter().map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn error_of_any_chunk_is_returned() {
+        let items = (0..1_000).collect::<Vec<u32>>();
+
+        let result = try_par_map(&items, |i| if *i == 999 { Err(*i) } else { Ok(*i) });
+
+        assert_eq!(result, Err(999));
+    }
+
+    #[test]
+    fn signed_cets_verify() {
+        let own_sk = random_sk();
+        let other_sk = random_sk();
+        let own_pk = PublicKey::from_secret_key(SECP256K1, &own_sk);
+        let other_pk = PublicKey::from_secret_key(SECP256K1, &other_sk);
+        let funding_script_pubkey = dlc::make_funding_redeemscript(&own_pk, &other_pk);
+
+        let cets = (0..100).map(dummy_cet).collect::<Vec<_>>();
+
+        let adaptor_sigs =
+            sign_cet_adaptor_sigs(&cets, &own_sk, &funding_script_pubkey, 100_000).unwrap();
+
+        verify_cet_adaptor_sigs(
+            &cets,
+            &adaptor_sigs,
+            &own_pk,
+            &funding_script_pubkey,
+            100_000,
+        )
+        .unwrap();
+        assert!(verify_cet_adaptor_sigs(
+            &cets,
+            &adaptor_sigs,
+            &other_pk,
+            &funding_script_pubkey,
+            100_000,
+        )
+        .is_err());
+    }
+
+    fn random_sk() -> SecretKey {
+        SecretKey::from_slice(&thread_rng().gen::<[u8; 32]>()).unwrap()
+    }
+
+    fn dummy_cet(output_value: u64) -> AdaptedCet {
+        let adaptor_sk = random_sk();
+
+        AdaptedCet {
+            cet: Transaction {
+                version: 2,
+                lock_time: PackedLockTime::ZERO,
+                input: vec![TxIn {
+                    previous_output: OutPoint::default(),
+                    script_sig: Script::new(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                }],
+                output: vec![TxOut {
+                    value: output_value,
+                    script_pubkey: Script::new(),
+                }],
+            },
+            adaptor_point: PublicKey::from_secret_key(SECP256K1, &adaptor_sk),
+        }
+    }
+}
@@ -0,0 +1,455 @@
+//! A client for the esplora API of electrs, which retries every request failing with a transient
+//! error.
+//!
+//! The on-chain wallet sync fetches the history of every script pubkey with many requests. We
+//! scan the script histories ourselves rather than with [`bdk_esplora::EsploraAsyncExt`], so that
+//! a failed request is retried on its own, instead of fetching the entire history again.
+
+use bdk::chain::BlockId;
+use bdk::chain::ConfirmationTimeHeightAnchor;
+use bdk::chain::TxGraph;
+use bdk_esplora::esplora_client;
+use bdk_esplora::esplora_client::OutputStatus;
+use bdk_esplora::esplora_client::Tx;
+use bdk_esplora::esplora_client::TxStatus;
+use bdk_esplora::EsploraAsyncExt;
+use bitcoin::OutPoint;
+use bitcoin::Script;
+use bitcoin::ScriptBuf;
+use bitcoin::Transaction;
+use bitcoin::TxOut;
+use bitcoin::Txid;
+use futures::stream::FuturesOrdered;
+use futures::TryStreamExt;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+/// How often we send a read request to electrs before giving up.
+const MAX_REQUEST_ATTEMPTS: u32 = 3;
+
+/// The delay before retrying a failed request, multiplied by the number of failed attempts.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Electrs returns at most this many transactions per page of a script history.
+const SCRIPT_HISTORY_PAGE_SIZE: usize = 25;
+
+pub(crate) type Error = esplora_client::Error;
+
+#[derive(Clone)]
+pub(crate) struct EsploraClient {
+    inner: esplora_client::AsyncClient,
+}
+
+impl EsploraClient {
+    pub fn new(inner: esplora_client::AsyncClient) -> Self {
+        Self { inner }
+    }
+
+    pub async fn broadcast(&self, tx: &Transaction) -> Result<(), Error> {
+        // Broadcasting is not retried, since electrs rejects a transaction it already knows.
+        self.inner.broadcast(tx).await
+    }
+
+    pub async fn scripthash_txs(
+        &self,
+        script: &Script,
+        last_seen: Option<Txid>,
+    ) -> Result<Vec<Tx>, Error> {
+        with_retry("scripthash_txs", || {
+            self.inner.scripthash_txs(script, last_seen)
+        })
+        .await
+    }
+
+    pub async fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>, Error> {
+        with_retry("get_tx", || self.inner.get_tx(txid)).await
+    }
+
+    pub async fn get_tx_status(&self, txid: &Txid) -> Result<TxStatus, Error> {
+        with_retry("get_tx_status", || self.inner.get_tx_status(txid)).await
+    }
+
+    pub async fn get_output_status(
+        &self,
+        txid: &Txid,
+        index: u64,
+    ) -> Result<Option<OutputStatus>, Error> {
+        with_retry("get_output_status", || {
+            self.inner.get_output_status(txid, index)
+        })
+        .await
+    }
+
+    /// Fetch the blocks needed to connect the `missing_heights` to our local chain.
+    ///
+    /// This only takes a few requests, hence it is retried as a whole.
+    pub async fn update_local_chain(
+        &self,
+        local_tip: bdk::chain::local_chain::CheckPoint,
+        missing_heights: Vec<u32>,
+    ) -> Result<bdk::chain::local_chain::Update, Error> {
+        with_retry("update_local_chain", || {
+            self.inner
+                .update_local_chain(local_tip.clone(), missing_heights.clone())
+        })
+        .await
+    }
+
+    /// Fetch the histories of the script pubkeys of every keychain, until `stop_gap` consecutive
+    /// script pubkeys without transactions are found.
+    ///
+    /// Up to `parallel_requests` script histories are fetched at a time. Returns the transactions
+    /// found and the index of the last script pubkey with transactions per keychain.
+    pub async fn full_scan<K: Ord + Clone>(
+        &self,
+        keychain_spks: BTreeMap<K, impl IntoIterator<Item = (u32, ScriptBuf)>>,
+        stop_gap: usize,
+        parallel_requests: usize,
+    ) -> Result<(TxGraph<ConfirmationTimeHeightAnchor>, BTreeMap<K, u32>), Error> {
+        let parallel_requests = parallel_requests.max(1);
+        let stop_gap = stop_gap.max(1);
+
+        let mut graph = TxGraph::<ConfirmationTimeHeightAnchor>::default();
+        let mut last_active_indices = BTreeMap::<K, u32>::new();
+
+        for (keychain, spks) in keychain_spks {
+            let mut spks = spks.into_iter();
+            let mut last_index = None;
+            let mut last_active_index = None;
+
+            loop {
+                let histories = spks
+                    .by_ref()
+                    .take(parallel_requests)
+                    .map(|(index, spk)| async move {
+                        let txs = self.script_history(&spk).await?;
+                        Ok::<_, Error>((index, txs))
+                    })
+                    .collect::<FuturesOrdered<_>>();
+
+                if histories.is_empty() {
+                    break;
+                }
+
+                for (index, txs) in histories.try_collect::<Vec<_>>().await? {
+                    last_index = Some(index);
+                    if !txs.is_empty() {
+                        last_active_index = Some(index);
+                    }
+
+                    for tx in txs {
+                        insert_tx(&mut graph, tx);
+                    }
+                }
+
+                if stop_gap_reached(last_index, last_active_index, stop_gap) {
+                    break;
+                }
+            }
+
+            if let Some(last_active_index) = last_active_index {
+                last_active_indices.insert(keychain, last_active_index);
+            }
+        }
+
+        Ok((graph, last_active_indices))
+    }
+
+    /// Fetch the histories of the given script pubkeys, the status of the given transactions and
+    /// whether the given outputs have been spent.
+    pub async fn sync(
+        &self,
+        spks: Vec<ScriptBuf>,
+        txids: Vec<Txid>,
+        outpoints: Vec<OutPoint>,
+        parallel_requests: usize,
+    ) -> Result<TxGraph<ConfirmationTimeHeightAnchor>, Error> {
+        let parallel_requests = parallel_requests.max(1);
+
+        let spks = spks
+            .into_iter()
+            .enumerate()
+            .map(|(index, spk)| (index as u32, spk));
+        let (mut graph, _) = self
+            .full_scan(BTreeMap::from([((), spks)]), usize::MAX, parallel_requests)
+            .await?;
+
+        let txids = txids
+            .into_iter()
+            .filter(|txid| graph.get_tx(*txid).is_none())
+            .collect::<Vec<_>>();
+        for txids in txids.chunks(parallel_requests) {
+            let statuses = txids
+                .iter()
+                .map(|txid| async move {
+                    let status = self.get_tx_status(txid).await?;
+                    Ok::<_, Error>((*txid, status))
+                })
+                .collect::<FuturesOrdered<_>>()
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            for (txid, status) in statuses {
+                if let Some(anchor) = anchor_from_status(&status) {
+                    let _ = graph.insert_anchor(txid, anchor);
+                }
+            }
+        }
+
+        for outpoint in outpoints {
+            self.insert_missing_tx(&mut graph, outpoint.txid).await?;
+
+            let spending_txid = self
+                .get_output_status(&outpoint.txid, outpoint.vout as u64)
+                .await?
+                .and_then(|status| status.txid);
+            if let Some(spending_txid) = spending_txid {
+                self.insert_missing_tx(&mut graph, spending_txid).await?;
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Fetch all pages of the history of a script pubkey.
+    async fn script_history(&self, spk: &Script) -> Result<Vec<Tx>, Error> {
+        let mut history = Vec::new();
+        let mut last_seen = None;
+        loop {
+            let txs = self.scripthash_txs(spk, last_seen).await?;
+            let is_last_page = txs.len() < SCRIPT_HISTORY_PAGE_SIZE;
+
+            last_seen = txs.last().map(|tx| tx.txid);
+            history.extend(txs);
+
+            if is_last_page {
+                return Ok(history);
+            }
+        }
+    }
+
+    async fn insert_missing_tx(
+        &self,
+        graph: &mut TxGraph<ConfirmationTimeHeightAnchor>,
+        txid: Txid,
+    ) -> Result<(), Error> {
+        if graph.get_tx(txid).is_some() {
+            return Ok(());
+        }
+
+        if let Some(tx) = self.get_tx(&txid).await? {
+            let _ = graph.insert_tx(tx);
+        }
+
+        let status = self.get_tx_status(&txid).await?;
+        if let Some(anchor) = anchor_from_status(&status) {
+            let _ = graph.insert_anchor(txid, anchor);
+        }
+
+        Ok(())
+    }
+}
+
+/// Insert a transaction from a script history into the graph, together with the outputs it
+/// spends, so that we can compute its fee.
+fn insert_tx(graph: &mut TxGraph<ConfirmationTimeHeightAnchor>, tx: Tx) {
+    let _ = graph.insert_tx(tx.to_tx());
+
+    if let Some(anchor) = anchor_from_status(&tx.status) {
+        let _ = graph.insert_anchor(tx.txid, anchor);
+    }
+
+    for vin in tx.vin {
+        if let Some(prevout) = vin.prevout {
+            let outpoint = OutPoint {
+                txid: vin.txid,
+                vout: vin.vout,
+            };
+            let txout = TxOut {
+                script_pubkey: prevout.scriptpubkey,
+                value: prevout.value,
+            };
+
+            let _ = graph.insert_txout(outpoint, txout);
+        }
+    }
+}
+
+fn anchor_from_status(status: &TxStatus) -> Option<ConfirmationTimeHeightAnchor> {
+    match status {
+        TxStatus {
+            block_height: Some(height),
+            block_hash: Some(hash),
+            block_time: Some(time),
+            ..
+        } => Some(ConfirmationTimeHeightAnchor {
+            anchor_block: BlockId {
+                height: *height,
+                hash: *hash,
+            },
+            confirmation_height: *height,
+            confirmation_time: *time,
+        }),
+        _ => None,
+    }
+}
+
+/// Whether we found `stop_gap` script pubkeys without transactions after the last one with
+/// transactions.
+fn stop_gap_reached(
+    last_index: Option<u32>,
+    last_active_index: Option<u32>,
+    stop_gap: usize,
+) -> bool {
+    let Some(last_index) = last_index else {
+        return false;
+    };
+    let stop_gap = u32::try_from(stop_gap).unwrap_or(u32::MAX);
+
+    match last_active_index {
+        Some(last_active_index) => last_index >= last_active_index.saturating_add(stop_gap),
+        None => last_index.saturating_add(1) >= stop_gap,
+    }
+}
+
+/// Whether a failed request to electrs may succeed if we send it again, e.g. because it timed
+/// out or electrs is overloaded.
+pub(crate) fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::Reqwest(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status()
+                    .map(|status| is_transient_status(status.as_u16()))
+                    .unwrap_or(false)
+        }
+        Error::Ureq(ureq::Error::Status(status, _)) => is_transient_status(*status),
+        Error::Ureq(ureq::Error::Transport(_)) | Error::UreqTransport(_) => true,
+        Error::HttpResponse { status, .. } => is_transient_status(*status),
+        Error::Io(_) => true,
+        _ => false,
+    }
+}
+
+fn is_transient_status(status: u16) -> bool {
+    // Too many requests, or a server error.
+    status == 429 || (500..600).contains(&status)
+}
+
+/// Send an idempotent request to electrs, retrying it with a linear backoff if it fails with a
+/// transient error.
+async fn with_retry<T, F, Fut>(operation: &str, mut request: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match request().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < MAX_REQUEST_ATTEMPTS && is_transient(&e) => {
+                tracing::debug!(
+                    operation,
+                    attempt,
+                    "Request to electrs failed, retrying: {e}"
+                );
+                tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Blocking version of [`with_retry`].
+pub(crate) fn with_retry_blocking<T, F>(operation: &str, mut request: F) -> Result<T, Error>
+where
+    F: FnMut() -> Result<T, Error>,
+{
+    let mut attempt = 1;
+    loop {
+        match request() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < MAX_REQUEST_ATTEMPTS && is_transient(&e) => {
+                tracing::debug!(
+                    operation,
+                    attempt,
+                    "Request to electrs failed, retrying: {e}"
+                );
+                std::thread::sleep(RETRY_BACKOFF * attempt);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn retries_transient_error() {
+        let attempts = Cell::new(0);
+
+        let result = with_retry_blocking("test", || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < MAX_REQUEST_ATTEMPTS {
+                Err(server_error())
+            } else {
+                Ok(42)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.get(), MAX_REQUEST_ATTEMPTS);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let attempts = Cell::new(0);
+
+        let result: Result<(), _> = with_retry_blocking("test", || {
+            attempts.set(attempts.get() + 1);
+            Err(server_error())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), MAX_REQUEST_ATTEMPTS);
+    }
+
+    #[test]
+    fn does_not_retry_permanent_error() {
+        let attempts = Cell::new(0);
+
+        let result: Result<(), _> = with_retry_blocking("test", || {
+            attempts.set(attempts.get() + 1);
+            Err(Error::HttpResponse {
+                status: 400,
+                message: "Invalid hex string".to_string(),
+            })
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn stop_gap_is_reached_after_unused_script_pubkeys() {
+        assert!(!stop_gap_reached(None, None, 20));
+        assert!(!stop_gap_reached(Some(18), None, 20));
+        assert!(stop_gap_reached(Some(19), None, 20));
+        assert!(!stop_gap_reached(Some(24), Some(5), 20));
+        assert!(stop_gap_reached(Some(25), Some(5), 20));
+        assert!(!stop_gap_reached(Some(1_000), Some(5), usize::MAX));
+    }
+
+    fn server_error() -> Error {
+        Error::HttpResponse {
+            status: 503,
+            message: "Service unavailable".to_string(),
+        }
+    }
+}
@@ -1,45 +1,100 @@
+#[cfg(feature = "node")]
 use crate::dlc::TracingLogger;
+#[cfg(feature = "node")]
 use crate::message_handler::TenTenOneMessageHandler;
+#[cfg(feature = "node")]
 use crate::networking::DynamicSocketDescriptor;
+#[cfg(feature = "node")]
 use dlc_custom_signer::CustomKeysManager;
+#[cfg(feature = "node")]
 use lightning::ln::peer_handler::ErroringMessageHandler;
+#[cfg(feature = "node")]
 use lightning::ln::peer_handler::IgnoringMessageHandler;
 use std::fmt;
+#[cfg(feature = "node")]
 use std::sync::Arc;
 
+#[cfg(feature = "node")]
 mod blockchain;
+#[cfg(feature = "node")]
 mod dlc_custom_signer;
+#[cfg(feature = "node")]
 mod dlc_wallet;
+#[cfg(feature = "node")]
+mod esplora;
+#[cfg(feature = "node")]
 mod fee_rate_estimator;
+#[cfg(feature = "node")]
 mod on_chain_wallet;
+#[cfg(feature = "node")]
 mod shadow;
 
-pub mod bitcoin_conversion;
-pub mod bitmex_client;
 pub mod cfd;
 pub mod commons;
+
+#[cfg(feature = "node")]
+pub mod bitcoin_conversion;
+#[cfg(feature = "node")]
+pub mod bitmex_client;
+#[cfg(feature = "node")]
 pub mod config;
+#[cfg(feature = "node")]
 pub mod dlc;
+#[cfg(feature = "node")]
 pub mod dlc_message;
+#[cfg(feature = "node")]
 pub mod message_handler;
+#[cfg(feature = "node")]
 pub mod networking;
+#[cfg(feature = "node")]
 pub mod node;
+#[cfg(feature = "node")]
 pub mod seed;
+#[cfg(feature = "node")]
 pub mod storage;
+#[cfg(feature = "node")]
 pub mod transaction;
 
 pub use commons::FundingFeeEvent;
+#[cfg(feature = "node")]
 pub use config::CONFIRMATION_TARGET;
+#[cfg(feature = "node")]
 pub use dlc::ContractDetails;
+#[cfg(feature = "node")]
 pub use dlc::DlcChannelDetails;
+#[cfg(feature = "node")]
 pub use lightning;
+#[cfg(feature = "node")]
 pub use on_chain_wallet::ConfirmationStatus;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::Deposit;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::DepositConfidence;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::DepositStatus;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::DepositTracker;
+#[cfg(feature = "node")]
 pub use on_chain_wallet::FeeConfig;
+#[cfg(feature = "node")]
 pub use on_chain_wallet::TransactionDetails;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::WalletDescriptors;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::Watch;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::WatchEvent;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::WatchTracker;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::WatchedStatus;
+#[cfg(feature = "node")]
+pub use on_chain_wallet::WatchedTransaction;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "node"))]
 mod tests;
 
+#[cfg(feature = "node")]
 pub(crate) type PeerManager<D> = lightning::ln::peer_handler::PeerManager<
     DynamicSocketDescriptor,
     Arc<ErroringMessageHandler>,
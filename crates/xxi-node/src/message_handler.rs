@@ -2,10 +2,13 @@ use crate::bitcoin_conversion::to_secp_pk_30;
 use crate::commons::FilledWith;
 use crate::commons::Order;
 use crate::commons::OrderReason;
+use crate::dlc_message::SerializedDlcMessage;
 use crate::node::event::NodeEvent;
 use crate::node::event::NodeEventHandler;
+use crate::node::Storage;
 use anyhow::Result;
 use bitcoin::SignedAmount;
+use dlc_manager::DlcChannelId;
 use dlc_manager::ReferenceId;
 use dlc_messages::channel::AcceptChannel;
 use dlc_messages::channel::CollaborativeCloseOffer;
@@ -73,6 +76,9 @@ pub struct TenTenOneMessageHandler {
     msg_events: Mutex<VecDeque<(PublicKey, WireMessage)>>,
     msg_received: Mutex<Vec<(PublicKey, TenTenOneMessage)>>,
     segment_readers: Mutex<HashMap<PublicKey, SegmentReader>>,
+    /// The protocol version agreed on with each connected peer which announced its
+    /// [`ProtocolVersion`].
+    protocol_versions: Mutex<HashMap<PublicKey, u16>>,
 }
 
 impl TenTenOneMessageHandler {
@@ -82,8 +88,72 @@ impl TenTenOneMessageHandler {
             msg_events: Mutex::new(Default::default()),
             msg_received: Mutex::new(vec![]),
             segment_readers: Mutex::new(Default::default()),
+            protocol_versions: Mutex::new(Default::default()),
         }
     }
+
+    /// The protocol version agreed on with the peer.
+    ///
+    /// Peers which did not announce their [`ProtocolVersion`] predate the negotiation and are
+    /// assumed to speak [`LEGACY_PROTOCOL_VERSION`].
+    pub fn peer_protocol_version(&self, peer: &PublicKey) -> u16 {
+        self.protocol_versions
+            .lock()
+            .expect("to get lock")
+            .get(peer)
+            .copied()
+            .unwrap_or(LEGACY_PROTOCOL_VERSION)
+    }
+
+    /// Whether the peer knows about the [`CollaborativeCloseFee`] negotiation.
+    pub fn supports_collaborative_close_fee(&self, peer: &PublicKey) -> bool {
+        self.peer_protocol_version(peer) > LEGACY_PROTOCOL_VERSION
+    }
+
+    /// Whether the peer knows about channel migrations, i.e. [`OrderReason::ChannelMigration`]
+    /// and [`Message::AsyncMatch`].
+    ///
+    /// [`Message::AsyncMatch`]: crate::commons::Message::AsyncMatch
+    pub fn supports_channel_migration(&self, peer: &PublicKey) -> bool {
+        self.peer_protocol_version(peer) > LEGACY_PROTOCOL_VERSION
+    }
+
+    fn handle_protocol_version(
+        &self,
+        peer: &PublicKey,
+        theirs: ProtocolVersion,
+    ) -> Result<(), LightningError> {
+        match ProtocolVersion::ours().negotiate(theirs) {
+            Ok(version) => {
+                tracing::info!(%peer, ?theirs, version, "Agreed on protocol version");
+
+                self.protocol_versions
+                    .lock()
+                    .expect("to get lock")
+                    .insert(*peer, version);
+
+                Ok(())
+            }
+            Err(mismatch) => Err(self.reject_incompatible_peer(peer, mismatch)),
+        }
+    }
+
+    /// Let the rest of the node know that we can't talk to the peer and disconnect it, instead of
+    /// stalling on messages one of us does not understand.
+    fn reject_incompatible_peer(
+        &self,
+        peer: &PublicKey,
+        mismatch: ProtocolVersionMismatch,
+    ) -> LightningError {
+        tracing::warn!(%peer, ?mismatch, "Disconnecting peer with incompatible protocol version");
+
+        self.handler.publish(NodeEvent::ProtocolVersionMismatch {
+            peer: to_secp_pk_30(*peer),
+            mismatch,
+        });
+
+        to_ln_error(mismatch, "Incompatible protocol version")
+    }
 }
 
 /// Copied from the IgnoringMessageHandler
@@ -106,13 +176,25 @@ impl OnionMessageHandler for TenTenOneMessageHandler {
     ) -> Result<(), ()> {
         tracing::info!(%their_node_id, inbound, "Peer connected!");
 
+        // Both sides announce their protocol version, so that each of them can tell whether they
+        // are compatible.
+        self.msg_events.lock().expect("to get lock").push_back((
+            *their_node_id,
+            WireMessage::ProtocolVersion(ProtocolVersion::ours()),
+        ));
+
         self.handler.publish(NodeEvent::Connected {
             peer: to_secp_pk_30(*their_node_id),
         });
 
         Ok(())
     }
-    fn peer_disconnected(&self, _their_node_id: &PublicKey) {}
+    fn peer_disconnected(&self, their_node_id: &PublicKey) {
+        self.protocol_versions
+            .lock()
+            .expect("to get lock")
+            .remove(their_node_id);
+    }
     fn provided_node_features(&self) -> NodeFeatures {
         NodeFeatures::empty()
     }
@@ -126,6 +208,124 @@ pub enum WireMessage {
     Message(TenTenOneMessage),
     SegmentStart(SegmentStart),
     SegmentChunk(SegmentChunk),
+    CollaborativeCloseFee(CollaborativeCloseFee),
+    ProtocolVersion(ProtocolVersion),
+}
+
+/// The version of the protocol spoken through the custom messages, i.e. which messages a node
+/// understands and how they are encoded.
+///
+/// Bump it whenever a message is added or its encoding changes incompatibly, and keep talking to
+/// peers on the previous version, e.g. by not sending them the new message.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+/// The protocol version of peers which do not announce one, because they predate the negotiation.
+/// They do not know about the [`CollaborativeCloseFee`] negotiation or channel migrations either.
+pub const LEGACY_PROTOCOL_VERSION: u16 = 1;
+
+/// The oldest protocol version we still talk to. Only the immediately-previous version is kept
+/// compatible.
+pub const MIN_PROTOCOL_VERSION: u16 = PROTOCOL_VERSION - 1;
+
+/// Announced to every peer once connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolVersion {
+    pub version: u16,
+    /// The oldest version of a peer this node still talks to.
+    pub min_version: u16,
+}
+
+impl ProtocolVersion {
+    pub fn ours() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+        }
+    }
+
+    /// The protocol version to speak with a peer announcing `theirs`, i.e. the newer version both
+    /// of us understand.
+    pub fn negotiate(self, theirs: ProtocolVersion) -> Result<u16, ProtocolVersionMismatch> {
+        let version = self.version.min(theirs.version);
+
+        if version < theirs.min_version {
+            return Err(ProtocolVersionMismatch::Outdated {
+                version: self.version,
+                peer_min_version: theirs.min_version,
+            });
+        }
+
+        if version < self.min_version {
+            return Err(ProtocolVersionMismatch::PeerOutdated {
+                peer_version: theirs.version,
+                min_version: self.min_version,
+            });
+        }
+
+        Ok(version)
+    }
+}
+
+/// Why we can't talk to a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+pub enum ProtocolVersionMismatch {
+    /// We are too old for the peer, i.e. we have to be updated.
+    #[error("protocol version {version} is no longer supported by the peer, which requires at least version {peer_min_version}")]
+    Outdated { version: u16, peer_min_version: u16 },
+    /// The peer is too old for us, i.e. it has to be updated.
+    #[error("protocol version {peer_version} of the peer is no longer supported, at least version {min_version} is required")]
+    PeerOutdated { peer_version: u16, min_version: u16 },
+}
+
+/// A message of the fee negotiation which precedes the collaborative close of a DLC channel.
+///
+/// The party closing the channel proposes a fee rate. The counterparty either accepts it, if it is
+/// within its bounds, or counters with the closest fee rate within its bounds. The collaborative
+/// close offer is only sent once both parties agreed on a fee rate, or with the fee rate of the
+/// contract if the counter is out of the bounds of the proposing party or the counterparty does
+/// not respond in time. The proposing party then bumps the close transaction to the agreed fee
+/// rate via CPFP.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollaborativeCloseFee {
+    Propose {
+        channel_id: DlcChannelId,
+        fee_rate_sats_per_vb: u64,
+    },
+    Counter {
+        channel_id: DlcChannelId,
+        fee_rate_sats_per_vb: u64,
+    },
+    Accept {
+        channel_id: DlcChannelId,
+        fee_rate_sats_per_vb: u64,
+    },
+}
+
+impl CollaborativeCloseFee {
+    pub fn channel_id(&self) -> DlcChannelId {
+        match self {
+            CollaborativeCloseFee::Propose { channel_id, .. }
+            | CollaborativeCloseFee::Counter { channel_id, .. }
+            | CollaborativeCloseFee::Accept { channel_id, .. } => *channel_id,
+        }
+    }
+
+    pub fn fee_rate_sats_per_vb(&self) -> u64 {
+        match self {
+            CollaborativeCloseFee::Propose {
+                fee_rate_sats_per_vb,
+                ..
+            }
+            | CollaborativeCloseFee::Counter {
+                fee_rate_sats_per_vb,
+                ..
+            }
+            | CollaborativeCloseFee::Accept {
+                fee_rate_sats_per_vb,
+                ..
+            } => *fee_rate_sats_per_vb,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -184,7 +384,9 @@ impl TenTenOneMessage {
             | TenTenOneMessage::SettleConfirm(TenTenOneSettleConfirm { order_reason, .. })
             | TenTenOneMessage::SettleFinalize(TenTenOneSettleFinalize { order_reason, .. }) => {
                 match order_reason {
-                    OrderReason::Manual => TenTenOneMessageType::Trade,
+                    OrderReason::Manual | OrderReason::ChannelMigration => {
+                        TenTenOneMessageType::Trade
+                    }
                     OrderReason::Expired => TenTenOneMessageType::Expire,
                     OrderReason::CoordinatorLiquidated | OrderReason::TraderLiquidated => {
                         TenTenOneMessageType::Liquidate
@@ -204,6 +406,29 @@ impl TenTenOneMessage {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TenTenOneReject {
     pub reject: Reject,
+    /// The order the rejected offer was meant to fill, if known.
+    #[serde(default)]
+    pub order_id: Option<Uuid>,
+    #[serde(default)]
+    pub reason: RejectReason,
+}
+
+/// Why an offer was rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RejectReason {
+    /// The reason was not recorded, e.g. for rejections stored before reasons were introduced.
+    #[default]
+    Unknown,
+    /// The offer does not match the order it was meant to fill, e.g. it is for an unexpected
+    /// oracle event.
+    InvalidOffer,
+    /// The offer is valid, but it could not be accepted, e.g. because of the state of the DLC
+    /// channel.
+    AcceptFailed,
+    /// The offer was still pending when the app was restarted or reconnected.
+    Stale,
+    /// The offer was withdrawn by the party which made it, e.g. because sending it failed.
+    Cancelled,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -360,6 +585,17 @@ impl TenTenOneMessageHandler {
         }
     }
 
+    /// Send a [`CollaborativeCloseFee`] message to the peer with the given node id.
+    ///
+    /// The fee negotiation is not part of the DLC protocol, hence these messages are neither
+    /// segmented nor processed by the DLC manager.
+    pub fn send_collaborative_close_fee(&self, node_id: PublicKey, msg: CollaborativeCloseFee) {
+        self.msg_events
+            .lock()
+            .expect("to get lock")
+            .push_back((node_id, WireMessage::CollaborativeCloseFee(msg)));
+    }
+
     /// Returns whether the message handler has any message to be sent.
     pub fn has_pending_messages(&self) -> bool {
         !self.msg_events.lock().expect("to get lock").is_empty()
@@ -380,6 +616,10 @@ impl CustomMessageReader for TenTenOneMessageHandler {
             segmentation::SEGMENT_CHUNK_TYPE => {
                 WireMessage::SegmentChunk(Readable::read(&mut buffer)?)
             }
+            COLLABORATIVE_CLOSE_FEE_TYPE => {
+                WireMessage::CollaborativeCloseFee(Readable::read(&mut buffer)?)
+            }
+            PROTOCOL_VERSION_TYPE => WireMessage::ProtocolVersion(Readable::read(&mut buffer)?),
             _ => return read_tentenone_message(msg_type, buffer),
         };
 
@@ -395,6 +635,19 @@ impl CustomMessageHandler for TenTenOneMessageHandler {
         msg: WireMessage,
         org: &PublicKey,
     ) -> Result<(), LightningError> {
+        // Peers announce their protocol version before sending anything else, hence a peer without
+        // one predates the negotiation. We can only tell once it sends us another message.
+        let version = self.peer_protocol_version(org);
+        if version < MIN_PROTOCOL_VERSION && !matches!(msg, WireMessage::ProtocolVersion(_)) {
+            return Err(self.reject_incompatible_peer(
+                org,
+                ProtocolVersionMismatch::PeerOutdated {
+                    peer_version: version,
+                    min_version: MIN_PROTOCOL_VERSION,
+                },
+            ));
+        }
+
         let mut segment_readers = self.segment_readers.lock().expect("to get lock");
         let segment_reader = segment_readers.entry(*org).or_default();
 
@@ -446,6 +699,13 @@ impl CustomMessageHandler for TenTenOneMessageHandler {
             WireMessage::SegmentStart(s) => segment_reader
                 .process_segment_start(s)
                 .map_err(|e| to_ln_error(e, "Error processing segment start"))?,
+            WireMessage::CollaborativeCloseFee(msg) => {
+                self.handler.publish(NodeEvent::CollaborativeCloseFee {
+                    peer: to_secp_pk_30(*org),
+                    msg,
+                })
+            }
+            WireMessage::ProtocolVersion(theirs) => self.handle_protocol_version(org, theirs)?,
             WireMessage::SegmentChunk(_) => {
                 return Err(LightningError {
                     err: "Received a SegmentChunk while not expecting one.".to_string(),
@@ -507,6 +767,41 @@ pub fn tentenone_message_name(msg: &TenTenOneMessage) -> String {
     name.to_string()
 }
 
+/// Remember `msg` as the last outbound DLC message to `peer`.
+///
+/// Only the last outbound DLC message per peer is kept. It is resent whenever the peer
+/// reconnects, in case the peer did not process it before disconnecting.
+pub fn store_last_outbound_dlc_message<N: Storage>(
+    storage: &N,
+    peer: bitcoin::secp256k1::PublicKey,
+    msg: &TenTenOneMessage,
+) -> Result<()> {
+    let serialized_msg = SerializedDlcMessage::try_from(msg)?;
+    storage.upsert_last_outbound_dlc_message(peer, serialized_msg)
+}
+
+/// The last outbound DLC message to `peer`, if any.
+pub fn get_last_outbound_dlc_message<N: Storage>(
+    storage: &N,
+    peer: &bitcoin::secp256k1::PublicKey,
+) -> Result<Option<TenTenOneMessage>> {
+    storage
+        .get_last_outbound_dlc_message(peer)?
+        .map(|serialized_msg| TenTenOneMessage::try_from(&serialized_msg))
+        .transpose()
+}
+
+/// The last outbound DLC message of every peer.
+pub fn list_last_outbound_dlc_messages<N: Storage>(
+    storage: &N,
+) -> Result<Vec<(bitcoin::secp256k1::PublicKey, TenTenOneMessage)>> {
+    storage
+        .all_last_outbound_dlc_messages()?
+        .into_iter()
+        .map(|(peer, serialized_msg)| Ok((peer, TenTenOneMessage::try_from(&serialized_msg)?)))
+        .collect()
+}
+
 impl TenTenOneMessage {
     /// Builds a 10101 message from the rust-dlc response message. Note, a response can never return
     /// an offer so if an offer is passed the function will panic. This is most likely not a future
@@ -595,8 +890,13 @@ impl TenTenOneMessage {
             ) => TenTenOneMessage::CollaborativeCloseOffer(TenTenOneCollaborativeCloseOffer {
                 collaborative_close_offer,
             }),
+            // rust-dlc only responds with a reject if it could not process the offer.
             (Message::Channel(ChannelMessage::Reject(reject)), None) => {
-                TenTenOneMessage::Reject(TenTenOneReject { reject })
+                TenTenOneMessage::Reject(TenTenOneReject {
+                    reject,
+                    order_id: None,
+                    reason: RejectReason::InvalidOffer,
+                })
             }
             (_, _) => {
                 unreachable!()
@@ -743,6 +1043,7 @@ impl TenTenOneMessage {
             })
             | TenTenOneMessage::Reject(TenTenOneReject {
                 reject: Reject { reference_id, .. },
+                ..
             }) => *reference_id,
         }
     }
@@ -812,7 +1113,9 @@ impl From<TenTenOneMessage> for ChannelMessage {
             TenTenOneMessage::CollaborativeCloseOffer(TenTenOneCollaborativeCloseOffer {
                 collaborative_close_offer,
             }) => ChannelMessage::CollaborativeCloseOffer(collaborative_close_offer),
-            TenTenOneMessage::Reject(TenTenOneReject { reject }) => ChannelMessage::Reject(reject),
+            TenTenOneMessage::Reject(TenTenOneReject { reject, .. }) => {
+                ChannelMessage::Reject(reject)
+            }
         }
     }
 }
@@ -831,6 +1134,31 @@ pub fn read_uuid<R: ::std::io::Read>(reader: &mut R) -> std::result::Result<Uuid
     Uuid::from_str(&uuid).map_err(|_| DecodeError::InvalidValue)
 }
 
+/// Writes an optional uuid to the given writer.
+pub fn write_optional_uuid<W: Writer>(
+    uuid: &Option<Uuid>,
+    writer: &mut W,
+) -> std::result::Result<(), ::std::io::Error> {
+    match uuid {
+        Some(uuid) => {
+            1u8.write(writer)?;
+            write_uuid(uuid, writer)
+        }
+        None => 0u8.write(writer),
+    }
+}
+
+/// Reads an optional uuid from the given reader.
+pub fn read_optional_uuid<R: ::std::io::Read>(
+    reader: &mut R,
+) -> std::result::Result<Option<Uuid>, DecodeError> {
+    match <u8 as Readable>::read(reader)? {
+        0 => Ok(None),
+        1 => Ok(Some(read_uuid(reader)?)),
+        _ => Err(DecodeError::InvalidValue),
+    }
+}
+
 /// Writes a [`FundingFeeEvent`] to the given writer.
 pub fn write_funding_fee_event<W: Writer>(
     input: &FundingFeeEvent,
@@ -934,7 +1262,7 @@ macro_rules! impl_serde_writeable {
     };
 }
 
-impl_type_writeable_for_enum!(WireMessage, { Message, SegmentStart, SegmentChunk });
+impl_type_writeable_for_enum!(WireMessage, { Message, SegmentStart, SegmentChunk, CollaborativeCloseFee, ProtocolVersion });
 impl_type_writeable_for_enum!(TenTenOneMessage,
 {
     Reject,
@@ -958,7 +1286,7 @@ impl_type_writeable_for_enum!(TenTenOneMessage,
     CollaborativeCloseOffer
 });
 
-impl_dlc_writeable!(TenTenOneReject, { (reject, writeable) });
+impl_dlc_writeable!(TenTenOneReject, { (reject, writeable), (order_id, {cb_writeable, write_optional_uuid, read_optional_uuid}), (reason, writeable) });
 impl_dlc_writeable!(TenTenOneOfferChannel, { (filled_with, writeable), (offer_channel, writeable) });
 impl_dlc_writeable!(TenTenOneAcceptChannel, { (order_id, {cb_writeable, write_uuid, read_uuid}), (accept_channel, writeable) });
 impl_dlc_writeable!(TenTenOneSignChannel, { (order_id, {cb_writeable, write_uuid, read_uuid}), (sign_channel, writeable) });
@@ -1012,9 +1340,16 @@ impl_type!(
     43022
 );
 
+impl_type!(COLLABORATIVE_CLOSE_FEE_TYPE, CollaborativeCloseFee, 43038);
+// Odd, so that peers predating the negotiation ignore it instead of disconnecting.
+impl_type!(PROTOCOL_VERSION_TYPE, ProtocolVersion, 43039);
+
 impl_serde_writeable!(Order);
 impl_serde_writeable!(FilledWith);
 impl_serde_writeable!(OrderReason);
+impl_serde_writeable!(RejectReason);
+impl_serde_writeable!(CollaborativeCloseFee);
+impl_serde_writeable!(ProtocolVersion);
 
 fn read_tentenone_message<R: ::std::io::Read>(
     msg_type: u16,
@@ -1054,6 +1389,7 @@ mod tests {
     use crate::commons::OrderState;
     use crate::commons::OrderType;
     use crate::node::event::NodeEventHandler;
+    use crate::node::InMemoryStore;
     use anyhow::anyhow;
     use anyhow::Result;
     use dlc_manager::DlcChannelId;
@@ -1079,6 +1415,8 @@ mod tests {
                 timestamp: 0,
                 reference_id: None,
             },
+            order_id: Some(Uuid::default()),
+            reason: RejectReason::InvalidOffer,
         };
 
         let json_msg = handler_read_test(reject);
@@ -1122,6 +1460,105 @@ mod tests {
         assert_eq!(original, result);
     }
 
+    #[test]
+    fn protocol_version_roundtrip() {
+        let json_msg = handler_read_test(ProtocolVersion::ours()).unwrap();
+
+        assert_eq!(
+            json_msg,
+            format!(
+                r#"{{"ProtocolVersion":{{"version":{PROTOCOL_VERSION},"min_version":{MIN_PROTOCOL_VERSION}}}}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn previous_protocol_version_is_compatible() {
+        let previous = ProtocolVersion {
+            version: PROTOCOL_VERSION - 1,
+            min_version: PROTOCOL_VERSION - 2,
+        };
+
+        assert_eq!(
+            ProtocolVersion::ours().negotiate(previous),
+            Ok(PROTOCOL_VERSION - 1)
+        );
+        assert_eq!(
+            previous.negotiate(ProtocolVersion::ours()),
+            Ok(PROTOCOL_VERSION - 1)
+        );
+    }
+
+    #[test]
+    fn incompatible_protocol_versions_tell_who_is_outdated() {
+        let ancient = ProtocolVersion {
+            version: 1,
+            min_version: 1,
+        };
+        let latest = ProtocolVersion {
+            version: 5,
+            min_version: 4,
+        };
+
+        assert_eq!(
+            latest.negotiate(ancient),
+            Err(ProtocolVersionMismatch::PeerOutdated {
+                peer_version: 1,
+                min_version: 4
+            })
+        );
+        assert_eq!(
+            ancient.negotiate(latest),
+            Err(ProtocolVersionMismatch::Outdated {
+                version: 1,
+                peer_min_version: 4
+            })
+        );
+    }
+
+    #[test]
+    fn peers_are_legacy_until_they_announce_their_protocol_version() {
+        let handler = TenTenOneMessageHandler::new(Arc::new(NodeEventHandler::new()));
+        let peer = dummy_pubkey();
+
+        assert_eq!(
+            handler.peer_protocol_version(&peer),
+            LEGACY_PROTOCOL_VERSION
+        );
+        assert!(!handler.supports_collaborative_close_fee(&peer));
+
+        handler
+            .handle_custom_message(WireMessage::ProtocolVersion(ProtocolVersion::ours()), &peer)
+            .unwrap();
+
+        assert_eq!(handler.peer_protocol_version(&peer), PROTOCOL_VERSION);
+        assert!(handler.supports_collaborative_close_fee(&peer));
+    }
+
+    #[test]
+    fn incompatible_peer_is_disconnected() {
+        let events = Arc::new(NodeEventHandler::new());
+        let mut receiver = events.subscribe();
+        let handler = TenTenOneMessageHandler::new(events);
+
+        let result = handler.handle_custom_message(
+            WireMessage::ProtocolVersion(ProtocolVersion {
+                version: PROTOCOL_VERSION + 2,
+                min_version: PROTOCOL_VERSION + 1,
+            }),
+            &dummy_pubkey(),
+        );
+
+        assert!(result.is_err());
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(NodeEvent::ProtocolVersionMismatch {
+                mismatch: ProtocolVersionMismatch::Outdated { .. },
+                ..
+            })
+        ));
+    }
+
     fn dummy_filled_with() -> FilledWith {
         FilledWith {
             order_id: Default::default(),
@@ -1149,6 +1586,8 @@ mod tests {
             order_state: OrderState::Open,
             order_reason: OrderReason::Manual,
             stable: false,
+            display_quantity: None,
+            contract_expiry: None,
         }
     }
 
@@ -1188,4 +1627,55 @@ mod tests {
         let msg = serde_json::to_string(&message)?;
         Ok(msg)
     }
+
+    fn peer() -> bitcoin::secp256k1::PublicKey {
+        bitcoin::secp256k1::PublicKey::from_str(
+            "0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166",
+        )
+        .unwrap()
+    }
+
+    fn reject(channel_id: DlcChannelId) -> TenTenOneMessage {
+        TenTenOneMessage::Reject(TenTenOneReject {
+            reject: Reject {
+                channel_id,
+                timestamp: 0,
+                reference_id: None,
+            },
+            order_id: None,
+            reason: RejectReason::Unknown,
+        })
+    }
+
+    #[test]
+    fn last_outbound_dlc_message_is_replaced() {
+        let storage = InMemoryStore::default();
+
+        store_last_outbound_dlc_message(&storage, peer(), &reject([1u8; 32])).unwrap();
+        store_last_outbound_dlc_message(&storage, peer(), &reject([2u8; 32])).unwrap();
+
+        let msg = get_last_outbound_dlc_message(&storage, &peer())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            msg,
+            TenTenOneMessage::Reject(TenTenOneReject { reject, .. }) if reject.channel_id == [2u8; 32]
+        ));
+        assert_eq!(list_last_outbound_dlc_messages(&storage).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn no_last_outbound_dlc_message_after_deletion() {
+        let storage = InMemoryStore::default();
+
+        store_last_outbound_dlc_message(&storage, peer(), &reject([1u8; 32])).unwrap();
+        storage.delete_last_outbound_dlc_message(&peer()).unwrap();
+
+        assert!(get_last_outbound_dlc_message(&storage, &peer())
+            .unwrap()
+            .is_none());
+        assert!(list_last_outbound_dlc_messages(&storage)
+            .unwrap()
+            .is_empty());
+    }
 }
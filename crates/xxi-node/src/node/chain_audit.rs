@@ -0,0 +1,465 @@
+use crate::bitcoin_conversion::to_txid_30;
+use crate::blockchain::Blockchain;
+use crate::node::event::NodeEvent;
+use crate::node::event::NodeEventHandler;
+use crate::node::Storage;
+use crate::node::XXINodeSettings;
+use crate::storage::DlcStorageProvider;
+use crate::storage::TenTenOneStorage;
+use bitcoin::OutPoint;
+use bitcoin::Txid;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::channel::Channel;
+use dlc_manager::DlcChannelId;
+use dlc_manager::Storage as _;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::task::spawn_blocking;
+
+/// How long the closing transaction of a closed DLC channel may stay unconfirmed before we report
+/// it as a [`ChainDiscrepancy`].
+pub const CLOSING_TRANSACTION_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The funding output of a parked DLC channel is only audited every so many audits.
+pub const PARKED_CHANNEL_AUDIT_RATIO: u64 = 24;
+
+/// A mismatch between our view of a DLC channel and the blockchain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainDiscrepancy {
+    /// The funding output of a DLC channel we consider open has been spent.
+    FundingOutputSpent {
+        funding_txid: Txid,
+        spending_txid: Txid,
+    },
+    /// The funding output of a closing DLC channel has been spent by a different transaction than
+    /// the one closing the channel.
+    UnknownFundingSpend {
+        funding_txid: Txid,
+        spending_txid: Txid,
+        expected_txid: Txid,
+    },
+    /// The closing transaction of a DLC channel we consider closed has not been confirmed within
+    /// [`CLOSING_TRANSACTION_CONFIRMATION_TIMEOUT`].
+    ClosingTransactionUnconfirmed { closing_txid: Txid },
+}
+
+impl ChainDiscrepancy {
+    /// The transaction which force-closed the DLC channel behind our back, if any.
+    ///
+    /// If set, the DLC channel has to be treated as force-closed with this transaction, instead of
+    /// the one we expected to close it with, if any.
+    pub fn force_close_txid(&self) -> Option<Txid> {
+        match self {
+            ChainDiscrepancy::FundingOutputSpent { spending_txid, .. }
+            | ChainDiscrepancy::UnknownFundingSpend { spending_txid, .. } => Some(*spending_txid),
+            ChainDiscrepancy::ClosingTransactionUnconfirmed { .. } => None,
+        }
+    }
+
+    /// A short name of the kind of discrepancy, e.g. to label metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChainDiscrepancy::FundingOutputSpent { .. } => "funding_output_spent",
+            ChainDiscrepancy::UnknownFundingSpend { .. } => "unknown_funding_spend",
+            ChainDiscrepancy::ClosingTransactionUnconfirmed { .. } => {
+                "closing_transaction_unconfirmed"
+            }
+        }
+    }
+}
+
+/// Periodically verifies our view of the DLC channels against the blockchain.
+///
+/// - The funding output of a DLC channel we consider open must be unspent.
+/// - The funding output of a closing DLC channel must only be spent by its closing transaction.
+/// - The closing transaction of a closed DLC channel must get confirmed.
+#[derive(Default)]
+pub struct ChainAuditor {
+    /// Closing transactions which are confirmed and hence do not need to be polled anymore.
+    confirmed_closing_txids: RwLock<HashSet<Txid>>,
+    /// When we first saw an unconfirmed closing transaction.
+    unconfirmed_closing_txids: RwLock<HashMap<Txid, Instant>>,
+    /// The discrepancies found in the last audit.
+    discrepancies: RwLock<HashMap<DlcChannelId, ChainDiscrepancy>>,
+    /// The number of discrepancies found since the node was started.
+    discrepancy_count: AtomicU64,
+    /// DLC channels which are audited less frequently, see [`ChainAuditor::park_channel`].
+    parked_channels: RwLock<HashSet<DlcChannelId>>,
+    /// The number of audits run since the node was started.
+    audit_count: AtomicU64,
+}
+
+impl ChainAuditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The discrepancies found in the last audit.
+    pub fn discrepancies(&self) -> Vec<(DlcChannelId, ChainDiscrepancy)> {
+        self.discrepancies
+            .read()
+            .iter()
+            .map(|(channel_id, discrepancy)| (*channel_id, *discrepancy))
+            .collect()
+    }
+
+    /// The number of discrepancies found since the node was started. A discrepancy which persists
+    /// across audits is only counted once.
+    pub fn discrepancy_count(&self) -> u64 {
+        self.discrepancy_count.load(Ordering::Relaxed)
+    }
+
+    /// Audit the funding output of the DLC channel only every [`PARKED_CHANNEL_AUDIT_RATIO`]
+    /// audits, e.g. because the counterparty has been offline for a long time.
+    ///
+    /// Returns `false` if the channel was already parked.
+    pub fn park_channel(&self, channel_id: DlcChannelId) -> bool {
+        self.parked_channels.write().insert(channel_id)
+    }
+
+    /// Audit the DLC channel with every audit again.
+    ///
+    /// Returns `false` if the channel was not parked.
+    pub fn unpark_channel(&self, channel_id: &DlcChannelId) -> bool {
+        self.parked_channels.write().remove(channel_id)
+    }
+
+    pub fn parked_channels(&self) -> Vec<DlcChannelId> {
+        self.parked_channels.read().iter().copied().collect()
+    }
+
+    /// Audit all DLC channels against the blockchain.
+    ///
+    /// Returns a [`NodeEvent::ChainDiscrepancy`] for every discrepancy which was not already found
+    /// in the previous audit.
+    fn audit<S: TenTenOneStorage, N: Storage>(
+        &self,
+        dlc_storage: &DlcStorageProvider<S>,
+        blockchain: &Blockchain<N>,
+        now: Instant,
+    ) -> anyhow::Result<Vec<NodeEvent>> {
+        let audit_parked_channels =
+            self.audit_count.fetch_add(1, Ordering::Relaxed) % PARKED_CHANNEL_AUDIT_RATIO == 0;
+
+        let mut discrepancies = HashMap::new();
+        for channel in dlc_storage.get_channels()? {
+            let discrepancy = match &channel {
+                // A parked channel keeps the discrepancy found when it was last audited.
+                Channel::Signed(signed_channel)
+                    if !audit_parked_channels && self.is_parked(&signed_channel.channel_id) =>
+                {
+                    Ok(self
+                        .discrepancies
+                        .read()
+                        .get(&signed_channel.channel_id)
+                        .copied())
+                }
+                Channel::Signed(signed_channel) => {
+                    self.audit_funding_output(signed_channel, blockchain)
+                }
+                Channel::Closed(closed_channel)
+                | Channel::CounterClosed(closed_channel)
+                | Channel::CollaborativelyClosed(closed_channel) => self.audit_closing_transaction(
+                    to_txid_30(closed_channel.closing_txid),
+                    blockchain,
+                    now,
+                ),
+                _ => Ok(None),
+            };
+
+            match discrepancy {
+                Ok(Some(discrepancy)) => {
+                    discrepancies.insert(channel.get_id(), discrepancy);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(
+                    channel_id = hex::encode(channel.get_id()),
+                    "Failed to audit DLC channel: {e:#}"
+                ),
+            }
+        }
+
+        Ok(self.update_discrepancies(discrepancies))
+    }
+
+    fn is_parked(&self, channel_id: &DlcChannelId) -> bool {
+        self.parked_channels.read().contains(channel_id)
+    }
+
+    fn audit_funding_output<N: Storage>(
+        &self,
+        signed_channel: &SignedChannel,
+        blockchain: &Blockchain<N>,
+    ) -> anyhow::Result<Option<ChainDiscrepancy>> {
+        let funding_txid = to_txid_30(signed_channel.fund_tx.txid());
+        let funding_output = OutPoint {
+            txid: funding_txid,
+            vout: signed_channel.fund_output_index as u32,
+        };
+
+        let spending_txid = match blockchain.get_txo_confirmations(&funding_output)? {
+            Some((_, spending_txid)) => spending_txid,
+            None => return Ok(None),
+        };
+
+        Ok(funding_spend_discrepancy(
+            funding_txid,
+            spending_txid,
+            expected_funding_spend(&signed_channel.state),
+        ))
+    }
+
+    fn audit_closing_transaction<N: Storage>(
+        &self,
+        closing_txid: Txid,
+        blockchain: &Blockchain<N>,
+        now: Instant,
+    ) -> anyhow::Result<Option<ChainDiscrepancy>> {
+        if self.confirmed_closing_txids.read().contains(&closing_txid) {
+            return Ok(None);
+        }
+
+        let confirmations = blockchain.get_transaction_confirmations(&closing_txid)?;
+
+        Ok(self.record_closing_transaction(closing_txid, confirmations, now))
+    }
+
+    /// Record the number of confirmations of the closing transaction of a closed DLC channel.
+    ///
+    /// Returns a [`ChainDiscrepancy::ClosingTransactionUnconfirmed`] if the transaction has been
+    /// unconfirmed for longer than [`CLOSING_TRANSACTION_CONFIRMATION_TIMEOUT`].
+    fn record_closing_transaction(
+        &self,
+        closing_txid: Txid,
+        confirmations: u32,
+        now: Instant,
+    ) -> Option<ChainDiscrepancy> {
+        if confirmations > 0 {
+            self.unconfirmed_closing_txids.write().remove(&closing_txid);
+            self.confirmed_closing_txids.write().insert(closing_txid);
+            return None;
+        }
+
+        let first_seen = *self
+            .unconfirmed_closing_txids
+            .write()
+            .entry(closing_txid)
+            .or_insert(now);
+
+        (now.duration_since(first_seen) >= CLOSING_TRANSACTION_CONFIRMATION_TIMEOUT)
+            .then_some(ChainDiscrepancy::ClosingTransactionUnconfirmed { closing_txid })
+    }
+
+    /// Replace the discrepancies of the previous audit, returning events for the new ones.
+    fn update_discrepancies(
+        &self,
+        discrepancies: HashMap<DlcChannelId, ChainDiscrepancy>,
+    ) -> Vec<NodeEvent> {
+        let mut previous = self.discrepancies.write();
+
+        let events = discrepancies
+            .iter()
+            .filter(|(channel_id, discrepancy)| previous.get(*channel_id) != Some(*discrepancy))
+            .map(|(channel_id, discrepancy)| NodeEvent::ChainDiscrepancy {
+                channel_id: *channel_id,
+                discrepancy: *discrepancy,
+            })
+            .collect::<Vec<_>>();
+
+        self.discrepancy_count
+            .fetch_add(events.len() as u64, Ordering::Relaxed);
+
+        *previous = discrepancies;
+
+        events
+    }
+}
+
+/// The transaction we expect to spend the funding output of a signed DLC channel, if the channel
+/// is being closed.
+fn expected_funding_spend(state: &SignedChannelState) -> Option<Txid> {
+    let tx = match state {
+        SignedChannelState::Closing {
+            buffer_transaction, ..
+        } => buffer_transaction,
+        SignedChannelState::SettledClosing {
+            settle_transaction, ..
+        } => settle_transaction,
+        SignedChannelState::CollaborativeCloseOffered { close_tx, .. } => close_tx,
+        _ => return None,
+    };
+
+    Some(to_txid_30(tx.txid()))
+}
+
+fn funding_spend_discrepancy(
+    funding_txid: Txid,
+    spending_txid: Txid,
+    expected_txid: Option<Txid>,
+) -> Option<ChainDiscrepancy> {
+    match expected_txid {
+        Some(expected_txid) if expected_txid == spending_txid => None,
+        Some(expected_txid) => Some(ChainDiscrepancy::UnknownFundingSpend {
+            funding_txid,
+            spending_txid,
+            expected_txid,
+        }),
+        None => Some(ChainDiscrepancy::FundingOutputSpent {
+            funding_txid,
+            spending_txid,
+        }),
+    }
+}
+
+/// Audit the DLC channels every [`XXINodeSettings::chain_audit_interval`], until the audit is
+/// disabled.
+pub(crate) async fn audit_chain_periodically<S, N>(
+    settings: Arc<tokio::sync::RwLock<XXINodeSettings>>,
+    auditor: Arc<ChainAuditor>,
+    dlc_storage: Arc<DlcStorageProvider<S>>,
+    blockchain: Arc<Blockchain<N>>,
+    event_handler: Arc<NodeEventHandler>,
+) where
+    S: TenTenOneStorage + 'static,
+    N: Storage + Send + Sync + 'static,
+{
+    loop {
+        let interval = {
+            let guard = settings.read().await;
+            guard.chain_audit_interval
+        };
+        let Some(interval) = interval else {
+            tracing::debug!("Chain audit is disabled");
+            return;
+        };
+
+        let events = spawn_blocking({
+            let auditor = auditor.clone();
+            let dlc_storage = dlc_storage.clone();
+            let blockchain = blockchain.clone();
+            move || auditor.audit(&dlc_storage, &blockchain, Instant::now())
+        })
+        .await
+        .expect("task to complete");
+
+        match events {
+            Ok(events) => {
+                for event in events {
+                    tracing::error!(
+                        ?event,
+                        "Found discrepancy between DLC channel and blockchain"
+                    );
+                    event_handler.publish(event);
+                }
+            }
+            Err(e) => tracing::error!("Failed to audit DLC channels against the blockchain: {e:#}"),
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+
+    #[test]
+    fn spend_of_open_channel_funding_output_is_force_close() {
+        let funding_txid = Txid::all_zeros();
+        let spending_txid = Txid::from_byte_array([1u8; 32]);
+
+        let discrepancy = funding_spend_discrepancy(funding_txid, spending_txid, None).unwrap();
+
+        assert_eq!(discrepancy.force_close_txid(), Some(spending_txid));
+    }
+
+    #[test]
+    fn spend_by_expected_closing_transaction_is_fine() {
+        let funding_txid = Txid::all_zeros();
+        let closing_txid = Txid::from_byte_array([1u8; 32]);
+
+        assert!(
+            funding_spend_discrepancy(funding_txid, closing_txid, Some(closing_txid)).is_none()
+        );
+
+        let spending_txid = Txid::from_byte_array([2u8; 32]);
+        let discrepancy =
+            funding_spend_discrepancy(funding_txid, spending_txid, Some(closing_txid)).unwrap();
+
+        assert!(matches!(
+            discrepancy,
+            ChainDiscrepancy::UnknownFundingSpend { .. }
+        ));
+        assert_eq!(discrepancy.force_close_txid(), Some(spending_txid));
+    }
+
+    #[test]
+    fn unconfirmed_closing_transaction_is_reported_after_timeout() {
+        let auditor = ChainAuditor::new();
+        let closing_txid = Txid::all_zeros();
+        let now = Instant::now();
+
+        assert!(auditor
+            .record_closing_transaction(closing_txid, 0, now)
+            .is_none());
+        assert_eq!(
+            auditor.record_closing_transaction(
+                closing_txid,
+                0,
+                now + CLOSING_TRANSACTION_CONFIRMATION_TIMEOUT
+            ),
+            Some(ChainDiscrepancy::ClosingTransactionUnconfirmed { closing_txid })
+        );
+        assert!(auditor
+            .record_closing_transaction(
+                closing_txid,
+                1,
+                now + CLOSING_TRANSACTION_CONFIRMATION_TIMEOUT
+            )
+            .is_none());
+        assert!(auditor
+            .confirmed_closing_txids
+            .read()
+            .contains(&closing_txid));
+    }
+
+    #[test]
+    fn persisting_discrepancy_is_reported_once() {
+        let auditor = ChainAuditor::new();
+        let channel_id = [1u8; 32];
+        let discrepancy = ChainDiscrepancy::ClosingTransactionUnconfirmed {
+            closing_txid: Txid::all_zeros(),
+        };
+
+        let events = auditor.update_discrepancies(HashMap::from([(channel_id, discrepancy)]));
+        assert_eq!(events.len(), 1);
+
+        let events = auditor.update_discrepancies(HashMap::from([(channel_id, discrepancy)]));
+        assert!(events.is_empty());
+
+        assert_eq!(auditor.discrepancy_count(), 1);
+        assert_eq!(auditor.discrepancies(), vec![(channel_id, discrepancy)]);
+    }
+
+    #[test]
+    fn parked_channel_can_be_unparked() {
+        let auditor = ChainAuditor::new();
+        let channel_id = [1u8; 32];
+
+        assert!(auditor.park_channel(channel_id));
+        assert!(!auditor.park_channel(channel_id));
+        assert_eq!(auditor.parked_channels(), vec![channel_id]);
+
+        assert!(auditor.unpark_channel(&channel_id));
+        assert!(!auditor.unpark_channel(&channel_id));
+        assert!(auditor.parked_channels().is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Applying the fee rate agreed on for a collaborative close to the close transaction.
+//!
+//! `rust-dlc` builds the close transaction such that it pays the fee reserve of the fund output,
+//! which was estimated with the fee rate of the contract. We can't choose the fee rate of the
+//! close transaction itself, so once it has been published the proposer of the close bumps it to
+//! the agreed fee rate with a child transaction spending its output (CPFP), unless it already pays
+//! at least that much.
+
+use crate::bitcoin_conversion::to_txid_30;
+use crate::blockchain::Blockchain;
+use crate::node::Storage;
+use crate::node::XXINodeSettings;
+use crate::on_chain_wallet::BdkStorage;
+use crate::on_chain_wallet::FeeConfig;
+use crate::on_chain_wallet::OnChainWallet;
+use crate::storage::DlcStorageProvider;
+use crate::storage::DlcStoreProvider;
+use crate::storage::TenTenOneStorage;
+use anyhow::Result;
+use bdk::FeeRate;
+use bitcoin::Amount;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::channel::Channel;
+use dlc_manager::DlcChannelId;
+use dlc_manager::Storage as _;
+use std::sync::Arc;
+use tokio::task::spawn_blocking;
+
+/// The virtual size we budget for the close transaction and the child bumping it. Bounds the fee
+/// we are willing to pay for a bump.
+const MAX_CLOSE_PACKAGE_VSIZE: usize = 500;
+
+#[derive(Debug, PartialEq)]
+enum CloseFeeBump {
+    /// The close transaction has not been published yet.
+    Pending,
+    /// Nothing left to do for the channel.
+    Done,
+}
+
+/// Remember to bump the transaction collaboratively closing the DLC channel to the agreed
+/// `fee_rate_sats_per_vb`, unless it is the fee rate of the contract.
+///
+/// Only the party proposing the collaborative close bumps the close transaction, once both
+/// parties agreed on the fee rate. Otherwise both parties would pay for a child reaching the
+/// agreed fee rate on their own. A bump remembered for an earlier attempt to close the DLC
+/// channel is dropped when falling back to the fee rate of the contract.
+pub(crate) fn register_close_fee_bump<K: DlcStoreProvider>(
+    dlc_storage: &DlcStorageProvider<K>,
+    channel_id: DlcChannelId,
+    fee_rate_sats_per_vb: u64,
+    contract_fee_rate_sats_per_vb: u64,
+) -> Result<()> {
+    if fee_rate_sats_per_vb == contract_fee_rate_sats_per_vb {
+        return dlc_storage.delete_close_fee_bump(&channel_id);
+    }
+
+    dlc_storage.upsert_close_fee_bump(&channel_id, fee_rate_sats_per_vb)
+}
+
+fn bump_close_fees<D, S, N>(
+    dlc_storage: &DlcStorageProvider<S>,
+    wallet: &OnChainWallet<D>,
+    blockchain: &Blockchain<N>,
+) -> Result<()>
+where
+    D: BdkStorage,
+    S: TenTenOneStorage,
+    N: Storage,
+{
+    for (channel_id, fee_rate_sats_per_vb) in dlc_storage.get_close_fee_bumps()? {
+        match bump_close_transaction(
+            channel_id,
+            fee_rate_sats_per_vb,
+            dlc_storage,
+            wallet,
+            blockchain,
+        ) {
+            Ok(CloseFeeBump::Pending) => {}
+            Ok(CloseFeeBump::Done) => dlc_storage.delete_close_fee_bump(&channel_id)?,
+            Err(e) => tracing::warn!(
+                channel_id = hex::encode(channel_id),
+                fee_rate_sats_per_vb,
+                "Failed to apply agreed fee rate to close transaction: {e:#}"
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+fn bump_close_transaction<D, S, N>(
+    channel_id: DlcChannelId,
+    fee_rate_sats_per_vb: u64,
+    dlc_storage: &DlcStorageProvider<S>,
+    wallet: &OnChainWallet<D>,
+    blockchain: &Blockchain<N>,
+) -> Result<CloseFeeBump>
+where
+    D: BdkStorage,
+    S: TenTenOneStorage,
+    N: Storage,
+{
+    let close_txid = match dlc_storage.get_channel(&channel_id)? {
+        // We offered to close the channel and wait for the close transaction to confirm.
+        Some(Channel::Signed(SignedChannel {
+            state: SignedChannelState::CollaborativeCloseOffered { close_tx, .. },
+            ..
+        })) => to_txid_30(close_tx.txid()),
+        // The counterparty accepted to close the channel and published the close transaction.
+        Some(Channel::CollaborativelyClosed(closed_channel)) => {
+            to_txid_30(closed_channel.closing_txid)
+        }
+        // The close offer was rejected or the channel was closed otherwise.
+        _ => return Ok(CloseFeeBump::Done),
+    };
+
+    // The wallet only knows the close transaction once it has been published.
+    let Ok(close_fee_rate) = wallet.unconfirmed_tx_fee_rate(close_txid) else {
+        return Ok(CloseFeeBump::Pending);
+    };
+
+    let agreed_fee_rate = FeeRate::from_sat_per_vb(fee_rate_sats_per_vb as f32);
+    match close_fee_rate {
+        Some(close_fee_rate) if close_fee_rate < agreed_fee_rate => {
+            let max_fee = Amount::from_sat(agreed_fee_rate.fee_vb(MAX_CLOSE_PACKAGE_VSIZE));
+            let (child, fee) =
+                wallet.build_cpfp_tx(close_txid, FeeConfig::FeeRate(agreed_fee_rate), max_fee)?;
+            let child_txid = blockchain.broadcast_transaction_blocking(&child)?;
+
+            tracing::info!(
+                channel_id = hex::encode(channel_id),
+                %close_txid,
+                %child_txid,
+                close_fee_rate = close_fee_rate.as_sat_per_vb(),
+                fee_rate_sats_per_vb,
+                %fee,
+                "Bumped close transaction to the agreed fee rate"
+            );
+        }
+        // The close transaction is already confirmed or pays enough.
+        _ => {}
+    }
+
+    Ok(CloseFeeBump::Done)
+}
+
+pub(crate) async fn bump_close_fees_periodically<D, S, N>(
+    settings: Arc<tokio::sync::RwLock<XXINodeSettings>>,
+    dlc_storage: Arc<DlcStorageProvider<S>>,
+    wallet: Arc<OnChainWallet<D>>,
+    blockchain: Arc<Blockchain<N>>,
+) where
+    D: BdkStorage,
+    S: TenTenOneStorage + 'static,
+    N: Storage + Send + Sync + 'static,
+{
+    loop {
+        let result = spawn_blocking({
+            let dlc_storage = dlc_storage.clone();
+            let wallet = wallet.clone();
+            let blockchain = blockchain.clone();
+            move || bump_close_fees(&dlc_storage, &wallet, &blockchain)
+        })
+        .await
+        .expect("task to complete");
+
+        if let Err(e) = result {
+            tracing::error!("Failed to bump close transactions: {e:#}");
+        }
+
+        let interval = {
+            let guard = settings.read().await;
+            guard.confirmation_tracking_interval
+        };
+        tokio::time::sleep(interval).await;
+    }
+}
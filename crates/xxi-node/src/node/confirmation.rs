@@ -0,0 +1,329 @@
+use crate::bitcoin_conversion::to_txid_30;
+use crate::blockchain::Blockchain;
+use crate::node::event::NodeEvent;
+use crate::node::event::NodeEventHandler;
+use crate::node::Storage;
+use crate::node::XXINodeSettings;
+use crate::storage::DlcStorageProvider;
+use crate::storage::TenTenOneStorage;
+use bitcoin::Txid;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::channel::Channel;
+use dlc_manager::contract::Contract;
+use dlc_manager::DlcChannelId;
+use dlc_manager::Storage as _;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::task::spawn_blocking;
+
+/// The confirmation counts at which a [`NodeEvent::FundingTransactionConfirmations`] is emitted.
+pub const CONFIRMATION_MILESTONES: [u32; 3] = [1, 3, 6];
+
+/// An operation on a DLC channel which requires the channel's funding transaction to be confirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelOperation {
+    ResizePosition,
+    ClosePosition,
+}
+
+/// The minimum number of confirmations of the funding transaction after which a
+/// [`ChannelOperation`] is allowed, even if `rust-dlc` does not consider the DLC channel confirmed
+/// yet.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub struct MinConfirmations {
+    pub resize_position: u32,
+    pub close_position: u32,
+}
+
+impl MinConfirmations {
+    pub fn for_operation(&self, operation: ChannelOperation) -> u32 {
+        match operation {
+            ChannelOperation::ResizePosition => self.resize_position,
+            ChannelOperation::ClosePosition => self.close_position,
+        }
+    }
+}
+
+impl Default for MinConfirmations {
+    fn default() -> Self {
+        Self {
+            resize_position: 1,
+            close_position: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TrackedTransaction {
+    channel_id: DlcChannelId,
+    confirmations: u32,
+}
+
+/// Keeps track of the number of confirmations of the funding transactions of our DLC channels.
+///
+/// The confirmation counts are updated periodically in the background, so that querying them
+/// never has to hit the blockchain.
+#[derive(Default)]
+pub struct ConfirmationTracker {
+    transactions: RwLock<HashMap<Txid, TrackedTransaction>>,
+}
+
+impl ConfirmationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking the funding transaction of a DLC channel.
+    ///
+    /// Tracking a transaction which is already tracked has no effect.
+    pub fn track(&self, txid: Txid, channel_id: DlcChannelId) {
+        self.transactions
+            .write()
+            .entry(txid)
+            .or_insert(TrackedTransaction {
+                channel_id,
+                confirmations: 0,
+            });
+    }
+
+    pub fn untrack(&self, txid: &Txid) {
+        self.transactions.write().remove(txid);
+    }
+
+    /// The last known number of confirmations of a tracked transaction.
+    pub fn confirmations(&self, txid: &Txid) -> Option<u32> {
+        self.transactions
+            .read()
+            .get(txid)
+            .map(|tracked| tracked.confirmations)
+    }
+
+    /// The last known number of confirmations of the funding transaction of a DLC channel.
+    pub fn channel_confirmations(&self, channel_id: &DlcChannelId) -> Option<u32> {
+        self.transactions
+            .read()
+            .values()
+            .find(|tracked| &tracked.channel_id == channel_id)
+            .map(|tracked| tracked.confirmations)
+    }
+
+    /// Whether the funding transaction of a DLC channel has enough confirmations for the given
+    /// [`ChannelOperation`].
+    pub fn is_confirmed_for(
+        &self,
+        channel_id: &DlcChannelId,
+        operation: ChannelOperation,
+        min_confirmations: &MinConfirmations,
+    ) -> bool {
+        self.channel_confirmations(channel_id).unwrap_or_default()
+            >= min_confirmations.for_operation(operation)
+    }
+
+    /// Record the number of confirmations of a tracked transaction.
+    ///
+    /// Returns a [`NodeEvent::FundingTransactionConfirmations`] if the transaction reached one of
+    /// the [`CONFIRMATION_MILESTONES`] since it was last recorded.
+    pub(crate) fn record(&self, txid: Txid, confirmations: u32) -> Option<NodeEvent> {
+        let mut transactions = self.transactions.write();
+        let tracked = transactions.get_mut(&txid)?;
+
+        let previous = tracked.confirmations;
+        tracked.confirmations = confirmations;
+
+        let reached_milestone = CONFIRMATION_MILESTONES
+            .iter()
+            .any(|milestone| previous < *milestone && *milestone <= confirmations);
+
+        reached_milestone.then_some(NodeEvent::FundingTransactionConfirmations {
+            channel_id: tracked.channel_id,
+            txid,
+            confirmations,
+        })
+    }
+
+    /// Stop tracking the transactions which reached the highest of the
+    /// [`CONFIRMATION_MILESTONES`] or whose DLC channel is no longer signed, i.e. closed.
+    fn untrack_settled(&self, signed_channel_ids: &HashSet<DlcChannelId>) {
+        let max_milestone = CONFIRMATION_MILESTONES[CONFIRMATION_MILESTONES.len() - 1];
+
+        self.transactions.write().retain(|_, tracked| {
+            tracked.confirmations < max_milestone
+                && signed_channel_ids.contains(&tracked.channel_id)
+        });
+    }
+
+    /// Track the funding transactions of the signed DLC channels which `rust-dlc` does not
+    /// consider confirmed yet and update the number of confirmations of every tracked
+    /// transaction.
+    fn update<S: TenTenOneStorage, N: Storage>(
+        &self,
+        dlc_storage: &DlcStorageProvider<S>,
+        blockchain: &Blockchain<N>,
+    ) -> anyhow::Result<Vec<NodeEvent>> {
+        let mut signed_channel_ids = HashSet::new();
+        for channel in dlc_storage.get_channels()? {
+            let Channel::Signed(signed_channel) = channel else {
+                continue;
+            };
+
+            signed_channel_ids.insert(signed_channel.channel_id);
+
+            if let SignedChannelState::Established {
+                signed_contract_id, ..
+            } = signed_channel.state
+            {
+                let is_confirmed = matches!(
+                    dlc_storage.get_contract(&signed_contract_id)?,
+                    Some(Contract::Confirmed(_))
+                );
+                if !is_confirmed {
+                    self.track(
+                        to_txid_30(signed_channel.fund_tx.txid()),
+                        signed_channel.channel_id,
+                    );
+                }
+            }
+        }
+
+        let tracked = self.transactions.read().keys().copied().collect::<Vec<_>>();
+
+        let mut events = Vec::new();
+        for txid in tracked {
+            match blockchain.get_transaction_confirmations(&txid) {
+                Ok(confirmations) => events.extend(self.record(txid, confirmations)),
+                Err(e) => {
+                    tracing::warn!(%txid, "Failed to get transaction confirmations: {e:#}")
+                }
+            }
+        }
+
+        self.untrack_settled(&signed_channel_ids);
+
+        Ok(events)
+    }
+}
+
+pub(crate) async fn track_confirmations_periodically<S, N>(
+    settings: Arc<tokio::sync::RwLock<XXINodeSettings>>,
+    tracker: Arc<ConfirmationTracker>,
+    dlc_storage: Arc<DlcStorageProvider<S>>,
+    blockchain: Arc<Blockchain<N>>,
+    event_handler: Arc<NodeEventHandler>,
+) where
+    S: TenTenOneStorage + 'static,
+    N: Storage + Send + Sync + 'static,
+{
+    loop {
+        let events = spawn_blocking({
+            let tracker = tracker.clone();
+            let dlc_storage = dlc_storage.clone();
+            let blockchain = blockchain.clone();
+            move || tracker.update(&dlc_storage, &blockchain)
+        })
+        .await
+        .expect("task to complete");
+
+        match events {
+            Ok(events) => {
+                for event in events {
+                    tracing::debug!(?event, "Funding transaction reached confirmation target");
+                    event_handler.publish(event);
+                }
+            }
+            Err(e) => tracing::error!("Failed to update transaction confirmations: {e:#}"),
+        }
+
+        let interval = {
+            let guard = settings.read().await;
+            guard.confirmation_tracking_interval
+        };
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+
+    #[test]
+    fn emits_event_when_reaching_milestone() {
+        let tracker = ConfirmationTracker::new();
+        let txid = Txid::all_zeros();
+        let channel_id = [1u8; 32];
+
+        tracker.track(txid, channel_id);
+
+        assert!(tracker.record(txid, 0).is_none());
+        assert!(matches!(
+            tracker.record(txid, 1),
+            Some(NodeEvent::FundingTransactionConfirmations {
+                confirmations: 1,
+                ..
+            })
+        ));
+        assert!(tracker.record(txid, 2).is_none());
+        assert!(matches!(
+            tracker.record(txid, 7),
+            Some(NodeEvent::FundingTransactionConfirmations {
+                confirmations: 7,
+                ..
+            })
+        ));
+        assert_eq!(tracker.channel_confirmations(&channel_id), Some(7));
+    }
+
+    #[test]
+    fn operations_require_configured_confirmations() {
+        let tracker = ConfirmationTracker::new();
+        let txid = Txid::all_zeros();
+        let channel_id = [1u8; 32];
+        let min_confirmations = MinConfirmations {
+            resize_position: 3,
+            close_position: 1,
+        };
+
+        tracker.track(txid, channel_id);
+        tracker.record(txid, 1);
+
+        assert!(tracker.is_confirmed_for(
+            &channel_id,
+            ChannelOperation::ClosePosition,
+            &min_confirmations
+        ));
+        assert!(!tracker.is_confirmed_for(
+            &channel_id,
+            ChannelOperation::ResizePosition,
+            &min_confirmations
+        ));
+        assert!(!tracker.is_confirmed_for(
+            &[2u8; 32],
+            ChannelOperation::ClosePosition,
+            &min_confirmations
+        ));
+    }
+
+    #[test]
+    fn untracks_confirmed_and_closed_channels() {
+        let tracker = ConfirmationTracker::new();
+        let confirmed = (Txid::from_byte_array([1u8; 32]), [1u8; 32]);
+        let confirming = (Txid::from_byte_array([2u8; 32]), [2u8; 32]);
+        let closed = (Txid::from_byte_array([3u8; 32]), [3u8; 32]);
+
+        for (txid, channel_id) in [confirmed, confirming, closed] {
+            tracker.track(txid, channel_id);
+        }
+        tracker.record(confirmed.0, 6);
+        tracker.record(confirming.0, 2);
+
+        tracker.untrack_settled(&HashSet::from([confirmed.1, confirming.1]));
+
+        assert_eq!(tracker.confirmations(&confirmed.0), None);
+        assert_eq!(tracker.confirmations(&confirming.0), Some(2));
+        assert_eq!(tracker.confirmations(&closed.0), None);
+    }
+}
@@ -1,6 +1,8 @@
 use crate::bitcoin_conversion::to_secp_pk_29;
 use crate::bitcoin_conversion::to_secp_pk_30;
+use crate::bitcoin_conversion::to_txid_30;
 use crate::commons;
+use crate::message_handler::CollaborativeCloseFee;
 use crate::message_handler::FundingFeeEvent;
 use crate::message_handler::TenTenOneCollaborativeCloseOffer;
 use crate::message_handler::TenTenOneMessage;
@@ -10,7 +12,12 @@ use crate::message_handler::TenTenOneRenewOffer;
 use crate::message_handler::TenTenOneRolloverOffer;
 use crate::message_handler::TenTenOneSettleAccept;
 use crate::message_handler::TenTenOneSettleOffer;
+use crate::node::close_fee::register_close_fee_bump;
+use crate::node::confirmation::ChannelOperation;
+use crate::node::dlc_manager::DlcManager;
 use crate::node::event::NodeEvent;
+use crate::node::event::NodeEventHandler;
+use crate::node::oracle::ensure_valid_event_ids;
 use crate::node::Node;
 use crate::node::ProtocolId;
 use crate::node::Storage as LnDlcStorage;
@@ -35,6 +42,10 @@ use dlc_manager::DlcChannelId;
 use dlc_manager::Oracle;
 use dlc_manager::ReferenceId;
 use dlc_manager::Storage;
+use lightning::chain::chaininterface::ConfirmationTarget;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::Arc;
 use time::OffsetDateTime;
 use tokio::task::spawn_blocking;
 use uuid::Uuid;
@@ -56,6 +67,8 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
             "Sending DLC channel offer"
         );
 
+        ensure_valid_event_ids(&contract_input)?;
+
         if let Some(channel) = self
             .list_signed_dlc_channels()?
             .iter()
@@ -80,7 +93,7 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
             move || {
                 let announcements: Vec<_> = p2pd_oracles
                     .into_iter()
-                    .filter(|o| oracles.public_keys.contains(&o.public_key))
+                    .filter(|o| oracles.public_keys.contains(&o.get_public_key()))
                     .filter_map(|oracle| oracle.get_announcement(&event_id).ok())
                     .collect();
 
@@ -161,13 +174,254 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
         if is_force_close {
             self.force_close_dlc_channel(channel, protocol_id)?;
         } else {
-            self.propose_dlc_channel_collaborative_close(channel, protocol_id)
+            self.propose_collaborative_close_fee(channel, protocol_id)
                 .await?
         }
 
         Ok(protocol_id)
     }
 
+    /// Start the fee negotiation preceding the collaborative close of a DLC channel.
+    ///
+    /// The collaborative close offer is only sent once the counterparty accepted our fee rate or
+    /// countered with a fee rate within our bounds, see
+    /// [`Node::handle_collaborative_close_fee`].
+    ///
+    /// Peers on the legacy protocol version do not know about the negotiation, hence we offer to
+    /// close the DLC channel with the fee rate of the contract straight away, see
+    /// [`TenTenOneMessageHandler::supports_collaborative_close_fee`].
+    async fn propose_collaborative_close_fee(
+        &self,
+        channel: SignedChannel,
+        protocol_id: ProtocolId,
+    ) -> Result<()> {
+        if !matches!(channel.state, SignedChannelState::Settled { .. }) {
+            tracing::error!(state = %channel.state, "Can't collaboratively close a channel which is not settled.");
+            bail!("Can't collaboratively close a channel which is not settled");
+        }
+
+        if !self
+            .dlc_message_handler
+            .supports_collaborative_close_fee(&channel.counter_party)
+        {
+            tracing::info!(
+                channel_id = hex::encode(channel.channel_id),
+                peer = %channel.counter_party,
+                "Peer does not support the close fee negotiation, closing with the fee rate of \
+                 the contract"
+            );
+
+            let fee_rate_sats_per_vb = channel.fee_rate_per_vb;
+            return self
+                .propose_dlc_channel_collaborative_close(channel, protocol_id, fee_rate_sats_per_vb)
+                .await;
+        }
+
+        let bounds = self.settings.read().await.close_fee_rate_bounds;
+        let fee_rate = self.fee_rate_estimator.get(ConfirmationTarget::Normal);
+        let fee_rate_sats_per_vb = bounds.clamp(fee_rate.as_sat_per_vb().ceil() as u64);
+
+        let channel_id = channel.channel_id;
+        let counterparty = channel.counter_party;
+
+        self.close_fee_negotiations.lock().insert(
+            channel_id,
+            PendingCloseFee {
+                protocol_id,
+                fee_rate_sats_per_vb,
+            },
+        );
+
+        tracing::info!(
+            channel_id = hex::encode(channel_id),
+            fee_rate_sats_per_vb,
+            "Proposing collaborative close fee rate"
+        );
+
+        self.send_collaborative_close_fee(
+            counterparty,
+            CollaborativeCloseFee::Propose {
+                channel_id,
+                fee_rate_sats_per_vb,
+            },
+        );
+
+        // A counterparty which does not respond should not keep the channel from being closed.
+        tokio::spawn({
+            let close_fee_negotiations = self.close_fee_negotiations.clone();
+            let dlc_manager = self.dlc_manager.clone();
+            let event_handler = self.event_handler.clone();
+            async move {
+                tokio::time::sleep(CLOSE_FEE_NEGOTIATION_TIMEOUT).await;
+
+                let timed_out = {
+                    let mut close_fee_negotiations = close_fee_negotiations.lock();
+                    match close_fee_negotiations.get(&channel_id) {
+                        Some(pending) if pending.protocol_id == protocol_id => {
+                            close_fee_negotiations.remove(&channel_id)
+                        }
+                        _ => None,
+                    }
+                };
+
+                if timed_out.is_none() {
+                    return;
+                }
+
+                tracing::warn!(
+                    channel_id = hex::encode(channel_id),
+                    timeout = ?CLOSE_FEE_NEGOTIATION_TIMEOUT,
+                    "Counterparty did not respond to close fee proposal, closing with the fee \
+                     rate of the contract"
+                );
+
+                if let Err(e) = offer_collaborative_close_with_contract_fee_rate(
+                    dlc_manager,
+                    event_handler,
+                    channel_id,
+                    protocol_id,
+                )
+                .await
+                {
+                    tracing::error!(
+                        channel_id = hex::encode(channel_id),
+                        "Failed to collaboratively close DLC channel: {e:#}"
+                    );
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Process a [`CollaborativeCloseFee`] message received from `peer`.
+    ///
+    /// A proposed fee rate is accepted if it is within our [`CloseFeeRateBounds`], otherwise we
+    /// counter with the closest fee rate within our bounds. Once the counterparty accepted our
+    /// proposal, or countered with a fee rate within our bounds, we offer to collaboratively close
+    /// the DLC channel.
+    pub async fn handle_collaborative_close_fee(
+        &self,
+        peer: PublicKey,
+        msg: CollaborativeCloseFee,
+    ) -> Result<()> {
+        let channel_id = msg.channel_id();
+        let fee_rate_sats_per_vb = msg.fee_rate_sats_per_vb();
+        let bounds = self.settings.read().await.close_fee_rate_bounds;
+
+        let channel = self
+            .get_signed_dlc_channel(|channel| channel.channel_id == channel_id)?
+            .context("DLC channel to close not found")?;
+
+        ensure!(
+            channel.counter_party == to_secp_pk_29(peer),
+            "Received close fee message for a DLC channel with another peer"
+        );
+
+        tracing::info!(
+            %peer,
+            channel_id = hex::encode(channel_id),
+            ?msg,
+            "Received collaborative close fee message"
+        );
+
+        match msg {
+            CollaborativeCloseFee::Propose { .. } => {
+                let response = match negotiate_close_fee_rate(fee_rate_sats_per_vb, bounds) {
+                    CloseFeeDecision::Accept => CollaborativeCloseFee::Accept {
+                        channel_id,
+                        fee_rate_sats_per_vb,
+                    },
+                    CloseFeeDecision::Counter(fee_rate_sats_per_vb) => {
+                        CollaborativeCloseFee::Counter {
+                            channel_id,
+                            fee_rate_sats_per_vb,
+                        }
+                    }
+                };
+
+                // Only the proposer bumps the close transaction, once it agreed on the fee rate
+                // and offered to close the channel.
+                self.send_collaborative_close_fee(channel.counter_party, response);
+            }
+            CollaborativeCloseFee::Counter { .. } => {
+                let pending = self
+                    .close_fee_negotiations
+                    .lock()
+                    .remove(&channel_id)
+                    .context("No pending collaborative close fee negotiation")?;
+
+                let fee_rate_sats_per_vb = match agree_on_countered_close_fee_rate(
+                    fee_rate_sats_per_vb,
+                    bounds,
+                    channel.fee_rate_per_vb,
+                ) {
+                    CloseFeeAgreement::Countered(fee_rate_sats_per_vb) => fee_rate_sats_per_vb,
+                    CloseFeeAgreement::Original(original_fee_rate_sats_per_vb) => {
+                        tracing::warn!(
+                            channel_id = hex::encode(channel_id),
+                            countered_fee_rate_sats_per_vb = fee_rate_sats_per_vb,
+                            original_fee_rate_sats_per_vb,
+                            "Countered close fee rate is out of bounds, falling back to the fee \
+                             rate of the contract"
+                        );
+
+                        original_fee_rate_sats_per_vb
+                    }
+                };
+
+                self.propose_dlc_channel_collaborative_close(
+                    channel,
+                    pending.protocol_id,
+                    fee_rate_sats_per_vb,
+                )
+                .await?;
+            }
+            CollaborativeCloseFee::Accept { .. } => {
+                let pending = self
+                    .close_fee_negotiations
+                    .lock()
+                    .remove(&channel_id)
+                    .context("No pending collaborative close fee negotiation")?;
+
+                let fee_rate_sats_per_vb = if pending.fee_rate_sats_per_vb == fee_rate_sats_per_vb {
+                    fee_rate_sats_per_vb
+                } else {
+                    tracing::warn!(
+                        channel_id = hex::encode(channel_id),
+                        accepted_fee_rate_sats_per_vb = fee_rate_sats_per_vb,
+                        proposed_fee_rate_sats_per_vb = pending.fee_rate_sats_per_vb,
+                        "Counterparty accepted a different close fee rate than we proposed, \
+                         falling back to the fee rate of the contract"
+                    );
+
+                    channel.fee_rate_per_vb
+                };
+
+                self.propose_dlc_channel_collaborative_close(
+                    channel,
+                    pending.protocol_id,
+                    fee_rate_sats_per_vb,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send_collaborative_close_fee(
+        &self,
+        counterparty: secp256k1_zkp::PublicKey,
+        msg: CollaborativeCloseFee,
+    ) {
+        self.dlc_message_handler
+            .send_collaborative_close_fee(counterparty, msg);
+
+        // Ensure that the message is sent straight away.
+        self.peer_manager.process_events();
+    }
+
     fn force_close_dlc_channel(
         &self,
         channel: SignedChannel,
@@ -187,56 +441,23 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
     }
 
     /// Close a DLC channel on-chain collaboratively, if there is no open position.
+    ///
+    /// The close transaction is bumped to `fee_rate_sats_per_vb` once it has been published, see
+    /// [`register_close_fee_bump`].
     async fn propose_dlc_channel_collaborative_close(
         &self,
         channel: SignedChannel,
         protocol_id: ProtocolId,
+        fee_rate_sats_per_vb: u64,
     ) -> Result<()> {
-        let counterparty = channel.counter_party;
-
-        match channel.state {
-            SignedChannelState::Settled { counter_payout, .. } => {
-                spawn_blocking({
-                    let dlc_manager = self.dlc_manager.clone();
-                    let event_handler = self.event_handler.clone();
-                    move || {
-                        tracing::info!(
-                            counter_payout,
-                            channel_id = hex::encode(channel.channel_id),
-                            "Proposing collaborative close"
-                        );
-
-                        let settle_offer = dlc_manager
-                            .offer_collaborative_close(
-                                &channel.channel_id,
-                                counter_payout,
-                                Some(protocol_id.into()),
-                            )
-                            .context(
-                                "Could not propose to collaboratively close the dlc channel.",
-                            )?;
-
-                        event_handler.publish(NodeEvent::SendDlcMessage {
-                            peer: to_secp_pk_30(counterparty),
-                            msg: TenTenOneMessage::CollaborativeCloseOffer(
-                                TenTenOneCollaborativeCloseOffer {
-                                    collaborative_close_offer: settle_offer,
-                                },
-                            ),
-                        });
-
-                        anyhow::Ok(())
-                    }
-                })
-                .await??;
-            }
-            _ => {
-                tracing::error!( state = %channel.state, "Can't collaboratively close a channel which is not settled.");
-                bail!("Can't collaboratively close a channel which is not settled");
-            }
-        }
-
-        Ok(())
+        offer_collaborative_close(
+            self.dlc_manager.clone(),
+            self.event_handler.clone(),
+            channel,
+            protocol_id,
+            fee_rate_sats_per_vb,
+        )
+        .await
     }
 
     /// Collaboratively close a position within a DLC Channel
@@ -328,6 +549,9 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
         protocol_id: ProtocolId,
     ) -> Result<ContractId> {
         tracing::info!(channel_id = %hex::encode(dlc_channel_id), "Proposing a DLC channel reopen or resize");
+
+        ensure_valid_event_ids(&contract_input)?;
+
         spawn_blocking({
             let dlc_manager = self.dlc_manager.clone();
             let dlc_channel_id = *dlc_channel_id;
@@ -382,6 +606,9 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
         funding_fee_events: Vec<FundingFeeEvent>,
     ) -> Result<ContractId> {
         tracing::info!(channel_id = %hex::encode(dlc_channel_id), "Proposing a DLC channel rollover");
+
+        ensure_valid_event_ids(&contract_input)?;
+
         spawn_blocking({
             let dlc_manager = self.dlc_manager.clone();
             let dlc_channel_id = *dlc_channel_id;
@@ -606,6 +833,56 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
         Ok(true)
     }
 
+    /// Checks if the signed channel with the trader is confirmed, or if its funding transaction
+    /// has at least the confirmations configured for the given [`ChannelOperation`].
+    ///
+    /// This is never stricter than [`Node::check_if_signed_channel_is_confirmed`], but allows the
+    /// operation before `rust-dlc` considers the DLC channel confirmed. The confirmations are
+    /// usually served by the [`ConfirmationTracker`]. Only if they are not sufficient yet, we ask
+    /// the blockchain for the latest number of confirmations.
+    ///
+    /// [`ConfirmationTracker`]: crate::node::confirmation::ConfirmationTracker
+    pub async fn check_if_signed_channel_is_confirmed_for(
+        &self,
+        trader: PublicKey,
+        operation: ChannelOperation,
+    ) -> Result<bool> {
+        let signed_channel = self.get_signed_channel_by_trader_id(trader)?;
+        let channel_id = signed_channel.channel_id;
+
+        if self.is_dlc_channel_confirmed(&channel_id)? {
+            return Ok(true);
+        }
+
+        let min_confirmations = self.settings.read().await.min_confirmations;
+
+        if self
+            .confirmation_tracker
+            .is_confirmed_for(&channel_id, operation, &min_confirmations)
+        {
+            return Ok(true);
+        }
+
+        let txid = to_txid_30(signed_channel.fund_tx.txid());
+        let confirmations = spawn_blocking({
+            let blockchain = self.blockchain.clone();
+            move || blockchain.get_transaction_confirmations(&txid)
+        })
+        .await
+        .expect("task to complete")?;
+
+        self.confirmation_tracker.track(txid, channel_id);
+        if let Some(event) = self.confirmation_tracker.record(txid, confirmations) {
+            self.event_handler.publish(event);
+        }
+
+        if confirmations >= min_confirmations.for_operation(operation) {
+            return Ok(true);
+        }
+
+        self.check_if_signed_channel_is_confirmed(trader).await
+    }
+
     fn is_contract_confirmed(&self, contract_id: &ContractId) -> Result<bool> {
         let contract = self
             .get_contract_by_id(contract_id)?
@@ -927,6 +1204,171 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: LnDlcStorage + Sync + Send
     }
 }
 
+/// How long we wait for the counterparty to respond to our close fee proposal, before we close
+/// the DLC channel with the fee rate of the contract.
+const CLOSE_FEE_NEGOTIATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+async fn offer_collaborative_close_with_contract_fee_rate<D, S, N>(
+    dlc_manager: Arc<DlcManager<D, S, N>>,
+    event_handler: Arc<NodeEventHandler>,
+    channel_id: DlcChannelId,
+    protocol_id: ProtocolId,
+) -> Result<()>
+where
+    D: BdkStorage,
+    S: TenTenOneStorage + 'static,
+    N: LnDlcStorage + Sync + Send + 'static,
+{
+    let channel = match dlc_manager.get_store().get_channel(&channel_id)? {
+        Some(Channel::Signed(channel)) => channel,
+        _ => bail!("DLC channel to close not found"),
+    };
+
+    let fee_rate_sats_per_vb = channel.fee_rate_per_vb;
+    offer_collaborative_close(
+        dlc_manager,
+        event_handler,
+        channel,
+        protocol_id,
+        fee_rate_sats_per_vb,
+    )
+    .await
+}
+
+async fn offer_collaborative_close<D, S, N>(
+    dlc_manager: Arc<DlcManager<D, S, N>>,
+    event_handler: Arc<NodeEventHandler>,
+    channel: SignedChannel,
+    protocol_id: ProtocolId,
+    fee_rate_sats_per_vb: u64,
+) -> Result<()>
+where
+    D: BdkStorage,
+    S: TenTenOneStorage + 'static,
+    N: LnDlcStorage + Sync + Send + 'static,
+{
+    let counterparty = channel.counter_party;
+    let channel_id = channel.channel_id;
+
+    let counter_payout = match channel.state {
+        SignedChannelState::Settled { counter_payout, .. } => counter_payout,
+        _ => {
+            tracing::error!(state = %channel.state, "Can't collaboratively close a channel which is not settled.");
+            bail!("Can't collaboratively close a channel which is not settled");
+        }
+    };
+
+    let contract_fee_rate_sats_per_vb = channel.fee_rate_per_vb;
+
+    spawn_blocking(move || {
+        tracing::info!(
+            counter_payout,
+            fee_rate_sats_per_vb,
+            channel_id = hex::encode(channel_id),
+            "Proposing collaborative close"
+        );
+
+        let settle_offer = dlc_manager
+            .offer_collaborative_close(&channel_id, counter_payout, Some(protocol_id.into()))
+            .context("Could not propose to collaboratively close the dlc channel.")?;
+
+        event_handler.publish(NodeEvent::SendDlcMessage {
+            peer: to_secp_pk_30(counterparty),
+            msg: TenTenOneMessage::CollaborativeCloseOffer(TenTenOneCollaborativeCloseOffer {
+                collaborative_close_offer: settle_offer,
+            }),
+        });
+
+        register_close_fee_bump(
+            dlc_manager.get_store(),
+            channel_id,
+            fee_rate_sats_per_vb,
+            contract_fee_rate_sats_per_vb,
+        )
+        .context("Failed to remember to bump the close transaction")
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// The fee rates we accept for the transaction collaboratively closing a DLC channel.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub struct CloseFeeRateBounds {
+    pub min_sats_per_vb: u64,
+    pub max_sats_per_vb: u64,
+}
+
+impl CloseFeeRateBounds {
+    pub fn contains(&self, fee_rate_sats_per_vb: u64) -> bool {
+        (self.min_sats_per_vb..=self.max_sats_per_vb).contains(&fee_rate_sats_per_vb)
+    }
+
+    /// The fee rate within the bounds which is closest to `fee_rate_sats_per_vb`.
+    pub fn clamp(&self, fee_rate_sats_per_vb: u64) -> u64 {
+        fee_rate_sats_per_vb
+            .max(self.min_sats_per_vb)
+            .min(self.max_sats_per_vb)
+    }
+}
+
+impl Default for CloseFeeRateBounds {
+    fn default() -> Self {
+        Self {
+            min_sats_per_vb: 1,
+            max_sats_per_vb: 100,
+        }
+    }
+}
+
+/// A collaborative close fee negotiation which we started and which is waiting for the
+/// counterparty's response.
+pub(crate) struct PendingCloseFee {
+    protocol_id: ProtocolId,
+    fee_rate_sats_per_vb: u64,
+}
+
+#[derive(Debug, PartialEq)]
+enum CloseFeeDecision {
+    Accept,
+    Counter(u64),
+}
+
+fn negotiate_close_fee_rate(
+    proposed_fee_rate_sats_per_vb: u64,
+    bounds: CloseFeeRateBounds,
+) -> CloseFeeDecision {
+    if bounds.contains(proposed_fee_rate_sats_per_vb) {
+        CloseFeeDecision::Accept
+    } else {
+        CloseFeeDecision::Counter(bounds.clamp(proposed_fee_rate_sats_per_vb))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CloseFeeAgreement {
+    /// Close with the fee rate the counterparty countered with.
+    Countered(u64),
+    /// The parties disagree, close with the fee rate the contract was created with.
+    Original(u64),
+}
+
+/// Decide on the fee rate after the counterparty countered our proposal.
+///
+/// A disagreement on the fee rate should not prevent the channel from being closed, hence we fall
+/// back to the fee rate of the contract, which both parties agreed to when opening the channel.
+fn agree_on_countered_close_fee_rate(
+    countered_fee_rate_sats_per_vb: u64,
+    bounds: CloseFeeRateBounds,
+    original_fee_rate_sats_per_vb: u64,
+) -> CloseFeeAgreement {
+    if bounds.contains(countered_fee_rate_sats_per_vb) {
+        CloseFeeAgreement::Countered(countered_fee_rate_sats_per_vb)
+    } else {
+        CloseFeeAgreement::Original(original_fee_rate_sats_per_vb)
+    }
+}
+
 /// Ensure that a [`dlc_messages::Message`] is sent straight away.
 ///
 /// Use this instead of [`MessageHandler`]'s `send_message` which only enqueues the message.
@@ -1011,3 +1453,129 @@ pub fn estimated_funding_transaction_fee(fee_rate_sats_per_vb: f64) -> Amount {
 
     Amount::from_sat(fee)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryDlcStoreProvider;
+    use crate::storage::DlcChannelEventBus;
+    use crate::storage::DlcStorageProvider;
+
+    #[test]
+    fn accept_close_fee_rate_within_bounds() {
+        let bounds = CloseFeeRateBounds {
+            min_sats_per_vb: 2,
+            max_sats_per_vb: 50,
+        };
+
+        assert_eq!(
+            negotiate_close_fee_rate(2, bounds),
+            CloseFeeDecision::Accept
+        );
+        assert_eq!(
+            negotiate_close_fee_rate(50, bounds),
+            CloseFeeDecision::Accept
+        );
+    }
+
+    #[test]
+    fn counter_close_fee_rate_out_of_bounds() {
+        let bounds = CloseFeeRateBounds {
+            min_sats_per_vb: 2,
+            max_sats_per_vb: 50,
+        };
+
+        assert_eq!(
+            negotiate_close_fee_rate(1, bounds),
+            CloseFeeDecision::Counter(2)
+        );
+        assert_eq!(
+            negotiate_close_fee_rate(120, bounds),
+            CloseFeeDecision::Counter(50)
+        );
+    }
+
+    #[test]
+    fn fall_back_to_original_fee_rate_on_disagreement() {
+        let bounds = CloseFeeRateBounds {
+            min_sats_per_vb: 2,
+            max_sats_per_vb: 50,
+        };
+
+        assert_eq!(
+            agree_on_countered_close_fee_rate(30, bounds, 10),
+            CloseFeeAgreement::Countered(30)
+        );
+        assert_eq!(
+            agree_on_countered_close_fee_rate(120, bounds, 10),
+            CloseFeeAgreement::Original(10)
+        );
+    }
+
+    #[test]
+    fn no_close_fee_bump_when_the_proposer_falls_back() {
+        let channel_id = [1; 32];
+        let contract_fee_rate = 10;
+        let proposer_bounds = CloseFeeRateBounds {
+            min_sats_per_vb: 2,
+            max_sats_per_vb: 50,
+        };
+        let acceptor_bounds = CloseFeeRateBounds {
+            min_sats_per_vb: 60,
+            max_sats_per_vb: 100,
+        };
+
+        let proposer =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+        let acceptor =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+
+        // An earlier attempt to close the channel agreed on 30 sats/vbyte, but the close offer was
+        // never accepted.
+        register_close_fee_bump(&proposer, channel_id, 30, contract_fee_rate).unwrap();
+
+        // The acceptor counters with a fee rate out of the bounds of the proposer, which does not
+        // bump anything on the side of the acceptor.
+        let countered_fee_rate = match negotiate_close_fee_rate(30, acceptor_bounds) {
+            CloseFeeDecision::Counter(fee_rate) => fee_rate,
+            decision => panic!("Unexpected decision: {decision:?}"),
+        };
+
+        let fee_rate = match agree_on_countered_close_fee_rate(
+            countered_fee_rate,
+            proposer_bounds,
+            contract_fee_rate,
+        ) {
+            CloseFeeAgreement::Original(fee_rate) => fee_rate,
+            agreement => panic!("Unexpected agreement: {agreement:?}"),
+        };
+        register_close_fee_bump(&proposer, channel_id, fee_rate, contract_fee_rate).unwrap();
+
+        assert!(proposer.get_close_fee_bumps().unwrap().is_empty());
+        assert!(acceptor.get_close_fee_bumps().unwrap().is_empty());
+    }
+
+    #[test]
+    fn proposer_bumps_to_the_agreed_close_fee_rate() {
+        let channel_id = [1; 32];
+        let contract_fee_rate = 10;
+        let bounds = CloseFeeRateBounds {
+            min_sats_per_vb: 2,
+            max_sats_per_vb: 50,
+        };
+
+        let proposer =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+
+        let fee_rate = match agree_on_countered_close_fee_rate(30, bounds, contract_fee_rate) {
+            CloseFeeAgreement::Countered(fee_rate) => fee_rate,
+            agreement => panic!("Unexpected agreement: {agreement:?}"),
+        };
+        register_close_fee_bump(&proposer, channel_id, fee_rate, contract_fee_rate).unwrap();
+
+        assert_eq!(
+            proposer.get_close_fee_bumps().unwrap(),
+            vec![(channel_id, 30)]
+        );
+    }
+}
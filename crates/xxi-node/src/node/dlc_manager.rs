@@ -3,6 +3,7 @@ use crate::dlc_wallet::DlcWallet;
 use crate::fee_rate_estimator::FeeRateEstimator;
 use crate::message_handler::TenTenOneMessage;
 use crate::node::Node;
+use crate::node::OracleClient;
 use crate::node::Storage;
 use crate::on_chain_wallet::BdkStorage;
 use crate::storage::DlcStorageProvider;
@@ -12,9 +13,9 @@ use anyhow::Result;
 use bitcoin::secp256k1::PublicKey;
 use dlc_manager::channel::signed_channel::SignedChannel;
 use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::Oracle;
 use dlc_manager::Storage as DlcStorage;
 use dlc_manager::SystemTimeProvider;
-use p2pd_oracle_client::P2PDOracleClient;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
@@ -24,7 +25,7 @@ pub type DlcManager<D, S, N> = dlc_manager::manager::Manager<
     Arc<DlcWallet<D, S, N>>,
     Arc<DlcWallet<D, S, N>>,
     Arc<DlcStorageProvider<S>>,
-    Arc<P2PDOracleClient>,
+    Arc<OracleClient>,
     Arc<SystemTimeProvider>,
     Arc<FeeRateEstimator>,
 >;
@@ -33,15 +34,15 @@ pub fn build<D: BdkStorage, S: TenTenOneStorage, N: Storage>(
     data_dir: &Path,
     wallet: Arc<DlcWallet<D, S, N>>,
     dlc_storage: Arc<DlcStorageProvider<S>>,
-    p2pdoracles: Vec<Arc<P2PDOracleClient>>,
+    oracle_clients: Vec<Arc<OracleClient>>,
     fee_rate_estimator: Arc<FeeRateEstimator>,
 ) -> Result<DlcManager<D, S, N>> {
     let offers_path = data_dir.join("offers");
     fs::create_dir_all(offers_path)?;
 
     let mut oracles = HashMap::new();
-    for oracle in p2pdoracles.into_iter() {
-        oracles.insert(oracle.public_key, oracle);
+    for oracle in oracle_clients.into_iter() {
+        oracles.insert(oracle.get_public_key(), oracle);
     }
 
     // FIXME: We need to do this to ensure that we can upgrade `Node`s from LDK 0.0.114 to 0.0.116.
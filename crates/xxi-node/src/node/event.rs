@@ -1,11 +1,14 @@
+use crate::message_handler::CollaborativeCloseFee;
+use crate::message_handler::ProtocolVersionMismatch;
 use crate::message_handler::TenTenOneMessage;
+use crate::node::chain_audit::ChainDiscrepancy;
 use crate::storage::DlcChannelEvent;
 use bitcoin::secp256k1::PublicKey;
-use std::sync::mpsc;
+use bitcoin::Txid;
+use dlc_manager::DlcChannelId;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::Receiver;
-use tokio::task::spawn_blocking;
 
 #[derive(Clone, Debug)]
 pub enum NodeEvent {
@@ -26,6 +29,27 @@ pub enum NodeEvent {
     DlcChannelEvent {
         dlc_channel_event: DlcChannelEvent,
     },
+    /// The funding transaction of a DLC channel reached one of the confirmation milestones.
+    FundingTransactionConfirmations {
+        channel_id: DlcChannelId,
+        txid: Txid,
+        confirmations: u32,
+    },
+    /// A peer sent a message negotiating the fee rate of a collaborative close transaction.
+    CollaborativeCloseFee {
+        peer: PublicKey,
+        msg: CollaborativeCloseFee,
+    },
+    /// Our view of a DLC channel does not match the blockchain.
+    ChainDiscrepancy {
+        channel_id: DlcChannelId,
+        discrepancy: ChainDiscrepancy,
+    },
+    /// A peer was disconnected, because one of us is too old to talk to the other.
+    ProtocolVersionMismatch {
+        peer: PublicKey,
+        mismatch: ProtocolVersionMismatch,
+    },
 }
 
 #[derive(Clone)]
@@ -59,16 +83,21 @@ impl NodeEventHandler {
 
 pub fn connect_node_event_handler_to_dlc_channel_events(
     node_event_handler: Arc<NodeEventHandler>,
-    dlc_event_receiver: mpsc::Receiver<DlcChannelEvent>,
+    mut dlc_event_receiver: Receiver<DlcChannelEvent>,
 ) {
-    spawn_blocking(move || loop {
-        match dlc_event_receiver.recv() {
-            Ok(dlc_channel_event) => {
-                node_event_handler.publish(NodeEvent::DlcChannelEvent { dlc_channel_event })
-            }
-            Err(e) => {
-                tracing::error!("The dlc event channel has been closed. Error: {e:#}");
-                break;
+    tokio::spawn(async move {
+        loop {
+            match dlc_event_receiver.recv().await {
+                Ok(dlc_channel_event) => {
+                    node_event_handler.publish(NodeEvent::DlcChannelEvent { dlc_channel_event })
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Skipped {skipped} DLC channel events");
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    tracing::error!("The DLC channel event bus has been closed");
+                    break;
+                }
             }
         }
     });
@@ -4,7 +4,18 @@ use crate::dlc::TracingLogger;
 use crate::dlc_custom_signer::CustomKeysManager;
 use crate::dlc_wallet::DlcWallet;
 use crate::fee_rate_estimator::FeeRateEstimator;
+use crate::message_handler;
+use crate::message_handler::TenTenOneMessage;
 use crate::message_handler::TenTenOneMessageHandler;
+use crate::node::chain_audit::audit_chain_periodically;
+use crate::node::chain_audit::ChainAuditor;
+use crate::node::close_fee::bump_close_fees_periodically;
+use crate::node::confirmation::track_confirmations_periodically;
+use crate::node::confirmation::ConfirmationTracker;
+use crate::node::confirmation::MinConfirmations;
+use crate::node::dlc_channel::send_dlc_message;
+use crate::node::dlc_channel::CloseFeeRateBounds;
+use crate::node::dlc_channel::PendingCloseFee;
 use crate::node::event::connect_node_event_handler_to_dlc_channel_events;
 use crate::node::event::NodeEventHandler;
 use crate::on_chain_wallet::BdkStorage;
@@ -12,12 +23,16 @@ use crate::on_chain_wallet::FeeConfig;
 use crate::on_chain_wallet::OnChainWallet;
 use crate::seed::Bip39Seed;
 use crate::shadow::Shadow;
-use crate::storage::DlcChannelEvent;
+use crate::storage::DlcChannelEventBus;
 use crate::storage::DlcStorageProvider;
 use crate::storage::TenTenOneStorage;
 use crate::PeerManager;
+use anyhow::Context;
 use anyhow::Result;
 use bitcoin::address::NetworkUnchecked;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::HashEngine;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::secp256k1::XOnlyPublicKey;
 use bitcoin::Address;
@@ -32,6 +47,7 @@ use serde::Deserialize;
 use serde::Serialize;
 use serde_with::serde_as;
 use serde_with::DurationSeconds;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -39,25 +55,28 @@ use std::net::SocketAddr;
 use std::path::Path;
 use std::str::from_utf8;
 use std::str::FromStr;
-use std::sync::mpsc;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::SystemTime;
 use tokio::sync::RwLock;
 use tokio::task::spawn_blocking;
 
+mod close_fee;
 mod connection;
 mod dlc_manager;
 mod oracle;
 mod storage;
 mod wallet;
 
+pub mod chain_audit;
+pub mod confirmation;
 pub mod dlc_channel;
 pub mod event;
 pub mod peer_manager;
 
 pub use crate::message_handler::tentenone_message_name;
 pub use ::dlc_manager as rust_dlc_manager;
+use ::dlc_manager::DlcChannelId;
 use ::dlc_manager::ReferenceId;
 use bdk_esplora::esplora_client::OutputStatus;
 use bdk_esplora::esplora_client::Tx;
@@ -66,6 +85,8 @@ pub use dlc_manager::DlcManager;
 use lightning::ln::peer_handler::ErroringMessageHandler;
 use lightning::ln::peer_handler::IgnoringMessageHandler;
 use lightning::ln::peer_handler::MessageHandler;
+pub use oracle::validate_oracle_events;
+pub use oracle::OracleClient;
 pub use oracle::OracleInfo;
 use secp256k1_zkp::SECP256K1;
 pub use storage::InMemoryStore;
@@ -94,7 +115,7 @@ pub struct Node<D: BdkStorage, S: TenTenOneStorage, N: Storage> {
     pub dlc_manager: Arc<DlcManager<D, S, N>>,
 
     /// All oracles clients the node is aware of.
-    pub oracles: Vec<Arc<P2PDOracleClient>>,
+    pub oracles: Vec<Arc<OracleClient>>,
     pub dlc_message_handler: Arc<TenTenOneMessageHandler>,
 
     /// The oracle pubkey used for proposing dlc channels
@@ -102,6 +123,14 @@ pub struct Node<D: BdkStorage, S: TenTenOneStorage, N: Storage> {
 
     pub event_handler: Arc<NodeEventHandler>,
 
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
+
+    pub chain_auditor: Arc<ChainAuditor>,
+
+    /// The collaborative close fee negotiations we started and which are awaiting a response.
+    pub(crate) close_fee_negotiations:
+        Arc<parking_lot::Mutex<HashMap<DlcChannelId, PendingCloseFee>>>,
+
     // storage
     // TODO(holzeis): The node storage should get extracted to the corresponding application
     // layers.
@@ -143,6 +172,21 @@ pub struct XXINodeSettings {
     /// How often we sync the shadow states
     #[serde_as(as = "DurationSeconds")]
     pub shadow_sync_interval: Duration,
+    /// How often we update the confirmations of the DLC channel funding transactions
+    #[serde_as(as = "DurationSeconds")]
+    pub confirmation_tracking_interval: Duration,
+    /// How often we audit our view of the DLC channels against the blockchain, if at all.
+    ///
+    /// Only the coordinator audits the chain, as every audit polls esplora for each DLC channel.
+    #[serde_as(as = "Option<DurationSeconds>")]
+    #[serde(default)]
+    pub chain_audit_interval: Option<Duration>,
+    /// The confirmations of the funding transaction required per channel operation
+    pub min_confirmations: MinConfirmations,
+    /// The fee rates we accept for collaboratively closing a DLC channel
+    pub close_fee_rate_bounds: CloseFeeRateBounds,
+    /// How many requests we send to electrs in parallel when syncing the on-chain wallet
+    pub bdk_client_concurrency: u8,
 }
 
 impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 'static>
@@ -170,7 +214,7 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 's
         oracle_clients: Vec<P2PDOracleClient>,
         oracle_pubkey: XOnlyPublicKey,
         node_event_handler: Arc<NodeEventHandler>,
-        dlc_event_sender: mpsc::Sender<DlcChannelEvent>,
+        dlc_event_bus: DlcChannelEventBus,
     ) -> Result<Self> {
         let time_since_unix_epoch = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
 
@@ -188,10 +232,24 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 's
         )?;
         let on_chain_wallet = Arc::new(on_chain_wallet);
 
-        let blockchain = Blockchain::new(electrs_server_url.clone(), node_storage.clone())?;
+        let blockchain = Blockchain::new(
+            electrs_server_url.clone(),
+            node_storage.clone(),
+            settings.bdk_client_concurrency,
+        )?;
         let blockchain = Arc::new(blockchain);
 
-        let dlc_storage = Arc::new(DlcStorageProvider::new(storage.clone(), dlc_event_sender));
+        let dlc_storage = Arc::new(DlcStorageProvider::new(storage.clone(), dlc_event_bus));
+        let schema_version = dlc_storage
+            .migrate()
+            .context("Failed to migrate DLC storage")?;
+        tracing::debug!(schema_version, "DLC storage is up to date");
+        dlc_storage
+            .archive_closed_contracts()
+            .context("Failed to archive closed contracts")?;
+        dlc_storage
+            .rebuild_signed_channel_index()
+            .context("Failed to rebuild signed channel index")?;
 
         let keys_manager = {
             Arc::new(CustomKeysManager::new(
@@ -204,8 +262,10 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 's
             ))
         };
 
-        let oracle_clients: Vec<Arc<P2PDOracleClient>> =
-            oracle_clients.into_iter().map(Arc::new).collect();
+        let oracle_clients: Vec<Arc<OracleClient>> = oracle_clients
+            .into_iter()
+            .map(|client| Arc::new(OracleClient::new(client)))
+            .collect();
 
         let dlc_wallet = DlcWallet::new(
             on_chain_wallet.clone(),
@@ -267,16 +327,16 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 's
             listen_address,
             oracle_pubkey,
             event_handler: node_event_handler,
+            confirmation_tracker: Arc::new(ConfirmationTracker::new()),
+            chain_auditor: Arc::new(ChainAuditor::new()),
+            close_fee_negotiations: Arc::new(parking_lot::Mutex::new(HashMap::new())),
         })
     }
 
     /// Starts the background handles - if the returned handles are dropped, the
     /// background tasks are stopped.
     // TODO: Consider having handles for *all* the tasks & threads for a clean shutdown.
-    pub fn start(
-        &self,
-        dlc_event_receiver: mpsc::Receiver<DlcChannelEvent>,
-    ) -> Result<RunningNode> {
+    pub fn start(&self) -> Result<RunningNode> {
         #[cfg(feature = "ln_net_tcp")]
         let handles = vec![spawn_connection_management(
             self.peer_manager.clone(),
@@ -297,9 +357,32 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 's
             self.fee_rate_estimator.clone(),
         ));
 
+        tokio::spawn(track_confirmations_periodically(
+            self.settings.clone(),
+            self.confirmation_tracker.clone(),
+            self.dlc_storage.clone(),
+            self.blockchain.clone(),
+            self.event_handler.clone(),
+        ));
+
+        tokio::spawn(audit_chain_periodically(
+            self.settings.clone(),
+            self.chain_auditor.clone(),
+            self.dlc_storage.clone(),
+            self.blockchain.clone(),
+            self.event_handler.clone(),
+        ));
+
+        tokio::spawn(bump_close_fees_periodically(
+            self.settings.clone(),
+            self.dlc_storage.clone(),
+            self.wallet.clone(),
+            self.blockchain.clone(),
+        ));
+
         connect_node_event_handler_to_dlc_channel_events(
             self.event_handler.clone(),
-            dlc_event_receiver,
+            self.dlc_storage.subscribe(),
         );
 
         tracing::info!("Node started with node ID {}", self.info);
@@ -332,6 +415,29 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 's
         Ok(txid)
     }
 
+    /// Accelerate the unconfirmed transaction with the given `txid` by spending its outputs of
+    /// the on-chain wallet in a child transaction paying for both (CPFP), which pays at most
+    /// `max_fee`.
+    ///
+    /// Returns the ID of the child transaction and the fee it pays.
+    pub async fn accelerate_with_cpfp(
+        &self,
+        txid: Txid,
+        fee_config: FeeConfig,
+        max_fee: Amount,
+    ) -> Result<(Txid, Amount)> {
+        let (tx, fee) = spawn_blocking({
+            let wallet = self.wallet.clone();
+            move || wallet.build_cpfp_tx(txid, fee_config, max_fee)
+        })
+        .await
+        .expect("task to complete")?;
+
+        let child_txid = self.blockchain.broadcast_transaction_blocking(&tx)?;
+
+        Ok((child_txid, fee))
+    }
+
     pub fn list_peers(&self) -> Vec<PublicKey> {
         self.peer_manager
             .get_peer_node_ids()
@@ -340,6 +446,49 @@ impl<D: BdkStorage, S: TenTenOneStorage + 'static, N: Storage + Sync + Send + 's
             .collect()
     }
 
+    /// Remember `msg` as the last outbound DLC message to `peer`, so that it can be resent on
+    /// reconnect.
+    pub fn store_last_outbound_dlc_message(
+        &self,
+        peer: PublicKey,
+        msg: &TenTenOneMessage,
+    ) -> Result<()> {
+        message_handler::store_last_outbound_dlc_message(self.node_storage.as_ref(), peer, msg)
+    }
+
+    /// Resend the last outbound DLC message to `peer`, if any.
+    pub fn resend_last_outbound_dlc_message(&self, peer: PublicKey) -> Result<()> {
+        match self.get_last_outbound_dlc_message(&peer)? {
+            Some(msg) => {
+                tracing::debug!(
+                    %peer,
+                    msg = tentenone_message_name(&msg),
+                    "Resending last outbound DLC message"
+                );
+                send_dlc_message(&self.dlc_message_handler, &self.peer_manager, peer, msg);
+            }
+            None => tracing::debug!(%peer, "No last dlc message found. Nothing todo."),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_last_outbound_dlc_message(
+        &self,
+        peer: &PublicKey,
+    ) -> Result<Option<TenTenOneMessage>> {
+        message_handler::get_last_outbound_dlc_message(self.node_storage.as_ref(), peer)
+    }
+
+    pub fn list_last_outbound_dlc_messages(&self) -> Result<Vec<(PublicKey, TenTenOneMessage)>> {
+        message_handler::list_last_outbound_dlc_messages(self.node_storage.as_ref())
+    }
+
+    /// Forget the last outbound DLC message to `peer`, so that it is not resent on reconnect.
+    pub fn delete_last_outbound_dlc_message(&self, peer: &PublicKey) -> Result<()> {
+        self.node_storage.delete_last_outbound_dlc_message(peer)
+    }
+
     pub async fn get_unspent_txs(&self, address: &Address) -> Result<Vec<(Tx, Amount)>> {
         let txs = self.get_utxo_for_address(address).await?;
         let mut statuses = vec![];
@@ -507,6 +656,22 @@ impl ProtocolId {
         ProtocolId(Uuid::new_v4())
     }
 
+    /// Derive the [`ProtocolId`] of a trade from the order and the match it executes.
+    ///
+    /// The derivation is deterministic, so that executing the same match twice (e.g. because of
+    /// a retried request) results in the same [`ProtocolId`] and can be detected.
+    pub fn from_order_and_match(order_id: Uuid, match_id: Uuid) -> Self {
+        let mut engine = sha256::Hash::engine();
+        engine.input(order_id.as_bytes());
+        engine.input(match_id.as_bytes());
+        let hash = sha256::Hash::from_engine(engine);
+
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+
+        ProtocolId(uuid::Builder::from_custom_bytes(bytes).into_uuid())
+    }
+
     pub fn to_uuid(&self) -> Uuid {
         self.0
     }
@@ -1,14 +1,29 @@
 use crate::bitcoin_conversion::to_xonly_pk_29;
 use crate::bitcoin_conversion::to_xonly_pk_30;
+use crate::commons::ContractSymbol;
+use crate::commons::OracleEventId;
 use crate::node::Node;
 use crate::node::Storage;
 use crate::on_chain_wallet::BdkStorage;
 use crate::storage::TenTenOneStorage;
+use anyhow::Context;
+use anyhow::Result;
 use bitcoin::secp256k1::XOnlyPublicKey;
+use dlc_manager::contract::contract_input::ContractInput;
 use dlc_manager::Oracle;
+use dlc_messages::contract_msgs;
+use dlc_messages::contract_msgs::ContractInfo;
+use dlc_messages::oracle_msgs::OracleAnnouncement;
+use dlc_messages::oracle_msgs::OracleAttestation;
 use p2pd_oracle_client::P2PDOracleClient;
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::str::FromStr;
+use time::OffsetDateTime;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OracleInfo {
@@ -25,6 +40,90 @@ impl From<OracleInfo> for P2PDOracleClient {
     }
 }
 
+/// A client of an oracle, which keeps the announcements it fetched.
+///
+/// Announcements can be fetched ahead of time with [`OracleClient::prefetch_announcement`], so
+/// that offering a contract on an event does not depend on the oracle being reachable at that
+/// moment.
+///
+/// The attestations of events can be withheld from the DLC manager with
+/// [`OracleClient::withhold_attestations`], which keeps it from settling DLC channels on those
+/// events.
+pub struct OracleClient {
+    client: P2PDOracleClient,
+    announcements: RwLock<HashMap<String, OracleAnnouncement>>,
+    withheld_attestations: RwLock<HashSet<String>>,
+}
+
+impl OracleClient {
+    pub fn new(client: P2PDOracleClient) -> Self {
+        Self {
+            client,
+            announcements: RwLock::new(HashMap::new()),
+            withheld_attestations: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Withhold the attestations of the given events, replacing the events withheld so far.
+    pub fn withhold_attestations(&self, event_ids: HashSet<String>) {
+        *self.withheld_attestations.write() = event_ids;
+    }
+
+    /// Fetch the announcement of the given event, unless it is cached already.
+    ///
+    /// Note, this function is blocking as it queries the oracle.
+    pub fn prefetch_announcement(&self, event_id: &str) -> Result<()> {
+        self.get_announcement(event_id)?;
+
+        Ok(())
+    }
+
+    /// Forget the cached announcements of events which matured before `now`.
+    pub fn prune_announcements(&self, now: OffsetDateTime) {
+        let now = now.unix_timestamp();
+
+        self.announcements
+            .write()
+            .retain(|_, announcement| announcement.oracle_event.event_maturity_epoch as i64 >= now);
+    }
+}
+
+impl Oracle for OracleClient {
+    fn get_public_key(&self) -> bitcoin_old::XOnlyPublicKey {
+        self.client.get_public_key()
+    }
+
+    fn get_announcement(
+        &self,
+        event_id: &str,
+    ) -> Result<OracleAnnouncement, dlc_manager::error::Error> {
+        if let Some(announcement) = self.announcements.read().get(event_id) {
+            return Ok(announcement.clone());
+        }
+
+        let announcement = self.client.get_announcement(event_id)?;
+
+        self.announcements
+            .write()
+            .insert(event_id.to_string(), announcement.clone());
+
+        Ok(announcement)
+    }
+
+    fn get_attestation(
+        &self,
+        event_id: &str,
+    ) -> Result<OracleAttestation, dlc_manager::error::Error> {
+        if self.withheld_attestations.read().contains(event_id) {
+            return Err(dlc_manager::error::Error::OracleError(format!(
+                "Attestation of event {event_id} is withheld"
+            )));
+        }
+
+        self.client.get_attestation(event_id)
+    }
+}
+
 impl<D: BdkStorage, S: TenTenOneStorage, N: Storage> Node<D, S, N> {
     pub fn oracle_pk(&self) -> Vec<XOnlyPublicKey> {
         self.oracles
@@ -33,4 +132,131 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage> Node<D, S, N> {
             .map(|oracle| to_xonly_pk_30(oracle.get_public_key()))
             .collect()
     }
+
+    /// Fetch the price attested by each of our oracles for the given event.
+    ///
+    /// Oracles which have not attested to the event (yet) are skipped. Withheld attestations are
+    /// included. Note, this function is blocking as it queries every oracle.
+    pub fn get_attested_prices(&self, event_id: &str) -> Vec<(XOnlyPublicKey, Decimal)> {
+        self.oracles
+            .iter()
+            .filter_map(|oracle| {
+                let public_key = to_xonly_pk_30(oracle.get_public_key());
+                let attestation = match oracle.client.get_attestation(event_id) {
+                    Ok(attestation) => attestation,
+                    Err(e) => {
+                        tracing::debug!(%public_key, event_id, "No attestation from oracle: {e:#}");
+                        return None;
+                    }
+                };
+
+                match Decimal::from_str_radix(&attestation.outcomes.join(""), 2) {
+                    Ok(price) => Some((public_key, price)),
+                    Err(e) => {
+                        tracing::warn!(%public_key, event_id, "Invalid attested price: {e:#}");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Withhold the attestations of the given events from the DLC manager, so that it does not
+    /// settle DLC channels on them.
+    pub fn withhold_attestations(&self, event_ids: HashSet<String>) {
+        for oracle in self.oracles.iter() {
+            oracle.withhold_attestations(event_ids.clone());
+        }
+    }
+
+    /// Fetch the announcement of the given event from each of our oracles ahead of time.
+    ///
+    /// Returns the oracles which have not announced the event (yet). Note, this function is
+    /// blocking as it queries every oracle whose announcement is not cached already.
+    pub fn prefetch_announcements(&self, event_id: &str) -> Vec<XOnlyPublicKey> {
+        self.oracles
+            .iter()
+            .filter_map(|oracle| {
+                let public_key = to_xonly_pk_30(oracle.get_public_key());
+                match oracle.prefetch_announcement(event_id) {
+                    Ok(()) => None,
+                    Err(e) => {
+                        tracing::debug!(%public_key, event_id, "No announcement from oracle: {e:#}");
+                        Some(public_key)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Forget the cached announcements of events which matured before `now`.
+    pub fn prune_announcements(&self, now: OffsetDateTime) {
+        for oracle in self.oracles.iter() {
+            oracle.prune_announcements(now);
+        }
+    }
+
+    /// The announcement of the given event by the given oracle, from the cache if it has been
+    /// fetched before.
+    ///
+    /// Note, this function is blocking if the announcement has to be fetched from the oracle.
+    pub fn get_announcement(
+        &self,
+        oracle_pk: XOnlyPublicKey,
+        event_id: &str,
+    ) -> Result<OracleAnnouncement> {
+        let oracle = self
+            .oracles
+            .iter()
+            .find(|oracle| to_xonly_pk_30(oracle.get_public_key()) == oracle_pk)
+            .with_context(|| format!("Unknown oracle {oracle_pk}"))?;
+
+        let announcement = oracle
+            .get_announcement(event_id)
+            .with_context(|| format!("No announcement for event {event_id} from {oracle_pk}"))?;
+
+        Ok(announcement)
+    }
+}
+
+/// Ensure that we only offer contracts on oracle events with a valid id.
+pub(crate) fn ensure_valid_event_ids(contract_input: &ContractInput) -> Result<()> {
+    for contract_info in contract_input.contract_infos.iter() {
+        OracleEventId::from_str(&contract_info.oracles.event_id)?;
+    }
+
+    Ok(())
+}
+
+/// Ensure that every oracle event of an offered contract attests the price of `contract_symbol`
+/// at the maturity announced by the oracle.
+///
+/// A contract on any other event could never be attested, so it must not be accepted.
+pub fn validate_oracle_events(
+    contract_info: &ContractInfo,
+    contract_symbol: ContractSymbol,
+) -> Result<()> {
+    let contract_infos = match contract_info {
+        ContractInfo::SingleContractInfo(info) => vec![&info.contract_info],
+        ContractInfo::DisjointContractInfo(info) => info.contract_infos.iter().collect(),
+    };
+
+    let announcements = contract_infos
+        .into_iter()
+        .flat_map(|info| match &info.oracle_info {
+            contract_msgs::OracleInfo::Single(info) => vec![&info.oracle_announcement],
+            contract_msgs::OracleInfo::Multi(info) => info.oracle_announcements.iter().collect(),
+        });
+
+    for announcement in announcements {
+        let event = &announcement.oracle_event;
+
+        let event_id = OracleEventId::from_str(&event.event_id)?;
+        let maturity = OffsetDateTime::from_unix_timestamp(event.event_maturity_epoch as i64)
+            .context("Invalid oracle event maturity")?;
+
+        event_id.ensure_matches(contract_symbol, maturity)?;
+    }
+
+    Ok(())
 }
@@ -1,5 +1,7 @@
+use crate::dlc_message::SerializedDlcMessage;
 use crate::transaction::Transaction;
 use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
 use lightning::chain::transaction::OutPoint;
 use lightning::sign::DelayedPaymentOutputDescriptor;
 use lightning::sign::SpendableOutputDescriptor;
@@ -41,12 +43,31 @@ pub trait Storage {
     fn get_transaction(&self, txid: &str) -> Result<Option<Transaction>>;
     /// Get all transactions without fees
     fn all_transactions_without_fees(&self) -> Result<Vec<Transaction>>;
+
+    // Last outbound DLC messages
+
+    /// Insert or replace the last outbound DLC message sent to `peer`.
+    fn upsert_last_outbound_dlc_message(
+        &self,
+        peer: PublicKey,
+        message: SerializedDlcMessage,
+    ) -> Result<()>;
+    /// Get the last outbound DLC message sent to `peer`.
+    fn get_last_outbound_dlc_message(
+        &self,
+        peer: &PublicKey,
+    ) -> Result<Option<SerializedDlcMessage>>;
+    /// Delete the last outbound DLC message sent to `peer`.
+    fn delete_last_outbound_dlc_message(&self, peer: &PublicKey) -> Result<()>;
+    /// Get the last outbound DLC message of every peer.
+    fn all_last_outbound_dlc_messages(&self) -> Result<Vec<(PublicKey, SerializedDlcMessage)>>;
 }
 
 #[derive(Default, Clone)]
 pub struct InMemoryStore {
     spendable_outputs: Arc<Mutex<HashMap<OutPoint, SpendableOutputDescriptor>>>,
     transactions: Arc<Mutex<HashMap<String, Transaction>>>,
+    last_outbound_dlc_messages: Arc<Mutex<HashMap<PublicKey, SerializedDlcMessage>>>,
 }
 
 impl Storage for InMemoryStore {
@@ -106,4 +127,36 @@ impl Storage for InMemoryStore {
             .cloned()
             .collect())
     }
+
+    // Last outbound DLC messages
+
+    fn upsert_last_outbound_dlc_message(
+        &self,
+        peer: PublicKey,
+        message: SerializedDlcMessage,
+    ) -> Result<()> {
+        self.last_outbound_dlc_messages.lock().insert(peer, message);
+        Ok(())
+    }
+
+    fn get_last_outbound_dlc_message(
+        &self,
+        peer: &PublicKey,
+    ) -> Result<Option<SerializedDlcMessage>> {
+        Ok(self.last_outbound_dlc_messages.lock().get(peer).cloned())
+    }
+
+    fn delete_last_outbound_dlc_message(&self, peer: &PublicKey) -> Result<()> {
+        self.last_outbound_dlc_messages.lock().remove(peer);
+        Ok(())
+    }
+
+    fn all_last_outbound_dlc_messages(&self) -> Result<Vec<(PublicKey, SerializedDlcMessage)>> {
+        Ok(self
+            .last_outbound_dlc_messages
+            .lock()
+            .iter()
+            .map(|(peer, message)| (*peer, message.clone()))
+            .collect())
+    }
 }
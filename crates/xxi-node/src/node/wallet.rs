@@ -2,32 +2,38 @@ use crate::bitcoin_conversion::to_secp_sk_30;
 use crate::node::Node;
 use crate::node::Storage;
 use crate::on_chain_wallet::BdkStorage;
+use crate::on_chain_wallet::ConfirmationStatus;
+use crate::on_chain_wallet::Deposit;
+use crate::on_chain_wallet::DescriptorBalance;
 use crate::on_chain_wallet::FeeConfig;
 use crate::on_chain_wallet::OnChainWallet;
 use crate::on_chain_wallet::TransactionDetails;
+use crate::on_chain_wallet::WalletDescriptors;
+use crate::on_chain_wallet::Watch;
+use crate::on_chain_wallet::WatchedTransaction;
 use crate::storage::TenTenOneStorage;
 use anyhow::Context;
 use anyhow::Result;
-use bdk_esplora::EsploraAsyncExt;
 use bitcoin::secp256k1::SecretKey;
 use bitcoin::Address;
 use bitcoin::Amount;
 use bitcoin::OutPoint;
 use bitcoin::ScriptBuf;
 use bitcoin::TxOut;
+use bitcoin::Txid;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::task::spawn_blocking;
 
-/// The number of parallel requests to be used during the on-chain sync.
-///
-/// This number was chosen arbitrarily.
-const PARALLEL_REQUESTS_SYNC: usize = 5;
-
 impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Node<D, S, N> {
     pub fn wallet(&self) -> Arc<OnChainWallet<D>> {
         self.wallet.clone()
     }
 
+    async fn bdk_client_concurrency(&self) -> usize {
+        self.settings.read().await.bdk_client_concurrency.max(1) as usize
+    }
+
     pub fn get_new_address(&self) -> Result<Address> {
         self.wallet.get_new_address()
     }
@@ -46,6 +52,14 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Nod
         self.wallet.get_balance()
     }
 
+    pub fn get_on_chain_balance_by_descriptor(&self) -> DescriptorBalance {
+        self.wallet.get_balance_by_descriptor()
+    }
+
+    pub fn get_wallet_descriptors(&self) -> WalletDescriptors {
+        self.wallet.descriptors()
+    }
+
     pub fn node_key(&self) -> SecretKey {
         to_secp_sk_30(self.keys_manager.get_node_secret_key())
     }
@@ -54,6 +68,28 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Nod
         self.wallet.get_on_chain_history()
     }
 
+    pub fn get_deposits(&self) -> Vec<Deposit> {
+        self.wallet.get_deposits()
+    }
+
+    /// Follow a transaction or address outside of the wallet with every sync, see
+    /// [`OnChainWallet::watch`].
+    pub fn watch(&self, watch: Watch) -> bool {
+        self.wallet.watch(watch)
+    }
+
+    pub fn unwatch(&self, watch: &Watch) -> bool {
+        self.wallet.unwatch(watch)
+    }
+
+    pub fn get_watched_transactions(&self) -> Vec<WatchedTransaction> {
+        self.wallet.get_watched_transactions()
+    }
+
+    pub fn get_confirmation_status(&self, txid: &Txid) -> ConfirmationStatus {
+        self.wallet.get_confirmation_status(txid)
+    }
+
     pub fn get_utxos(&self) -> Vec<(OutPoint, TxOut)> {
         self.wallet.get_utxos()
     }
@@ -69,8 +105,13 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Nod
     }
 
     /// Sync the state of the on-chain wallet against the blockchain.
+    ///
+    /// The script histories are fetched from electrs in batches of `bdk_client_concurrency`
+    /// parallel requests. Every request which fails with a transient error is retried on its own.
     pub async fn sync_on_chain_wallet(&self) -> Result<()> {
         let client = &self.blockchain.esplora_client_async;
+        let concurrency = self.bdk_client_concurrency().await;
+        let started_at = Instant::now();
 
         let (local_chain, unused_revealed_script_pubkeys, unconfirmed_txids, utxos) =
             spawn_blocking({
@@ -85,12 +126,14 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Nod
                 unused_revealed_script_pubkeys,
                 unconfirmed_txids,
                 utxos,
-                PARALLEL_REQUESTS_SYNC,
+                concurrency,
             )
             .await?;
 
         let chain_update = {
-            let missing_heights = graph_update.missing_heights(&local_chain);
+            let missing_heights = graph_update
+                .missing_heights(&local_chain)
+                .collect::<Vec<_>>();
 
             client
                 .update_local_chain(local_chain.tip(), missing_heights)
@@ -118,13 +161,21 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Nod
         // find already spent utxos and release those which were locked unnecessarily.
         self.wallet.locked_utxos.lock().clear();
 
+        tracing::debug!(
+            elapsed_ms = started_at.elapsed().as_millis(),
+            concurrency,
+            "Synced on-chain wallet"
+        );
+
         Ok(())
     }
 
     pub async fn full_sync(&self, stop_gap: usize) -> Result<()> {
         let client = &self.blockchain.esplora_client_async;
+        let concurrency = self.bdk_client_concurrency().await;
+        let started_at = Instant::now();
 
-        tracing::info!("Running full sync of on-chain wallet");
+        tracing::info!(concurrency, "Running full sync of on-chain wallet");
 
         let (local_chain, all_script_pubkeys) = spawn_blocking({
             let wallet = self.wallet.clone();
@@ -139,11 +190,13 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Nod
         .expect("task to complete");
 
         let (graph_update, last_active_indices) = client
-            .full_scan(all_script_pubkeys, stop_gap, PARALLEL_REQUESTS_SYNC)
+            .full_scan(all_script_pubkeys, stop_gap, concurrency)
             .await?;
 
         let chain_update = {
-            let missing_heights = graph_update.missing_heights(&local_chain);
+            let missing_heights = graph_update
+                .missing_heights(&local_chain)
+                .collect::<Vec<_>>();
 
             client
                 .update_local_chain(local_chain.tip(), missing_heights)
@@ -167,7 +220,10 @@ impl<D: BdkStorage, S: TenTenOneStorage, N: Storage + Send + Sync + 'static> Nod
         .await
         .expect("task to complete")?;
 
-        tracing::info!("Finished full sync of on-chain wallet");
+        tracing::info!(
+            elapsed_ms = started_at.elapsed().as_millis(),
+            "Finished full sync of on-chain wallet"
+        );
 
         Ok(())
     }
@@ -3,6 +3,8 @@ use crate::fee_rate_estimator::FeeRateEstimator;
 use crate::seed::WalletSeed;
 use anyhow::anyhow;
 use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
 use anyhow::Result;
 use bdk::chain::indexed_tx_graph::Indexer;
 use bdk::chain::local_chain::LocalChain;
@@ -11,6 +13,8 @@ use bdk::chain::tx_graph::CanonicalTx;
 use bdk::chain::Append;
 use bdk::chain::ChainPosition;
 use bdk::chain::PersistBackend;
+use bdk::miniscript::Descriptor;
+use bdk::miniscript::DescriptorPublicKey;
 use bdk::psbt::PsbtUtils;
 use bdk::wallet::IsDust;
 use bdk::FeeRate;
@@ -32,8 +36,12 @@ use bitcoin::Txid;
 use lightning::chain::chaininterface::ConfirmationTarget;
 use parking_lot::Mutex;
 use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
 use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::num::NonZeroU32;
+use std::str::FromStr;
 use std::sync::Arc;
 use time::OffsetDateTime;
 
@@ -49,6 +57,8 @@ pub struct OnChainWallet<D> {
     pub(crate) fee_rate_estimator: Arc<FeeRateEstimator>,
     pub(crate) network: Network,
     pub(crate) secp: Secp256k1<All>,
+    /// Transactions and addresses outside of the wallet which are synced alongside it.
+    watches: Arc<RwLock<Vec<Watch>>>,
 }
 
 impl<D> OnChainWallet<D> {
@@ -83,6 +93,179 @@ impl<D> OnChainWallet<D> {
         .collect()
     }
 
+    /// List the transactions paying to this wallet, including unconfirmed ones and those which
+    /// have been replaced by a conflicting transaction.
+    pub fn get_deposits(&self) -> Vec<Deposit> {
+        let bdk = self.bdk.read();
+
+        // The transactions in the best chain by the outputs they spend, to find the transaction
+        // which replaced a deposit.
+        let spent_by = bdk
+            .transactions()
+            .flat_map(|tx| {
+                let txid = tx.tx_node.txid;
+                tx.tx_node
+                    .tx
+                    .input
+                    .iter()
+                    .map(move |input| (input.previous_output, txid))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let confidence = |tx: &Transaction| {
+            let spends_own_outputs = tx.input.iter().all(|input| {
+                bdk.tx_graph()
+                    .get_txout(input.previous_output)
+                    .map(|txout| bdk.is_mine(&txout.script_pubkey))
+                    .unwrap_or(false)
+            });
+
+            if spends_own_outputs {
+                DepositConfidence::High
+            } else if tx.input.iter().any(|input| input.sequence.is_rbf()) {
+                DepositConfidence::Low
+            } else {
+                DepositConfidence::Medium
+            }
+        };
+
+        bdk.tx_graph()
+            .full_txs()
+            .filter(|tx| bdk.spk_index().is_tx_relevant(tx.tx))
+            .filter_map(|tx| {
+                let (sent, received) = bdk.sent_and_received(tx.tx);
+                let amount = received.checked_sub(sent).filter(|amount| *amount > 0)?;
+
+                let status = match bdk.get_tx(tx.txid) {
+                    Some(CanonicalTx {
+                        chain_position: ChainPosition::Confirmed(_),
+                        ..
+                    }) => DepositStatus::Confirmed,
+                    Some(CanonicalTx {
+                        chain_position: ChainPosition::Unconfirmed(_),
+                        ..
+                    }) => DepositStatus::Unconfirmed {
+                        confidence: confidence(tx.tx),
+                    },
+                    // Transactions which are not part of the best chain and do not conflict with
+                    // it have just been evicted from the mempool. We ignore them.
+                    None => {
+                        let replaced_by = tx
+                            .tx
+                            .input
+                            .iter()
+                            .find_map(|input| spent_by.get(&input.previous_output))?;
+
+                        DepositStatus::Replaced {
+                            replaced_by: *replaced_by,
+                        }
+                    }
+                };
+
+                Some(Deposit {
+                    txid: tx.txid,
+                    amount: Amount::from_sat(amount),
+                    status,
+                })
+            })
+            .collect()
+    }
+
+    /// Sync the given transaction or address alongside the wallet, e.g. to follow a deposit from
+    /// an exchange as soon as it is broadcast.
+    ///
+    /// Returns `false` if it was already watched.
+    pub fn watch(&self, watch: Watch) -> bool {
+        let mut watches = self.watches.write();
+        if watches.contains(&watch) {
+            return false;
+        }
+
+        watches.push(watch);
+
+        true
+    }
+
+    /// Stop watching the given transaction or address.
+    ///
+    /// Returns `false` if it was not watched.
+    pub fn unwatch(&self, watch: &Watch) -> bool {
+        let mut watches = self.watches.write();
+        let len = watches.len();
+        watches.retain(|watched| watched != watch);
+
+        watches.len() < len
+    }
+
+    pub fn get_watches(&self) -> Vec<Watch> {
+        self.watches.read().clone()
+    }
+
+    /// List the watched transactions we have seen, and the transactions paying to the watched
+    /// addresses.
+    ///
+    /// Watched transactions which we have not seen yet are not included.
+    pub fn get_watched_transactions(&self) -> Vec<WatchedTransaction> {
+        let bdk = self.bdk.read();
+
+        let status = |confirmed: bool| {
+            if confirmed {
+                WatchedStatus::Confirmed
+            } else {
+                WatchedStatus::Mempool
+            }
+        };
+
+        let mut watched = vec![];
+        for watch in self.watches.read().iter() {
+            match watch {
+                Watch::Txid(txid) => {
+                    let tx = match bdk.get_tx(*txid) {
+                        Some(tx) => tx,
+                        None => continue,
+                    };
+
+                    // A transaction from an exchange will usually pay to one of our addresses.
+                    let (_, received) = bdk.sent_and_received(tx.tx_node.tx);
+
+                    watched.push(WatchedTransaction {
+                        watch: watch.clone(),
+                        txid: *txid,
+                        amount: Amount::from_sat(received),
+                        status: status(tx.chain_position.is_confirmed()),
+                    });
+                }
+                Watch::Address(address) => {
+                    let script_pubkey = address.script_pubkey();
+
+                    for tx in bdk.transactions() {
+                        let amount = tx
+                            .tx_node
+                            .tx
+                            .output
+                            .iter()
+                            .filter(|output| output.script_pubkey == script_pubkey)
+                            .map(|output| output.value)
+                            .sum::<u64>();
+
+                        if amount == 0 {
+                            continue;
+                        }
+
+                        watched.push(WatchedTransaction {
+                            watch: watch.clone(),
+                            txid: tx.tx_node.txid,
+                            amount: Amount::from_sat(amount),
+                            status: status(tx.chain_position.is_confirmed()),
+                        });
+                    }
+                }
+            }
+        }
+
+        watched
+    }
+
     pub fn network(&self) -> Network {
         self.bdk.read().network()
     }
@@ -156,6 +339,39 @@ impl<D> OnChainWallet<D> {
             .collect()
     }
 
+    /// The value of the unspent outputs of each descriptor of the wallet, including unconfirmed
+    /// ones.
+    pub fn get_balance_by_descriptor(&self) -> DescriptorBalance {
+        let bdk = self.bdk.read();
+
+        let mut balance = DescriptorBalance::default();
+        for local_output in bdk.list_unspent() {
+            let value = Amount::from_sat(local_output.txout.value);
+            match local_output.keychain {
+                KeychainKind::External => balance.external += value,
+                KeychainKind::Internal => balance.internal += value,
+            }
+        }
+
+        balance
+    }
+
+    /// The public descriptors the wallet derives its addresses from.
+    pub fn descriptors(&self) -> WalletDescriptors {
+        let bdk = self.bdk.read();
+
+        let descriptor = |keychain| {
+            bdk.public_descriptor(keychain)
+                .map(|descriptor| descriptor.to_string())
+                .expect("descriptor of both keychains")
+        };
+
+        WalletDescriptors {
+            external: descriptor(KeychainKind::External),
+            internal: descriptor(KeychainKind::Internal),
+        }
+    }
+
     pub(crate) fn is_mine(&self, script_pubkey: &ScriptBuf) -> bool {
         self.bdk.read().is_mine(script_pubkey)
     }
@@ -192,18 +408,34 @@ impl<D> OnChainWallet<D> {
 
         let local_chain = bdk.local_chain().clone();
 
+        let watches = self.watches.read();
+
         // We must watch every new address we generate (until it is used).
         let unused_revealed_script_pubkeys = bdk
             .spk_index()
             .unused_spks()
             .map(|(_, _, s)| ScriptBuf::from(s))
+            .chain(watches.iter().filter_map(|watch| match watch {
+                Watch::Address(address) => Some(address.script_pubkey()),
+                Watch::Txid(_) => None,
+            }))
             .collect();
 
+        // Watched transactions are synced until they confirm, just like our own.
+        let watched_txids = watches.iter().filter_map(|watch| match watch {
+            Watch::Txid(txid) => Some(*txid),
+            Watch::Address(_) => None,
+        });
         let unconfirmed_txids = bdk
             .tx_graph()
             .list_chain_txs(&local_chain, local_chain.tip().block_id())
             .filter(|tx| !tx.chain_position.is_confirmed())
             .map(|tx| tx.tx_node.txid)
+            .chain(watched_txids.filter(|txid| {
+                !bdk.get_tx(*txid)
+                    .map(|tx| tx.chain_position.is_confirmed())
+                    .unwrap_or(false)
+            }))
             .collect();
 
         let indexed_outpoints = bdk.spk_index().outpoints().iter().cloned();
@@ -265,6 +497,7 @@ where
             fee_rate_estimator,
             network,
             secp,
+            watches: Default::default(),
         })
     }
 
@@ -328,6 +561,136 @@ where
         Ok(tx)
     }
 
+    /// The fee rate of the transaction with the given `txid`, or `None` if it is already
+    /// confirmed.
+    ///
+    /// Fails if the wallet does not know the transaction, or the outputs it spends.
+    pub(crate) fn unconfirmed_tx_fee_rate(&self, txid: Txid) -> Result<Option<FeeRate>> {
+        let bdk = self.bdk.read();
+
+        let tx = bdk
+            .get_tx(txid)
+            .with_context(|| format!("Unknown transaction {txid}"))?;
+        if let ChainPosition::Confirmed(_) = tx.chain_position {
+            return Ok(None);
+        }
+
+        let fee = bdk
+            .calculate_fee(tx.tx_node.tx)
+            .map_err(|e| anyhow!("Failed to calculate fee of {txid}: {e:?}"))?;
+
+        Ok(Some(FeeRate::from_sat_per_vb(
+            fee as f32 / tx.tx_node.tx.vsize() as f32,
+        )))
+    }
+
+    /// Build a child transaction spending the outputs of the unconfirmed transaction with the
+    /// given `parent_txid` which belong to the wallet, so that both transactions together pay
+    /// the fee rate of `fee_config` (child pays for parent). Fails if the child would have to pay
+    /// more than `max_fee`.
+    ///
+    /// Returns the child transaction and the fee it pays.
+    pub(crate) fn build_cpfp_tx(
+        &self,
+        parent_txid: Txid,
+        fee_config: FeeConfig,
+        max_fee: Amount,
+    ) -> Result<(Transaction, Amount)> {
+        let fee_rate = self.fee_rate_from_config(fee_config);
+
+        let (parent_fee, parent_vsize, outpoints) = {
+            let bdk = self.bdk.read();
+
+            let parent = bdk
+                .get_tx(parent_txid)
+                .with_context(|| format!("Unknown transaction {parent_txid}"))?;
+            if let ChainPosition::Confirmed(_) = parent.chain_position {
+                bail!("Transaction {parent_txid} is already confirmed");
+            }
+
+            let parent_fee = bdk
+                .calculate_fee(parent.tx_node.tx)
+                .map_err(|e| anyhow!("Failed to calculate fee of {parent_txid}: {e:?}"))?;
+
+            let outpoints = parent
+                .tx_node
+                .tx
+                .output
+                .iter()
+                .enumerate()
+                .filter(|(_, txo)| bdk.is_mine(&txo.script_pubkey))
+                .map(|(vout, _)| OutPoint::new(parent_txid, vout as u32))
+                .collect::<Vec<_>>();
+
+            (parent_fee, parent.tx_node.tx.vsize(), outpoints)
+        };
+
+        ensure!(
+            !outpoints.is_empty(),
+            "Transaction {parent_txid} has no output to spend"
+        );
+
+        let change = self
+            .bdk
+            .write()
+            .try_get_internal_address(bdk::wallet::AddressIndex::New)
+            .map_err(|e| anyhow!("{e:?}"))?
+            .script_pubkey();
+
+        let build_psbt = |fee: Option<u64>| {
+            let wallet = &mut self.bdk.write();
+            let mut builder = wallet.build_tx();
+
+            builder
+                .add_utxos(&outpoints)
+                .map_err(|e| anyhow!("{e:?}"))?
+                .manually_selected_only()
+                .drain_to(change.clone());
+
+            match fee {
+                Some(fee) => builder.fee_absolute(fee),
+                None => builder.fee_rate(fee_rate),
+            };
+
+            builder.finish().map_err(|e| anyhow!("{e:?}"))
+        };
+
+        // The fee of the child at the target fee rate tells us its size, from which we can
+        // compute the fee it has to pay for the whole package.
+        let child_fee = build_psbt(None)?
+            .fee_amount()
+            .context("Missing fee of child transaction")?;
+        let fee = cpfp_child_fee(parent_fee, parent_vsize, child_fee, fee_rate);
+        ensure!(
+            fee <= max_fee.to_sat(),
+            "CPFP transaction would pay {fee} sats, more than the maximum of {max_fee}"
+        );
+
+        let mut psbt = build_psbt(Some(fee))?;
+        let finalized = self
+            .bdk
+            .write()
+            .sign(&mut psbt, SignOptions::default())
+            .map_err(|e| anyhow!("{e:?}"))?;
+        if !finalized {
+            bail!("PSBT not finalized");
+        }
+
+        let tx = psbt.extract_tx();
+
+        self.locked_utxos.lock().extend(outpoints);
+
+        tracing::info!(
+            %parent_txid,
+            txid = %tx.txid(),
+            fee,
+            fee_rate = fee_rate.as_sat_per_vb(),
+            "Built CPFP transaction"
+        );
+
+        Ok((tx, Amount::from_sat(fee)))
+    }
+
     /// Build a PSBT to send some sats to an [`Address`].
     pub fn build_psbt(
         &self,
@@ -452,6 +815,181 @@ impl ConfirmationStatus {
     }
 }
 
+/// The balance of the wallet split by descriptor.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DescriptorBalance {
+    /// Received on the addresses handed out for deposits.
+    pub external: Amount,
+    /// Change of our own transactions.
+    pub internal: Amount,
+}
+
+/// The public descriptors of the wallet.
+///
+/// They are part of the backup, so that a restored wallet can detect whether it derives its
+/// addresses differently than the wallet which was backed up, e.g. after a change of the
+/// derivation between versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletDescriptors {
+    pub external: String,
+    pub internal: String,
+}
+
+impl WalletDescriptors {
+    /// The first `count` addresses of both descriptors.
+    pub fn addresses(&self, network: Network, count: u32) -> Result<Vec<Address>> {
+        let mut addresses = Vec::with_capacity(2 * count as usize);
+        for descriptor in [&self.external, &self.internal] {
+            let descriptor = Descriptor::<DescriptorPublicKey>::from_str(descriptor)
+                .map_err(|e| anyhow!("Invalid descriptor {descriptor}: {e}"))?;
+
+            for index in 0..count {
+                let address = descriptor
+                    .at_derivation_index(index)
+                    .map_err(|e| anyhow!("Failed to derive address {index}: {e}"))?
+                    .address(network)
+                    .map_err(|e| anyhow!("Failed to derive address {index}: {e}"))?;
+
+                addresses.push(address);
+            }
+        }
+
+        Ok(addresses)
+    }
+}
+
+/// A transaction paying to our wallet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deposit {
+    pub txid: Txid,
+    /// The amount we receive, net of what the transaction spends from our wallet.
+    pub amount: Amount,
+    pub status: DepositStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepositStatus {
+    Unconfirmed {
+        confidence: DepositConfidence,
+    },
+    Confirmed,
+    /// The deposit was replaced by a conflicting transaction spending the same inputs, e.g. a
+    /// fee bump using RBF. The replacing transaction does not necessarily pay to us.
+    Replaced {
+        replaced_by: Txid,
+    },
+}
+
+/// How likely an unconfirmed deposit is to confirm as it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepositConfidence {
+    /// The transaction signals replaceability (BIP125), so the sender can easily replace it.
+    Low,
+    /// The transaction does not signal replaceability, but could still be double-spent.
+    Medium,
+    /// The transaction only spends our own outputs, so nobody else can double-spend it.
+    High,
+}
+
+/// Keeps track of the status of our deposits, to report every change.
+#[derive(Debug, Default)]
+pub struct DepositTracker {
+    statuses: BTreeMap<Txid, DepositStatus>,
+}
+
+impl DepositTracker {
+    pub const fn new() -> Self {
+        Self {
+            statuses: BTreeMap::new(),
+        }
+    }
+
+    /// Update the tracked deposits and return those whose status changed.
+    ///
+    /// A deposit which is already confirmed or replaced when we first see it is tracked, but not
+    /// reported. Otherwise, we would report the entire history on startup.
+    pub fn update(&mut self, deposits: Vec<Deposit>) -> Vec<Deposit> {
+        deposits
+            .into_iter()
+            .filter(
+                |deposit| match self.statuses.insert(deposit.txid, deposit.status) {
+                    Some(previous_status) => previous_status != deposit.status,
+                    None => matches!(deposit.status, DepositStatus::Unconfirmed { .. }),
+                },
+            )
+            .collect()
+    }
+}
+
+/// A transaction or address outside of the wallet which we follow on-chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Watch {
+    /// A transaction we expect, e.g. because the exchange told us its ID.
+    Txid(Txid),
+    /// An address which is not derived from our wallet, e.g. of an external wallet.
+    Address(Address),
+}
+
+/// A watched transaction, or a transaction paying to a watched address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchedTransaction {
+    /// What the transaction was found for.
+    pub watch: Watch,
+    pub txid: Txid,
+    /// The amount paid to the watched address, or to our wallet in case of a watched transaction.
+    pub amount: Amount,
+    pub status: WatchedStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchedStatus {
+    Mempool,
+    Confirmed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    /// We saw the transaction for the first time, be it in the mempool or already confirmed.
+    FirstSeen(WatchedTransaction),
+    /// The transaction is back in the mempool, e.g. because its block was reorged.
+    Mempool(WatchedTransaction),
+    Confirmed(WatchedTransaction),
+}
+
+/// Keeps track of the status of the watched transactions, to report every change.
+#[derive(Debug, Default)]
+pub struct WatchTracker {
+    statuses: BTreeMap<Txid, WatchedStatus>,
+}
+
+impl WatchTracker {
+    pub const fn new() -> Self {
+        Self {
+            statuses: BTreeMap::new(),
+        }
+    }
+
+    /// Update the tracked transactions and return what changed.
+    ///
+    /// Unlike [`DepositTracker::update`], transactions which are already confirmed when we first
+    /// see them are reported, since they were explicitly watched.
+    pub fn update(&mut self, transactions: Vec<WatchedTransaction>) -> Vec<WatchEvent> {
+        transactions
+            .into_iter()
+            .filter_map(|transaction| {
+                match self.statuses.insert(transaction.txid, transaction.status) {
+                    None => Some(WatchEvent::FirstSeen(transaction)),
+                    Some(previous_status) if previous_status == transaction.status => None,
+                    Some(_) => match transaction.status {
+                        WatchedStatus::Mempool => Some(WatchEvent::Mempool(transaction)),
+                        WatchedStatus::Confirmed => Some(WatchEvent::Confirmed(transaction)),
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
 /// Fee configuration for an on-chain transaction.
 #[derive(Clone, Copy)]
 pub enum FeeConfig {
@@ -498,3 +1036,200 @@ impl PersistBackend<bdk::wallet::ChangeSet> for InMemoryStorage {
         Ok(self.0.clone())
     }
 }
+
+/// The fee a child transaction has to pay, so that it and its parent together pay `fee_rate`.
+///
+/// `child_fee` is the fee of the child at `fee_rate`, from which we derive its size. The child
+/// never pays less than that.
+fn cpfp_child_fee(parent_fee: u64, parent_vsize: usize, child_fee: u64, fee_rate: FeeRate) -> u64 {
+    let child_vsize = (child_fee as f32 / fee_rate.as_sat_per_vb()).ceil() as usize;
+
+    let package_fee = fee_rate.fee_vb(parent_vsize + child_vsize);
+    package_fee.saturating_sub(parent_fee).max(child_fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+
+    fn txid(n: u8) -> Txid {
+        Txid::from_byte_array([n; 32])
+    }
+
+    fn deposit(n: u8, status: DepositStatus) -> Deposit {
+        Deposit {
+            txid: txid(n),
+            amount: Amount::from_sat(10_000),
+            status,
+        }
+    }
+
+    const UNCONFIRMED: DepositStatus = DepositStatus::Unconfirmed {
+        confidence: DepositConfidence::Low,
+    };
+
+    #[test]
+    fn reports_new_unconfirmed_deposit() {
+        let mut tracker = DepositTracker::new();
+
+        let changed = tracker.update(vec![deposit(1, UNCONFIRMED)]);
+
+        assert_eq!(changed, vec![deposit(1, UNCONFIRMED)]);
+    }
+
+    #[test]
+    fn does_not_report_history() {
+        let mut tracker = DepositTracker::new();
+
+        let changed = tracker.update(vec![
+            deposit(1, DepositStatus::Confirmed),
+            deposit(
+                2,
+                DepositStatus::Replaced {
+                    replaced_by: txid(3),
+                },
+            ),
+        ]);
+
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn reports_status_changes_only() {
+        let mut tracker = DepositTracker::new();
+        tracker.update(vec![deposit(1, UNCONFIRMED), deposit(2, UNCONFIRMED)]);
+
+        let replaced = DepositStatus::Replaced {
+            replaced_by: txid(3),
+        };
+        let changed = tracker.update(vec![
+            deposit(1, DepositStatus::Confirmed),
+            deposit(2, replaced),
+        ]);
+
+        assert_eq!(
+            changed,
+            vec![deposit(1, DepositStatus::Confirmed), deposit(2, replaced)]
+        );
+
+        let changed = tracker.update(vec![
+            deposit(1, DepositStatus::Confirmed),
+            deposit(2, replaced),
+        ]);
+
+        assert!(changed.is_empty());
+    }
+
+    fn watched(n: u8, status: WatchedStatus) -> WatchedTransaction {
+        WatchedTransaction {
+            watch: Watch::Txid(txid(n)),
+            txid: txid(n),
+            amount: Amount::from_sat(10_000),
+            status,
+        }
+    }
+
+    #[test]
+    fn reports_watched_transaction_from_first_seen_to_confirmed() {
+        let mut tracker = WatchTracker::new();
+
+        let changed = tracker.update(vec![
+            watched(1, WatchedStatus::Mempool),
+            watched(2, WatchedStatus::Confirmed),
+        ]);
+
+        assert_eq!(
+            changed,
+            vec![
+                WatchEvent::FirstSeen(watched(1, WatchedStatus::Mempool)),
+                WatchEvent::FirstSeen(watched(2, WatchedStatus::Confirmed)),
+            ]
+        );
+
+        let changed = tracker.update(vec![
+            watched(1, WatchedStatus::Confirmed),
+            watched(2, WatchedStatus::Confirmed),
+        ]);
+
+        assert_eq!(
+            changed,
+            vec![WatchEvent::Confirmed(watched(1, WatchedStatus::Confirmed))]
+        );
+
+        let changed = tracker.update(vec![watched(1, WatchedStatus::Mempool)]);
+
+        assert_eq!(
+            changed,
+            vec![WatchEvent::Mempool(watched(1, WatchedStatus::Mempool))]
+        );
+    }
+
+    #[test]
+    fn reports_confidence_changes() {
+        let mut tracker = DepositTracker::new();
+        tracker.update(vec![deposit(1, UNCONFIRMED)]);
+
+        let high_confidence = DepositStatus::Unconfirmed {
+            confidence: DepositConfidence::High,
+        };
+        let changed = tracker.update(vec![deposit(1, high_confidence)]);
+
+        assert_eq!(changed, vec![deposit(1, high_confidence)]);
+    }
+
+    #[test]
+    fn derives_addresses_of_both_descriptors() {
+        // Test vector of BIP84.
+        let xpub = "[73c5da0a/84'/0'/0']xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+        let descriptors = WalletDescriptors {
+            external: format!("wpkh({xpub}/0/*)"),
+            internal: format!("wpkh({xpub}/1/*)"),
+        };
+
+        let addresses = descriptors
+            .addresses(Network::Bitcoin, 1)
+            .unwrap()
+            .iter()
+            .map(|address| address.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            addresses,
+            vec![
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el"
+            ]
+        );
+    }
+
+    #[test]
+    fn cpfp_child_bumps_close_transaction_to_agreed_fee_rate() {
+        // A close transaction paying 1 sat/vB, to be bumped to the agreed 10 sats/vB.
+        let close_fee = 200;
+        let close_vsize = 200;
+        let agreed_fee_rate = FeeRate::from_sat_per_vb(10.0);
+        let child_fee_at_agreed_rate = 1_100;
+        let child_vsize = 110;
+
+        let fee = cpfp_child_fee(
+            close_fee,
+            close_vsize,
+            child_fee_at_agreed_rate,
+            agreed_fee_rate,
+        );
+
+        assert_eq!(fee, 2_900);
+
+        let package_fee_rate = (close_fee + fee) as f32 / (close_vsize + child_vsize) as f32;
+        assert_eq!(package_fee_rate, agreed_fee_rate.as_sat_per_vb());
+    }
+
+    #[test]
+    fn cpfp_child_pays_at_least_its_own_fee() {
+        // The close transaction already pays 20 sats/vB.
+        let fee = cpfp_child_fee(4_000, 200, 1_100, FeeRate::from_sat_per_vb(10.0));
+
+        assert_eq!(fee, 1_100);
+    }
+}
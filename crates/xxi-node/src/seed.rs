@@ -84,6 +84,26 @@ impl Bip39Seed {
         }
     }
 
+    /// The key used to encrypt data at rest, e.g. the coordinator's message archive.
+    pub fn encryption_key(&self) -> [u8; 32] {
+        let mut key = [0u8; 32];
+
+        Hkdf::<Sha256>::new(None, &self.seed())
+            .expand(b"ENCRYPTION_KEY", &mut key)
+            .expect("array is of correct length");
+        key
+    }
+
+    /// The key used to encrypt the DLC storage at rest.
+    pub fn dlc_storage_key(&self) -> [u8; 32] {
+        let mut key = [0u8; 32];
+
+        Hkdf::<Sha256>::new(None, &self.seed())
+            .expand(b"DLC_STORAGE_KEY", &mut key)
+            .expect("array is of correct length");
+        key
+    }
+
     pub fn get_seed_phrase(&self) -> Vec<String> {
         self.mnemonic.word_iter().map(|word| word.into()).collect()
     }
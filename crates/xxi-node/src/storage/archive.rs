@@ -0,0 +1,191 @@
+//! Archive of the contracts which reached a final state.
+//!
+//! Closed and refunded contracts are never updated again, but would otherwise be loaded and
+//! deserialized together with the active contracts. They are moved into their own kind instead,
+//! next to a compact index of when they were archived, which is used to page through and to prune
+//! the archive without deserializing it.
+
+use crate::storage::deserialize_contract;
+use crate::storage::to_storage_error;
+use crate::storage::DlcStorageProvider;
+use crate::storage::DlcStoreProvider;
+use crate::storage::Op;
+use crate::storage::ARCHIVED_CONTRACT;
+use crate::storage::ARCHIVE_INDEX;
+use crate::storage::CONTRACT;
+use anyhow::Context;
+use anyhow::Result;
+use dlc_manager::contract::Contract;
+use dlc_manager::error::Error;
+use time::OffsetDateTime;
+
+/// Whether the contract reached a final state and belongs into the archive.
+pub(crate) fn is_archivable(contract: &Contract) -> bool {
+    matches!(contract, Contract::Closed(_) | Contract::Refunded(_))
+}
+
+/// The operations to move the contract into the archive.
+pub(crate) fn archive_ops(
+    serialized: Vec<u8>,
+    contract: &Contract,
+    archived_at: OffsetDateTime,
+) -> Vec<Op> {
+    let id = contract.get_id().to_vec();
+
+    vec![
+        Op::Delete {
+            kind: CONTRACT,
+            key: Some(contract.get_temporary_id().to_vec()),
+        },
+        Op::Delete {
+            kind: CONTRACT,
+            key: Some(id.clone()),
+        },
+        Op::Write {
+            kind: ARCHIVED_CONTRACT,
+            key: id.clone(),
+            value: serialized,
+        },
+        Op::Write {
+            kind: ARCHIVE_INDEX,
+            key: id,
+            value: archived_at.unix_timestamp().to_be_bytes().to_vec(),
+        },
+    ]
+}
+
+/// An entry of the archive index.
+struct IndexEntry {
+    contract_id: Vec<u8>,
+    archived_at: i64,
+}
+
+impl<K: DlcStoreProvider> DlcStorageProvider<K> {
+    /// Move the closed and refunded contracts which are still stored with the active ones into
+    /// the archive. Returns the number of archived contracts.
+    ///
+    /// Contracts are archived as soon as they are closed. This is only needed once for the
+    /// contracts closed before the archive existed.
+    pub fn archive_closed_contracts(&self) -> Result<usize> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut ops = vec![];
+        let mut archived = 0;
+        for kv in self.store.read(CONTRACT, None)? {
+            let contract = match deserialize_contract(&kv.value) {
+                Ok(contract) => contract,
+                Err(e) => {
+                    tracing::error!("Failed to deserialize contract: {e}");
+                    continue;
+                }
+            };
+
+            if is_archivable(&contract) {
+                ops.extend(archive_ops(kv.value, &contract, now));
+                archived += 1;
+            }
+        }
+
+        if archived > 0 {
+            self.store.write_batch(ops)?;
+            tracing::info!(archived, "Archived closed contracts");
+        }
+
+        Ok(archived)
+    }
+
+    /// The archived contracts, most recently archived first.
+    pub fn get_archived_contracts(&self, page: usize, limit: usize) -> Result<Vec<Contract>> {
+        let index = self.read_archive_index()?;
+
+        index
+            .into_iter()
+            .skip(page.saturating_mul(limit))
+            .take(limit)
+            .filter_map(|entry| {
+                self.get_archived_contract(&entry.contract_id)
+                    .map_err(anyhow::Error::new)
+                    .transpose()
+            })
+            .collect()
+    }
+
+    /// Delete the contracts archived before the given timestamp. Returns the number of deleted
+    /// contracts.
+    ///
+    /// Pruned contracts cannot be looked up anymore, e.g. to show the history of a position.
+    pub fn prune_closed_before(&self, timestamp: OffsetDateTime) -> Result<usize> {
+        let timestamp = timestamp.unix_timestamp();
+
+        let ops = self
+            .read_archive_index()?
+            .into_iter()
+            .filter(|entry| entry.archived_at < timestamp)
+            .flat_map(|entry| {
+                [
+                    Op::Delete {
+                        kind: ARCHIVED_CONTRACT,
+                        key: Some(entry.contract_id.clone()),
+                    },
+                    Op::Delete {
+                        kind: ARCHIVE_INDEX,
+                        key: Some(entry.contract_id),
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let pruned = ops.len() / 2;
+        if pruned > 0 {
+            self.store.write_batch(ops)?;
+            tracing::info!(pruned, "Pruned archived contracts");
+        }
+
+        Ok(pruned)
+    }
+
+    pub(crate) fn get_archived_contract(
+        &self,
+        contract_id: &[u8],
+    ) -> Result<Option<Contract>, Error> {
+        match self
+            .store
+            .read(ARCHIVED_CONTRACT, Some(contract_id.to_vec()))
+            .map_err(to_storage_error)?
+            .first()
+        {
+            Some(kv) => Ok(Some(deserialize_contract(&kv.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The entries of the archive index, most recently archived first.
+    fn read_archive_index(&self) -> Result<Vec<IndexEntry>> {
+        let mut index = self
+            .store
+            .read(ARCHIVE_INDEX, None)?
+            .into_iter()
+            .map(|kv| {
+                let archived_at = kv
+                    .value
+                    .try_into()
+                    .map(i64::from_be_bytes)
+                    .ok()
+                    .context("Invalid archive index entry")?;
+
+                Ok(IndexEntry {
+                    contract_id: kv.key,
+                    archived_at,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        index.sort_by(|a, b| {
+            b.archived_at
+                .cmp(&a.archived_at)
+                .then_with(|| a.contract_id.cmp(&b.contract_id))
+        });
+
+        Ok(index)
+    }
+}
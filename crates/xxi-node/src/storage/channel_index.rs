@@ -0,0 +1,162 @@
+//! Index of the signed channels by their state.
+//!
+//! Looking up the signed channels in a given state would otherwise require reading every stored
+//! channel. Instead, the ids of the signed channels are kept per state, and updated in the same
+//! batch as the channels themselves.
+
+use crate::storage::deserialize_channel;
+use crate::storage::to_storage_error;
+use crate::storage::ChannelPrefix;
+use crate::storage::DlcStorageProvider;
+use crate::storage::DlcStoreProvider;
+use crate::storage::Op;
+use crate::storage::SignedChannelPrefix;
+use crate::storage::CHANNEL;
+use crate::storage::SIGNED_CHANNEL_INDEX;
+use anyhow::Result;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelStateType;
+use dlc_manager::channel::Channel;
+use dlc_manager::error::Error;
+use dlc_manager::DlcChannelId;
+use std::collections::BTreeMap;
+
+const CHANNEL_ID_LEN: usize = 32;
+
+/// The state prefix of the serialized channel, if it is a signed channel.
+pub(crate) fn signed_state(serialized_channel: &[u8]) -> Option<u8> {
+    match serialized_channel {
+        [prefix, state, ..] if *prefix == u8::from(ChannelPrefix::Signed) => Some(*state),
+        _ => None,
+    }
+}
+
+impl<K: DlcStoreProvider> DlcStorageProvider<K> {
+    /// Rebuild the index from the stored channels. Returns the number of indexed channels.
+    ///
+    /// The index is maintained whenever a channel is stored or deleted. This is only needed for
+    /// the channels stored before the index existed, but is cheap enough to be done on every
+    /// start.
+    pub fn rebuild_signed_channel_index(&self) -> Result<usize> {
+        let _guard = self.channel_index_lock.lock();
+
+        let mut index = BTreeMap::<u8, Vec<u8>>::new();
+        for kv in self.store.read(CHANNEL, None)? {
+            if let Some(state) = signed_state(&kv.value) {
+                index.entry(state).or_default().extend(kv.key);
+            }
+        }
+
+        let indexed = index.values().map(|ids| ids.len() / CHANNEL_ID_LEN).sum();
+
+        let mut ops = vec![Op::Delete {
+            kind: SIGNED_CHANNEL_INDEX,
+            key: None,
+        }];
+        ops.extend(index.into_iter().map(|(state, ids)| Op::Write {
+            kind: SIGNED_CHANNEL_INDEX,
+            key: vec![state],
+            value: ids,
+        }));
+
+        self.store.write_batch(ops)?;
+
+        tracing::debug!(indexed, "Rebuilt signed channel index");
+
+        Ok(indexed)
+    }
+
+    /// The signed channels in the given state.
+    pub(crate) fn get_indexed_signed_channels(
+        &self,
+        state: SignedChannelStateType,
+    ) -> Result<Vec<SignedChannel>, Error> {
+        let state = SignedChannelPrefix::get_prefix(&state);
+
+        let mut channels = vec![];
+        for channel_id in self.read_index_entry(state)? {
+            let kv = self
+                .store
+                .read(CHANNEL, Some(channel_id.to_vec()))
+                .map_err(to_storage_error)?;
+
+            match kv.first().map(|kv| deserialize_channel(&kv.value)) {
+                Some(Ok(Channel::Signed(channel))) => channels.push(channel),
+                Some(Ok(_)) | None => {
+                    tracing::warn!(
+                        channel_id = hex::encode(channel_id),
+                        "Signed channel index is out of date"
+                    );
+                }
+                Some(Err(e)) => tracing::error!("Failed to deserialize data: {e}"),
+            }
+        }
+
+        Ok(channels)
+    }
+
+    /// The operations to move the channel from the `previous` to the `next` signed state in the
+    /// index.
+    ///
+    /// Must be called while holding the index lock, until the returned operations are written.
+    pub(crate) fn signed_channel_index_ops(
+        &self,
+        channel_id: &DlcChannelId,
+        previous: Option<u8>,
+        next: Option<u8>,
+    ) -> Result<Vec<Op>, Error> {
+        if previous == next {
+            return Ok(vec![]);
+        }
+
+        let mut ops = vec![];
+
+        if let Some(state) = previous {
+            let mut ids = self.read_index_entry(state)?;
+            ids.retain(|id| id != channel_id);
+            ops.push(index_entry_op(state, ids));
+        }
+
+        if let Some(state) = next {
+            let mut ids = self.read_index_entry(state)?;
+            ids.push(*channel_id);
+            ops.push(index_entry_op(state, ids));
+        }
+
+        Ok(ops)
+    }
+
+    fn read_index_entry(&self, state: u8) -> Result<Vec<DlcChannelId>, Error> {
+        let entry = self
+            .store
+            .read(SIGNED_CHANNEL_INDEX, Some(vec![state]))
+            .map_err(to_storage_error)?;
+
+        let ids = entry
+            .first()
+            .map(|kv| {
+                kv.value
+                    .chunks_exact(CHANNEL_ID_LEN)
+                    .map(|id| id.try_into().expect("chunk of channel id length"))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ids)
+    }
+}
+
+fn index_entry_op(state: u8, ids: Vec<DlcChannelId>) -> Op {
+    if ids.is_empty() {
+        Op::Delete {
+            kind: SIGNED_CHANNEL_INDEX,
+            key: Some(vec![state]),
+        }
+    } else {
+        Op::Write {
+            kind: SIGNED_CHANNEL_INDEX,
+            key: vec![state],
+            value: ids.concat(),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! The fee rates agreed on for collaboratively closing DLC channels, which still have to be
+//! applied to the close transactions, see [`crate::node::close_fee`].
+//!
+//! They are persisted, so that a close transaction is still bumped after a restart.
+
+use crate::storage::DlcStorageProvider;
+use crate::storage::DlcStoreProvider;
+use crate::storage::CLOSE_FEE_BUMP;
+use anyhow::Context;
+use anyhow::Result;
+use dlc_manager::DlcChannelId;
+
+impl<K: DlcStoreProvider> DlcStorageProvider<K> {
+    /// Remember to bump the transaction collaboratively closing the DLC channel to
+    /// `fee_rate_sats_per_vb`.
+    pub(crate) fn upsert_close_fee_bump(
+        &self,
+        channel_id: &DlcChannelId,
+        fee_rate_sats_per_vb: u64,
+    ) -> Result<()> {
+        self.store.write(
+            CLOSE_FEE_BUMP,
+            channel_id.to_vec(),
+            fee_rate_sats_per_vb.to_be_bytes().to_vec(),
+        )
+    }
+
+    pub(crate) fn delete_close_fee_bump(&self, channel_id: &DlcChannelId) -> Result<()> {
+        self.store.delete(CLOSE_FEE_BUMP, Some(channel_id.to_vec()))
+    }
+
+    /// The close transactions still to be bumped, with the agreed fee rate in sats/vbyte.
+    pub(crate) fn get_close_fee_bumps(&self) -> Result<Vec<(DlcChannelId, u64)>> {
+        self.store
+            .read(CLOSE_FEE_BUMP, None)?
+            .into_iter()
+            .map(|kv| {
+                let channel_id = DlcChannelId::try_from(kv.key.as_slice())
+                    .context("Invalid channel ID of close fee bump")?;
+                let fee_rate_sats_per_vb = <[u8; 8]>::try_from(kv.value.as_slice())
+                    .map(u64::from_be_bytes)
+                    .context("Invalid fee rate of close fee bump")?;
+
+                Ok((channel_id, fee_rate_sats_per_vb))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::memory::InMemoryDlcStoreProvider;
+    use crate::storage::DlcChannelEventBus;
+    use crate::storage::DlcStorageProvider;
+
+    #[test]
+    fn close_fee_bumps_are_persisted() {
+        let store = InMemoryDlcStoreProvider::new();
+        let storage = DlcStorageProvider::new(store.clone(), DlcChannelEventBus::new());
+
+        storage.upsert_close_fee_bump(&[1; 32], 10).unwrap();
+        storage.upsert_close_fee_bump(&[2; 32], 20).unwrap();
+        storage.upsert_close_fee_bump(&[1; 32], 15).unwrap();
+
+        // E.g. after a restart.
+        let storage = DlcStorageProvider::new(store, DlcChannelEventBus::new());
+
+        let mut bumps = storage.get_close_fee_bumps().unwrap();
+        bumps.sort();
+        assert_eq!(bumps, vec![([1; 32], 15), ([2; 32], 20)]);
+
+        storage.delete_close_fee_bump(&[1; 32]).unwrap();
+
+        assert_eq!(storage.get_close_fee_bumps().unwrap(), vec![([2; 32], 20)]);
+    }
+}
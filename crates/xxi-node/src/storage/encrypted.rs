@@ -0,0 +1,279 @@
+//! Encryption at rest of the values in the kv store.
+//!
+//! The key pairs, contracts and channels are encrypted with a key derived from the seed, so that
+//! they cannot be read from the device without it. The keys of the kv store remain in plaintext,
+//! as values are looked up by them.
+
+use crate::storage::sled::SledStorageExport;
+use crate::storage::sled::SledStorageProvider;
+use crate::storage::DlcStoreProvider;
+use crate::storage::KeyValue;
+use crate::storage::Op;
+use crate::storage::ACTION;
+use crate::storage::ARCHIVED_CONTRACT;
+use crate::storage::CHAIN_MONITOR;
+use crate::storage::CHANNEL;
+use crate::storage::CONTRACT;
+use crate::storage::KEY_PAIR;
+use crate::storage::SUB_CHANNEL;
+use crate::storage::VERSION;
+use aes_gcm_siv::AeadInPlace;
+use aes_gcm_siv::Aes256GcmSiv;
+use aes_gcm_siv::KeyInit;
+use aes_gcm_siv::Nonce;
+use anyhow::anyhow;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use rand::Rng;
+
+const NONCE_LENGTH: usize = 12;
+
+/// The kinds whose values are encrypted. The values of the [`VERSION`] kind are kept in plaintext,
+/// as they tell whether the other values are encrypted already.
+const ENCRYPTED_KINDS: [u8; 7] = [
+    CONTRACT,
+    CHANNEL,
+    CHAIN_MONITOR,
+    KEY_PAIR,
+    SUB_CHANNEL,
+    ACTION,
+    ARCHIVED_CONTRACT,
+];
+
+/// Marks a store whose values have been encrypted.
+const ENCRYPTED_KEY: &str = "encrypted";
+
+/// Encrypts the values written to and decrypts the values read from the wrapped store.
+#[derive(Clone)]
+pub struct EncryptedDlcStoreProvider<K> {
+    store: K,
+    cipher: Aes256GcmSiv,
+}
+
+impl<K: DlcStoreProvider> EncryptedDlcStoreProvider<K> {
+    /// Wrap the given store, encrypting the values it still keeps in plaintext.
+    pub fn new(store: K, key: [u8; 32]) -> Result<Self> {
+        let cipher = Aes256GcmSiv::new_from_slice(&key).expect("key to have correct size");
+        let provider = Self { store, cipher };
+
+        provider
+            .encrypt_plaintext_values()
+            .context("Failed to encrypt DLC storage")?;
+
+        Ok(provider)
+    }
+
+    pub fn inner(&self) -> &K {
+        &self.store
+    }
+
+    /// Encrypt all values of a store written before it was encrypted, in a single batch.
+    fn encrypt_plaintext_values(&self) -> Result<()> {
+        let is_encrypted = !self
+            .store
+            .read(VERSION, Some(ENCRYPTED_KEY.as_bytes().to_vec()))?
+            .is_empty();
+        if is_encrypted {
+            return Ok(());
+        }
+
+        let mut ops = vec![];
+        for kind in ENCRYPTED_KINDS {
+            for kv in self.store.read(kind, None)? {
+                let value = self.encrypt(kind, &kv.key, &kv.value)?;
+                ops.push(Op::Write {
+                    kind,
+                    key: kv.key,
+                    value,
+                });
+            }
+        }
+
+        let encrypted = ops.len();
+        ops.push(Op::Write {
+            kind: VERSION,
+            key: ENCRYPTED_KEY.as_bytes().to_vec(),
+            value: vec![1],
+        });
+        self.store.write_batch(ops)?;
+
+        tracing::info!(encrypted, "Encrypted DLC storage");
+
+        Ok(())
+    }
+
+    /// Encrypt the value with a random nonce, which is prepended to the ciphertext.
+    ///
+    /// The kind and the key are authenticated as well, so that a value cannot be moved to another
+    /// key without being noticed.
+    fn encrypt(&self, kind: u8, key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = rand::thread_rng().gen::<[u8; NONCE_LENGTH]>();
+        let nonce = Nonce::from_slice(&nonce);
+
+        let mut buffer = plaintext.to_vec();
+        self.cipher
+            .encrypt_in_place(nonce, &associated_data(kind, key), &mut buffer)
+            .map_err(|e| anyhow!("Failed to encrypt value: {e}"))?;
+
+        let mut ciphertext = nonce.to_vec();
+        ciphertext.extend_from_slice(&buffer);
+
+        Ok(ciphertext)
+    }
+
+    fn decrypt(&self, kind: u8, key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        ensure!(ciphertext.len() >= NONCE_LENGTH, "Ciphertext is too short");
+
+        let (nonce, ciphertext) = ciphertext.split_at(NONCE_LENGTH);
+        let nonce = Nonce::from_slice(nonce);
+
+        let mut buffer = ciphertext.to_vec();
+        self.cipher
+            .decrypt_in_place(nonce, &associated_data(kind, key), &mut buffer)
+            .map_err(|e| anyhow!("Failed to decrypt value {}: {e}", hex::encode(key)))?;
+
+        Ok(buffer)
+    }
+}
+
+impl EncryptedDlcStoreProvider<SledStorageProvider> {
+    /// All key value pairs of the store, decrypted, e.g. to back them up.
+    pub fn export(&self) -> Result<Vec<SledStorageExport>> {
+        self.store
+            .export()
+            .into_iter()
+            .map(|export| {
+                let value = if is_encrypted(export.kind) {
+                    self.decrypt(export.kind, &export.key, &export.value)?
+                } else {
+                    export.value
+                };
+
+                Ok(SledStorageExport { value, ..export })
+            })
+            .collect()
+    }
+}
+
+impl<K: DlcStoreProvider> DlcStoreProvider for EncryptedDlcStoreProvider<K> {
+    fn read(&self, kind: u8, key: Option<Vec<u8>>) -> Result<Vec<KeyValue>> {
+        let kvs = self.store.read(kind, key)?;
+        if !is_encrypted(kind) {
+            return Ok(kvs);
+        }
+
+        kvs.into_iter()
+            .map(|kv| {
+                let value = self.decrypt(kind, &kv.key, &kv.value)?;
+                Ok(KeyValue { key: kv.key, value })
+            })
+            .collect()
+    }
+
+    fn write(&self, kind: u8, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        let value = if is_encrypted(kind) {
+            self.encrypt(kind, &key, &value)?
+        } else {
+            value
+        };
+
+        self.store.write(kind, key, value)
+    }
+
+    fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> Result<()> {
+        self.store.delete(kind, key)
+    }
+
+    fn write_batch(&self, ops: Vec<Op>) -> Result<()> {
+        let ops = ops
+            .into_iter()
+            .map(|op| match op {
+                Op::Write { kind, key, value } if is_encrypted(kind) => {
+                    let value = self.encrypt(kind, &key, &value)?;
+                    Ok(Op::Write { kind, key, value })
+                }
+                op => Ok(op),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.store.write_batch(ops)
+    }
+}
+
+fn is_encrypted(kind: u8) -> bool {
+    ENCRYPTED_KINDS.contains(&kind)
+}
+
+fn associated_data(kind: u8, key: &[u8]) -> Vec<u8> {
+    let mut data = vec![kind];
+    data.extend_from_slice(key);
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryDlcStoreProvider;
+
+    #[test]
+    fn values_are_encrypted_at_rest() {
+        let inner = InMemoryDlcStoreProvider::new();
+        let store = EncryptedDlcStoreProvider::new(inner.clone(), [1; 32]).unwrap();
+
+        store
+            .write(KEY_PAIR, b"pubkey".to_vec(), b"secret".to_vec())
+            .unwrap();
+
+        let at_rest = inner.read(KEY_PAIR, None).unwrap();
+        assert_ne!(at_rest[0].value, b"secret".to_vec());
+
+        let read = store.read(KEY_PAIR, None).unwrap();
+        assert_eq!(read[0].value, b"secret".to_vec());
+    }
+
+    #[test]
+    fn plaintext_store_is_encrypted_when_opened() {
+        let inner = InMemoryDlcStoreProvider::new();
+        inner
+            .write(CONTRACT, b"contract".to_vec(), b"offered".to_vec())
+            .unwrap();
+
+        let store = EncryptedDlcStoreProvider::new(inner.clone(), [1; 32]).unwrap();
+
+        let at_rest = inner.read(CONTRACT, None).unwrap();
+        assert_ne!(at_rest[0].value, b"offered".to_vec());
+        assert_eq!(
+            store.read(CONTRACT, None).unwrap()[0].value,
+            b"offered".to_vec()
+        );
+
+        // Opening the store again must not encrypt the values twice.
+        let store = EncryptedDlcStoreProvider::new(inner, [1; 32]).unwrap();
+        assert_eq!(
+            store.read(CONTRACT, None).unwrap()[0].value,
+            b"offered".to_vec()
+        );
+    }
+
+    #[test]
+    fn values_cannot_be_decrypted_with_other_key_or_under_other_key() {
+        let inner = InMemoryDlcStoreProvider::new();
+        let store = EncryptedDlcStoreProvider::new(inner.clone(), [1; 32]).unwrap();
+        store
+            .write(CHANNEL, b"channel".to_vec(), b"signed".to_vec())
+            .unwrap();
+
+        let other_store = EncryptedDlcStoreProvider::new(inner.clone(), [2; 32]).unwrap();
+        assert!(other_store.read(CHANNEL, None).is_err());
+
+        let ciphertext = inner.read(CHANNEL, None).unwrap().remove(0).value;
+        inner
+            .write(CHANNEL, b"other-channel".to_vec(), ciphertext)
+            .unwrap();
+        assert!(store
+            .read(CHANNEL, Some(b"other-channel".to_vec()))
+            .is_err());
+    }
+}
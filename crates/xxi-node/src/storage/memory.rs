@@ -1,5 +1,6 @@
 use crate::storage::DlcStoreProvider;
 use crate::storage::KeyValue;
+use crate::storage::Op;
 use anyhow::Context;
 use parking_lot::RwLock;
 use std::collections::HashMap;
@@ -36,6 +37,10 @@ impl DlcStoreProvider for TenTenOneInMemoryStorage {
     fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> anyhow::Result<()> {
         self.dlc_store.delete(kind, key)
     }
+
+    fn write_batch(&self, ops: Vec<Op>) -> anyhow::Result<()> {
+        self.dlc_store.write_batch(ops)
+    }
 }
 
 type InMemoryStore = Arc<RwLock<HashMap<u8, HashMap<Vec<u8>, Vec<u8>>>>>;
@@ -113,4 +118,30 @@ impl DlcStoreProvider for InMemoryDlcStoreProvider {
 
         Ok(())
     }
+
+    fn write_batch(&self, ops: Vec<Op>) -> anyhow::Result<()> {
+        // Holding the lock for the whole batch keeps readers from seeing it half applied.
+        let mut memory = self.memory.write();
+
+        for op in ops {
+            match op {
+                Op::Write { kind, key, value } => {
+                    memory.entry(kind).or_default().insert(key, value);
+                }
+                Op::Delete {
+                    kind,
+                    key: Some(key),
+                } => {
+                    if let Some(store) = memory.get_mut(&kind) {
+                        store.remove(&key);
+                    }
+                }
+                Op::Delete { kind, key: None } => {
+                    memory.remove(&kind);
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
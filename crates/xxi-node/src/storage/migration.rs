@@ -0,0 +1,203 @@
+//! Versioning of the values in the kv store.
+//!
+//! Contracts and channels are stored in the serialization format of rust-dlc. If that format
+//! changes, values written by older versions can no longer be deserialized and would be skipped
+//! when loading them. Instead, every such change comes with a [`Migration`] rewriting the affected
+//! values, which is applied once when the storage is opened.
+
+use crate::storage::DlcStoreProvider;
+use crate::storage::Op;
+use crate::storage::VERSION;
+use crate::storage::VERSION_KEY;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+
+/// The migrations of the schema, in the order in which they are applied.
+///
+/// The migration at index `i` migrates the schema from version `i + 1` to version `i + 2`.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// The schema version of the values written by this version.
+pub const SCHEMA_VERSION: u32 = schema_version(MIGRATIONS);
+
+/// The schema version of stores written before the schema was versioned.
+const UNVERSIONED: u32 = 1;
+
+/// Rewrites the values of one kind to the next schema version.
+pub struct Migration {
+    pub description: &'static str,
+    pub kind: u8,
+    /// Returns the value in the next schema version, or `None` if the value is unaffected.
+    pub migrate: fn(&[u8]) -> Result<Option<Vec<u8>>>,
+}
+
+/// The schema version of the values in the store.
+pub fn get_schema_version<K: DlcStoreProvider>(store: &K) -> Result<u32> {
+    Ok(read_schema_version(store)?.unwrap_or(UNVERSIONED))
+}
+
+/// Apply the `migrations` the store has not seen yet.
+///
+/// Every migration is applied in a single batch together with the new schema version, so that
+/// an interrupted migration is simply applied again. Returns the schema version of the store.
+pub fn migrate<K: DlcStoreProvider>(store: &K, migrations: &[Migration]) -> Result<u32> {
+    let latest = schema_version(migrations);
+    let stored = read_schema_version(store)?;
+    let mut version = stored.unwrap_or(UNVERSIONED);
+
+    if version < UNVERSIONED {
+        bail!("Invalid DLC storage schema version {version}");
+    }
+
+    if version > latest {
+        bail!(
+            "DLC storage has schema version {version}, but only version {latest} is supported. \
+             Was it written by a newer version?"
+        );
+    }
+
+    for migration in &migrations[(version - UNVERSIONED) as usize..] {
+        let next = version + 1;
+
+        let mut ops = vec![];
+        for kv in store.read(migration.kind, None)? {
+            let value = (migration.migrate)(&kv.value).with_context(|| {
+                format!(
+                    "Failed to migrate value {} to schema version {next}",
+                    hex::encode(&kv.key)
+                )
+            })?;
+
+            if let Some(value) = value {
+                ops.push(Op::Write {
+                    kind: migration.kind,
+                    key: kv.key,
+                    value,
+                });
+            }
+        }
+
+        let migrated = ops.len();
+        ops.push(Op::Write {
+            kind: VERSION,
+            key: VERSION_KEY.as_bytes().to_vec(),
+            value: next.to_be_bytes().to_vec(),
+        });
+        store.write_batch(ops)?;
+
+        tracing::info!(
+            version = next,
+            migrated,
+            description = migration.description,
+            "Migrated DLC storage"
+        );
+
+        version = next;
+    }
+
+    // Mark stores which have never been migrated, so that values written from now on are known to
+    // be in the current schema version.
+    if stored.is_none() && version == UNVERSIONED {
+        write_schema_version(store, version)?;
+    }
+
+    Ok(version)
+}
+
+fn read_schema_version<K: DlcStoreProvider>(store: &K) -> Result<Option<u32>> {
+    store
+        .read(VERSION, Some(VERSION_KEY.as_bytes().to_vec()))?
+        .first()
+        .map(|kv| {
+            let version = kv
+                .value
+                .as_slice()
+                .try_into()
+                .context("Invalid schema version")?;
+
+            Ok(u32::from_be_bytes(version))
+        })
+        .transpose()
+}
+
+fn write_schema_version<K: DlcStoreProvider>(store: &K, version: u32) -> Result<()> {
+    store.write(
+        VERSION,
+        VERSION_KEY.as_bytes().to_vec(),
+        version.to_be_bytes().to_vec(),
+    )
+}
+
+const fn schema_version(migrations: &[Migration]) -> u32 {
+    UNVERSIONED + migrations.len() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryDlcStoreProvider;
+    use crate::storage::CONTRACT;
+
+    const APPEND_ONE: Migration = Migration {
+        description: "Append one",
+        kind: CONTRACT,
+        migrate: |value| {
+            let mut value = value.to_vec();
+            value.push(1);
+
+            Ok(Some(value))
+        },
+    };
+
+    const APPEND_TWO: Migration = Migration {
+        description: "Append two",
+        kind: CONTRACT,
+        migrate: |value| {
+            if value.len() > 2 {
+                return Ok(None);
+            }
+
+            let mut value = value.to_vec();
+            value.push(2);
+
+            Ok(Some(value))
+        },
+    };
+
+    #[test]
+    fn marks_unversioned_store_with_current_version() {
+        let store = InMemoryDlcStoreProvider::new();
+        store.write(CONTRACT, b"a".to_vec(), vec![0]).unwrap();
+
+        assert_eq!(migrate(&store, &[]).unwrap(), UNVERSIONED);
+
+        assert_eq!(get_schema_version(&store).unwrap(), UNVERSIONED);
+        assert_eq!(store.read(CONTRACT, None).unwrap()[0].value, vec![0]);
+    }
+
+    #[test]
+    fn applies_pending_migrations_in_order() {
+        let store = InMemoryDlcStoreProvider::new();
+        store.write(CONTRACT, b"a".to_vec(), vec![0]).unwrap();
+        store.write(CONTRACT, b"b".to_vec(), vec![0, 0]).unwrap();
+
+        assert_eq!(migrate(&store, &[APPEND_ONE]).unwrap(), 2);
+        assert_eq!(migrate(&store, &[APPEND_ONE, APPEND_TWO]).unwrap(), 3);
+        // Migrations are only applied once.
+        assert_eq!(migrate(&store, &[APPEND_ONE, APPEND_TWO]).unwrap(), 3);
+
+        let a = store.read(CONTRACT, Some(b"a".to_vec())).unwrap();
+        assert_eq!(a[0].value, vec![0, 1, 2]);
+        let b = store.read(CONTRACT, Some(b"b".to_vec())).unwrap();
+        assert_eq!(b[0].value, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn refuses_store_of_newer_version() {
+        let store = InMemoryDlcStoreProvider::new();
+        migrate(&store, &[APPEND_ONE, APPEND_TWO]).unwrap();
+
+        assert!(migrate(&store, &[APPEND_ONE]).is_err());
+    }
+}
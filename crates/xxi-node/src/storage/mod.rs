@@ -32,15 +32,22 @@ use dlc_manager::ReferenceId;
 use lightning::ln::ChannelId;
 use lightning::util::ser::Readable;
 use lightning::util::ser::Writeable;
+use parking_lot::Mutex;
 use std::convert::TryInto;
 use std::io::Cursor;
 use std::io::Read;
 use std::io::Seek;
 use std::io::SeekFrom;
 use std::string::ToString;
-use std::sync::mpsc;
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
 
+pub mod archive;
+pub mod channel_index;
+pub mod close_fee_bump;
+pub mod encrypted;
 pub mod memory;
+pub mod migration;
 pub mod sled;
 
 pub use memory::TenTenOneInMemoryStorage;
@@ -53,8 +60,14 @@ const CHAIN_MONITOR: u8 = 3;
 const KEY_PAIR: u8 = 6;
 const SUB_CHANNEL: u8 = 7;
 const ACTION: u8 = 9;
+const VERSION: u8 = 10;
+const ARCHIVED_CONTRACT: u8 = 11;
+const ARCHIVE_INDEX: u8 = 12;
+const SIGNED_CHANNEL_INDEX: u8 = 13;
+const CLOSE_FEE_BUMP: u8 = 14;
 
 const CHAIN_MONITOR_KEY: &str = "chain_monitor";
+const VERSION_KEY: &str = "schema_version";
 
 pub trait WalletStorage {
     fn upsert_key_pair(&self, public_key: &PublicKey, privkey: &SecretKey) -> Result<()>;
@@ -66,6 +79,18 @@ pub struct KeyValue {
     pub value: Vec<u8>,
 }
 
+/// A change to the kv store, see [`DlcStoreProvider::write_batch`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    Write {
+        kind: u8,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    /// Delete the key value pair with the given key, or all of the given kind without one.
+    Delete { kind: u8, key: Option<Vec<u8>> },
+}
+
 pub trait DlcStoreProvider {
     /// Read the object from a kv store by the given key
     fn read(&self, kind: u8, key: Option<Vec<u8>>) -> Result<Vec<KeyValue>>;
@@ -73,6 +98,17 @@ pub trait DlcStoreProvider {
     fn write(&self, kind: u8, key: Vec<u8>, value: Vec<u8>) -> Result<()>;
 
     fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> Result<()>;
+
+    /// Apply the operations in the given order, either all of them or none at all.
+    fn write_batch(&self, ops: Vec<Op>) -> Result<()>;
+}
+
+impl Op {
+    pub fn kind(&self) -> u8 {
+        match self {
+            Op::Write { kind, .. } | Op::Delete { kind, .. } => *kind,
+        }
+    }
 }
 
 pub trait TenTenOneStorage: DlcStoreProvider + Sync + Send + Clone {}
@@ -134,12 +170,81 @@ impl DlcChannelEvent {
             DlcChannelEvent::Deleted(reference_id) => reference_id,
         }
     }
+
+    /// The name of the state transition, without the reference ID.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DlcChannelEvent::Offered(_) => "Offered",
+            DlcChannelEvent::Accepted(_) => "Accepted",
+            DlcChannelEvent::Established(_) => "Established",
+            DlcChannelEvent::SettledOffered(_) => "SettledOffered",
+            DlcChannelEvent::SettledReceived(_) => "SettledReceived",
+            DlcChannelEvent::SettledAccepted(_) => "SettledAccepted",
+            DlcChannelEvent::SettledConfirmed(_) => "SettledConfirmed",
+            DlcChannelEvent::Settled(_) => "Settled",
+            DlcChannelEvent::SettledClosing(_) => "SettledClosing",
+            DlcChannelEvent::RenewOffered(_) => "RenewOffered",
+            DlcChannelEvent::RenewAccepted(_) => "RenewAccepted",
+            DlcChannelEvent::RenewConfirmed(_) => "RenewConfirmed",
+            DlcChannelEvent::RenewFinalized(_) => "RenewFinalized",
+            DlcChannelEvent::Closing(_) => "Closing",
+            DlcChannelEvent::CollaborativeCloseOffered(_) => "CollaborativeCloseOffered",
+            DlcChannelEvent::Closed(_) => "Closed",
+            DlcChannelEvent::CounterClosed(_) => "CounterClosed",
+            DlcChannelEvent::ClosedPunished(_) => "ClosedPunished",
+            DlcChannelEvent::CollaborativelyClosed(_) => "CollaborativelyClosed",
+            DlcChannelEvent::FailedAccept(_) => "FailedAccept",
+            DlcChannelEvent::FailedSign(_) => "FailedSign",
+            DlcChannelEvent::Cancelled(_) => "Cancelled",
+            DlcChannelEvent::Deleted(_) => "Deleted",
+        }
+    }
+}
+
+/// The number of [`DlcChannelEvent`]s a slow subscriber may fall behind before it misses some.
+const DLC_CHANNEL_EVENT_BUS_CAPACITY: usize = 100;
+
+/// Publishes the state transitions of the DLC channels to any number of subscribers, e.g. the
+/// [`crate::node::event::NodeEventHandler`] and the metrics of the coordinator.
+#[derive(Clone)]
+pub struct DlcChannelEventBus {
+    sender: broadcast::Sender<DlcChannelEvent>,
+}
+
+impl DlcChannelEventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(DLC_CHANNEL_EVENT_BUS_CAPACITY);
+
+        Self { sender }
+    }
+
+    /// A receiver for the events published after subscribing.
+    pub fn subscribe(&self) -> broadcast::Receiver<DlcChannelEvent> {
+        self.sender.subscribe()
+    }
+
+    fn publish(&self, event: DlcChannelEvent) {
+        if let Err(e) = self.sender.send(event) {
+            tracing::warn!(
+                dlc_channel_event = ?e.0,
+                "Dropping DLC channel event without subscribers"
+            );
+        }
+    }
+}
+
+impl Default for DlcChannelEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Implementation of the dlc storage interface.
 pub struct DlcStorageProvider<K> {
     store: K,
-    event_sender: mpsc::Sender<DlcChannelEvent>,
+    event_bus: DlcChannelEventBus,
+    /// Serializes the updates of the signed channel index, which are read-modify-write.
+    channel_index_lock: Mutex<()>,
 }
 
 macro_rules! convertible_enum {
@@ -263,34 +368,54 @@ where
     Error::StorageError(e.to_string())
 }
 
+/// The operations to store the contract, replacing it under its temporary id once the
+/// contract has its final id.
+///
+/// Contracts which reached a final state are moved into the archive.
+fn contract_ops(serialized: Vec<u8>, contract: &Contract) -> Vec<Op> {
+    if archive::is_archivable(contract) {
+        return archive::archive_ops(serialized, contract, OffsetDateTime::now_utc());
+    }
+
+    let mut ops = vec![];
+
+    if let a @ Contract::Accepted(_) | a @ Contract::Signed(_) = contract {
+        ops.push(Op::Delete {
+            kind: CONTRACT,
+            key: Some(a.get_temporary_id().to_vec()),
+        });
+    }
+
+    ops.push(Op::Write {
+        kind: CONTRACT,
+        key: contract.get_id().to_vec(),
+        value: serialized,
+    });
+
+    ops
+}
+
 impl<K: DlcStoreProvider> DlcStorageProvider<K> {
     /// Creates a new instance of a DlcStorageProvider
-    pub fn new(store: K, event_sender: mpsc::Sender<DlcChannelEvent>) -> Self {
+    pub fn new(store: K, event_bus: DlcChannelEventBus) -> Self {
         DlcStorageProvider {
             store,
-            event_sender,
+            event_bus,
+            channel_index_lock: Mutex::new(()),
         }
     }
 
-    fn insert_contract(
-        &self,
-        serialized: Vec<u8>,
-        contract: &Contract,
-    ) -> Result<Option<Vec<u8>>, Error> {
-        match contract {
-            a @ Contract::Accepted(_) | a @ Contract::Signed(_) => {
-                self.store
-                    .delete(CONTRACT, Some(a.get_temporary_id().to_vec()))
-                    .map_err(to_storage_error)?;
-            }
-            _ => {}
-        };
-
-        self.store
-            .write(CONTRACT, contract.get_id().to_vec(), serialized.clone())
-            .map_err(to_storage_error)?;
+    /// A receiver for the state transitions of the DLC channels stored after subscribing.
+    pub fn subscribe(&self) -> broadcast::Receiver<DlcChannelEvent> {
+        self.event_bus.subscribe()
+    }
 
-        Ok(Some(serialized))
+    /// Migrate the stored contracts and channels to the current [`migration::SCHEMA_VERSION`].
+    ///
+    /// Has to be called before anything is read from the storage, as values in an older schema
+    /// version may fail to deserialize.
+    pub fn migrate(&self) -> Result<u32> {
+        migration::migrate(&self.store, migration::MIGRATIONS)
     }
 
     fn get_data_with_prefix<T: Serializable>(
@@ -323,6 +448,18 @@ impl<K: DlcStoreProvider> DlcStorageProvider<K> {
             .collect()
     }
 
+    fn read_raw_channel(&self, channel_id: &DlcChannelId) -> Result<Option<Vec<u8>>, Error> {
+        let channel = self
+            .store
+            .read(CHANNEL, Some(channel_id.to_vec()))
+            .map_err(to_storage_error)?
+            .into_iter()
+            .next()
+            .map(|kv| kv.value);
+
+        Ok(channel)
+    }
+
     fn get_raw_contracts(&self) -> Result<Vec<Vec<u8>>, Error> {
         let contracts = self
             .store
@@ -345,10 +482,12 @@ impl<K: DlcStoreProvider> dlc_manager::Storage for DlcStorageProvider<K> {
             .first()
         {
             Some(res) => Ok(Some(deserialize_contract(&res.value)?)),
-            None => Ok(None),
+            None => self.get_archived_contract(contract_id),
         }
     }
 
+    /// The contracts which have not been archived yet, see
+    /// [`DlcStorageProvider::get_archived_contracts`] for the others.
     fn get_contracts(&self) -> Result<Vec<Contract>, Error> {
         let contracts = self.store.read(CONTRACT, None).map_err(to_storage_error)?;
 
@@ -374,25 +513,22 @@ impl<K: DlcStoreProvider> dlc_manager::Storage for DlcStorageProvider<K> {
     }
 
     fn delete_contract(&self, contract_id: &ContractId) -> Result<(), Error> {
-        self.store
-            .delete(CONTRACT, Some(contract_id.to_vec()))
-            .map_err(to_storage_error)
+        let ops = [CONTRACT, ARCHIVED_CONTRACT, ARCHIVE_INDEX]
+            .into_iter()
+            .map(|kind| Op::Delete {
+                kind,
+                key: Some(contract_id.to_vec()),
+            })
+            .collect();
+
+        self.store.write_batch(ops).map_err(to_storage_error)
     }
 
     fn update_contract(&self, contract: &Contract) -> Result<(), Error> {
         let serialized = serialize_contract(contract)?;
 
-        match contract {
-            a @ Contract::Accepted(_) | a @ Contract::Signed(_) => {
-                self.store
-                    .delete(CONTRACT, Some(a.get_temporary_id().to_vec()))
-                    .map_err(to_storage_error)?;
-            }
-            _ => {}
-        };
-
         self.store
-            .write(CONTRACT, contract.get_id().to_vec(), serialized)
+            .write_batch(contract_ops(serialized, contract))
             .map_err(to_storage_error)
     }
 
@@ -423,60 +559,79 @@ impl<K: DlcStoreProvider> dlc_manager::Storage for DlcStorageProvider<K> {
     fn upsert_channel(&self, channel: Channel, contract: Option<Contract>) -> Result<(), Error> {
         let serialized = serialize_channel(&channel)?;
 
-        let serialized_contract = match contract.as_ref() {
-            Some(c) => Some(serialize_contract(c)?),
-            None => None,
-        };
+        // The channel and its contract are written in a single batch, so that a crash cannot
+        // leave one of them behind the other.
+        let mut ops = vec![];
 
-        match &channel {
-            a @ Channel::Accepted(_) | a @ Channel::Signed(_) => {
-                self.store
-                    .delete(CHANNEL, Some(a.get_temporary_id().to_vec()))
-                    .map_err(to_storage_error)?;
-            }
-            _ => {}
-        };
+        if let a @ Channel::Accepted(_) | a @ Channel::Signed(_) = &channel {
+            ops.push(Op::Delete {
+                kind: CHANNEL,
+                key: Some(a.get_temporary_id().to_vec()),
+            });
+        }
 
-        self.store
-            .write(CHANNEL, channel.get_id().to_vec(), serialized)
-            .map_err(to_storage_error)?;
+        let channel_id = channel.get_id();
+        let next_state = channel_index::signed_state(&serialized);
+
+        ops.push(Op::Write {
+            kind: CHANNEL,
+            key: channel_id.to_vec(),
+            value: serialized,
+        });
 
         if let Some(contract) = contract.as_ref() {
-            self.insert_contract(
-                serialized_contract.expect("to have the serialized version"),
-                contract,
-            )?;
+            let serialized_contract = serialize_contract(contract)?;
+            ops.extend(contract_ops(serialized_contract, contract));
         }
 
-        let dlc_channel_event = DlcChannelEvent::from(channel);
-        let _ = self.event_sender.send(dlc_channel_event);
+        {
+            let _guard = self.channel_index_lock.lock();
+
+            let previous_state = self
+                .read_raw_channel(&channel_id)?
+                .and_then(|serialized| channel_index::signed_state(&serialized));
+            ops.extend(self.signed_channel_index_ops(&channel_id, previous_state, next_state)?);
+
+            self.store.write_batch(ops).map_err(to_storage_error)?;
+        }
+
+        self.event_bus.publish(DlcChannelEvent::from(channel));
 
         Ok(())
     }
 
     fn delete_channel(&self, channel_id: &DlcChannelId) -> Result<(), Error> {
-        let channel = self.get_channel(channel_id)?;
+        let channel = {
+            let _guard = self.channel_index_lock.lock();
+
+            let serialized = self.read_raw_channel(channel_id)?;
+            let channel = serialized.as_ref().map(deserialize_channel).transpose()?;
+
+            let mut ops = vec![Op::Delete {
+                kind: CHANNEL,
+                key: Some(channel_id.to_vec()),
+            }];
+            ops.extend(self.signed_channel_index_ops(
+                channel_id,
+                serialized.and_then(|serialized| channel_index::signed_state(&serialized)),
+                None,
+            )?);
 
-        self.store
-            .delete(CHANNEL, Some(channel_id.to_vec()))
-            .map_err(to_storage_error)?;
+            self.store.write_batch(ops).map_err(to_storage_error)?;
 
-        let dlc_channel_event =
-            DlcChannelEvent::Deleted(channel.and_then(|channel| channel.get_reference_id()));
+            channel
+        };
 
-        let _ = self.event_sender.send(dlc_channel_event);
+        self.event_bus.publish(DlcChannelEvent::Deleted(
+            channel.and_then(|channel| channel.get_reference_id()),
+        ));
 
         Ok(())
     }
 
     fn get_channel(&self, channel_id: &DlcChannelId) -> Result<Option<Channel>, Error> {
-        match self
-            .store
-            .read(CHANNEL, Some(channel_id.to_vec()))
-            .map_err(to_storage_error)?
-            .first()
-        {
-            Some(res) => Ok(Some(deserialize_channel(&res.value)?)),
+        match self.read_raw_channel(channel_id)? {
+            Some(serialized) => Ok(Some(deserialize_channel(&serialized)?)),
             None => Ok(None),
         }
     }
@@ -485,17 +640,9 @@ impl<K: DlcStoreProvider> dlc_manager::Storage for DlcStorageProvider<K> {
         &self,
         channel_state: Option<SignedChannelStateType>,
     ) -> Result<Vec<SignedChannel>, Error> {
-        let (prefix, consume) = if let Some(state) = &channel_state {
-            (
-                vec![
-                    ChannelPrefix::Signed.into(),
-                    SignedChannelPrefix::get_prefix(state),
-                ],
-                None,
-            )
-        } else {
-            (vec![ChannelPrefix::Signed.into()], Some(1))
-        };
+        if let Some(state) = channel_state {
+            return self.get_indexed_signed_channels(state);
+        }
 
         let channels = self
             .store
@@ -505,7 +652,8 @@ impl<K: DlcStoreProvider> dlc_manager::Storage for DlcStorageProvider<K> {
             .map(|x| x.value)
             .collect::<Vec<Vec<u8>>>();
 
-        let channels = self.get_data_with_prefix(&channels, &prefix, consume)?;
+        let channels =
+            self.get_data_with_prefix(&channels, &[ChannelPrefix::Signed.into()], Some(1))?;
 
         Ok(channels)
     }
@@ -941,13 +1089,65 @@ mod tests {
         T::deserialize(&mut cursor).unwrap()
     }
 
+    /// Fails if the fixture can no longer be read, or is written differently, by the current
+    /// version of rust-dlc. Such a change of the serialization format breaks the DLC storage of
+    /// existing nodes and needs a migration. Once there is one, regenerate the fixtures with the
+    /// `gen-fixtures` binary.
+    fn assert_round_trip<T>(name: &str, serialized: &[u8])
+    where
+        T: Serializable,
+    {
+        let mut cursor = std::io::Cursor::new(serialized);
+        let object = T::deserialize(&mut cursor).unwrap_or_else(|e| {
+            panic!("Fixture {name} can no longer be deserialized, the format changed: {e:?}")
+        });
+
+        assert_eq!(
+            cursor.position() as usize,
+            serialized.len(),
+            "Fixture {name} was not fully read, the format changed"
+        );
+        assert!(
+            object.serialize().unwrap() == serialized,
+            "Fixture {name} is serialized differently, the format changed"
+        );
+    }
+
+    #[test]
+    fn fixtures_round_trip() {
+        macro_rules! assert_fixture {
+            ($type:ty, $name:literal) => {
+                assert_round_trip::<$type>(
+                    $name,
+                    include_bytes!(concat!("../../test_files/", $name)),
+                )
+            };
+        }
+
+        assert_fixture!(OfferedContract, "Offered");
+        assert_fixture!(AcceptedContract, "Accepted");
+        assert_fixture!(SignedContract, "Signed");
+        assert_fixture!(SignedContract, "Signed1");
+        assert_fixture!(SignedContract, "Confirmed");
+        assert_fixture!(SignedContract, "Confirmed1");
+        assert_fixture!(PreClosedContract, "PreClosed");
+        assert_fixture!(OfferedChannel, "OfferedChannel");
+        assert_fixture!(AcceptedChannel, "AcceptedChannel");
+        assert_fixture!(SignedChannel, "SignedChannelEstablished");
+        assert_fixture!(SignedChannel, "SignedChannelSettled");
+        assert_fixture!(SubChannel, "OfferedSubChannel");
+        assert_fixture!(SubChannel, "OfferedSubChannel1");
+        assert_fixture!(SubChannel, "AcceptedSubChannel");
+        assert_fixture!(SubChannel, "SignedSubChannel");
+    }
+
     #[test]
     fn create_contract_can_be_retrieved() {
         let serialized = include_bytes!("../../test_files/Offered");
         let contract = deserialize_object(serialized);
 
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         storage
             .create_contract(&contract)
             .expect("Error creating contract");
@@ -971,8 +1171,8 @@ mod tests {
         let accepted_contract = deserialize_object(serialized);
         let accepted_contract = Contract::Accepted(accepted_contract);
 
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         storage
             .create_contract(&offered_contract)
             .expect("Error creating contract");
@@ -995,8 +1195,8 @@ mod tests {
         let serialized = include_bytes!("../../test_files/Offered");
         let contract = deserialize_object(serialized);
 
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         storage
             .create_contract(&contract)
             .expect("Error creating contract");
@@ -1103,8 +1303,8 @@ mod tests {
 
     #[test]
     fn get_signed_contracts_only_signed() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         insert_offered_signed_and_confirmed(&mut storage);
 
         let signed_contracts = storage
@@ -1116,8 +1316,8 @@ mod tests {
 
     #[test]
     fn get_confirmed_contracts_only_confirmed() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         insert_offered_signed_and_confirmed(&mut storage);
 
         let confirmed_contracts = storage
@@ -1129,8 +1329,8 @@ mod tests {
 
     #[test]
     fn get_offered_contracts_only_offered() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         insert_offered_signed_and_confirmed(&mut storage);
 
         let offered_contracts = storage
@@ -1142,8 +1342,8 @@ mod tests {
 
     #[test]
     fn get_preclosed_contracts_only_preclosed() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         insert_offered_signed_and_confirmed(&mut storage);
 
         let preclosed_contracts = storage
@@ -1155,8 +1355,8 @@ mod tests {
 
     #[test]
     fn get_contracts_all_returned() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         insert_offered_signed_and_confirmed(&mut storage);
 
         let contracts = storage.get_contracts().expect("Error retrieving contracts");
@@ -1164,15 +1364,116 @@ mod tests {
         assert_eq!(6, contracts.len());
     }
 
+    #[test]
+    fn closed_contract_is_archived() {
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+
+        let serialized = include_bytes!("../../test_files/Closed");
+        let closed_contract = Contract::Closed(deserialize_object(serialized));
+        storage
+            .update_contract(&closed_contract)
+            .expect("Error updating contract");
+
+        assert!(storage.get_contracts().unwrap().is_empty());
+        assert!(matches!(
+            storage.get_contract(&closed_contract.get_id()).unwrap(),
+            Some(Contract::Closed(_))
+        ));
+
+        let archived = storage.get_archived_contracts(0, 10).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert!(storage.get_archived_contracts(1, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn closed_contracts_stored_before_archive_are_archived() {
+        let store = InMemoryDlcStoreProvider::new();
+        let storage = DlcStorageProvider::new(store.clone(), DlcChannelEventBus::new());
+
+        let serialized = include_bytes!("../../test_files/Closed");
+        let closed_contract = Contract::Closed(deserialize_object(serialized));
+        store
+            .write(
+                CONTRACT,
+                closed_contract.get_id().to_vec(),
+                serialize_contract(&closed_contract).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(storage.get_contracts().unwrap().len(), 1);
+
+        assert_eq!(storage.archive_closed_contracts().unwrap(), 1);
+        assert_eq!(storage.archive_closed_contracts().unwrap(), 0);
+
+        assert!(storage.get_contracts().unwrap().is_empty());
+        assert_eq!(storage.get_archived_contracts(0, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn prune_deletes_contracts_archived_before_timestamp() {
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+
+        let serialized = include_bytes!("../../test_files/Closed");
+        let closed_contract = Contract::Closed(deserialize_object(serialized));
+        storage
+            .update_contract(&closed_contract)
+            .expect("Error updating contract");
+
+        let now = OffsetDateTime::now_utc();
+        assert_eq!(
+            storage
+                .prune_closed_before(now - time::Duration::HOUR)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            storage
+                .prune_closed_before(now + time::Duration::MINUTE)
+                .unwrap(),
+            1
+        );
+
+        assert!(storage.get_archived_contracts(0, 10).unwrap().is_empty());
+        assert!(storage
+            .get_contract(&closed_contract.get_id())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn dlc_channel_events_are_published_to_every_subscriber() {
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+        let mut first = storage.subscribe();
+        let mut second = storage.subscribe();
+
+        insert_offered_and_signed_channels(&mut storage);
+
+        for receiver in [&mut first, &mut second] {
+            assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Offered(None));
+            assert_eq!(
+                receiver.try_recv().unwrap(),
+                DlcChannelEvent::Established(None)
+            );
+            assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Settled(None));
+            assert!(receiver.try_recv().is_err());
+        }
+    }
+
     #[test]
     fn get_offered_channels_only_offered() {
-        let (sender, receiver) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+        let mut receiver = storage.subscribe();
         insert_offered_and_signed_channels(&mut storage);
 
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Offered(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Established(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Settled(None));
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Offered(None));
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            DlcChannelEvent::Established(None)
+        );
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Settled(None));
 
         let offered_channels = storage
             .get_offered_channels()
@@ -1182,13 +1483,17 @@ mod tests {
 
     #[test]
     fn get_signed_established_channel_only_established() {
-        let (sender, receiver) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+        let mut receiver = storage.subscribe();
         insert_offered_and_signed_channels(&mut storage);
 
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Offered(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Established(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Settled(None));
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Offered(None));
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            DlcChannelEvent::Established(None)
+        );
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Settled(None));
 
         let signed_channels = storage
             .get_signed_channels(Some(SignedChannelStateType::Established))
@@ -1207,13 +1512,17 @@ mod tests {
 
     #[test]
     fn get_channel_by_id_returns_correct_channel() {
-        let (sender, receiver) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+        let mut receiver = storage.subscribe();
         insert_offered_and_signed_channels(&mut storage);
 
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Offered(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Established(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Settled(None));
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Offered(None));
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            DlcChannelEvent::Established(None)
+        );
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Settled(None));
 
         let serialized = include_bytes!("../../test_files/AcceptedChannel");
         let accepted_channel: AcceptedChannel = deserialize_object(serialized);
@@ -1222,7 +1531,7 @@ mod tests {
             .upsert_channel(Channel::Accepted(accepted_channel), None)
             .expect("Error creating contract");
 
-        let accepted_dlc_event = receiver.recv().unwrap();
+        let accepted_dlc_event = receiver.try_recv().unwrap();
         assert_eq!(accepted_dlc_event, DlcChannelEvent::Accepted(None));
 
         storage
@@ -1233,13 +1542,17 @@ mod tests {
 
     #[test]
     fn delete_channel_is_not_returned() {
-        let (sender, receiver) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+        let mut receiver = storage.subscribe();
         insert_offered_and_signed_channels(&mut storage);
 
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Offered(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Established(None));
-        assert_eq!(receiver.recv().unwrap(), DlcChannelEvent::Settled(None));
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Offered(None));
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            DlcChannelEvent::Established(None)
+        );
+        assert_eq!(receiver.try_recv().unwrap(), DlcChannelEvent::Settled(None));
 
         let serialized = include_bytes!("../../test_files/AcceptedChannel");
         let accepted_channel: AcceptedChannel = deserialize_object(serialized);
@@ -1248,7 +1561,7 @@ mod tests {
             .upsert_channel(Channel::Accepted(accepted_channel), None)
             .expect("Error creating contract");
 
-        let accepted_dlc_event = receiver.recv().unwrap();
+        let accepted_dlc_event = receiver.try_recv().unwrap();
         assert_eq!(accepted_dlc_event, DlcChannelEvent::Accepted(None));
 
         storage
@@ -1259,7 +1572,7 @@ mod tests {
             .delete_channel(&channel_id)
             .expect("to be able to delete the channel");
 
-        let deleted_dlc_event = receiver.recv().unwrap();
+        let deleted_dlc_event = receiver.try_recv().unwrap();
         assert_eq!(deleted_dlc_event, DlcChannelEvent::Deleted(None));
 
         assert!(storage
@@ -1268,10 +1581,89 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn signed_channel_index_follows_state_changes() {
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
+
+        let serialized = include_bytes!("../../test_files/SignedChannelEstablished");
+        let established: SignedChannel = deserialize_object(serialized);
+        let channel_id = established.channel_id;
+        storage
+            .upsert_channel(Channel::Signed(established), None)
+            .unwrap();
+
+        let serialized = include_bytes!("../../test_files/SignedChannelSettled");
+        let mut settled: SignedChannel = deserialize_object(serialized);
+        settled.channel_id = channel_id;
+        storage
+            .upsert_channel(Channel::Signed(settled), None)
+            .unwrap();
+
+        assert!(storage
+            .get_signed_channels(Some(SignedChannelStateType::Established))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            storage
+                .get_signed_channels(Some(SignedChannelStateType::Settled))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        storage.delete_channel(&channel_id).unwrap();
+
+        assert!(storage
+            .get_signed_channels(Some(SignedChannelStateType::Settled))
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .store
+            .read(SIGNED_CHANNEL_INDEX, None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn signed_channels_stored_before_index_are_indexed() {
+        let store = InMemoryDlcStoreProvider::new();
+        let storage = DlcStorageProvider::new(store.clone(), DlcChannelEventBus::new());
+
+        for serialized in [
+            include_bytes!("../../test_files/SignedChannelEstablished").as_slice(),
+            include_bytes!("../../test_files/SignedChannelSettled").as_slice(),
+        ] {
+            let channel = Channel::Signed(deserialize_object(serialized));
+            store
+                .write(
+                    CHANNEL,
+                    channel.get_id().to_vec(),
+                    serialize_channel(&channel).unwrap(),
+                )
+                .unwrap();
+        }
+        assert!(storage
+            .get_signed_channels(Some(SignedChannelStateType::Established))
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(storage.rebuild_signed_channel_index().unwrap(), 2);
+
+        assert_eq!(
+            storage
+                .get_signed_channels(Some(SignedChannelStateType::Established))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(storage.get_signed_channels(None).unwrap().len(), 2);
+    }
+
     #[test]
     fn persist_chain_monitor_test() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         let chain_monitor = ChainMonitor::new(123);
 
         storage
@@ -1301,8 +1693,8 @@ mod tests {
 
     #[test]
     fn get_offered_sub_channels_only_offered() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         insert_sub_channels(&mut storage);
 
         let offered_sub_channels = storage
@@ -1313,8 +1705,8 @@ mod tests {
 
     #[test]
     fn get_sub_channels_all_returned() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let mut storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let mut storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         insert_sub_channels(&mut storage);
 
         let offered_sub_channels = storage
@@ -1325,8 +1717,8 @@ mod tests {
 
     #[test]
     fn save_actions_roundtip_test() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         let actions: Vec<_> =
             serde_json::from_str(include_str!("../../test_files/sub_channel_actions.json"))
                 .unwrap();
@@ -1341,8 +1733,8 @@ mod tests {
 
     #[test]
     fn get_actions_unset_test() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         let actions = storage
             .get_sub_channel_actions()
             .expect("Error getting sub channel actions");
@@ -1351,8 +1743,8 @@ mod tests {
 
     #[test]
     fn get_empty_actions_test() {
-        let (sender, _) = mpsc::channel::<DlcChannelEvent>();
-        let storage = DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), sender);
+        let storage =
+            DlcStorageProvider::new(InMemoryDlcStoreProvider::new(), DlcChannelEventBus::new());
         storage.save_sub_channel_actions(&[]).unwrap();
         let actions = storage
             .get_sub_channel_actions()
@@ -1,7 +1,13 @@
 use crate::storage::DlcStoreProvider;
 use crate::storage::KeyValue;
+use crate::storage::Op;
+use anyhow::anyhow;
 use anyhow::Result;
+use sled::transaction::TransactionError;
 use sled::Db;
+use sled::Transactional;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
 
 #[derive(Clone)]
 pub struct SledStorageProvider {
@@ -21,6 +27,18 @@ impl SledStorageProvider {
         }
     }
 
+    /// The space the sled storage takes up on disk, in bytes.
+    pub fn size_on_disk(&self) -> Result<u64> {
+        Ok(self.db.size_on_disk()?)
+    }
+
+    /// Flushes all dirty buffers to disk, so that everything written so far is durable.
+    ///
+    /// Returns the number of bytes flushed.
+    pub fn flush(&self) -> Result<usize> {
+        Ok(self.db.flush()?)
+    }
+
     /// Exports all key value pairs from the sled storage
     pub fn export(&self) -> Vec<SledStorageExport> {
         let mut export = vec![];
@@ -95,12 +113,70 @@ impl DlcStoreProvider for SledStorageProvider {
         self.db.flush()?;
         Ok(())
     }
+
+    fn write_batch(&self, ops: Vec<Op>) -> Result<()> {
+        let kinds = ops.iter().map(Op::kind).collect::<BTreeSet<_>>();
+        let kinds = kinds.into_iter().collect::<Vec<_>>();
+        let trees = kinds
+            .iter()
+            .map(|kind| self.db.open_tree([*kind]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Clearing a tree is not transactional, hence we delete its keys one by one instead: the
+        // ones stored before the batch and the ones written by the batch so far.
+        let mut written = vec![];
+        let mut changes = vec![];
+        for op in ops {
+            let tree = kinds.binary_search(&op.kind()).expect("tree to be opened");
+
+            match op {
+                Op::Write { key, value, .. } => {
+                    written.push((tree, key.clone()));
+                    changes.push((tree, key, Some(value)));
+                }
+                Op::Delete { key: Some(key), .. } => changes.push((tree, key, None)),
+                Op::Delete { key: None, .. } => {
+                    let mut keys = trees[tree]
+                        .iter()
+                        .keys()
+                        .map(|key| key.map(|key| key.to_vec()))
+                        .collect::<Result<HashSet<_>, _>>()?;
+                    keys.extend(
+                        written
+                            .iter()
+                            .filter(|(written_tree, _)| *written_tree == tree)
+                            .map(|(_, key)| key.clone()),
+                    );
+
+                    changes.extend(keys.into_iter().map(|key| (tree, key, None)));
+                }
+            }
+        }
+
+        trees
+            .as_slice()
+            .transaction(|trees| {
+                for (tree, key, value) in changes.iter() {
+                    match value {
+                        Some(value) => trees[*tree].insert(key.as_slice(), value.as_slice())?,
+                        None => trees[*tree].remove(key.as_slice())?,
+                    };
+                }
+
+                Ok(())
+            })
+            .map_err(|e: TransactionError| anyhow!("Failed to write batch: {e}"))?;
+
+        self.db.flush()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::storage::sled::SledStorageProvider;
     use crate::storage::DlcStoreProvider;
+    use crate::storage::Op;
 
     macro_rules! sled_test {
         ($name: ident, $body: expr) => {
@@ -257,4 +333,49 @@ mod tests {
         let result = storage.read(1, None).unwrap();
         assert_eq!(1, result.len());
     });
+
+    sled_test!(write_batch_across_kinds, |storage: SledStorageProvider| {
+        storage
+            .write(
+                1,
+                "temporary".to_string().into_bytes(),
+                "offered".to_string().into_bytes(),
+            )
+            .unwrap();
+        storage
+            .write(
+                2,
+                "key".to_string().into_bytes(),
+                "test".to_string().into_bytes(),
+            )
+            .unwrap();
+
+        storage
+            .write_batch(vec![
+                Op::Delete {
+                    kind: 1,
+                    key: Some("temporary".to_string().into_bytes()),
+                },
+                Op::Write {
+                    kind: 1,
+                    key: "final".to_string().into_bytes(),
+                    value: "signed".to_string().into_bytes(),
+                },
+                Op::Write {
+                    kind: 3,
+                    key: "key2".to_string().into_bytes(),
+                    value: "test2".to_string().into_bytes(),
+                },
+                Op::Delete { kind: 2, key: None },
+            ])
+            .unwrap();
+
+        let result = storage.read(1, None).unwrap();
+        assert_eq!(1, result.len());
+        assert_eq!("final".to_string().into_bytes(), result[0].key);
+        assert_eq!("signed".to_string().into_bytes(), result[0].value);
+
+        assert_eq!(0, storage.read(2, None).unwrap().len());
+        assert_eq!(1, storage.read(3, None).unwrap().len());
+    });
 }
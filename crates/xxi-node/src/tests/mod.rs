@@ -1,7 +1,9 @@
 use crate::bitcoin_conversion::to_secp_pk_29;
 use crate::bitcoin_conversion::to_xonly_pk_29;
 use crate::commons;
+use crate::node::confirmation::MinConfirmations;
 use crate::node::dlc_channel::send_dlc_message;
+use crate::node::dlc_channel::CloseFeeRateBounds;
 use crate::node::event::NodeEvent;
 use crate::node::event::NodeEventHandler;
 use crate::node::InMemoryStore;
@@ -12,7 +14,7 @@ use crate::node::RunningNode;
 use crate::node::XXINodeSettings;
 use crate::on_chain_wallet;
 use crate::seed::Bip39Seed;
-use crate::storage::DlcChannelEvent;
+use crate::storage::DlcChannelEventBus;
 use crate::storage::TenTenOneInMemoryStorage;
 use anyhow::Result;
 use bitcoin::secp256k1::XOnlyPublicKey;
@@ -41,7 +43,6 @@ use std::net::TcpListener;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::string::ToString;
-use std::sync::mpsc;
 use std::sync::Arc;
 use std::sync::Once;
 use std::time::Duration;
@@ -139,7 +140,7 @@ impl Node<on_chain_wallet::InMemoryStorage, TenTenOneInMemoryStorage, InMemorySt
         let storage = TenTenOneInMemoryStorage::new();
         let wallet_storage = on_chain_wallet::InMemoryStorage::new();
 
-        let (dlc_event_sender, dlc_event_receiver) = mpsc::channel::<DlcChannelEvent>();
+        let dlc_event_bus = DlcChannelEventBus::new();
         let event_handler = Arc::new(NodeEventHandler::new());
         let node = Node::new(
             name,
@@ -157,7 +158,7 @@ impl Node<on_chain_wallet::InMemoryStorage, TenTenOneInMemoryStorage, InMemorySt
             vec![oracle.into()],
             XOnlyPublicKey::from_str(ORACLE_PUBKEY)?,
             event_handler.clone(),
-            dlc_event_sender,
+            dlc_event_bus,
         )?;
         let node = Arc::new(node);
 
@@ -194,6 +195,14 @@ impl Node<on_chain_wallet::InMemoryStorage, TenTenOneInMemoryStorage, InMemorySt
                         }
                         Ok(NodeEvent::Connected { .. }) => {} // ignored
                         Ok(NodeEvent::DlcChannelEvent { .. }) => {} // ignored
+                        Ok(NodeEvent::FundingTransactionConfirmations { .. }) => {} // ignored
+                        Ok(NodeEvent::ChainDiscrepancy { .. }) => {} // ignored
+                        Ok(NodeEvent::ProtocolVersionMismatch { .. }) => {} // ignored
+                        Ok(NodeEvent::CollaborativeCloseFee { peer, msg }) => {
+                            if let Err(e) = node.handle_collaborative_close_fee(peer, msg).await {
+                                tracing::error!(%peer, "Failed to handle collaborative close fee message. {e:#}");
+                            }
+                        }
                         Err(_) => {
                             tracing::error!(
                                 "Failed to receive message from node event handler channel."
@@ -205,7 +214,7 @@ impl Node<on_chain_wallet::InMemoryStorage, TenTenOneInMemoryStorage, InMemorySt
             }
         });
 
-        let running = node.start(dlc_event_receiver)?;
+        let running = node.start()?;
 
         tracing::debug!(%name, info = %node.info, "Node started");
 
@@ -322,6 +331,11 @@ fn xxi_node_settings_coordinator() -> XXINodeSettings {
         fee_rate_sync_interval: Duration::from_secs(20),
         sub_channel_manager_periodic_check_interval: Duration::from_secs(30),
         shadow_sync_interval: Duration::from_secs(600),
+        confirmation_tracking_interval: Duration::from_secs(60),
+        chain_audit_interval: Some(Duration::from_secs(600)),
+        min_confirmations: MinConfirmations::default(),
+        close_fee_rate_bounds: CloseFeeRateBounds::default(),
+        bdk_client_concurrency: 5,
     }
 }
 
@@ -332,6 +346,11 @@ fn xxi_node_settings_app() -> XXINodeSettings {
         fee_rate_sync_interval: Duration::from_secs(20),
         sub_channel_manager_periodic_check_interval: Duration::from_secs(30),
         shadow_sync_interval: Duration::from_secs(600),
+        confirmation_tracking_interval: Duration::from_secs(60),
+        chain_audit_interval: None,
+        min_confirmations: MinConfirmations::default(),
+        close_fee_rate_bounds: CloseFeeRateBounds::default(),
+        bdk_client_concurrency: 5,
     }
 }
 
@@ -347,7 +366,6 @@ fn dummy_contract_input(
     let rounding_mod = total_collateral / (n_cets + 1);
 
     let maturity_time = OffsetDateTime::now_utc() + time::Duration::days(7);
-    let maturity_time = maturity_time.unix_timestamp() as u64;
 
     ContractInput {
         offer_collateral,
@@ -427,7 +445,11 @@ fn dummy_contract_input(
             }),
             oracles: OracleInput {
                 public_keys: vec![to_xonly_pk_29(oracle_pk)],
-                event_id: format!("btcusd{maturity_time}"),
+                event_id: commons::OracleEventId::new(
+                    commons::ContractSymbol::BtcUsd,
+                    maturity_time,
+                )
+                .to_string(),
                 threshold: 1,
             },
         }],
@@ -452,6 +474,8 @@ pub fn dummy_order() -> commons::Order {
         order_state: commons::OrderState::Open,
         order_reason: commons::OrderReason::Manual,
         stable: false,
+        display_quantity: None,
+        contract_expiry: None,
     }
 }
 
@@ -10,6 +10,7 @@ import 'package:get_10101/ffi.dart' as rust;
 import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
 import 'package:get_10101/util/environment.dart';
 import 'package:get_10101/util/file.dart';
+import 'package:get_10101/util/poll_change_notified.dart';
 import 'package:get_10101/util/preferences.dart';
 import 'package:package_info_plus/package_info_plus.dart';
 import 'package:path_provider/path_provider.dart';
@@ -61,6 +62,7 @@ Future<void> runBackend(BuildContext context) async {
   final tradeChangeNotifier = context.read<TradeChangeNotifier>();
   final fundingRateChangeNotifier = context.read<FundingRateChangeNotifier>();
   final dlcChannelChangeNotifier = context.read<DlcChannelChangeNotifier>();
+  final pollChangeNotifier = context.read<PollChangeNotifier>();
 
   final seedDir = (await getApplicationSupportDirectory()).path;
 
@@ -75,12 +77,27 @@ Future<void> runBackend(BuildContext context) async {
 
   await rust.api.runInFlutter(seedDir: seedDir, fcmToken: fcmToken);
 
+  rust.api.setSupportTicketConsent(consent: await Preferences.instance.hasSupportTicketConsent());
+
   // these notifiers depend on the backend running
   await orderChangeNotifier.initialize();
   await positionChangeNotifier.initialize();
   await tradeChangeNotifier.initialize();
   await dlcChannelChangeNotifier.initialize();
   await fundingRateChangeNotifier.initialize();
+
+  // a single request for what we would otherwise have to ask the coordinator for one by one
+  rust.api
+      .bootstrap()
+      .then((bootstrap) => pollChangeNotifier.update(bootstrap.poll))
+      .catchError((e) => logger.e("Failed to bootstrap: $e"));
+
+  if (await Preferences.instance.isChannelForceClosedPending()) {
+    await Preferences.instance.setChannelForceClosedPending(false);
+    rust.api
+        .onChannelForceClosed()
+        .catchError((e) => logger.e("Failed to handle force-closed channel: $e"));
+  }
 }
 
 void _setupRustLogging() {
@@ -0,0 +1,26 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
+import 'package:get_10101/common/application/event_service.dart';
+
+/// Keeps track of the actions which were queued while offline and are waiting to be submitted.
+class IntentChangeNotifier extends ChangeNotifier implements Subscriber {
+  final Map<String, bridge.Intent> _queued = {};
+
+  IntentChangeNotifier();
+
+  int get queued => _queued.length;
+
+  @override
+  void notify(bridge.Event event) {
+    if (event is bridge.Event_IntentUpdate) {
+      final intent = event.field0;
+      if (intent.state is bridge.IntentState_Queued) {
+        _queued[intent.id] = intent;
+      } else {
+        _queued.remove(intent.id);
+      }
+
+      notifyListeners();
+    }
+  }
+}
@@ -0,0 +1,55 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
+import 'package:get_10101/common/application/event_service.dart';
+
+/// Keeps track of the coordinator's kill switch, which the operator engages in an emergency.
+class KillSwitchChangeNotifier extends ChangeNotifier implements Subscriber {
+  bridge.KillSwitch? _killSwitch;
+
+  KillSwitchChangeNotifier();
+
+  bool get isEngaged {
+    final killSwitch = _killSwitch;
+    return killSwitch != null &&
+        (killSwitch.readOnly ||
+            killSwitch.noNewChannels ||
+            killSwitch.noWithdrawals ||
+            killSwitch.fullHalt);
+  }
+
+  /// A message explaining what is currently disabled, or null if the kill switch is not engaged.
+  String? get message {
+    final killSwitch = _killSwitch;
+    if (killSwitch == null || !isEngaged) {
+      return null;
+    }
+
+    String message;
+    if (killSwitch.fullHalt) {
+      message = "Trading is halted.";
+    } else {
+      final disabled = [
+        if (killSwitch.readOnly) "new orders",
+        if (killSwitch.noNewChannels) "opening channels",
+        if (killSwitch.noWithdrawals) "closing channels",
+      ];
+      message = "Temporarily disabled: ${disabled.join(", ")}.";
+    }
+
+    final reason = killSwitch.reason;
+    if (reason != null && reason.isNotEmpty) {
+      message = "$message $reason";
+    }
+
+    return message;
+  }
+
+  @override
+  void notify(bridge.Event event) {
+    if (event is bridge.Event_KillSwitchUpdate) {
+      _killSwitch = event.field0;
+
+      notifyListeners();
+    }
+  }
+}
@@ -0,0 +1,40 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
+import 'package:get_10101/common/application/event_service.dart';
+import 'package:get_10101/ffi.dart' as rust;
+
+/// Keeps track of how far the backend got while starting up.
+///
+/// Until the phase is [bridge.StartupPhase.Ready], the balances and positions shown may be the
+/// ones cached from the previous run.
+class StartupPhaseChangeNotifier extends ChangeNotifier implements Subscriber {
+  bridge.StartupPhase phase = rust.api.getStartupPhase();
+
+  StartupPhaseChangeNotifier();
+
+  bool get isReady => phase == bridge.StartupPhase.Ready;
+
+  String get message {
+    switch (phase) {
+      case bridge.StartupPhase.NotStarted:
+        return "Starting 10101";
+      case bridge.StartupPhase.CachedStateLoaded:
+        return "Loaded your last known balance";
+      case bridge.StartupPhase.StartingNode:
+        return "Starting node";
+      case bridge.StartupPhase.Syncing:
+        return "Syncing wallet";
+      case bridge.StartupPhase.Ready:
+        return "10101 is ready";
+    }
+  }
+
+  @override
+  void notify(bridge.Event event) {
+    if (event is bridge.Event_StartupPhase) {
+      phase = event.field0;
+
+      notifyListeners();
+    }
+  }
+}
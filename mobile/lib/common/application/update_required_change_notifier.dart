@@ -0,0 +1,21 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
+import 'package:get_10101/common/application/event_service.dart';
+
+/// Keeps track of whether the coordinator still talks to this version of the app.
+class UpdateRequiredChangeNotifier extends ChangeNotifier implements Subscriber {
+  bool _updateRequired = false;
+
+  UpdateRequiredChangeNotifier();
+
+  bool get updateRequired => _updateRequired;
+
+  @override
+  void notify(bridge.Event event) {
+    if (event is bridge.Event_UpdateRequired) {
+      _updateRequired = true;
+
+      notifyListeners();
+    }
+  }
+}
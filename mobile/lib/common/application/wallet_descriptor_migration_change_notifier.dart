@@ -0,0 +1,24 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
+import 'package:get_10101/common/application/event_service.dart';
+
+/// Keeps track of whether the restored wallet derives different descriptors than the backed up
+/// wallet, in which case funds may remain on the addresses of the backed up descriptors.
+class WalletDescriptorMigrationChangeNotifier extends ChangeNotifier implements Subscriber {
+  List<String>? _backedUp;
+
+  WalletDescriptorMigrationChangeNotifier();
+
+  bool get migrationRequired => _backedUp != null;
+
+  List<String> get backedUp => _backedUp ?? [];
+
+  @override
+  void notify(bridge.Event event) {
+    if (event is bridge.Event_WalletDescriptorMigration) {
+      _backedUp = event.backedUp;
+
+      notifyListeners();
+    }
+  }
+}
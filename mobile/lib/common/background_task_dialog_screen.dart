@@ -1,7 +1,10 @@
 import 'package:flutter/material.dart';
 import 'package:flutter/services.dart';
 import 'package:get_10101/common/background_task_change_notifier.dart';
+import 'package:get_10101/common/channel_closing_change_notifier.dart';
+import 'package:get_10101/common/channel_closing_screen.dart';
 import 'package:get_10101/common/domain/background_task.dart';
+import 'package:get_10101/common/support_ticket_change_notifier.dart';
 import 'package:get_10101/common/task_status_dialog.dart';
 import 'package:get_10101/features/wallet/wallet_screen.dart';
 import 'package:get_10101/logger/logger.dart';
@@ -42,6 +45,34 @@ class _BackgroundTaskDialogScreenState extends State<BackgroundTaskDialogScreen>
 
     final task = events.isEmpty ? null : events.peek;
 
+    final channelClosingChangeNotifier = context.watch<ChannelClosingChangeNotifier>();
+    if (channelClosingChangeNotifier.pending) {
+      channelClosingChangeNotifier.markShown();
+      WidgetsBinding.instance.addPostFrameCallback((_) {
+        GoRouter.of(context).go(ChannelClosingScreen.route);
+      });
+    }
+
+    final supportTicketChangeNotifier = context.watch<SupportTicketChangeNotifier>();
+    final supportTicketReference = supportTicketChangeNotifier.reference;
+    if (supportTicketReference != null) {
+      supportTicketChangeNotifier.markShown();
+      WidgetsBinding.instance.addPostFrameCallback((_) {
+        showDialog(
+            context: context,
+            builder: (context) => AlertDialog(
+                    title: const Text("Support ticket created"),
+                    content: Text("We have reported your failed trade to our support team.\n\n"
+                        "Please quote the reference $supportTicketReference when contacting us."),
+                    actions: [
+                      TextButton(
+                        onPressed: () => Navigator.pop(context, 'OK'),
+                        child: const Text('OK'),
+                      ),
+                    ]));
+      });
+    }
+
     WidgetsBinding.instance.addPostFrameCallback((_) {
       final taskStatusDialog = getTaskStatusDialog(task);
 
@@ -194,7 +225,7 @@ class _BackgroundTaskDialogScreenState extends State<BackgroundTaskDialogScreen>
   void compareCoordinatorVersion(bridge.Config config) {
     Future.wait<dynamic>([
       PackageInfo.fromPlatform(),
-      http.get(Uri.parse('http://${config.host}:${config.httpPort}/api/version'))
+      http.get(Uri.parse('http://${config.host}:${config.httpPort}/api/v2/version'))
     ]).then((value) {
       final packageInfo = value[0];
       final response = value[1];
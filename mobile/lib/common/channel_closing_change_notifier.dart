@@ -0,0 +1,30 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
+import 'package:get_10101/common/application/event_service.dart';
+
+/// Keeps track of whether our channel is being force-closed on-chain, so that the user can be
+/// guided through the recovery of their funds.
+class ChannelClosingChangeNotifier extends ChangeNotifier implements Subscriber {
+  bool _pending = false;
+  String? _closingTxid;
+
+  /// Whether the user has yet to be shown that the channel is closing on-chain.
+  bool get pending => _pending;
+
+  /// The force-close transaction, if the app has seen it on-chain already.
+  String? get closingTxid => _closingTxid;
+
+  void markShown() {
+    _pending = false;
+  }
+
+  @override
+  void notify(bridge.Event event) {
+    if (event is bridge.Event_ChannelClosingOnChain) {
+      _pending = true;
+      _closingTxid = event.closingTxid;
+
+      notifyListeners();
+    }
+  }
+}
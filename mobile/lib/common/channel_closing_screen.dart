@@ -0,0 +1,101 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/common/channel_closing_change_notifier.dart';
+import 'package:get_10101/features/wallet/wallet_history_item.dart';
+import 'package:get_10101/features/wallet/wallet_screen.dart';
+import 'package:go_router/go_router.dart';
+import 'package:provider/provider.dart';
+
+/// Shown if the coordinator force-closed our channel, explaining how the funds are recovered.
+class ChannelClosingScreen extends StatelessWidget {
+  static const route = "/channel-closing";
+
+  const ChannelClosingScreen({super.key});
+
+  @override
+  Widget build(BuildContext context) {
+    final closingTxid = context.watch<ChannelClosingChangeNotifier>().closingTxid;
+
+    return Scaffold(
+      body: SafeArea(
+        child: Container(
+          padding: const EdgeInsets.only(top: 20, left: 20, right: 20, bottom: 10),
+          child: Column(
+            crossAxisAlignment: CrossAxisAlignment.start,
+            children: [
+              const Center(
+                child: Text(
+                  "Channel closing on-chain",
+                  style: TextStyle(fontWeight: FontWeight.w500, fontSize: 20),
+                ),
+              ),
+              const SizedBox(height: 20),
+              const Text(
+                "Your channel with 10101 is being force-closed. Your funds are safe, but they are "
+                "returned to your on-chain wallet through a series of on-chain transactions, which "
+                "can take a few days.",
+                style: TextStyle(fontSize: 16),
+              ),
+              const SizedBox(height: 20),
+              const Text("What you should do",
+                  style: TextStyle(fontWeight: FontWeight.w500, fontSize: 16)),
+              const SizedBox(height: 10),
+              const _RecoveryStep(
+                  number: 1,
+                  text: "Open the app regularly until the funds show up in your on-chain wallet, "
+                      "so that it can claim them on time."),
+              const _RecoveryStep(
+                  number: 2, text: "Do not uninstall the app and make sure your seed is backed up."),
+              const _RecoveryStep(
+                  number: 3,
+                  text: "If your funds have not arrived after a few days, share your logs with us "
+                      "or use the emergency kit in the settings."),
+              const SizedBox(height: 20),
+              if (closingTxid != null)
+                Row(
+                  mainAxisAlignment: MainAxisAlignment.spaceBetween,
+                  children: [
+                    const Text("Force-close transaction"),
+                    TransactionIdText(closingTxid),
+                  ],
+                )
+              else
+                const Text(
+                  "The force-close transaction has not been seen on-chain yet.",
+                  style: TextStyle(color: Colors.grey),
+                ),
+              const Spacer(),
+              SizedBox(
+                width: double.infinity,
+                child: ElevatedButton(
+                  onPressed: () => GoRouter.of(context).go(WalletScreen.route),
+                  child: const Text("Got it"),
+                ),
+              ),
+            ],
+          ),
+        ),
+      ),
+    );
+  }
+}
+
+class _RecoveryStep extends StatelessWidget {
+  final int number;
+  final String text;
+
+  const _RecoveryStep({required this.number, required this.text});
+
+  @override
+  Widget build(BuildContext context) {
+    return Padding(
+      padding: const EdgeInsets.only(bottom: 8),
+      child: Row(
+        crossAxisAlignment: CrossAxisAlignment.start,
+        children: [
+          Text("$number. ", style: const TextStyle(fontSize: 16)),
+          Expanded(child: Text(text, style: const TextStyle(fontSize: 16))),
+        ],
+      ),
+    );
+  }
+}
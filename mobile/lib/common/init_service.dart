@@ -1,12 +1,19 @@
 import 'package:flutter/material.dart';
+import 'package:get_10101/common/application/intent_change_notifier.dart';
+import 'package:get_10101/common/application/kill_switch_change_notifier.dart';
+import 'package:get_10101/common/application/startup_phase_change_notifier.dart';
 import 'package:get_10101/common/application/tentenone_config_change_notifier.dart';
+import 'package:get_10101/common/application/update_required_change_notifier.dart';
+import 'package:get_10101/common/application/wallet_descriptor_migration_change_notifier.dart';
 import 'package:get_10101/common/background_task_change_notifier.dart';
+import 'package:get_10101/common/channel_closing_change_notifier.dart';
 import 'package:get_10101/common/dlc_channel_change_notifier.dart';
 import 'package:get_10101/common/dlc_channel_service.dart';
 import 'package:get_10101/common/domain/dlc_channel.dart';
 import 'package:get_10101/common/domain/funding_channel_task.dart';
 import 'package:get_10101/common/domain/tentenone_config.dart';
 import 'package:get_10101/common/funding_channel_task_change_notifier.dart';
+import 'package:get_10101/common/support_ticket_change_notifier.dart';
 import 'package:get_10101/features/brag/meme_service.dart';
 import 'package:get_10101/features/trade/application/trade_service.dart';
 import 'package:get_10101/features/trade/domain/funding_rate.dart';
@@ -66,6 +73,13 @@ List<SingleChildWidget> createProviders() {
     ChangeNotifierProvider(create: (context) => FundingChannelChangeNotifier()),
     ChangeNotifierProvider(create: (context) => TenTenOneConfigChangeNotifier(channelInfoService)),
     ChangeNotifierProvider(create: (context) => PollChangeNotifier(pollService)),
+    ChangeNotifierProvider(create: (context) => KillSwitchChangeNotifier()),
+    ChangeNotifierProvider(create: (context) => ChannelClosingChangeNotifier()),
+    ChangeNotifierProvider(create: (context) => StartupPhaseChangeNotifier()),
+    ChangeNotifierProvider(create: (context) => SupportTicketChangeNotifier()),
+    ChangeNotifierProvider(create: (context) => UpdateRequiredChangeNotifier()),
+    ChangeNotifierProvider(create: (context) => IntentChangeNotifier()),
+    ChangeNotifierProvider(create: (context) => WalletDescriptorMigrationChangeNotifier()),
     Provider(create: (context) => config),
     Provider(create: (context) => channelInfoService),
     Provider(create: (context) => pollService),
@@ -93,6 +107,14 @@ void subscribeToNotifiers(BuildContext context) {
   final fundingChannelChangeNotifier = context.read<FundingChannelChangeNotifier>();
   final tentenoneConfigChangeNotifier = context.read<TenTenOneConfigChangeNotifier>();
   final dlcChannelChangeNotifier = context.read<DlcChannelChangeNotifier>();
+  final killSwitchChangeNotifier = context.read<KillSwitchChangeNotifier>();
+  final channelClosingChangeNotifier = context.read<ChannelClosingChangeNotifier>();
+  final startupPhaseChangeNotifier = context.read<StartupPhaseChangeNotifier>();
+  final supportTicketChangeNotifier = context.read<SupportTicketChangeNotifier>();
+  final updateRequiredChangeNotifier = context.read<UpdateRequiredChangeNotifier>();
+  final intentChangeNotifier = context.read<IntentChangeNotifier>();
+  final walletDescriptorMigrationChangeNotifier =
+      context.read<WalletDescriptorMigrationChangeNotifier>();
 
   eventService.subscribe(
       orderChangeNotifier, bridge.Event.orderUpdateNotification(Order.apiDummy()));
@@ -138,6 +160,35 @@ void subscribeToNotifiers(BuildContext context) {
   eventService.subscribe(
       dlcChannelChangeNotifier, bridge.Event.dlcChannelEvent(DlcChannel.apiDummy()));
 
+  eventService.subscribe(
+      killSwitchChangeNotifier,
+      const bridge.Event.killSwitchUpdate(bridge.KillSwitch(
+          readOnly: false, noNewChannels: false, noWithdrawals: false, fullHalt: false)));
+
+  eventService.subscribe(
+      channelClosingChangeNotifier, const bridge.Event.channelClosingOnChain());
+
+  eventService.subscribe(startupPhaseChangeNotifier,
+      const bridge.Event.startupPhase(bridge.StartupPhase.NotStarted));
+
+  eventService.subscribe(
+      supportTicketChangeNotifier, const bridge.Event.supportTicketCreated(reference: ""));
+
+  eventService.subscribe(updateRequiredChangeNotifier,
+      const bridge.Event.updateRequired(version: 0, minVersion: 0));
+
+  eventService.subscribe(walletDescriptorMigrationChangeNotifier,
+      const bridge.Event.walletDescriptorMigration(backedUp: [], derived: []));
+
+  eventService.subscribe(
+      intentChangeNotifier,
+      const bridge.Event.intentUpdate(bridge.Intent(
+          id: "",
+          action: bridge.IntentAction.updateNickname(nickname: ""),
+          state: bridge.IntentState.queued(),
+          createdAt: 0,
+          validUntil: 0)));
+
   eventService.subscribe(
       AnonSubscriber((event) => logger.i(event.field0)), const bridge.Event.log(""));
 }
@@ -1,5 +1,6 @@
 import 'package:flutter/material.dart';
 import 'package:get_10101/common/background_task_dialog_screen.dart';
+import 'package:get_10101/common/channel_closing_screen.dart';
 import 'package:get_10101/common/global_keys.dart';
 import 'package:get_10101/common/scaffold_with_nav_bar.dart';
 import 'package:get_10101/common/settings/app_info_screen.dart';
@@ -231,6 +232,12 @@ GoRouter createRoutes() {
                   ])
                 ],
               ),
+              GoRoute(
+                path: ChannelClosingScreen.route,
+                builder: (BuildContext context, GoRouterState state) {
+                  return const ChannelClosingScreen();
+                },
+              ),
               GoRoute(
                 path: ChannelConfigurationScreen.route,
                 builder: (BuildContext context, GoRouterState state) {
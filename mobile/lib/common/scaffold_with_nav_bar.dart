@@ -1,12 +1,17 @@
 import 'package:flutter/material.dart';
 import 'package:flutter/services.dart';
 import 'package:get_10101/common/app_bar_wrapper.dart';
+import 'package:get_10101/common/application/intent_change_notifier.dart';
+import 'package:get_10101/common/application/kill_switch_change_notifier.dart';
+import 'package:get_10101/common/application/update_required_change_notifier.dart';
+import 'package:get_10101/common/application/wallet_descriptor_migration_change_notifier.dart';
 import 'package:get_10101/common/color.dart';
 import 'package:get_10101/features/trade/trade_screen.dart';
 import 'package:get_10101/features/wallet/wallet_screen.dart';
 import 'package:get_10101/main.dart';
 import 'package:get_10101/util/constants.dart';
 import 'package:go_router/go_router.dart';
+import 'package:provider/provider.dart';
 
 /// Wrapper for the main application screens
 class ScaffoldWithNavBar extends StatelessWidget {
@@ -19,10 +24,71 @@ class ScaffoldWithNavBar extends StatelessWidget {
 
   @override
   Widget build(BuildContext context) {
+    final killSwitchMessage = context.watch<KillSwitchChangeNotifier>().message;
+    final updateRequired = context.watch<UpdateRequiredChangeNotifier>().updateRequired;
+    final queuedIntents = context.watch<IntentChangeNotifier>().queued;
+    final descriptorMigrationRequired =
+        context.watch<WalletDescriptorMigrationChangeNotifier>().migrationRequired;
+
     return AnnotatedRegion<SystemUiOverlayStyle>(
       value: SystemUiOverlayStyle.dark,
       child: Scaffold(
-        body: child,
+        body: Column(
+          children: [
+            if (updateRequired)
+              Container(
+                width: double.infinity,
+                color: Colors.red.shade100,
+                padding: const EdgeInsets.symmetric(horizontal: 16, vertical: 8),
+                child: const Row(children: [
+                  Icon(Icons.system_update, color: Colors.red),
+                  SizedBox(width: 8),
+                  Expanded(
+                      child: Text(
+                          "This version of 10101 is no longer supported. Please update the app to keep trading.")),
+                ]),
+              ),
+            if (descriptorMigrationRequired)
+              Container(
+                width: double.infinity,
+                color: Colors.orange.shade100,
+                padding: const EdgeInsets.symmetric(horizontal: 16, vertical: 8),
+                child: const Row(children: [
+                  Icon(Icons.account_balance_wallet_outlined, color: Colors.orange),
+                  SizedBox(width: 8),
+                  Expanded(
+                      child: Text(
+                          "Your backup was created with a different wallet derivation. Funds received on its addresses show up as watched transactions, please move them into your wallet.")),
+                ]),
+              ),
+            if (killSwitchMessage != null)
+              Container(
+                width: double.infinity,
+                color: Colors.orange.shade100,
+                padding: const EdgeInsets.symmetric(horizontal: 16, vertical: 8),
+                child: Row(children: [
+                  const Icon(Icons.warning_amber_rounded, color: Colors.orange),
+                  const SizedBox(width: 8),
+                  Expanded(child: Text(killSwitchMessage)),
+                ]),
+              ),
+            if (queuedIntents > 0)
+              Container(
+                width: double.infinity,
+                color: Colors.blue.shade50,
+                padding: const EdgeInsets.symmetric(horizontal: 16, vertical: 8),
+                child: Row(children: [
+                  const Icon(Icons.schedule_send, color: Colors.blue),
+                  const SizedBox(width: 8),
+                  Expanded(
+                      child: Text(queuedIntents == 1
+                          ? "1 action is queued until you are back online."
+                          : "$queuedIntents actions are queued until you are back online.")),
+                ]),
+              ),
+            Expanded(child: child),
+          ],
+        ),
         appBar: const PreferredSize(
             preferredSize: Size.fromHeight(40), child: SafeArea(child: AppBarWrapper())),
         bottomNavigationBar: BottomNavigationBar(
@@ -3,6 +3,7 @@ import 'package:get_10101/common/application/share_logs_button.dart';
 import 'package:get_10101/common/application/switch.dart';
 import 'package:get_10101/common/color.dart';
 import 'package:get_10101/common/settings/settings_screen.dart';
+import 'package:get_10101/ffi.dart' as rust;
 import 'package:get_10101/logger/logger.dart';
 import 'package:get_10101/util/preferences.dart';
 import 'package:go_router/go_router.dart';
@@ -131,7 +132,41 @@ class _ShareLogsScreenState extends State<ShareLogsScreen> {
                               const Text("Restart the app to apply your changes."),
                             ],
                           )),
-                    )
+                    ),
+                    const SizedBox(height: 20),
+                    Container(
+                      padding: const EdgeInsets.symmetric(vertical: 10, horizontal: 20),
+                      decoration: BoxDecoration(
+                          color: Colors.white, borderRadius: BorderRadius.circular(15)),
+                      child: Row(
+                        mainAxisAlignment: MainAxisAlignment.spaceBetween,
+                        children: [
+                          Text(
+                            "Report failed trades to support",
+                            style: TextStyle(
+                                color: tenTenOnePurple.shade800,
+                                fontSize: 16,
+                                fontWeight: FontWeight.w500),
+                          ),
+                          FutureBuilder(
+                              future: Preferences.instance.hasSupportTicketConsent(),
+                              builder: (BuildContext context, AsyncSnapshot<bool> snapshot) {
+                                if (!snapshot.hasData) {
+                                  return Container();
+                                }
+
+                                return TenTenOneSwitch(
+                                    value: snapshot.data ?? false,
+                                    onChanged: (value) {
+                                      setState(() {
+                                        Preferences.instance.setSupportTicketConsent(value);
+                                        rust.api.setSupportTicketConsent(consent: value);
+                                      });
+                                    });
+                              }),
+                        ],
+                      ),
+                    ),
                   ],
                 ),
               )
@@ -1,5 +1,6 @@
 import 'package:flutter/material.dart';
 import 'package:flutter/services.dart';
+import 'package:get_10101/common/application/switch.dart';
 import 'package:get_10101/common/settings/settings_screen.dart';
 import 'package:get_10101/common/snack_bar.dart';
 import 'package:get_10101/logger/logger.dart';
@@ -20,6 +21,44 @@ class _WalletSettingsState extends State<WalletSettings> {
   var lookAheadController = TextEditingController();
   var syncing = false;
 
+  var autoTopUp = false;
+  var thresholdController = TextEditingController();
+  var capController = TextEditingController();
+
+  @override
+  void initState() {
+    super.initState();
+    loadAutoTopUpPolicy();
+  }
+
+  Future<void> loadAutoTopUpPolicy() async {
+    try {
+      final policy = await rust.api.getReserveAutoTopUp();
+      setState(() {
+        autoTopUp = policy != null;
+        thresholdController.text = policy?.thresholdSats.toString() ?? "";
+        capController.text = policy?.capSats.toString() ?? "";
+      });
+    } catch (exception) {
+      logger.e("Failed to load reserve auto top-up policy $exception");
+    }
+  }
+
+  Future<void> setAutoTopUpPolicy(rust.AutoTopUpPolicy? policy) async {
+    final messenger = ScaffoldMessenger.of(context);
+    try {
+      await rust.api.setReserveAutoTopUp(policy: policy);
+      setState(() {
+        autoTopUp = policy != null;
+      });
+      showSnackBar(messenger,
+          policy == null ? "Disabled reserve auto top-up." : "Saved reserve auto top-up.");
+    } catch (exception) {
+      logger.e("Failed to set reserve auto top-up policy $exception");
+      showSnackBar(messenger, "Error when setting reserve auto top-up $exception");
+    }
+  }
+
   @override
   Widget build(BuildContext context) {
     return Scaffold(
@@ -134,7 +173,89 @@ class _WalletSettingsState extends State<WalletSettings> {
                               ))
                         ],
                       ),
-                    )
+                    ),
+                    const Divider(),
+                    Row(
+                      mainAxisAlignment: MainAxisAlignment.spaceBetween,
+                      children: [
+                        const Text(
+                          "Reserve auto top-up",
+                          style: TextStyle(fontSize: 18, fontWeight: FontWeight.bold),
+                        ),
+                        TenTenOneSwitch(
+                            value: autoTopUp,
+                            onChanged: (value) {
+                              if (value) {
+                                setState(() {
+                                  autoTopUp = true;
+                                });
+                              } else {
+                                setAutoTopUpPolicy(null);
+                              }
+                            }),
+                      ],
+                    ),
+                    const Text(
+                      "\nTop up the collateral reserve of your channel from your on-chain wallet once it drops below the threshold, up to the cap.",
+                      style: TextStyle(fontSize: 18),
+                    ),
+                    Visibility(
+                      visible: autoTopUp,
+                      child: Padding(
+                        padding: const EdgeInsets.symmetric(horizontal: 8, vertical: 16),
+                        child: Column(
+                          children: [
+                            TextFormField(
+                              inputFormatters: <TextInputFormatter>[
+                                FilteringTextInputFormatter.digitsOnly
+                              ],
+                              keyboardType: TextInputType.number,
+                              controller: thresholdController,
+                              decoration: const InputDecoration(
+                                border: UnderlineInputBorder(),
+                                labelText: 'Threshold (sats)',
+                              ),
+                            ),
+                            Stack(
+                              alignment: Alignment.centerRight,
+                              children: [
+                                TextFormField(
+                                  inputFormatters: <TextInputFormatter>[
+                                    FilteringTextInputFormatter.digitsOnly
+                                  ],
+                                  keyboardType: TextInputType.number,
+                                  controller: capController,
+                                  decoration: const InputDecoration(
+                                    border: UnderlineInputBorder(),
+                                    labelText: 'Cap (sats)',
+                                  ),
+                                ),
+                                IconButton(
+                                  icon: const Icon(
+                                    Icons.check,
+                                    color: Colors.green,
+                                  ),
+                                  onPressed: () async {
+                                    final messenger = ScaffoldMessenger.of(context);
+                                    final threshold = int.tryParse(thresholdController.text);
+                                    final cap = int.tryParse(capController.text);
+
+                                    if (threshold == null || cap == null || cap <= threshold) {
+                                      showSnackBar(messenger,
+                                          "The cap has to be greater than the threshold.");
+                                      return;
+                                    }
+
+                                    await setAutoTopUpPolicy(rust.AutoTopUpPolicy(
+                                        thresholdSats: threshold, capSats: cap));
+                                  },
+                                )
+                              ],
+                            ),
+                          ],
+                        ),
+                      ),
+                    ),
                   ],
                 ),
               ),
@@ -0,0 +1,25 @@
+import 'package:flutter/material.dart';
+import 'package:get_10101/bridge_generated/bridge_definitions.dart' as bridge;
+import 'package:get_10101/common/application/event_service.dart';
+
+/// Keeps track of the support tickets created for failed trades, so that the user can be shown the
+/// reference to quote when contacting support.
+class SupportTicketChangeNotifier extends ChangeNotifier implements Subscriber {
+  String? _reference;
+
+  /// The reference of a ticket the user has yet to be shown.
+  String? get reference => _reference;
+
+  void markShown() {
+    _reference = null;
+  }
+
+  @override
+  void notify(bridge.Event event) {
+    if (event is bridge.Event_SupportTicketCreated) {
+      _reference = event.reference;
+
+      notifyListeners();
+    }
+  }
+}
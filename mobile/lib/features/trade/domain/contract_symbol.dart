@@ -1,12 +1,15 @@
 import 'package:get_10101/ffi.dart' as rust;
 
 enum ContractSymbol {
-  btcusd;
+  btcusd,
+  ethusd;
 
   static ContractSymbol fromApi(rust.ContractSymbol contractSymbol) {
     switch (contractSymbol) {
       case rust.ContractSymbol.BtcUsd:
         return ContractSymbol.btcusd;
+      case rust.ContractSymbol.EthUsd:
+        return ContractSymbol.ethusd;
     }
   }
 
@@ -16,6 +19,8 @@ enum ContractSymbol {
     switch (this) {
       case ContractSymbol.btcusd:
         return rust.ContractSymbol.BtcUsd;
+      case ContractSymbol.ethusd:
+        return rust.ContractSymbol.EthUsd;
     }
   }
 }
@@ -7,7 +7,8 @@ import 'package:get_10101/features/trade/domain/leverage.dart';
 enum OrderReason {
   manual,
   expired,
-  liquidated;
+  liquidated,
+  channelMigration;
 
   static OrderReason fromApi(bridge.OrderReason orderReason) {
     switch (orderReason) {
@@ -17,6 +18,8 @@ enum OrderReason {
         return OrderReason.expired;
       case bridge.OrderReason.Liquidated:
         return OrderReason.liquidated;
+      case bridge.OrderReason.ChannelMigration:
+        return OrderReason.channelMigration;
     }
   }
 
@@ -90,6 +93,10 @@ class FailureReason {
             failureType: FailureReasonType.protocolError, details: failureReason.field0);
       case bridge.FailureReason_TimedOut():
         return timeout;
+      case bridge.FailureReason_OfferRejected():
+        return FailureReason._(
+            failureType: FailureReasonType.protocolError,
+            details: _rejectReasonDetails(failureReason.field0));
       case bridge.FailureReason_OrderRejected():
         return FailureReason._(
             failureType: FailureReasonType.rejected, details: failureReason.field0);
@@ -97,6 +104,21 @@ class FailureReason {
         return unknown;
     }
   }
+
+  static String _rejectReasonDetails(bridge.RejectReason reason) {
+    switch (reason) {
+      case bridge.RejectReason.InvalidOffer:
+        return "The offer from the coordinator did not match the order.";
+      case bridge.RejectReason.AcceptFailed:
+        return "We failed accepting the offer from the coordinator.";
+      case bridge.RejectReason.Stale:
+        return "The offer was still pending after a restart.";
+      case bridge.RejectReason.Cancelled:
+        return "The offer was cancelled.";
+      case bridge.RejectReason.Unknown:
+        return "The offer was rejected.";
+    }
+  }
 }
 
 enum OrderType {
@@ -1,5 +1,6 @@
 import 'package:flutter/material.dart';
 import 'package:flutter/services.dart';
+import 'package:get_10101/common/application/startup_phase_change_notifier.dart';
 import 'package:get_10101/common/scrollable_safe_area.dart';
 import 'package:get_10101/common/snack_bar.dart';
 import 'package:flutter_native_splash/flutter_native_splash.dart';
@@ -12,6 +13,7 @@ import 'package:get_10101/logger/logger.dart';
 import 'package:get_10101/util/preferences.dart';
 import 'package:get_10101/util/file.dart';
 import 'package:go_router/go_router.dart';
+import 'package:provider/provider.dart';
 
 class LoadingScreen extends StatefulWidget {
   static const route = "/loading";
@@ -26,6 +28,7 @@ class LoadingScreen extends StatefulWidget {
 
 class _LoadingScreenState extends State<LoadingScreen> {
   String message = "Welcome to 10101";
+  bool starting = false;
 
   @override
   void initState() {
@@ -68,7 +71,7 @@ class _LoadingScreenState extends State<LoadingScreen> {
   }
 
   void start(BuildContext context, String? position) {
-    setState(() => message = "Starting 10101");
+    setState(() => starting = true);
     runBackend(context).then((value) {
       logger.i("Backend started");
 
@@ -87,6 +90,9 @@ class _LoadingScreenState extends State<LoadingScreen> {
 
   @override
   Widget build(BuildContext context) {
+    // Once the backend is starting, we report its progress.
+    final startupMessage = context.watch<StartupPhaseChangeNotifier>().message;
+
     return AnnotatedRegion<SystemUiOverlayStyle>(
         value: SystemUiOverlayStyle.dark,
         child: Scaffold(
@@ -101,7 +107,7 @@ class _LoadingScreenState extends State<LoadingScreen> {
                 const SizedBox(height: 40),
                 const Center(child: CircularProgressIndicator()),
                 const SizedBox(height: 15),
-                Text(message)
+                Text(starting ? startupMessage : message)
               ],
             ))));
   }
@@ -1,3 +1,5 @@
+import 'dart:io';
+
 import 'package:flutter/material.dart';
 import 'package:flutter/services.dart';
 import 'package:get_10101/common/application/switch.dart';
@@ -256,7 +258,8 @@ class _WelcomeScreenState extends State<WelcomeScreen> {
     logger.i("Successfully stored the contact: $_contact .");
     await api.initNewMnemonic(targetSeedFilePath: seedPath);
     logger.d("Registering user with $_contact & $_referralCode");
-    await api.registerBeta(contact: _contact, referralCode: _referralCode);
+    await api.registerBeta(
+        contact: _contact, referralCode: _referralCode, locale: Platform.localeName);
   }
 
   @override
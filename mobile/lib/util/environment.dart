@@ -28,6 +28,12 @@ class Environment {
       }
     }
 
+    // The HTTP endpoints (`host:port`) of further coordinators, by priority, separated by commas.
+    List<String> fallbackCoordinators = const String.fromEnvironment('FALLBACK_COORDINATORS')
+        .split(',')
+        .where((endpoint) => endpoint.isNotEmpty)
+        .toList();
+
     int healthCheckIntervalSeconds =
         const int.fromEnvironment('HEALTH_CHECK_INTERVAL_SECONDS', defaultValue: 10);
 
@@ -41,6 +47,7 @@ class Environment {
         oracleEndpoint: oracleEndpoint,
         oraclePubkey: oraclePubkey,
         healthCheckIntervalSecs: healthCheckIntervalSeconds,
-        memeEndpoint: memeEndpoint);
+        memeEndpoint: memeEndpoint,
+        fallbackCoordinators: fallbackCoordinators);
   }
 }
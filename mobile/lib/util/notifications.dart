@@ -2,8 +2,13 @@ import 'package:get_10101/logger/logger.dart';
 import 'package:firebase_core/firebase_core.dart';
 import 'package:firebase_messaging/firebase_messaging.dart';
 import 'package:flutter_local_notifications/flutter_local_notifications.dart';
+import 'package:get_10101/ffi.dart' as rust;
 import 'package:get_10101/firebase_options.dart';
 import 'package:get_10101/util/environment.dart';
+import 'package:get_10101/util/preferences.dart';
+
+/// The type of the data message the coordinator sends if our channel is being force-closed.
+const channelForceClosedMessageType = "channel_force_closed";
 
 /// Ask the user for permission to send notifications via Firebase
 Future<void> requestNotificationPermission() async {
@@ -43,6 +48,12 @@ Future<void> configureFirebase(FlutterLocalNotificationsPlugin localNotification
     // TODO: Handle messages from Firebase
     logger.d("Firebase message received: ${message.data}");
 
+    if (message.data["type"] == channelForceClosedMessageType) {
+      rust.api
+          .onChannelForceClosed()
+          .catchError((e) => logger.e("Failed to handle force-closed channel: $e"));
+    }
+
     if (message.notification != null) {
       logger.d("Message also contained a notification: ${message.notification}");
       showNotification(message.notification!.toMap(), localNotifications);
@@ -74,6 +85,12 @@ Future<void> _firebaseMessagingBackgroundHandler(RemoteMessage message) async {
   await Firebase.initializeApp();
   final localNotifications = initLocalNotifications();
 
+  if (message.data["type"] == channelForceClosedMessageType) {
+    // The backend is not running, hence we react to the force-close on the next start of the app.
+    await Preferences.instance.setChannelForceClosedPending(true);
+    showNotification(message.data, localNotifications);
+  }
+
   if (message.notification != null) {
     logger.d("Message also contained a notification: ${message.notification}");
     showNotification(message.notification!.toMap(), localNotifications);
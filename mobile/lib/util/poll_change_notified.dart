@@ -24,6 +24,12 @@ class PollChangeNotifier extends ChangeNotifier {
     }
   }
 
+  void update(Poll? poll) {
+    _polls = poll;
+
+    super.notifyListeners();
+  }
+
   Future<void> answer(Choice answer, Poll poll) async {
     await service.postAnswer(answer, poll);
     refresh();
@@ -13,6 +13,8 @@ class Preferences {
   static const fullBackup = "fullBackup";
   static const logLevelTrace = "logLevelTrace";
   static const _hasSeenReferralDialogTimePassed = "hasSeenReferralDialogTimePassed";
+  static const _channelForceClosedPending = "channelForceClosedPending";
+  static const _supportTicketConsent = "supportTicketConsent";
 
   Future<bool> setLogLevelTrace(bool trace) async {
     SharedPreferences preferences = await SharedPreferences.getInstance();
@@ -24,6 +26,29 @@ class Preferences {
     return preferences.getBool(logLevelTrace) ?? kDebugMode;
   }
 
+  /// Remember that the coordinator notified us about a force-closed channel while the app was not
+  /// running.
+  Future<bool> setChannelForceClosedPending(bool pending) async {
+    SharedPreferences preferences = await SharedPreferences.getInstance();
+    return preferences.setBool(_channelForceClosedPending, pending);
+  }
+
+  Future<bool> isChannelForceClosedPending() async {
+    SharedPreferences preferences = await SharedPreferences.getInstance();
+    return preferences.getBool(_channelForceClosedPending) ?? false;
+  }
+
+  /// Whether the user agreed to automatically create a support ticket when a trade fails.
+  Future<bool> setSupportTicketConsent(bool consent) async {
+    SharedPreferences preferences = await SharedPreferences.getInstance();
+    return preferences.setBool(_supportTicketConsent, consent);
+  }
+
+  Future<bool> hasSupportTicketConsent() async {
+    SharedPreferences preferences = await SharedPreferences.getInstance();
+    return preferences.getBool(_supportTicketConsent) ?? false;
+  }
+
   Future<bool> setFullBackupRequired(bool required) async {
     SharedPreferences preferences = await SharedPreferences.getInstance();
     return preferences.setBool(fullBackup, required);
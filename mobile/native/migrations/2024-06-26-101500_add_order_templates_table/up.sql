@@ -0,0 +1,12 @@
+CREATE TABLE order_templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    contract_symbol TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    -- Exactly one of `quantity` and `quantity_percent` is set.
+    quantity FLOAT,
+    quantity_percent FLOAT,
+    leverage FLOAT NOT NULL,
+    created_at BIGINT NOT NULL,
+    CHECK ((quantity IS NULL) <> (quantity_percent IS NULL))
+);
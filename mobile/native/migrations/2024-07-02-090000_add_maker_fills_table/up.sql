@@ -0,0 +1,11 @@
+CREATE TABLE maker_fills (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    order_id TEXT NOT NULL UNIQUE,
+    contract_symbol TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    price FLOAT NOT NULL,
+    quantity FLOAT NOT NULL,
+    -- The index price the filled quote was placed around.
+    index_price FLOAT NOT NULL,
+    filled_at BIGINT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- The last known wallet balances, so that the app can show them before the node is started.
+CREATE TABLE wallet_balances (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    on_chain BIGINT NOT NULL,
+    off_chain BIGINT,
+    updated_at BIGINT NOT NULL
+);
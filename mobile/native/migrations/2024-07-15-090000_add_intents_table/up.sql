@@ -0,0 +1,10 @@
+CREATE TABLE intents (
+    id TEXT PRIMARY KEY NOT NULL,
+    -- The action as JSON, so that new kinds of actions do not need a migration.
+    action TEXT NOT NULL,
+    state TEXT NOT NULL,
+    failure_reason TEXT,
+    created_at BIGINT NOT NULL,
+    valid_until BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
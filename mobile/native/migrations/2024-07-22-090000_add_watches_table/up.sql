@@ -0,0 +1,6 @@
+CREATE TABLE watches (
+    -- The txid or the address, depending on the kind of the watch.
+    target TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
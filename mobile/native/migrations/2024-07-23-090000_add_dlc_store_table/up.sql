@@ -0,0 +1,7 @@
+-- The DLC channels and contracts, if the app keeps them in the database instead of sled.
+CREATE TABLE dlc_store (
+    kind INTEGER NOT NULL,
+    key BLOB NOT NULL,
+    value BLOB NOT NULL,
+    PRIMARY KEY (kind, key)
+);
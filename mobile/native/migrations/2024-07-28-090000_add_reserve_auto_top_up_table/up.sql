@@ -0,0 +1,9 @@
+-- The policy the user opted in to, for automatically topping up the collateral reserve from the
+-- on-chain wallet.
+CREATE TABLE reserve_auto_top_up (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    threshold_sats BIGINT NOT NULL,
+    cap_sats BIGINT NOT NULL,
+    -- The amount we deposited for, which has not been credited yet.
+    pending_sats BIGINT
+);
@@ -1,4 +1,8 @@
+use crate::authorization;
+pub use crate::authorization::SensitiveOperation;
 use crate::calculations;
+use crate::channel_close_estimate;
+use crate::channel_close_estimate::ChannelCloseEstimate;
 use crate::channel_trade_constraints;
 use crate::channel_trade_constraints::TradeConstraints;
 use crate::commons::api::Price;
@@ -16,23 +20,41 @@ pub use crate::dlc_channel::SignedChannelState;
 use crate::emergency_kit;
 use crate::event;
 use crate::event::api::FlutterSubscriber;
+use crate::event::api::PendingBalance;
+use crate::event::api::WatchTarget;
 use crate::event::BackgroundTask;
 use crate::event::EventInternal;
 use crate::event::TaskStatus;
 use crate::health;
+use crate::lightning_withdrawal;
 use crate::logger;
 use crate::max_quantity::max_quantity;
 use crate::polls;
+use crate::reserve_top_up;
+use crate::startup;
+pub use crate::startup::StartupPhase;
+use crate::support_ticket;
 use crate::trade::funding_fee_event::handler::get_funding_fee_events;
+use crate::trade::intent;
+use crate::trade::intent::api::Intent;
+use crate::trade::intent::api::IntentAction;
+use crate::trade::liquidity;
+use crate::trade::liquidity::api::LiquidityConfig;
+use crate::trade::liquidity::api::LiquidityStatus;
 use crate::trade::order;
 use crate::trade::order::api::NewOrder;
 use crate::trade::order::api::Order;
+use crate::trade::order_template;
+use crate::trade::order_template::api::OrderTemplate;
+use crate::trade::order_template::api::TemplateQuantity;
 use crate::trade::position;
 use crate::trade::position::api::Position;
+use crate::trade::position::api::PositionRisk;
 use crate::trade::trades::api::Trade;
 use crate::trade::users;
 use crate::unfunded_channel_opening_order;
 use crate::unfunded_channel_opening_order::ExternalFunding;
+use crate::watcher;
 use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
@@ -52,10 +74,13 @@ use std::time::Duration;
 use time::OffsetDateTime;
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::channel;
+use uuid::Uuid;
+use xxi_node::commons::round_collateral_reserve;
 use xxi_node::commons::ChannelOpeningParams;
 pub use xxi_node::commons::ContractSymbol;
 pub use xxi_node::commons::Direction;
 use xxi_node::commons::OrderbookRequest;
+use xxi_node::commons::ReserveRounding;
 use xxi_node::seed::Bip39Seed;
 
 /// Initialise logging infrastructure for Rust
@@ -96,6 +121,13 @@ pub async fn refresh_wallet_info() -> Result<()> {
     Ok(())
 }
 
+/// To be called when the app receives the coordinator's notification that our DLC channel is
+/// being force-closed.
+#[tokio::main(flavor = "current_thread")]
+pub async fn on_channel_force_closed() -> Result<()> {
+    watcher::handle_channel_force_closed().await
+}
+
 #[tokio::main(flavor = "current_thread")]
 pub async fn full_sync(stop_gap: usize) -> Result<()> {
     dlc::full_sync(stop_gap).await?;
@@ -354,7 +386,10 @@ pub async fn submit_channel_opening_order(
         order.into(),
         Some(ChannelOpeningParams {
             coordinator_reserve: Amount::from_sat(coordinator_reserve),
-            trader_reserve: Amount::from_sat(trader_reserve),
+            trader_reserve: round_collateral_reserve(
+                Amount::from_sat(trader_reserve),
+                ReserveRounding::Down,
+            ),
             pre_image: None,
         }),
     )
@@ -374,6 +409,73 @@ pub async fn get_orders() -> Result<Vec<Order>> {
     Ok(orders)
 }
 
+pub fn create_order_template(
+    name: String,
+    contract_symbol: ContractSymbol,
+    direction: Direction,
+    quantity: TemplateQuantity,
+    leverage: f32,
+) -> Result<()> {
+    let template = order_template::OrderTemplate::new(
+        name,
+        contract_symbol,
+        direction,
+        quantity.into(),
+        Decimal::from_f32(leverage).context("Invalid leverage")?,
+    )?;
+
+    order_template::handler::create_order_template(template)
+}
+
+pub fn get_order_templates() -> Result<Vec<OrderTemplate>> {
+    let templates = order_template::handler::get_order_templates()?
+        .into_iter()
+        .map(OrderTemplate::from)
+        .collect();
+
+    Ok(templates)
+}
+
+/// Build a market order from the order template with the given name.
+///
+/// The returned order is validated against the current prices and trading limits and can be
+/// submitted as is.
+pub fn apply_order_template(name: String) -> Result<NewOrder> {
+    order_template::handler::apply_order_template(&name)
+}
+
+pub fn delete_order_template(name: String) -> Result<()> {
+    order_template::handler::delete_order_template(&name)
+}
+
+/// Queue an action taken while offline, to be submitted once the app is back online.
+///
+/// The intent expires if it could not be submitted within `validity_secs`. Its progress is
+/// published as [`event::api::Event::IntentUpdate`].
+pub fn queue_intent(action: IntentAction, validity_secs: u64) -> Result<Intent> {
+    let validity = time::Duration::seconds(validity_secs.try_into()?);
+    let intent = intent::Intent::new(action.into(), validity)?;
+
+    let intent = intent::handler::queue_intent(intent)?;
+
+    Ok(intent.into())
+}
+
+pub fn get_intents() -> Result<Vec<Intent>> {
+    let intents = intent::handler::get_intents()?
+        .into_iter()
+        .map(|intent| intent.into())
+        .collect();
+
+    Ok(intents)
+}
+
+pub fn cancel_intent(id: String) -> Result<()> {
+    let id = Uuid::parse_str(&id)?;
+
+    intent::handler::cancel_intent(id)
+}
+
 #[tokio::main(flavor = "current_thread")]
 pub async fn get_positions() -> Result<Vec<Position>> {
     let positions = position::handler::get_positions()?
@@ -384,6 +486,17 @@ pub async fn get_positions() -> Result<Vec<Position>> {
     Ok(positions)
 }
 
+/// The effective liquidation price and margin ratio of the current position, accounting for the
+/// funding fees accrued since it was opened.
+///
+/// The risk changes whenever a funding fee event is received, which is announced with a position
+/// update.
+pub fn get_position_risk() -> Result<Option<PositionRisk>> {
+    let risk = position::handler::get_position_risk()?.map(PositionRisk::from);
+
+    Ok(risk)
+}
+
 #[tokio::main(flavor = "current_thread")]
 pub async fn get_trades() -> Result<Vec<Trade>> {
     let trades = crate::trade::trades::handler::get_trades()?
@@ -418,6 +531,34 @@ pub fn subscribe(stream: StreamSink<event::api::Event>) {
     event::subscribe(FlutterSubscriber::new(stream))
 }
 
+/// Subscribes flutter to the given events only, e.g. the events needed by the current screen.
+///
+/// The subscription ends once the stream is closed.
+pub fn subscribe_filtered(
+    stream: StreamSink<event::api::Event>,
+    filter: Vec<event::api::EventFilter>,
+) {
+    tracing::debug!("Subscribing flutter to filtered events of event hub");
+    event::subscribe_filtered(
+        FlutterSubscriber::new(stream),
+        filter.into_iter().map(event::EventType::from).collect(),
+    )
+}
+
+pub fn get_event_delivery_metrics() -> Vec<event::api::EventDeliveryMetrics> {
+    event::delivery_metrics()
+        .into_iter()
+        .map(event::api::EventDeliveryMetrics::from)
+        .collect()
+}
+
+/// The phase the backend is in, so that the UI can show the startup progress.
+///
+/// Subsequent phases are published as events.
+pub fn get_startup_phase() -> SyncReturn<StartupPhase> {
+    SyncReturn(startup::get_phase())
+}
+
 /// Wrapper for Flutter purposes - can throw an exception.
 pub fn run_in_flutter(seed_dir: String, fcm_token: String) -> Result<()> {
     match crate::state::try_get_websocket() {
@@ -455,6 +596,8 @@ pub fn run_in_flutter(seed_dir: String, fcm_token: String) -> Result<()> {
                     version: Some(version),
                     os: Some(os),
                     signature,
+                    client_info: Some(crate::state::get_client_info()),
+                    cancel_on_disconnect: false,
                 })
             })?;
         }
@@ -485,6 +628,11 @@ pub enum IncludeBacktraceOnPanic {
 
 pub fn set_config(config: Config, app_dir: String, seed_dir: String) -> Result<()> {
     crate::state::set_config((config, Directories { app_dir, seed_dir }).into());
+
+    if let Err(e) = logger::init_log_file(config::get_log_dir()) {
+        tracing::error!("Failed to initialise log file: {e:#}");
+    }
+
     Ok(())
 }
 
@@ -516,6 +664,11 @@ fn run_internal(
 
     db::init_db(&config::get_data_dir(), get_network())?;
 
+    // Show the state from the previous run while the node is starting.
+    if let Err(e) = startup::load_cached_state() {
+        tracing::warn!("Failed to load cached state: {e:#}");
+    }
+
     let runtime = crate::state::get_or_create_tokio_runtime()?;
 
     let seed_dir = Path::new(&seed_dir).join(get_network().to_string());
@@ -537,6 +690,30 @@ pub fn get_unused_address() -> Result<String> {
     dlc::get_unused_address()
 }
 
+/// Follow a transaction or address outside of the wallet, e.g. when withdrawing from an
+/// exchange, so that the deposit shows up as soon as it is broadcast.
+///
+/// The target is followed from the next sync onwards, see [`refresh_wallet_info`]. Updates are
+/// published as [`event::api::Event::WatchUpdate`].
+pub fn watch(target: WatchTarget) -> Result<()> {
+    dlc::watch(target)
+}
+
+pub fn unwatch(target: WatchTarget) -> Result<()> {
+    dlc::unwatch(target)
+}
+
+pub fn get_watches() -> Result<Vec<WatchTarget>> {
+    dlc::get_watches()
+}
+
+/// The unconfirmed deposits to our on-chain wallet, by how likely they are to confirm.
+///
+/// Changes to the status of a deposit are published as [`event::api::Event::DepositUpdate`].
+pub fn get_pending_balance() -> PendingBalance {
+    dlc::get_pending_balance()
+}
+
 #[tokio::main(flavor = "current_thread")]
 pub async fn close_channel() -> Result<()> {
     event::publish(&EventInternal::BackgroundNotification(
@@ -561,11 +738,66 @@ pub async fn close_channel() -> Result<()> {
     Ok(())
 }
 
+/// Force-close the DLC channel. Requires an `authorization` for
+/// [`SensitiveOperation::ForceCloseChannel`], see [`authorize_sensitive_operation`].
 #[tokio::main(flavor = "current_thread")]
-pub async fn force_close_channel() -> Result<()> {
+pub async fn force_close_channel(authorization: String) -> Result<()> {
+    authorization::consume(
+        &authorization,
+        SensitiveOperation::ForceCloseChannel,
+        OffsetDateTime::now_utc(),
+    )?;
+
     dlc::close_channel(true).await
 }
 
+/// Estimate the fees and the time until the funds are in our on-chain wallet when closing the
+/// given DLC channel, compared to closing it the other way.
+pub fn estimate_channel_close(channel_id: String, force: bool) -> Result<ChannelCloseEstimate> {
+    channel_close_estimate::estimate_channel_close(channel_id, force)
+}
+
+/// Withdraw from the collateral reserve of the DLC channel by having the coordinator pay the
+/// given Lightning invoice.
+#[tokio::main(flavor = "current_thread")]
+pub async fn withdraw_via_lightning(invoice: String) -> Result<()> {
+    lightning_withdrawal::withdraw(invoice).await
+}
+
+/// Automatically top up the collateral reserve of the DLC channel from the on-chain wallet once it
+/// drops below `threshold_sats`, up to `cap_sats`. Passing `None` disables the automatic top-up.
+///
+/// The progress of a top-up is published as [`event::api::Event::ReserveTopUpUpdate`].
+pub fn set_reserve_auto_top_up(policy: Option<AutoTopUpPolicy>) -> Result<()> {
+    reserve_top_up::set_policy(policy.map(|policy| reserve_top_up::AutoTopUpPolicy {
+        threshold: Amount::from_sat(policy.threshold_sats),
+        cap: Amount::from_sat(policy.cap_sats),
+    }))
+}
+
+/// The automatic top-up policy of the collateral reserve, if the user opted in.
+pub fn get_reserve_auto_top_up() -> Result<Option<AutoTopUpPolicy>> {
+    let policy = reserve_top_up::get_policy()?.map(|policy| AutoTopUpPolicy {
+        threshold_sats: policy.threshold.to_sat(),
+        cap_sats: policy.cap.to_sat(),
+    });
+
+    Ok(policy)
+}
+
+pub struct AutoTopUpPolicy {
+    pub threshold_sats: u64,
+    pub cap_sats: u64,
+}
+
+/// Whether a support ticket is created automatically when a trade fails.
+///
+/// The reference of the ticket is published as [`event::api::Event::SupportTicketCreated`].
+pub fn set_support_ticket_consent(consent: bool) -> SyncReturn<()> {
+    support_ticket::set_consent(consent);
+    SyncReturn(())
+}
+
 pub fn channel_trade_constraints() -> Result<SyncReturn<TradeConstraints>> {
     let trade_constraints = channel_trade_constraints::channel_trade_constraints()?;
     Ok(SyncReturn(trade_constraints))
@@ -724,8 +956,26 @@ pub fn fee_rate(confirmation_target: ConfirmationTarget) -> f32 {
     dlc::get_fee_rate_for_target(confirmation_target.into()).as_sat_per_vb()
 }
 
+/// Send `amount` sats on-chain, or the whole balance if the `amount` is zero.
+///
+/// Sending more than [`authorization::LARGE_PAYMENT_THRESHOLD`] requires an `authorization` for
+/// [`SensitiveOperation::LargePayment`], see [`authorize_sensitive_operation`].
 #[tokio::main(flavor = "current_thread")]
-pub async fn send_payment(amount: u64, address: String, fee: FeeConfig) -> Result<String> {
+pub async fn send_payment(
+    amount: u64,
+    address: String,
+    fee: FeeConfig,
+    authorization: Option<String>,
+) -> Result<String> {
+    if authorization::is_large_payment(amount) {
+        let authorization = authorization.context("Large payments must be authorized")?;
+        authorization::consume(
+            &authorization,
+            SensitiveOperation::LargePayment,
+            OffsetDateTime::now_utc(),
+        )?;
+    }
+
     let txid = dlc::send_payment(amount, address, fee).await?;
 
     Ok(txid.to_string())
@@ -736,8 +986,28 @@ pub struct LastLogin {
     pub date: String,
 }
 
-pub fn get_seed_phrase() -> SyncReturn<Vec<String>> {
-    SyncReturn(dlc::get_seed_phrase())
+/// The seed phrase of the wallet. Requires an `authorization` for
+/// [`SensitiveOperation::ShowSeedPhrase`], see [`authorize_sensitive_operation`].
+pub fn get_seed_phrase(authorization: String) -> Result<SyncReturn<Vec<String>>> {
+    authorization::consume(
+        &authorization,
+        SensitiveOperation::ShowSeedPhrase,
+        OffsetDateTime::now_utc(),
+    )?;
+
+    Ok(SyncReturn(dlc::get_seed_phrase()))
+}
+
+/// Report that the user confirmed their identity with biometrics in order to perform the
+/// `operation`.
+///
+/// Returns a token authorizing a single invocation of the operation within the next minute. Must
+/// only be called after a successful biometric authentication.
+pub fn authorize_sensitive_operation(operation: SensitiveOperation) -> SyncReturn<String> {
+    SyncReturn(authorization::authorize(
+        operation,
+        OffsetDateTime::now_utc(),
+    ))
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -758,11 +1028,18 @@ pub fn init_new_mnemonic(target_seed_file_path: String) -> Result<()> {
 }
 
 /// Enroll or update a user in the beta program
+///
+/// The `locale` (e.g. `de_AT`) determines the language of notifications and error messages sent
+/// by the coordinator.
 #[tokio::main(flavor = "current_thread")]
-pub async fn register_beta(contact: String, referral_code: Option<String>) -> Result<()> {
+pub async fn register_beta(
+    contact: String,
+    referral_code: Option<String>,
+    locale: Option<String>,
+) -> Result<()> {
     let version = env!("CARGO_PKG_VERSION").to_string();
 
-    users::register_beta(contact, version, referral_code).await
+    users::register_beta(contact, version, referral_code, locale).await
 }
 
 #[derive(Debug)]
@@ -787,6 +1064,56 @@ pub async fn get_user_details() -> Result<User> {
     users::get_user_details().await.map(|user| user.into())
 }
 
+pub struct ReserveInterest {
+    /// The annual percentage rate paid on the collateral reserve.
+    pub apr: f32,
+    /// Interest which has accrued, but will only be paid with the next renew or rollover.
+    pub pending_sats: u64,
+    /// Interest which has already been paid into the collateral reserve.
+    pub paid_sats: u64,
+}
+
+impl From<xxi_node::commons::ReserveInterest> for ReserveInterest {
+    fn from(value: xxi_node::commons::ReserveInterest) -> Self {
+        ReserveInterest {
+            apr: value.apr.to_f32().expect("to fit"),
+            pending_sats: value.pending.to_sat(),
+            paid_sats: value.paid.to_sat(),
+        }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+pub async fn get_reserve_interest() -> Result<ReserveInterest> {
+    users::get_reserve_interest()
+        .await
+        .map(|reserve_interest| reserve_interest.into())
+}
+
+/// What the app needs from the coordinator on startup, loaded with a single request.
+pub struct Bootstrap {
+    /// [`None`] if the user has not registered yet.
+    pub user: Option<User>,
+    pub reserve_interest: ReserveInterest,
+    /// The first poll the user has not answered or ignored yet.
+    pub poll: Option<Poll>,
+}
+
+/// Load the app's startup data from the coordinator.
+///
+/// The configuration, the prices and the funding rates are published as events, just as if they
+/// had been received through the orderbook websocket.
+#[tokio::main(flavor = "current_thread")]
+pub async fn bootstrap() -> Result<Bootstrap> {
+    let bootstrap = crate::bootstrap::bootstrap().await?;
+
+    Ok(Bootstrap {
+        user: bootstrap.user.map(User::from),
+        reserve_interest: bootstrap.reserve_interest.into(),
+        poll: bootstrap.polls.into_iter().next().map(Poll::from),
+    })
+}
+
 pub enum Destination {
     Bolt11 {
         description: String,
@@ -942,3 +1269,25 @@ pub async fn submit_unfunded_channel_opening_order(
 pub async fn abort_unfunded_channel_opening_order() -> Result<()> {
     unfunded_channel_opening_order::abort_watcher().await
 }
+
+/// Start providing liquidity by maintaining a bid and an ask around the index price.
+pub fn start_liquidity_provision(config: LiquidityConfig) -> Result<()> {
+    let config = liquidity::LiquidityConfig::new(
+        config.spread_bps,
+        Decimal::from_f32(config.quantity).context("Invalid quantity")?,
+        Decimal::from_f32(config.leverage).context("Invalid leverage")?,
+    )?;
+
+    liquidity::handler::start(config)
+}
+
+/// Stop providing liquidity, withdrawing all quotes from the orderbook.
+pub fn stop_liquidity_provision() -> Result<()> {
+    liquidity::handler::stop()
+}
+
+pub fn get_liquidity_status() -> Result<LiquidityStatus> {
+    let status = liquidity::handler::status()?;
+
+    Ok(status.into())
+}
@@ -0,0 +1,134 @@
+//! Authorization of sensitive operations, e.g. revealing the seed phrase.
+//!
+//! A sensitive operation requires a token, which is only minted once the app reports that the
+//! user confirmed their identity with biometrics. A token authorizes a single invocation of one
+//! kind of operation and expires shortly after it was minted, so that an operation is neither
+//! triggered by accident nor by UI code reusing an earlier confirmation.
+
+use anyhow::bail;
+use anyhow::Result;
+use bitcoin::secp256k1::rand::thread_rng;
+use bitcoin::secp256k1::rand::RngCore;
+use bitcoin::Amount;
+use parking_lot::Mutex;
+use state::Storage;
+use std::collections::HashMap;
+use time::Duration;
+use time::OffsetDateTime;
+
+/// How long a token can be used after it was minted.
+const TOKEN_VALIDITY: Duration = Duration::seconds(60);
+
+/// On-chain payments of more than this amount are [`SensitiveOperation::LargePayment`]s.
+pub const LARGE_PAYMENT_THRESHOLD: Amount = Amount::from_sat(1_000_000);
+
+/// The unused tokens, by token.
+static AUTHORIZATIONS: Storage<Mutex<HashMap<String, Authorization>>> = Storage::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensitiveOperation {
+    ShowSeedPhrase,
+    /// An on-chain payment of more than [`LARGE_PAYMENT_THRESHOLD`], or of the whole balance.
+    LargePayment,
+    ForceCloseChannel,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Authorization {
+    operation: SensitiveOperation,
+    expires_at: OffsetDateTime,
+}
+
+fn authorizations() -> &'static Mutex<HashMap<String, Authorization>> {
+    AUTHORIZATIONS.get_or_set(|| Mutex::new(HashMap::new()))
+}
+
+/// Whether sending `amount_sats` on-chain is a [`SensitiveOperation::LargePayment`]. An amount
+/// of zero sends the whole balance.
+pub fn is_large_payment(amount_sats: u64) -> bool {
+    amount_sats == 0 || Amount::from_sat(amount_sats) > LARGE_PAYMENT_THRESHOLD
+}
+
+/// Mint a token authorizing a single invocation of the `operation`.
+///
+/// Must only be called once the user confirmed their identity with biometrics.
+pub fn authorize(operation: SensitiveOperation, now: OffsetDateTime) -> String {
+    let mut token = [0u8; 32];
+    thread_rng().fill_bytes(&mut token);
+    let token = hex::encode(token);
+
+    let mut authorizations = authorizations().lock();
+    authorizations.retain(|_, authorization| authorization.expires_at > now);
+    authorizations.insert(
+        token.clone(),
+        Authorization {
+            operation,
+            expires_at: now + TOKEN_VALIDITY,
+        },
+    );
+
+    tracing::info!(?operation, "Authorized sensitive operation");
+
+    token
+}
+
+/// Use up the `token`, failing unless it authorizes the `operation` at `now`.
+///
+/// The token cannot be used again, even if it authorizes another operation.
+pub fn consume(token: &str, operation: SensitiveOperation, now: OffsetDateTime) -> Result<()> {
+    let authorization = authorizations().lock().remove(token);
+
+    match authorization {
+        Some(authorization) if authorization.expires_at <= now => {
+            bail!("Authorization of {operation:?} expired")
+        }
+        Some(authorization) if authorization.operation != operation => {
+            bail!(
+                "Authorization of {:?} does not authorize {operation:?}",
+                authorization.operation
+            )
+        }
+        Some(_) => Ok(()),
+        None => bail!("{operation:?} is not authorized"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_authorizes_a_single_invocation() {
+        let now = OffsetDateTime::now_utc();
+        let token = authorize(SensitiveOperation::ShowSeedPhrase, now);
+
+        assert!(consume(&token, SensitiveOperation::ShowSeedPhrase, now).is_ok());
+        assert!(consume(&token, SensitiveOperation::ShowSeedPhrase, now).is_err());
+    }
+
+    #[test]
+    fn token_is_scoped_to_its_operation() {
+        let now = OffsetDateTime::now_utc();
+        let token = authorize(SensitiveOperation::ShowSeedPhrase, now);
+
+        assert!(consume(&token, SensitiveOperation::ForceCloseChannel, now).is_err());
+        // A token which was presented for the wrong operation is used up.
+        assert!(consume(&token, SensitiveOperation::ShowSeedPhrase, now).is_err());
+    }
+
+    #[test]
+    fn token_expires() {
+        let now = OffsetDateTime::now_utc();
+        let token = authorize(SensitiveOperation::LargePayment, now);
+
+        let later = now + TOKEN_VALIDITY;
+        assert!(consume(&token, SensitiveOperation::LargePayment, later).is_err());
+    }
+
+    #[test]
+    fn payments_of_the_whole_balance_are_large() {
+        assert!(is_large_payment(0));
+        assert!(!is_large_payment(LARGE_PAYMENT_THRESHOLD.to_sat()));
+        assert!(is_large_payment(LARGE_PAYMENT_THRESHOLD.to_sat() + 1));
+    }
+}
@@ -4,6 +4,7 @@ use crate::db;
 use crate::event::subscriber::Subscriber;
 use crate::event::EventInternal;
 use crate::event::EventType;
+use crate::wallet_descriptors;
 use anyhow::bail;
 use anyhow::ensure;
 use anyhow::Result;
@@ -18,6 +19,7 @@ use std::time::Duration;
 use xxi_node::commons::Backup;
 use xxi_node::commons::DeleteBackup;
 use xxi_node::commons::Restore;
+use xxi_node::storage::encrypted::EncryptedDlcStoreProvider;
 use xxi_node::storage::sled::SledStorageProvider;
 use xxi_node::storage::DlcStoreProvider;
 
@@ -27,6 +29,8 @@ pub const DB_BACKUP_KEY: &str = "10101";
 pub const LN_BACKUP_KEY: &str = "ln";
 pub const DLC_BACKUP_KEY: &str = "dlc";
 pub const DB_BACKUP_NAME: &str = "db";
+pub const WALLET_BACKUP_KEY: &str = "wallet";
+pub const WALLET_DESCRIPTORS_BACKUP_NAME: &str = "descriptors";
 
 #[derive(Clone)]
 pub struct DBBackupSubscriber {
@@ -72,6 +76,7 @@ impl Subscriber for DBBackupSubscriber {
             EventType::OrderUpdateNotification,
             EventType::OrderFilledWith,
             EventType::SpendableOutputs,
+            EventType::OrderTemplatesUpdated,
         ]
     }
 }
@@ -92,7 +97,7 @@ impl RemoteBackupClient {
 
         Self {
             inner,
-            endpoint: format!("http://{}/api", config::get_http_endpoint()),
+            endpoint: format!("http://{}/api/v2", config::get_http_endpoint()),
             cipher,
         }
     }
@@ -199,7 +204,10 @@ impl RemoteBackupClient {
         remote_handle
     }
 
-    pub async fn restore(&self, dlc_storage: Arc<SledStorageProvider>) -> Result<()> {
+    pub async fn restore(
+        &self,
+        dlc_storage: Arc<EncryptedDlcStoreProvider<SledStorageProvider>>,
+    ) -> Result<()> {
         let runtime = crate::state::get_or_create_tokio_runtime()?;
         runtime
             .spawn({
@@ -271,6 +279,12 @@ impl RemoteBackupClient {
                                         );
                                         fs::write(db_file.as_path(), decrypted_value)?;
                                     }
+                                    x if x == WALLET_BACKUP_KEY
+                                        && key == WALLET_DESCRIPTORS_BACKUP_NAME =>
+                                    {
+                                        tracing::debug!("Restoring wallet descriptors");
+                                        wallet_descriptors::store_restored(&decrypted_value)?;
+                                    }
                                     _ => {
                                         tracing::warn!(backup_key, "Received unknown backup key")
                                     }
@@ -0,0 +1,48 @@
+use crate::commons::reqwest_client;
+use crate::config;
+use crate::event;
+use crate::event::EventInternal;
+use crate::orderbook;
+use crate::polls;
+use crate::session;
+use anyhow::Context;
+use anyhow::Result;
+use xxi_node::commons::Bootstrap;
+
+/// Load everything the app needs from the coordinator on startup with a single request.
+///
+/// The parts which are otherwise pushed through the orderbook websocket are applied right away,
+/// so that the app does not have to wait for the websocket connection. The returned polls only
+/// include the ones the user has not answered or ignored yet.
+pub async fn bootstrap() -> Result<Bootstrap> {
+    let client = reqwest_client();
+    let request = client.get(format!(
+        "http://{}/api/v2/bootstrap",
+        config::get_http_endpoint()
+    ));
+    let response = session::send(request)
+        .await
+        .context("Failed to bootstrap")?
+        .error_for_status()?;
+
+    let mut bootstrap = response.json::<Bootstrap>().await?;
+
+    tracing::info!(
+        referral_status = ?bootstrap.config.referral_status,
+        orders = bootstrap.orders.len(),
+        funding_fee_events = bootstrap.funding_fee_events.len(),
+        "Received bootstrap from coordinator"
+    );
+
+    orderbook::apply_config(bootstrap.config.clone());
+    orderbook::set_initial_prices(&bootstrap.orders);
+    orderbook::apply_funding_fee_events(bootstrap.funding_fee_events.clone())?;
+
+    if let Some(funding_rate) = bootstrap.next_funding_rate {
+        event::publish(&EventInternal::NextFundingRate(funding_rate));
+    }
+
+    bootstrap.polls = polls::unanswered_polls(bootstrap.polls)?;
+
+    Ok(bootstrap)
+}
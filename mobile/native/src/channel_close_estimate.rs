@@ -0,0 +1,268 @@
+use crate::dlc;
+use crate::state;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::Amount;
+use dlc_manager::channel::signed_channel::SignedChannel;
+use dlc_manager::channel::signed_channel::SignedChannelState;
+use dlc_manager::DlcChannelId;
+use hex::FromHex;
+use lightning::chain::chaininterface::ConfirmationTarget;
+use time::Duration;
+use time::OffsetDateTime;
+
+/// The relative timelock of the CET spending the buffer transaction of a force-closed DLC channel,
+/// which is also the relative timelock of our output of the settle transaction. Mirrors
+/// `CET_NSEQUENCE` of `rust-dlc`.
+const CSV_DELAY_BLOCKS: u32 = 288;
+
+/// The average time between two blocks.
+const BLOCK_INTERVAL: Duration = Duration::minutes(10);
+
+/// An upper bound for the weight of the transaction claiming our output of the settle
+/// transaction once its timelock has expired: one P2WSH input spending the timelocked branch and
+/// one P2WPKH output.
+const SETTLE_CLAIM_TX_WEIGHT_WU: u64 = 500;
+
+/// How much it costs to close a DLC channel and how long it takes until the funds are in our
+/// on-chain wallet.
+pub struct ChannelCloseEstimate {
+    /// The estimate for closing the DLC channel the requested way.
+    pub requested: CloseEstimate,
+    /// The estimate for the other way of closing the DLC channel, if that is possible. A DLC
+    /// channel with an open position can only be force-closed.
+    pub alternative: Option<CloseEstimate>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CloseEstimate {
+    pub force: bool,
+    /// What we expect to receive in our on-chain wallet. None if it depends on the price attested
+    /// at the expiry of the open position.
+    pub payout_sats: Option<u64>,
+    /// Our share of the fees set aside when the DLC channel was opened, which pay for the
+    /// transactions closing it.
+    pub reserved_fee_sats: u64,
+    /// The fee of the transactions we have to publish after the DLC channel is closed, at the
+    /// current fee rate.
+    pub additional_fee_sats: u64,
+    /// The number of blocks until the funds are in our on-chain wallet, including the
+    /// confirmation of the first transaction closing the DLC channel.
+    pub blocks_until_funds: u32,
+    /// The estimated time until the funds are in our on-chain wallet.
+    pub seconds_until_funds: u64,
+}
+
+/// What we need to know about a DLC channel to estimate the cost of closing it.
+struct ClosableChannel {
+    /// The fees set aside by both parties when the DLC channel was opened.
+    reserved_fee: Amount,
+    /// Our balance, if there is no open position.
+    own_payout: Option<Amount>,
+    /// The expiry of the open position, if any.
+    expiry: Option<OffsetDateTime>,
+}
+
+pub fn estimate_channel_close(channel_id: String, force: bool) -> Result<ChannelCloseEstimate> {
+    let channel_id = DlcChannelId::from_hex(channel_id).context("Invalid DLC channel ID")?;
+
+    let channel = dlc::get_signed_dlc_channels()?
+        .into_iter()
+        .find(|channel| channel.channel_id == channel_id)
+        .context("DLC channel not found")?;
+
+    let channel = closable_channel(&channel)?;
+
+    let fee_rate = dlc::get_fee_rate_for_target(ConfirmationTarget::Normal);
+    let fee_rate_sats_per_vb = fee_rate.as_sat_per_vb() as f64;
+
+    let now = OffsetDateTime::now_utc();
+
+    let requested = estimate(&channel, force, fee_rate_sats_per_vb, now)?;
+    let alternative = estimate(&channel, !force, fee_rate_sats_per_vb, now).ok();
+
+    Ok(ChannelCloseEstimate {
+        requested,
+        alternative,
+    })
+}
+
+fn closable_channel(channel: &SignedChannel) -> Result<ClosableChannel> {
+    let fund_output = channel
+        .fund_tx
+        .output
+        .get(channel.fund_output_index)
+        .context("DLC channel without fund output")?;
+    let total_collateral = channel.own_params.collateral + channel.counter_params.collateral;
+    let reserved_fee = Amount::from_sat(fund_output.value.saturating_sub(total_collateral));
+
+    let (own_payout, expiry) = match channel.state {
+        SignedChannelState::Settled { own_payout, .. } => {
+            (Some(Amount::from_sat(own_payout)), None)
+        }
+        SignedChannelState::Established { .. } => {
+            let expiry = state::get_node()
+                .inner
+                .get_expiry_for_confirmed_dlc_channel(&channel.channel_id)?;
+
+            (None, Some(expiry))
+        }
+        ref state => bail!("Can't close DLC channel in state {state}"),
+    };
+
+    Ok(ClosableChannel {
+        reserved_fee,
+        own_payout,
+        expiry,
+    })
+}
+
+fn estimate(
+    channel: &ClosableChannel,
+    force: bool,
+    fee_rate_sats_per_vb: f64,
+    now: OffsetDateTime,
+) -> Result<CloseEstimate> {
+    // The fee reserve is split evenly between the two parties.
+    let reserved_fee = channel.reserved_fee / 2;
+
+    let estimate = match (force, channel.own_payout) {
+        // The close transaction pays us straight away.
+        (false, Some(own_payout)) => CloseEstimate {
+            force,
+            payout_sats: Some(own_payout.to_sat()),
+            reserved_fee_sats: reserved_fee.to_sat(),
+            additional_fee_sats: 0,
+            blocks_until_funds: 1,
+            seconds_until_funds: BLOCK_INTERVAL.whole_seconds() as u64,
+        },
+        (false, None) => bail!("A DLC channel with an open position can only be force-closed"),
+        // Our output of the settle transaction is timelocked and has to be claimed by us.
+        (true, Some(own_payout)) => {
+            let claim_fee = claim_fee(fee_rate_sats_per_vb);
+            let blocks = 1 + CSV_DELAY_BLOCKS + 1;
+
+            CloseEstimate {
+                force,
+                payout_sats: Some(
+                    own_payout
+                        .checked_sub(claim_fee)
+                        .unwrap_or(Amount::ZERO)
+                        .to_sat(),
+                ),
+                reserved_fee_sats: reserved_fee.to_sat(),
+                additional_fee_sats: claim_fee.to_sat(),
+                blocks_until_funds: blocks,
+                seconds_until_funds: seconds_for_blocks(blocks),
+            }
+        }
+        // The CET spending the buffer transaction pays us directly, but it can only be published
+        // once the timelock has expired and the oracle has attested to the price at expiry.
+        (true, None) => {
+            let blocks = 1 + CSV_DELAY_BLOCKS + 1;
+
+            let until_expiry = channel
+                .expiry
+                .map(|expiry| (expiry - now).whole_seconds().max(0) as u64)
+                .unwrap_or_default();
+            let seconds = seconds_for_blocks(blocks)
+                .max(until_expiry + BLOCK_INTERVAL.whole_seconds() as u64);
+
+            CloseEstimate {
+                force,
+                payout_sats: None,
+                reserved_fee_sats: reserved_fee.to_sat(),
+                additional_fee_sats: 0,
+                blocks_until_funds: blocks,
+                seconds_until_funds: seconds,
+            }
+        }
+    };
+
+    Ok(estimate)
+}
+
+fn claim_fee(fee_rate_sats_per_vb: f64) -> Amount {
+    let weight_vb = SETTLE_CLAIM_TX_WEIGHT_WU as f64 / 4.0;
+    let fee = (weight_vb * fee_rate_sats_per_vb).ceil() as u64;
+
+    Amount::from_sat(fee)
+}
+
+fn seconds_for_blocks(blocks: u32) -> u64 {
+    (BLOCK_INTERVAL * blocks).whole_seconds() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> OffsetDateTime {
+        // Wed Jul 10 2024 12:00:00 GMT+0000
+        OffsetDateTime::from_unix_timestamp(1720612800).unwrap()
+    }
+
+    fn settled_channel() -> ClosableChannel {
+        ClosableChannel {
+            reserved_fee: Amount::from_sat(2_000),
+            own_payout: Some(Amount::from_sat(100_000)),
+            expiry: None,
+        }
+    }
+
+    #[test]
+    fn collaborative_close_only_costs_the_reserved_fee() {
+        let estimate = estimate(&settled_channel(), false, 10.0, now()).unwrap();
+
+        assert_eq!(
+            estimate,
+            CloseEstimate {
+                force: false,
+                payout_sats: Some(100_000),
+                reserved_fee_sats: 1_000,
+                additional_fee_sats: 0,
+                blocks_until_funds: 1,
+                seconds_until_funds: 600,
+            }
+        );
+    }
+
+    #[test]
+    fn force_close_of_settled_channel_has_to_claim_the_timelocked_output() {
+        let estimate = estimate(&settled_channel(), true, 10.0, now()).unwrap();
+
+        assert_eq!(
+            estimate,
+            CloseEstimate {
+                force: true,
+                payout_sats: Some(100_000 - 1_250),
+                reserved_fee_sats: 1_000,
+                additional_fee_sats: 1_250,
+                blocks_until_funds: 290,
+                seconds_until_funds: 290 * 600,
+            }
+        );
+    }
+
+    #[test]
+    fn channel_with_open_position_can_only_be_force_closed() {
+        let channel = ClosableChannel {
+            reserved_fee: Amount::from_sat(2_000),
+            own_payout: None,
+            expiry: Some(now() + Duration::days(7)),
+        };
+
+        assert!(estimate(&channel, false, 10.0, now()).is_err());
+
+        let estimate = estimate(&channel, true, 10.0, now()).unwrap();
+
+        assert_eq!(estimate.payout_sats, None);
+        assert_eq!(estimate.additional_fee_sats, 0);
+        // The funds are only available after the position expired.
+        assert_eq!(
+            estimate.seconds_until_funds,
+            (Duration::days(7) + BLOCK_INTERVAL).whole_seconds() as u64
+        );
+    }
+}
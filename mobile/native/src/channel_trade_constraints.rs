@@ -42,7 +42,9 @@ pub fn channel_trade_constraints() -> Result<TradeConstraints> {
     let min_margin = match &signed_channel {
         Some(_) => 1,
         // TODO(holzeis): https://github.com/get10101/10101/issues/1905
-        None => 250_000,
+        //
+        // The coordinator raises the minimum channel collateral if on-chain fees are high.
+        None => config.min_channel_collateral_sats.max(250_000),
     };
 
     let min_quantity = config.min_quantity;
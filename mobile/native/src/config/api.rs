@@ -17,6 +17,10 @@ pub struct Config {
     pub oracle_pubkey: String,
     pub health_check_interval_secs: u64,
     pub meme_endpoint: String,
+    /// The HTTP endpoints (`host:port`) of further coordinators, by priority. They only serve
+    /// read-only requests, e.g. for the prices, while the coordinator holding the DLC channel is
+    /// unreachable.
+    pub fallback_coordinators: Vec<String>,
 }
 
 pub struct Directories {
@@ -40,6 +44,11 @@ impl From<(Config, Directories)> for ConfigInternal {
             p2p_endpoint: format!("{}:{}", config.host, config.p2p_port)
                 .parse()
                 .expect("host and p2p_port to be valid"),
+            fallback_http_endpoints: config
+                .fallback_coordinators
+                .iter()
+                .map(|endpoint| endpoint.parse().expect("fallback coordinator to be valid"))
+                .collect(),
             network: parse_network(&config.network),
             oracle_endpoint: config.oracle_endpoint,
             oracle_pubkey: XOnlyPublicKey::from_str(config.oracle_pubkey.as_str())
@@ -59,6 +68,10 @@ pub fn parse_network(network: &str) -> Network {
         "testnet" => Network::Testnet,
         "mainnet" => Network::Bitcoin,
         "bitcoin" => Network::Bitcoin,
-        _ => Network::Regtest,
+        "regtest" => Network::Regtest,
+        network => {
+            tracing::warn!(network, "Unknown network, falling back to regtest");
+            Network::Regtest
+        }
     }
 }
@@ -16,6 +16,8 @@ pub struct ConfigInternal {
     http_endpoint: SocketAddr,
     #[allow(dead_code)] // Irrelevant when using websockets
     p2p_endpoint: SocketAddr,
+    /// The coordinators to fall back to for read-only requests, by priority.
+    fallback_http_endpoints: Vec<SocketAddr>,
     network: Network,
     oracle_endpoint: String,
     oracle_pubkey: XOnlyPublicKey,
@@ -24,9 +26,8 @@ pub struct ConfigInternal {
     seed_dir: String,
 }
 
-pub fn coordinator_health_endpoint() -> String {
-    let config = crate::state::get_config();
-    format!("http://{}/health", config.http_endpoint)
+pub fn coordinator_health_endpoint(http_endpoint: SocketAddr) -> String {
+    format!("http://{http_endpoint}/health")
 }
 
 pub fn health_check_interval() -> Duration {
@@ -66,6 +67,10 @@ pub fn get_http_endpoint() -> SocketAddr {
     crate::state::get_config().http_endpoint
 }
 
+pub fn get_fallback_http_endpoints() -> Vec<SocketAddr> {
+    crate::state::get_config().fallback_http_endpoints
+}
+
 pub fn get_network() -> Network {
     crate::state::get_config().network
 }
@@ -85,3 +90,10 @@ pub fn get_backup_dir() -> String {
         .to_string_lossy()
         .to_string()
 }
+
+pub fn get_log_dir() -> String {
+    Path::new(&get_data_dir())
+        .join("logs")
+        .to_string_lossy()
+        .to_string()
+}
@@ -48,6 +48,7 @@ impl ToSql<Text, Sqlite> for OrderReason {
             OrderReason::Expired => "Expired".to_string(),
             OrderReason::CoordinatorLiquidated => "CoordinatorLiquidated".to_string(),
             OrderReason::TraderLiquidated => "TraderLiquidated".to_string(),
+            OrderReason::ChannelMigration => "ChannelMigration".to_string(),
         };
         out.set_value(text);
         Ok(IsNull::No)
@@ -63,6 +64,7 @@ impl FromSql<Text, Sqlite> for OrderReason {
             "Expired" => Ok(OrderReason::Expired),
             "CoordinatorLiquidated" => Ok(OrderReason::CoordinatorLiquidated),
             "TraderLiquidated" => Ok(OrderReason::TraderLiquidated),
+            "ChannelMigration" => Ok(OrderReason::ChannelMigration),
             _ => Err("Unrecognized enum variant".into()),
         };
     }
@@ -103,6 +105,7 @@ impl ToSql<Text, Sqlite> for ContractSymbol {
     fn to_sql(&self, out: &mut Output<Sqlite>) -> serialize::Result {
         let text = match *self {
             ContractSymbol::BtcUsd => "BtcUsd",
+            ContractSymbol::EthUsd => "EthUsd",
         };
         out.set_value(text);
         Ok(IsNull::No)
@@ -115,6 +118,7 @@ impl FromSql<Text, Sqlite> for ContractSymbol {
 
         return match string.as_str() {
             "BtcUsd" => Ok(ContractSymbol::BtcUsd),
+            "EthUsd" => Ok(ContractSymbol::EthUsd),
             _ => Err("Unrecognized enum variant".into()),
         };
     }
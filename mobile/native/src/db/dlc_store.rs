@@ -0,0 +1,187 @@
+use crate::db;
+use crate::schema::dlc_store;
+use anyhow::Result;
+use diesel::prelude::*;
+use xxi_node::storage::DlcStoreProvider;
+use xxi_node::storage::KeyValue;
+use xxi_node::storage::Op;
+
+/// Keeps the DLC channels and contracts in the app database instead of sled.
+#[derive(Clone, Copy, Default)]
+pub struct SqliteStorageProvider;
+
+#[derive(Insertable, Queryable, Debug, Clone, PartialEq)]
+#[diesel(table_name = dlc_store)]
+pub(crate) struct DlcStoreEntry {
+    kind: i32,
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+impl DlcStoreEntry {
+    /// The key value pairs of the given kind, or only the one with the given key.
+    pub fn get(
+        conn: &mut SqliteConnection,
+        kind: u8,
+        key: Option<&[u8]>,
+    ) -> QueryResult<Vec<KeyValue>> {
+        let mut query = dlc_store::table
+            .filter(dlc_store::kind.eq(i32::from(kind)))
+            .into_boxed();
+
+        if let Some(key) = key {
+            query = query.filter(dlc_store::key.eq(key));
+        }
+
+        let entries: Vec<DlcStoreEntry> = query.load(conn)?;
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| KeyValue {
+                key: entry.key,
+                value: entry.value,
+            })
+            .collect())
+    }
+
+    pub fn upsert(
+        conn: &mut SqliteConnection,
+        kind: u8,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> QueryResult<()> {
+        diesel::replace_into(dlc_store::table)
+            .values(DlcStoreEntry {
+                kind: i32::from(kind),
+                key,
+                value,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Apply the operations in a single transaction.
+    pub fn apply(conn: &mut SqliteConnection, ops: Vec<Op>) -> QueryResult<()> {
+        conn.transaction(|conn| {
+            for op in ops {
+                match op {
+                    Op::Write { kind, key, value } => Self::upsert(conn, kind, key, value)?,
+                    Op::Delete { kind, key } => {
+                        Self::delete(conn, kind, key.as_deref())?;
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Delete the key value pair with the given key, or all of the given kind without one.
+    pub fn delete(conn: &mut SqliteConnection, kind: u8, key: Option<&[u8]>) -> QueryResult<usize> {
+        let query = dlc_store::table.filter(dlc_store::kind.eq(i32::from(kind)));
+
+        match key {
+            Some(key) => diesel::delete(query.filter(dlc_store::key.eq(key))).execute(conn),
+            None => diesel::delete(query).execute(conn),
+        }
+    }
+}
+
+impl DlcStoreProvider for SqliteStorageProvider {
+    fn read(&self, kind: u8, key: Option<Vec<u8>>) -> Result<Vec<KeyValue>> {
+        let mut conn = db::connection()?;
+        let key_values = DlcStoreEntry::get(&mut conn, kind, key.as_deref())?;
+
+        Ok(key_values)
+    }
+
+    fn write(&self, kind: u8, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        let mut conn = db::connection()?;
+        DlcStoreEntry::upsert(&mut conn, kind, key, value)?;
+
+        Ok(())
+    }
+
+    fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> Result<()> {
+        let mut conn = db::connection()?;
+        DlcStoreEntry::delete(&mut conn, kind, key.as_deref())?;
+
+        Ok(())
+    }
+
+    fn write_batch(&self, ops: Vec<Op>) -> Result<()> {
+        let mut conn = db::connection()?;
+        DlcStoreEntry::apply(&mut conn, ops)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATIONS;
+    use diesel::Connection;
+    use diesel::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+
+    #[test]
+    fn test_dlc_store() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        DlcStoreEntry::upsert(&mut conn, 1, b"contract".to_vec(), b"offered".to_vec()).unwrap();
+        DlcStoreEntry::upsert(&mut conn, 1, b"contract".to_vec(), b"signed".to_vec()).unwrap();
+        DlcStoreEntry::upsert(&mut conn, 2, b"channel-1".to_vec(), b"signed".to_vec()).unwrap();
+        DlcStoreEntry::upsert(&mut conn, 2, b"channel-2".to_vec(), b"closed".to_vec()).unwrap();
+
+        let contracts = DlcStoreEntry::get(&mut conn, 1, None).unwrap();
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].key, b"contract".to_vec());
+        assert_eq!(contracts[0].value, b"signed".to_vec());
+
+        let channel = DlcStoreEntry::get(&mut conn, 2, Some(b"channel-2")).unwrap();
+        assert_eq!(channel.len(), 1);
+        assert_eq!(channel[0].value, b"closed".to_vec());
+        assert!(DlcStoreEntry::get(&mut conn, 2, Some(b"unknown"))
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            DlcStoreEntry::delete(&mut conn, 2, Some(b"channel-1")).unwrap(),
+            1
+        );
+        assert_eq!(DlcStoreEntry::get(&mut conn, 2, None).unwrap().len(), 1);
+
+        DlcStoreEntry::delete(&mut conn, 2, None).unwrap();
+        assert!(DlcStoreEntry::get(&mut conn, 2, None).unwrap().is_empty());
+        assert_eq!(DlcStoreEntry::get(&mut conn, 1, None).unwrap().len(), 1);
+
+        DlcStoreEntry::apply(
+            &mut conn,
+            vec![
+                Op::Delete {
+                    kind: 1,
+                    key: Some(b"contract".to_vec()),
+                },
+                Op::Write {
+                    kind: 1,
+                    key: b"final-contract".to_vec(),
+                    value: b"signed".to_vec(),
+                },
+                Op::Write {
+                    kind: 2,
+                    key: b"channel".to_vec(),
+                    value: b"signed".to_vec(),
+                },
+            ],
+        )
+        .unwrap();
+
+        let contracts = DlcStoreEntry::get(&mut conn, 1, None).unwrap();
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].key, b"final-contract".to_vec());
+        assert_eq!(DlcStoreEntry::get(&mut conn, 2, None).unwrap().len(), 1);
+    }
+}
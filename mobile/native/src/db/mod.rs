@@ -1,15 +1,23 @@
 use crate::config;
 use crate::db::models::FailureReason;
 use crate::db::models::FundingFeeEvent;
+use crate::db::models::Intent;
+use crate::db::models::MakerFill;
+use crate::db::models::NewMakerFill;
+use crate::db::models::NewOrderTemplate;
 use crate::db::models::NewTrade;
 use crate::db::models::Order;
 use crate::db::models::OrderState;
+use crate::db::models::OrderTemplate;
 use crate::db::models::Position;
+use crate::db::models::ReserveAutoTopUp;
 use crate::db::models::SpendableOutputInsertable;
 use crate::db::models::SpendableOutputQueryable;
 use crate::db::models::Trade;
 use crate::db::models::Transaction;
 use crate::db::models::UnpaidFundingFeeEvent;
+use crate::db::models::WalletBalances;
+use crate::db::models::Watch;
 use crate::trade;
 use anyhow::anyhow;
 use anyhow::Context;
@@ -41,6 +49,7 @@ use xxi_node::commons;
 mod custom_types;
 
 pub mod dlc_messages;
+pub mod dlc_store;
 pub mod last_outbound_dlc_messages;
 pub mod models;
 pub mod polls;
@@ -53,6 +62,9 @@ pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 /// there is no need for concurrent access to the database.
 const MAX_DB_POOL_SIZE: u32 = 1;
 
+/// The name of the local copy of the database which is created to back it up.
+const DB_BACKUP_FILE_NAME: &str = "trades.sqlite";
+
 static DB: Storage<Arc<Pool<ConnectionManager<SqliteConnection>>>> = Storage::new();
 static BACKUP_CONNECTION: Storage<Arc<Mutex<Connection>>> = Storage::new();
 
@@ -89,7 +101,7 @@ pub fn init_db(db_dir: &str, network: Network) -> Result<()> {
         return Ok(());
     }
 
-    let database_url = format!("sqlite://{db_dir}/trades-{network}.sqlite");
+    let database_url = format!("sqlite://{}", database_path(db_dir, network));
     let manager = ConnectionManager::<SqliteConnection>::new(database_url);
     let pool = r2d2::Pool::builder()
         .max_size(MAX_DB_POOL_SIZE)
@@ -111,7 +123,7 @@ pub fn init_db(db_dir: &str, network: Network) -> Result<()> {
 
     tracing::debug!("Opening read-only backup connection");
     let backup_conn = Connection::open_with_flags(
-        format!("{db_dir}/trades-{network}.sqlite"),
+        database_path(db_dir, network),
         // [`OpenFlags::SQLITE_OPEN_READ_ONLY`]: The database is opened in read-only mode. If the database does not already exist, an error is returned
         // [`OpenFlags::SQLITE_OPEN_NO_MUTEX`]: The new database connection will use the "multi-thread" threading mode. This means that separate threads are allowed to use SQLite at the same time, as long as each thread is using a different database connection.
         // https://www.sqlite.org/c3ref/open.html
@@ -128,7 +140,7 @@ pub fn init_db(db_dir: &str, network: Network) -> Result<()> {
 pub fn back_up() -> Result<String> {
     let connection = BACKUP_CONNECTION.get().lock();
     let backup_dir = config::get_backup_dir();
-    let dst_path = Path::new(&backup_dir).join("trades.sqlite");
+    let dst_path = Path::new(&backup_dir).join(DB_BACKUP_FILE_NAME);
 
     let mut dst = Connection::open(dst_path.clone())?;
     let backup = Backup::new(&connection, &mut dst)?;
@@ -138,6 +150,35 @@ pub fn back_up() -> Result<String> {
     Ok(dst_path.to_string_lossy().to_string())
 }
 
+/// The space the database takes up on disk, including its write-ahead log, in bytes.
+pub fn size_on_disk() -> Result<u64> {
+    let db_path = database_path(&config::get_data_dir(), config::get_network());
+
+    let mut size = 0;
+    for suffix in ["", "-wal", "-shm"] {
+        if let Ok(metadata) = std::fs::metadata(format!("{db_path}{suffix}")) {
+            size += metadata.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Moves the write-ahead log into the database and rebuilds the database file, returning unused
+/// pages to the file system.
+pub fn compact() -> Result<()> {
+    let mut connection = connection()?;
+    connection
+        .batch_execute("PRAGMA wal_checkpoint(TRUNCATE); VACUUM;")
+        .context("Failed to compact database")?;
+
+    Ok(())
+}
+
+fn database_path(db_dir: &str, network: Network) -> String {
+    format!("{db_dir}/trades-{network}.sqlite")
+}
+
 pub fn connection() -> Result<PooledConnection<ConnectionManager<SqliteConnection>>> {
     let pool = DB.try_get().context("DB uninitialised").cloned()?;
 
@@ -572,3 +613,171 @@ pub fn mark_funding_fee_events_as_paid(
 
     Ok(())
 }
+
+pub fn insert_order_template(template: crate::trade::order_template::OrderTemplate) -> Result<()> {
+    let mut db = connection()?;
+
+    NewOrderTemplate::insert(&mut db, template).context("Failed to insert order template")?;
+
+    Ok(())
+}
+
+pub fn get_order_templates() -> Result<Vec<crate::trade::order_template::OrderTemplate>> {
+    let mut db = connection()?;
+
+    let templates = OrderTemplate::get_all(&mut db)?;
+
+    Ok(templates)
+}
+
+pub fn get_order_template(
+    name: &str,
+) -> Result<Option<crate::trade::order_template::OrderTemplate>> {
+    let mut db = connection()?;
+
+    let template = OrderTemplate::get(&mut db, name)?;
+
+    Ok(template)
+}
+
+/// Delete the order template with the given name, returning whether it existed.
+pub fn delete_order_template(name: &str) -> Result<bool> {
+    let mut db = connection()?;
+
+    let deleted = OrderTemplate::delete(&mut db, name)?;
+
+    Ok(deleted > 0)
+}
+
+pub fn insert_intent(intent: &crate::trade::intent::Intent) -> Result<()> {
+    let mut db = connection()?;
+
+    Intent::insert(&mut db, intent).context("Failed to insert intent")?;
+
+    Ok(())
+}
+
+pub fn get_intents() -> Result<Vec<crate::trade::intent::Intent>> {
+    let mut db = connection()?;
+
+    Intent::get_all(&mut db)
+}
+
+pub fn get_queued_intents() -> Result<Vec<crate::trade::intent::Intent>> {
+    let mut db = connection()?;
+
+    Intent::get_queued(&mut db)
+}
+
+pub fn update_intent_state(id: Uuid, state: &crate::trade::intent::IntentState) -> Result<()> {
+    let mut db = connection()?;
+
+    let affected_rows = Intent::update_state(&mut db, id, state)?;
+    if affected_rows == 0 {
+        return Err(anyhow!("Could not update intent {id}"));
+    }
+
+    Ok(())
+}
+
+/// Delete the intent with the given id, returning whether it was still queued.
+pub fn delete_queued_intent(id: Uuid) -> Result<bool> {
+    let mut db = connection()?;
+
+    let deleted = Intent::delete_queued(&mut db, id)?;
+
+    Ok(deleted > 0)
+}
+
+/// Record a filled quote of the liquidity provider mode, returning whether it was not known yet.
+pub fn insert_maker_fill(fill: crate::trade::liquidity::MakerFill) -> Result<bool> {
+    let mut db = connection()?;
+
+    let inserted = NewMakerFill::insert(&mut db, fill).context("Failed to insert maker fill")?;
+
+    Ok(inserted > 0)
+}
+
+pub fn get_maker_fills() -> Result<Vec<crate::trade::liquidity::MakerFill>> {
+    let mut db = connection()?;
+
+    let fills = MakerFill::get_all(&mut db)?;
+
+    Ok(fills)
+}
+
+/// Remember the wallet balances, so that they can be shown right away on the next start.
+pub fn cache_wallet_balances(balances: crate::event::api::Balances) -> Result<()> {
+    let mut db = connection()?;
+
+    WalletBalances::upsert(&mut db, balances).context("Failed to cache wallet balances")?;
+
+    Ok(())
+}
+
+pub fn get_cached_wallet_balances() -> Result<Option<crate::event::api::Balances>> {
+    let mut db = connection()?;
+
+    let balances = WalletBalances::get(&mut db)?;
+
+    Ok(balances)
+}
+
+/// Persist the automatic top-up policy of the collateral reserve. `None` opts out of it.
+pub fn set_reserve_auto_top_up_policy(
+    policy: Option<crate::reserve_top_up::AutoTopUpPolicy>,
+) -> Result<()> {
+    let mut db = connection()?;
+
+    match policy {
+        Some(policy) => ReserveAutoTopUp::upsert_policy(&mut db, policy),
+        None => ReserveAutoTopUp::delete(&mut db),
+    }
+    .context("Failed to set reserve auto top-up policy")?;
+
+    Ok(())
+}
+
+/// The automatic top-up policy of the collateral reserve, if the user opted in, and the amount we
+/// deposited for which has not been credited yet.
+pub fn get_reserve_auto_top_up(
+) -> Result<Option<(crate::reserve_top_up::AutoTopUpPolicy, Option<Amount>)>> {
+    let mut db = connection()?;
+
+    let auto_top_up = ReserveAutoTopUp::get(&mut db)?;
+
+    Ok(auto_top_up)
+}
+
+pub fn set_pending_reserve_top_up(pending: Option<Amount>) -> Result<()> {
+    let mut db = connection()?;
+
+    ReserveAutoTopUp::set_pending(&mut db, pending)
+        .context("Failed to set pending reserve top-up")?;
+
+    Ok(())
+}
+
+/// Remember a watched transaction or address, returning whether it was not watched yet.
+pub fn insert_watch(watch: &xxi_node::Watch) -> Result<bool> {
+    let mut db = connection()?;
+
+    let inserted = Watch::insert(&mut db, watch).context("Failed to insert watch")?;
+
+    Ok(inserted)
+}
+
+pub fn get_watches() -> Result<Vec<xxi_node::Watch>> {
+    let mut db = connection()?;
+
+    Watch::get_all(&mut db)
+}
+
+/// Forget a watched transaction or address, returning whether it was watched.
+pub fn delete_watch(watch: &xxi_node::Watch) -> Result<bool> {
+    let mut db = connection()?;
+
+    let deleted = Watch::delete(&mut db, watch)?;
+
+    Ok(deleted > 0)
+}
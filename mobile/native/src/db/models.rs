@@ -29,11 +29,26 @@ use std::str::FromStr;
 use time::OffsetDateTime;
 use uuid::Uuid;
 use xxi_node::commons;
+use xxi_node::message_handler::RejectReason;
 
 mod funding_fee_event;
+mod intent;
+mod maker_fill;
+mod order_template;
+mod reserve_auto_top_up;
+mod wallet_balances;
+mod watch;
 
 pub(crate) use funding_fee_event::FundingFeeEvent;
 pub(crate) use funding_fee_event::UnpaidFundingFeeEvent;
+pub(crate) use intent::Intent;
+pub(crate) use maker_fill::MakerFill;
+pub(crate) use maker_fill::NewMakerFill;
+pub(crate) use order_template::NewOrderTemplate;
+pub(crate) use order_template::OrderTemplate;
+pub(crate) use reserve_auto_top_up::ReserveAutoTopUp;
+pub(crate) use wallet_balances::WalletBalances;
+pub(crate) use watch::Watch;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -285,6 +300,7 @@ impl From<crate::trade::order::OrderReason> for OrderReason {
                 OrderReason::CoordinatorLiquidated
             }
             crate::trade::order::OrderReason::TraderLiquidated => OrderReason::TraderLiquidated,
+            crate::trade::order::OrderReason::ChannelMigration => OrderReason::ChannelMigration,
         }
     }
 }
@@ -298,6 +314,7 @@ impl From<OrderReason> for crate::trade::order::OrderReason {
                 crate::trade::order::OrderReason::CoordinatorLiquidated
             }
             OrderReason::TraderLiquidated => crate::trade::order::OrderReason::TraderLiquidated,
+            OrderReason::ChannelMigration => crate::trade::order::OrderReason::ChannelMigration,
         }
     }
 }
@@ -571,12 +588,14 @@ impl From<PositionState> for crate::trade::position::PositionState {
 #[diesel(sql_type = Text)]
 pub enum ContractSymbol {
     BtcUsd,
+    EthUsd,
 }
 
 impl From<commons::ContractSymbol> for ContractSymbol {
     fn from(value: commons::ContractSymbol) -> Self {
         match value {
             commons::ContractSymbol::BtcUsd => ContractSymbol::BtcUsd,
+            commons::ContractSymbol::EthUsd => ContractSymbol::EthUsd,
         }
     }
 }
@@ -585,6 +604,7 @@ impl From<ContractSymbol> for commons::ContractSymbol {
     fn from(value: ContractSymbol) -> Self {
         match value {
             ContractSymbol::BtcUsd => commons::ContractSymbol::BtcUsd,
+            ContractSymbol::EthUsd => commons::ContractSymbol::EthUsd,
         }
     }
 }
@@ -653,6 +673,7 @@ pub enum OrderReason {
     Expired,
     CoordinatorLiquidated,
     TraderLiquidated,
+    ChannelMigration,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, FromSqlRow, AsExpression)]
@@ -699,6 +720,7 @@ pub enum FailureReason {
     SubchannelOfferOutdated,
     SubchannelOfferDateUndetermined,
     SubchannelOfferUnacceptable,
+    OfferRejected(RejectReason),
     OrderRejected(String),
     #[default]
     Unknown,
@@ -734,6 +756,9 @@ impl From<FailureReason> for crate::trade::order::FailureReason {
                     InvalidSubchannelOffer::Unacceptable,
                 )
             }
+            FailureReason::OfferRejected(reason) => {
+                crate::trade::order::FailureReason::OfferRejected(reason)
+            }
             FailureReason::OrderRejected(reason) => {
                 crate::trade::order::FailureReason::OrderRejected(reason)
             }
@@ -764,6 +789,9 @@ impl From<crate::trade::order::FailureReason> for FailureReason {
                 }
                 InvalidSubchannelOffer::Unacceptable => FailureReason::SubchannelOfferUnacceptable,
             },
+            crate::trade::order::FailureReason::OfferRejected(reason) => {
+                FailureReason::OfferRejected(reason)
+            }
             crate::trade::order::FailureReason::OrderRejected(reason) => {
                 FailureReason::OrderRejected(reason)
             }
@@ -0,0 +1,193 @@
+use crate::schema::intents;
+use crate::trade::intent::IntentState;
+use diesel::prelude::*;
+use diesel::Queryable;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const QUEUED: &str = "queued";
+const EXECUTED: &str = "executed";
+const EXPIRED: &str = "expired";
+const FAILED: &str = "failed";
+
+#[derive(Insertable, Queryable, Debug, Clone, PartialEq)]
+#[diesel(table_name = intents)]
+pub(crate) struct Intent {
+    id: String,
+    action: String,
+    state: String,
+    failure_reason: Option<String>,
+    created_at: i64,
+    valid_until: i64,
+    updated_at: i64,
+}
+
+impl Intent {
+    pub fn insert(
+        conn: &mut SqliteConnection,
+        intent: &crate::trade::intent::Intent,
+    ) -> anyhow::Result<()> {
+        let (state, failure_reason) = state_to_sql(&intent.state);
+
+        let intent = Intent {
+            id: intent.id.to_string(),
+            action: serde_json::to_string(&intent.action)?,
+            state: state.to_string(),
+            failure_reason,
+            created_at: intent.created_at.unix_timestamp(),
+            valid_until: intent.valid_until.unix_timestamp(),
+            updated_at: OffsetDateTime::now_utc().unix_timestamp(),
+        };
+
+        diesel::insert_into(intents::table)
+            .values(intent)
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// All intents, the most recently queued first.
+    pub fn get_all(
+        conn: &mut SqliteConnection,
+    ) -> anyhow::Result<Vec<crate::trade::intent::Intent>> {
+        let intents: Vec<Intent> = intents::table
+            .order_by(intents::created_at.desc())
+            .load(conn)?;
+
+        intents.into_iter().map(TryFrom::try_from).collect()
+    }
+
+    /// The intents waiting to be submitted, in the order they were queued.
+    pub fn get_queued(
+        conn: &mut SqliteConnection,
+    ) -> anyhow::Result<Vec<crate::trade::intent::Intent>> {
+        let intents: Vec<Intent> = intents::table
+            .filter(intents::state.eq(QUEUED))
+            .order_by(intents::created_at.asc())
+            .load(conn)?;
+
+        intents.into_iter().map(TryFrom::try_from).collect()
+    }
+
+    pub fn update_state(
+        conn: &mut SqliteConnection,
+        id: Uuid,
+        state: &IntentState,
+    ) -> QueryResult<usize> {
+        let (state, failure_reason) = state_to_sql(state);
+
+        diesel::update(intents::table)
+            .filter(intents::id.eq(id.to_string()))
+            .set((
+                intents::state.eq(state),
+                intents::failure_reason.eq(failure_reason),
+                intents::updated_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)
+    }
+
+    pub fn delete_queued(conn: &mut SqliteConnection, id: Uuid) -> QueryResult<usize> {
+        diesel::delete(intents::table)
+            .filter(intents::id.eq(id.to_string()))
+            .filter(intents::state.eq(QUEUED))
+            .execute(conn)
+    }
+}
+
+fn state_to_sql(state: &IntentState) -> (&'static str, Option<String>) {
+    match state {
+        IntentState::Queued => (QUEUED, None),
+        IntentState::Executed => (EXECUTED, None),
+        IntentState::Expired => (EXPIRED, None),
+        IntentState::Failed { reason } => (FAILED, Some(reason.clone())),
+    }
+}
+
+impl TryFrom<Intent> for crate::trade::intent::Intent {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        Intent {
+            id,
+            action,
+            state,
+            failure_reason,
+            created_at,
+            valid_until,
+            updated_at: _,
+        }: Intent,
+    ) -> anyhow::Result<Self> {
+        let state = match state.as_str() {
+            QUEUED => IntentState::Queued,
+            EXECUTED => IntentState::Executed,
+            EXPIRED => IntentState::Expired,
+            FAILED => IntentState::Failed {
+                reason: failure_reason.unwrap_or_default(),
+            },
+            state => anyhow::bail!("Unknown intent state {state}"),
+        };
+
+        Ok(Self {
+            id: Uuid::from_str(&id)?,
+            action: serde_json::from_str(&action)?,
+            state,
+            created_at: OffsetDateTime::from_unix_timestamp(created_at)?,
+            valid_until: OffsetDateTime::from_unix_timestamp(valid_until)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATIONS;
+    use crate::trade::intent::IntentAction;
+    use diesel::Connection;
+    use diesel::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+
+    #[test]
+    fn test_intents() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let nickname = crate::trade::intent::Intent::new(
+            IntentAction::UpdateNickname {
+                nickname: "satoshi".to_string(),
+            },
+            time::Duration::hours(1),
+        )
+        .unwrap();
+        let withdrawal = crate::trade::intent::Intent::new(
+            IntentAction::WithdrawViaLightning {
+                invoice: "lnbc1".to_string(),
+            },
+            time::Duration::hours(1),
+        )
+        .unwrap();
+
+        Intent::insert(&mut conn, &nickname).unwrap();
+        Intent::insert(&mut conn, &withdrawal).unwrap();
+
+        assert_eq!(Intent::get_queued(&mut conn).unwrap().len(), 2);
+
+        let failed = IntentState::Failed {
+            reason: "Invoice expired".to_string(),
+        };
+        Intent::update_state(&mut conn, withdrawal.id, &failed).unwrap();
+
+        let queued = Intent::get_queued(&mut conn).unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].action, nickname.action);
+
+        let intents = Intent::get_all(&mut conn).unwrap();
+        assert_eq!(intents.len(), 2);
+        assert!(intents.iter().any(|intent| intent.state == failed));
+
+        // Only queued intents can be deleted.
+        assert_eq!(Intent::delete_queued(&mut conn, withdrawal.id).unwrap(), 0);
+        assert_eq!(Intent::delete_queued(&mut conn, nickname.id).unwrap(), 1);
+        assert!(Intent::get_queued(&mut conn).unwrap().is_empty());
+    }
+}
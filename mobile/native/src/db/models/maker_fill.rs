@@ -0,0 +1,148 @@
+use crate::db::models::ContractSymbol;
+use crate::db::models::Direction;
+use crate::schema::maker_fills;
+use diesel::prelude::*;
+use diesel::Queryable;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Insertable, Debug, Clone, PartialEq)]
+#[diesel(table_name = maker_fills)]
+pub(crate) struct NewMakerFill {
+    order_id: String,
+    contract_symbol: ContractSymbol,
+    direction: Direction,
+    price: f32,
+    quantity: f32,
+    index_price: f32,
+    filled_at: i64,
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+#[diesel(table_name = maker_fills)]
+pub(crate) struct MakerFill {
+    id: i32,
+    order_id: String,
+    contract_symbol: ContractSymbol,
+    direction: Direction,
+    price: f32,
+    quantity: f32,
+    index_price: f32,
+    filled_at: i64,
+}
+
+impl NewMakerFill {
+    /// Insert the fill, unless a fill of the same order was already recorded.
+    pub fn insert(
+        conn: &mut SqliteConnection,
+        fill: crate::trade::liquidity::MakerFill,
+    ) -> QueryResult<usize> {
+        diesel::insert_into(maker_fills::table)
+            .values(NewMakerFill::from(fill))
+            .on_conflict(maker_fills::order_id)
+            .do_nothing()
+            .execute(conn)
+    }
+}
+
+impl MakerFill {
+    pub fn get_all(
+        conn: &mut SqliteConnection,
+    ) -> QueryResult<Vec<crate::trade::liquidity::MakerFill>> {
+        let fills: Vec<MakerFill> = maker_fills::table
+            .order_by(maker_fills::filled_at.asc())
+            .load(conn)?;
+
+        let fills = fills
+            .into_iter()
+            .map(crate::trade::liquidity::MakerFill::from)
+            .collect();
+
+        Ok(fills)
+    }
+}
+
+impl From<crate::trade::liquidity::MakerFill> for NewMakerFill {
+    fn from(
+        crate::trade::liquidity::MakerFill {
+            order_id,
+            contract_symbol,
+            direction,
+            price,
+            quantity,
+            index_price,
+            filled_at,
+        }: crate::trade::liquidity::MakerFill,
+    ) -> Self {
+        Self {
+            order_id: order_id.to_string(),
+            contract_symbol: contract_symbol.into(),
+            direction: direction.into(),
+            price: price.to_f32().expect("to fit"),
+            quantity: quantity.to_f32().expect("to fit"),
+            index_price: index_price.to_f32().expect("to fit"),
+            filled_at: filled_at.unix_timestamp(),
+        }
+    }
+}
+
+impl From<MakerFill> for crate::trade::liquidity::MakerFill {
+    fn from(
+        MakerFill {
+            id: _,
+            order_id,
+            contract_symbol,
+            direction,
+            price,
+            quantity,
+            index_price,
+            filled_at,
+        }: MakerFill,
+    ) -> Self {
+        Self {
+            order_id: Uuid::parse_str(&order_id).expect("valid uuid"),
+            contract_symbol: contract_symbol.into(),
+            direction: direction.into(),
+            price: Decimal::try_from(price).expect("to fit"),
+            quantity: Decimal::try_from(quantity).expect("to fit"),
+            index_price: Decimal::try_from(index_price).expect("to fit"),
+            filled_at: OffsetDateTime::from_unix_timestamp(filled_at).expect("valid"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATIONS;
+    use diesel::Connection;
+    use diesel::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_maker_fills() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let fill = crate::trade::liquidity::MakerFill {
+            order_id: Uuid::new_v4(),
+            contract_symbol: xxi_node::commons::ContractSymbol::BtcUsd,
+            direction: xxi_node::commons::Direction::Long,
+            price: dec!(49_950),
+            quantity: dec!(100),
+            index_price: dec!(50_000),
+            filled_at: OffsetDateTime::from_unix_timestamp(1_719_907_200).unwrap(),
+        };
+
+        assert_eq!(NewMakerFill::insert(&mut conn, fill.clone()).unwrap(), 1);
+
+        // A fill is only recorded once, even if we learn about it again.
+        assert_eq!(NewMakerFill::insert(&mut conn, fill.clone()).unwrap(), 0);
+
+        let fills = MakerFill::get_all(&mut conn).unwrap();
+        assert_eq!(fills, vec![fill]);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::db::models::ContractSymbol;
+use crate::db::models::Direction;
+use crate::schema::order_templates;
+use crate::trade::order_template::TemplateQuantity;
+use diesel::prelude::*;
+use diesel::Queryable;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+
+#[derive(Insertable, Debug, Clone, PartialEq)]
+#[diesel(table_name = order_templates)]
+pub(crate) struct NewOrderTemplate {
+    name: String,
+    contract_symbol: ContractSymbol,
+    direction: Direction,
+    quantity: Option<f32>,
+    quantity_percent: Option<f32>,
+    leverage: f32,
+    created_at: i64,
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+#[diesel(table_name = order_templates)]
+pub(crate) struct OrderTemplate {
+    id: i32,
+    name: String,
+    contract_symbol: ContractSymbol,
+    direction: Direction,
+    quantity: Option<f32>,
+    quantity_percent: Option<f32>,
+    leverage: f32,
+    created_at: i64,
+}
+
+impl NewOrderTemplate {
+    pub fn insert(
+        conn: &mut SqliteConnection,
+        template: crate::trade::order_template::OrderTemplate,
+    ) -> QueryResult<()> {
+        diesel::insert_into(order_templates::table)
+            .values(NewOrderTemplate::from(template))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+impl OrderTemplate {
+    pub fn get_all(
+        conn: &mut SqliteConnection,
+    ) -> QueryResult<Vec<crate::trade::order_template::OrderTemplate>> {
+        let templates: Vec<OrderTemplate> = order_templates::table
+            .order_by(order_templates::created_at.asc())
+            .load(conn)?;
+
+        let templates = templates
+            .into_iter()
+            .map(crate::trade::order_template::OrderTemplate::from)
+            .collect();
+
+        Ok(templates)
+    }
+
+    pub fn get(
+        conn: &mut SqliteConnection,
+        name: &str,
+    ) -> QueryResult<Option<crate::trade::order_template::OrderTemplate>> {
+        let template: Option<OrderTemplate> = order_templates::table
+            .filter(order_templates::name.eq(name))
+            .first(conn)
+            .optional()?;
+
+        Ok(template.map(crate::trade::order_template::OrderTemplate::from))
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, name: &str) -> QueryResult<usize> {
+        diesel::delete(order_templates::table)
+            .filter(order_templates::name.eq(name))
+            .execute(conn)
+    }
+}
+
+impl From<crate::trade::order_template::OrderTemplate> for NewOrderTemplate {
+    fn from(
+        crate::trade::order_template::OrderTemplate {
+            name,
+            contract_symbol,
+            direction,
+            quantity,
+            leverage,
+            created_at,
+        }: crate::trade::order_template::OrderTemplate,
+    ) -> Self {
+        let (quantity, quantity_percent) = match quantity {
+            TemplateQuantity::Contracts(quantity) => (Some(quantity), None),
+            TemplateQuantity::PercentOfMax(percent) => (None, Some(percent)),
+        };
+
+        Self {
+            name,
+            contract_symbol: contract_symbol.into(),
+            direction: direction.into(),
+            quantity: quantity.map(|quantity| quantity.to_f32().expect("to fit")),
+            quantity_percent: quantity_percent.map(|percent| percent.to_f32().expect("to fit")),
+            leverage: leverage.to_f32().expect("to fit"),
+            created_at: created_at.unix_timestamp(),
+        }
+    }
+}
+
+impl From<OrderTemplate> for crate::trade::order_template::OrderTemplate {
+    fn from(
+        OrderTemplate {
+            id: _,
+            name,
+            contract_symbol,
+            direction,
+            quantity,
+            quantity_percent,
+            leverage,
+            created_at,
+        }: OrderTemplate,
+    ) -> Self {
+        let quantity = match (quantity, quantity_percent) {
+            (Some(quantity), _) => {
+                TemplateQuantity::Contracts(Decimal::try_from(quantity).expect("to fit"))
+            }
+            (None, Some(percent)) => {
+                TemplateQuantity::PercentOfMax(Decimal::try_from(percent).expect("to fit"))
+            }
+            (None, None) => unreachable!("enforced by a check constraint"),
+        };
+
+        Self {
+            name,
+            contract_symbol: contract_symbol.into(),
+            direction: direction.into(),
+            quantity,
+            leverage: Decimal::try_from(leverage).expect("to fit"),
+            created_at: OffsetDateTime::from_unix_timestamp(created_at).expect("valid"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATIONS;
+    use diesel::Connection;
+    use diesel::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_order_templates() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let fixed = crate::trade::order_template::OrderTemplate::new(
+            "fixed".to_string(),
+            xxi_node::commons::ContractSymbol::BtcUsd,
+            xxi_node::commons::Direction::Long,
+            TemplateQuantity::Contracts(dec!(100)),
+            dec!(2),
+        )
+        .unwrap();
+        let relative = crate::trade::order_template::OrderTemplate::new(
+            "relative".to_string(),
+            xxi_node::commons::ContractSymbol::BtcUsd,
+            xxi_node::commons::Direction::Short,
+            TemplateQuantity::PercentOfMax(dec!(50)),
+            dec!(1),
+        )
+        .unwrap();
+
+        NewOrderTemplate::insert(&mut conn, fixed.clone()).unwrap();
+        NewOrderTemplate::insert(&mut conn, relative.clone()).unwrap();
+
+        // Template names are unique.
+        assert!(NewOrderTemplate::insert(&mut conn, fixed.clone()).is_err());
+
+        let templates = OrderTemplate::get_all(&mut conn).unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(
+            OrderTemplate::get(&mut conn, "relative")
+                .unwrap()
+                .unwrap()
+                .quantity,
+            relative.quantity
+        );
+
+        assert_eq!(OrderTemplate::delete(&mut conn, "fixed").unwrap(), 1);
+        assert!(OrderTemplate::get(&mut conn, "fixed").unwrap().is_none());
+    }
+}
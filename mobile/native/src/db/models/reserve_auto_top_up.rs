@@ -0,0 +1,124 @@
+use crate::reserve_top_up::AutoTopUpPolicy;
+use crate::schema::reserve_auto_top_up;
+use bitcoin::Amount;
+use diesel::prelude::*;
+use diesel::Queryable;
+
+/// There is at most one policy, in the row with this id.
+const ID: i32 = 1;
+
+#[derive(Insertable, Queryable, Debug, Clone, PartialEq)]
+#[diesel(table_name = reserve_auto_top_up)]
+pub(crate) struct ReserveAutoTopUp {
+    id: i32,
+    threshold_sats: i64,
+    cap_sats: i64,
+    pending_sats: Option<i64>,
+}
+
+impl ReserveAutoTopUp {
+    /// Opt in to the automatic top-up with `policy`, or update the policy.
+    pub fn upsert_policy(conn: &mut SqliteConnection, policy: AutoTopUpPolicy) -> QueryResult<()> {
+        let threshold_sats = policy.threshold.to_sat() as i64;
+        let cap_sats = policy.cap.to_sat() as i64;
+
+        diesel::insert_into(reserve_auto_top_up::table)
+            .values(&ReserveAutoTopUp {
+                id: ID,
+                threshold_sats,
+                cap_sats,
+                pending_sats: None,
+            })
+            .on_conflict(reserve_auto_top_up::id)
+            .do_update()
+            .set((
+                reserve_auto_top_up::threshold_sats.eq(threshold_sats),
+                reserve_auto_top_up::cap_sats.eq(cap_sats),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Opt out of the automatic top-up.
+    pub fn delete(conn: &mut SqliteConnection) -> QueryResult<()> {
+        diesel::delete(reserve_auto_top_up::table).execute(conn)?;
+
+        Ok(())
+    }
+
+    /// The policy and the pending top-up, if the user opted in.
+    pub fn get(
+        conn: &mut SqliteConnection,
+    ) -> QueryResult<Option<(AutoTopUpPolicy, Option<Amount>)>> {
+        let auto_top_up: Option<ReserveAutoTopUp> = reserve_auto_top_up::table
+            .filter(reserve_auto_top_up::id.eq(ID))
+            .first(conn)
+            .optional()?;
+
+        Ok(auto_top_up.map(|auto_top_up| {
+            (
+                AutoTopUpPolicy {
+                    threshold: Amount::from_sat(auto_top_up.threshold_sats as u64),
+                    cap: Amount::from_sat(auto_top_up.cap_sats as u64),
+                },
+                auto_top_up
+                    .pending_sats
+                    .map(|pending| Amount::from_sat(pending as u64)),
+            )
+        }))
+    }
+
+    /// Remember the amount we deposited for, until it is credited.
+    pub fn set_pending(conn: &mut SqliteConnection, pending: Option<Amount>) -> QueryResult<()> {
+        diesel::update(reserve_auto_top_up::table)
+            .filter(reserve_auto_top_up::id.eq(ID))
+            .set(
+                reserve_auto_top_up::pending_sats
+                    .eq(pending.map(|pending| pending.to_sat() as i64)),
+            )
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATIONS;
+    use diesel::Connection;
+    use diesel::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+
+    #[test]
+    fn test_reserve_auto_top_up() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        assert!(ReserveAutoTopUp::get(&mut conn).unwrap().is_none());
+
+        let policy = AutoTopUpPolicy {
+            threshold: Amount::from_sat(20_000),
+            cap: Amount::from_sat(100_000),
+        };
+        ReserveAutoTopUp::upsert_policy(&mut conn, policy).unwrap();
+        ReserveAutoTopUp::set_pending(&mut conn, Some(Amount::from_sat(85_000))).unwrap();
+
+        // Updating the policy keeps the pending top-up.
+        let policy = AutoTopUpPolicy {
+            threshold: Amount::from_sat(30_000),
+            cap: Amount::from_sat(120_000),
+        };
+        ReserveAutoTopUp::upsert_policy(&mut conn, policy).unwrap();
+
+        assert_eq!(
+            ReserveAutoTopUp::get(&mut conn).unwrap(),
+            Some((policy, Some(Amount::from_sat(85_000))))
+        );
+
+        ReserveAutoTopUp::delete(&mut conn).unwrap();
+
+        assert!(ReserveAutoTopUp::get(&mut conn).unwrap().is_none());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::event::api::Balances;
+use crate::schema::wallet_balances;
+use diesel::prelude::*;
+use diesel::Queryable;
+use time::OffsetDateTime;
+
+/// We only ever keep the latest balances, in the row with this id.
+const ID: i32 = 1;
+
+#[derive(Insertable, Queryable, AsChangeset, Debug, Clone, PartialEq)]
+#[diesel(table_name = wallet_balances)]
+pub(crate) struct WalletBalances {
+    id: i32,
+    on_chain: i64,
+    off_chain: Option<i64>,
+    updated_at: i64,
+}
+
+impl WalletBalances {
+    /// Replace the cached balances.
+    pub fn upsert(conn: &mut SqliteConnection, balances: Balances) -> QueryResult<()> {
+        let balances = WalletBalances {
+            id: ID,
+            on_chain: balances.on_chain as i64,
+            off_chain: balances.off_chain.map(|off_chain| off_chain as i64),
+            updated_at: OffsetDateTime::now_utc().unix_timestamp(),
+        };
+
+        diesel::insert_into(wallet_balances::table)
+            .values(&balances)
+            .on_conflict(wallet_balances::id)
+            .do_update()
+            .set(&balances)
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// The cached balances, if the wallet was ever synced.
+    pub fn get(conn: &mut SqliteConnection) -> QueryResult<Option<Balances>> {
+        let balances: Option<WalletBalances> = wallet_balances::table
+            .filter(wallet_balances::id.eq(ID))
+            .first(conn)
+            .optional()?;
+
+        Ok(balances.map(|balances| Balances {
+            on_chain: balances.on_chain as u64,
+            off_chain: balances.off_chain.map(|off_chain| off_chain as u64),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATIONS;
+    use diesel::Connection;
+    use diesel::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+
+    #[test]
+    fn test_wallet_balances() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        assert!(WalletBalances::get(&mut conn).unwrap().is_none());
+
+        WalletBalances::upsert(
+            &mut conn,
+            Balances {
+                on_chain: 100_000,
+                off_chain: None,
+            },
+        )
+        .unwrap();
+        WalletBalances::upsert(
+            &mut conn,
+            Balances {
+                on_chain: 50_000,
+                off_chain: Some(40_000),
+            },
+        )
+        .unwrap();
+
+        let balances = WalletBalances::get(&mut conn).unwrap().unwrap();
+        assert_eq!(balances.on_chain, 50_000);
+        assert_eq!(balances.off_chain, Some(40_000));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::schema::watches;
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::Address;
+use bitcoin::Txid;
+use diesel::prelude::*;
+use diesel::Queryable;
+use std::str::FromStr;
+use time::OffsetDateTime;
+
+const TXID: &str = "txid";
+const ADDRESS: &str = "address";
+
+#[derive(Insertable, Queryable, Debug, Clone, PartialEq)]
+#[diesel(table_name = watches)]
+pub(crate) struct Watch {
+    target: String,
+    kind: String,
+    created_at: i64,
+}
+
+impl Watch {
+    /// Remember the watch, returning whether it was not known yet.
+    pub fn insert(conn: &mut SqliteConnection, watch: &xxi_node::Watch) -> QueryResult<bool> {
+        let (target, kind) = watch_to_sql(watch);
+
+        let affected_rows = diesel::insert_or_ignore_into(watches::table)
+            .values(Watch {
+                target,
+                kind: kind.to_string(),
+                created_at: OffsetDateTime::now_utc().unix_timestamp(),
+            })
+            .execute(conn)?;
+
+        Ok(affected_rows > 0)
+    }
+
+    /// All watches, in the order they were added.
+    ///
+    /// Addresses are not checked against the network again, as they were checked when the watch
+    /// was added.
+    pub fn get_all(conn: &mut SqliteConnection) -> anyhow::Result<Vec<xxi_node::Watch>> {
+        let watches: Vec<Watch> = watches::table
+            .order_by(watches::created_at.asc())
+            .load(conn)?;
+
+        watches.into_iter().map(TryFrom::try_from).collect()
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, watch: &xxi_node::Watch) -> QueryResult<usize> {
+        let (target, _) = watch_to_sql(watch);
+
+        diesel::delete(watches::table)
+            .filter(watches::target.eq(target))
+            .execute(conn)
+    }
+}
+
+fn watch_to_sql(watch: &xxi_node::Watch) -> (String, &'static str) {
+    match watch {
+        xxi_node::Watch::Txid(txid) => (txid.to_string(), TXID),
+        xxi_node::Watch::Address(address) => (address.to_string(), ADDRESS),
+    }
+}
+
+impl TryFrom<Watch> for xxi_node::Watch {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Watch) -> anyhow::Result<Self> {
+        let watch = match value.kind.as_str() {
+            TXID => xxi_node::Watch::Txid(Txid::from_str(&value.target)?),
+            ADDRESS => xxi_node::Watch::Address(
+                Address::<NetworkUnchecked>::from_str(&value.target)?.assume_checked(),
+            ),
+            kind => anyhow::bail!("Unknown watch kind {kind}"),
+        };
+
+        Ok(watch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATIONS;
+    use bitcoin::hashes::Hash;
+    use diesel::Connection;
+    use diesel::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+
+    #[test]
+    fn test_watches() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let txid = xxi_node::Watch::Txid(Txid::from_byte_array([1; 32]));
+        let address = xxi_node::Watch::Address(
+            Address::<NetworkUnchecked>::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+                .unwrap()
+                .assume_checked(),
+        );
+
+        assert!(Watch::insert(&mut conn, &txid).unwrap());
+        assert!(Watch::insert(&mut conn, &address).unwrap());
+        assert!(!Watch::insert(&mut conn, &txid).unwrap());
+
+        let watches = Watch::get_all(&mut conn).unwrap();
+        assert_eq!(watches.len(), 2);
+        assert!(watches.contains(&txid));
+        assert!(watches.contains(&address));
+
+        assert_eq!(Watch::delete(&mut conn, &txid).unwrap(), 1);
+        assert_eq!(Watch::get_all(&mut conn).unwrap(), vec![address]);
+    }
+}
@@ -12,6 +12,7 @@ use tokio::sync::broadcast;
 use tokio::sync::broadcast::error::RecvError;
 use xxi_node::dlc_message::DlcMessage;
 use xxi_node::dlc_message::SerializedDlcMessage;
+use xxi_node::message_handler::RejectReason;
 use xxi_node::message_handler::TenTenOneMessage;
 use xxi_node::node::dlc_channel::send_dlc_message;
 use xxi_node::node::event::NodeEvent;
@@ -66,6 +67,19 @@ pub async fn handle_outbound_dlc_messages(
                 }
             }
             Ok(NodeEvent::DlcChannelEvent { .. }) => {} // ignored
+            Ok(NodeEvent::FundingTransactionConfirmations { .. }) => {} // ignored
+            Ok(NodeEvent::ChainDiscrepancy { .. }) => {} // ignored
+            Ok(NodeEvent::ProtocolVersionMismatch { .. }) => {} // ignored
+            Ok(NodeEvent::CollaborativeCloseFee { peer, msg }) => {
+                if let Err(e) = dlc_handler
+                    .node
+                    .inner
+                    .handle_collaborative_close_fee(peer, msg)
+                    .await
+                {
+                    tracing::error!(peer=%peer, "Failed to handle collaborative close fee message. {e:#}")
+                }
+            }
             Err(RecvError::Lagged(skipped)) => {
                 tracing::warn!("Skipped {skipped} messages");
             }
@@ -95,34 +109,14 @@ impl DlcHandler {
         let mut conn = db::connection()?;
 
         let serialized_outbound_message = SerializedDlcMessage::try_from(&msg)?;
-        let outbound_msg = DlcMessage::new(peer, serialized_outbound_message.clone(), false)?;
+        let outbound_msg = DlcMessage::new(peer, serialized_outbound_message, false)?;
 
         db::dlc_messages::DlcMessage::insert(&mut conn, outbound_msg)?;
-        db::last_outbound_dlc_messages::LastOutboundDlcMessage::upsert(
-            &mut conn,
-            &peer,
-            serialized_outbound_message,
-        )
+        self.node.inner.store_last_outbound_dlc_message(peer, &msg)
     }
 
     pub fn send_last_dlc_message(&self, peer: PublicKey) -> Result<()> {
-        let mut conn = db::connection()?;
-        let last_serialized_message =
-            db::last_outbound_dlc_messages::LastOutboundDlcMessage::get(&mut conn, &peer)?;
-
-        if let Some(last_serialized_message) = last_serialized_message {
-            let message = TenTenOneMessage::try_from(&last_serialized_message)?;
-            send_dlc_message(
-                &self.node.inner.dlc_message_handler,
-                &self.node.inner.peer_manager,
-                peer,
-                message,
-            );
-        } else {
-            tracing::debug!(%peer, "No last dlc message found. Nothing todo.");
-        }
-
-        Ok(())
+        self.node.inner.resend_last_outbound_dlc_message(peer)
     }
 
     /// Rejects all pending dlc channel offers. This is important as there might be several
@@ -154,7 +148,11 @@ impl DlcHandler {
             // Pending dlc channel offer not yet confirmed on-chain
 
             self.node
-                .reject_dlc_channel_offer(None, &offered_channel.get_temporary_id())
+                .reject_dlc_channel_offer(
+                    None,
+                    &offered_channel.get_temporary_id(),
+                    RejectReason::Stale,
+                )
                 .context("Failed to reject pending dlc channel offer")?;
         }
 
@@ -184,7 +182,7 @@ impl DlcHandler {
                     ));
 
                     self.node
-                        .reject_settle_offer(None, channel_id)
+                        .reject_settle_offer(None, channel_id, RejectReason::Stale)
                         .context("Failed to reject pending settle offer")?;
 
                     event::publish(&EventInternal::BackgroundNotification(
@@ -212,7 +210,7 @@ impl DlcHandler {
                     // state. Hence at the moment we also reject pending `RolloverOffers` the same
                     // way we reject `RenewOffers`
                     self.node
-                        .reject_renew_offer(None, channel_id)
+                        .reject_renew_offer(None, channel_id, RejectReason::Stale)
                         .context("Failed to reject pending renew offer")?;
 
                     event::publish(&EventInternal::BackgroundNotification(
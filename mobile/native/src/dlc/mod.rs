@@ -12,12 +12,19 @@ use crate::dlc::node::Node;
 use crate::dlc::node::NodeStorage;
 use crate::dlc::node::WalletHistory;
 use crate::event;
+use crate::event::api::PendingBalance;
+use crate::event::api::WatchTarget;
 use crate::event::EventInternal;
 use crate::health::Tx;
 use crate::orderbook;
 use crate::position::ForceCloseDlcChannelSubscriber;
+use crate::reserve_top_up;
+use crate::startup;
+use crate::startup::StartupPhase;
 use crate::state;
 use crate::storage::TenTenOneNodeStorage;
+use crate::storage_monitor;
+use crate::trade::intent::handler::IntentSubmitter;
 use crate::trade::order;
 use crate::trade::order::FailureReason;
 use crate::trade::order::Order;
@@ -25,8 +32,11 @@ use crate::trade::order::OrderReason;
 use crate::trade::order::OrderState;
 use crate::trade::order::OrderType;
 use crate::trade::position;
+use crate::wallet_descriptors;
 use crate::watcher::InvoiceWatcher;
 use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use bdk::wallet::Balance;
@@ -47,6 +57,7 @@ use itertools::chain;
 use itertools::Itertools;
 use lightning::chain::chaininterface::ConfirmationTarget;
 use lightning::sign::KeysManager;
+use parking_lot::Mutex;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -56,7 +67,6 @@ use std::net::SocketAddr;
 use std::net::TcpListener;
 use std::path::Path;
 use std::str::FromStr;
-use std::sync::mpsc;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::SystemTime;
@@ -74,8 +84,10 @@ use xxi_node::bitcoin_conversion::to_txid_29;
 use xxi_node::bitcoin_conversion::to_txid_30;
 use xxi_node::commons::CollaborativeRevertTraderResponse;
 use xxi_node::commons::OrderbookRequest;
+use xxi_node::node::confirmation::MinConfirmations;
 use xxi_node::node::dlc_channel::estimated_dlc_channel_fee_reserve;
 use xxi_node::node::dlc_channel::estimated_funding_transaction_fee;
+use xxi_node::node::dlc_channel::CloseFeeRateBounds;
 use xxi_node::node::event::NodeEventHandler;
 use xxi_node::node::rust_dlc_manager::channel::signed_channel::SignedChannel;
 use xxi_node::node::rust_dlc_manager::channel::ClosedChannel;
@@ -84,8 +96,15 @@ use xxi_node::node::rust_dlc_manager::Signer;
 use xxi_node::node::rust_dlc_manager::Storage as DlcStorage;
 use xxi_node::node::XXINodeSettings;
 use xxi_node::seed::Bip39Seed;
-use xxi_node::storage::DlcChannelEvent;
+use xxi_node::storage::DlcChannelEventBus;
 use xxi_node::ConfirmationStatus;
+use xxi_node::DepositConfidence;
+use xxi_node::DepositStatus;
+use xxi_node::DepositTracker;
+use xxi_node::Watch;
+use xxi_node::WatchEvent;
+use xxi_node::WatchTracker;
+use xxi_node::WatchedStatus;
 
 pub mod dlc_handler;
 mod subscriber;
@@ -107,6 +126,12 @@ const WALLET_DB_FILE_NAME: &str = "bdk-wallet";
 /// start-up.
 const WALLET_DB_PREFIX: &str = "10101-app";
 
+/// The last known status of our deposits, so that we only publish changes.
+static DEPOSIT_TRACKER: Mutex<DepositTracker> = parking_lot::const_mutex(DepositTracker::new());
+
+/// The last known status of the watched transactions, so that we only publish changes.
+static WATCH_TRACKER: Mutex<WatchTracker> = parking_lot::const_mutex(WatchTracker::new());
+
 /// Trigger an on-chain sync followed by an update to the wallet balance and history.
 ///
 /// We do not wait for the triggered task to finish, because the effect will be reflected
@@ -256,6 +281,7 @@ pub fn get_storage() -> TenTenOneNodeStorage {
                 config::get_data_dir(),
                 config::get_network(),
                 get_node_key(),
+                get_seed().dlc_storage_key(),
             );
             tracing::info!("Initialized 10101 storage!");
             state::set_storage(storage.clone());
@@ -275,6 +301,7 @@ pub fn run(
 ) -> Result<()> {
     runtime.block_on(async move {
         event::publish(&EventInternal::Init("Starting full ldk node".to_string()));
+        startup::set_phase(StartupPhase::StartingNode);
 
         let mut ephemeral_randomness = [0; 32];
         thread_rng().fill_bytes(&mut ephemeral_randomness);
@@ -290,6 +317,7 @@ pub fn run(
 
         event::subscribe(DBBackupSubscriber::new(storage.clone().client));
         event::subscribe(ForceCloseDlcChannelSubscriber);
+        event::subscribe(IntentSubmitter);
 
         let (ln_sender, _) = broadcast::channel::<String>(5);
         event::subscribe(InvoiceWatcher {
@@ -304,7 +332,7 @@ pub fn run(
             bdk_file_store::Store::open_or_create_new(WALLET_DB_PREFIX.as_bytes(), wallet_dir)?
         };
 
-        let (dlc_event_sender, dlc_event_receiver) = mpsc::channel::<DlcChannelEvent>();
+        let dlc_event_bus = DlcChannelEventBus::new();
         let node = xxi_node::node::Node::new(
             "10101",
             config::get_network(),
@@ -321,15 +349,31 @@ pub fn run(
             vec![config::get_oracle_info().into()],
             config::get_oracle_info().public_key,
             node_event_handler.clone(),
-            dlc_event_sender,
+            dlc_event_bus,
         )?;
         let node = Arc::new(node);
 
-        let _running = node.start(dlc_event_receiver)?;
+        let _running = node.start()?;
 
         let node = Arc::new(Node::new(node, _running));
         state::set_node(node.clone());
 
+        match db::get_watches() {
+            Ok(watches) => {
+                for watch in watches {
+                    node.inner.watch(watch);
+                }
+            }
+            Err(e) => tracing::error!("Failed to load watched transactions and addresses: {e:#}"),
+        }
+
+        if let Err(e) = wallet_descriptors::cross_check(&node, &storage.client) {
+            tracing::error!("Failed to cross-check restored wallet descriptors: {e:#}");
+        }
+        if let Err(e) = wallet_descriptors::back_up(&node, &storage.client) {
+            tracing::error!("Failed to back up wallet descriptors: {e:#}");
+        }
+
         orderbook::subscribe(
             node.inner.node_key(),
             runtime,
@@ -338,15 +382,15 @@ pub fn run(
             tx_websocket,
         )?;
 
-        if let Err(e) = spawn_blocking({
+        // The UI already shows the cached balances, hence we don't wait for the wallet.
+        spawn_blocking({
             let node = node.clone();
-            move || keep_wallet_balance_and_history_up_to_date(&node)
-        })
-        .await
-        .expect("To spawn blocking task")
-        {
-            tracing::error!("Failed to update balance and history: {e:#}");
-        }
+            move || {
+                if let Err(e) = keep_wallet_balance_and_history_up_to_date(&node) {
+                    tracing::error!("Failed to update balance and history: {e:#}");
+                }
+            }
+        });
 
         let dlc_handler = DlcHandler::new(node.clone());
         runtime.spawn(async move {
@@ -374,13 +418,30 @@ pub fn run(
             }
         });
 
+        startup::set_phase(StartupPhase::Syncing);
+
         runtime.spawn({
             let runtime = runtime.handle().clone();
+            let node = node.clone();
             async move {
-                loop {
-                    sync_node(&runtime).await;
+                sync_node(&runtime).await;
+
+                // Reconcile the cached balances with the synced wallet before reporting that we
+                // are ready.
+                if let Err(e) =
+                    spawn_blocking(move || keep_wallet_balance_and_history_up_to_date(&node))
+                        .await
+                        .expect("To spawn blocking task")
+                {
+                    tracing::error!("Failed to update balance and history: {e:#}");
+                }
+
+                startup::set_phase(StartupPhase::Ready);
 
+                loop {
                     tokio::time::sleep(NODE_SYNC_INTERVAL).await;
+
+                    sync_node(&runtime).await;
                 }
             }
         });
@@ -417,6 +478,9 @@ pub fn run(
             }
         });
 
+        storage_monitor::spawn_storage_monitor(runtime);
+        reserve_top_up::spawn_auto_top_up(runtime);
+
         event::publish(&EventInternal::Init("10101 is ready.".to_string()));
 
         tokio::spawn(full_sync_on_wallet_db_migration());
@@ -492,6 +556,7 @@ pub async fn restore_from_mnemonic(seed_words: &str, target_seed_file: &Path) ->
         config::get_data_dir(),
         config::get_network(),
         get_node_key(),
+        get_seed().dlc_storage_key(),
     );
     tracing::info!("Initialized 10101 storage!");
     state::set_storage(storage.clone());
@@ -589,41 +654,7 @@ fn keep_wallet_balance_and_history_up_to_date(node: &Node) -> Result<()> {
         })
     });
 
-    let trades = db::get_all_trades()?;
-
-    // We reverse the `Trade`s so that they are already pre-sorted _from oldest to newest_ in terms
-    // of insertion. This is important because we sometimes insert `Trade`s back-to-back, so the
-    // timestamps can coincide.
-    let trades = trades.iter().rev().map(|trade| {
-        let flow = if trade.trade_cost.is_positive() {
-            PaymentFlow::Outbound
-        } else {
-            PaymentFlow::Inbound
-        };
-
-        let amount_sats = trade.trade_cost.abs().to_sat() as u64;
-
-        let timestamp = trade.timestamp;
-
-        // TODO: Add context about direction + contracts!
-        WalletHistoryItem {
-            flow,
-            amount_sats,
-            timestamp: timestamp.unix_timestamp() as u64,
-            status: Status::Confirmed,
-            wallet_type: WalletHistoryItemType::Trade {
-                order_id: trade.order_id.to_string(),
-                fee_sat: trade.fee.to_sat(),
-                pnl: trade.pnl.map(|pnl| pnl.to_sat()),
-                contracts: trade
-                    .contracts
-                    .ceil()
-                    .to_u64()
-                    .expect("Decimal to fit into u64"),
-                direction: trade.direction.to_string(),
-            },
-        }
-    });
+    let trades = trade_history()?;
 
     let history = chain![on_chain, trades, dlc_channel_funding_tx_details]
         .sorted_by(|a, b| b.timestamp.cmp(&a.timestamp))
@@ -634,17 +665,188 @@ fn keep_wallet_balance_and_history_up_to_date(node: &Node) -> Result<()> {
         history,
     };
 
+    if let Err(e) = db::cache_wallet_balances(wallet_info.balances.clone()) {
+        tracing::warn!("Failed to cache wallet balances: {e:#}");
+    }
+
     event::publish(&EventInternal::WalletInfoUpdateNotification(wallet_info));
 
+    let deposit_updates = DEPOSIT_TRACKER.lock().update(node.inner.get_deposits());
+    for deposit in deposit_updates {
+        tracing::info!(
+            txid = %deposit.txid,
+            amount = %deposit.amount,
+            status = ?deposit.status,
+            "Deposit status changed"
+        );
+
+        event::publish(&EventInternal::DepositUpdate(deposit));
+    }
+
+    let watch_updates = WATCH_TRACKER
+        .lock()
+        .update(node.inner.get_watched_transactions());
+    for event in watch_updates {
+        tracing::info!(?event, "Watched transaction changed");
+
+        // A watched transaction is only of interest until it confirms, unlike a watched address
+        // which may be paid to again.
+        if let WatchEvent::FirstSeen(transaction) | WatchEvent::Confirmed(transaction) = &event {
+            if transaction.status == WatchedStatus::Confirmed {
+                if let watch @ Watch::Txid(_) = &transaction.watch {
+                    node.inner.unwatch(watch);
+                    if let Err(e) = db::delete_watch(watch) {
+                        tracing::error!(?watch, "Failed to delete confirmed watch: {e:#}");
+                    }
+                }
+            }
+        }
+
+        event::publish(&EventInternal::WatchUpdate(event));
+    }
+
     Ok(())
 }
 
+/// The unconfirmed deposits which are not yet part of the on-chain balance.
+pub fn get_pending_balance() -> PendingBalance {
+    let deposits = state::get_node().inner.get_deposits();
+
+    let mut pending_balance = PendingBalance::default();
+    for deposit in deposits {
+        match deposit.status {
+            DepositStatus::Unconfirmed {
+                confidence: DepositConfidence::Low,
+            } => pending_balance.low_confidence_sats += deposit.amount.to_sat(),
+            DepositStatus::Unconfirmed {
+                confidence: DepositConfidence::Medium,
+            } => pending_balance.medium_confidence_sats += deposit.amount.to_sat(),
+            // High-confidence deposits are already part of the on-chain balance.
+            DepositStatus::Unconfirmed {
+                confidence: DepositConfidence::High,
+            }
+            | DepositStatus::Confirmed
+            | DepositStatus::Replaced { .. } => continue,
+        }
+
+        pending_balance.deposits.push(deposit.into());
+    }
+
+    pending_balance
+}
+
+/// The trades as wallet history items, which are known without the node running.
+pub(crate) fn trade_history() -> Result<Vec<WalletHistoryItem>> {
+    let trades = db::get_all_trades()?;
+
+    // We reverse the `Trade`s so that they are already pre-sorted _from oldest to newest_ in terms
+    // of insertion. This is important because we sometimes insert `Trade`s back-to-back, so the
+    // timestamps can coincide.
+    let history = trades
+        .iter()
+        .rev()
+        .map(|trade| {
+            let flow = if trade.trade_cost.is_positive() {
+                PaymentFlow::Outbound
+            } else {
+                PaymentFlow::Inbound
+            };
+
+            let amount_sats = trade.trade_cost.abs().to_sat() as u64;
+
+            let timestamp = trade.timestamp;
+
+            // TODO: Add context about direction + contracts!
+            WalletHistoryItem {
+                flow,
+                amount_sats,
+                timestamp: timestamp.unix_timestamp() as u64,
+                status: Status::Confirmed,
+                wallet_type: WalletHistoryItemType::Trade {
+                    order_id: trade.order_id.to_string(),
+                    fee_sat: trade.fee.to_sat(),
+                    pnl: trade.pnl.map(|pnl| pnl.to_sat()),
+                    contracts: trade
+                        .contracts
+                        .ceil()
+                        .to_u64()
+                        .expect("Decimal to fit into u64"),
+                    direction: trade.direction.to_string(),
+                },
+            }
+        })
+        .collect();
+
+    Ok(history)
+}
+
 pub fn get_unused_address() -> Result<String> {
     let address = state::get_node().inner.get_unused_address()?;
 
     Ok(address.to_string())
 }
 
+/// Follow a transaction or address outside of the wallet, e.g. the withdrawal from an exchange,
+/// from the next sync onwards.
+///
+/// Updates are published as [`EventInternal::WatchUpdate`]. A watched transaction is dropped once
+/// it is confirmed.
+pub fn watch(target: WatchTarget) -> Result<()> {
+    let watch = parse_watch_target(target)?;
+
+    if !db::insert_watch(&watch)? {
+        tracing::debug!(?watch, "Already watching");
+    }
+
+    // The persisted watches are registered once the node has started.
+    if let Some(node) = state::try_get_node() {
+        node.inner.watch(watch);
+    }
+
+    Ok(())
+}
+
+pub fn unwatch(target: WatchTarget) -> Result<()> {
+    let watch = parse_watch_target(target)?;
+
+    ensure!(db::delete_watch(&watch)?, "Not watching {watch:?}");
+
+    if let Some(node) = state::try_get_node() {
+        node.inner.unwatch(&watch);
+    }
+
+    Ok(())
+}
+
+pub fn get_watches() -> Result<Vec<WatchTarget>> {
+    let watches = db::get_watches()?;
+
+    Ok(watches.into_iter().map(WatchTarget::from).collect())
+}
+
+fn parse_watch_target(target: WatchTarget) -> Result<Watch> {
+    let watch = match target {
+        WatchTarget::Txid { txid } => Watch::Txid(txid.parse().context("Invalid txid")?),
+        WatchTarget::Address { address } => {
+            let address = address
+                .parse::<Address<NetworkUnchecked>>()
+                .context("Invalid address")?
+                .require_network(config::get_network())
+                .context("Address is for a different network")?;
+
+            if state::try_get_node()
+                .is_some_and(|node| node.inner.is_mine(&address.script_pubkey()))
+            {
+                bail!("Address belongs to our own wallet, which is synced anyway");
+            }
+
+            Watch::Address(address)
+        }
+    };
+
+    Ok(watch)
+}
+
 pub fn get_new_address() -> Result<String> {
     let address = state::get_node().inner.get_new_address()?;
 
@@ -652,6 +854,12 @@ pub fn get_new_address() -> Result<String> {
 }
 
 pub async fn close_channel(is_force_close: bool) -> Result<()> {
+    // A force-close does not need the coordinator, hence it is always possible.
+    let kill_switch = state::get_kill_switch();
+    if !is_force_close && !kill_switch.allows_withdrawals() {
+        bail!("Withdrawals are disabled by the coordinator: {kill_switch}");
+    }
+
     let node = state::get_node();
 
     let channels = node.inner.list_signed_dlc_channels()?;
@@ -766,7 +974,7 @@ pub fn collaborative_revert_channel(
         async move {
             match client
                 .post(format!(
-                    "http://{}/api/channels/confirm-collab-revert",
+                    "http://{}/api/v2/channels/confirm-collab-revert",
                     config::get_http_endpoint(),
                 ))
                 .json(&data)
@@ -1014,6 +1222,12 @@ fn xxi_node_settings() -> XXINodeSettings {
         fee_rate_sync_interval: Duration::from_secs(20),
         sub_channel_manager_periodic_check_interval: Duration::from_secs(30),
         shadow_sync_interval: Duration::from_secs(600),
+        confirmation_tracking_interval: Duration::from_secs(60),
+        // Only the coordinator audits the chain.
+        chain_audit_interval: None,
+        min_confirmations: MinConfirmations::default(),
+        close_fee_rate_bounds: CloseFeeRateBounds::default(),
+        bdk_client_concurrency: 5,
     }
 }
 
@@ -8,7 +8,6 @@ use crate::trade::funding_fee_event::handler::handle_unpaid_funding_fee_events;
 use crate::trade::funding_fee_event::handler::mark_funding_fee_events_as_paid;
 use crate::trade::order;
 use crate::trade::order::FailureReason;
-use crate::trade::order::InvalidSubchannelOffer;
 use crate::trade::position;
 use crate::trade::position::handler::get_positions;
 use crate::trade::position::handler::handle_rollover_offer;
@@ -26,6 +25,7 @@ use dlc_messages::channel::OfferChannel;
 use dlc_messages::channel::Reject;
 use dlc_messages::channel::RenewOffer;
 use dlc_messages::channel::SettleOffer;
+use dlc_messages::contract_msgs::ContractInfo;
 use itertools::Itertools;
 use lightning::chain::transaction::OutPoint;
 use lightning::sign::DelayedPaymentOutputDescriptor;
@@ -45,6 +45,7 @@ use xxi_node::commons;
 use xxi_node::commons::OrderReason;
 use xxi_node::dlc_message::DlcMessage;
 use xxi_node::dlc_message::SerializedDlcMessage;
+use xxi_node::message_handler::RejectReason;
 use xxi_node::message_handler::TenTenOneAcceptChannel;
 use xxi_node::message_handler::TenTenOneCollaborativeCloseOffer;
 use xxi_node::message_handler::TenTenOneMessage;
@@ -64,6 +65,7 @@ use xxi_node::node;
 use xxi_node::node::event::NodeEvent;
 use xxi_node::node::rust_dlc_manager::DlcChannelId;
 use xxi_node::node::tentenone_message_name;
+use xxi_node::node::validate_oracle_events;
 use xxi_node::node::NodeInfo;
 use xxi_node::node::RunningNode;
 use xxi_node::transaction::Transaction;
@@ -417,7 +419,9 @@ impl Node {
                     .context("Failed to update position after DLC closure")?;
 
                 let task = match filled_order.reason.into() {
-                    OrderReason::Manual => BackgroundTask::AsyncTrade(TaskStatus::Success),
+                    OrderReason::Manual | OrderReason::ChannelMigration => {
+                        BackgroundTask::AsyncTrade(TaskStatus::Success)
+                    }
                     OrderReason::Expired => BackgroundTask::Expire(TaskStatus::Success),
                     OrderReason::CoordinatorLiquidated | OrderReason::TraderLiquidated => {
                         BackgroundTask::Liquidate(TaskStatus::Success)
@@ -500,16 +504,22 @@ impl Node {
             reference_id,
         };
 
+        let reason = RejectReason::InvalidOffer;
+
         order::handler::order_failed(
             None,
-            FailureReason::InvalidDlcOffer(InvalidSubchannelOffer::Unacceptable),
+            FailureReason::OfferRejected(reason),
             anyhow!("Failed to accept offer"),
         )
         .context("Could not set order to failed")?;
 
         self.send_dlc_message(
             counterparty,
-            TenTenOneMessage::Reject(TenTenOneReject { reject }),
+            TenTenOneMessage::Reject(TenTenOneReject {
+                reject,
+                order_id: None,
+                reason,
+            }),
         )
     }
 
@@ -518,8 +528,9 @@ impl Node {
         &self,
         order_id: Option<Uuid>,
         channel_id: &DlcChannelId,
+        reason: RejectReason,
     ) -> Result<()> {
-        tracing::warn!("Rejecting dlc channel offer!");
+        tracing::warn!(?reason, "Rejecting dlc channel offer!");
 
         let (reject, counterparty) = self
             .inner
@@ -534,14 +545,18 @@ impl Node {
 
         order::handler::order_failed(
             order_id,
-            FailureReason::InvalidDlcOffer(InvalidSubchannelOffer::Unacceptable),
+            FailureReason::OfferRejected(reason),
             anyhow!("Failed to accept dlc channel offer"),
         )
         .context("Could not set order to failed")?;
 
         self.send_dlc_message(
             to_secp_pk_30(counterparty),
-            TenTenOneMessage::Reject(TenTenOneReject { reject }),
+            TenTenOneMessage::Reject(TenTenOneReject {
+                reject,
+                order_id,
+                reason,
+            }),
         )
     }
 
@@ -552,19 +567,23 @@ impl Node {
         let order_id = offer.filled_with.order_id;
 
         let channel_id = offer.offer_channel.temporary_channel_id;
-        match self
-            .inner
-            .dlc_manager
-            .accept_channel(
-                &channel_id,
-                offer
-                    .offer_channel
-                    .fee_config
-                    .map(dlc::FeeConfig::from)
-                    .unwrap_or(dlc::FeeConfig::EvenSplit),
-            )
-            .map_err(anyhow::Error::new)
+
+        if let Err(e) =
+            validate_oracle_events_for_order(order_id, &offer.offer_channel.contract_info)
         {
+            tracing::error!("Received invalid DLC channel offer: {e:#}");
+            self.reject_dlc_channel_offer(Some(order_id), &channel_id, RejectReason::InvalidOffer)?;
+            return Ok(());
+        }
+
+        match self.inner.dlc_manager.accept_channel(
+            &channel_id,
+            offer
+                .offer_channel
+                .fee_config
+                .map(dlc::FeeConfig::from)
+                .unwrap_or(dlc::FeeConfig::EvenSplit),
+        ) {
             Ok((accept_channel, _, _, node_id)) => {
                 self.send_dlc_message(
                     to_secp_pk_30(node_id),
@@ -576,7 +595,11 @@ impl Node {
             }
             Err(e) => {
                 tracing::error!("Failed to accept DLC channel offer: {e:#}");
-                self.reject_dlc_channel_offer(Some(order_id), &channel_id)?;
+                self.reject_dlc_channel_offer(
+                    Some(order_id),
+                    &channel_id,
+                    RejectReason::AcceptFailed,
+                )?;
             }
         }
 
@@ -588,19 +611,27 @@ impl Node {
         &self,
         order_id: Option<Uuid>,
         channel_id: &DlcChannelId,
+        reason: RejectReason,
     ) -> Result<()> {
-        tracing::warn!("Rejecting pending dlc channel collaborative settlement offer!");
+        tracing::warn!(
+            ?reason,
+            "Rejecting pending dlc channel collaborative settlement offer!"
+        );
         let (reject, counterparty) = self.inner.dlc_manager.reject_settle_offer(channel_id)?;
 
         order::handler::order_failed(
             order_id,
-            FailureReason::InvalidDlcOffer(InvalidSubchannelOffer::Unacceptable),
+            FailureReason::OfferRejected(reason),
             anyhow!("Failed to accept settle offer"),
         )?;
 
         self.send_dlc_message(
             to_secp_pk_30(counterparty),
-            TenTenOneMessage::Reject(TenTenOneReject { reject }),
+            TenTenOneMessage::Reject(TenTenOneReject {
+                reject,
+                order_id,
+                reason,
+            }),
         )
     }
 
@@ -613,16 +644,19 @@ impl Node {
         match order_reason {
             OrderReason::Expired
             | OrderReason::CoordinatorLiquidated
-            | OrderReason::TraderLiquidated => {
+            | OrderReason::TraderLiquidated
+            | OrderReason::ChannelMigration => {
                 tracing::info!(
                     %order_id,
                     "Received an async match from orderbook. Reason: {order_reason:?}"
                 );
 
-                let task = if order_reason == OrderReason::Expired {
-                    BackgroundTask::Expire(TaskStatus::Pending)
-                } else {
-                    BackgroundTask::Liquidate(TaskStatus::Pending)
+                let task = match order_reason {
+                    OrderReason::Expired => BackgroundTask::Expire(TaskStatus::Pending),
+                    OrderReason::ChannelMigration => {
+                        BackgroundTask::AsyncTrade(TaskStatus::Pending)
+                    }
+                    _ => BackgroundTask::Liquidate(TaskStatus::Pending),
                 };
 
                 event::publish(&EventInternal::BackgroundNotification(task));
@@ -647,7 +681,7 @@ impl Node {
             &channel_id,
         ) {
             tracing::error!("Failed to accept dlc channel collaborative settlement offer. {e:#}");
-            self.reject_settle_offer(Some(order_id), &channel_id)?;
+            self.reject_settle_offer(Some(order_id), &channel_id, RejectReason::AcceptFailed)?;
         }
 
         Ok(())
@@ -673,20 +707,25 @@ impl Node {
         &self,
         order_id: Option<Uuid>,
         channel_id: &DlcChannelId,
+        reason: RejectReason,
     ) -> Result<()> {
-        tracing::warn!("Rejecting dlc channel renew offer!");
+        tracing::warn!(?reason, "Rejecting dlc channel renew offer!");
 
         let (reject, counterparty) = self.inner.dlc_manager.reject_renew_offer(channel_id)?;
 
         order::handler::order_failed(
             order_id,
-            FailureReason::InvalidDlcOffer(InvalidSubchannelOffer::Unacceptable),
+            FailureReason::OfferRejected(reason),
             anyhow!("Failed to accept renew offer"),
         )?;
 
         self.send_dlc_message(
             to_secp_pk_30(counterparty),
-            TenTenOneMessage::Reject(TenTenOneReject { reject }),
+            TenTenOneMessage::Reject(TenTenOneReject {
+                reject,
+                order_id,
+                reason,
+            }),
         )
     }
 
@@ -697,6 +736,14 @@ impl Node {
         let order_id = offer.filled_with.order_id;
 
         let channel_id = offer.renew_offer.channel_id;
+
+        if let Err(e) = validate_oracle_events_for_order(order_id, &offer.renew_offer.contract_info)
+        {
+            tracing::error!("Received invalid DLC channel renew offer: {e:#}");
+            self.reject_renew_offer(Some(order_id), &channel_id, RejectReason::InvalidOffer)?;
+            return Ok(());
+        }
+
         match self.inner.dlc_manager.accept_renew_offer(&channel_id) {
             Ok((renew_accept, node_id)) => {
                 self.set_order_to_filling(offer.filled_with.clone())?;
@@ -714,7 +761,7 @@ impl Node {
             Err(e) => {
                 tracing::error!("Failed to accept dlc channel renew offer. {e:#}");
 
-                self.reject_renew_offer(Some(order_id), &channel_id)?;
+                self.reject_renew_offer(Some(order_id), &channel_id, RejectReason::AcceptFailed)?;
             }
         };
 
@@ -722,14 +769,22 @@ impl Node {
     }
 
     #[instrument(fields(channel_id = hex::encode(channel_id)),skip_all, err(Debug))]
-    pub fn reject_rollover_offer(&self, channel_id: &DlcChannelId) -> Result<()> {
-        tracing::warn!("Rejecting rollover offer!");
+    pub fn reject_rollover_offer(
+        &self,
+        channel_id: &DlcChannelId,
+        reason: RejectReason,
+    ) -> Result<()> {
+        tracing::warn!(?reason, "Rejecting rollover offer!");
 
         let (reject, counterparty) = self.inner.dlc_manager.reject_renew_offer(channel_id)?;
 
         self.send_dlc_message(
             to_secp_pk_30(counterparty),
-            TenTenOneMessage::Reject(TenTenOneReject { reject }),
+            TenTenOneMessage::Reject(TenTenOneReject {
+                reject,
+                order_id: None,
+                reason,
+            }),
         )
     }
 
@@ -745,11 +800,29 @@ impl Node {
 
         let channel_id = offer.renew_offer.channel_id;
 
-        match self.inner.dlc_manager.accept_renew_offer(&channel_id) {
-            Ok((renew_accept, node_id)) => {
-                let positions = get_positions()?;
-                let position = positions.first().context("No position to roll over")?;
+        let accepted = get_positions()
+            .and_then(|positions| {
+                positions
+                    .first()
+                    .copied()
+                    .context("No position to roll over")
+            })
+            .map_err(|e| (e, RejectReason::AcceptFailed))
+            .and_then(|position| {
+                validate_oracle_events(&offer.renew_offer.contract_info, position.contract_symbol)
+                    .map_err(|e| (e, RejectReason::InvalidOffer))?;
+
+                let accepted = self
+                    .inner
+                    .dlc_manager
+                    .accept_renew_offer(&channel_id)
+                    .map_err(|e| (e.into(), RejectReason::AcceptFailed))?;
 
+                Ok((accepted, position))
+            });
+
+        match accepted {
+            Ok(((renew_accept, node_id), position)) => {
                 let new_unpaid_funding_fee_events = handle_unpaid_funding_fee_events(
                     &offer
                         .funding_fee_events
@@ -774,14 +847,14 @@ impl Node {
                     TenTenOneMessage::RolloverAccept(TenTenOneRolloverAccept { renew_accept }),
                 )?;
             }
-            Err(e) => {
+            Err((e, reason)) => {
                 tracing::error!("Failed to accept DLC channel rollover offer: {e}");
 
                 event::publish(&EventInternal::BackgroundNotification(
                     BackgroundTask::Rollover(TaskStatus::Failed(format!("{e}"))),
                 ));
 
-                self.reject_rollover_offer(&channel_id)?;
+                self.reject_rollover_offer(&channel_id, reason)?;
             }
         };
 
@@ -832,6 +905,14 @@ impl Node {
     }
 }
 
+/// Ensure that the contract offered to fill an order is attested by the oracle event for the
+/// order's contract symbol.
+fn validate_oracle_events_for_order(order_id: Uuid, contract_info: &ContractInfo) -> Result<()> {
+    let order = db::get_order(order_id)?.with_context(|| format!("Order {order_id} not found"))?;
+
+    validate_oracle_events(contract_info, order.contract_symbol)
+}
+
 #[derive(Clone)]
 pub struct NodeStorage;
 
@@ -879,4 +960,35 @@ impl node::Storage for NodeStorage {
     fn all_transactions_without_fees(&self) -> Result<Vec<Transaction>> {
         db::get_all_transactions_without_fees()
     }
+
+    // Last outbound DLC messages
+
+    fn upsert_last_outbound_dlc_message(
+        &self,
+        peer: PublicKey,
+        message: SerializedDlcMessage,
+    ) -> Result<()> {
+        let mut conn = db::connection()?;
+        db::last_outbound_dlc_messages::LastOutboundDlcMessage::upsert(&mut conn, &peer, message)
+    }
+
+    fn get_last_outbound_dlc_message(
+        &self,
+        peer: &PublicKey,
+    ) -> Result<Option<SerializedDlcMessage>> {
+        let mut conn = db::connection()?;
+        let message = db::last_outbound_dlc_messages::LastOutboundDlcMessage::get(&mut conn, peer)?;
+        Ok(message)
+    }
+
+    fn delete_last_outbound_dlc_message(&self, peer: &PublicKey) -> Result<()> {
+        let mut conn = db::connection()?;
+        db::last_outbound_dlc_messages::LastOutboundDlcMessage::delete(&mut conn, peer)?;
+        Ok(())
+    }
+
+    fn all_last_outbound_dlc_messages(&self) -> Result<Vec<(PublicKey, SerializedDlcMessage)>> {
+        let mut conn = db::connection()?;
+        db::last_outbound_dlc_messages::LastOutboundDlcMessage::get_all(&mut conn)
+    }
 }
@@ -3,6 +3,7 @@ use crate::dlc::DlcChannel;
 use crate::event;
 use crate::event::EventInternal;
 use tokio::sync::broadcast::error::RecvError;
+use xxi_node::message_handler::ProtocolVersionMismatch;
 use xxi_node::node::event::NodeEvent;
 
 impl Node {
@@ -27,10 +28,25 @@ impl Node {
                                 }
                             }
                         }
+                        Ok(NodeEvent::ProtocolVersionMismatch {
+                            mismatch:
+                                ProtocolVersionMismatch::Outdated {
+                                    version,
+                                    peer_min_version,
+                                },
+                            ..
+                        }) => event::publish(&EventInternal::UpdateRequired {
+                            version,
+                            min_version: peer_min_version,
+                        }),
                         Ok(NodeEvent::Connected { .. })
                         | Ok(NodeEvent::SendDlcMessage { .. })
                         | Ok(NodeEvent::StoreDlcMessage { .. })
-                        | Ok(NodeEvent::SendLastDlcMessage { .. }) => {} // ignored
+                        | Ok(NodeEvent::SendLastDlcMessage { .. })
+                        | Ok(NodeEvent::FundingTransactionConfirmations { .. })
+                        | Ok(NodeEvent::ChainDiscrepancy { .. })
+                        | Ok(NodeEvent::CollaborativeCloseFee { .. })
+                        | Ok(NodeEvent::ProtocolVersionMismatch { .. }) => {} // ignored
                         Err(RecvError::Lagged(skipped)) => {
                             tracing::warn!("Skipped {skipped} messages");
                         }
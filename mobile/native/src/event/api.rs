@@ -6,7 +6,12 @@ use crate::event;
 use crate::event::subscriber::Subscriber;
 use crate::event::EventInternal;
 use crate::event::EventType;
+use crate::health::CoordinatorAvailability;
 use crate::health::ServiceUpdate;
+use crate::reserve_top_up;
+use crate::startup::StartupPhase;
+use crate::storage_monitor;
+use crate::trade::intent::api::Intent;
 use crate::trade::order::api::Order;
 use crate::trade::position::api::Position;
 use crate::trade::trades::api::Trade;
@@ -14,7 +19,12 @@ use core::convert::From;
 use flutter_rust_bridge::frb;
 use flutter_rust_bridge::StreamSink;
 use rust_decimal::prelude::ToPrimitive;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::KillSwitchMode;
+use xxi_node::commons::KillSwitchStatus;
 
 #[frb]
 #[derive(Clone)]
@@ -28,13 +38,44 @@ pub enum Event {
     AskPriceUpdateNotification(f32),
     BidPriceUpdateNotification(f32),
     ServiceHealthUpdate(ServiceUpdate),
+    /// Whether the coordinator holding the DLC channel is reachable, or only a fallback
+    /// coordinator serving the prices, or no coordinator at all.
+    CoordinatorAvailabilityUpdate(CoordinatorAvailability),
     BackgroundNotification(BackgroundTask),
     Authenticated(TenTenOneConfig),
     DlcChannelEvent(DlcChannel),
     FundingChannelNotification(FundingChannelTask),
-    LnPaymentReceived { r_hash: String },
+    LnPaymentReceived {
+        r_hash: String,
+    },
     NewTrade(Trade),
     NextFundingRate(FundingRate),
+    StorageWarning(StorageUsage),
+    KillSwitchUpdate(KillSwitch),
+    ChannelClosingOnChain {
+        closing_txid: Option<String>,
+    },
+    StartupPhase(StartupPhase),
+    DepositUpdate(Deposit),
+    WatchUpdate(WatchUpdate),
+    SupportTicketCreated {
+        order_id: Option<String>,
+        reference: String,
+    },
+    /// The protocol `version` of the app is older than the `min_version` the coordinator still
+    /// talks to.
+    UpdateRequired {
+        version: u16,
+        min_version: u16,
+    },
+    IntentUpdate(Intent),
+    /// The restored wallet derives different descriptors than the backed up wallet. The addresses
+    /// of the `backed_up` descriptors are watched, funds on them should be moved into the wallet.
+    WalletDescriptorMigration {
+        backed_up: Vec<String>,
+        derived: Vec<String>,
+    },
+    ReserveTopUpUpdate(ReserveTopUpUpdate),
 }
 
 #[frb]
@@ -77,6 +118,9 @@ impl From<EventInternal> for Event {
                 Event::PositionClosedNotification(PositionClosed { contract_symbol })
             }
             EventInternal::ServiceHealthUpdate(update) => Event::ServiceHealthUpdate(update),
+            EventInternal::CoordinatorAvailabilityUpdate(availability) => {
+                Event::CoordinatorAvailabilityUpdate(availability)
+            }
             EventInternal::BackgroundNotification(task) => {
                 Event::BackgroundNotification(task.into())
             }
@@ -103,6 +147,39 @@ impl From<EventInternal> for Event {
                 end_date: funding_rate.end_date().unix_timestamp(),
             }),
             EventInternal::FundingFeeEvent(event) => Event::NewTrade(event.into()),
+            EventInternal::StorageWarning(usage) => Event::StorageWarning(usage.into()),
+            EventInternal::OrderTemplatesUpdated => {
+                unreachable!("This internal event is not exposed to the UI")
+            }
+            EventInternal::KillSwitchUpdate(status) => Event::KillSwitchUpdate(status.into()),
+            EventInternal::ChannelClosingOnChain { closing_txid } => {
+                Event::ChannelClosingOnChain { closing_txid }
+            }
+            EventInternal::StartupPhase(phase) => Event::StartupPhase(phase),
+            EventInternal::DepositUpdate(deposit) => Event::DepositUpdate(deposit.into()),
+            EventInternal::WatchUpdate(event) => Event::WatchUpdate(event.into()),
+            EventInternal::SupportTicketCreated {
+                order_id,
+                reference,
+            } => Event::SupportTicketCreated {
+                order_id: order_id.map(|order_id| order_id.to_string()),
+                reference,
+            },
+            EventInternal::UpdateRequired {
+                version,
+                min_version,
+            } => Event::UpdateRequired {
+                version,
+                min_version,
+            },
+            EventInternal::IntentUpdate(intent) => Event::IntentUpdate(intent.into()),
+            EventInternal::WalletDescriptorMigration { backed_up, derived } => {
+                Event::WalletDescriptorMigration {
+                    backed_up: vec![backed_up.external, backed_up.internal],
+                    derived: vec![derived.external, derived.internal],
+                }
+            }
+            EventInternal::ReserveTopUpUpdate(update) => Event::ReserveTopUpUpdate(update.into()),
         }
     }
 }
@@ -120,12 +197,19 @@ pub struct PositionClosed {
 #[derive(Clone)]
 pub struct FlutterSubscriber {
     stream: StreamSink<Event>,
+    closed: Arc<AtomicBool>,
 }
 
 /// Subscribes to event relevant for flutter and forwards them to the stream sink.
 impl Subscriber for FlutterSubscriber {
     fn notify(&self, event: &EventInternal) {
-        self.stream.add(event.clone().into());
+        if !self.stream.add(event.clone().into()) {
+            self.closed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
     }
 
     fn events(&self) -> Vec<EventType> {
@@ -138,6 +222,7 @@ impl Subscriber for FlutterSubscriber {
             EventType::AskPriceUpdateNotification,
             EventType::BidPriceUpdateNotification,
             EventType::ServiceHealthUpdate,
+            EventType::CoordinatorAvailabilityUpdate,
             EventType::ChannelStatusUpdate,
             EventType::BackgroundNotification,
             EventType::FundingChannelNotification,
@@ -145,13 +230,120 @@ impl Subscriber for FlutterSubscriber {
             EventType::DlcChannelEvent,
             EventType::NewTrade,
             EventType::NextFundingRate,
+            EventType::StorageWarning,
+            EventType::KillSwitchUpdate,
+            EventType::ChannelClosingOnChain,
+            EventType::StartupPhase,
+            EventType::DepositUpdate,
+            EventType::WatchUpdate,
+            EventType::SupportTicketCreated,
+            EventType::UpdateRequired,
+            EventType::IntentUpdate,
+            EventType::WalletDescriptorMigration,
+            EventType::ReserveTopUpUpdate,
         ]
     }
 }
 
 impl FlutterSubscriber {
     pub fn new(stream: StreamSink<Event>) -> Self {
-        FlutterSubscriber { stream }
+        FlutterSubscriber {
+            stream,
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+/// The events a screen can subscribe to, so that it only receives what it needs.
+#[frb]
+#[derive(Clone, Copy)]
+pub enum EventFilter {
+    Init,
+    WalletInfoUpdateNotification,
+    OrderUpdateNotification,
+    PositionUpdateNotification,
+    PositionClosedNotification,
+    AskPriceUpdateNotification,
+    BidPriceUpdateNotification,
+    ServiceHealthUpdate,
+    CoordinatorAvailabilityUpdate,
+    BackgroundNotification,
+    FundingChannelNotification,
+    Authenticated,
+    DlcChannelEvent,
+    NewTrade,
+    NextFundingRate,
+    StorageWarning,
+    KillSwitchUpdate,
+    ChannelClosingOnChain,
+    StartupPhase,
+    DepositUpdate,
+    WatchUpdate,
+    SupportTicketCreated,
+    UpdateRequired,
+    IntentUpdate,
+    WalletDescriptorMigration,
+    ReserveTopUpUpdate,
+}
+
+impl From<EventFilter> for EventType {
+    fn from(value: EventFilter) -> Self {
+        match value {
+            EventFilter::Init => EventType::Init,
+            EventFilter::WalletInfoUpdateNotification => EventType::WalletInfoUpdateNotification,
+            EventFilter::OrderUpdateNotification => EventType::OrderUpdateNotification,
+            EventFilter::PositionUpdateNotification => EventType::PositionUpdateNotification,
+            EventFilter::PositionClosedNotification => EventType::PositionClosedNotification,
+            EventFilter::AskPriceUpdateNotification => EventType::AskPriceUpdateNotification,
+            EventFilter::BidPriceUpdateNotification => EventType::BidPriceUpdateNotification,
+            EventFilter::ServiceHealthUpdate => EventType::ServiceHealthUpdate,
+            EventFilter::CoordinatorAvailabilityUpdate => EventType::CoordinatorAvailabilityUpdate,
+            EventFilter::BackgroundNotification => EventType::BackgroundNotification,
+            EventFilter::FundingChannelNotification => EventType::FundingChannelNotification,
+            EventFilter::Authenticated => EventType::Authenticated,
+            EventFilter::DlcChannelEvent => EventType::DlcChannelEvent,
+            EventFilter::NewTrade => EventType::NewTrade,
+            EventFilter::NextFundingRate => EventType::NextFundingRate,
+            EventFilter::StorageWarning => EventType::StorageWarning,
+            EventFilter::KillSwitchUpdate => EventType::KillSwitchUpdate,
+            EventFilter::ChannelClosingOnChain => EventType::ChannelClosingOnChain,
+            EventFilter::StartupPhase => EventType::StartupPhase,
+            EventFilter::DepositUpdate => EventType::DepositUpdate,
+            EventFilter::WatchUpdate => EventType::WatchUpdate,
+            EventFilter::SupportTicketCreated => EventType::SupportTicketCreated,
+            EventFilter::UpdateRequired => EventType::UpdateRequired,
+            EventFilter::IntentUpdate => EventType::IntentUpdate,
+            EventFilter::WalletDescriptorMigration => EventType::WalletDescriptorMigration,
+            EventFilter::ReserveTopUpUpdate => EventType::ReserveTopUpUpdate,
+        }
+    }
+}
+
+#[frb]
+#[derive(Clone)]
+pub struct EventDeliveryMetrics {
+    pub subscriber: String,
+    pub queued: u64,
+    pub published: u64,
+    pub delivered: u64,
+    pub coalesced: u64,
+    pub dropped: u64,
+    pub overflowed: u64,
+    pub max_queued: u64,
+}
+
+impl From<event::DeliveryMetrics> for EventDeliveryMetrics {
+    fn from(value: event::DeliveryMetrics) -> Self {
+        EventDeliveryMetrics {
+            subscriber: value.subscriber,
+            queued: value.queued as u64,
+            published: value.published,
+            delivered: value.delivered,
+            coalesced: value.coalesced,
+            dropped: value.dropped,
+            overflowed: value.overflowed,
+            max_queued: value.max_queued as u64,
+        }
     }
 }
 
@@ -206,6 +398,172 @@ pub struct Balances {
     pub off_chain: Option<u64>,
 }
 
+/// A transaction paying to our on-chain wallet.
+#[frb]
+#[derive(Clone, Debug)]
+pub struct Deposit {
+    pub txid: String,
+    pub amount_sats: u64,
+    pub status: DepositStatus,
+}
+
+#[frb]
+#[derive(Clone, Debug)]
+pub enum DepositStatus {
+    Unconfirmed {
+        confidence: DepositConfidence,
+    },
+    Confirmed,
+    /// The deposit was replaced by another transaction spending the same inputs, e.g. a fee bump.
+    Replaced {
+        replaced_by: String,
+    },
+}
+
+/// How likely an unconfirmed deposit is to confirm as it is.
+#[frb]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepositConfidence {
+    /// The sender signalled that they may replace the transaction.
+    Low,
+    Medium,
+    /// The deposit only spends our own coins, e.g. change.
+    High,
+}
+
+impl From<xxi_node::Deposit> for Deposit {
+    fn from(value: xxi_node::Deposit) -> Self {
+        let status = match value.status {
+            xxi_node::DepositStatus::Unconfirmed { confidence } => DepositStatus::Unconfirmed {
+                confidence: confidence.into(),
+            },
+            xxi_node::DepositStatus::Confirmed => DepositStatus::Confirmed,
+            xxi_node::DepositStatus::Replaced { replaced_by } => DepositStatus::Replaced {
+                replaced_by: replaced_by.to_string(),
+            },
+        };
+
+        Self {
+            txid: value.txid.to_string(),
+            amount_sats: value.amount.to_sat(),
+            status,
+        }
+    }
+}
+
+impl From<xxi_node::DepositConfidence> for DepositConfidence {
+    fn from(value: xxi_node::DepositConfidence) -> Self {
+        match value {
+            xxi_node::DepositConfidence::Low => DepositConfidence::Low,
+            xxi_node::DepositConfidence::Medium => DepositConfidence::Medium,
+            xxi_node::DepositConfidence::High => DepositConfidence::High,
+        }
+    }
+}
+
+/// A transaction or address outside of the wallet which the app follows on-chain, e.g. the
+/// withdrawal from an exchange.
+#[frb]
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatchTarget {
+    Txid { txid: String },
+    Address { address: String },
+}
+
+/// A watched transaction, or a transaction paying to a watched address, was seen or changed its
+/// status.
+#[frb]
+#[derive(Clone, Debug)]
+pub struct WatchUpdate {
+    pub target: WatchTarget,
+    pub txid: String,
+    /// The amount paid to the watched address, or to our wallet in case of a watched transaction.
+    pub amount_sats: u64,
+    pub kind: WatchUpdateKind,
+    pub confirmed: bool,
+}
+
+#[frb]
+#[derive(Clone, Copy, Debug)]
+pub enum WatchUpdateKind {
+    /// The transaction was seen for the first time, usually right after it was broadcast.
+    FirstSeen,
+    /// The transaction is back in the mempool, e.g. because its block was reorged.
+    Mempool,
+    Confirmed,
+}
+
+impl From<xxi_node::Watch> for WatchTarget {
+    fn from(value: xxi_node::Watch) -> Self {
+        match value {
+            xxi_node::Watch::Txid(txid) => WatchTarget::Txid {
+                txid: txid.to_string(),
+            },
+            xxi_node::Watch::Address(address) => WatchTarget::Address {
+                address: address.to_string(),
+            },
+        }
+    }
+}
+
+impl From<xxi_node::WatchEvent> for WatchUpdate {
+    fn from(value: xxi_node::WatchEvent) -> Self {
+        let (kind, transaction) = match value {
+            xxi_node::WatchEvent::FirstSeen(transaction) => {
+                (WatchUpdateKind::FirstSeen, transaction)
+            }
+            xxi_node::WatchEvent::Mempool(transaction) => (WatchUpdateKind::Mempool, transaction),
+            xxi_node::WatchEvent::Confirmed(transaction) => {
+                (WatchUpdateKind::Confirmed, transaction)
+            }
+        };
+
+        Self {
+            target: transaction.watch.into(),
+            txid: transaction.txid.to_string(),
+            amount_sats: transaction.amount.to_sat(),
+            kind,
+            confirmed: transaction.status == xxi_node::WatchedStatus::Confirmed,
+        }
+    }
+}
+
+/// The unconfirmed deposits to our on-chain wallet, which are not part of the on-chain balance
+/// yet.
+///
+/// Deposits with [`DepositConfidence::High`] are already part of the on-chain balance and thus not
+/// included.
+#[frb]
+#[derive(Clone, Debug, Default)]
+pub struct PendingBalance {
+    pub low_confidence_sats: u64,
+    pub medium_confidence_sats: u64,
+    pub deposits: Vec<Deposit>,
+}
+
+/// The space the app data takes up on disk, in bytes.
+#[frb]
+#[derive(Clone)]
+pub struct StorageUsage {
+    pub dlc_storage: u64,
+    pub database: u64,
+    pub logs: u64,
+    pub backup: u64,
+    pub total: u64,
+}
+
+impl From<storage_monitor::StorageUsage> for StorageUsage {
+    fn from(value: storage_monitor::StorageUsage) -> Self {
+        StorageUsage {
+            dlc_storage: value.dlc_storage,
+            database: value.database,
+            logs: value.logs,
+            backup: value.backup,
+            total: value.total(),
+        }
+    }
+}
+
 #[frb]
 #[derive(Clone)]
 pub struct FundingRate {
@@ -234,3 +592,63 @@ impl From<event::FundingChannelTask> for FundingChannelTask {
         }
     }
 }
+
+/// The parts of the coordinator which the operator disabled in an emergency.
+#[frb]
+#[derive(Clone, Debug, Default)]
+pub struct KillSwitch {
+    /// No new orders can be submitted.
+    pub read_only: bool,
+    /// No new DLC channels can be opened.
+    pub no_new_channels: bool,
+    /// The DLC channel cannot be closed.
+    pub no_withdrawals: bool,
+    /// Nothing but the on-chain wallet can be used.
+    pub full_halt: bool,
+    pub reason: Option<String>,
+}
+
+impl From<KillSwitchStatus> for KillSwitch {
+    fn from(value: KillSwitchStatus) -> Self {
+        KillSwitch {
+            read_only: value.modes.contains(&KillSwitchMode::ReadOnly),
+            no_new_channels: value.modes.contains(&KillSwitchMode::NoNewChannels),
+            no_withdrawals: value.modes.contains(&KillSwitchMode::NoWithdrawals),
+            full_halt: value.modes.contains(&KillSwitchMode::FullHalt),
+            reason: value.reason,
+        }
+    }
+}
+
+#[frb]
+#[derive(Clone, Debug)]
+pub enum ReserveTopUpUpdate {
+    Started { amount_sats: u64 },
+    Deposited { txid: String, amount_sats: u64 },
+    Credited { amount_sats: u64 },
+    Failed { reason: String },
+}
+
+impl From<reserve_top_up::ReserveTopUpUpdate> for ReserveTopUpUpdate {
+    fn from(value: reserve_top_up::ReserveTopUpUpdate) -> Self {
+        match value {
+            reserve_top_up::ReserveTopUpUpdate::Started { amount } => ReserveTopUpUpdate::Started {
+                amount_sats: amount.to_sat(),
+            },
+            reserve_top_up::ReserveTopUpUpdate::Deposited { txid, amount } => {
+                ReserveTopUpUpdate::Deposited {
+                    txid: txid.to_string(),
+                    amount_sats: amount.to_sat(),
+                }
+            }
+            reserve_top_up::ReserveTopUpUpdate::Credited { amount } => {
+                ReserveTopUpUpdate::Credited {
+                    amount_sats: amount.to_sat(),
+                }
+            }
+            reserve_top_up::ReserveTopUpUpdate::Failed { reason } => {
+                ReserveTopUpUpdate::Failed { reason }
+            }
+        }
+    }
+}
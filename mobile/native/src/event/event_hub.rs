@@ -1,54 +1,398 @@
 use crate::event::subscriber::Subscriber;
 use crate::event::EventInternal;
 use crate::event::EventType;
+use parking_lot::Condvar;
 use parking_lot::Mutex;
-use parking_lot::MutexGuard;
+use parking_lot::RwLock;
 use state::Storage;
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::vec;
+use std::thread;
 
-static EVENT_HUB: Storage<Arc<Mutex<EventHub>>> = Storage::new();
+/// The number of events which may be queued for a single subscriber before events are dropped or
+/// the overflow is reported.
+const QUEUE_CAPACITY: usize = 256;
 
-pub(crate) fn get() -> MutexGuard<'static, EventHub> {
-    EVENT_HUB
-        .get_or_set(|| {
-            Arc::new(Mutex::new(EventHub {
-                subscribers: HashMap::new(),
-            }))
-        })
-        .lock()
+static EVENT_HUB: Storage<EventHub> = Storage::new();
+
+pub(crate) fn get() -> &'static EventHub {
+    EVENT_HUB.get_or_set(EventHub::default)
+}
+
+/// How queued events of the same [`EventType`] are treated if a subscriber can't keep up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coalescing {
+    /// Only the latest event is of interest, e.g. a price update. A queued event is replaced by
+    /// a newer event of the same type.
+    LatestWins,
+    /// Events may be lost under load, e.g. log lines. If the queue is full, the oldest event of
+    /// this kind is dropped.
+    DropOldest,
+    /// Every event must be delivered, e.g. trade events. These events are never dropped, even if
+    /// the queue is full.
+    None,
+}
+
+impl EventType {
+    pub fn coalescing(&self) -> Coalescing {
+        match self {
+            EventType::AskPriceUpdateNotification
+            | EventType::BidPriceUpdateNotification
+            | EventType::WalletInfoUpdateNotification
+            | EventType::NextFundingRate
+            | EventType::StorageWarning
+            | EventType::KillSwitchUpdate
+            | EventType::UpdateRequired
+            | EventType::StartupPhase => Coalescing::LatestWins,
+            EventType::Log => Coalescing::DropOldest,
+            _ => Coalescing::None,
+        }
+    }
+}
+
+/// The delivery statistics of a single subscriber.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeliveryMetrics {
+    pub subscriber: String,
+    /// Events currently waiting to be delivered.
+    pub queued: usize,
+    pub published: u64,
+    pub delivered: u64,
+    /// Events which were replaced by a newer event of the same type before being delivered.
+    pub coalesced: u64,
+    /// Events which were dropped because the queue was full.
+    pub dropped: u64,
+    /// Events which had to be queued even though the queue was full, because they can't be
+    /// dropped.
+    pub overflowed: u64,
+    /// The largest number of events which were queued at the same time.
+    pub max_queued: usize,
 }
 
+#[derive(Default)]
 pub struct EventHub {
-    subscribers: HashMap<EventType, Vec<Box<dyn Subscriber + 'static + Send + Sync>>>,
+    subscriptions: RwLock<Vec<Arc<Subscription>>>,
 }
 
 impl EventHub {
     /// Subscribes the subscriber to the events registered through the filter implementation. Note,
     /// that the filter hook will only be called once during the subscribe function and is not
     /// considered anymore when publishing.
-    pub fn subscribe(&mut self, subscriber: impl Subscriber + 'static + Send + Sync + Clone) {
-        for event_type in subscriber.events() {
-            match self.subscribers.entry(event_type) {
-                Entry::Vacant(e) => {
-                    e.insert(vec![Box::new(subscriber.clone())]);
-                }
-                Entry::Occupied(mut e) => {
-                    e.get_mut().push(Box::new(subscriber.clone()));
+    pub fn subscribe(&self, subscriber: impl Subscriber + 'static + Send + Sync) {
+        let events = subscriber.events();
+        self.subscribe_to(subscriber, events);
+    }
+
+    /// Subscribes the subscriber to the given events only.
+    pub fn subscribe_to(
+        &self,
+        subscriber: impl Subscriber + 'static + Send + Sync,
+        events: Vec<EventType>,
+    ) {
+        let subscription = Arc::new(Subscription::new(
+            subscriber.name(),
+            events.into_iter().collect(),
+            QUEUE_CAPACITY,
+        ));
+
+        let result = thread::Builder::new()
+            .name("event-delivery".to_string())
+            .spawn({
+                let subscription = subscription.clone();
+                move || subscription.deliver(subscriber)
+            });
+
+        if let Err(e) = result {
+            tracing::error!(
+                subscriber = subscription.name,
+                "Failed to spawn event delivery thread: {e:#}"
+            );
+            return;
+        }
+
+        let mut subscriptions = self.subscriptions.write();
+        subscriptions.retain(|subscription| !subscription.is_closed());
+        subscriptions.push(subscription);
+    }
+
+    /// Queues the given event for all subscribers. Publishing never blocks on a subscriber.
+    pub fn publish(&self, event: &EventInternal) {
+        let event_type = EventType::from(event.clone());
+
+        for subscription in self.subscriptions.read().iter() {
+            if subscription.events.contains(&event_type) {
+                subscription.enqueue(event_type, event);
+            }
+        }
+    }
+
+    pub fn delivery_metrics(&self) -> Vec<DeliveryMetrics> {
+        self.subscriptions
+            .read()
+            .iter()
+            .map(|subscription| subscription.metrics())
+            .collect()
+    }
+}
+
+struct Subscription {
+    name: &'static str,
+    events: HashSet<EventType>,
+    queue: Mutex<Queue>,
+    available: Condvar,
+}
+
+impl Subscription {
+    fn new(name: &'static str, events: HashSet<EventType>, capacity: usize) -> Self {
+        Self {
+            name,
+            events,
+            queue: Mutex::new(Queue::new(capacity)),
+            available: Condvar::new(),
+        }
+    }
+
+    fn enqueue(&self, event_type: EventType, event: &EventInternal) {
+        let (result, queued) = {
+            let mut queue = self.queue.lock();
+            if queue.closed {
+                return;
+            }
+
+            let result = queue.push(event_type, event.clone());
+            (result, queue.events.len())
+        };
+
+        self.available.notify_one();
+
+        if result == Push::Overflowed {
+            tracing::warn!(
+                subscriber = self.name,
+                %event,
+                queued,
+                "Subscriber can't keep up with events"
+            );
+        }
+    }
+
+    /// Delivers the queued events to the subscriber until it is closed.
+    fn deliver(&self, subscriber: impl Subscriber) {
+        loop {
+            let event = {
+                let mut queue = self.queue.lock();
+                loop {
+                    if let Some((_, event)) = queue.events.pop_front() {
+                        break event;
+                    }
+                    self.available.wait(&mut queue);
                 }
+            };
+
+            subscriber.notify(&event);
+
+            let mut queue = self.queue.lock();
+            queue.metrics.delivered += 1;
+
+            if subscriber.is_closed() {
+                tracing::debug!(subscriber = self.name, "Unsubscribing closed subscriber");
+                queue.closed = true;
+                queue.events.clear();
+                return;
             }
         }
     }
 
-    /// Publishes the given event to all subscribers. Note, that this will be executed in a loop.
-    pub fn publish(&self, event: &EventInternal) {
-        if let Some(subscribers) = self.subscribers.get(&EventType::from(event.clone())) {
-            for subscriber in subscribers {
-                // todo: we should tokio spawn here.
-                subscriber.notify(event);
+    fn is_closed(&self) -> bool {
+        self.queue.lock().closed
+    }
+
+    fn metrics(&self) -> DeliveryMetrics {
+        let queue = self.queue.lock();
+
+        DeliveryMetrics {
+            subscriber: self.name.to_string(),
+            queued: queue.events.len(),
+            ..queue.metrics.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Push {
+    Queued,
+    Coalesced,
+    Dropped,
+    Overflowed,
+}
+
+struct Queue {
+    events: VecDeque<(EventType, EventInternal)>,
+    capacity: usize,
+    closed: bool,
+    metrics: DeliveryMetrics,
+}
+
+impl Queue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            capacity,
+            closed: false,
+            metrics: DeliveryMetrics::default(),
+        }
+    }
+
+    fn push(&mut self, event_type: EventType, event: EventInternal) -> Push {
+        self.metrics.published += 1;
+
+        let coalescing = event_type.coalescing();
+
+        if coalescing == Coalescing::LatestWins {
+            if let Some(queued) = self.events.iter_mut().find(|(t, _)| *t == event_type) {
+                queued.1 = event;
+                self.metrics.coalesced += 1;
+                return Push::Coalesced;
+            }
+        }
+
+        let mut result = Push::Queued;
+        if self.events.len() >= self.capacity {
+            match coalescing {
+                Coalescing::DropOldest => {
+                    match self.events.iter().position(|(t, _)| *t == event_type) {
+                        Some(index) => {
+                            self.events.remove(index);
+                            self.metrics.dropped += 1;
+                        }
+                        None => {
+                            self.metrics.dropped += 1;
+                            return Push::Dropped;
+                        }
+                    }
+                }
+                Coalescing::LatestWins | Coalescing::None => {
+                    self.metrics.overflowed += 1;
+                    result = Push::Overflowed;
+                }
             }
         }
+
+        self.events.push_back((event_type, event));
+        self.metrics.max_queued = self.metrics.max_queued.max(self.events.len());
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::time::Duration;
+    use std::time::Instant;
+    use xxi_node::commons::ContractSymbol;
+
+    #[test]
+    fn latest_price_wins() {
+        let mut queue = Queue::new(10);
+
+        queue.push(
+            EventType::AskPriceUpdateNotification,
+            EventInternal::AskPriceUpdateNotification(Decimal::ONE),
+        );
+        queue.push(
+            EventType::BidPriceUpdateNotification,
+            EventInternal::BidPriceUpdateNotification(Decimal::ONE),
+        );
+        let result = queue.push(
+            EventType::AskPriceUpdateNotification,
+            EventInternal::AskPriceUpdateNotification(Decimal::TWO),
+        );
+
+        assert_eq!(result, Push::Coalesced);
+        assert_eq!(queue.events.len(), 2);
+        assert!(matches!(
+            queue.events[0].1,
+            EventInternal::AskPriceUpdateNotification(price) if price == Decimal::TWO
+        ));
+        assert_eq!(queue.metrics.coalesced, 1);
+    }
+
+    #[test]
+    fn oldest_log_is_dropped_if_full() {
+        let mut queue = Queue::new(2);
+
+        queue.push(EventType::Log, EventInternal::Log("first".to_string()));
+        queue.push(EventType::Log, EventInternal::Log("second".to_string()));
+        queue.push(EventType::Log, EventInternal::Log("third".to_string()));
+
+        let logs = queue
+            .events
+            .iter()
+            .map(|(_, event)| match event {
+                EventInternal::Log(log) => log.as_str(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(logs, vec!["second", "third"]);
+        assert_eq!(queue.metrics.dropped, 1);
+    }
+
+    #[test]
+    fn trade_events_are_never_dropped() {
+        let mut queue = Queue::new(1);
+
+        queue.push(EventType::Log, EventInternal::Log("log".to_string()));
+        let result = queue.push(
+            EventType::PositionClosedNotification,
+            EventInternal::PositionCloseNotification(ContractSymbol::BtcUsd),
+        );
+        let log = queue.push(EventType::Log, EventInternal::Log("dropped".to_string()));
+
+        assert_eq!(result, Push::Overflowed);
+        assert_eq!(log, Push::Dropped);
+        assert_eq!(queue.events.len(), 2);
+        assert_eq!(queue.metrics.overflowed, 1);
+        assert_eq!(queue.metrics.max_queued, 2);
+    }
+
+    #[test]
+    fn subscriber_only_receives_filtered_events() {
+        let hub = EventHub::default();
+        let subscriber = TestSubscriber::default();
+
+        hub.subscribe_to(subscriber.clone(), vec![EventType::Log]);
+
+        hub.publish(&EventInternal::Init("init".to_string()));
+        hub.publish(&EventInternal::Log("log".to_string()));
+
+        let start = Instant::now();
+        while hub.delivery_metrics()[0].delivered < 1 && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let received = subscriber.received.lock().clone();
+        assert_eq!(received, vec!["Log".to_string()]);
+
+        let metrics = &hub.delivery_metrics()[0];
+        assert_eq!(metrics.published, 1);
+        assert_eq!(metrics.delivered, 1);
+    }
+
+    #[derive(Clone, Default)]
+    struct TestSubscriber {
+        received: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Subscriber for TestSubscriber {
+        fn notify(&self, event: &EventInternal) {
+            self.received.lock().push(event.to_string());
+        }
+
+        fn events(&self) -> Vec<EventType> {
+            vec![EventType::Init, EventType::Log]
+        }
     }
 }
@@ -2,7 +2,12 @@ use crate::dlc::DlcChannel;
 use crate::event::api::WalletInfo;
 use crate::event::event_hub::get;
 use crate::event::subscriber::Subscriber;
+use crate::health::CoordinatorAvailability;
 use crate::health::ServiceUpdate;
+use crate::reserve_top_up::ReserveTopUpUpdate;
+use crate::startup::StartupPhase;
+use crate::storage_monitor::StorageUsage;
+use crate::trade::intent::Intent;
 use crate::trade::order::Order;
 use crate::trade::position::Position;
 use crate::trade::FundingFeeEvent;
@@ -10,19 +15,46 @@ use crate::trade::Trade;
 use rust_decimal::Decimal;
 use std::fmt;
 use std::hash::Hash;
+use uuid::Uuid;
 use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::FundingRate;
+use xxi_node::commons::KillSwitchStatus;
 use xxi_node::commons::TenTenOneConfig;
+use xxi_node::Deposit;
+use xxi_node::WalletDescriptors;
+use xxi_node::WatchEvent;
 
 mod event_hub;
 
 pub mod api;
 pub mod subscriber;
 
-pub fn subscribe(subscriber: impl Subscriber + 'static + Send + Sync + Clone) {
+pub use event_hub::Coalescing;
+pub use event_hub::DeliveryMetrics;
+
+pub fn subscribe(subscriber: impl Subscriber + 'static + Send + Sync) {
     get().subscribe(subscriber);
 }
 
+/// Subscribes the subscriber only to those of its events which are part of the `filter`.
+pub fn subscribe_filtered(
+    subscriber: impl Subscriber + 'static + Send + Sync,
+    filter: Vec<EventType>,
+) {
+    let events = subscriber
+        .events()
+        .into_iter()
+        .filter(|event_type| filter.contains(event_type))
+        .collect();
+
+    get().subscribe_to(subscriber, events);
+}
+
+/// The delivery statistics of all subscribers.
+pub fn delivery_metrics() -> Vec<DeliveryMetrics> {
+    get().delivery_metrics()
+}
+
 pub fn publish(event: &EventInternal) {
     get().publish(event);
 }
@@ -38,15 +70,55 @@ pub enum EventInternal {
     AskPriceUpdateNotification(Decimal),
     BidPriceUpdateNotification(Decimal),
     ServiceHealthUpdate(ServiceUpdate),
+    /// The primary coordinator became unreachable or reachable again, or a fallback coordinator
+    /// took over the read-only requests.
+    CoordinatorAvailabilityUpdate(CoordinatorAvailability),
     Authenticated(TenTenOneConfig),
     BackgroundNotification(BackgroundTask),
     SpendableOutputs,
     DlcChannelEvent(DlcChannel),
     FundingChannelNotification(FundingChannelTask),
-    LnPaymentReceived { r_hash: String },
+    LnPaymentReceived {
+        r_hash: String,
+    },
     NewTrade(Trade),
     FundingFeeEvent(FundingFeeEvent),
     NextFundingRate(FundingRate),
+    StorageWarning(StorageUsage),
+    OrderTemplatesUpdated,
+    KillSwitchUpdate(KillSwitchStatus),
+    /// Our DLC channel is being force-closed on-chain.
+    ///
+    /// The closing transaction is unknown if the app has not seen it on-chain yet.
+    ChannelClosingOnChain {
+        closing_txid: Option<String>,
+    },
+    StartupPhase(StartupPhase),
+    /// The status of an on-chain deposit changed.
+    DepositUpdate(Deposit),
+    /// A watched transaction, or a transaction paying to a watched address, was seen or changed
+    /// its status.
+    WatchUpdate(WatchEvent),
+    /// A support ticket was created for a failed trade.
+    SupportTicketCreated {
+        order_id: Option<Uuid>,
+        reference: String,
+    },
+    /// The coordinator no longer talks to this version of the app.
+    UpdateRequired {
+        version: u16,
+        min_version: u16,
+    },
+    /// An intent was queued while offline, or was submitted, expired or failed once back online.
+    IntentUpdate(Intent),
+    /// The restored wallet derives different descriptors than the backed up wallet. The addresses
+    /// of the backed up descriptors are watched, so that their funds can be moved into the wallet.
+    WalletDescriptorMigration {
+        backed_up: WalletDescriptors,
+        derived: WalletDescriptors,
+    },
+    /// The collateral reserve is being topped up automatically.
+    ReserveTopUpUpdate(ReserveTopUpUpdate),
 }
 
 #[derive(Clone, Debug)]
@@ -85,6 +157,7 @@ impl fmt::Display for EventInternal {
             EventInternal::PositionUpdateNotification(_) => "PositionUpdateNotification",
             EventInternal::PositionCloseNotification(_) => "PositionCloseNotification",
             EventInternal::ServiceHealthUpdate(_) => "ServiceHealthUpdate",
+            EventInternal::CoordinatorAvailabilityUpdate(_) => "CoordinatorAvailabilityUpdate",
             EventInternal::BackgroundNotification(_) => "BackgroundNotification",
             EventInternal::SpendableOutputs => "SpendableOutputs",
             EventInternal::Authenticated(_) => "Authenticated",
@@ -96,6 +169,18 @@ impl fmt::Display for EventInternal {
             EventInternal::NewTrade(_) => "NewTrade",
             EventInternal::FundingFeeEvent(_) => "FundingFeeEvent",
             EventInternal::NextFundingRate(_) => "NextFundingRate",
+            EventInternal::StorageWarning(_) => "StorageWarning",
+            EventInternal::OrderTemplatesUpdated => "OrderTemplatesUpdated",
+            EventInternal::KillSwitchUpdate(_) => "KillSwitchUpdate",
+            EventInternal::ChannelClosingOnChain { .. } => "ChannelClosingOnChain",
+            EventInternal::StartupPhase(_) => "StartupPhase",
+            EventInternal::DepositUpdate(_) => "DepositUpdate",
+            EventInternal::WatchUpdate(_) => "WatchUpdate",
+            EventInternal::SupportTicketCreated { .. } => "SupportTicketCreated",
+            EventInternal::UpdateRequired { .. } => "UpdateRequired",
+            EventInternal::IntentUpdate(_) => "IntentUpdate",
+            EventInternal::WalletDescriptorMigration { .. } => "WalletDescriptorMigration",
+            EventInternal::ReserveTopUpUpdate(_) => "ReserveTopUpUpdate",
         }
         .fmt(f)
     }
@@ -113,6 +198,9 @@ impl From<EventInternal> for EventType {
             EventInternal::PositionUpdateNotification(_) => EventType::PositionUpdateNotification,
             EventInternal::PositionCloseNotification(_) => EventType::PositionClosedNotification,
             EventInternal::ServiceHealthUpdate(_) => EventType::ServiceHealthUpdate,
+            EventInternal::CoordinatorAvailabilityUpdate(_) => {
+                EventType::CoordinatorAvailabilityUpdate
+            }
             EventInternal::BackgroundNotification(_) => EventType::BackgroundNotification,
             EventInternal::SpendableOutputs => EventType::SpendableOutputs,
             EventInternal::Authenticated(_) => EventType::Authenticated,
@@ -124,11 +212,23 @@ impl From<EventInternal> for EventType {
             EventInternal::NewTrade(_) => EventType::NewTrade,
             EventInternal::FundingFeeEvent(_) => EventType::NewTrade,
             EventInternal::NextFundingRate(_) => EventType::NextFundingRate,
+            EventInternal::StorageWarning(_) => EventType::StorageWarning,
+            EventInternal::OrderTemplatesUpdated => EventType::OrderTemplatesUpdated,
+            EventInternal::KillSwitchUpdate(_) => EventType::KillSwitchUpdate,
+            EventInternal::ChannelClosingOnChain { .. } => EventType::ChannelClosingOnChain,
+            EventInternal::StartupPhase(_) => EventType::StartupPhase,
+            EventInternal::DepositUpdate(_) => EventType::DepositUpdate,
+            EventInternal::WatchUpdate(_) => EventType::WatchUpdate,
+            EventInternal::SupportTicketCreated { .. } => EventType::SupportTicketCreated,
+            EventInternal::UpdateRequired { .. } => EventType::UpdateRequired,
+            EventInternal::IntentUpdate(_) => EventType::IntentUpdate,
+            EventInternal::WalletDescriptorMigration { .. } => EventType::WalletDescriptorMigration,
+            EventInternal::ReserveTopUpUpdate(_) => EventType::ReserveTopUpUpdate,
         }
     }
 }
 
-#[derive(Copy, Clone, Eq, Hash, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum EventType {
     Init,
     Log,
@@ -140,6 +240,7 @@ pub enum EventType {
     ChannelReady,
     LnPaymentReceived,
     ServiceHealthUpdate,
+    CoordinatorAvailabilityUpdate,
     ChannelStatusUpdate,
     BackgroundNotification,
     SpendableOutputs,
@@ -150,4 +251,16 @@ pub enum EventType {
     FundingChannelNotification,
     NewTrade,
     NextFundingRate,
+    StorageWarning,
+    OrderTemplatesUpdated,
+    KillSwitchUpdate,
+    ChannelClosingOnChain,
+    StartupPhase,
+    DepositUpdate,
+    WatchUpdate,
+    SupportTicketCreated,
+    UpdateRequired,
+    IntentUpdate,
+    WalletDescriptorMigration,
+    ReserveTopUpUpdate,
 }
@@ -2,10 +2,26 @@ use crate::event::EventInternal;
 use crate::event::EventType;
 
 pub trait Subscriber {
-    /// Notifies the subcriber about an event. If false is returned the subscriber will be
-    /// unsubscribed, if true the subscriber will remain subscribed.
+    /// Notifies the subscriber about an event.
+    ///
+    /// Events are delivered from a dedicated thread per subscriber, in the order they were
+    /// published, unless they were coalesced while the subscriber was busy.
     fn notify(&self, event: &EventInternal);
 
     /// Returns a list of events the subscriber wants to subscribe to.
     fn events(&self) -> Vec<EventType>;
+
+    /// Whether the subscriber does not accept events anymore, e.g. because the stream it forwards
+    /// events to was closed. A closed subscriber is unsubscribed from the event hub.
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    /// The name of the subscriber, used to report its delivery metrics.
+    fn name(&self) -> &'static str
+    where
+        Self: Sized,
+    {
+        std::any::type_name::<Self>()
+    }
 }
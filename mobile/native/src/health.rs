@@ -1,11 +1,13 @@
 use crate::config;
 use crate::event;
 use crate::event::EventInternal;
+use crate::orderbook;
 use anyhow::Context;
 use anyhow::Result;
 use futures::future::RemoteHandle;
 use futures::FutureExt;
 use reqwest::StatusCode;
+use std::net::SocketAddr;
 use std::time::Duration;
 use tokio::runtime::Runtime;
 use tokio::sync::watch;
@@ -26,6 +28,23 @@ pub enum ServiceStatus {
     Offline,
 }
 
+/// Which coordinators the app can reach.
+///
+/// Only the primary coordinator holds the DLC channel. The fallback coordinators only serve
+/// read-only requests, e.g. for the prices.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinatorAvailability {
+    #[default]
+    Unknown,
+    /// The primary coordinator is reachable.
+    Primary,
+    /// Only a fallback coordinator is reachable. The prices are still live, but trading and
+    /// everything else involving the DLC channel has to wait for the primary coordinator.
+    FallbackOnly,
+    /// No coordinator is reachable.
+    Offline,
+}
+
 #[derive(Debug, Clone)]
 pub struct ServiceUpdate {
     pub service: Service,
@@ -66,8 +85,9 @@ impl Health {
         let (coordinator_tx, coordinator_rx) = watch::channel(ServiceStatus::Unknown);
 
         let check_coordinator = runtime
-            .spawn(check_health_endpoint(
-                config::coordinator_health_endpoint(),
+            .spawn(check_coordinators(
+                config::get_http_endpoint(),
+                config::get_fallback_http_endpoints(),
                 coordinator_tx,
                 config::health_check_interval(),
             ))
@@ -111,24 +131,78 @@ async fn publish_status_updates(service: Service, mut rx: watch::Receiver<Servic
     }
 }
 
-/// Periodically checks the health of a given service and updates the watch channel
-async fn check_health_endpoint(
-    endpoint: String,
+/// Periodically checks the health of the primary coordinator and updates the watch channel.
+///
+/// While the primary coordinator is unreachable, the prices are fetched from the first reachable
+/// fallback coordinator.
+async fn check_coordinators(
+    primary: SocketAddr,
+    fallbacks: Vec<SocketAddr>,
     tx: watch::Sender<ServiceStatus>,
     interval: Duration,
 ) {
+    let mut availability = CoordinatorAvailability::Unknown;
+
     loop {
-        let status = if send_request(&endpoint).await.is_ok() {
+        let primary_online = send_request(&config::coordinator_health_endpoint(primary))
+            .await
+            .is_ok();
+
+        let status = if primary_online {
             ServiceStatus::Online
         } else {
             ServiceStatus::Offline
         };
-
         tx.send(status).expect("Receiver not to be dropped");
+
+        let fallback = if primary_online {
+            None
+        } else {
+            first_reachable(&fallbacks).await
+        };
+
+        let new_availability = match (primary_online, fallback) {
+            (true, _) => CoordinatorAvailability::Primary,
+            (false, Some(_)) => CoordinatorAvailability::FallbackOnly,
+            (false, None) => CoordinatorAvailability::Offline,
+        };
+        if new_availability != availability {
+            tracing::info!(
+                ?availability,
+                ?new_availability,
+                ?fallback,
+                "Coordinator availability changed"
+            );
+
+            event::publish(&EventInternal::CoordinatorAvailabilityUpdate(
+                new_availability,
+            ));
+            availability = new_availability;
+        }
+
+        if let Some(fallback) = fallback {
+            if let Err(e) = orderbook::update_prices_from_fallback(fallback).await {
+                tracing::warn!(%fallback, "Failed to update prices from fallback coordinator: {e:#}");
+            }
+        }
+
         tokio::time::sleep(interval).await;
     }
 }
 
+async fn first_reachable(coordinators: &[SocketAddr]) -> Option<SocketAddr> {
+    for coordinator in coordinators {
+        if send_request(&config::coordinator_health_endpoint(*coordinator))
+            .await
+            .is_ok()
+        {
+            return Some(*coordinator);
+        }
+    }
+
+    None
+}
+
 // Returns the status code of the health endpoint, returning an error if the request fails
 async fn send_request(endpoint: &str) -> Result<StatusCode> {
     tracing::trace!(%endpoint, "Sending request");
@@ -22,7 +22,7 @@ pub async fn get_hodl_invoice_from_coordinator(amount: Amount) -> Result<HodlInv
     let client = reqwest_client();
     let url = format!("http://{}", config::get_http_endpoint());
     let url = Url::parse(&url).expect("correct URL");
-    let url = url.join("/api/invoice")?;
+    let url = url.join("/api/v2/invoice")?;
 
     let invoice_params = commons::HodlInvoiceParams {
         trader_pubkey: get_node_pubkey(),
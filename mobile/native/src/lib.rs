@@ -14,7 +14,10 @@ pub mod state;
 pub mod trade;
 pub mod watcher;
 
+mod authorization;
 mod backup;
+mod bootstrap;
+mod channel_close_estimate;
 mod cipher;
 mod destination;
 mod dlc_channel;
@@ -24,7 +27,13 @@ mod names;
 mod orderbook;
 mod polls;
 mod report_error;
+mod reserve_top_up;
+mod session;
+mod startup;
 mod storage;
+mod storage_monitor;
+mod support_ticket;
+mod wallet_descriptors;
 
 pub use dlc::get_maintenance_margin_rate;
 pub use report_error::report_error_to_coordinator;
@@ -37,5 +46,6 @@ pub use report_error::report_error_to_coordinator;
 )]
 mod bridge_generated;
 mod hodl_invoice;
+mod lightning_withdrawal;
 mod position;
 mod unfunded_channel_opening_order;
@@ -0,0 +1,42 @@
+use crate::commons::reqwest_client;
+use crate::config;
+use crate::dlc::get_node_key;
+use crate::dlc::get_node_pubkey;
+use anyhow::anyhow;
+use anyhow::Result;
+use reqwest::Url;
+use xxi_node::commons;
+
+/// Ask the coordinator to pay `invoice` from our collateral reserve.
+///
+/// The coordinator debits our collateral reserve with a renew of the DLC channel, which we
+/// accept like a rollover.
+pub async fn withdraw(invoice: String) -> Result<()> {
+    let client = reqwest_client();
+    let url = format!("http://{}", config::get_http_endpoint());
+    let url = Url::parse(&url).expect("correct URL");
+    let url = url.join("/api/v2/withdraw/lightning")?;
+
+    let params = commons::LightningWithdrawalParams {
+        trader_pubkey: get_node_pubkey(),
+        invoice,
+    };
+    let params = commons::SignedValue::new(params, get_node_key())?;
+
+    let response = client.post(url).json(&params).send().await?;
+
+    let status_code = response.status();
+    if !status_code.is_success() {
+        let response_text = match response.text().await {
+            Ok(text) => text,
+            Err(err) => {
+                format!("could not decode response {err:#}")
+            }
+        };
+        return Err(anyhow!(
+            "Could not withdraw via Lightning: HTTP${status_code}: {response_text}"
+        ));
+    }
+
+    Ok(())
+}
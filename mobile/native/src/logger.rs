@@ -1,7 +1,15 @@
 use anyhow::Context;
 use anyhow::Result;
 use flutter_rust_bridge::StreamSink;
+use parking_lot::Mutex;
 use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Once;
 use tracing_log::LogTracer;
@@ -9,6 +17,7 @@ use tracing_subscriber::filter::Directive;
 use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::fmt::time;
 use tracing_subscriber::fmt::time::UtcTime;
+use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
@@ -17,6 +26,15 @@ use tracing_subscriber::Layer;
 const RUST_LOG_ENV: &str = "RUST_LOG";
 static INIT_LOGGER_ONCE: Once = Once::new();
 
+const LOG_FILE_NAME: &str = "native.log";
+/// The size at which the log file is rotated.
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+/// The number of rotated log files kept next to the current log file.
+const MAX_ROTATED_LOG_FILES: usize = 3;
+
+/// The log file, once the log directory is known.
+static LOG_FILE: Mutex<Option<RotatingLogFile>> = parking_lot::const_mutex(None);
+
 // Tracing log directives config
 pub fn log_base_directives(env: EnvFilter, level: LevelFilter) -> Result<EnvFilter> {
     let filter = env
@@ -95,6 +113,147 @@ where
     }
 }
 
+/// Start writing logs into a size-capped, rotating log file in the given directory.
+///
+/// Logs are only written to stderr and Flutter until this is called, because the log directory
+/// is only known once the app config has been set.
+pub fn init_log_file(log_dir: impl AsRef<Path>) -> Result<()> {
+    let log_file =
+        RotatingLogFile::open(log_dir.as_ref(), MAX_LOG_FILE_SIZE, MAX_ROTATED_LOG_FILES)?;
+
+    *LOG_FILE.lock() = Some(log_file);
+
+    Ok(())
+}
+
+/// Delete all rotated log files, keeping only the current one.
+///
+/// Returns the number of bytes freed.
+pub fn prune_log_files() -> Result<u64> {
+    match LOG_FILE.lock().as_ref() {
+        Some(log_file) => log_file.prune(),
+        None => Ok(0),
+    }
+}
+
+/// Writes into the rotating log file, if it has been initialised.
+struct LogFileWriter;
+
+impl<'a> MakeWriter<'a> for LogFileWriter {
+    type Writer = LogFileWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogFileWriter
+    }
+}
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Note, we must not log in here, as we are holding the lock of the log file.
+        match LOG_FILE.lock().as_mut() {
+            Some(log_file) => log_file.write(buf),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match LOG_FILE.lock().as_mut() {
+            Some(log_file) => log_file.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A log file which is moved to `<name>.1` once it exceeds `max_size`, shifting the previously
+/// rotated files by one and deleting the oldest one.
+struct RotatingLogFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_rotated_files: usize,
+}
+
+impl RotatingLogFile {
+    fn open(dir: &Path, max_size: u64, max_rotated_files: usize) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create log dir {}", dir.display()))?;
+
+        let file = open_log_file(dir)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+            max_size,
+            max_rotated_files,
+        })
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+
+        Ok(written)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        let oldest = self.rotated_path(self.max_rotated_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+
+        for index in (1..self.max_rotated_files).rev() {
+            let path = self.rotated_path(index);
+            if path.exists() {
+                fs::rename(path, self.rotated_path(index + 1))?;
+            }
+        }
+
+        if self.max_rotated_files > 0 {
+            fs::rename(self.dir.join(LOG_FILE_NAME), self.rotated_path(1))?;
+        } else {
+            fs::remove_file(self.dir.join(LOG_FILE_NAME))?;
+        }
+
+        self.file = open_log_file(&self.dir)?;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    fn prune(&self) -> Result<u64> {
+        let mut freed = 0;
+        for index in 1..=self.max_rotated_files {
+            let path = self.rotated_path(index);
+            if let Ok(metadata) = fs::metadata(&path) {
+                fs::remove_file(&path)?;
+                freed += metadata.len();
+            }
+        }
+
+        Ok(freed)
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{LOG_FILE_NAME}.{index}"))
+    }
+}
+
+fn open_log_file(dir: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE_NAME))
+}
+
 struct Visitor<'a>(&'a mut BTreeMap<String, String>);
 
 impl<'a> tracing::field::Visit for Visitor<'a> {
@@ -162,10 +321,15 @@ pub fn init_tracing(level: LevelFilter, json_format: bool) -> Result<()> {
         fmt_layer.with_timer(time::UtcTime::rfc_3339()).boxed()
     };
 
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_writer(LogFileWriter)
+        .with_timer(UtcTime::rfc_3339());
+
     tracing_subscriber::registry()
         .with(filter)
         .with(DartSendLayer)
         .with(fmt_layer)
+        .with(file_layer)
         .try_init()
         .context("Failed to init tracing")?;
 
@@ -175,3 +339,40 @@ pub fn init_tracing(level: LevelFilter, json_format: bool) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_file_is_rotated_once_full() {
+        let dir = std::env::temp_dir().join(format!("10101-logs-{}", uuid::Uuid::new_v4()));
+        let mut log_file = RotatingLogFile::open(&dir, 10, 2).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log_file.write(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(
+            fs::read_to_string(dir.join(LOG_FILE_NAME)).unwrap(),
+            "fourth\n"
+        );
+        assert_eq!(
+            fs::read_to_string(log_file.rotated_path(1)).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(log_file.rotated_path(2)).unwrap(),
+            "second\n"
+        );
+        assert!(!log_file.rotated_path(3).exists());
+
+        let freed = log_file.prune().unwrap();
+
+        assert_eq!(freed, 13);
+        assert!(!log_file.rotated_path(1).exists());
+        assert!(dir.join(LOG_FILE_NAME).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
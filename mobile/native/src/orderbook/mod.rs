@@ -1,3 +1,4 @@
+use crate::commons::reqwest_client;
 use crate::config;
 use crate::dlc;
 use crate::event;
@@ -5,9 +6,12 @@ use crate::event::BackgroundTask;
 use crate::event::EventInternal;
 use crate::event::TaskStatus;
 use crate::health::ServiceStatus;
+use crate::session;
 use crate::state;
+use crate::support_ticket;
 use crate::trade::funding_fee_event;
 use crate::trade::funding_fee_event::FundingFeeEvent;
+use crate::trade::liquidity;
 use crate::trade::order;
 use crate::trade::order::FailureReason;
 use crate::trade::position;
@@ -19,8 +23,9 @@ use futures::SinkExt;
 use futures::TryStreamExt;
 use itertools::Itertools;
 use parking_lot::Mutex;
-use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Runtime;
@@ -28,19 +33,28 @@ use tokio::sync::broadcast;
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::watch;
 use tokio_tungstenite_wasm as tungstenite;
-use xxi_node::commons::best_ask_price;
-use xxi_node::commons::best_bid_price;
-use xxi_node::commons::ContractSymbol;
-use xxi_node::commons::Direction;
+use uuid::Uuid;
 use xxi_node::commons::Message;
 use xxi_node::commons::Order;
 use xxi_node::commons::OrderState;
 use xxi_node::commons::OrderbookRequest;
+use xxi_node::commons::PageParams;
 use xxi_node::commons::Signature;
+use xxi_node::commons::TenTenOneConfig;
+use xxi_node::commons::NEXT_CURSOR_HEADER;
+
+pub mod price_feed;
 
 // Set to the same timeout as the p2p connection reconnect
 const WS_RECONNECT_TIMEOUT: Duration = Duration::from_secs(1);
 
+/// Whether the app is already maintaining its orderbook connection.
+static SUBSCRIBED: AtomicBool = AtomicBool::new(false);
+
+/// Maintain the single connection of the app to the orderbook.
+///
+/// Prices received from the orderbook are distributed to all subsystems through the
+/// [`price_feed`]. Calling this function again does not open a second connection.
 pub fn subscribe(
     secret_key: SecretKey,
     runtime: &Runtime,
@@ -48,9 +62,16 @@ pub fn subscribe(
     fcm_token: String,
     tx_websocket: broadcast::Sender<OrderbookRequest>,
 ) -> Result<()> {
+    if SUBSCRIBED.swap(true, Ordering::SeqCst) {
+        tracing::warn!("Already subscribed to the orderbook, not opening another connection");
+        return Ok(());
+    }
+
+    price_feed::spawn_ui_forwarder(runtime);
+
     runtime.spawn(async move {
         let url = format!(
-            "ws://{}/api/orderbook/websocket",
+            "ws://{}/api/v2/orderbook/websocket",
             config::get_http_endpoint()
         );
 
@@ -74,7 +95,8 @@ pub fn subscribe(
             let fcm_token = fcm_token.clone();
             let version = env!("CARGO_PKG_VERSION").to_string();
             let os = std::env::consts::OS.to_string();
-            match orderbook_client::subscribe_with_authentication(url, authenticate, fcm_token, Some(version), Some(os))
+            let client_info = state::get_client_info();
+            match orderbook_client::subscribe_with_authentication(url, authenticate, fcm_token, Some(version), Some(os), Some(client_info), false)
                 .await
             {
                 Ok((mut sink, mut stream)) => {
@@ -108,7 +130,6 @@ pub fn subscribe(
                         }
                     });
 
-                    let mut cached_best_price: HashMap<Direction, Decimal> = HashMap::new();
                     loop {
                         let msg = match stream.try_next().await {
                             Ok(Some(msg)) => msg,
@@ -122,10 +143,7 @@ pub fn subscribe(
                             }
                         };
 
-                        if let Err(e) =
-                            handle_orderbook_message(orders.clone(), &mut cached_best_price, msg)
-                                .await
-                        {
+                        if let Err(e) = handle_orderbook_message(orders.clone(), msg).await {
                             tracing::error!("Failed to handle event: {e:#}");
                         }
                     }
@@ -138,6 +156,8 @@ pub fn subscribe(
                 }
             };
 
+            price_feed::set_offline();
+
             if let Err(e) = orderbook_status.send(ServiceStatus::Offline) {
                 tracing::warn!("Cannot update orderbook status: {e:#}");
             };
@@ -160,11 +180,77 @@ pub fn subscribe(
     Ok(())
 }
 
-async fn handle_orderbook_message(
-    orders: Arc<Mutex<Vec<Order>>>,
-    cached_best_price: &mut HashMap<Direction, Decimal>,
-    msg: String,
+/// Apply the configuration the coordinator shares with us once we are authenticated.
+pub(crate) fn apply_config(config: TenTenOneConfig) {
+    if let Some(session_token) = config.session_token.clone() {
+        session::set_session_token(session_token);
+    }
+    state::set_kill_switch(config.kill_switch.clone());
+    event::publish(&EventInternal::KillSwitchUpdate(config.kill_switch.clone()));
+    state::set_tentenone_config(config.clone());
+    event::publish(&EventInternal::Authenticated(config));
+}
+
+/// Apply all the funding fee events the coordinator knows about for our position.
+pub(crate) fn apply_funding_fee_events(
+    funding_fee_events: Vec<xxi_node::commons::FundingFeeEvent>,
 ) -> Result<()> {
+    let funding_fee_events = funding_fee_events
+        .into_iter()
+        .map(FundingFeeEvent::from)
+        .collect_vec();
+
+    let new_funding_fee_events =
+        funding_fee_event::handler::handle_unpaid_funding_fee_events(&funding_fee_events)
+            .context("Failed to handle funding fee events from coordinator")?;
+
+    position::handler::handle_funding_fee_events(&new_funding_fee_events)
+        .context("Failed to apply all funding fee events from coordinator")?;
+
+    Ok(())
+}
+
+/// Show the prices of the given orders until the orderbook connection provides its own.
+pub(crate) fn set_initial_prices(orders: &[Order]) {
+    price_feed::initialize(orders);
+}
+
+/// Update the prices with the orderbook of a fallback coordinator, while the orderbook of the
+/// primary coordinator is unreachable.
+pub(crate) async fn update_prices_from_fallback(http_endpoint: SocketAddr) -> Result<()> {
+    let client = reqwest_client();
+
+    let mut orders = vec![];
+    let mut page = PageParams::<Uuid>::default();
+    loop {
+        let response = client
+            .get(format!("http://{http_endpoint}/api/v2/orderbook/orders"))
+            .query(&page)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let next = response
+            .headers()
+            .get(NEXT_CURSOR_HEADER)
+            .map(|cursor| anyhow::Ok(cursor.to_str()?.parse()?))
+            .transpose()
+            .context("Invalid cursor to the next page of orders")?;
+
+        orders.extend(response.json::<Vec<Order>>().await?);
+
+        match next {
+            Some(next) => page.after = Some(next),
+            None => break,
+        }
+    }
+
+    price_feed::update(&orders);
+
+    Ok(())
+}
+
+async fn handle_orderbook_message(orders: Arc<Mutex<Vec<Order>>>, msg: String) -> Result<()> {
     let msg =
         serde_json::from_str::<Message>(&msg).context("Could not deserialize orderbook message")?;
 
@@ -175,8 +261,7 @@ async fn handle_orderbook_message(
             tracing::info!(
                 referral_status = ?config.referral_status,
                 "Successfully logged in to 10101 websocket api!");
-            state::set_tentenone_config(config.clone());
-            event::publish(&EventInternal::Authenticated(config));
+            apply_config(config);
         }
         Message::AllOrders(initial_orders) => {
             let mut orders = orders.lock();
@@ -191,22 +276,14 @@ async fn handle_orderbook_message(
 
             *orders = initial_orders;
 
-            // if we receive a full set of new orders, we can clear the cached best price as it is
-            // outdated information.
-            cached_best_price.clear();
-            update_both_prices_if_needed(cached_best_price, &orders);
+            // if we receive a full set of new orders, the previous prices are outdated information.
+            price_feed::reset(&orders);
         }
         Message::NewOrder(order) => {
             let mut orders = orders.lock();
-            let direction = order.direction;
             orders.push(order);
 
-            match direction {
-                Direction::Long => update_bid_price_if_needed(cached_best_price, orders.as_slice()),
-                Direction::Short => {
-                    update_ask_price_if_needed(cached_best_price, orders.as_slice())
-                }
-            }
+            price_feed::update(&orders);
         }
         Message::DeleteOrder(order_id) => {
             let mut orders = orders.lock();
@@ -219,7 +296,9 @@ async fn handle_orderbook_message(
                 tracing::warn!(%order_id, "Could not remove non-existing order");
             }
 
-            update_both_prices_if_needed(cached_best_price, &orders);
+            price_feed::update(&orders);
+
+            liquidity::handler::on_order_deleted(order_id);
         }
         Message::Update(updated_order) => {
             let mut orders = orders.lock();
@@ -236,20 +315,10 @@ async fn handle_orderbook_message(
                 orders.push(updated_order);
             }
 
-            update_both_prices_if_needed(cached_best_price, &orders);
+            price_feed::update(&orders);
         }
         Message::AllFundingFeeEvents(funding_fee_events) => {
-            let funding_fee_events = funding_fee_events
-                .into_iter()
-                .map(FundingFeeEvent::from)
-                .collect_vec();
-
-            let new_funding_fee_events =
-                funding_fee_event::handler::handle_unpaid_funding_fee_events(&funding_fee_events)
-                    .context("Failed to handle funding fee events from coordinator")?;
-
-            position::handler::handle_funding_fee_events(&new_funding_fee_events)
-                .context("Failed to apply all funding fee events from coordinator")?;
+            apply_funding_fee_events(funding_fee_events)?;
         }
         Message::NextFundingRate(funding_rate) => {
             tracing::info!(?funding_rate, "Got next funding rate");
@@ -301,80 +370,88 @@ async fn handle_orderbook_message(
         msg @ Message::InvalidAuthentication(_) => {
             tracing::debug!(?msg, "Skipping message from orderbook");
         }
-        Message::TradeError { order_id, error } => {
+        Message::TradeError {
+            order_id,
+            error,
+            localized,
+        } => {
+            let reason = match localized {
+                Some(localized) => format!("{} {error}", localized.message),
+                None => error.to_string(),
+            };
+
+            let error_code = error.trade_error_code();
+            let error_message = error.to_string();
+
             order::handler::order_failed(
                 Some(order_id),
-                FailureReason::TradeResponse(error.to_string()),
+                FailureReason::TradeResponse(reason),
                 error.into(),
             )
             .context("Could not set order to failed")?;
+
+            if support_ticket::has_consent() {
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        support_ticket::create_for_trade_error(order_id, error_code, error_message)
+                            .await
+                    {
+                        tracing::error!(%order_id, "Failed to create support ticket: {e:#}");
+                    }
+                });
+            }
         }
-        Message::RolloverError { error } => {
+        Message::RolloverError { error, localized } => {
             tracing::error!("Failed to rollover position: {error:#}");
+
+            let reason = match localized {
+                Some(localized) => localized.message,
+                None => format!("{error:#}"),
+            };
+
+            event::publish(&EventInternal::BackgroundNotification(
+                BackgroundTask::Rollover(TaskStatus::Failed(reason)),
+            ));
+        }
+        Message::AsyncMatch { order, filled_with } => {
+            let order_reason = order.order_reason.clone();
+            let order_id = order.id;
+
+            tracing::info!(
+                %order_id,
+                "Received an async match from orderbook. Reason: {order_reason:?}"
+            );
+
             event::publish(&EventInternal::BackgroundNotification(
-                BackgroundTask::Rollover(TaskStatus::Failed(format!("{error:#}"))),
+                BackgroundTask::AsyncTrade(TaskStatus::Pending),
             ));
+
+            order::handler::async_order_filling(&order, &filled_with).with_context(|| {
+                format!("Failed to process async match update from orderbook. order_id {order_id}")
+            })?;
         }
         Message::LnPaymentReceived { r_hash, amount } => {
             tracing::info!(r_hash, %amount, "Received a payment received event.");
             event::publish(&EventInternal::LnPaymentReceived { r_hash })
         }
+        Message::KillSwitch(status) => {
+            tracing::warn!(%status, "Coordinator changed kill switch");
+            state::set_kill_switch(status.clone());
+            event::publish(&EventInternal::KillSwitchUpdate(status));
+        }
+        Message::ExpirySettlement(settlement) => {
+            tracing::info!(
+                contract_symbol = ?settlement.contract_symbol,
+                attested_price = %settlement.attested_price,
+                interval_start = settlement.interval_start,
+                interval_end = settlement.interval_end,
+                trader_payout = %settlement.trader_payout,
+                exact_trader_payout = %settlement.exact_trader_payout,
+                deviation = %settlement.deviation(),
+                "Expired position is being settled on-chain"
+            );
+        }
     };
 
     Ok(())
 }
-
-fn update_both_prices_if_needed(
-    cached_best_price: &mut HashMap<Direction, Decimal>,
-    orders: &[Order],
-) {
-    update_bid_price_if_needed(cached_best_price, orders);
-    update_ask_price_if_needed(cached_best_price, orders);
-}
-
-fn update_bid_price_if_needed(
-    cached_best_price: &mut HashMap<Direction, Decimal>,
-    orders: &[Order],
-) {
-    let bid_price = best_bid_price(orders, ContractSymbol::BtcUsd);
-
-    update_price_if_needed(cached_best_price, bid_price, Direction::Long);
-}
-
-fn update_ask_price_if_needed(
-    cached_best_price: &mut HashMap<Direction, Decimal>,
-    orders: &[Order],
-) {
-    let ask_price = best_ask_price(orders, ContractSymbol::BtcUsd);
-
-    update_price_if_needed(cached_best_price, ask_price, Direction::Short);
-}
-
-fn update_price_if_needed(
-    cached_best_price: &mut HashMap<Direction, Decimal>,
-    new_price: Option<Decimal>,
-    direction: Direction,
-) {
-    if let Some(new_price) = new_price {
-        if let Some(cached_price) = cached_best_price.get(&direction) {
-            if *cached_price != new_price {
-                update_price(direction, new_price);
-                cached_best_price.insert(direction, new_price);
-            }
-        } else {
-            update_price(direction, new_price);
-        }
-    }
-}
-
-fn update_price(direction: Direction, new_price: Decimal) {
-    tracing::trace!(%new_price, direction = %direction, "New price");
-    match direction {
-        Direction::Long => {
-            event::publish(&EventInternal::BidPriceUpdateNotification(new_price));
-        }
-        Direction::Short => {
-            event::publish(&EventInternal::AskPriceUpdateNotification(new_price));
-        }
-    }
-}
@@ -0,0 +1,276 @@
+use crate::event;
+use crate::event::EventInternal;
+use crate::state;
+use bitcoin::secp256k1::PublicKey;
+use rust_decimal::Decimal;
+use state::Storage;
+use tokio::runtime::Runtime;
+use tokio::sync::watch;
+use xxi_node::commons::best_ask_price;
+use xxi_node::commons::best_bid_price;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Order;
+
+static PRICE_FEED: Storage<watch::Sender<Prices>> = Storage::new();
+
+/// The best prices in the orderbook.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Prices {
+    pub bid: Option<Decimal>,
+    pub ask: Option<Decimal>,
+    /// Whether the prices are live, i.e. the app is connected to the orderbook. If not, the prices
+    /// are the last known ones.
+    pub online: bool,
+}
+
+fn feed() -> &'static watch::Sender<Prices> {
+    PRICE_FEED.get_or_set(|| watch::channel(Prices::default()).0)
+}
+
+/// Subscribe to the best prices in the orderbook.
+///
+/// All subscribers share the single orderbook connection of the app. A subscription outlives
+/// reconnects of the orderbook connection: once reconnected, subscribers are notified about the
+/// prices of the new orderbook snapshot.
+pub fn subscribe() -> watch::Receiver<Prices> {
+    feed().subscribe()
+}
+
+/// The latest known best prices.
+pub fn latest() -> Prices {
+    *feed().borrow()
+}
+
+/// Replace the prices with the best prices of a full orderbook snapshot, e.g. after reconnecting.
+///
+/// Subscribers are always notified, even if the prices did not change.
+pub(super) fn reset(orders: &[Order]) {
+    reset_prices(feed(), &others_orders(orders));
+}
+
+/// Use the best prices of an orderbook snapshot which was not received through the orderbook
+/// connection, e.g. the one loaded on startup.
+///
+/// The prices are not live, and they are ignored if we already know any prices.
+pub(super) fn initialize(orders: &[Order]) {
+    initialize_prices(feed(), &others_orders(orders));
+}
+
+/// Update the prices after a change to the orderbook.
+///
+/// Subscribers are only notified if one of the prices changed.
+pub(super) fn update(orders: &[Order]) {
+    update_prices(feed(), &others_orders(orders));
+}
+
+/// The orders of everyone but us.
+///
+/// Our own quotes in liquidity provider mode must not move the prices they are placed around.
+fn others_orders(orders: &[Order]) -> Vec<Order> {
+    match state::try_get_node() {
+        Some(node) => {
+            let own_id = node.inner.info.pubkey;
+            exclude_trader(orders, own_id)
+        }
+        None => orders.to_vec(),
+    }
+}
+
+fn exclude_trader(orders: &[Order], trader_id: PublicKey) -> Vec<Order> {
+    orders
+        .iter()
+        .filter(|order| order.trader_id != trader_id)
+        .cloned()
+        .collect()
+}
+
+/// Mark the prices as stale, because the orderbook connection was lost.
+pub(super) fn set_offline() {
+    feed().send_if_modified(|prices| std::mem::replace(&mut prices.online, false));
+}
+
+/// Forward price changes to the UI through the event hub.
+pub(super) fn spawn_ui_forwarder(runtime: &Runtime) {
+    let mut receiver = subscribe();
+
+    runtime.spawn(async move {
+        let mut forwarded = Prices::default();
+
+        while receiver.changed().await.is_ok() {
+            let prices = *receiver.borrow_and_update();
+
+            if !prices.online {
+                // Forward the prices again once we are back online.
+                forwarded = Prices::default();
+                continue;
+            }
+
+            if let Some(bid) = prices.bid.filter(|bid| Some(*bid) != forwarded.bid) {
+                tracing::trace!(%bid, "New bid price");
+                event::publish(&EventInternal::BidPriceUpdateNotification(bid));
+            }
+
+            if let Some(ask) = prices.ask.filter(|ask| Some(*ask) != forwarded.ask) {
+                tracing::trace!(%ask, "New ask price");
+                event::publish(&EventInternal::AskPriceUpdateNotification(ask));
+            }
+
+            forwarded = prices;
+        }
+    });
+}
+
+fn reset_prices(feed: &watch::Sender<Prices>, orders: &[Order]) {
+    feed.send_replace(best_prices(orders));
+}
+
+fn initialize_prices(feed: &watch::Sender<Prices>, orders: &[Order]) {
+    feed.send_if_modified(|prices| {
+        if *prices != Prices::default() {
+            return false;
+        }
+
+        *prices = Prices {
+            online: false,
+            ..best_prices(orders)
+        };
+
+        true
+    });
+}
+
+fn update_prices(feed: &watch::Sender<Prices>, orders: &[Order]) {
+    let new_prices = best_prices(orders);
+
+    feed.send_if_modified(|prices| {
+        if *prices == new_prices {
+            return false;
+        }
+
+        *prices = new_prices;
+        true
+    });
+}
+
+fn best_prices(orders: &[Order]) -> Prices {
+    Prices {
+        bid: best_bid_price(orders, ContractSymbol::BtcUsd),
+        ask: best_ask_price(orders, ContractSymbol::BtcUsd),
+        online: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::PublicKey;
+    use rust_decimal_macros::dec;
+    use std::str::FromStr;
+    use time::Duration;
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+    use xxi_node::commons::Direction;
+    use xxi_node::commons::OrderReason;
+    use xxi_node::commons::OrderState;
+    use xxi_node::commons::OrderType;
+
+    #[test]
+    fn subscribers_share_price_updates() {
+        let (feed, mut first) = watch::channel(Prices::default());
+        let mut second = feed.subscribe();
+
+        let orders = vec![
+            dummy_order(Direction::Long, dec!(49_000)),
+            dummy_order(Direction::Short, dec!(51_000)),
+        ];
+        update_prices(&feed, &orders);
+
+        let expected = Prices {
+            bid: Some(dec!(49_000)),
+            ask: Some(dec!(51_000)),
+            online: true,
+        };
+        assert!(first.has_changed().unwrap());
+        assert_eq!(*first.borrow_and_update(), expected);
+        assert_eq!(*second.borrow_and_update(), expected);
+    }
+
+    #[test]
+    fn unchanged_prices_do_not_notify() {
+        let (feed, mut receiver) = watch::channel(Prices::default());
+        let orders = vec![dummy_order(Direction::Long, dec!(49_000))];
+
+        update_prices(&feed, &orders);
+        receiver.borrow_and_update();
+
+        update_prices(&feed, &orders);
+        assert!(!receiver.has_changed().unwrap());
+
+        reset_prices(&feed, &orders);
+        assert!(receiver.has_changed().unwrap());
+    }
+
+    #[test]
+    fn initial_prices_do_not_replace_known_prices() {
+        let (feed, mut receiver) = watch::channel(Prices::default());
+
+        initialize_prices(&feed, &[dummy_order(Direction::Long, dec!(49_000))]);
+
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(
+            *receiver.borrow_and_update(),
+            Prices {
+                bid: Some(dec!(49_000)),
+                ask: None,
+                online: false,
+            }
+        );
+
+        update_prices(&feed, &[dummy_order(Direction::Long, dec!(50_000))]);
+        receiver.borrow_and_update();
+
+        initialize_prices(&feed, &[dummy_order(Direction::Long, dec!(48_000))]);
+
+        assert!(!receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow().bid, Some(dec!(50_000)));
+    }
+
+    #[test]
+    fn own_orders_do_not_count_towards_prices() {
+        let mut own_order = dummy_order(Direction::Long, dec!(49_500));
+        let own_id = PublicKey::from_str(
+            "02d5aa8fce495f6301b466594af056a46104dcdc6d735ec4793aa43108854cbd4a",
+        )
+        .unwrap();
+        own_order.trader_id = own_id;
+
+        let orders = vec![own_order, dummy_order(Direction::Long, dec!(49_000))];
+
+        let prices = best_prices(&exclude_trader(&orders, own_id));
+
+        assert_eq!(prices.bid, Some(dec!(49_000)));
+    }
+
+    fn dummy_order(direction: Direction, price: Decimal) -> Order {
+        Order {
+            id: Uuid::new_v4(),
+            price,
+            leverage: 1.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            trader_id: PublicKey::from_str(
+                "027f31ebc5462c1fdce1b737ecff52d37d75dea43ce11c74d25aa297165faa2007",
+            )
+            .unwrap(),
+            direction,
+            quantity: dec!(100),
+            order_type: OrderType::Limit,
+            timestamp: OffsetDateTime::now_utc(),
+            expiry: OffsetDateTime::now_utc() + Duration::minutes(1),
+            order_state: OrderState::Open,
+            order_reason: OrderReason::Manual,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        }
+    }
+}
@@ -13,8 +13,13 @@ pub(crate) async fn get_new_polls() -> Result<Vec<Poll>> {
     let node = crate::state::get_node();
     let new_polls = fetch_polls(&node.inner.info.pubkey).await?;
     tracing::debug!(new_polls = new_polls.len(), "Fetched new polls");
+    unanswered_polls(new_polls)
+}
+
+/// Filter out the polls which were already answered or ignored.
+pub(crate) fn unanswered_polls(polls: Vec<Poll>) -> Result<Vec<Poll>> {
     let answered_polls = db::load_ignored_or_answered_polls()?;
-    let unanswered_polls = new_polls
+    let unanswered_polls = polls
         .into_iter()
         .filter(|poll| {
             !answered_polls
@@ -47,7 +52,7 @@ async fn fetch_polls(node_id: &PublicKey) -> Result<Vec<Poll>> {
     let client = reqwest_client();
     let url = format!("http://{}", config::get_http_endpoint());
     let url = Url::parse(&url).expect("correct URL");
-    let url = url.join(format!("/api/polls/{node_id}").as_str())?;
+    let url = url.join(format!("/api/v2/polls/{node_id}").as_str())?;
     let response = client.get(url).send().await?;
     let polls = response.json().await?;
     Ok(polls)
@@ -57,7 +62,7 @@ async fn post_selected_choice(choice: Choice, poll_id: i32, trader_pk: PublicKey
     let client = reqwest_client();
     let url = format!("http://{}", config::get_http_endpoint());
     let url = Url::parse(&url).expect("correct URL");
-    let url = url.join("/api/polls")?;
+    let url = url.join("/api/v2/polls")?;
     let response = client
         .post(url)
         .json(&PollAnswers {
@@ -12,7 +12,7 @@ pub fn report_error_to_coordinator<E: ToString>(error: &E) {
 
     let url = Url::parse(&format!("http://{}", config::get_http_endpoint()))
         .expect("valid URL")
-        .join("/api/report-error")
+        .join("/api/v2/report-error")
         .expect("valid URL");
 
     let error_string = error.to_string();
@@ -0,0 +1,256 @@
+use crate::api::ConfirmationTarget;
+use crate::api::FeeConfig;
+use crate::commons::reqwest_client;
+use crate::config;
+use crate::db;
+use crate::dlc;
+use crate::dlc::get_node_key;
+use crate::dlc::get_node_pubkey;
+use crate::event;
+use crate::event::EventInternal;
+use crate::state;
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::Address;
+use bitcoin::Amount;
+use bitcoin::Txid;
+use reqwest::Url;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use xxi_node::commons;
+
+const AUTO_TOP_UP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Top up the collateral reserve of the DLC channel from the on-chain wallet, once it drops below
+/// `threshold`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoTopUpPolicy {
+    pub threshold: Amount,
+    /// The collateral reserve to top up to.
+    pub cap: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReserveTopUpUpdate {
+    /// The collateral reserve is about to be topped up by `amount`.
+    Started {
+        amount: Amount,
+    },
+    /// The deposit was broadcast. The coordinator credits it once it is confirmed.
+    Deposited {
+        txid: Txid,
+        amount: Amount,
+    },
+    /// The deposit was credited to the collateral reserve.
+    Credited {
+        amount: Amount,
+    },
+    Failed {
+        reason: String,
+    },
+}
+
+/// Persist the policy chosen by the user, or opt out with `None`.
+pub fn set_policy(policy: Option<AutoTopUpPolicy>) -> Result<()> {
+    db::set_reserve_auto_top_up_policy(policy)?;
+
+    tracing::info!(?policy, "Set reserve auto top-up policy");
+
+    Ok(())
+}
+
+pub fn get_policy() -> Result<Option<AutoTopUpPolicy>> {
+    let policy = db::get_reserve_auto_top_up()?.map(|(policy, _)| policy);
+
+    Ok(policy)
+}
+
+/// Periodically check the collateral reserve against the [`AutoTopUpPolicy`], if there is one.
+pub fn spawn_auto_top_up(runtime: &Runtime) {
+    runtime.spawn(async move {
+        loop {
+            if let Err(e) = check_reserve().await {
+                tracing::error!("Failed to top up collateral reserve: {e:#}");
+
+                event::publish(&EventInternal::ReserveTopUpUpdate(
+                    ReserveTopUpUpdate::Failed {
+                        reason: format!("{e:#}"),
+                    },
+                ));
+            }
+
+            tokio::time::sleep(AUTO_TOP_UP_INTERVAL).await;
+        }
+    });
+}
+
+async fn check_reserve() -> Result<()> {
+    let (policy, pending) = match db::get_reserve_auto_top_up()? {
+        Some(auto_top_up) => auto_top_up,
+        None => return Ok(()),
+    };
+
+    if dlc::get_signed_dlc_channel()?.is_none() {
+        return Ok(());
+    }
+
+    let reserve = dlc::get_usable_dlc_channel_balance()?;
+
+    if let Some(amount) = pending {
+        if reserve >= policy.threshold {
+            db::set_pending_reserve_top_up(None)?;
+
+            tracing::info!(%amount, %reserve, "Reserve top-up credited");
+            event::publish(&EventInternal::ReserveTopUpUpdate(
+                ReserveTopUpUpdate::Credited { amount },
+            ));
+        }
+
+        return Ok(());
+    }
+
+    let amount = match top_up_amount(policy, reserve) {
+        Some(amount) => amount,
+        None => return Ok(()),
+    };
+
+    tracing::info!(%amount, %reserve, ?policy, "Topping up collateral reserve");
+    event::publish(&EventInternal::ReserveTopUpUpdate(
+        ReserveTopUpUpdate::Started { amount },
+    ));
+
+    let top_up = request(amount).await?;
+
+    let address = top_up
+        .address
+        .parse::<Address<NetworkUnchecked>>()
+        .context("Invalid deposit address")?
+        .require_network(config::get_network())?;
+    let amount = Amount::from_sat(top_up.amount_sats);
+
+    // The coordinator hands out the address of a previous request again if it has not seen our
+    // deposit yet. We might have sent it before the app was restarted.
+    let txid = match find_deposit(&address) {
+        Some(txid) => txid,
+        None => {
+            dlc::send_payment(
+                amount.to_sat(),
+                address.to_string(),
+                FeeConfig::Priority(ConfirmationTarget::Normal),
+            )
+            .await?
+        }
+    };
+
+    db::set_pending_reserve_top_up(Some(amount))?;
+
+    tracing::info!(%txid, %amount, "Deposited reserve top-up");
+    event::publish(&EventInternal::ReserveTopUpUpdate(
+        ReserveTopUpUpdate::Deposited { txid, amount },
+    ));
+
+    Ok(())
+}
+
+/// The amount to top up the collateral reserve by, if it is below the threshold of the policy.
+fn top_up_amount(policy: AutoTopUpPolicy, reserve: Amount) -> Option<Amount> {
+    if reserve >= policy.threshold {
+        return None;
+    }
+
+    policy
+        .cap
+        .checked_sub(reserve)
+        .filter(|amount| *amount > Amount::ZERO)
+}
+
+/// Ask the coordinator where to deposit to top up our collateral reserve by `amount`.
+async fn request(amount: Amount) -> Result<commons::ReserveTopUp> {
+    let client = reqwest_client();
+    let url = format!("http://{}", config::get_http_endpoint());
+    let url = Url::parse(&url).expect("correct URL");
+    let url = url.join("/api/v2/reserve/top-up")?;
+
+    let params = commons::ReserveTopUpParams {
+        trader_pubkey: get_node_pubkey(),
+        amount_sats: amount.to_sat(),
+    };
+    let params = commons::SignedValue::new(params, get_node_key())?;
+
+    let response = client.post(url).json(&params).send().await?;
+
+    let status_code = response.status();
+    if !status_code.is_success() {
+        let response_text = match response.text().await {
+            Ok(text) => text,
+            Err(err) => {
+                format!("could not decode response {err:#}")
+            }
+        };
+        return Err(anyhow!(
+            "Could not request reserve top-up: HTTP${status_code}: {response_text}"
+        ));
+    }
+
+    let top_up = response.json().await?;
+
+    Ok(top_up)
+}
+
+/// Find a transaction of ours paying to `address`.
+fn find_deposit(address: &Address) -> Option<Txid> {
+    let script_pubkey = address.script_pubkey();
+
+    state::get_node()
+        .inner
+        .get_on_chain_history()
+        .into_iter()
+        .find(|details| {
+            details
+                .transaction
+                .output
+                .iter()
+                .any(|output| output.script_pubkey == script_pubkey)
+        })
+        .map(|details| details.transaction.txid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tops_up_to_cap_below_threshold() {
+        let policy = AutoTopUpPolicy {
+            threshold: Amount::from_sat(20_000),
+            cap: Amount::from_sat(100_000),
+        };
+
+        assert_eq!(
+            top_up_amount(policy, Amount::from_sat(15_000)),
+            Some(Amount::from_sat(85_000))
+        );
+    }
+
+    #[test]
+    fn no_top_up_above_threshold() {
+        let policy = AutoTopUpPolicy {
+            threshold: Amount::from_sat(20_000),
+            cap: Amount::from_sat(100_000),
+        };
+
+        assert_eq!(top_up_amount(policy, Amount::from_sat(20_000)), None);
+    }
+
+    #[test]
+    fn no_top_up_with_cap_below_reserve() {
+        let policy = AutoTopUpPolicy {
+            threshold: Amount::from_sat(20_000),
+            cap: Amount::from_sat(10_000),
+        };
+
+        assert_eq!(top_up_amount(policy, Amount::from_sat(15_000)), None);
+    }
+}
@@ -35,6 +35,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    dlc_store (kind, key) {
+        kind -> Integer,
+        key -> Binary,
+        value -> Binary,
+    }
+}
+
 diesel::table! {
     funding_fee_events (id) {
         id -> Integer,
@@ -56,6 +64,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    intents (id) {
+        id -> Text,
+        action -> Text,
+        state -> Text,
+        failure_reason -> Nullable<Text>,
+        created_at -> BigInt,
+        valid_until -> BigInt,
+        updated_at -> BigInt,
+    }
+}
+
 diesel::table! {
     last_outbound_dlc_messages (peer_id) {
         peer_id -> Text,
@@ -65,6 +85,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    maker_fills (id) {
+        id -> Integer,
+        order_id -> Text,
+        contract_symbol -> Text,
+        direction -> Text,
+        price -> Float,
+        quantity -> Float,
+        index_price -> Float,
+        filled_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    order_templates (id) {
+        id -> Integer,
+        name -> Text,
+        contract_symbol -> Text,
+        direction -> Text,
+        quantity -> Nullable<Float>,
+        quantity_percent -> Nullable<Float>,
+        leverage -> Float,
+        created_at -> BigInt,
+    }
+}
+
 diesel::table! {
     orders (id) {
         id -> Text,
@@ -121,6 +167,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    reserve_auto_top_up (id) {
+        id -> Integer,
+        threshold_sats -> BigInt,
+        cap_sats -> BigInt,
+        pending_sats -> Nullable<BigInt>,
+    }
+}
+
 diesel::table! {
     rollover_params (protocol_id) {
         protocol_id -> Text,
@@ -163,20 +218,44 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    wallet_balances (id) {
+        id -> Integer,
+        on_chain -> BigInt,
+        off_chain -> Nullable<BigInt>,
+        updated_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    watches (target) {
+        target -> Text,
+        kind -> Text,
+        created_at -> BigInt,
+    }
+}
+
 diesel::joinable!(last_outbound_dlc_messages -> dlc_messages (message_hash));
 
 diesel::allow_tables_to_appear_in_same_query!(
     answered_polls,
     channels,
     dlc_messages,
+    dlc_store,
     funding_fee_events,
     ignored_polls,
+    intents,
     last_outbound_dlc_messages,
+    maker_fills,
+    order_templates,
     orders,
     payments,
     positions,
+    reserve_auto_top_up,
     rollover_params,
     spendable_outputs,
     trades,
     transactions,
+    wallet_balances,
+    watches,
 );
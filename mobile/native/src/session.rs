@@ -0,0 +1,91 @@
+use crate::commons::reqwest_client;
+use crate::config;
+use crate::dlc;
+use anyhow::Context;
+use anyhow::Result;
+use parking_lot::RwLock;
+use reqwest::RequestBuilder;
+use reqwest::Response;
+use reqwest::StatusCode;
+use state::Storage;
+use time::Duration;
+use time::OffsetDateTime;
+use xxi_node::commons::CreateSessionTokenParams;
+use xxi_node::commons::SessionToken;
+use xxi_node::commons::SignedValue;
+
+/// The token authenticating us towards the coordinator's REST API.
+static SESSION_TOKEN: Storage<RwLock<Option<SessionToken>>> = Storage::new();
+
+/// A token which expires within this margin is refreshed before it is used.
+const EXPIRY_MARGIN: Duration = Duration::seconds(30);
+
+fn session_token() -> &'static RwLock<Option<SessionToken>> {
+    SESSION_TOKEN.get_or_set(|| RwLock::new(None))
+}
+
+/// Remember the session token issued by the coordinator, e.g. when authenticating with the
+/// orderbook websocket.
+pub fn set_session_token(token: SessionToken) {
+    *session_token().write() = Some(token);
+}
+
+/// Send a request which requires a session token.
+///
+/// A new session token is requested if we don't have a valid one. If the coordinator nevertheless
+/// rejects the token, e.g. because it expired in the meantime, the token is refreshed and the
+/// request is sent once more.
+pub async fn send(request: RequestBuilder) -> Result<Response> {
+    let retry = request.try_clone();
+
+    let token = match valid_session_token(OffsetDateTime::now_utc()) {
+        Some(token) => token,
+        None => refresh().await?,
+    };
+
+    let response = request.bearer_auth(token.token).send().await?;
+
+    match (response.status(), retry) {
+        (StatusCode::UNAUTHORIZED, Some(retry)) => {
+            tracing::debug!("Session token was rejected, refreshing it");
+
+            let token = refresh().await?;
+            Ok(retry.bearer_auth(token.token).send().await?)
+        }
+        _ => Ok(response),
+    }
+}
+
+fn valid_session_token(now: OffsetDateTime) -> Option<SessionToken> {
+    session_token()
+        .read()
+        .clone()
+        .filter(|token| token.is_valid_for(EXPIRY_MARGIN, now))
+}
+
+/// Request a new session token from the coordinator, proving our identity with the node key.
+async fn refresh() -> Result<SessionToken> {
+    let params = CreateSessionTokenParams {
+        trader_pubkey: dlc::get_node_pubkey(),
+        timestamp: OffsetDateTime::now_utc(),
+    };
+    let params = SignedValue::new(params, dlc::get_node_key())?;
+
+    let token = reqwest_client()
+        .post(format!(
+            "http://{}/api/v2/session-tokens",
+            config::get_http_endpoint()
+        ))
+        .json(&params)
+        .send()
+        .await
+        .context("Failed to request session token")?
+        .error_for_status()
+        .context("Coordinator refused to issue session token")?
+        .json::<SessionToken>()
+        .await?;
+
+    set_session_token(token.clone());
+
+    Ok(token)
+}
@@ -0,0 +1,68 @@
+use crate::db;
+use crate::dlc;
+use crate::event;
+use crate::event::api::WalletInfo;
+use crate::event::EventInternal;
+use anyhow::Result;
+use flutter_rust_bridge::frb;
+use itertools::Itertools;
+use parking_lot::Mutex;
+
+/// The phases the backend goes through when the app is started.
+///
+/// The app shows the state cached from the previous run right away and reconciles it once the node
+/// is synced.
+#[frb]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StartupPhase {
+    NotStarted,
+    /// The balances and positions from the previous run have been published. They may be
+    /// outdated.
+    CachedStateLoaded,
+    /// The Lightning and DLC node is starting.
+    StartingNode,
+    /// The node is running and syncing with the blockchain and the coordinator.
+    Syncing,
+    /// The node is synced and the published state is up to date.
+    Ready,
+}
+
+static PHASE: Mutex<StartupPhase> = parking_lot::const_mutex(StartupPhase::NotStarted);
+
+pub fn get_phase() -> StartupPhase {
+    *PHASE.lock()
+}
+
+pub(crate) fn set_phase(phase: StartupPhase) {
+    *PHASE.lock() = phase;
+
+    tracing::info!(?phase, "Entered startup phase");
+
+    event::publish(&EventInternal::StartupPhase(phase));
+}
+
+/// Publish the balances and positions we know from the previous run, without waiting for the
+/// node.
+pub(crate) fn load_cached_state() -> Result<()> {
+    for position in db::get_positions()? {
+        event::publish(&EventInternal::PositionUpdateNotification(position));
+    }
+
+    // Without cached balances, the wallet has never been synced and there is nothing to show yet.
+    if let Some(balances) = db::get_cached_wallet_balances()? {
+        // The on-chain history is only known to the node, but the trades are in our database.
+        let history = dlc::trade_history()?
+            .into_iter()
+            .sorted_by(|a, b| b.timestamp.cmp(&a.timestamp))
+            .collect();
+
+        event::publish(&EventInternal::WalletInfoUpdateNotification(WalletInfo {
+            balances,
+            history,
+        }));
+    }
+
+    set_phase(StartupPhase::CachedStateLoaded);
+
+    Ok(())
+}
@@ -9,6 +9,9 @@ use state::Storage;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 use tokio::sync::broadcast::Sender;
+use xxi_node::commons::ClientInfo;
+use xxi_node::commons::ClientKind;
+use xxi_node::commons::KillSwitchStatus;
 use xxi_node::commons::OrderbookRequest;
 use xxi_node::commons::TenTenOneConfig;
 use xxi_node::seed::Bip39Seed;
@@ -27,6 +30,8 @@ static WEBSOCKET: Storage<RwLock<Sender<OrderbookRequest>>> = Storage::new();
 static LOG_STREAM_SINK: Storage<RwLock<Arc<StreamSink<LogEntry>>>> = Storage::new();
 static TENTENONE_CONFIG: Storage<RwLock<TenTenOneConfig>> = Storage::new();
 static LN_PAYMENT_WATCHER: Storage<RwLock<Sender<String>>> = Storage::new();
+static KILL_SWITCH: Storage<RwLock<KillSwitchStatus>> = Storage::new();
+static CLIENT_INFO: Storage<ClientInfo> = Storage::new();
 
 pub fn set_config(config: ConfigInternal) {
     match CONFIG.try_get() {
@@ -148,6 +153,44 @@ pub fn try_get_tentenone_config() -> Option<TenTenOneConfig> {
     TENTENONE_CONFIG.try_get().map(|w| w.read().clone())
 }
 
+pub fn set_kill_switch(status: KillSwitchStatus) {
+    match KILL_SWITCH.try_get() {
+        None => {
+            KILL_SWITCH.set(RwLock::new(status));
+        }
+        Some(s) => {
+            *s.write() = status;
+        }
+    }
+}
+
+/// The kill switch of the coordinator, as of the last time we heard from it.
+pub fn get_kill_switch() -> KillSwitchStatus {
+    KILL_SWITCH
+        .try_get()
+        .map(|s| s.read().clone())
+        .unwrap_or_default()
+}
+
+/// Identify the client embedding this crate towards the coordinator. Must be called before the
+/// node is started; defaults to the app.
+pub fn set_client_info(client_info: ClientInfo) {
+    if !CLIENT_INFO.set(client_info) {
+        tracing::warn!("Client info has already been set");
+    }
+}
+
+/// The client which is attached to the orders and the orderbook authentication.
+pub fn get_client_info() -> ClientInfo {
+    CLIENT_INFO
+        .try_get()
+        .cloned()
+        .unwrap_or_else(|| ClientInfo {
+            kind: ClientKind::App,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+}
+
 pub fn set_ln_payment_watcher(ln_payment_watcher: Sender<String>) {
     match LN_PAYMENT_WATCHER.try_get() {
         None => {
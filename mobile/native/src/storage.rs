@@ -11,21 +11,29 @@ use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use xxi_node::storage::encrypted::EncryptedDlcStoreProvider;
 use xxi_node::storage::sled::SledStorageProvider;
 use xxi_node::storage::DlcStoreProvider;
 use xxi_node::storage::KeyValue;
+use xxi_node::storage::Op;
 
 #[derive(Clone)]
 pub struct TenTenOneNodeStorage {
     pub client: RemoteBackupClient,
-    pub dlc_storage: Arc<SledStorageProvider>,
+    pub dlc_storage: Arc<EncryptedDlcStoreProvider<SledStorageProvider>>,
     pub data_dir: String,
     pub backup_dir: String,
     pub network: Network,
 }
 
 impl TenTenOneNodeStorage {
-    pub fn new(data_dir: String, network: Network, secret_key: SecretKey) -> TenTenOneNodeStorage {
+    /// The DLC storage is encrypted at rest with the `dlc_storage_key`.
+    pub fn new(
+        data_dir: String,
+        network: Network,
+        secret_key: SecretKey,
+        dlc_storage_key: [u8; 32],
+    ) -> TenTenOneNodeStorage {
         let mut data_dir = PathBuf::from(data_dir);
         data_dir.push(network.to_string());
 
@@ -42,7 +50,10 @@ impl TenTenOneNodeStorage {
         tracing::info!("Created backup dir at {backup_dir}");
 
         let data_dir = data_dir.to_string_lossy().to_string();
-        let dlc_storage = Arc::new(SledStorageProvider::new(&data_dir));
+        let dlc_storage =
+            EncryptedDlcStoreProvider::new(SledStorageProvider::new(&data_dir), dlc_storage_key)
+                .expect("Failed to open DLC storage");
+        let dlc_storage = Arc::new(dlc_storage);
         let client = RemoteBackupClient::new(AesCipher::new(secret_key));
 
         TenTenOneNodeStorage {
@@ -66,7 +77,7 @@ impl TenTenOneNodeStorage {
             .backup(format!("{DB_BACKUP_KEY}/{DB_BACKUP_NAME}"), value);
         handles.push(handle);
 
-        for dlc_backup in self.dlc_storage.export().into_iter() {
+        for dlc_backup in self.dlc_storage.export()?.into_iter() {
             let key = [
                 DLC_BACKUP_KEY,
                 &hex::encode([dlc_backup.kind]),
@@ -92,20 +103,43 @@ impl DlcStoreProvider for TenTenOneNodeStorage {
 
     fn write(&self, kind: u8, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
         self.dlc_storage.write(kind, key.clone(), value.clone())?;
+        self.back_up_write(kind, &key, value);
 
+        Ok(())
+    }
+
+    fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> Result<()> {
+        self.dlc_storage.delete(kind, key.clone())?;
+        self.back_up_delete(kind, key.as_deref());
+
+        Ok(())
+    }
+
+    fn write_batch(&self, ops: Vec<Op>) -> Result<()> {
+        self.dlc_storage.write_batch(ops.clone())?;
+
+        for op in ops {
+            match op {
+                Op::Write { kind, key, value } => self.back_up_write(kind, &key, value),
+                Op::Delete { kind, key } => self.back_up_delete(kind, key.as_deref()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TenTenOneNodeStorage {
+    fn back_up_write(&self, kind: u8, key: &[u8], value: Vec<u8>) {
         let key = [DLC_BACKUP_KEY, &hex::encode([kind]), &hex::encode(key)].join("/");
 
         // Let the backup run asynchronously we don't really care if it is successful or not as the
         // next write may fix the issue. Note, if we want to handle failed backup attempts we
         // would need to remember those remote handles and handle a failure accordingly.
         self.client.backup(key, value).forget();
-
-        Ok(())
     }
 
-    fn delete(&self, kind: u8, key: Option<Vec<u8>>) -> Result<()> {
-        self.dlc_storage.delete(kind, key.clone())?;
-
+    fn back_up_delete(&self, kind: u8, key: Option<&[u8]>) {
         let key = match key {
             Some(key) => [DLC_BACKUP_KEY, &hex::encode([kind]), &hex::encode(key)].join("/"),
             None => [DLC_BACKUP_KEY, &hex::encode([kind])].join("/"),
@@ -116,6 +150,5 @@ impl DlcStoreProvider for TenTenOneNodeStorage {
         // be a problem. Note, if we want to handle failed backup attempts we would need to
         // remember those remote handles and handle a failure accordingly.
         self.client.delete(key).forget();
-        Ok(())
     }
 }
@@ -0,0 +1,170 @@
+use crate::config;
+use crate::db;
+use crate::dlc;
+use crate::event;
+use crate::event::EventInternal;
+use crate::logger;
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::task::spawn_blocking;
+
+const STORAGE_CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// Once the app takes up more space than this, the user is warned.
+const STORAGE_WARNING_THRESHOLD: u64 = 500 * 1024 * 1024;
+
+/// Once the app takes up more space than this, we prune old logs and compact the database.
+///
+/// This is well above [`STORAGE_WARNING_THRESHOLD`], so that an app which stays above the warning
+/// threshold, e.g. because of a large DLC storage, does not vacuum its database on every check.
+const STORAGE_CLEANUP_THRESHOLD: u64 = 1024 * 1024 * 1024;
+
+/// The space the app data takes up on disk, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StorageUsage {
+    pub dlc_storage: u64,
+    pub database: u64,
+    pub logs: u64,
+    pub backup: u64,
+}
+
+impl StorageUsage {
+    pub fn total(&self) -> u64 {
+        self.dlc_storage + self.database + self.logs + self.backup
+    }
+}
+
+/// Periodically account for the space used by the app, warning the user if it exceeds
+/// [`STORAGE_WARNING_THRESHOLD`] and cleaning up if it exceeds [`STORAGE_CLEANUP_THRESHOLD`].
+pub fn spawn_storage_monitor(runtime: &Runtime) {
+    runtime.spawn(async move {
+        loop {
+            match spawn_blocking(check_storage)
+                .await
+                .expect("To spawn blocking task")
+            {
+                Ok(usage) => {
+                    if needs_warning(&usage) {
+                        tracing::warn!(?usage, "App is using a lot of storage");
+                        event::publish(&EventInternal::StorageWarning(usage));
+                    }
+                }
+                Err(e) => tracing::error!("Failed to check storage usage: {e:#}"),
+            }
+
+            tokio::time::sleep(STORAGE_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+fn check_storage() -> Result<StorageUsage> {
+    let usage = storage_usage()?;
+    tracing::debug!(?usage, total = usage.total(), "Storage usage");
+
+    if !needs_cleanup(&usage) {
+        return Ok(usage);
+    }
+
+    clean_up();
+
+    let usage_after_cleanup = storage_usage()?;
+    tracing::info!(
+        before = usage.total(),
+        after = usage_after_cleanup.total(),
+        "Cleaned up storage"
+    );
+
+    Ok(usage_after_cleanup)
+}
+
+pub fn storage_usage() -> Result<StorageUsage> {
+    Ok(StorageUsage {
+        dlc_storage: dlc::get_storage().dlc_storage.inner().size_on_disk()?,
+        database: db::size_on_disk()?,
+        logs: dir_size(Path::new(&config::get_log_dir())),
+        backup: dir_size(Path::new(&config::get_backup_dir())),
+    })
+}
+
+/// Frees up space, by deleting rotated logs and by compacting the database.
+///
+/// The backup directory is left alone, since its files may be in the middle of being uploaded. The
+/// DLC storage is left alone too, since sled can't be compacted in place.
+///
+/// The individual steps are best effort, a failing step does not prevent the others.
+fn clean_up() {
+    match logger::prune_log_files() {
+        Ok(freed) => tracing::debug!(freed, "Pruned rotated log files"),
+        Err(e) => tracing::warn!("Failed to prune log files: {e:#}"),
+    }
+
+    if let Err(e) = db::compact() {
+        tracing::warn!("Failed to compact database: {e:#}");
+    }
+}
+
+fn needs_cleanup(usage: &StorageUsage) -> bool {
+    usage.total() > STORAGE_CLEANUP_THRESHOLD
+}
+
+fn needs_warning(usage: &StorageUsage) -> bool {
+    usage.total() > STORAGE_WARNING_THRESHOLD
+}
+
+/// The size of all files in the given directory and its subdirectories, in bytes.
+fn dir_size(dir: &Path) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warning_happens_before_cleanup() {
+        let usage = StorageUsage {
+            dlc_storage: 400 * 1024 * 1024,
+            database: 150 * 1024 * 1024,
+            logs: 0,
+            backup: 0,
+        };
+
+        assert!(needs_warning(&usage));
+        assert!(!needs_cleanup(&usage));
+
+        let usage = StorageUsage {
+            logs: 500 * 1024 * 1024,
+            ..usage
+        };
+
+        assert!(needs_cleanup(&usage));
+    }
+
+    #[test]
+    fn dir_size_includes_subdirectories() {
+        let dir = std::env::temp_dir().join(format!("10101-storage-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a"), [0; 10]).unwrap();
+        fs::write(dir.join("nested").join("b"), [0; 5]).unwrap();
+
+        assert_eq!(dir_size(&dir), 15);
+        assert_eq!(dir_size(&dir.join("missing")), 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use crate::commons::reqwest_client;
+use crate::config;
+use crate::dlc::get_node_key;
+use crate::dlc::get_node_pubkey;
+use crate::event;
+use crate::event::EventInternal;
+use anyhow::anyhow;
+use anyhow::Result;
+use reqwest::Url;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use uuid::Uuid;
+use xxi_node::commons;
+use xxi_node::commons::ErrorCode;
+
+/// Whether the user agreed to automatically report failed trades to support.
+static CONSENT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_consent(consent: bool) {
+    CONSENT.store(consent, Ordering::SeqCst);
+}
+
+pub fn has_consent() -> bool {
+    CONSENT.load(Ordering::SeqCst)
+}
+
+/// Create a support ticket for a failed trade and publish its reference to the app.
+///
+/// Reporting the same order more than once returns the reference of the existing ticket.
+pub async fn create_for_trade_error(
+    order_id: Uuid,
+    error_code: ErrorCode,
+    error: String,
+) -> Result<()> {
+    let client = reqwest_client();
+    let url = format!("http://{}", config::get_http_endpoint());
+    let url = Url::parse(&url).expect("correct URL");
+    let url = url.join("/api/v2/support-tickets")?;
+
+    let ticket = commons::NewSupportTicket {
+        trader_pubkey: get_node_pubkey(),
+        error_code,
+        error,
+        order_id: Some(order_id),
+        protocol_id: None,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let ticket = commons::SignedValue::new(ticket, get_node_key())?;
+
+    let response = client.post(url).json(&ticket).send().await?;
+
+    let status_code = response.status();
+    if !status_code.is_success() {
+        let response_text = match response.text().await {
+            Ok(text) => text,
+            Err(err) => {
+                format!("could not decode response {err:#}")
+            }
+        };
+        return Err(anyhow!(
+            "Could not create support ticket: HTTP${status_code}: {response_text}"
+        ));
+    }
+
+    let commons::SupportTicketReference { reference } = response.json().await?;
+
+    tracing::info!(%order_id, reference, "Created support ticket for failed trade");
+
+    event::publish(&EventInternal::SupportTicketCreated {
+        order_id: Some(order_id),
+        reference,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::trade::intent;
+use flutter_rust_bridge::frb;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+#[frb]
+#[derive(Debug, Clone)]
+pub struct Intent {
+    pub id: String,
+    pub action: Box<IntentAction>,
+    pub state: IntentState,
+    pub created_at: i64,
+    pub valid_until: i64,
+}
+
+#[frb]
+#[derive(Debug, Clone)]
+pub enum IntentAction {
+    /// Submit a market order, if the price has not moved more than `max_price_deviation` percent
+    /// away from the `reference_price` by the time the app is back online.
+    SubmitOrder {
+        contract_symbol: ContractSymbol,
+        direction: Direction,
+        quantity: f32,
+        leverage: f32,
+        reference_price: f32,
+        max_price_deviation: f32,
+    },
+    WithdrawViaLightning {
+        invoice: String,
+    },
+    UpdateNickname {
+        nickname: String,
+    },
+}
+
+#[frb]
+#[derive(Debug, Clone)]
+pub enum IntentState {
+    Queued,
+    Executed,
+    Expired,
+    Failed { reason: String },
+}
+
+impl From<intent::Intent> for Intent {
+    fn from(value: intent::Intent) -> Self {
+        Intent {
+            id: value.id.to_string(),
+            action: Box::new(value.action.into()),
+            state: value.state.into(),
+            created_at: value.created_at.unix_timestamp(),
+            valid_until: value.valid_until.unix_timestamp(),
+        }
+    }
+}
+
+impl From<intent::IntentAction> for IntentAction {
+    fn from(value: intent::IntentAction) -> Self {
+        match value {
+            intent::IntentAction::SubmitOrder {
+                contract_symbol,
+                direction,
+                quantity,
+                leverage,
+                reference_price,
+                max_price_deviation,
+            } => IntentAction::SubmitOrder {
+                contract_symbol,
+                direction,
+                quantity: quantity.to_f32().expect("to fit"),
+                leverage: leverage.to_f32().expect("to fit"),
+                reference_price: reference_price.to_f32().expect("to fit"),
+                max_price_deviation: max_price_deviation.to_f32().expect("to fit"),
+            },
+            intent::IntentAction::WithdrawViaLightning { invoice } => {
+                IntentAction::WithdrawViaLightning { invoice }
+            }
+            intent::IntentAction::UpdateNickname { nickname } => {
+                IntentAction::UpdateNickname { nickname }
+            }
+        }
+    }
+}
+
+impl From<IntentAction> for intent::IntentAction {
+    fn from(value: IntentAction) -> Self {
+        match value {
+            IntentAction::SubmitOrder {
+                contract_symbol,
+                direction,
+                quantity,
+                leverage,
+                reference_price,
+                max_price_deviation,
+            } => intent::IntentAction::SubmitOrder {
+                contract_symbol,
+                direction,
+                quantity: Decimal::from_f32(quantity).expect("to fit"),
+                leverage: Decimal::from_f32(leverage).expect("to fit"),
+                reference_price: Decimal::from_f32(reference_price).expect("to fit"),
+                max_price_deviation: Decimal::from_f32(max_price_deviation).expect("to fit"),
+            },
+            IntentAction::WithdrawViaLightning { invoice } => {
+                intent::IntentAction::WithdrawViaLightning { invoice }
+            }
+            IntentAction::UpdateNickname { nickname } => {
+                intent::IntentAction::UpdateNickname { nickname }
+            }
+        }
+    }
+}
+
+impl From<intent::IntentState> for IntentState {
+    fn from(value: intent::IntentState) -> Self {
+        match value {
+            intent::IntentState::Queued => IntentState::Queued,
+            intent::IntentState::Executed => IntentState::Executed,
+            intent::IntentState::Expired => IntentState::Expired,
+            intent::IntentState::Failed { reason } => IntentState::Failed { reason },
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use crate::db;
+use crate::event;
+use crate::event::subscriber::Subscriber;
+use crate::event::EventInternal;
+use crate::event::EventType;
+use crate::health::ServiceStatus;
+use crate::health::ServiceUpdate;
+use crate::lightning_withdrawal;
+use crate::max_quantity::max_quantity;
+use crate::orderbook::price_feed;
+use crate::state;
+use crate::trade::intent::Intent;
+use crate::trade::intent::IntentAction;
+use crate::trade::intent::IntentState;
+use crate::trade::order;
+use crate::trade::order::api::NewOrder;
+use crate::trade::order::api::OrderType;
+use crate::trade::order_template::TradeLimits;
+use crate::trade::users;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::Direction;
+
+/// Set while the queued intents are being submitted, so that an intent is never submitted twice.
+static SUBMITTING: AtomicBool = AtomicBool::new(false);
+
+pub fn queue_intent(intent: Intent) -> Result<Intent> {
+    db::insert_intent(&intent)?;
+
+    tracing::info!(id = %intent.id, action = ?intent.action, "Queued intent");
+    event::publish(&EventInternal::IntentUpdate(intent.clone()));
+
+    Ok(intent)
+}
+
+pub fn get_intents() -> Result<Vec<Intent>> {
+    db::get_intents()
+}
+
+/// Drop an intent which has not been submitted yet.
+pub fn cancel_intent(id: Uuid) -> Result<()> {
+    ensure!(
+        db::delete_queued_intent(id)?,
+        "No queued intent with id {id}"
+    );
+
+    Ok(())
+}
+
+/// Submit the queued intents in the order they were queued.
+///
+/// Expired intents are not submitted. Orders are kept queued until live prices are available.
+pub async fn submit_queued_intents() -> Result<()> {
+    if SUBMITTING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        tracing::debug!("Already submitting queued intents");
+        return Ok(());
+    }
+
+    let result = submit_queued_intents_internal().await;
+
+    SUBMITTING.store(false, Ordering::SeqCst);
+
+    result
+}
+
+async fn submit_queued_intents_internal() -> Result<()> {
+    let intents = db::get_queued_intents()?;
+
+    for intent in intents {
+        if intent.is_expired(OffsetDateTime::now_utc()) {
+            tracing::info!(id = %intent.id, "Queued intent expired");
+            update_state(intent, IntentState::Expired)?;
+            continue;
+        }
+
+        if intent.action.needs_prices() && !price_feed::latest().online {
+            tracing::debug!(id = %intent.id, "Waiting for live prices to submit queued intent");
+            continue;
+        }
+
+        let state = match execute(&intent.action).await {
+            Ok(()) => {
+                tracing::info!(id = %intent.id, "Submitted queued intent");
+                IntentState::Executed
+            }
+            Err(e) => {
+                tracing::warn!(id = %intent.id, "Failed to submit queued intent: {e:#}");
+                IntentState::Failed {
+                    reason: format!("{e:#}"),
+                }
+            }
+        };
+
+        update_state(intent, state)?;
+    }
+
+    Ok(())
+}
+
+async fn execute(action: &IntentAction) -> Result<()> {
+    match action {
+        IntentAction::SubmitOrder {
+            contract_symbol,
+            direction,
+            quantity,
+            leverage,
+            ..
+        } => {
+            let config =
+                state::try_get_tentenone_config().context("We can't trade without LSP config")?;
+
+            // A market order is filled at the best price on the opposite side of the orderbook.
+            let prices = price_feed::latest();
+            let price = match direction {
+                Direction::Long => prices.ask,
+                Direction::Short => prices.bid,
+            }
+            .context("No price available in the orderbook")?;
+
+            let leverage = leverage.to_f32().expect("to fit");
+
+            let limits = TradeLimits {
+                min_quantity: Decimal::from(config.min_quantity),
+                max_quantity: max_quantity(price, leverage, *direction)?,
+                max_leverage: Decimal::from(config.max_leverage),
+            };
+            action.revalidate(price, limits)?;
+
+            let order = NewOrder {
+                leverage,
+                quantity: quantity.to_f32().expect("to fit"),
+                contract_symbol: *contract_symbol,
+                direction: *direction,
+                order_type: Box::new(OrderType::Market),
+                stable: false,
+            };
+
+            order::handler::submit_order(order.into(), None).await?;
+        }
+        IntentAction::WithdrawViaLightning { invoice } => {
+            lightning_withdrawal::withdraw(invoice.clone()).await?;
+        }
+        IntentAction::UpdateNickname { nickname } => {
+            users::update_username(nickname.clone()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn update_state(mut intent: Intent, state: IntentState) -> Result<()> {
+    db::update_intent_state(intent.id, &state)?;
+
+    intent.state = state;
+    event::publish(&EventInternal::IntentUpdate(intent));
+
+    Ok(())
+}
+
+/// Submits the queued intents whenever a service reports to be online.
+///
+/// The health of the services is reported periodically, so intents which could not be submitted
+/// yet, e.g. for lack of live prices, are retried.
+#[derive(Clone, Copy)]
+pub struct IntentSubmitter;
+
+impl Subscriber for IntentSubmitter {
+    fn notify(&self, event: &EventInternal) {
+        if !matches!(
+            event,
+            EventInternal::ServiceHealthUpdate(ServiceUpdate {
+                status: ServiceStatus::Online,
+                ..
+            })
+        ) {
+            return;
+        }
+
+        let runtime = match state::get_or_create_tokio_runtime() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                tracing::error!("Failed to get tokio runtime. Error: {e:#}");
+                return;
+            }
+        };
+
+        runtime.spawn(async {
+            if let Err(e) = submit_queued_intents().await {
+                tracing::error!("Failed to submit queued intents: {e:#}");
+            }
+        });
+    }
+
+    fn events(&self) -> Vec<EventType> {
+        vec![EventType::ServiceHealthUpdate]
+    }
+}
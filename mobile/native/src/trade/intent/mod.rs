@@ -0,0 +1,264 @@
+use crate::trade::order_template::TradeLimits;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use time::Duration;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+pub mod api;
+pub mod handler;
+
+/// Intents are not kept around for longer than this, as the user will hardly expect a trade to
+/// be submitted weeks after they queued it.
+const MAX_VALIDITY: Duration = Duration::days(7);
+
+/// An action the user took while the app was offline, e.g. on a flight.
+///
+/// Intents are persisted and submitted once the app is back online, unless they have expired by
+/// then.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Intent {
+    pub id: Uuid,
+    pub action: IntentAction,
+    pub state: IntentState,
+    pub created_at: OffsetDateTime,
+    /// The intent expires if it could not be submitted until then.
+    pub valid_until: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IntentAction {
+    /// Submit a market order.
+    SubmitOrder {
+        contract_symbol: ContractSymbol,
+        direction: Direction,
+        quantity: Decimal,
+        leverage: Decimal,
+        /// The price the user saw when queuing the order.
+        reference_price: Decimal,
+        /// How far the market price may move away from the `reference_price`, in percent, for
+        /// the order to still be submitted.
+        max_price_deviation: Decimal,
+    },
+    /// Withdraw from the collateral reserve by having the coordinator pay the invoice.
+    WithdrawViaLightning {
+        invoice: String,
+    },
+    UpdateNickname {
+        nickname: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntentState {
+    /// Waiting for the app to be back online.
+    Queued,
+    Executed,
+    Expired,
+    Failed {
+        reason: String,
+    },
+}
+
+impl Intent {
+    pub fn new(action: IntentAction, validity: Duration) -> Result<Self> {
+        ensure!(
+            validity.is_positive() && validity <= MAX_VALIDITY,
+            "Intent must be valid for some time up to {MAX_VALIDITY}, got {validity}"
+        );
+
+        action.validate()?;
+
+        let created_at = OffsetDateTime::now_utc();
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            action,
+            state: IntentState::Queued,
+            created_at,
+            valid_until: created_at + validity,
+        })
+    }
+
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        now > self.valid_until
+    }
+}
+
+impl IntentAction {
+    /// Whether the action can only be submitted with live prices.
+    pub fn needs_prices(&self) -> bool {
+        matches!(self, IntentAction::SubmitOrder { .. })
+    }
+
+    /// Re-validate a queued order against the current `price` and [`TradeLimits`], since the
+    /// market may have moved while the app was offline.
+    ///
+    /// Other actions do not depend on the market and are always valid.
+    pub fn revalidate(&self, price: Decimal, limits: TradeLimits) -> Result<()> {
+        let (quantity, leverage, reference_price, max_price_deviation) = match self {
+            IntentAction::SubmitOrder {
+                quantity,
+                leverage,
+                reference_price,
+                max_price_deviation,
+                ..
+            } => (quantity, leverage, reference_price, max_price_deviation),
+            IntentAction::WithdrawViaLightning { .. } | IntentAction::UpdateNickname { .. } => {
+                return Ok(())
+            }
+        };
+
+        let deviation = (price - *reference_price).abs() / *reference_price * Decimal::ONE_HUNDRED;
+        ensure!(
+            deviation <= *max_price_deviation,
+            "Price moved from {reference_price} to {price}, more than the accepted \
+             {max_price_deviation}%"
+        );
+
+        ensure!(
+            *leverage <= limits.max_leverage,
+            "Leverage {leverage} exceeds the max leverage of {}",
+            limits.max_leverage
+        );
+        ensure!(
+            *quantity >= limits.min_quantity,
+            "Quantity {quantity} is below the min quantity of {}",
+            limits.min_quantity
+        );
+        ensure!(
+            *quantity <= limits.max_quantity,
+            "Quantity {quantity} exceeds the max quantity of {}",
+            limits.max_quantity
+        );
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self {
+            IntentAction::SubmitOrder {
+                quantity,
+                leverage,
+                reference_price,
+                max_price_deviation,
+                ..
+            } => {
+                ensure!(
+                    *quantity > Decimal::ZERO,
+                    "Quantity must be positive, got {quantity}"
+                );
+                ensure!(
+                    *leverage >= Decimal::ONE,
+                    "Leverage must be at least 1, got {leverage}"
+                );
+                ensure!(
+                    *reference_price > Decimal::ZERO,
+                    "Reference price must be positive, got {reference_price}"
+                );
+                ensure!(
+                    *max_price_deviation >= Decimal::ZERO
+                        && *max_price_deviation <= Decimal::ONE_HUNDRED,
+                    "Max price deviation must be in [0, 100], got {max_price_deviation}"
+                );
+            }
+            IntentAction::WithdrawViaLightning { invoice } if invoice.trim().is_empty() => {
+                bail!("Invoice must not be empty")
+            }
+            IntentAction::UpdateNickname { nickname } if nickname.trim().is_empty() => {
+                bail!("Nickname must not be empty")
+            }
+            IntentAction::WithdrawViaLightning { .. } | IntentAction::UpdateNickname { .. } => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn limits() -> TradeLimits {
+        TradeLimits {
+            min_quantity: dec!(1),
+            max_quantity: dec!(1_000),
+            max_leverage: dec!(5),
+        }
+    }
+
+    fn order(quantity: Decimal, leverage: Decimal) -> IntentAction {
+        IntentAction::SubmitOrder {
+            contract_symbol: ContractSymbol::BtcUsd,
+            direction: Direction::Long,
+            quantity,
+            leverage,
+            reference_price: dec!(60_000),
+            max_price_deviation: dec!(1),
+        }
+    }
+
+    #[test]
+    fn intent_expires_after_its_validity() {
+        let intent = Intent::new(order(dec!(100), dec!(2)), Duration::hours(2)).unwrap();
+
+        assert!(!intent.is_expired(intent.created_at + Duration::hours(1)));
+        assert!(intent.is_expired(intent.created_at + Duration::hours(3)));
+    }
+
+    #[test]
+    fn order_is_revalidated_against_the_current_price() {
+        let order = order(dec!(100), dec!(2));
+
+        assert!(order.revalidate(dec!(60_600), limits()).is_ok());
+        assert!(order.revalidate(dec!(59_400), limits()).is_ok());
+        assert!(order.revalidate(dec!(60_601), limits()).is_err());
+        assert!(order.revalidate(dec!(59_000), limits()).is_err());
+    }
+
+    #[test]
+    fn order_is_revalidated_against_the_current_limits() {
+        assert!(order(dec!(1_001), dec!(2))
+            .revalidate(dec!(60_000), limits())
+            .is_err());
+        assert!(order(dec!(100), dec!(10))
+            .revalidate(dec!(60_000), limits())
+            .is_err());
+
+        let nickname = IntentAction::UpdateNickname {
+            nickname: "satoshi".to_string(),
+        };
+        assert!(nickname.revalidate(dec!(0), limits()).is_ok());
+    }
+
+    #[test]
+    fn invalid_intent_cannot_be_queued() {
+        assert!(Intent::new(order(dec!(0), dec!(2)), Duration::hours(1)).is_err());
+        assert!(Intent::new(order(dec!(100), dec!(2)), Duration::ZERO).is_err());
+        assert!(Intent::new(order(dec!(100), dec!(2)), Duration::days(8)).is_err());
+        assert!(Intent::new(
+            IntentAction::WithdrawViaLightning {
+                invoice: " ".to_string()
+            },
+            Duration::hours(1)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn intent_action_roundtrips_through_json() {
+        let action = order(dec!(100), dec!(2));
+
+        let json = serde_json::to_string(&action).unwrap();
+
+        assert_eq!(serde_json::from_str::<IntentAction>(&json).unwrap(), action);
+    }
+}
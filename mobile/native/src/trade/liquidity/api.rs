@@ -0,0 +1,97 @@
+use crate::trade::liquidity;
+use crate::trade::liquidity::handler;
+use flutter_rust_bridge::frb;
+use rust_decimal::prelude::ToPrimitive;
+use xxi_node::commons::Direction;
+
+#[frb]
+#[derive(Debug, Clone, Copy)]
+pub struct LiquidityConfig {
+    /// The distance of each quote from the index price, in basis points.
+    pub spread_bps: u32,
+    /// The number of contracts of each quote.
+    pub quantity: f32,
+    pub leverage: f32,
+}
+
+#[frb]
+#[derive(Debug, Clone)]
+pub struct LiquidityQuote {
+    pub order_id: String,
+    pub direction: Direction,
+    pub price: f32,
+    pub quantity: f32,
+    pub expiry: i64,
+}
+
+#[frb]
+#[derive(Debug, Clone)]
+pub struct MakerFill {
+    pub order_id: String,
+    pub direction: Direction,
+    pub price: f32,
+    pub quantity: f32,
+    pub earned_sats: u64,
+    pub filled_at: i64,
+}
+
+#[frb]
+#[derive(Debug, Clone)]
+pub struct LiquidityStatus {
+    pub active: bool,
+    pub config: Option<LiquidityConfig>,
+    pub quotes: Vec<LiquidityQuote>,
+    pub fills: Vec<MakerFill>,
+    /// The total earnings of all fills.
+    pub earned_sats: u64,
+}
+
+impl From<liquidity::LiquidityConfig> for LiquidityConfig {
+    fn from(value: liquidity::LiquidityConfig) -> Self {
+        LiquidityConfig {
+            spread_bps: value.spread_bps,
+            quantity: value.quantity.to_f32().expect("to fit"),
+            leverage: value.leverage.to_f32().expect("to fit"),
+        }
+    }
+}
+
+impl From<liquidity::Quote> for LiquidityQuote {
+    fn from(value: liquidity::Quote) -> Self {
+        LiquidityQuote {
+            order_id: value.order_id.to_string(),
+            direction: value.direction,
+            price: value.price.to_f32().expect("to fit"),
+            quantity: value.quantity.to_f32().expect("to fit"),
+            expiry: value.expiry.unix_timestamp(),
+        }
+    }
+}
+
+impl From<liquidity::MakerFill> for MakerFill {
+    fn from(value: liquidity::MakerFill) -> Self {
+        MakerFill {
+            order_id: value.order_id.to_string(),
+            direction: value.direction,
+            price: value.price.to_f32().expect("to fit"),
+            quantity: value.quantity.to_f32().expect("to fit"),
+            earned_sats: value.earnings().to_sat(),
+            filled_at: value.filled_at.unix_timestamp(),
+        }
+    }
+}
+
+impl From<handler::LiquidityStatus> for LiquidityStatus {
+    fn from(value: handler::LiquidityStatus) -> Self {
+        let fills: Vec<MakerFill> = value.fills.into_iter().map(MakerFill::from).collect();
+        let earned_sats = fills.iter().map(|fill| fill.earned_sats).sum();
+
+        LiquidityStatus {
+            active: value.config.is_some(),
+            config: value.config.map(LiquidityConfig::from),
+            quotes: value.quotes.into_iter().map(LiquidityQuote::from).collect(),
+            fills,
+            earned_sats,
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use crate::config;
+use crate::db;
+use crate::dlc;
+use crate::orderbook::price_feed;
+use crate::state;
+use crate::trade::liquidity::index_price;
+use crate::trade::liquidity::LiquidityConfig;
+use crate::trade::liquidity::MakerFill;
+use crate::trade::liquidity::Quote;
+use crate::trade::order;
+use crate::trade::order::orderbook_client::OrderbookClient;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use parking_lot::Mutex;
+use reqwest::Url;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+use xxi_node::commons::NewLimitOrder;
+use xxi_node::commons::OrderState;
+use xxi_node::commons::OrderbookRequest;
+
+/// How long a quote rests in the orderbook before it expires.
+const QUOTE_EXPIRY: Duration = Duration::from_secs(60);
+
+/// How long before their expiry quotes are replaced, so that the user keeps providing liquidity
+/// without gaps.
+const REFRESH_MARGIN: Duration = Duration::from_secs(15);
+
+/// The running liquidity provider, if the user enabled it.
+static PROVIDER: Mutex<Option<Provider>> = parking_lot::const_mutex(None);
+
+/// The quotes we placed and have not yet learned the fate of.
+static QUOTES: Mutex<Vec<Quote>> = parking_lot::const_mutex(Vec::new());
+
+struct Provider {
+    config: LiquidityConfig,
+    task: JoinHandle<()>,
+    /// Wakes up the provider to replace its quotes, e.g. because one of them was filled.
+    requote: Arc<Notify>,
+}
+
+/// The state of the liquidity provider mode.
+#[derive(Debug, Clone)]
+pub struct LiquidityStatus {
+    /// The configuration of the liquidity provider, if it is running.
+    pub config: Option<LiquidityConfig>,
+    /// The quotes currently resting in the orderbook.
+    pub quotes: Vec<Quote>,
+    pub fills: Vec<MakerFill>,
+}
+
+/// Start maintaining a bid and an ask around the index price.
+///
+/// Note, the coordinator only accepts limit orders from whitelisted makers.
+pub fn start(config: LiquidityConfig) -> Result<()> {
+    let mut provider = PROVIDER.lock();
+
+    ensure!(
+        provider.is_none(),
+        "Already providing liquidity, stop first to change the configuration"
+    );
+    ensure!(
+        state::try_get_websocket().is_some(),
+        "Can't provide liquidity without orderbook connection"
+    );
+    order::handler::check_kill_switch(false)?;
+
+    let requote = Arc::new(Notify::new());
+    let runtime = state::get_or_create_tokio_runtime()?;
+    let task = runtime.spawn(provide_liquidity(config, requote.clone()));
+
+    tracing::info!(?config, "Started providing liquidity");
+
+    *provider = Some(Provider {
+        config,
+        task,
+        requote,
+    });
+
+    Ok(())
+}
+
+/// Stop providing liquidity and withdraw all quotes from the orderbook.
+pub fn stop() -> Result<()> {
+    let provider = PROVIDER.lock().take().context("Not providing liquidity")?;
+
+    provider.task.abort();
+    withdraw_quotes();
+
+    tracing::info!("Stopped providing liquidity");
+
+    Ok(())
+}
+
+pub fn status() -> Result<LiquidityStatus> {
+    let config = PROVIDER.lock().as_ref().map(|provider| provider.config);
+    let quotes = QUOTES
+        .lock()
+        .iter()
+        .filter(|quote| !quote.withdrawn)
+        .cloned()
+        .collect();
+    let fills = db::get_maker_fills()?;
+
+    Ok(LiquidityStatus {
+        config,
+        quotes,
+        fills,
+    })
+}
+
+/// Handle the removal of an order from the orderbook.
+///
+/// If it was one of our quotes, we ask the orderbook whether it was filled, because the orderbook
+/// removes matched and expired orders alike.
+pub fn on_order_deleted(order_id: Uuid) {
+    let quote = {
+        let mut quotes = QUOTES.lock();
+        match quotes.iter().position(|quote| quote.order_id == order_id) {
+            Some(index) => quotes.remove(index),
+            None => return,
+        }
+    };
+
+    if !quote.withdrawn {
+        // Replace the missing quote right away, instead of waiting for the next refresh.
+        if let Some(provider) = PROVIDER.lock().as_ref() {
+            provider.requote.notify_one();
+        }
+    }
+
+    let runtime = match state::get_or_create_tokio_runtime() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            tracing::error!(%order_id, "Failed to check if quote was filled: {e:#}");
+            return;
+        }
+    };
+
+    runtime.spawn(async move {
+        if let Err(e) = check_fill(&quote).await {
+            tracing::error!(%order_id, "Failed to check if quote was filled: {e:#}");
+        }
+    });
+}
+
+async fn check_fill(quote: &Quote) -> Result<()> {
+    let url = format!("http://{}", config::get_http_endpoint());
+    let url = Url::parse(&url).expect("correct URL");
+    let orderbook_client = OrderbookClient::new(url);
+
+    let order = orderbook_client.get_order(quote.order_id).await?;
+
+    match order.order_state {
+        OrderState::Matched | OrderState::Taken => {
+            let fill = MakerFill::new(quote, OffsetDateTime::now_utc());
+            let earnings = fill.earnings();
+
+            if db::insert_maker_fill(fill)? {
+                tracing::info!(order_id = %quote.order_id, %earnings, "Quote was filled");
+            }
+        }
+        order_state => {
+            tracing::debug!(order_id = %quote.order_id, ?order_state, "Quote was not filled");
+        }
+    }
+
+    Ok(())
+}
+
+async fn provide_liquidity(config: LiquidityConfig, requote: Arc<Notify>) {
+    let mut prices = price_feed::subscribe();
+
+    loop {
+        let index = index_price(&prices.borrow_and_update());
+
+        let quoted_index = match index {
+            Some(index) if order::handler::check_kill_switch(false).is_ok() => {
+                match replace_quotes(config, index) {
+                    Ok(()) => Some(index),
+                    Err(e) => {
+                        tracing::error!("Failed to place quotes: {e:#}");
+                        withdraw_quotes();
+                        None
+                    }
+                }
+            }
+            _ => {
+                // Without a reliable index price we could be picked off, so we rather don't quote.
+                withdraw_quotes();
+                None
+            }
+        };
+
+        let refresh = tokio::time::sleep(QUOTE_EXPIRY - REFRESH_MARGIN);
+        tokio::pin!(refresh);
+
+        loop {
+            tokio::select! {
+                _ = &mut refresh => break,
+                _ = requote.notified() => break,
+                changed = prices.changed() => {
+                    if changed.is_err() {
+                        tracing::warn!("Price feed closed, no longer providing liquidity");
+                        return;
+                    }
+
+                    let index = index_price(&prices.borrow());
+                    if config.needs_requote(quoted_index, index) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Place a new bid and ask around the index price, replacing the previous ones.
+fn replace_quotes(config: LiquidityConfig, index: Decimal) -> Result<()> {
+    let websocket = state::try_get_websocket().context("No orderbook connection")?;
+    let trader_id = dlc::get_node_pubkey();
+
+    let (bid, ask) = config.quote_prices(index);
+    let expiry = OffsetDateTime::now_utc() + QUOTE_EXPIRY;
+
+    let mut new_quotes = Vec::with_capacity(2);
+    for (direction, price) in [(Direction::Long, bid), (Direction::Short, ask)] {
+        let quote = Quote {
+            order_id: Uuid::new_v4(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            direction,
+            price,
+            quantity: config.quantity,
+            index_price: index,
+            expiry,
+            withdrawn: false,
+        };
+
+        let sent = websocket.send(OrderbookRequest::InsertOrder(NewLimitOrder {
+            id: quote.order_id,
+            contract_symbol: quote.contract_symbol,
+            price: quote.price,
+            quantity: quote.quantity,
+            trader_id,
+            direction: quote.direction,
+            leverage: config.leverage,
+            expiry: quote.expiry,
+            stable: false,
+            display_quantity: None,
+            contract_expiry: None,
+        }));
+
+        if sent.is_err() {
+            // Keep track of the quotes we did place, so that they can be withdrawn.
+            QUOTES.lock().extend(new_quotes);
+            bail!("Orderbook connection closed");
+        }
+
+        new_quotes.push(quote);
+    }
+
+    tracing::debug!(%index, %bid, %ask, "Placed quotes");
+
+    // Only withdraw the previous quotes once their replacements are placed, so that the user does
+    // not stop providing liquidity in between.
+    withdraw_quotes();
+
+    let mut quotes = QUOTES.lock();
+
+    // Forget about quotes we never heard back from, e.g. because we were offline when they expired.
+    let now = OffsetDateTime::now_utc();
+    quotes.retain(|quote| quote.expiry + QUOTE_EXPIRY > now);
+    quotes.extend(new_quotes);
+
+    Ok(())
+}
+
+/// Withdraw all active quotes from the orderbook.
+///
+/// The quotes are kept until the orderbook confirms their removal, as they could have been filled
+/// in the meantime.
+fn withdraw_quotes() {
+    let websocket = state::try_get_websocket();
+
+    for quote in QUOTES.lock().iter_mut().filter(|quote| !quote.withdrawn) {
+        quote.withdrawn = true;
+
+        let sent = websocket
+            .as_ref()
+            .map(|websocket| websocket.send(OrderbookRequest::DeleteOrder(quote.order_id)));
+
+        if !matches!(sent, Some(Ok(_))) {
+            // The quote expires on its own shortly.
+            tracing::warn!(order_id = %quote.order_id, "Could not withdraw quote");
+        }
+    }
+}
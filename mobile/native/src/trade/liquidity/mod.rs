@@ -0,0 +1,222 @@
+use crate::orderbook::price_feed::Prices;
+use anyhow::ensure;
+use anyhow::Result;
+use bitcoin::Amount;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+pub mod api;
+pub mod handler;
+
+/// The widest spread the app quotes with, to protect the user from a typo.
+const MAX_SPREAD_BPS: u32 = 1_000;
+
+/// The parameters with which the app provides liquidity to the orderbook.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidityConfig {
+    /// The distance of each quote from the index price, in basis points.
+    pub spread_bps: u32,
+    /// The number of contracts of each quote.
+    pub quantity: Decimal,
+    pub leverage: Decimal,
+}
+
+/// A resting limit order the app placed on behalf of the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub order_id: Uuid,
+    pub contract_symbol: ContractSymbol,
+    pub direction: Direction,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// The index price the quote was placed around.
+    pub index_price: Decimal,
+    pub expiry: OffsetDateTime,
+    /// Whether the quote was replaced or withdrawn, i.e. it is only kept to learn if it got filled
+    /// in the meantime.
+    pub withdrawn: bool,
+}
+
+/// A quote of the user which was filled by a taker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MakerFill {
+    pub order_id: Uuid,
+    pub contract_symbol: ContractSymbol,
+    pub direction: Direction,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub index_price: Decimal,
+    pub filled_at: OffsetDateTime,
+}
+
+impl LiquidityConfig {
+    pub fn new(spread_bps: u32, quantity: Decimal, leverage: Decimal) -> Result<Self> {
+        ensure!(spread_bps > 0, "Spread must be positive");
+        ensure!(
+            spread_bps <= MAX_SPREAD_BPS,
+            "Spread must not exceed {MAX_SPREAD_BPS} bps, got {spread_bps}"
+        );
+        ensure!(
+            quantity > Decimal::ZERO,
+            "Quantity must be positive, got {quantity}"
+        );
+        ensure!(
+            leverage >= Decimal::ONE,
+            "Leverage must be at least 1, got {leverage}"
+        );
+
+        Ok(Self {
+            spread_bps,
+            quantity,
+            leverage,
+        })
+    }
+
+    /// The prices of the bid and the ask around the given index price.
+    ///
+    /// The prices are rounded away from the index price, so that the spread is never tighter than
+    /// configured.
+    pub fn quote_prices(&self, index_price: Decimal) -> (Decimal, Decimal) {
+        let spread = Decimal::from(self.spread_bps) / Decimal::from(10_000);
+
+        let bid = (index_price * (Decimal::ONE - spread))
+            .round_dp_with_strategy(1, RoundingStrategy::ToNegativeInfinity);
+        let ask = (index_price * (Decimal::ONE + spread))
+            .round_dp_with_strategy(1, RoundingStrategy::ToPositiveInfinity);
+
+        (bid, ask)
+    }
+
+    /// Whether the quotes have to be replaced, because the index price moved by more than half of
+    /// the spread since they were placed.
+    pub fn needs_requote(&self, quoted_index: Option<Decimal>, index: Option<Decimal>) -> bool {
+        match (quoted_index, index) {
+            (Some(quoted_index), Some(index)) => {
+                let threshold =
+                    quoted_index * Decimal::from(self.spread_bps) / Decimal::from(20_000);
+                (index - quoted_index).abs() > threshold
+            }
+            (None, None) => false,
+            _ => true,
+        }
+    }
+}
+
+/// The index price the quotes are placed around, i.e. the mid price of the orderbook.
+///
+/// Our own quotes are not part of the [`Prices`], otherwise we would follow ourselves.
+pub fn index_price(prices: &Prices) -> Option<Decimal> {
+    if !prices.online {
+        return None;
+    }
+
+    match (prices.bid, prices.ask) {
+        (Some(bid), Some(ask)) => Some((bid + ask) / Decimal::TWO),
+        _ => None,
+    }
+}
+
+impl MakerFill {
+    pub fn new(quote: &Quote, filled_at: OffsetDateTime) -> Self {
+        Self {
+            order_id: quote.order_id,
+            contract_symbol: quote.contract_symbol,
+            direction: quote.direction,
+            price: quote.price,
+            quantity: quote.quantity,
+            index_price: quote.index_price,
+            filled_at,
+        }
+    }
+
+    /// What the user earned with this fill, i.e. the spread between the quote and the index price
+    /// at the time of quoting.
+    pub fn earnings(&self) -> Amount {
+        let earnings_btc =
+            (Decimal::ONE / self.index_price - Decimal::ONE / self.price).abs() * self.quantity;
+        let earnings_sats = (earnings_btc * Decimal::from(100_000_000))
+            .round()
+            .to_u64()
+            .expect("to fit");
+
+        Amount::from_sat(earnings_sats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn quotes_are_placed_symmetrically_around_the_index_price() {
+        let config = LiquidityConfig::new(10, dec!(100), dec!(2)).unwrap();
+
+        let (bid, ask) = config.quote_prices(dec!(50_000.05));
+
+        assert_eq!(bid, dec!(49_950.0));
+        assert_eq!(ask, dec!(50_050.1));
+    }
+
+    #[test]
+    fn requote_once_index_moved_by_half_the_spread() {
+        let config = LiquidityConfig::new(10, dec!(100), dec!(2)).unwrap();
+
+        assert!(!config.needs_requote(Some(dec!(50_000)), Some(dec!(50_025))));
+        assert!(config.needs_requote(Some(dec!(50_000)), Some(dec!(50_026))));
+        assert!(config.needs_requote(None, Some(dec!(50_000))));
+        assert!(config.needs_requote(Some(dec!(50_000)), None));
+        assert!(!config.needs_requote(None, None));
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        assert!(LiquidityConfig::new(0, dec!(100), dec!(2)).is_err());
+        assert!(LiquidityConfig::new(1_001, dec!(100), dec!(2)).is_err());
+        assert!(LiquidityConfig::new(10, dec!(0), dec!(2)).is_err());
+        assert!(LiquidityConfig::new(10, dec!(100), dec!(0.5)).is_err());
+    }
+
+    #[test]
+    fn no_index_price_without_live_prices() {
+        let prices = Prices {
+            bid: Some(dec!(49_000)),
+            ask: Some(dec!(51_000)),
+            online: true,
+        };
+        assert_eq!(index_price(&prices), Some(dec!(50_000)));
+
+        let offline = Prices {
+            online: false,
+            ..prices
+        };
+        assert_eq!(index_price(&offline), None);
+
+        let one_sided = Prices {
+            ask: None,
+            ..prices
+        };
+        assert_eq!(index_price(&one_sided), None);
+    }
+
+    #[test]
+    fn maker_earns_the_spread_to_the_index_price() {
+        let fill = MakerFill {
+            order_id: Uuid::new_v4(),
+            contract_symbol: ContractSymbol::BtcUsd,
+            direction: Direction::Short,
+            price: dec!(50_050),
+            quantity: dec!(1_000),
+            index_price: dec!(50_000),
+            filled_at: OffsetDateTime::now_utc(),
+        };
+
+        // 1_000 / 50_000 - 1_000 / 50_050 = 0.00001998 BTC
+        assert_eq!(fill.earnings(), Amount::from_sat(1_998));
+    }
+}
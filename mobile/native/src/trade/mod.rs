@@ -1,5 +1,8 @@
 pub mod funding_fee_event;
+pub mod intent;
+pub mod liquidity;
 pub mod order;
+pub mod order_template;
 pub mod position;
 pub mod trades;
 pub mod users;
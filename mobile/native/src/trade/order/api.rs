@@ -4,11 +4,13 @@ use time::OffsetDateTime;
 use uuid::Uuid;
 pub use xxi_node::commons::ContractSymbol;
 pub use xxi_node::commons::Direction;
+use xxi_node::message_handler;
 
 #[frb(mirror(ContractSymbol))]
 #[derive(Debug, Clone, Copy)]
 pub enum _ContractSymbol {
     BtcUsd,
+    EthUsd,
 }
 
 #[frb(mirror(Direction))]
@@ -44,6 +46,7 @@ pub enum OrderReason {
     Manual,
     Expired,
     Liquidated,
+    ChannelMigration,
 }
 
 #[frb]
@@ -58,10 +61,21 @@ pub enum FailureReason {
     OrderNotAcceptable,
     TimedOut,
     InvalidDlcOffer,
+    OfferRejected(RejectReason),
     OrderRejected(String),
     Unknown,
 }
 
+#[frb]
+#[derive(Debug, Clone, Copy)]
+pub enum RejectReason {
+    Unknown,
+    InvalidOffer,
+    AcceptFailed,
+    Stale,
+    Cancelled,
+}
+
 #[frb]
 #[derive(Debug, Clone)]
 pub struct NewOrder {
@@ -140,6 +154,7 @@ impl From<order::OrderReason> for OrderReason {
             order::OrderReason::Expired => OrderReason::Expired,
             order::OrderReason::CoordinatorLiquidated => OrderReason::Liquidated,
             order::OrderReason::TraderLiquidated => OrderReason::Liquidated,
+            order::OrderReason::ChannelMigration => OrderReason::ChannelMigration,
         }
     }
 }
@@ -153,6 +168,9 @@ impl From<order::FailureReason> for FailureReason {
             order::FailureReason::OrderNotAcceptable => FailureReason::OrderNotAcceptable,
             order::FailureReason::TimedOut => FailureReason::TimedOut,
             order::FailureReason::InvalidDlcOffer(_) => FailureReason::InvalidDlcOffer,
+            order::FailureReason::OfferRejected(reason) => {
+                FailureReason::OfferRejected(reason.into())
+            }
             order::FailureReason::OrderRejected(reason) => FailureReason::OrderRejected(reason),
             order::FailureReason::CollabRevert => FailureReason::CollabRevert,
             order::FailureReason::Unknown => FailureReason::Unknown,
@@ -160,6 +178,18 @@ impl From<order::FailureReason> for FailureReason {
     }
 }
 
+impl From<message_handler::RejectReason> for RejectReason {
+    fn from(value: message_handler::RejectReason) -> Self {
+        match value {
+            message_handler::RejectReason::Unknown => RejectReason::Unknown,
+            message_handler::RejectReason::InvalidOffer => RejectReason::InvalidOffer,
+            message_handler::RejectReason::AcceptFailed => RejectReason::AcceptFailed,
+            message_handler::RejectReason::Stale => RejectReason::Stale,
+            message_handler::RejectReason::Cancelled => RejectReason::Cancelled,
+        }
+    }
+}
+
 impl From<OrderType> for order::OrderType {
     fn from(value: OrderType) -> Self {
         match value {
@@ -9,6 +9,7 @@ use crate::event::BackgroundTask;
 use crate::event::EventInternal;
 use crate::event::TaskStatus;
 use crate::report_error_to_coordinator;
+use crate::state;
 use crate::trade::order::orderbook_client::OrderbookClient;
 use crate::trade::order::FailureReason;
 use crate::trade::order::Order;
@@ -61,6 +62,8 @@ pub enum SubmitOrderError {
     },
     #[error("Failed to post order to orderbook: {0}")]
     Orderbook(anyhow::Error),
+    #[error("Disabled by the coordinator's kill switch: {0}")]
+    KillSwitch(String),
 }
 
 pub async fn submit_order(
@@ -81,10 +84,22 @@ pub async fn submit_order(
         })
 }
 
+/// Fail early if the coordinator would reject the order anyway because of its kill switch.
+pub fn check_kill_switch(opens_channel: bool) -> Result<(), SubmitOrderError> {
+    let kill_switch = state::get_kill_switch();
+
+    if !kill_switch.allows_new_orders() || (opens_channel && !kill_switch.allows_new_channels()) {
+        return Err(SubmitOrderError::KillSwitch(kill_switch.to_string()));
+    }
+
+    Ok(())
+}
+
 pub async fn submit_order_internal(
     order: Order,
     channel_opening_params: Option<ChannelOpeningParams>,
 ) -> Result<Uuid, SubmitOrderError> {
+    check_kill_switch(channel_opening_params.is_some())?;
     check_channel_state().await?;
 
     // Having an order in `Filling` should mean that the subchannel is in the midst of an update.
@@ -330,7 +345,9 @@ pub(crate) fn order_failed(
     let task_status = TaskStatus::Failed(format!("{error:#}"));
     let task = match order {
         Some(order) => match order.reason {
-            OrderReason::Manual => BackgroundTask::AsyncTrade(task_status),
+            OrderReason::Manual | OrderReason::ChannelMigration => {
+                BackgroundTask::AsyncTrade(task_status)
+            }
             OrderReason::Expired => BackgroundTask::Expire(task_status),
             OrderReason::CoordinatorLiquidated | OrderReason::TraderLiquidated => {
                 BackgroundTask::Expire(task_status)
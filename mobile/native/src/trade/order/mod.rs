@@ -9,10 +9,11 @@ use uuid::Uuid;
 use xxi_node::commons;
 use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::Direction;
+use xxi_node::message_handler::RejectReason;
 
 pub mod api;
 pub mod handler;
-mod orderbook_client;
+pub(crate) mod orderbook_client;
 
 // When naming this the same as `api_model::order::OrderType` the generated code somehow uses
 // `trade::OrderType` and contains errors, hence different name is used.
@@ -40,6 +41,8 @@ pub enum FailureReason {
     /// The order timed out, i.e. we did not receive a match in time
     TimedOut,
     InvalidDlcOffer(InvalidSubchannelOffer),
+    /// We rejected the DLC protocol offer for the order
+    OfferRejected(RejectReason),
     /// The order has been rejected by the orderbook
     OrderRejected(String),
     Unknown,
@@ -164,6 +167,7 @@ pub enum OrderReason {
     Expired,
     CoordinatorLiquidated,
     TraderLiquidated,
+    ChannelMigration,
 }
 
 impl From<OrderReason> for commons::OrderReason {
@@ -173,6 +177,7 @@ impl From<OrderReason> for commons::OrderReason {
             OrderReason::Expired => commons::OrderReason::Expired,
             OrderReason::CoordinatorLiquidated => commons::OrderReason::CoordinatorLiquidated,
             OrderReason::TraderLiquidated => commons::OrderReason::TraderLiquidated,
+            OrderReason::ChannelMigration => commons::OrderReason::ChannelMigration,
         }
     }
 }
@@ -184,6 +189,7 @@ impl From<commons::OrderReason> for OrderReason {
             commons::OrderReason::Expired => OrderReason::Expired,
             commons::OrderReason::CoordinatorLiquidated => OrderReason::CoordinatorLiquidated,
             commons::OrderReason::TraderLiquidated => OrderReason::TraderLiquidated,
+            commons::OrderReason::ChannelMigration => OrderReason::ChannelMigration,
         }
     }
 }
@@ -261,6 +267,7 @@ impl From<Order> for commons::NewMarketOrder {
             leverage: Decimal::from_f32(order.leverage).expect("to fit into f32"),
             expiry: order.order_expiry_timestamp,
             stable: order.stable,
+            contract_expiry: None,
         }
     }
 }
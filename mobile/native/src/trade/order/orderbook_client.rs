@@ -1,12 +1,16 @@
 use crate::commons::reqwest_client;
 use crate::dlc::get_node_key;
+use crate::session;
+use crate::state;
 use anyhow::bail;
 use anyhow::Result;
 use reqwest::Url;
+use uuid::Uuid;
 use xxi_node::commons::ChannelOpeningParams;
 use xxi_node::commons::NewMarketOrder;
 use xxi_node::commons::NewOrder;
 use xxi_node::commons::NewOrderRequest;
+use xxi_node::commons::Order;
 
 pub struct OrderbookClient {
     url: Url,
@@ -29,18 +33,37 @@ impl OrderbookClient {
             value: NewOrder::Market(order),
             signature,
             channel_opening_params,
+            client_info: Some(state::get_client_info()),
         };
 
-        let url = self.url.join("/api/orderbook/orders")?;
+        let url = self.url.join("/api/v2/orderbook/orders")?;
         let client = reqwest_client();
 
-        let response = client.post(url).json(&new_order_request).send().await?;
+        let response = session::send(client.post(url).json(&new_order_request)).await?;
 
-        if response.status().as_u16() == 200 {
+        if response.status().is_success() {
             Ok(())
         } else {
             let error = response.text().await?;
             bail!("Could not create new order: {error}")
         }
     }
+
+    /// Fetch an order from the orderbook, e.g. to learn if it was matched or has expired.
+    pub(crate) async fn get_order(&self, order_id: Uuid) -> Result<Order> {
+        let url = self
+            .url
+            .join(&format!("/api/v2/orderbook/orders/{order_id}"))?;
+        let client = reqwest_client();
+
+        let response = session::send(client.get(url)).await?;
+
+        if response.status().is_success() {
+            let order = response.json().await?;
+            Ok(order)
+        } else {
+            let error = response.text().await?;
+            bail!("Could not get order {order_id}: {error}")
+        }
+    }
 }
@@ -0,0 +1,72 @@
+use crate::trade::order_template;
+use flutter_rust_bridge::frb;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+#[frb]
+#[derive(Debug, Clone)]
+pub struct OrderTemplate {
+    pub name: String,
+    pub contract_symbol: ContractSymbol,
+    pub direction: Direction,
+    pub quantity: Box<TemplateQuantity>,
+    pub leverage: f32,
+    pub created_at: i64,
+}
+
+#[frb]
+#[derive(Debug, Clone, Copy)]
+pub enum TemplateQuantity {
+    /// A fixed number of contracts.
+    Contracts { quantity: f32 },
+    /// A percentage of the max quantity at the time the template is applied.
+    PercentOfMax { percent: f32 },
+}
+
+impl From<order_template::OrderTemplate> for OrderTemplate {
+    fn from(value: order_template::OrderTemplate) -> Self {
+        OrderTemplate {
+            name: value.name,
+            contract_symbol: value.contract_symbol,
+            direction: value.direction,
+            quantity: Box::new(value.quantity.into()),
+            leverage: value.leverage.to_f32().expect("to fit"),
+            created_at: value.created_at.unix_timestamp(),
+        }
+    }
+}
+
+impl From<order_template::TemplateQuantity> for TemplateQuantity {
+    fn from(value: order_template::TemplateQuantity) -> Self {
+        match value {
+            order_template::TemplateQuantity::Contracts(quantity) => TemplateQuantity::Contracts {
+                quantity: quantity.to_f32().expect("to fit"),
+            },
+            order_template::TemplateQuantity::PercentOfMax(percent) => {
+                TemplateQuantity::PercentOfMax {
+                    percent: percent.to_f32().expect("to fit"),
+                }
+            }
+        }
+    }
+}
+
+impl From<TemplateQuantity> for order_template::TemplateQuantity {
+    fn from(value: TemplateQuantity) -> Self {
+        match value {
+            TemplateQuantity::Contracts { quantity } => {
+                order_template::TemplateQuantity::Contracts(
+                    Decimal::from_f32(quantity).expect("to fit"),
+                )
+            }
+            TemplateQuantity::PercentOfMax { percent } => {
+                order_template::TemplateQuantity::PercentOfMax(
+                    Decimal::from_f32(percent).expect("to fit"),
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::db;
+use crate::event;
+use crate::event::EventInternal;
+use crate::max_quantity::max_quantity;
+use crate::orderbook::price_feed;
+use crate::state;
+use crate::trade::order::api::NewOrder;
+use crate::trade::order::api::OrderType;
+use crate::trade::order_template::OrderTemplate;
+use crate::trade::order_template::TradeLimits;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use xxi_node::commons::Direction;
+
+pub fn create_order_template(template: OrderTemplate) -> Result<()> {
+    ensure!(
+        db::get_order_template(&template.name)?.is_none(),
+        "An order template named {} already exists",
+        template.name
+    );
+
+    db::insert_order_template(template)?;
+    event::publish(&EventInternal::OrderTemplatesUpdated);
+
+    Ok(())
+}
+
+pub fn get_order_templates() -> Result<Vec<OrderTemplate>> {
+    db::get_order_templates()
+}
+
+pub fn delete_order_template(name: &str) -> Result<()> {
+    ensure!(
+        db::delete_order_template(name)?,
+        "No order template named {name}"
+    );
+
+    event::publish(&EventInternal::OrderTemplatesUpdated);
+
+    Ok(())
+}
+
+/// Turn the order template into a market order, validated against the current prices and trading
+/// limits.
+pub fn apply_order_template(name: &str) -> Result<NewOrder> {
+    let template =
+        db::get_order_template(name)?.with_context(|| format!("No order template named {name}"))?;
+
+    let config = state::try_get_tentenone_config().context("We can't trade without LSP config")?;
+
+    let prices = price_feed::latest();
+    ensure!(
+        prices.online,
+        "Can't apply order template without live prices"
+    );
+
+    // A market order is filled at the best price on the opposite side of the orderbook.
+    let price = match template.direction {
+        Direction::Long => prices.ask,
+        Direction::Short => prices.bid,
+    }
+    .context("No price available in the orderbook")?;
+
+    let leverage = template.leverage.to_f32().expect("to fit");
+
+    let limits = TradeLimits {
+        min_quantity: Decimal::from(config.min_quantity),
+        max_quantity: max_quantity(price, leverage, template.direction)?,
+        max_leverage: Decimal::from(config.max_leverage),
+    };
+    let quantity = template.quantity(limits)?;
+
+    Ok(NewOrder {
+        leverage,
+        quantity: quantity.to_f32().expect("to fit"),
+        contract_symbol: template.contract_symbol,
+        direction: template.direction,
+        order_type: Box::new(OrderType::Market),
+        stable: false,
+    })
+}
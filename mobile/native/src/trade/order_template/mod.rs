@@ -0,0 +1,171 @@
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Result;
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use xxi_node::commons::ContractSymbol;
+use xxi_node::commons::Direction;
+
+pub mod api;
+pub mod handler;
+
+/// A named set of order parameters, so that the user can re-enter a similar position with a
+/// single tap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderTemplate {
+    pub name: String,
+    pub contract_symbol: ContractSymbol,
+    pub direction: Direction,
+    pub quantity: TemplateQuantity,
+    pub leverage: Decimal,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemplateQuantity {
+    /// A fixed number of contracts.
+    Contracts(Decimal),
+    /// A percentage of the max quantity the user can trade when the template is applied.
+    PercentOfMax(Decimal),
+}
+
+/// The trading limits at the time a template is applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeLimits {
+    pub min_quantity: Decimal,
+    pub max_quantity: Decimal,
+    pub max_leverage: Decimal,
+}
+
+impl OrderTemplate {
+    pub fn new(
+        name: String,
+        contract_symbol: ContractSymbol,
+        direction: Direction,
+        quantity: TemplateQuantity,
+        leverage: Decimal,
+    ) -> Result<Self> {
+        let template = Self {
+            name: name.trim().to_string(),
+            contract_symbol,
+            direction,
+            quantity,
+            leverage,
+            created_at: OffsetDateTime::now_utc(),
+        };
+
+        template.validate()?;
+
+        Ok(template)
+    }
+
+    /// The quantity to trade with this template, given the current [`TradeLimits`].
+    ///
+    /// Fails if the resulting quantity or the template's leverage is outside of the limits.
+    pub fn quantity(&self, limits: TradeLimits) -> Result<Decimal> {
+        ensure!(
+            self.leverage <= limits.max_leverage,
+            "Leverage {} exceeds the max leverage of {}",
+            self.leverage,
+            limits.max_leverage
+        );
+
+        let quantity = match self.quantity {
+            TemplateQuantity::Contracts(quantity) => quantity,
+            TemplateQuantity::PercentOfMax(percent) => {
+                (limits.max_quantity * percent / Decimal::ONE_HUNDRED).floor()
+            }
+        };
+
+        ensure!(
+            quantity >= limits.min_quantity,
+            "Quantity {quantity} is below the min quantity of {}",
+            limits.min_quantity
+        );
+        ensure!(
+            quantity <= limits.max_quantity,
+            "Quantity {quantity} exceeds the max quantity of {}",
+            limits.max_quantity
+        );
+
+        Ok(quantity)
+    }
+
+    fn validate(&self) -> Result<()> {
+        ensure!(!self.name.is_empty(), "Template name must not be empty");
+        ensure!(
+            self.leverage >= Decimal::ONE,
+            "Leverage must be at least 1, got {}",
+            self.leverage
+        );
+
+        match self.quantity {
+            TemplateQuantity::Contracts(quantity) if quantity <= Decimal::ZERO => {
+                bail!("Quantity must be positive, got {quantity}")
+            }
+            TemplateQuantity::PercentOfMax(percent)
+                if percent <= Decimal::ZERO || percent > Decimal::ONE_HUNDRED =>
+            {
+                bail!("Percentage of max quantity must be in (0, 100], got {percent}")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn limits() -> TradeLimits {
+        TradeLimits {
+            min_quantity: dec!(1),
+            max_quantity: dec!(1_000),
+            max_leverage: dec!(5),
+        }
+    }
+
+    fn template(quantity: TemplateQuantity, leverage: Decimal) -> Result<OrderTemplate> {
+        OrderTemplate::new(
+            "scalp".to_string(),
+            ContractSymbol::BtcUsd,
+            Direction::Long,
+            quantity,
+            leverage,
+        )
+    }
+
+    #[test]
+    fn percentage_of_max_quantity_is_rounded_down() {
+        let template = template(TemplateQuantity::PercentOfMax(dec!(33.33)), dec!(2)).unwrap();
+
+        assert_eq!(template.quantity(limits()).unwrap(), dec!(333));
+    }
+
+    #[test]
+    fn template_outside_of_limits_is_rejected() {
+        let too_large = template(TemplateQuantity::Contracts(dec!(1_001)), dec!(2)).unwrap();
+        let too_small = template(TemplateQuantity::PercentOfMax(dec!(0.01)), dec!(2)).unwrap();
+        let too_leveraged = template(TemplateQuantity::Contracts(dec!(100)), dec!(10)).unwrap();
+
+        assert!(too_large.quantity(limits()).is_err());
+        assert!(too_small.quantity(limits()).is_err());
+        assert!(too_leveraged.quantity(limits()).is_err());
+    }
+
+    #[test]
+    fn invalid_template_cannot_be_created() {
+        assert!(template(TemplateQuantity::Contracts(dec!(0)), dec!(2)).is_err());
+        assert!(template(TemplateQuantity::PercentOfMax(dec!(101)), dec!(2)).is_err());
+        assert!(template(TemplateQuantity::Contracts(dec!(100)), dec!(0.5)).is_err());
+        assert!(OrderTemplate::new(
+            " ".to_string(),
+            ContractSymbol::BtcUsd,
+            Direction::Short,
+            TemplateQuantity::Contracts(dec!(100)),
+            dec!(2),
+        )
+        .is_err());
+    }
+}
@@ -55,6 +55,22 @@ pub struct Position {
     pub stable: bool,
 }
 
+/// The risk of the current position once funding fees have accrued.
+#[frb]
+#[derive(Debug, Clone)]
+pub struct PositionRisk {
+    /// The price at which the position gets liquidated, given the collateral left after funding
+    /// fees.
+    pub liquidation_price: f32,
+    /// The maintenance margin as a share of the collateral. The position is liquidated at its
+    /// entry price once this reaches 1.
+    pub margin_ratio: f32,
+    pub collateral: u64,
+    /// The funding fees which have not been paid yet, in sats. A positive amount indicates that
+    /// the trader pays.
+    pub outstanding_funding_fee: i64,
+}
+
 impl From<position::PositionState> for PositionState {
     fn from(value: position::PositionState) -> Self {
         match value {
@@ -82,3 +98,14 @@ impl From<position::Position> for Position {
         }
     }
 }
+
+impl From<position::PositionRisk> for PositionRisk {
+    fn from(value: position::PositionRisk) -> Self {
+        PositionRisk {
+            liquidation_price: value.liquidation_price,
+            margin_ratio: value.margin_ratio,
+            collateral: value.collateral.to_sat(),
+            outstanding_funding_fee: value.outstanding_funding_fee.to_sat(),
+        }
+    }
+}
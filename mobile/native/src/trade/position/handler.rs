@@ -1,8 +1,10 @@
 use crate::db;
 use crate::event;
 use crate::event::EventInternal;
+use crate::get_maintenance_margin_rate;
 use crate::trade::order::Order;
 use crate::trade::position::Position;
+use crate::trade::position::PositionRisk;
 use crate::trade::position::PositionState;
 use crate::trade::trades::handler::new_trades;
 use crate::trade::FundingFeeEvent;
@@ -19,6 +21,24 @@ pub fn get_positions() -> Result<Vec<Position>> {
     db::get_positions()
 }
 
+/// The risk of the current position, if any, accounting for the funding fee events which have
+/// not been paid yet.
+pub fn get_position_risk() -> Result<Option<PositionRisk>> {
+    let position = match db::get_positions()?.first() {
+        Some(position) => *position,
+        None => return Ok(None),
+    };
+
+    let unpaid_funding_fee_events = db::get_all_funding_fee_events()?
+        .into_iter()
+        .filter(|event| event.paid_date.is_none())
+        .collect::<Vec<_>>();
+
+    let risk = position.risk(&unpaid_funding_fee_events, get_maintenance_margin_rate());
+
+    Ok(Some(risk))
+}
+
 /// Update the position once an order was submitted
 ///
 /// If the new order submitted is an order that closes the current position, then the position will
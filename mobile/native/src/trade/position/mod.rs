@@ -18,6 +18,7 @@ use rust_decimal::RoundingStrategy;
 use serde::Serialize;
 use time::OffsetDateTime;
 use xxi_node::cfd::calculate_leverage;
+use xxi_node::cfd::calculate_margin_ratio;
 use xxi_node::commons;
 use xxi_node::commons::ContractSymbol;
 use xxi_node::commons::Direction;
@@ -83,6 +84,20 @@ pub struct Position {
     pub order_matching_fees: Amount,
 }
 
+/// The risk of a [`Position`] once funding fees have accrued.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionRisk {
+    /// The price at which the position gets liquidated, given the collateral left after funding
+    /// fees.
+    pub liquidation_price: f32,
+    /// The maintenance margin as a share of the collateral, see [`calculate_margin_ratio`].
+    pub margin_ratio: f32,
+    pub collateral: Amount,
+    /// The funding fees which have not been paid in the DLC channel yet. A positive amount
+    /// indicates that the trader pays the coordinator.
+    pub outstanding_funding_fee: SignedAmount,
+}
+
 impl Position {
     /// Construct a new open position from an initial [`OrderState::Filled`] order.
     pub fn new_open(order: Order, expiry: OffsetDateTime) -> (Self, Trade) {
@@ -607,6 +622,34 @@ impl Position {
         })
     }
 
+    /// The risk of the position, given the funding fee events which have not been paid yet.
+    ///
+    /// The funding fees paid by the trader are applied to the position as soon as the events are
+    /// received, so the liquidation price of the position is already the effective one. Funding
+    /// fees earned by the trader are added to their collateral reserve and leave the liquidation
+    /// price unchanged.
+    pub fn risk(
+        &self,
+        unpaid_funding_fee_events: &[FundingFeeEvent],
+        maintenance_margin_rate: Decimal,
+    ) -> PositionRisk {
+        let outstanding_funding_fee = unpaid_funding_fee_events
+            .iter()
+            .filter(|event| event.contract_symbol == self.contract_symbol)
+            .map(|event| event.fee)
+            .sum();
+
+        let margin_ratio =
+            calculate_margin_ratio(decimal_from_f32(self.leverage), maintenance_margin_rate);
+
+        PositionRisk {
+            liquidation_price: self.liquidation_price,
+            margin_ratio: f32_from_decimal(margin_ratio),
+            collateral: Amount::from_sat(self.collateral),
+            outstanding_funding_fee,
+        }
+    }
+
     /// Start rollover protocol.
     fn start_rollover(self, expiry: OffsetDateTime) -> Self {
         Self {
@@ -733,6 +776,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn position_risk_accounts_for_unpaid_funding_fees() {
+        let now = OffsetDateTime::now_utc();
+
+        let position = Position {
+            leverage: 2.0,
+            quantity: 10.0,
+            contract_symbol: ContractSymbol::BtcUsd,
+            direction: Direction::Long,
+            average_entry_price: 36_469.5,
+            liquidation_price: 24_313.0,
+            position_state: PositionState::Open,
+            collateral: 13_710,
+            expiry: now,
+            updated: now,
+            created: now,
+            stable: false,
+            order_matching_fees: Amount::from_sat(1000),
+        };
+
+        let funding_fee_event = |fee| FundingFeeEvent {
+            contract_symbol: ContractSymbol::BtcUsd,
+            contracts: dec!(10),
+            direction: Direction::Long,
+            price: dec!(36_000),
+            fee: SignedAmount::from_sat(fee),
+            due_date: now,
+            paid_date: None,
+        };
+
+        let risk = position.risk(&[funding_fee_event(100), funding_fee_event(-30)], dec!(0.1));
+
+        assert_eq!(
+            risk,
+            PositionRisk {
+                liquidation_price: 24_313.0,
+                margin_ratio: 0.2,
+                collateral: Amount::from_sat(13_710),
+                outstanding_funding_fee: SignedAmount::from_sat(70),
+            }
+        );
+    }
+
     #[test]
     fn close_position() {
         let now = OffsetDateTime::now_utc();
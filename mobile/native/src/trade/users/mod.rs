@@ -1,10 +1,14 @@
 use crate::commons::reqwest_client;
 use crate::config;
 use crate::dlc;
+use crate::session;
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
+use std::str::FromStr;
+use xxi_node::commons::Locale;
 use xxi_node::commons::RegisterParams;
+use xxi_node::commons::ReserveInterest;
 use xxi_node::commons::UpdateUsernameParams;
 use xxi_node::commons::User;
 
@@ -13,7 +17,16 @@ pub async fn register_beta(
     contact: String,
     version: String,
     referral_code: Option<String>,
+    locale: Option<String>,
 ) -> Result<()> {
+    let locale = locale.and_then(|locale| match Locale::from_str(&locale) {
+        Ok(locale) => Some(locale),
+        Err(e) => {
+            tracing::warn!("Falling back to default locale: {e:#}");
+            None
+        }
+    });
+
     let name = crate::names::get_new_name();
     let register = RegisterParams {
         pubkey: dlc::get_node_pubkey(),
@@ -22,19 +35,24 @@ pub async fn register_beta(
         version: Some(version.clone()),
         os: Some(std::env::consts::OS.to_string()),
         referral_code,
+        locale,
     };
 
     tracing::debug!(
         pubkey = register.pubkey.to_string(),
         contact = register.contact,
         referral_code = register.referral_code,
+        locale = ?register.locale,
         version,
         "Registering user"
     );
 
     let client = reqwest_client();
     let response = client
-        .post(format!("http://{}/api/users", config::get_http_endpoint()))
+        .post(format!(
+            "http://{}/api/v2/users",
+            config::get_http_endpoint()
+        ))
         .json(&register)
         .send()
         .await
@@ -61,13 +79,12 @@ pub async fn get_user_details() -> Result<User> {
     let key = dlc::get_node_pubkey();
 
     let client = reqwest_client();
-    let response = client
-        .get(format!(
-            "http://{}/api/users/{}",
-            config::get_http_endpoint(),
-            key
-        ))
-        .send()
+    let request = client.get(format!(
+        "http://{}/api/v2/users/{}",
+        config::get_http_endpoint(),
+        key
+    ));
+    let response = session::send(request)
         .await
         .context("Failed to retrieve user details")?;
 
@@ -77,6 +94,26 @@ pub async fn get_user_details() -> Result<User> {
     Ok(user)
 }
 
+/// Retrieve the interest accrued on the user's collateral reserve
+pub async fn get_reserve_interest() -> Result<ReserveInterest> {
+    let key = dlc::get_node_pubkey();
+
+    let client = reqwest_client();
+    let request = client.get(format!(
+        "http://{}/api/v2/reserve-interest/{}",
+        config::get_http_endpoint(),
+        key
+    ));
+    let response = session::send(request)
+        .await
+        .context("Failed to retrieve reserve interest")?
+        .error_for_status()?;
+
+    let reserve_interest = response.json::<ReserveInterest>().await?;
+
+    Ok(reserve_interest)
+}
+
 /// Update a user's name on the coordinator
 pub async fn update_username(name: String) -> Result<()> {
     let update_nickname = UpdateUsernameParams {
@@ -93,7 +130,7 @@ pub async fn update_username(name: String) -> Result<()> {
     let client = reqwest_client();
     let response = client
         .put(format!(
-            "http://{}/api/users/nickname",
+            "http://{}/api/v2/users/nickname",
             config::get_http_endpoint()
         ))
         .json(&update_nickname)
@@ -8,7 +8,9 @@ use crate::trade::order::api::NewOrder;
 use crate::watcher;
 use anyhow::Error;
 use bitcoin::Amount;
+use xxi_node::commons::round_collateral_reserve;
 use xxi_node::commons::ChannelOpeningParams;
+use xxi_node::commons::ReserveRounding;
 
 pub struct ExternalFunding {
     pub bitcoin_address: String,
@@ -32,6 +34,12 @@ pub async fn submit_unfunded_channel_opening_order(
     estimated_margin: u64,
     order_matching_fee: u64,
 ) -> anyhow::Result<ExternalFunding, Error> {
+    order::handler::check_kill_switch(true)?;
+
+    // The trader pays for the reserve, so a dust reserve is dropped instead of charged for.
+    let trader_reserve =
+        round_collateral_reserve(Amount::from_sat(trader_reserve), ReserveRounding::Down);
+
     let node = get_node();
     let bitcoin_address = node.inner.get_new_address()?;
 
@@ -39,7 +47,7 @@ pub async fn submit_unfunded_channel_opening_order(
         + crate::dlc::estimated_fee_reserve()?
         + crate::dlc::estimated_funding_tx_fee()?;
 
-    let funding_amount = Amount::from_sat(estimated_margin + trader_reserve) + fees;
+    let funding_amount = Amount::from_sat(estimated_margin) + trader_reserve + fees;
     let hodl_invoice = hodl_invoice::get_hodl_invoice_from_coordinator(funding_amount)
         .await
         .inspect_err(|e| tracing::error!("Failed to get hodl invoice. Error: {e:#}"))
@@ -88,7 +96,7 @@ pub async fn submit_unfunded_channel_opening_order(
                 order.into(),
                 Some(ChannelOpeningParams {
                     coordinator_reserve: Amount::from_sat(coordinator_reserve),
-                    trader_reserve: Amount::from_sat(trader_reserve),
+                    trader_reserve,
                     pre_image: maybe_pre_image,
                 })
             )
@@ -0,0 +1,106 @@
+//! The descriptors of the on-chain wallet are part of the backup, so that a restored wallet can
+//! detect if it derives its addresses differently than the wallet which was backed up, e.g.
+//! because the derivation changed between versions. Funds on the addresses of the backed up
+//! descriptors would otherwise be silently invisible.
+
+use crate::backup::DBBackupSubscriber;
+use crate::backup::RemoteBackupClient;
+use crate::backup::WALLET_BACKUP_KEY;
+use crate::backup::WALLET_DESCRIPTORS_BACKUP_NAME;
+use crate::config;
+use crate::db;
+use crate::dlc::node::Node;
+use crate::event;
+use crate::event::EventInternal;
+use anyhow::Context;
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use xxi_node::WalletDescriptors;
+use xxi_node::Watch;
+
+/// The backed up descriptors are kept in this file after a restore, until they have been compared
+/// with the descriptors of the restored wallet.
+const RESTORED_DESCRIPTORS_FILE_NAME: &str = "restored-wallet-descriptors.json";
+
+/// How many addresses of each backed up descriptor are watched if the restored wallet derives
+/// different descriptors.
+const WATCHED_ADDRESSES_PER_DESCRIPTOR: u32 = 100;
+
+/// Upload the descriptors of the wallet.
+pub fn back_up(node: &Node, client: &RemoteBackupClient) -> Result<()> {
+    let descriptors = node.inner.get_wallet_descriptors();
+    let value = serde_json::to_vec(&descriptors)?;
+
+    client
+        .backup(
+            format!("{WALLET_BACKUP_KEY}/{WALLET_DESCRIPTORS_BACKUP_NAME}"),
+            value,
+        )
+        .forget();
+
+    Ok(())
+}
+
+/// Keep the backed up descriptors until the restored wallet has been created, see
+/// [`cross_check`].
+pub fn store_restored(value: &[u8]) -> Result<()> {
+    let file = restored_descriptors_file();
+    fs::create_dir_all(file.parent().expect("parent"))?;
+    fs::write(file, value)?;
+
+    Ok(())
+}
+
+/// Compare the descriptors of a restored wallet with the backed up ones.
+///
+/// If they differ, the addresses of the backed up descriptors are watched and a
+/// [`EventInternal::WalletDescriptorMigration`] is published, so that the user can move the
+/// funds on them into the wallet.
+pub fn cross_check(node: &Node, client: &RemoteBackupClient) -> Result<()> {
+    let file = restored_descriptors_file();
+    if !file.exists() {
+        return Ok(());
+    }
+
+    let backed_up = fs::read(&file)?;
+    let backed_up = serde_json::from_slice::<WalletDescriptors>(&backed_up)
+        .context("Failed to parse backed up wallet descriptors")?;
+    let derived = node.inner.get_wallet_descriptors();
+
+    if backed_up == derived {
+        tracing::info!("Restored wallet derives the backed up descriptors");
+    } else {
+        tracing::warn!(
+            ?backed_up,
+            ?derived,
+            "Restored wallet derives different descriptors than the backed up wallet. \
+             Watching the addresses of the backed up descriptors"
+        );
+
+        let addresses =
+            backed_up.addresses(config::get_network(), WATCHED_ADDRESSES_PER_DESCRIPTOR)?;
+        for address in addresses {
+            let watch = Watch::Address(address);
+            db::insert_watch(&watch)?;
+            node.inner.watch(watch);
+        }
+
+        // The watches have to survive another restore, even though the backed up descriptors are
+        // replaced with the derived ones.
+        DBBackupSubscriber::new(client.clone()).back_up()?;
+
+        event::publish(&EventInternal::WalletDescriptorMigration { backed_up, derived });
+    }
+
+    fs::remove_file(file)?;
+
+    Ok(())
+}
+
+fn restored_descriptors_file() -> PathBuf {
+    Path::new(&config::get_data_dir())
+        .join(config::get_network().to_string())
+        .join(RESTORED_DESCRIPTORS_FILE_NAME)
+}
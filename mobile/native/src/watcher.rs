@@ -1,3 +1,7 @@
+use crate::dlc;
+use crate::dlc::ChannelState;
+use crate::dlc::SignedChannelState;
+use crate::event;
 use crate::event::subscriber::Subscriber;
 use crate::event::EventInternal;
 use crate::event::EventType;
@@ -113,3 +117,42 @@ pub(crate) async fn watch_funding_address(
         tokio::time::sleep(Duration::from_secs(10)).await;
     }
 }
+
+/// Reacts to the coordinator telling us that our DLC channel is being force-closed.
+///
+/// Instead of waiting for the next periodic sync, we sync with the chain right away, so that the
+/// user can be guided through the recovery of their funds.
+pub(crate) async fn handle_channel_force_closed() -> Result<()> {
+    tracing::warn!("Coordinator notified us that our DLC channel is being force-closed");
+
+    dlc::refresh_wallet_info().await?;
+
+    let closing_txid = dlc::list_dlc_channels()?
+        .iter()
+        .map(dlc::DlcChannel::from)
+        .find_map(|channel| force_close_txid(&channel.channel_state));
+
+    match &closing_txid {
+        Some(closing_txid) => tracing::info!(%closing_txid, "DLC channel is closing on-chain"),
+        None => tracing::warn!("Did not find the force-close transaction on-chain yet"),
+    }
+
+    event::publish(&EventInternal::ChannelClosingOnChain { closing_txid });
+
+    Ok(())
+}
+
+/// The transaction with which a DLC channel is being force-closed, if the force-close is still in
+/// progress.
+fn force_close_txid(channel_state: &ChannelState) -> Option<String> {
+    match channel_state {
+        ChannelState::Closing { buffer_txid, .. } => Some(buffer_txid.clone()),
+        ChannelState::SettledClosing { settle_txid } => Some(settle_txid.clone()),
+        ChannelState::Signed {
+            state: SignedChannelState::SettledClosing,
+            closing_txid,
+            ..
+        } => closing_txid.clone(),
+        _ => None,
+    }
+}
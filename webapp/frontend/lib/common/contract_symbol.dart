@@ -1,5 +1,6 @@
 enum ContractSymbol {
-  btcusd;
+  btcusd,
+  ethusd;
 
   String get label => "${name.substring(0, 3).toUpperCase()}/${name.substring(3).toUpperCase()}";
 }
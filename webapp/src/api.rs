@@ -1,3 +1,8 @@
+use crate::audit;
+use crate::audit::AuditEntry;
+use crate::auth::Backend;
+use crate::auth::Role;
+use crate::logs::LogLine;
 use crate::AppState;
 use anyhow::anyhow;
 use anyhow::Context;
@@ -6,13 +11,22 @@ use axum::extract::Path;
 use axum::extract::Query;
 use axum::extract::State;
 use axum::http::StatusCode;
+use axum::middleware;
+use axum::response::sse;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
 use axum::response::IntoResponse;
 use axum::response::Response;
+use axum::routing::delete;
 use axum::routing::get;
 use axum::routing::post;
 use axum::Json;
 use axum::Router;
+use axum_login::permission_required;
+use axum_login::AuthSession;
 use bitcoin::Amount;
+use futures::Stream;
+use futures::StreamExt;
 use native::api::FeeConfig;
 use native::api::WalletHistoryItemType;
 use native::calculations::calculate_pnl;
@@ -31,26 +45,55 @@ use std::fmt;
 use std::str::FromStr;
 use std::sync::Arc;
 use time::OffsetDateTime;
+use tokio::sync::broadcast::error::RecvError;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use xxi_node::commons;
 use xxi_node::commons::order_matching_fee;
 use xxi_node::commons::ChannelOpeningParams;
 
+/// The routes of the wallet and trading API, grouped by the [`Role`] required to call them.
+///
+/// Calls to routes which require more than [`Role::Viewer`] are recorded in the [`AuditLog`],
+/// including the ones which were denied.
 pub fn router(app_state: AppState) -> Router {
-    Router::new()
+    let audit_log = app_state.audit_log.clone();
+
+    let viewer = Router::new()
         .route("/api/balance", get(get_balance))
-        .route("/api/newaddress", get(get_unused_address))
-        .route("/api/sendpayment", post(send_payment))
         .route("/api/history", get(get_onchain_payment_history))
-        .route("/api/orders", get(get_orders).post(post_new_order))
+        .route("/api/orders", get(get_orders))
         .route("/api/positions", get(get_positions))
         .route("/api/quotes/:contract_symbol", get(get_best_quote))
         .route("/api/node", get(get_node_id))
+        .route("/api/channels", get(get_channels))
+        .route("/api/tradeconstraints", get(get_trade_constraints))
+        .route("/api/user", get(get_user))
+        .route_layer(permission_required!(Backend, Role::Viewer));
+
+    let trader = Router::new()
+        .route("/api/newaddress", get(get_unused_address))
+        .route("/api/orders", post(post_new_order))
         .route("/api/sync", post(post_sync))
+        .route("/api/channels", delete(close_channel))
+        .route_layer(permission_required!(Backend, Role::Trader))
+        .route_layer(middleware::from_fn_with_state(
+            audit_log.clone(),
+            audit::record,
+        ));
+
+    let admin = Router::new()
+        .route("/api/sendpayment", post(send_payment))
         .route("/api/seed", get(get_seed_phrase))
-        .route("/api/channels", get(get_channels).delete(close_channel))
-        .route("/api/tradeconstraints", get(get_trade_constraints))
+        .route("/api/audit", get(get_audit_log))
+        .route("/api/logs", get(get_logs))
+        .route("/api/logs/filter", get(get_log_filter).put(put_log_filter))
+        .route_layer(permission_required!(Backend, Role::Admin))
+        .route_layer(middleware::from_fn_with_state(audit_log, audit::record));
+
+    viewer
+        .merge(trader)
+        .merge(admin)
         .with_state(Arc::new(app_state))
 }
 
@@ -235,6 +278,120 @@ pub async fn get_node_id() -> impl IntoResponse {
     dlc::get_node_pubkey().to_string()
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct User {
+    name: String,
+    role: Role,
+}
+
+#[utoipa::path(
+get,
+path = "/api/user",
+responses(
+(status = 200, description = "Returns the logged in user and their role", body = User)
+)
+)]
+pub async fn get_user(auth_session: AuthSession<Backend>) -> Result<Json<User>, AppError> {
+    let user = auth_session.user.context("Not logged in")?;
+
+    Ok(Json(User {
+        name: user.name,
+        role: user.role,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogParams {
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+get,
+path = "/api/audit",
+params(
+    ("limit" = Option<usize>, Query, description = "The maximum number of entries, defaults to 100")
+),
+responses(
+(status = 200, description = "Returns the most recent privileged actions, newest first", body = [AuditEntry])
+)
+)]
+pub async fn get_audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AuditLogParams>,
+) -> Result<Json<Vec<AuditEntry>>, AppError> {
+    let entries = state.audit_log.entries(params.limit.unwrap_or(100))?;
+
+    Ok(Json(entries))
+}
+
+#[utoipa::path(
+get,
+path = "/api/logs",
+responses(
+(status = 200, description = "Streams the recent and all following log lines as server-sent events", body = LogLine, content_type = "text/event-stream")
+)
+)]
+pub async fn get_logs(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<sse::Event, axum::Error>>> {
+    let (buffered, receiver) = state.log_stream.subscribe();
+
+    let live = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(line) => return Some((line, receiver)),
+                // The client is too slow. Skip the lines it missed rather than disconnecting it.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let stream = futures::stream::iter(buffered)
+        .chain(live)
+        .map(|line| sse::Event::default().json_data(line));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LogFilter {
+    /// The filter directives in the format of `RUST_LOG`, e.g. `info,native=debug`.
+    directives: String,
+}
+
+#[utoipa::path(
+get,
+path = "/api/logs/filter",
+responses(
+(status = 200, description = "Returns the filter of the streamed log lines", body = LogFilter)
+)
+)]
+pub async fn get_log_filter(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LogFilter>, AppError> {
+    let directives = state.log_stream.filter()?;
+
+    Ok(Json(LogFilter { directives }))
+}
+
+#[utoipa::path(
+put,
+path = "/api/logs/filter",
+request_body = LogFilter,
+responses(
+(status = 200, description = "Replaced the filter of the streamed log lines")
+)
+)]
+pub async fn put_log_filter(
+    State(state): State<Arc<AppState>>,
+    Json(filter): Json<LogFilter>,
+) -> Result<(), AppError> {
+    state.log_stream.set_filter(&filter.directives)?;
+
+    Ok(())
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct Seed {
     seed: Vec<String>,
@@ -298,12 +455,14 @@ pub struct NewOrderParams {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
 pub enum ContractSymbol {
     BtcUsd,
+    EthUsd,
 }
 
 impl From<ContractSymbol> for commons::ContractSymbol {
     fn from(value: ContractSymbol) -> Self {
         match value {
             ContractSymbol::BtcUsd => commons::ContractSymbol::BtcUsd,
+            ContractSymbol::EthUsd => commons::ContractSymbol::EthUsd,
         }
     }
 }
@@ -311,6 +470,7 @@ impl From<commons::ContractSymbol> for ContractSymbol {
     fn from(value: commons::ContractSymbol) -> Self {
         match value {
             commons::ContractSymbol::BtcUsd => ContractSymbol::BtcUsd,
+            commons::ContractSymbol::EthUsd => ContractSymbol::EthUsd,
         }
     }
 }
@@ -0,0 +1,123 @@
+use crate::auth::Backend;
+use crate::auth::Role;
+use anyhow::Context;
+use anyhow::Result;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum_login::AuthSession;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// An append-only log of the privileged actions taken through the webapp.
+///
+/// Every entry is stored as a line of JSON.
+#[derive(Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    pub user: String,
+    pub role: Role,
+    pub method: String,
+    pub path: String,
+    /// The HTTP status code we responded with.
+    pub status: u16,
+}
+
+impl AuditLog {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Could not open audit log at {path:?}"))?;
+
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock();
+        file.write_all(&line)?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// The most recent `limit` entries, newest first.
+    pub fn entries(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        // Hold the lock so that we don't read a partially written entry.
+        let _file = self.file.lock();
+
+        let reader = BufReader::new(File::open(&self.path)?);
+
+        let mut entries = reader
+            .lines()
+            .map(|line| {
+                let entry: AuditEntry = serde_json::from_str(&line?)?;
+                anyhow::Ok(entry)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        entries.reverse();
+        entries.truncate(limit);
+
+        Ok(entries)
+    }
+}
+
+/// Middleware recording the requests made to the routes it is layered on.
+///
+/// Must only be layered on routes which require a login.
+pub async fn record(
+    State(audit_log): State<AuditLog>,
+    auth_session: AuthSession<Backend>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+
+    let response = next.run(request).await;
+
+    if let Some(user) = auth_session.user {
+        let entry = AuditEntry {
+            timestamp: OffsetDateTime::now_utc(),
+            user: user.name,
+            role: user.role,
+            method,
+            path,
+            status: response.status().as_u16(),
+        };
+
+        tracing::info!(?entry, "Privileged action");
+
+        if let Err(e) = audit_log.append(&entry) {
+            tracing::error!(?entry, "Failed to write to audit log: {e:#}");
+        }
+    }
+
+    response
+}
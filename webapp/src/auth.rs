@@ -1,34 +1,75 @@
+use anyhow::bail;
+use anyhow::Context;
 use axum::async_trait;
 use axum::routing::get;
 use axum::routing::post;
 use axum::Router;
 use axum_login::AuthUser;
 use axum_login::AuthnBackend;
+use axum_login::AuthzBackend;
 use axum_login::UserId;
 use serde::Deserialize;
+use serde::Serialize;
 use sha2::digest::FixedOutput;
 use sha2::Digest;
 use sha2::Sha256;
+use std::collections::HashSet;
 use std::error::Error;
+use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::str::FromStr;
+use std::sync::Arc;
 use utoipa::ToSchema;
 
+/// The name of the user configured with `--password`.
+pub const ADMIN_USERNAME: &str = "admin";
+
 #[derive(Clone)]
 pub struct Backend {
-    pub(crate) hashed_password: String,
+    pub(crate) users: Arc<Vec<User>>,
 }
 
 #[derive(Clone, Debug)]
 pub struct User {
+    id: u64,
+    pub name: String,
+    pub role: Role,
     password: String,
 }
 
+/// What a user is allowed to do.
+///
+/// Every role includes the permissions of the roles before it.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Can see balances, orders, positions and channels.
+    Viewer,
+    /// Can additionally submit orders, close the channel and sync the wallet.
+    Trader,
+    /// Can additionally withdraw funds, see the seed phrase and read the audit log.
+    Admin,
+}
+
 #[derive(Clone, Deserialize, ToSchema)]
 pub struct Credentials {
+    /// Defaults to the user configured with `--password`.
+    #[serde(default)]
+    pub username: Option<String>,
     pub password: String,
 }
 
+/// A user configured via `--user <name>:<role>:<password>`.
+#[derive(Clone, Debug)]
+pub struct UserConfig {
+    pub name: String,
+    pub role: Role,
+    pub hashed_password: String,
+}
+
 #[derive(std::fmt::Debug)]
 pub struct BackendError(String);
 
@@ -50,24 +91,56 @@ impl AuthnBackend for Backend {
         &self,
         creds: Self::Credentials,
     ) -> Result<Option<Self::User>, Self::Error> {
-        let mut hasher = Sha256::new();
-        hasher.update(creds.password.as_bytes());
-        let hashed_password = hex::encode(hasher.finalize_fixed());
-
-        let user = match hashed_password == self.hashed_password {
-            true => Some(User {
-                password: self.hashed_password.clone(),
-            }),
-            false => None,
-        };
+        let hashed_password = hash_password(&creds.password);
+        let username = creds.username.as_deref().unwrap_or(ADMIN_USERNAME);
+
+        let user = self
+            .users
+            .iter()
+            .find(|user| user.name == username && user.password == hashed_password)
+            .cloned();
 
         Ok(user)
     }
 
-    async fn get_user(&self, _user_id: &UserId<Self>) -> Result<Option<Self::User>, Self::Error> {
-        Ok(Some(User {
-            password: self.hashed_password.clone(),
-        }))
+    async fn get_user(&self, user_id: &UserId<Self>) -> Result<Option<Self::User>, Self::Error> {
+        Ok(self.users.iter().find(|user| user.id == *user_id).cloned())
+    }
+}
+
+#[async_trait]
+impl AuthzBackend for Backend {
+    type Permission = Role;
+
+    async fn get_user_permissions(
+        &self,
+        user: &Self::User,
+    ) -> Result<HashSet<Self::Permission>, Self::Error> {
+        let permissions = [Role::Viewer, Role::Trader, Role::Admin]
+            .into_iter()
+            .filter(|role| *role <= user.role)
+            .collect();
+
+        Ok(permissions)
+    }
+}
+
+impl Backend {
+    pub fn new(users: Vec<UserConfig>) -> Self {
+        let users = users
+            .into_iter()
+            .enumerate()
+            .map(|(id, user)| User {
+                id: id as u64,
+                name: user.name,
+                role: user.role,
+                password: user.hashed_password,
+            })
+            .collect();
+
+        Self {
+            users: Arc::new(users),
+        }
     }
 }
 
@@ -75,7 +148,7 @@ impl AuthUser for User {
     type Id = u64;
 
     fn id(&self) -> Self::Id {
-        0
+        self.id
     }
 
     fn session_auth_hash(&self) -> &[u8] {
@@ -83,6 +156,61 @@ impl AuthUser for User {
     }
 }
 
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let role = match self {
+            Role::Viewer => "viewer",
+            Role::Trader => "trader",
+            Role::Admin => "admin",
+        };
+
+        role.fmt(f)
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let role = match s {
+            "viewer" => Role::Viewer,
+            "trader" => Role::Trader,
+            "admin" => Role::Admin,
+            role => bail!("Unknown role: {role}"),
+        };
+
+        Ok(role)
+    }
+}
+
+impl FromStr for UserConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+
+        let name = parts.next().context("Missing user name")?;
+        let role = parts.next().context("Missing user role")?;
+        let password = parts.next().context("Missing user password")?;
+
+        if name.is_empty() || name == ADMIN_USERNAME {
+            bail!("Invalid user name: {name:?}");
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            role: role.parse()?,
+            hashed_password: hash_password(password),
+        })
+    }
+}
+
+pub fn hash_password(password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    hex::encode(hasher.finalize_fixed())
+}
+
 pub fn router() -> Router {
     Router::new()
         .route("/api/login", post(post::login))
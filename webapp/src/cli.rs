@@ -1,9 +1,11 @@
+use crate::auth::hash_password;
+use crate::auth::Role;
+use crate::auth::UserConfig;
+use crate::auth::ADMIN_USERNAME;
 use anyhow::ensure;
+use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
-use sha2::digest::FixedOutput;
-use sha2::Digest;
-use sha2::Sha256;
 use std::env::current_dir;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -41,9 +43,16 @@ pub struct Opts {
     #[clap(long)]
     cert_dir: Option<PathBuf>,
 
+    /// The password of the `admin` user.
     #[clap(long, default_value = "satoshi")]
     password: String,
 
+    /// Additional users, given as `<name>:<role>:<password>`. The role is one of `viewer`,
+    /// `trader` or `admin`.
+    #[arg(num_args(0..))]
+    #[clap(long)]
+    user: Vec<String>,
+
     #[clap(long)]
     pub secure: bool,
 
@@ -59,6 +68,12 @@ pub struct Opts {
     /// The location where our memes are hosted
     #[clap(long, default_value = "https://localhost:8080/memes/")]
     pub meme_endpoint: String,
+
+    /// The HTTP endpoints (`host:port`) of further coordinators, by priority. They only serve
+    /// the prices while the coordinator is unreachable.
+    #[arg(num_args(0..))]
+    #[clap(long)]
+    pub fallback_coordinator: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
@@ -90,10 +105,26 @@ impl Opts {
         self.network.into()
     }
 
-    pub fn password(&self) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(self.password.as_bytes());
-        hex::encode(hasher.finalize_fixed())
+    pub fn users(&self) -> Result<Vec<UserConfig>> {
+        let admin = UserConfig {
+            name: ADMIN_USERNAME.to_string(),
+            role: Role::Admin,
+            hashed_password: hash_password(&self.password),
+        };
+
+        let mut users = vec![admin];
+        for user in self.user.iter() {
+            let user = UserConfig::from_str(user).context("Invalid user")?;
+            ensure!(
+                users.iter().all(|other| other.name != user.name),
+                "Duplicate user: {}",
+                user.name
+            );
+
+            users.push(user);
+        }
+
+        Ok(users)
     }
 
     pub fn data_dir(&self) -> Result<PathBuf> {
@@ -1,3 +1,4 @@
+use crate::logs::LogStream;
 use anyhow::Context;
 use anyhow::Result;
 use time::macros::format_description;
@@ -12,9 +13,15 @@ use tracing_subscriber::Layer;
 const RUST_LOG_ENV: &str = "RUST_LOG";
 
 // Configure and initialise tracing subsystem
-pub fn init_tracing(level: LevelFilter, json_format: bool, tokio_console: bool) -> Result<()> {
+pub fn init_tracing(
+    level: LevelFilter,
+    json_format: bool,
+    tokio_console: bool,
+) -> Result<LogStream> {
+    let (log_stream, log_stream_layer) = LogStream::new()?;
+
     if level == LevelFilter::OFF {
-        return Ok(());
+        return Ok(log_stream);
     }
 
     let is_terminal = atty::is(atty::Stream::Stderr);
@@ -81,6 +88,7 @@ pub fn init_tracing(level: LevelFilter, json_format: bool, tokio_console: bool)
     };
 
     tracing_subscriber::registry()
+        .with(log_stream_layer)
         .with(filter)
         .with(console_layer)
         .with(fmt_layer)
@@ -89,5 +97,5 @@ pub fn init_tracing(level: LevelFilter, json_format: bool, tokio_console: bool)
 
     tracing::info!("Initialized logger");
 
-    Ok(())
+    Ok(log_stream)
 }
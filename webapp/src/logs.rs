@@ -0,0 +1,167 @@
+use anyhow::Context;
+use anyhow::Result;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Write;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::Event;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context as LayerContext;
+use tracing_subscriber::reload;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::Registry;
+use utoipa::ToSchema;
+
+/// The number of log lines replayed to a client when it starts streaming.
+const BUFFERED_LINES: usize = 1000;
+
+/// The log lines shown in the browser, unless adjusted at runtime.
+pub const DEFAULT_FILTER: &str = "info";
+
+/// The live logs of the webapp and its native layer, so that self-hosters can follow them in the
+/// browser instead of having to ssh into the machine.
+///
+/// The most recent lines are kept in a ring buffer. The filter deciding which lines are kept is
+/// independent of the one of the stderr output and can be adjusted at runtime.
+#[derive(Clone)]
+pub struct LogStream {
+    buffer: Arc<Mutex<VecDeque<LogLine>>>,
+    sender: broadcast::Sender<LogLine>,
+    filter: reload::Handle<EnvFilter, Registry>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LogLine {
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    pub level: String,
+    pub target: String,
+    /// The message of the event, followed by its fields.
+    pub message: String,
+}
+
+/// Records the events passing the filter of the [`LogStream`].
+struct LogStreamLayer {
+    buffer: Arc<Mutex<VecDeque<LogLine>>>,
+    sender: broadcast::Sender<LogLine>,
+}
+
+impl LogStream {
+    /// Create the log stream and the layer feeding it, which has to be added to the
+    /// [`Registry`] directly.
+    pub fn new() -> Result<(Self, impl Layer<Registry>)> {
+        let filter = EnvFilter::try_new(DEFAULT_FILTER)?;
+        let (filter, handle) = reload::Layer::new(filter);
+
+        let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(BUFFERED_LINES)));
+        let (sender, _) = broadcast::channel(BUFFERED_LINES);
+
+        let layer = LogStreamLayer {
+            buffer: buffer.clone(),
+            sender: sender.clone(),
+        }
+        .with_filter(filter);
+
+        let log_stream = Self {
+            buffer,
+            sender,
+            filter: handle,
+        };
+
+        Ok((log_stream, layer))
+    }
+
+    /// The buffered log lines, oldest first, and a receiver for the ones logged afterwards.
+    pub fn subscribe(&self) -> (Vec<LogLine>, broadcast::Receiver<LogLine>) {
+        // Holding the lock while subscribing ensures that no line is missed or sent twice.
+        let buffer = self.buffer.lock();
+        let receiver = self.sender.subscribe();
+
+        (buffer.iter().cloned().collect(), receiver)
+    }
+
+    pub fn filter(&self) -> Result<String> {
+        self.filter
+            .with_current(|filter| filter.to_string())
+            .context("Logger has been shut down")
+    }
+
+    /// Replace the filter of the log stream, e.g. with `debug,native=trace`.
+    pub fn set_filter(&self, directives: &str) -> Result<()> {
+        let filter = EnvFilter::try_new(directives)
+            .with_context(|| format!("Invalid log filter: {directives}"))?;
+
+        self.filter
+            .reload(filter)
+            .context("Logger has been shut down")?;
+
+        tracing::info!(%directives, "Updated log stream filter");
+
+        Ok(())
+    }
+}
+
+impl<S> Layer<S> for LogStreamLayer
+where
+    S: Subscriber,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let line = LogLine {
+            timestamp: OffsetDateTime::now_utc(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.into_message(),
+        };
+
+        let mut buffer = self.buffer.lock();
+        if buffer.len() == BUFFERED_LINES {
+            buffer.pop_front();
+        }
+        buffer.push_back(line.clone());
+
+        // Nobody may be streaming the logs. We must not log the error, as that would end up here
+        // again.
+        let _ = self.sender.send(line);
+    }
+}
+
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl LineVisitor {
+    fn into_message(self) -> String {
+        format!("{}{}", self.message, self.fields)
+    }
+}
+
+impl Visit for LineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
@@ -1,13 +1,17 @@
 mod api;
+mod audit;
 mod auth;
 mod cli;
 mod logger;
+mod logs;
 mod session;
 mod subscribers;
 
 use crate::api::version;
+use crate::audit::AuditLog;
 use crate::auth::Backend;
 use crate::cli::Opts;
+use crate::logs::LogStream;
 use crate::session::InMemorySessionStore;
 use crate::subscribers::AppSubscribers;
 use anyhow::Context;
@@ -53,10 +57,12 @@ use utoipa_rapidoc::RapiDoc;
 use utoipa_redoc::Redoc;
 use utoipa_redoc::Servable;
 use utoipa_swagger_ui::SwaggerUi;
+use xxi_node::commons::ClientInfo;
+use xxi_node::commons::ClientKind;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    logger::init_tracing(LevelFilter::DEBUG, false, false)?;
+    let log_stream = logger::init_tracing(LevelFilter::DEBUG, false, false)?;
 
     let opts = Opts::read();
     let network = opts.network();
@@ -78,11 +84,12 @@ async fn main() -> Result<()> {
     let coordinator_pubkey = opts.coordinator_pubkey()?;
     let oracle_endpoint = opts.oracle_endpoint()?;
     let oracle_pubkey = opts.oracle_pubkey()?;
-    let password = opts.password();
+    let users = opts.users()?;
     let coordinator_http_port = opts.coordinator_http_port;
     let electrs_endpoint = opts.electrs;
     let secure = opts.secure;
     let meme_endpoint = opts.meme_endpoint;
+    let fallback_coordinators = opts.fallback_coordinator;
 
     let config = native::config::api::Config {
         coordinator_pubkey,
@@ -95,8 +102,14 @@ async fn main() -> Result<()> {
         oracle_pubkey,
         health_check_interval_secs: 60,
         meme_endpoint,
+        fallback_coordinators,
     };
 
+    native::state::set_client_info(ClientInfo {
+        kind: ClientKind::Webapp,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    });
+
     let seed_dir = data_dir.clone();
     native::api::set_config(config, data_dir.clone(), seed_dir.clone()).expect("to set config");
 
@@ -120,18 +133,16 @@ async fn main() -> Result<()> {
         .with_secure(matches!(network, Network::Bitcoin))
         .with_expiry(Expiry::OnInactivity(time::Duration::hours(1)));
 
-    let auth_layer = AuthManagerLayerBuilder::new(
-        Backend {
-            hashed_password: password,
-        },
-        session_layer,
-    )
-    .build();
+    let auth_layer = AuthManagerLayerBuilder::new(Backend::new(users), session_layer).build();
+
+    let audit_log = AuditLog::open(Path::new(&data_dir).join("audit.log"))?;
 
     let app_state = AppState {
+        audit_log,
         whitelist_withdrawal_addresses: opts.whitelist_withdrawal_addresses,
         withdrawal_addresses: opts.withdrawal_address,
         subscribers: Arc::new(rx),
+        log_stream,
     };
 
     let app = api::router(app_state)
@@ -206,9 +217,11 @@ async fn main() -> Result<()> {
 }
 
 pub struct AppState {
+    pub audit_log: AuditLog,
     pub whitelist_withdrawal_addresses: bool,
     pub withdrawal_addresses: Vec<String>,
     pub subscribers: Arc<AppSubscribers>,
+    pub log_stream: LogStream,
 }
 
 fn router(network: Network) -> Router {
@@ -232,6 +245,11 @@ fn router(network: Network) -> Router {
             api::get_channels,
             api::close_channel,
             api::get_trade_constraints,
+            api::get_user,
+            api::get_audit_log,
+            api::get_logs,
+            api::get_log_filter,
+            api::put_log_filter,
         ),
         components(schemas(
             auth::Credentials,
@@ -257,6 +275,11 @@ fn router(network: Network) -> Router {
             api::OrderState,
             api::ChannelState,
             api::SignedChannelState,
+            api::User,
+            auth::Role,
+            audit::AuditEntry,
+            api::LogFilter,
+            logs::LogLine,
         ))
     )]
     struct ApiDoc;